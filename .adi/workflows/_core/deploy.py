@@ -2,8 +2,10 @@
 """ADI Coolify Deployment Helper - deploy, monitor, and manage service deployments."""
 
 import argparse
+import hashlib
 import json
 import os
+import re
 import sys
 import time
 import urllib.error
@@ -361,6 +363,387 @@ def cmd_list(args: argparse.Namespace):
         print(f"{color}{icon} {status:<10}{NC} {commit:<15} {created}")
 
 
+# ---------------------------------------------------------------------------
+# Release (CI gate)
+# ---------------------------------------------------------------------------
+
+# Exit codes of `release`
+EXIT_RELEASED = 0
+EXIT_ROLLED_BACK = 1
+EXIT_ROLLBACK_FAILED = 2
+EXIT_NOT_ROLLED_BACK = 3
+EXIT_NOT_DEPLOYED = 4
+
+# Application statuses that count as up once the deployment finished.
+# "running:unknown" means the app has no healthcheck configured.
+READY_STATUSES = ("running:healthy", "running", "running:unknown")
+
+
+class ReleaseFailed(Exception):
+    pass
+
+
+def trigger_deploy(uuid: str, force: bool = False) -> str:
+    """Start a deployment and return its uuid."""
+    force_param = "&force=true" if force else ""
+    result = api_call("GET", f"/deploy?uuid={uuid}{force_param}")
+    if isinstance(result, dict):
+        deployments = result.get("deployments", [])
+        if deployments and deployments[0].get("deployment_uuid"):
+            return deployments[0]["deployment_uuid"]
+        raise ReleaseFailed(result.get("message", result.get("error", "deploy not started")))
+    raise ReleaseFailed("deploy not started")
+
+
+def last_good_commit(uuid: str) -> str | None:
+    """Commit of the most recent successful deployment, if any."""
+    deployments = api_call("GET", f"/applications/{uuid}/deployments?take=20")
+    if not isinstance(deployments, list):
+        return None
+    for dep in deployments:
+        if dep.get("status") in ("finished", "success") and dep.get("commit"):
+            return dep["commit"]
+    return None
+
+
+def wait_deployment(deploy_uuid: str, deadline: float):
+    """Block until the deployment finishes; raise if it fails or times out."""
+    last = ""
+    while time.monotonic() < deadline:
+        deploy_info = api_call("GET", f"/deployments/{deploy_uuid}")
+        status = deploy_info.get("status", "unknown") if isinstance(deploy_info, dict) else "unknown"
+        if status != last:
+            print(f"  deployment: {status_color(status)}{status_icon(status)} {status}{NC}", flush=True)
+            last = status
+        if status in ("finished", "success"):
+            return
+        if is_terminal_status(status):
+            raise ReleaseFailed(f"deployment {status}")
+        time.sleep(2)
+    raise ReleaseFailed("timed out waiting for the deployment")
+
+
+def wait_healthy(uuid: str, deadline: float):
+    """Block until the application reports running (healthy if checked)."""
+    last = ""
+    while time.monotonic() < deadline:
+        app_info = api_call("GET", f"/applications/{uuid}")
+        status = app_info.get("status", "unknown") if isinstance(app_info, dict) else "unknown"
+        if status != last:
+            print(f"  application: {status_color(status)}{status_icon(status)} {status}{NC}", flush=True)
+            last = status
+        if status in READY_STATUSES:
+            return
+        time.sleep(3)
+    raise ReleaseFailed(f"not healthy before the timeout (last status: {last or 'unknown'})")
+
+
+def verify_url(url: str, expect_status: int, expect_body: str | None, deadline: float):
+    """Hit the verify URL until it answers as expected or the deadline passes."""
+    problem = "not checked"
+    while time.monotonic() < deadline:
+        try:
+            with urllib.request.urlopen(url, timeout=10) as resp:
+                status, body = resp.status, resp.read().decode(errors="replace")
+        except urllib.error.HTTPError as exc:
+            status, body = exc.code, exc.read().decode(errors="replace")
+        except (urllib.error.URLError, TimeoutError) as exc:
+            status, body = None, ""
+            problem = f"request failed: {getattr(exc, 'reason', exc)}"
+
+        if status is not None:
+            if status != expect_status:
+                problem = f"HTTP {status}, expected {expect_status}"
+            elif expect_body and expect_body not in body:
+                problem = f"body does not contain {expect_body!r}"
+            else:
+                print(f"  verify: {GREEN}\u25cf HTTP {status}{NC}", flush=True)
+                return
+        time.sleep(3)
+    raise ReleaseFailed(f"verify {url}: {problem}")
+
+
+def rollback(uuid: str, commit: str, timeout: int) -> bool:
+    """Redeploy `commit`, then point the application back at its branch head."""
+    print(f"{YELLOW}Rolling back to {commit[:7]}...{NC}", flush=True)
+    ok = False
+    try:
+        api_call("PATCH", f"/applications/{uuid}", json.dumps({"git_commit_sha": commit}))
+        deadline = time.monotonic() + timeout
+        wait_deployment(trigger_deploy(uuid), deadline)
+        wait_healthy(uuid, deadline)
+        ok = True
+    except ReleaseFailed as exc:
+        print(f"{RED}Rollback failed{NC}: {exc}", file=sys.stderr)
+    finally:
+        # Later deploys should build the branch again, not the pinned commit
+        api_call("PATCH", f"/applications/{uuid}", json.dumps({"git_commit_sha": "HEAD"}))
+    return ok
+
+
+def cmd_release(args: argparse.Namespace):
+    """Deploy, wait until healthy, verify, and roll back on failure."""
+    _, uuid, display = resolve_service(args.service)
+    deadline = time.monotonic() + args.timeout
+    previous = last_good_commit(uuid)
+
+    print(f"{BOLD}Releasing {display}{NC}", flush=True)
+    try:
+        deploy_uuid = trigger_deploy(uuid, args.force)
+    except ReleaseFailed as exc:
+        # Nothing was deployed, so the previous release is still live
+        print(f"{RED}Release failed{NC}: {exc}", file=sys.stderr)
+        sys.exit(EXIT_NOT_DEPLOYED)
+
+    try:
+        print(f"  {DIM}deployment {deploy_uuid}{NC}", flush=True)
+        wait_deployment(deploy_uuid, deadline)
+        wait_healthy(uuid, deadline)
+        if args.verify_url:
+            verify_url(args.verify_url, args.expect_status, args.expect_body, deadline)
+    except ReleaseFailed as exc:
+        print(f"{RED}Release failed{NC}: {exc}", file=sys.stderr)
+        if args.no_rollback:
+            sys.exit(EXIT_NOT_ROLLED_BACK)
+        if previous is None:
+            print(f"{RED}No previous successful deployment to roll back to{NC}", file=sys.stderr)
+            sys.exit(EXIT_ROLLBACK_FAILED)
+        sys.exit(EXIT_ROLLED_BACK if rollback(uuid, previous, args.timeout) else EXIT_ROLLBACK_FAILED)
+
+    success(f"{display} released")
+    sys.exit(EXIT_RELEASED)
+
+
+# ---------------------------------------------------------------------------
+# Preview environments
+# ---------------------------------------------------------------------------
+
+# Previews created from this checkout, keyed by "<service>:<branch>"
+PREVIEWS_FILE = PROJECT_ROOT / ".adi" / "coolify" / "previews.json"
+
+DEFAULT_PREVIEW_TTL_HOURS = 72
+
+# Settings copied from the source application when creating a preview
+PREVIEW_COPIED_FIELDS = (
+    "git_repository", "build_pack", "ports_exposes", "base_directory",
+    "dockerfile_location", "docker_compose_location", "install_command",
+    "build_command", "start_command", "publish_directory", "health_check_enabled",
+    "health_check_path", "health_check_port", "limits_memory", "limits_cpus",
+)
+
+
+def load_previews() -> dict:
+    if not PREVIEWS_FILE.is_file():
+        return {}
+    try:
+        return json.loads(PREVIEWS_FILE.read_text())
+    except json.JSONDecodeError:
+        warn(f"Ignoring unreadable {PREVIEWS_FILE}")
+        return {}
+
+
+def save_previews(previews: dict):
+    PREVIEWS_FILE.parent.mkdir(parents=True, exist_ok=True)
+    PREVIEWS_FILE.write_text(json.dumps(previews, indent=2, sort_keys=True) + "\n")
+
+
+def preview_key(service: str, branch: str) -> str:
+    return f"{service}:{branch}"
+
+
+def preview_label(service: str, branch: str) -> str:
+    """DNS label for a preview: `<service>-<branch slug>`, at most 63 chars.
+
+    A short hash of the branch keeps labels unique when slugs collide or get
+    truncated (`feature/x` and `feature-x`).
+    """
+    slug = re.sub(r"[^a-z0-9]+", "-", branch.lower()).strip("-") or "branch"
+    digest = hashlib.sha256(branch.encode()).hexdigest()[:6]
+    label = f"{service}-{slug}"[: 63 - len(digest) - 1].rstrip("-")
+    return f"{label}-{digest}"
+
+
+def preview_settings() -> tuple[str, str, str, str]:
+    """Return (project_uuid, server_uuid, environment, base_domain) or exit."""
+    project = os.environ.get("COOLIFY_PREVIEW_PROJECT_UUID", "")
+    server = os.environ.get("COOLIFY_PREVIEW_SERVER_UUID", "")
+    domain = os.environ.get("COOLIFY_PREVIEW_DOMAIN", "")
+    missing = [name for name, value in (
+        ("COOLIFY_PREVIEW_PROJECT_UUID", project),
+        ("COOLIFY_PREVIEW_SERVER_UUID", server),
+        ("COOLIFY_PREVIEW_DOMAIN", domain),
+    ) if not value]
+    if missing:
+        error(f"Preview environments need {', '.join(missing)}")
+    environment = os.environ.get("COOLIFY_PREVIEW_ENVIRONMENT", "preview")
+    return project, server, environment, domain.strip(".")
+
+
+def api_error(result) -> str | None:
+    """Error of a failed API call (transport or validation), None otherwise."""
+    if isinstance(result, dict) and ("error" in result or "errors" in result):
+        return str(result.get("errors") or result.get("error"))
+    return None
+
+
+def copy_envs(source_uuid: str, target_uuid: str, domain: str) -> int:
+    """Copy environment variables; values naming the source domain get the preview's."""
+    source_fqdn = ""
+    app_info = api_call("GET", f"/applications/{source_uuid}")
+    if isinstance(app_info, dict):
+        source_fqdn = (app_info.get("fqdn") or "").split(",")[0].split("://")[-1]
+
+    envs = api_call("GET", f"/applications/{source_uuid}/envs")
+    if not isinstance(envs, list):
+        return 0
+
+    copied = 0
+    for env in envs:
+        if env.get("is_preview"):
+            continue
+        value = env.get("value") or ""
+        if source_fqdn:
+            value = value.replace(source_fqdn, domain)
+        result = api_call("POST", f"/applications/{target_uuid}/envs", json.dumps({
+            "key": env.get("key"),
+            "value": value,
+            "is_build_time": env.get("is_build_time", False),
+            "is_literal": env.get("is_literal", False),
+        }))
+        if api_error(result) is None:
+            copied += 1
+    return copied
+
+
+def delete_preview(key: str, preview: dict) -> bool:
+    result = api_call("DELETE", f"/applications/{preview['uuid']}?delete_volumes=true&docker_cleanup=true")
+    message = str(result.get("message", "")) if isinstance(result, dict) else ""
+    # Coolify answers "...deletion request queued" or "Application not found"
+    if api_error(result) or not any(word in message.lower() for word in ("delet", "not found")):
+        print(f"  {RED}\u2717{NC} {key}: {api_error(result) or message or 'unexpected response'}", file=sys.stderr)
+        return False
+    print(f"  {GREEN}\u25cf{NC} {key}: deleted {preview['domain']}")
+    return True
+
+
+def expire_previews(previews: dict, now: float) -> int:
+    """Delete previews past their TTL; returns how many went away."""
+    expired = [key for key, p in previews.items() if p.get("expires_at", now + 1) <= now]
+    for key in expired:
+        if delete_preview(key, previews[key]):
+            del previews[key]
+    return len(expired)
+
+
+def cmd_preview_create(args: argparse.Namespace):
+    """Clone a service into a preview app for a branch, or refresh the existing one."""
+    _, source_uuid, display = resolve_service(args.service)
+    project, server, environment, base_domain = preview_settings()
+    previews = load_previews()
+    now = time.time()
+    expire_previews(previews, now)
+
+    key = preview_key(args.service, args.branch)
+    ttl = args.ttl * 3600
+    existing = previews.get(key)
+    if existing:
+        existing["expires_at"] = now + ttl
+        save_previews(previews)
+        info(f"Preview {key} exists, redeploying {existing['domain']}")
+        deploy_uuid = trigger_deploy(existing["uuid"], args.force)
+        success(f"https://{existing['domain']} (deployment {deploy_uuid})")
+        return
+
+    source = api_call("GET", f"/applications/{source_uuid}")
+    if not isinstance(source, dict) or "uuid" not in source:
+        message = source.get("message") if isinstance(source, dict) else None
+        error(f"Cannot read {display}: {api_error(source) or message or 'unexpected response'}")
+
+    label = preview_label(args.service, args.branch)
+    domain = f"{label}.{base_domain}"
+    payload = {field: source[field] for field in PREVIEW_COPIED_FIELDS if source.get(field) is not None}
+    payload.update({
+        "project_uuid": project,
+        "server_uuid": server,
+        "environment_name": environment,
+        "git_branch": args.branch,
+        "name": label,
+        "description": f"Preview of {display} for {args.branch}",
+        "domains": f"https://{domain}",
+        "instant_deploy": False,
+    })
+
+    github_app = os.environ.get("COOLIFY_PREVIEW_GITHUB_APP_UUID", "")
+    if github_app:
+        payload["github_app_uuid"] = github_app
+        created = api_call("POST", "/applications/private-github-app", json.dumps(payload))
+    else:
+        created = api_call("POST", "/applications/public", json.dumps(payload))
+    preview_uuid = created.get("uuid", "") if isinstance(created, dict) else ""
+    if not preview_uuid:
+        message = created.get("message") if isinstance(created, dict) else None
+        error(f"Could not create the preview app: {api_error(created) or message or 'no uuid returned'}")
+
+    envs = copy_envs(source_uuid, preview_uuid, domain)
+
+    previews[key] = {
+        "service": args.service,
+        "branch": args.branch,
+        "uuid": preview_uuid,
+        "domain": domain,
+        "created_at": now,
+        "expires_at": now + ttl,
+    }
+    save_previews(previews)
+
+    print(f"{BOLD}Preview {key}{NC}")
+    print(f"  app:         {preview_uuid} ({envs} env vars copied)")
+    print(f"  domain:      https://{domain}")
+    print(f"  expires in:  {args.ttl}h")
+    try:
+        deploy_uuid = trigger_deploy(preview_uuid, args.force)
+    except ReleaseFailed as exc:
+        error(f"Preview created but deploy did not start: {exc}")
+    success(f"Deploying {key} ({deploy_uuid})")
+
+
+def cmd_preview_list(_args: argparse.Namespace):
+    """List tracked previews with their remaining TTL."""
+    previews = load_previews()
+    if not previews:
+        print("No preview environments")
+        return
+
+    now = time.time()
+    print(f"{'PREVIEW':<36} {'DOMAIN':<48} {'EXPIRES'}")
+    print("\u2500" * 96)
+    for key, preview in sorted(previews.items()):
+        left = preview.get("expires_at", now) - now
+        expires = f"{RED}expired{NC}" if left <= 0 else f"in {int(left // 3600)}h {int(left % 3600 // 60)}m"
+        print(f"{key:<36} {preview['domain']:<48} {expires}")
+
+
+def cmd_preview_delete(args: argparse.Namespace):
+    """Tear down one preview."""
+    previews = load_previews()
+    key = preview_key(args.service, args.branch)
+    preview = previews.get(key)
+    if preview is None:
+        error(f"No preview {key}. List them with: deploy.py preview list")
+    if not delete_preview(key, preview):
+        sys.exit(1)
+    del previews[key]
+    save_previews(previews)
+
+
+def cmd_preview_expire(_args: argparse.Namespace):
+    """Tear down every preview past its TTL (run on a schedule)."""
+    previews = load_previews()
+    removed = expire_previews(previews, time.time())
+    save_previews(previews)
+    success(f"{removed} expired preview(s) removed")
+
+
 # ---------------------------------------------------------------------------
 # Main
 # ---------------------------------------------------------------------------
@@ -379,12 +762,31 @@ environment:
   COOLIFY_URL       Coolify instance URL (default: http://in.the-ihor.com)
   COOLIFY_API_KEY   API token (required)
 
+preview environment:
+  COOLIFY_PREVIEW_PROJECT_UUID     Project previews are created in (required)
+  COOLIFY_PREVIEW_SERVER_UUID      Server previews run on (required)
+  COOLIFY_PREVIEW_DOMAIN           Base domain, previews get <service>-<branch>-<hash>.<domain> (required)
+  COOLIFY_PREVIEW_ENVIRONMENT      Environment name (default: preview)
+  COOLIFY_PREVIEW_GITHUB_APP_UUID  GitHub app for private repositories
+
 examples:
   deploy.py status                  Show all services
   deploy.py deploy web              Deploy web UI
   deploy.py deploy all              Deploy everything
   deploy.py deploy auth --force     Force rebuild auth
   deploy.py list platform           Recent deployments
+  deploy.py release web --verify-url https://adi.example.com/health
+                                    Deploy, verify, roll back on failure
+  deploy.py preview create --branch feature/x --service web
+                                    Preview app for a branch
+  deploy.py preview delete --branch feature/x --service web
+
+release exit codes:
+  0  released and verified
+  1  release failed, previous commit redeployed
+  2  release failed and rollback failed or was impossible
+  3  release failed, not rolled back (--no-rollback)
+  4  deployment did not start, nothing changed
 """,
     )
     sub = parser.add_subparsers(dest="command")
@@ -410,14 +812,54 @@ examples:
     p_list.add_argument("service", help="Service key")
     p_list.add_argument("count", nargs="?", type=int, default=5, help="Number of deployments (default: 5)")
 
+    # release
+    p_release = sub.add_parser("release", help="Deploy, wait until healthy, verify; roll back on failure")
+    p_release.add_argument("service", help="Service key")
+    p_release.add_argument("--verify-url", help="URL to check once the service is healthy")
+    p_release.add_argument("--expect-status", type=int, default=200, help="Expected HTTP status (default: 200)")
+    p_release.add_argument("--expect-body", help="Substring the verify response must contain")
+    p_release.add_argument("--timeout", type=int, default=600, help="Seconds for deploy + health + verify (default: 600)")
+    p_release.add_argument("--force", "-f", action="store_true", help="Force rebuild (no cache)")
+    p_release.add_argument("--no-rollback", action="store_true", help="Only report failure, do not roll back")
+
+    # preview
+    p_preview = sub.add_parser("preview", help="Per-branch preview environments")
+    preview_sub = p_preview.add_subparsers(dest="preview_command")
+    p_pcreate = preview_sub.add_parser("create", help="Create (or redeploy) a branch preview")
+    p_pcreate.add_argument("--branch", required=True, help="Git branch to deploy")
+    p_pcreate.add_argument("--service", required=True, help="Service key to clone")
+    p_pcreate.add_argument("--ttl", type=int, default=DEFAULT_PREVIEW_TTL_HOURS,
+                           help=f"Hours until the preview expires (default: {DEFAULT_PREVIEW_TTL_HOURS})")
+    p_pcreate.add_argument("--force", "-f", action="store_true", help="Force rebuild (no cache)")
+    preview_sub.add_parser("list", help="List preview environments")
+    p_pdelete = preview_sub.add_parser("delete", help="Tear down a preview")
+    p_pdelete.add_argument("--branch", required=True, help="Git branch of the preview")
+    p_pdelete.add_argument("--service", required=True, help="Service key of the preview")
+    preview_sub.add_parser("expire", help="Tear down previews past their TTL")
+
     args = parser.parse_args()
 
+    if args.command == "preview":
+        preview_commands = {
+            "create": cmd_preview_create,
+            "list":   cmd_preview_list,
+            "delete": cmd_preview_delete,
+            "expire": cmd_preview_expire,
+        }
+        handler = preview_commands.get(args.preview_command)
+        if handler is None:
+            p_preview.print_help()
+            sys.exit(0)
+        handler(args)
+        return
+
     commands = {
         "status": cmd_status,
         "deploy": cmd_deploy,
         "watch":  cmd_watch,
         "logs":   cmd_logs,
         "list":   cmd_list,
+        "release": cmd_release,
     }
 
     handler = commands.get(args.command)
@@ -3,7 +3,7 @@
  * DO NOT EDIT.
  */
 
-import type { AuthOption, AuthRequirement, CocoonKind, ConnectionInfo, DeviceInfo, RoomInfo } from './types';
+import type { AdiServiceUsage, AuthOption, AuthRequirement, Capability, CapabilityUnavailableReason, CocoonKind, CocoonMember, CocoonPoolStatus, CocoonRole, ConnectionInfo, DelegatedGrant, DeviceInfo, DisconnectInfo, GpuInfo, OwnershipAuditEvent, RoomInfo } from './types';
 
 export type SignalingMessage =
   // ── auth ──
@@ -18,7 +18,8 @@ export type SignalingMessage =
   | { type: 'device_deregister'; device_id: string; reason?: string }
   | { type: 'device_deregister_response'; device_id: string }
   | { type: 'device_peer_connected'; peer_id: string }
-  | { type: 'device_peer_disconnected'; peer_id: string }
+  | { type: 'device_peer_disconnected'; peer_id: string; info?: DisconnectInfo }
+  | { type: 'device_disconnect'; info: DisconnectInfo }
   | { type: 'device_update_tags'; tags: Record<string, string> }
   | { type: 'device_update_tags_response'; device_id: string; tags: Record<string, string> }
   | { type: 'device_update_device'; tags?: Record<string, string>; device_config?: unknown }
@@ -26,6 +27,26 @@ export type SignalingMessage =
   | { type: 'device_query_devices'; tag_filter: Record<string, string> }
   | { type: 'device_query_devices_response'; devices: DeviceInfo[] }
   | { type: 'device_device_list_updated'; devices: DeviceInfo[] }
+  | { type: 'device_ownership_changed'; event: OwnershipAuditEvent }
+  | { type: 'device_ownership_history'; device_id: string }
+  | { type: 'device_ownership_history_response'; device_id: string; events: OwnershipAuditEvent[] }
+  | { type: 'device_list_members'; device_id: string }
+  | { type: 'device_list_members_response'; device_id: string; members: CocoonMember[] }
+  | { type: 'device_update_member_role'; device_id: string; user_id: string; role: CocoonRole }
+  | { type: 'device_update_member_role_response'; device_id: string; member: CocoonMember }
+  | { type: 'device_remove_member'; device_id: string; user_id: string }
+  | { type: 'device_remove_member_response'; device_id: string; user_id: string }
+  | { type: 'device_issue_delegated_token'; device_id: string; scopes: string[]; ttl_secs: number }
+  | { type: 'device_issue_delegated_token_response'; token: string; grant: DelegatedGrant }
+  | { type: 'device_validate_delegated_token'; token: string }
+  | { type: 'device_validate_delegated_token_response'; token: string; valid: boolean; grant?: DelegatedGrant }
+  | { type: 'device_revoke_delegated_token'; token_id: string }
+  | { type: 'device_revoke_delegated_token_response'; token_id: string; revoked: boolean }
+  | { type: 'device_config_push'; device_ids?: string[]; label_selector?: Record<string, string>; config_patch: unknown; version: number }
+  | { type: 'device_config_push_response'; version: number; sent_to: string[]; offline: string[] }
+  | { type: 'device_config_applied'; device_id: string; version: number; success: boolean; error?: string }
+  | { type: 'device_heartbeat'; device_id: string; adi_usage: AdiServiceUsage[] }
+  | { type: 'device_rekey'; setup_token: string; secret: string }
 
   // ── pairing ──
   | { type: 'pairing_create_code' }
@@ -36,14 +57,27 @@ export type SignalingMessage =
 
   // ── sync ──
   | { type: 'sync_data'; payload: unknown }
+  | { type: 'sync_queued_delivery'; message_id: string; device_id: string; expires_at: number }
+  | { type: 'sync_retrieve_queued'; device_id: string }
+  | { type: 'sync_retrieve_queued_response'; device_id: string; delivered: number; expired: number }
+  | { type: 'sync_delivery_receipt'; message_id: string; device_id: string; delivered_at: number }
 
   // ── hive ──
-  | { type: 'hive_register'; hive_id: string; version: string; cocoon_kinds: CocoonKind[]; hive_id_signature: string }
+  | { type: 'hive_register'; hive_id: string; version: string; cocoon_kinds: CocoonKind[]; hive_id_signature: string; runners?: string[]; gpus?: GpuInfo[]; singletons?: string[] }
   | { type: 'hive_register_response'; hive_id: string }
-  | { type: 'hive_spawn_cocoon'; request_id: string; setup_token: string; name?: string; kind: string }
+  | { type: 'hive_heartbeat'; gpus: GpuInfo[]; singletons?: string[] }
+  | { type: 'hive_spawn_cocoon'; request_id: string; setup_token: string; name?: string; kind: string; gpu_required?: boolean; min_vram_mb?: number; from_pool?: boolean }
   | { type: 'hive_terminate_cocoon'; request_id: string; container_id: string }
-  | { type: 'hive_spawn_cocoon_result'; request_id: string; success: boolean; device_id?: string; container_id?: string; error?: string }
+  | { type: 'hive_spawn_cocoon_result'; request_id: string; success: boolean; device_id?: string; container_id?: string; error?: string; from_pool?: boolean }
   | { type: 'hive_terminate_cocoon_result'; request_id: string; success: boolean; error?: string }
+  | { type: 'hive_maintenance'; on: boolean; reason?: string }
+  | { type: 'hive_drain_cocoon'; request_id: string; container_id: string; setup_token: string; name?: string; kind: string; gpu_required?: boolean; min_vram_mb?: number }
+  | { type: 'hive_drain_cocoon_result'; request_id: string; container_id: string; success: boolean; target_hive_id?: string; error?: string }
+  | { type: 'hive_singleton_leader'; service: string; leader_hive_id?: string; fencing_token: number }
+  | { type: 'hive_set_pool_size'; kind: string; size: number }
+  | { type: 'hive_pool_status'; pools: CocoonPoolStatus[] }
+  | { type: 'hive_rekey_cocoon'; request_id: string; slot_secret: string; setup_token: string; secret: string }
+  | { type: 'hive_rekey_cocoon_response'; request_id: string; success: boolean; error?: string }
 
   // ── room ──
   | { type: 'room_create'; room_id?: string }
@@ -67,5 +101,13 @@ export type SignalingMessage =
   | { type: 'room_actor_left'; room_id: string; device_id: string }
   | { type: 'room_updated'; room: RoomInfo }
 
+  // ── capability ──
+  | { type: 'capability_update'; capabilities: Capability[]; version: number; hash: string }
+  | { type: 'capability_delta'; base_version: number; version: number; added: Capability[]; removed: string[]; changed: Capability[]; hash: string }
+  | { type: 'capability_resync'; version: number }
+  | { type: 'capability_request'; request_id: string; capability: Capability; payload: unknown; prefer_device?: string; from_device?: string }
+  | { type: 'capability_response'; request_id: string; payload: unknown; error?: string; from_device?: string }
+  | { type: 'capability_unavailable'; request_id: string; reason: CapabilityUnavailableReason; closest_match?: Capability }
+
   // ── system ──
   | { type: 'system_error'; message: string };
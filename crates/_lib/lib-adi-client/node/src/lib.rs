@@ -0,0 +1,213 @@
+//! Node.js bindings for `lib-adi-client`, built with `napi build` as the
+//! `@adi-family/client` package.
+//!
+//! Every network call returns a promise. JSON params and results are plain
+//! JavaScript values, and failures reject with an `Error` carrying the client
+//! error message.
+//!
+//! ```js
+//! const { login, connect } = require('@adi-family/client')
+//!
+//! const token = await login('https://auth.example.com', 'me', 'secret')
+//! const client = await connect({
+//!   signalingUrl: 'wss://signal.example.com/ws',
+//!   accessToken: token.access_token,
+//!   device: 'laptop',
+//! })
+//! const tasks = await client.call('adi.tasks', 'list', { status: 'open' })
+//! const { exitCode, stdout } = await client.exec('nvidia-smi')
+//! const updates = await client.subscribe('adi.tasks', 'task_updated')
+//! for (let event; (event = await updates.next()) !== null; ) {
+//!   console.log(event.event, event.data)
+//! }
+//! ```
+
+#![cfg(feature = "node")]
+
+use lib_adi_client::{AdiClientError, ClientConfig, ConnectionState, Transport, TransportMode};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+fn error(e: AdiClientError) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+fn to_js(value: &impl serde::Serialize) -> Result<JsonValue> {
+    serde_json::to_value(value).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+#[napi(object)]
+pub struct ConnectOptions {
+    pub signaling_url: String,
+    pub access_token: String,
+    /// Device ID, `name` tag or unique ID prefix; the only online device
+    /// when omitted
+    pub device: Option<String>,
+    /// "auto" (default), "webrtc" or "relay"
+    pub transport: Option<String>,
+}
+
+#[napi(object)]
+pub struct ExecResult {
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    pub cwd: String,
+}
+
+#[napi(object)]
+pub struct SubscriptionEvent {
+    pub event: String,
+    pub data: JsonValue,
+}
+
+/// Log in to the auth service.
+#[napi]
+pub async fn login(auth_url: String, login: String, password: String) -> Result<JsonValue> {
+    let token = lib_adi_client::login(&auth_url, &login, &password)
+        .await
+        .map_err(error)?;
+    to_js(&token)
+}
+
+/// The user's devices.
+#[napi]
+pub async fn list_devices(signaling_url: String, access_token: String) -> Result<JsonValue> {
+    let devices = lib_adi_client::list_devices(&signaling_url, &access_token)
+        .await
+        .map_err(error)?;
+    to_js(&devices)
+}
+
+/// Connect to a cocoon.
+#[napi]
+pub async fn connect(options: ConnectOptions) -> Result<Client> {
+    let transport = match options.transport.as_deref() {
+        None | Some("auto") => TransportMode::Auto,
+        Some("webrtc") => TransportMode::WebRtc,
+        Some("relay") => TransportMode::Relay,
+        Some(other) => {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "transport must be 'auto', 'webrtc' or 'relay', not '{}'",
+                    other
+                ),
+            ))
+        }
+    };
+    let mut config =
+        ClientConfig::new(options.signaling_url, options.access_token).transport(transport);
+    config.device = options.device;
+    let client = lib_adi_client::AdiClient::connect(config)
+        .await
+        .map_err(error)?;
+    Ok(Client {
+        client: Arc::new(client),
+    })
+}
+
+/// Connection to one cocoon
+#[napi]
+pub struct Client {
+    client: Arc<lib_adi_client::AdiClient>,
+}
+
+#[napi]
+impl Client {
+    /// The connected device
+    #[napi(getter)]
+    pub fn device(&self) -> Result<JsonValue> {
+        to_js(self.client.device())
+    }
+
+    /// "connecting", "webrtc", "relay" or "closed"
+    #[napi(getter)]
+    pub fn state(&self) -> String {
+        match self.client.state() {
+            ConnectionState::Connecting => "connecting",
+            ConnectionState::Connected(Transport::WebRtc) => "webrtc",
+            ConnectionState::Connected(Transport::Relay) => "relay",
+            ConnectionState::Closed => "closed",
+        }
+        .to_string()
+    }
+
+    /// Call a plugin method with JSON params and resolve with its JSON result.
+    #[napi]
+    pub async fn call(
+        &self,
+        plugin: String,
+        method: String,
+        params: Option<JsonValue>,
+    ) -> Result<JsonValue> {
+        self.client
+            .call(&plugin, &method, &params.unwrap_or(JsonValue::Null))
+            .await
+            .map_err(error)
+    }
+
+    /// Plugins registered on the cocoon
+    #[napi]
+    pub async fn list_plugins(&self) -> Result<JsonValue> {
+        let plugins = self.client.list_plugins().await.map_err(error)?;
+        to_js(&plugins)
+    }
+
+    /// Subscribe to a plugin event.
+    #[napi]
+    pub async fn subscribe(
+        &self,
+        plugin: String,
+        event: String,
+        filter: Option<JsonValue>,
+    ) -> Result<Subscription> {
+        let subscription = self
+            .client
+            .subscribe(&plugin, &event, filter)
+            .await
+            .map_err(error)?;
+        Ok(Subscription {
+            subscription: Arc::new(Mutex::new(subscription)),
+        })
+    }
+
+    /// Run a shell command in the client's Silk session and wait for it.
+    #[napi]
+    pub async fn exec(&self, command: String) -> Result<ExecResult> {
+        let output = self.client.exec(&command).await.map_err(error)?;
+        Ok(ExecResult {
+            exit_code: output.exit_code,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            cwd: output.cwd,
+        })
+    }
+
+    #[napi]
+    pub fn close(&self) {
+        self.client.close();
+    }
+}
+
+/// Events of one subscription
+#[napi]
+pub struct Subscription {
+    subscription: Arc<Mutex<lib_adi_client::Subscription>>,
+}
+
+#[napi]
+impl Subscription {
+    /// Next event; `null` once the subscription has ended.
+    #[napi]
+    pub async fn next(&self) -> Option<SubscriptionEvent> {
+        let event = self.subscription.lock().await.next().await?;
+        Some(SubscriptionEvent {
+            event: event.event,
+            data: event.data,
+        })
+    }
+}
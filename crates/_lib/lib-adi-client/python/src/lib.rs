@@ -0,0 +1,288 @@
+//! Python bindings for `lib-adi-client`, built with maturin as the
+//! `adi_client` module.
+//!
+//! Every network call is a coroutine running on a shared tokio runtime.
+//! JSON params and results are plain Python values (converted with the
+//! `json` module), and all failures raise `adi_client.AdiError`.
+//!
+//! ```python
+//! import asyncio, adi_client
+//!
+//! async def main():
+//!     token = await adi_client.login("https://auth.example.com", "me", "secret")
+//!     client = await adi_client.connect(
+//!         "wss://signal.example.com/ws", token["access_token"], device="laptop"
+//!     )
+//!     tasks = await client.call("adi.tasks", "list", {"status": "open"})
+//!     result = await client.exec("nvidia-smi")
+//!     print(result.exit_code, result.stdout)
+//!     async for event in await client.subscribe("adi.tasks", "task_updated"):
+//!         print(event["event"], event["data"])
+//!
+//! asyncio.run(main())
+//! ```
+
+#![cfg(feature = "python")]
+
+use lib_adi_client::{AdiClientError, ClientConfig, ConnectionState, Transport, TransportMode};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyStopAsyncIteration, PyValueError};
+use pyo3::prelude::*;
+use pyo3_async_runtimes::tokio::future_into_py;
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+create_exception!(
+    adi_client,
+    AdiError,
+    PyException,
+    "Error raised by the ADI client"
+);
+
+fn error(e: AdiClientError) -> PyErr {
+    AdiError::new_err(e.to_string())
+}
+
+fn to_json(value: Option<&Bound<'_, PyAny>>) -> PyResult<JsonValue> {
+    let Some(value) = value else {
+        return Ok(JsonValue::Null);
+    };
+    let text: String = value
+        .py()
+        .import("json")?
+        .call_method1("dumps", (value,))?
+        .extract()?;
+    serde_json::from_str(&text).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn to_py(value: &impl serde::Serialize) -> PyResult<PyObject> {
+    let text = serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Python::with_gil(|py| Ok(py.import("json")?.call_method1("loads", (text,))?.unbind()))
+}
+
+fn transport_mode(transport: &str) -> PyResult<TransportMode> {
+    match transport {
+        "auto" => Ok(TransportMode::Auto),
+        "webrtc" => Ok(TransportMode::WebRtc),
+        "relay" => Ok(TransportMode::Relay),
+        other => Err(PyValueError::new_err(format!(
+            "transport must be 'auto', 'webrtc' or 'relay', not '{}'",
+            other
+        ))),
+    }
+}
+
+/// Log in to the auth service; returns the token as a dict.
+#[pyfunction]
+fn login<'py>(
+    py: Python<'py>,
+    auth_url: String,
+    login: String,
+    password: String,
+) -> PyResult<Bound<'py, PyAny>> {
+    future_into_py(py, async move {
+        let token = lib_adi_client::login(&auth_url, &login, &password)
+            .await
+            .map_err(error)?;
+        to_py(&token)
+    })
+}
+
+/// The user's devices, as dicts.
+#[pyfunction]
+fn list_devices<'py>(
+    py: Python<'py>,
+    signaling_url: String,
+    access_token: String,
+) -> PyResult<Bound<'py, PyAny>> {
+    future_into_py(py, async move {
+        let devices = lib_adi_client::list_devices(&signaling_url, &access_token)
+            .await
+            .map_err(error)?;
+        to_py(&devices)
+    })
+}
+
+/// Connect to a cocoon. `device` is an ID, `name` tag or unique ID prefix;
+/// without it the only online device is used.
+#[pyfunction]
+#[pyo3(signature = (signaling_url, access_token, device=None, transport="auto"))]
+fn connect<'py>(
+    py: Python<'py>,
+    signaling_url: String,
+    access_token: String,
+    device: Option<String>,
+    transport: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    let mut config =
+        ClientConfig::new(signaling_url, access_token).transport(transport_mode(transport)?);
+    config.device = device;
+    future_into_py(py, async move {
+        let client = lib_adi_client::AdiClient::connect(config)
+            .await
+            .map_err(error)?;
+        Ok(Client {
+            client: Arc::new(client),
+        })
+    })
+}
+
+/// Connection to one cocoon
+#[pyclass(module = "adi_client", frozen)]
+struct Client {
+    client: Arc<lib_adi_client::AdiClient>,
+}
+
+#[pymethods]
+impl Client {
+    /// The connected device, as a dict
+    #[getter]
+    fn device(&self) -> PyResult<PyObject> {
+        to_py(self.client.device())
+    }
+
+    /// "connecting", "webrtc", "relay" or "closed"
+    #[getter]
+    fn state(&self) -> &'static str {
+        match self.client.state() {
+            ConnectionState::Connecting => "connecting",
+            ConnectionState::Connected(Transport::WebRtc) => "webrtc",
+            ConnectionState::Connected(Transport::Relay) => "relay",
+            ConnectionState::Closed => "closed",
+        }
+    }
+
+    /// Call a plugin method with JSON params and return its JSON result.
+    #[pyo3(signature = (plugin, method, params=None))]
+    fn call<'py>(
+        &self,
+        py: Python<'py>,
+        plugin: String,
+        method: String,
+        params: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let params = to_json(params)?;
+        let client = self.client.clone();
+        future_into_py(py, async move {
+            let result: JsonValue = client
+                .call(&plugin, &method, &params)
+                .await
+                .map_err(error)?;
+            to_py(&result)
+        })
+    }
+
+    /// Plugins registered on the cocoon, as dicts
+    fn list_plugins<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        future_into_py(py, async move {
+            let plugins = client.list_plugins().await.map_err(error)?;
+            to_py(&plugins)
+        })
+    }
+
+    /// Subscribe to a plugin event; iterate the result with `async for`.
+    #[pyo3(signature = (plugin, event, filter=None))]
+    fn subscribe<'py>(
+        &self,
+        py: Python<'py>,
+        plugin: String,
+        event: String,
+        filter: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let filter = match to_json(filter)? {
+            JsonValue::Null => None,
+            filter => Some(filter),
+        };
+        let client = self.client.clone();
+        future_into_py(py, async move {
+            let subscription = client
+                .subscribe(&plugin, &event, filter)
+                .await
+                .map_err(error)?;
+            Ok(Subscription {
+                subscription: Arc::new(Mutex::new(subscription)),
+            })
+        })
+    }
+
+    /// Run a shell command in the client's Silk session and wait for it.
+    fn exec<'py>(&self, py: Python<'py>, command: String) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        future_into_py(py, async move {
+            let output = client.exec(&command).await.map_err(error)?;
+            Ok(ExecResult {
+                exit_code: output.exit_code,
+                stdout: output.stdout,
+                stderr: output.stderr,
+                cwd: output.cwd,
+            })
+        })
+    }
+
+    fn close(&self) {
+        self.client.close();
+    }
+}
+
+/// Events of one subscription, as `{"event": ..., "data": ...}` dicts
+#[pyclass(module = "adi_client", frozen)]
+struct Subscription {
+    subscription: Arc<Mutex<lib_adi_client::Subscription>>,
+}
+
+#[pymethods]
+impl Subscription {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let subscription = self.subscription.clone();
+        future_into_py(py, async move {
+            match subscription.lock().await.next().await {
+                Some(event) => {
+                    to_py(&serde_json::json!({ "event": event.event, "data": event.data }))
+                }
+                None => Err(PyStopAsyncIteration::new_err(())),
+            }
+        })
+    }
+}
+
+/// Result of `Client.exec`
+#[pyclass(module = "adi_client", frozen, get_all)]
+struct ExecResult {
+    exit_code: i32,
+    stdout: String,
+    stderr: String,
+    cwd: String,
+}
+
+#[pymethods]
+impl ExecResult {
+    #[getter]
+    fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ExecResult(exit_code={}, cwd={:?})",
+            self.exit_code, self.cwd
+        )
+    }
+}
+
+#[pymodule]
+fn adi_client(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("AdiError", m.py().get_type::<AdiError>())?;
+    m.add_class::<Client>()?;
+    m.add_class::<Subscription>()?;
+    m.add_class::<ExecResult>()?;
+    m.add_function(wrap_pyfunction!(login, m)?)?;
+    m.add_function(wrap_pyfunction!(list_devices, m)?)?;
+    m.add_function(wrap_pyfunction!(connect, m)?)?;
+    Ok(())
+}
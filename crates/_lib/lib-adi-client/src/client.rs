@@ -0,0 +1,1266 @@
+//! [`AdiClient`]: calls, streams and subscriptions over a self-healing
+//! connection to one cocoon.
+//!
+//! A supervisor task owns the current [`Connection`]. When it drops, calls
+//! in flight fail with [`AdiClientError::Disconnected`], the client reconnects
+//! following its [`ReconnectPolicy`] and live subscriptions are subscribed
+//! again, so [`Subscription`] handles survive reconnects. The Silk session
+//! used by [`AdiClient::exec`] is kept across reconnects and replaced if the
+//! cocoon no longer knows it.
+//!
+//! Streams and subscriptions end early through a [`CancellationToken`]
+//! (`cancel_on`), `cancel` or by dropping them; the cocoon is then told to
+//! stop the call or drop the subscription.
+
+use crate::connection::{
+    self, ConnectOptions, Connection, Link, OnMessage, OnSilk, Transport, TransportMode,
+};
+use crate::error::{AdiClientError, Result};
+use crate::frame::{self, RequestHeader, ResponseStatus, PROTOCOL_VERSION};
+use crate::protocol::{AdiDiscovery, AdiStreamControl, AdiSubscription};
+use crate::silk::{self, ExecOutput, ExecStream, SilkEvent};
+use bytes::Bytes;
+use futures::Stream;
+use lib_adi_service::{AdiPluginInfo, SubscriptionEvent};
+use lib_retry::RetryPolicy;
+use lib_signaling_protocol::DeviceInfo;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+use uuid::Uuid;
+
+/// Used when `ice_servers` is empty
+const DEFAULT_STUN_SERVER: &str = "stun:stun.l.google.com:19302";
+
+/// Silk error for a session the cocoon does not have (e.g. after a restart)
+const SESSION_NOT_FOUND: &str = "session_not_found";
+
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub signaling_url: String,
+    pub access_token: String,
+    /// Device ID, `name` tag or unique ID prefix; `None` picks the only
+    /// online device
+    pub device: Option<String>,
+    pub transport: TransportMode,
+    /// Limit for single responses, discovery and subscribing, including the
+    /// wait for a connection while reconnecting
+    pub request_timeout: Duration,
+    /// Limit for the signaling handshake and, separately, WebRTC negotiation
+    pub connect_timeout: Duration,
+    /// Backoff between reconnect attempts. A disconnect hint from the
+    /// signaling server overrides it: no attempts after a permanent one, and
+    /// the first attempt waits at least its `retry_after`.
+    pub reconnect: RetryPolicy,
+    /// STUN/TURN URLs for WebRTC
+    pub ice_servers: Vec<String>,
+    /// Token from `adi cocoon share` for a device the user does not own;
+    /// `device` must then be its full ID
+    pub delegated_token: Option<String>,
+}
+
+impl ClientConfig {
+    pub fn new(signaling_url: impl Into<String>, access_token: impl Into<String>) -> Self {
+        Self {
+            signaling_url: signaling_url.into(),
+            access_token: access_token.into(),
+            device: None,
+            transport: TransportMode::default(),
+            request_timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(15),
+            reconnect: RetryPolicy::default(),
+            ice_servers: vec![DEFAULT_STUN_SERVER.to_string()],
+            delegated_token: None,
+        }
+    }
+
+    pub fn device(mut self, device: impl Into<String>) -> Self {
+        self.device = Some(device.into());
+        self
+    }
+
+    pub fn transport(mut self, transport: TransportMode) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    pub fn delegated_token(mut self, token: impl Into<String>) -> Self {
+        self.delegated_token = Some(token.into());
+        self
+    }
+}
+
+/// Connection state as seen by callers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connecting,
+    Connected(Transport),
+    /// Closed, or gave up reconnecting
+    Closed,
+}
+
+#[derive(Clone)]
+enum LinkState {
+    Connecting,
+    Connected(Link),
+    Closed,
+}
+
+enum CallSink {
+    Single(oneshot::Sender<Result<Bytes>>),
+    Stream(mpsc::UnboundedSender<Result<Bytes>>),
+}
+
+struct PendingSubscribe {
+    subscription: u64,
+    /// `None` when re-subscribing after a reconnect
+    responder: Option<oneshot::Sender<Result<()>>>,
+}
+
+struct ActiveSubscription {
+    plugin: String,
+    event: String,
+    filter: Option<JsonValue>,
+    /// Cocoon-side ID; changes with every reconnect
+    remote: Option<Uuid>,
+    events: mpsc::UnboundedSender<SubscriptionEvent>,
+}
+
+#[derive(Default)]
+struct Pending {
+    calls: HashMap<Uuid, CallSink>,
+    discovery: HashMap<Uuid, oneshot::Sender<Vec<AdiPluginInfo>>>,
+    subscribing: HashMap<Uuid, PendingSubscribe>,
+    subscriptions: HashMap<u64, ActiveSubscription>,
+    next_subscription: u64,
+    silk_create: Option<oneshot::Sender<Result<Uuid>>>,
+    /// Running Silk commands by command ID
+    commands: HashMap<String, mpsc::UnboundedSender<SilkEvent>>,
+}
+
+struct Inner {
+    config: ClientConfig,
+    state: watch::Sender<LinkState>,
+    pending: Mutex<Pending>,
+    devices: Mutex<Vec<DeviceInfo>>,
+    /// Silk session used by `exec`; locked while one is created
+    silk_session: tokio::sync::Mutex<Option<Uuid>>,
+}
+
+/// Client for the ADI services of one cocoon.
+///
+/// Dropping the client closes its connection.
+pub struct AdiClient {
+    inner: Arc<Inner>,
+    device: DeviceInfo,
+    supervisor: JoinHandle<()>,
+}
+
+impl AdiClient {
+    /// Connect to the configured device. Fails without retrying if the first
+    /// connection cannot be made.
+    pub async fn connect(config: ClientConfig) -> Result<Self> {
+        let inner = Arc::new(Inner::new(config));
+        let connection = inner.connect(inner.config.device.as_deref()).await?;
+        let device = connection.device.clone();
+        let supervisor = tokio::spawn(supervise(inner.clone(), connection));
+        Ok(Self {
+            inner,
+            device,
+            supervisor,
+        })
+    }
+
+    /// The cocoon this client talks to
+    pub fn device(&self) -> &DeviceInfo {
+        &self.device
+    }
+
+    /// The user's devices as of the last (re)connect
+    pub fn devices(&self) -> Vec<DeviceInfo> {
+        self.inner.devices.lock().unwrap().clone()
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        match &*self.inner.state.borrow() {
+            LinkState::Connecting => ConnectionState::Connecting,
+            LinkState::Connected(link) => ConnectionState::Connected(link.transport()),
+            LinkState::Closed => ConnectionState::Closed,
+        }
+    }
+
+    /// Typed handle for one plugin
+    pub fn service(&self, plugin: impl Into<String>) -> Service<'_> {
+        Service {
+            client: self,
+            plugin: plugin.into(),
+        }
+    }
+
+    /// Call a method with JSON params and decode its JSON result.
+    ///
+    /// `()` or `None` params are sent as an empty payload, which the cocoon
+    /// treats as `{}`.
+    pub async fn call<P, R>(&self, plugin: &str, method: &str, params: &P) -> Result<R>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let response = self
+            .call_raw(plugin, method, encode_params(params)?)
+            .await?;
+        decode_result(&response)
+    }
+
+    /// [`call`](Self::call), retried per `policy` when the connection drops
+    /// or the call times out. Only for methods that are safe to run twice.
+    pub async fn call_with_retry<P, R>(
+        &self,
+        plugin: &str,
+        method: &str,
+        params: &P,
+        policy: &RetryPolicy,
+    ) -> Result<R>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let payload = encode_params(params)?;
+        let response = lib_retry::retry_if(
+            policy,
+            || self.call_raw(plugin, method, payload.clone()),
+            |e| matches!(e, AdiClientError::Disconnected | AdiClientError::Timeout),
+        )
+        .await?;
+        decode_result(&response)
+    }
+
+    /// Call a method with an opaque payload.
+    pub async fn call_raw(
+        &self,
+        plugin: &str,
+        method: &str,
+        payload: impl Into<Bytes>,
+    ) -> Result<Bytes> {
+        let link = self.inner.link().await?;
+        let id = Uuid::new_v4();
+        let (tx, rx) = oneshot::channel();
+        self.inner.pending().calls.insert(id, CallSink::Single(tx));
+
+        let frame = request_frame(id, plugin, method, false, &payload.into());
+        if let Err(e) = link.send_frame(frame).await {
+            self.inner.pending().calls.remove(&id);
+            return Err(e);
+        }
+        match tokio::time::timeout(self.inner.config.request_timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(AdiClientError::Disconnected),
+            Err(_) => {
+                self.inner.pending().calls.remove(&id);
+                Err(AdiClientError::Timeout)
+            }
+        }
+    }
+
+    /// Call a streaming method. Chunks are not subject to `request_timeout`.
+    pub async fn call_stream<P>(&self, plugin: &str, method: &str, params: &P) -> Result<CallStream>
+    where
+        P: Serialize + ?Sized,
+    {
+        let link = self.inner.link().await?;
+        let id = Uuid::new_v4();
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.inner.pending().calls.insert(id, CallSink::Stream(tx));
+
+        let frame = request_frame(id, plugin, method, true, &encode_params(params)?);
+        if let Err(e) = link.send_frame(frame).await {
+            self.inner.pending().calls.remove(&id);
+            return Err(e);
+        }
+        Ok(CallStream {
+            id,
+            chunks: rx,
+            inner: Arc::downgrade(&self.inner),
+            cancelled: None,
+            done: false,
+        })
+    }
+
+    /// Plugins registered on the cocoon
+    pub async fn list_plugins(&self) -> Result<Vec<AdiPluginInfo>> {
+        let link = self.inner.link().await?;
+        let request_id = Uuid::new_v4();
+        let (tx, rx) = oneshot::channel();
+        self.inner.pending().discovery.insert(request_id, tx);
+
+        let text = serde_json::to_string(&AdiDiscovery::ListPlugins { request_id })?;
+        if let Err(e) = link.send_text(text).await {
+            self.inner.pending().discovery.remove(&request_id);
+            return Err(e);
+        }
+        match tokio::time::timeout(self.inner.config.request_timeout, rx).await {
+            Ok(Ok(plugins)) => Ok(plugins),
+            Ok(Err(_)) => Err(AdiClientError::Disconnected),
+            Err(_) => {
+                self.inner.pending().discovery.remove(&request_id);
+                Err(AdiClientError::Timeout)
+            }
+        }
+    }
+
+    /// Subscribe to a plugin event. The subscription is renewed after
+    /// reconnects and ends when the handle is dropped.
+    pub async fn subscribe(
+        &self,
+        plugin: &str,
+        event: &str,
+        filter: Option<JsonValue>,
+    ) -> Result<Subscription> {
+        let link = self.inner.link().await?;
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let (tx, rx) = oneshot::channel();
+        let request_id = Uuid::new_v4();
+        let id = {
+            let mut pending = self.inner.pending();
+            let id = pending.next_subscription;
+            pending.next_subscription += 1;
+            pending.subscriptions.insert(
+                id,
+                ActiveSubscription {
+                    plugin: plugin.to_string(),
+                    event: event.to_string(),
+                    filter: filter.clone(),
+                    remote: None,
+                    events: events_tx,
+                },
+            );
+            pending.subscribing.insert(
+                request_id,
+                PendingSubscribe {
+                    subscription: id,
+                    responder: Some(tx),
+                },
+            );
+            id
+        };
+        let subscription = Subscription {
+            id,
+            events,
+            inner: Arc::downgrade(&self.inner),
+            cancelled: None,
+            done: false,
+        };
+
+        let text = serde_json::to_string(&AdiSubscription::Subscribe {
+            request_id,
+            plugin: plugin.to_string(),
+            event: event.to_string(),
+            filter,
+        })?;
+        if let Err(e) = link.send_text(text).await {
+            self.inner.pending().subscribing.remove(&request_id);
+            return Err(e);
+        }
+        // Dropping `subscription` on any error below forgets it again
+        match tokio::time::timeout(self.inner.config.request_timeout, rx).await {
+            Ok(Ok(Ok(()))) => Ok(subscription),
+            Ok(Ok(Err(e))) => Err(e),
+            Ok(Err(_)) => Err(AdiClientError::Disconnected),
+            Err(_) => {
+                self.inner.pending().subscribing.remove(&request_id);
+                Err(AdiClientError::Timeout)
+            }
+        }
+    }
+
+    /// Run a shell command in the client's Silk session and collect its
+    /// output. See [`exec_stream`](Self::exec_stream).
+    pub async fn exec(&self, command: &str) -> Result<ExecOutput> {
+        self.exec_stream(command).await?.collect().await
+    }
+
+    /// Run a shell command in the client's Silk session on the cocoon.
+    ///
+    /// The session is created on first use and keeps its working directory
+    /// and environment between commands. Only starting the command is
+    /// subject to `request_timeout`. Commands that need a terminal fail with
+    /// [`AdiClientError::Interactive`].
+    pub async fn exec_stream(&self, command: &str) -> Result<ExecStream> {
+        let link = self.inner.link().await?;
+        let mut session = self.inner.silk_session.lock().await;
+        let mut renewed = false;
+        loop {
+            let session_id = match *session {
+                Some(session_id) => session_id,
+                None => {
+                    let session_id = self.inner.create_silk_session(&link).await?;
+                    *session = Some(session_id);
+                    session_id
+                }
+            };
+
+            let command_id = Uuid::new_v4().to_string();
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            self.inner.pending().commands.insert(command_id.clone(), tx);
+            if let Err(e) = link.send_silk(silk::execute_request(session_id, command, &command_id))
+            {
+                self.inner.pending().commands.remove(&command_id);
+                return Err(e);
+            }
+
+            // Wait for the cocoon to take the command, so a stale session can
+            // be replaced before the caller sees it
+            let first =
+                match tokio::time::timeout(self.inner.config.request_timeout, rx.recv()).await {
+                    Ok(first) => first,
+                    Err(_) => {
+                        self.inner.pending().commands.remove(&command_id);
+                        return Err(AdiClientError::Timeout);
+                    }
+                };
+            match first {
+                Some(SilkEvent::Error { code, .. }) if code == SESSION_NOT_FOUND && !renewed => {
+                    *session = None;
+                    renewed = true;
+                }
+                Some(SilkEvent::CommandStarted { .. }) => return Ok(ExecStream::new(None, rx)),
+                first => return Ok(ExecStream::new(first, rx)),
+            }
+        }
+    }
+
+    /// Close the connection and stop reconnecting.
+    pub fn close(&self) {
+        self.supervisor.abort();
+        self.inner.close();
+    }
+}
+
+impl Drop for AdiClient {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// [`AdiClient`] calls bound to one plugin
+pub struct Service<'a> {
+    client: &'a AdiClient,
+    plugin: String,
+}
+
+impl Service<'_> {
+    pub async fn call<P, R>(&self, method: &str, params: &P) -> Result<R>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        self.client.call(&self.plugin, method, params).await
+    }
+
+    pub async fn call_stream<P>(&self, method: &str, params: &P) -> Result<CallStream>
+    where
+        P: Serialize + ?Sized,
+    {
+        self.client.call_stream(&self.plugin, method, params).await
+    }
+
+    pub async fn subscribe(&self, event: &str, filter: Option<JsonValue>) -> Result<Subscription> {
+        self.client.subscribe(&self.plugin, event, filter).await
+    }
+}
+
+/// Chunks of a streaming call; ends after the last chunk, an error or
+/// cancellation. Dropping it before the end cancels the call.
+pub struct CallStream {
+    id: Uuid,
+    chunks: mpsc::UnboundedReceiver<Result<Bytes>>,
+    inner: Weak<Inner>,
+    /// Set by `cancel_on`
+    cancelled: Option<Pin<Box<WaitForCancellationFutureOwned>>>,
+    done: bool,
+}
+
+impl CallStream {
+    pub async fn next(&mut self) -> Option<Result<Bytes>> {
+        std::future::poll_fn(|cx| self.poll_chunk(cx)).await
+    }
+
+    /// Next chunk decoded as JSON
+    pub async fn next_json<R: DeserializeOwned>(&mut self) -> Option<Result<R>> {
+        let chunk = self.next().await?;
+        Some(chunk.and_then(|data| decode_result(&data)))
+    }
+
+    /// Cancel the call once `token` is cancelled; the stream then ends
+    pub fn cancel_on(mut self, token: CancellationToken) -> Self {
+        self.cancelled = Some(Box::pin(token.cancelled_owned()));
+        self
+    }
+
+    /// Tell the cocoon to stop the call and end the stream. Chunks already
+    /// received are dropped.
+    pub fn cancel(&mut self) {
+        self.done = true;
+        self.cancelled = None;
+        if let Some(inner) = self.inner.upgrade() {
+            inner.cancel_stream(self.id);
+        }
+    }
+
+    fn poll_chunk(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        if let Some(cancelled) = &mut self.cancelled {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                self.cancel();
+            }
+        }
+        if self.done {
+            return Poll::Ready(None);
+        }
+        self.chunks.poll_recv(cx)
+    }
+}
+
+impl Stream for CallStream {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_chunk(cx)
+    }
+}
+
+impl Drop for CallStream {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.upgrade() {
+            inner.cancel_stream(self.id);
+        }
+    }
+}
+
+/// Events of one subscription
+pub struct Subscription {
+    id: u64,
+    events: mpsc::UnboundedReceiver<SubscriptionEvent>,
+    inner: Weak<Inner>,
+    /// Set by `cancel_on`
+    cancelled: Option<Pin<Box<WaitForCancellationFutureOwned>>>,
+    done: bool,
+}
+
+impl Subscription {
+    /// Next event; `None` once the plugin ended the subscription, the
+    /// client closed or the subscription was cancelled.
+    pub async fn next(&mut self) -> Option<SubscriptionEvent> {
+        std::future::poll_fn(|cx| self.poll_event(cx)).await
+    }
+
+    /// Unsubscribe once `token` is cancelled; the subscription then ends
+    pub fn cancel_on(mut self, token: CancellationToken) -> Self {
+        self.cancelled = Some(Box::pin(token.cancelled_owned()));
+        self
+    }
+
+    /// Unsubscribe now and end the subscription
+    pub fn cancel(&mut self) {
+        self.done = true;
+        self.cancelled = None;
+        if let Some(inner) = self.inner.upgrade() {
+            inner.unsubscribe(self.id);
+        }
+    }
+
+    fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<Option<SubscriptionEvent>> {
+        if let Some(cancelled) = &mut self.cancelled {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                self.cancel();
+            }
+        }
+        if self.done {
+            return Poll::Ready(None);
+        }
+        self.events.poll_recv(cx)
+    }
+}
+
+impl Stream for Subscription {
+    type Item = SubscriptionEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_event(cx)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.upgrade() {
+            inner.unsubscribe(self.id);
+        }
+    }
+}
+
+impl Inner {
+    fn new(config: ClientConfig) -> Self {
+        Self {
+            config,
+            state: watch::Sender::new(LinkState::Connecting),
+            pending: Mutex::new(Pending::default()),
+            devices: Mutex::new(Vec::new()),
+            silk_session: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    fn pending(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap()
+    }
+
+    async fn connect(self: &Arc<Self>, device: Option<&str>) -> Result<Connection> {
+        let config = &self.config;
+        let ice_servers = if config.ice_servers.is_empty() {
+            vec![DEFAULT_STUN_SERVER.to_string()]
+        } else {
+            config.ice_servers.clone()
+        };
+        let weak = Arc::downgrade(self);
+        let on_message: OnMessage = Arc::new(move |data| {
+            if let Some(inner) = weak.upgrade() {
+                inner.dispatch(&data);
+            }
+        });
+        let weak = Arc::downgrade(self);
+        let on_silk: OnSilk = Arc::new(move |event| {
+            if let Some(inner) = weak.upgrade() {
+                inner.dispatch_silk(event);
+            }
+        });
+        let connection = connection::connect(
+            ConnectOptions {
+                signaling_url: &config.signaling_url,
+                access_token: &config.access_token,
+                device,
+                mode: config.transport,
+                timeout: config.connect_timeout,
+                ice_servers: &ice_servers,
+                delegated_token: config.delegated_token.as_deref(),
+            },
+            on_message,
+            on_silk,
+        )
+        .await?;
+        *self.devices.lock().unwrap() = connection.devices.clone();
+        Ok(connection)
+    }
+
+    /// The current link, waiting up to `request_timeout` while reconnecting
+    async fn link(&self) -> Result<Link> {
+        let mut state = self.state.subscribe();
+        let wait = async {
+            loop {
+                let current = state.borrow_and_update().clone();
+                match current {
+                    LinkState::Connected(link) => return Ok(link),
+                    LinkState::Closed => return Err(AdiClientError::Closed),
+                    LinkState::Connecting => {}
+                }
+                if state.changed().await.is_err() {
+                    return Err(AdiClientError::Closed);
+                }
+            }
+        };
+        tokio::time::timeout(self.config.request_timeout, wait)
+            .await
+            .map_err(|_| AdiClientError::Timeout)?
+    }
+
+    fn close(&self) {
+        self.state.send_replace(LinkState::Closed);
+        let mut pending = self.pending();
+        pending.subscriptions.clear();
+        drop(pending);
+        self.fail_in_flight();
+    }
+
+    /// Route a message received on the ADI channel.
+    fn dispatch(&self, data: &[u8]) {
+        if frame::is_json_message(data) {
+            self.dispatch_json(data);
+            return;
+        }
+        let (header, payload) = match frame::parse_response(data) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                tracing::debug!("Dropping invalid ADI frame: {}", e);
+                return;
+            }
+        };
+
+        let mut pending = self.pending();
+        match pending.calls.remove(&header.id) {
+            Some(CallSink::Single(tx)) => {
+                let result = match header.status {
+                    ResponseStatus::Success => Ok(payload),
+                    status => Err(AdiClientError::from_response(status, &payload)),
+                };
+                let _ = tx.send(result);
+            }
+            Some(CallSink::Stream(tx)) => match header.status {
+                ResponseStatus::StreamChunk | ResponseStatus::Success => {
+                    let _ = tx.send(Ok(payload));
+                    if header.status == ResponseStatus::StreamChunk {
+                        pending.calls.insert(header.id, CallSink::Stream(tx));
+                    }
+                }
+                ResponseStatus::StreamEnd => {
+                    if !payload.is_empty() {
+                        let _ = tx.send(Ok(payload));
+                    }
+                }
+                status => {
+                    let _ = tx.send(Err(AdiClientError::from_response(status, &payload)));
+                }
+            },
+            None => tracing::debug!("ADI response for unknown request {}", header.id),
+        }
+    }
+
+    fn dispatch_json(&self, data: &[u8]) {
+        if let Ok(AdiDiscovery::PluginsList {
+            request_id,
+            plugins,
+        }) = serde_json::from_slice(data)
+        {
+            if let Some(tx) = self.pending().discovery.remove(&request_id) {
+                let _ = tx.send(plugins);
+            }
+            return;
+        }
+        let Ok(msg) = serde_json::from_slice::<AdiSubscription>(data) else {
+            tracing::debug!("Ignoring unrecognized ADI message");
+            return;
+        };
+
+        let mut pending = self.pending();
+        match msg {
+            AdiSubscription::Subscribed {
+                request_id,
+                subscription_id,
+                ..
+            } => {
+                let Some(subscribing) = pending.subscribing.remove(&request_id) else {
+                    return;
+                };
+                match pending.subscriptions.get_mut(&subscribing.subscription) {
+                    Some(active) => active.remote = Some(subscription_id),
+                    None => {
+                        // Handle dropped while subscribing
+                        drop(pending);
+                        self.send_unsubscribe(subscription_id);
+                        return;
+                    }
+                }
+                if let Some(tx) = subscribing.responder {
+                    let _ = tx.send(Ok(()));
+                }
+            }
+            AdiSubscription::Event {
+                subscription_id,
+                event,
+                data,
+            } => {
+                let active = pending
+                    .subscriptions
+                    .values()
+                    .find(|s| s.remote == Some(subscription_id));
+                if let Some(active) = active {
+                    let _ = active.events.send(SubscriptionEvent { event, data });
+                }
+            }
+            AdiSubscription::Error {
+                request_id,
+                code,
+                message,
+            } => {
+                let Some(subscribing) = pending.subscribing.remove(&request_id) else {
+                    return;
+                };
+                let removed = pending.subscriptions.remove(&subscribing.subscription);
+                match subscribing.responder {
+                    Some(tx) => {
+                        let _ = tx.send(Err(AdiClientError::Service { code, message }));
+                    }
+                    None => {
+                        if let Some(removed) = removed {
+                            tracing::warn!(
+                                "Re-subscribing to {}.{} failed: {}: {}",
+                                removed.plugin,
+                                removed.event,
+                                code,
+                                message
+                            );
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Create a Silk session; callers hold `silk_session` so only one
+    /// creation is in flight.
+    async fn create_silk_session(&self, link: &Link) -> Result<Uuid> {
+        let (tx, rx) = oneshot::channel();
+        self.pending().silk_create = Some(tx);
+        if let Err(e) = link.send_silk(silk::create_session_request()) {
+            self.pending().silk_create = None;
+            return Err(e);
+        }
+        match tokio::time::timeout(self.config.request_timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(AdiClientError::Disconnected),
+            Err(_) => {
+                self.pending().silk_create = None;
+                Err(AdiClientError::Timeout)
+            }
+        }
+    }
+
+    /// Route a relayed Silk reply. Replies for commands of other clients of
+    /// the same user are ignored.
+    fn dispatch_silk(&self, event: SilkEvent) {
+        let mut pending = self.pending();
+        match &event {
+            SilkEvent::SessionCreated { session_id } => {
+                if let Some(tx) = pending.silk_create.take() {
+                    let _ = tx.send(Ok(*session_id));
+                }
+                return;
+            }
+            SilkEvent::Error {
+                command_id: None,
+                code,
+                message,
+            } => {
+                if let Some(tx) = pending.silk_create.take() {
+                    let _ = tx.send(Err(AdiClientError::Service {
+                        code: code.clone(),
+                        message: message.clone(),
+                    }));
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let Some(command_id) = event.command_id().map(str::to_string) else {
+            return;
+        };
+        let Some(tx) = pending.commands.get(&command_id) else {
+            return;
+        };
+        if let SilkEvent::InteractiveRequired { pty_session_id, .. } = &event {
+            // The cocoon opened a PTY for it; there is no terminal to attach
+            self.send_silk(silk::pty_close_request(*pty_session_id));
+        }
+        let finished = matches!(
+            event,
+            SilkEvent::CommandCompleted { .. }
+                | SilkEvent::InteractiveRequired { .. }
+                | SilkEvent::Error { .. }
+        );
+        if tx.send(event).is_err() || finished {
+            pending.commands.remove(&command_id);
+        }
+    }
+
+    fn send_silk(&self, request: JsonValue) {
+        if let LinkState::Connected(link) = &*self.state.borrow() {
+            let _ = link.send_silk(request);
+        }
+    }
+
+    /// Fail everything waiting on the dropped connection.
+    fn fail_in_flight(&self) {
+        let mut pending = self.pending();
+        for (_, call) in pending.calls.drain() {
+            match call {
+                CallSink::Single(tx) => {
+                    let _ = tx.send(Err(AdiClientError::Disconnected));
+                }
+                CallSink::Stream(tx) => {
+                    let _ = tx.send(Err(AdiClientError::Disconnected));
+                }
+            }
+        }
+        pending.discovery.clear();
+        pending.subscribing.clear();
+        pending.silk_create = None;
+        pending.commands.clear();
+        for active in pending.subscriptions.values_mut() {
+            active.remote = None;
+        }
+    }
+
+    /// Renew all subscriptions on a new link.
+    async fn resubscribe(&self, link: &Link) {
+        let messages: Vec<AdiSubscription> = {
+            let mut pending = self.pending();
+            let Pending {
+                subscriptions,
+                subscribing,
+                ..
+            } = &mut *pending;
+            subscriptions
+                .iter()
+                .map(|(id, active)| {
+                    let request_id = Uuid::new_v4();
+                    subscribing.insert(
+                        request_id,
+                        PendingSubscribe {
+                            subscription: *id,
+                            responder: None,
+                        },
+                    );
+                    AdiSubscription::Subscribe {
+                        request_id,
+                        plugin: active.plugin.clone(),
+                        event: active.event.clone(),
+                        filter: active.filter.clone(),
+                    }
+                })
+                .collect()
+        };
+        for msg in messages {
+            if let Ok(text) = serde_json::to_string(&msg) {
+                let _ = link.send_text(text).await;
+            }
+        }
+    }
+
+    fn unsubscribe(&self, id: u64) {
+        let removed = self.pending().subscriptions.remove(&id);
+        if let Some(subscription_id) = removed.and_then(|s| s.remote) {
+            self.send_unsubscribe(subscription_id);
+        }
+    }
+
+    /// Forget a streaming call and, if it was still running, tell the
+    /// cocoon to stop it.
+    fn cancel_stream(&self, request_id: Uuid) {
+        if self.pending().calls.remove(&request_id).is_none() {
+            return;
+        }
+        let Ok(text) = serde_json::to_string(&AdiStreamControl::CancelStream { request_id }) else {
+            return;
+        };
+        self.send_text_detached(text);
+    }
+
+    fn send_unsubscribe(&self, subscription_id: Uuid) {
+        let Ok(text) = serde_json::to_string(&AdiSubscription::Unsubscribe { subscription_id })
+        else {
+            return;
+        };
+        self.send_text_detached(text);
+    }
+
+    /// Send a control message without waiting; used from `Drop`, so it is
+    /// dropped when there is no link or runtime.
+    fn send_text_detached(&self, text: String) {
+        let LinkState::Connected(link) = self.state.borrow().clone() else {
+            return;
+        };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = link.send_text(text).await;
+            });
+        }
+    }
+}
+
+/// Keep a connection up: publish its link, wait for it to drop, reconnect.
+async fn supervise(inner: Arc<Inner>, mut connection: Connection) {
+    // Reconnects go to the same cocoon even if the config named it by prefix
+    let device_id = connection.device.device_id.clone();
+    loop {
+        inner
+            .state
+            .send_replace(LinkState::Connected(connection.link.clone()));
+        inner.resubscribe(&connection.link).await;
+        connection.closed().await;
+        let hint = connection.disconnect_info();
+
+        inner.state.send_replace(LinkState::Connecting);
+        drop(connection);
+        inner.fail_in_flight();
+        if hint.as_ref().is_some_and(|info| !info.should_retry()) {
+            tracing::warn!(
+                "Not reconnecting to cocoon {}: server sent {:?}",
+                device_id,
+                hint
+            );
+            inner.close();
+            return;
+        }
+        tracing::info!("Connection to cocoon {} lost, reconnecting", device_id);
+
+        let mut backoff = inner.config.reconnect.backoff();
+        connection = loop {
+            let Some(delay) = backoff.next_delay() else {
+                tracing::warn!("Giving up reconnecting to cocoon {}", device_id);
+                inner.close();
+                return;
+            };
+            let attempt = backoff.retries();
+            // The server's retry_after is a floor for the first attempt
+            let delay = match hint.as_ref().and_then(|info| info.retry_after()) {
+                Some(after) if attempt == 1 => delay.max(after),
+                _ => delay,
+            };
+            tokio::time::sleep(delay).await;
+            match inner.connect(Some(&device_id)).await {
+                Ok(connection) => break connection,
+                Err(e) => tracing::debug!("Reconnect attempt {} failed: {}", attempt, e),
+            }
+        };
+    }
+}
+
+fn request_frame(id: Uuid, plugin: &str, method: &str, stream: bool, payload: &[u8]) -> Bytes {
+    let header = RequestHeader {
+        v: PROTOCOL_VERSION,
+        id,
+        plugin: plugin.to_string(),
+        method: method.to_string(),
+        stream,
+    };
+    frame::build_request(&header, payload)
+}
+
+fn encode_params<P: Serialize + ?Sized>(params: &P) -> Result<Bytes> {
+    match serde_json::to_value(params)? {
+        JsonValue::Null => Ok(Bytes::new()),
+        value => Ok(Bytes::from(serde_json::to_vec(&value)?)),
+    }
+}
+
+fn decode_result<R: DeserializeOwned>(data: &[u8]) -> Result<R> {
+    if data.is_empty() {
+        return Ok(serde_json::from_value(JsonValue::Null)?);
+    }
+    Ok(serde_json::from_slice(data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::ResponseHeader;
+    use serde_json::json;
+
+    fn inner() -> Inner {
+        Inner::new(ClientConfig::new("ws://localhost:8080/ws", "token"))
+    }
+
+    fn response(id: Uuid, status: ResponseStatus, payload: &[u8]) -> Vec<u8> {
+        let header = serde_json::to_vec(&ResponseHeader {
+            v: 1,
+            id,
+            status,
+            seq: 0,
+        })
+        .unwrap();
+        let mut buf = (header.len() as u32).to_be_bytes().to_vec();
+        buf.extend_from_slice(&header);
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let policy = RetryPolicy::exponential(Duration::from_secs(1), Duration::from_secs(10))
+            .max_retries(5);
+        let delays: Vec<_> = policy.backoff().map(|d| d.as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 10]);
+        assert_eq!(RetryPolicy::disabled().backoff().next(), None);
+        assert!(RetryPolicy::default().backoff().nth(100).is_some());
+    }
+
+    #[test]
+    fn test_params_and_results() {
+        assert!(encode_params(&()).unwrap().is_empty());
+        assert!(encode_params(&None::<u32>).unwrap().is_empty());
+        assert_eq!(
+            encode_params(&json!({ "n": 1 })).unwrap().as_ref(),
+            br#"{"n":1}"#
+        );
+
+        let unit: () = decode_result(b"").unwrap();
+        assert_eq!(unit, ());
+        let value: JsonValue = decode_result(br#"{"ok":true}"#).unwrap();
+        assert_eq!(value["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_routes_responses() {
+        let inner = inner();
+
+        let (tx, rx) = oneshot::channel();
+        let ok = Uuid::new_v4();
+        inner.pending().calls.insert(ok, CallSink::Single(tx));
+        inner.dispatch(&response(ok, ResponseStatus::Success, b"{}"));
+        assert_eq!(rx.await.unwrap().unwrap().as_ref(), b"{}");
+
+        let (tx, rx) = oneshot::channel();
+        let failed = Uuid::new_v4();
+        inner.pending().calls.insert(failed, CallSink::Single(tx));
+        inner.dispatch(&response(
+            failed,
+            ResponseStatus::Error,
+            br#"{"code":"invalid_params","message":"/n: not an integer"}"#,
+        ));
+        assert!(matches!(
+            rx.await.unwrap(),
+            Err(AdiClientError::Service { code, .. }) if code == "invalid_params"
+        ));
+
+        let (tx, mut chunks) = mpsc::unbounded_channel();
+        let stream = Uuid::new_v4();
+        inner.pending().calls.insert(stream, CallSink::Stream(tx));
+        inner.dispatch(&response(stream, ResponseStatus::StreamChunk, b"1"));
+        inner.dispatch(&response(stream, ResponseStatus::StreamEnd, b"2"));
+        assert_eq!(chunks.recv().await.unwrap().unwrap().as_ref(), b"1");
+        assert_eq!(chunks.recv().await.unwrap().unwrap().as_ref(), b"2");
+        assert!(chunks.recv().await.is_none());
+        assert!(inner.pending().calls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_stream_ends_and_forgets_call() {
+        let inner = Arc::new(inner());
+        let (tx, chunks) = mpsc::unbounded_channel();
+        let id = Uuid::new_v4();
+        inner.pending().calls.insert(id, CallSink::Stream(tx));
+        let token = CancellationToken::new();
+        let mut stream = CallStream {
+            id,
+            chunks,
+            inner: Arc::downgrade(&inner),
+            cancelled: None,
+            done: false,
+        }
+        .cancel_on(token.clone());
+
+        inner.dispatch(&response(id, ResponseStatus::StreamChunk, b"1"));
+        assert_eq!(stream.next().await.unwrap().unwrap().as_ref(), b"1");
+
+        inner.dispatch(&response(id, ResponseStatus::StreamChunk, b"2"));
+        token.cancel();
+        assert!(stream.next().await.is_none());
+        assert!(inner.pending().calls.is_empty());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_survive_reconnect() {
+        let inner = inner();
+        let (events_tx, mut events) = mpsc::unbounded_channel();
+        let (tx, rx) = oneshot::channel();
+        let request_id = Uuid::new_v4();
+        {
+            let mut pending = inner.pending();
+            pending.subscriptions.insert(
+                0,
+                ActiveSubscription {
+                    plugin: "adi.tasks".to_string(),
+                    event: "task_updated".to_string(),
+                    filter: None,
+                    remote: None,
+                    events: events_tx,
+                },
+            );
+            pending.subscribing.insert(
+                request_id,
+                PendingSubscribe {
+                    subscription: 0,
+                    responder: Some(tx),
+                },
+            );
+        }
+
+        let first = Uuid::new_v4();
+        let subscribed = json!({
+            "type": "subscribed",
+            "request_id": request_id,
+            "subscription_id": first,
+            "plugin": "adi.tasks",
+            "event": "task_updated",
+        });
+        inner.dispatch(subscribed.to_string().as_bytes());
+        rx.await.unwrap().unwrap();
+
+        let event = json!({ "type": "event", "subscription_id": first, "event": "task_updated", "data": { "id": 7 } });
+        inner.dispatch(event.to_string().as_bytes());
+        assert_eq!(events.recv().await.unwrap().data["id"], 7);
+
+        // After a reconnect the old cocoon-side ID is stale until re-subscribed
+        inner.fail_in_flight();
+        inner.dispatch(event.to_string().as_bytes());
+        assert!(events.try_recv().is_err());
+
+        let renewed = Uuid::new_v4();
+        let request_id = Uuid::new_v4();
+        inner.pending().subscribing.insert(
+            request_id,
+            PendingSubscribe {
+                subscription: 0,
+                responder: None,
+            },
+        );
+        let subscribed = json!({
+            "type": "subscribed",
+            "request_id": request_id,
+            "subscription_id": renewed,
+            "plugin": "adi.tasks",
+            "event": "task_updated",
+        });
+        inner.dispatch(subscribed.to_string().as_bytes());
+        let event = json!({ "type": "event", "subscription_id": renewed, "event": "task_updated", "data": { "id": 8 } });
+        inner.dispatch(event.to_string().as_bytes());
+        assert_eq!(events.recv().await.unwrap().data["id"], 8);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_silk_routes_by_command() {
+        let inner = inner();
+        let silk = |value: JsonValue| serde_json::from_value::<SilkEvent>(value).unwrap();
+
+        let (tx, rx) = oneshot::channel();
+        inner.pending().silk_create = Some(tx);
+        let session_id = Uuid::new_v4();
+        inner.dispatch_silk(silk(json!({
+            "type": "silk_create_session_response",
+            "session_id": session_id,
+            "cwd": "/",
+            "shell": "/bin/sh",
+        })));
+        assert_eq!(rx.await.unwrap().unwrap(), session_id);
+
+        let (tx, commands) = mpsc::unbounded_channel();
+        inner.pending().commands.insert("c1".to_string(), tx);
+        let output = json!({ "type": "silk_output", "session_id": session_id, "command_id": "c1", "stream": "stdout", "data": "ok" });
+        inner.dispatch_silk(silk(output));
+        inner.dispatch_silk(silk(json!({ "type": "silk_output", "session_id": session_id, "command_id": "other", "stream": "stdout", "data": "x" })));
+        inner.dispatch_silk(silk(json!({ "type": "silk_command_completed", "session_id": session_id, "command_id": "c1", "exit_code": 0, "cwd": "/" })));
+
+        assert!(inner.pending().commands.is_empty());
+        let output = ExecStream::new(None, commands).collect().await.unwrap();
+        assert_eq!(output.stdout, "ok");
+        assert!(output.success());
+    }
+}
@@ -0,0 +1,357 @@
+//! One connection to a cocoon: an authenticated signaling socket and the ADI
+//! channel on top of it, either a WebRTC data channel or the relay.
+
+use crate::error::{AdiClientError, Result};
+use crate::protocol::{WebRtcMessage, ADI_CHANNEL};
+use crate::signaling::{self, select_device, DeviceSender, SignalingStream};
+use crate::silk::SilkEvent;
+use base64::Engine;
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use lib_signaling_protocol::{DeviceInfo, DisconnectInfo, RelayPriority, SignalingMessage};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+#[cfg(feature = "webrtc")]
+use crate::peer;
+#[cfg(feature = "webrtc")]
+use webrtc::data_channel::RTCDataChannel;
+#[cfg(feature = "webrtc")]
+use webrtc::peer_connection::RTCPeerConnection;
+
+/// Called with every message received on the ADI channel
+pub(crate) type OnMessage = Arc<dyn Fn(Bytes) + Send + Sync>;
+
+/// Called with every relayed Silk reply
+pub(crate) type OnSilk = Arc<dyn Fn(SilkEvent) + Send + Sync>;
+
+/// How ADI traffic reaches the cocoon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// Direct WebRTC data channel
+    WebRtc,
+    /// Through the signaling server
+    Relay,
+}
+
+/// Which transports [`AdiClient`](crate::AdiClient) may use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransportMode {
+    /// WebRTC, falling back to the relay when no peer connection can be made
+    #[default]
+    Auto,
+    /// WebRTC only; connecting fails without a peer connection
+    WebRtc,
+    /// Relay only
+    Relay,
+}
+
+/// Where to send ADI messages on the current connection
+#[derive(Clone)]
+pub(crate) enum Link {
+    Relay {
+        device: DeviceSender,
+        session_id: String,
+    },
+    #[cfg(feature = "webrtc")]
+    DataChannel {
+        dc: Arc<RTCDataChannel>,
+        device: DeviceSender,
+    },
+}
+
+impl Link {
+    pub fn transport(&self) -> Transport {
+        match self {
+            Self::Relay { .. } => Transport::Relay,
+            #[cfg(feature = "webrtc")]
+            Self::DataChannel { .. } => Transport::WebRtc,
+        }
+    }
+
+    /// Relay a Silk request to the cocoon; Silk never uses the data channel.
+    pub fn send_silk(&self, request: serde_json::Value) -> Result<()> {
+        let device = match self {
+            Self::Relay { device, .. } => device,
+            #[cfg(feature = "webrtc")]
+            Self::DataChannel { device, .. } => device,
+        };
+        if device.send(&request, RelayPriority::Interactive) {
+            Ok(())
+        } else {
+            Err(AdiClientError::Disconnected)
+        }
+    }
+
+    pub async fn send_frame(&self, frame: Bytes) -> Result<()> {
+        match self {
+            Self::Relay { device, session_id } => relay(
+                device,
+                session_id,
+                base64::engine::general_purpose::STANDARD.encode(&frame),
+                true,
+            ),
+            #[cfg(feature = "webrtc")]
+            Self::DataChannel { dc, .. } => dc
+                .send(&frame)
+                .await
+                .map(|_| ())
+                .map_err(|_| AdiClientError::Disconnected),
+        }
+    }
+
+    pub async fn send_text(&self, text: String) -> Result<()> {
+        match self {
+            Self::Relay { device, session_id } => relay(device, session_id, text, false),
+            #[cfg(feature = "webrtc")]
+            Self::DataChannel { dc, .. } => dc
+                .send_text(text)
+                .await
+                .map(|_| ())
+                .map_err(|_| AdiClientError::Disconnected),
+        }
+    }
+}
+
+/// Send on the "adi" channel as relayed `webrtc_data`
+fn relay(device: &DeviceSender, session_id: &str, data: String, binary: bool) -> Result<()> {
+    let msg = WebRtcMessage::WebrtcData {
+        session_id: session_id.to_string(),
+        channel: ADI_CHANNEL.to_string(),
+        data,
+        binary,
+    };
+    if device.send(&msg, RelayPriority::Interactive) {
+        Ok(())
+    } else {
+        Err(AdiClientError::Disconnected)
+    }
+}
+
+pub(crate) struct ConnectOptions<'a> {
+    pub signaling_url: &'a str,
+    pub access_token: &'a str,
+    pub device: Option<&'a str>,
+    pub mode: TransportMode,
+    pub timeout: Duration,
+    #[cfg_attr(not(feature = "webrtc"), allow(dead_code))]
+    pub ice_servers: &'a [String],
+    pub delegated_token: Option<&'a str>,
+}
+
+pub(crate) struct Connection {
+    pub link: Link,
+    pub device: DeviceInfo,
+    pub devices: Vec<DeviceInfo>,
+    closed: mpsc::Receiver<()>,
+    /// Reconnect hint the server sent before closing the socket
+    disconnect: Arc<Mutex<Option<DisconnectInfo>>>,
+    tasks: Vec<JoinHandle<()>>,
+    #[cfg(feature = "webrtc")]
+    peer: Option<Arc<RTCPeerConnection>>,
+}
+
+impl Connection {
+    /// Resolves once the signaling socket or the data channel is gone.
+    pub async fn closed(&mut self) {
+        let _ = self.closed.recv().await;
+    }
+
+    pub fn disconnect_info(&self) -> Option<DisconnectInfo> {
+        self.disconnect.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+        #[cfg(feature = "webrtc")]
+        if let Some(peer) = self.peer.take() {
+            tokio::spawn(async move {
+                let _ = peer.close().await;
+            });
+        }
+    }
+}
+
+/// Authenticate, pick the device and open the ADI channel to it.
+pub(crate) async fn connect(
+    options: ConnectOptions<'_>,
+    on_message: OnMessage,
+    on_silk: OnSilk,
+) -> Result<Connection> {
+    let (mut sink, stream, devices) =
+        signaling::connect(options.signaling_url, options.access_token, options.timeout).await?;
+    let device = match options.delegated_token {
+        // A shared device is not in the user's list
+        Some(_) => shared_device(options.device)?,
+        None => select_device(&devices, options.device)?,
+    };
+
+    let (outbox, mut outbox_rx) = mpsc::unbounded_channel::<SignalingMessage>();
+    let (closed_tx, closed) = mpsc::channel(1);
+    let writer = tokio::spawn(async move {
+        while let Some(msg) = outbox_rx.recv().await {
+            if signaling::send(&mut sink, &msg).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let session_id = Uuid::new_v4().to_string();
+    let (webrtc_tx, webrtc_rx) = mpsc::unbounded_channel();
+    let disconnect = Arc::new(Mutex::new(None));
+    let reader = tokio::spawn(read_signaling(
+        stream,
+        session_id.clone(),
+        webrtc_tx,
+        on_message.clone(),
+        on_silk,
+        disconnect.clone(),
+        closed_tx.clone(),
+    ));
+
+    let sender = DeviceSender {
+        device_id: device.device_id.clone().into(),
+        delegated_token: options.delegated_token.map(str::to_owned),
+        outbox,
+    };
+    #[cfg_attr(not(feature = "webrtc"), allow(unused_mut))]
+    let mut connection = Connection {
+        link: Link::Relay {
+            device: sender.clone(),
+            session_id: session_id.clone(),
+        },
+        device,
+        devices,
+        closed,
+        disconnect,
+        tasks: vec![writer, reader],
+        #[cfg(feature = "webrtc")]
+        peer: None,
+    };
+
+    match options.mode {
+        TransportMode::Relay => {}
+        #[cfg(feature = "webrtc")]
+        mode => {
+            let negotiated = peer::connect(
+                &sender,
+                &session_id,
+                webrtc_rx,
+                options.ice_servers,
+                on_message,
+                closed_tx,
+                options.timeout,
+            )
+            .await;
+            match negotiated {
+                Ok((peer, dc, task)) => {
+                    connection.link = Link::DataChannel { dc, device: sender };
+                    connection.peer = Some(peer);
+                    connection.tasks.push(task);
+                }
+                Err(e) if mode == TransportMode::Auto => {
+                    tracing::info!("WebRTC unavailable ({}), using the signaling relay", e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        #[cfg(not(feature = "webrtc"))]
+        TransportMode::WebRtc => {
+            drop(webrtc_rx);
+            return Err(AdiClientError::Connection(
+                "built without the `webrtc` feature".to_string(),
+            ));
+        }
+        #[cfg(not(feature = "webrtc"))]
+        TransportMode::Auto => drop(webrtc_rx),
+    }
+    Ok(connection)
+}
+
+/// The device a delegated token was issued for, addressed by its full ID.
+fn shared_device(device: Option<&str>) -> Result<DeviceInfo> {
+    let device_id = device.ok_or_else(|| {
+        AdiClientError::Connection("a delegated token needs the device's full ID".to_string())
+    })?;
+    Ok(DeviceInfo {
+        device_id: device_id.into(),
+        tags: Default::default(),
+        online: true,
+        device_type: None,
+        device_config: None,
+    })
+}
+
+/// Deliver relayed ADI messages of our session and Silk replies, hand
+/// WebRTC negotiation messages to `webrtc_tx` and keep the server's
+/// disconnect hint.
+async fn read_signaling(
+    mut stream: SignalingStream,
+    session_id: String,
+    webrtc_tx: mpsc::UnboundedSender<WebRtcMessage>,
+    on_message: OnMessage,
+    on_silk: OnSilk,
+    disconnect: Arc<Mutex<Option<DisconnectInfo>>>,
+    closed_tx: mpsc::Sender<()>,
+) {
+    while let Some(Ok(msg)) = stream.next().await {
+        let Message::Text(text) = msg else {
+            continue;
+        };
+        let payload = match serde_json::from_str(&text) {
+            Ok(SignalingMessage::SyncData { payload, .. }) => payload,
+            Ok(SignalingMessage::DeviceDisconnect { info }) => {
+                tracing::info!("Signaling server is closing the connection: {:?}", info);
+                *disconnect.lock().unwrap() = Some(info);
+                continue;
+            }
+            _ => continue,
+        };
+        if SilkEvent::matches(&payload) {
+            if let Ok(event) = serde_json::from_value::<SilkEvent>(payload) {
+                on_silk(event);
+            }
+            continue;
+        }
+        let Ok(msg) = serde_json::from_value::<WebRtcMessage>(payload) else {
+            continue;
+        };
+        if msg.session_id() != session_id {
+            continue;
+        }
+        match msg {
+            WebRtcMessage::WebrtcData {
+                channel,
+                data,
+                binary,
+                ..
+            } if channel == ADI_CHANNEL => {
+                let data = if binary {
+                    match base64::engine::general_purpose::STANDARD.decode(&data) {
+                        Ok(data) => Bytes::from(data),
+                        Err(e) => {
+                            tracing::debug!("Dropping relayed ADI frame: {}", e);
+                            continue;
+                        }
+                    }
+                } else {
+                    Bytes::from(data)
+                };
+                on_message(data);
+            }
+            msg => {
+                let _ = webrtc_tx.send(msg);
+            }
+        }
+    }
+    let _ = closed_tx.try_send(());
+}
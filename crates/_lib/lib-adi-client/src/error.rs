@@ -0,0 +1,86 @@
+use crate::frame::{FrameError, ResponseStatus};
+
+pub type Result<T> = std::result::Result<T, AdiClientError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdiClientError {
+    #[error("login failed: {0}")]
+    Login(String),
+
+    #[error("signaling connection failed: {0}")]
+    Connection(String),
+
+    #[error("authentication failed: {0}")]
+    Auth(String),
+
+    #[error("no device matches '{0}'")]
+    DeviceNotFound(String),
+
+    #[error("device '{0}' is offline")]
+    DeviceOffline(String),
+
+    #[error("device prefix '{0}' is ambiguous")]
+    AmbiguousDevice(String),
+
+    #[error("{0} devices are online, pick one")]
+    DeviceRequired(usize),
+
+    /// The connection to the cocoon dropped while the request was in flight
+    #[error("disconnected from cocoon")]
+    Disconnected,
+
+    /// The client was closed, or gave up reconnecting
+    #[error("client closed")]
+    Closed,
+
+    #[error("request timed out")]
+    Timeout,
+
+    /// Rejected by the cocoon's router before reaching the plugin
+    #[error("{status:?}: {message}")]
+    Router {
+        status: ResponseStatus,
+        message: String,
+    },
+
+    /// Returned by the plugin, in `AdiServiceError` form
+    #[error("{code}: {message}")]
+    Service { code: String, message: String },
+
+    /// The Silk command needs a terminal and was not run
+    #[error("command needs a terminal: {0}")]
+    Interactive(String),
+
+    #[error("invalid frame: {0}")]
+    Frame(#[from] FrameError),
+
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl AdiClientError {
+    /// Error for a response frame with a non-success status.
+    ///
+    /// Plugin errors carry an `AdiServiceError` JSON payload; router errors
+    /// a plain message.
+    pub(crate) fn from_response(status: ResponseStatus, payload: &[u8]) -> Self {
+        #[derive(serde::Deserialize)]
+        struct ServiceError {
+            code: String,
+            message: String,
+        }
+
+        if status == ResponseStatus::Error {
+            if let Ok(e) = serde_json::from_slice::<ServiceError>(payload) {
+                return Self::Service {
+                    code: e.code,
+                    message: e.message,
+                };
+            }
+        }
+        Self::Router {
+            status,
+            message: String::from_utf8_lossy(payload).into_owned(),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Client half of the ADI binary framing.
+//!
+//! Frame layout: `[header_len: u32 BE][JSON header][payload bytes]`, the same
+//! as the cocoon's `adi_frame` module. Requests carry a [`RequestHeader`],
+//! responses a [`ResponseHeader`]; payloads are opaque to the framing.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Framing version spoken by this client
+pub const PROTOCOL_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestHeader {
+    pub v: u8,
+    pub id: Uuid,
+    pub plugin: String,
+    pub method: String,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseHeader {
+    pub v: u8,
+    pub id: Uuid,
+    pub status: ResponseStatus,
+    /// Sequence number of a stream chunk, 0 for single responses
+    #[serde(default)]
+    pub seq: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Error,
+    PluginNotFound,
+    MethodNotFound,
+    StreamChunk,
+    StreamEnd,
+    InvalidRequest,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FrameError {
+    #[error("frame too short (need at least 4 bytes for header length)")]
+    TooShort,
+    #[error("header length {declared} exceeds available data {available}")]
+    HeaderTooLarge { declared: u32, available: usize },
+    #[error("invalid header JSON: {0}")]
+    InvalidHeaderJson(#[from] serde_json::Error),
+}
+
+pub fn build_request(header: &RequestHeader, payload: &[u8]) -> Bytes {
+    let header_json = serde_json::to_vec(header).expect("RequestHeader is always serializable");
+    let mut buf = BytesMut::with_capacity(4 + header_json.len() + payload.len());
+    buf.put_u32(header_json.len() as u32);
+    buf.put_slice(&header_json);
+    buf.put_slice(payload);
+    buf.freeze()
+}
+
+pub fn parse_response(data: &[u8]) -> Result<(ResponseHeader, Bytes), FrameError> {
+    if data.len() < 4 {
+        return Err(FrameError::TooShort);
+    }
+    let mut cursor = data;
+    let header_len = cursor.get_u32() as usize;
+    if cursor.len() < header_len {
+        return Err(FrameError::HeaderTooLarge {
+            declared: header_len as u32,
+            available: cursor.len(),
+        });
+    }
+    let header: ResponseHeader = serde_json::from_slice(&cursor[..header_len])?;
+    Ok((header, Bytes::copy_from_slice(&cursor[header_len..])))
+}
+
+/// Whether a message on the "adi" channel is JSON text rather than a frame.
+///
+/// The cocoon sends discovery and subscription replies as JSON in binary
+/// messages; a frame starts with its header length, whose first byte is 0
+/// for any header under 16 MiB.
+pub fn is_json_message(data: &[u8]) -> bool {
+    data.first() == Some(&b'{')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(header: &ResponseHeader, payload: &[u8]) -> Vec<u8> {
+        let header_json = serde_json::to_vec(header).unwrap();
+        let mut buf = (header_json.len() as u32).to_be_bytes().to_vec();
+        buf.extend_from_slice(&header_json);
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    #[test]
+    fn test_request_layout() {
+        let header = RequestHeader {
+            v: PROTOCOL_VERSION,
+            id: Uuid::nil(),
+            plugin: "adi.tasks".to_string(),
+            method: "list".to_string(),
+            stream: false,
+        };
+        let frame = build_request(&header, b"{}");
+        let header_len = u32::from_be_bytes([frame[0], frame[1], frame[2], frame[3]]) as usize;
+        let parsed: RequestHeader = serde_json::from_slice(&frame[4..4 + header_len]).unwrap();
+        assert_eq!(parsed.plugin, "adi.tasks");
+        assert_eq!(&frame[4 + header_len..], b"{}");
+        assert!(!is_json_message(&frame));
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let id = Uuid::new_v4();
+        let header = ResponseHeader {
+            v: 1,
+            id,
+            status: ResponseStatus::StreamChunk,
+            seq: 2,
+        };
+        let (parsed, payload) = parse_response(&response(&header, b"chunk")).unwrap();
+        assert_eq!(parsed.id, id);
+        assert_eq!(parsed.status, ResponseStatus::StreamChunk);
+        assert_eq!(parsed.seq, 2);
+        assert_eq!(payload.as_ref(), b"chunk");
+
+        assert!(matches!(parse_response(&[0, 0]), Err(FrameError::TooShort)));
+        assert!(matches!(
+            parse_response(&[0, 0, 0, 9, b'{']),
+            Err(FrameError::HeaderTooLarge { declared: 9, .. })
+        ));
+        assert!(is_json_message(br#"{"type":"plugins_list"}"#));
+    }
+}
@@ -0,0 +1,54 @@
+//! Client SDK for apps talking to a cocoon's ADI services.
+//!
+//! One dependency covers the whole path: log in to get an access token
+//! ([`login`]), list the user's devices ([`list_devices`]), open an ADI
+//! channel to a cocoon over WebRTC or the signaling relay ([`AdiClient`]),
+//! then make typed calls, read streams, follow subscriptions and run shell
+//! commands through Silk. The client reconnects on its own and renews
+//! subscriptions when it does.
+//!
+//! ```no_run
+//! use lib_adi_client::{AdiClient, ClientConfig};
+//! use serde_json::{json, Value};
+//!
+//! # async fn run() -> lib_adi_client::Result<()> {
+//! let token = lib_adi_client::login("https://auth.example.com", "me", "secret").await?;
+//! let client = AdiClient::connect(
+//!     ClientConfig::new("wss://signal.example.com/ws", token.access_token).device("laptop"),
+//! )
+//! .await?;
+//!
+//! let tasks = client.service("adi.tasks");
+//! let open: Vec<Value> = tasks.call("list", &json!({ "status": "open" })).await?;
+//!
+//! let mut updates = tasks.subscribe("task_updated", None).await?;
+//! while let Some(event) = updates.next().await {
+//!     println!("{}: {}", event.event, event.data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod connection;
+pub mod error;
+pub mod frame;
+pub mod login;
+#[cfg(feature = "webrtc")]
+mod peer;
+mod protocol;
+pub mod signaling;
+mod silk;
+
+pub use client::{AdiClient, CallStream, ClientConfig, ConnectionState, Service, Subscription};
+pub use connection::{Transport, TransportMode};
+pub use error::{AdiClientError, Result};
+pub use lib_retry::RetryPolicy;
+pub use tokio_util::sync::CancellationToken;
+pub use login::{login, AuthToken};
+pub use protocol::ADI_CHANNEL;
+pub use signaling::{display_name, list_devices, select_device};
+pub use silk::{ExecEvent, ExecOutput, ExecStream, OutputStream};
+
+pub use lib_adi_service::{AdiMethodInfo, AdiPluginInfo, SubscriptionEvent};
+pub use lib_signaling_protocol::DeviceInfo;
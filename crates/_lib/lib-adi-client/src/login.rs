@@ -0,0 +1,78 @@
+//! Obtaining an access token from the ADI auth service.
+//!
+//! `auth_url` is the base URL the auth service routes (`/login`, `/verify`, ...)
+//! are served under.
+
+use crate::error::{AdiClientError, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthToken {
+    pub access_token: String,
+    pub token_type: String,
+    /// Lifetime in seconds
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+/// Log in with a login and password.
+pub async fn login(auth_url: &str, login: &str, password: &str) -> Result<AuthToken> {
+    post(
+        auth_url,
+        "login",
+        serde_json::json!({ "login": login, "password": password }),
+    )
+    .await
+}
+
+/// Email a one-time code to `email`, to be passed to [`verify_code`].
+pub async fn request_code(auth_url: &str, email: &str) -> Result<()> {
+    post::<serde_json::Value>(
+        auth_url,
+        "request-code",
+        serde_json::json!({ "email": email }),
+    )
+    .await
+    .map(|_| ())
+}
+
+/// Exchange an emailed code for an access token.
+pub async fn verify_code(auth_url: &str, email: &str, code: &str) -> Result<AuthToken> {
+    post(
+        auth_url,
+        "verify",
+        serde_json::json!({ "email": email, "code": code }),
+    )
+    .await
+}
+
+async fn post<T: serde::de::DeserializeOwned>(
+    auth_url: &str,
+    path: &str,
+    body: serde_json::Value,
+) -> Result<T> {
+    let url = format!("{}/{}", auth_url.trim_end_matches('/'), path);
+    let response = reqwest::Client::new()
+        .post(&url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| AdiClientError::Login(format!("{}: {}", url, e)))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let message = match response.json::<ApiError>().await {
+            Ok(e) => e.message,
+            Err(_) => status.to_string(),
+        };
+        return Err(AdiClientError::Login(message));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| AdiClientError::Login(format!("invalid response from {}: {}", url, e)))
+}
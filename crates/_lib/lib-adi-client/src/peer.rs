@@ -0,0 +1,261 @@
+//! WebRTC offerer side of an ADI session.
+//!
+//! The client opens the "adi" data channel itself and negotiates through the
+//! relay: `webrtc_start_session`, then the offer, the cocoon's answer and
+//! trickled ICE candidates in both directions.
+
+use crate::connection::OnMessage;
+use crate::error::{AdiClientError, Result};
+use crate::protocol::{WebRtcMessage, ADI_CHANNEL};
+use crate::signaling::DeviceSender;
+use lib_signaling_protocol::RelayPriority;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::APIBuilder;
+use webrtc::data_channel::data_channel_message::DataChannelMessage;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::interceptor::registry::Registry;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+
+/// Negotiate a peer connection and wait until its ADI channel is open.
+///
+/// The returned task keeps adding trickled candidates and reports on
+/// `closed_tx` when the connection or channel goes away.
+pub(crate) async fn connect(
+    device: &DeviceSender,
+    session_id: &str,
+    mut events: mpsc::UnboundedReceiver<WebRtcMessage>,
+    ice_servers: &[String],
+    on_message: OnMessage,
+    closed_tx: mpsc::Sender<()>,
+    timeout: Duration,
+) -> Result<(Arc<RTCPeerConnection>, Arc<RTCDataChannel>, JoinHandle<()>)> {
+    let mut media_engine = MediaEngine::default();
+    let registry =
+        register_default_interceptors(Registry::new(), &mut media_engine).map_err(|e| {
+            AdiClientError::Connection(format!("Failed to register interceptors: {}", e))
+        })?;
+    let api = APIBuilder::new()
+        .with_media_engine(media_engine)
+        .with_interceptor_registry(registry)
+        .build();
+    let config = RTCConfiguration {
+        ice_servers: vec![RTCIceServer {
+            urls: ice_servers.to_vec(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let peer = Arc::new(api.new_peer_connection(config).await.map_err(|e| {
+        AdiClientError::Connection(format!("Failed to create peer connection: {}", e))
+    })?);
+
+    let (state_tx, mut state_rx) = mpsc::unbounded_channel();
+    peer.on_peer_connection_state_change(Box::new(move |state| {
+        let _ = state_tx.send(state);
+        Box::pin(async {})
+    }));
+
+    let dc = peer
+        .create_data_channel(ADI_CHANNEL, None)
+        .await
+        .map_err(|e| AdiClientError::Connection(format!("Failed to create data channel: {}", e)))?;
+    dc.on_message(Box::new(move |msg: DataChannelMessage| {
+        on_message(msg.data);
+        Box::pin(async {})
+    }));
+    let (open_tx, open_rx) = oneshot::channel();
+    let open_tx = Mutex::new(Some(open_tx));
+    dc.on_open(Box::new(move || {
+        if let Some(tx) = open_tx.lock().unwrap().take() {
+            let _ = tx.send(());
+        }
+        Box::pin(async {})
+    }));
+
+    let negotiated = tokio::time::timeout(
+        timeout,
+        negotiate(
+            &peer,
+            device,
+            session_id,
+            &mut events,
+            &mut state_rx,
+            open_rx,
+        ),
+    )
+    .await
+    .unwrap_or_else(|_| {
+        Err(AdiClientError::Connection(
+            "WebRTC negotiation timed out".to_string(),
+        ))
+    });
+    if let Err(e) = negotiated {
+        device.send(
+            &WebRtcMessage::WebrtcSessionEnded {
+                session_id: session_id.to_string(),
+                reason: Some("relay_fallback".to_string()),
+            },
+            RelayPriority::Interactive,
+        );
+        let _ = peer.close().await;
+        return Err(e);
+    }
+
+    // Registered only now: a channel torn down by a failed negotiation must
+    // not end the relay connection that replaces it
+    let close_tx = closed_tx.clone();
+    dc.on_close(Box::new(move || {
+        let _ = close_tx.try_send(());
+        Box::pin(async {})
+    }));
+
+    let task_peer = peer.clone();
+    let task = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                state = state_rx.recv() => match state {
+                    Some(RTCPeerConnectionState::Failed)
+                    | Some(RTCPeerConnectionState::Closed)
+                    | Some(RTCPeerConnectionState::Disconnected)
+                    | None => break,
+                    Some(_) => {}
+                },
+                msg = events.recv() => match msg {
+                    Some(WebRtcMessage::WebrtcIceCandidate { candidate, sdp_mid, sdp_mline_index, .. }) => {
+                        let _ = task_peer
+                            .add_ice_candidate(ice_candidate(candidate, sdp_mid, sdp_mline_index))
+                            .await;
+                    }
+                    Some(WebRtcMessage::WebrtcSessionEnded { .. }) | Some(WebRtcMessage::WebrtcError { .. }) | None => break,
+                    Some(_) => {}
+                },
+            }
+        }
+        let _ = closed_tx.try_send(());
+    });
+    Ok((peer, dc, task))
+}
+
+async fn negotiate(
+    peer: &Arc<RTCPeerConnection>,
+    device: &DeviceSender,
+    session_id: &str,
+    events: &mut mpsc::UnboundedReceiver<WebRtcMessage>,
+    state_rx: &mut mpsc::UnboundedReceiver<RTCPeerConnectionState>,
+    mut open_rx: oneshot::Receiver<()>,
+) -> Result<()> {
+    let candidate_device = device.clone();
+    let candidate_session = session_id.to_string();
+    peer.on_ice_candidate(Box::new(move |candidate| {
+        if let Some(json) = candidate.and_then(|c| c.to_json().ok()) {
+            // webrtc-rs leaves sdp_mid empty; the data channel is media section "0"
+            let sdp_mid = match json.sdp_mid.as_deref() {
+                Some("") | None => Some("0".to_string()),
+                other => other.map(|s| s.to_string()),
+            };
+            candidate_device.send(
+                &WebRtcMessage::WebrtcIceCandidate {
+                    session_id: candidate_session.clone(),
+                    candidate: json.candidate,
+                    sdp_mid,
+                    sdp_mline_index: json.sdp_mline_index.map(|i| i as i32),
+                },
+                RelayPriority::Interactive,
+            );
+        }
+        Box::pin(async {})
+    }));
+
+    let offer = peer
+        .create_offer(None)
+        .await
+        .map_err(|e| AdiClientError::Connection(format!("Failed to create offer: {}", e)))?;
+
+    // Candidates are gathered once the local description is set, so the
+    // cocoon learns about the session before the first one arrives
+    device.send(
+        &WebRtcMessage::WebrtcStartSession {
+            session_id: session_id.to_string(),
+            device_id: device.device_id.clone(),
+            user_id: None,
+            data_channels: Some(vec![ADI_CHANNEL.to_string()]),
+            delegated_token: device.delegated_token.clone(),
+        },
+        RelayPriority::Interactive,
+    );
+    device.send(
+        &WebRtcMessage::WebrtcOffer {
+            session_id: session_id.to_string(),
+            sdp: offer.sdp.clone(),
+        },
+        RelayPriority::Interactive,
+    );
+    peer.set_local_description(offer).await.map_err(|e| {
+        AdiClientError::Connection(format!("Failed to set local description: {}", e))
+    })?;
+
+    let mut answered = false;
+    let mut pending = Vec::new();
+    loop {
+        tokio::select! {
+            opened = &mut open_rx => {
+                return opened.map_err(|_| AdiClientError::Connection("data channel closed".to_string()))
+            }
+            state = state_rx.recv() => match state {
+                Some(RTCPeerConnectionState::Failed) | Some(RTCPeerConnectionState::Closed) | None => {
+                    return Err(AdiClientError::Connection("connection failed".to_string()))
+                }
+                Some(_) => {}
+            },
+            msg = events.recv() => match msg {
+                Some(WebRtcMessage::WebrtcAnswer { sdp, .. }) => {
+                    let answer = RTCSessionDescription::answer(sdp).map_err(|e| AdiClientError::Connection(e.to_string()))?;
+                    peer.set_remote_description(answer)
+                        .await
+                        .map_err(|e| AdiClientError::Connection(format!("Failed to set remote description: {}", e)))?;
+                    answered = true;
+                    for candidate in pending.drain(..) {
+                        let _ = peer.add_ice_candidate(candidate).await;
+                    }
+                }
+                Some(WebRtcMessage::WebrtcIceCandidate { candidate, sdp_mid, sdp_mline_index, .. }) => {
+                    let candidate = ice_candidate(candidate, sdp_mid, sdp_mline_index);
+                    if answered {
+                        let _ = peer.add_ice_candidate(candidate).await;
+                    } else {
+                        pending.push(candidate);
+                    }
+                }
+                Some(WebRtcMessage::WebrtcError { message, .. }) => return Err(AdiClientError::Connection(message)),
+                Some(WebRtcMessage::WebrtcSessionEnded { reason, .. }) => {
+                    return Err(AdiClientError::Connection(reason.unwrap_or_else(|| "session ended".to_string())))
+                }
+                Some(_) => {}
+                None => return Err(AdiClientError::Disconnected),
+            },
+        }
+    }
+}
+
+fn ice_candidate(
+    candidate: String,
+    sdp_mid: Option<String>,
+    sdp_mline_index: Option<i32>,
+) -> RTCIceCandidateInit {
+    RTCIceCandidateInit {
+        candidate,
+        sdp_mid,
+        sdp_mline_index: sdp_mline_index.map(|i| i as u16),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,129 @@
+//! JSON messages exchanged with the cocoon.
+//!
+//! Mirrors the subset of the cocoon protocol a client needs: the `webrtc`
+//! channel messages relayed through signaling to set up a session, and the
+//! text discovery, subscription and stream control messages of the "adi"
+//! data channel.
+
+use lib_adi_service::AdiPluginInfo;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+/// Data channel (and relay channel name) carrying ADI traffic
+pub const ADI_CHANNEL: &str = "adi";
+
+/// Named like the cocoon's `CocoonMessage` variants they mirror
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum WebRtcMessage {
+    WebrtcStartSession {
+        session_id: String,
+        device_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        user_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data_channels: Option<Vec<String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delegated_token: Option<String>,
+    },
+    WebrtcOffer {
+        session_id: String,
+        sdp: String,
+    },
+    WebrtcAnswer {
+        session_id: String,
+        sdp: String,
+    },
+    WebrtcIceCandidate {
+        session_id: String,
+        candidate: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sdp_mid: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sdp_mline_index: Option<i32>,
+    },
+    WebrtcSessionEnded {
+        session_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    WebrtcData {
+        session_id: String,
+        channel: String,
+        data: String,
+        binary: bool,
+    },
+    WebrtcError {
+        session_id: String,
+        code: String,
+        message: String,
+    },
+}
+
+impl WebRtcMessage {
+    pub(crate) fn session_id(&self) -> &str {
+        match self {
+            Self::WebrtcStartSession { session_id, .. }
+            | Self::WebrtcOffer { session_id, .. }
+            | Self::WebrtcAnswer { session_id, .. }
+            | Self::WebrtcIceCandidate { session_id, .. }
+            | Self::WebrtcSessionEnded { session_id, .. }
+            | Self::WebrtcData { session_id, .. }
+            | Self::WebrtcError { session_id, .. } => session_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum AdiDiscovery {
+    ListPlugins {
+        request_id: Uuid,
+    },
+    PluginsList {
+        request_id: Uuid,
+        plugins: Vec<AdiPluginInfo>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum AdiSubscription {
+    Subscribe {
+        request_id: Uuid,
+        plugin: String,
+        event: String,
+        filter: Option<JsonValue>,
+    },
+    Subscribed {
+        request_id: Uuid,
+        subscription_id: Uuid,
+        plugin: String,
+        event: String,
+    },
+    Unsubscribe {
+        subscription_id: Uuid,
+    },
+    Unsubscribed {
+        subscription_id: Uuid,
+    },
+    Event {
+        subscription_id: Uuid,
+        event: String,
+        data: JsonValue,
+    },
+    Error {
+        request_id: Uuid,
+        code: String,
+        message: String,
+    },
+}
+
+/// Stops a streaming call before its last chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum AdiStreamControl {
+    CancelStream { request_id: Uuid },
+}
@@ -0,0 +1,225 @@
+//! Signaling server connection: authentication, device listing and the
+//! relay used to reach a cocoon.
+
+use crate::error::{AdiClientError, Result};
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use lib_signaling_protocol::{DeviceInfo, RelayPriority, SignalingMessage};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+pub(crate) type SignalingSink = SplitSink<Socket, Message>;
+pub(crate) type SignalingStream = SplitStream<Socket>;
+
+/// Characters of a device ID shown when the device has no `name` tag
+const SHORT_ID_LEN: usize = 8;
+
+/// Connect to the signaling server and authenticate as an app client.
+///
+/// Returns the split socket and the user's devices.
+pub(crate) async fn connect(
+    signaling_url: &str,
+    access_token: &str,
+    timeout: Duration,
+) -> Result<(SignalingSink, SignalingStream, Vec<DeviceInfo>)> {
+    let (ws, _) = tokio::time::timeout(timeout, tokio_tungstenite::connect_async(signaling_url))
+        .await
+        .map_err(|_| {
+            AdiClientError::Connection(format!("timed out connecting to {}", signaling_url))
+        })?
+        .map_err(|e| AdiClientError::Connection(format!("{}: {}", signaling_url, e)))?;
+    let (mut sink, mut stream) = ws.split();
+    let devices = authenticate(&mut sink, &mut stream, access_token, timeout).await?;
+    Ok((sink, stream, devices))
+}
+
+async fn authenticate(
+    sink: &mut SignalingSink,
+    stream: &mut SignalingStream,
+    access_token: &str,
+    timeout: Duration,
+) -> Result<Vec<DeviceInfo>> {
+    let mut authenticating = false;
+    loop {
+        let next = tokio::time::timeout(timeout, stream.next())
+            .await
+            .map_err(|_| {
+                AdiClientError::Connection("timed out waiting for the signaling server".to_string())
+            })?;
+        let text = match next {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(AdiClientError::Connection(e.to_string())),
+            None => {
+                return Err(AdiClientError::Connection(
+                    "signaling server closed the connection".to_string(),
+                ))
+            }
+        };
+
+        match serde_json::from_str::<SignalingMessage>(&text) {
+            Ok(SignalingMessage::AuthHello { .. }) if !authenticating => {
+                authenticating = true;
+                let auth = SignalingMessage::AuthAuthenticate {
+                    access_token: access_token.to_string(),
+                };
+                send(sink, &auth).await?;
+            }
+            Ok(SignalingMessage::AuthHelloAuthed { devices, .. }) => return Ok(devices),
+            Ok(SignalingMessage::SystemError { message }) => {
+                return Err(AdiClientError::Auth(message))
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) async fn send(sink: &mut SignalingSink, msg: &SignalingMessage) -> Result<()> {
+    let json = serde_json::to_string(msg)?;
+    sink.send(Message::Text(json))
+        .await
+        .map_err(|_| AdiClientError::Disconnected)
+}
+
+/// The user's devices, as seen by the signaling server.
+pub async fn list_devices(signaling_url: &str, access_token: &str) -> Result<Vec<DeviceInfo>> {
+    let (mut sink, _, devices) =
+        connect(signaling_url, access_token, Duration::from_secs(30)).await?;
+    let _ = sink.close().await;
+    Ok(devices)
+}
+
+/// Pick a device by ID, `name` tag or unique ID prefix.
+///
+/// With no `device`, the user's only online device is picked.
+pub fn select_device(devices: &[DeviceInfo], device: Option<&str>) -> Result<DeviceInfo> {
+    let Some(device) = device else {
+        let online: Vec<_> = devices.iter().filter(|d| d.online).collect();
+        return match online.as_slice() {
+            [only] => Ok((*only).clone()),
+            [] => Err(AdiClientError::DeviceNotFound(
+                "any online device".to_string(),
+            )),
+            _ => Err(AdiClientError::DeviceRequired(online.len())),
+        };
+    };
+
+    let exact = devices
+        .iter()
+        .find(|d| d.device_id == device || d.tags.get("name").is_some_and(|n| n == device));
+    let found = match exact {
+        Some(found) => found,
+        None => {
+            let matches: Vec<_> = devices
+                .iter()
+                .filter(|d| d.device_id.starts_with(device))
+                .collect();
+            match matches.as_slice() {
+                [found] => *found,
+                [] => return Err(AdiClientError::DeviceNotFound(device.to_string())),
+                _ => return Err(AdiClientError::AmbiguousDevice(device.to_string())),
+            }
+        }
+    };
+    if !found.online {
+        return Err(AdiClientError::DeviceOffline(display_name(found)));
+    }
+    Ok(found.clone())
+}
+
+/// Tagged name if the device has one, otherwise its shortened ID.
+pub fn display_name(device: &DeviceInfo) -> String {
+    match device.tags.get("name") {
+        Some(name) => name.clone(),
+        None => device.device_id.chars().take(SHORT_ID_LEN).collect(),
+    }
+}
+
+/// Signaling messages addressed to one cocoon, written by the connection's
+/// writer task.
+#[derive(Debug, Clone)]
+pub(crate) struct DeviceSender {
+    pub device_id: String,
+    /// Presented to signaling with every message when the device is shared
+    pub delegated_token: Option<String>,
+    pub outbox: mpsc::UnboundedSender<SignalingMessage>,
+}
+
+impl DeviceSender {
+    /// Returns `false` once the signaling connection is gone.
+    pub fn send(&self, msg: &impl Serialize, priority: RelayPriority) -> bool {
+        let mut payload = serde_json::json!({ "to": self.device_id, "data": msg });
+        if let Some(ref token) = self.delegated_token {
+            payload["delegated_token"] = token.clone().into();
+        }
+        self.outbox
+            .send(SignalingMessage::SyncData {
+                payload,
+                priority: Some(priority),
+            })
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn device(id: &str, name: Option<&str>, online: bool) -> DeviceInfo {
+        let mut tags = HashMap::new();
+        if let Some(name) = name {
+            tags.insert("name".to_string(), name.to_string());
+        }
+        DeviceInfo {
+            device_id: id.into(),
+            tags,
+            online,
+            device_type: None,
+            device_config: None,
+        }
+    }
+
+    #[test]
+    fn test_select_device() {
+        let devices = vec![
+            device("a1b2c3d4e5", Some("laptop"), true),
+            device("a1f00000", None, true),
+            device("ffff0000", Some("gpu-box"), false),
+        ];
+
+        assert_eq!(
+            select_device(&devices, Some("laptop")).unwrap().device_id,
+            "a1b2c3d4e5"
+        );
+        assert_eq!(
+            select_device(&devices, Some("a1f")).unwrap().device_id,
+            "a1f00000"
+        );
+        assert!(matches!(
+            select_device(&devices, Some("a1")),
+            Err(AdiClientError::AmbiguousDevice(_))
+        ));
+        assert!(matches!(
+            select_device(&devices, Some("gpu-box")),
+            Err(AdiClientError::DeviceOffline(name)) if name == "gpu-box"
+        ));
+        assert!(matches!(
+            select_device(&devices, Some("zzz")),
+            Err(AdiClientError::DeviceNotFound(_))
+        ));
+        assert!(matches!(
+            select_device(&devices, None),
+            Err(AdiClientError::DeviceRequired(2))
+        ));
+        assert_eq!(
+            select_device(&devices[..1], None).unwrap().device_id,
+            "a1b2c3d4e5"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Silk command execution on the cocoon.
+//!
+//! Silk messages always take the signaling relay, like `adi cocoon exec`:
+//! requests are sent as `sync_data` to the device and replies come back
+//! without a session, so they are routed by command ID. Session creation
+//! replies carry no ID at all, which is why sessions are created one at a
+//! time.
+
+use crate::error::{AdiClientError, Result};
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Output stream of a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// What a running command produced
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecEvent {
+    Output {
+        stream: OutputStream,
+        data: String,
+    },
+    /// Last event of the command
+    Exited {
+        exit_code: i32,
+        /// Working directory of the session afterwards
+        cwd: String,
+    },
+}
+
+/// Collected result of [`AdiClient::exec`](crate::AdiClient::exec)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecOutput {
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    pub cwd: String,
+}
+
+impl ExecOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// Silk replies relayed back from the cocoon. Only the fields the client
+/// needs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum SilkEvent {
+    #[serde(rename = "silk_create_session_response")]
+    SessionCreated { session_id: Uuid },
+    #[serde(rename = "silk_command_started")]
+    CommandStarted { command_id: String },
+    #[serde(rename = "silk_output")]
+    Output {
+        command_id: String,
+        stream: OutputStream,
+        data: String,
+    },
+    #[serde(rename = "silk_interactive_required")]
+    InteractiveRequired {
+        command_id: String,
+        reason: String,
+        pty_session_id: Uuid,
+    },
+    #[serde(rename = "silk_command_completed")]
+    CommandCompleted {
+        command_id: String,
+        exit_code: i32,
+        #[serde(default)]
+        cwd: String,
+    },
+    #[serde(rename = "silk_error")]
+    Error {
+        #[serde(default)]
+        command_id: Option<String>,
+        code: String,
+        message: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+impl SilkEvent {
+    /// Whether a relayed payload is a Silk reply
+    pub fn matches(payload: &serde_json::Value) -> bool {
+        payload
+            .get("type")
+            .and_then(|t| t.as_str())
+            .is_some_and(|t| t.starts_with("silk_"))
+    }
+
+    pub fn command_id(&self) -> Option<&str> {
+        match self {
+            Self::CommandStarted { command_id }
+            | Self::Output { command_id, .. }
+            | Self::InteractiveRequired { command_id, .. }
+            | Self::CommandCompleted { command_id, .. } => Some(command_id),
+            Self::Error { command_id, .. } => command_id.as_deref(),
+            Self::SessionCreated { .. } | Self::Other => None,
+        }
+    }
+}
+
+pub(crate) fn create_session_request() -> serde_json::Value {
+    serde_json::json!({ "type": "silk_create_session" })
+}
+
+pub(crate) fn execute_request(
+    session_id: Uuid,
+    command: &str,
+    command_id: &str,
+) -> serde_json::Value {
+    serde_json::json!({
+        "type": "silk_execute",
+        "session_id": session_id,
+        "command": command,
+        "command_id": command_id,
+    })
+}
+
+pub(crate) fn pty_close_request(pty_session_id: Uuid) -> serde_json::Value {
+    serde_json::json!({ "type": "pty_close", "session_id": pty_session_id })
+}
+
+/// Events of one command; ends after [`ExecEvent::Exited`] or an error.
+pub struct ExecStream {
+    /// Reply already taken off `events` while the command was being started
+    first: Option<SilkEvent>,
+    events: mpsc::UnboundedReceiver<SilkEvent>,
+    done: bool,
+}
+
+impl ExecStream {
+    pub(crate) fn new(
+        first: Option<SilkEvent>,
+        events: mpsc::UnboundedReceiver<SilkEvent>,
+    ) -> Self {
+        Self {
+            first,
+            events,
+            done: false,
+        }
+    }
+
+    pub async fn next(&mut self) -> Option<Result<ExecEvent>> {
+        loop {
+            if self.done {
+                return None;
+            }
+            let event = match self.first.take() {
+                Some(first) => Some(first),
+                None => self.events.recv().await,
+            };
+            if let Some(item) = self.map(event) {
+                return Some(item);
+            }
+        }
+    }
+
+    /// Run the command to completion, collecting its output.
+    pub async fn collect(mut self) -> Result<ExecOutput> {
+        let mut output = ExecOutput::default();
+        while let Some(event) = self.next().await {
+            match event? {
+                ExecEvent::Output {
+                    stream: OutputStream::Stdout,
+                    data,
+                } => output.stdout.push_str(&data),
+                ExecEvent::Output {
+                    stream: OutputStream::Stderr,
+                    data,
+                } => output.stderr.push_str(&data),
+                ExecEvent::Exited { exit_code, cwd } => {
+                    output.exit_code = exit_code;
+                    output.cwd = cwd;
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Turn a relayed reply into an item; `None` for replies callers do not
+    /// see. A closed channel means the connection dropped.
+    fn map(&mut self, event: Option<SilkEvent>) -> Option<Result<ExecEvent>> {
+        let item = match event {
+            Some(SilkEvent::Output { stream, data, .. }) => Ok(ExecEvent::Output { stream, data }),
+            Some(SilkEvent::CommandCompleted { exit_code, cwd, .. }) => {
+                self.done = true;
+                Ok(ExecEvent::Exited { exit_code, cwd })
+            }
+            Some(SilkEvent::InteractiveRequired { reason, .. }) => {
+                self.done = true;
+                Err(AdiClientError::Interactive(reason))
+            }
+            Some(SilkEvent::Error { code, message, .. }) => {
+                self.done = true;
+                Err(AdiClientError::Service { code, message })
+            }
+            Some(_) => return None,
+            None => {
+                self.done = true;
+                Err(AdiClientError::Disconnected)
+            }
+        };
+        Some(item)
+    }
+}
+
+impl futures::Stream for ExecStream {
+    type Item = Result<ExecEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+            let event = match self.first.take() {
+                Some(first) => Some(first),
+                None => futures::ready!(self.events.poll_recv(cx)),
+            };
+            if let Some(item) = self.map(event) {
+                return Poll::Ready(Some(item));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_exec_stream_collects_output() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let events = [
+            json!({ "type": "silk_command_started", "session_id": Uuid::nil(), "command_id": "c1", "interactive": false }),
+            json!({ "type": "silk_output", "session_id": Uuid::nil(), "command_id": "c1", "stream": "stdout", "data": "hi\n", "html": [] }),
+            json!({ "type": "silk_output", "session_id": Uuid::nil(), "command_id": "c1", "stream": "stderr", "data": "warn\n" }),
+            json!({ "type": "silk_command_completed", "session_id": Uuid::nil(), "command_id": "c1", "exit_code": 2, "cwd": "/tmp" }),
+        ];
+        for event in events {
+            assert!(SilkEvent::matches(&event));
+            let event: SilkEvent = serde_json::from_value(event).unwrap();
+            assert_eq!(event.command_id(), Some("c1"));
+            tx.send(event).unwrap();
+        }
+
+        let output = ExecStream::new(None, rx).collect().await.unwrap();
+        assert_eq!(
+            output,
+            ExecOutput {
+                exit_code: 2,
+                stdout: "hi\n".to_string(),
+                stderr: "warn\n".to_string(),
+                cwd: "/tmp".to_string(),
+            }
+        );
+        assert!(!output.success());
+    }
+
+    #[tokio::test]
+    async fn test_exec_stream_errors() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(SilkEvent::InteractiveRequired {
+            command_id: "c1".to_string(),
+            reason: "vim is a full-screen editor".to_string(),
+            pty_session_id: Uuid::nil(),
+        })
+        .unwrap();
+        let mut stream = ExecStream::new(None, rx);
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(AdiClientError::Interactive(_)))
+        ));
+        assert!(stream.next().await.is_none());
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        drop(tx);
+        assert!(matches!(
+            ExecStream::new(None, rx).collect().await,
+            Err(AdiClientError::Disconnected)
+        ));
+        assert!(!SilkEvent::matches(&json!({ "type": "webrtc_answer" })));
+    }
+}
@@ -36,6 +36,10 @@ pub struct AdiMethodInfo {
     pub result_schema: Option<JsonValue>,
     pub deprecated: Option<bool>,
     pub deprecated_message: Option<String>,
+    /// Skip router-side `params_schema` validation (for hot paths that
+    /// validate on their own)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_params_validation: Option<bool>,
 }
 
 impl Default for AdiMethodInfo {
@@ -48,6 +52,7 @@ impl Default for AdiMethodInfo {
             result_schema: None,
             deprecated: None,
             deprecated_message: None,
+            skip_params_validation: None,
         }
     }
 }
@@ -0,0 +1,125 @@
+//! YAML fixtures describing what a mock device offers and how it answers.
+//!
+//! ```yaml
+//! device_id: gpu-box
+//! latency_ms: 5              # added to every response
+//! capabilities:
+//!   - protocol: embeddings
+//!     version: 1.0.0
+//!     responses:
+//!       - when: { model: small }   # payload must contain these fields
+//!         payload: { dims: 384 }
+//!       - error: model not loaded  # answered once, then the next entry
+//!         times: 1
+//!       - payload: { dims: 1024 }
+//!         latency_ms: 20
+//! ```
+
+use crate::MockError;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fixture {
+    #[serde(default = "default_device_id")]
+    pub device_id: String,
+    #[serde(default)]
+    pub latency_ms: u64,
+    #[serde(default)]
+    pub capabilities: Vec<CapabilityFixture>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CapabilityFixture {
+    pub protocol: String,
+    pub version: String,
+    #[serde(default)]
+    pub responses: Vec<ScriptedResponse>,
+}
+
+/// One scripted answer. The first response whose `when` matches the request
+/// payload and that has uses left is sent.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScriptedResponse {
+    /// Fields the request payload must contain; matches every request if unset
+    #[serde(default)]
+    pub when: Option<JsonValue>,
+    #[serde(default)]
+    pub payload: JsonValue,
+    /// Answer with this error instead of `payload`
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Extra delay for this response
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    /// How often this response may be used; unlimited if unset
+    #[serde(default)]
+    pub times: Option<usize>,
+}
+
+impl ScriptedResponse {
+    pub fn ok(payload: JsonValue) -> Self {
+        Self {
+            payload,
+            ..Default::default()
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Only answer requests whose payload contains `fields`
+    pub fn when(mut self, fields: JsonValue) -> Self {
+        self.when = Some(fields);
+        self
+    }
+
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency_ms = Some(latency.as_millis() as u64);
+        self
+    }
+
+    pub(crate) fn matches(&self, payload: &JsonValue) -> bool {
+        self.times != Some(0)
+            && self
+                .when
+                .as_ref()
+                .is_none_or(|fields| json_contains(payload, fields))
+    }
+}
+
+impl Fixture {
+    pub fn from_yaml(yaml: &str) -> Result<Self, MockError> {
+        Ok(serde_yml::from_str(yaml)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MockError> {
+        Self::from_yaml(&std::fs::read_to_string(path)?)
+    }
+}
+
+fn default_device_id() -> String {
+    "mock-device".to_string()
+}
+
+/// Whether `actual` contains everything in `expected`: object fields are
+/// compared recursively, anything else must be equal.
+pub fn json_contains(actual: &JsonValue, expected: &JsonValue) -> bool {
+    match (actual, expected) {
+        (JsonValue::Object(actual), JsonValue::Object(expected)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|a| json_contains(a, value))),
+        _ => actual == expected,
+    }
+}
@@ -0,0 +1,423 @@
+//! In-process capability router for plugin tests.
+//!
+//! Stands in for the signaling server and the devices behind it: requests are
+//! answered from scripted responses (built in code or loaded from a YAML
+//! fixture), with optional latency and error injection, and every received
+//! payload is recorded so tests can assert on what the plugin sent.
+//!
+//! ```ignore
+//! let mock = MockCapabilityRouter::from_fixture_str(include_str!("embeddings.yaml"))?;
+//! let reply = mock.call("embeddings", "1.0.0", json!({ "model": "small" })).await;
+//! mock.assert_received("embeddings", json!({ "model": "small" }));
+//! ```
+
+mod fixture;
+
+pub use fixture::{json_contains, CapabilityFixture, Fixture, ScriptedResponse};
+
+use lib_tarminal_sync::{Capability, SignalingMessage};
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MockError {
+    #[error("Failed to read fixture: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid fixture: {0}")]
+    Fixture(#[from] serde_yml::Error),
+}
+
+struct MockCapability {
+    version: String,
+    responses: Vec<ScriptedResponse>,
+    /// Errors injected with `fail_next`, used before any scripted response
+    injected_errors: VecDeque<String>,
+    received: Vec<JsonValue>,
+}
+
+struct State {
+    capabilities: HashMap<String, MockCapability>,
+    latency: Duration,
+}
+
+/// Answers `CapabilityRequest`s from scripted responses and records them.
+///
+/// Cloning shares the same state, so a clone can be handed to the code under
+/// test while the original is kept for assertions.
+#[derive(Clone)]
+pub struct MockCapabilityRouter {
+    device_id: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockCapabilityRouter {
+    pub fn new(device_id: impl Into<String>) -> Self {
+        Self {
+            device_id: device_id.into(),
+            state: Arc::new(Mutex::new(State {
+                capabilities: HashMap::new(),
+                latency: Duration::ZERO,
+            })),
+        }
+    }
+
+    pub fn from_fixture(fixture: Fixture) -> Self {
+        let router = Self::new(fixture.device_id);
+        router.set_latency(Duration::from_millis(fixture.latency_ms));
+        for capability in fixture.capabilities {
+            router.add_capability(&capability.protocol, &capability.version);
+            for response in capability.responses {
+                router.respond(&capability.protocol, response);
+            }
+        }
+        router
+    }
+
+    pub fn from_fixture_str(yaml: &str) -> Result<Self, MockError> {
+        Ok(Self::from_fixture(Fixture::from_yaml(yaml)?))
+    }
+
+    pub fn from_fixture_file(path: impl AsRef<Path>) -> Result<Self, MockError> {
+        Ok(Self::from_fixture(Fixture::load(path)?))
+    }
+
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    /// Advertise a capability. Replaces its responses if already present.
+    pub fn add_capability(&self, protocol: &str, version: &str) -> &Self {
+        self.lock().capabilities.insert(
+            protocol.to_string(),
+            MockCapability {
+                version: version.to_string(),
+                responses: Vec::new(),
+                injected_errors: VecDeque::new(),
+                received: Vec::new(),
+            },
+        );
+        self
+    }
+
+    /// Append a scripted response to a capability.
+    ///
+    /// # Panics
+    /// If the capability was not added.
+    pub fn respond(&self, protocol: &str, response: ScriptedResponse) -> &Self {
+        self.with_capability(protocol, |cap| cap.responses.push(response));
+        self
+    }
+
+    /// Delay added to every response
+    pub fn set_latency(&self, latency: Duration) {
+        self.lock().latency = latency;
+    }
+
+    /// Answer the next request to `protocol` with `error`, ahead of any
+    /// scripted response. Repeated calls queue further errors.
+    pub fn fail_next(&self, protocol: &str, error: impl Into<String>) {
+        let error = error.into();
+        self.with_capability(protocol, |cap| cap.injected_errors.push_back(error));
+    }
+
+    /// Capabilities as a device would advertise them
+    pub fn capabilities(&self) -> Vec<Capability> {
+        let mut capabilities: Vec<_> = self
+            .lock()
+            .capabilities
+            .iter()
+            .map(|(protocol, cap)| Capability {
+                protocol: protocol.clone(),
+                version: cap.version.clone(),
+            })
+            .collect();
+        capabilities.sort_by(|a, b| a.protocol.cmp(&b.protocol));
+        capabilities
+    }
+
+    /// Answer a `CapabilityRequest` with a `CapabilityResponse`. Other
+    /// messages are ignored.
+    pub async fn handle(&self, msg: SignalingMessage) -> Option<SignalingMessage> {
+        let SignalingMessage::CapabilityRequest {
+            request_id,
+            capability,
+            payload,
+            ..
+        } = msg
+        else {
+            return None;
+        };
+
+        let (delay, result) = self.resolve(&capability, payload);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        let (payload, error) = match result {
+            Ok(payload) => (payload, None),
+            Err(error) => (JsonValue::Null, Some(error)),
+        };
+        Some(SignalingMessage::CapabilityResponse {
+            request_id,
+            from_device: self.device_id.clone(),
+            payload,
+            error,
+        })
+    }
+
+    /// Send a request through the router and return the response payload, or
+    /// the error it was answered with.
+    pub async fn call(
+        &self,
+        protocol: &str,
+        version: &str,
+        payload: JsonValue,
+    ) -> Result<JsonValue, String> {
+        let request = SignalingMessage::CapabilityRequest {
+            request_id: "mock-request".to_string(),
+            capability: Capability {
+                protocol: protocol.to_string(),
+                version: version.to_string(),
+            },
+            payload,
+            prefer_device: None,
+        };
+        match self.handle(request).await {
+            Some(SignalingMessage::CapabilityResponse {
+                error: Some(error), ..
+            }) => Err(error),
+            Some(SignalingMessage::CapabilityResponse { payload, .. }) => Ok(payload),
+            _ => unreachable!("capability requests are always answered"),
+        }
+    }
+
+    /// Run the router as a message loop, like a signaling connection: send
+    /// requests into the returned sender, read responses from the receiver.
+    /// Each request is answered in its own task, so a slow response does not
+    /// hold up the others.
+    pub fn spawn(
+        &self,
+    ) -> (
+        mpsc::UnboundedSender<SignalingMessage>,
+        mpsc::UnboundedReceiver<SignalingMessage>,
+    ) {
+        let (request_tx, mut request_rx) = mpsc::unbounded_channel();
+        let (response_tx, response_rx) = mpsc::unbounded_channel();
+        let router = self.clone();
+
+        tokio::spawn(async move {
+            while let Some(msg) = request_rx.recv().await {
+                let router = router.clone();
+                let response_tx = response_tx.clone();
+                tokio::spawn(async move {
+                    if let Some(response) = router.handle(msg).await {
+                        let _ = response_tx.send(response);
+                    }
+                });
+            }
+        });
+
+        (request_tx, response_rx)
+    }
+
+    /// Payloads received for a capability, in arrival order
+    pub fn requests(&self, protocol: &str) -> Vec<JsonValue> {
+        self.lock()
+            .capabilities
+            .get(protocol)
+            .map(|cap| cap.received.clone())
+            .unwrap_or_default()
+    }
+
+    /// # Panics
+    /// Unless exactly `times` requests were received for `protocol`.
+    #[track_caller]
+    pub fn assert_called(&self, protocol: &str, times: usize) {
+        let received = self.requests(protocol);
+        assert_eq!(
+            received.len(),
+            times,
+            "expected {} request(s) to {}, got {}: {:#?}",
+            times,
+            protocol,
+            received.len(),
+            received
+        );
+    }
+
+    /// # Panics
+    /// Unless some request to `protocol` contained all fields of `expected`.
+    #[track_caller]
+    pub fn assert_received(&self, protocol: &str, expected: JsonValue) {
+        let received = self.requests(protocol);
+        assert!(
+            received.iter().any(|p| json_contains(p, &expected)),
+            "no request to {} contained {}; received: {:#?}",
+            protocol,
+            expected,
+            received
+        );
+    }
+
+    /// Record the request and pick its answer and delay.
+    fn resolve(
+        &self,
+        capability: &Capability,
+        payload: JsonValue,
+    ) -> (Duration, Result<JsonValue, String>) {
+        let mut state = self.lock();
+        let base_latency = state.latency;
+
+        let Some(cap) = state.capabilities.get_mut(&capability.protocol) else {
+            return (
+                base_latency,
+                Err(format!("No device provides {}", capability.protocol)),
+            );
+        };
+        if major_version(&cap.version) != major_version(&capability.version) {
+            return (
+                base_latency,
+                Err(format!(
+                    "No device provides {} {} (mock offers {})",
+                    capability.protocol, capability.version, cap.version
+                )),
+            );
+        }
+
+        cap.received.push(payload.clone());
+        if let Some(error) = cap.injected_errors.pop_front() {
+            return (base_latency, Err(error));
+        }
+
+        let Some(response) = cap.responses.iter_mut().find(|r| r.matches(&payload)) else {
+            return (
+                base_latency,
+                Err(format!(
+                    "No scripted response for {} matches {}",
+                    capability.protocol, payload
+                )),
+            );
+        };
+        if let Some(times) = response.times.as_mut() {
+            *times -= 1;
+        }
+
+        let delay = base_latency + Duration::from_millis(response.latency_ms.unwrap_or(0));
+        let result = match &response.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(response.payload.clone()),
+        };
+        (delay, result)
+    }
+
+    fn with_capability(&self, protocol: &str, f: impl FnOnce(&mut MockCapability)) {
+        let mut state = self.lock();
+        let cap = state
+            .capabilities
+            .get_mut(protocol)
+            .unwrap_or_else(|| panic!("capability {} was not added to the mock", protocol));
+        f(cap);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // A panicking assertion in one test task must not poison the others
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn major_version(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const FIXTURE: &str = r#"
+device_id: gpu-box
+capabilities:
+  - protocol: embeddings
+    version: 1.2.0
+    responses:
+      - when: { model: small }
+        payload: { dims: 384 }
+      - error: model not loaded
+        times: 1
+      - payload: { dims: 1024 }
+        latency_ms: 20
+"#;
+
+    #[tokio::test]
+    async fn test_fixture_responses() {
+        let mock = MockCapabilityRouter::from_fixture_str(FIXTURE).unwrap();
+        assert_eq!(mock.device_id(), "gpu-box");
+        assert_eq!(mock.capabilities()[0].version, "1.2.0");
+
+        let small = mock.call(
+            "embeddings",
+            "1.0.0",
+            json!({ "model": "small", "text": "hi" }),
+        );
+        assert_eq!(small.await, Ok(json!({ "dims": 384 })));
+
+        let large = json!({ "model": "large" });
+        assert_eq!(
+            mock.call("embeddings", "1.0.0", large.clone()).await,
+            Err("model not loaded".to_string())
+        );
+        assert_eq!(
+            mock.call("embeddings", "1.0.0", large).await,
+            Ok(json!({ "dims": 1024 }))
+        );
+
+        assert!(mock.call("embeddings", "2.0.0", json!({})).await.is_err());
+        assert!(mock.call("llm.chat", "1.0.0", json!({})).await.is_err());
+
+        mock.assert_called("embeddings", 3);
+        mock.assert_received("embeddings", json!({ "text": "hi" }));
+    }
+
+    #[tokio::test]
+    async fn test_injection_and_message_loop() {
+        let mock = MockCapabilityRouter::new("mock");
+        mock.add_capability("tasks", "1.0.0")
+            .respond("tasks", ScriptedResponse::ok(json!({ "id": 1 })));
+        mock.fail_next("tasks", "device offline");
+        mock.set_latency(Duration::from_millis(5));
+
+        let (tx, mut rx) = mock.spawn();
+        for request_id in ["a", "b"] {
+            tx.send(SignalingMessage::CapabilityRequest {
+                request_id: request_id.to_string(),
+                capability: Capability {
+                    protocol: "tasks".to_string(),
+                    version: "1.0.0".to_string(),
+                },
+                payload: json!({ "title": request_id }),
+                prefer_device: None,
+            })
+            .unwrap();
+        }
+
+        let mut errors = 0;
+        for _ in 0..2 {
+            match rx.recv().await.unwrap() {
+                SignalingMessage::CapabilityResponse {
+                    from_device, error, ..
+                } => {
+                    assert_eq!(from_device, "mock");
+                    errors += usize::from(error.is_some());
+                }
+                other => panic!("unexpected message: {:?}", other),
+            }
+        }
+        assert_eq!(errors, 1);
+        mock.assert_called("tasks", 2);
+    }
+}
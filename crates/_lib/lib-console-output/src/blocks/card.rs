@@ -13,7 +13,7 @@
 //! ```
 
 use super::{visible_width, Renderable};
-use crate::theme;
+use crate::theme::{self, borders};
 use std::fmt;
 
 /// Bordered card with optional title.
@@ -99,7 +99,7 @@ impl Default for Card {
 impl fmt::Display for Card {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let inner = self.inner_width();
-        let h = "\u{2500}"; // ─
+        let h = borders::HORIZONTAL.as_str();
 
         // Top border — style border chars individually to not override title styling
         if let Some(ref title) = self.title {
@@ -109,19 +109,19 @@ impl fmt::Display for Card {
             writeln!(
                 f,
                 "{}{}{}{}{}",
-                theme::muted("\u{256D}"),
+                theme::muted(borders::TOP_LEFT),
                 theme::muted(h),
                 title_str,
                 theme::muted(h.repeat(remaining)),
-                theme::muted("\u{256E}")
+                theme::muted(borders::TOP_RIGHT)
             )?;
         } else {
             writeln!(
                 f,
                 "{}{}{}",
-                theme::muted("\u{256D}"),
+                theme::muted(borders::TOP_LEFT),
                 theme::muted(h.repeat(inner)),
-                theme::muted("\u{256E}")
+                theme::muted(borders::TOP_RIGHT)
             )?;
         }
 
@@ -133,11 +133,11 @@ impl fmt::Display for Card {
             writeln!(
                 f,
                 "{}{}{}{} {}",
-                theme::muted("\u{2502}"),
+                theme::muted(borders::VERTICAL),
                 pad,
                 line,
                 " ".repeat(right_pad),
-                theme::muted("\u{2502}")
+                theme::muted(borders::VERTICAL)
             )?;
         }
 
@@ -145,9 +145,9 @@ impl fmt::Display for Card {
         writeln!(
             f,
             "{}{}{}",
-            theme::muted("\u{2570}"),
+            theme::muted(borders::BOTTOM_LEFT),
             theme::muted(h.repeat(inner)),
-            theme::muted("\u{256F}")
+            theme::muted(borders::BOTTOM_RIGHT)
         )?;
 
         Ok(())
@@ -12,7 +12,7 @@
 //! ```
 
 use super::{pad_visible, visible_width, Renderable};
-use crate::theme;
+use crate::theme::{self, borders};
 use std::fmt;
 
 /// Borderless aligned columns.
@@ -153,7 +153,7 @@ impl fmt::Display for Columns {
             // Underline separator
             let separators: Vec<String> = widths
                 .iter()
-                .map(|&w| theme::muted("\u{2500}".repeat(w)).to_string())
+                .map(|&w| theme::muted(borders::HORIZONTAL.as_str().repeat(w)).to_string())
                 .collect();
             writeln!(f, "{}{}", pad, separators.join(&gap))?;
         }
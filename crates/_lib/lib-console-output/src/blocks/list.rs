@@ -79,7 +79,7 @@ impl fmt::Display for List {
             let bullet = if self.numbered {
                 format!("{}.", pad_num(i + 1, num_width))
             } else {
-                format!("{}", theme::brand("\u{2022}")) // •
+                format!("{}", theme::brand(theme::icons::BULLET))
             };
             writeln!(f, "{}{} {}", pad, bullet, item)?;
         }
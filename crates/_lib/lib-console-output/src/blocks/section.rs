@@ -9,7 +9,7 @@
 //! ```
 
 use super::{visible_width, Renderable};
-use crate::theme;
+use crate::theme::{self, borders};
 use std::fmt;
 
 /// Section header with a title and separator line.
@@ -38,7 +38,7 @@ impl fmt::Display for Section {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let title_str = format!(" {} ", theme::brand_bold(&self.title));
         let title_visible = visible_width(&title_str);
-        let dash = "\u{2500}"; // ─
+        let dash = borders::HORIZONTAL.as_str();
 
         let left = format!("{}{}", theme::muted(dash), theme::muted(dash));
         let remaining = self.width.saturating_sub(2 + title_visible);
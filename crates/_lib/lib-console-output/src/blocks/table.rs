@@ -14,7 +14,7 @@
 //! ```
 
 use super::{pad_visible, visible_width, LiveHandle, Renderable};
-use crate::theme;
+use crate::theme::{self, borders, Glyph};
 use console::Term;
 use std::fmt;
 use std::io::Write;
@@ -105,18 +105,18 @@ impl Table {
     }
 
     /// Format a horizontal border line.
-    fn border_line(&self, widths: &[usize], left: &str, mid: &str, right: &str) -> String {
-        let h = "\u{2500}"; // ─
+    fn border_line(&self, widths: &[usize], left: Glyph, mid: Glyph, right: Glyph) -> String {
+        let h = borders::HORIZONTAL.as_str();
         let segments: Vec<String> = widths
             .iter()
             .map(|&w| h.repeat(w + self.padding * 2))
             .collect();
-        theme::muted(format!("{}{}{}", left, segments.join(mid), right)).to_string()
+        theme::muted(format!("{}{}{}", left, segments.join(mid.as_str()), right)).to_string()
     }
 
     /// Format a data row.
     fn format_data_row(&self, cells: &[String], widths: &[usize]) -> String {
-        let v = theme::muted("\u{2502}").to_string(); // │
+        let v = theme::muted(borders::VERTICAL).to_string();
         let pad = " ".repeat(self.padding);
 
         let formatted: Vec<String> = widths
@@ -146,7 +146,12 @@ impl fmt::Display for Table {
         writeln!(
             f,
             "{}",
-            self.border_line(&widths, "\u{256D}", "\u{252C}", "\u{256E}")
+            self.border_line(
+                &widths,
+                borders::TOP_LEFT,
+                borders::TOP_TEE,
+                borders::TOP_RIGHT
+            )
         )?;
 
         // Header row
@@ -161,7 +166,12 @@ impl fmt::Display for Table {
             writeln!(
                 f,
                 "{}",
-                self.border_line(&widths, "\u{251C}", "\u{253C}", "\u{2524}")
+                self.border_line(
+                    &widths,
+                    borders::LEFT_TEE,
+                    borders::CROSS,
+                    borders::RIGHT_TEE
+                )
             )?;
         }
 
@@ -174,7 +184,12 @@ impl fmt::Display for Table {
         writeln!(
             f,
             "{}",
-            self.border_line(&widths, "\u{2570}", "\u{2534}", "\u{256F}")
+            self.border_line(
+                &widths,
+                borders::BOTTOM_LEFT,
+                borders::BOTTOM_TEE,
+                borders::BOTTOM_RIGHT
+            )
         )?;
 
         Ok(())
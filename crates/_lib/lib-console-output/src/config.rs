@@ -4,7 +4,7 @@
 
 //! Configuration for console output behavior.
 
-use lib_env_parse::{env_bool, env_opt, env_vars};
+use lib_env_parse::{env_bool, env_vars};
 
 use crate::{Level, OutputMode};
 
@@ -43,7 +43,8 @@ impl ConsoleConfig {
     ///
     /// Reads:
     /// - `SILK_MODE` - Set to "true" or "1" for JSON stream output
-    /// - `NO_COLOR` - Set to disable colors in text mode
+    /// - `NO_COLOR` - Set (non-empty) to disable colors in text mode
+    /// - `ADI_OUTPUT_THEME` / `ADI_COLOR_SCHEME` - `plain` or `mono` also disable colors
     /// - `VERBOSE` - Set to "true" or "1" for trace-level output
     /// - `QUIET` - Set to "true" or "1" for errors only
     pub fn from_env() -> Self {
@@ -61,7 +62,7 @@ impl ConsoleConfig {
             Level::Info
         };
 
-        let colors_enabled = env_opt(EnvVar::NoColor.as_str()).is_none();
+        let colors_enabled = crate::theme::colors_enabled();
 
         Self {
             mode,
@@ -186,7 +186,11 @@ impl<T: Clone> MultiSelect<T> {
             let is_cursor = i == cursor;
             let is_selected = selected.contains(&i);
             let prefix = if is_cursor { ">" } else { " " };
-            let checkbox = if is_selected { "[\u{2713}]" } else { "[ ]" };
+            let checkbox = if is_selected {
+                theme::icons::CHECKED.as_str()
+            } else {
+                "[ ]"
+            };
 
             let line = if opt.disabled {
                 format!(
@@ -17,6 +17,8 @@
 //!
 //! - `SILK_MODE=true|1` - Enable JSON stream mode
 //! - `NO_COLOR` - Disable colors in text mode
+//! - `ADI_OUTPUT_THEME=unicode|ascii|plain` - Glyph set; `plain` also disables colors
+//! - `ADI_COLOR_SCHEME=default|high-contrast|mono` - Color palette
 //! - `VERBOSE=true|1` - Show trace-level output
 //! - `QUIET=true|1` - Show only errors
 //!
@@ -202,7 +204,7 @@ mod style;
 pub mod theme;
 
 pub use config::{ConsoleConfig, NO_COLOR_ENV, QUIET_ENV, SILK_MODE_ENV, VERBOSE_ENV};
-pub use theme::ADI_THEME_ENV;
+pub use theme::{ColorScheme, OutputTheme, ADI_COLOR_SCHEME_ENV, ADI_OUTPUT_THEME_ENV, ADI_THEME_ENV};
 pub use console::{
     console, data, debug, error, info, init, is_initialized, message, success, trace, warn, Console,
 };
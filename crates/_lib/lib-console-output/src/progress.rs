@@ -149,7 +149,7 @@ impl Spinner {
         }
 
         if self.last_tick.elapsed() >= SPINNER_INTERVAL {
-            self.frame = (self.frame + 1) % theme::SPINNER_FRAMES.len();
+            self.frame = (self.frame + 1) % theme::spinner_frames().len();
             self.last_tick = Instant::now();
 
             if self.interactive && self.mode.is_text() {
@@ -160,7 +160,7 @@ impl Spinner {
 
     /// Render the current spinner state.
     fn render(&mut self) {
-        let frame = theme::SPINNER_FRAMES[self.frame];
+        let frame = theme::spinner_frames()[self.frame];
         let line = format!("{} {}", theme::brand(frame), self.message);
         if self.rendered {
             let _ = self.term.clear_last_lines(1);
@@ -376,8 +376,8 @@ impl ProgressBar {
 
         let bar = format!(
             "{}{}",
-            theme::icons::BAR_FILLED.repeat(filled),
-            theme::icons::BAR_EMPTY.repeat(empty)
+            theme::icons::BAR_FILLED.as_str().repeat(filled),
+            theme::icons::BAR_EMPTY.as_str().repeat(empty)
         );
 
         let line = format!(
@@ -722,16 +722,16 @@ impl MultiProgress {
             OutputMode::Text => {
                 let (icon, style_fn): (&str, fn(&str) -> StyledObject<&str>) = match item.status {
                     MultiProgressStatus::Pending => {
-                        (theme::icons::PENDING, |s| theme::muted(s))
+                        (theme::icons::PENDING.as_str(), |s| theme::muted(s))
                     }
                     MultiProgressStatus::InProgress => {
-                        (theme::icons::IN_PROGRESS, |s| theme::brand(s))
+                        (theme::icons::IN_PROGRESS.as_str(), |s| theme::brand(s))
                     }
                     MultiProgressStatus::Complete => {
-                        (theme::icons::SUCCESS, |s| theme::success(s))
+                        (theme::icons::SUCCESS.as_str(), |s| theme::success(s))
                     }
                     MultiProgressStatus::Failed => {
-                        (theme::icons::ERROR, |s| theme::error(s))
+                        (theme::icons::ERROR.as_str(), |s| theme::error(s))
                     }
                 };
 
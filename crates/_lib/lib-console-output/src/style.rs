@@ -15,32 +15,32 @@ pub mod icons {
 
     /// Green checkmark for success messages.
     pub fn success() -> StyledObject<&'static str> {
-        theme::success(theme::icons::SUCCESS)
+        theme::success(theme::icons::SUCCESS.as_str())
     }
 
     /// Red X for error messages.
     pub fn error() -> StyledObject<&'static str> {
-        theme::error(theme::icons::ERROR)
+        theme::error(theme::icons::ERROR.as_str())
     }
 
     /// Yellow warning sign for warnings.
     pub fn warning() -> StyledObject<&'static str> {
-        theme::warning(theme::icons::WARNING)
+        theme::warning(theme::icons::WARNING.as_str())
     }
 
     /// Magenta info icon.
     pub fn info() -> StyledObject<&'static str> {
-        theme::info(theme::icons::INFO)
+        theme::info(theme::icons::INFO.as_str())
     }
 
     /// Cyan arrow for debug.
     pub fn debug() -> StyledObject<&'static str> {
-        theme::debug(theme::icons::DEBUG)
+        theme::debug(theme::icons::DEBUG.as_str())
     }
 
     /// Dimmed dot for trace.
     pub fn trace() -> StyledObject<&'static str> {
-        theme::muted(theme::icons::TRACE)
+        theme::muted(theme::icons::TRACE.as_str())
     }
 }
 
@@ -10,14 +10,29 @@
 //! The accent color is driven by the active theme (selected via `ADI_THEME`
 //! env var or programmatically via [`init`]). Status colors (success, error,
 //! warning) are universal across all themes.
+//!
+//! Independently of the accent theme, the output theme picks the glyph set
+//! (`unicode`, `ascii` or `plain`) and the color scheme picks the palette
+//! (`default`, `high-contrast` or `mono`), via `ADI_OUTPUT_THEME` /
+//! `ADI_COLOR_SCHEME` or [`init_output`]. `plain` spells statuses out as
+//! words and drops colors for screen readers; a non-empty `NO_COLOR` also
+//! drops colors.
 
 use console::{style, StyledObject};
+use std::fmt;
+use std::str::FromStr;
 use std::sync::OnceLock;
 
 /// Environment variable for theme selection.
 pub const ADI_THEME_ENV: &str = "ADI_THEME";
 // Note: ADI_THEME is also managed in cli/src/clienv.rs for the CLI crate.
 
+/// Environment variable for the glyph set (`unicode`, `ascii`, `plain`).
+pub const ADI_OUTPUT_THEME_ENV: &str = "ADI_OUTPUT_THEME";
+
+/// Environment variable for the color scheme (`default`, `high-contrast`, `mono`).
+pub const ADI_COLOR_SCHEME_ENV: &str = "ADI_COLOR_SCHEME";
+
 /// Generated theme definitions from packages/theme/themes.json.
 pub mod generated {
     include!("../../../../packages/theme/generated/themes.rs");
@@ -46,6 +61,124 @@ static FOREGROUND_SGR_CACHE: OnceLock<String> = OnceLock::new();
 /// SGR reset sequence.
 pub const RESET_SGR: &str = "\x1b[0m";
 
+// High-contrast palette: basic bright ANSI colors, readable on any background
+// and honored by terminals without 256-color support.
+const HC_ACCENT: u8 = 14; // bright cyan
+const HC_SUCCESS: u8 = 10; // bright green
+const HC_ERROR: u8 = 9; // bright red
+const HC_WARNING: u8 = 11; // bright yellow
+const HC_DEBUG: u8 = 12; // bright blue
+const HC_FOREGROUND: u8 = 15; // bright white
+const HC_MUTED: u8 = 7; // white
+
+// Colors for telling apart items of a series, e.g. services in an
+// interleaved log view. Distinct from the semantic colors above.
+const SERIES_256: [u8; 6] = [45, 170, 178, 77, 111, 209];
+const HC_SERIES: [u8; 6] = [14, 13, 11, 10, 12, 6];
+
+/// Active output theme and color scheme.
+static OUTPUT: OnceLock<(OutputTheme, ColorScheme)> = OnceLock::new();
+
+/// Glyph set used for icons, spinners and borders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputTheme {
+    /// Unicode icons and box drawing.
+    #[default]
+    Unicode,
+    /// ASCII-only icons and borders.
+    Ascii,
+    /// ASCII borders, statuses spelled out as words, no colors.
+    Plain,
+}
+
+impl OutputTheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unicode => "unicode",
+            Self::Ascii => "ascii",
+            Self::Plain => "plain",
+        }
+    }
+}
+
+impl FromStr for OutputTheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "unicode" => Ok(Self::Unicode),
+            "ascii" => Ok(Self::Ascii),
+            "plain" => Ok(Self::Plain),
+            _ => Err(format!(
+                "Unknown output theme '{}' (expected unicode, ascii or plain)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputTheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Palette used for styled output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorScheme {
+    /// Theme accent with the WCAG AA status colors.
+    #[default]
+    Default,
+    /// Bright basic ANSI colors.
+    HighContrast,
+    /// No colors; emphasis through bold only.
+    Mono,
+}
+
+impl ColorScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::HighContrast => "high-contrast",
+            Self::Mono => "mono",
+        }
+    }
+}
+
+impl FromStr for ColorScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "high-contrast" | "high_contrast" => Ok(Self::HighContrast),
+            "mono" | "monochrome" | "none" => Ok(Self::Mono),
+            _ => Err(format!(
+                "Unknown color scheme '{}' (expected default, high-contrast or mono)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColorScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Semantic color roles, mapped to a palette by the active color scheme.
+#[derive(Debug, Clone, Copy)]
+enum Role {
+    Accent,
+    Success,
+    Error,
+    Warning,
+    Debug,
+    Foreground,
+    Muted,
+}
+
 /// Initialize the active theme by ID. Call early in main().
 ///
 /// If not called, the theme auto-resolves from `ADI_THEME` env var
@@ -60,7 +193,50 @@ pub fn init(theme_id: &str) {
     let (r, g, b) = parse_hex(theme.dark.text);
     let fg = rgb_to_ansi256(r, g, b);
     let _ = FOREGROUND_256.set(fg);
-    let _ = FOREGROUND_SGR_CACHE.set(format!("\x1b[38;5;{}m", fg));
+}
+
+/// Initialize the output theme and color scheme. Call early in main().
+///
+/// If not called, both resolve from `ADI_OUTPUT_THEME` / `ADI_COLOR_SCHEME`
+/// on first use. `plain` and a non-empty `NO_COLOR` force the `mono` scheme.
+pub fn init_output(theme: OutputTheme, scheme: ColorScheme) {
+    let _ = OUTPUT.set((theme, effective_scheme(theme, scheme)));
+}
+
+fn effective_scheme(theme: OutputTheme, scheme: ColorScheme) -> ColorScheme {
+    let no_color = lib_env_parse::env_opt(crate::NO_COLOR_ENV).is_some_and(|v| !v.is_empty());
+    if theme == OutputTheme::Plain || no_color {
+        ColorScheme::Mono
+    } else {
+        scheme
+    }
+}
+
+fn output() -> (OutputTheme, ColorScheme) {
+    *OUTPUT.get_or_init(|| {
+        let theme = lib_env_parse::env_opt(ADI_OUTPUT_THEME_ENV)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default();
+        let scheme = lib_env_parse::env_opt(ADI_COLOR_SCHEME_ENV)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default();
+        (theme, effective_scheme(theme, scheme))
+    })
+}
+
+/// Get the active output theme (glyph set).
+pub fn output_theme() -> OutputTheme {
+    output().0
+}
+
+/// Get the active color scheme.
+pub fn color_scheme() -> ColorScheme {
+    output().1
+}
+
+/// Whether styled output may use colors (`false` for `mono`, `plain` and `NO_COLOR`).
+pub fn colors_enabled() -> bool {
+    color_scheme() != ColorScheme::Mono
 }
 
 /// Get the active theme.
@@ -91,56 +267,87 @@ fn foreground_color() -> u8 {
     })
 }
 
-/// SGR escape sequence for the theme foreground color.
+/// SGR escape sequence for the theme foreground color; empty without colors.
 pub fn foreground_sgr() -> &'static str {
-    FOREGROUND_SGR_CACHE.get_or_init(|| {
-        format!("\x1b[38;5;{}m", foreground_color())
-    })
+    if !colors_enabled() {
+        return "";
+    }
+    FOREGROUND_SGR_CACHE.get_or_init(|| format!("\x1b[38;5;{}m", role_color(Role::Foreground)))
+}
+
+/// ANSI 256-color index of a role in the active color scheme.
+fn role_color(role: Role) -> u8 {
+    let high_contrast = color_scheme() == ColorScheme::HighContrast;
+    match (role, high_contrast) {
+        (Role::Accent, false) => accent_color(),
+        (Role::Accent, true) => HC_ACCENT,
+        (Role::Success, false) => SUCCESS_256,
+        (Role::Success, true) => HC_SUCCESS,
+        (Role::Error, false) => ERROR_256,
+        (Role::Error, true) => HC_ERROR,
+        (Role::Warning, false) => WARNING_256,
+        (Role::Warning, true) => HC_WARNING,
+        (Role::Debug, false) => DEBUG_256,
+        (Role::Debug, true) => HC_DEBUG,
+        (Role::Foreground, false) => foreground_color(),
+        (Role::Foreground, true) => HC_FOREGROUND,
+        (Role::Muted, false) => MUTED_256,
+        (Role::Muted, true) => HC_MUTED,
+    }
+}
+
+/// Style `val` with the color of `role`, or leave it uncolored under `mono`.
+fn paint<D: std::fmt::Display>(val: D, role: Role) -> StyledObject<D> {
+    if colors_enabled() {
+        style(val).color256(role_color(role))
+    } else {
+        style(val)
+    }
 }
 
 /// Brand color — accent from active theme. Used for spinners, selections, interactive highlights.
 pub fn brand<D: std::fmt::Display>(val: D) -> StyledObject<D> {
-    style(val).color256(accent_color())
+    paint(val, Role::Accent)
 }
 
 /// Brand color bold — used for brand mark, prominent headers.
 pub fn brand_bold<D: std::fmt::Display>(val: D) -> StyledObject<D> {
-    style(val).color256(accent_color()).bold()
+    paint(val, Role::Accent).bold()
 }
 
 /// Info styling — accent from active theme (brand-aligned informational messages).
 pub fn info<D: std::fmt::Display>(val: D) -> StyledObject<D> {
-    style(val).color256(accent_color())
+    paint(val, Role::Accent)
 }
 
 /// Debug styling — blue #00bfff (distinct from brand for diagnostic context).
 pub fn debug<D: std::fmt::Display>(val: D) -> StyledObject<D> {
-    style(val).color256(DEBUG_256)
+    paint(val, Role::Debug)
 }
 
 /// Success styling — green #22cc00 (WCAG AA, universal across all themes).
 pub fn success<D: std::fmt::Display>(val: D) -> StyledObject<D> {
-    style(val).color256(SUCCESS_256)
+    paint(val, Role::Success)
 }
 
 /// Warning styling — amber #ffaa00 (WCAG AA, universal across all themes).
 pub fn warning<D: std::fmt::Display>(val: D) -> StyledObject<D> {
-    style(val).color256(WARNING_256)
+    paint(val, Role::Warning)
 }
 
 /// Error styling — red #ff0000 bold (WCAG AA, universal across all themes).
 pub fn error<D: std::fmt::Display>(val: D) -> StyledObject<D> {
-    style(val).color256(ERROR_256).bold()
+    paint(val, Role::Error).bold()
 }
 
 /// Foreground styling — default text color from the active theme.
 pub fn foreground<D: std::fmt::Display>(val: D) -> StyledObject<D> {
-    style(val).color256(foreground_color())
+    paint(val, Role::Foreground)
 }
 
 /// Muted styling — gray-400 #a0a0a0 for trace-level and secondary information.
 pub fn muted<D: std::fmt::Display>(val: D) -> StyledObject<D> {
-    style(val).color256(MUTED_256)
+    paint(val, Role::Muted)
 }
 
 /// Bold text without color.
@@ -148,43 +355,126 @@ pub fn bold<D: std::fmt::Display>(val: D) -> StyledObject<D> {
     style(val).bold()
 }
 
-/// Unicode icons used across all console output.
+/// Series color `index` (cycling), for labels that only need to differ from
+/// each other, such as per-service log prefixes.
+pub fn series<D: std::fmt::Display>(val: D, index: usize) -> StyledObject<D> {
+    if !colors_enabled() {
+        return style(val);
+    }
+    let palette = if color_scheme() == ColorScheme::HighContrast {
+        HC_SERIES
+    } else {
+        SERIES_256
+    };
+    style(val).color256(palette[index % palette.len()])
+}
+
+/// A glyph with a variant per [`OutputTheme`]; displays the active one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph {
+    pub unicode: &'static str,
+    pub ascii: &'static str,
+    pub plain: &'static str,
+}
+
+impl Glyph {
+    pub const fn new(unicode: &'static str, ascii: &'static str, plain: &'static str) -> Self {
+        Self {
+            unicode,
+            ascii,
+            plain,
+        }
+    }
+
+    /// The variant for the active output theme.
+    pub fn as_str(&self) -> &'static str {
+        match output_theme() {
+            OutputTheme::Unicode => self.unicode,
+            OutputTheme::Ascii => self.ascii,
+            OutputTheme::Plain => self.plain,
+        }
+    }
+}
+
+impl fmt::Display for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Icons used across all console output.
 pub mod icons {
+    use super::Glyph;
+
     /// Brand mark.
-    pub const BRAND: &str = "\u{25C6}"; // ◆
+    pub const BRAND: Glyph = Glyph::new("\u{25C6}", "*", "*"); // ◆
 
     /// Success checkmark.
-    pub const SUCCESS: &str = "\u{2713}"; // ✓
+    pub const SUCCESS: Glyph = Glyph::new("\u{2713}", "+", "ok"); // ✓
 
     /// Error cross.
-    pub const ERROR: &str = "\u{2715}"; // ✕
+    pub const ERROR: Glyph = Glyph::new("\u{2715}", "x", "error"); // ✕
 
     /// Warning sign.
-    pub const WARNING: &str = "\u{26A0}"; // ⚠
+    pub const WARNING: Glyph = Glyph::new("\u{26A0}", "!", "warning"); // ⚠
 
     /// Info symbol.
-    pub const INFO: &str = "\u{2139}"; // ℹ
+    pub const INFO: Glyph = Glyph::new("\u{2139}", "i", "info"); // ℹ
 
     /// Debug arrow.
-    pub const DEBUG: &str = "\u{203A}"; // ›
+    pub const DEBUG: Glyph = Glyph::new("\u{203A}", ">", "debug"); // ›
 
     /// Trace dot.
-    pub const TRACE: &str = "\u{00B7}"; // ·
+    pub const TRACE: Glyph = Glyph::new("\u{00B7}", ".", "trace"); // ·
 
     /// Selection cursor.
-    pub const CURSOR: &str = ">";
+    pub const CURSOR: Glyph = Glyph::new(">", ">", ">");
 
     /// Pending circle (empty).
-    pub const PENDING: &str = "\u{25CB}"; // ○
+    pub const PENDING: Glyph = Glyph::new("\u{25CB}", "o", "todo"); // ○
 
     /// In-progress circle (half).
-    pub const IN_PROGRESS: &str = "\u{25D0}"; // ◐
+    pub const IN_PROGRESS: Glyph = Glyph::new("\u{25D0}", "~", "doing"); // ◐
+
+    /// Complete circle (filled).
+    pub const COMPLETE: Glyph = Glyph::new("\u{25CF}", "*", "done"); // ●
+
+    /// Blocked cross.
+    pub const BLOCKED: Glyph = Glyph::new("\u{2715}", "x", "blocked"); // ✕
+
+    /// Cancelled circle (slashed).
+    pub const CANCELLED: Glyph = Glyph::new("\u{2298}", "-", "cancelled"); // ⊘
+
+    /// Checked checkbox.
+    pub const CHECKED: Glyph = Glyph::new("[\u{2713}]", "[x]", "[x]"); // [✓]
+
+    /// List bullet.
+    pub const BULLET: Glyph = Glyph::new("\u{2022}", "*", "-"); // •
 
     /// Progress bar filled block.
-    pub const BAR_FILLED: &str = "\u{2588}"; // █
+    pub const BAR_FILLED: Glyph = Glyph::new("\u{2588}", "#", "#"); // █
 
     /// Progress bar empty block.
-    pub const BAR_EMPTY: &str = "\u{2591}"; // ░
+    pub const BAR_EMPTY: Glyph = Glyph::new("\u{2591}", ".", "."); // ░
+}
+
+/// Border glyphs for tables, cards, sections and trees.
+pub mod borders {
+    use super::Glyph;
+
+    pub const HORIZONTAL: Glyph = Glyph::new("\u{2500}", "-", "-"); // ─
+    pub const VERTICAL: Glyph = Glyph::new("\u{2502}", "|", "|"); // │
+    pub const TOP_LEFT: Glyph = Glyph::new("\u{256D}", "+", "+"); // ╭
+    pub const TOP_RIGHT: Glyph = Glyph::new("\u{256E}", "+", "+"); // ╮
+    pub const BOTTOM_LEFT: Glyph = Glyph::new("\u{2570}", "+", "+"); // ╰
+    pub const BOTTOM_RIGHT: Glyph = Glyph::new("\u{256F}", "+", "+"); // ╯
+    pub const TOP_TEE: Glyph = Glyph::new("\u{252C}", "+", "+"); // ┬
+    pub const BOTTOM_TEE: Glyph = Glyph::new("\u{2534}", "+", "+"); // ┴
+    pub const LEFT_TEE: Glyph = Glyph::new("\u{251C}", "+", "+"); // ├
+    pub const RIGHT_TEE: Glyph = Glyph::new("\u{2524}", "+", "+"); // ┤
+    pub const CROSS: Glyph = Glyph::new("\u{253C}", "+", "+"); // ┼
+    pub const BRANCH: Glyph = Glyph::new("\u{251C}\u{2500}", "|-", "|-"); // ├─
+    pub const LAST_BRANCH: Glyph = Glyph::new("\u{2514}\u{2500}", "`-", "`-"); // └─
 }
 
 /// Spinner animation frames (braille pattern).
@@ -194,6 +484,17 @@ pub const SPINNER_FRAMES: &[&str] = &[
     "\u{2807}", "\u{280F}",
 ]; // ⠋ ⠙ ⠹ ⠸ ⠼ ⠴ ⠦ ⠧ ⠇ ⠏
 
+/// Spinner animation frames for the `ascii` and `plain` output themes.
+pub const ASCII_SPINNER_FRAMES: &[&str] = &["|", "/", "-", "\\"];
+
+/// Spinner frames for the active output theme.
+pub fn spinner_frames() -> &'static [&'static str] {
+    match output_theme() {
+        OutputTheme::Unicode => SPINNER_FRAMES,
+        OutputTheme::Ascii | OutputTheme::Plain => ASCII_SPINNER_FRAMES,
+    }
+}
+
 /// Convert a hex color (e.g. "#875fd7") to the closest ANSI 256-color index.
 pub fn hex_to_ansi256(hex: &str) -> u8 {
     let (r, g, b) = parse_hex(hex);
@@ -0,0 +1,57 @@
+//! ChaCha20-Poly1305 sealing, in the same `base64(nonce || ciphertext)`
+//! format as hive's `crypto` module.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Nonce,
+};
+use rand::RngCore;
+
+use crate::error::{CredentialStoreError, Result};
+
+const NONCE_SIZE: usize = 12;
+
+pub fn random_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    rand::rng().fill_bytes(&mut key);
+    key
+}
+
+/// Encrypt with ChaCha20-Poly1305; returns base64(nonce || ciphertext).
+pub fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<String> {
+    let cipher = ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| CredentialStoreError::Key(format!("failed to create cipher: {e}")))?;
+
+    let mut nonce_bytes = [0u8; NONCE_SIZE];
+    rand::rng().fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .map_err(|_| CredentialStoreError::Corrupt("encryption failed".to_string()))?;
+
+    let mut combined = nonce_bytes.to_vec();
+    combined.extend(ciphertext);
+    Ok(BASE64.encode(combined))
+}
+
+/// Inverse of [`seal`]. Fails on a wrong key or tampered data.
+pub fn open(key: &[u8; 32], sealed: &str) -> Result<Vec<u8>> {
+    let combined = BASE64
+        .decode(sealed.trim())
+        .map_err(|e| CredentialStoreError::Corrupt(format!("invalid base64: {e}")))?;
+    if combined.len() <= NONCE_SIZE {
+        return Err(CredentialStoreError::Corrupt(
+            "sealed data too short".to_string(),
+        ));
+    }
+
+    let (nonce_bytes, ciphertext) = combined.split_at(NONCE_SIZE);
+    let cipher = ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| CredentialStoreError::Key(format!("failed to create cipher: {e}")))?;
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| CredentialStoreError::Decrypt)
+}
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CredentialStoreError {
+    #[error("credential store I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid credential store key: {0}")]
+    Key(String),
+
+    /// Wrong key, or the file was changed outside the store
+    #[error("credential store could not be decrypted")]
+    Decrypt,
+
+    #[error("corrupt credential store: {0}")]
+    Corrupt(String),
+}
+
+pub type Result<T> = std::result::Result<T, CredentialStoreError>;
@@ -0,0 +1,424 @@
+//! Encrypted local credential cache.
+//!
+//! Cocoon secrets, signaling access tokens and browser debug tokens used to
+//! live in environment variables or plaintext files. [`CredentialStore`]
+//! keeps them in one ChaCha20-Poly1305 sealed file so signaling clients can
+//! reconnect without them being passed in again:
+//!
+//! ```no_run
+//! use lib_credential_store::CredentialStore;
+//!
+//! # fn run() -> lib_credential_store::Result<()> {
+//! let store = CredentialStore::open_default()?;
+//! store.set_access_token("wss://signal.example.com/ws", "eyJ...", None)?;
+//! let token = store.access_token("wss://signal.example.com/ws")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Files live in `$ADI_CREDENTIALS_DIR` (default `~/.local/share/adi`):
+//! `credentials.enc` holds the sealed entries and `credentials.key` the
+//! random key, created on first use with mode 0600. Setting
+//! `ADI_CREDENTIALS_KEY` (64 hex chars) uses that key instead, so the file can
+//! sit on shared storage. Reads take a shared lock on `credentials.lock` and
+//! updates an exclusive one, so concurrent CLI invocations never lose each
+//! other's writes.
+
+mod crypto;
+pub mod error;
+
+pub use error::{CredentialStoreError, Result};
+
+use fs2::FileExt;
+use lib_env_parse::{env_opt, env_vars};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+env_vars! {
+    AdiCredentialsDir => "ADI_CREDENTIALS_DIR",
+    AdiCredentialsKey => "ADI_CREDENTIALS_KEY",
+}
+
+const ADI_SUBDIR: &str = "adi";
+const STORE_FILE: &str = "credentials.enc";
+const KEY_FILE: &str = "credentials.key";
+const LOCK_FILE: &str = "credentials.lock";
+
+/// A cached secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credential {
+    pub value: String,
+    /// Unix ms; `None` never expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+impl Credential {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            expires_at: None,
+        }
+    }
+
+    pub fn expiring(value: impl Into<String>, expires_at: Option<i64>) -> Self {
+        Self {
+            value: value.into(),
+            expires_at,
+        }
+    }
+
+    pub fn is_expired(&self, now_ms: i64) -> bool {
+        self.expires_at.is_some_and(|at| at <= now_ms)
+    }
+}
+
+/// What a credential is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialKey<'a> {
+    /// `COCOON_SECRET` of a cocoon, by name
+    CocoonSecret(&'a str),
+    /// Access token for a signaling server, by URL
+    AccessToken(&'a str),
+    /// Browser debug token issued by a signaling server, by URL
+    BrowserDebugToken(&'a str),
+    /// Anything else, e.g. refresh tokens of an auth client
+    Custom(&'a str),
+}
+
+impl CredentialKey<'_> {
+    fn entry_name(&self) -> String {
+        match self {
+            Self::CocoonSecret(name) => format!("cocoon-secret:{name}"),
+            Self::AccessToken(url) => format!("access-token:{url}"),
+            Self::BrowserDebugToken(url) => format!("browser-debug-token:{url}"),
+            Self::Custom(name) => format!("custom:{name}"),
+        }
+    }
+}
+
+/// Sealed credential file plus its key
+#[derive(Clone)]
+pub struct CredentialStore {
+    dir: PathBuf,
+    key: [u8; 32],
+}
+
+impl std::fmt::Debug for CredentialStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialStore")
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CredentialStore {
+    /// Store in `$ADI_CREDENTIALS_DIR` or `~/.local/share/adi`
+    pub fn open_default() -> Result<Self> {
+        Self::open(default_dir())
+    }
+
+    /// Store in `dir`, keyed by `$ADI_CREDENTIALS_KEY` or the key file, which
+    /// is created when missing
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let key = match env_opt(EnvVar::AdiCredentialsKey.as_str()) {
+            Some(hex_key) => parse_key(&hex_key)?,
+            None => {
+                // Two first runs must not each create a key
+                let _lock = lock(&dir, true)?;
+                load_or_create_key(&dir.join(KEY_FILE))?
+            }
+        };
+        Ok(Self { dir, key })
+    }
+
+    /// Store in `dir` with an explicit key; no key file is read or written
+    pub fn with_key(dir: impl Into<PathBuf>, key: [u8; 32]) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, key })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The credential for `key`, unless it is missing or expired
+    pub fn get(&self, key: CredentialKey<'_>) -> Result<Option<Credential>> {
+        let _lock = lock(&self.dir, false)?;
+        let now = now_ms();
+        Ok(self
+            .read_entries()?
+            .remove(&key.entry_name())
+            .filter(|credential| !credential.is_expired(now)))
+    }
+
+    pub fn set(&self, key: CredentialKey<'_>, credential: Credential) -> Result<()> {
+        self.update(|entries| {
+            entries.insert(key.entry_name(), credential);
+        })
+    }
+
+    /// Drop the credential for `key`; returns whether there was one
+    pub fn remove(&self, key: CredentialKey<'_>) -> Result<bool> {
+        self.update(|entries| entries.remove(&key.entry_name()).is_some())
+    }
+
+    pub fn cocoon_secret(&self, cocoon: &str) -> Result<Option<String>> {
+        self.value(CredentialKey::CocoonSecret(cocoon))
+    }
+
+    pub fn set_cocoon_secret(&self, cocoon: &str, secret: &str) -> Result<()> {
+        self.set(CredentialKey::CocoonSecret(cocoon), Credential::new(secret))
+    }
+
+    pub fn access_token(&self, signaling_url: &str) -> Result<Option<String>> {
+        self.value(CredentialKey::AccessToken(signaling_url))
+    }
+
+    /// `expires_at` in unix ms
+    pub fn set_access_token(
+        &self,
+        signaling_url: &str,
+        token: &str,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        self.set(
+            CredentialKey::AccessToken(signaling_url),
+            Credential::expiring(token, expires_at),
+        )
+    }
+
+    pub fn browser_debug_token(&self, signaling_url: &str) -> Result<Option<String>> {
+        self.value(CredentialKey::BrowserDebugToken(signaling_url))
+    }
+
+    /// `expires_at` in unix ms, as in `browser_debug_tab_available`
+    pub fn set_browser_debug_token(
+        &self,
+        signaling_url: &str,
+        token: &str,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        self.set(
+            CredentialKey::BrowserDebugToken(signaling_url),
+            Credential::expiring(token, expires_at),
+        )
+    }
+
+    fn value(&self, key: CredentialKey<'_>) -> Result<Option<String>> {
+        Ok(self.get(key)?.map(|credential| credential.value))
+    }
+
+    /// Read-modify-write under the exclusive lock; expired entries are dropped
+    fn update<T>(&self, f: impl FnOnce(&mut BTreeMap<String, Credential>) -> T) -> Result<T> {
+        let _lock = lock(&self.dir, true)?;
+        let mut entries = self.read_entries()?;
+        let result = f(&mut entries);
+        let now = now_ms();
+        entries.retain(|_, credential| !credential.is_expired(now));
+        self.write_entries(&entries)?;
+        Ok(result)
+    }
+
+    fn read_entries(&self) -> Result<BTreeMap<String, Credential>> {
+        let sealed = match fs::read_to_string(self.dir.join(STORE_FILE)) {
+            Ok(sealed) => sealed,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let plaintext = crypto::open(&self.key, &sealed)?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| CredentialStoreError::Corrupt(format!("invalid entries: {e}")))
+    }
+
+    /// Replace the store file atomically, so readers never see half of it
+    fn write_entries(&self, entries: &BTreeMap<String, Credential>) -> Result<()> {
+        let plaintext = serde_json::to_vec(entries)
+            .map_err(|e| CredentialStoreError::Corrupt(format!("failed to encode entries: {e}")))?;
+        let sealed = crypto::seal(&self.key, &plaintext)?;
+
+        let tmp = self.dir.join(format!("{STORE_FILE}.tmp"));
+        write_private(&tmp, sealed.as_bytes())?;
+        fs::rename(&tmp, self.dir.join(STORE_FILE))?;
+        Ok(())
+    }
+}
+
+/// `$ADI_CREDENTIALS_DIR` or `~/.local/share/adi`
+pub fn default_dir() -> PathBuf {
+    env_opt(EnvVar::AdiCredentialsDir.as_str())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            dirs::data_local_dir()
+                .unwrap_or_else(|| PathBuf::from("~/.local/share"))
+                .join(ADI_SUBDIR)
+        })
+}
+
+/// Lock held until the returned file is dropped
+fn lock(dir: &Path, exclusive: bool) -> Result<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))?;
+    if exclusive {
+        file.lock_exclusive()?;
+    } else {
+        FileExt::lock_shared(&file)?;
+    }
+    Ok(file)
+}
+
+fn load_or_create_key(path: &Path) -> Result<[u8; 32]> {
+    match fs::read_to_string(path) {
+        Ok(hex_key) => parse_key(&hex_key),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let key = crypto::random_key();
+            write_private(path, hex::encode(key).as_bytes())?;
+            Ok(key)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn parse_key(hex_key: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(hex_key.trim())
+        .map_err(|e| CredentialStoreError::Key(format!("invalid hex: {e}")))?;
+    bytes
+        .try_into()
+        .map_err(|_| CredentialStoreError::Key("key must be exactly 32 bytes".to_string()))
+}
+
+/// Write a file only the current user can read
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.create(true).truncate(true).write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_accessors_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CredentialStore::with_key(dir.path(), [7u8; 32]).unwrap();
+        let url = "wss://signal.example.com/ws";
+
+        assert_eq!(store.access_token(url).unwrap(), None);
+        store.set_access_token(url, "token-1", None).unwrap();
+        store.set_cocoon_secret("box", "s3cr3t").unwrap();
+        store
+            .set_browser_debug_token(url, "debug-1", Some(now_ms() + 60_000))
+            .unwrap();
+
+        assert_eq!(store.access_token(url).unwrap().as_deref(), Some("token-1"));
+        assert_eq!(store.access_token("wss://other/ws").unwrap(), None);
+        assert_eq!(
+            store.cocoon_secret("box").unwrap().as_deref(),
+            Some("s3cr3t")
+        );
+        assert_eq!(
+            store.browser_debug_token(url).unwrap().as_deref(),
+            Some("debug-1")
+        );
+
+        // Nothing readable is left on disk
+        let raw = fs::read_to_string(dir.path().join(STORE_FILE)).unwrap();
+        assert!(!raw.contains("token-1") && !raw.contains("s3cr3t"));
+
+        assert!(store.remove(CredentialKey::CocoonSecret("box")).unwrap());
+        assert!(!store.remove(CredentialKey::CocoonSecret("box")).unwrap());
+        assert_eq!(store.cocoon_secret("box").unwrap(), None);
+    }
+
+    #[test]
+    fn test_expired_credentials_are_hidden_and_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CredentialStore::with_key(dir.path(), [7u8; 32]).unwrap();
+        let url = "wss://signal.example.com/ws";
+
+        store
+            .set_access_token(url, "old", Some(now_ms() - 1))
+            .unwrap();
+        assert_eq!(store.access_token(url).unwrap(), None);
+
+        store
+            .set(CredentialKey::Custom("refresh"), Credential::new("r"))
+            .unwrap();
+        assert!(!store
+            .read_entries()
+            .unwrap()
+            .contains_key("access-token:wss://signal.example.com/ws"));
+    }
+
+    #[test]
+    fn test_key_file_is_created_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = CredentialStore::open(dir.path()).unwrap();
+        first.set_cocoon_secret("box", "s3cr3t").unwrap();
+
+        let second = CredentialStore::open(dir.path()).unwrap();
+        assert_eq!(
+            second.cocoon_secret("box").unwrap().as_deref(),
+            Some("s3cr3t")
+        );
+
+        let other = CredentialStore::with_key(dir.path(), [1u8; 32]).unwrap();
+        assert!(matches!(
+            other.cocoon_secret("box"),
+            Err(CredentialStoreError::Decrypt)
+        ));
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_every_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let dir = dir.path().to_path_buf();
+                std::thread::spawn(move || {
+                    let store = CredentialStore::with_key(dir, [7u8; 32]).unwrap();
+                    store
+                        .set_cocoon_secret(&format!("cocoon-{i}"), "s3cr3t")
+                        .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let store = CredentialStore::with_key(dir.path(), [7u8; 32]).unwrap();
+        for i in 0..8 {
+            assert!(store
+                .cocoon_secret(&format!("cocoon-{i}"))
+                .unwrap()
+                .is_some());
+        }
+    }
+}
@@ -1,14 +1,16 @@
 use crate::paths;
 use crate::protocol::{
-    ArchivedResponse, ArchivedServiceInfo, ArchivedServiceState, MessageFrame, Request, Response,
+    ArchivedDaemonEvent, ArchivedJobInfo, ArchivedResponse, ArchivedServiceInfo,
+    ArchivedServiceState, DaemonEvent, JobInfo, JobSpec, MessageFrame, Request, Response,
     ServiceConfig, ServiceInfo, ServiceState,
 };
 use anyhow::{anyhow, Result};
 use lib_daemon_core::{spawn_background, SpawnConfig};
+use lib_retry::RetryPolicy;
 use std::path::PathBuf;
 use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, Instrument};
 
 /// Default timeout for IPC operations
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
@@ -16,8 +18,14 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
 /// Maximum time to wait for daemon to start
 const DAEMON_START_TIMEOUT: Duration = Duration::from_secs(15);
 
-/// Interval for checking daemon startup
-const DAEMON_START_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+/// Polling for the socket of a freshly started daemon
+const DAEMON_START_RETRY: RetryPolicy =
+    RetryPolicy::fixed(Duration::from_millis(100)).budget(DAEMON_START_TIMEOUT);
+
+#[cfg(unix)]
+type IpcStream = tokio::net::UnixStream;
+#[cfg(not(unix))]
+type IpcStream = tokio::net::TcpStream;
 
 pub struct DaemonClient {
     socket_path: PathBuf,
@@ -76,6 +84,20 @@ impl DaemonClient {
         }
     }
 
+    /// Replace the running daemon with `binary` without stopping services
+    pub async fn update(&self, binary: &str) -> Result<()> {
+        let response = self
+            .request(&Request::Update {
+                binary: binary.to_string(),
+            })
+            .await?;
+        match response {
+            Response::Ok => Ok(()),
+            Response::Error { message } => Err(anyhow!("Update failed: {}", message)),
+            _ => Err(anyhow!("Unexpected response")),
+        }
+    }
+
     pub async fn start_service(&self, name: &str, config: Option<ServiceConfig>) -> Result<()> {
         let response = self
             .request(&Request::StartService {
@@ -194,6 +216,94 @@ impl DaemonClient {
         }
     }
 
+    /// Publish an event on the daemon bus
+    pub async fn publish(&self, event: DaemonEvent) -> Result<()> {
+        let response = self.request(&Request::Publish { event }).await?;
+        match response {
+            Response::Ok => Ok(()),
+            Response::Error { message } => Err(anyhow!("Failed to publish event: {}", message)),
+            _ => Err(anyhow!("Unexpected response")),
+        }
+    }
+
+    /// Subscribe to bus events matching any of the topic patterns (`*`, `service.*`, ...)
+    pub async fn subscribe(&self, topics: &[String]) -> Result<EventSubscription> {
+        let mut stream = tokio::time::timeout(self.timeout, self.connect())
+            .await
+            .map_err(|_| anyhow!("Daemon request timed out after {:?}", self.timeout))??;
+
+        write_request(
+            &mut stream,
+            &Request::Subscribe {
+                topics: topics.to_vec(),
+            },
+        )
+        .await?;
+
+        let ack = tokio::time::timeout(self.timeout, read_response(&mut stream))
+            .await
+            .map_err(|_| anyhow!("Daemon request timed out after {:?}", self.timeout))??;
+        match ack {
+            Response::Ok => Ok(EventSubscription { stream }),
+            Response::Error { message } => Err(anyhow!("Failed to subscribe: {}", message)),
+            _ => Err(anyhow!("Unexpected response")),
+        }
+    }
+
+    /// Register a recurring job, replacing any job with the same ID.
+    /// Safe to call on every plugin start; an unchanged job keeps its schedule.
+    pub async fn schedule_job(&self, job: JobSpec) -> Result<()> {
+        let response = self.request(&Request::ScheduleJob { job }).await?;
+        match response {
+            Response::Ok => Ok(()),
+            Response::Error { message } => Err(anyhow!("Failed to schedule job: {}", message)),
+            _ => Err(anyhow!("Unexpected response")),
+        }
+    }
+
+    pub async fn unschedule_job(&self, id: &str) -> Result<()> {
+        let response = self
+            .request(&Request::UnscheduleJob { id: id.to_string() })
+            .await?;
+        match response {
+            Response::Ok => Ok(()),
+            Response::Error { message } => Err(anyhow!("Failed to unschedule job: {}", message)),
+            _ => Err(anyhow!("Unexpected response")),
+        }
+    }
+
+    pub async fn list_jobs(&self) -> Result<Vec<JobInfo>> {
+        let response = self.request(&Request::ListJobs).await?;
+        match response {
+            Response::Jobs { list } => Ok(list),
+            Response::Error { message } => Err(anyhow!("Failed to list jobs: {}", message)),
+            _ => Err(anyhow!("Unexpected response")),
+        }
+    }
+
+    /// Run a scheduled job now and wait for it to finish.
+    ///
+    /// The client timeout covers the whole run; use
+    /// [`with_timeout`](Self::with_timeout) for long-running jobs.
+    pub async fn run_job_now(&self, id: &str) -> Result<CommandOutput> {
+        let response = self
+            .request(&Request::RunJobNow { id: id.to_string() })
+            .await?;
+        match response {
+            Response::CommandResult {
+                exit_code,
+                stdout,
+                stderr,
+            } => Ok(CommandOutput {
+                exit_code,
+                stdout,
+                stderr,
+            }),
+            Response::Error { message } => Err(anyhow!("Failed to run job: {}", message)),
+            _ => Err(anyhow!("Unexpected response")),
+        }
+    }
+
     pub async fn ensure_running(&self) -> Result<()> {
         if self.is_running().await {
             debug!("Daemon already running");
@@ -204,8 +314,8 @@ impl DaemonClient {
         start_daemon()?;
 
         // Wait for socket to appear
-        let start = std::time::Instant::now();
-        while start.elapsed() < DAEMON_START_TIMEOUT {
+        let mut backoff = DAEMON_START_RETRY.backoff();
+        loop {
             if self.socket_exists() {
                 // Socket exists, try to ping
                 if self.ping().await.is_ok() {
@@ -213,7 +323,10 @@ impl DaemonClient {
                     return Ok(());
                 }
             }
-            tokio::time::sleep(DAEMON_START_CHECK_INTERVAL).await;
+            match backoff.next_delay() {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => break,
+            }
         }
 
         Err(anyhow!(
@@ -223,7 +336,11 @@ impl DaemonClient {
     }
 
     async fn request(&self, request: &Request) -> Result<Response> {
-        let result = tokio::time::timeout(self.timeout, self.request_inner(request)).await;
+        // Timed by `adi --profile`
+        let span = tracing::info_span!(target: "profile", "ipc", request = request.as_str());
+        let result = tokio::time::timeout(self.timeout, self.request_inner(request))
+            .instrument(span)
+            .await;
 
         match result {
             Ok(inner_result) => inner_result,
@@ -235,14 +352,19 @@ impl DaemonClient {
     }
 
     async fn request_inner(&self, request: &Request) -> Result<Response> {
-        // Connect to socket
+        let mut stream = self.connect().await?;
+        write_request(&mut stream, request).await?;
+        read_response(&mut stream).await
+    }
+
+    async fn connect(&self) -> Result<IpcStream> {
         #[cfg(unix)]
-        let mut stream = tokio::net::UnixStream::connect(&self.socket_path)
+        let stream = tokio::net::UnixStream::connect(&self.socket_path)
             .await
             .map_err(|e| anyhow!("Failed to connect to daemon: {}", e))?;
 
         #[cfg(not(unix))]
-        let mut stream = {
+        let stream = {
             // On non-Unix, fall back to TCP
             let port = paths::daemon_tcp_port();
             tokio::net::TcpStream::connect(format!("127.0.0.1:{}", port))
@@ -251,27 +373,54 @@ impl DaemonClient {
         };
 
         trace!("Connected to daemon socket");
+        Ok(stream)
+    }
+}
+
+/// Open event stream from [`DaemonClient::subscribe`]
+pub struct EventSubscription {
+    stream: IpcStream,
+}
+
+impl EventSubscription {
+    /// Wait for the next event. Returns `None` when the daemon closes the stream.
+    pub async fn next(&mut self) -> Result<Option<DaemonEvent>> {
+        match read_response(&mut self.stream).await {
+            Ok(Response::Event { event }) => Ok(Some(event)),
+            Ok(Response::StreamEnd) => Ok(None),
+            Ok(Response::Error { message }) => Err(anyhow!("Event stream error: {}", message)),
+            Ok(_) => Err(anyhow!("Unexpected response")),
+            Err(e) => match e.downcast_ref::<std::io::Error>() {
+                Some(io) if io.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+                _ => Err(e),
+            },
+        }
+    }
+}
 
-        let request_bytes = MessageFrame::encode_request(request)
-            .map_err(|e| anyhow!("Failed to encode request: {}", e))?;
+async fn write_request(stream: &mut IpcStream, request: &Request) -> Result<()> {
+    let request_bytes = MessageFrame::encode_request(request)
+        .map_err(|e| anyhow!("Failed to encode request: {}", e))?;
 
-        stream.write_all(&request_bytes).await?;
-        stream.flush().await?;
-        trace!("Sent request to daemon");
+    stream.write_all(&request_bytes).await?;
+    stream.flush().await?;
+    trace!("Sent request to daemon");
+    Ok(())
+}
 
-        let mut len_buf = [0u8; 4];
-        stream.read_exact(&mut len_buf).await?;
-        let len = MessageFrame::read_length(&len_buf);
-        trace!("Response length: {} bytes", len);
+async fn read_response(stream: &mut IpcStream) -> Result<Response> {
+    let mut len_buf = [0u8; 4];
+    stream.read_exact(&mut len_buf).await?;
+    let len = MessageFrame::read_length(&len_buf);
+    trace!("Response length: {} bytes", len);
 
-        let mut response_buf = vec![0u8; len];
-        stream.read_exact(&mut response_buf).await?;
+    let mut response_buf = vec![0u8; len];
+    stream.read_exact(&mut response_buf).await?;
 
-        let archived = rkyv::access::<ArchivedResponse, rkyv::rancor::Error>(&response_buf)
-            .map_err(|e| anyhow!("Failed to deserialize response: {}", e))?;
+    let archived = rkyv::access::<ArchivedResponse, rkyv::rancor::Error>(&response_buf)
+        .map_err(|e| anyhow!("Failed to deserialize response: {}", e))?;
 
-        deserialize_response(archived)
-    }
+    deserialize_response(archived)
 }
 
 impl Default for DaemonClient {
@@ -404,6 +553,43 @@ fn deserialize_response(archived: &ArchivedResponse) -> Result<Response> {
         ArchivedResponse::SudoDenied { reason } => Ok(Response::SudoDenied {
             reason: reason.to_string(),
         }),
+        ArchivedResponse::Event { event } => Ok(Response::Event {
+            event: deserialize_event(event),
+        }),
+        ArchivedResponse::Jobs { list } => Ok(Response::Jobs {
+            list: list.iter().map(deserialize_job_info).collect(),
+        }),
+    }
+}
+
+/// Convert an archived job spec (from a request or response) into an owned one
+pub fn deserialize_job_spec(archived: &crate::protocol::ArchivedJobSpec) -> JobSpec {
+    JobSpec {
+        id: archived.id.to_string(),
+        owner: archived.owner.to_string(),
+        args: archived.args.iter().map(|s| s.to_string()).collect(),
+        interval_secs: archived.interval_secs.into(),
+        jitter_secs: archived.jitter_secs.into(),
+    }
+}
+
+fn deserialize_job_info(archived: &ArchivedJobInfo) -> JobInfo {
+    JobInfo {
+        spec: deserialize_job_spec(&archived.spec),
+        next_run_ms: archived.next_run_ms.into(),
+        last_run_ms: archived.last_run_ms.as_ref().map(|t| (*t).into()),
+        last_exit_code: archived.last_exit_code.as_ref().map(|c| (*c).into()),
+        running: archived.running,
+    }
+}
+
+/// Convert an archived event (from a request or response) into an owned one
+pub fn deserialize_event(archived: &ArchivedDaemonEvent) -> DaemonEvent {
+    DaemonEvent {
+        topic: archived.topic.to_string(),
+        source: archived.source.to_string(),
+        payload: archived.payload.to_string(),
+        timestamp_ms: archived.timestamp_ms.into(),
     }
 }
 
@@ -0,0 +1,87 @@
+//! Daemon event bus: well-known topics and topic matching
+//!
+//! Any IPC client can publish to the bus and subscribe to it. The daemon
+//! itself publishes service lifecycle and log events; plugins publish their
+//! own (cocoon connections, certificate expiry, ...) and may define new topics.
+
+use crate::protocol::DaemonEvent;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub mod topics {
+    /// Service state changed. Payload: `{service, state, pid?, error?}`
+    pub const SERVICE_STATE: &str = "service.state";
+    /// Service process exited unexpectedly. Payload: `{service, pid?, restarts, will_restart}`
+    pub const SERVICE_CRASHED: &str = "service.crashed";
+    /// Service logged too many errors in a short window. Payload: `{service, errors, window_secs, last_line}`
+    pub const LOG_THRESHOLD: &str = "log.threshold";
+    /// TLS certificate is due for renewal. Payload: `{domains, renew_before_days, renewed, error?}`
+    pub const CERT_EXPIRY: &str = "cert.expiry";
+    /// Cocoon registered with signaling. Payload: `{device_id, name?}`
+    pub const COCOON_CONNECTED: &str = "cocoon.connected";
+    /// Cocoon lost its signaling connection. Payload: `{device_id, reason?}`
+    pub const COCOON_DISCONNECTED: &str = "cocoon.disconnected";
+    /// Scheduled job finished. Payload: `{job, owner, exit_code, duration_ms}`
+    pub const SCHEDULE_COMPLETED: &str = "schedule.completed";
+    /// Scheduled job exited non-zero or could not start. Payload: `{job, owner, exit_code?, error?, duration_ms}`
+    pub const SCHEDULE_FAILED: &str = "schedule.failed";
+}
+
+impl DaemonEvent {
+    /// Create an event stamped with the current time
+    pub fn new(
+        topic: impl Into<String>,
+        source: impl Into<String>,
+        payload: impl Into<String>,
+    ) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Self {
+            topic: topic.into(),
+            source: source.into(),
+            payload: payload.into(),
+            timestamp_ms,
+        }
+    }
+
+    /// Check if this event matches any of the subscription patterns
+    pub fn matches_any(&self, patterns: &[String]) -> bool {
+        patterns.iter().any(|p| topic_matches(p, &self.topic))
+    }
+}
+
+/// Match a topic against a pattern: exact, `*` for everything, or `prefix.*`
+pub fn topic_matches(pattern: &str, topic: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.strip_suffix(".*") {
+        Some(prefix) => topic
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('.')),
+        None => pattern == topic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_matches() {
+        assert!(topic_matches("*", "service.state"));
+        assert!(topic_matches("service.state", "service.state"));
+        assert!(topic_matches("service.*", "service.crashed"));
+        assert!(!topic_matches("service.*", "services.state"));
+        assert!(!topic_matches("service.*", "service"));
+        assert!(!topic_matches("service.state", "service.crashed"));
+    }
+
+    #[test]
+    fn test_matches_any() {
+        let event = DaemonEvent::new(topics::COCOON_CONNECTED, "adi.cocoon", "{}");
+        assert!(event.matches_any(&["log.*".to_string(), "cocoon.*".to_string()]));
+        assert!(!event.matches_any(&[]));
+    }
+}
@@ -4,8 +4,12 @@
 //! `adi daemon` background service manager.
 
 pub mod client;
+pub mod events;
 pub mod paths;
 pub mod protocol;
 
-pub use client::{CommandOutput, DaemonClient};
-pub use protocol::{MessageFrame, Request, Response, ServiceConfig, ServiceInfo, ServiceState};
+pub use client::{CommandOutput, DaemonClient, EventSubscription};
+pub use protocol::{
+    DaemonEvent, JobInfo, JobSpec, MessageFrame, Request, Response, ServiceConfig, ServiceInfo,
+    ServiceState,
+};
@@ -7,6 +7,11 @@ pub enum Request {
     Shutdown {
         graceful: bool,
     },
+    /// Start `binary` as the new daemon and hand it the listening socket and
+    /// running services; the current daemon exits once the successor is ready
+    Update {
+        binary: String,
+    },
 
     StartService {
         name: String,
@@ -38,6 +43,52 @@ pub enum Request {
         args: Vec<String>,
         reason: String,
     },
+
+    /// Broadcast an event to all subscribers on the daemon bus
+    Publish {
+        event: DaemonEvent,
+    },
+    /// Keeps the connection open: the daemon acks with `Ok`, then streams
+    /// `Event` frames matching any of the topic patterns
+    Subscribe {
+        topics: Vec<String>,
+    },
+
+    /// Add a recurring job, or replace the one with the same ID
+    ScheduleJob {
+        job: JobSpec,
+    },
+    UnscheduleJob {
+        id: String,
+    },
+    ListJobs,
+    /// Run a job immediately and wait for it; answered with `CommandResult`
+    RunJobNow {
+        id: String,
+    },
+}
+
+impl Request {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Request::Ping => "ping",
+            Request::Shutdown { .. } => "shutdown",
+            Request::Update { .. } => "update",
+            Request::StartService { .. } => "start_service",
+            Request::StopService { .. } => "stop_service",
+            Request::RestartService { .. } => "restart_service",
+            Request::ListServices => "list_services",
+            Request::ServiceLogs { .. } => "service_logs",
+            Request::Run { .. } => "run",
+            Request::SudoRun { .. } => "sudo_run",
+            Request::Publish { .. } => "publish",
+            Request::Subscribe { .. } => "subscribe",
+            Request::ScheduleJob { .. } => "schedule_job",
+            Request::UnscheduleJob { .. } => "unschedule_job",
+            Request::ListJobs => "list_jobs",
+            Request::RunJobNow { .. } => "run_job_now",
+        }
+    }
 }
 
 #[derive(Archive, Deserialize, Serialize, Debug, Clone)]
@@ -70,6 +121,27 @@ pub enum Response {
     SudoDenied {
         reason: String,
     },
+    /// Streamed to subscribers
+    Event {
+        event: DaemonEvent,
+    },
+    Jobs {
+        list: Vec<JobInfo>,
+    },
+}
+
+/// Event on the daemon bus
+#[derive(Archive, Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[rkyv(derive(Debug))]
+pub struct DaemonEvent {
+    /// Dot-separated topic, see [`crate::events::topics`]
+    pub topic: String,
+    /// Publisher: service name, plugin ID, or `daemon`
+    pub source: String,
+    /// JSON-encoded payload
+    pub payload: String,
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
 }
 
 #[derive(Archive, Deserialize, Serialize, Debug, Clone)]
@@ -190,6 +262,60 @@ impl ServiceConfig {
     }
 }
 
+/// Recurring job run by the daemon scheduler: `adi <args>` every
+/// `interval_secs`, delayed by a random `0..=jitter_secs` so jobs registered
+/// together don't all fire at once.
+#[derive(Archive, Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[rkyv(derive(Debug))]
+pub struct JobSpec {
+    /// Unique ID, by convention `<plugin-id>.<job>` (e.g. `adi.tools.reindex`)
+    pub id: String,
+    /// Plugin ID or client that registered the job
+    pub owner: String,
+    /// Arguments to the `adi` binary (e.g. `["tools", "reindex"]`)
+    pub args: Vec<String>,
+    pub interval_secs: u64,
+    pub jitter_secs: u64,
+}
+
+impl JobSpec {
+    pub fn new<I, S>(id: impl Into<String>, owner: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            id: id.into(),
+            owner: owner.into(),
+            args: args.into_iter().map(|s| s.into()).collect(),
+            interval_secs: 3600,
+            jitter_secs: 0,
+        }
+    }
+
+    pub fn every(mut self, interval_secs: u64) -> Self {
+        self.interval_secs = interval_secs;
+        self
+    }
+
+    pub fn jitter(mut self, jitter_secs: u64) -> Self {
+        self.jitter_secs = jitter_secs;
+        self
+    }
+}
+
+/// Scheduled job with its run state
+#[derive(Archive, Deserialize, Serialize, Debug, Clone)]
+#[rkyv(derive(Debug))]
+pub struct JobInfo {
+    pub spec: JobSpec,
+    /// Unix time in milliseconds
+    pub next_run_ms: u64,
+    pub last_run_ms: Option<u64>,
+    pub last_exit_code: Option<i32>,
+    pub running: bool,
+}
+
 /// Message frame for wire protocol
 ///
 /// Format: [4-byte length (little-endian)][rkyv bytes]
@@ -252,6 +378,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_event_roundtrip() {
+        let request = Request::Publish {
+            event: DaemonEvent {
+                topic: "service.crashed".to_string(),
+                source: "daemon".to_string(),
+                payload: r#"{"service":"hive"}"#.to_string(),
+                timestamp_ms: 1_700_000_000_000,
+            },
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&request).unwrap();
+        let archived = rkyv::access::<ArchivedRequest, rkyv::rancor::Error>(&bytes).unwrap();
+
+        if let ArchivedRequest::Publish { event } = archived {
+            assert_eq!(event.topic.as_str(), "service.crashed");
+            assert_eq!(event.payload.as_str(), r#"{"service":"hive"}"#);
+            assert_eq!(event.timestamp_ms, 1_700_000_000_000);
+        } else {
+            panic!("Expected Publish request");
+        }
+    }
+
+    #[test]
+    fn test_schedule_job_roundtrip() {
+        let request = Request::ScheduleJob {
+            job: JobSpec::new("adi.tools.reindex", "adi.tools", ["tools", "reindex"])
+                .every(6 * 3600)
+                .jitter(600),
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&request).unwrap();
+        let archived = rkyv::access::<ArchivedRequest, rkyv::rancor::Error>(&bytes).unwrap();
+
+        if let ArchivedRequest::ScheduleJob { job } = archived {
+            assert_eq!(job.id.as_str(), "adi.tools.reindex");
+            assert_eq!(job.args.len(), 2);
+            assert_eq!(job.interval_secs, 21_600);
+            assert_eq!(job.jitter_secs, 600);
+        } else {
+            panic!("Expected ScheduleJob request");
+        }
+    }
+
     #[test]
     fn test_service_state() {
         assert!(ServiceState::Running.is_running());
@@ -0,0 +1,191 @@
+//! File descriptor passing over Unix sockets (`SCM_RIGHTS`).
+//!
+//! Used to hand a live listening socket and open pipes from one daemon
+//! process to its successor, so clients and supervised children never see
+//! the socket go away during an upgrade.
+//!
+//! Each frame is a little-endian `u32` length followed by the payload. The
+//! descriptors ride along with the length prefix, so a receiver always gets
+//! them together with the frame they belong to.
+
+use crate::error::{DaemonError, Result};
+use std::io::{Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::UnixStream;
+
+/// Upper bound on descriptors accepted in a single frame
+pub const MAX_FDS: usize = 253;
+
+/// Upper bound on a single frame payload
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Send one frame, attaching `fds` as ancillary data.
+///
+/// The descriptors are duplicated into the receiving process; the caller
+/// keeps its own copies open.
+pub fn send_with_fds(stream: &UnixStream, payload: &[u8], fds: &[RawFd]) -> Result<()> {
+    if fds.len() > MAX_FDS {
+        return Err(DaemonError::ProtocolError(format!(
+            "Cannot pass {} descriptors in one frame (max {})",
+            fds.len(),
+            MAX_FDS
+        )));
+    }
+
+    let header = (payload.len() as u32).to_le_bytes();
+
+    if fds.is_empty() {
+        (&*stream).write_all(&header)?;
+    } else {
+        let fd_bytes = std::mem::size_of_val(fds);
+        let space = unsafe { libc::CMSG_SPACE(fd_bytes as u32) } as usize;
+        // u64 storage keeps the control buffer aligned for cmsghdr
+        let mut control = vec![0u64; space.div_ceil(8)];
+
+        let mut iov = libc::iovec {
+            iov_base: header.as_ptr() as *mut libc::c_void,
+            iov_len: header.len(),
+        };
+
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = space as _;
+
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(fd_bytes as u32) as _;
+            std::ptr::copy_nonoverlapping(
+                fds.as_ptr() as *const u8,
+                libc::CMSG_DATA(cmsg),
+                fd_bytes,
+            );
+        }
+
+        let sent = unsafe { libc::sendmsg(stream.as_raw_fd(), &msg, 0) };
+        if sent < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // Ancillary data is delivered with the first byte; finish the header plainly
+        (&*stream).write_all(&header[sent as usize..])?;
+    }
+
+    (&*stream).write_all(payload)?;
+    (&*stream).flush()?;
+    Ok(())
+}
+
+/// Receive one frame and any descriptors attached to it.
+///
+/// Received descriptors are marked close-on-exec.
+pub fn recv_with_fds(stream: &UnixStream) -> Result<(Vec<u8>, Vec<OwnedFd>)> {
+    let mut header = [0u8; 4];
+    let space =
+        unsafe { libc::CMSG_SPACE((MAX_FDS * std::mem::size_of::<RawFd>()) as u32) } as usize;
+    let mut control = vec![0u64; space.div_ceil(8)];
+
+    let mut iov = libc::iovec {
+        iov_base: header.as_mut_ptr() as *mut libc::c_void,
+        iov_len: header.len(),
+    };
+
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = space as _;
+
+    let received = unsafe { libc::recvmsg(stream.as_raw_fd(), &mut msg, 0) };
+    if received < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    if received == 0 {
+        return Err(DaemonError::ProtocolError(
+            "Connection closed before frame".to_string(),
+        ));
+    }
+
+    let mut fds = Vec::new();
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data_len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                let count = data_len / std::mem::size_of::<RawFd>();
+                let data = libc::CMSG_DATA(cmsg) as *const RawFd;
+                for i in 0..count {
+                    let fd = std::ptr::read_unaligned(data.add(i));
+                    libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                    fds.push(OwnedFd::from_raw_fd(fd));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+        return Err(DaemonError::ProtocolError(
+            "Descriptor list was truncated".to_string(),
+        ));
+    }
+
+    (&*stream).read_exact(&mut header[received as usize..])?;
+
+    let len = u32::from_le_bytes(header) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(DaemonError::ProtocolError(format!(
+            "Frame of {} bytes exceeds limit",
+            len
+        )));
+    }
+
+    let mut payload = vec![0u8; len];
+    (&*stream).read_exact(&mut payload)?;
+
+    Ok((payload, fds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_frame_without_fds() {
+        let (a, b) = UnixStream::pair().unwrap();
+        send_with_fds(&a, b"hello", &[]).unwrap();
+
+        let (payload, fds) = recv_with_fds(&b).unwrap();
+        assert_eq!(payload, b"hello");
+        assert!(fds.is_empty());
+    }
+
+    #[test]
+    fn test_passed_fd_refers_to_same_pipe() {
+        let (a, b) = UnixStream::pair().unwrap();
+        let (reader, mut writer) = UnixStream::pair().unwrap();
+
+        send_with_fds(&a, b"state", &[reader.as_raw_fd()]).unwrap();
+        drop(reader);
+
+        let (payload, mut fds) = recv_with_fds(&b).unwrap();
+        assert_eq!(payload, b"state");
+        assert_eq!(fds.len(), 1);
+
+        writer.write_all(b"ping").unwrap();
+        let mut received = UnixStream::from(fds.remove(0));
+        let mut buf = [0u8; 4];
+        received.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[test]
+    fn test_closed_peer_is_an_error() {
+        let (a, b) = UnixStream::pair().unwrap();
+        drop(a);
+        assert!(recv_with_fds(&b).is_err());
+    }
+}
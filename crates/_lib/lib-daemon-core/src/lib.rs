@@ -8,6 +8,7 @@
 //! - System service integration (systemd, launchd)
 //! - Background process spawning
 //! - Autostart configuration
+//! - Descriptor passing for handing a live daemon over to a new binary
 //!
 //! ## Quick Start with DaemonBuilder
 //!
@@ -111,6 +112,8 @@ pub mod socket;
 
 // New cross-platform modules
 pub mod builder;
+#[cfg(unix)]
+pub mod fd_passing;
 pub mod ipc_transport;
 pub mod platform;
 pub mod service;
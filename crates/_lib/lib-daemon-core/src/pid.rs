@@ -126,6 +126,14 @@ impl PidFile {
         }
     }
 
+    /// Give up ownership without removing the file
+    ///
+    /// Used when another process has taken over the PID file, e.g. after a
+    /// daemon handed its socket to a newer binary.
+    pub fn release(&mut self) {
+        self.owns_file = false;
+    }
+
     /// Check if daemon is running and return error if it is
     ///
     /// This is a convenience method for daemon startup validation.
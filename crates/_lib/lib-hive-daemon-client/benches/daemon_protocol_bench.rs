@@ -0,0 +1,118 @@
+//! Criterion benchmarks for the daemon wire protocol
+//!
+//! Run with: cargo bench -p lib-hive-daemon-client
+//!
+//! Regression thresholds that CI enforces live in `tests/throughput_budget.rs`.
+
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lib_hive_daemon_client::chrono::Utc;
+use lib_hive_daemon_client::frame::encode;
+use lib_hive_daemon_client::uuid::Uuid;
+use lib_hive_daemon_client::{DaemonResponse, FrameReader, FrameWriter, LogLine, WireFormat};
+use tokio::net::UnixStream;
+
+const FORMATS: [WireFormat; 2] = [WireFormat::Json, WireFormat::Binary];
+
+fn log_stream(message_len: usize) -> DaemonResponse {
+    DaemonResponse::LogStream {
+        stream_id: Uuid::new_v4(),
+        line: LogLine {
+            timestamp: Utc::now(),
+            level: "info".to_string(),
+            service_fqn: "default:api".to_string(),
+            message: "GET /api/tasks 200 "
+                .chars()
+                .cycle()
+                .take(message_len)
+                .collect(),
+            fields: Some(HashMap::from([
+                ("request_id".to_string(), serde_json::json!("7f3c2a")),
+                ("duration_ms".to_string(), serde_json::json!(12)),
+            ])),
+        },
+    }
+}
+
+fn benchmark_log_line_codec(c: &mut Criterion) {
+    let mut group = c.benchmark_group("log_stream");
+
+    for len in [80, 1024, 16 * 1024] {
+        let msg = log_stream(len);
+        group.throughput(Throughput::Bytes(len as u64));
+
+        for format in FORMATS {
+            let mut buf = Vec::new();
+            group.bench_with_input(
+                BenchmarkId::new(format!("encode_{:?}", format), len),
+                &msg,
+                |b, msg| b.iter(|| encode(format, black_box(msg), &mut buf).unwrap()),
+            );
+
+            encode(format, &msg, &mut buf).unwrap();
+            let body = match format {
+                WireFormat::Json => buf.clone(),
+                WireFormat::Binary => buf[4..].to_vec(),
+            };
+            group.bench_with_input(
+                BenchmarkId::new(format!("decode_{:?}", format), len),
+                &body,
+                |b, body| {
+                    b.iter(|| -> DaemonResponse {
+                        match format {
+                            WireFormat::Json => serde_json::from_slice(black_box(body)).unwrap(),
+                            WireFormat::Binary => rmp_serde::from_slice(black_box(body)).unwrap(),
+                        }
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+/// Stream a batch of log lines through a socket pair, as `adi hive logs -f` does
+fn benchmark_loopback(c: &mut Criterion) {
+    const BATCH: usize = 256;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("loopback");
+    group.throughput(Throughput::Elements(BATCH as u64));
+
+    for format in FORMATS {
+        let msg = log_stream(200);
+        group.bench_function(format!("{:?}", format), |b| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    let (a, z) = UnixStream::pair().unwrap();
+                    let mut writer = FrameWriter::new(a);
+                    let mut reader = FrameReader::new(z);
+                    writer.set_format(format);
+                    reader.set_format(format);
+
+                    let send = async {
+                        for _ in 0..BATCH {
+                            writer.send(&msg).await.unwrap();
+                        }
+                    };
+                    let recv = async {
+                        for _ in 0..BATCH {
+                            let _: DaemonResponse = reader.read().await.unwrap().unwrap();
+                        }
+                    };
+                    tokio::join!(send, recv);
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_log_line_codec, benchmark_loopback);
+criterion_main!(benches);
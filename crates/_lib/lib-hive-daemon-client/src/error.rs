@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub type Result<T> = std::result::Result<T, DaemonClientError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DaemonClientError {
+    /// Nothing is listening on the daemon socket
+    #[error("Daemon is not running (no socket at {})", socket.display())]
+    NotRunning { socket: PathBuf },
+
+    /// Connecting failed, or the daemon dropped the connection mid-request
+    #[error("Connection to daemon lost: {0}")]
+    ConnectionLost(#[source] std::io::Error),
+
+    #[error("Request timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// Malformed frame, or a response the request does not expect
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    /// Returned by the daemon as `DaemonResponse::Error`
+    #[error("Daemon error [{code}]: {message}")]
+    DaemonError { code: String, message: String },
+
+    /// The caller's cancellation token fired before the request finished
+    #[error("Request cancelled")]
+    Cancelled,
+
+    #[error("Could not determine home directory")]
+    NoHomeDir,
+}
+
+impl DaemonClientError {
+    /// Error code sent by the daemon, e.g. `NOT_FOUND`
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Self::DaemonError { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Whether the daemon answered that the requested item does not exist
+    pub fn is_not_found(&self) -> bool {
+        self.code() == Some("NOT_FOUND")
+    }
+
+    /// Whether an open connection dropped, e.g. because the daemon restarted
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(self, Self::ConnectionLost(_))
+    }
+
+    /// Whether no daemon could be reached, which a retry may fix
+    pub(crate) fn is_unreachable(&self) -> bool {
+        matches!(self, Self::NotRunning { .. } | Self::ConnectionLost(_))
+    }
+
+    pub(crate) fn closed() -> Self {
+        Self::ConnectionLost(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "daemon closed connection",
+        ))
+    }
+
+    pub(crate) fn unexpected() -> Self {
+        Self::Protocol("unexpected response".to_string())
+    }
+
+    /// Classify a failed connect: a missing socket or a refused connection
+    /// means no daemon is listening.
+    pub(crate) fn connect(socket: &std::path::Path, err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused => {
+                Self::NotRunning {
+                    socket: socket.to_path_buf(),
+                }
+            }
+            _ => Self::ConnectionLost(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for DaemonClientError {
+    fn from(err: std::io::Error) -> Self {
+        // Framing reports oversized frames as InvalidData
+        if err.kind() == std::io::ErrorKind::InvalidData {
+            return Self::Protocol(err.to_string());
+        }
+        Self::ConnectionLost(err)
+    }
+}
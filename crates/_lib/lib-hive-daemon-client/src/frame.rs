@@ -0,0 +1,249 @@
+//! Wire framing for the daemon socket.
+//!
+//! Connections start in newline-delimited JSON. `DaemonClient` sends
+//! `DaemonRequest::Hello` on connect to switch to length-prefixed binary
+//! frames: a 4-byte big-endian payload length followed by a MessagePack body.
+//! MessagePack rather than bincode because the protocol enums are internally
+//! tagged (`#[serde(tag = "type")]`), which needs a self-describing format.
+
+use crate::error::{DaemonClientError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Upper bound on a single binary frame; larger lengths indicate a desync.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+const LEN_PREFIX: usize = 4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WireFormat {
+    /// Newline-delimited JSON (default, human-readable)
+    #[default]
+    Json,
+    /// Length-prefixed MessagePack frames
+    Binary,
+}
+
+/// Serialize `value` as one frame into `out` (cleared first).
+pub fn encode<T: Serialize>(format: WireFormat, value: &T, out: &mut Vec<u8>) -> Result<()> {
+    out.clear();
+    match format {
+        WireFormat::Json => {
+            serde_json::to_writer(&mut *out, value).map_err(|e| {
+                DaemonClientError::Protocol(format!("Failed to encode JSON message: {}", e))
+            })?;
+            out.push(b'\n');
+        }
+        WireFormat::Binary => {
+            out.extend_from_slice(&[0; LEN_PREFIX]);
+            rmp_serde::encode::write_named(out, value).map_err(|e| {
+                DaemonClientError::Protocol(format!("Failed to encode binary frame: {}", e))
+            })?;
+            let len = out.len() - LEN_PREFIX;
+            if len > MAX_FRAME_LEN {
+                return Err(DaemonClientError::Protocol(format!(
+                    "Frame of {} bytes exceeds {} byte limit",
+                    len, MAX_FRAME_LEN
+                )));
+            }
+            out[..LEN_PREFIX].copy_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+    Ok(())
+}
+
+/// Reads frames into a buffer that is reused across calls, so the framing
+/// layer does not allocate once the buffer has grown to the working size.
+pub struct FrameReader<R> {
+    inner: BufReader<R>,
+    format: WireFormat,
+    buf: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> FrameReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner: BufReader::new(inner),
+            format: WireFormat::Json,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn format(&self) -> WireFormat {
+        self.format
+    }
+
+    pub fn set_format(&mut self, format: WireFormat) {
+        self.format = format;
+    }
+
+    /// Read the next raw frame. Returns `false` on a clean end of stream.
+    pub async fn read_frame(&mut self) -> std::io::Result<bool> {
+        self.buf.clear();
+        match self.format {
+            WireFormat::Json => Ok(self.inner.read_until(b'\n', &mut self.buf).await? > 0),
+            WireFormat::Binary => {
+                let mut len = [0u8; LEN_PREFIX];
+                match self.inner.read_exact(&mut len).await {
+                    Ok(_) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+                    Err(e) => return Err(e),
+                }
+
+                let len = u32::from_be_bytes(len) as usize;
+                if len > MAX_FRAME_LEN {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "Frame of {} bytes exceeds {} byte limit",
+                            len, MAX_FRAME_LEN
+                        ),
+                    ));
+                }
+
+                self.buf.resize(len, 0);
+                self.inner.read_exact(&mut self.buf).await?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Wait until bytes are available without consuming them. Returns `false`
+    /// at end of stream. Cancel safe, unlike [`read_frame`](Self::read_frame).
+    pub async fn wait_readable(&mut self) -> std::io::Result<bool> {
+        Ok(!self.inner.fill_buf().await?.is_empty())
+    }
+
+    /// Decode the frame last read by [`read_frame`](Self::read_frame).
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        match self.format {
+            WireFormat::Json => serde_json::from_slice(&self.buf).map_err(|e| {
+                DaemonClientError::Protocol(format!(
+                    "Invalid JSON ({}): {}",
+                    e,
+                    String::from_utf8_lossy(&self.buf).trim()
+                ))
+            }),
+            WireFormat::Binary => rmp_serde::from_slice(&self.buf)
+                .map_err(|e| DaemonClientError::Protocol(format!("Invalid binary frame: {}", e))),
+        }
+    }
+
+    /// Read and decode the next message, or `None` on end of stream.
+    pub async fn read<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        if !self.read_frame().await? {
+            return Ok(None);
+        }
+        self.decode().map(Some)
+    }
+}
+
+/// Encodes messages into a reused buffer and writes them in one call.
+pub struct FrameWriter<W> {
+    inner: W,
+    format: WireFormat,
+    buf: Vec<u8>,
+}
+
+impl<W: AsyncWrite + Unpin> FrameWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            format: WireFormat::Json,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn format(&self) -> WireFormat {
+        self.format
+    }
+
+    pub fn set_format(&mut self, format: WireFormat) {
+        self.format = format;
+    }
+
+    pub async fn send<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        encode(self.format, value, &mut self.buf)?;
+        self.inner.write_all(&self.buf).await?;
+        self.inner.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DaemonRequest, DaemonResponse};
+
+    async fn roundtrip(format: WireFormat) {
+        let (client, server) = tokio::io::duplex(1024);
+        let mut writer = FrameWriter::new(client);
+        let mut reader = FrameReader::new(server);
+        writer.set_format(format);
+        reader.set_format(format);
+
+        let stream_id = uuid::Uuid::new_v4();
+        writer
+            .send(&DaemonRequest::StopLogStream { stream_id })
+            .await
+            .unwrap();
+        writer
+            .send(&DaemonRequest::Snapshot {
+                include_logs: true,
+                passphrase: None,
+            })
+            .await
+            .unwrap();
+        drop(writer);
+
+        match reader.read::<DaemonRequest>().await.unwrap() {
+            Some(DaemonRequest::StopLogStream { stream_id: id }) => assert_eq!(id, stream_id),
+            other => panic!("unexpected {:?}", other),
+        }
+        match reader.read::<DaemonRequest>().await.unwrap() {
+            Some(DaemonRequest::Snapshot {
+                include_logs,
+                passphrase,
+            }) => {
+                assert!(include_logs);
+                assert!(passphrase.is_none());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(reader.read::<DaemonRequest>().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_json_roundtrip() {
+        roundtrip(WireFormat::Json).await;
+    }
+
+    #[tokio::test]
+    async fn test_binary_roundtrip() {
+        roundtrip(WireFormat::Binary).await;
+    }
+
+    #[tokio::test]
+    async fn test_binary_rejects_oversized_frame() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = FrameReader::new(server);
+        reader.set_format(WireFormat::Binary);
+
+        client
+            .write_all(&((MAX_FRAME_LEN as u32) + 1).to_be_bytes())
+            .await
+            .unwrap();
+        assert!(reader.read_frame().await.is_err());
+    }
+
+    #[test]
+    fn test_hello_is_json_compatible() {
+        let json = serde_json::to_string(&DaemonResponse::Hello {
+            format: WireFormat::Binary,
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"type":"hello","format":"binary"}"#);
+    }
+}
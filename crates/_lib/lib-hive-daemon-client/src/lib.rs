@@ -4,19 +4,28 @@
 //! with the Hive daemon via Unix socket. Used by hive-core (server side),
 //! hive-plugin (CLI side), and core plugins (signaling_control).
 
-use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::UnixStream;
 use tokio::sync::Mutex;
 use tracing::debug;
 use uuid::Uuid;
 
+pub mod error;
+pub mod frame;
+#[cfg(feature = "mock")]
+pub mod mock;
+
+pub use error::{DaemonClientError, Result};
+pub use frame::{FrameReader, FrameWriter, WireFormat};
+pub use lib_retry::RetryPolicy;
+pub use tokio_util::sync::CancellationToken;
+
 // Re-export types for convenience
 pub use chrono;
 pub use uuid;
@@ -58,11 +67,28 @@ pub enum DaemonRequest {
     /// Disable a source
     DisableSource { name: String },
 
+    /// Turn reloading a YAML source when its hive.yaml changes on or off,
+    /// overriding the file's `auto_reload` until the daemon restarts
+    SetSourceAutoReload { name: String, enabled: bool },
+
+    /// Environment profile of a source and what it resolves to
+    GetSourceEnv { name: String },
+
     /// Start all services in a source
-    StartSource { name: String },
+    StartSource {
+        name: String,
+        /// Send `OperationProgress` updates before the final response
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        progress: bool,
+    },
 
     /// Stop all services in a source
-    StopSource { name: String },
+    StopSource {
+        name: String,
+        /// Send `OperationProgress` updates before the final response
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        progress: bool,
+    },
 
     /// Start a specific service (FQN: source:service)
     StartService { fqn: String },
@@ -71,7 +97,14 @@ pub enum DaemonRequest {
     StopService { fqn: String },
 
     /// Restart a specific service
-    RestartService { fqn: String },
+    RestartService {
+        fqn: String,
+        #[serde(default, skip_serializing_if = "RestartStrategy::is_immediate")]
+        strategy: RestartStrategy,
+        /// Send `OperationProgress` updates before the final response
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        progress: bool,
+    },
 
     /// Get service status
     GetServiceStatus { fqn: String },
@@ -84,12 +117,18 @@ pub enum DaemonRequest {
         source_id: String,
         name: String,
         config: serde_json::Value,
+        /// Replaces any `healthcheck` in `config`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        health: Option<HealthProbe>,
     },
 
     /// Update a service configuration
     UpdateService {
         fqn: String,
         patch: serde_json::Value,
+        /// Replaces the service's `healthcheck`; applies from its next start
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        health: Option<HealthProbe>,
     },
 
     /// Delete a service
@@ -98,6 +137,33 @@ pub enum DaemonRequest {
     /// List exposed services
     ListExposed,
 
+    /// Expose/consume edges from `${expose:...}` references across sources
+    ExposeGraph,
+
+    /// Resource usage of a service, or of all services if `fqn` is None
+    GetMetrics { fqn: Option<String> },
+
+    /// Context of a service's most recent crash
+    GetCrashReport { fqn: String },
+
+    /// Reserve a port for `owner` (usually a service FQN). Fails with
+    /// `PORT_CONFLICT` when another owner reserved it or has it in its config;
+    /// reserving a port again for the same owner is a no-op.
+    ReservePort {
+        port: u16,
+        owner: String,
+        /// Port name in the owner's config (e.g. "http")
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+
+    /// Drop a reservation made with `ReservePort`. Fails with
+    /// `PORT_CONFLICT` when `owner` is not the one that reserved the port.
+    ReleasePort { port: u16, owner: String },
+
+    /// Reserved ports and ports from service configs, sorted by port
+    ListPortAllocations,
+
     /// Get logs for a service or all services
     GetLogs {
         /// Service FQN (optional, if None returns all logs)
@@ -114,6 +180,10 @@ pub enum DaemonRequest {
     StreamLogs {
         /// Service FQN pattern (supports wildcards like "source:*")
         fqn: Option<String>,
+        /// Explicit service FQNs, streamed together with `fqn`; lines from
+        /// several services are interleaved by timestamp
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        fqns: Vec<String>,
         /// Minimum log level
         level: Option<String>,
     },
@@ -130,10 +200,90 @@ pub enum DaemonRequest {
     /// Stop a service status stream
     StopServiceStream { stream_id: Uuid },
 
+    /// Stream daemon status periodically (returns stream_id, then sends
+    /// StatusUpdate messages)
+    StreamStatus {
+        /// Milliseconds between updates (daemon default when omitted)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        interval_ms: Option<u64>,
+    },
+
+    /// Stop a daemon status stream
+    StopStatusStream { stream_id: Uuid },
+
+    /// Subscribe to daemon state changes (returns stream_id, then sends
+    /// Event messages as they happen)
+    SubscribeEvents {
+        /// Source name filter (optional)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+
+    /// Stop an event stream
+    StopEventStream { stream_id: Uuid },
+
+    /// Export sources, dynamic services, secrets and port reservations as a
+    /// versioned JSON archive. Secrets are included only when a passphrase is given.
+    Snapshot {
+        include_logs: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        passphrase: Option<Passphrase>,
+    },
+
+    /// Rebuild state from a `Snapshot` archive (e.g. on a replacement machine)
+    Restore {
+        archive: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        passphrase: Option<Passphrase>,
+    },
+
+    /// Enter or leave maintenance mode. While on, new spawn/start requests are
+    /// refused; `drain` also moves running cocoons to other hives through the
+    /// signaling scheduler.
+    SetMaintenance {
+        on: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        drain: bool,
+    },
+
+    /// Run several requests in one round trip. They are processed one after
+    /// another and answered with one `Batch` response in request order; a
+    /// failing request does not affect the others. Streaming, handshake, maintenance and nested batch
+    /// requests are refused with a per-request error.
+    Batch { requests: Vec<DaemonRequest> },
+
+    /// Negotiate the wire format for this connection. Sent as JSON; after the
+    /// JSON `Hello` reply both sides switch to the chosen format.
+    Hello { formats: Vec<WireFormat> },
+
     /// Ping (for connection check)
     Ping,
 }
 
+impl DaemonRequest {
+    /// Read-only requests, which the client resends after reconnecting when
+    /// the daemon dropped the connection mid-request.
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            Self::Status
+                | Self::Ping
+                | Self::ListSources
+                | Self::GetSourceEnv { .. }
+                | Self::GetServiceStatus { .. }
+                | Self::ListServices { .. }
+                | Self::ListExposed
+                | Self::ExposeGraph
+                | Self::GetMetrics { .. }
+                | Self::GetCrashReport { .. }
+                | Self::ListPortAllocations
+                | Self::GetLogs { .. }
+        )
+    }
+}
+
 /// Daemon response types (canonical protocol definition).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -153,6 +303,9 @@ pub enum DaemonResponse {
     /// List of sources
     Sources { sources: Vec<SourceInfo> },
 
+    /// Resolved environment profile of a source
+    SourceEnv { env: SourceEnv },
+
     /// List of services
     Services { services: Vec<ServiceStatus> },
 
@@ -162,6 +315,22 @@ pub enum DaemonResponse {
     /// List of exposed services
     Exposed { exposed: Vec<ExposedServiceInfo> },
 
+    /// Expose/consume graph
+    ExposeGraph { edges: Vec<ExposeEdgeInfo> },
+
+    /// Per-service resource usage
+    Metrics { metrics: Vec<ServiceMetrics> },
+
+    /// Most recent crash of a service; None if it has not crashed since the
+    /// daemon started
+    CrashReport { report: Option<CrashReport> },
+
+    /// Port reserved (or already reserved by the same owner)
+    PortReserved { allocation: PortAllocation },
+
+    /// Port allocations across all sources
+    PortAllocations { allocations: Vec<PortAllocation> },
+
     /// Log lines
     Logs { logs: Vec<LogLine> },
 
@@ -180,6 +349,33 @@ pub enum DaemonResponse {
         services: Vec<ServiceStatus>,
     },
 
+    /// Daemon status update (sent during status streaming). `changed` holds
+    /// services whose state or health differ from the previous update; the
+    /// first update lists every service.
+    StatusUpdate {
+        stream_id: Uuid,
+        status: DaemonStatus,
+        changed: Vec<ServiceStatus>,
+    },
+
+    /// Daemon state change (sent during event streaming)
+    Event { stream_id: Uuid, event: HiveEvent },
+
+    /// Snapshot archive (JSON)
+    Snapshot { archive: String },
+
+    /// Result of a restore
+    Restored(RestoreReport),
+
+    /// Progress of a long-running operation, sent before its final Ok/Error
+    OperationProgress(OperationProgress),
+
+    /// Responses to a `Batch`, one per request and in the same order
+    Batch { responses: Vec<DaemonResponse> },
+
+    /// Wire format chosen for the rest of the connection
+    Hello { format: WireFormat },
+
     /// Pong response
     Pong,
 }
@@ -199,6 +395,37 @@ pub struct DaemonStatus {
     pub total_services: usize,
     pub proxy_addresses: Vec<String>,
     pub uptime_secs: u64,
+    /// Set while the daemon is in maintenance mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<MaintenanceStatus>,
+    /// Leader election state of `singleton: true` services
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub singletons: Vec<SingletonStatus>,
+}
+
+/// Maintenance mode state, see `DaemonRequest::SetMaintenance`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub since: DateTime<Utc>,
+    /// Cocoons handed to the scheduler and not yet running elsewhere
+    #[serde(default)]
+    pub draining: usize,
+}
+
+/// Singleton service as seen by this hive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SingletonStatus {
+    /// `source:service`
+    pub service: String,
+    /// Hive elected to run it; `None` until signaling has elected one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leader_hive_id: Option<String>,
+    pub fencing_token: u64,
+    /// This hive is the leader
+    #[serde(default)]
+    pub leader: bool,
 }
 
 /// Source information
@@ -210,6 +437,9 @@ pub struct SourceInfo {
     pub enabled: bool,
     pub service_count: usize,
     pub status: SourceStatus,
+    /// Reloaded when its hive.yaml changes on disk
+    #[serde(default)]
+    pub auto_reload: bool,
 }
 
 /// Source type
@@ -230,6 +460,21 @@ pub enum SourceStatus {
     Error(String),
 }
 
+/// Environment profile of a source, applied to every service it spawns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceEnv {
+    pub source: String,
+    /// Dotenv file of the profile, relative to the source root
+    pub env_file: Option<String>,
+    /// Directories prepended to PATH, as configured
+    pub path_prepend: Vec<String>,
+    /// Whether `direnv export` output is applied
+    pub direnv: bool,
+    /// Variables the profile sets (including the final PATH), before
+    /// source-wide and per-service `environment` sections
+    pub vars: BTreeMap<String, String>,
+}
+
 /// Service status information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceStatus {
@@ -253,8 +498,89 @@ pub struct ServiceStatus {
     pub started_at: Option<DateTime<Utc>>,
     /// Assigned ports
     pub ports: HashMap<String, u16>,
+    /// Allocations by other owners of ports this service is configured with
+    /// or has reserved; non-empty means the service will not get them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub port_conflicts: Vec<PortAllocation>,
     /// Restart count
     pub restart_count: u32,
+    /// Most recent health probe, for services with a healthcheck
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_probe: Option<ProbeResult>,
+}
+
+/// Health probe of a service, as set with `CreateService`/`UpdateService`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthProbe {
+    #[serde(flatten)]
+    pub check: ProbeCheck,
+    /// Milliseconds between probes (daemon default when omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_ms: Option<u64>,
+    /// Milliseconds before a probe counts as failed (daemon default when omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Consecutive failed probes before the service is unhealthy (default 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_threshold: Option<u32>,
+}
+
+/// What a `HealthProbe` checks. Ports are numbers or `{{runtime.port.<name>}}`
+/// templates, as in a `healthcheck` config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProbeCheck {
+    /// Request `path`; any 2xx passes unless `status` is set
+    Http {
+        port: String,
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<u16>,
+    },
+    /// Open a TCP connection
+    Tcp { port: String },
+    /// Run a shell command (inside the container for docker services);
+    /// exit code 0 passes
+    Exec { command: String },
+}
+
+/// Outcome of a service's most recent health probe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeResult {
+    /// Probe type ("http", "tcp" or "exec")
+    pub check: String,
+    pub passed: bool,
+    pub checked_at: DateTime<Utc>,
+    pub latency_ms: u64,
+    /// Response status of an HTTP probe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    /// Why the probe failed (e.g. "connection refused", "timed out after 5000ms")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+    /// Failed probes in a row, up to and including this one
+    #[serde(default)]
+    pub consecutive_failures: u32,
+}
+
+/// A port claimed by a service config or a `ReservePort` request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortAllocation {
+    pub port: u16,
+    /// Holder, usually a service FQN (source:service)
+    pub owner: String,
+    /// Port name in the owner's config (e.g. "http")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Set for `ReservePort` reservations; None for ports from a service config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reserved_at: Option<DateTime<Utc>>,
+}
+
+impl PortAllocation {
+    pub fn is_reservation(&self) -> bool {
+        self.reserved_at.is_some()
+    }
 }
 
 /// Exposed service information
@@ -272,6 +598,168 @@ pub struct ExposedServiceInfo {
     pub var_names: Vec<String>,
     /// Exposed port names
     pub port_names: Vec<String>,
+    /// Exposed ports by name
+    #[serde(default)]
+    pub ports: HashMap<String, u16>,
+}
+
+/// A consumer reading variables exposed by a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExposeEdgeInfo {
+    /// Consumer FQN (source:service)
+    pub consumer: String,
+    /// Provider FQN (source:service)
+    pub provider: String,
+    /// Referenced variable names
+    pub vars: Vec<String>,
+    /// Whether the provider is registered and healthy
+    pub resolved: bool,
+}
+
+/// Resource usage of a service, as returned by `DaemonRequest::GetMetrics`
+///
+/// Usage fields are None when the daemon has no sample yet (e.g. the service
+/// is stopped, or a docker service has no `stats_interval`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceMetrics {
+    /// Fully qualified name (source:service)
+    pub fqn: String,
+    /// Current state (string representation for forward compatibility)
+    pub state: String,
+    /// Process ID (if running)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// CPU usage since the previous sample, in percent of one core
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_percent: Option<f32>,
+    /// Resident memory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_rss_bytes: Option<u64>,
+    /// Open file descriptors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_fds: Option<u32>,
+    /// Restart count
+    pub restart_count: u32,
+    /// Seconds since the service was started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uptime_secs: Option<u64>,
+    /// When the usage was sampled
+    pub sampled_at: DateTime<Utc>,
+}
+
+/// What a service left behind when it crashed, as returned by
+/// `DaemonRequest::GetCrashReport`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// Fully qualified name (source:service)
+    pub fqn: String,
+    pub crashed_at: DateTime<Utc>,
+    /// Exit code, when the service exited on its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Signal that ended the process (e.g. 9 for SIGKILL)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+    /// Why the daemon considers the service crashed (e.g. a failed build)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Last stdout/stderr lines before the crash, oldest first
+    pub output: Vec<CrashOutputLine>,
+    /// Lines dropped from the start of `output` to stay within the buffer
+    #[serde(default)]
+    pub truncated: bool,
+    /// Hash of the environment the service was started with, to tell
+    /// whether it changed between runs without exposing values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_fingerprint: Option<String>,
+    /// Environment variable names (keys only, not values for security)
+    #[serde(default)]
+    pub env_vars: Vec<String>,
+    /// Restart count at the time of the crash
+    #[serde(default)]
+    pub restart_count: u32,
+}
+
+/// One line of captured service output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashOutputLine {
+    pub timestamp: DateTime<Utc>,
+    /// "stdout" or "stderr"
+    pub stream: String,
+    pub line: String,
+}
+
+/// Passphrase of a `Snapshot`/`Restore` request. Serialized as a plain
+/// string, but never printed by `Debug`, so request logging can't leak it.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Passphrase(String);
+
+impl Passphrase {
+    pub fn new(passphrase: impl Into<String>) -> Self {
+        Self(passphrase.into())
+    }
+}
+
+impl std::ops::Deref for Passphrase {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Passphrase(<redacted>)")
+    }
+}
+
+/// Outcome of `DaemonRequest::Restore`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestoreReport {
+    /// Sources registered (or reloaded) from the archive
+    pub sources: Vec<String>,
+    /// Dynamic services recreated (FQNs)
+    pub services: Vec<String>,
+    /// Whether encrypted secrets were decrypted and written back
+    pub secrets_restored: bool,
+    /// Non-fatal problems (existing files kept, ports in use, ...)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// How `RestartService` replaces a running service
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RestartStrategy {
+    /// Stop the service, then start it again
+    #[default]
+    Immediate,
+    /// Start a new instance on the alternate ports of the service's
+    /// blue-green rollout, wait for its healthcheck, switch the proxy route,
+    /// let the old instance finish in-flight requests for `drain_seconds`,
+    /// then stop it. The old instance keeps serving if the new one fails.
+    BlueGreen { drain_seconds: u32 },
+}
+
+impl RestartStrategy {
+    pub fn is_immediate(&self) -> bool {
+        matches!(self, RestartStrategy::Immediate)
+    }
+}
+
+/// One step of a long-running operation (e.g. starting a large source)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationProgress {
+    /// Identifies the operation; constant across its updates
+    pub op_id: Uuid,
+    /// Steps completed so far (0 before the first one finishes)
+    pub step: u32,
+    /// Total number of steps
+    pub total: u32,
+    /// What is happening now (e.g. "api: building")
+    pub message: String,
 }
 
 /// Log line
@@ -286,22 +774,85 @@ pub struct LogLine {
     pub fields: Option<HashMap<String, serde_json::Value>>,
 }
 
+/// Daemon state change pushed to event subscribers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HiveEvent {
+    ServiceStarted {
+        fqn: String,
+    },
+    ServiceStopped {
+        fqn: String,
+    },
+    /// The service exited unexpectedly (it may be restarted by its policy)
+    ServiceCrashed {
+        fqn: String,
+    },
+    /// A source's hive.yaml was re-read from disk
+    SourceReloaded {
+        source: String,
+        /// Services the reload added, removed or changed
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        added: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        removed: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        changed: Vec<String>,
+        /// Triggered by a file change rather than `ReloadSource`
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        auto: bool,
+    },
+    /// All health checks of a service started passing, or one started failing
+    HealthChanged {
+        fqn: String,
+        healthy: bool,
+    },
+}
+
 // ============================================================================
 // CLIENT IMPLEMENTATION
 // ============================================================================
 
+/// Connection state reported to [`DaemonClient::on_connection_state_change`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    /// The connection was lost or closed with `disconnect`
+    Disconnected,
+    /// Waiting `delay` before reconnect attempt `attempt + 1`
+    Reconnecting {
+        attempt: u32,
+        delay: Duration,
+    },
+}
+
+/// Reconnect policy of a new [`DaemonClient`]: 8 attempts from 100ms, capped
+/// at 3s, about 10 seconds in total, enough for a daemon restart. The first
+/// connection is tried once, so a daemon that is not running is reported
+/// right away.
+pub const DEFAULT_RECONNECT_POLICY: RetryPolicy =
+    RetryPolicy::exponential(Duration::from_millis(100), Duration::from_secs(3)).max_attempts(8);
+
+type StateCallback = Arc<dyn Fn(ConnectionState) + Send + Sync>;
+
 /// Daemon client for communicating with the Hive daemon.
 ///
-/// Uses a persistent connection model (Arc<Mutex<ClientInner>>).
+/// Uses a persistent connection model (Arc<Mutex<ClientInner>>). A lost
+/// connection is reopened on the next request following its
+/// [`RetryPolicy`]; idempotent requests that were in flight are resent.
 #[derive(Clone)]
 pub struct DaemonClient {
     socket_path: PathBuf,
+    reconnect: RetryPolicy,
+    on_state_change: Option<StateCallback>,
     inner: Arc<Mutex<ClientInner>>,
 }
 
 struct ClientInner {
-    reader: Option<BufReader<tokio::net::unix::OwnedReadHalf>>,
-    writer: Option<tokio::net::unix::OwnedWriteHalf>,
+    reader: Option<FrameReader<OwnedReadHalf>>,
+    writer: Option<FrameWriter<OwnedWriteHalf>>,
+    /// Whether a connection was ever open; later connects are reconnects
+    was_connected: bool,
 }
 
 impl DaemonClient {
@@ -309,9 +860,12 @@ impl DaemonClient {
     pub fn new(socket_path: impl Into<PathBuf>) -> Self {
         Self {
             socket_path: socket_path.into(),
+            reconnect: DEFAULT_RECONNECT_POLICY,
+            on_state_change: None,
             inner: Arc::new(Mutex::new(ClientInner {
                 reader: None,
                 writer: None,
+                was_connected: false,
             })),
         }
     }
@@ -319,40 +873,115 @@ impl DaemonClient {
     /// Create a client with default socket path (~/.adi/hive/hive.sock)
     pub fn new_default() -> Result<Self> {
         let socket_path = dirs::home_dir()
-            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .ok_or(DaemonClientError::NoHomeDir)?
             .join(".adi/hive/hive.sock");
 
         Ok(Self::new(socket_path))
     }
 
+    /// Replace [`DEFAULT_RECONNECT_POLICY`]
+    pub fn with_reconnect_policy(mut self, policy: RetryPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Call `callback` whenever the connection opens, drops or is being retried.
+    /// The callback runs inline and must not block.
+    pub fn on_connection_state_change(
+        mut self,
+        callback: impl Fn(ConnectionState) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_state_change = Some(Arc::new(callback));
+        self
+    }
+
+    fn notify(&self, state: ConnectionState) {
+        if let Some(callback) = &self.on_state_change {
+            callback(state);
+        }
+    }
+
     /// Get the socket path
     pub fn socket_path(&self) -> &Path {
         &self.socket_path
     }
 
-    /// Connect to the daemon (lazy connection)
-    async fn ensure_connected(&self) -> Result<()> {
-        let mut inner = self.inner.lock().await;
+    /// Open a new connection and negotiate binary frames. Daemons that do not
+    /// understand `Hello` answer it with an error and stay on JSON.
+    async fn connect(&self) -> Result<(FrameReader<OwnedReadHalf>, FrameWriter<OwnedWriteHalf>)> {
+        let stream = UnixStream::connect(&self.socket_path)
+            .await
+            .map_err(|e| DaemonClientError::connect(&self.socket_path, e))?;
+
+        let (r, w) = stream.into_split();
+        let mut reader = FrameReader::new(r);
+        let mut writer = FrameWriter::new(w);
+
+        writer
+            .send(&DaemonRequest::Hello {
+                formats: vec![WireFormat::Binary, WireFormat::Json],
+            })
+            .await?;
+
+        match reader.read::<DaemonResponse>().await? {
+            Some(DaemonResponse::Hello { format }) => {
+                reader.set_format(format);
+                writer.set_format(format);
+            }
+            Some(_) => debug!("Daemon does not support format negotiation, using JSON"),
+            None => return Err(DaemonClientError::closed()),
+        }
 
-        if inner.writer.is_none() {
-            debug!("Connecting to daemon at {:?}", self.socket_path);
+        Ok((reader, writer))
+    }
 
-            let stream = UnixStream::connect(&self.socket_path)
-                .await
-                .with_context(|| {
-                    format!(
-                        "Failed to connect to daemon at {}. Is the daemon running?",
-                        self.socket_path.display()
-                    )
-                })?;
-
-            let (r, w) = stream.into_split();
-            inner.reader = Some(BufReader::new(r));
-            inner.writer = Some(w);
-            debug!("Connected to daemon");
+    /// Connect to the daemon (lazy connection). Reconnects retry with
+    /// backoff while the daemon is unreachable.
+    async fn ensure_connected(&self, policy: &RetryPolicy) -> Result<()> {
+        let mut inner = self.inner.lock().await;
+        if inner.writer.is_some() {
+            return Ok(());
+        }
+
+        let mut backoff = if inner.was_connected {
+            policy.backoff()
+        } else {
+            RetryPolicy::disabled().backoff()
+        };
+        loop {
+            let attempt = backoff.retries() + 1;
+            debug!(
+                "Connecting to daemon at {:?} (attempt {})",
+                self.socket_path, attempt
+            );
+            let err = match self.connect().await {
+                Ok((reader, writer)) => {
+                    debug!("Connected to daemon ({:?})", writer.format());
+                    inner.reader = Some(reader);
+                    inner.writer = Some(writer);
+                    inner.was_connected = true;
+                    self.notify(ConnectionState::Connected);
+                    return Ok(());
+                }
+                Err(e) if e.is_unreachable() => e,
+                Err(e) => return Err(e),
+            };
+            let Some(delay) = backoff.next_delay() else {
+                return Err(err);
+            };
+            self.notify(ConnectionState::Reconnecting { attempt, delay });
+            tokio::time::sleep(delay).await;
         }
+    }
 
-        Ok(())
+    /// Drop the connection after it failed, so the next request reconnects
+    async fn connection_lost(&self) {
+        let mut inner = self.inner.lock().await;
+        if inner.writer.take().is_some() {
+            inner.reader = None;
+            debug!("Lost connection to daemon");
+            self.notify(ConnectionState::Disconnected);
+        }
     }
 
     /// Send a request and wait for response (alias for `request`)
@@ -362,56 +991,70 @@ impl DaemonClient {
 
     /// Send a request without waiting for the response.
     pub async fn send_fire_and_forget(&self, req: DaemonRequest) -> Result<()> {
-        self.ensure_connected().await?;
-
-        let mut inner = self.inner.lock().await;
-        let writer = inner
-            .writer
-            .as_mut()
-            .ok_or_else(|| anyhow!("Not connected to daemon"))?;
-
-        let json = serde_json::to_string(&req).with_context(|| "Failed to serialize request")?;
-        debug!("Sending fire-and-forget request: {}", json);
+        self.ensure_connected(&self.reconnect).await?;
 
-        writer.write_all(json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-        writer.flush().await?;
+        let result = {
+            let mut inner = self.inner.lock().await;
+            let writer = inner
+                .writer
+                .as_mut()
+                .ok_or_else(DaemonClientError::closed)?;
 
-        Ok(())
+            debug!("Sending fire-and-forget request: {:?}", req);
+            writer.send(&req).await
+        };
+        if result.is_err() {
+            self.connection_lost().await;
+        }
+        result
     }
 
     /// Send a request and wait for response
     pub async fn request(&self, req: DaemonRequest) -> Result<DaemonResponse> {
-        self.ensure_connected().await?;
-
-        let mut inner = self.inner.lock().await;
-        let ClientInner { reader, writer } = &mut *inner;
-        let writer = writer.as_mut().ok_or_else(|| anyhow!("Not connected to daemon"))?;
-        let reader = reader.as_mut().ok_or_else(|| anyhow!("Not connected to daemon"))?;
-
-        let json = serde_json::to_string(&req).with_context(|| "Failed to serialize request")?;
+        self.request_with_policy(req, &self.reconnect).await
+    }
 
-        debug!("Sending request: {}", json);
+    /// Send a request, reconnecting per `policy` instead of the client's
+    /// policy, e.g. [`RetryPolicy::disabled`] for a quick liveness check.
+    pub async fn request_with_policy(
+        &self,
+        req: DaemonRequest,
+        policy: &RetryPolicy,
+    ) -> Result<DaemonResponse> {
+        self.ensure_connected(policy).await?;
 
-        writer.write_all(json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
+        match self.exchange(&req).await {
+            Err(e) if e.is_connection_lost() && req.is_idempotent() => {
+                debug!("Connection lost during {:?}, retrying", req);
+                self.ensure_connected(policy).await?;
+                self.exchange(&req).await
+            }
+            result => result,
+        }
+    }
 
-        let mut response_line = String::new();
-        reader
-            .read_line(&mut response_line)
+    /// One request/response round trip on the open connection
+    async fn exchange(&self, req: &DaemonRequest) -> Result<DaemonResponse> {
+        let result = {
+            let mut inner = self.inner.lock().await;
+            let ClientInner { reader, writer, .. } = &mut *inner;
+            let writer = writer.as_mut().ok_or_else(DaemonClientError::closed)?;
+            let reader = reader.as_mut().ok_or_else(DaemonClientError::closed)?;
+
+            debug!("Sending request: {:?}", req);
+            async {
+                writer.send(req).await?;
+                reader.read().await?.ok_or_else(DaemonClientError::closed)
+            }
             .await
-            .with_context(|| "Failed to read response from daemon")?;
+        };
 
-        if response_line.is_empty() {
-            return Err(anyhow!("Daemon closed connection"));
+        match &result {
+            Ok(response) => debug!("Received response: {:?}", response),
+            Err(e) if e.is_connection_lost() => self.connection_lost().await,
+            Err(_) => {}
         }
-
-        let response: DaemonResponse = serde_json::from_str(&response_line)
-            .with_context(|| format!("Failed to parse daemon response: {}", response_line))?;
-
-        debug!("Received response: {:?}", response);
-
-        Ok(response)
+        result
     }
 
     /// Send a request with a custom timeout
@@ -420,9 +1063,14 @@ impl DaemonClient {
         req: DaemonRequest,
         timeout: Duration,
     ) -> Result<DaemonResponse> {
-        tokio::time::timeout(timeout, self.request(req))
-            .await
-            .map_err(|_| anyhow!("Request timed out"))?
+        match tokio::time::timeout(timeout, self.request(req)).await {
+            Ok(result) => result,
+            Err(_) => {
+                // The abandoned request may have left a reply half-read
+                self.connection_lost().await;
+                Err(DaemonClientError::Timeout(timeout))
+            }
+        }
     }
 
     /// Send a request and extract an expected response variant.
@@ -434,9 +1082,9 @@ impl DaemonClient {
     ) -> Result<T> {
         match self.request(req).await? {
             DaemonResponse::Error { code, message } => {
-                Err(anyhow!("Daemon error [{}]: {}", code, message))
+                Err(DaemonClientError::DaemonError { code, message })
             }
-            resp => f(resp).ok_or_else(|| anyhow!("Unexpected response")),
+            resp => f(resp).ok_or_else(DaemonClientError::unexpected),
         }
     }
 
@@ -449,9 +1097,9 @@ impl DaemonClient {
     ) -> Result<T> {
         match self.request_with_timeout(req, timeout).await? {
             DaemonResponse::Error { code, message } => {
-                Err(anyhow!("Daemon error [{}]: {}", code, message))
+                Err(DaemonClientError::DaemonError { code, message })
             }
-            resp => f(resp).ok_or_else(|| anyhow!("Unexpected response")),
+            resp => f(resp).ok_or_else(DaemonClientError::unexpected),
         }
     }
 
@@ -500,36 +1148,54 @@ impl DaemonClient {
         .await
     }
 
-    /// List all services (optionally filtered by source)
-    pub async fn list_services(&self, source: Option<&str>) -> Result<Vec<ServiceStatus>> {
+    /// Get the resolved environment profile of a source
+    pub async fn get_source_env(&self, name: &str) -> Result<SourceEnv> {
         self.extract(
-            DaemonRequest::ListServices {
-                source: source.map(String::from),
+            DaemonRequest::GetSourceEnv {
+                name: name.to_string(),
             },
             |r| match r {
-                DaemonResponse::Services { services } => Some(services),
+                DaemonResponse::SourceEnv { env } => Some(env),
                 _ => None,
             },
         )
         .await
     }
 
-    /// Get service status by FQN (source:service)
-    pub async fn get_service_status(&self, fqn: &str) -> Result<Option<ServiceStatus>> {
+    /// List all services (optionally filtered by source)
+    pub async fn list_services(&self, source: Option<&str>) -> Result<Vec<ServiceStatus>> {
         self.extract(
-            DaemonRequest::GetServiceStatus {
-                fqn: fqn.to_string(),
+            DaemonRequest::ListServices {
+                source: source.map(String::from),
             },
             |r| match r {
-                DaemonResponse::Service { service } => Some(Some(service)),
-                DaemonResponse::Services { services } => Some(services.into_iter().next()),
-                DaemonResponse::Error { code, .. } if code == "NOT_FOUND" => Some(None),
+                DaemonResponse::Services { services } => Some(services),
                 _ => None,
             },
         )
         .await
     }
 
+    /// Get service status by FQN (source:service)
+    pub async fn get_service_status(&self, fqn: &str) -> Result<Option<ServiceStatus>> {
+        let result = self
+            .extract(
+                DaemonRequest::GetServiceStatus {
+                    fqn: fqn.to_string(),
+                },
+                |r| match r {
+                    DaemonResponse::Service { service } => Some(Some(service)),
+                    DaemonResponse::Services { services } => Some(services.into_iter().next()),
+                    _ => None,
+                },
+            )
+            .await;
+        match result {
+            Err(e) if e.is_not_found() => Ok(None),
+            other => other,
+        }
+    }
+
     /// Add a source (idempotent — reloads if path already registered)
     ///
     /// Returns the resolved source name.
@@ -555,32 +1221,156 @@ impl DaemonClient {
         .await
     }
 
+    /// Turn auto-reload of a YAML source on or off
+    pub async fn set_source_auto_reload(&self, name: &str, enabled: bool) -> Result<()> {
+        self.expect_ok(DaemonRequest::SetSourceAutoReload {
+            name: name.to_string(),
+            enabled,
+        })
+        .await
+    }
+
     /// Start a source
     pub async fn start_source(&self, name: &str) -> Result<()> {
         self.expect_ok_with_timeout(
             DaemonRequest::StartSource {
                 name: name.to_string(),
+                progress: false,
             },
             Duration::from_secs(5 * 60),
         )
         .await
     }
 
-    /// Stop a source
-    pub async fn stop_source(&self, name: &str) -> Result<()> {
-        self.expect_ok_with_timeout(
-            DaemonRequest::StopSource {
+    /// Start a source, reporting each step to `on_progress`.
+    ///
+    /// The timeout applies between updates rather than to the whole
+    /// operation, so large sources are not cut off while still making progress.
+    pub async fn start_source_with_progress(
+        &self,
+        name: &str,
+        on_progress: impl FnMut(OperationProgress),
+    ) -> Result<()> {
+        self.request_with_progress(
+            DaemonRequest::StartSource {
                 name: name.to_string(),
+                progress: true,
             },
-            Duration::from_secs(2 * 60),
+            Duration::from_secs(5 * 60),
+            on_progress,
         )
         .await
     }
 
-    /// Shutdown the daemon
-    pub async fn shutdown(&self, graceful: bool) -> Result<()> {
+    /// Start a source like `start_source_with_progress`, giving up when
+    /// `cancel` fires. The daemon keeps starting a source after the client
+    /// leaves, so on cancel a source this call started is stopped again, and
+    /// `DaemonClientError::Cancelled` returned. A source that was already
+    /// running is left alone.
+    pub async fn start_source_cancellable(
+        &self,
+        name: &str,
+        cancel: &CancellationToken,
+        on_progress: impl FnMut(OperationProgress),
+    ) -> Result<()> {
+        let was_running = self
+            .list_sources()
+            .await?
+            .iter()
+            .any(|s| s.name == name && s.status == SourceStatus::Running);
+
+        tokio::select! {
+            result = self.start_source_with_progress(name, on_progress) => result,
+            _ = cancel.cancelled() => {
+                // The abandoned request may have left a reply half-read
+                self.connection_lost().await;
+                if !was_running {
+                    self.stop_source(name).await?;
+                }
+                Err(DaemonClientError::Cancelled)
+            }
+        }
+    }
+
+    /// Stop a source
+    pub async fn stop_source(&self, name: &str) -> Result<()> {
         self.expect_ok_with_timeout(
-            DaemonRequest::Shutdown { graceful },
+            DaemonRequest::StopSource {
+                name: name.to_string(),
+                progress: false,
+            },
+            Duration::from_secs(2 * 60),
+        )
+        .await
+    }
+
+    /// Stop a source, reporting each step to `on_progress`.
+    pub async fn stop_source_with_progress(
+        &self,
+        name: &str,
+        on_progress: impl FnMut(OperationProgress),
+    ) -> Result<()> {
+        self.request_with_progress(
+            DaemonRequest::StopSource {
+                name: name.to_string(),
+                progress: true,
+            },
+            Duration::from_secs(2 * 60),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Send a request that answers with `OperationProgress` updates followed
+    /// by `Ok` or `Error`. `idle_timeout` bounds the wait for each message.
+    async fn request_with_progress(
+        &self,
+        req: DaemonRequest,
+        idle_timeout: Duration,
+        mut on_progress: impl FnMut(OperationProgress),
+    ) -> Result<()> {
+        self.ensure_connected(&self.reconnect).await?;
+
+        let result = {
+            let mut inner = self.inner.lock().await;
+            let ClientInner { reader, writer, .. } = &mut *inner;
+            let writer = writer.as_mut().ok_or_else(DaemonClientError::closed)?;
+            let reader = reader.as_mut().ok_or_else(DaemonClientError::closed)?;
+
+            debug!("Sending request with progress: {:?}", req);
+            async {
+                writer.send(&req).await?;
+
+                loop {
+                    let response: DaemonResponse =
+                        tokio::time::timeout(idle_timeout, reader.read())
+                            .await
+                            .map_err(|_| DaemonClientError::Timeout(idle_timeout))??
+                            .ok_or_else(DaemonClientError::closed)?;
+
+                    match response {
+                        DaemonResponse::OperationProgress(progress) => on_progress(progress),
+                        DaemonResponse::Ok { .. } => return Ok(()),
+                        DaemonResponse::Error { code, message } => {
+                            return Err(DaemonClientError::DaemonError { code, message });
+                        }
+                        _ => return Err(DaemonClientError::unexpected()),
+                    }
+                }
+            }
+            .await
+        };
+
+        if result.as_ref().is_err_and(|e| e.is_connection_lost()) {
+            self.connection_lost().await;
+        }
+        result
+    }
+
+    /// Shutdown the daemon
+    pub async fn shutdown(&self, graceful: bool) -> Result<()> {
+        self.expect_ok_with_timeout(
+            DaemonRequest::Shutdown { graceful },
             Duration::from_secs(2 * 60),
         )
         .await
@@ -613,23 +1403,58 @@ impl DaemonClient {
         self.expect_ok_with_timeout(
             DaemonRequest::RestartService {
                 fqn: fqn.to_string(),
+                strategy: RestartStrategy::Immediate,
+                progress: false,
             },
             Duration::from_secs(5 * 60),
         )
         .await
     }
 
+    /// Restart a service with `strategy`, reporting each phase (e.g.
+    /// "waiting for health", "draining old instance") to `on_progress`.
+    pub async fn restart_service_with_progress(
+        &self,
+        fqn: &str,
+        strategy: RestartStrategy,
+        on_progress: impl FnMut(OperationProgress),
+    ) -> Result<()> {
+        self.request_with_progress(
+            DaemonRequest::RestartService {
+                fqn: fqn.to_string(),
+                strategy,
+                progress: true,
+            },
+            Duration::from_secs(5 * 60),
+            on_progress,
+        )
+        .await
+    }
+
     /// Create a new service dynamically
     pub async fn create_service(
         &self,
         source_id: &str,
         name: &str,
         config: serde_json::Value,
+    ) -> Result<()> {
+        self.create_service_with_probe(source_id, name, config, None)
+            .await
+    }
+
+    /// Create a new service dynamically, with `probe` as its healthcheck
+    pub async fn create_service_with_probe(
+        &self,
+        source_id: &str,
+        name: &str,
+        config: serde_json::Value,
+        probe: Option<HealthProbe>,
     ) -> Result<()> {
         self.expect_ok(DaemonRequest::CreateService {
             source_id: source_id.to_string(),
             name: name.to_string(),
             config,
+            health: probe,
         })
         .await
     }
@@ -639,6 +1464,17 @@ impl DaemonClient {
         self.expect_ok(DaemonRequest::UpdateService {
             fqn: fqn.to_string(),
             patch,
+            health: None,
+        })
+        .await
+    }
+
+    /// Replace a service's healthcheck; applies from its next start
+    pub async fn set_health_probe(&self, fqn: &str, probe: HealthProbe) -> Result<()> {
+        self.expect_ok(DaemonRequest::UpdateService {
+            fqn: fqn.to_string(),
+            patch: serde_json::Value::Null,
+            health: Some(probe),
         })
         .await
     }
@@ -651,6 +1487,145 @@ impl DaemonClient {
         .await
     }
 
+    /// List exposed services
+    pub async fn list_exposed(&self) -> Result<Vec<ExposedServiceInfo>> {
+        self.extract(DaemonRequest::ListExposed, |r| match r {
+            DaemonResponse::Exposed { exposed } => Some(exposed),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Get the expose/consume graph
+    pub async fn expose_graph(&self) -> Result<Vec<ExposeEdgeInfo>> {
+        self.extract(DaemonRequest::ExposeGraph, |r| match r {
+            DaemonResponse::ExposeGraph { edges } => Some(edges),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Get resource usage of a service, or of all services
+    pub async fn get_metrics(&self, fqn: Option<&str>) -> Result<Vec<ServiceMetrics>> {
+        self.extract(
+            DaemonRequest::GetMetrics {
+                fqn: fqn.map(String::from),
+            },
+            |r| match r {
+                DaemonResponse::Metrics { metrics } => Some(metrics),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Get the most recent crash report of a service; None if it has not
+    /// crashed since the daemon started
+    pub async fn get_crash_report(&self, fqn: &str) -> Result<Option<CrashReport>> {
+        self.extract(
+            DaemonRequest::GetCrashReport {
+                fqn: fqn.to_string(),
+            },
+            |r| match r {
+                DaemonResponse::CrashReport { report } => Some(report),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Reserve a port for `owner`; fails with `PORT_CONFLICT` if someone else holds it
+    pub async fn reserve_port(
+        &self,
+        port: u16,
+        owner: &str,
+        name: Option<&str>,
+    ) -> Result<PortAllocation> {
+        self.extract(
+            DaemonRequest::ReservePort {
+                port,
+                owner: owner.to_string(),
+                name: name.map(String::from),
+            },
+            |r| match r {
+                DaemonResponse::PortReserved { allocation } => Some(allocation),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Release a port reservation held by `owner`
+    pub async fn release_port(&self, port: u16, owner: &str) -> Result<()> {
+        self.expect_ok(DaemonRequest::ReleasePort {
+            port,
+            owner: owner.to_string(),
+        })
+        .await
+    }
+
+    /// List reserved and configured ports
+    pub async fn list_port_allocations(&self) -> Result<Vec<PortAllocation>> {
+        self.extract(DaemonRequest::ListPortAllocations, |r| match r {
+            DaemonResponse::PortAllocations { allocations } => Some(allocations),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Export daemon state as a snapshot archive
+    pub async fn snapshot(&self, include_logs: bool, passphrase: Option<&str>) -> Result<String> {
+        self.extract_with_timeout(
+            DaemonRequest::Snapshot {
+                include_logs,
+                passphrase: passphrase.map(Passphrase::new),
+            },
+            Duration::from_secs(60),
+            |r| match r {
+                DaemonResponse::Snapshot { archive } => Some(archive),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Enter or leave maintenance mode, returning the daemon's summary
+    pub async fn set_maintenance(
+        &self,
+        on: bool,
+        reason: Option<&str>,
+        drain: bool,
+    ) -> Result<Option<String>> {
+        self.extract(
+            DaemonRequest::SetMaintenance {
+                on,
+                reason: reason.map(|r| r.to_string()),
+                drain,
+            },
+            |r| match r {
+                DaemonResponse::Ok { message } => Some(message),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Rebuild daemon state from a snapshot archive
+    pub async fn restore(&self, archive: String, passphrase: Option<&str>) -> Result<RestoreReport> {
+        self.extract_with_timeout(
+            DaemonRequest::Restore {
+                archive,
+                passphrase: passphrase.map(Passphrase::new),
+            },
+            Duration::from_secs(5 * 60),
+            |r| match r {
+                DaemonResponse::Restored(report) => Some(report),
+                _ => None,
+            },
+        )
+        .await
+    }
+
     /// Get logs
     pub async fn get_logs(
         &self,
@@ -684,44 +1659,49 @@ impl DaemonClient {
         fqn: Option<&str>,
         level: Option<&str>,
     ) -> Result<LogStreamHandle> {
-        let stream = UnixStream::connect(&self.socket_path)
-            .await
-            .with_context(|| {
-                format!(
-                    "Failed to connect to daemon at {}. Is the daemon running?",
-                    self.socket_path.display()
-                )
-            })?;
-
-        let (reader, mut writer) = stream.into_split();
-        let mut reader = BufReader::new(reader);
-
-        let request = DaemonRequest::StreamLogs {
+        self.open_log_stream(DaemonRequest::StreamLogs {
             fqn: fqn.map(String::from),
+            fqns: Vec::new(),
             level: level.map(String::from),
-        };
-        let request_json = serde_json::to_string(&request)?;
-        writer.write_all(request_json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
+        })
+        .await
+    }
+
+    /// Stream logs of several services at once, interleaved by timestamp.
+    pub async fn stream_logs_of(
+        &self,
+        fqns: &[&str],
+        level: Option<&str>,
+    ) -> Result<LogStreamHandle> {
+        self.open_log_stream(DaemonRequest::StreamLogs {
+            fqn: None,
+            fqns: fqns.iter().map(|f| f.to_string()).collect(),
+            level: level.map(String::from),
+        })
+        .await
+    }
 
-        let mut response_line = String::new();
-        reader.read_line(&mut response_line).await?;
+    async fn open_log_stream(&self, request: DaemonRequest) -> Result<LogStreamHandle> {
+        let (mut reader, mut writer) = self.connect().await?;
+        writer.send(&request).await?;
 
-        let response: DaemonResponse = serde_json::from_str(response_line.trim())
-            .with_context(|| "Invalid response from daemon")?;
+        let response: DaemonResponse =
+            reader.read().await?.ok_or_else(DaemonClientError::closed)?;
 
         let stream_id = match response {
             DaemonResponse::StreamStarted { stream_id } => stream_id,
             DaemonResponse::Error { code, message } => {
-                return Err(anyhow!("Daemon error [{}]: {}", code, message));
+                return Err(DaemonClientError::DaemonError { code, message });
             }
-            _ => return Err(anyhow!("Unexpected response")),
+            _ => return Err(DaemonClientError::unexpected()),
         };
 
         Ok(LogStreamHandle {
             stream_id,
             reader,
             writer,
+            ended: false,
+            cancel: None,
         })
     }
 
@@ -733,37 +1713,22 @@ impl DaemonClient {
         &self,
         source: Option<&str>,
     ) -> Result<ServiceStreamHandle> {
-        let stream = UnixStream::connect(&self.socket_path)
-            .await
-            .with_context(|| {
-                format!(
-                    "Failed to connect to daemon at {}. Is the daemon running?",
-                    self.socket_path.display()
-                )
-            })?;
-
-        let (reader, mut writer) = stream.into_split();
-        let mut reader = BufReader::new(reader);
+        let (mut reader, mut writer) = self.connect().await?;
 
         let request = DaemonRequest::SubscribeServices {
             source: source.map(String::from),
         };
-        let request_json = serde_json::to_string(&request)?;
-        writer.write_all(request_json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-
-        let mut response_line = String::new();
-        reader.read_line(&mut response_line).await?;
+        writer.send(&request).await?;
 
-        let response: DaemonResponse = serde_json::from_str(response_line.trim())
-            .with_context(|| "Invalid response from daemon")?;
+        let response: DaemonResponse =
+            reader.read().await?.ok_or_else(DaemonClientError::closed)?;
 
         let stream_id = match response {
             DaemonResponse::StreamStarted { stream_id } => stream_id,
             DaemonResponse::Error { code, message } => {
-                return Err(anyhow!("Daemon error [{}]: {}", code, message));
+                return Err(DaemonClientError::DaemonError { code, message });
             }
-            _ => return Err(anyhow!("Unexpected response")),
+            _ => return Err(DaemonClientError::unexpected()),
         };
 
         Ok(ServiceStreamHandle {
@@ -773,6 +1738,81 @@ impl DaemonClient {
         })
     }
 
+    /// Stream daemon status every `interval`, returning a handle for
+    /// receiving updates.
+    ///
+    /// Opens a dedicated connection (like `stream_logs`) so a dashboard can
+    /// keep receiving updates while issuing other requests.
+    pub async fn stream_status(&self, interval: Option<Duration>) -> Result<StatusStreamHandle> {
+        let (mut reader, mut writer) = self.connect().await?;
+
+        let request = DaemonRequest::StreamStatus {
+            interval_ms: interval.map(|d| d.as_millis() as u64),
+        };
+        writer.send(&request).await?;
+
+        let response: DaemonResponse =
+            reader.read().await?.ok_or_else(DaemonClientError::closed)?;
+
+        let stream_id = match response {
+            DaemonResponse::StreamStarted { stream_id } => stream_id,
+            DaemonResponse::Error { code, message } => {
+                return Err(DaemonClientError::DaemonError { code, message });
+            }
+            _ => return Err(DaemonClientError::unexpected()),
+        };
+
+        Ok(StatusStreamHandle {
+            stream_id,
+            reader,
+            writer,
+        })
+    }
+
+    /// Subscribe to daemon state changes, returning a handle for receiving
+    /// events.
+    ///
+    /// Opens a dedicated connection (like `stream_logs`) so consumers can react
+    /// to service and source changes without polling `ListServices`.
+    pub async fn subscribe_events(&self, source: Option<&str>) -> Result<EventStreamHandle> {
+        let (mut reader, mut writer) = self.connect().await?;
+
+        let request = DaemonRequest::SubscribeEvents {
+            source: source.map(String::from),
+        };
+        writer.send(&request).await?;
+
+        let response: DaemonResponse =
+            reader.read().await?.ok_or_else(DaemonClientError::closed)?;
+
+        let stream_id = match response {
+            DaemonResponse::StreamStarted { stream_id } => stream_id,
+            DaemonResponse::Error { code, message } => {
+                return Err(DaemonClientError::DaemonError { code, message });
+            }
+            _ => return Err(DaemonClientError::unexpected()),
+        };
+
+        Ok(EventStreamHandle {
+            stream_id,
+            reader,
+            writer,
+        })
+    }
+
+    /// Start a batch of requests to send in one round trip.
+    ///
+    /// ```ignore
+    /// let results = client.batch().stop_service("app:api").stop_service("app:web").send().await?;
+    /// ```
+    pub fn batch(&self) -> BatchBuilder<'_> {
+        BatchBuilder {
+            client: self,
+            requests: Vec::new(),
+            timeout: Duration::from_secs(5 * 60),
+        }
+    }
+
     /// Disconnect from daemon
     pub async fn disconnect(&self) {
         let mut inner = self.inner.lock().await;
@@ -780,18 +1820,109 @@ impl DaemonClient {
             inner.reader.take();
             inner.writer.take();
             debug!("Disconnected from daemon");
+            self.notify(ConnectionState::Disconnected);
         }
     }
 }
 
+/// Requests collected for a single `DaemonRequest::Batch`.
+pub struct BatchBuilder<'a> {
+    client: &'a DaemonClient,
+    requests: Vec<DaemonRequest>,
+    timeout: Duration,
+}
+
+impl BatchBuilder<'_> {
+    /// Add any request
+    pub fn request(mut self, req: DaemonRequest) -> Self {
+        self.requests.push(req);
+        self
+    }
+
+    pub fn start_service(self, fqn: &str) -> Self {
+        self.request(DaemonRequest::StartService {
+            fqn: fqn.to_string(),
+        })
+    }
+
+    pub fn stop_service(self, fqn: &str) -> Self {
+        self.request(DaemonRequest::StopService {
+            fqn: fqn.to_string(),
+        })
+    }
+
+    pub fn restart_service(self, fqn: &str) -> Self {
+        self.request(DaemonRequest::RestartService {
+            fqn: fqn.to_string(),
+            strategy: RestartStrategy::Immediate,
+            progress: false,
+        })
+    }
+
+    /// Time allowed for the whole batch (5 minutes by default)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Send the batch. Returns one result per request, in the order they were
+    /// added; `DaemonResponse::Error` replies become `DaemonError`s. The outer
+    /// error is for failures of the batch as a whole.
+    pub async fn send(self) -> Result<Vec<Result<DaemonResponse>>> {
+        if self.requests.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let expected = self.requests.len();
+        let request = DaemonRequest::Batch {
+            requests: self.requests,
+        };
+        let responses = self
+            .client
+            .extract_with_timeout(request, self.timeout, |r| match r {
+                DaemonResponse::Batch { responses } => Some(responses),
+                _ => None,
+            })
+            .await?;
+
+        if responses.len() != expected {
+            return Err(DaemonClientError::Protocol(format!(
+                "batch of {} requests answered with {} responses",
+                expected,
+                responses.len()
+            )));
+        }
+
+        Ok(responses
+            .into_iter()
+            .map(|response| match response {
+                DaemonResponse::Error { code, message } => {
+                    Err(DaemonClientError::DaemonError { code, message })
+                }
+                response => Ok(response),
+            })
+            .collect())
+    }
+}
+
 /// Handle for streaming logs from the daemon.
 ///
 /// Uses a dedicated Unix socket connection so log lines can be
 /// received independently of other daemon requests.
 pub struct LogStreamHandle {
     stream_id: Uuid,
-    reader: BufReader<tokio::net::unix::OwnedReadHalf>,
-    writer: tokio::net::unix::OwnedWriteHalf,
+    reader: FrameReader<OwnedReadHalf>,
+    writer: FrameWriter<OwnedWriteHalf>,
+    ended: bool,
+    cancel: Option<CancellationToken>,
 }
 
 impl LogStreamHandle {
@@ -800,37 +1931,86 @@ impl LogStreamHandle {
         self.stream_id
     }
 
-    /// Receive the next log line, or `None` when the stream ends.
-    pub async fn recv(&mut self) -> Result<Option<LogLine>> {
-        let mut line = String::new();
-        let bytes_read = self.reader.read_line(&mut line).await?;
+    /// Stop the stream once `token` is cancelled: the daemon is sent
+    /// `StopLogStream` and `recv` returns `None` from then on.
+    pub fn cancel_on(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
 
-        if bytes_read == 0 {
+    /// Receive the next log line, or `None` when the stream ends or is
+    /// cancelled.
+    pub async fn recv(&mut self) -> Result<Option<LogLine>> {
+        if self.ended {
             return Ok(None);
         }
-
-        let response: DaemonResponse = serde_json::from_str(line.trim())
-            .with_context(|| "Invalid response from daemon")?;
+        let cancel = self.cancel.clone();
+        let read = match &cancel {
+            Some(token) => tokio::select! {
+                read = self.reader.read::<DaemonResponse>() => read,
+                _ = token.cancelled() => {
+                    self.ended = true;
+                    self.send_stop().await?;
+                    return Ok(None);
+                }
+            },
+            None => self.reader.read::<DaemonResponse>().await,
+        };
+        let Some(response) = read? else {
+            self.ended = true;
+            return Ok(None);
+        };
 
         match response {
             DaemonResponse::LogStream { line, .. } => Ok(Some(line)),
-            DaemonResponse::StreamEnded { .. } => Ok(None),
+            DaemonResponse::StreamEnded { .. } => {
+                self.ended = true;
+                Ok(None)
+            }
             DaemonResponse::Error { code, message } => {
-                Err(anyhow!("Daemon error [{}]: {}", code, message))
+                Err(DaemonClientError::DaemonError { code, message })
+            }
+            _ => Err(DaemonClientError::unexpected()),
+        }
+    }
+
+    /// Receive up to `max` log lines (at least one) in one call: waits for the
+    /// first line, then takes whatever else arrives within `timeout` of it.
+    /// Empty when the stream has ended.
+    pub async fn recv_batch(&mut self, max: usize, timeout: Duration) -> Result<Vec<LogLine>> {
+        let mut lines = Vec::new();
+        let Some(first) = self.recv().await? else {
+            return Ok(lines);
+        };
+        lines.push(first);
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        while lines.len() < max && !self.ended {
+            // Only the wait is bounded: abandoning a half-read frame would
+            // desync the stream, and the daemon writes frames whole.
+            match tokio::time::timeout_at(deadline, self.reader.wait_readable()).await {
+                Ok(Ok(true)) => {}
+                Ok(Ok(false)) | Err(_) => break,
+                Ok(Err(e)) => return Err(e.into()),
+            }
+            match self.recv().await? {
+                Some(line) => lines.push(line),
+                None => break,
             }
-            _ => Err(anyhow!("Unexpected response")),
         }
+        Ok(lines)
     }
 
     /// Stop the log stream
     pub async fn stop(mut self) -> Result<()> {
+        self.send_stop().await
+    }
+
+    async fn send_stop(&mut self) -> Result<()> {
         let request = DaemonRequest::StopLogStream {
             stream_id: self.stream_id,
         };
-        let request_json = serde_json::to_string(&request)?;
-        self.writer.write_all(request_json.as_bytes()).await?;
-        self.writer.write_all(b"\n").await?;
-        Ok(())
+        self.writer.send(&request).await
     }
 }
 
@@ -840,8 +2020,8 @@ impl LogStreamHandle {
 /// independently of other daemon requests.
 pub struct ServiceStreamHandle {
     stream_id: Uuid,
-    reader: BufReader<tokio::net::unix::OwnedReadHalf>,
-    writer: tokio::net::unix::OwnedWriteHalf,
+    reader: FrameReader<OwnedReadHalf>,
+    writer: FrameWriter<OwnedWriteHalf>,
 }
 
 impl ServiceStreamHandle {
@@ -852,23 +2032,17 @@ impl ServiceStreamHandle {
 
     /// Receive the next service status update, or `None` when the stream ends.
     pub async fn recv(&mut self) -> Result<Option<Vec<ServiceStatus>>> {
-        let mut line = String::new();
-        let bytes_read = self.reader.read_line(&mut line).await?;
-
-        if bytes_read == 0 {
+        let Some(response) = self.reader.read::<DaemonResponse>().await? else {
             return Ok(None);
-        }
-
-        let response: DaemonResponse = serde_json::from_str(line.trim())
-            .with_context(|| "Invalid response from daemon")?;
+        };
 
         match response {
             DaemonResponse::ServiceStatusUpdate { services, .. } => Ok(Some(services)),
             DaemonResponse::StreamEnded { .. } => Ok(None),
             DaemonResponse::Error { code, message } => {
-                Err(anyhow!("Daemon error [{}]: {}", code, message))
+                Err(DaemonClientError::DaemonError { code, message })
             }
-            _ => Err(anyhow!("Unexpected response")),
+            _ => Err(DaemonClientError::unexpected()),
         }
     }
 
@@ -877,10 +2051,103 @@ impl ServiceStreamHandle {
         let request = DaemonRequest::StopServiceStream {
             stream_id: self.stream_id,
         };
-        let request_json = serde_json::to_string(&request)?;
-        self.writer.write_all(request_json.as_bytes()).await?;
-        self.writer.write_all(b"\n").await?;
-        Ok(())
+        self.writer.send(&request).await
+    }
+}
+
+/// One update of a daemon status stream
+#[derive(Debug, Clone)]
+pub struct StatusUpdate {
+    pub status: DaemonStatus,
+    /// Services that changed state or health since the previous update
+    /// (every service in the first update)
+    pub changed: Vec<ServiceStatus>,
+}
+
+/// Handle for streaming daemon status from the daemon.
+///
+/// Uses a dedicated Unix socket connection so updates can be received
+/// independently of other daemon requests.
+pub struct StatusStreamHandle {
+    stream_id: Uuid,
+    reader: FrameReader<OwnedReadHalf>,
+    writer: FrameWriter<OwnedWriteHalf>,
+}
+
+impl StatusStreamHandle {
+    /// Get the stream ID
+    pub fn stream_id(&self) -> Uuid {
+        self.stream_id
+    }
+
+    /// Receive the next status update, or `None` when the stream ends.
+    pub async fn recv(&mut self) -> Result<Option<StatusUpdate>> {
+        let Some(response) = self.reader.read::<DaemonResponse>().await? else {
+            return Ok(None);
+        };
+
+        match response {
+            DaemonResponse::StatusUpdate {
+                status, changed, ..
+            } => Ok(Some(StatusUpdate { status, changed })),
+            DaemonResponse::StreamEnded { .. } => Ok(None),
+            DaemonResponse::Error { code, message } => {
+                Err(DaemonClientError::DaemonError { code, message })
+            }
+            _ => Err(DaemonClientError::unexpected()),
+        }
+    }
+
+    /// Stop the status stream
+    pub async fn stop(mut self) -> Result<()> {
+        let request = DaemonRequest::StopStatusStream {
+            stream_id: self.stream_id,
+        };
+        self.writer.send(&request).await
+    }
+}
+
+/// Handle for streaming daemon events.
+///
+/// Uses a dedicated Unix socket connection so events can be received
+/// independently of other daemon requests.
+pub struct EventStreamHandle {
+    stream_id: Uuid,
+    reader: FrameReader<OwnedReadHalf>,
+    writer: FrameWriter<OwnedWriteHalf>,
+}
+
+impl EventStreamHandle {
+    /// Get the stream ID
+    pub fn stream_id(&self) -> Uuid {
+        self.stream_id
+    }
+
+    /// Receive the next event, or `None` when the stream ends.
+    ///
+    /// Fails with code `STREAM_LAGGED` if the daemon had to drop events for
+    /// this subscriber; resubscribe and re-read state with `list_services`.
+    pub async fn recv(&mut self) -> Result<Option<HiveEvent>> {
+        let Some(response) = self.reader.read::<DaemonResponse>().await? else {
+            return Ok(None);
+        };
+
+        match response {
+            DaemonResponse::Event { event, .. } => Ok(Some(event)),
+            DaemonResponse::StreamEnded { .. } => Ok(None),
+            DaemonResponse::Error { code, message } => {
+                Err(DaemonClientError::DaemonError { code, message })
+            }
+            _ => Err(DaemonClientError::unexpected()),
+        }
+    }
+
+    /// Stop the event stream
+    pub async fn stop(mut self) -> Result<()> {
+        let request = DaemonRequest::StopEventStream {
+            stream_id: self.stream_id,
+        };
+        self.writer.send(&request).await
     }
 }
 
@@ -888,6 +2155,28 @@ impl ServiceStreamHandle {
 mod tests {
     use super::*;
 
+    /// Accept one client and answer its `Hello` like the daemon does
+    async fn accept(
+        listener: &tokio::net::UnixListener,
+    ) -> (FrameReader<OwnedReadHalf>, FrameWriter<OwnedWriteHalf>) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (r, w) = stream.into_split();
+        let (mut reader, mut writer) = (FrameReader::new(r), FrameWriter::new(w));
+        let Ok(Some(DaemonRequest::Hello { formats })) = reader.read().await else {
+            panic!("expected Hello");
+        };
+        assert_eq!(formats[0], WireFormat::Binary);
+        writer
+            .send(&DaemonResponse::Hello {
+                format: WireFormat::Binary,
+            })
+            .await
+            .unwrap();
+        reader.set_format(WireFormat::Binary);
+        writer.set_format(WireFormat::Binary);
+        (reader, writer)
+    }
+
     #[tokio::test]
     async fn test_client_creation() {
         let client = DaemonClient::new(PathBuf::from("/tmp/test.sock"));
@@ -921,6 +2210,105 @@ mod tests {
         assert_eq!(status.fqn, "src:svc");
         assert!(status.container_id.is_none());
         assert!(status.started_at.is_none());
+        assert!(status.port_conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_create_service_with_probe() {
+        let req = DaemonRequest::CreateService {
+            source_id: "app".to_string(),
+            name: "api".to_string(),
+            config: serde_json::json!({"runner": {"type": "script"}}),
+            health: Some(HealthProbe {
+                check: ProbeCheck::Http {
+                    port: "{{runtime.port.http}}".to_string(),
+                    path: "/health".to_string(),
+                    status: None,
+                },
+                interval_ms: Some(2000),
+                timeout_ms: None,
+                failure_threshold: Some(3),
+            }),
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert!(json.contains(
+            r#""health":{"type":"http","port":"{{runtime.port.http}}","path":"/health","interval_ms":2000,"failure_threshold":3}"#
+        ));
+        let parsed: DaemonRequest = serde_json::from_str(&json).unwrap();
+        let DaemonRequest::CreateService { health, .. } = parsed else {
+            panic!("Wrong variant");
+        };
+        assert_eq!(health.unwrap().failure_threshold, Some(3));
+
+        // Older clients send no probe
+        let json = r#"{"type":"update_service","fqn":"app:api","patch":{}}"#;
+        let parsed: DaemonRequest = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            parsed,
+            DaemonRequest::UpdateService { health: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_service_status_last_probe() {
+        let json = r#"{"fqn":"src:svc","source":"src","name":"svc","state":"unhealthy","healthy":false,"pid":123,"ports":{},"restart_count":0,"last_probe":{"check":"http","passed":false,"checked_at":"2026-01-01T00:00:00Z","latency_ms":12,"status_code":503,"failure":"HTTP 503","consecutive_failures":2}}"#;
+        let status: ServiceStatus = serde_json::from_str(json).unwrap();
+        let probe = status.last_probe.unwrap();
+        assert!(!probe.passed);
+        assert_eq!(probe.status_code, Some(503));
+        assert_eq!(probe.consecutive_failures, 2);
+    }
+
+    #[test]
+    fn test_crash_report_response() {
+        let json = r#"{"type":"crash_report","report":{"fqn":"app:api","crashed_at":"2026-01-01T00:00:00Z","signal":9,"output":[{"timestamp":"2026-01-01T00:00:00Z","stream":"stderr","line":"out of memory"}],"env_fingerprint":"3f2a9c1d","env_vars":["PORT"]}}"#;
+        let resp: DaemonResponse = serde_json::from_str(json).unwrap();
+        let DaemonResponse::CrashReport { report: Some(report) } = resp else {
+            panic!("Wrong variant");
+        };
+        assert_eq!(report.exit_code, None);
+        assert_eq!(report.signal, Some(9));
+        assert_eq!(report.output[0].stream, "stderr");
+        assert!(!report.truncated);
+
+        let json = r#"{"type":"crash_report","report":null}"#;
+        let resp: DaemonResponse = serde_json::from_str(json).unwrap();
+        assert!(matches!(resp, DaemonResponse::CrashReport { report: None }));
+    }
+
+    #[test]
+    fn test_port_allocation_kinds() {
+        let json = r#"{"type":"port_allocations","allocations":[{"port":8080,"owner":"app:api","name":"http"},{"port":8080,"owner":"manual","reserved_at":"2026-01-01T00:00:00Z"}]}"#;
+        let resp: DaemonResponse = serde_json::from_str(json).unwrap();
+        let DaemonResponse::PortAllocations { allocations } = resp else {
+            panic!("Wrong variant");
+        };
+        assert!(!allocations[0].is_reservation());
+        assert!(allocations[1].is_reservation());
+        assert!(allocations[1].name.is_none());
+
+        let req = DaemonRequest::ReservePort {
+            port: 8080,
+            owner: "manual".to_string(),
+            name: None,
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"reserve_port","port":8080,"owner":"manual"}"#
+        );
+    }
+
+    #[test]
+    fn test_service_metrics_without_samples() {
+        let json = r#"{"fqn":"src:svc","state":"stopped","restart_count":2,"sampled_at":"2026-01-01T00:00:00Z"}"#;
+        let metrics: ServiceMetrics = serde_json::from_str(json).unwrap();
+        assert_eq!(metrics.restart_count, 2);
+        assert!(metrics.cpu_percent.is_none());
+        assert!(metrics.uptime_secs.is_none());
+
+        let json = serde_json::to_string(&metrics).unwrap();
+        assert!(!json.contains("memory_rss_bytes"));
     }
 
     #[test]
@@ -936,4 +2324,369 @@ mod tests {
         assert!(json.contains("service_fqn"));
         assert!(json.contains("Server started"));
     }
+
+    #[test]
+    fn test_snapshot_request_passphrase_optional() {
+        let json = r#"{"type":"snapshot","include_logs":true}"#;
+        let req: DaemonRequest = serde_json::from_str(json).unwrap();
+        match req {
+            DaemonRequest::Snapshot {
+                include_logs,
+                passphrase,
+            } => {
+                assert!(include_logs);
+                assert!(passphrase.is_none());
+            }
+            _ => panic!("Wrong variant"),
+        }
+
+        let req = DaemonRequest::Restore {
+            archive: "{}".to_string(),
+            passphrase: None,
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert!(!json.contains("passphrase"));
+    }
+
+    #[test]
+    fn test_passphrase_redacted_in_debug() {
+        let req = DaemonRequest::Snapshot {
+            include_logs: false,
+            passphrase: Some(Passphrase::new("hunter2")),
+        };
+        assert!(!format!("{:?}", req).contains("hunter2"));
+
+        let json = serde_json::to_string(&req).unwrap();
+        assert!(json.contains(r#""passphrase":"hunter2""#));
+        let req: DaemonRequest = serde_json::from_str(&json).unwrap();
+        match req {
+            DaemonRequest::Snapshot { passphrase, .. } => {
+                assert_eq!(passphrase.as_deref(), Some("hunter2"))
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_maintenance_fields_optional() {
+        let req: DaemonRequest =
+            serde_json::from_str(r#"{"type":"set_maintenance","on":false}"#).unwrap();
+        assert!(matches!(
+            req,
+            DaemonRequest::SetMaintenance {
+                on: false,
+                reason: None,
+                drain: false
+            }
+        ));
+
+        // Status from a daemon without maintenance support
+        let json = r#"{"running":true,"pid":1,"version":"0.1.0","source_count":0,"running_services":0,"total_services":0,"proxy_addresses":[],"uptime_secs":5}"#;
+        let status: DaemonStatus = serde_json::from_str(json).unwrap();
+        assert!(status.maintenance.is_none());
+        assert!(!serde_json::to_string(&status).unwrap().contains("maintenance"));
+    }
+
+    #[test]
+    fn test_start_source_progress_flag_optional() {
+        // Older clients omit the flag and keep getting a single response
+        let req: DaemonRequest =
+            serde_json::from_str(r#"{"type":"start_source","name":"app"}"#).unwrap();
+        assert!(matches!(req, DaemonRequest::StartSource { progress: false, .. }));
+
+        let progress = DaemonResponse::OperationProgress(OperationProgress {
+            op_id: Uuid::nil(),
+            step: 2,
+            total: 5,
+            message: "api: building".to_string(),
+        });
+        let json = serde_json::to_value(&progress).unwrap();
+        assert_eq!(json["type"], "operation_progress");
+        assert_eq!(json["step"], 2);
+        assert_eq!(json["total"], 5);
+    }
+
+    #[test]
+    fn test_restart_strategy_serialization() {
+        let req = DaemonRequest::RestartService {
+            fqn: "app:api".to_string(),
+            strategy: RestartStrategy::BlueGreen { drain_seconds: 10 },
+            progress: true,
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"restart_service","fqn":"app:api","strategy":{"type":"blue_green","drain_seconds":10},"progress":true}"#
+        );
+
+        // Older clients send neither field and get an immediate restart
+        let req: DaemonRequest =
+            serde_json::from_str(r#"{"type":"restart_service","fqn":"app:api"}"#).unwrap();
+        assert!(matches!(
+            req,
+            DaemonRequest::RestartService {
+                strategy: RestartStrategy::Immediate,
+                progress: false,
+                ..
+            }
+        ));
+        let json = serde_json::to_string(&req).unwrap();
+        assert_eq!(json, r#"{"type":"restart_service","fqn":"app:api"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_error_kinds() {
+        let dir = std::env::temp_dir().join(format!("hive-client-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("hive.sock");
+
+        let err = DaemonClient::new(&socket).status().await.unwrap_err();
+        assert!(matches!(err, DaemonClientError::NotRunning { .. }));
+
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        tokio::spawn(async move {
+            let (mut reader, mut writer) = accept(&listener).await;
+            while let Ok(Some(req)) = reader.read::<DaemonRequest>().await {
+                let response = match req {
+                    DaemonRequest::GetServiceStatus { .. } => DaemonResponse::Error {
+                        code: "NOT_FOUND".to_string(),
+                        message: "no such service".to_string(),
+                    },
+                    _ => DaemonResponse::Error {
+                        code: "START_SERVICE_FAILED".to_string(),
+                        message: "boom".to_string(),
+                    },
+                };
+                writer.send(&response).await.unwrap();
+            }
+        });
+
+        let client = DaemonClient::new(&socket);
+        let status = client.get_service_status("app:api").await.unwrap();
+        assert!(status.is_none());
+        let err = client.start_service("app:api").await.unwrap_err();
+        assert_eq!(err.code(), Some("START_SERVICE_FAILED"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_batch_preserves_order_and_isolates_errors() {
+        let dir = std::env::temp_dir().join(format!("hive-client-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("hive.sock");
+
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        tokio::spawn(async move {
+            let (mut reader, mut writer) = accept(&listener).await;
+            while let Ok(Some(DaemonRequest::Batch { requests })) = reader.read().await {
+                let responses = requests
+                    .into_iter()
+                    .map(|req| match req {
+                        DaemonRequest::StopService { fqn } if fqn == "app:db" => {
+                            DaemonResponse::Error {
+                                code: "STOP_SERVICE_FAILED".to_string(),
+                                message: "still in use".to_string(),
+                            }
+                        }
+                        DaemonRequest::StopService { fqn } => {
+                            DaemonResponse::Ok { message: Some(fqn) }
+                        }
+                        _ => DaemonResponse::Pong,
+                    })
+                    .collect();
+                writer
+                    .send(&DaemonResponse::Batch { responses })
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let client = DaemonClient::new(&socket);
+        assert!(client.batch().send().await.unwrap().is_empty());
+
+        let batch = client
+            .batch()
+            .stop_service("app:api")
+            .stop_service("app:db")
+            .request(DaemonRequest::Ping);
+        assert_eq!(batch.len(), 3);
+        let results = batch.send().await.unwrap();
+
+        assert!(matches!(
+            &results[0],
+            Ok(DaemonResponse::Ok { message: Some(m) }) if m == "app:api"
+        ));
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.code(), Some("STOP_SERVICE_FAILED"));
+        assert!(matches!(results[2], Ok(DaemonResponse::Pong)));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_log_stream_batches() {
+        let dir = std::env::temp_dir().join(format!("hive-client-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("hive.sock");
+
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        tokio::spawn(async move {
+            let (mut reader, mut writer) = accept(&listener).await;
+            let Ok(Some(DaemonRequest::StreamLogs { fqns, .. })) = reader.read().await else {
+                panic!("expected StreamLogs");
+            };
+            assert_eq!(fqns, vec!["app:api", "app:worker"]);
+
+            let stream_id = Uuid::new_v4();
+            writer
+                .send(&DaemonResponse::StreamStarted { stream_id })
+                .await
+                .unwrap();
+            let line = |i: usize| DaemonResponse::LogStream {
+                stream_id,
+                line: LogLine {
+                    timestamp: Utc::now(),
+                    level: "info".to_string(),
+                    service_fqn: fqns[i % 2].clone(),
+                    message: format!("line {}", i),
+                    fields: None,
+                },
+            };
+            for i in 0..3 {
+                writer.send(&line(i)).await.unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            writer.send(&line(3)).await.unwrap();
+            writer
+                .send(&DaemonResponse::StreamEnded { stream_id })
+                .await
+                .unwrap();
+            // Keep the connection open: the handle must stop at StreamEnded
+            let _ = reader.read::<DaemonRequest>().await;
+        });
+
+        let client = DaemonClient::new(&socket);
+        let mut stream = client
+            .stream_logs_of(&["app:api", "app:worker"], None)
+            .await
+            .unwrap();
+
+        let batch = stream.recv_batch(2, Duration::from_millis(50)).await.unwrap();
+        assert_eq!(batch.len(), 2);
+        let batch = stream.recv_batch(10, Duration::from_millis(50)).await.unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].service_fqn, "app:api");
+
+        let batch = stream.recv_batch(10, Duration::from_millis(50)).await.unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].message, "line 3");
+        assert!(stream.recv_batch(10, Duration::from_millis(50)).await.unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_after_daemon_restart() {
+        let dir = std::env::temp_dir().join(format!("hive-client-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("hive.sock");
+
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        let server_socket = socket.clone();
+        tokio::spawn(async move {
+            // Answer one ping, then go away in the middle of the next request
+            let (mut reader, mut writer) = accept(&listener).await;
+            let _ = reader.read::<DaemonRequest>().await;
+            writer.send(&DaemonResponse::Pong).await.unwrap();
+            let _ = reader.read::<DaemonRequest>().await;
+            drop(listener);
+            std::fs::remove_file(&server_socket).unwrap();
+            drop((reader, writer));
+
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let listener = tokio::net::UnixListener::bind(&server_socket).unwrap();
+            let (mut reader, mut writer) = accept(&listener).await;
+            while let Ok(Some(_)) = reader.read::<DaemonRequest>().await {
+                writer.send(&DaemonResponse::Pong).await.unwrap();
+            }
+        });
+
+        let states = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = states.clone();
+        let client = DaemonClient::new(&socket)
+            .with_reconnect_policy(RetryPolicy {
+                initial_delay: Duration::from_millis(10),
+                ..DEFAULT_RECONNECT_POLICY
+            })
+            .on_connection_state_change(move |state| recorded.lock().unwrap().push(state));
+
+        assert!(client.ping().await.unwrap());
+        // The in-flight ping is resent once the daemon is back
+        assert!(client.ping().await.unwrap());
+
+        let states = states.lock().unwrap().clone();
+        assert_eq!(states[0], ConnectionState::Connected);
+        assert_eq!(states[1], ConnectionState::Disconnected);
+        assert!(matches!(
+            states[2],
+            ConnectionState::Reconnecting { attempt: 1, delay } if delay == Duration::from_millis(10)
+        ));
+        assert_eq!(states.last(), Some(&ConnectionState::Connected));
+
+        assert!(DaemonRequest::ListServices { source: None }.is_idempotent());
+        assert!(!DaemonRequest::Shutdown { graceful: true }.is_idempotent());
+        assert_eq!(
+            DEFAULT_RECONNECT_POLICY.delay(10),
+            DEFAULT_RECONNECT_POLICY.max_delay
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stream_status_interval_optional() {
+        let req: DaemonRequest = serde_json::from_str(r#"{"type":"stream_status"}"#).unwrap();
+        assert!(matches!(
+            req,
+            DaemonRequest::StreamStatus { interval_ms: None }
+        ));
+
+        let json = r#"{"type":"status_update","stream_id":"00000000-0000-0000-0000-000000000000","status":{"running":true,"pid":1,"version":"0.1.0","source_count":1,"running_services":1,"total_services":2,"proxy_addresses":[],"uptime_secs":5},"changed":[]}"#;
+        let resp: DaemonResponse = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            resp,
+            DaemonResponse::StatusUpdate { status, .. } if status.running_services == 1
+        ));
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let req: DaemonRequest = serde_json::from_str(r#"{"type":"subscribe_events"}"#).unwrap();
+        assert!(matches!(
+            req,
+            DaemonRequest::SubscribeEvents { source: None }
+        ));
+
+        let resp = DaemonResponse::Event {
+            stream_id: Uuid::nil(),
+            event: HiveEvent::HealthChanged {
+                fqn: "app:api".to_string(),
+                healthy: false,
+            },
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(
+            json.contains(r#""event":{"event":"health_changed","fqn":"app:api","healthy":false}"#)
+        );
+
+        let resp: DaemonResponse = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            resp,
+            DaemonResponse::Event {
+                event: HiveEvent::HealthChanged { healthy: false, .. },
+                ..
+            }
+        ));
+    }
 }
@@ -0,0 +1,471 @@
+//! In-process mock daemon for testing `DaemonClient` consumers
+//!
+//! [`MockDaemon`] listens on a Unix socket in a temporary directory and
+//! answers requests from programmable [`Rule`]s. It negotiates binary frames
+//! like the daemon. Every request but that handshake is captured, and rules
+//! can delay their reply or drop the connection to exercise timeouts and
+//! reconnects. [`fixtures`] has rules for the standard flows.
+//!
+//! ```ignore
+//! let daemon = MockDaemon::start().await?;
+//! daemon.mock_all(fixtures::standard_rules(vec![fixtures::service("app:api", "running")]));
+//! daemon.mock(Rule::when(|r| matches!(r, DaemonRequest::StartService { .. })).error("START_SERVICE_FAILED", "boom"));
+//!
+//! let client = daemon.client();
+//! assert!(client.start_service("app:api").await.is_err());
+//! assert_eq!(daemon.received(|r| matches!(r, DaemonRequest::StartService { .. })), 1);
+//! ```
+
+use crate::frame::{FrameReader, FrameWriter};
+use crate::{DaemonClient, DaemonRequest, DaemonResponse, WireFormat};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Error code answered to requests that no rule matches
+pub const UNMOCKED: &str = "UNMOCKED";
+
+type Matcher = Box<dyn Fn(&DaemonRequest) -> bool + Send>;
+type Responder = Box<dyn Fn(&DaemonRequest) -> DaemonResponse + Send>;
+
+/// How a rule answers a matching request
+enum Reply {
+    /// Send these frames in order (several for streams and progress updates)
+    Frames(Vec<DaemonResponse>),
+    /// Build the answer from the request
+    With(Responder),
+    /// Close the connection without answering
+    HangUp,
+}
+
+/// A programmable answer to matching requests
+pub struct Rule {
+    matcher: Matcher,
+    reply: Reply,
+    delay: Duration,
+    /// Matches left before the rule stops applying; `None` for unlimited
+    remaining: Option<usize>,
+}
+
+impl Rule {
+    /// Match requests for which `matcher` returns true. Answers `Ok` until a
+    /// reply is set.
+    pub fn when(matcher: impl Fn(&DaemonRequest) -> bool + Send + 'static) -> Self {
+        Self {
+            matcher: Box::new(matcher),
+            reply: Reply::Frames(vec![DaemonResponse::Ok { message: None }]),
+            delay: Duration::ZERO,
+            remaining: None,
+        }
+    }
+
+    /// Match every request
+    pub fn any() -> Self {
+        Self::when(|_| true)
+    }
+
+    pub fn respond(self, response: DaemonResponse) -> Self {
+        self.respond_all(vec![response])
+    }
+
+    /// Answer with several frames, e.g. `StreamStarted`, `LogStream`s and
+    /// `StreamEnded`
+    pub fn respond_all(mut self, responses: Vec<DaemonResponse>) -> Self {
+        self.reply = Reply::Frames(responses);
+        self
+    }
+
+    /// Build the answer from the request
+    pub fn respond_with(
+        mut self,
+        responder: impl Fn(&DaemonRequest) -> DaemonResponse + Send + 'static,
+    ) -> Self {
+        self.reply = Reply::With(Box::new(responder));
+        self
+    }
+
+    pub fn error(self, code: &str, message: &str) -> Self {
+        self.respond(DaemonResponse::Error {
+            code: code.to_string(),
+            message: message.to_string(),
+        })
+    }
+
+    /// Drop the connection instead of answering, as a crashing daemon would
+    pub fn hang_up(mut self) -> Self {
+        self.reply = Reply::HangUp;
+        self
+    }
+
+    /// Wait before answering
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Apply to the first `n` matching requests only
+    pub fn times(mut self, n: usize) -> Self {
+        self.remaining = Some(n);
+        self
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    rules: Vec<Rule>,
+    requests: Vec<DaemonRequest>,
+}
+
+impl MockState {
+    /// Take the reply of the most recently added rule that matches.
+    fn reply_to(&mut self, request: &DaemonRequest) -> (Option<Vec<DaemonResponse>>, Duration) {
+        let Some(rule) = self
+            .rules
+            .iter_mut()
+            .rev()
+            .find(|r| r.remaining != Some(0) && (r.matcher)(request))
+        else {
+            return match request {
+                // Batches work without rules of their own
+                DaemonRequest::Batch { requests } => {
+                    let responses = requests
+                        .iter()
+                        .map(|r| match self.reply_to(r).0 {
+                            Some(mut frames) if !frames.is_empty() => frames.remove(0),
+                            _ => unmocked(r),
+                        })
+                        .collect();
+                    (
+                        Some(vec![DaemonResponse::Batch { responses }]),
+                        Duration::ZERO,
+                    )
+                }
+                _ => (Some(vec![unmocked(request)]), Duration::ZERO),
+            };
+        };
+
+        if let Some(remaining) = &mut rule.remaining {
+            *remaining -= 1;
+        }
+        match &rule.reply {
+            Reply::Frames(frames) => (Some(frames.clone()), rule.delay),
+            Reply::With(responder) => (Some(vec![responder(request)]), rule.delay),
+            Reply::HangUp => (None, rule.delay),
+        }
+    }
+}
+
+fn unmocked(request: &DaemonRequest) -> DaemonResponse {
+    DaemonResponse::Error {
+        code: UNMOCKED.to_string(),
+        message: format!("No mock rule for {:?}", request),
+    }
+}
+
+/// Mock daemon serving on a temporary Unix socket until dropped
+pub struct MockDaemon {
+    dir: PathBuf,
+    socket_path: PathBuf,
+    state: Arc<Mutex<MockState>>,
+    server: JoinHandle<()>,
+}
+
+impl MockDaemon {
+    /// Bind a fresh socket and start serving. Without rules every request
+    /// (except `Hello` and `Batch`) is answered with an [`UNMOCKED`] error.
+    pub async fn start() -> std::io::Result<Self> {
+        let dir = std::env::temp_dir().join(format!("hive-mock-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir)?;
+        let socket_path = dir.join("hive.sock");
+        let listener = UnixListener::bind(&socket_path)?;
+
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let server = tokio::spawn(serve(listener, state.clone()));
+
+        Ok(Self {
+            dir,
+            socket_path,
+            state,
+            server,
+        })
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Client connected to this daemon
+    pub fn client(&self) -> DaemonClient {
+        DaemonClient::new(&self.socket_path)
+    }
+
+    /// Add a rule. Rules added later take precedence, so tests can override
+    /// fixtures.
+    pub fn mock(&self, rule: Rule) -> &Self {
+        self.state.lock().unwrap().rules.push(rule);
+        self
+    }
+
+    pub fn mock_all(&self, rules: impl IntoIterator<Item = Rule>) -> &Self {
+        self.state.lock().unwrap().rules.extend(rules);
+        self
+    }
+
+    /// Requests received so far, in order across connections
+    pub fn requests(&self) -> Vec<DaemonRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Number of received requests matching `filter`
+    pub fn received(&self, filter: impl Fn(&DaemonRequest) -> bool) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|r| filter(r))
+            .count()
+    }
+
+    /// Forget rules and captured requests
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.rules.clear();
+        state.requests.clear();
+    }
+}
+
+impl Drop for MockDaemon {
+    fn drop(&mut self) {
+        self.server.abort();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+async fn serve(listener: UnixListener, state: Arc<Mutex<MockState>>) {
+    let mut connections = tokio::task::JoinSet::new();
+    while let Ok((stream, _)) = listener.accept().await {
+        connections.spawn(handle_connection(stream, state.clone()));
+    }
+}
+
+async fn handle_connection(stream: UnixStream, state: Arc<Mutex<MockState>>) {
+    let (r, w) = stream.into_split();
+    let (mut reader, mut writer) = (FrameReader::new(r), FrameWriter::new(w));
+
+    while let Ok(Some(request)) = reader.read::<DaemonRequest>().await {
+        // Negotiate like the daemon; the handshake is not a captured request
+        if let DaemonRequest::Hello { formats } = &request {
+            let format = if formats.contains(&WireFormat::Binary) {
+                WireFormat::Binary
+            } else {
+                WireFormat::Json
+            };
+            if writer
+                .send(&DaemonResponse::Hello { format })
+                .await
+                .is_err()
+            {
+                return;
+            }
+            reader.set_format(format);
+            writer.set_format(format);
+            continue;
+        }
+
+        let (frames, delay) = {
+            let mut state = state.lock().unwrap();
+            state.requests.push(request.clone());
+            state.reply_to(&request)
+        };
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        let Some(frames) = frames else {
+            return;
+        };
+        for frame in &frames {
+            if writer.send(frame).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Sample data and rules for the standard flows
+pub mod fixtures {
+    use super::Rule;
+    use crate::{
+        DaemonRequest, DaemonResponse, DaemonStatus, LogLine, ServiceStatus, SourceInfo,
+        SourceStatus, SourceType,
+    };
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    /// Stream id used by [`log_stream`]
+    pub const STREAM_ID: Uuid = Uuid::from_u128(0x11ad_e000_0000_4000_8000_0000_0000_0001);
+
+    /// Running daemon with the given services
+    pub fn status(services: &[ServiceStatus]) -> DaemonStatus {
+        let mut sources: Vec<&str> = services.iter().map(|s| s.source.as_str()).collect();
+        sources.sort_unstable();
+        sources.dedup();
+
+        DaemonStatus {
+            running: true,
+            pid: Some(4242),
+            version: "0.0.0-mock".to_string(),
+            source_count: sources.len(),
+            running_services: services.iter().filter(|s| s.state == "running").count(),
+            total_services: services.len(),
+            proxy_addresses: vec!["127.0.0.1:8080".to_string()],
+            uptime_secs: 60,
+            maintenance: None,
+            singletons: Vec::new(),
+        }
+    }
+
+    /// Service `source:name` in `state`
+    pub fn service(fqn: &str, state: &str) -> ServiceStatus {
+        let (source, name) = fqn.split_once(':').unwrap_or(("default", fqn));
+        ServiceStatus {
+            fqn: format!("{}:{}", source, name),
+            source: source.to_string(),
+            name: name.to_string(),
+            state: state.to_string(),
+            healthy: (state == "running").then_some(true),
+            pid: (state == "running").then_some(10_000),
+            container_id: None,
+            started_at: (state == "running").then(Utc::now),
+            ports: HashMap::new(),
+            port_conflicts: Vec::new(),
+            restart_count: 0,
+            last_probe: None,
+        }
+    }
+
+    /// Sources of `services`; running when any of their services runs
+    pub fn sources(services: &[ServiceStatus]) -> Vec<SourceInfo> {
+        let mut names: Vec<&str> = services.iter().map(|s| s.source.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+
+        names
+            .into_iter()
+            .map(|name| {
+                let members = services.iter().filter(|s| s.source == name);
+                SourceInfo {
+                    name: name.to_string(),
+                    path: format!("/mock/{}", name).into(),
+                    source_type: SourceType::Yaml,
+                    enabled: true,
+                    service_count: members.clone().count(),
+                    status: if members.clone().any(|s| s.state == "running") {
+                        SourceStatus::Running
+                    } else {
+                        SourceStatus::Loaded
+                    },
+                    auto_reload: false,
+                }
+            })
+            .collect()
+    }
+
+    pub fn log_line(fqn: &str, message: &str) -> LogLine {
+        LogLine {
+            timestamp: Utc::now(),
+            level: "info".to_string(),
+            service_fqn: fqn.to_string(),
+            message: message.to_string(),
+            fields: None,
+        }
+    }
+
+    /// Frames of a complete log stream: `StreamStarted`, one `LogStream` per
+    /// line, then `StreamEnded`
+    pub fn log_stream(lines: Vec<LogLine>) -> Vec<DaemonResponse> {
+        let mut frames = vec![DaemonResponse::StreamStarted {
+            stream_id: STREAM_ID,
+        }];
+        frames.extend(lines.into_iter().map(|line| DaemonResponse::LogStream {
+            stream_id: STREAM_ID,
+            line,
+        }));
+        frames.push(DaemonResponse::StreamEnded {
+            stream_id: STREAM_ID,
+        });
+        frames
+    }
+
+    /// Answer `StreamLogs` with `lines`, and `StopLogStream` with `Ok`
+    pub fn log_stream_rules(lines: Vec<LogLine>) -> Vec<Rule> {
+        vec![
+            Rule::when(|r| matches!(r, DaemonRequest::StreamLogs { .. }))
+                .respond_all(log_stream(lines)),
+            Rule::when(|r| matches!(r, DaemonRequest::StopLogStream { .. })),
+        ]
+    }
+
+    /// Ping, status, service and source listing, service lookup, and
+    /// start/stop/restart of services and sources, for a daemon running
+    /// `services`
+    pub fn standard_rules(services: Vec<ServiceStatus>) -> Vec<Rule> {
+        let status = status(&services);
+        let sources = sources(&services);
+        let listed = services.clone();
+
+        vec![
+            Rule::when(|r| matches!(r, DaemonRequest::Ping)).respond(DaemonResponse::Pong),
+            Rule::when(|r| matches!(r, DaemonRequest::Status))
+                .respond(DaemonResponse::Status(status)),
+            Rule::when(|r| matches!(r, DaemonRequest::ListSources))
+                .respond(DaemonResponse::Sources { sources }),
+            Rule::when(|r| {
+                matches!(
+                    r,
+                    DaemonRequest::StartService { .. }
+                        | DaemonRequest::StopService { .. }
+                        | DaemonRequest::RestartService { .. }
+                        | DaemonRequest::StartSource { .. }
+                        | DaemonRequest::StopSource { .. }
+                )
+            }),
+            Rule::when(|r| matches!(r, DaemonRequest::ListServices { .. })).respond_with(
+                move |r| {
+                    let source = match r {
+                        DaemonRequest::ListServices { source } => source.as_deref(),
+                        _ => None,
+                    };
+                    let services = listed
+                        .iter()
+                        .filter(|s| source.is_none_or(|source| s.source == source))
+                        .cloned()
+                        .collect();
+                    DaemonResponse::Services { services }
+                },
+            ),
+            Rule::when(|r| matches!(r, DaemonRequest::GetServiceStatus { .. })).respond_with(
+                move |r| {
+                    let fqn = match r {
+                        DaemonRequest::GetServiceStatus { fqn } => fqn.as_str(),
+                        _ => "",
+                    };
+                    match services.iter().find(|s| s.fqn == fqn) {
+                        Some(service) => DaemonResponse::Service {
+                            service: service.clone(),
+                        },
+                        None => DaemonResponse::Error {
+                            code: "NOT_FOUND".to_string(),
+                            message: format!("Service '{}' not found", fqn),
+                        },
+                    }
+                },
+            ),
+        ]
+    }
+}
@@ -0,0 +1,190 @@
+//! The mock daemon, driven through a real `DaemonClient`
+
+use std::time::Duration;
+
+use lib_hive_daemon_client::mock::{fixtures, MockDaemon, Rule, UNMOCKED};
+use lib_hive_daemon_client::{
+    CancellationToken, DaemonClientError, DaemonRequest, DaemonResponse, RetryPolicy,
+    DEFAULT_RECONNECT_POLICY,
+};
+
+#[tokio::test]
+async fn test_standard_flows() {
+    let daemon = MockDaemon::start().await.unwrap();
+    daemon.mock_all(fixtures::standard_rules(vec![
+        fixtures::service("app:api", "running"),
+        fixtures::service("app:db", "stopped"),
+        fixtures::service("tools:docs", "running"),
+    ]));
+    let client = daemon.client();
+
+    assert!(client.ping().await.unwrap());
+    let status = client.status().await.unwrap();
+    assert_eq!(status.source_count, 2);
+    assert_eq!(status.running_services, 2);
+
+    assert_eq!(client.list_services(None).await.unwrap().len(), 3);
+    assert_eq!(client.list_services(Some("app")).await.unwrap().len(), 2);
+    let db = client.get_service_status("app:db").await.unwrap().unwrap();
+    assert_eq!(db.state, "stopped");
+    assert!(client
+        .get_service_status("app:gone")
+        .await
+        .unwrap()
+        .is_none());
+
+    client.start_service("app:db").await.unwrap();
+    client.stop_source("app").await.unwrap();
+    assert_eq!(
+        daemon.received(|r| matches!(r, DaemonRequest::StartService { fqn } if fqn == "app:db")),
+        1
+    );
+
+    let err = client.get_logs(None, None, None, None).await.unwrap_err();
+    assert_eq!(err.code(), Some(UNMOCKED));
+}
+
+#[tokio::test]
+async fn test_log_streaming() {
+    let daemon = MockDaemon::start().await.unwrap();
+    daemon.mock_all(fixtures::log_stream_rules(vec![
+        fixtures::log_line("app:api", "listening"),
+        fixtures::log_line("app:api", "ready"),
+    ]));
+
+    let mut stream = daemon
+        .client()
+        .stream_logs(Some("app:api"), None)
+        .await
+        .unwrap();
+    assert_eq!(stream.stream_id(), fixtures::STREAM_ID);
+    assert_eq!(stream.recv().await.unwrap().unwrap().message, "listening");
+    assert_eq!(stream.recv().await.unwrap().unwrap().message, "ready");
+    assert!(stream.recv().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_cancellation() {
+    let daemon = MockDaemon::start().await.unwrap();
+    daemon.mock_all(fixtures::standard_rules(vec![
+        fixtures::service("app:api", "running"),
+        fixtures::service("web:ui", "stopped"),
+    ]));
+    // A stream that never ends on its own
+    daemon.mock(
+        Rule::when(|r| matches!(r, DaemonRequest::StreamLogs { .. })).respond_all(vec![
+            DaemonResponse::StreamStarted {
+                stream_id: fixtures::STREAM_ID,
+            },
+            DaemonResponse::LogStream {
+                stream_id: fixtures::STREAM_ID,
+                line: fixtures::log_line("app:api", "listening"),
+            },
+        ]),
+    );
+    daemon.mock(Rule::when(|r| {
+        matches!(r, DaemonRequest::StopLogStream { .. })
+    }));
+    let client = daemon.client();
+
+    let token = CancellationToken::new();
+    let mut stream = client
+        .stream_logs(Some("app:api"), None)
+        .await
+        .unwrap()
+        .cancel_on(token.clone());
+    assert_eq!(stream.recv().await.unwrap().unwrap().message, "listening");
+    token.cancel();
+    assert!(stream.recv().await.unwrap().is_none());
+    assert!(stream.recv().await.unwrap().is_none());
+
+    daemon.mock(
+        Rule::when(|r| matches!(r, DaemonRequest::StartSource { .. }))
+            .delay(Duration::from_secs(60))
+            .respond(DaemonResponse::Ok { message: None }),
+    );
+    // Only a source the cancelled call started is stopped again
+    for (source, stops) in [("web", 1), ("app", 0)] {
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            cancel.cancel();
+        });
+        let err = client
+            .start_source_cancellable(source, &token, |_| {})
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DaemonClientError::Cancelled));
+        assert_eq!(
+            daemon.received(
+                |r| matches!(r, DaemonRequest::StopSource { name, .. } if name == source)
+            ),
+            stops
+        );
+    }
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        daemon.received(|r| matches!(r, DaemonRequest::StopLogStream { .. })),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_overrides_and_error_injection() {
+    let daemon = MockDaemon::start().await.unwrap();
+    daemon.mock_all(fixtures::standard_rules(vec![fixtures::service(
+        "app:api", "running",
+    )]));
+    daemon.mock(
+        Rule::when(|r| matches!(r, DaemonRequest::StartService { .. }))
+            .error("START_SERVICE_FAILED", "boom")
+            .times(1),
+    );
+    let client = daemon.client();
+
+    let err = client.start_service("app:api").await.unwrap_err();
+    assert_eq!(err.code(), Some("START_SERVICE_FAILED"));
+    // The override is used up; the fixture answers again
+    client.start_service("app:api").await.unwrap();
+
+    daemon.mock(
+        Rule::when(|r| matches!(r, DaemonRequest::Status))
+            .delay(Duration::from_millis(200))
+            .respond(DaemonResponse::Pong)
+            .times(1),
+    );
+    let err = client
+        .request_with_timeout(DaemonRequest::Status, Duration::from_millis(20))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DaemonClientError::Timeout(_)));
+    // The late reply to the timed-out request is not taken for this one
+    assert_eq!(client.status().await.unwrap().version, "0.0.0-mock");
+
+    // A dropped connection is retried for idempotent requests. Rules are
+    // tried newest first, so the hang-up fires before the answer.
+    daemon.mock(
+        Rule::when(|r| matches!(r, DaemonRequest::ListExposed)).respond(DaemonResponse::Exposed {
+            exposed: Vec::new(),
+        }),
+    );
+    let client = daemon.client().with_reconnect_policy(RetryPolicy {
+        initial_delay: Duration::from_millis(10),
+        ..DEFAULT_RECONNECT_POLICY
+    });
+    daemon.mock(
+        Rule::when(|r| matches!(r, DaemonRequest::ListExposed))
+            .hang_up()
+            .times(1),
+    );
+    assert!(client.list_exposed().await.unwrap().is_empty());
+    assert_eq!(
+        daemon.received(|r| matches!(r, DaemonRequest::ListExposed)),
+        2
+    );
+
+    daemon.reset();
+    assert!(daemon.requests().is_empty());
+}
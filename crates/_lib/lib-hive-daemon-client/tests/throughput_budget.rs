@@ -0,0 +1,78 @@
+//! Size and scaling budgets for the daemon wire protocol
+//!
+//! Frame size budgets and the timing-based scaling check run with the regular
+//! test suite; for absolute numbers run `cargo bench -p lib-hive-daemon-client`.
+
+use lib_hive_daemon_client::chrono::{TimeZone, Utc};
+use lib_hive_daemon_client::frame::encode;
+use lib_hive_daemon_client::uuid::Uuid;
+use lib_hive_daemon_client::{DaemonResponse, LogLine, WireFormat};
+use lib_timing_budget::{assert_scales_linearly, best_of};
+
+/// Bytes a `LogStream` frame may add around the log message itself
+const MAX_LOG_FRAME_OVERHEAD: usize = 256;
+
+fn log_stream(message_len: usize) -> DaemonResponse {
+    DaemonResponse::LogStream {
+        stream_id: Uuid::nil(),
+        line: LogLine {
+            timestamp: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+            level: "info".to_string(),
+            service_fqn: "default:api".to_string(),
+            message: "x".repeat(message_len),
+            fields: None,
+        },
+    }
+}
+
+fn frame_len(format: WireFormat, msg: &DaemonResponse) -> usize {
+    let mut buf = Vec::new();
+    encode(format, msg, &mut buf).unwrap();
+    buf.len()
+}
+
+#[test]
+fn test_log_frame_overhead_is_bounded() {
+    for format in [WireFormat::Json, WireFormat::Binary] {
+        for len in [0, 80, 64 * 1024] {
+            let overhead = frame_len(format, &log_stream(len)) - len;
+            assert!(
+                overhead <= MAX_LOG_FRAME_OVERHEAD,
+                "{:?} LogStream frame adds {} bytes around a {} byte message",
+                format,
+                overhead,
+                len
+            );
+        }
+    }
+}
+
+#[test]
+fn test_binary_frames_are_not_larger_than_json() {
+    for len in [0, 80, 4096] {
+        let msg = log_stream(len);
+        assert!(frame_len(WireFormat::Binary, &msg) <= frame_len(WireFormat::Json, &msg));
+    }
+}
+
+#[test]
+fn test_codec_scales_linearly() {
+    for format in [WireFormat::Json, WireFormat::Binary] {
+        assert_scales_linearly(
+            &format!("{:?} codec", format),
+            64 * 1024,
+            1024 * 1024,
+            |len| {
+                let msg = log_stream(len);
+                let mut buf = Vec::new();
+                best_of(5, || {
+                    encode(format, &msg, &mut buf).unwrap();
+                    let _: DaemonResponse = match format {
+                        WireFormat::Json => serde_json::from_slice(&buf).unwrap(),
+                        WireFormat::Binary => rmp_serde::from_slice(&buf[4..]).unwrap(),
+                    };
+                })
+            },
+        );
+    }
+}
@@ -230,6 +230,9 @@ pub struct CliResult {
 
     /// Standard error
     pub stderr: String,
+
+    /// Structured failure, so the host can render hints (`stderr` holds the plain rendering)
+    pub error: Option<CliError>,
 }
 
 impl CliResult {
@@ -239,6 +242,7 @@ impl CliResult {
             exit_code: 0,
             stdout: output.into(),
             stderr: String::new(),
+            error: None,
         }
     }
 
@@ -248,6 +252,18 @@ impl CliResult {
             exit_code: 1,
             stdout: String::new(),
             stderr: message.into(),
+            error: None,
+        }
+    }
+
+    /// Create a failed result from a structured error, exiting with its code
+    pub fn failure(error: impl Into<CliError>) -> Self {
+        let error = error.into();
+        Self {
+            exit_code: error.code.exit_code(),
+            stdout: String::new(),
+            stderr: error.render(),
+            error: Some(error),
         }
     }
 
@@ -257,6 +273,228 @@ impl CliResult {
             exit_code,
             stdout: stdout.into(),
             stderr: stderr.into(),
+            error: None,
+        }
+    }
+}
+
+impl From<CliError> for CliResult {
+    fn from(error: CliError) -> Self {
+        Self::failure(error)
+    }
+}
+
+/// Failure category of a CLI command, mapped to a stable process exit code
+/// so scripts can branch on the kind of failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CliErrorCode {
+    /// Uncategorized failure (exit code 1)
+    General,
+    /// Missing or invalid configuration (exit code 2)
+    Config,
+    /// Network or remote service failure (exit code 3)
+    Network,
+    /// Requested resource does not exist (exit code 4)
+    NotFound,
+    /// Invalid arguments or input (exit code 5)
+    InvalidInput,
+    /// Not authenticated or not allowed (exit code 6)
+    PermissionDenied,
+    /// Resource already exists or is in a conflicting state (exit code 7)
+    Conflict,
+    /// Required local service (daemon, runtime) is not running (exit code 8)
+    Unavailable,
+}
+
+impl CliErrorCode {
+    pub const ALL: [CliErrorCode; 8] = [
+        Self::General,
+        Self::Config,
+        Self::Network,
+        Self::NotFound,
+        Self::InvalidInput,
+        Self::PermissionDenied,
+        Self::Conflict,
+        Self::Unavailable,
+    ];
+
+    /// Process exit code for this category
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::General => 1,
+            Self::Config => 2,
+            Self::Network => 3,
+            Self::NotFound => 4,
+            Self::InvalidInput => 5,
+            Self::PermissionDenied => 6,
+            Self::Conflict => 7,
+            Self::Unavailable => 8,
+        }
+    }
+}
+
+/// Structured CLI command error
+///
+/// Plugin commands return this instead of a plain string to get a
+/// categorized exit code and an optional remediation hint and docs link.
+///
+/// ```rust
+/// use lib_plugin_abi_v3::cli::{CliError, CliErrorCode};
+///
+/// let err = CliError::not_found("Task #42 not found").with_hint("List tasks with: adi tasks list");
+/// assert_eq!(err.code.exit_code(), 4);
+/// assert_eq!(err.code, CliErrorCode::NotFound);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CliError {
+    pub code: CliErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_url: Option<String>,
+}
+
+impl CliError {
+    pub fn new(code: CliErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            hint: None,
+            docs_url: None,
+        }
+    }
+
+    pub fn general(message: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::General, message)
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::Config, message)
+    }
+
+    pub fn network(message: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::Network, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::InvalidInput, message)
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::PermissionDenied, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::Conflict, message)
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::Unavailable, message)
+    }
+
+    /// Add a remediation hint (e.g. the command that fixes the problem)
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Add a link to documentation about the failure
+    pub fn with_docs_url(mut self, url: impl Into<String>) -> Self {
+        self.docs_url = Some(url.into());
+        self
+    }
+
+    /// Plain-text rendering: the message, then `hint:` and `docs:` lines
+    pub fn render(&self) -> String {
+        let mut out = self.message.clone();
+        if let Some(hint) = &self.hint {
+            out.push_str(&format!("\nhint: {hint}"));
+        }
+        if let Some(url) = &self.docs_url {
+            out.push_str(&format!("\ndocs: {url}"));
         }
+        out
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Plain string errors stay uncategorized (exit code 1)
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        Self::general(message)
+    }
+}
+
+impl From<&str> for CliError {
+    fn from(message: &str) -> Self {
+        Self::general(message)
+    }
+}
+
+impl From<crate::PluginError> for CliError {
+    fn from(error: crate::PluginError) -> Self {
+        use crate::PluginError as E;
+        let code = match &error {
+            E::Config(_) => CliErrorCode::Config,
+            E::NotFound(_) => CliErrorCode::NotFound,
+            E::InvalidInput(_) => CliErrorCode::InvalidInput,
+            E::HttpRequestFailed(_) => CliErrorCode::Network,
+            _ => CliErrorCode::General,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let codes: std::collections::HashSet<i32> =
+            CliErrorCode::ALL.iter().map(|c| c.exit_code()).collect();
+        assert_eq!(codes.len(), CliErrorCode::ALL.len());
+        assert!(!codes.contains(&0));
+        assert_eq!(CliErrorCode::Config.exit_code(), 2);
+        assert_eq!(CliErrorCode::Network.exit_code(), 3);
+        assert_eq!(CliErrorCode::NotFound.exit_code(), 4);
+    }
+
+    #[test]
+    fn test_failure_result() {
+        let result = CliResult::failure(
+            CliError::config("Missing API key")
+                .with_hint("Set it with: adi config set api_key <key>")
+                .with_docs_url("https://docs.example.com/config"),
+        );
+        assert_eq!(result.exit_code, 2);
+        assert_eq!(
+            result.stderr,
+            "Missing API key\nhint: Set it with: adi config set api_key <key>\ndocs: https://docs.example.com/config"
+        );
+        assert_eq!(result.error.map(|e| e.code), Some(CliErrorCode::Config));
+
+        // Plain strings keep the old behaviour
+        let result = CliResult::failure("boom");
+        assert_eq!((result.exit_code, result.stderr.as_str()), (1, "boom"));
+    }
+
+    #[test]
+    fn test_error_json_omits_empty_fields() {
+        let json = serde_json::to_value(CliError::not_found("no such task")).unwrap();
+        assert_eq!(json, serde_json::json!({ "code": "not_found", "message": "no such task" }));
     }
 }
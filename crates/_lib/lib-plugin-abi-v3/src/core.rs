@@ -5,6 +5,7 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Base trait that all plugins must implement
 #[async_trait]
@@ -210,6 +211,9 @@ pub struct PluginContext {
 
     /// Plugin configuration from config.toml
     pub config: Value,
+
+    /// Other plugins' services, when the host offers them
+    pub services: Option<Arc<dyn crate::services::PluginServices>>,
 }
 
 impl PluginContext {
@@ -225,8 +229,15 @@ impl PluginContext {
             data_dir,
             config_dir,
             config,
+            services: None,
         }
     }
+
+    /// Let the plugin reach other plugins' services through `services`
+    pub fn with_services(mut self, services: Arc<dyn crate::services::PluginServices>) -> Self {
+        self.services = Some(services);
+        self
+    }
 }
 
 /// Plugin events
@@ -240,6 +251,9 @@ pub enum PluginEvent {
     /// Host is shutting down
     HostShutdown,
 
+    /// Active project changed (`None` when outside any project)
+    ProjectChanged(Option<crate::project::ProjectContext>),
+
     /// Custom event
     Custom {
         event_type: String,
@@ -92,10 +92,16 @@ pub mod hooks;
 
 pub mod logs;
 
+pub mod search;
+
+pub mod project;
+
 pub mod webrtc;
 
 pub mod daemon;
 
+pub mod services;
+
 mod error;
 pub use error::{PluginError, Result};
 
@@ -123,6 +129,8 @@ pub const SERVICE_PROXY_MIDDLEWARE: &str = "orchestration.proxy";
 pub const SERVICE_OBSERVABILITY_SINK: &str = "orchestration.obs";
 pub const SERVICE_ROLLOUT_STRATEGY: &str = "orchestration.rollout";
 pub const SERVICE_LOG_PROVIDER: &str = "logs.provider";
+pub const SERVICE_SEARCH_PROVIDER: &str = "search.provider";
 pub const SERVICE_WEBRTC_HANDLERS: &str = "webrtc.handlers";
 pub const SERVICE_DAEMON_SERVICE: &str = "daemon.service";
 pub const SERVICE_GLOBAL_COMMANDS: &str = "cli.global";
+pub const SERVICE_ADI_SERVICE: &str = "adi.service";
@@ -0,0 +1,118 @@
+//! Project context shared between the host and plugins
+//!
+//! The host detects which project the user is working in and sends it to
+//! every plugin as [`PluginEvent::ProjectChanged`](crate::PluginEvent), so
+//! plugins don't each have to guess from the working directory.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Directory that marks an ADI project root
+pub const ADI_PROJECT_DIR: &str = ".adi";
+
+/// Directory that marks a git repository root
+const GIT_DIR: &str = ".git";
+
+/// How a project root was recognized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectMarker {
+    /// Contains an `.adi/` directory
+    Adi,
+    /// Git repository root
+    Git,
+    /// Plain directory selected explicitly
+    Directory,
+}
+
+impl std::fmt::Display for ProjectMarker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectMarker::Adi => write!(f, "adi"),
+            ProjectMarker::Git => write!(f, "git"),
+            ProjectMarker::Directory => write!(f, "directory"),
+        }
+    }
+}
+
+/// The project plugins should operate on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectContext {
+    /// Project root directory
+    pub root: PathBuf,
+    pub marker: ProjectMarker,
+    /// Selected with `adi context use` rather than detected from the working directory
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl ProjectContext {
+    /// Detect the project containing `start` by walking up to the nearest
+    /// directory with an `.adi/` directory or `.git` entry. At the same level
+    /// `.adi/` wins over `.git`.
+    pub fn detect(start: &Path) -> Option<Self> {
+        start.ancestors().find_map(|dir| {
+            let marker = if dir.join(ADI_PROJECT_DIR).is_dir() {
+                ProjectMarker::Adi
+            } else if dir.join(GIT_DIR).exists() {
+                ProjectMarker::Git
+            } else {
+                return None;
+            };
+            Some(Self {
+                root: dir.to_path_buf(),
+                marker,
+                pinned: false,
+            })
+        })
+    }
+
+    /// Project-local ADI directory (`<root>/.adi`), which may not exist yet
+    pub fn adi_dir(&self) -> PathBuf {
+        self.root.join(ADI_PROJECT_DIR)
+    }
+
+    /// Project name, taken from the root directory name
+    pub fn name(&self) -> String {
+        self.root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.root.display().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_nearest_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let nested = repo.join("services/api");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(nested.join("src")).unwrap();
+
+        let ctx = ProjectContext::detect(&nested.join("src")).unwrap();
+        assert_eq!(ctx.root, repo);
+        assert_eq!(ctx.marker, ProjectMarker::Git);
+        assert!(!ctx.pinned);
+
+        std::fs::create_dir(nested.join(ADI_PROJECT_DIR)).unwrap();
+        let ctx = ProjectContext::detect(&nested.join("src")).unwrap();
+        assert_eq!(ctx.root, nested);
+        assert_eq!(ctx.marker, ProjectMarker::Adi);
+        assert_eq!(ctx.name(), "api");
+    }
+
+    #[test]
+    fn test_adi_marker_wins_at_same_level() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        std::fs::create_dir(dir.path().join(ADI_PROJECT_DIR)).unwrap();
+
+        let ctx = ProjectContext::detect(dir.path()).unwrap();
+        assert_eq!(ctx.marker, ProjectMarker::Adi);
+        assert_eq!(ctx.adi_dir(), dir.path().join(".adi"));
+    }
+}
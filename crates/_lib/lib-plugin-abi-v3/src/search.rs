@@ -0,0 +1,132 @@
+//! Cross-plugin search service trait
+//!
+//! Plugins that own searchable data (tasks, tools, ...) implement
+//! [`SearchProvider`] so `adi search` can fan a query out to all of them and
+//! merge the results into one ranked list.
+
+use crate::{Plugin, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Query sent to every search provider
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub text: String,
+    /// Maximum hits to return from this provider
+    pub limit: usize,
+}
+
+/// A single search hit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// Result type tag used for display and `--type` filtering (e.g. "tasks")
+    pub kind: String,
+    /// Provider-specific identifier
+    pub id: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// Relevance in `0.0..=1.0`; providers should normalize so results merge sensibly
+    pub score: f32,
+    /// Command that opens the hit (e.g. "adi tasks show 12")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_command: Option<String>,
+}
+
+impl SearchHit {
+    pub fn new(
+        kind: impl Into<String>,
+        id: impl Into<String>,
+        title: impl Into<String>,
+        score: f32,
+    ) -> Self {
+        Self {
+            kind: kind.into(),
+            id: id.into(),
+            title: title.into(),
+            snippet: None,
+            score: score.clamp(0.0, 1.0),
+            open_command: None,
+        }
+    }
+
+    pub fn with_snippet(mut self, snippet: impl Into<String>) -> Self {
+        self.snippet = Some(snippet.into());
+        self
+    }
+
+    pub fn with_open_command(mut self, command: impl Into<String>) -> Self {
+        self.open_command = Some(command.into());
+        self
+    }
+}
+
+/// Score for the hit at `rank` (0-based) out of `total`, for providers whose
+/// backend returns ordered results without scores. Maps to `0.5..=1.0`.
+pub fn rank_score(rank: usize, total: usize) -> f32 {
+    if total <= 1 {
+        return 1.0;
+    }
+    1.0 - 0.5 * (rank as f32 / (total - 1) as f32)
+}
+
+/// Merge hits from several providers: keep only `types` (all when empty),
+/// order by score (ties by type, then title) and keep the top `limit`.
+pub fn merge_hits(mut hits: Vec<SearchHit>, types: &[String], limit: usize) -> Vec<SearchHit> {
+    hits.retain(|hit| types.is_empty() || types.iter().any(|t| t == &hit.kind));
+    hits.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.kind.cmp(&b.kind))
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    hits.truncate(limit);
+    hits
+}
+
+/// Service trait for plugins that expose searchable content
+#[async_trait]
+pub trait SearchProvider: Plugin {
+    /// Result types this provider returns, matched against `--type` filters
+    fn search_types(&self) -> Vec<String>;
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_score_spans_upper_half() {
+        assert_eq!(rank_score(0, 1), 1.0);
+        assert_eq!(rank_score(0, 5), 1.0);
+        assert_eq!(rank_score(4, 5), 0.5);
+        assert!(rank_score(1, 5) > rank_score(2, 5));
+    }
+
+    #[test]
+    fn test_hit_score_is_clamped() {
+        assert_eq!(SearchHit::new("tools", "x", "x", 3.0).score, 1.0);
+        assert_eq!(SearchHit::new("tools", "x", "x", -1.0).score, 0.0);
+    }
+
+    #[test]
+    fn test_merge_filters_and_ranks() {
+        let hits = vec![
+            SearchHit::new("tasks", "1", "Fix login", 0.6),
+            SearchHit::new("tools", "docker-ps", "docker ps", 0.9),
+            SearchHit::new("kb", "n1", "Login flow", 0.95),
+            SearchHit::new("tasks", "2", "Add logout", 0.8),
+        ];
+
+        let merged = merge_hits(hits.clone(), &[], 3);
+        let ids: Vec<_> = merged.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, ["n1", "docker-ps", "2"]);
+
+        let types = vec!["tasks".to_string(), "tools".to_string()];
+        let merged = merge_hits(hits, &types, 10);
+        let ids: Vec<_> = merged.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, ["docker-ps", "2", "1"]);
+    }
+}
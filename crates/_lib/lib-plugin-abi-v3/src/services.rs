@@ -0,0 +1,29 @@
+//! Services plugins offer each other
+//!
+//! A plugin that owns data other plugins work with (tasks, ...) implements
+//! [`AdiServiceProvider`] and exports it as `plugin_create_adi_service`. Its
+//! [`AdiService`] API is the contract; callers never link the owner's crates
+//! or open its storage. Other plugins reach it through the
+//! [`PluginServices`] the host puts in their [`PluginContext`](crate::PluginContext),
+//! which loads the owner on demand and picks the active project.
+
+use crate::project::ProjectContext;
+use crate::{Plugin, Result};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+pub use lib_adi_service::AdiService;
+
+/// Service trait for plugins that serve their ADI service API to other plugins
+#[async_trait]
+pub trait AdiServiceProvider: Plugin {
+    /// The service for `project`, or the global scope outside any project
+    async fn adi_service(&self, project: Option<&ProjectContext>) -> Result<Arc<dyn AdiService>>;
+}
+
+/// Host side: other plugins' services, as seen from one plugin
+#[async_trait]
+pub trait PluginServices: Send + Sync {
+    /// ADI service of `plugin_id` for the active project
+    async fn adi_service(&self, plugin_id: &str) -> Result<Arc<dyn AdiService>>;
+}
@@ -145,6 +145,42 @@ pub fn rebuild_index(plugins_dir: &Path) -> Result<(), HostError> {
     Ok(())
 }
 
+/// Check whether the command index is out of date with installed plugins.
+///
+/// Stale when the `commands/` directory is missing or a plugin's `latest` symlink
+/// does not point to the version in its `.version` file (e.g. a plugin was
+/// installed or updated by an older host). Only reads `.version` files and links,
+/// no manifests are parsed.
+pub fn is_stale(plugins_dir: &Path) -> bool {
+    if !commands_dir(plugins_dir).is_dir() {
+        return true;
+    }
+
+    let Ok(entries) = std::fs::read_dir(plugins_dir) else {
+        return false;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name() == COMMANDS_DIR_NAME {
+            continue;
+        }
+
+        let path = entry.path();
+        let Ok(version) = std::fs::read_to_string(path.join(".version")) else {
+            continue;
+        };
+        let link_path = path.join(LATEST_LINK_NAME);
+        #[cfg(unix)]
+        let linked = std::fs::read_link(&link_path).ok();
+        #[cfg(windows)]
+        let linked = std::fs::read_to_string(&link_path).ok().map(PathBuf::from);
+        if linked.as_deref() != Some(Path::new(version.trim())) {
+            return true;
+        }
+    }
+
+    false
+}
+
 /// Resolve a command name to its plugin manifest path via the index.
 ///
 /// Returns `Some(absolute_path_to_plugin_toml)` if the symlink chain resolves.
@@ -382,6 +418,37 @@ name = "plugin"
         assert!(plugins_dir.join("adi.tasks").join(LATEST_LINK_NAME).is_symlink());
     }
 
+    #[test]
+    fn test_is_stale() {
+        let tmp = tempfile::tempdir().unwrap();
+        let plugins_dir = tmp.path();
+
+        assert!(is_stale(plugins_dir));
+
+        write_plugin_toml(plugins_dir, "adi.hive", "0.8.8", "hive", &[]);
+        rebuild_index(plugins_dir).unwrap();
+        assert!(!is_stale(plugins_dir));
+
+        // `.version` bumped without touching the index
+        write_plugin_toml(plugins_dir, "adi.hive", "0.9.0", "hive", &[]);
+        assert!(is_stale(plugins_dir));
+
+        rebuild_index(plugins_dir).unwrap();
+        assert!(!is_stale(plugins_dir));
+    }
+
+    #[test]
+    fn test_is_stale_new_plugin_without_link() {
+        let tmp = tempfile::tempdir().unwrap();
+        let plugins_dir = tmp.path();
+
+        write_plugin_toml(plugins_dir, "adi.hive", "0.8.8", "hive", &[]);
+        rebuild_index(plugins_dir).unwrap();
+
+        write_plugin_toml(plugins_dir, "adi.tasks", "0.5.0", "tasks", &[]);
+        assert!(is_stale(plugins_dir));
+    }
+
     #[test]
     fn test_resolve_nonexistent_command() {
         let tmp = tempfile::tempdir().unwrap();
@@ -0,0 +1,217 @@
+//! Compatibility checks between plugin styles and the v3 host.
+//!
+//! Two plugin styles are in circulation:
+//!
+//! - **SDK plugins** (`lib-plugin-prelude`, `#[command]`) export
+//!   `plugin_abi_version` and one `plugin_create_*` symbol per service.
+//! - **Raw plugins** implement the v3 traits by hand against
+//!   `lib-plugin-abi-v3` and often predate the version symbol or only export
+//!   `plugin_create`, leaving services their manifest declares unreachable.
+//!
+//! [`PluginExports::probe`] records what a library exports and [`check`]
+//! turns that plus the manifest into [`CompatIssue`]s. The loader refuses
+//! fatal issues and logs the rest; `adi plugins migrate-scaffold` uses the
+//! same report to annotate the generated v3 skeleton.
+
+use crate::loader_v3::resolve_plugin_binary;
+use crate::PluginError;
+use lib_plugin_abi_v3::PLUGIN_API_VERSION;
+use lib_plugin_manifest::PluginManifest;
+use libloading::Library;
+use std::fmt;
+use std::path::Path;
+
+/// Entry points a plugin library exports
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PluginExports {
+    /// Value of `plugin_abi_version`, if exported
+    pub abi_version: Option<u32>,
+    pub plugin_create: bool,
+    pub cli: bool,
+    pub search_provider: bool,
+    pub log_provider: bool,
+    pub daemon_service: bool,
+    pub http_routes: bool,
+    pub adi_service: bool,
+}
+
+impl PluginExports {
+    /// Inspect a loaded library without creating the plugin
+    pub fn probe(library: &Library) -> Self {
+        let has = |name: &[u8]| unsafe { library.get::<*const ()>(name).is_ok() };
+        Self {
+            abi_version: unsafe {
+                library
+                    .get::<extern "C" fn() -> u32>(b"plugin_abi_version")
+                    .ok()
+                    .map(|sym| sym())
+            },
+            plugin_create: has(b"plugin_create"),
+            cli: has(b"plugin_create_cli"),
+            search_provider: has(b"plugin_create_search_provider"),
+            log_provider: has(b"plugin_create_log_provider"),
+            daemon_service: has(b"plugin_create_daemon_service"),
+            http_routes: has(b"plugin_create_http"),
+            adi_service: has(b"plugin_create_adi_service"),
+        }
+    }
+
+    /// Load an installed plugin's library and probe it
+    pub fn probe_installed(manifest: &PluginManifest, plugin_dir: &Path) -> crate::Result<Self> {
+        let lib_path = resolve_plugin_binary(manifest, plugin_dir)?;
+        let library = unsafe { Library::new(&lib_path) }.map_err(|e| {
+            PluginError::InitFailed(format!("Failed to load library {:?}: {}", lib_path, e))
+        })?;
+        Ok(Self::probe(&library))
+    }
+
+    /// Plugin built against the SDK with a declared ABI version
+    pub fn is_sdk(&self) -> bool {
+        self.abi_version.is_some()
+    }
+}
+
+/// A mismatch between what a plugin declares, exports and the host expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompatIssue {
+    /// `plugin_create` is missing; nothing can be loaded
+    MissingCreate,
+    /// `plugin_abi_version` differs from the host's
+    AbiMismatch { plugin: u32, host: u32 },
+    /// No `plugin_abi_version`; trait layouts are assumed to match
+    UnversionedAbi,
+    /// Manifest targets an older plugin API
+    ManifestApiVersion(u32),
+    /// Manifest declares a CLI but `plugin_create_cli` is not exported
+    CliNotExported,
+}
+
+impl CompatIssue {
+    /// Issues that make loading unsafe or pointless
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Self::MissingCreate | Self::AbiMismatch { .. })
+    }
+}
+
+impl fmt::Display for CompatIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingCreate => write!(f, "missing plugin_create symbol"),
+            Self::AbiMismatch { plugin, host } => {
+                write!(f, "plugin exports ABI v{}, host expects v{}", plugin, host)
+            }
+            Self::UnversionedAbi => write!(f, "plugin does not export plugin_abi_version"),
+            Self::ManifestApiVersion(v) => {
+                write!(
+                    f,
+                    "manifest targets plugin API v{}, host is v{}",
+                    v, PLUGIN_API_VERSION
+                )
+            }
+            Self::CliNotExported => {
+                write!(
+                    f,
+                    "manifest declares a CLI but plugin_create_cli is not exported"
+                )
+            }
+        }
+    }
+}
+
+/// Compare a plugin's exports with its manifest and the host ABI
+pub fn check(manifest: &PluginManifest, exports: &PluginExports) -> Vec<CompatIssue> {
+    let mut issues = Vec::new();
+
+    if !exports.plugin_create {
+        issues.push(CompatIssue::MissingCreate);
+    }
+    match exports.abi_version {
+        Some(v) if v != PLUGIN_API_VERSION => issues.push(CompatIssue::AbiMismatch {
+            plugin: v,
+            host: PLUGIN_API_VERSION,
+        }),
+        Some(_) => {}
+        None => issues.push(CompatIssue::UnversionedAbi),
+    }
+    if manifest.compatibility.api_version < PLUGIN_API_VERSION {
+        issues.push(CompatIssue::ManifestApiVersion(
+            manifest.compatibility.api_version,
+        ));
+    }
+    let declares_cli =
+        manifest.cli.is_some() || manifest.provides.iter().any(|s| s.id.ends_with(".cli"));
+    if declares_cli && !exports.cli {
+        issues.push(CompatIssue::CliNotExported);
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(api_version: u32, cli: bool) -> PluginManifest {
+        let mut toml = format!(
+            r#"
+[plugin]
+id = "vendor.sample"
+name = "Sample"
+version = "1.0.0"
+type = "core"
+
+[compatibility]
+api_version = {}
+"#,
+            api_version
+        );
+        if cli {
+            toml.push_str("\n[cli]\ncommand = \"sample\"\ndescription = \"Sample\"\n");
+        }
+        PluginManifest::from_toml(&toml).unwrap()
+    }
+
+    fn sdk_exports() -> PluginExports {
+        PluginExports {
+            abi_version: Some(PLUGIN_API_VERSION),
+            plugin_create: true,
+            cli: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sdk_plugin_is_clean() {
+        assert!(check(&manifest(PLUGIN_API_VERSION, true), &sdk_exports()).is_empty());
+    }
+
+    #[test]
+    fn test_raw_plugin_issues() {
+        // Hand-written plugin exporting only plugin_create
+        let exports = PluginExports {
+            plugin_create: true,
+            ..Default::default()
+        };
+        let issues = check(&manifest(2, true), &exports);
+        assert_eq!(
+            issues,
+            vec![
+                CompatIssue::UnversionedAbi,
+                CompatIssue::ManifestApiVersion(2),
+                CompatIssue::CliNotExported,
+            ]
+        );
+        assert!(!issues.iter().any(CompatIssue::is_fatal));
+    }
+
+    #[test]
+    fn test_fatal_issues() {
+        let exports = PluginExports {
+            abi_version: Some(PLUGIN_API_VERSION + 1),
+            ..Default::default()
+        };
+        let issues = check(&manifest(PLUGIN_API_VERSION, false), &exports);
+        assert!(issues.contains(&CompatIssue::MissingCreate));
+        assert!(issues.iter().filter(|i| i.is_fatal()).count() == 2);
+    }
+}
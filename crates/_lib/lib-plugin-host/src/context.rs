@@ -0,0 +1,147 @@
+//! Current project context registry.
+//!
+//! Resolves the project the host should report to plugins: a project pinned
+//! with [`ProjectContextRegistry::pin`] wins, otherwise the project is
+//! detected from the working directory. The pin is persisted as JSON so it
+//! survives across CLI invocations.
+
+use crate::{HostError, Result};
+use lib_plugin_abi_v3::project::{ProjectContext, ProjectMarker};
+use std::path::{Path, PathBuf};
+
+/// File name of the persisted pin, relative to the host config directory
+pub const CONTEXT_STATE_FILE: &str = "context.json";
+
+/// Tracks the pinned project, if any.
+#[derive(Debug, Clone)]
+pub struct ProjectContextRegistry {
+    state_path: PathBuf,
+    pinned: Option<ProjectContext>,
+}
+
+impl ProjectContextRegistry {
+    /// Load the registry from `state_path`. A missing or unreadable file
+    /// means nothing is pinned.
+    pub fn load(state_path: impl Into<PathBuf>) -> Self {
+        let state_path = state_path.into();
+        let pinned =
+            std::fs::read_to_string(&state_path).ok().and_then(
+                |content| match serde_json::from_str::<ProjectContext>(&content) {
+                    Ok(ctx) => Some(ctx),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Ignoring invalid context file {}: {}",
+                            state_path.display(),
+                            e
+                        );
+                        None
+                    }
+                },
+            );
+
+        Self { state_path, pinned }
+    }
+
+    /// The pinned project, if any
+    pub fn pinned(&self) -> Option<&ProjectContext> {
+        self.pinned.as_ref()
+    }
+
+    /// The project plugins should see when running from `cwd`.
+    ///
+    /// A pinned project whose root no longer exists is ignored.
+    pub fn resolve(&self, cwd: &Path) -> Option<ProjectContext> {
+        match &self.pinned {
+            Some(ctx) if ctx.root.is_dir() => Some(ctx.clone()),
+            Some(ctx) => {
+                tracing::warn!("Pinned project {} no longer exists", ctx.root.display());
+                ProjectContext::detect(cwd)
+            }
+            None => ProjectContext::detect(cwd),
+        }
+    }
+
+    /// Pin the project containing `path` (or `path` itself if it is not inside
+    /// a project) and persist the choice.
+    pub fn pin(&mut self, path: &Path) -> Result<ProjectContext> {
+        let path = path.canonicalize()?;
+        if !path.is_dir() {
+            return Err(HostError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Not a directory: {}", path.display()),
+            )));
+        }
+
+        let mut ctx = ProjectContext::detect(&path).unwrap_or(ProjectContext {
+            root: path,
+            marker: ProjectMarker::Directory,
+            pinned: true,
+        });
+        ctx.pinned = true;
+
+        if let Some(parent) = self.state_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(&ctx)
+            .map_err(|e| HostError::Io(std::io::Error::other(e)))?;
+        std::fs::write(&self.state_path, content)?;
+
+        self.pinned = Some(ctx.clone());
+        Ok(ctx)
+    }
+
+    /// Remove the pin so the project is detected from the working directory again.
+    pub fn clear(&mut self) -> Result<()> {
+        match std::fs::remove_file(&self.state_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.pinned = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_persists_and_overrides_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("config").join(CONTEXT_STATE_FILE);
+        let project = dir.path().join("project");
+        let other = dir.path().join("other");
+        std::fs::create_dir_all(project.join(".adi")).unwrap();
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::create_dir_all(other.join(".git")).unwrap();
+        let project = project.canonicalize().unwrap();
+
+        let mut registry = ProjectContextRegistry::load(&state);
+        assert!(registry.pinned().is_none());
+        assert_eq!(registry.resolve(&other).unwrap().marker, ProjectMarker::Git);
+
+        let ctx = registry.pin(&project.join("src")).unwrap();
+        assert_eq!(ctx.root, project);
+        assert_eq!(ctx.marker, ProjectMarker::Adi);
+        assert!(ctx.pinned);
+
+        let registry = ProjectContextRegistry::load(&state);
+        assert_eq!(registry.resolve(&other), Some(ctx));
+
+        let mut registry = registry;
+        registry.clear().unwrap();
+        registry.clear().unwrap();
+        assert!(ProjectContextRegistry::load(&state).pinned().is_none());
+    }
+
+    #[test]
+    fn test_pin_plain_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = ProjectContextRegistry::load(dir.path().join(CONTEXT_STATE_FILE));
+
+        let ctx = registry.pin(dir.path()).unwrap();
+        assert_eq!(ctx.marker, ProjectMarker::Directory);
+        assert!(registry.pin(&dir.path().join("missing")).is_err());
+    }
+}
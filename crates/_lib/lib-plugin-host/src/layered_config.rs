@@ -0,0 +1,462 @@
+//! Layered plugin configuration.
+//!
+//! A plugin's effective config is merged from these layers, lowest priority
+//! first:
+//!
+//! 1. manifest defaults (`[config.defaults]` in plugin.toml)
+//! 2. user config (`~/.config/adi/<plugin-id>/config.json`)
+//! 3. project config (`<project>/.adi/config/<plugin-id>.json`)
+//! 4. environment (`ADI_CONFIG_<PLUGIN_ID>__<KEY>`, e.g. `ADI_CONFIG_ADI_TASKS__LIMIT`)
+//!
+//! Layers are merged per top-level key: the highest layer that sets a key
+//! wins, and its value replaces lower ones wholesale.
+
+use crate::{HostError, Result};
+use lib_plugin_abi_v3::project::ADI_PROJECT_DIR;
+use lib_plugin_manifest::{ConfigField, ConfigValueType, PluginManifest};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Prefix of environment variables that override plugin config
+pub const CONFIG_ENV_PREFIX: &str = "ADI_CONFIG_";
+
+/// User config file name, relative to the plugin config directory
+pub const USER_CONFIG_FILE: &str = "config.json";
+
+/// Directory under `.adi/` holding per-plugin project config files
+pub const PROJECT_CONFIG_DIR: &str = "config";
+
+/// Where a config value came from, ordered by priority (lowest first).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLayerKind {
+    Default,
+    User,
+    Project,
+    Env,
+}
+
+impl std::fmt::Display for ConfigLayerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigLayerKind::Default => write!(f, "default"),
+            ConfigLayerKind::User => write!(f, "user"),
+            ConfigLayerKind::Project => write!(f, "project"),
+            ConfigLayerKind::Env => write!(f, "env"),
+        }
+    }
+}
+
+/// Values one layer sets.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigLayer {
+    pub kind: ConfigLayerKind,
+    /// File path or environment variable prefix the values were read from
+    pub source: String,
+    pub values: Map<String, Value>,
+}
+
+/// One layer's value for a key, as reported by [`LayeredConfig::explain`].
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerValue {
+    pub kind: ConfigLayerKind,
+    pub source: String,
+    /// `None` when the layer does not set the key
+    pub value: Option<Value>,
+    /// Whether this layer's value is the effective one
+    pub winner: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigIssueSeverity {
+    Error,
+    Warning,
+}
+
+/// A problem found by [`LayeredConfig::validate`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigIssue {
+    pub severity: ConfigIssueSeverity,
+    pub key: String,
+    /// Layer that set the offending value, `None` for missing keys
+    pub layer: Option<ConfigLayerKind>,
+    pub message: String,
+}
+
+/// All config layers of one plugin.
+#[derive(Debug, Clone, Serialize)]
+pub struct LayeredConfig {
+    pub plugin_id: String,
+    /// Layers that exist, lowest priority first
+    pub layers: Vec<ConfigLayer>,
+}
+
+/// User config file of a plugin (`~/.config/adi/<plugin-id>/config.json`).
+pub fn user_config_path(plugin_id: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("adi").join(plugin_id).join(USER_CONFIG_FILE))
+}
+
+/// Project config file of a plugin (`<project>/.adi/config/<plugin-id>.json`).
+pub fn project_config_path(project_root: &Path, plugin_id: &str) -> PathBuf {
+    project_root
+        .join(ADI_PROJECT_DIR)
+        .join(PROJECT_CONFIG_DIR)
+        .join(format!("{plugin_id}.json"))
+}
+
+/// Environment variable prefix of a plugin, e.g. `ADI_CONFIG_ADI_TASKS__`.
+pub fn env_prefix(plugin_id: &str) -> String {
+    format!("{CONFIG_ENV_PREFIX}{}__", env_segment(plugin_id))
+}
+
+/// Environment variable overriding `key` of a plugin.
+pub fn env_var_name(plugin_id: &str, key: &str) -> String {
+    format!("{}{}", env_prefix(plugin_id), env_segment(key))
+}
+
+fn env_segment(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl LayeredConfig {
+    /// Load every layer of `manifest`'s plugin from the standard locations
+    /// and the process environment.
+    pub fn load(manifest: &PluginManifest, project_root: Option<&Path>) -> Result<Self> {
+        let plugin_id = &manifest.plugin.id;
+        let user = user_config_path(plugin_id).ok_or_else(|| {
+            HostError::InitFailed("Cannot determine config directory".to_string())
+        })?;
+        let project = project_root.map(|root| project_config_path(root, plugin_id));
+        Self::from_sources(manifest, &user, project.as_deref(), std::env::vars())
+    }
+
+    /// Load layers from explicit sources. Missing files are skipped.
+    pub fn from_sources(
+        manifest: &PluginManifest,
+        user_config: &Path,
+        project_config: Option<&Path>,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let plugin_id = manifest.plugin.id.clone();
+        let mut layers = Vec::new();
+
+        let defaults: Map<String, Value> = manifest
+            .config
+            .defaults
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), serde_json::to_value(value).ok()?)))
+            .collect();
+        if !defaults.is_empty() {
+            layers.push(ConfigLayer {
+                kind: ConfigLayerKind::Default,
+                source: "plugin.toml".to_string(),
+                values: defaults,
+            });
+        }
+
+        if let Some(values) = read_json_layer(user_config)? {
+            layers.push(ConfigLayer {
+                kind: ConfigLayerKind::User,
+                source: user_config.display().to_string(),
+                values,
+            });
+        }
+
+        if let Some(path) = project_config {
+            if let Some(values) = read_json_layer(path)? {
+                layers.push(ConfigLayer {
+                    kind: ConfigLayerKind::Project,
+                    source: path.display().to_string(),
+                    values,
+                });
+            }
+        }
+
+        let prefix = env_prefix(&plugin_id);
+        let env_values: Map<String, Value> = env
+            .into_iter()
+            .filter_map(|(name, raw)| {
+                let key = name.strip_prefix(&prefix)?.to_ascii_lowercase();
+                let value = parse_env_value(&raw, manifest.config.schema.get(&key));
+                Some((key, value))
+            })
+            .collect();
+        if !env_values.is_empty() {
+            layers.push(ConfigLayer {
+                kind: ConfigLayerKind::Env,
+                source: format!("{prefix}*"),
+                values: env_values,
+            });
+        }
+
+        Ok(Self { plugin_id, layers })
+    }
+
+    /// Effective config passed to the plugin.
+    pub fn merged(&self) -> Value {
+        let mut merged = Map::new();
+        for layer in &self.layers {
+            merged.extend(layer.values.clone());
+        }
+        Value::Object(merged)
+    }
+
+    /// Every layer's value for `key`, marking the one that wins.
+    pub fn explain(&self, key: &str) -> Vec<LayerValue> {
+        let winner = self.layers.iter().rposition(|l| l.values.contains_key(key));
+        self.layers
+            .iter()
+            .enumerate()
+            .map(|(i, layer)| LayerValue {
+                kind: layer.kind,
+                source: layer.source.clone(),
+                value: layer.values.get(key).cloned(),
+                winner: Some(i) == winner,
+            })
+            .collect()
+    }
+
+    /// Check every layer against the declared `schema`.
+    ///
+    /// Reports values of the wrong type and required keys no layer sets as
+    /// errors, and keys the schema does not declare as warnings. Plugins
+    /// without a schema only get the required-key check, which is trivially
+    /// satisfied.
+    pub fn validate(&self, schema: &HashMap<String, ConfigField>) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        for layer in &self.layers {
+            let mut keys: Vec<&String> = layer.values.keys().collect();
+            keys.sort();
+            for key in keys {
+                let value = &layer.values[key];
+                match schema.get(key) {
+                    Some(field) if !type_matches(field.value_type, value) => {
+                        issues.push(ConfigIssue {
+                            severity: ConfigIssueSeverity::Error,
+                            key: key.clone(),
+                            layer: Some(layer.kind),
+                            message: format!(
+                                "expected {}, found {}",
+                                field.value_type,
+                                json_type_name(value)
+                            ),
+                        });
+                    }
+                    Some(_) => {}
+                    None if !schema.is_empty() => issues.push(ConfigIssue {
+                        severity: ConfigIssueSeverity::Warning,
+                        key: key.clone(),
+                        layer: Some(layer.kind),
+                        message: "unknown key".to_string(),
+                    }),
+                    None => {}
+                }
+            }
+        }
+
+        let mut required: Vec<&String> = schema
+            .iter()
+            .filter(|(_, field)| field.required)
+            .map(|(key, _)| key)
+            .collect();
+        required.sort();
+        for key in required {
+            if !self.layers.iter().any(|l| l.values.contains_key(key)) {
+                issues.push(ConfigIssue {
+                    severity: ConfigIssueSeverity::Error,
+                    key: key.clone(),
+                    layer: None,
+                    message: "required key is not set".to_string(),
+                });
+            }
+        }
+
+        issues
+    }
+}
+
+/// Read a JSON object layer; `None` when the file does not exist.
+fn read_json_layer(path: &Path) -> Result<Option<Map<String, Value>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)?;
+    match serde_json::from_str(&content) {
+        Ok(Value::Object(values)) => Ok(Some(values)),
+        Ok(_) => Err(HostError::InitFailed(format!(
+            "Config {} must be a JSON object",
+            path.display()
+        ))),
+        Err(e) => Err(HostError::InitFailed(format!(
+            "Failed to parse config {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Environment values are JSON unless the key is declared as a string, so
+/// `20` becomes a number and `true` a boolean, but a string key keeps `20`.
+fn parse_env_value(raw: &str, field: Option<&ConfigField>) -> Value {
+    if field.is_some_and(|f| f.value_type == ConfigValueType::String) {
+        return Value::String(raw.to_string());
+    }
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+fn type_matches(expected: ConfigValueType, value: &Value) -> bool {
+    match expected {
+        ConfigValueType::String => value.is_string(),
+        ConfigValueType::Integer => value.is_i64() || value.is_u64(),
+        ConfigValueType::Number => value.is_number(),
+        ConfigValueType::Boolean => value.is_boolean(),
+        ConfigValueType::Array => value.is_array(),
+        ConfigValueType::Object => value.is_object(),
+    }
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[plugin]
+id = "adi.tasks"
+name = "ADI Tasks"
+version = "1.0.0"
+type = "core"
+
+[config.defaults]
+limit = 20
+color = true
+
+[config.schema.limit]
+type = "integer"
+
+[config.schema.color]
+type = "boolean"
+
+[config.schema.label]
+type = "string"
+
+[config.schema.token]
+type = "string"
+required = true
+"#;
+
+    fn manifest() -> PluginManifest {
+        PluginManifest::from_toml(MANIFEST).unwrap()
+    }
+
+    #[test]
+    fn test_env_var_name() {
+        assert_eq!(
+            env_var_name("adi.tasks", "limit"),
+            "ADI_CONFIG_ADI_TASKS__LIMIT"
+        );
+        assert_eq!(
+            env_prefix("hive.runner-docker"),
+            "ADI_CONFIG_HIVE_RUNNER_DOCKER__"
+        );
+    }
+
+    #[test]
+    fn test_layers_merge_by_priority() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("config.json");
+        let project = dir.path().join("project.json");
+        std::fs::write(&user, r#"{"limit": 50, "label": "mine"}"#).unwrap();
+        std::fs::write(&project, r#"{"limit": 5}"#).unwrap();
+        let env = [
+            ("ADI_CONFIG_ADI_TASKS__LABEL".to_string(), "42".to_string()),
+            ("ADI_CONFIG_OTHER__LIMIT".to_string(), "1".to_string()),
+        ];
+
+        let config = LayeredConfig::from_sources(&manifest(), &user, Some(&project), env).unwrap();
+        let merged = config.merged();
+        assert_eq!(merged["limit"], 5);
+        assert_eq!(merged["color"], true);
+        // Declared as string, so not parsed as a number
+        assert_eq!(merged["label"], "42");
+
+        let limit = config.explain("limit");
+        let kinds: Vec<_> = limit.iter().map(|l| (l.kind, l.winner)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (ConfigLayerKind::Default, false),
+                (ConfigLayerKind::User, false),
+                (ConfigLayerKind::Project, true),
+                (ConfigLayerKind::Env, false),
+            ]
+        );
+        assert_eq!(limit[3].value, None);
+    }
+
+    #[test]
+    fn test_validate_reports_type_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("config.json");
+        std::fs::write(&user, r#"{"limit": "ten", "colour": false}"#).unwrap();
+
+        let manifest = manifest();
+        let config = LayeredConfig::from_sources(&manifest, &user, None, []).unwrap();
+        let issues = config.validate(&manifest.config.schema);
+
+        let summary: Vec<_> = issues
+            .iter()
+            .map(|i| (i.severity, i.key.as_str(), i.layer))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    ConfigIssueSeverity::Warning,
+                    "colour",
+                    Some(ConfigLayerKind::User)
+                ),
+                (
+                    ConfigIssueSeverity::Error,
+                    "limit",
+                    Some(ConfigLayerKind::User)
+                ),
+                (ConfigIssueSeverity::Error, "token", None),
+            ]
+        );
+        assert_eq!(issues[1].message, "expected integer, found string");
+    }
+
+    #[test]
+    fn test_invalid_json_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("config.json");
+        std::fs::write(&user, "[1, 2]").unwrap();
+
+        assert!(LayeredConfig::from_sources(&manifest(), &user, None, []).is_err());
+    }
+}
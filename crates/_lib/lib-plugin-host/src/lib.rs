@@ -27,7 +27,7 @@
 //!
 //!     // Load a plugin
 //!     let manifest = lib_plugin_manifest::PluginManifest::from_file("plugin.toml")?;
-//!     let loaded = LoadedPluginV3::load(manifest, &config.plugins_dir).await?;
+//!     let loaded = LoadedPluginV3::load(manifest, &config.plugins_dir, None, None).await?;
 //!     manager.register(loaded)?;
 //!
 //!     // Set as current for plugin-to-plugin access
@@ -39,19 +39,25 @@
 //! ```
 
 pub mod command_index;
+pub mod compat;
 mod config;
+mod context;
 mod error;
 mod installed;
 mod installer;
+mod layered_config;
+pub mod scaffold;
 
 // V3 plugin support
 mod loader_v3;
 mod manager_v3;
 
 pub use config::*;
+pub use context::*;
 pub use error::*;
 pub use installed::*;
 pub use installer::*;
+pub use layered_config::*;
 
 // V3 exports
 pub use loader_v3::*;
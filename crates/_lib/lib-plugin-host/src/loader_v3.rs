@@ -1,7 +1,9 @@
 //! Plugin loader for v3 ABI (native async traits)
 
-use crate::PluginError;
-use lib_plugin_abi_v3::{cli::CliCommands, daemon::DaemonService, http::HttpRoutes, logs::LogProvider, Plugin, PluginContext, PluginMetadata, PLUGIN_API_VERSION};
+use crate::compat::{check, PluginExports};
+use crate::{project_config_path, LayeredConfig, PluginError, USER_CONFIG_FILE};
+use lib_plugin_abi_v3::services::{AdiServiceProvider, PluginServices};
+use lib_plugin_abi_v3::{cli::CliCommands, daemon::DaemonService, http::HttpRoutes, logs::LogProvider, search::SearchProvider, Plugin, PluginContext, PluginMetadata};
 use lib_plugin_manifest::PluginManifest;
 use libloading::{Library, Symbol};
 use std::panic::AssertUnwindSafe;
@@ -25,11 +27,17 @@ pub struct LoadedPluginV3 {
     /// Optional log provider trait object (if plugin provides log streaming)
     pub log_provider: Option<Arc<dyn LogProvider>>,
 
+    /// Optional search provider trait object (if plugin provides search)
+    pub search_provider: Option<Arc<dyn SearchProvider>>,
+
     /// Optional daemon service trait object (if plugin provides daemon)
     pub daemon_service: Option<Arc<dyn DaemonService>>,
 
     /// Optional HTTP routes trait object (if plugin provides HTTP endpoints)
     pub http_routes: Option<Arc<dyn HttpRoutes>>,
+
+    /// Optional ADI service provider (if plugin serves other plugins)
+    pub adi_service_provider: Option<Arc<dyn AdiServiceProvider>>,
 }
 
 impl LoadedPluginV3 {
@@ -38,13 +46,21 @@ impl LoadedPluginV3 {
     /// Checks the plugin's ABI version before calling any trait methods.
     /// Wraps the load in `catch_unwind` and a timeout to guard against
     /// broken or ABI-incompatible plugins that crash or hang.
-    pub async fn load(manifest: PluginManifest, plugin_dir: &Path) -> crate::Result<Self> {
+    ///
+    /// `project_root` selects the project config layer, see [`LayeredConfig`].
+    /// `services` is handed to the plugin so it can reach other plugins.
+    pub async fn load(
+        manifest: PluginManifest,
+        plugin_dir: &Path,
+        project_root: Option<&Path>,
+        services: Option<Arc<dyn PluginServices>>,
+    ) -> crate::Result<Self> {
         let lib_path = resolve_plugin_binary(&manifest, plugin_dir)?;
         let plugin_id = manifest.plugin.id.clone();
 
         // Wrap the entire loading sequence in a timeout (10s) so a hung
         // dlopen / plugin_create / init cannot block the process forever.
-        let load_future = Self::load_inner(manifest, &lib_path, &plugin_id);
+        let load_future = Self::load_inner(manifest, &lib_path, &plugin_id, project_root, services);
         match tokio::time::timeout(std::time::Duration::from_secs(10), load_future).await {
             Ok(result) => result,
             Err(_) => Err(PluginError::InitFailed(format!(
@@ -59,6 +75,8 @@ impl LoadedPluginV3 {
         manifest: PluginManifest,
         lib_path: &Path,
         plugin_id: &str,
+        project_root: Option<&Path>,
+        services: Option<Arc<dyn PluginServices>>,
     ) -> crate::Result<Self> {
         // Load library inside catch_unwind (dlopen can trigger constructors that panic)
         let lib_path_owned = lib_path.to_path_buf();
@@ -75,28 +93,21 @@ impl LoadedPluginV3 {
         .map_err(|_| PluginError::InitFailed(format!("Library::new panicked for {} ({:?})", plugin_id, lib_path_owned)))?
         .map_err(|e| PluginError::InitFailed(format!("Failed to load library {:?}: {}", lib_path_owned, e)))?;
 
-        // --- ABI version gate ---
-        // If the plugin exports `plugin_abi_version`, verify it matches the host.
-        // If the symbol is absent we allow loading (older plugins built before this check).
-        let abi_version: Option<u32> = unsafe {
-            library
-                .get::<extern "C" fn() -> u32>(b"plugin_abi_version")
-                .ok()
-                .map(|sym| sym())
-        };
-
-        if let Some(version) = abi_version {
-            if version != PLUGIN_API_VERSION {
+        // --- Compatibility gate ---
+        // Refuse ABI mismatches; raw plugins without a version symbol still load.
+        let exports = PluginExports::probe(&library);
+        for issue in check(&manifest, &exports) {
+            if issue.is_fatal() {
                 return Err(PluginError::InitFailed(format!(
-                    "ABI mismatch for {}: plugin exports v{}, host expects v{}. Reinstall the plugin.",
-                    plugin_id, version, PLUGIN_API_VERSION
+                    "{} is incompatible: {}. Reinstall the plugin.",
+                    plugin_id, issue
                 )));
             }
-            tracing::debug!(plugin_id, version, "ABI version check passed");
-        } else {
             tracing::debug!(
                 plugin_id,
-                "Plugin does not export plugin_abi_version — skipping ABI check (legacy plugin)"
+                %issue,
+                "Plugin compatibility issue (run `adi plugins migrate-scaffold {}`)",
+                plugin_id
             );
         }
 
@@ -115,7 +126,10 @@ impl LoadedPluginV3 {
             )))?;
 
         // Create plugin context
-        let ctx = create_plugin_context(&manifest)?;
+        let mut ctx = create_plugin_context(&manifest, project_root)?;
+        if let Some(services) = services {
+            ctx = ctx.with_services(services);
+        }
 
         // Initialize plugin
         let result: lib_plugin_abi_v3::Result<()> = plugin.init(&ctx).await;
@@ -161,6 +175,22 @@ impl LoadedPluginV3 {
             }
         };
 
+        // Try to get SearchProvider if the plugin provides it
+        let search_provider: Option<Arc<dyn SearchProvider>> = {
+            let search_fn: Result<Symbol<fn() -> Box<dyn SearchProvider>>, _> =
+                unsafe { library.get(b"plugin_create_search_provider") };
+
+            if let Ok(search_fn) = search_fn {
+                std::panic::catch_unwind(AssertUnwindSafe(|| Arc::from(search_fn())))
+                    .map_err(|_| {
+                        tracing::warn!(plugin_id, "plugin_create_search_provider panicked");
+                    })
+                    .ok()
+            } else {
+                None
+            }
+        };
+
         // Try to get DaemonService if the plugin provides it
         let daemon_service: Option<Arc<dyn DaemonService>> = {
             let daemon_fn: Result<Symbol<fn() -> Box<dyn DaemonService>>, _> =
@@ -193,14 +223,32 @@ impl LoadedPluginV3 {
             }
         };
 
+        // Try to get AdiServiceProvider if the plugin serves other plugins
+        let adi_service_provider: Option<Arc<dyn AdiServiceProvider>> = {
+            let adi_fn: Result<Symbol<fn() -> Box<dyn AdiServiceProvider>>, _> =
+                unsafe { library.get(b"plugin_create_adi_service") };
+
+            if let Ok(adi_fn) = adi_fn {
+                std::panic::catch_unwind(AssertUnwindSafe(|| Arc::from(adi_fn())))
+                    .map_err(|_| {
+                        tracing::warn!(plugin_id, "plugin_create_adi_service panicked");
+                    })
+                    .ok()
+            } else {
+                None
+            }
+        };
+
         Ok(Self {
             manifest,
             _library: library,
             plugin: Arc::from(plugin),
             cli_commands,
             log_provider,
+            search_provider,
             daemon_service,
             http_routes,
+            adi_service_provider,
         })
     }
 
@@ -226,7 +274,7 @@ impl LoadedPluginV3 {
 }
 
 /// Resolve plugin binary path
-fn resolve_plugin_binary(manifest: &PluginManifest, plugin_dir: &Path) -> crate::Result<PathBuf> {
+pub(crate) fn resolve_plugin_binary(manifest: &PluginManifest, plugin_dir: &Path) -> crate::Result<PathBuf> {
     let binary_name = &manifest.binary.name;
 
     // Try platform-specific names
@@ -262,7 +310,10 @@ fn resolve_plugin_binary(manifest: &PluginManifest, plugin_dir: &Path) -> crate:
 }
 
 /// Create plugin context
-fn create_plugin_context(manifest: &PluginManifest) -> crate::Result<PluginContext> {
+fn create_plugin_context(
+    manifest: &PluginManifest,
+    project_root: Option<&Path>,
+) -> crate::Result<PluginContext> {
     let plugin_id = manifest.plugin.id.clone();
 
     // Data directory: ~/.local/share/adi/<plugin-id>/
@@ -281,15 +332,16 @@ fn create_plugin_context(manifest: &PluginManifest) -> crate::Result<PluginConte
     std::fs::create_dir_all(&data_dir)?;
     std::fs::create_dir_all(&config_dir)?;
 
-    // Load plugin config (if exists)
-    let config_path = config_dir.join("config.json");
-    let config = if config_path.exists() {
-        let content = std::fs::read_to_string(&config_path)?;
-        serde_json::from_str(&content)
-            .map_err(|e| PluginError::InitFailed(format!("Failed to parse config: {}", e)))?
-    } else {
-        serde_json::json!({})
-    };
+    // Defaults, user, project and env layers merged
+    let config = LayeredConfig::from_sources(
+        manifest,
+        &config_dir.join(USER_CONFIG_FILE),
+        project_root
+            .map(|root| project_config_path(root, &plugin_id))
+            .as_deref(),
+        std::env::vars(),
+    )?
+    .merged();
 
     Ok(PluginContext::new(plugin_id, data_dir, config_dir, config))
 }
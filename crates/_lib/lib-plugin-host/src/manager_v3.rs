@@ -68,8 +68,17 @@ pub struct PluginManagerV3 {
     // Log streaming
     log_providers: HashMap<String, Arc<dyn logs::LogProvider>>,
 
+    // Cross-plugin search
+    search_providers: HashMap<String, Arc<dyn search::SearchProvider>>,
+
     // Daemon services
     daemon_services: HashMap<String, Arc<dyn daemon::DaemonService>>,
+
+    // ADI services plugins offer each other
+    adi_service_providers: HashMap<String, Arc<dyn services::AdiServiceProvider>>,
+
+    /// Project context last broadcast to plugins
+    project_context: Option<project::ProjectContext>,
 }
 
 impl PluginManagerV3 {
@@ -89,7 +98,10 @@ impl PluginManagerV3 {
             obs_sinks: HashMap::new(),
             rollout_strategies: HashMap::new(),
             log_providers: HashMap::new(),
+            search_providers: HashMap::new(),
             daemon_services: HashMap::new(),
+            adi_service_providers: HashMap::new(),
+            project_context: None,
         }
     }
 
@@ -119,6 +131,12 @@ impl PluginManagerV3 {
             tracing::debug!("Registered log provider for plugin: {}", plugin_id);
         }
 
+        // Register search provider if available
+        if let Some(search_provider) = loaded.search_provider {
+            self.search_providers.insert(plugin_id.clone(), search_provider);
+            tracing::debug!("Registered search provider for plugin: {}", plugin_id);
+        }
+
         // Register daemon service if available
         if let Some(daemon_service) = loaded.daemon_service {
             self.daemon_services.insert(plugin_id.clone(), daemon_service);
@@ -131,6 +149,12 @@ impl PluginManagerV3 {
             tracing::debug!("Registered HTTP routes for plugin: {}", plugin_id);
         }
 
+        // Register ADI service provider if available
+        if let Some(provider) = loaded.adi_service_provider {
+            self.adi_service_providers.insert(plugin_id.clone(), provider);
+            tracing::debug!("Registered ADI service provider for plugin: {}", plugin_id);
+        }
+
         Ok(())
     }
 
@@ -236,6 +260,19 @@ impl PluginManagerV3 {
         self.log_providers.get(plugin_id).cloned()
     }
 
+    /// Register a search provider plugin
+    pub fn register_search_provider(&mut self, plugin_id: impl Into<String>, plugin: Arc<dyn search::SearchProvider>) {
+        self.search_providers.insert(plugin_id.into(), plugin);
+    }
+
+    /// Get all search provider plugins
+    pub fn all_search_providers(&self) -> Vec<(String, Arc<dyn search::SearchProvider>)> {
+        self.search_providers
+            .iter()
+            .map(|(id, provider)| (id.clone(), provider.clone()))
+            .collect()
+    }
+
     /// Register a daemon service plugin
     pub fn register_daemon_service(&mut self, plugin_id: impl Into<String>, service: Arc<dyn daemon::DaemonService>) {
         self.daemon_services.insert(plugin_id.into(), service);
@@ -254,6 +291,11 @@ impl PluginManagerV3 {
             .collect()
     }
 
+    /// Get the ADI service provider of a plugin
+    pub fn get_adi_service_provider(&self, plugin_id: &str) -> Option<Arc<dyn services::AdiServiceProvider>> {
+        self.adi_service_providers.get(plugin_id).cloned()
+    }
+
     /// Register a language analyzer plugin
     pub fn register_language_analyzer(&mut self, language: impl Into<String>, plugin: Arc<dyn lang::LanguageAnalyzer>) {
         self.language_analyzers.insert(language.into(), plugin);
@@ -305,11 +347,63 @@ impl PluginManagerV3 {
         !self.embedders.is_empty()
     }
 
+    /// Current project context
+    pub fn project_context(&self) -> Option<&project::ProjectContext> {
+        self.project_context.as_ref()
+    }
+
+    /// Set the current project context. Returns `true` if it changed, in
+    /// which case the caller should [`broadcast_event`] a
+    /// [`PluginEvent::ProjectChanged`] to [`Self::event_targets`].
+    pub fn set_project_context(&mut self, context: Option<project::ProjectContext>) -> bool {
+        if self.project_context == context {
+            return false;
+        }
+        self.project_context = context;
+        true
+    }
+
     /// Get a plugin by ID
     pub fn get_plugin(&self, plugin_id: &str) -> Option<Arc<dyn Plugin>> {
         self.plugins.get(plugin_id).cloned()
     }
 
+    /// Get all loaded plugins
+    pub fn all_plugins(&self) -> Vec<(String, Arc<dyn Plugin>)> {
+        self.plugins
+            .iter()
+            .map(|(id, plugin)| (id.clone(), plugin.clone()))
+            .collect()
+    }
+
+    /// Every instance events must reach: each plugin's base instance plus the
+    /// CLI, search, log, daemon, HTTP and ADI service instances it created separately,
+    /// which keep state of their own.
+    pub fn event_targets(&self) -> Vec<(String, Arc<dyn Plugin>)> {
+        self.plugins
+            .keys()
+            .flat_map(|id| self.event_targets_of(id))
+            .collect()
+    }
+
+    /// [`Self::event_targets`] of one plugin
+    pub fn event_targets_of(&self, plugin_id: &str) -> Vec<(String, Arc<dyn Plugin>)> {
+        let instances: [Option<Arc<dyn Plugin>>; 7] = [
+            self.plugins.get(plugin_id).cloned(),
+            self.cli_commands.get(plugin_id).map(|p| p.clone() as Arc<dyn Plugin>),
+            self.search_providers.get(plugin_id).map(|p| p.clone() as Arc<dyn Plugin>),
+            self.log_providers.get(plugin_id).map(|p| p.clone() as Arc<dyn Plugin>),
+            self.daemon_services.get(plugin_id).map(|p| p.clone() as Arc<dyn Plugin>),
+            self.http_routes.get(plugin_id).map(|p| p.clone() as Arc<dyn Plugin>),
+            self.adi_service_providers.get(plugin_id).map(|p| p.clone() as Arc<dyn Plugin>),
+        ];
+        instances
+            .into_iter()
+            .flatten()
+            .map(|plugin| (plugin_id.to_string(), plugin))
+            .collect()
+    }
+
     /// List all loaded plugins
     pub fn list_plugins(&self) -> Vec<PluginMetadata> {
         self.plugins
@@ -338,6 +432,7 @@ impl PluginManagerV3 {
         self.obs_sinks.clear();
         self.rollout_strategies.clear();
         self.log_providers.clear();
+        self.search_providers.clear();
         self.daemon_services.clear();
 
         // Drop library handles last, after all trait objects are gone
@@ -347,6 +442,18 @@ impl PluginManagerV3 {
     }
 }
 
+/// Deliver `event` to each plugin's [`Plugin::handle_event`].
+///
+/// Takes plugins out of the manager so no lock is held while they run.
+/// Failures are logged and do not stop delivery to the remaining plugins.
+pub async fn broadcast_event(plugins: Vec<(String, Arc<dyn Plugin>)>, event: &PluginEvent) {
+    for (id, plugin) in plugins {
+        if let Err(e) = plugin.handle_event(event).await {
+            tracing::warn!("Plugin {} failed to handle event: {}", id, e);
+        }
+    }
+}
+
 impl Default for PluginManagerV3 {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,387 @@
+//! v3 SDK skeleton generation for `adi plugins migrate-scaffold`.
+//!
+//! Builds a `lib-plugin-prelude` crate from an installed plugin's manifest
+//! and, when it can be loaded, its command schema: one `#[derive(CliArgs)]`
+//! struct and one `#[command]` stub per command, plus the full set of export
+//! symbols. Command bodies are left as TODOs; the [`CompatIssue`]s found for
+//! the installed build are listed at the top of `src/lib.rs`.
+
+use crate::compat::CompatIssue;
+use lib_plugin_abi_v3::cli::{CliArg, CliArgType, CliCommand};
+use lib_plugin_abi_v3::PLUGIN_API_VERSION;
+use lib_plugin_manifest::PluginManifest;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Files of a generated plugin crate, relative to its root
+pub fn scaffold_v3(
+    manifest: &PluginManifest,
+    commands: &[CliCommand],
+    issues: &[CompatIssue],
+) -> Vec<(PathBuf, String)> {
+    vec![
+        (PathBuf::from("Cargo.toml"), cargo_toml(manifest)),
+        (
+            PathBuf::from("src/lib.rs"),
+            lib_rs(manifest, commands, issues),
+        ),
+        (
+            PathBuf::from("locales/en-US/messages.ftl"),
+            messages_ftl(manifest),
+        ),
+    ]
+}
+
+/// `vendor.my-plugin` -> `my-plugin`
+fn short_name(id: &str) -> &str {
+    id.rsplit('.').next().unwrap_or(id)
+}
+
+/// `my-plugin` -> `MyPlugin`
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// `--dry-run` -> `dry_run`
+fn snake_case(name: &str) -> String {
+    name.trim_start_matches('-').replace('-', "_")
+}
+
+/// `CliArgs` derives the option key from the field name, so keywords can't
+/// be expressed as fields
+fn is_keyword(field: &str) -> bool {
+    matches!(
+        field,
+        "type" | "fn" | "mod" | "use" | "ref" | "match" | "impl" | "self" | "in" | "as" | "where"
+    )
+}
+
+fn toml_str(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+fn cargo_toml(manifest: &PluginManifest) -> String {
+    let meta = &manifest.plugin;
+    let mut out = String::new();
+
+    let _ = writeln!(out, "[package]");
+    let _ = writeln!(out, "name = \"{}-plugin\"", short_name(&meta.id));
+    let _ = writeln!(out, "version = {}", toml_str(&meta.version));
+    let _ = writeln!(out, "edition = \"2021\"");
+    if let Some(license) = &meta.license {
+        let _ = writeln!(out, "license = {}", toml_str(license));
+    }
+    let _ = writeln!(out, "description = {}", toml_str(&meta.description));
+    let _ = writeln!(out, "authors = [{}]", toml_str(&meta.author));
+    out.push_str(
+        "\n[lib]\ncrate-type = [\"cdylib\"]\n\n[dependencies]\n\
+         # Paths assume the crate lives at crates/<component>/plugin\n\
+         lib-plugin-prelude = { path = \"../../_lib/lib-plugin-prelude\" }\n\
+         lib-plugin-abi-v3 = { path = \"../../_lib/lib-plugin-abi-v3\" }\n",
+    );
+
+    let _ = writeln!(out, "\n[package.metadata.plugin]");
+    let _ = writeln!(out, "id = {}", toml_str(&meta.id));
+    let _ = writeln!(out, "name = {}", toml_str(&meta.name));
+    let _ = writeln!(out, "type = {}", toml_str(&meta.plugin_type));
+
+    let _ = writeln!(out, "\n[package.metadata.plugin.compatibility]");
+    let _ = writeln!(out, "api_version = {}", PLUGIN_API_VERSION);
+    if let Some(min) = &manifest.compatibility.min_host_version {
+        let _ = writeln!(out, "min_host_version = {}", toml_str(min));
+    }
+
+    if let Some(cli) = &manifest.cli {
+        let _ = writeln!(out, "\n[package.metadata.plugin.cli]");
+        let _ = writeln!(out, "command = {}", toml_str(&cli.command));
+        let _ = writeln!(out, "description = {}", toml_str(&cli.description));
+        if !cli.aliases.is_empty() {
+            let aliases: Vec<_> = cli.aliases.iter().map(|a| toml_str(a)).collect();
+            let _ = writeln!(out, "aliases = [{}]", aliases.join(", "));
+        }
+    }
+
+    for service in &manifest.provides {
+        let _ = writeln!(out, "\n[[package.metadata.plugin.provides]]");
+        let _ = writeln!(out, "id = {}", toml_str(&service.id));
+        let _ = writeln!(out, "version = {}", toml_str(&service.version));
+        let _ = writeln!(out, "description = {}", toml_str(&service.description));
+    }
+
+    out
+}
+
+fn arg_field(arg: &CliArg) -> String {
+    let field = snake_case(&arg.name);
+    let ty = match arg.arg_type {
+        CliArgType::String => "String",
+        CliArgType::Int => "i64",
+        CliArgType::Float => "f64",
+        CliArgType::Bool => "bool",
+    };
+    let ty = if arg.required || arg.arg_type == CliArgType::Bool {
+        ty.to_string()
+    } else {
+        format!("Option<{}>", ty)
+    };
+
+    let attr = match arg.position {
+        Some(position) => format!("position = {}", position),
+        None => "long".to_string(),
+    };
+
+    if is_keyword(&field) {
+        return format!(
+            "    // TODO: `{}` is a Rust keyword; read it with `ctx.option({:?})` instead\n    // #[arg({})]\n    // pub {}: {},\n",
+            arg.name, field, attr, field, ty
+        );
+    }
+    format!("    #[arg({})]\n    pub {}: {},\n", attr, field, ty)
+}
+
+fn lib_rs(manifest: &PluginManifest, commands: &[CliCommand], issues: &[CompatIssue]) -> String {
+    let meta = &manifest.plugin;
+    let plugin = format!("{}Plugin", pascal_case(short_name(&meta.id)));
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "//! {} plugin, migrated to the v3 SDK.\n//!\n\
+         //! Generated by `adi plugins migrate-scaffold {}` from version {}.\n\
+         //! TODO: port each command body from the original plugin.",
+        meta.name, meta.id, meta.version
+    );
+    if !issues.is_empty() {
+        out.push_str("//!\n//! Compatibility issues of the installed build:\n");
+        for issue in issues {
+            let _ = writeln!(out, "//! - {}", issue);
+        }
+    }
+    out.push_str("\nuse lib_plugin_prelude::*;\n");
+
+    for cmd in commands.iter().filter(|cmd| !cmd.args.is_empty()) {
+        let _ = writeln!(out, "\n#[derive(CliArgs)]");
+        let _ = writeln!(out, "pub struct {}Args {{", pascal_case(&cmd.name));
+        for (i, arg) in cmd.args.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&arg_field(arg));
+        }
+        out.push_str("}\n");
+    }
+
+    let _ = write!(
+        out,
+        r#"
+pub struct {plugin};
+
+#[async_trait]
+impl Plugin for {plugin} {{
+    fn metadata(&self) -> PluginMetadata {{
+        PluginMetadata::new({id}, t!("plugin-name"), env!("CARGO_PKG_VERSION"))
+            .with_type(PluginType::Core)
+            .with_author(t!("plugin-author"))
+            .with_description(t!("plugin-description"))
+    }}
+
+    async fn init(&mut self, _ctx: &PluginContext) -> Result<()> {{
+        lib_plugin_prelude::init_plugin_i18n(
+            "en-US",
+            include_str!("../locales/en-US/messages.ftl"),
+        );
+        Ok(())
+    }}
+
+    async fn shutdown(&self) -> Result<()> {{
+        Ok(())
+    }}
+
+    fn provides(&self) -> Vec<&'static str> {{
+        vec![SERVICE_CLI_COMMANDS]
+    }}
+}}
+
+#[async_trait]
+impl CliCommands for {plugin} {{
+    async fn list_commands(&self) -> Vec<CliCommand> {{
+        vec![
+"#,
+        plugin = plugin,
+        id = toml_str(&meta.id),
+    );
+    for cmd in commands {
+        let _ = writeln!(
+            out,
+            "            Self::__sdk_cmd_meta_{}(),",
+            snake_case(&cmd.name)
+        );
+    }
+    out.push_str(
+        "        ]\n    }\n\n    async fn run_command(&self, ctx: &CliContext) -> Result<CliResult> {\n        match ctx.subcommand.as_deref() {\n",
+    );
+    for cmd in commands {
+        let _ = writeln!(
+            out,
+            "            Some({:?}) => self.__sdk_cmd_handler_{}(ctx).await,",
+            cmd.name,
+            snake_case(&cmd.name)
+        );
+    }
+    out.push_str(
+        "            Some(cmd) => Ok(CliResult::error(format!(\"Unknown command: {}\", cmd))),\n            None => Ok(CliResult::error(\"No command given\")),\n        }\n    }\n}\n",
+    );
+
+    let _ = writeln!(out, "\nimpl {} {{", plugin);
+    for (i, cmd) in commands.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let (params, unused) = if cmd.args.is_empty() {
+            (String::new(), "")
+        } else {
+            (
+                format!(", args: {}Args", pascal_case(&cmd.name)),
+                "        let _ = args;\n",
+            )
+        };
+        let _ = write!(
+            out,
+            r#"    #[command(name = {name:?}, description = {description:?})]
+    async fn {func}(&self{params}) -> CmdResult {{
+{unused}        Err("TODO: port `{name}` from the original plugin".to_string())
+    }}
+"#,
+            name = cmd.name,
+            description = cmd.description,
+            func = snake_case(&cmd.name),
+        );
+    }
+    out.push_str("}\n");
+
+    let _ = write!(
+        out,
+        r#"
+#[no_mangle]
+pub extern "C" fn plugin_abi_version() -> u32 {{
+    lib_plugin_abi_v3::PLUGIN_API_VERSION
+}}
+
+#[no_mangle]
+pub fn plugin_create() -> Box<dyn Plugin> {{
+    Box::new({plugin})
+}}
+
+#[no_mangle]
+pub fn plugin_create_cli() -> Box<dyn CliCommands> {{
+    Box::new({plugin})
+}}
+"#,
+        plugin = plugin
+    );
+
+    out
+}
+
+fn messages_ftl(manifest: &PluginManifest) -> String {
+    let meta = &manifest.plugin;
+    format!(
+        "plugin-name = {}\nplugin-description = {}\nplugin-author = {}\n",
+        meta.name, meta.description, meta.author
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> PluginManifest {
+        PluginManifest::from_toml(
+            r#"
+[plugin]
+id = "vendor.log-tail"
+name = "Log Tail"
+version = "0.4.1"
+type = "core"
+author = "Vendor"
+description = "Tail logs"
+
+[compatibility]
+api_version = 2
+
+[cli]
+command = "tail"
+description = "Tail logs"
+aliases = ["lt"]
+"#,
+        )
+        .unwrap()
+    }
+
+    fn commands() -> Vec<CliCommand> {
+        vec![CliCommand {
+            name: "follow".to_string(),
+            description: "Follow a log file".to_string(),
+            args: vec![
+                CliArg::positional(0, "file", CliArgType::String, true),
+                CliArg::optional("--max-lines", CliArgType::Int),
+                CliArg::optional("--json", CliArgType::Bool),
+            ],
+            has_subcommands: false,
+        }]
+    }
+
+    fn file<'a>(files: &'a [(PathBuf, String)], path: &str) -> &'a str {
+        &files
+            .iter()
+            .find(|(p, _)| p == &PathBuf::from(path))
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_cargo_toml_targets_v3() {
+        let files = scaffold_v3(&manifest(), &[], &[]);
+        let cargo = file(&files, "Cargo.toml");
+
+        assert!(cargo.contains("name = \"log-tail-plugin\""));
+        assert!(cargo.contains(&format!("api_version = {}", PLUGIN_API_VERSION)));
+        assert!(cargo.contains("aliases = [\"lt\"]"));
+        assert!(cargo.parse::<toml::Table>().is_ok());
+    }
+
+    #[test]
+    fn test_lib_rs_stubs_commands() {
+        let issues = [CompatIssue::UnversionedAbi, CompatIssue::CliNotExported];
+        let files = scaffold_v3(&manifest(), &commands(), &issues);
+        let lib = file(&files, "src/lib.rs");
+
+        assert!(lib.contains("pub struct LogTailPlugin;"));
+        assert!(lib.contains("pub struct FollowArgs {"));
+        assert!(lib.contains("    #[arg(position = 0)]\n    pub file: String,"));
+        assert!(lib.contains("    #[arg(long)]\n    pub max_lines: Option<i64>,"));
+        assert!(lib.contains("    #[arg(long)]\n    pub json: bool,"));
+        assert!(lib.contains("#[command(name = \"follow\", description = \"Follow a log file\")]"));
+        assert!(lib.contains("Some(\"follow\") => self.__sdk_cmd_handler_follow(ctx).await"));
+        assert!(lib.contains("pub extern \"C\" fn plugin_abi_version()"));
+        assert!(lib.contains("pub fn plugin_create_cli()"));
+        assert!(lib.contains("//! - plugin does not export plugin_abi_version"));
+    }
+
+    #[test]
+    fn test_field_names() {
+        assert_eq!(snake_case("--dry-run"), "dry_run");
+        assert_eq!(pascal_case("log-tail"), "LogTail");
+        let arg = CliArg::optional("--type", CliArgType::String);
+        assert!(arg_field(&arg).starts_with("    // TODO: `--type` is a Rust keyword"));
+    }
+}
@@ -255,12 +255,59 @@ pub struct SignatureInfo {
     pub signature_file: String,
 }
 
-/// Default configuration values.
+/// Default configuration values and the schema they are validated against.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ConfigInfo {
     /// Default configuration values
     #[serde(default)]
     pub defaults: HashMap<String, toml::Value>,
+
+    /// Declared config keys (`[config.schema.<key>]`); empty means undeclared
+    #[serde(default)]
+    pub schema: HashMap<String, ConfigField>,
+}
+
+/// Declaration of a single top-level config key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigField {
+    /// Expected value type
+    #[serde(rename = "type")]
+    pub value_type: ConfigValueType,
+
+    /// Human-readable description
+    #[serde(default)]
+    pub description: String,
+
+    /// Whether the key must be set in some layer
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Value type of a declared config key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigValueType {
+    String,
+    Integer,
+    /// Integer or float
+    Number,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl std::fmt::Display for ConfigValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ConfigValueType::String => "string",
+            ConfigValueType::Integer => "integer",
+            ConfigValueType::Number => "number",
+            ConfigValueType::Boolean => "boolean",
+            ConfigValueType::Array => "array",
+            ConfigValueType::Object => "object",
+        };
+        f.write_str(name)
+    }
 }
 
 /// Service provided by this plugin.
@@ -547,6 +594,35 @@ name = "tasks_plugin"
         assert_eq!(cli.aliases, vec!["t"]);
     }
 
+    #[test]
+    fn test_config_schema() {
+        let toml = r#"
+[plugin]
+id = "adi.tasks"
+name = "ADI Tasks"
+version = "1.0.0"
+type = "core"
+
+[config.defaults]
+limit = 20
+
+[config.schema.limit]
+type = "integer"
+description = "Default number of tasks to list"
+
+[config.schema.token]
+type = "string"
+required = true
+"#;
+
+        let manifest = PluginManifest::from_toml(toml).unwrap();
+        let schema = &manifest.config.schema;
+        assert_eq!(schema["limit"].value_type, ConfigValueType::Integer);
+        assert!(!schema["limit"].required);
+        assert_eq!(schema["token"].value_type, ConfigValueType::String);
+        assert!(schema["token"].required);
+    }
+
     #[test]
     fn test_no_cli_config() {
         let toml = r#"
@@ -1,13 +1,15 @@
-use lib_plugin_abi_v3::PluginContext;
+use lib_plugin_abi_v3::services::{AdiService, PluginServices};
+use lib_plugin_abi_v3::{PluginContext, PluginError, Result};
 use serde_json::Value;
 use std::path::{Path, PathBuf};
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
 struct PluginCtxInner {
     plugin_id: String,
     data_dir: PathBuf,
     config_dir: PathBuf,
     config: Value,
+    services: Option<Arc<dyn PluginServices>>,
 }
 
 static CTX: OnceLock<PluginCtxInner> = OnceLock::new();
@@ -30,6 +32,7 @@ impl PluginCtx {
             data_dir: ctx.data_dir.clone(),
             config_dir: ctx.config_dir.clone(),
             config: ctx.config.clone(),
+            services: ctx.services.clone(),
         });
     }
 
@@ -57,4 +60,13 @@ impl PluginCtx {
     pub fn config() -> &'static Value {
         &Self::inner().config
     }
+
+    /// ADI service of another plugin (e.g. `"adi.tasks"`) for the active
+    /// project, loading that plugin if needed.
+    pub async fn adi_service(plugin_id: &str) -> Result<Arc<dyn AdiService>> {
+        let services = Self::inner().services.clone().ok_or_else(|| {
+            PluginError::Runtime("The host does not offer plugin services".to_string())
+        })?;
+        services.adi_service(plugin_id).await
+    }
 }
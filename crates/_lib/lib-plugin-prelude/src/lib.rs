@@ -66,7 +66,8 @@ pub use lib_plugin_abi_v3::{
     async_trait,
     // CLI types - CliArgs trait available for explicit use
     cli::{
-        CliArg, CliArgType, CliArgs as CliArgsTrait, CliCommand, CliCommands, CliContext, CliResult,
+        CliArg, CliArgType, CliArgs as CliArgsTrait, CliCommand, CliCommands, CliContext, CliError,
+        CliErrorCode, CliResult,
     },
     // Daemon types
     daemon::{
@@ -75,6 +76,12 @@ pub use lib_plugin_abi_v3::{
     },
     // HTTP types
     http::{HttpMethod, HttpRequest, HttpResponse, HttpRoute, HttpRoutes},
+    // Active project
+    project::ProjectContext,
+    // Search types
+    search::{rank_score, SearchHit, SearchProvider, SearchQuery},
+    // Services plugins offer each other
+    services::{AdiService, AdiServiceProvider, PluginServices},
     // WebRTC types
     webrtc::{Message, Peer, WebRtcHandlers},
     // Core plugin traits
@@ -88,10 +95,12 @@ pub use lib_plugin_abi_v3::{
     PluginType,
     Result,
     // Service identifiers
+    SERVICE_ADI_SERVICE,
     SERVICE_CLI_COMMANDS,
     SERVICE_DAEMON_SERVICE,
     SERVICE_GLOBAL_COMMANDS,
     SERVICE_HTTP_ROUTES,
+    SERVICE_SEARCH_PROVIDER,
     SERVICE_WEBRTC_HANDLERS,
 };
 
@@ -113,5 +122,8 @@ pub fn init_plugin_i18n(default_lang: &str, ftl_content: &str) {
 // === Async Support ===
 pub use tokio::sync::RwLock;
 
-/// Command result type alias for convenience
-pub type CmdResult = std::result::Result<String, String>;
+/// Command result type alias for convenience.
+///
+/// Use `CmdResult<CliError>` for categorized exit codes and remediation hints;
+/// plain `String` errors exit with code 1.
+pub type CmdResult<E = String> = std::result::Result<String, E>;
@@ -101,6 +101,11 @@ fn analyze_type(ty: &Type) -> (bool, &Type, &'static str) {
     (false, ty, "String")
 }
 
+fn is_vec(ty: &Type) -> bool {
+    matches!(ty, Type::Path(type_path)
+        if type_path.path.segments.last().is_some_and(|s| s.ident == "Vec"))
+}
+
 /// Get CLI arg type string from Rust type
 fn get_cli_arg_type(ty: &Type) -> &'static str {
     if let Type::Path(type_path) = ty {
@@ -189,7 +194,15 @@ pub fn expand_cli_args(input: DeriveInput) -> Result<TokenStream> {
         if let Some(pos) = attr.position {
             // Positional argument
             let pos_usize = pos as usize;
-            if is_optional {
+            if is_vec(field_type) {
+                // Takes this and all following positionals
+                parse_items.push(quote! {
+                    let #field_name: #field_type = __ctx.args.iter()
+                        .skip(#pos_usize)
+                        .map(|s| s.parse().map_err(|_| format!("Invalid value for {}", #arg_name)))
+                        .collect::<std::result::Result<_, String>>()?;
+                });
+            } else if is_optional {
                 parse_items.push(quote! {
                     let #field_name: #field_type = __ctx.arg(#pos_usize).map(|s| s.parse().ok()).flatten();
                 });
@@ -146,12 +146,14 @@ pub fn expand_command(
         };
 
         let body = quote! {
-            let #args_name = <#args_ty as CliArgsTrait>::parse(__ctx)
-                .map_err(|e| PluginError::InvalidInput(e))?;
+            let #args_name = match <#args_ty as CliArgsTrait>::parse(__ctx) {
+                Ok(args) => args,
+                Err(e) => return Ok(CliResult::failure(CliError::invalid_input(e))),
+            };
             let result = self.#fn_name(#args_name).await;
             match result {
                 Ok(output) => Ok(CliResult::success(output)),
-                Err(e) => Ok(CliResult::error(e)),
+                Err(e) => Ok(CliResult::failure(e)),
             }
         };
 
@@ -163,7 +165,7 @@ pub fn expand_command(
             let result = self.#fn_name().await;
             match result {
                 Ok(output) => Ok(CliResult::success(output)),
-                Err(e) => Ok(CliResult::error(e)),
+                Err(e) => Ok(CliResult::failure(e)),
             }
         };
 
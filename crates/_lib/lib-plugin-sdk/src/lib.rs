@@ -308,6 +308,7 @@ pub fn daemon_sudo(input: TokenStream) -> TokenStream {
 /// | `Option<i32>` | no | Int |
 /// | `bool` | no (flag) | Bool |
 /// | `f64` | yes | Float |
+/// | `Vec<String>` (positional) | no | String, this and all later positionals |
 ///
 /// # Example
 ///
@@ -0,0 +1,276 @@
+//! Retry and backoff policies shared by the ADI clients.
+//!
+//! A [`RetryPolicy`] describes how long to wait between retries and when to
+//! give up; a [`Backoff`] walks it for one operation. Callers that report
+//! progress (reconnect callbacks, log lines) drive the [`Backoff`] themselves,
+//! everyone else can use [`retry_if`] (feature `tokio`).
+//!
+//! ```
+//! use lib_retry::RetryPolicy;
+//! use std::time::Duration;
+//!
+//! let policy = RetryPolicy::exponential(Duration::from_millis(100), Duration::from_secs(3))
+//!     .max_attempts(4);
+//! let delays: Vec<_> = policy.backoff().map(|d| d.as_millis()).collect();
+//! assert_eq!(delays, vec![100, 200, 400]);
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+/// When and how often to retry a failing operation.
+///
+/// Retries are counted after the first attempt: retry 1 waits
+/// `initial_delay`, each later one `factor` times longer, capped at
+/// `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// Growth per retry; 1 keeps the delay fixed
+    pub factor: u32,
+    /// Up to this percentage of each delay is added at random, so clients
+    /// that failed together do not retry together
+    pub jitter: u32,
+    /// `None` retries until the budget runs out, or forever without one
+    pub max_retries: Option<u32>,
+    /// Give up once a retry would start later than this after the first attempt
+    pub budget: Option<Duration>,
+}
+
+impl RetryPolicy {
+    /// Doubling delays from `initial` up to `max`, retrying forever
+    pub const fn exponential(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial_delay: initial,
+            max_delay: max,
+            factor: 2,
+            jitter: 0,
+            max_retries: None,
+            budget: None,
+        }
+    }
+
+    /// The same delay before every retry
+    pub const fn fixed(delay: Duration) -> Self {
+        Self {
+            factor: 1,
+            ..Self::exponential(delay, delay)
+        }
+    }
+
+    /// Never retry
+    pub const fn disabled() -> Self {
+        Self::fixed(Duration::ZERO).max_retries(0)
+    }
+
+    pub const fn factor(mut self, factor: u32) -> Self {
+        self.factor = factor;
+        self
+    }
+
+    pub const fn jitter(mut self, percent: u32) -> Self {
+        self.jitter = percent;
+        self
+    }
+
+    pub const fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = Some(retries);
+        self
+    }
+
+    /// Limit the total number of tries, counting the first
+    pub const fn max_attempts(self, attempts: u32) -> Self {
+        self.max_retries(attempts.saturating_sub(1))
+    }
+
+    pub const fn budget(mut self, budget: Duration) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Delay before retry `retry` (from 1), without jitter or limits
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(self.factor.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay)
+    }
+
+    /// Start walking the policy for one operation
+    pub fn backoff(&self) -> Backoff {
+        Backoff {
+            policy: *self,
+            retries: 0,
+            started: Instant::now(),
+            waited: Duration::ZERO,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    /// 1s doubling up to 30s, retrying forever
+    fn default() -> Self {
+        Self::exponential(Duration::from_secs(1), Duration::from_secs(30))
+    }
+}
+
+/// Delays for the retries of one operation, ending when the policy gives up.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    policy: RetryPolicy,
+    retries: u32,
+    started: Instant,
+    /// Sum of the delays handed out, for callers that do not sleep them
+    waited: Duration,
+}
+
+impl Backoff {
+    /// Retries handed out so far
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Delay before the next retry, or `None` once attempts or budget are used up
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self
+            .policy
+            .max_retries
+            .is_some_and(|max| self.retries >= max)
+        {
+            return None;
+        }
+
+        let base = self.policy.delay(self.retries + 1);
+        let delay = base + jitter(base, self.policy.jitter);
+        if let Some(budget) = self.policy.budget {
+            if self.started.elapsed().max(self.waited) + delay > budget {
+                return None;
+            }
+        }
+
+        self.retries += 1;
+        self.waited += delay;
+        Some(delay)
+    }
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        self.next_delay()
+    }
+}
+
+/// Random extra delay of up to `percent` of `delay`
+fn jitter(delay: Duration, percent: u32) -> Duration {
+    let max = delay.as_millis() as u64 * percent as u64 / 100;
+    if max == 0 {
+        return Duration::ZERO;
+    }
+    // Every RandomState is freshly keyed, which is all the randomness jitter needs
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(random % (max + 1))
+}
+
+/// Run `op` until it succeeds, `should_retry` rejects its error, or `policy`
+/// gives up. The last error is returned.
+#[cfg(feature = "tokio")]
+pub async fn retry_if<T, E, F, Fut, P>(
+    policy: &RetryPolicy,
+    mut op: F,
+    should_retry: P,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+    P: Fn(&E) -> bool,
+{
+    let mut backoff = policy.backoff();
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if should_retry(&e) => match backoff.next_delay() {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(backoff: Backoff) -> Vec<u64> {
+        backoff.map(|d| d.as_secs()).collect()
+    }
+
+    #[test]
+    fn test_exponential_delays_are_capped() {
+        let policy = RetryPolicy::exponential(Duration::from_secs(1), Duration::from_secs(10))
+            .max_retries(6);
+        assert_eq!(secs(policy.backoff()), vec![1, 2, 4, 8, 10, 10]);
+        assert_eq!(policy.delay(100), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_attempt_limits() {
+        let policy = RetryPolicy::fixed(Duration::from_secs(2)).max_attempts(3);
+        assert_eq!(secs(policy.backoff()), vec![2, 2]);
+        assert_eq!(RetryPolicy::disabled().backoff().next(), None);
+        assert!(RetryPolicy::default().backoff().nth(1000).is_some());
+    }
+
+    #[test]
+    fn test_budget_stops_retries() {
+        let policy = RetryPolicy::exponential(Duration::from_secs(1), Duration::from_secs(60))
+            .budget(Duration::from_secs(5));
+        // A third wait of 4s would end 7s after the first try
+        assert_eq!(secs(policy.backoff()), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_percentage() {
+        let policy = RetryPolicy::fixed(Duration::from_secs(10)).jitter(20);
+        for delay in policy.backoff().take(100) {
+            assert!(delay >= Duration::from_secs(10));
+            assert!(delay <= Duration::from_secs(12));
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_retry_if() {
+        use std::cell::Cell;
+
+        let policy = RetryPolicy::fixed(Duration::from_millis(1)).max_attempts(3);
+        let calls = Cell::new(0);
+        let result: Result<(), &str> = retry_if(
+            &policy,
+            || {
+                calls.set(calls.get() + 1);
+                async { Err("down") }
+            },
+            |_| true,
+        )
+        .await;
+        assert_eq!(result, Err("down"));
+        assert_eq!(calls.get(), 3);
+
+        calls.set(0);
+        let result: Result<(), &str> = retry_if(
+            &policy,
+            || {
+                calls.set(calls.get() + 1);
+                async { Err("fatal") }
+            },
+            |e| *e != "fatal",
+        )
+        .await;
+        assert_eq!(result, Err("fatal"));
+        assert_eq!(calls.get(), 1);
+    }
+}
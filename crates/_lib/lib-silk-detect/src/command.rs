@@ -0,0 +1,224 @@
+use crate::{Detection, InteractiveKind};
+
+/// Programs that take over the screen
+const TUI_PROGRAMS: &[&str] = &[
+    "vim", "nvim", "vi", "nano", "emacs", "top", "htop", "btop", "fzf", "lazygit", "tig", "claude",
+    "tmux", "screen", "mc", "ranger", "nnn",
+];
+
+const PAGERS: &[&str] = &["less", "more", "most", "man"];
+
+/// REPLs that are only interactive when started without a script or command
+const REPLS: &[&str] = &[
+    "python",
+    "python3",
+    "node",
+    "irb",
+    "psql",
+    "mysql",
+    "sqlite3",
+    "mongosh",
+    "redis-cli",
+    "ghci",
+    "lua",
+    "bash",
+    "zsh",
+    "sh",
+];
+
+/// Flags after which a REPL runs non-interactively
+const REPL_BATCH_FLAGS: &[&str] = &["-c", "-e", "--eval", "-f", "--file", "--command"];
+
+/// Commands that may ask for a password, depending on cached credentials
+const PASSWORD_PROGRAMS: &[&str] = &["sudo", "su", "passwd", "gpg", "ssh-add"];
+
+const INTERACTIVE_FLAGS: &[&str] = &["-i", "-it", "-ti", "--interactive", "--tty"];
+
+/// Wrappers whose first non-flag argument is the command that actually runs
+const WRAPPERS: &[&str] = &["sudo", "env", "nice", "time", "exec", "command"];
+
+/// Classify a shell command line before running it. Returns `None` when
+/// nothing suggests the command needs a terminal.
+pub fn detect_command(command: &str) -> Option<Detection> {
+    let tokens: Vec<&str> = command.split_whitespace().collect();
+    let mut detection = None;
+    let mut rest = &tokens[..];
+
+    // Skip env assignments (FOO=bar cmd) and wrappers (sudo cmd)
+    loop {
+        match rest.first() {
+            Some(token) if is_assignment(token) => rest = &rest[1..],
+            Some(token) if WRAPPERS.contains(&program_name(token)) => {
+                let wrapper = program_name(token);
+                if PASSWORD_PROGRAMS.contains(&wrapper) {
+                    detection = Some(Detection::new(
+                        InteractiveKind::PasswordPrompt,
+                        0.5,
+                        format!("'{}' may ask for a password", wrapper),
+                    ));
+                }
+                rest = &rest[1..];
+                while rest
+                    .first()
+                    .is_some_and(|t| t.starts_with('-') || is_assignment(t))
+                {
+                    rest = &rest[1..];
+                }
+            }
+            _ => break,
+        }
+    }
+
+    let Some((program, args)) = rest.split_first() else {
+        return detection;
+    };
+    let name = program_name(program);
+
+    detection = Detection::strongest(detection, detect_program(name, args));
+
+    if args.iter().any(|a| INTERACTIVE_FLAGS.contains(a)) {
+        detection = Detection::strongest(
+            detection,
+            Some(Detection::new(
+                InteractiveKind::Repl,
+                0.75,
+                "interactive flag",
+            )),
+        );
+    }
+
+    detection
+}
+
+fn detect_program(name: &str, args: &[&str]) -> Option<Detection> {
+    if TUI_PROGRAMS.contains(&name) {
+        return Some(Detection::new(
+            InteractiveKind::Tui,
+            0.95,
+            format!("'{}' is a full-screen program", name),
+        ));
+    }
+
+    if PAGERS.contains(&name) {
+        return Some(Detection::new(
+            InteractiveKind::Pager,
+            0.9,
+            format!("'{}' is a pager", name),
+        ));
+    }
+
+    if REPLS.contains(&name) {
+        let batch = args.iter().any(|a| REPL_BATCH_FLAGS.contains(a));
+        let script = args.iter().any(|a| !a.starts_with('-'));
+        let confidence = if batch || script { 0.2 } else { 0.9 };
+        return Some(Detection::new(
+            InteractiveKind::Repl,
+            confidence,
+            format!("'{}' starts a REPL without a script", name),
+        ));
+    }
+
+    if name == "rails" && args.first().is_some_and(|a| *a == "c" || *a == "console") {
+        return Some(Detection::new(
+            InteractiveKind::Repl,
+            0.9,
+            "'rails console' is a REPL",
+        ));
+    }
+
+    if name == "ssh" {
+        // `ssh host` opens a shell; `ssh host cmd` still asks for passwords
+        // and host keys on the terminal, so both need a PTY
+        let positional = args.iter().filter(|a| !a.starts_with('-')).count();
+        return Some(if positional <= 1 {
+            Detection::new(InteractiveKind::Repl, 0.9, "'ssh' opens a remote shell")
+        } else {
+            Detection::new(
+                InteractiveKind::PasswordPrompt,
+                0.75,
+                "'ssh' may ask for a password or host key confirmation",
+            )
+        });
+    }
+
+    if PASSWORD_PROGRAMS.contains(&name) {
+        return Some(Detection::new(
+            InteractiveKind::PasswordPrompt,
+            0.6,
+            format!("'{}' may ask for a password", name),
+        ));
+    }
+
+    None
+}
+
+/// `/usr/bin/vim` -> `vim`
+fn program_name(token: &str) -> &str {
+    token.rsplit('/').next().unwrap_or(token)
+}
+
+fn is_assignment(token: &str) -> bool {
+    token.split_once('=').is_some_and(|(key, _)| {
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn confident(command: &str) -> bool {
+        detect_command(command).is_some_and(|d| d.is_confident())
+    }
+
+    #[test]
+    fn test_known_programs() {
+        assert!(confident("vim"));
+        assert!(confident("vim file.txt"));
+        assert!(confident("/usr/bin/vim"));
+        assert!(confident("claude"));
+        assert!(confident("python"));
+        assert!(confident("python3 -i"));
+        assert!(confident("rails c"));
+        assert!(confident("ssh prod"));
+        assert!(!confident("ls"));
+        assert!(!confident("cat file.txt"));
+        assert!(!confident("echo hello"));
+    }
+
+    #[test]
+    fn test_repl_with_script_is_batch() {
+        assert!(!confident("python3 script.py"));
+        assert!(!confident("node -e 'console.log(1)'"));
+        assert!(!confident("psql -c 'select 1'"));
+    }
+
+    #[test]
+    fn test_ssh_always_needs_a_terminal() {
+        assert_eq!(
+            detect_command("ssh prod").unwrap().kind,
+            InteractiveKind::Repl
+        );
+        let detection = detect_command("ssh -p 2222 prod uptime").unwrap();
+        assert_eq!(detection.kind, InteractiveKind::PasswordPrompt);
+        assert!(detection.is_confident());
+    }
+
+    #[test]
+    fn test_wrappers_and_assignments() {
+        let detection = detect_command("sudo -E FOO=1 htop").unwrap();
+        assert_eq!(detection.kind, InteractiveKind::Tui);
+
+        assert!(confident("EDITOR=nano nano notes"));
+
+        let detection = detect_command("sudo apt update").unwrap();
+        assert_eq!(detection.kind, InteractiveKind::PasswordPrompt);
+        assert!(!detection.is_confident());
+    }
+
+    #[test]
+    fn test_interactive_flag_is_exact() {
+        assert!(confident("docker run -it alpine"));
+        assert!(!confident("grep -ignore-case foo"));
+    }
+}
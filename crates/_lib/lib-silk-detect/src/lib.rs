@@ -0,0 +1,84 @@
+//! Interactive command and prompt detection for Silk
+//!
+//! Silk runs commands with piped I/O unless they need a terminal. This crate
+//! decides that, both up front from the command line and while a command
+//! runs from its output, and scores every guess with a confidence.
+//!
+//! ```
+//! use lib_silk_detect::{detect_command, InteractiveKind, OutputDetector};
+//!
+//! let detection = detect_command("vim notes.md").unwrap();
+//! assert_eq!(detection.kind, InteractiveKind::Tui);
+//! assert!(detection.is_confident());
+//!
+//! let mut detector = OutputDetector::new();
+//! let detection = detector.feed(b"[sudo] password for adi: ").unwrap();
+//! assert_eq!(detection.kind, InteractiveKind::PasswordPrompt);
+//! ```
+
+mod command;
+mod output;
+
+pub use command::detect_command;
+pub use output::OutputDetector;
+
+/// Minimum confidence at which a detection should switch a command to a PTY
+pub const CONFIDENCE_THRESHOLD: f32 = 0.7;
+
+/// What kind of interaction a command is waiting for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InteractiveKind {
+    /// Secret input without echo (sudo, ssh, gpg)
+    PasswordPrompt,
+    /// Yes/no question
+    Confirmation,
+    /// Paged output (less, more, man)
+    Pager,
+    /// Read-eval-print loop or remote shell
+    Repl,
+    /// Full-screen program (editors, top, alternate screen users)
+    Tui,
+}
+
+impl std::fmt::Display for InteractiveKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InteractiveKind::PasswordPrompt => write!(f, "password prompt"),
+            InteractiveKind::Confirmation => write!(f, "confirmation"),
+            InteractiveKind::Pager => write!(f, "pager"),
+            InteractiveKind::Repl => write!(f, "repl"),
+            InteractiveKind::Tui => write!(f, "tui"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub kind: InteractiveKind,
+    /// 0.0 (guess) to 1.0 (certain)
+    pub confidence: f32,
+    /// Human-readable evidence, e.g. "alternate screen enabled"
+    pub reason: String,
+}
+
+impl Detection {
+    pub fn new(kind: InteractiveKind, confidence: f32, reason: impl Into<String>) -> Self {
+        Self {
+            kind,
+            confidence,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn is_confident(&self) -> bool {
+        self.confidence >= CONFIDENCE_THRESHOLD
+    }
+
+    /// The more confident of two optional detections
+    pub fn strongest(a: Option<Detection>, b: Option<Detection>) -> Option<Detection> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(if b.confidence > a.confidence { b } else { a }),
+            (a, b) => a.or(b),
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use crate::{Detection, InteractiveKind};
+
+/// Longest control sequence matched, kept between chunks so sequences split
+/// across reads are still seen
+const CARRY_BYTES: usize = 16;
+
+/// Prompts are short; anything longer is ordinary output
+const MAX_PENDING_LINE: usize = 512;
+
+/// Control sequences a program writes when it drives the terminal itself
+const CONTROL_SEQUENCES: &[(&[u8], InteractiveKind, f32, &str)] = &[
+    (
+        b"\x1b[?1049h",
+        InteractiveKind::Tui,
+        0.95,
+        "alternate screen enabled",
+    ),
+    (
+        b"\x1b[?1047h",
+        InteractiveKind::Tui,
+        0.95,
+        "alternate screen enabled",
+    ),
+    (
+        b"\x1b[?47h",
+        InteractiveKind::Tui,
+        0.9,
+        "alternate screen enabled",
+    ),
+    (
+        b"\x1b[?1000h",
+        InteractiveKind::Tui,
+        0.85,
+        "mouse tracking enabled",
+    ),
+    (
+        b"\x1b[?1002h",
+        InteractiveKind::Tui,
+        0.85,
+        "mouse tracking enabled",
+    ),
+    (
+        b"\x1b[?1006h",
+        InteractiveKind::Tui,
+        0.85,
+        "mouse tracking enabled",
+    ),
+    (
+        b"\x1bP+q",
+        InteractiveKind::Tui,
+        0.8,
+        "terminfo capability query",
+    ),
+    (
+        b"\x1b[6n",
+        InteractiveKind::Tui,
+        0.8,
+        "cursor position query",
+    ),
+    (
+        b"\x1b[>c",
+        InteractiveKind::Tui,
+        0.8,
+        "terminal identification query",
+    ),
+    (
+        b"\x1b[c",
+        InteractiveKind::Tui,
+        0.75,
+        "terminal identification query",
+    ),
+    (
+        b"\x1b]11;?",
+        InteractiveKind::Tui,
+        0.75,
+        "background color query",
+    ),
+    (
+        b"\x1b[?2004h",
+        InteractiveKind::Repl,
+        0.6,
+        "bracketed paste enabled",
+    ),
+];
+
+/// Line endings that ask for a yes/no answer
+const CONFIRMATION_SUFFIXES: &[&str] = &[
+    "[y/n]",
+    "(y/n)",
+    "[y/n]?",
+    "(y/n)?",
+    "(yes/no)",
+    "(yes/no)?",
+    "(yes/no/[fingerprint])?",
+];
+
+/// Known REPL prompts, matched against the end of the pending line
+const REPL_PROMPTS: &[(&str, f32)] = &[
+    (">>> ", 0.9),
+    ("sqlite> ", 0.9),
+    ("mysql> ", 0.9),
+    ("=# ", 0.85),
+    ("=> ", 0.6),
+    ("> ", 0.5),
+];
+
+/// Streaming detector fed with a command's raw output
+#[derive(Debug, Default)]
+pub struct OutputDetector {
+    /// Tail of the previous chunk, for split control sequences
+    carry: Vec<u8>,
+    /// Text after the last newline, ANSI sequences removed
+    pending_line: String,
+    /// Unterminated escape sequence at the end of the previous chunk
+    partial_escape: String,
+}
+
+impl OutputDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scan the next chunk of output. Returns the strongest signal in it:
+    /// a control sequence, or a prompt the output stopped at.
+    pub fn feed(&mut self, chunk: &[u8]) -> Option<Detection> {
+        let mut scan = std::mem::take(&mut self.carry);
+        let carried = scan.len();
+        scan.extend_from_slice(chunk);
+
+        let mut detection = None;
+        for (sequence, kind, confidence, reason) in CONTROL_SEQUENCES {
+            // Sequences entirely inside the carry were reported last time
+            let start = carried.saturating_sub(sequence.len() - 1);
+            if find(&scan[start..], sequence).is_some() {
+                detection = Detection::strongest(
+                    detection,
+                    Some(Detection::new(*kind, *confidence, *reason)),
+                );
+            }
+        }
+
+        let keep = scan.len().min(CARRY_BYTES);
+        self.carry = scan[scan.len() - keep..].to_vec();
+
+        let mut raw = std::mem::take(&mut self.partial_escape);
+        raw.push_str(&String::from_utf8_lossy(chunk));
+        let (text, partial) = strip_ansi(&raw);
+        if partial.len() <= MAX_PENDING_LINE {
+            self.partial_escape = partial.to_string();
+        }
+        match text.rfind(['\n', '\r']) {
+            Some(at) => self.pending_line = text[at + 1..].to_string(),
+            None => self.pending_line.push_str(&text),
+        }
+        if self.pending_line.len() > MAX_PENDING_LINE {
+            self.pending_line.clear();
+        }
+
+        Detection::strongest(detection, detect_prompt(&self.pending_line))
+    }
+
+    /// Text the output currently stops at, without ANSI sequences
+    pub fn pending_line(&self) -> &str {
+        &self.pending_line
+    }
+
+    pub fn reset(&mut self) {
+        self.carry.clear();
+        self.pending_line.clear();
+        self.partial_escape.clear();
+    }
+}
+
+/// Classify an unterminated output line the program may be waiting on
+fn detect_prompt(line: &str) -> Option<Detection> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    let lower = line.to_lowercase();
+    let trimmed = lower.trim_end();
+
+    let secret = ["password", "passphrase", "passcode"]
+        .iter()
+        .any(|word| trimmed.contains(word));
+    if secret {
+        let confidence = if trimmed.ends_with(':') { 0.95 } else { 0.5 };
+        return Some(Detection::new(
+            InteractiveKind::PasswordPrompt,
+            confidence,
+            format!("password prompt '{}'", line.trim()),
+        ));
+    }
+
+    if CONFIRMATION_SUFFIXES.iter().any(|s| trimmed.ends_with(s)) {
+        return Some(Detection::new(
+            InteractiveKind::Confirmation,
+            0.9,
+            format!("confirmation prompt '{}'", line.trim()),
+        ));
+    }
+
+    if trimmed == "(end)" || trimmed.starts_with("--more--") || trimmed == ":" {
+        let confidence = if trimmed == ":" { 0.6 } else { 0.9 };
+        return Some(Detection::new(
+            InteractiveKind::Pager,
+            confidence,
+            "pager status line",
+        ));
+    }
+
+    if line.starts_with("irb(") && line.ends_with("> ") {
+        return Some(Detection::new(
+            InteractiveKind::Repl,
+            0.9,
+            format!("REPL prompt '{}'", line.trim()),
+        ));
+    }
+
+    // Prompts keep their trailing space, so match on the untrimmed line
+    for (prompt, confidence) in REPL_PROMPTS {
+        if line.ends_with(prompt) {
+            return Some(Detection::new(
+                InteractiveKind::Repl,
+                *confidence,
+                format!("REPL prompt '{}'", line.trim()),
+            ));
+        }
+    }
+
+    if trimmed.ends_with('?') {
+        return Some(Detection::new(
+            InteractiveKind::Confirmation,
+            0.4,
+            format!("question '{}'", line.trim()),
+        ));
+    }
+
+    None
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Remove CSI (`ESC [ ... final`) and OSC/DCS (`ESC ] ... BEL/ST`)
+/// sequences. Also returns a trailing sequence that is not terminated yet.
+fn strip_ansi(input: &str) -> (String, &str) {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        let terminated = match chars.next() {
+            Some((_, '[')) => chars.by_ref().any(|(_, c)| ('@'..='~').contains(&c)),
+            Some((_, ']')) | Some((_, 'P')) => loop {
+                match chars.next() {
+                    Some((_, '\x07')) => break true,
+                    Some((_, '\x1b')) if chars.peek().is_some_and(|(_, c)| *c == '\\') => {
+                        chars.next();
+                        break true;
+                    }
+                    Some(_) => {}
+                    None => break false,
+                }
+            },
+            Some(_) => true,
+            None => false,
+        };
+        if !terminated {
+            return (out, &input[start..]);
+        }
+    }
+
+    (out, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_control_sequence() {
+        let mut detector = OutputDetector::new();
+        assert!(detector.feed(b"loading\n\x1b[?10").is_none());
+
+        let detection = detector.feed(b"49h\x1b[H").unwrap();
+        assert_eq!(detection.kind, InteractiveKind::Tui);
+        assert!(detection.is_confident());
+
+        // Already reported, not repeated from the carry
+        assert!(detector.feed(b"\n").is_none());
+    }
+
+    #[test]
+    fn test_prompt_across_chunks() {
+        let mut detector = OutputDetector::new();
+        assert!(detector
+            .feed(b"Proceed with installation? ")
+            .is_some_and(|d| !d.is_confident()));
+
+        let detection = detector.feed(b"[Y/n] ").unwrap();
+        assert_eq!(detection.kind, InteractiveKind::Confirmation);
+        assert!(detection.is_confident());
+        assert_eq!(detector.pending_line(), "Proceed with installation? [Y/n] ");
+    }
+
+    #[test]
+    fn test_plain_output_is_not_a_prompt() {
+        let mut detector = OutputDetector::new();
+        assert!(detector.feed(b"Compiling lib v0.1.0\n").is_none());
+        assert!(detector
+            .feed(b"\x1b[32mFinished\x1b[0m release\n")
+            .is_none());
+        assert!(detector
+            .feed(b"error: password must be set in config\n")
+            .is_none());
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[1;31mred\x1b[0m"), ("red".to_string(), ""));
+        assert_eq!(strip_ansi("\x1b]0;title\x07>>> "), (">>> ".to_string(), ""));
+        assert_eq!(strip_ansi("ok\x1b[3"), ("ok".to_string(), "\x1b[3"));
+    }
+}
@@ -0,0 +1,93 @@
+//! Replays recorded terminal transcripts through the output detector.
+
+use lib_silk_detect::{Detection, InteractiveKind, OutputDetector};
+
+/// Feed in small chunks so sequences and prompts split across reads
+const CHUNK_SIZE: usize = 7;
+
+fn replay(transcript: &[u8]) -> Option<Detection> {
+    let mut detector = OutputDetector::new();
+    transcript.chunks(CHUNK_SIZE).fold(None, |best, chunk| {
+        Detection::strongest(best, detector.feed(chunk))
+    })
+}
+
+fn assert_detects(transcript: &[u8], kind: InteractiveKind) {
+    let detection = replay(transcript).expect("nothing detected");
+    assert_eq!(detection.kind, kind, "{:?}", detection);
+    assert!(detection.is_confident(), "{:?}", detection);
+}
+
+#[test]
+fn test_sudo_password() {
+    assert_detects(
+        include_bytes!("transcripts/sudo_password.log"),
+        InteractiveKind::PasswordPrompt,
+    );
+}
+
+#[test]
+fn test_apt_confirmation() {
+    assert_detects(
+        include_bytes!("transcripts/apt_confirm.log"),
+        InteractiveKind::Confirmation,
+    );
+}
+
+#[test]
+fn test_ssh_host_key_confirmation() {
+    assert_detects(
+        include_bytes!("transcripts/ssh_host_key.log"),
+        InteractiveKind::Confirmation,
+    );
+}
+
+#[test]
+fn test_vim_alternate_screen() {
+    assert_detects(
+        include_bytes!("transcripts/vim_startup.log"),
+        InteractiveKind::Tui,
+    );
+}
+
+#[test]
+fn test_terminfo_query() {
+    assert_detects(
+        include_bytes!("transcripts/terminfo_query.log"),
+        InteractiveKind::Tui,
+    );
+}
+
+#[test]
+fn test_python_repl() {
+    assert_detects(
+        include_bytes!("transcripts/python_repl.log"),
+        InteractiveKind::Repl,
+    );
+}
+
+#[test]
+fn test_psql_repl() {
+    assert_detects(
+        include_bytes!("transcripts/psql_repl.log"),
+        InteractiveKind::Repl,
+    );
+}
+
+#[test]
+fn test_man_pager() {
+    assert_detects(
+        include_bytes!("transcripts/man_pager.log"),
+        InteractiveKind::Pager,
+    );
+}
+
+#[test]
+fn test_build_output_is_not_interactive() {
+    let detection = replay(include_bytes!("transcripts/cargo_build.log"));
+    assert!(
+        !detection.as_ref().is_some_and(|d| d.is_confident()),
+        "{:?}",
+        detection
+    );
+}
@@ -0,0 +1,337 @@
+//! Debug token lifetime and scope enforcement
+//!
+//! Whoever routes `browser_debug_*` messages keeps a [`BrowserDebugGrant`]
+//! per token, built from the tab's `browser_debug_tab_available`, and passes
+//! every message for that token through [`BrowserDebugGrant::authorize`]
+//! before forwarding it. Revoked tokens are simply dropped from the router's
+//! table, after which it answers `browser_debug_token_revoked`.
+
+use crate::{
+    BrowserDebugScope, NetworkEventData, NetworkRequest, SignalingMessage, WebSocketConnection,
+    WebSocketEventData,
+};
+use std::fmt;
+
+/// What a debug token allows, and until when
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowserDebugGrant {
+    pub token: String,
+    /// Unix ms; `None` never expires
+    pub expires_at: Option<i64>,
+    pub scopes: Vec<BrowserDebugScope>,
+}
+
+/// Why a message for a debug token was not routed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrowserDebugDenied {
+    Expired,
+    /// The message needs a capability the token's scopes exclude
+    OutOfScope(&'static str),
+}
+
+impl fmt::Display for BrowserDebugDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Expired => write!(f, "debug token expired"),
+            Self::OutOfScope(what) => write!(f, "debug token does not grant {} access", what),
+        }
+    }
+}
+
+impl std::error::Error for BrowserDebugDenied {}
+
+impl BrowserDebugGrant {
+    /// Grant announced by a `browser_debug_tab_available` message
+    pub fn from_tab_available(msg: &SignalingMessage) -> Option<Self> {
+        match msg {
+            SignalingMessage::BrowserDebugTabAvailable {
+                token,
+                expires_at,
+                scopes,
+                ..
+            } => Some(Self {
+                token: token.clone(),
+                expires_at: *expires_at,
+                scopes: scopes.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn is_expired(&self, now_ms: i64) -> bool {
+        self.expires_at.is_some_and(|at| now_ms >= at)
+    }
+
+    pub fn allows_network(&self) -> bool {
+        !self.scopes.contains(&BrowserDebugScope::ConsoleOnly)
+    }
+
+    pub fn allows_console(&self) -> bool {
+        !self.scopes.contains(&BrowserDebugScope::NetworkOnly)
+    }
+
+    pub fn allows_bodies(&self) -> bool {
+        !self.scopes.contains(&BrowserDebugScope::NoBodies)
+    }
+
+    /// Check a message routed for this token, stripping bodies if the scopes
+    /// hide them. Messages unrelated to network or console data pass through.
+    pub fn authorize(
+        &self,
+        msg: SignalingMessage,
+        now_ms: i64,
+    ) -> Result<SignalingMessage, BrowserDebugDenied> {
+        if self.is_expired(now_ms) {
+            return Err(BrowserDebugDenied::Expired);
+        }
+
+        match msg {
+            SignalingMessage::BrowserDebugNetworkEvent { token, event, data } => {
+                self.require_network()?;
+                Ok(SignalingMessage::BrowserDebugNetworkEvent {
+                    token,
+                    event,
+                    data: self.redact_event(data),
+                })
+            }
+            SignalingMessage::BrowserDebugNetworkData {
+                request_id,
+                requests,
+            } => {
+                self.require_network()?;
+                Ok(SignalingMessage::BrowserDebugNetworkData {
+                    request_id,
+                    requests: requests
+                        .into_iter()
+                        .map(|r| self.redact_request(r))
+                        .collect(),
+                })
+            }
+            SignalingMessage::BrowserDebugWebSocketEvent { token, event, data } => {
+                self.require_network()?;
+                Ok(SignalingMessage::BrowserDebugWebSocketEvent {
+                    token,
+                    event,
+                    data: self.redact_web_socket_event(data),
+                })
+            }
+            SignalingMessage::BrowserDebugWebSocketData {
+                request_id,
+                connections,
+            } => {
+                self.require_network()?;
+                Ok(SignalingMessage::BrowserDebugWebSocketData {
+                    request_id,
+                    connections: connections
+                        .into_iter()
+                        .map(|c| self.redact_connection(c))
+                        .collect(),
+                })
+            }
+            msg @ (SignalingMessage::BrowserDebugGetNetwork { .. }
+            | SignalingMessage::BrowserDebugGetWebSockets { .. }) => {
+                self.require_network()?;
+                Ok(msg)
+            }
+            msg @ (SignalingMessage::BrowserDebugConsoleEvent { .. }
+            | SignalingMessage::BrowserDebugGetConsole { .. }
+            | SignalingMessage::BrowserDebugConsoleData { .. }) => {
+                if !self.allows_console() {
+                    return Err(BrowserDebugDenied::OutOfScope("console"));
+                }
+                Ok(msg)
+            }
+            msg => Ok(msg),
+        }
+    }
+
+    fn require_network(&self) -> Result<(), BrowserDebugDenied> {
+        if self.allows_network() {
+            Ok(())
+        } else {
+            Err(BrowserDebugDenied::OutOfScope("network"))
+        }
+    }
+
+    fn redact_event(&self, mut data: NetworkEventData) -> NetworkEventData {
+        if !self.allows_bodies() {
+            data.request_body = None;
+            data.response_body = None;
+            data.response_body_truncated = None;
+        }
+        data
+    }
+
+    /// Frame sizes and directions stay; payloads count as bodies
+    fn redact_web_socket_event(&self, mut data: WebSocketEventData) -> WebSocketEventData {
+        if !self.allows_bodies() {
+            if let Some(frame) = &mut data.frame {
+                frame.payload = None;
+                frame.truncated = false;
+            }
+        }
+        data
+    }
+
+    fn redact_connection(&self, mut connection: WebSocketConnection) -> WebSocketConnection {
+        if !self.allows_bodies() {
+            for frame in &mut connection.frames {
+                frame.payload = None;
+                frame.truncated = false;
+            }
+        }
+        connection
+    }
+
+    fn redact_request(&self, mut request: NetworkRequest) -> NetworkRequest {
+        if !self.allows_bodies() {
+            request.request_body = None;
+            request.response_body = None;
+            request.response_body_truncated = None;
+        }
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ConsoleEntry, ConsoleLevel, NetworkEventType, WebSocketDirection, WebSocketEventType,
+        WebSocketFrame, WebSocketOpcode,
+    };
+
+    fn grant(expires_at: Option<i64>, scopes: Vec<BrowserDebugScope>) -> BrowserDebugGrant {
+        BrowserDebugGrant {
+            token: "tok".to_string(),
+            expires_at,
+            scopes,
+        }
+    }
+
+    fn network_event() -> SignalingMessage {
+        SignalingMessage::BrowserDebugNetworkEvent {
+            token: "tok".to_string(),
+            event: NetworkEventType::Finished,
+            data: NetworkEventData {
+                request_id: "r1".to_string(),
+                timestamp: 0,
+                method: None,
+                url: None,
+                request_headers: None,
+                request_body: Some("password=hunter2".to_string()),
+                status: Some(200),
+                status_text: None,
+                response_headers: None,
+                mime_type: None,
+                response_body: Some("{}".to_string()),
+                response_body_truncated: Some(false),
+                duration_ms: None,
+                error: None,
+            },
+        }
+    }
+
+    fn console_event() -> SignalingMessage {
+        SignalingMessage::BrowserDebugConsoleEvent {
+            token: "tok".to_string(),
+            entry: ConsoleEntry {
+                timestamp: 0,
+                level: ConsoleLevel::Log,
+                message: "hi".to_string(),
+                args: vec![],
+                source: None,
+                line: None,
+                column: None,
+                stack_trace: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_expiry() {
+        let g = grant(Some(1_000), vec![]);
+        assert!(g.authorize(console_event(), 999).is_ok());
+        assert_eq!(
+            g.authorize(console_event(), 1_000).unwrap_err(),
+            BrowserDebugDenied::Expired
+        );
+        assert!(!grant(None, vec![]).is_expired(i64::MAX));
+    }
+
+    #[test]
+    fn test_scopes() {
+        let network_only = grant(None, vec![BrowserDebugScope::NetworkOnly]);
+        assert!(network_only.authorize(network_event(), 0).is_ok());
+        assert_eq!(
+            network_only.authorize(console_event(), 0).unwrap_err(),
+            BrowserDebugDenied::OutOfScope("console")
+        );
+
+        let console_only = grant(None, vec![BrowserDebugScope::ConsoleOnly]);
+        assert!(console_only.authorize(console_event(), 0).is_ok());
+        assert!(console_only.authorize(network_event(), 0).is_err());
+    }
+
+    #[test]
+    fn test_no_bodies_strips_payloads() {
+        let g = grant(None, vec![BrowserDebugScope::NoBodies]);
+        match g.authorize(network_event(), 0).unwrap() {
+            SignalingMessage::BrowserDebugNetworkEvent { data, .. } => {
+                assert!(data.request_body.is_none());
+                assert!(data.response_body.is_none());
+                assert_eq!(data.status, Some(200));
+            }
+            other => panic!("Wrong message type: {:?}", other),
+        }
+
+        // Full grants keep bodies
+        match grant(None, vec![]).authorize(network_event(), 0).unwrap() {
+            SignalingMessage::BrowserDebugNetworkEvent { data, .. } => {
+                assert!(data.request_body.is_some());
+            }
+            other => panic!("Wrong message type: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_web_socket_frames_follow_network_scopes() {
+        let frame_event = || SignalingMessage::BrowserDebugWebSocketEvent {
+            token: "tok".to_string(),
+            event: WebSocketEventType::Frame,
+            data: WebSocketEventData {
+                connection_id: "ws-1".to_string(),
+                timestamp: 0,
+                url: None,
+                protocol: None,
+                frame: Some(WebSocketFrame {
+                    timestamp: 0,
+                    direction: WebSocketDirection::Sent,
+                    opcode: WebSocketOpcode::Text,
+                    size: 24,
+                    payload: Some("{\"auth\":\"secret-token\"}".to_string()),
+                    truncated: false,
+                }),
+                close_code: None,
+                close_reason: None,
+                error: None,
+            },
+        };
+
+        let console_only = grant(None, vec![BrowserDebugScope::ConsoleOnly]);
+        assert_eq!(
+            console_only.authorize(frame_event(), 0).unwrap_err(),
+            BrowserDebugDenied::OutOfScope("network")
+        );
+
+        let g = grant(None, vec![BrowserDebugScope::NoBodies]);
+        match g.authorize(frame_event(), 0).unwrap() {
+            SignalingMessage::BrowserDebugWebSocketEvent { data, .. } => {
+                let frame = data.frame.unwrap();
+                assert!(frame.payload.is_none());
+                assert_eq!(frame.size, 24);
+            }
+            other => panic!("Wrong message type: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Chunked Silk file transfer
+//!
+//! Files move through a Silk session as base64 chunks numbered from 0:
+//! `upload_file` requests from the client, `file_chunk` responses for a
+//! `download_file`. The chunk with `done` set carries the hex SHA-256 of the
+//! whole file. [`split_file`] cuts a file into chunks on the sending side;
+//! [`FileAssembler`] puts them back in order on the receiving side and only
+//! hands the file out once the hash matches. A successful upload is answered
+//! with `file_uploaded`.
+
+use crate::{SilkRequest, SilkResponse};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use uuid::Uuid;
+
+/// Raw bytes per chunk; base64 makes a 64 KiB payload
+pub const FILE_CHUNK_BYTES: usize = 48 * 1024;
+
+/// Largest file a session accepts or serves
+pub const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Chunks buffered ahead of a missing one before the transfer is given up
+pub const MAX_PENDING_FILE_CHUNKS: usize = 256;
+
+/// Hex SHA-256 of `data`, as carried by the last chunk
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// One chunk of a file, before it is put in a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChunk {
+    pub seq: u64,
+    /// Base64-encoded bytes
+    pub chunk: String,
+    pub done: bool,
+    /// Whole-file hash, on the last chunk only
+    pub sha256: Option<String>,
+}
+
+impl FileChunk {
+    pub fn into_upload(self, session_id: Uuid, path: impl Into<String>) -> SilkRequest {
+        SilkRequest::UploadFile {
+            session_id,
+            path: path.into(),
+            chunk: self.chunk,
+            seq: self.seq,
+            done: self.done,
+            sha256: self.sha256,
+        }
+    }
+
+    pub fn into_response(self, session_id: Uuid, path: impl Into<String>) -> SilkResponse {
+        SilkResponse::FileChunk {
+            session_id,
+            path: path.into(),
+            chunk: self.chunk,
+            seq: self.seq,
+            done: self.done,
+            sha256: self.sha256,
+        }
+    }
+}
+
+/// Cut `data` into chunks of [`FILE_CHUNK_BYTES`]. An empty file is one
+/// empty last chunk.
+pub fn split_file(data: &[u8]) -> Vec<FileChunk> {
+    let sha256 = sha256_hex(data);
+    let mut chunks: Vec<FileChunk> = data
+        .chunks(FILE_CHUNK_BYTES)
+        .enumerate()
+        .map(|(seq, bytes)| FileChunk {
+            seq: seq as u64,
+            chunk: STANDARD.encode(bytes),
+            done: false,
+            sha256: None,
+        })
+        .collect();
+    if chunks.is_empty() {
+        chunks.push(FileChunk {
+            seq: 0,
+            chunk: String::new(),
+            done: false,
+            sha256: None,
+        });
+    }
+    if let Some(last) = chunks.last_mut() {
+        last.done = true;
+        last.sha256 = Some(sha256);
+    }
+    chunks
+}
+
+/// Why a chunk could not be added to a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileTransferError {
+    /// Chunk is not valid base64
+    InvalidChunk { seq: u64 },
+    /// A chunk numbered past the last one
+    ChunkOutOfRange { seq: u64, chunks: u64 },
+    /// The last chunk came without a hash
+    MissingHash,
+    /// The reassembled file does not match the announced hash
+    HashMismatch { expected: String, actual: String },
+    /// The file grew past the size limit
+    TooLarge { limit: u64 },
+    /// Too many chunks arrived ahead of a missing one
+    TooManyPending,
+}
+
+impl fmt::Display for FileTransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidChunk { seq } => write!(f, "chunk {} is not valid base64", seq),
+            Self::ChunkOutOfRange { seq, chunks } => {
+                write!(
+                    f,
+                    "chunk {} is past the end of a {} chunk file",
+                    seq, chunks
+                )
+            }
+            Self::MissingHash => write!(f, "last chunk has no sha256"),
+            Self::HashMismatch { expected, actual } => {
+                write!(f, "sha256 mismatch: expected {}, got {}", expected, actual)
+            }
+            Self::TooLarge { limit } => write!(f, "file is larger than {} bytes", limit),
+            Self::TooManyPending => write!(
+                f,
+                "more than {} chunks arrived ahead of a missing one",
+                MAX_PENDING_FILE_CHUNKS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FileTransferError {}
+
+/// Reassembles one file from its chunks
+#[derive(Debug, Clone)]
+pub struct FileAssembler {
+    max_bytes: u64,
+    data: Vec<u8>,
+    next_seq: u64,
+    /// Decoded chunks that arrived ahead of `next_seq`
+    pending: BTreeMap<u64, Vec<u8>>,
+    pending_bytes: u64,
+    /// Chunk count and hash from the last chunk
+    end: Option<(u64, String)>,
+    /// The file was handed out
+    complete: bool,
+}
+
+impl Default for FileAssembler {
+    fn default() -> Self {
+        Self::new(MAX_FILE_BYTES)
+    }
+}
+
+impl FileAssembler {
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            data: Vec::new(),
+            next_seq: 0,
+            pending: BTreeMap::new(),
+            pending_bytes: 0,
+            end: None,
+            complete: false,
+        }
+    }
+
+    /// Add a chunk. Returns the whole file once every chunk arrived and its
+    /// hash matches; chunks that were already added are ignored.
+    pub fn push(
+        &mut self,
+        seq: u64,
+        chunk: &str,
+        done: bool,
+        sha256: Option<&str>,
+    ) -> Result<Option<Vec<u8>>, FileTransferError> {
+        if let Some((chunks, _)) = &self.end {
+            if seq >= *chunks {
+                return Err(FileTransferError::ChunkOutOfRange {
+                    seq,
+                    chunks: *chunks,
+                });
+            }
+        }
+        if done {
+            let sha256 = sha256.ok_or(FileTransferError::MissingHash)?;
+            let chunks = seq + 1;
+            if let Some((&ahead, _)) = self.pending.range(chunks..).next() {
+                return Err(FileTransferError::ChunkOutOfRange { seq: ahead, chunks });
+            }
+            self.end = Some((chunks, sha256.to_ascii_lowercase()));
+        }
+        if seq < self.next_seq || self.pending.contains_key(&seq) {
+            return self.finish();
+        }
+        if seq > self.next_seq && self.pending.len() >= MAX_PENDING_FILE_CHUNKS {
+            return Err(FileTransferError::TooManyPending);
+        }
+
+        let bytes = STANDARD
+            .decode(chunk)
+            .map_err(|_| FileTransferError::InvalidChunk { seq })?;
+        let size = self.data.len() as u64 + self.pending_bytes + bytes.len() as u64;
+        if size > self.max_bytes {
+            return Err(FileTransferError::TooLarge {
+                limit: self.max_bytes,
+            });
+        }
+        self.pending_bytes += bytes.len() as u64;
+        self.pending.insert(seq, bytes);
+        while let Some(bytes) = self.pending.remove(&self.next_seq) {
+            self.pending_bytes -= bytes.len() as u64;
+            self.data.extend_from_slice(&bytes);
+            self.next_seq += 1;
+        }
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Result<Option<Vec<u8>>, FileTransferError> {
+        let Some((chunks, expected)) = &self.end else {
+            return Ok(None);
+        };
+        if self.complete || self.next_seq != *chunks {
+            return Ok(None);
+        }
+        let actual = sha256_hex(&self.data);
+        if actual != *expected {
+            return Err(FileTransferError::HashMismatch {
+                expected: expected.clone(),
+                actual,
+            });
+        }
+        self.complete = true;
+        Ok(Some(std::mem::take(&mut self.data)))
+    }
+
+    /// Every chunk arrived and the hash matched
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Bytes received so far
+    pub fn received_bytes(&self) -> u64 {
+        self.data.len() as u64 + self.pending_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assemble(chunks: &[FileChunk]) -> Result<Option<Vec<u8>>, FileTransferError> {
+        let mut assembler = FileAssembler::default();
+        let mut file = None;
+        for c in chunks {
+            file = assembler.push(c.seq, &c.chunk, c.done, c.sha256.as_deref())?;
+        }
+        Ok(file)
+    }
+
+    #[test]
+    fn test_split_and_assemble() {
+        let data: Vec<u8> = (0..FILE_CHUNK_BYTES * 2 + 10).map(|i| i as u8).collect();
+        let chunks = split_file(&data);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[..2].iter().all(|c| !c.done && c.sha256.is_none()));
+        assert_eq!(
+            chunks[2].sha256.as_deref(),
+            Some(sha256_hex(&data).as_str())
+        );
+        assert_eq!(assemble(&chunks).unwrap(), Some(data));
+
+        let empty = split_file(b"");
+        assert_eq!(empty.len(), 1);
+        assert!(empty[0].done);
+        assert_eq!(assemble(&empty).unwrap(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_out_of_order_and_duplicate_chunks() {
+        let data = vec![7u8; FILE_CHUNK_BYTES * 2 + 1];
+        let chunks = split_file(&data);
+        let mut assembler = FileAssembler::default();
+        for i in [2, 1, 1] {
+            let c = &chunks[i];
+            assert_eq!(
+                assembler.push(c.seq, &c.chunk, c.done, c.sha256.as_deref()),
+                Ok(None)
+            );
+        }
+        let c = &chunks[0];
+        assert_eq!(
+            assembler.push(c.seq, &c.chunk, c.done, None),
+            Ok(Some(data))
+        );
+    }
+
+    #[test]
+    fn test_rejected_chunks() {
+        let mut chunks = split_file(b"hello\n");
+        chunks[0].sha256 = Some(sha256_hex(b"other"));
+        assert!(matches!(
+            assemble(&chunks),
+            Err(FileTransferError::HashMismatch { .. })
+        ));
+
+        let mut assembler = FileAssembler::new(4);
+        assert_eq!(
+            assembler.push(0, "aGVsbG8K", false, None),
+            Err(FileTransferError::TooLarge { limit: 4 })
+        );
+        assert_eq!(
+            assembler.push(0, "not base64!", false, None),
+            Err(FileTransferError::InvalidChunk { seq: 0 })
+        );
+        assert_eq!(
+            assembler.push(0, "", true, None),
+            Err(FileTransferError::MissingHash)
+        );
+
+        let mut assembler = FileAssembler::default();
+        assembler.push(0, "", true, Some(&sha256_hex(b""))).unwrap();
+        assert_eq!(
+            assembler.push(1, "", false, None),
+            Err(FileTransferError::ChunkOutOfRange { seq: 1, chunks: 1 })
+        );
+    }
+
+    #[test]
+    fn test_chunk_messages() {
+        let session_id = Uuid::new_v4();
+        let chunk = split_file(b"hello\n").remove(0);
+        let json =
+            serde_json::to_value(chunk.clone().into_upload(session_id, "notes.txt")).unwrap();
+        assert_eq!(json["type"], "upload_file");
+        assert_eq!(json["chunk"], "aGVsbG8K");
+        assert_eq!(json["done"], true);
+
+        let json = serde_json::to_value(chunk.into_response(session_id, "notes.txt")).unwrap();
+        assert_eq!(json["type"], "file_chunk");
+        assert_eq!(
+            json["sha256"],
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"
+        );
+    }
+}
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 
 /// Terminal cell with character and styling
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Cell {
     pub char: char,
     pub fg: TerminalColor,
@@ -38,6 +39,7 @@ impl Default for Cell {
 
 /// Terminal color types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum TerminalColor {
     Default,
@@ -48,6 +50,7 @@ pub enum TerminalColor {
 
 /// Named ANSI colors (0-15)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum NamedColor {
     Black = 0,
@@ -70,6 +73,7 @@ pub enum NamedColor {
 
 /// Delta operations for incremental grid sync
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GridDelta {
     pub operations: Vec<GridOperation>,
     pub base_version: u64,
@@ -78,6 +82,7 @@ pub struct GridDelta {
 
 /// Individual grid operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "op", rename_all = "snake_case")]
 pub enum GridOperation {
     SetCells {
@@ -118,6 +123,7 @@ pub enum GridOperation {
 
 /// Full terminal grid snapshot
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GridSnapshot {
     pub cols: usize,
     pub rows: usize,
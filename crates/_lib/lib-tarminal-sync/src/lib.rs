@@ -8,16 +8,30 @@
 //! - Device pairing and discovery
 //! - Incremental and full-state synchronization
 //! - Terminal grid delta/snapshot sync
+//! - Browser debug WebSocket capture with bounded frame samples
+//! - Streamed HTTP proxy responses with in-order reassembly
+//! - Chunked Silk file upload and download with SHA-256 checks
+//! - JSON Schema export and golden conformance messages (feature `schema`)
 //! - Transport-agnostic (works with WebSocket, peer-to-peer, etc.)
 
+pub mod browser_debug;
+pub mod file_transfer;
 pub mod grid;
 pub mod messages;
 pub mod metadata;
+pub mod proxy_stream;
+#[cfg(feature = "schema")]
+pub mod schema;
 pub mod transport;
 pub mod version_vector;
+pub mod websocket_capture;
 
+pub use browser_debug::*;
+pub use file_transfer::*;
 pub use grid::*;
 pub use messages::*;
 pub use metadata::*;
+pub use proxy_stream::*;
 pub use transport::*;
 pub use version_vector::*;
+pub use websocket_capture::*;
@@ -11,6 +11,7 @@ use uuid::Uuid;
 
 /// Messages exchanged between peers during synchronization
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SyncMessage {
     /// Initial handshake with device info
@@ -56,6 +57,7 @@ pub enum SyncMessage {
 
 /// Entity types for delete operations
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum EntityType {
     Workspace,
@@ -65,6 +67,7 @@ pub enum EntityType {
 
 /// Complete application state for full sync
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AppState {
     pub workspaces: Vec<SyncableWorkspace>,
     pub sessions: Vec<SyncableSession>,
@@ -73,6 +76,7 @@ pub struct AppState {
 
 /// Syncable workspace entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SyncableWorkspace {
     pub id: Uuid,
     pub name: String,
@@ -84,6 +88,7 @@ pub struct SyncableWorkspace {
 
 /// Syncable session entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SyncableSession {
     pub id: Uuid,
     pub workspace_id: Uuid,
@@ -96,6 +101,7 @@ pub struct SyncableSession {
 
 /// Session type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum SessionType {
     /// Block-based terminal (command + output blocks)
@@ -106,6 +112,7 @@ pub enum SessionType {
 
 /// Syncable command block entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SyncableCommandBlock {
     pub id: Uuid,
     pub session_id: Uuid,
@@ -268,6 +275,31 @@ pub enum SignalingMessage {
         body: Option<String>,
     },
 
+    /// Start of a streamed proxy response, for large downloads and SSE.
+    /// The body follows as `ProxyResponseChunk`s and ends with
+    /// `ProxyResponseEnd`; see `ProxyResponseAssembler`
+    ProxyResponseStart {
+        request_id: String,
+        status_code: u16,
+        headers: HashMap<String, String>,
+    },
+
+    /// Piece of a streamed proxy response body, `seq` counting from 0
+    ProxyResponseChunk {
+        request_id: String,
+        seq: u64,
+        data: String,
+    },
+
+    /// End of a streamed proxy response after `chunks` chunks. `error` is set
+    /// when the upstream failed midway and the body is incomplete
+    ProxyResponseEnd {
+        request_id: String,
+        chunks: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+
     // ========== Query Aggregation ==========
     /// Aggregate query across all user's devices
     AggregateQuery {
@@ -446,6 +478,12 @@ pub enum SignalingMessage {
         /// Favicon URL (optional)
         #[serde(skip_serializing_if = "Option::is_none")]
         favicon: Option<String>,
+        /// Token expiry (unix ms) from X-ADI-Debug-Token-TTL; `None` never expires
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at: Option<i64>,
+        /// Restrictions from X-ADI-Debug-Token-Scopes; empty grants full access
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        scopes: Vec<BrowserDebugScope>,
     },
 
     /// Browser tab closed or navigated away
@@ -468,6 +506,13 @@ pub enum SignalingMessage {
     /// Console event streamed from browser extension
     BrowserDebugConsoleEvent { token: String, entry: ConsoleEntry },
 
+    /// WebSocket connection event streamed from browser extension
+    BrowserDebugWebSocketEvent {
+        token: String,
+        event: WebSocketEventType,
+        data: WebSocketEventData,
+    },
+
     /// List all debug tabs available to this user
     /// Sent by: MCP plugin
     BrowserDebugListTabs { access_token: String },
@@ -505,6 +550,33 @@ pub enum SignalingMessage {
         entries: Vec<ConsoleEntry>,
     },
 
+    /// Get WebSocket connections and their frame samples from a tab
+    /// Sent by: CLI/MCP plugin, routed to extension
+    BrowserDebugGetWebSockets {
+        request_id: String,
+        token: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        filters: Option<WebSocketFilters>,
+    },
+
+    /// WebSocket data response from extension
+    BrowserDebugWebSocketData {
+        request_id: String,
+        connections: Vec<WebSocketConnection>,
+    },
+
+    /// Revoke a debug token before it expires
+    /// Sent by: CLI/MCP plugin of the tab's owner
+    BrowserDebugRevokeToken { access_token: String, token: String },
+
+    /// Debug token revoked or expired; no further requests are routed for it
+    /// Sent by: Server to the extension and the requester
+    BrowserDebugTokenRevoked {
+        token: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+
     // ========== WebRTC Session Management ==========
     /// Request to start a WebRTC session with a cocoon
     /// Sent by: Browser/Client to initiate WebRTC connection
@@ -595,6 +667,26 @@ pub struct HiveInfo {
     pub connected_at: String,
     /// Available cocoon kinds this hive can spawn
     pub cocoon_kinds: Vec<CocoonKind>,
+    /// Hive refuses new cocoons while in maintenance
+    #[serde(default)]
+    pub maintenance: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance_reason: Option<String>,
+    /// Singleton services this hive can run, and which hive runs each
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub singletons: Vec<SingletonStatus>,
+}
+
+/// Leader election state of a `singleton: true` service
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SingletonStatus {
+    /// `source:service`
+    pub service: String,
+    /// Hive running the service; `None` while no hive with a recent heartbeat lists it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leader_hive_id: Option<String>,
+    /// Grows with every leader change
+    pub fencing_token: u64,
 }
 
 /// Available cocoon image/kind that a Hive can spawn
@@ -782,6 +874,23 @@ pub struct BrowserDebugTab {
     pub cocoon_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub favicon: Option<String>,
+    /// Token expiry (unix ms)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<BrowserDebugScope>,
+}
+
+/// Restriction on what a debug token may be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrowserDebugScope {
+    /// Network events and requests only
+    NetworkOnly,
+    /// Console entries only
+    ConsoleOnly,
+    /// Request and response bodies are stripped
+    NoBodies,
 }
 
 /// Network request filters
@@ -847,10 +956,119 @@ pub struct NetworkRequest {
     pub error: Option<String>,
 }
 
+/// WebSocket event type for streaming
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebSocketEventType {
+    Open,
+    Frame,
+    Close,
+    Error,
+}
+
+/// Frame direction, from the page's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebSocketDirection {
+    Sent,
+    Received,
+}
+
+/// WebSocket frame opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebSocketOpcode {
+    Text,
+    Binary,
+    Ping,
+    Pong,
+    Close,
+}
+
+/// One captured WebSocket frame
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebSocketFrame {
+    pub timestamp: i64,
+    pub direction: WebSocketDirection,
+    pub opcode: WebSocketOpcode,
+    /// Full payload size in bytes
+    pub size: u64,
+    /// Payload sample (base64 for binary frames)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<String>,
+    /// The sample is shorter than the payload
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// WebSocket event data (varies by event type)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketEventData {
+    pub connection_id: String,
+    pub timestamp: i64,
+    // Open fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    // Frame fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame: Option<WebSocketFrame>,
+    // Close fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_code: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_reason: Option<String>,
+    // Error fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Complete WebSocket connection (aggregated from events)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketConnection {
+    pub connection_id: String,
+    pub url: String,
+    pub opened_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_code: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Oldest first
+    #[serde(default)]
+    pub frames: Vec<WebSocketFrame>,
+    /// Older frames evicted once the per-connection limit was reached
+    #[serde(default)]
+    pub frames_dropped: u32,
+}
+
+/// WebSocket connection filters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebSocketFilters {
+    /// Substring of the connection URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<WebSocketDirection>,
+    /// Frames at or after this unix ms timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+    /// Most recent frames per connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
 // ========== Silk Terminal Protocol ==========
 
 /// Silk command request - sent from web to cocoon via SyncData
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SilkRequest {
     /// Create a new Silk session (persistent shell for env preservation)
@@ -900,10 +1118,34 @@ pub enum SilkRequest {
 
     /// Close session
     CloseSession { session_id: Uuid },
+
+    /// Upload one chunk of a file into the session; see `file_transfer`
+    UploadFile {
+        session_id: Uuid,
+        /// Destination, relative to the session cwd unless absolute
+        path: String,
+        /// Base64-encoded bytes
+        chunk: String,
+        /// Chunk number, counting from 0
+        seq: u64,
+        /// Last chunk of the file
+        done: bool,
+        /// Hex SHA-256 of the whole file, sent with the last chunk
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sha256: Option<String>,
+    },
+
+    /// Download a file from the session as `file_chunk` responses
+    DownloadFile {
+        session_id: Uuid,
+        /// Source, relative to the session cwd unless absolute
+        path: String,
+    },
 }
 
 /// Signals that can be sent to running commands
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum SilkSignal {
     Interrupt, // SIGINT (Ctrl+C)
@@ -913,6 +1155,7 @@ pub enum SilkSignal {
 
 /// Silk response - sent from cocoon to web via SyncData
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SilkResponse {
     /// Session created successfully
@@ -975,6 +1218,31 @@ pub enum SilkResponse {
     /// Session closed
     SessionClosed { session_id: Uuid },
 
+    /// One chunk of a downloaded file
+    FileChunk {
+        session_id: Uuid,
+        /// Path as given in `download_file`
+        path: String,
+        /// Base64-encoded bytes
+        chunk: String,
+        /// Chunk number, counting from 0
+        seq: u64,
+        /// Last chunk of the file
+        done: bool,
+        /// Hex SHA-256 of the whole file, sent with the last chunk
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sha256: Option<String>,
+    },
+
+    /// Uploaded file was written after its hash checked out
+    FileUploaded {
+        session_id: Uuid,
+        /// Path as given in `upload_file`
+        path: String,
+        size: u64,
+        sha256: String,
+    },
+
     /// Error occurred
     Error {
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -988,6 +1256,7 @@ pub enum SilkResponse {
 
 /// Output stream identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum SilkStream {
     Stdout,
@@ -996,6 +1265,7 @@ pub enum SilkStream {
 
 /// Pre-parsed HTML span for styled output
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SilkHtmlSpan {
     /// Text content
     pub text: String,
@@ -1599,6 +1869,13 @@ mod tests {
                         id: "linux".to_string(),
                         image: "registry.the-ihor.com/cocoon:latest".to_string(),
                     }],
+                    maintenance: false,
+                    maintenance_reason: None,
+                    singletons: vec![SingletonStatus {
+                        service: "app:scheduler".to_string(),
+                        leader_hive_id: Some("hive-001".to_string()),
+                        fencing_token: 4,
+                    }],
                 },
                 HiveInfo {
                     hive_id: "hive-002".to_string(),
@@ -1615,6 +1892,9 @@ mod tests {
                             image: "registry.the-ihor.com/cocoon:cuda".to_string(),
                         },
                     ],
+                    maintenance: true,
+                    maintenance_reason: Some("driver upgrade".to_string()),
+                    singletons: vec![],
                 },
             ],
         };
@@ -1633,6 +1913,13 @@ mod tests {
                 assert_eq!(hives[0].cocoon_kinds.len(), 1);
                 assert_eq!(hives[1].version, "0.1.1");
                 assert_eq!(hives[1].cocoon_kinds.len(), 2);
+                assert!(!hives[0].maintenance);
+                assert_eq!(
+                    hives[1].maintenance_reason.as_deref(),
+                    Some("driver upgrade")
+                );
+                assert_eq!(hives[0].singletons[0].fencing_token, 4);
+                assert!(hives[1].singletons.is_empty());
             }
             _ => panic!("Wrong message type"),
         }
@@ -1648,11 +1935,14 @@ mod tests {
             url: "https://example.com/app".to_string(),
             title: "My App".to_string(),
             favicon: Some("https://example.com/favicon.ico".to_string()),
+            expires_at: Some(1_700_000_000_000),
+            scopes: vec![BrowserDebugScope::NetworkOnly, BrowserDebugScope::NoBodies],
         };
 
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("browser_debug_tab_available"));
         assert!(json.contains("browser_id"));
+        assert!(json.contains(r#""scopes":["network_only","no_bodies"]"#));
 
         let deserialized: SignalingMessage = serde_json::from_str(&json).unwrap();
         match deserialized {
@@ -1662,12 +1952,16 @@ mod tests {
                 url,
                 title,
                 favicon,
+                expires_at,
+                scopes,
             } => {
                 assert!(token.starts_with("eyJ"));
                 assert_eq!(browser_id, "browser-abc-123");
                 assert_eq!(url, "https://example.com/app");
                 assert_eq!(title, "My App");
                 assert!(favicon.is_some());
+                assert_eq!(expires_at, Some(1_700_000_000_000));
+                assert_eq!(scopes.len(), 2);
             }
             _ => panic!("Wrong message type"),
         }
@@ -1774,6 +2068,8 @@ mod tests {
                     cocoon_id: "cocoon-123".to_string(),
                     cocoon_name: Some("dev-server".to_string()),
                     favicon: None,
+                    expires_at: None,
+                    scopes: vec![],
                 },
                 BrowserDebugTab {
                     token: "token-2".to_string(),
@@ -1783,6 +2079,8 @@ mod tests {
                     cocoon_id: "cocoon-123".to_string(),
                     cocoon_name: Some("dev-server".to_string()),
                     favicon: Some("https://app.example.com/favicon.ico".to_string()),
+                    expires_at: Some(1_700_000_000_000),
+                    scopes: vec![BrowserDebugScope::ConsoleOnly],
                 },
             ],
         };
@@ -1950,6 +2248,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_browser_debug_web_socket_event() {
+        let msg = SignalingMessage::BrowserDebugWebSocketEvent {
+            token: "debug-token-123".to_string(),
+            event: WebSocketEventType::Frame,
+            data: WebSocketEventData {
+                connection_id: "ws-1".to_string(),
+                timestamp: 1234567890,
+                url: None,
+                protocol: None,
+                frame: Some(WebSocketFrame {
+                    timestamp: 1234567890,
+                    direction: WebSocketDirection::Received,
+                    opcode: WebSocketOpcode::Text,
+                    size: 17,
+                    payload: Some("{\"type\":\"ping\"}".to_string()),
+                    truncated: false,
+                }),
+                close_code: None,
+                close_reason: None,
+                error: None,
+            },
+        };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("browser_debug_web_socket_event"));
+        assert!(json.contains("\"direction\":\"received\""));
+        assert!(!json.contains("truncated"));
+
+        let deserialized: SignalingMessage = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            SignalingMessage::BrowserDebugWebSocketEvent { event, data, .. } => {
+                assert_eq!(event, WebSocketEventType::Frame);
+                assert_eq!(data.frame.unwrap().size, 17);
+            }
+            _ => panic!("Wrong message type"),
+        }
+    }
+
+    #[test]
+    fn test_browser_debug_get_web_sockets_with_filters() {
+        let msg = SignalingMessage::BrowserDebugGetWebSockets {
+            request_id: "req-ws".to_string(),
+            token: "debug-token".to_string(),
+            filters: Some(WebSocketFilters {
+                url_pattern: Some("/live".to_string()),
+                direction: Some(WebSocketDirection::Sent),
+                since: None,
+                limit: Some(50),
+            }),
+        };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("browser_debug_get_web_sockets"));
+
+        let deserialized: SignalingMessage = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            SignalingMessage::BrowserDebugGetWebSockets { filters, .. } => {
+                let f = filters.unwrap();
+                assert_eq!(f.direction, Some(WebSocketDirection::Sent));
+                assert_eq!(f.limit, Some(50));
+            }
+            _ => panic!("Wrong message type"),
+        }
+    }
+
     // ========== SSL Certificate Tests ==========
 
     #[test]
@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 
 /// Metadata attached to every syncable entity for conflict resolution
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SyncMetadata {
     /// When the entity was first created
     pub created_at: DateTime<Utc>,
@@ -0,0 +1,299 @@
+//! Streamed proxy responses
+//!
+//! A single `proxy_response` carries the whole body, which does not work for
+//! large downloads or server-sent events. A cocoon streams those instead:
+//! `proxy_response_start` with status and headers, `proxy_response_chunk`s
+//! numbered from 0, and `proxy_response_end` with the chunk count.
+//! [`ProxyResponseStream`] numbers the messages on the sending side;
+//! [`ProxyResponseAssembler`] puts chunks back in order on the receiving side
+//! and also accepts a plain `proxy_response`, so receivers handle both forms
+//! the same way.
+
+use crate::SignalingMessage;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Chunks buffered ahead of a missing one before the stream is given up
+pub const MAX_PENDING_CHUNKS: usize = 1024;
+
+/// Builds the messages of one streamed response
+#[derive(Debug, Clone)]
+pub struct ProxyResponseStream {
+    request_id: String,
+    seq: u64,
+}
+
+impl ProxyResponseStream {
+    pub fn new(request_id: impl Into<String>) -> Self {
+        Self {
+            request_id: request_id.into(),
+            seq: 0,
+        }
+    }
+
+    pub fn start(&self, status_code: u16, headers: HashMap<String, String>) -> SignalingMessage {
+        SignalingMessage::ProxyResponseStart {
+            request_id: self.request_id.clone(),
+            status_code,
+            headers,
+        }
+    }
+
+    pub fn chunk(&mut self, data: impl Into<String>) -> SignalingMessage {
+        let seq = self.seq;
+        self.seq += 1;
+        SignalingMessage::ProxyResponseChunk {
+            request_id: self.request_id.clone(),
+            seq,
+            data: data.into(),
+        }
+    }
+
+    /// Close the stream; `error` marks the body as incomplete
+    pub fn end(self, error: Option<String>) -> SignalingMessage {
+        SignalingMessage::ProxyResponseEnd {
+            request_id: self.request_id,
+            chunks: self.seq,
+            error,
+        }
+    }
+}
+
+/// Why a message could not be added to a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyStreamError {
+    /// Not a proxy response, or one for another request
+    UnexpectedMessage,
+    /// A chunk numbered past the count announced by `proxy_response_end`
+    ChunkOutOfRange { seq: u64, chunks: u64 },
+    /// Too many chunks arrived ahead of a missing one
+    TooManyPending,
+}
+
+impl fmt::Display for ProxyStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedMessage => write!(f, "message does not belong to this response"),
+            Self::ChunkOutOfRange { seq, chunks } => {
+                write!(
+                    f,
+                    "chunk {} is past the end of a {} chunk response",
+                    seq, chunks
+                )
+            }
+            Self::TooManyPending => write!(
+                f,
+                "more than {} chunks arrived ahead of a missing one",
+                MAX_PENDING_CHUNKS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProxyStreamError {}
+
+/// Reassembles one proxy response, streamed or not
+#[derive(Debug, Clone)]
+pub struct ProxyResponseAssembler {
+    request_id: String,
+    head: Option<(u16, HashMap<String, String>)>,
+    next_seq: u64,
+    /// Chunks that arrived ahead of `next_seq`
+    pending: BTreeMap<u64, String>,
+    /// Chunk count and error from `proxy_response_end`
+    end: Option<(u64, Option<String>)>,
+}
+
+impl ProxyResponseAssembler {
+    pub fn new(request_id: impl Into<String>) -> Self {
+        Self {
+            request_id: request_id.into(),
+            head: None,
+            next_seq: 0,
+            pending: BTreeMap::new(),
+            end: None,
+        }
+    }
+
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    /// Add a message and return the body data it makes available, in order.
+    /// Chunks that were already delivered are ignored.
+    pub fn push(&mut self, msg: SignalingMessage) -> Result<Vec<String>, ProxyStreamError> {
+        match msg {
+            SignalingMessage::ProxyResponse {
+                request_id,
+                status_code,
+                headers,
+                body,
+            } if request_id == self.request_id => {
+                self.head = Some((status_code, headers));
+                self.end = Some((self.next_seq, None));
+                Ok(body.into_iter().collect())
+            }
+            SignalingMessage::ProxyResponseStart {
+                request_id,
+                status_code,
+                headers,
+            } if request_id == self.request_id => {
+                self.head = Some((status_code, headers));
+                Ok(Vec::new())
+            }
+            SignalingMessage::ProxyResponseChunk {
+                request_id,
+                seq,
+                data,
+            } if request_id == self.request_id => {
+                if let Some((chunks, _)) = self.end {
+                    if seq >= chunks {
+                        return Err(ProxyStreamError::ChunkOutOfRange { seq, chunks });
+                    }
+                }
+                if seq < self.next_seq {
+                    return Ok(Vec::new());
+                }
+                if seq > self.next_seq && self.pending.len() >= MAX_PENDING_CHUNKS {
+                    return Err(ProxyStreamError::TooManyPending);
+                }
+                self.pending.insert(seq, data);
+                Ok(self.drain_ready())
+            }
+            SignalingMessage::ProxyResponseEnd {
+                request_id,
+                chunks,
+                error,
+            } if request_id == self.request_id => {
+                if let Some((&seq, _)) = self.pending.range(chunks..).next() {
+                    return Err(ProxyStreamError::ChunkOutOfRange { seq, chunks });
+                }
+                self.end = Some((chunks, error));
+                Ok(Vec::new())
+            }
+            _ => Err(ProxyStreamError::UnexpectedMessage),
+        }
+    }
+
+    fn drain_ready(&mut self) -> Vec<String> {
+        let mut ready = Vec::new();
+        while let Some(data) = self.pending.remove(&self.next_seq) {
+            ready.push(data);
+            self.next_seq += 1;
+        }
+        ready
+    }
+
+    /// Status code and headers, once the start of the response arrived
+    pub fn head(&self) -> Option<(u16, &HashMap<String, String>)> {
+        self.head
+            .as_ref()
+            .map(|(status, headers)| (*status, headers))
+    }
+
+    /// Head, every chunk and the end have arrived
+    pub fn is_complete(&self) -> bool {
+        self.head.is_some()
+            && self
+                .end
+                .as_ref()
+                .is_some_and(|(chunks, _)| self.next_seq == *chunks)
+    }
+
+    /// Error the sender ended the stream with
+    pub fn error(&self) -> Option<&str> {
+        self.end.as_ref().and_then(|(_, error)| error.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn streamed(body: &[&str]) -> Vec<SignalingMessage> {
+        let mut stream = ProxyResponseStream::new("req-1");
+        let mut messages = vec![stream.start(200, HashMap::new())];
+        messages.extend(body.iter().map(|data| stream.chunk(*data)));
+        messages.push(stream.end(None));
+        messages
+    }
+
+    #[test]
+    fn test_in_order_stream() {
+        let mut assembler = ProxyResponseAssembler::new("req-1");
+        let mut body = String::new();
+        for msg in streamed(&["data: a\n\n", "data: b\n\n"]) {
+            body.extend(assembler.push(msg).unwrap());
+        }
+        assert_eq!(body, "data: a\n\ndata: b\n\n");
+        assert_eq!(assembler.head().map(|(status, _)| status), Some(200));
+        assert!(assembler.is_complete());
+        assert_eq!(assembler.error(), None);
+    }
+
+    #[test]
+    fn test_out_of_order_and_duplicate_chunks() {
+        let messages = streamed(&["a", "b", "c"]);
+        let mut assembler = ProxyResponseAssembler::new("req-1");
+
+        // End and a late chunk can overtake each other across transports
+        assert!(assembler.push(messages[0].clone()).unwrap().is_empty());
+        assert!(assembler.push(messages[4].clone()).unwrap().is_empty());
+        assert!(assembler.push(messages[3].clone()).unwrap().is_empty());
+        assert!(assembler.push(messages[2].clone()).unwrap().is_empty());
+        assert!(!assembler.is_complete());
+        assert_eq!(
+            assembler.push(messages[1].clone()).unwrap(),
+            vec!["a", "b", "c"]
+        );
+        assert!(assembler.push(messages[2].clone()).unwrap().is_empty());
+        assert!(assembler.is_complete());
+    }
+
+    #[test]
+    fn test_plain_response_and_errors() {
+        let mut assembler = ProxyResponseAssembler::new("req-1");
+        let body = assembler
+            .push(SignalingMessage::ProxyResponse {
+                request_id: "req-1".to_string(),
+                status_code: 404,
+                headers: HashMap::new(),
+                body: Some("not found".to_string()),
+            })
+            .unwrap();
+        assert_eq!(body, vec!["not found"]);
+        assert!(assembler.is_complete());
+
+        let mut assembler = ProxyResponseAssembler::new("req-1");
+        let mut stream = ProxyResponseStream::new("req-1");
+        assembler.push(stream.start(200, HashMap::new())).unwrap();
+        let extra = stream.chunk("late");
+        assembler
+            .push(ProxyResponseStream::new("req-1").end(Some("upstream reset".into())))
+            .unwrap();
+        assert_eq!(
+            assembler.push(extra),
+            Err(ProxyStreamError::ChunkOutOfRange { seq: 0, chunks: 0 })
+        );
+        assert_eq!(assembler.error(), Some("upstream reset"));
+
+        let other = ProxyResponseStream::new("req-2").start(200, HashMap::new());
+        assert_eq!(
+            assembler.push(other),
+            Err(ProxyStreamError::UnexpectedMessage)
+        );
+    }
+
+    #[test]
+    fn test_chunk_serialization() {
+        let msg = ProxyResponseStream::new("req-1").chunk("hello");
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"proxy_response_chunk","request_id":"req-1","seq":0,"data":"hello"}"#
+        );
+
+        let end = serde_json::to_string(&ProxyResponseStream::new("req-1").end(None)).unwrap();
+        assert!(!end.contains("error"));
+    }
+}
@@ -0,0 +1,68 @@
+//! JSON Schema export of the sync and Silk protocols (feature `schema`)
+//!
+//! The Rust types are the source of truth for Tarminal clients. Schemas
+//! follow the serde form: messages are objects tagged by `type`, and optional
+//! fields may be left out. Golden messages every implementation must accept
+//! live in `conformance/<name>.json`, one file per message schema;
+//! `tests/conformance.rs` checks that they validate and round-trip through
+//! the Rust types unchanged. The signaling schema is exported by
+//! `lib-signaling-protocol`, which is generated from `signaling.tsp`.
+
+use crate::{SilkRequest, SilkResponse, SilkSignal, SilkStream, SyncMessage};
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use std::path::{Path, PathBuf};
+
+/// Message schemas with a conformance corpus, by name
+pub const MESSAGE_SCHEMAS: &[&str] = &["sync_message", "silk_request", "silk_response"];
+
+/// Every exported schema by name: the message enums first, then the enums
+/// shared between messages.
+pub fn protocol_schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("sync_message", schema_for!(SyncMessage)),
+        ("silk_request", schema_for!(SilkRequest)),
+        ("silk_response", schema_for!(SilkResponse)),
+        ("silk_stream", schema_for!(SilkStream)),
+        ("silk_signal", schema_for!(SilkSignal)),
+    ]
+}
+
+/// Schema of one message or enum, by its name in [`protocol_schemas`]
+pub fn schema(name: &str) -> Option<RootSchema> {
+    protocol_schemas()
+        .into_iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, schema)| schema)
+}
+
+/// Write every schema to `<dir>/<name>.schema.json` and return the paths.
+pub fn write_schemas(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for (name, schema) in protocol_schemas() {
+        let path = dir.join(format!("{}.schema.json", name));
+        let json = serde_json::to_string_pretty(&schema).map_err(std::io::Error::other)?;
+        std::fs::write(&path, json + "\n")?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_schemas_list_variant_tags() {
+        let silk = serde_json::to_string(&schema("silk_request").unwrap()).unwrap();
+        for tag in ["create_session", "execute", "upload_file"] {
+            assert!(silk.contains(&format!("\"{}\"", tag)), "missing {}", tag);
+        }
+
+        let stream = serde_json::to_value(schema("silk_stream").unwrap()).unwrap();
+        assert_eq!(stream["enum"], serde_json::json!(["stdout", "stderr"]));
+        assert!(schema("signaling_message").is_none());
+        assert!(MESSAGE_SCHEMAS.iter().all(|name| schema(name).is_some()));
+    }
+}
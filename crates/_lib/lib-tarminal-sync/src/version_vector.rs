@@ -12,6 +12,7 @@ pub type DeviceId = Uuid;
 
 /// Logical clock for tracking causality across distributed devices
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct VersionVector {
     /// Map of device_id -> clock value
     pub clocks: HashMap<DeviceId, u64>,
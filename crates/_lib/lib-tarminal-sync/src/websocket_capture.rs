@@ -0,0 +1,490 @@
+//! WebSocket frame capture for browser debug tabs
+//!
+//! The extension feeds every `browser_debug_web_socket_event` it streams into
+//! a [`WebSocketCapture`], which keeps one [`WebSocketConnection`] per socket
+//! with a bounded number of size-limited frame samples, and answers
+//! `browser_debug_get_web_sockets` from it. [`render_websocket_timeline`]
+//! renders the answer for `adi browser-debug ws <token>`.
+
+use crate::{
+    WebSocketConnection, WebSocketDirection, WebSocketEventData, WebSocketEventType,
+    WebSocketFilters, WebSocketFrame, WebSocketOpcode,
+};
+use std::fmt::Write;
+
+/// Bytes of payload kept per frame
+pub const DEFAULT_MAX_PAYLOAD_BYTES: usize = 2048;
+/// Frames kept per connection; older ones are evicted
+pub const DEFAULT_MAX_FRAMES: usize = 500;
+/// Connections kept per tab; closed ones are evicted first
+pub const DEFAULT_MAX_CONNECTIONS: usize = 50;
+
+/// Characters of payload shown per frame in the timeline
+const PREVIEW_CHARS: usize = 96;
+
+/// Per-tab WebSocket buffer
+#[derive(Debug, Clone)]
+pub struct WebSocketCapture {
+    max_payload_bytes: usize,
+    max_frames: usize,
+    max_connections: usize,
+    /// Oldest first
+    connections: Vec<WebSocketConnection>,
+}
+
+impl Default for WebSocketCapture {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_MAX_PAYLOAD_BYTES,
+            DEFAULT_MAX_FRAMES,
+            DEFAULT_MAX_CONNECTIONS,
+        )
+    }
+}
+
+impl WebSocketCapture {
+    pub fn new(max_payload_bytes: usize, max_frames: usize, max_connections: usize) -> Self {
+        Self {
+            max_payload_bytes,
+            max_frames: max_frames.max(1),
+            max_connections: max_connections.max(1),
+            connections: Vec::new(),
+        }
+    }
+
+    pub fn connections(&self) -> &[WebSocketConnection] {
+        &self.connections
+    }
+
+    /// Frame for `payload`, cut to the payload limit. Binary payloads are
+    /// base64 (as the debugger reports them) and `size` is the decoded length.
+    pub fn sample_frame(
+        &self,
+        timestamp: i64,
+        direction: WebSocketDirection,
+        opcode: WebSocketOpcode,
+        payload: &str,
+    ) -> WebSocketFrame {
+        sample_frame(
+            self.max_payload_bytes,
+            timestamp,
+            direction,
+            opcode,
+            payload,
+        )
+    }
+
+    /// Record an event; frames and closes for unknown connections are ignored
+    pub fn apply(&mut self, event: WebSocketEventType, data: WebSocketEventData) {
+        let (max_payload_bytes, max_frames) = (self.max_payload_bytes, self.max_frames);
+        match event {
+            WebSocketEventType::Open => self.open(data),
+            WebSocketEventType::Frame => {
+                let (Some(connection), Some(mut frame)) =
+                    (self.connection_mut(&data.connection_id), data.frame)
+                else {
+                    return;
+                };
+                // Extensions may stream frames they did not sample
+                if let Some(payload) = &frame.payload {
+                    if payload.len() > max_payload_bytes {
+                        let sample = sample_frame(
+                            max_payload_bytes,
+                            frame.timestamp,
+                            frame.direction,
+                            frame.opcode,
+                            payload,
+                        );
+                        frame.payload = sample.payload;
+                        frame.truncated |= sample.truncated;
+                    }
+                }
+                connection.frames.push(frame);
+                if connection.frames.len() > max_frames {
+                    connection.frames.remove(0);
+                    connection.frames_dropped += 1;
+                }
+            }
+            WebSocketEventType::Close => {
+                if let Some(connection) = self.connection_mut(&data.connection_id) {
+                    connection.closed_at = Some(data.timestamp);
+                    connection.close_code = data.close_code;
+                    connection.close_reason = data.close_reason;
+                }
+            }
+            WebSocketEventType::Error => {
+                if let Some(connection) = self.connection_mut(&data.connection_id) {
+                    connection.error = data.error;
+                }
+            }
+        }
+    }
+
+    /// Connections matching `filters`, with their frames filtered too
+    pub fn query(&self, filters: &WebSocketFilters) -> Vec<WebSocketConnection> {
+        self.connections
+            .iter()
+            .filter(|c| {
+                filters
+                    .url_pattern
+                    .as_ref()
+                    .is_none_or(|pattern| c.url.contains(pattern.as_str()))
+            })
+            .map(|c| {
+                let mut frames: Vec<WebSocketFrame> = c
+                    .frames
+                    .iter()
+                    .filter(|f| filters.direction.is_none_or(|d| f.direction == d))
+                    .filter(|f| filters.since.is_none_or(|since| f.timestamp >= since))
+                    .cloned()
+                    .collect();
+                if let Some(limit) = filters.limit {
+                    let skip = frames.len().saturating_sub(limit as usize);
+                    frames.drain(..skip);
+                }
+                WebSocketConnection {
+                    frames,
+                    ..c.clone()
+                }
+            })
+            .collect()
+    }
+
+    fn connection_mut(&mut self, connection_id: &str) -> Option<&mut WebSocketConnection> {
+        self.connections
+            .iter_mut()
+            .find(|c| c.connection_id == connection_id)
+    }
+
+    fn open(&mut self, data: WebSocketEventData) {
+        self.connections
+            .retain(|c| c.connection_id != data.connection_id);
+        if self.connections.len() >= self.max_connections {
+            let evict = self
+                .connections
+                .iter()
+                .position(|c| c.closed_at.is_some())
+                .unwrap_or(0);
+            self.connections.remove(evict);
+        }
+        self.connections.push(WebSocketConnection {
+            connection_id: data.connection_id,
+            url: data.url.unwrap_or_default(),
+            opened_at: data.timestamp,
+            protocol: data.protocol,
+            closed_at: None,
+            close_code: None,
+            close_reason: None,
+            error: None,
+            frames: Vec::new(),
+            frames_dropped: 0,
+        });
+    }
+}
+
+fn sample_frame(
+    max_payload_bytes: usize,
+    timestamp: i64,
+    direction: WebSocketDirection,
+    opcode: WebSocketOpcode,
+    payload: &str,
+) -> WebSocketFrame {
+    let (size, cut) = if opcode == WebSocketOpcode::Binary {
+        let padding = payload.bytes().rev().take_while(|b| *b == b'=').count();
+        let size = (payload.len() / 4 * 3).saturating_sub(padding);
+        // Keep whole base64 quanta so the sample still decodes
+        let limit = max_payload_bytes.div_ceil(3) * 4;
+        (size, limit.min(payload.len()))
+    } else {
+        let mut cut = max_payload_bytes.min(payload.len());
+        while !payload.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        (payload.len(), cut)
+    };
+
+    WebSocketFrame {
+        timestamp,
+        direction,
+        opcode,
+        size: size as u64,
+        payload: Some(payload[..cut].to_string()),
+        truncated: cut < payload.len(),
+    }
+}
+
+/// Frame timeline of each connection, times relative to when it opened
+pub fn render_websocket_timeline(connections: &[WebSocketConnection]) -> String {
+    if connections.is_empty() {
+        return "No WebSocket connections captured\n".to_string();
+    }
+
+    let mut out = String::new();
+    for (i, connection) in connections.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let _ = write!(out, "{} {}", connection.connection_id, connection.url);
+        if let Some(protocol) = &connection.protocol {
+            let _ = write!(out, " ({})", protocol);
+        }
+        out.push('\n');
+        if connection.frames_dropped > 0 {
+            let _ = writeln!(
+                out,
+                "  ... {} earlier frames dropped",
+                connection.frames_dropped
+            );
+        }
+
+        for frame in &connection.frames {
+            let arrow = match frame.direction {
+                WebSocketDirection::Sent => "->",
+                WebSocketDirection::Received => "<-",
+            };
+            let _ = write!(
+                out,
+                "  {:>10} {} {:<6} {:>9}",
+                elapsed(connection.opened_at, frame.timestamp),
+                arrow,
+                opcode_name(frame.opcode),
+                format_size(frame.size)
+            );
+            if let Some(payload) = &frame.payload {
+                let _ = write!(out, "  {}", preview(payload, frame.truncated));
+            }
+            out.push('\n');
+        }
+
+        if let Some(error) = &connection.error {
+            let _ = writeln!(out, "  error: {}", error);
+        }
+        if let Some(closed_at) = connection.closed_at {
+            let _ = write!(
+                out,
+                "  {:>10} closed",
+                elapsed(connection.opened_at, closed_at)
+            );
+            if let Some(code) = connection.close_code {
+                let _ = write!(out, " {}", code);
+            }
+            if let Some(reason) = connection.close_reason.as_deref().filter(|r| !r.is_empty()) {
+                let _ = write!(out, " ({})", reason);
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn elapsed(from: i64, to: i64) -> String {
+    format!("+{:.3}s", (to - from).max(0) as f64 / 1000.0)
+}
+
+fn opcode_name(opcode: WebSocketOpcode) -> &'static str {
+    match opcode {
+        WebSocketOpcode::Text => "text",
+        WebSocketOpcode::Binary => "binary",
+        WebSocketOpcode::Ping => "ping",
+        WebSocketOpcode::Pong => "pong",
+        WebSocketOpcode::Close => "close",
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+fn preview(payload: &str, truncated: bool) -> String {
+    let mut line: String = payload
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(PREVIEW_CHARS)
+        .collect();
+    if truncated || payload.chars().count() > PREVIEW_CHARS {
+        line.push_str("...");
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(connection_id: &str, timestamp: i64) -> WebSocketEventData {
+        WebSocketEventData {
+            connection_id: connection_id.to_string(),
+            timestamp,
+            url: None,
+            protocol: None,
+            frame: None,
+            close_code: None,
+            close_reason: None,
+            error: None,
+        }
+    }
+
+    fn open(capture: &mut WebSocketCapture, connection_id: &str, url: &str) {
+        capture.apply(
+            WebSocketEventType::Open,
+            WebSocketEventData {
+                url: Some(url.to_string()),
+                ..event(connection_id, 1_000)
+            },
+        );
+    }
+
+    fn frame(
+        capture: &mut WebSocketCapture,
+        connection_id: &str,
+        timestamp: i64,
+        direction: WebSocketDirection,
+        payload: &str,
+    ) {
+        let frame = capture.sample_frame(timestamp, direction, WebSocketOpcode::Text, payload);
+        capture.apply(
+            WebSocketEventType::Frame,
+            WebSocketEventData {
+                frame: Some(frame),
+                ..event(connection_id, timestamp)
+            },
+        );
+    }
+
+    #[test]
+    fn test_sample_frame_limits_payload() {
+        let capture = WebSocketCapture::new(4, 10, 10);
+
+        let text =
+            capture.sample_frame(0, WebSocketDirection::Sent, WebSocketOpcode::Text, "héllo");
+        assert_eq!(text.size, 6);
+        assert_eq!(text.payload.as_deref(), Some("hél"));
+        assert!(text.truncated);
+
+        // 9 decoded bytes; 4 bytes round up to two base64 quanta
+        let binary = capture.sample_frame(
+            0,
+            WebSocketDirection::Received,
+            WebSocketOpcode::Binary,
+            "AAECAwQFBgcI",
+        );
+        assert_eq!(binary.size, 9);
+        assert_eq!(binary.payload.as_deref(), Some("AAECAwQF"));
+        assert!(binary.truncated);
+
+        let short = capture.sample_frame(0, WebSocketDirection::Sent, WebSocketOpcode::Text, "hi");
+        assert!(!short.truncated);
+    }
+
+    #[test]
+    fn test_capture_bounds_frames_and_connections() {
+        let mut capture = WebSocketCapture::new(64, 2, 2);
+        open(&mut capture, "a", "wss://example.com/live");
+        for ts in 1_001..=1_003 {
+            frame(&mut capture, "a", ts, WebSocketDirection::Received, "tick");
+        }
+        let a = &capture.connections()[0];
+        assert_eq!(a.frames.len(), 2);
+        assert_eq!(a.frames[0].timestamp, 1_002);
+        assert_eq!(a.frames_dropped, 1);
+
+        capture.apply(
+            WebSocketEventType::Close,
+            WebSocketEventData {
+                close_code: Some(1000),
+                ..event("a", 2_000)
+            },
+        );
+        open(&mut capture, "b", "wss://example.com/b");
+        open(&mut capture, "c", "wss://example.com/c");
+        let ids: Vec<&str> = capture
+            .connections()
+            .iter()
+            .map(|c| c.connection_id.as_str())
+            .collect();
+        assert_eq!(ids, ["b", "c"]);
+
+        // Frames for unknown connections are dropped
+        frame(&mut capture, "a", 2_001, WebSocketDirection::Sent, "late");
+        assert!(capture.connections().iter().all(|c| c.frames.is_empty()));
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let mut capture = WebSocketCapture::default();
+        open(&mut capture, "a", "wss://example.com/live");
+        open(&mut capture, "b", "wss://other.dev/socket");
+        frame(&mut capture, "a", 1_001, WebSocketDirection::Sent, "sub");
+        frame(
+            &mut capture,
+            "a",
+            1_002,
+            WebSocketDirection::Received,
+            "one",
+        );
+        frame(
+            &mut capture,
+            "a",
+            1_003,
+            WebSocketDirection::Received,
+            "two",
+        );
+
+        let result = capture.query(&WebSocketFilters {
+            url_pattern: Some("/live".to_string()),
+            direction: Some(WebSocketDirection::Received),
+            since: None,
+            limit: Some(1),
+        });
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].frames.len(), 1);
+        assert_eq!(result[0].frames[0].payload.as_deref(), Some("two"));
+
+        assert_eq!(capture.query(&WebSocketFilters::default()).len(), 2);
+    }
+
+    #[test]
+    fn test_render_websocket_timeline() {
+        let mut capture = WebSocketCapture::default();
+        open(&mut capture, "ws-1", "wss://example.com/live");
+        frame(
+            &mut capture,
+            "ws-1",
+            1_250,
+            WebSocketDirection::Sent,
+            "{\"op\":\"sub\"}",
+        );
+        frame(
+            &mut capture,
+            "ws-1",
+            1_500,
+            WebSocketDirection::Received,
+            "line\nbreak",
+        );
+        capture.apply(
+            WebSocketEventType::Close,
+            WebSocketEventData {
+                close_code: Some(1000),
+                close_reason: Some("bye".to_string()),
+                ..event("ws-1", 3_000)
+            },
+        );
+
+        let out = render_websocket_timeline(capture.connections());
+        assert!(out.starts_with("ws-1 wss://example.com/live\n"));
+        assert!(out.contains("    +0.250s -> text        12 B  {\"op\":\"sub\"}\n"));
+        assert!(out.contains("<- text        10 B  line break\n"));
+        assert!(out.contains("    +2.000s closed 1000 (bye)\n"));
+
+        assert_eq!(
+            render_websocket_timeline(&[]),
+            "No WebSocket connections captured\n"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! Golden messages in `conformance/` must round-trip through the Rust types
+//! unchanged and, with the `schema` feature, validate against the exported
+//! schemas. Other implementations run the same corpus against their own
+//! encoders and the schemas.
+
+use lib_tarminal_sync::{SilkRequest, SilkResponse, SyncMessage};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Deserialize)]
+struct Golden {
+    name: String,
+    message: Value,
+}
+
+fn corpus(name: &str) -> Vec<Golden> {
+    let path = format!("{}/conformance/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    serde_json::from_str(&text).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+fn assert_round_trip<T: Serialize + DeserializeOwned>(name: &str) {
+    for golden in corpus(name) {
+        let parsed: T = serde_json::from_value(golden.message.clone())
+            .unwrap_or_else(|e| panic!("{}/{}: {}", name, golden.name, e));
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            golden.message,
+            "{}/{} changed on round trip",
+            name,
+            golden.name
+        );
+    }
+}
+
+#[test]
+fn test_golden_messages_round_trip() {
+    assert_round_trip::<SyncMessage>("sync_message");
+    assert_round_trip::<SilkRequest>("silk_request");
+    assert_round_trip::<SilkResponse>("silk_response");
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn test_golden_messages_match_schemas() {
+    for name in lib_tarminal_sync::schema::MESSAGE_SCHEMAS {
+        let schema =
+            serde_json::to_value(lib_tarminal_sync::schema::schema(name).unwrap()).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        for golden in corpus(name) {
+            let errors: Vec<String> = validator
+                .iter_errors(&golden.message)
+                .map(|e| format!("{}: {}", e.instance_path, e))
+                .collect();
+            assert!(errors.is_empty(), "{}/{}: {:?}", name, golden.name, errors);
+        }
+
+        // A message of the wrong shape must not pass
+        assert!(!validator.is_valid(&serde_json::json!({ "type": "no_such_message" })));
+    }
+}
@@ -0,0 +1,72 @@
+//! Linear-scaling checks for the protocol throughput budget tests.
+//!
+//! Wall-clock ratios depend on the machine and whatever else runs on it, so a
+//! check passes if any of [`ATTEMPTS`] measurements fits its budget: noise
+//! fails single measurements, a quadratic path fails all of them. That keeps
+//! the checks stable enough for the regular test suite.
+//!
+//! ```
+//! use lib_timing_budget::{assert_scales_linearly, best_of};
+//!
+//! assert_scales_linearly("vec clone", 1024, 16 * 1024, |len| {
+//!     let data = vec![0u8; len];
+//!     best_of(5, || drop(data.clone()))
+//! });
+//! ```
+
+use std::time::{Duration, Instant};
+
+/// Slowdown tolerated on top of the payload growth; linear code stays near 1
+pub const SCALING_SLACK: f64 = 4.0;
+
+/// Measurements taken before a scaling check fails
+pub const ATTEMPTS: usize = 3;
+
+/// Fastest of several runs, to keep scheduler noise out of ratios
+pub fn best_of(runs: usize, mut f: impl FnMut()) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .expect("best_of needs at least one run")
+}
+
+/// Assert that `time(large)` grows no faster than the payload, within
+/// [`SCALING_SLACK`], compared to `time(small)` in at least one of
+/// [`ATTEMPTS`] measurements.
+pub fn assert_scales_linearly(
+    what: &str,
+    small: usize,
+    large: usize,
+    mut time: impl FnMut(usize) -> Duration,
+) {
+    let budget = large as f64 / small as f64 * SCALING_SLACK;
+    let mut measure = || {
+        let small_time = time(small);
+        let large_time = time(large);
+        let ratio = large_time.as_secs_f64() / small_time.as_secs_f64().max(1e-6);
+        (small_time, large_time, ratio)
+    };
+
+    let (mut small_time, mut large_time, mut ratio) = measure();
+    for _ in 1..ATTEMPTS {
+        if ratio <= budget {
+            return;
+        }
+        (small_time, large_time, ratio) = measure();
+    }
+    assert!(
+        ratio <= budget,
+        "{} took {:?} for {} bytes vs {:?} for {} bytes ({:.1}x, budget {:.1}x)",
+        what,
+        large_time,
+        large,
+        small_time,
+        small,
+        ratio,
+        budget
+    );
+}
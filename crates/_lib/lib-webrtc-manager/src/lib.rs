@@ -1,4 +1,4 @@
-use lib_signaling_protocol::SignalingMessage;
+use lib_signaling_protocol::{SessionId, SignalingMessage};
 use lib_env_parse::{env_vars, env_opt};
 
 env_vars! {
@@ -70,14 +70,14 @@ fn build_ice_servers() -> Vec<RTCIceServer> {
 }
 
 pub struct WebRtcSession {
-    pub session_id: String,
+    pub session_id: SessionId,
     pub peer_connection: Arc<RTCPeerConnection>,
     pub data_channels: HashMap<String, Arc<RTCDataChannel>>,
     pub state: String,
 }
 
 pub struct WebRtcManager {
-    sessions: Arc<Mutex<HashMap<String, WebRtcSession>>>,
+    sessions: Arc<Mutex<HashMap<SessionId, WebRtcSession>>>,
     signaling_tx: mpsc::UnboundedSender<SignalingMessage>,
     close_timeout: std::time::Duration,
 }
@@ -103,7 +103,8 @@ impl WebRtcManager {
         }
     }
 
-    pub async fn create_session(&self, session_id: String) -> Result<(), String> {
+    pub async fn create_session(&self, session_id: impl Into<String>) -> Result<(), String> {
+        let session_id = SessionId::new(session_id).map_err(|e| e.to_string())?;
         let ice_servers = build_ice_servers();
         let config = RTCConfiguration {
             ice_servers,
@@ -160,7 +161,7 @@ impl WebRtcManager {
                         );
 
                         let _ = tx.send(SignalingMessage::WebRtcIceCandidate {
-                            session_id,
+                            session_id: session_id.to_string(),
                             candidate: json.candidate,
                             sdp_mid: json.sdp_mid,
                             sdp_mline_index: json.sdp_mline_index.map(|i| i as u32),
@@ -210,7 +211,7 @@ impl WebRtcManager {
                 match state {
                     RTCPeerConnectionState::Connected => {
                         tracing::info!("WebRTC session {} connected", session_id);
-                        if let Some(session) = sessions.lock().await.get_mut(&session_id) {
+                        if let Some(session) = sessions.lock().await.get_mut(session_id.as_str()) {
                             session.state = "connected".to_string();
                         }
                     }
@@ -231,11 +232,11 @@ impl WebRtcManager {
                         };
 
                         let _ = tx.send(SignalingMessage::WebRtcSessionEnded {
-                            session_id: session_id.clone(),
+                            session_id: session_id.to_string(),
                             reason: Some(reason.to_string()),
                         });
 
-                        sessions.lock().await.remove(&session_id);
+                        sessions.lock().await.remove(session_id.as_str());
                     }
                     _ => {}
                 }
@@ -258,7 +259,7 @@ impl WebRtcManager {
                     dc_label
                 );
 
-                if let Some(session) = sessions.lock().await.get_mut(&session_id) {
+                if let Some(session) = sessions.lock().await.get_mut(session_id.as_str()) {
                     session.data_channels.insert(dc_label.clone(), dc.clone());
                 }
 
@@ -278,7 +279,7 @@ impl WebRtcManager {
                         };
 
                         let _ = tx.send(SignalingMessage::WebRtcData {
-                            session_id,
+                            session_id: session_id.to_string(),
                             channel,
                             data,
                             binary,
@@ -435,7 +436,7 @@ impl WebRtcManager {
         Ok(())
     }
 
-    pub async fn list_sessions(&self) -> Vec<String> {
+    pub async fn list_sessions(&self) -> Vec<SessionId> {
         self.sessions
             .lock()
             .await
@@ -505,7 +506,7 @@ mod tests {
         let sessions = manager.list_sessions().await;
         for i in 1..=5 {
             assert!(
-                sessions.contains(&format!("session-{}", i)),
+                sessions.contains(&SessionId::from(format!("session-{}", i))),
                 "Session {} not found in list",
                 i
             );
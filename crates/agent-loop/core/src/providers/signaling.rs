@@ -152,7 +152,7 @@ impl SignalingLlmProvider {
                         }
                     }
                     // Also try parsing the whole message as sync_data
-                    if let Ok(SignalingMessage::SyncData { payload }) =
+                    if let Ok(SignalingMessage::SyncData { payload, .. }) =
                         serde_json::from_str::<SignalingMessage>(&text)
                     {
                         if let Ok(resp) =
@@ -271,6 +271,7 @@ impl SignalingLlmProvider {
         let sync_msg = SignalingMessage::SyncData {
             payload: serde_json::to_value(&request)
                 .map_err(|e| AgentError::SerializationError(e))?,
+            priority: None,
         };
 
         self.handle
@@ -68,6 +68,17 @@ plugin-uninstall-progress = Uninstalling { $id }...
 plugin-uninstall-success = { $id } uninstalled successfully!
 plugin-uninstall-error-not-installed = Plugin { $id } is not installed
 
+# Command index
+plugin-refresh-success = Command index rebuilt ({ $count } commands)
+
+# Migration scaffold
+plugin-migrate-issues = { $id } compatibility issues:
+plugin-migrate-no-issues = { $id } has no compatibility issues
+plugin-migrate-no-commands = Could not load { $id }; commands are not included in the scaffold
+plugin-migrate-exists = { $path } already exists
+plugin-migrate-success = v3 scaffold written to { $path }
+plugin-migrate-next = Move it into the workspace, port the TODO command bodies and build with `adi wf build-plugin`
+
 # ============================================================================
 # SEARCH DOMAIN
 # ============================================================================
@@ -78,6 +89,13 @@ search-packages-title = Packages:
 search-plugins-title = Plugins:
 search-results-summary = Found { $packages } package(s) and { $plugins } plugin(s)
 
+# Cross-plugin search (adi search)
+search-all-no-providers = No installed plugin provides search.
+search-all-provider-failed = { $plugin } search failed: { $error }
+search-all-provider-timeout = { $plugin } search timed out
+search-all-results-title = Results:
+search-all-results-summary = { $count } result(s) from { $providers } plugin(s)
+
 # ============================================================================
 # SERVICES DOMAIN
 # ============================================================================
@@ -141,6 +159,8 @@ info-cmd-start = Start local ADI server
 info-cmd-plugin = Manage plugins
 info-cmd-run = Run a plugin CLI
 info-cmd-logs = Stream plugin logs
+info-cmd-search = Search across plugins
+info-cmd-context = Show or pin the active project
 info-cmd-self-update = Update adi CLI
 
 # ============================================================================
@@ -197,6 +217,7 @@ interactive-plugin-update = Update
 interactive-plugin-update-all = Update all
 interactive-plugin-uninstall = Uninstall
 interactive-plugin-path = Show path
+interactive-plugin-refresh = Refresh command index
 interactive-plugin-install-id = Plugin ID to install (e.g., adi.tasks)
 interactive-plugin-update-id = Plugin ID to update
 interactive-plugin-uninstall-id = Plugin ID to uninstall
@@ -220,6 +241,8 @@ common-tags-label = Tags:
 common-error-prefix = Error:
 common-warning-prefix = Warning:
 common-info-prefix = Info:
+common-hint-prefix = Hint:
+common-docs-prefix = Docs:
 common-success-prefix = Success:
 common-downloading-prefix = →
 common-checkmark = ✓
@@ -9,6 +9,17 @@ pub(crate) struct Cli {
     #[arg(long, global = true)]
     pub lang: Option<String>,
 
+    /// Print a timing breakdown after the command (text or json). Can also be set via ADI_PROFILE env var.
+    #[arg(
+        long,
+        global = true,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text",
+        value_name = "FORMAT"
+    )]
+    pub profile: Option<cli::profile::ProfileFormat>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -67,6 +78,30 @@ pub(crate) enum Commands {
         service: Option<String>,
     },
 
+    /// Search across installed plugins (tasks, tools, ...)
+    Search {
+        /// Search query
+        query: String,
+
+        /// Only include these result types (comma-separated, e.g. tasks,tools)
+        #[arg(long = "type", value_delimiter = ',')]
+        types: Vec<String>,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show or pin the project plugins operate on
+    Context {
+        #[command(subcommand)]
+        command: Option<ContextCommands>,
+    },
+
     /// Select and persist the active ADI theme
     Theme,
 
@@ -86,6 +121,18 @@ pub(crate) enum Commands {
         command: DaemonCommands,
     },
 
+    /// Manage daemon event notifications
+    Notify {
+        #[command(subcommand)]
+        command: NotifyCommands,
+    },
+
+    /// Manage recurring jobs scheduled with the daemon
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommands,
+    },
+
     /// Plugin-provided commands (dynamically discovered from installed plugins)
     #[command(external_subcommand)]
     External(Vec<String>),
@@ -112,6 +159,13 @@ pub(crate) enum DaemonCommands {
     /// Restart the daemon
     Restart,
 
+    /// Replace the running daemon with a new binary without stopping services
+    Update {
+        /// Daemon binary to switch to (defaults to this `adi` executable)
+        #[arg(long)]
+        binary: Option<std::path::PathBuf>,
+    },
+
     /// Show daemon and services status
     #[command(visible_alias = "ps")]
     Status,
@@ -159,6 +213,13 @@ pub(crate) enum DaemonCommands {
         follow: bool,
     },
 
+    /// Stream events from the daemon event bus
+    Events {
+        /// Topic patterns to follow (e.g., service.*, cert.expiry)
+        #[arg(default_value = "*")]
+        topics: Vec<String>,
+    },
+
     /// Run a specific plugin's daemon service (internal, used by daemon supervisor)
     RunService {
         /// Plugin ID to run (e.g., "adi.hive")
@@ -169,16 +230,97 @@ pub(crate) enum DaemonCommands {
     Setup,
 }
 
+#[derive(Subcommand)]
+pub(crate) enum NotifyCommands {
+    /// List configured notification sinks and routes
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Send a test notification to a sink
+    Test {
+        /// Sink name from [notify.sinks] in config.toml
+        sink: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ScheduleCommands {
+    /// List scheduled jobs with their next and last runs
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Run a scheduled job immediately and wait for it to finish
+    RunNow {
+        /// Job ID (e.g., "adi.indexer.reindex")
+        id: String,
+    },
+
+    /// Remove a scheduled job
+    #[command(visible_alias = "rm")]
+    Remove {
+        /// Job ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ContextCommands {
+    /// Show the active project (default)
+    Show,
+
+    /// Pin the project containing a path, regardless of the working directory
+    Use {
+        /// Path inside the project
+        path: std::path::PathBuf,
+    },
+
+    /// Unpin the project and detect it from the working directory again
+    Clear,
+}
+
 #[derive(Subcommand)]
 pub(crate) enum ConfigCommands {
     /// Show current configuration
     Show,
 
+    /// Set a configuration value (e.g. `adi config set output.theme ascii`)
+    Set {
+        /// Key: output.theme (unicode, ascii, plain), output.colors (default,
+        /// high-contrast, mono), theme, language or power_user
+        key: String,
+
+        /// New value
+        value: String,
+    },
+
     /// Enable or disable power user mode
     PowerUser {
         /// Set to "true" to enable or "false" to disable
         enable: String,
     },
+
+    /// Show a plugin config key in every layer and which one wins
+    Explain {
+        /// Plugin ID (e.g., adi.tasks)
+        plugin: String,
+
+        /// Top-level config key
+        key: String,
+
+        /// Print the layers as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check plugin configs against their declared schemas
+    Validate {
+        /// Only validate this plugin (default: all installed plugins)
+        plugin: Option<String>,
+
+        /// Print issues as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -225,4 +367,17 @@ pub(crate) enum PluginCommands {
         /// Plugin ID
         plugin_id: String,
     },
+
+    /// Rebuild the command index used to load only the invoked plugin
+    Refresh,
+
+    /// Generate a v3 SDK crate skeleton from an installed plugin
+    MigrateScaffold {
+        /// Plugin ID
+        plugin_id: String,
+
+        /// Output directory (default: ./<plugin-id>)
+        #[arg(short, long)]
+        out: Option<std::path::PathBuf>,
+    },
 }
@@ -5,12 +5,15 @@ use lib_env_parse::{env_bool_default_true, env_opt, env_or, env_vars};
 env_vars! {
     AdiConfigDir       => "ADI_CONFIG_DIR",
     AdiTheme           => "ADI_THEME",
+    AdiOutputTheme     => "ADI_OUTPUT_THEME",
+    AdiColorScheme     => "ADI_COLOR_SCHEME",
     AdiLang            => "ADI_LANG",
     AdiPowerUser       => "ADI_POWER_USER",
     Lang               => "LANG",
     AdiAutoInstall     => "ADI_AUTO_INSTALL",
     AdiRegistryUrl     => "ADI_REGISTRY_URL",
     SignalingServerUrl  => "SIGNALING_SERVER_URL",
+    AdiProfile         => "ADI_PROFILE",
     // Daemon env vars
     AdiDaemonSocket    => "ADI_DAEMON_SOCKET",
     AdiDaemonPid       => "ADI_DAEMON_PID",
@@ -46,6 +49,20 @@ pub fn theme() -> Option<String> {
     val
 }
 
+/// Output glyph set override ($ADI_OUTPUT_THEME)
+pub fn output_theme() -> Option<String> {
+    let val = env_opt(EnvVar::AdiOutputTheme.as_str());
+    tracing::trace!(value = ?val, "ADI_OUTPUT_THEME env var");
+    val
+}
+
+/// Output color scheme override ($ADI_COLOR_SCHEME)
+pub fn color_scheme() -> Option<String> {
+    let val = env_opt(EnvVar::AdiColorScheme.as_str());
+    tracing::trace!(value = ?val, "ADI_COLOR_SCHEME env var");
+    val
+}
+
 /// ADI language override ($ADI_LANG)
 pub fn lang() -> Option<String> {
     let val = env_opt(EnvVar::AdiLang.as_str());
@@ -60,6 +77,13 @@ pub fn system_lang() -> Option<String> {
     val
 }
 
+/// Command profiling ($ADI_PROFILE)
+pub fn profile() -> Option<String> {
+    let val = env_opt(EnvVar::AdiProfile.as_str());
+    tracing::trace!(value = ?val, "ADI_PROFILE env var");
+    val
+}
+
 /// Power user mode from env var ($ADI_POWER_USER)
 pub fn power_user_env() -> Option<bool> {
     let val = env_opt(EnvVar::AdiPowerUser.as_str());
@@ -1,21 +1,29 @@
+use std::path::PathBuf;
+
+use cli::plugin_runtime::{PluginRuntime, RuntimeConfig};
 use cli::user_config::UserConfig;
 use dialoguer::console::{style, Key, Term};
-use lib_console_output::blocks::{KeyValue, Renderable, Section};
+use lib_console_output::blocks::{KeyValue, Renderable, Section, Table};
 use lib_console_output::theme;
-use lib_console_output::{out_info, out_success};
+use lib_console_output::{out_error, out_info, out_success, out_warn};
+use lib_plugin_abi_v3::cli::CliErrorCode;
+use lib_plugin_host::{ConfigIssue, ConfigIssueSeverity, LayeredConfig};
+use serde_json::json;
 
 use crate::args::ConfigCommands;
 
 pub(crate) async fn cmd_config(command: Option<ConfigCommands>) -> anyhow::Result<()> {
     match command {
         Some(ConfigCommands::Show) => cmd_config_show(),
+        Some(ConfigCommands::Set { key, value }) => cmd_config_set(&key, &value),
         Some(ConfigCommands::PowerUser { enable }) => {
-            let value = match enable.to_lowercase().as_str() {
-                "true" | "1" | "yes" | "on" => true,
-                "false" | "0" | "no" | "off" => false,
-                _ => anyhow::bail!("Invalid value '{}'. Use 'true' or 'false'.", enable),
-            };
-            cmd_config_power_user_set(value)
+            cmd_config_power_user_set(parse_enable(&enable)?)
+        }
+        Some(ConfigCommands::Explain { plugin, key, json }) => {
+            cmd_config_explain(&plugin, &key, json).await
+        }
+        Some(ConfigCommands::Validate { plugin, json }) => {
+            cmd_config_validate(plugin.as_deref(), json).await
         }
         None => {
             // No subcommand: interactive in TTY, show otherwise
@@ -52,10 +60,26 @@ fn cmd_config_show() -> anyhow::Result<()> {
         .map(|t| theme::brand(t).to_string())
         .unwrap_or_else(|| theme::muted("default").to_string());
 
+    let output_theme_status = config
+        .output
+        .theme
+        .as_deref()
+        .map(|t| theme::foreground(t).to_string())
+        .unwrap_or_else(|| theme::muted("default (unicode)").to_string());
+
+    let colors_status = config
+        .output
+        .colors
+        .as_deref()
+        .map(|c| theme::foreground(c).to_string())
+        .unwrap_or_else(|| theme::muted("default").to_string());
+
     KeyValue::new()
         .entry("Power User", power_user_status)
         .entry("Language", language_status)
         .entry("Theme", theme_status)
+        .entry("Output Theme", output_theme_status)
+        .entry("Colors", colors_status)
         .entry(
             "Config File",
             theme::muted(config_path.display()).to_string(),
@@ -65,6 +89,181 @@ fn cmd_config_show() -> anyhow::Result<()> {
     Ok(())
 }
 
+fn parse_enable(value: &str) -> anyhow::Result<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        _ => anyhow::bail!("Invalid value '{}'. Use 'true' or 'false'.", value),
+    }
+}
+
+fn cmd_config_set(key: &str, value: &str) -> anyhow::Result<()> {
+    let mut config = UserConfig::load()?;
+    match key {
+        "output.theme" => {
+            let output_theme: theme::OutputTheme = value.parse().map_err(anyhow::Error::msg)?;
+            config.output.theme = Some(output_theme.to_string());
+        }
+        "output.colors" => {
+            let scheme: theme::ColorScheme = value.parse().map_err(anyhow::Error::msg)?;
+            config.output.colors = Some(scheme.to_string());
+        }
+        "theme" => {
+            if theme::find_theme(value).is_none() {
+                anyhow::bail!("Unknown theme '{}'. Run 'adi theme' to list themes.", value);
+            }
+            config.theme = Some(value.to_string());
+        }
+        "language" => config.language = Some(value.to_string()),
+        "power_user" => return cmd_config_power_user_set(parse_enable(value)?),
+        _ => anyhow::bail!(
+            "Unknown key '{}'. Use output.theme, output.colors, theme, language or power_user.",
+            key
+        ),
+    }
+    config.save()?;
+
+    out_success!("{} set to {}", key, value);
+    Ok(())
+}
+
+/// Root of the project whose `.adi/config/` layer applies
+fn project_root() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    PluginRuntime::context_registry().resolve(&cwd).map(|ctx| ctx.root)
+}
+
+async fn cmd_config_explain(plugin_id: &str, key: &str, json: bool) -> anyhow::Result<()> {
+    let runtime = PluginRuntime::new(RuntimeConfig::default()).await?;
+    let Ok(manifest) = runtime.plugin_manifest(plugin_id) else {
+        out_error!("Plugin {} is not installed", plugin_id);
+        std::process::exit(CliErrorCode::NotFound.exit_code());
+    };
+
+    let config = LayeredConfig::load(&manifest, project_root().as_deref())?;
+    let layers = config.explain(key);
+    let field = manifest.config.schema.get(key);
+
+    if json {
+        let output = json!({
+            "plugin": plugin_id,
+            "key": key,
+            "value": config.merged().get(key),
+            "type": field.map(|f| f.value_type),
+            "layers": layers,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    Section::new(format!("{plugin_id} · {key}")).width(50).print();
+    if let Some(field) = field {
+        let description = if field.description.is_empty() {
+            String::new()
+        } else {
+            format!(" — {}", field.description)
+        };
+        out_info!("Type: {}{}", field.value_type, description);
+    } else if !manifest.config.schema.is_empty() {
+        out_warn!("{} is not declared in the plugin's config schema", key);
+    }
+    println!();
+
+    if layers.iter().all(|l| !l.winner) {
+        out_info!("{} is not set in any layer", key);
+        return Ok(());
+    }
+
+    let mut table = Table::new().header(["", "Layer", "Value", "Source"]);
+    for layer in &layers {
+        let (marker, value) = match (&layer.value, layer.winner) {
+            (Some(value), true) => (theme::success("→").to_string(), theme::bold(value).to_string()),
+            (Some(value), false) => (String::new(), theme::muted(value).to_string()),
+            (None, _) => (String::new(), theme::muted("-").to_string()),
+        };
+        table = table.row([
+            marker,
+            layer.kind.to_string(),
+            value,
+            theme::muted(&layer.source).to_string(),
+        ]);
+    }
+    table.print();
+
+    Ok(())
+}
+
+async fn cmd_config_validate(plugin_id: Option<&str>, json: bool) -> anyhow::Result<()> {
+    let runtime = PluginRuntime::new(RuntimeConfig::default()).await?;
+    let manifests = match plugin_id {
+        Some(id) => match runtime.plugin_manifest(id) {
+            Ok(manifest) => vec![manifest],
+            Err(_) => {
+                out_error!("Plugin {} is not installed", id);
+                std::process::exit(CliErrorCode::NotFound.exit_code());
+            }
+        },
+        None => runtime.installed_manifests(),
+    };
+    let project_root = project_root();
+
+    let mut report: Vec<(String, Vec<ConfigIssue>)> = Vec::new();
+    for manifest in &manifests {
+        let issues = match LayeredConfig::load(manifest, project_root.as_deref()) {
+            Ok(config) => config.validate(&manifest.config.schema),
+            Err(e) => vec![ConfigIssue {
+                severity: ConfigIssueSeverity::Error,
+                key: String::new(),
+                layer: None,
+                message: e.to_string(),
+            }],
+        };
+        report.push((manifest.plugin.id.clone(), issues));
+    }
+
+    let errors = report
+        .iter()
+        .flat_map(|(_, issues)| issues)
+        .filter(|i| i.severity == ConfigIssueSeverity::Error)
+        .count();
+
+    if json {
+        let output: serde_json::Map<String, serde_json::Value> = report
+            .iter()
+            .map(|(id, issues)| (id.clone(), json!(issues)))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        for (id, issues) in &report {
+            if issues.is_empty() {
+                out_success!("{}", id);
+                continue;
+            }
+            for issue in issues {
+                let location = match (&issue.layer, issue.key.is_empty()) {
+                    (_, true) => id.clone(),
+                    (Some(layer), false) => format!("{id} {} ({layer})", issue.key),
+                    (None, false) => format!("{id} {}", issue.key),
+                };
+                match issue.severity {
+                    ConfigIssueSeverity::Error => out_error!("{}: {}", location, issue.message),
+                    ConfigIssueSeverity::Warning => out_warn!("{}: {}", location, issue.message),
+                }
+            }
+        }
+    }
+
+    if errors > 0 {
+        if !json {
+            println!();
+            out_error!("{} config error(s) found", errors);
+        }
+        std::process::exit(CliErrorCode::Config.exit_code());
+    }
+
+    Ok(())
+}
+
 struct ConfigOption {
     key: &'static str,
     label: &'static str,
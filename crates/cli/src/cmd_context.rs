@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use cli::plugin_runtime::{PluginRuntime, RuntimeConfig};
+use lib_console_output::blocks::{KeyValue, Renderable, Section};
+use lib_console_output::theme;
+use lib_console_output::{out_info, out_success};
+use lib_plugin_abi_v3::project::ProjectContext;
+
+use crate::args::ContextCommands;
+
+pub(crate) async fn cmd_context(command: Option<ContextCommands>) -> anyhow::Result<()> {
+    match command.unwrap_or(ContextCommands::Show) {
+        ContextCommands::Show => cmd_context_show(),
+        ContextCommands::Use { path } => cmd_context_use(&path).await,
+        ContextCommands::Clear => cmd_context_clear().await,
+    }
+}
+
+fn cmd_context_show() -> anyhow::Result<()> {
+    let cwd = std::env::current_dir()?;
+    let Some(ctx) = PluginRuntime::context_registry().resolve(&cwd) else {
+        out_info!("Not inside a project (no .adi/ directory or git repository found)");
+        return Ok(());
+    };
+    print_context(&ctx);
+    Ok(())
+}
+
+async fn cmd_context_use(path: &Path) -> anyhow::Result<()> {
+    let runtime = load_runtime().await?;
+
+    let ctx = PluginRuntime::context_registry().pin(path)?;
+    runtime.set_project_context(Some(ctx.clone())).await;
+
+    out_success!("Using project {}", theme::bold(ctx.root.display()));
+    Ok(())
+}
+
+async fn cmd_context_clear() -> anyhow::Result<()> {
+    let runtime = load_runtime().await?;
+
+    let mut registry = PluginRuntime::context_registry();
+    if registry.pinned().is_none() {
+        out_info!("No project pinned");
+        return Ok(());
+    }
+    registry.clear()?;
+
+    let ctx = registry.resolve(&std::env::current_dir()?);
+    runtime.set_project_context(ctx).await;
+
+    out_success!("Project unpinned, detecting from the working directory again");
+    Ok(())
+}
+
+/// Runtime with all plugins loaded, so context changes reach every plugin
+async fn load_runtime() -> anyhow::Result<PluginRuntime> {
+    let runtime = PluginRuntime::new(RuntimeConfig::default()).await?;
+    runtime.load_all_plugins().await?;
+    Ok(runtime)
+}
+
+fn print_context(ctx: &ProjectContext) {
+    Section::new("Project Context").width(50).print();
+
+    let source = if ctx.pinned {
+        "pinned (adi context use)"
+    } else {
+        "detected from working directory"
+    };
+    KeyValue::new()
+        .entry("Name", theme::brand_bold(ctx.name()).to_string())
+        .entry("Root", theme::muted(ctx.root.display()).to_string())
+        .entry("Marker", ctx.marker.to_string())
+        .entry("Source", theme::muted(source).to_string())
+        .print();
+}
@@ -14,6 +14,7 @@ pub async fn cmd_daemon(command: DaemonCommands) -> Result<()> {
         DaemonCommands::Start => cmd_daemon_start().await,
         DaemonCommands::Stop { force } => cmd_daemon_stop(force).await,
         DaemonCommands::Restart => cmd_daemon_restart().await,
+        DaemonCommands::Update { binary } => cmd_daemon_update(binary).await,
         DaemonCommands::Status => cmd_daemon_status().await,
         DaemonCommands::StartService { service } => cmd_start_service(&service).await,
         DaemonCommands::StopService { service, force } => cmd_stop_service(&service, force).await,
@@ -24,6 +25,7 @@ pub async fn cmd_daemon(command: DaemonCommands) -> Result<()> {
             lines,
             follow,
         } => cmd_service_logs(&service, lines, follow).await,
+        DaemonCommands::Events { topics } => cmd_daemon_events(&topics).await,
         DaemonCommands::RunService { plugin_id } => cmd_daemon_run_service(&plugin_id).await,
         DaemonCommands::Setup => cmd_daemon_setup().await,
     }
@@ -123,6 +125,59 @@ async fn cmd_daemon_restart() -> Result<()> {
     cmd_daemon_start().await
 }
 
+async fn cmd_daemon_update(binary: Option<std::path::PathBuf>) -> Result<()> {
+    // Spawning the successor and handing over services takes longer than a plain request
+    let client = DaemonClient::new().with_timeout(std::time::Duration::from_secs(60));
+
+    if !client.is_running().await {
+        println!(
+            "{} Daemon is not running (start it with `adi daemon start`)",
+            theme::icons::INFO
+        );
+        return Ok(());
+    }
+
+    let binary = match binary {
+        Some(path) => path,
+        None => std::env::current_exe()?,
+    };
+    let binary = binary
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("Daemon binary {}: {}", binary.display(), e))?;
+
+    let (_uptime, old_version) = client.ping().await?;
+    let old_pid = lib_daemon_core::PidFile::new(clienv::daemon_pid_path()).is_running()?;
+
+    println!(
+        "{} Handing daemon v{} over to {}...",
+        theme::icons::INFO,
+        old_version,
+        theme::muted(binary.display())
+    );
+    client.update(&binary.display().to_string()).await?;
+
+    if let Some(pid) = old_pid {
+        let timeout = std::time::Duration::from_secs(10);
+        if !lib_daemon_core::wait_for_exit(pid, timeout).await {
+            println!(
+                "{} Previous daemon (PID {}) is still exiting",
+                theme::icons::WARNING,
+                pid
+            );
+        }
+    }
+
+    let (_uptime, new_version) = client.ping().await?;
+    println!(
+        "{} Daemon updated: v{} -> v{}, services kept running",
+        theme::icons::SUCCESS,
+        old_version,
+        new_version
+    );
+
+    Ok(())
+}
+
 async fn cmd_daemon_status() -> Result<()> {
     let client = DaemonClient::new();
 
@@ -356,6 +411,33 @@ async fn cmd_service_logs(name: &str, lines: usize, follow: bool) -> Result<()>
     Ok(())
 }
 
+async fn cmd_daemon_events(topics: &[String]) -> Result<()> {
+    let client = DaemonClient::new();
+
+    if !client.is_running().await {
+        anyhow::bail!("Daemon is not running. Start it with `adi daemon start`");
+    }
+
+    let mut subscription = client.subscribe(topics).await?;
+    println!(
+        "{} Streaming events for {} (Ctrl+C to stop)...",
+        theme::icons::INFO,
+        theme::bold(topics.join(", "))
+    );
+
+    while let Some(event) = subscription.next().await? {
+        println!(
+            "{} {} {}",
+            theme::bold(&event.topic),
+            theme::muted(format!("[{}]", event.source)),
+            event.payload
+        );
+    }
+
+    println!("{} Daemon closed the event stream", theme::icons::WARNING);
+    Ok(())
+}
+
 async fn cmd_daemon_setup() -> Result<()> {
     cli::daemon::setup::run_setup().await
 }
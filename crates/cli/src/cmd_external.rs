@@ -2,6 +2,7 @@ use cli::plugin_registry::PluginManager;
 use cli::plugin_runtime::{PluginCliCommand, PluginRuntime, RuntimeConfig};
 use lib_console_output::{theme, blocks::{Columns, Section, Renderable}, out_info, out_warn, out_error, out_success};
 use lib_i18n_core::{t, LocalizedError};
+use lib_plugin_abi_v3::cli::CliErrorCode;
 
 use crate::cmd_run::handle_cli_result;
 
@@ -23,20 +24,22 @@ pub(crate) async fn cmd_external(args: Vec<String>) -> anyhow::Result<()> {
 
 async fn resolve_plugin_with_runtime(command: &str) -> anyhow::Result<(String, PluginRuntime)> {
     let mut runtime = PluginRuntime::new(RuntimeConfig::default()).await?;
-    let cli_commands = runtime.discover_cli_commands();
 
-    let plugin_id = match find_installed_plugin(command, &cli_commands) {
+    // Only the owning plugin is loaded; the full command list is read on a miss
+    let plugin_id = match runtime.find_plugin_by_command(command) {
         Some(id) => id,
         None => {
             tracing::trace!(command = %command, "No installed plugin found, trying auto-install");
+            let cli_commands = runtime.discover_cli_commands();
             match try_autoinstall_plugin(command, &cli_commands).await {
                 AutoinstallResult::Installed(id) => {
                     runtime = PluginRuntime::new(RuntimeConfig::default()).await?;
                     id
                 }
-                AutoinstallResult::NotFound
-                | AutoinstallResult::Declined
-                | AutoinstallResult::Failed => {
+                AutoinstallResult::NotFound => {
+                    std::process::exit(CliErrorCode::NotFound.exit_code());
+                }
+                AutoinstallResult::Declined | AutoinstallResult::Failed => {
                     std::process::exit(1);
                 }
             }
@@ -46,16 +49,6 @@ async fn resolve_plugin_with_runtime(command: &str) -> anyhow::Result<(String, P
     Ok((plugin_id, runtime))
 }
 
-fn find_installed_plugin(command: &str, cli_commands: &[PluginCliCommand]) -> Option<String> {
-    cli_commands
-        .iter()
-        .find(|c| c.command == command || c.aliases.iter().any(|a| a == command))
-        .map(|c| {
-            tracing::trace!(plugin_id = %c.plugin_id, "Found matching plugin for command");
-            c.plugin_id.clone()
-        })
-}
-
 async fn execute_external_command(
     runtime: &PluginRuntime,
     plugin_id: &str,
@@ -69,6 +69,8 @@ async fn print_available_commands() {
         ("plugin", t!("info-cmd-plugin")),
         ("run", t!("info-cmd-run")),
         ("logs", t!("info-cmd-logs")),
+        ("search", t!("info-cmd-search")),
+        ("context", t!("info-cmd-context")),
         ("self-update", t!("info-cmd-self-update")),
     ];
 
@@ -153,6 +153,7 @@ fn prompt_plugin() -> Option<Commands> {
             (t!("interactive-plugin-update-all"), "update-all"),
             (t!("interactive-plugin-uninstall"), "uninstall"),
             (t!("interactive-plugin-path"), "path"),
+            (t!("interactive-plugin-refresh"), "refresh"),
         ])
         .run()?;
 
@@ -184,6 +185,7 @@ fn dispatch_plugin_subcmd(subcmd: &str) -> Option<Commands> {
             let plugin_id = Input::new(t!("interactive-plugin-path-id")).required().run()?;
             PluginCommands::Path { plugin_id }
         }
+        "refresh" => PluginCommands::Refresh,
         _ => return None,
     };
     Some(Commands::Plugin { command: cmd })
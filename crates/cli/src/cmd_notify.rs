@@ -0,0 +1,81 @@
+use cli::daemon::notify::{Notifier, SinkConfig};
+use cli::user_config::UserConfig;
+use lib_console_output::blocks::{Renderable, Section, Table};
+use lib_console_output::theme;
+use lib_console_output::{out_info, out_success};
+
+use crate::args::NotifyCommands;
+
+pub(crate) async fn cmd_notify(command: NotifyCommands) -> anyhow::Result<()> {
+    match command {
+        NotifyCommands::List => cmd_notify_list(),
+        NotifyCommands::Test { sink } => cmd_notify_test(&sink).await,
+    }
+}
+
+fn cmd_notify_list() -> anyhow::Result<()> {
+    let config = UserConfig::load()?.notify;
+
+    if config.is_empty() {
+        out_info!(
+            "No notifications configured. Add [notify.sinks] and [[notify.routes]] to {}",
+            UserConfig::config_path()?.display()
+        );
+        return Ok(());
+    }
+
+    Section::new("Notification Sinks").print();
+    let mut sinks = Table::new().header(["Sink", "Type", "Target"]);
+    for (name, sink) in &config.sinks {
+        let target = match sink {
+            SinkConfig::Webhook { url, .. } | SinkConfig::Slack { url, .. } => url.clone(),
+            SinkConfig::Desktop => "-".to_string(),
+            SinkConfig::Email(email) => email.to.join(", "),
+        };
+        sinks = sinks.row([name.clone(), sink.kind().to_string(), target]);
+    }
+    sinks.print();
+    println!();
+
+    Section::new("Routes").print();
+    let mut routes = Table::new().header(["Topics", "Match", "Sinks"]);
+    for route in &config.routes {
+        let matches = route
+            .match_fields
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(", ");
+        routes = routes.row([
+            route.topics.join(", "),
+            if matches.is_empty() {
+                "-".to_string()
+            } else {
+                matches
+            },
+            route.sinks.join(", "),
+        ]);
+    }
+    routes.print();
+    println!();
+    println!(
+        "  {}",
+        theme::muted(format!(
+            "dedupe {}s, max {}/min per sink",
+            config.dedupe_secs, config.max_per_minute
+        ))
+    );
+
+    Ok(())
+}
+
+async fn cmd_notify_test(sink: &str) -> anyhow::Result<()> {
+    let config = UserConfig::load()?.notify;
+    let notifier = Notifier::new(config);
+
+    out_info!("Sending test notification to {}...", theme::bold(sink));
+    notifier.test(sink).await?;
+    out_success!("Delivered test notification to {}", sink);
+
+    Ok(())
+}
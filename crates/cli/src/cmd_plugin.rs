@@ -1,8 +1,12 @@
 use cli::completions;
 use cli::plugin_registry::PluginManager;
+use cli::plugin_runtime::{PluginRuntime, RuntimeConfig};
 use lib_console_output::{theme, blocks::{Columns, Section, Renderable}, out_info, out_warn, out_error, out_success};
 use lib_console_output::input::Confirm;
 use lib_i18n_core::{t, LocalizedError};
+use lib_plugin_abi_v3::cli::CliCommand;
+use lib_plugin_host::scaffold::scaffold_v3;
+use std::path::PathBuf;
 
 use crate::args::{Cli, PluginCommands};
 
@@ -21,6 +25,10 @@ pub(crate) async fn cmd_plugin(command: PluginCommands) -> anyhow::Result<()> {
         PluginCommands::UpdateAll => handle_update_all(&manager).await,
         PluginCommands::Uninstall { plugin_id } => handle_uninstall(&manager, &plugin_id).await,
         PluginCommands::Path { plugin_id } => handle_path(&manager, &plugin_id).await,
+        PluginCommands::Refresh => handle_refresh().await,
+        PluginCommands::MigrateScaffold { plugin_id, out } => {
+            handle_migrate_scaffold(&plugin_id, out).await
+        }
     }
 }
 
@@ -151,6 +159,62 @@ async fn handle_path(manager: &PluginManager, plugin_id: &str) -> anyhow::Result
     Ok(())
 }
 
+async fn handle_refresh() -> anyhow::Result<()> {
+    tracing::trace!("Rebuilding command index");
+    let runtime = PluginRuntime::new(RuntimeConfig::default()).await?;
+    let count = runtime.refresh_command_index()?;
+    out_success!("{}", t!("plugin-refresh-success", "count" => &count.to_string()));
+    regenerate_completions_quiet();
+    Ok(())
+}
+
+async fn handle_migrate_scaffold(plugin_id: &str, out: Option<PathBuf>) -> anyhow::Result<()> {
+    tracing::trace!(plugin_id = %plugin_id, "Generating v3 migration scaffold");
+    let out = out.unwrap_or_else(|| PathBuf::from(plugin_id));
+    if out.exists() {
+        anyhow::bail!("{}", t!("plugin-migrate-exists", "path" => &out.display().to_string()));
+    }
+
+    let runtime = PluginRuntime::new(RuntimeConfig::default()).await?;
+    let manifest = runtime.plugin_manifest(plugin_id)?;
+
+    let issues = runtime.plugin_compat_issues(plugin_id)?;
+    if issues.is_empty() {
+        out_info!("{}", t!("plugin-migrate-no-issues", "id" => plugin_id));
+    } else {
+        out_warn!("{}", t!("plugin-migrate-issues", "id" => plugin_id));
+        for issue in &issues {
+            out_warn!("  - {}", issue);
+        }
+    }
+
+    // Raw plugins that don't export plugin_create_cli have no command schema
+    let commands = match runtime.scan_and_load_plugin(plugin_id).await {
+        Ok(()) => runtime
+            .list_cli_commands(plugin_id)
+            .await
+            .ok()
+            .and_then(|json| serde_json::from_str::<Vec<CliCommand>>(&json).ok()),
+        Err(_) => None,
+    };
+    let commands = commands.unwrap_or_else(|| {
+        out_warn!("{}", t!("plugin-migrate-no-commands", "id" => plugin_id));
+        Vec::new()
+    });
+
+    for (path, content) in scaffold_v3(&manifest, &commands, &issues) {
+        let path = out.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+    }
+
+    out_success!("{}", t!("plugin-migrate-success", "path" => &out.display().to_string()));
+    out_info!("{}", t!("plugin-migrate-next"));
+    Ok(())
+}
+
 fn regenerate_completions_quiet() {
     if let Err(e) = completions::regenerate_completions::<Cli>("adi") {
         #[cfg(debug_assertions)]
@@ -1,15 +1,21 @@
 use cli::plugin_runtime::{PluginRuntime, RuntimeConfig};
 use lib_console_output::{theme, blocks::{Columns, Section, Renderable}, out_info, out_error};
 use lib_i18n_core::{t, LocalizedError};
+use lib_plugin_abi_v3::cli::{CliError, CliErrorCode};
 
 pub(crate) async fn cmd_run(plugin_id: Option<String>, args: Vec<String>) -> anyhow::Result<()> {
     tracing::trace!(plugin_id = ?plugin_id, args = ?args, "cmd_run invoked");
 
     let runtime = PluginRuntime::new(RuntimeConfig::default()).await?;
-    runtime.load_all_plugins().await?;
 
-    let runnable = runtime.list_runnable_plugins();
-    tracing::trace!(runnable_count = runnable.len(), "Loaded runnable plugins");
+    // Listed from manifests; only the selected plugin's binary is loaded
+    let mut runnable: Vec<(String, String)> = runtime
+        .discover_cli_commands()
+        .into_iter()
+        .map(|cmd| (cmd.plugin_id, cmd.description))
+        .collect();
+    runnable.sort();
+    tracing::trace!(runnable_count = runnable.len(), "Discovered runnable plugins");
 
     let plugin_id = match plugin_id {
         Some(id) => id,
@@ -43,6 +49,11 @@ pub(crate) async fn cmd_run(plugin_id: Option<String>, args: Vec<String>) -> any
                 out_info!("  - {}", id);
             }
         }
+        std::process::exit(CliErrorCode::NotFound.exit_code());
+    }
+
+    if let Err(e) = runtime.scan_and_load_plugin(&plugin_id).await {
+        out_error!("{} {}", t!("common-error-prefix"), t!("run-error-failed", "error" => &e.localized()));
         std::process::exit(1);
     }
 
@@ -70,6 +81,8 @@ pub(crate) fn handle_cli_result(result_json: &str) {
         exit_code: i32,
         stdout: String,
         stderr: String,
+        #[serde(default)]
+        error: Option<CliError>,
     }
 
     match serde_json::from_str::<CliResult>(result_json) {
@@ -78,10 +91,13 @@ pub(crate) fn handle_cli_result(result_json: &str) {
             if !result.stdout.is_empty() {
                 print!("{}", result.stdout);
             }
-            if !result.stderr.is_empty() {
+            if let Some(error) = &result.error {
+                print_cli_error(error);
+            } else if !result.stderr.is_empty() {
                 eprint!("{}", result.stderr);
             }
             if result.exit_code != 0 {
+                cli::profile::report();
                 std::process::exit(result.exit_code);
             }
         }
@@ -91,3 +107,13 @@ pub(crate) fn handle_cli_result(result_json: &str) {
         }
     }
 }
+
+fn print_cli_error(error: &CliError) {
+    out_error!("{} {}", t!("common-error-prefix"), error.message);
+    if let Some(hint) = &error.hint {
+        out_info!("{} {}", t!("common-hint-prefix"), hint);
+    }
+    if let Some(url) = &error.docs_url {
+        out_info!("{} {}", t!("common-docs-prefix"), theme::muted(url));
+    }
+}
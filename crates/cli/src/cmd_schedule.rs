@@ -0,0 +1,120 @@
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use cli::daemon::DaemonClient;
+use lib_console_output::blocks::{Renderable, Section, Table};
+use lib_console_output::theme;
+use lib_console_output::{out_error, out_info, out_success};
+
+use crate::args::ScheduleCommands;
+
+/// Jobs run to completion, so `run-now` waits far longer than regular IPC.
+const RUN_NOW_TIMEOUT: Duration = Duration::from_secs(3600);
+
+pub(crate) async fn cmd_schedule(command: ScheduleCommands) -> Result<()> {
+    let client = DaemonClient::new();
+
+    if !client.is_running().await {
+        anyhow::bail!("Daemon is not running. Start it with `adi daemon start`");
+    }
+
+    match command {
+        ScheduleCommands::List => cmd_schedule_list(&client).await,
+        ScheduleCommands::RunNow { id } => cmd_schedule_run_now(client, &id).await,
+        ScheduleCommands::Remove { id } => {
+            client.unschedule_job(&id).await?;
+            out_success!("Removed scheduled job {}", theme::bold(&id));
+            Ok(())
+        }
+    }
+}
+
+async fn cmd_schedule_list(client: &DaemonClient) -> Result<()> {
+    let jobs = client.list_jobs().await?;
+
+    if jobs.is_empty() {
+        out_info!("No scheduled jobs. Plugins register jobs with the daemon when loaded.");
+        return Ok(());
+    }
+
+    Section::new("Scheduled Jobs").print();
+    println!();
+
+    let now = now_ms();
+    let mut table = Table::new().header(["Job", "Owner", "Command", "Every", "Next", "Last"]);
+
+    for job in &jobs {
+        let next = if job.running {
+            theme::info("running").to_string()
+        } else {
+            format!(
+                "in {}",
+                format_duration(job.next_run_ms.saturating_sub(now) / 1000)
+            )
+        };
+        let last = match (job.last_run_ms, job.last_exit_code) {
+            (Some(at), Some(0)) => theme::success(format_ago(now, at)).to_string(),
+            (Some(at), Some(code)) => {
+                theme::error(format!("{} (exit {})", format_ago(now, at), code)).to_string()
+            }
+            (Some(at), None) => {
+                theme::error(format!("{} (failed)", format_ago(now, at))).to_string()
+            }
+            (None, _) => theme::muted("never").to_string(),
+        };
+
+        table = table.row([
+            job.spec.id.clone(),
+            job.spec.owner.clone(),
+            format!("adi {}", job.spec.args.join(" ")),
+            format_duration(job.spec.interval_secs),
+            next,
+            last,
+        ]);
+    }
+
+    table.print();
+    println!();
+
+    Ok(())
+}
+
+async fn cmd_schedule_run_now(client: DaemonClient, id: &str) -> Result<()> {
+    out_info!("Running {}...", theme::bold(id));
+
+    let output = client.with_timeout(RUN_NOW_TIMEOUT).run_job_now(id).await?;
+    std::io::stdout().write_all(&output.stdout)?;
+    std::io::stderr().write_all(&output.stderr)?;
+
+    if output.success() {
+        out_success!("Job {} finished", id);
+        Ok(())
+    } else {
+        out_error!("Job {} exited with code {}", id, output.exit_code);
+        std::process::exit(output.exit_code.max(1));
+    }
+}
+
+fn format_ago(now: u64, at: u64) -> String {
+    format!("{} ago", format_duration(now.saturating_sub(at) / 1000))
+}
+
+fn format_duration(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    } else {
+        format!("{}d {}h", secs / 86400, (secs % 86400) / 3600)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -1,6 +1,10 @@
 use cli::plugin_registry::PluginManager;
-use lib_console_output::{theme, blocks::{Columns, Section, Renderable}, out_info};
+use cli::plugin_runtime::{PluginRuntime, RuntimeConfig};
+use lib_console_output::{theme, blocks::{Columns, Section, Renderable}, out_info, out_warn};
 use lib_i18n_core::t;
+use lib_plugin_abi_v3::search::{merge_hits, SearchQuery};
+use std::time::Duration;
+use tokio::task::JoinSet;
 
 pub(crate) async fn cmd_search(query: &str) -> anyhow::Result<()> {
     tracing::trace!(query = %query, "cmd_search invoked");
@@ -39,3 +43,92 @@ pub(crate) async fn cmd_search(query: &str) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Per-plugin budget so one slow provider cannot stall `adi search`.
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(crate) async fn cmd_search_all(
+    query: &str,
+    types: &[String],
+    limit: usize,
+    json: bool,
+) -> anyhow::Result<()> {
+    tracing::trace!(query = %query, types = ?types, limit = limit, "cmd_search_all invoked");
+
+    let runtime = PluginRuntime::new(RuntimeConfig::default()).await?;
+    runtime.load_all_plugins().await?;
+
+    let providers: Vec<_> = runtime
+        .search_providers()
+        .into_iter()
+        .filter(|(_, provider)| {
+            types.is_empty() || provider.search_types().iter().any(|t| types.contains(t))
+        })
+        .collect();
+    tracing::trace!(providers = providers.len(), "Search providers selected");
+
+    if providers.is_empty() {
+        out_info!("{}", t!("search-all-no-providers"));
+        return Ok(());
+    }
+    let provider_count = providers.len();
+
+    let search_query = SearchQuery {
+        text: query.to_string(),
+        limit,
+    };
+    let mut tasks = JoinSet::new();
+    for (plugin_id, provider) in providers {
+        let search_query = search_query.clone();
+        tasks.spawn(async move {
+            let result = tokio::time::timeout(PROVIDER_TIMEOUT, provider.search(&search_query)).await;
+            (plugin_id, result)
+        });
+    }
+
+    let mut hits = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((_, Ok(Ok(provider_hits)))) => hits.extend(provider_hits),
+            Ok((plugin_id, Ok(Err(e)))) => {
+                out_warn!("{}", t!("search-all-provider-failed", "plugin" => &plugin_id, "error" => &e.to_string()));
+            }
+            Ok((plugin_id, Err(_))) => {
+                out_warn!("{}", t!("search-all-provider-timeout", "plugin" => &plugin_id));
+            }
+            Err(e) => tracing::warn!("Search provider task failed: {}", e),
+        }
+    }
+
+    let hits = merge_hits(hits, types, limit);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        out_info!("{}", t!("search-no-results"));
+        return Ok(());
+    }
+
+    Section::new(t!("search-all-results-title")).print();
+    Columns::new()
+        .header(["Type", "Title", "Open"])
+        .rows(hits.iter().map(|hit| [
+            theme::warning(&hit.kind).to_string(),
+            match &hit.snippet {
+                Some(snippet) => format!("{} {}", hit.title, theme::muted(snippet)),
+                None => hit.title.clone(),
+            },
+            theme::muted(hit.open_command.as_deref().unwrap_or(&hit.id)).to_string(),
+        ]))
+        .print();
+
+    out_info!("{}", t!("search-all-results-summary",
+        "count" => &hits.len().to_string(),
+        "providers" => &provider_count.to_string()
+    ));
+
+    Ok(())
+}
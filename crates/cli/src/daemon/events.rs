@@ -0,0 +1,146 @@
+use super::protocol::DaemonEvent;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::trace;
+
+pub use lib_daemon_client::events::topics;
+
+const DEFAULT_CAPACITY: usize = 1024;
+const DEFAULT_ERROR_THRESHOLD: usize = 20;
+const DEFAULT_ERROR_WINDOW: Duration = Duration::from_secs(60);
+
+/// In-process pub/sub bus. Daemon components publish directly; IPC clients
+/// publish through `Request::Publish` and receive events over `Request::Subscribe`.
+pub struct EventBus {
+    tx: broadcast::Sender<DaemonEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx }
+    }
+
+    pub fn publish(&self, event: DaemonEvent) {
+        trace!("Event {} from {}", event.topic, event.source);
+        // No subscribers is not an error
+        let _ = self.tx.send(event);
+    }
+
+    /// Publish a daemon-originated event with a JSON payload.
+    pub fn emit(&self, topic: &str, payload: serde_json::Value) {
+        self.publish(DaemonEvent::new(topic, "daemon", payload.to_string()));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DaemonEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// Counts error lines per service and fires once when `threshold` errors
+/// land within `window`.
+pub struct LogThresholdWatch {
+    threshold: usize,
+    window: Duration,
+    hits: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl LogThresholdWatch {
+    pub fn new(threshold: usize, window: Duration) -> Self {
+        Self {
+            threshold,
+            window,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Record a log line; returns the error count when the threshold is crossed.
+    pub fn record(&self, service: &str, line: &str) -> Option<usize> {
+        if !is_error_line(line) {
+            return None;
+        }
+
+        let now = Instant::now();
+        let mut hits = self.hits.lock().expect("LogThresholdWatch lock poisoned");
+        let entries = hits.entry(service.to_string()).or_default();
+        entries.push_back(now);
+        while entries
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > self.window)
+        {
+            entries.pop_front();
+        }
+
+        if entries.len() >= self.threshold {
+            let count = entries.len();
+            // Start counting afresh so a sustained burst fires once per window
+            entries.clear();
+            return Some(count);
+        }
+        None
+    }
+}
+
+impl Default for LogThresholdWatch {
+    fn default() -> Self {
+        Self::new(DEFAULT_ERROR_THRESHOLD, DEFAULT_ERROR_WINDOW)
+    }
+}
+
+fn is_error_line(line: &str) -> bool {
+    line.contains("ERROR") || line.contains("FATAL") || line.contains("panicked at")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribers_receive_published_events() {
+        let bus = EventBus::default();
+        let mut rx = bus.subscribe();
+
+        bus.emit(
+            topics::SERVICE_CRASHED,
+            serde_json::json!({ "service": "hive" }),
+        );
+
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.topic, topics::SERVICE_CRASHED);
+        assert_eq!(event.source, "daemon");
+        assert_eq!(event.payload, r#"{"service":"hive"}"#);
+    }
+
+    #[test]
+    fn publish_without_subscribers_is_ok() {
+        let bus = EventBus::default();
+        bus.emit(topics::SERVICE_STATE, serde_json::json!({}));
+    }
+
+    #[test]
+    fn log_threshold_fires_once_per_burst() {
+        let watch = LogThresholdWatch::new(3, Duration::from_secs(60));
+
+        assert_eq!(watch.record("svc", "INFO started"), None);
+        assert_eq!(watch.record("svc", "ERROR one"), None);
+        assert_eq!(watch.record("other", "ERROR elsewhere"), None);
+        assert_eq!(watch.record("svc", "ERROR two"), None);
+        assert_eq!(
+            watch.record("svc", "thread 'main' panicked at src/main.rs"),
+            Some(3)
+        );
+        assert_eq!(watch.record("svc", "ERROR four"), None);
+    }
+}
@@ -0,0 +1,477 @@
+//! Zero-downtime daemon self-update.
+//!
+//! `adi daemon update` asks the running daemon to hand itself over to a new
+//! binary:
+//!
+//! 1. The old daemon binds `<socket>.handover` and starts the new binary as
+//!    `adi daemon run` with [`HANDOVER_ENV`] pointing at that path.
+//! 2. The new daemon connects and sends a [`Hello`] with its handover protocol.
+//! 3. If the protocols match, the old daemon replies with a snapshot of every
+//!    managed service and passes the IPC listener plus each service's log
+//!    pipes as file descriptors.
+//! 4. The new daemon adopts the services by PID, writes the PID file and sends
+//!    a ready frame; the old daemon then exits without stopping anything.
+//!
+//! The listening socket never closes, so clients only see queued connections,
+//! and services keep running because their pipes stay open throughout.
+
+use super::protocol::{ServiceConfig, ServiceState};
+use super::services::{spawn_log_reader, ManagedService, ServiceManager};
+use crate::clienv;
+use anyhow::{anyhow, bail, Context, Result};
+use lib_daemon_core::fd_passing::{recv_with_fds, send_with_fds};
+use lib_daemon_core::{kill_process, spawn_background, SpawnConfig};
+use rkyv::{Archive, Deserialize, Serialize};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Set on the successor process; holds the path of the handover socket
+pub const HANDOVER_ENV: &str = "ADI_DAEMON_HANDOVER";
+
+/// Bump whenever [`Hello`], [`Reply`] or [`ServiceSnapshot`] change shape.
+/// Daemons only hand over to binaries speaking the same version.
+pub const HANDOVER_PROTOCOL: u32 = 1;
+
+/// How long each side waits for the other before giving up
+const HANDOVER_TIMEOUT: Duration = Duration::from_secs(30);
+
+const READY: &[u8] = b"ready";
+
+/// First frame, sent by the new daemon
+#[derive(Archive, Deserialize, Serialize, Debug)]
+pub struct Hello {
+    pub protocol: u32,
+    pub version: String,
+}
+
+/// Old daemon's answer to [`Hello`]
+#[derive(Archive, Deserialize, Serialize, Debug)]
+pub enum Reply {
+    /// Sent with the listener fd followed by each service's log pipes
+    Accepted {
+        services: Vec<ServiceSnapshot>,
+    },
+    Rejected {
+        reason: String,
+    },
+}
+
+/// Supervision state of one service at handover time
+#[derive(Archive, Deserialize, Serialize, Debug)]
+pub struct ServiceSnapshot {
+    pub name: String,
+    pub config: ServiceConfig,
+    pub state: ServiceState,
+    pub pid: Option<u32>,
+    pub uptime_secs: Option<u64>,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    /// Number of log pipe fds belonging to this service
+    pub log_fds: u32,
+}
+
+/// Hand the daemon over to `binary`, returning the successor's version once
+/// it is serving. On error nothing has changed and the caller keeps running.
+pub async fn hand_over(
+    services: &ServiceManager,
+    listener_fd: RawFd,
+    socket_path: &Path,
+    binary: &str,
+) -> Result<String> {
+    let (snapshots, mut fds) = snapshot(services).await?;
+    // SAFETY: the listener outlives the daemon's request handling
+    let listener = unsafe { BorrowedFd::borrow_raw(listener_fd) };
+    fds.insert(0, listener.try_clone_to_owned()?);
+
+    let path = handover_path(socket_path);
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    let handover_listener =
+        UnixListener::bind(&path).with_context(|| format!("Failed to bind {}", path.display()))?;
+
+    let log_path = clienv::daemon_log_path();
+    let config = SpawnConfig::new(binary)
+        .args(["daemon", "run"])
+        .stdout(log_path.display().to_string())
+        .stderr(log_path.display().to_string())
+        .env("RUST_LOG", "info")
+        .env(HANDOVER_ENV, path.display().to_string());
+    let pid = spawn_background(&config)?;
+    info!("Started successor daemon {} (PID {})", binary, pid);
+
+    let result = tokio::task::spawn_blocking(move || {
+        exchange(
+            &handover_listener,
+            Reply::Accepted {
+                services: snapshots,
+            },
+            &fds,
+        )
+    })
+    .await?;
+
+    let _ = std::fs::remove_file(&path);
+    if result.is_err() {
+        // A successor that never got the state may still be waiting
+        let _ = kill_process(pid);
+    }
+    result
+}
+
+/// Old daemon's side of the protocol, run on a blocking thread
+fn exchange(listener: &UnixListener, reply: Reply, fds: &[OwnedFd]) -> Result<String> {
+    let stream = accept_with_timeout(listener)?;
+    stream.set_read_timeout(Some(HANDOVER_TIMEOUT))?;
+
+    let (payload, _) = recv_with_fds(&stream)?;
+    let hello = rkyv::from_bytes::<Hello, rkyv::rancor::Error>(&aligned(&payload))
+        .map_err(|e| anyhow!("Invalid handover hello: {}", e))?;
+
+    if hello.protocol != HANDOVER_PROTOCOL {
+        let reason = format!(
+            "daemon v{} speaks handover protocol {}, but v{} speaks {}",
+            env!("CARGO_PKG_VERSION"),
+            HANDOVER_PROTOCOL,
+            hello.version,
+            hello.protocol
+        );
+        send(
+            &stream,
+            &Reply::Rejected {
+                reason: reason.clone(),
+            },
+            &[],
+        )?;
+        bail!("Incompatible daemon binary: {}", reason);
+    }
+
+    let raw: Vec<RawFd> = fds.iter().map(|fd| fd.as_raw_fd()).collect();
+    send(&stream, &reply, &raw)?;
+
+    let (ack, _) = recv_with_fds(&stream).context("New daemon failed to start")?;
+    if ack != READY {
+        bail!("New daemon did not confirm the handover");
+    }
+
+    Ok(hello.version)
+}
+
+/// Collect service state and duplicated log pipes, in matching order.
+async fn snapshot(services: &ServiceManager) -> Result<(Vec<ServiceSnapshot>, Vec<OwnedFd>)> {
+    let map = services.services_ref();
+    let map = map.read().await;
+
+    let mut snapshots = Vec::with_capacity(map.len());
+    let mut fds = Vec::new();
+
+    for (name, service) in map.iter() {
+        if matches!(
+            service.state,
+            ServiceState::Starting | ServiceState::Stopping
+        ) {
+            bail!(
+                "Service '{}' is {}; retry once it settles",
+                name,
+                service.state.as_str()
+            );
+        }
+
+        let running = service.state.is_running();
+        let log_fds = if running {
+            service
+                .log_fds
+                .iter()
+                .map(|fd| fd.try_clone())
+                .collect::<std::io::Result<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+
+        snapshots.push(ServiceSnapshot {
+            name: name.clone(),
+            config: service.config.clone(),
+            state: service.state,
+            pid: service.pid().filter(|_| running),
+            uptime_secs: service.uptime_secs(),
+            restarts: service.restarts,
+            last_error: service.last_error.clone(),
+            log_fds: log_fds.len() as u32,
+        });
+        fds.extend(log_fds);
+    }
+
+    Ok((snapshots, fds))
+}
+
+/// State received by a new daemon started for a handover
+pub struct Incoming {
+    stream: UnixStream,
+    listener: UnixListener,
+    services: Vec<ServiceSnapshot>,
+    log_fds: Vec<OwnedFd>,
+}
+
+impl Incoming {
+    /// Connect to the old daemon named by [`HANDOVER_ENV`], if this process
+    /// was started for a handover.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(path) = std::env::var_os(HANDOVER_ENV) else {
+            return Ok(None);
+        };
+        // Services spawned later must not see it
+        std::env::remove_var(HANDOVER_ENV);
+        Self::receive(Path::new(&path)).map(Some)
+    }
+
+    fn receive(path: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(path)
+            .with_context(|| format!("Failed to connect to {}", path.display()))?;
+        stream.set_read_timeout(Some(HANDOVER_TIMEOUT))?;
+
+        let hello = Hello {
+            protocol: HANDOVER_PROTOCOL,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&hello)
+            .map_err(|e| anyhow!("Failed to encode handover hello: {}", e))?;
+        send_with_fds(&stream, &bytes, &[])?;
+
+        let (payload, mut fds) = recv_with_fds(&stream)?;
+        let reply = rkyv::from_bytes::<Reply, rkyv::rancor::Error>(&aligned(&payload))
+            .map_err(|e| anyhow!("Invalid handover reply: {}", e))?;
+
+        let services = match reply {
+            Reply::Accepted { services } => services,
+            Reply::Rejected { reason } => bail!("Handover rejected: {}", reason),
+        };
+
+        if fds.is_empty() {
+            bail!("Handover did not include the daemon listener");
+        }
+        let listener = UnixListener::from(fds.remove(0));
+
+        let expected: u32 = services.iter().map(|s| s.log_fds).sum();
+        if fds.len() != expected as usize {
+            bail!(
+                "Handover passed {} log pipes, expected {}",
+                fds.len(),
+                expected
+            );
+        }
+
+        Ok(Self {
+            stream,
+            listener,
+            services,
+            log_fds: fds,
+        })
+    }
+
+    /// Register the handed-over services with `manager`, returning the names
+    /// of those still running.
+    pub async fn adopt(&mut self, manager: &ServiceManager) -> Vec<String> {
+        let map = manager.services_ref();
+        let mut map = map.write().await;
+        let mut pipes = std::mem::take(&mut self.log_fds).into_iter();
+        let mut running = Vec::new();
+
+        for snapshot in self.services.drain(..) {
+            let mut service = ManagedService::new(snapshot.config);
+            service.state = snapshot.state;
+            service.adopted_pid = snapshot.pid;
+            service.restarts = snapshot.restarts;
+            service.last_error = snapshot.last_error;
+            service.started_at = snapshot
+                .uptime_secs
+                .and_then(|secs| Instant::now().checked_sub(Duration::from_secs(secs)));
+
+            for fd in pipes.by_ref().take(snapshot.log_fds as usize) {
+                match adopt_pipe(&snapshot.name, fd, manager) {
+                    Ok(dup) => service.log_fds.push(dup),
+                    Err(e) => warn!("Failed to adopt log pipe of '{}': {}", snapshot.name, e),
+                }
+            }
+
+            if service.state.is_running() {
+                info!(
+                    "Adopted service '{}' (PID {:?})",
+                    snapshot.name, service.adopted_pid
+                );
+                running.push(snapshot.name.clone());
+            }
+            map.insert(snapshot.name, service);
+        }
+
+        running
+    }
+
+    /// Tell the old daemon we are taking over, so it can exit, and return
+    /// the inherited IPC listener.
+    pub fn confirm(self) -> Result<tokio::net::UnixListener> {
+        self.listener.set_nonblocking(true)?;
+        let listener = tokio::net::UnixListener::from_std(self.listener)?;
+        send_with_fds(&self.stream, READY, &[])?;
+        Ok(listener)
+    }
+}
+
+/// Start reading a handed-over log pipe, keeping a duplicate for the next handover.
+fn adopt_pipe(name: &str, fd: OwnedFd, manager: &ServiceManager) -> Result<OwnedFd> {
+    let dup = fd.as_fd().try_clone_to_owned()?;
+    let pipe = tokio::net::unix::pipe::Receiver::from_owned_fd(fd)?;
+    spawn_log_reader(
+        name,
+        Box::new(pipe),
+        manager.log_buffer(),
+        manager.events(),
+        manager.log_watch(),
+    );
+    Ok(dup)
+}
+
+fn send(stream: &UnixStream, reply: &Reply, fds: &[RawFd]) -> Result<()> {
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(reply)
+        .map_err(|e| anyhow!("Failed to encode handover reply: {}", e))?;
+    send_with_fds(stream, &bytes, fds)?;
+    Ok(())
+}
+
+fn accept_with_timeout(listener: &UnixListener) -> Result<UnixStream> {
+    listener.set_nonblocking(true)?;
+    let deadline = Instant::now() + HANDOVER_TIMEOUT;
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(stream);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                if Instant::now() >= deadline {
+                    bail!("New daemon did not connect within {:?}", HANDOVER_TIMEOUT);
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// rkyv needs aligned input; received frames are plain byte vectors
+fn aligned(bytes: &[u8]) -> rkyv::util::AlignedVec {
+    let mut buf = rkyv::util::AlignedVec::with_capacity(bytes.len());
+    buf.extend_from_slice(bytes);
+    buf
+}
+
+fn handover_path(socket_path: &Path) -> PathBuf {
+    socket_path.with_extension("handover")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::events::EventBus;
+    use crate::daemon::log_buffer::LogBuffer;
+    use std::sync::Arc;
+
+    fn snapshot_of(name: &str, state: ServiceState, pid: Option<u32>) -> ServiceSnapshot {
+        ServiceSnapshot {
+            name: name.to_string(),
+            config: ServiceConfig::new("sleep").args(["60"]),
+            state,
+            pid,
+            uptime_secs: Some(120),
+            restarts: 2,
+            last_error: None,
+            log_fds: 0,
+        }
+    }
+
+    #[test]
+    fn test_reply_roundtrip() {
+        let reply = Reply::Accepted {
+            services: vec![snapshot_of("indexer", ServiceState::Running, Some(42))],
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&reply).unwrap();
+        let decoded = rkyv::from_bytes::<Reply, rkyv::rancor::Error>(&aligned(&bytes)).unwrap();
+
+        let Reply::Accepted { services } = decoded else {
+            panic!("Expected Accepted");
+        };
+        assert_eq!(services[0].name, "indexer");
+        assert_eq!(services[0].pid, Some(42));
+        assert_eq!(services[0].config.args, vec!["60"]);
+    }
+
+    #[test]
+    fn test_incompatible_protocol_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.handover");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let new_daemon = std::thread::spawn(move || {
+            let stream = UnixStream::connect(&path).unwrap();
+            let hello = Hello {
+                protocol: HANDOVER_PROTOCOL + 1,
+                version: "99.0.0".to_string(),
+            };
+            let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&hello).unwrap();
+            send_with_fds(&stream, &bytes, &[]).unwrap();
+            let (payload, fds) = recv_with_fds(&stream).unwrap();
+            assert!(fds.is_empty());
+            rkyv::from_bytes::<Reply, rkyv::rancor::Error>(&aligned(&payload)).unwrap()
+        });
+
+        let err = exchange(&listener, Reply::Accepted { services: vec![] }, &[]).unwrap_err();
+        assert!(err.to_string().contains("Incompatible"));
+        assert!(matches!(new_daemon.join().unwrap(), Reply::Rejected { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_handover_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.handover");
+        let listener = UnixListener::bind(&path).unwrap();
+        let (ipc, _) = UnixStream::pair().unwrap();
+
+        let old = std::thread::spawn(move || {
+            let reply = Reply::Accepted {
+                services: vec![
+                    snapshot_of("indexer", ServiceState::Running, Some(4242)),
+                    snapshot_of("llm-proxy", ServiceState::Failed, None),
+                ],
+            };
+            exchange(&listener, reply, &[OwnedFd::from(ipc)])
+        });
+
+        let mut incoming = tokio::task::spawn_blocking(move || {
+            Incoming::receive(&dir.path().join("daemon.handover"))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let manager = ServiceManager::new(
+            Arc::new(LogBuffer::default()),
+            Arc::new(EventBus::default()),
+        );
+        let running = incoming.adopt(&manager).await;
+        incoming.confirm().unwrap();
+
+        assert_eq!(old.join().unwrap().unwrap(), env!("CARGO_PKG_VERSION"));
+        assert_eq!(running, vec!["indexer"]);
+
+        let indexer = manager.get("indexer").await.unwrap();
+        assert_eq!(indexer.pid, Some(4242));
+        assert_eq!(indexer.restarts, 2);
+        assert!(indexer.uptime_secs.unwrap() >= 120);
+        assert_eq!(
+            manager.get("llm-proxy").await.unwrap().state,
+            ServiceState::Failed
+        );
+    }
+}
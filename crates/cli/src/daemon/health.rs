@@ -1,10 +1,10 @@
+use super::events::{topics, EventBus, LogThresholdWatch};
 use super::log_buffer::LogBuffer;
 use super::protocol::ServiceState;
-use super::services::{ManagedService, ServiceManager};
+use super::services::{emit_state, spawn_log_readers, ManagedService, ServiceManager};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 
@@ -13,6 +13,8 @@ const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 pub struct HealthManager {
     services: Arc<RwLock<HashMap<String, ManagedService>>>,
     log_buffer: Arc<LogBuffer>,
+    events: Arc<EventBus>,
+    log_watch: Arc<LogThresholdWatch>,
     check_interval: Duration,
 }
 
@@ -21,6 +23,8 @@ impl HealthManager {
         Self {
             services: service_manager.services_ref(),
             log_buffer: Arc::clone(service_manager.log_buffer()),
+            events: Arc::clone(service_manager.events()),
+            log_watch: Arc::clone(service_manager.log_watch()),
             check_interval: DEFAULT_CHECK_INTERVAL,
         }
     }
@@ -91,7 +95,7 @@ impl HealthManager {
                 } else {
                     warn!("Service '{}' has no PID, marking as failed", name);
                 }
-                self.handle_service_death(&name, restart_on_failure, max_restarts)
+                self.handle_service_death(&name, pid, restart_on_failure, max_restarts)
                     .await;
             } else {
                 debug!("Service '{}' (PID {:?}) is healthy", name, pid);
@@ -99,11 +103,28 @@ impl HealthManager {
         }
     }
 
-    async fn handle_service_death(&self, name: &str, restart_on_failure: bool, max_restarts: u32) {
+    async fn handle_service_death(
+        &self,
+        name: &str,
+        pid: Option<u32>,
+        restart_on_failure: bool,
+        max_restarts: u32,
+    ) {
         let mut services = self.services.write().await;
 
         if let Some(service) = services.get_mut(name) {
-            if restart_on_failure && service.restarts < max_restarts {
+            let will_restart = restart_on_failure && service.restarts < max_restarts;
+            self.events.emit(
+                topics::SERVICE_CRASHED,
+                serde_json::json!({
+                    "service": name,
+                    "pid": pid,
+                    "restarts": service.restarts,
+                    "will_restart": will_restart,
+                }),
+            );
+
+            if will_restart {
                 info!(
                     "Restarting service '{}' (attempt {}/{})",
                     name,
@@ -113,7 +134,7 @@ impl HealthManager {
 
                 service.state = ServiceState::Starting;
                 service.restarts += 1;
-                service.process = None;
+                service.clear_process();
                 service.started_at = None;
 
                 let config = service.config.clone();
@@ -126,7 +147,14 @@ impl HealthManager {
             } else {
                 service.state = ServiceState::Failed;
                 service.last_error = Some("Process died and max restarts exceeded".to_string());
-                service.process = None;
+                service.clear_process();
+                emit_state(
+                    &self.events,
+                    name,
+                    ServiceState::Failed,
+                    None,
+                    service.last_error.as_deref(),
+                );
 
                 error!(
                     "Service '{}' failed after {} restarts",
@@ -160,27 +188,15 @@ impl HealthManager {
 
         let mut child = cmd.spawn()?;
 
-        // Capture stdout/stderr into log buffer
-        if let Some(stdout) = child.stdout.take() {
-            let buf = Arc::clone(&self.log_buffer);
-            let svc = name.to_string();
-            tokio::spawn(async move {
-                let mut lines = BufReader::new(stdout).lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    buf.push(&svc, line);
-                }
-            });
-        }
-        if let Some(stderr) = child.stderr.take() {
-            let buf = Arc::clone(&self.log_buffer);
-            let svc = name.to_string();
-            tokio::spawn(async move {
-                let mut lines = BufReader::new(stderr).lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    buf.push(&svc, line);
-                }
-            });
-        }
+        #[cfg(unix)]
+        let log_fds = super::services::dup_log_fds(&child);
+        spawn_log_readers(
+            name,
+            &mut child,
+            &self.log_buffer,
+            &self.events,
+            &self.log_watch,
+        );
 
         let mut services = self.services.write().await;
         if let Some(service) = services.get_mut(name) {
@@ -188,9 +204,14 @@ impl HealthManager {
             info!("Service '{}' restarted with PID {:?}", name, pid);
 
             service.process = Some(child);
+            #[cfg(unix)]
+            {
+                service.log_fds = log_fds;
+            }
             service.state = ServiceState::Running;
             service.started_at = Some(std::time::Instant::now());
             service.last_error = None;
+            emit_state(&self.events, name, ServiceState::Running, pid, None);
         }
 
         Ok(())
@@ -201,7 +222,8 @@ impl HealthManager {
         if let Some(service) = services.get_mut(name) {
             service.state = ServiceState::Failed;
             service.last_error = Some(error.to_string());
-            service.process = None;
+            service.clear_process();
+            emit_state(&self.events, name, ServiceState::Failed, None, Some(error));
         }
     }
 }
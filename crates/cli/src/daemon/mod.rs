@@ -1,16 +1,24 @@
 pub mod client;
+pub mod events;
 pub mod executor;
+#[cfg(unix)]
+pub mod handover;
 pub mod health;
 pub mod log_buffer;
+pub mod notify;
 pub mod protocol;
+pub mod scheduler;
 pub mod server;
 pub mod services;
 pub mod setup;
 
 pub use client::DaemonClient;
+pub use events::EventBus;
 pub use executor::CommandExecutor;
 pub use health::HealthManager;
 pub use log_buffer::LogBuffer;
+pub use notify::{Notifier, NotifyConfig};
 pub use protocol::{Request, Response, ServiceConfig, ServiceInfo, ServiceState};
+pub use scheduler::Scheduler;
 pub use server::DaemonServer;
 pub use services::ServiceManager;
@@ -0,0 +1,542 @@
+//! Notification routing for daemon bus events.
+//!
+//! Routes from the `[notify]` section of the user config map event topics to
+//! named sinks (webhook, Slack, desktop, email). Deliveries are deduplicated
+//! and rate limited per sink so a crash loop doesn't flood anyone's inbox.
+
+use super::events::EventBus;
+use super::protocol::DaemonEvent;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
+
+/// Topic used by `adi notify test`
+pub const TEST_TOPIC: &str = "notify.test";
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sinks: BTreeMap<String, SinkConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<RouteConfig>,
+    /// Identical notifications to the same sink within this window are dropped
+    #[serde(default = "default_dedupe_secs")]
+    pub dedupe_secs: u64,
+    /// Per-sink cap on deliveries per minute
+    #[serde(default = "default_max_per_minute")]
+    pub max_per_minute: u32,
+}
+
+fn default_dedupe_secs() -> u64 {
+    300
+}
+
+fn default_max_per_minute() -> u32 {
+    10
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            sinks: BTreeMap::new(),
+            routes: Vec::new(),
+            dedupe_secs: default_dedupe_secs(),
+            max_per_minute: default_max_per_minute(),
+        }
+    }
+}
+
+impl NotifyConfig {
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty() && self.routes.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkConfig {
+    /// POST the raw event as JSON
+    Webhook {
+        url: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+    /// POST a Slack-compatible `{"text": ...}` payload (also works for Mattermost, Discord `/slack`)
+    Slack {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        channel: Option<String>,
+    },
+    /// Native notification via `osascript` (macOS) or `notify-send` (Linux)
+    Desktop,
+    Email(EmailSinkConfig),
+}
+
+impl SinkConfig {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SinkConfig::Webhook { .. } => "webhook",
+            SinkConfig::Slack { .. } => "slack",
+            SinkConfig::Desktop => "desktop",
+            SinkConfig::Email(_) => "email",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailSinkConfig {
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Disable only for local relays; port 465 uses implicit TLS, others STARTTLS
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_tls() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteConfig {
+    /// Topic patterns (`service.crashed`, `cert.*`, `*`)
+    pub topics: Vec<String>,
+    /// Sink names from `[notify.sinks]`
+    pub sinks: Vec<String>,
+    /// Top-level payload fields that must equal these values (e.g. `service = "hive"`)
+    #[serde(default, rename = "match", skip_serializing_if = "BTreeMap::is_empty")]
+    pub match_fields: BTreeMap<String, String>,
+}
+
+impl RouteConfig {
+    pub fn matches(&self, event: &DaemonEvent) -> bool {
+        if !event.matches_any(&self.topics) {
+            return false;
+        }
+        if self.match_fields.is_empty() {
+            return true;
+        }
+
+        let payload: serde_json::Value =
+            serde_json::from_str(&event.payload).unwrap_or(serde_json::Value::Null);
+        self.match_fields
+            .iter()
+            .all(|(key, expected)| match payload.get(key) {
+                Some(serde_json::Value::String(s)) => s == expected,
+                Some(other) => other.to_string() == *expected,
+                None => false,
+            })
+    }
+}
+
+/// Human-readable rendering of an event
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+}
+
+impl Notification {
+    pub fn from_event(event: &DaemonEvent) -> Self {
+        let title = format!("ADI: {}", event.topic);
+
+        let body = match serde_json::from_str::<serde_json::Value>(&event.payload) {
+            Ok(serde_json::Value::Object(fields)) => fields
+                .iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| match v {
+                    serde_json::Value::String(s) => format!("{}: {}", k, s),
+                    other => format!("{}: {}", k, other),
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => event.payload.clone(),
+        };
+
+        Self {
+            title,
+            body: format!("{}\n(from {})", body, event.source),
+        }
+    }
+}
+
+/// Per-sink deduplication and rate limiting
+struct Throttle {
+    dedupe: Duration,
+    max_per_window: usize,
+    recent: HashMap<(String, u64), Instant>,
+    sent: HashMap<String, VecDeque<Instant>>,
+}
+
+impl Throttle {
+    fn new(dedupe: Duration, max_per_window: u32) -> Self {
+        Self {
+            dedupe,
+            max_per_window: max_per_window.max(1) as usize,
+            recent: HashMap::new(),
+            sent: HashMap::new(),
+        }
+    }
+
+    fn allow(&mut self, sink: &str, event: &DaemonEvent, now: Instant) -> bool {
+        let dedupe = self.dedupe;
+        self.recent
+            .retain(|_, seen| now.duration_since(*seen) < dedupe);
+
+        let key = (sink.to_string(), event_fingerprint(event));
+        if self.recent.contains_key(&key) {
+            return false;
+        }
+
+        let sent = self.sent.entry(sink.to_string()).or_default();
+        while sent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW)
+        {
+            sent.pop_front();
+        }
+        if sent.len() >= self.max_per_window {
+            return false;
+        }
+
+        sent.push_back(now);
+        self.recent.insert(key, now);
+        true
+    }
+}
+
+fn event_fingerprint(event: &DaemonEvent) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    event.topic.hash(&mut hasher);
+    event.source.hash(&mut hasher);
+    event.payload.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub struct Notifier {
+    config: NotifyConfig,
+    http: reqwest::Client,
+    throttle: Mutex<Throttle>,
+}
+
+impl Notifier {
+    pub fn new(config: NotifyConfig) -> Self {
+        let throttle = Throttle::new(
+            Duration::from_secs(config.dedupe_secs),
+            config.max_per_minute,
+        );
+        Self {
+            config,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            throttle: Mutex::new(throttle),
+        }
+    }
+
+    pub fn config(&self) -> &NotifyConfig {
+        &self.config
+    }
+
+    /// Sink names routed for this event, in config order without duplicates
+    pub fn targets(&self, event: &DaemonEvent) -> Vec<String> {
+        let mut targets: Vec<String> = Vec::new();
+        for route in self.config.routes.iter().filter(|r| r.matches(event)) {
+            for sink in &route.sinks {
+                if !targets.contains(sink) {
+                    targets.push(sink.clone());
+                }
+            }
+        }
+        targets
+    }
+
+    /// Follow the bus and deliver routed events until the bus closes.
+    pub async fn run(self: Arc<Self>, events: Arc<EventBus>) {
+        for route in &self.config.routes {
+            for sink in route
+                .sinks
+                .iter()
+                .filter(|s| !self.config.sinks.contains_key(*s))
+            {
+                warn!("Notification route references unknown sink '{}'", sink);
+            }
+        }
+        info!(
+            "Notifier started ({} sinks, {} routes)",
+            self.config.sinks.len(),
+            self.config.routes.len()
+        );
+
+        let mut rx = events.subscribe();
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Notifier lagged, {} events dropped", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+
+            for sink in self.targets(&event) {
+                let allowed = {
+                    let mut throttle = self.throttle.lock().expect("Throttle lock poisoned");
+                    throttle.allow(&sink, &event, Instant::now())
+                };
+                if !allowed {
+                    debug!("Throttled {} notification to '{}'", event.topic, sink);
+                    continue;
+                }
+
+                let notifier = Arc::clone(&self);
+                let event = event.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = notifier.deliver(&sink, &event).await {
+                        warn!("Failed to notify '{}' about {}: {}", sink, event.topic, e);
+                    }
+                });
+            }
+        }
+    }
+
+    /// Send one event to a named sink, bypassing routing and throttling.
+    pub async fn deliver(&self, sink_name: &str, event: &DaemonEvent) -> Result<()> {
+        let sink = self
+            .config
+            .sinks
+            .get(sink_name)
+            .ok_or_else(|| anyhow!("Unknown notification sink: {}", sink_name))?;
+        let notification = Notification::from_event(event);
+
+        match sink {
+            SinkConfig::Webhook { url, headers } => {
+                let payload: serde_json::Value = serde_json::from_str(&event.payload)
+                    .unwrap_or_else(|_| serde_json::Value::String(event.payload.clone()));
+                let mut request = self.http.post(url).json(&serde_json::json!({
+                    "topic": event.topic,
+                    "source": event.source,
+                    "timestamp_ms": event.timestamp_ms,
+                    "payload": payload,
+                }));
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+                request.send().await?.error_for_status()?;
+            }
+            SinkConfig::Slack { url, channel } => {
+                let mut body = serde_json::json!({
+                    "text": format!("*{}*\n{}", notification.title, notification.body),
+                });
+                if let Some(channel) = channel {
+                    body["channel"] = serde_json::Value::String(channel.clone());
+                }
+                self.http
+                    .post(url)
+                    .json(&body)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            SinkConfig::Desktop => send_desktop(&notification).await?,
+            SinkConfig::Email(email) => send_email(email, &notification).await?,
+        }
+
+        debug!("Delivered {} to '{}'", event.topic, sink_name);
+        Ok(())
+    }
+
+    /// Deliver a synthetic `notify.test` event to verify a sink end to end.
+    pub async fn test(&self, sink_name: &str) -> Result<()> {
+        let event = DaemonEvent::new(
+            TEST_TOPIC,
+            "adi",
+            serde_json::json!({ "message": "Test notification from adi", "sink": sink_name })
+                .to_string(),
+        );
+        self.deliver(sink_name, &event).await
+    }
+}
+
+async fn send_desktop(notification: &Notification) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let output = {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"",
+            escape(&notification.body),
+            escape(&notification.title)
+        );
+        tokio::process::Command::new("osascript")
+            .args(["-e", &script])
+            .output()
+            .await
+            .context("Failed to run osascript")?
+    };
+
+    #[cfg(not(target_os = "macos"))]
+    let output = tokio::process::Command::new("notify-send")
+        .args(["--app-name=adi", &notification.title, &notification.body])
+        .output()
+        .await
+        .context("Failed to run notify-send (is libnotify installed?)")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Desktop notification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+async fn send_email(config: &EmailSinkConfig, notification: &Notification) -> Result<()> {
+    use lettre::message::header::ContentType;
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+    if config.to.is_empty() {
+        anyhow::bail!("Email sink has no recipients");
+    }
+
+    let mut builder = Message::builder()
+        .from(config.from.parse().context("Invalid 'from' address")?)
+        .subject(&notification.title)
+        .header(ContentType::TEXT_PLAIN);
+    for to in &config.to {
+        builder = builder.to(to
+            .parse()
+            .with_context(|| format!("Invalid recipient: {}", to))?);
+    }
+    let message = builder.body(notification.body.clone())?;
+
+    // TLS modes: disabled (plain), port 465 (implicit TLS), other (STARTTLS)
+    let builder = if !config.tls {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+    } else if config.smtp_port == 465 {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)?
+    };
+    let mut transport = builder.port(config.smtp_port);
+
+    if let (Some(user), Some(pass)) = (&config.username, &config.password) {
+        transport = transport.credentials(Credentials::new(user.clone(), pass.clone()));
+    }
+
+    transport.build().send(message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(topic: &str, payload: serde_json::Value) -> DaemonEvent {
+        DaemonEvent::new(topic, "daemon", payload.to_string())
+    }
+
+    fn config() -> NotifyConfig {
+        toml::from_str(
+            r#"
+            max_per_minute = 2
+
+            [sinks.ops]
+            type = "slack"
+            url = "https://hooks.example.com/ops"
+
+            [sinks.desk]
+            type = "desktop"
+
+            [[routes]]
+            topics = ["service.crashed"]
+            sinks = ["ops", "desk"]
+            match = { service = "hive" }
+
+            [[routes]]
+            topics = ["cert.*"]
+            sinks = ["ops"]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_sinks_and_routes() {
+        let config = config();
+        assert_eq!(config.sinks["ops"].kind(), "slack");
+        assert_eq!(config.sinks["desk"].kind(), "desktop");
+        assert_eq!(config.routes.len(), 2);
+        assert_eq!(config.dedupe_secs, 300);
+        assert_eq!(config.max_per_minute, 2);
+    }
+
+    #[test]
+    fn routes_by_topic_and_payload_fields() {
+        let notifier = Notifier::new(config());
+
+        let crash = event("service.crashed", serde_json::json!({ "service": "hive" }));
+        assert_eq!(notifier.targets(&crash), vec!["ops", "desk"]);
+
+        let other = event(
+            "service.crashed",
+            serde_json::json!({ "service": "indexer" }),
+        );
+        assert!(notifier.targets(&other).is_empty());
+
+        let cert = event("cert.expiry", serde_json::json!({ "renewed": false }));
+        assert_eq!(notifier.targets(&cert), vec!["ops"]);
+
+        let state = event("service.state", serde_json::json!({}));
+        assert!(notifier.targets(&state).is_empty());
+    }
+
+    #[test]
+    fn throttle_dedupes_and_rate_limits() {
+        let mut throttle = Throttle::new(Duration::from_secs(300), 2);
+        let now = Instant::now();
+        let a = event("service.crashed", serde_json::json!({ "service": "a" }));
+        let b = event("service.crashed", serde_json::json!({ "service": "b" }));
+        let c = event("service.crashed", serde_json::json!({ "service": "c" }));
+
+        assert!(throttle.allow("ops", &a, now));
+        assert!(!throttle.allow("ops", &a, now), "duplicate is dropped");
+        assert!(throttle.allow("desk", &a, now), "dedupe is per sink");
+        assert!(throttle.allow("ops", &b, now));
+        assert!(!throttle.allow("ops", &c, now), "rate limit reached");
+        assert!(throttle.allow("ops", &c, now + RATE_WINDOW));
+    }
+
+    #[test]
+    fn notification_renders_payload_fields() {
+        let n = Notification::from_event(&event(
+            "service.crashed",
+            serde_json::json!({ "service": "hive", "pid": 42, "error": null }),
+        ));
+        assert_eq!(n.title, "ADI: service.crashed");
+        let lines: Vec<&str> = n.body.lines().collect();
+        assert!(lines.contains(&"service: hive"));
+        assert!(lines.contains(&"pid: 42"));
+        assert!(!n.body.contains("error"), "null fields are skipped");
+        assert_eq!(lines.last(), Some(&"(from daemon)"));
+    }
+}
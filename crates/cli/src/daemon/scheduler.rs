@@ -0,0 +1,453 @@
+//! Recurring plugin maintenance jobs.
+//!
+//! Plugins register jobs over IPC (`Request::ScheduleJob`); each job runs
+//! `adi <args>` every `interval_secs` plus a random delay of up to
+//! `jitter_secs`. Jobs and their run state are persisted so schedules survive
+//! daemon restarts; runs missed while the daemon was down happen once,
+//! jittered, after startup.
+
+use super::events::{topics, EventBus};
+use super::protocol::{JobInfo, JobSpec};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+use std::process::Output;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+
+/// File in the daemon data directory holding registered jobs
+pub const SCHEDULE_FILE: &str = "schedule.json";
+
+/// Shortest accepted interval
+const MIN_INTERVAL_SECS: u64 = 60;
+
+/// Upper bound on one sleep, so clock jumps are picked up eventually
+const MAX_IDLE: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ScheduledJob {
+    id: String,
+    owner: String,
+    args: Vec<String>,
+    interval_secs: u64,
+    #[serde(default)]
+    jitter_secs: u64,
+    next_run_ms: u64,
+    #[serde(default)]
+    last_run_ms: Option<u64>,
+    #[serde(default)]
+    last_exit_code: Option<i32>,
+    #[serde(skip)]
+    running: bool,
+}
+
+impl ScheduledJob {
+    fn spec(&self) -> JobSpec {
+        JobSpec {
+            id: self.id.clone(),
+            owner: self.owner.clone(),
+            args: self.args.clone(),
+            interval_secs: self.interval_secs,
+            jitter_secs: self.jitter_secs,
+        }
+    }
+
+    fn to_info(&self) -> JobInfo {
+        JobInfo {
+            spec: self.spec(),
+            next_run_ms: self.next_run_ms,
+            last_run_ms: self.last_run_ms,
+            last_exit_code: self.last_exit_code,
+            running: self.running,
+        }
+    }
+
+    fn reschedule(&mut self, now_ms: u64) {
+        self.next_run_ms = now_ms
+            .saturating_add(self.interval_secs.saturating_mul(1000))
+            .saturating_add(jitter_ms(self.jitter_secs));
+    }
+}
+
+pub struct Scheduler {
+    path: PathBuf,
+    /// `adi` binary jobs run with
+    program: PathBuf,
+    jobs: Mutex<BTreeMap<String, ScheduledJob>>,
+    events: Arc<EventBus>,
+    wake: Notify,
+}
+
+impl Scheduler {
+    /// Load persisted jobs from `path`. Jobs that came due while the daemon
+    /// was down are spread over their jitter window instead of all running
+    /// at startup.
+    pub fn load(path: PathBuf, events: Arc<EventBus>) -> Self {
+        let mut jobs: BTreeMap<String, ScheduledJob> = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring invalid schedule file {}: {}", path.display(), e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+
+        let now = now_ms();
+        for job in jobs.values_mut().filter(|j| j.next_run_ms <= now) {
+            job.next_run_ms = now + jitter_ms(job.jitter_secs);
+        }
+        if !jobs.is_empty() {
+            info!("Loaded {} scheduled jobs", jobs.len());
+        }
+
+        let program = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("adi"));
+        Self {
+            path,
+            program,
+            jobs: Mutex::new(jobs),
+            events,
+            wake: Notify::new(),
+        }
+    }
+
+    /// Add or replace a job. Re-registering an unchanged job keeps its
+    /// schedule, so plugins can register on every start.
+    pub fn register(&self, spec: JobSpec) -> Result<()> {
+        anyhow::ensure!(!spec.id.is_empty(), "Job ID must not be empty");
+        anyhow::ensure!(!spec.args.is_empty(), "Job '{}' has no command", spec.id);
+        anyhow::ensure!(
+            spec.interval_secs >= MIN_INTERVAL_SECS,
+            "Job '{}' interval must be at least {}s",
+            spec.id,
+            MIN_INTERVAL_SECS
+        );
+
+        {
+            let mut jobs = self.jobs.lock().expect("scheduler lock poisoned");
+            if jobs.get(&spec.id).is_some_and(|j| j.spec() == spec) {
+                return Ok(());
+            }
+
+            let previous = jobs.remove(&spec.id);
+            let mut job = ScheduledJob {
+                id: spec.id.clone(),
+                owner: spec.owner,
+                args: spec.args,
+                interval_secs: spec.interval_secs,
+                jitter_secs: spec.jitter_secs,
+                next_run_ms: 0,
+                last_run_ms: previous.as_ref().and_then(|p| p.last_run_ms),
+                last_exit_code: previous.as_ref().and_then(|p| p.last_exit_code),
+                running: previous.is_some_and(|p| p.running),
+            };
+            job.reschedule(now_ms());
+            info!("Scheduled job {} every {}s", job.id, job.interval_secs);
+            jobs.insert(spec.id, job);
+        }
+
+        self.persist();
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    pub fn unregister(&self, id: &str) -> Result<()> {
+        let removed = self
+            .jobs
+            .lock()
+            .expect("scheduler lock poisoned")
+            .remove(id);
+        if removed.is_none() {
+            anyhow::bail!("Unknown job: {}", id);
+        }
+        info!("Unscheduled job {}", id);
+        self.persist();
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.jobs
+            .lock()
+            .expect("scheduler lock poisoned")
+            .values()
+            .map(ScheduledJob::to_info)
+            .collect()
+    }
+
+    /// Run a job immediately, outside its schedule. The next scheduled run
+    /// moves to one interval after this one.
+    pub async fn run_now(&self, id: &str) -> Result<Output> {
+        let spec = self.begin(id)?;
+        self.execute(spec).await.map_err(Into::into)
+    }
+
+    /// Run due jobs until the daemon exits.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            let now = now_ms();
+            let (due, next_ms) = {
+                let jobs = self.jobs.lock().expect("scheduler lock poisoned");
+                let due: Vec<String> = jobs
+                    .values()
+                    .filter(|j| !j.running && j.next_run_ms <= now)
+                    .map(|j| j.id.clone())
+                    .collect();
+                let next_ms = jobs
+                    .values()
+                    .filter(|j| !j.running && j.next_run_ms > now)
+                    .map(|j| j.next_run_ms)
+                    .min();
+                (due, next_ms)
+            };
+
+            for id in due {
+                let Ok(spec) = self.begin(&id) else { continue };
+                let scheduler = Arc::clone(&self);
+                tokio::spawn(async move {
+                    if let Err(e) = scheduler.execute(spec).await {
+                        debug!("Scheduled job {} failed to start: {}", id, e);
+                    }
+                });
+            }
+
+            let idle = next_ms
+                .map(|next| Duration::from_millis(next.saturating_sub(now_ms())))
+                .unwrap_or(MAX_IDLE)
+                .min(MAX_IDLE);
+            tokio::select! {
+                _ = tokio::time::sleep(idle) => {}
+                _ = self.wake.notified() => {}
+            }
+        }
+    }
+
+    /// Mark a job as running and return what to execute.
+    fn begin(&self, id: &str) -> Result<JobSpec> {
+        let mut jobs = self.jobs.lock().expect("scheduler lock poisoned");
+        let job = jobs
+            .get_mut(id)
+            .ok_or_else(|| anyhow!("Unknown job: {}", id))?;
+        anyhow::ensure!(!job.running, "Job '{}' is already running", id);
+        job.running = true;
+        Ok(job.spec())
+    }
+
+    async fn execute(&self, spec: JobSpec) -> std::io::Result<Output> {
+        info!(
+            "Running scheduled job {}: adi {}",
+            spec.id,
+            spec.args.join(" ")
+        );
+        let started = Instant::now();
+        let result = Command::new(&self.program)
+            .args(&spec.args)
+            .kill_on_drop(true)
+            .output()
+            .await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let exit_code = match &result {
+            Ok(output) => Some(output.status.code().unwrap_or(-1)),
+            Err(_) => None,
+        };
+        self.finish(&spec.id, exit_code);
+
+        match &result {
+            Ok(_) if exit_code == Some(0) => {
+                debug!("Scheduled job {} finished in {}ms", spec.id, duration_ms);
+                self.events.emit(
+                    topics::SCHEDULE_COMPLETED,
+                    json!({
+                        "job": spec.id,
+                        "owner": spec.owner,
+                        "exit_code": 0,
+                        "duration_ms": duration_ms,
+                    }),
+                );
+            }
+            Ok(output) => {
+                warn!("Scheduled job {} exited with {:?}", spec.id, exit_code);
+                self.events.emit(
+                    topics::SCHEDULE_FAILED,
+                    json!({
+                        "job": spec.id,
+                        "owner": spec.owner,
+                        "exit_code": exit_code,
+                        "error": last_line(&output.stderr),
+                        "duration_ms": duration_ms,
+                    }),
+                );
+            }
+            Err(e) => {
+                warn!("Scheduled job {} could not start: {}", spec.id, e);
+                self.events.emit(
+                    topics::SCHEDULE_FAILED,
+                    json!({
+                        "job": spec.id,
+                        "owner": spec.owner,
+                        "error": e.to_string(),
+                        "duration_ms": duration_ms,
+                    }),
+                );
+            }
+        }
+
+        result
+    }
+
+    fn finish(&self, id: &str, exit_code: Option<i32>) {
+        {
+            let mut jobs = self.jobs.lock().expect("scheduler lock poisoned");
+            // Unscheduled while running
+            let Some(job) = jobs.get_mut(id) else { return };
+            let now = now_ms();
+            job.running = false;
+            job.last_run_ms = Some(now);
+            job.last_exit_code = exit_code;
+            job.reschedule(now);
+        }
+        self.persist();
+        self.wake.notify_one();
+    }
+
+    fn persist(&self) {
+        let content = {
+            let jobs = self.jobs.lock().expect("scheduler lock poisoned");
+            serde_json::to_string_pretty(&*jobs)
+        };
+        let result = content
+            .context("Failed to serialize schedule")
+            .and_then(|content| {
+                if let Some(parent) = self.path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&self.path, content)
+                    .with_context(|| format!("Failed to write {}", self.path.display()))
+            });
+        if let Err(e) = result {
+            warn!("Failed to persist schedule: {:#}", e);
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Random delay in `0..=max_secs` seconds, in milliseconds
+fn jitter_ms(max_secs: u64) -> u64 {
+    if max_secs == 0 {
+        return 0;
+    }
+    // RandomState is randomly keyed per instance; good enough to spread runs
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(now_ms());
+    hasher.finish() % (max_secs.saturating_mul(1000) + 1)
+}
+
+fn last_line(bytes: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .map(|l| l.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(dir: &tempfile::TempDir) -> Scheduler {
+        Scheduler::load(
+            dir.path().join(SCHEDULE_FILE),
+            Arc::new(EventBus::default()),
+        )
+    }
+
+    #[test]
+    fn test_jitter_bounds() {
+        assert_eq!(jitter_ms(0), 0);
+        for _ in 0..100 {
+            assert!(jitter_ms(5) <= 5000);
+        }
+    }
+
+    #[test]
+    fn test_register_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = scheduler(&dir);
+
+        assert!(scheduler
+            .register(JobSpec::new("a", "adi.tools", Vec::<String>::new()))
+            .is_err());
+        assert!(scheduler
+            .register(JobSpec::new("a", "adi.tools", ["tools"]).every(10))
+            .is_err());
+        assert!(scheduler.list().is_empty());
+    }
+
+    #[test]
+    fn test_reregister_keeps_schedule() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = scheduler(&dir);
+        let spec = JobSpec::new("adi.tools.reindex", "adi.tools", ["tools", "reindex"])
+            .every(3600)
+            .jitter(600);
+
+        scheduler.register(spec.clone()).unwrap();
+        let first = scheduler.list()[0].next_run_ms;
+        scheduler.register(spec.clone()).unwrap();
+        assert_eq!(scheduler.list()[0].next_run_ms, first);
+
+        scheduler.register(spec.every(7200)).unwrap();
+        assert_eq!(scheduler.list()[0].spec.interval_secs, 7200);
+    }
+
+    #[test]
+    fn test_jobs_persist_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        scheduler(&dir)
+            .register(JobSpec::new(
+                "adi.linter.baseline",
+                "adi.linter",
+                ["lint", "baseline"],
+            ))
+            .unwrap();
+
+        let reloaded = scheduler(&dir);
+        let jobs = reloaded.list();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].spec.args, vec!["lint", "baseline"]);
+        assert!(!jobs[0].running);
+
+        reloaded.unregister("adi.linter.baseline").unwrap();
+        assert!(scheduler(&dir).list().is_empty());
+        assert!(reloaded.unregister("adi.linter.baseline").is_err());
+    }
+
+    #[test]
+    fn test_missed_runs_are_rescheduled_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SCHEDULE_FILE);
+        std::fs::write(
+            &path,
+            r#"{"adi.certs.renew": {"id": "adi.certs.renew", "owner": "adi.certs",
+                "args": ["certs", "renew"], "interval_secs": 86400, "jitter_secs": 60,
+                "next_run_ms": 1000}}"#,
+        )
+        .unwrap();
+
+        let before = now_ms();
+        let job = &scheduler(&dir).list()[0];
+        assert!(job.next_run_ms >= before);
+        assert!(job.next_run_ms <= now_ms() + 60_000);
+    }
+}
@@ -1,14 +1,20 @@
+use super::events::EventBus;
 use super::executor::CommandExecutor;
 use super::health::HealthManager;
 use super::log_buffer::LogBuffer;
+use super::notify::Notifier;
 use super::protocol::{ArchivedRequest, MessageFrame, Response};
+use super::scheduler::{Scheduler, SCHEDULE_FILE};
 use super::services::ServiceManager;
 use crate::clienv;
 use anyhow::Result;
+use lib_daemon_client::client::{deserialize_event, deserialize_job_spec};
 use lib_daemon_core::{PidFile, ShutdownCoordinator, ShutdownHandle};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast::error::RecvError;
 
 use tracing::{debug, error, info, trace, warn};
 
@@ -33,16 +39,24 @@ impl Default for DaemonConfig {
 pub struct DaemonServer {
     config: DaemonConfig,
     services: Arc<ServiceManager>,
+    events: Arc<EventBus>,
     executor: Arc<CommandExecutor>,
+    scheduler: Arc<Scheduler>,
     started_at: Instant,
     version: String,
     shutdown_handle: Option<ShutdownHandle>,
+    /// Raw fd of the IPC listener, passed on by `adi daemon update`
+    #[cfg(unix)]
+    listener_fd: Option<std::os::fd::RawFd>,
+    /// Set once a successor daemon took over; services are left running on exit
+    handed_over: AtomicBool,
 }
 
 impl DaemonServer {
     pub async fn new(mut config: DaemonConfig) -> Self {
         let log_buffer = Arc::new(LogBuffer::default());
-        let mut manager = ServiceManager::new(Arc::clone(&log_buffer));
+        let events = Arc::new(EventBus::default());
+        let mut manager = ServiceManager::new(Arc::clone(&log_buffer), Arc::clone(&events));
         if let Err(e) = manager.discover_plugins().await {
             warn!("Failed to discover plugin daemon services: {}", e);
         }
@@ -56,53 +70,73 @@ impl DaemonServer {
             }
         }
 
+        let scheduler = Scheduler::load(clienv::data_dir().join(SCHEDULE_FILE), Arc::clone(&events));
+
         Self {
             config,
             services: Arc::new(manager),
+            events,
             executor: Arc::new(CommandExecutor::new()),
+            scheduler: Arc::new(scheduler),
             started_at: Instant::now(),
             version: env!("CARGO_PKG_VERSION").to_string(),
             shutdown_handle: None,
+            #[cfg(unix)]
+            listener_fd: None,
+            handed_over: AtomicBool::new(false),
         }
     }
 
     pub async fn run(mut self) -> Result<()> {
         info!("ADI daemon starting...");
 
-        let pid_file = PidFile::new(&self.config.pid_path);
-        if let Some(pid) = pid_file.is_running()? {
-            anyhow::bail!("Daemon already running with PID {}", pid);
+        #[cfg(unix)]
+        let incoming = super::handover::Incoming::from_env()?;
+        #[cfg(unix)]
+        let taking_over = incoming.is_some();
+        #[cfg(not(unix))]
+        let taking_over = false;
+
+        // The daemon we take over from still holds the PID file
+        if !taking_over {
+            let pid_file = PidFile::new(&self.config.pid_path);
+            if let Some(pid) = pid_file.is_running()? {
+                anyhow::bail!("Daemon already running with PID {}", pid);
+            }
         }
-        drop(pid_file);
 
         let mut pid_file = PidFile::new(&self.config.pid_path);
         pid_file.write()?;
         info!("PID file written: {}", self.config.pid_path.display());
 
-        if self.config.socket_path.exists() {
-            std::fs::remove_file(&self.config.socket_path)?;
-        }
-
-        if let Some(parent) = self.config.socket_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
+        #[cfg(unix)]
+        let (listener, adopted) = if let Some(mut incoming) = incoming {
+            let adopted = incoming.adopt(&self.services).await;
+            let listener = incoming.confirm()?;
+            info!(
+                "Took over IPC socket and {} running service(s) from previous daemon",
+                adopted.len()
+            );
+            (listener, adopted)
+        } else {
+            (bind_socket(&self.config.socket_path)?, Vec::new())
+        };
 
         #[cfg(unix)]
-        let listener = tokio::net::UnixListener::bind(&self.config.socket_path)?;
+        {
+            use std::os::fd::AsRawFd;
+            self.listener_fd = Some(listener.as_raw_fd());
+        }
 
         info!(
             "IPC server listening on: {}",
             self.config.socket_path.display()
         );
 
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let perms = std::fs::Permissions::from_mode(0o600);
-            std::fs::set_permissions(&self.config.socket_path, perms)?;
-        }
-
         for name in &self.config.auto_start {
+            if adopted.contains(name) {
+                continue;
+            }
             info!("Auto-starting service: {}", name);
             if let Err(e) = self.services.start(name, None).await {
                 warn!("Failed to auto-start '{}': {}", name, e);
@@ -114,6 +148,17 @@ impl DaemonServer {
             health_manager.run().await;
         });
 
+        tokio::spawn(Arc::clone(&self.scheduler).run());
+
+        match crate::user_config::UserConfig::load() {
+            Ok(user_config) if !user_config.notify.routes.is_empty() => {
+                let notifier = Arc::new(Notifier::new(user_config.notify));
+                tokio::spawn(notifier.run(Arc::clone(&self.events)));
+            }
+            Ok(_) => debug!("No notification routes configured"),
+            Err(e) => warn!("Notifications disabled, failed to load config: {}", e),
+        }
+
         let mut shutdown = ShutdownCoordinator::new();
         self.shutdown_handle = Some(shutdown.handle());
 
@@ -175,6 +220,13 @@ impl DaemonServer {
             }
         }
 
+        if server.handed_over.load(Ordering::SeqCst) {
+            // The successor owns the socket, PID file and services now
+            pid_file.release();
+            info!("ADI daemon handed over, exiting");
+            return Ok(());
+        }
+
         info!("Stopping all services...");
         server.services.stop_all().await;
 
@@ -201,6 +253,15 @@ impl DaemonServer {
         let archived = rkyv::access::<ArchivedRequest, rkyv::rancor::Error>(&request_buf)
             .map_err(|e| anyhow::anyhow!("Failed to deserialize request: {}", e))?;
 
+        if let ArchivedRequest::Subscribe { topics } = archived {
+            let topics: Vec<String> = topics.iter().map(|t| t.to_string()).collect();
+            return self.stream_events(&mut stream, topics).await;
+        }
+
+        if let ArchivedRequest::Update { binary } = archived {
+            return self.update(&mut stream, binary.as_str()).await;
+        }
+
         let response = self.handle_request(archived).await;
 
         let response_bytes = MessageFrame::encode_response(&response)
@@ -226,6 +287,11 @@ impl DaemonServer {
         let archived = rkyv::access::<ArchivedRequest, rkyv::rancor::Error>(&request_buf)
             .map_err(|e| anyhow::anyhow!("Failed to deserialize request: {}", e))?;
 
+        if let ArchivedRequest::Subscribe { topics } = archived {
+            let topics: Vec<String> = topics.iter().map(|t| t.to_string()).collect();
+            return self.stream_events(&mut stream, topics).await;
+        }
+
         let response = self.handle_request(archived).await;
 
         let response_bytes = MessageFrame::encode_response(&response)
@@ -236,6 +302,80 @@ impl DaemonServer {
         Ok(())
     }
 
+    /// Hand over to a new daemon binary. The reply is written before shutdown
+    /// is triggered so the client learns the outcome from this process.
+    #[cfg(unix)]
+    async fn update(&self, stream: &mut tokio::net::UnixStream, binary: &str) -> Result<()> {
+        info!("Handling: Update({})", binary);
+        let listener_fd = self
+            .listener_fd
+            .ok_or_else(|| anyhow::anyhow!("IPC listener is not bound"))?;
+
+        let result = super::handover::hand_over(
+            &self.services,
+            listener_fd,
+            &self.config.socket_path,
+            binary,
+        )
+        .await;
+
+        let response = match result {
+            Ok(version) => {
+                info!("Handed over to daemon v{}", version);
+                self.handed_over.store(true, Ordering::SeqCst);
+                Response::Ok
+            }
+            Err(e) => {
+                warn!("Daemon update failed, keeping current daemon: {}", e);
+                Response::Error {
+                    message: e.to_string(),
+                }
+            }
+        };
+
+        write_response(stream, &response).await?;
+
+        if self.handed_over.load(Ordering::SeqCst) {
+            if let Some(handle) = &self.shutdown_handle {
+                handle.shutdown();
+            }
+        }
+        Ok(())
+    }
+
+    /// Acknowledge a subscription, then forward matching bus events until the
+    /// client disconnects.
+    async fn stream_events<S: AsyncWrite + Unpin>(
+        &self,
+        stream: &mut S,
+        topics: Vec<String>,
+    ) -> Result<()> {
+        debug!("Handling: Subscribe({:?})", topics);
+        let mut rx = self.events.subscribe();
+
+        write_response(stream, &Response::Ok).await?;
+
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Event subscriber lagged, {} events dropped", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            if !event.matches_any(&topics) {
+                continue;
+            }
+
+            if write_response(stream, &Response::Event { event }).await.is_err() {
+                trace!("Event subscriber disconnected");
+                return Ok(());
+            }
+        }
+    }
+
     async fn handle_request(&self, request: &ArchivedRequest) -> Response {
         match request {
             ArchivedRequest::Ping => {
@@ -328,10 +468,94 @@ impl DaemonServer {
                     },
                 }
             }
+
+            ArchivedRequest::Publish { event } => {
+                let event = deserialize_event(event);
+                debug!("Handling: Publish({} from {})", event.topic, event.source);
+                self.events.publish(event);
+                Response::Ok
+            }
+
+            ArchivedRequest::ScheduleJob { job } => {
+                let job = deserialize_job_spec(job);
+                debug!("Handling: ScheduleJob({} from {})", job.id, job.owner);
+                match self.scheduler.register(job) {
+                    Ok(()) => Response::Ok,
+                    Err(e) => Response::Error {
+                        message: e.to_string(),
+                    },
+                }
+            }
+
+            ArchivedRequest::UnscheduleJob { id } => {
+                debug!("Handling: UnscheduleJob({})", id);
+                match self.scheduler.unregister(id.as_str()) {
+                    Ok(()) => Response::Ok,
+                    Err(e) => Response::Error {
+                        message: e.to_string(),
+                    },
+                }
+            }
+
+            ArchivedRequest::ListJobs => {
+                debug!("Handling: ListJobs");
+                Response::Jobs {
+                    list: self.scheduler.list(),
+                }
+            }
+
+            ArchivedRequest::RunJobNow { id } => {
+                info!("Handling: RunJobNow({})", id);
+                match self.scheduler.run_now(id.as_str()).await {
+                    Ok(output) => Response::CommandResult {
+                        exit_code: output.status.code().unwrap_or(-1),
+                        stdout: output.stdout,
+                        stderr: output.stderr,
+                    },
+                    Err(e) => Response::Error {
+                        message: e.to_string(),
+                    },
+                }
+            }
+
+            // Long-lived; intercepted in handle_connection
+            ArchivedRequest::Subscribe { .. } => Response::Error {
+                message: "Subscribe must be the only request on a connection".to_string(),
+            },
+
+            // Intercepted in handle_connection on Unix; handover passes fds over the socket
+            ArchivedRequest::Update { .. } => Response::Error {
+                message: "Daemon update is only supported on Unix".to_string(),
+            },
         }
     }
 }
 
+#[cfg(unix)]
+fn bind_socket(path: &std::path::Path) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+async fn write_response<S: AsyncWrite + Unpin>(stream: &mut S, response: &Response) -> Result<()> {
+    let bytes = MessageFrame::encode_response(response)
+        .map_err(|e| anyhow::anyhow!("Failed to encode response: {}", e))?;
+    stream.write_all(&bytes).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
 fn deserialize_service_config(
     archived: &super::protocol::ArchivedServiceConfig,
 ) -> super::protocol::ServiceConfig {
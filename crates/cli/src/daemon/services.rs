@@ -1,13 +1,16 @@
+use super::events::{topics, EventBus, LogThresholdWatch};
 use super::log_buffer::LogBuffer;
 use super::protocol::{ServiceConfig, ServiceInfo, ServiceState};
 use crate::clienv;
 use anyhow::Result;
 use lib_daemon_core::is_process_running;
 use std::collections::HashMap;
+#[cfg(unix)]
+use std::os::fd::OwnedFd;
 use std::process::Stdio;
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
@@ -16,12 +19,20 @@ pub struct ServiceManager {
     services: Arc<RwLock<HashMap<String, ManagedService>>>,
     registry: ServiceRegistry,
     log_buffer: Arc<LogBuffer>,
+    events: Arc<EventBus>,
+    log_watch: Arc<LogThresholdWatch>,
 }
 
 pub struct ManagedService {
     pub config: ServiceConfig,
     pub state: ServiceState,
     pub process: Option<Child>,
+    /// PID of a process inherited from a previous daemon via `adi daemon update`;
+    /// there is no `Child` handle for it, so it is supervised by PID
+    pub adopted_pid: Option<u32>,
+    /// Duplicates of the stdout/stderr read ends, passed on at handover
+    #[cfg(unix)]
+    pub log_fds: Vec<OwnedFd>,
     pub started_at: Option<Instant>,
     /// Number of restarts since daemon started
     pub restarts: u32,
@@ -34,6 +45,9 @@ impl ManagedService {
             config,
             state: ServiceState::Stopped,
             process: None,
+            adopted_pid: None,
+            #[cfg(unix)]
+            log_fds: Vec::new(),
             started_at: None,
             restarts: 0,
             last_error: None,
@@ -41,7 +55,18 @@ impl ManagedService {
     }
 
     pub fn pid(&self) -> Option<u32> {
-        self.process.as_ref().and_then(|p| p.id())
+        self.process
+            .as_ref()
+            .and_then(|p| p.id())
+            .or(self.adopted_pid)
+    }
+
+    /// Forget the current process (owned or adopted) and its log pipes
+    pub fn clear_process(&mut self) {
+        self.process = None;
+        self.adopted_pid = None;
+        #[cfg(unix)]
+        self.log_fds.clear();
     }
 
     pub fn uptime_secs(&self) -> Option<u64> {
@@ -61,11 +86,13 @@ impl ManagedService {
 }
 
 impl ServiceManager {
-    pub fn new(log_buffer: Arc<LogBuffer>) -> Self {
+    pub fn new(log_buffer: Arc<LogBuffer>, events: Arc<EventBus>) -> Self {
         Self {
             services: Arc::new(RwLock::new(HashMap::new())),
             registry: ServiceRegistry::new(),
             log_buffer,
+            events,
+            log_watch: Arc::new(LogThresholdWatch::default()),
         }
     }
 
@@ -73,6 +100,14 @@ impl ServiceManager {
         &self.log_buffer
     }
 
+    pub fn events(&self) -> &Arc<EventBus> {
+        &self.events
+    }
+
+    pub fn log_watch(&self) -> &Arc<LogThresholdWatch> {
+        &self.log_watch
+    }
+
     /// Discover daemon services from installed plugin manifests
     pub async fn discover_plugins(&mut self) -> Result<()> {
         self.registry.discover_plugins().await
@@ -126,11 +161,22 @@ impl ServiceManager {
                 let pid = child.id();
                 info!("Started service '{}' with PID {:?}", name, pid);
 
-                spawn_log_readers(name, &mut child, &self.log_buffer);
+                #[cfg(unix)]
+                {
+                    service.log_fds = dup_log_fds(&child);
+                }
+                spawn_log_readers(
+                    name,
+                    &mut child,
+                    &self.log_buffer,
+                    &self.events,
+                    &self.log_watch,
+                );
 
                 service.process = Some(child);
                 service.state = ServiceState::Running;
                 service.started_at = Some(Instant::now());
+                emit_state(&self.events, name, ServiceState::Running, pid, None);
 
                 Ok(())
             }
@@ -138,6 +184,13 @@ impl ServiceManager {
                 error!("Failed to start service '{}': {}", name, e);
                 service.state = ServiceState::Failed;
                 service.last_error = Some(e.to_string());
+                emit_state(
+                    &self.events,
+                    name,
+                    ServiceState::Failed,
+                    None,
+                    Some(&e.to_string()),
+                );
                 Err(e.into())
             }
         }
@@ -189,11 +242,14 @@ impl ServiceManager {
                     }
                 }
             }
+        } else if let Some(pid) = service.adopted_pid {
+            stop_adopted(name, pid, force).await?;
         }
 
         service.state = ServiceState::Stopped;
-        service.process = None;
+        service.clear_process();
         service.started_at = None;
+        emit_state(&self.events, name, ServiceState::Stopped, None, None);
 
         Ok(())
     }
@@ -257,7 +313,8 @@ impl ServiceManager {
         if let Some(service) = services.get_mut(name) {
             service.state = ServiceState::Failed;
             service.last_error = Some(error.to_string());
-            service.process = None;
+            service.clear_process();
+            emit_state(&self.events, name, ServiceState::Failed, None, Some(error));
         }
     }
 
@@ -276,30 +333,142 @@ impl ServiceManager {
     }
 }
 
-/// Spawn background tasks that read stdout/stderr from a child process into the LogBuffer.
-fn spawn_log_readers(service_name: &str, child: &mut Child, log_buffer: &Arc<LogBuffer>) {
-    if let Some(stdout) = child.stdout.take() {
-        let buf = Arc::clone(log_buffer);
-        let name = service_name.to_string();
-        tokio::spawn(async move {
-            let mut lines = BufReader::new(stdout).lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                buf.push(&name, line);
-            }
-        });
-    }
-    if let Some(stderr) = child.stderr.take() {
-        let buf = Arc::clone(log_buffer);
-        let name = service_name.to_string();
-        tokio::spawn(async move {
-            let mut lines = BufReader::new(stderr).lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                buf.push(&name, line);
+/// Stop a process inherited through `adi daemon update` by signalling its PID.
+async fn stop_adopted(name: &str, pid: u32, force: bool) -> Result<()> {
+    if !force {
+        info!(
+            "Stopping adopted service '{}' (PID {}) gracefully",
+            name, pid
+        );
+        lib_daemon_core::kill_process(pid)?;
+        if lib_daemon_core::wait_for_exit(pid, tokio::time::Duration::from_secs(10)).await {
+            debug!("Service '{}' stopped gracefully", name);
+            return Ok(());
+        }
+        warn!("Service '{}' did not stop in time, force killing", name);
+    } else {
+        info!("Force killing adopted service '{}' (PID {})", name, pid);
+    }
+
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid as i32, libc::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    lib_daemon_core::kill_process(pid)?;
+
+    Ok(())
+}
+
+/// Duplicate a child's stdout/stderr read ends so they can be handed to a
+/// successor daemon; the originals are consumed by the log reader tasks.
+#[cfg(unix)]
+pub(super) fn dup_log_fds(child: &Child) -> Vec<OwnedFd> {
+    use std::os::fd::AsFd;
+
+    let stdout = child
+        .stdout
+        .as_ref()
+        .map(|s| s.as_fd().try_clone_to_owned());
+    let stderr = child
+        .stderr
+        .as_ref()
+        .map(|s| s.as_fd().try_clone_to_owned());
+
+    [stdout, stderr]
+        .into_iter()
+        .flatten()
+        .filter_map(|fd| match fd {
+            Ok(fd) => Some(fd),
+            Err(e) => {
+                warn!("Failed to duplicate log pipe: {}", e);
+                None
             }
-        });
+        })
+        .collect()
+}
+
+/// Publish a `service.state` event for a lifecycle transition.
+pub(super) fn emit_state(
+    events: &EventBus,
+    name: &str,
+    state: ServiceState,
+    pid: Option<u32>,
+    error: Option<&str>,
+) {
+    events.emit(
+        topics::SERVICE_STATE,
+        serde_json::json!({
+            "service": name,
+            "state": state.as_str(),
+            "pid": pid,
+            "error": error,
+        }),
+    );
+}
+
+/// Spawn background tasks that read stdout/stderr from a child process into the LogBuffer.
+///
+/// Lines are also fed to the error-rate watch, which publishes `log.threshold`
+/// when a service logs too many errors in a short window.
+pub(super) fn spawn_log_readers(
+    service_name: &str,
+    child: &mut Child,
+    log_buffer: &Arc<LogBuffer>,
+    events: &Arc<EventBus>,
+    log_watch: &Arc<LogThresholdWatch>,
+) {
+    let stdout = child
+        .stdout
+        .take()
+        .map(|s| Box::new(s) as Box<dyn AsyncRead + Send + Unpin>);
+    let stderr = child
+        .stderr
+        .take()
+        .map(|s| Box::new(s) as Box<dyn AsyncRead + Send + Unpin>);
+
+    for stream in [stdout, stderr].into_iter().flatten() {
+        spawn_log_reader(service_name, stream, log_buffer, events, log_watch);
     }
 }
 
+/// Spawn a task that reads lines from one output stream of a service.
+pub(super) fn spawn_log_reader(
+    service_name: &str,
+    stream: Box<dyn AsyncRead + Send + Unpin>,
+    log_buffer: &Arc<LogBuffer>,
+    events: &Arc<EventBus>,
+    log_watch: &Arc<LogThresholdWatch>,
+) {
+    let buf = Arc::clone(log_buffer);
+    let events = Arc::clone(events);
+    let watch = Arc::clone(log_watch);
+    let name = service_name.to_string();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(count) = watch.record(&name, &line) {
+                warn!(
+                    "Service '{}' logged {} errors in {:?}",
+                    name,
+                    count,
+                    watch.window()
+                );
+                events.emit(
+                    topics::LOG_THRESHOLD,
+                    serde_json::json!({
+                        "service": name,
+                        "errors": count,
+                        "window_secs": watch.window().as_secs(),
+                        "last_line": line,
+                    }),
+                );
+            }
+            buf.push(&name, line);
+        }
+    });
+}
+
 pub struct ServiceRegistry {
     builtin: HashMap<String, ServiceConfig>,
     auto_start: Vec<String>,
@@ -420,7 +589,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_service_manager_list() {
-        let manager = ServiceManager::new(Arc::new(LogBuffer::default()));
+        let manager = ServiceManager::new(
+            Arc::new(LogBuffer::default()),
+            Arc::new(EventBus::default()),
+        );
         let list = manager.list().await;
         assert!(list.is_empty()); // No services started
     }
@@ -12,6 +12,27 @@ pub(crate) fn initialize_theme() {
     lib_console_output::theme::init(&theme_id);
 }
 
+/// Resolve the output glyph set and color scheme (env overrides config).
+///
+/// Exported to the environment so plugins, which keep their own copy of the
+/// theme state, render the same way.
+pub(crate) fn initialize_output() {
+    let config = UserConfig::load().unwrap_or_default();
+    let output_theme: theme::OutputTheme = cli::clienv::output_theme()
+        .or(config.output.theme)
+        .and_then(|v| v.parse().map_err(|e| tracing::warn!("{}", e)).ok())
+        .unwrap_or_default();
+    let color_scheme: theme::ColorScheme = cli::clienv::color_scheme()
+        .or(config.output.colors)
+        .and_then(|v| v.parse().map_err(|e| tracing::warn!("{}", e)).ok())
+        .unwrap_or_default();
+    tracing::trace!(%output_theme, %color_scheme, "Initializing output");
+
+    std::env::set_var(theme::ADI_OUTPUT_THEME_ENV, output_theme.as_str());
+    std::env::set_var(theme::ADI_COLOR_SCHEME_ENV, color_scheme.as_str());
+    theme::init_output(output_theme, color_scheme);
+}
+
 async fn resolve_language(
     lang_override: Option<&str>,
     config: &mut UserConfig,
@@ -4,6 +4,7 @@ pub mod daemon;
 pub mod error;
 pub mod plugin_registry;
 pub mod plugin_runtime;
+pub mod profile;
 pub mod self_update;
 pub mod user_config;
 
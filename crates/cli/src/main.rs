@@ -1,12 +1,15 @@
 mod args;
 mod cmd_config;
+mod cmd_context;
 mod cmd_daemon;
 mod cmd_external;
 mod cmd_info;
 mod cmd_interactive;
 mod cmd_logs;
+mod cmd_notify;
 mod cmd_plugin;
 mod cmd_run;
+mod cmd_schedule;
 mod cmd_search;
 mod cmd_start;
 mod cmd_theme;
@@ -15,14 +18,22 @@ mod init;
 use args::{Cli, Commands};
 use clap::Parser;
 use cli::completions;
+use cli::profile::{self, ProfileFormat};
+use tracing::Instrument;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
+    // The env filter is per-layer so RUST_LOG does not hide profile spans
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_filter(tracing_subscriber::EnvFilter::from_default_env()),
         )
-        .with_target(false)
+        .with(profile::layer())
         .init();
 
     tracing::trace!("ADI CLI starting");
@@ -34,8 +45,19 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
     tracing::trace!(lang = ?cli.lang, has_command = cli.command.is_some(), "CLI arguments parsed");
 
-    init::initialize_i18n(cli.lang.as_deref()).await?;
-    init::initialize_theme();
+    if let Some(format) = cli.profile.or_else(ProfileFormat::from_env) {
+        profile::start(format);
+    }
+
+    init::initialize_output();
+
+    async {
+        init::initialize_i18n(cli.lang.as_deref()).await?;
+        init::initialize_theme();
+        anyhow::Ok(())
+    }
+    .instrument(tracing::info_span!(target: profile::TARGET, "startup"))
+    .await?;
 
     let command = match cli.command {
         Some(cmd) => cmd,
@@ -48,7 +70,11 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    dispatch_command(command).await?;
+    let result = dispatch_command(command)
+        .instrument(tracing::info_span!(target: profile::TARGET, "command"))
+        .await;
+    profile::report();
+    result?;
 
     tracing::trace!("ADI CLI finished");
     Ok(())
@@ -82,6 +108,19 @@ async fn dispatch_command(command: Commands) -> anyhow::Result<()> {
             tracing::trace!(plugin_id = %plugin_id, "Dispatching: logs");
             cmd_logs::cmd_logs(&plugin_id, follow, lines, level, service).await?
         }
+        Commands::Search {
+            query,
+            types,
+            limit,
+            json,
+        } => {
+            tracing::trace!(query = %query, types = ?types, "Dispatching: search");
+            cmd_search::cmd_search_all(&query, &types, limit, json).await?
+        }
+        Commands::Context { command } => {
+            tracing::trace!("Dispatching: context");
+            cmd_context::cmd_context(command).await?
+        }
         Commands::Theme => {
             tracing::trace!("Dispatching: theme");
             cmd_theme::cmd_theme()?
@@ -98,6 +137,14 @@ async fn dispatch_command(command: Commands) -> anyhow::Result<()> {
             tracing::trace!("Dispatching: daemon");
             cmd_daemon::cmd_daemon(command).await?
         }
+        Commands::Notify { command } => {
+            tracing::trace!("Dispatching: notify");
+            cmd_notify::cmd_notify(command).await?
+        }
+        Commands::Schedule { command } => {
+            tracing::trace!("Dispatching: schedule");
+            cmd_schedule::cmd_schedule(command).await?
+        }
         Commands::External(args) => {
             tracing::trace!(args = ?args, "Dispatching: external");
             cmd_external::cmd_external(args).await?
@@ -1,8 +1,13 @@
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
-use lib_plugin_host::{LoadedPluginV3, PluginManagerV3};
+use lib_plugin_abi_v3::project::ProjectContext;
+use lib_plugin_abi_v3::services::{AdiService, PluginServices};
+use lib_plugin_abi_v3::{async_trait, PluginError, PluginEvent};
+use lib_plugin_host::compat::{self, CompatIssue, PluginExports};
+use lib_plugin_host::{LoadedPluginV3, PluginManagerV3, ProjectContextRegistry};
 use lib_plugin_manifest::PluginManifest;
+use tracing::Instrument;
 
 use crate::error::Result;
 
@@ -46,12 +51,15 @@ impl PluginRuntime {
     #[allow(clippy::arc_with_non_send_sync)]
     pub async fn new(config: RuntimeConfig) -> Result<Self> {
         tracing::trace!(plugins_dir = %config.plugins_dir.display(), cache_dir = %config.cache_dir.display(), "Creating plugin runtime");
+        let _span = tracing::info_span!(target: crate::profile::TARGET, "runtime_init").entered();
 
         std::fs::create_dir_all(&config.plugins_dir)?;
         std::fs::create_dir_all(&config.cache_dir)?;
 
-        let manager_v3 = PluginManagerV3::new();
-        tracing::trace!("Plugin manager v3 initialized");
+        let mut manager_v3 = PluginManagerV3::new();
+        let cwd = std::env::current_dir().unwrap_or_default();
+        manager_v3.set_project_context(Self::context_registry().resolve(&cwd));
+        tracing::trace!(project = ?manager_v3.project_context().map(|c| &c.root), "Plugin manager v3 initialized");
 
         Ok(Self {
             manager_v3: Arc::new(RwLock::new(manager_v3)),
@@ -67,6 +75,29 @@ impl PluginRuntime {
         &self.config
     }
 
+    /// Registry holding the project pinned with `adi context use`
+    pub fn context_registry() -> ProjectContextRegistry {
+        ProjectContextRegistry::load(crate::clienv::config_dir().join(lib_plugin_host::CONTEXT_STATE_FILE))
+    }
+
+    pub fn project_context(&self) -> Option<ProjectContext> {
+        self.manager_v3.read().expect("plugin manager lock poisoned").project_context().cloned()
+    }
+
+    /// Switch the active project and notify every loaded plugin if it changed.
+    pub async fn set_project_context(&self, context: Option<ProjectContext>) {
+        let plugins = {
+            let mut manager = self.manager_v3.write().expect("plugin manager lock poisoned");
+            if !manager.set_project_context(context.clone()) {
+                return;
+            }
+            manager.event_targets()
+        };
+
+        tracing::trace!(count = plugins.len(), "Broadcasting project context change");
+        lib_plugin_host::broadcast_event(plugins, &PluginEvent::ProjectChanged(context)).await;
+    }
+
     pub async fn load_all_plugins(&self) -> Result<()> {
         let plugins_dir = &self.config.plugins_dir;
         if !plugins_dir.exists() {
@@ -74,6 +105,19 @@ impl PluginRuntime {
             return Ok(());
         }
 
+        for plugin_id in self.installed_plugin_ids() {
+            tracing::trace!(plugin_id = %plugin_id, "Loading plugin");
+            if let Err(e) = self.load_plugin_internal(&plugin_id).await {
+                tracing::warn!("Failed to enable plugin {}: {}", plugin_id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// IDs of every plugin directory, whether or not it loads
+    fn installed_plugin_ids(&self) -> Vec<String> {
+        let plugins_dir = &self.config.plugins_dir;
         tracing::trace!(dir = %plugins_dir.display(), "Scanning plugins directory");
 
         let mut plugin_ids = Vec::new();
@@ -92,15 +136,32 @@ impl PluginRuntime {
         }
 
         tracing::trace!(count = plugin_ids.len(), "Discovered plugin directories");
+        plugin_ids
+    }
 
-        for plugin_id in plugin_ids {
-            tracing::trace!(plugin_id = %plugin_id, "Loading plugin");
-            if let Err(e) = self.load_plugin_internal(&plugin_id).await {
-                tracing::warn!("Failed to enable plugin {}: {}", plugin_id, e);
-            }
-        }
+    /// Manifests of all installed plugins, sorted by ID, without loading binaries
+    pub fn installed_manifests(&self) -> Vec<PluginManifest> {
+        let mut manifests: Vec<PluginManifest> = self
+            .installed_plugin_ids()
+            .iter()
+            .filter_map(|id| self.find_plugin_manifest(id).ok())
+            .collect();
+        manifests.sort_by(|a, b| a.plugin.id.cmp(&b.plugin.id));
+        manifests
+    }
 
-        Ok(())
+    /// Manifest of an installed plugin, without loading its binary
+    pub fn plugin_manifest(&self, plugin_id: &str) -> Result<PluginManifest> {
+        self.find_plugin_manifest(plugin_id)
+    }
+
+    /// Compatibility issues of an installed plugin's binary, without creating it
+    pub fn plugin_compat_issues(&self, plugin_id: &str) -> Result<Vec<CompatIssue>> {
+        let manifest = self.find_plugin_manifest(plugin_id)?;
+        let plugin_dir = self.resolve_plugin_dir(plugin_id)?;
+        let exports = PluginExports::probe_installed(&manifest, &plugin_dir)
+            .map_err(|e| crate::error::InstallerError::Other(e.to_string()))?;
+        Ok(compat::check(&manifest, &exports))
     }
 
     async fn load_plugin_internal(&self, plugin_id: &str) -> Result<()> {
@@ -117,11 +178,24 @@ impl PluginRuntime {
         let plugin_dir = self.resolve_plugin_dir(&manifest.plugin.id)?;
         tracing::trace!(plugin_id = %manifest.plugin.id, dir = %plugin_dir.display(), "Loading v3 plugin binary");
 
-        match LoadedPluginV3::load(manifest.clone(), &plugin_dir).await {
+        let project_root = self.project_context().map(|ctx| ctx.root);
+        let services: Arc<dyn PluginServices> = Arc::new(self.clone());
+        let load = LoadedPluginV3::load(manifest.clone(), &plugin_dir, project_root.as_deref(), Some(services))
+            .instrument(tracing::info_span!(target: crate::profile::TARGET, "plugin_load", plugin = %manifest.plugin.id));
+        match load.await {
             Ok(loaded) => {
                 let plugin_id = manifest.plugin.id.clone();
 
-                self.manager_v3.write().expect("plugin manager lock poisoned").register(loaded)?;
+                let (targets, context) = {
+                    let mut manager = self.manager_v3.write().expect("plugin manager lock poisoned");
+                    manager.register(loaded)?;
+                    (manager.event_targets_of(&plugin_id), manager.project_context().cloned())
+                };
+
+                // Newly loaded plugins start with the current project context
+                if context.is_some() {
+                    lib_plugin_host::broadcast_event(targets, &PluginEvent::ProjectChanged(context)).await;
+                }
 
                 tracing::info!("Loaded v3 plugin: {}", plugin_id);
                 Ok(())
@@ -213,6 +287,10 @@ impl PluginRuntime {
         self.manager_v3.read().expect("plugin manager lock poisoned").get_log_provider(plugin_id)
     }
 
+    pub fn search_providers(&self) -> Vec<(String, std::sync::Arc<dyn lib_plugin_abi_v3::search::SearchProvider>)> {
+        self.manager_v3.read().expect("plugin manager lock poisoned").all_search_providers()
+    }
+
     pub fn get_daemon_service(&self, plugin_id: &str) -> Option<std::sync::Arc<dyn lib_plugin_abi_v3::daemon::DaemonService>> {
         self.manager_v3.read().expect("plugin manager lock poisoned").get_daemon_service(plugin_id)
     }
@@ -234,6 +312,7 @@ impl PluginRuntime {
 
         let result = plugin
             .run_command(&ctx)
+            .instrument(tracing::info_span!(target: crate::profile::TARGET, "dispatch", plugin = %plugin_id))
             .await
             .map_err(|e| crate::error::InstallerError::Other(e.to_string()))?;
 
@@ -243,6 +322,7 @@ impl PluginRuntime {
             "exit_code": result.exit_code,
             "stdout": result.stdout,
             "stderr": result.stderr,
+            "error": result.error,
         }))
         .expect("JSON serialization cannot fail for known structure"))
     }
@@ -319,6 +399,11 @@ impl PluginRuntime {
         let mut positional = Vec::new();
         let mut i = 0;
         while i < args.len() {
+            // `--` ends option parsing; the rest is passed through verbatim
+            if args[i] == "--" {
+                positional.extend_from_slice(&args[i + 1..]);
+                break;
+            }
             let Some(key) = args[i].strip_prefix("--") else {
                 positional.push(args[i].clone());
                 i += 1;
@@ -337,6 +422,7 @@ impl PluginRuntime {
 
     pub fn discover_cli_commands(&self) -> Vec<PluginCliCommand> {
         tracing::trace!("Discovering CLI commands");
+        let _span = tracing::info_span!(target: crate::profile::TARGET, "discover").entered();
 
         let plugins_dir = &self.config.plugins_dir;
         if !plugins_dir.exists() {
@@ -344,8 +430,7 @@ impl PluginRuntime {
             return Vec::new();
         }
 
-        let cmds_dir = lib_plugin_host::command_index::commands_dir(plugins_dir);
-        if cmds_dir.exists() {
+        if !lib_plugin_host::command_index::is_stale(plugins_dir) {
             let indexed = lib_plugin_host::command_index::list_indexed_commands(plugins_dir);
             if !indexed.is_empty() {
                 tracing::trace!(count = indexed.len(), "Using command index (fast path)");
@@ -353,7 +438,7 @@ impl PluginRuntime {
             }
         }
 
-        tracing::trace!("Command index missing or empty, falling back to full scan");
+        tracing::trace!("Command index missing, stale or empty, falling back to full scan");
         let commands = self.discover_cli_commands_full_scan();
 
         if let Err(e) = lib_plugin_host::command_index::rebuild_index(plugins_dir) {
@@ -363,6 +448,19 @@ impl PluginRuntime {
         commands
     }
 
+    /// Rebuild the command index from installed manifests, returning the number
+    /// of indexed command names (including aliases).
+    pub fn refresh_command_index(&self) -> Result<usize> {
+        let plugins_dir = &self.config.plugins_dir;
+        if !plugins_dir.exists() {
+            return Ok(0);
+        }
+
+        lib_plugin_host::command_index::rebuild_index(plugins_dir)
+            .map_err(|e| crate::error::InstallerError::Other(e.to_string()))?;
+        Ok(lib_plugin_host::command_index::list_indexed_commands(plugins_dir).len())
+    }
+
     fn commands_from_index(indexed: Vec<(String, PathBuf)>) -> Vec<PluginCliCommand> {
         let mut seen = std::collections::HashMap::<PathBuf, PluginCliCommand>::new();
 
@@ -434,7 +532,9 @@ impl PluginRuntime {
 
         let plugins_dir = &self.config.plugins_dir;
 
-        if let Some(manifest_path) =
+        if lib_plugin_host::command_index::is_stale(plugins_dir) {
+            tracing::trace!("Command index stale, skipping fast lookup");
+        } else if let Some(manifest_path) =
             lib_plugin_host::command_index::resolve_command(plugins_dir, command)
         {
             if let Ok(manifest) = PluginManifest::from_file(&manifest_path) {
@@ -456,6 +556,28 @@ impl PluginRuntime {
     }
 }
 
+/// What plugins reach each other through: the owner is loaded on first use
+/// and serves the project this runtime resolved.
+#[async_trait]
+impl PluginServices for PluginRuntime {
+    async fn adi_service(&self, plugin_id: &str) -> lib_plugin_abi_v3::Result<Arc<dyn AdiService>> {
+        let loaded = self.manager_v3.read().expect("plugin manager lock poisoned").get_plugin(plugin_id).is_some();
+        if !loaded {
+            self.load_plugin_internal(plugin_id)
+                .await
+                .map_err(|e| PluginError::NotFound(format!("{}: {}", plugin_id, e)))?;
+        }
+
+        let (provider, project) = {
+            let manager = self.manager_v3.read().expect("plugin manager lock poisoned");
+            (manager.get_adi_service_provider(plugin_id), manager.project_context().cloned())
+        };
+        let provider = provider
+            .ok_or_else(|| PluginError::NotFound(format!("{} does not offer an ADI service", plugin_id)))?;
+        provider.adi_service(project.as_ref()).await
+    }
+}
+
 impl Clone for PluginRuntime {
     fn clone(&self) -> Self {
         Self {
@@ -518,4 +640,19 @@ mod tests {
         let runtime = PluginRuntime::new(config).await;
         assert!(runtime.is_ok());
     }
+
+    #[test]
+    fn test_split_args_stops_at_double_dash() {
+        let args: Vec<String> = ["dev-1", "--label", "gpu", "--", "ls", "--all"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut options = std::collections::HashMap::new();
+
+        let positional = PluginRuntime::split_args_and_flags(&args, &mut options);
+
+        assert_eq!(positional, ["dev-1", "ls", "--all"]);
+        assert_eq!(options.len(), 1);
+        assert_eq!(options["label"], "gpu");
+    }
 }
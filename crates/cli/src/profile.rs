@@ -0,0 +1,322 @@
+//! Per-command timing (`adi --profile <command>` or `ADI_PROFILE=1`).
+//!
+//! Timed sections are `tracing` spans with target [`TARGET`], so the plugin
+//! host and the daemon client mark them without knowing about the CLI. The
+//! layer from [`layer`] records every such span from creation to close once
+//! [`start`] was called, and [`report`] prints the breakdown to stderr.
+
+use serde::Serialize;
+use std::fmt::{self, Write as _};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Span target of timed sections.
+pub const TARGET: &str = "profile";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProfileFormat {
+    /// Indented breakdown
+    Text,
+    /// One JSON object, for scripts
+    Json,
+}
+
+impl ProfileFormat {
+    /// `ADI_PROFILE`: `json` for JSON, any other truthy value for text.
+    pub fn from_env() -> Option<Self> {
+        let value = crate::clienv::profile()?;
+        if value.eq_ignore_ascii_case("json") {
+            Some(Self::Json)
+        } else if lib_env_parse::is_truthy(&value) || value.eq_ignore_ascii_case("text") {
+            Some(Self::Text)
+        } else {
+            None
+        }
+    }
+}
+
+/// One closed span.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpanTiming {
+    pub name: &'static str,
+    /// Span fields, e.g. `plugin=adi.cocoon`
+    pub detail: String,
+    /// Number of enclosing timed spans
+    pub depth: usize,
+    pub start_ms: f64,
+    pub duration_ms: f64,
+}
+
+/// Count and summed time of all spans with one name.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpanSummary {
+    pub name: &'static str,
+    pub count: usize,
+    pub total_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub total_ms: f64,
+    /// Ordered by start time
+    pub spans: Vec<SpanTiming>,
+    /// Ordered by first occurrence
+    pub summary: Vec<SpanSummary>,
+}
+
+impl Report {
+    fn new(total: Duration, mut spans: Vec<SpanTiming>) -> Self {
+        spans.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+
+        let mut summary: Vec<SpanSummary> = Vec::new();
+        for span in &spans {
+            match summary.iter_mut().find(|s| s.name == span.name) {
+                Some(entry) => {
+                    entry.count += 1;
+                    entry.total_ms += span.duration_ms;
+                }
+                None => summary.push(SpanSummary {
+                    name: span.name,
+                    count: 1,
+                    total_ms: span.duration_ms,
+                }),
+            }
+        }
+
+        Self {
+            total_ms: millis(total),
+            spans,
+            summary,
+        }
+    }
+
+    pub fn render_text(&self) -> String {
+        let label = |span: &SpanTiming| format!("{}{}", "  ".repeat(span.depth), span.name);
+        let width = self
+            .spans
+            .iter()
+            .map(|s| label(s).len())
+            .chain(self.summary.iter().map(|s| s.name.len()))
+            .max()
+            .unwrap_or(0);
+
+        let mut out = String::new();
+        let _ = writeln!(out, "Profile (total {:.1} ms)", self.total_ms);
+        for span in &self.spans {
+            let _ = write!(
+                out,
+                "  {:<width$}  {:>9.1} ms",
+                label(span),
+                span.duration_ms,
+                width = width
+            );
+            if !span.detail.is_empty() {
+                let _ = write!(out, "  {}", span.detail);
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+        for entry in &self.summary {
+            let _ = writeln!(
+                out,
+                "  {:<width$}  {:>9.1} ms  ×{}",
+                entry.name,
+                entry.total_ms,
+                entry.count,
+                width = width
+            );
+        }
+        out
+    }
+}
+
+struct Profiler {
+    started: Instant,
+    enabled: AtomicBool,
+    format: OnceLock<ProfileFormat>,
+    spans: Mutex<Vec<SpanTiming>>,
+}
+
+static PROFILER: OnceLock<Profiler> = OnceLock::new();
+
+fn profiler() -> &'static Profiler {
+    PROFILER.get_or_init(|| Profiler {
+        started: Instant::now(),
+        enabled: AtomicBool::new(false),
+        format: OnceLock::new(),
+        spans: Mutex::new(Vec::new()),
+    })
+}
+
+/// Layer recording timed spans; inert until [`start`]. Install it at
+/// startup so the total includes everything before the command runs.
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    profiler();
+    ProfileLayer.with_filter(filter_fn(|metadata| {
+        metadata.target() == TARGET && profiler().enabled.load(Ordering::Relaxed)
+    }))
+}
+
+/// Start recording; the report is printed in `format`.
+pub fn start(format: ProfileFormat) {
+    let profiler = profiler();
+    let _ = profiler.format.set(format);
+    profiler.enabled.store(true, Ordering::Relaxed);
+}
+
+/// Print the report to stderr if profiling was started. Only the first call
+/// prints, so it is safe before every `process::exit`.
+pub fn report() {
+    let profiler = profiler();
+    if !profiler.enabled.swap(false, Ordering::Relaxed) {
+        return;
+    }
+
+    let spans = std::mem::take(&mut *profiler.spans.lock().expect("profile lock poisoned"));
+    let report = Report::new(profiler.started.elapsed(), spans);
+    match profiler.format.get() {
+        Some(ProfileFormat::Json) => eprintln!(
+            "{}",
+            serde_json::to_string(&report)
+                .expect("JSON serialization cannot fail for profile report")
+        ),
+        _ => eprint!("\n{}", report.render_text()),
+    }
+}
+
+/// Span start time and fields, kept in the span's extensions until it closes.
+struct Timing {
+    start: Instant,
+    detail: String,
+}
+
+struct ProfileLayer;
+
+impl<S> Layer<S> for ProfileLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut detail = DetailVisitor(String::new());
+        attrs.record(&mut detail);
+        span.extensions_mut().insert(Timing {
+            start: Instant::now(),
+            detail: detail.0,
+        });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(timing) = span.extensions_mut().remove::<Timing>() else {
+            return;
+        };
+
+        let profiler = profiler();
+        let timing = SpanTiming {
+            name: span.metadata().name(),
+            detail: timing.detail,
+            // The filter hides untimed spans, so every ancestor is a timed one
+            depth: span.scope().skip(1).count(),
+            start_ms: millis(timing.start.duration_since(profiler.started)),
+            duration_ms: millis(timing.start.elapsed()),
+        };
+        profiler
+            .spans
+            .lock()
+            .expect("profile lock poisoned")
+            .push(timing);
+    }
+}
+
+/// Formats span fields as `key=value` pairs.
+struct DetailVisitor(String);
+
+impl DetailVisitor {
+    fn separator(&mut self) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+    }
+}
+
+impl Visit for DetailVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.separator();
+        let _ = write!(self.0, "{}={}", field.name(), value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.separator();
+        let _ = write!(self.0, "{}={:?}", field.name(), value);
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(name: &'static str, depth: usize, start_ms: f64, duration_ms: f64) -> SpanTiming {
+        SpanTiming {
+            name,
+            detail: String::new(),
+            depth,
+            start_ms,
+            duration_ms,
+        }
+    }
+
+    #[test]
+    fn test_report_orders_and_summarizes() {
+        let report = Report::new(
+            Duration::from_millis(100),
+            vec![
+                span("ipc", 1, 30.0, 2.0),
+                span("command", 0, 10.0, 80.0),
+                span("ipc", 1, 20.0, 3.0),
+            ],
+        );
+
+        let order: Vec<f64> = report.spans.iter().map(|s| s.start_ms).collect();
+        assert_eq!(order, vec![10.0, 20.0, 30.0]);
+        assert_eq!(report.summary.len(), 2);
+        assert_eq!(report.summary[0].name, "command");
+        assert_eq!(report.summary[1].name, "ipc");
+        assert_eq!(report.summary[1].count, 2);
+        assert_eq!(report.summary[1].total_ms, 5.0);
+    }
+
+    #[test]
+    fn test_render_text_indents_children() {
+        let mut child = span("plugin_load", 1, 1.0, 12.5);
+        child.detail = "plugin=adi.cocoon".to_string();
+        let report = Report::new(
+            Duration::from_millis(20),
+            vec![span("command", 0, 0.0, 19.0), child],
+        );
+
+        let text = report.render_text();
+        assert!(text.starts_with("Profile (total 20.0 ms)"));
+        assert!(text.contains("\n    plugin_load       12.5 ms  plugin=adi.cocoon\n"));
+        assert!(text.contains("\n  command             19.0 ms\n"));
+        assert!(text.contains("\n  plugin_load         12.5 ms  ×1\n"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::daemon::NotifyConfig;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -11,6 +12,26 @@ pub struct UserConfig {
     pub theme: Option<String>,
     /// Power user mode - enables advanced features and verbose output
     pub power_user: Option<bool>,
+    /// Daemon event notification sinks and routes
+    #[serde(default, skip_serializing_if = "NotifyConfig::is_empty")]
+    pub notify: NotifyConfig,
+    /// Glyph set and colors of terminal output
+    #[serde(default, skip_serializing_if = "OutputConfig::is_empty")]
+    pub output: OutputConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OutputConfig {
+    /// Glyph set: "unicode", "ascii" or "plain"
+    pub theme: Option<String>,
+    /// Color scheme: "default", "high-contrast" or "mono"
+    pub colors: Option<String>,
+}
+
+impl OutputConfig {
+    pub fn is_empty(&self) -> bool {
+        self.theme.is_none() && self.colors.is_none()
+    }
 }
 
 impl UserConfig {
@@ -0,0 +1,14 @@
+//! Live dependency graphs for `adi graph serve`.
+//!
+//! A [`GraphServer`] reloads its [`GraphSource`]s on an interval and serves
+//! them to a bundled single-page app: the current graphs from `/api/graphs`,
+//! every change as a Server-Sent Event on `/events`, and Graphviz DOT from
+//! `/api/graphs/{kind}/dot`.
+
+mod model;
+mod server;
+mod source;
+
+pub use model::{Graph, GraphEdge, GraphNode, NodeStatus};
+pub use server::{GraphServer, GraphSnapshot, DEFAULT_REFRESH_INTERVAL};
+pub use source::GraphSource;
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Directed graph of one kind of thing: tasks, services or cocoons.
+///
+/// Edges point from the dependent to what it depends on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub label: String,
+    /// Nodes of a group are drawn together, e.g. the services of a source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<NodeStatus>,
+    /// Shown on hover
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// How a node is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+    Ok,
+    Active,
+    Pending,
+    Warning,
+    Error,
+    Inactive,
+}
+
+impl NodeStatus {
+    /// Graphviz color name
+    pub const fn color(&self) -> &'static str {
+        match self {
+            Self::Ok => "green",
+            Self::Active => "blue",
+            Self::Pending => "black",
+            Self::Warning => "orange",
+            Self::Error => "red",
+            Self::Inactive => "gray",
+        }
+    }
+}
+
+impl GraphNode {
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            group: None,
+            status: None,
+            detail: None,
+        }
+    }
+
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    pub fn with_status(mut self, status: NodeStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl Graph {
+    pub fn add_node(&mut self, node: GraphNode) {
+        self.nodes.push(node);
+    }
+
+    /// Add an edge; edges whose ends are not (yet) nodes are dropped by
+    /// [`Graph::finish`].
+    pub fn add_edge(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        label: Option<String>,
+    ) {
+        self.edges.push(GraphEdge {
+            from: from.into(),
+            to: to.into(),
+            label,
+        });
+    }
+
+    /// Sort nodes and edges and drop dangling or duplicate edges, so the same
+    /// data always yields an equal graph and unchanged graphs are not resent.
+    pub fn finish(mut self) -> Self {
+        self.nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        self.nodes.dedup_by(|a, b| a.id == b.id);
+        let known = |id: &str| {
+            self.nodes
+                .binary_search_by(|n| n.id.as_str().cmp(id))
+                .is_ok()
+        };
+        let mut edges: Vec<GraphEdge> = std::mem::take(&mut self.edges)
+            .into_iter()
+            .filter(|e| known(&e.from) && known(&e.to))
+            .collect();
+        edges.sort_by(|a, b| (&a.from, &a.to, &a.label).cmp(&(&b.from, &b.to, &b.label)));
+        edges.dedup();
+        self.edges = edges;
+        self
+    }
+
+    /// Graphviz DOT rendering, for tools that still want a file
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut out = format!("digraph {} {{\n  rankdir=LR;\n", quote(name));
+        for node in &self.nodes {
+            let _ = write!(out, "  {} [label={}", quote(&node.id), quote(&node.label));
+            if let Some(status) = node.status {
+                let _ = write!(out, " color=\"{}\"", status.color());
+            }
+            if let Some(ref detail) = node.detail {
+                let _ = write!(out, " tooltip={}", quote(detail));
+            }
+            out.push_str("];\n");
+        }
+        for edge in &self.edges {
+            let _ = write!(out, "  {} -> {}", quote(&edge.from), quote(&edge.to));
+            if let Some(ref label) = edge.label {
+                let _ = write!(out, " [label={}]", quote(label));
+            }
+            out.push_str(";\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finish_sorts_and_drops_dangling_edges() {
+        let mut graph = Graph::default();
+        graph.add_node(GraphNode::new("b", "B"));
+        graph.add_node(GraphNode::new("a", "A"));
+        graph.add_edge("b", "a", None);
+        graph.add_edge("b", "a", None);
+        graph.add_edge("a", "gone", None);
+        let graph = graph.finish();
+
+        let ids: Vec<_> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(graph.edges.len(), 1);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut graph = Graph::default();
+        graph.add_node(GraphNode::new("1", "Say \"hi\"").with_status(NodeStatus::Ok));
+        graph.add_node(GraphNode::new("2", "Deploy"));
+        graph.add_edge("2", "1", Some("DB_URL".to_string()));
+
+        assert_eq!(
+            graph.finish().to_dot("tasks"),
+            "digraph \"tasks\" {\n  rankdir=LR;\n  \"1\" [label=\"Say \\\"hi\\\"\" color=\"green\"];\n  \
+             \"2\" [label=\"Deploy\"];\n  \"2\" -> \"1\" [label=\"DB_URL\"];\n}\n"
+        );
+    }
+}
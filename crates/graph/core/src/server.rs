@@ -0,0 +1,312 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
+    routing::get,
+    Json, Router,
+};
+use futures::Stream;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, RwLock};
+
+use crate::{Graph, GraphSource};
+
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+const INDEX_HTML: &str = include_str!("../assets/index.html");
+
+/// Changes a slow browser may fall behind before it is told to refetch
+const UPDATE_BUFFER: usize = 64;
+
+/// A source's graph as sent to the browser
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphSnapshot {
+    pub kind: String,
+    pub title: String,
+    /// `None` until the source loaded once, or when it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph: Option<Graph>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct ServerState {
+    snapshots: RwLock<Vec<GraphSnapshot>>,
+    updates: broadcast::Sender<GraphSnapshot>,
+}
+
+pub struct GraphServer {
+    sources: Vec<Box<dyn GraphSource>>,
+    interval: Duration,
+    state: Arc<ServerState>,
+}
+
+impl GraphServer {
+    pub fn new(sources: Vec<Box<dyn GraphSource>>) -> Self {
+        let snapshots = sources
+            .iter()
+            .map(|source| GraphSnapshot {
+                kind: source.kind().to_string(),
+                title: source.title().to_string(),
+                graph: None,
+                error: None,
+            })
+            .collect();
+        let (updates, _) = broadcast::channel(UPDATE_BUFFER);
+        Self {
+            sources,
+            interval: DEFAULT_REFRESH_INTERVAL,
+            state: Arc::new(ServerState {
+                snapshots: RwLock::new(snapshots),
+                updates,
+            }),
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Reload every source and publish the graphs that changed; returns
+    /// their kinds.
+    pub async fn refresh(&self) -> Vec<String> {
+        let loaded = futures::future::join_all(self.sources.iter().map(|s| s.load())).await;
+
+        let mut changed = Vec::new();
+        let mut snapshots = self.state.snapshots.write().await;
+        for (snapshot, result) in snapshots.iter_mut().zip(loaded) {
+            let (graph, error) = match result {
+                Ok(graph) => (Some(graph), None),
+                Err(e) => (None, Some(e)),
+            };
+            if snapshot.graph == graph && snapshot.error == error {
+                continue;
+            }
+            snapshot.graph = graph;
+            snapshot.error = error;
+            changed.push(snapshot.kind.clone());
+            // No browser connected is not an error
+            let _ = self.state.updates.send(snapshot.clone());
+        }
+        changed
+    }
+
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", get(|| async { Html(INDEX_HTML) }))
+            .route("/api/graphs", get(list_graphs))
+            .route("/api/graphs/{kind}", get(get_graph))
+            .route("/api/graphs/{kind}/dot", get(get_dot))
+            .route("/events", get(events))
+            .with_state(self.state.clone())
+    }
+
+    /// Serve on `listener` until `shutdown` resolves, refreshing the
+    /// sources every interval.
+    pub async fn serve(
+        self,
+        listener: TcpListener,
+        shutdown: impl Future<Output = ()> + Send,
+    ) -> std::io::Result<()> {
+        self.refresh().await;
+        let app = self.router();
+        let server = Arc::new(self);
+
+        let poller = {
+            let server = server.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(server.interval).await;
+                    let changed = server.refresh().await;
+                    if !changed.is_empty() {
+                        tracing::debug!("Graphs changed: {}", changed.join(", "));
+                    }
+                }
+            })
+        };
+
+        // Open event streams never end on their own, so there is no
+        // graceful shutdown to wait for
+        let result = tokio::select! {
+            result = axum::serve(listener, app) => result,
+            _ = shutdown => Ok(()),
+        };
+        poller.abort();
+        result
+    }
+}
+
+async fn list_graphs(State(state): State<Arc<ServerState>>) -> Json<Vec<GraphSnapshot>> {
+    Json(state.snapshots.read().await.clone())
+}
+
+async fn find(state: &ServerState, kind: &str) -> Option<GraphSnapshot> {
+    state
+        .snapshots
+        .read()
+        .await
+        .iter()
+        .find(|s| s.kind == kind)
+        .cloned()
+}
+
+async fn get_graph(State(state): State<Arc<ServerState>>, Path(kind): Path<String>) -> Response {
+    match find(&state, &kind).await {
+        Some(snapshot) => Json(snapshot).into_response(),
+        None => (StatusCode::NOT_FOUND, format!("Unknown graph: {}", kind)).into_response(),
+    }
+}
+
+async fn get_dot(State(state): State<Arc<ServerState>>, Path(kind): Path<String>) -> Response {
+    match find(&state, &kind).await {
+        Some(GraphSnapshot {
+            graph: Some(graph), ..
+        }) => (
+            [(header::CONTENT_TYPE, "text/vnd.graphviz")],
+            graph.to_dot(&kind),
+        )
+            .into_response(),
+        Some(GraphSnapshot { error, .. }) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            error.unwrap_or_else(|| "Graph is not loaded yet".to_string()),
+        )
+            .into_response(),
+        None => (StatusCode::NOT_FOUND, format!("Unknown graph: {}", kind)).into_response(),
+    }
+}
+
+/// `graph` events carry a changed [`GraphSnapshot`]; `resync` asks the
+/// browser to refetch everything after it fell behind.
+async fn events(
+    State(state): State<Arc<ServerState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let updates = state.updates.subscribe();
+    let stream = futures::stream::unfold(updates, |mut updates| async move {
+        let event = match updates.recv().await {
+            Ok(snapshot) => Event::default()
+                .event("graph")
+                .json_data(&snapshot)
+                .unwrap_or_else(|_| Event::default().event("resync").data("")),
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                Event::default().event("resync").data("")
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        };
+        Some((Ok(event), updates))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GraphNode;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Source whose graph has as many nodes as `nodes` says; 0 fails
+    struct FakeSource {
+        nodes: Arc<Mutex<usize>>,
+    }
+
+    #[async_trait]
+    impl GraphSource for FakeSource {
+        fn kind(&self) -> &str {
+            "fake"
+        }
+
+        fn title(&self) -> &str {
+            "Fake"
+        }
+
+        async fn load(&self) -> Result<Graph, String> {
+            let nodes = *self.nodes.lock().unwrap();
+            if nodes == 0 {
+                return Err("source is down".to_string());
+            }
+            let mut graph = Graph::default();
+            for i in 0..nodes {
+                graph.add_node(GraphNode::new(i.to_string(), format!("Node {}", i)));
+            }
+            Ok(graph.finish())
+        }
+    }
+
+    fn fake_server(nodes: usize) -> (GraphServer, Arc<Mutex<usize>>) {
+        let nodes = Arc::new(Mutex::new(nodes));
+        let source = FakeSource {
+            nodes: nodes.clone(),
+        };
+        (GraphServer::new(vec![Box::new(source)]), nodes)
+    }
+
+    #[tokio::test]
+    async fn test_refresh_publishes_only_changes() {
+        let (server, nodes) = fake_server(1);
+        let mut updates = server.state.updates.subscribe();
+
+        assert_eq!(server.refresh().await, vec!["fake"]);
+        assert!(server.refresh().await.is_empty());
+
+        *nodes.lock().unwrap() = 2;
+        assert_eq!(server.refresh().await, vec!["fake"]);
+        *nodes.lock().unwrap() = 0;
+        assert_eq!(server.refresh().await, vec!["fake"]);
+
+        assert_eq!(updates.recv().await.unwrap().graph.unwrap().nodes.len(), 1);
+        assert_eq!(updates.recv().await.unwrap().graph.unwrap().nodes.len(), 2);
+        let failed = updates.recv().await.unwrap();
+        assert!(failed.graph.is_none());
+        assert_eq!(failed.error.as_deref(), Some("source is down"));
+    }
+
+    async fn http_get(addr: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            path
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_serves_graphs() {
+        let (server, _) = fake_server(1);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let handle = tokio::spawn(server.serve(listener, async {
+            let _ = stopped.await;
+        }));
+
+        let index = http_get(addr, "/").await;
+        assert!(index.starts_with("HTTP/1.1 200"));
+        assert!(index.contains("EventSource"));
+
+        let graphs = http_get(addr, "/api/graphs").await;
+        assert!(graphs.contains(r#""kind":"fake""#));
+
+        let dot = http_get(addr, "/api/graphs/fake/dot").await;
+        assert!(dot.contains("text/vnd.graphviz"));
+        assert!(dot.contains(r#""0" [label="Node 0"];"#));
+
+        let missing = http_get(addr, "/api/graphs/nope").await;
+        assert!(missing.starts_with("HTTP/1.1 404"));
+
+        stop.send(()).unwrap();
+        handle.await.unwrap().unwrap();
+    }
+}
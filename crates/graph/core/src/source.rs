@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+
+use crate::Graph;
+
+/// Something [`crate::GraphServer`] can draw, reloaded on every refresh.
+#[async_trait]
+pub trait GraphSource: Send + Sync {
+    /// Stable id used in URLs and events, e.g. `tasks`
+    fn kind(&self) -> &str;
+
+    /// Tab title in the browser
+    fn title(&self) -> &str;
+
+    /// Current graph; the error is shown in place of the graph, e.g. when the
+    /// hive daemon is not running.
+    async fn load(&self) -> Result<Graph, String>;
+}
@@ -0,0 +1,26 @@
+# ============================================================================
+# ADI GRAPH - ENGLISH TRANSLATIONS
+# ============================================================================
+
+# Plugin metadata
+plugin-name = Graph
+plugin-author = ADI Team
+plugin-description = Live dependency graphs for tasks, services and cocoons
+
+# Command descriptions
+cmd-serve-help = Serve interactive dependency graphs in the browser
+
+# Help text
+graph-help-title = ADI Graph — Live dependency graphs
+graph-help-commands = Commands:
+graph-help-usage = Usage: adi graph serve [--port <port>] [--only tasks,hive,cocoons] [--interval <secs>] [--url <signaling-url>] [--token <token>]
+
+# Serve
+graph-serve-listening = Graphs at { $url } (Ctrl+C to stop)
+graph-serve-stopped = Graph server stopped
+
+# Errors
+graph-error-invalid-port = Invalid port: { $port }
+graph-error-invalid-interval = Invalid interval: { $interval } (seconds, at least 0.1)
+graph-error-unknown-graph = Unknown graph: { $graph } (available: { $graphs })
+graph-error-bind = Failed to listen on port { $port }: { $error }
@@ -0,0 +1,199 @@
+mod sources;
+
+use graph_core::{GraphServer, GraphSource};
+use lib_credential_store::CredentialStore;
+use lib_env_parse::{env_opt, env_vars};
+use lib_plugin_prelude::*;
+use sources::{CocoonSource, HiveSource, TasksSource};
+use std::time::Duration;
+
+env_vars! {
+    SignalingServerUrl => "SIGNALING_SERVER_URL",
+    SignalingAccessToken => "SIGNALING_ACCESS_TOKEN",
+}
+
+const DEFAULT_PORT: u16 = 7878;
+
+const GRAPH_KINDS: [&str; 3] = ["tasks", "hive", "cocoons"];
+
+#[derive(CliArgs)]
+pub struct ServeArgs {
+    #[arg(long)]
+    pub port: Option<String>,
+    /// Comma-separated graphs to show: tasks, hive, cocoons
+    #[arg(long)]
+    pub only: Option<String>,
+    /// Seconds between refreshes
+    #[arg(long)]
+    pub interval: Option<String>,
+    /// Signaling server for the cocoon map
+    #[arg(long)]
+    pub url: Option<String>,
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+pub struct GraphPlugin;
+
+impl GraphPlugin {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for GraphPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Plugin for GraphPlugin {
+    fn metadata(&self) -> PluginMetadata {
+        PluginMetadata::new("adi.graph", t!("plugin-name"), env!("CARGO_PKG_VERSION"))
+            .with_type(PluginType::Extension)
+            .with_author(t!("plugin-author"))
+            .with_description(t!("plugin-description"))
+    }
+
+    async fn init(&mut self, ctx: &PluginContext) -> Result<()> {
+        PluginCtx::init(ctx);
+        init_plugin_i18n("en-US", include_str!("../locales/en-US/messages.ftl"));
+        Ok(())
+    }
+
+    fn provides(&self) -> Vec<&'static str> {
+        vec![SERVICE_CLI_COMMANDS]
+    }
+}
+
+#[async_trait]
+impl CliCommands for GraphPlugin {
+    async fn list_commands(&self) -> Vec<CliCommand> {
+        vec![Self::__sdk_cmd_meta_serve()]
+    }
+
+    async fn run_command(&self, ctx: &CliContext) -> Result<CliResult> {
+        match ctx.subcommand.as_deref() {
+            Some("serve") => self.__sdk_cmd_handler_serve(ctx).await,
+            Some(cmd) => Ok(CliResult::error(format!("Unknown command: {cmd}"))),
+            None => Ok(CliResult::success(self.help())),
+        }
+    }
+}
+
+impl GraphPlugin {
+    fn help(&self) -> String {
+        format!(
+            "{}\n\n{}\n  serve    {}\n\n{}",
+            t!("graph-help-title"),
+            t!("graph-help-commands"),
+            t!("cmd-serve-help"),
+            t!("graph-help-usage"),
+        )
+    }
+
+    #[command(name = "serve", description = "cmd-serve-help")]
+    async fn serve(&self, args: ServeArgs) -> CmdResult {
+        let port = match args.port {
+            Some(ref port) => port
+                .parse::<u16>()
+                .map_err(|_| t!("graph-error-invalid-port", "port" => port.as_str()))?,
+            None => DEFAULT_PORT,
+        };
+        let interval = match args.interval {
+            Some(ref secs) => secs
+                .parse::<f64>()
+                .ok()
+                .filter(|secs| *secs >= 0.1)
+                .map(Duration::from_secs_f64)
+                .ok_or_else(|| t!("graph-error-invalid-interval", "interval" => secs.as_str()))?,
+            None => graph_core::DEFAULT_REFRESH_INTERVAL,
+        };
+        let kinds: Vec<String> = match args.only {
+            Some(ref only) => only
+                .split(',')
+                .map(|kind| kind.trim().to_string())
+                .filter(|kind| !kind.is_empty())
+                .collect(),
+            None => GRAPH_KINDS.iter().map(|kind| kind.to_string()).collect(),
+        };
+        if let Some(kind) = kinds
+            .iter()
+            .find(|kind| !GRAPH_KINDS.contains(&kind.as_str()))
+        {
+            return Err(
+                t!("graph-error-unknown-graph", "graph" => kind.as_str(), "graphs" => GRAPH_KINDS.join(", ")),
+            );
+        }
+
+        let mut sources: Vec<Box<dyn GraphSource>> = Vec::new();
+        for kind in &kinds {
+            match kind.as_str() {
+                // The tasks plugin picks the task store of the active project
+                "tasks" => sources.push(Box::new(TasksSource {
+                    service: PluginCtx::adi_service(sources::TASKS_PLUGIN_ID)
+                        .await
+                        .map_err(|e| e.to_string()),
+                })),
+                "hive" => sources.push(Box::new(HiveSource {
+                    daemon_config: hive_daemon_config(),
+                })),
+                _ => {
+                    let (url, token) = signaling_login(args.url.clone(), args.token.clone());
+                    sources.push(Box::new(CocoonSource::new(url, token)));
+                }
+            }
+        }
+        let server = GraphServer::new(sources).with_interval(interval);
+
+        let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+                .await
+                .map_err(|e| t!("graph-error-bind", "port" => port.to_string(), "error" => e.to_string()))?;
+            println!("{}", t!("graph-serve-listening", "url" => format!("http://127.0.0.1:{}", port)));
+            server
+                .serve(listener, async {
+                    let _ = tokio::signal::ctrl_c().await;
+                })
+                .await
+                .map_err(|e| e.to_string())
+        })?;
+        Ok(t!("graph-serve-stopped"))
+    }
+}
+
+/// The hive plugin keeps its daemon in its own data directory, next to ours
+fn hive_daemon_config() -> hive_core::DaemonConfig {
+    let data_dir = PluginCtx::data_dir();
+    hive_core::DaemonConfig::new(data_dir.parent().unwrap_or(data_dir).join("adi.hive"))
+}
+
+/// Signaling URL and access token for the cocoon map, from the flags, the
+/// environment, or the token `adi cocoon` cached for the URL
+fn signaling_login(url: Option<String>, token: Option<String>) -> (String, Option<String>) {
+    let signaling_url = url
+        .or_else(|| env_opt(EnvVar::SignalingServerUrl.as_str()))
+        .unwrap_or_else(|| "ws://localhost:8080/ws".to_string());
+    let access_token = token
+        .or_else(|| env_opt(EnvVar::SignalingAccessToken.as_str()))
+        .or_else(|| {
+            CredentialStore::open_default()
+                .ok()?
+                .access_token(&signaling_url)
+                .ok()
+                .flatten()
+        });
+    (signaling_url, access_token)
+}
+
+#[no_mangle]
+pub fn plugin_create() -> Box<dyn Plugin> {
+    Box::new(GraphPlugin::new())
+}
+
+#[no_mangle]
+pub fn plugin_create_cli() -> Box<dyn CliCommands> {
+    Box::new(GraphPlugin::new())
+}
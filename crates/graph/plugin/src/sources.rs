@@ -0,0 +1,396 @@
+//! Graphs drawn by `adi graph serve`: tasks, hive services and cocoons.
+
+use futures::{SinkExt, StreamExt};
+use graph_core::{Graph, GraphNode, GraphSource, NodeStatus};
+use lib_adi_service::{AdiCallerContext, AdiHandleResult};
+use lib_plugin_prelude::{async_trait, AdiService};
+use lib_signaling_protocol::{DeviceInfo, SignalingMessage};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Signaling is asked for the device list at most this often
+const COCOON_REFRESH: Duration = Duration::from_secs(10);
+
+/// How long signaling may take to list the devices
+const SIGNALING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Plugin serving the task API
+pub const TASKS_PLUGIN_ID: &str = "adi.tasks";
+
+/// Tasks and their dependencies, read through the tasks plugin's service
+/// API. `Err` if the tasks plugin could not be reached.
+pub struct TasksSource {
+    pub service: Result<Arc<dyn AdiService>, String>,
+}
+
+/// The parts of a task the graph shows (`list` / `get` of the task API)
+#[derive(Deserialize)]
+struct TaskRecord {
+    id: i64,
+    title: String,
+    status: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TaskDependencies {
+    depends_on: Vec<TaskRecord>,
+}
+
+#[async_trait]
+impl GraphSource for TasksSource {
+    fn kind(&self) -> &str {
+        "tasks"
+    }
+
+    fn title(&self) -> &str {
+        "Tasks"
+    }
+
+    async fn load(&self) -> Result<Graph, String> {
+        let service = self.service.as_ref().map_err(Clone::clone)?;
+        let tasks: Vec<TaskRecord> = call(service.as_ref(), "list", json!({})).await?;
+
+        let mut graph = Graph::default();
+        for task in tasks {
+            let id = task.id.to_string();
+            let mut node = GraphNode::new(&id, format!("#{} {}", id, task.title))
+                .with_status(task_status(&task.status));
+            if let Some(description) = task.description.filter(|d| !d.is_empty()) {
+                node = node.with_detail(description);
+            }
+            graph.add_node(node);
+            let deps: TaskDependencies =
+                call(service.as_ref(), "get", json!({ "task_id": task.id })).await?;
+            for dep in deps.depends_on {
+                graph.add_edge(&id, dep.id.to_string(), None);
+            }
+        }
+        Ok(graph.finish())
+    }
+}
+
+async fn call<T: serde::de::DeserializeOwned>(
+    service: &dyn AdiService,
+    method: &str,
+    params: Value,
+) -> Result<T, String> {
+    let payload = serde_json::to_vec(&params).map_err(|e| e.to_string())?;
+    match service
+        .handle(&AdiCallerContext::anonymous(), method, payload.into())
+        .await
+    {
+        Ok(AdiHandleResult::Success(data)) => {
+            serde_json::from_slice(&data).map_err(|e| e.to_string())
+        }
+        Ok(AdiHandleResult::Stream(_)) => Err(format!("{}: unexpected stream response", method)),
+        Err(e) => Err(format!("{}: {}", e.code, e.message)),
+    }
+}
+
+fn task_status(status: &str) -> NodeStatus {
+    match status {
+        "todo" => NodeStatus::Pending,
+        "in_progress" => NodeStatus::Active,
+        "done" => NodeStatus::Ok,
+        "blocked" => NodeStatus::Error,
+        _ => NodeStatus::Inactive,
+    }
+}
+
+/// Services of all hive sources, linked by `depends_on` and by the
+/// variables they consume from each other
+pub struct HiveSource {
+    pub daemon_config: hive_core::DaemonConfig,
+}
+
+#[async_trait]
+impl GraphSource for HiveSource {
+    fn kind(&self) -> &str {
+        "hive"
+    }
+
+    fn title(&self) -> &str {
+        "Services"
+    }
+
+    async fn load(&self) -> Result<Graph, String> {
+        let running =
+            hive_core::HiveDaemon::is_running(&self.daemon_config).map_err(|e| e.to_string())?;
+        if running.is_none() {
+            return Err(
+                "Hive daemon is not running. Start it with: adi hive daemon start".to_string(),
+            );
+        }
+
+        let client = hive_core::DaemonClient::new(self.daemon_config.socket_path());
+        let services = client
+            .list_services(None)
+            .await
+            .map_err(|e| e.to_string())?;
+        let sources = client.list_sources().await.map_err(|e| e.to_string())?;
+        let exposures = client.expose_graph().await.map_err(|e| e.to_string())?;
+
+        let mut graph = Graph::default();
+        for service in &services {
+            let mut detail = service.state.clone();
+            if !service.ports.is_empty() {
+                let mut ports: Vec<_> = service
+                    .ports
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect();
+                ports.sort();
+                detail.push_str(&format!("\nports: {}", ports.join(", ")));
+            }
+            graph.add_node(
+                GraphNode::new(&service.fqn, &service.name)
+                    .with_group(&service.source)
+                    .with_status(service_status(&service.state, service.healthy))
+                    .with_detail(detail),
+            );
+        }
+
+        for source in &sources {
+            for (name, depends_on) in source_dependencies(source) {
+                for dep in depends_on {
+                    graph.add_edge(
+                        format!("{}:{}", source.name, name),
+                        format!("{}:{}", source.name, dep),
+                        None,
+                    );
+                }
+            }
+        }
+        for edge in exposures {
+            graph.add_edge(edge.consumer, edge.provider, Some(edge.vars.join(", ")));
+        }
+        Ok(graph.finish())
+    }
+}
+
+/// `depends_on` of each service in a source; the daemon does not report
+/// them, so they are read from the source's config
+fn source_dependencies(source: &hive_core::WireSourceInfo) -> Vec<(String, Vec<String>)> {
+    let config = match source.source_type {
+        hive_core::WireSourceType::Yaml => hive_core::HiveConfigParser::new(&source.path).parse(),
+        hive_core::WireSourceType::Sqlite => {
+            hive_core::SqliteBackend::open(&source.path).and_then(|backend| backend.load_config())
+        }
+    };
+    match config {
+        Ok(config) => config
+            .services
+            .into_iter()
+            .map(|(name, service)| (name, service.depends_on))
+            .collect(),
+        Err(e) => {
+            tracing::debug!("Failed to read config of source {}: {}", source.name, e);
+            Vec::new()
+        }
+    }
+}
+
+fn service_status(state: &str, healthy: Option<bool>) -> NodeStatus {
+    match state {
+        "running" if healthy == Some(false) => NodeStatus::Warning,
+        "running" => NodeStatus::Ok,
+        "starting" | "stopping" => NodeStatus::Active,
+        "unhealthy" | "port conflict" => NodeStatus::Warning,
+        "crashed" => NodeStatus::Error,
+        _ => NodeStatus::Inactive,
+    }
+}
+
+/// The caller's devices and what each provides: the ADI plugins installed
+/// on it and the protocols it speaks
+pub struct CocoonSource {
+    pub signaling_url: String,
+    pub access_token: Option<String>,
+    cached: Mutex<Option<(Instant, Result<Graph, String>)>>,
+}
+
+impl CocoonSource {
+    pub fn new(signaling_url: String, access_token: Option<String>) -> Self {
+        Self {
+            signaling_url,
+            access_token,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl GraphSource for CocoonSource {
+    fn kind(&self) -> &str {
+        "cocoons"
+    }
+
+    fn title(&self) -> &str {
+        "Cocoons"
+    }
+
+    async fn load(&self) -> Result<Graph, String> {
+        if let Some((at, ref graph)) = *self.cached.lock().unwrap() {
+            if at.elapsed() < COCOON_REFRESH {
+                return graph.clone();
+            }
+        }
+
+        let graph = match self.access_token {
+            Some(ref token) => list_devices(&self.signaling_url, token)
+                .await
+                .map(|devices| cocoon_graph(&devices)),
+            None => Err("No access token. Pass --token or set SIGNALING_ACCESS_TOKEN.".to_string()),
+        };
+        *self.cached.lock().unwrap() = Some((Instant::now(), graph.clone()));
+        graph
+    }
+}
+
+fn cocoon_graph(devices: &[DeviceInfo]) -> Graph {
+    let mut graph = Graph::default();
+    for device in devices {
+        let id = format!("device:{}", device.device_id);
+        let label = device
+            .tags
+            .get("name")
+            .cloned()
+            .unwrap_or_else(|| device.device_id.chars().take(12).collect());
+        let mut tags: Vec<_> = device
+            .tags
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        tags.sort();
+        let status = if device.online {
+            NodeStatus::Ok
+        } else {
+            NodeStatus::Inactive
+        };
+        graph.add_node(
+            GraphNode::new(&id, label)
+                .with_group(device.device_type.as_deref().unwrap_or("device"))
+                .with_status(status)
+                .with_detail(format!("{}\n{}", device.device_id, tags.join("\n"))),
+        );
+
+        let Some(ref config) = device.device_config else {
+            continue;
+        };
+        for plugin in string_list(config, "adi_plugins") {
+            let (plugin_id, version) = plugin.rsplit_once(':').unwrap_or((plugin, ""));
+            let plugin_node = format!("plugin:{}", plugin_id);
+            graph.add_node(GraphNode::new(&plugin_node, plugin_id).with_group("plugins"));
+            graph.add_edge(
+                &id,
+                plugin_node,
+                (!version.is_empty()).then(|| version.to_string()),
+            );
+        }
+        for protocol in string_list(config, "protocols") {
+            let protocol_node = format!("protocol:{}", protocol);
+            graph.add_node(GraphNode::new(&protocol_node, protocol).with_group("protocols"));
+            graph.add_edge(&id, protocol_node, None);
+        }
+    }
+    graph.finish()
+}
+
+fn string_list<'a>(config: &'a serde_json::Value, key: &str) -> impl Iterator<Item = &'a str> {
+    config
+        .get(key)
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+}
+
+/// Sign in to signaling as an app client and return the caller's devices
+async fn list_devices(signaling_url: &str, access_token: &str) -> Result<Vec<DeviceInfo>, String> {
+    let (ws, _) = tokio_tungstenite::connect_async(signaling_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", signaling_url, e))?;
+    let (mut sink, mut stream) = ws.split();
+
+    let mut authenticating = false;
+    let devices = loop {
+        let next = tokio::time::timeout(SIGNALING_TIMEOUT, stream.next())
+            .await
+            .map_err(|_| "Timed out waiting for the signaling server".to_string())?;
+        let text = match next {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(format!("Signaling connection failed: {}", e)),
+            None => return Err("Signaling server closed the connection".to_string()),
+        };
+
+        match serde_json::from_str::<SignalingMessage>(&text) {
+            Ok(SignalingMessage::AuthHello { .. }) if !authenticating => {
+                authenticating = true;
+                let auth = SignalingMessage::AuthAuthenticate {
+                    access_token: access_token.to_string(),
+                };
+                let json = serde_json::to_string(&auth).map_err(|e| e.to_string())?;
+                sink.send(Message::Text(json))
+                    .await
+                    .map_err(|_| "Signaling connection closed".to_string())?;
+            }
+            Ok(SignalingMessage::AuthHelloAuthed { devices, .. }) => break devices,
+            Ok(SignalingMessage::SystemError { message }) => {
+                return Err(format!("Authentication failed: {}", message))
+            }
+            _ => {}
+        }
+    };
+    let _ = sink.close().await;
+    Ok(devices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_cocoon_graph_links_devices_to_capabilities() {
+        let device = |id: &str, plugins: &[&str]| DeviceInfo {
+            device_id: id.into(),
+            tags: HashMap::from([("name".to_string(), format!("{}-box", id))]),
+            online: id == "a",
+            device_type: Some("cocoon".to_string()),
+            device_config: Some(serde_json::json!({
+                "adi_plugins": plugins,
+                "protocols": ["silk"],
+            })),
+        };
+        let graph = cocoon_graph(&[
+            device("a", &["adi.tasks:0.8.8", "adi.hive:1.0.0"]),
+            device("b", &["adi.tasks:0.9.0"]),
+        ]);
+
+        let ids: Vec<_> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "device:a",
+                "device:b",
+                "plugin:adi.hive",
+                "plugin:adi.tasks",
+                "protocol:silk"
+            ]
+        );
+        let tasks_versions: Vec<_> = graph
+            .edges
+            .iter()
+            .filter(|e| e.to == "plugin:adi.tasks")
+            .map(|e| e.label.as_deref().unwrap())
+            .collect();
+        assert_eq!(tasks_versions, vec!["0.8.8", "0.9.0"]);
+        assert_eq!(graph.nodes[1].status, Some(NodeStatus::Inactive));
+    }
+}
@@ -0,0 +1,247 @@
+//! Cocoon Warm Pool
+//!
+//! Spawning a cocoon pulls its image and boots a container, which takes tens
+//! of seconds. With a pool size set for a kind (`HiveSignalingConfig::pool_sizes`
+//! or a `HiveSetPoolSize` push), the hive keeps that many unclaimed cocoons of
+//! the kind booted as `cocoon-pool-<kind>-<id>` services in the cocoon source.
+//!
+//! Every slot boots unowned under a secret only the hive knows. A spawn with
+//! `from_pool` claims the oldest ready slot and hands the running cocoon
+//! over: signaling pushes the caller's setup token and a fresh secret to the
+//! device registered with the slot's secret, and the cocoon registers again
+//! as the caller's (`HiveRekeyCocoon`). The claimed slot keeps its service
+//! name and the pool refills in the background. Spawns that find no ready
+//! slot, or whose slot cannot be re-keyed, fall back to a cold start; the
+//! former count as misses.
+//!
+//! This module only keeps the bookkeeping; `hive_signaling` starts and removes
+//! the slot services. Like the cocoon source itself, pools live in memory.
+
+use lib_signaling_protocol::{CocoonPoolStatus, MAX_POOL_SIZE};
+use rand::RngCore;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Service name prefix of warm pool slots
+pub const SLOT_PREFIX: &str = "cocoon-pool-";
+
+/// A warm pool cocoon: its service name and the secret it registers with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slot {
+    pub name: String,
+    pub secret: String,
+}
+
+#[derive(Debug, Default)]
+struct KindPool {
+    size: u32,
+    /// Booted slots, oldest first
+    ready: VecDeque<Slot>,
+    /// name → secret of slots still booting
+    warming: HashMap<String, String>,
+    claimed: u64,
+    misses: u64,
+}
+
+impl KindPool {
+    fn deficit(&self) -> u32 {
+        let held = (self.ready.len() + self.warming.len()) as u32;
+        self.size.saturating_sub(held)
+    }
+}
+
+/// Warm pools of every kind with a pool size
+#[derive(Debug, Default)]
+pub struct CocoonPool {
+    kinds: BTreeMap<String, KindPool>,
+    changed: bool,
+}
+
+impl CocoonPool {
+    pub fn new(sizes: &HashMap<String, u32>) -> Self {
+        let mut pool = Self::default();
+        for (kind, size) in sizes {
+            pool.set_size(kind, *size);
+        }
+        pool
+    }
+
+    /// Set the target size of `kind`'s pool, at most [`MAX_POOL_SIZE`].
+    /// Returns the names of ready slots beyond the new size, newest first,
+    /// for the caller to remove.
+    pub fn set_size(&mut self, kind: &str, size: u32) -> Vec<String> {
+        let size = size.min(MAX_POOL_SIZE);
+        let pool = self.kinds.entry(kind.to_string()).or_default();
+        pool.size = size;
+        let mut surplus = Vec::new();
+        while pool.ready.len() > size as usize {
+            surplus.extend(pool.ready.pop_back().map(|slot| slot.name));
+        }
+        self.changed = true;
+        surplus
+    }
+
+    /// Take the oldest ready slot of `kind`; `None` counts as a miss.
+    pub fn claim(&mut self, kind: &str) -> Option<Slot> {
+        let pool = self.kinds.entry(kind.to_string()).or_default();
+        let slot = pool.ready.pop_front();
+        match slot {
+            Some(_) => pool.claimed += 1,
+            None => pool.misses += 1,
+        }
+        self.changed = true;
+        slot
+    }
+
+    /// The slots `kind` is missing, marked as warming. The caller boots them
+    /// and reports back with [`CocoonPool::warmed`].
+    pub fn warm(&mut self, kind: &str) -> Vec<Slot> {
+        let Some(pool) = self.kinds.get_mut(kind) else {
+            return Vec::new();
+        };
+        let slots: Vec<Slot> = (0..pool.deficit())
+            .map(|_| {
+                let short_id = &uuid::Uuid::new_v4().to_string()[..8];
+                Slot {
+                    name: format!("{SLOT_PREFIX}{kind}-{short_id}"),
+                    secret: generate_secret(),
+                }
+            })
+            .collect();
+        if !slots.is_empty() {
+            pool.warming.extend(
+                slots
+                    .iter()
+                    .map(|slot| (slot.name.clone(), slot.secret.clone())),
+            );
+            self.changed = true;
+        }
+        slots
+    }
+
+    /// Kinds with fewer ready and warming slots than their size
+    pub fn short_kinds(&self) -> Vec<String> {
+        self.kinds
+            .iter()
+            .filter(|(_, pool)| pool.deficit() > 0)
+            .map(|(kind, _)| kind.clone())
+            .collect()
+    }
+
+    /// Record the outcome of booting `slot`. Returns whether the slot joined
+    /// the pool; a booted slot the pool no longer wants is for the caller to
+    /// remove.
+    pub fn warmed(&mut self, kind: &str, slot: &str, booted: bool) -> bool {
+        let Some(pool) = self.kinds.get_mut(kind) else {
+            return false;
+        };
+        let Some(secret) = pool.warming.remove(slot) else {
+            return false;
+        };
+        self.changed = true;
+        if booted && pool.ready.len() < pool.size as usize {
+            pool.ready.push_back(Slot {
+                name: slot.to_string(),
+                secret,
+            });
+            return true;
+        }
+        false
+    }
+
+    pub fn status(&self) -> Vec<CocoonPoolStatus> {
+        self.kinds
+            .iter()
+            .map(|(kind, pool)| CocoonPoolStatus {
+                kind: kind.clone(),
+                size: pool.size,
+                ready: pool.ready.len() as u32,
+                warming: pool.warming.len() as u32,
+                claimed: pool.claimed,
+                misses: pool.misses,
+            })
+            .collect()
+    }
+
+    /// Whether the pool changed since the last call, to report it only then
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+}
+
+/// Random device secret for a slot, or for the cocoon a claimed slot becomes
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boot_all(pool: &mut CocoonPool, kind: &str) -> Vec<Slot> {
+        let slots = pool.warm(kind);
+        for slot in &slots {
+            assert!(pool.warmed(kind, &slot.name, true));
+        }
+        slots
+    }
+
+    #[test]
+    fn test_claim_takes_oldest_ready_slot() {
+        let mut pool = CocoonPool::new(&HashMap::from([("linux".to_string(), 2)]));
+        assert_eq!(pool.short_kinds(), vec!["linux".to_string()]);
+
+        let slots = pool.warm("linux");
+        assert_eq!(slots.len(), 2);
+        assert!(slots[0].name.starts_with("cocoon-pool-linux-"));
+        // Each slot registers under its own secret
+        assert_ne!(slots[0].secret, slots[1].secret);
+        // Warming slots count towards the size
+        assert!(pool.warm("linux").is_empty());
+        assert!(pool.short_kinds().is_empty());
+        assert_eq!(pool.claim("linux"), None);
+
+        assert!(pool.warmed("linux", &slots[1].name, true));
+        assert!(!pool.warmed("linux", &slots[0].name, false));
+        let status = &pool.status()[0];
+        assert_eq!((status.ready, status.warming, status.misses), (1, 0, 1));
+
+        assert_eq!(pool.claim("linux"), Some(slots[1].clone()));
+        assert_eq!(pool.claim("macos"), None);
+        let status = pool.status();
+        assert_eq!((status[0].claimed, status[0].ready), (1, 0));
+        assert_eq!(status[1].kind, "macos");
+        assert_eq!((status[1].size, status[1].misses), (0, 1));
+        assert_eq!(pool.warm("linux").len(), 2);
+    }
+
+    #[test]
+    fn test_shrinking_returns_surplus_slots() {
+        let mut pool = CocoonPool::new(&HashMap::from([("linux".to_string(), 3)]));
+        let slots = boot_all(&mut pool, "linux");
+        assert!(pool.take_changed());
+        assert!(!pool.take_changed());
+
+        assert_eq!(
+            pool.set_size("linux", 1),
+            vec![slots[2].name.clone(), slots[1].name.clone()]
+        );
+        assert!(pool.take_changed());
+        assert_eq!(pool.claim("linux"), Some(slots[0].clone()));
+
+        // A slot that finishes booting after the pool shrank is not kept
+        pool.set_size("linux", 1);
+        let late = pool.warm("linux");
+        pool.set_size("linux", 0);
+        assert!(!pool.warmed("linux", &late[0].name, true));
+        assert!(!pool.warmed("linux", "cocoon-pool-linux-unknown", true));
+    }
+
+    #[test]
+    fn test_size_is_capped() {
+        let mut pool = CocoonPool::new(&HashMap::from([("linux".to_string(), 1000)]));
+        assert_eq!(pool.status()[0].size, MAX_POOL_SIZE);
+        assert_eq!(pool.warm("linux").len(), MAX_POOL_SIZE as usize);
+    }
+}
@@ -0,0 +1,329 @@
+//! Crash Reports
+//!
+//! Keeps the last stdout/stderr of every service in a ring buffer bounded by
+//! bytes, so the output leading up to a crash is still around after the log
+//! files rotated. The buffer is cleared when a service starts; a `Crashed`
+//! event snapshots it together with the exit code, signal and environment
+//! fingerprint the service manager put in the event's `details`. The latest
+//! report per service is served by `GetCrashReport`.
+
+use crate::observability::{LogStream, ObservabilityEvent, ServiceEventType};
+use chrono::{DateTime, Utc};
+use lib_hive_daemon_client::{CrashOutputLine, CrashReport};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::process::ExitStatus;
+use std::sync::Mutex;
+
+/// Hash of the environment a service was started with. Values are hashed,
+/// never stored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvFingerprint {
+    /// First 16 hex digits of the SHA-256 of the sorted `KEY=VALUE` lines
+    pub hash: String,
+    /// Sorted variable names
+    pub vars: Vec<String>,
+}
+
+impl EnvFingerprint {
+    pub fn of(env: &HashMap<String, String>) -> Self {
+        let mut vars: Vec<&String> = env.keys().collect();
+        vars.sort();
+        let mut hasher = Sha256::new();
+        for key in &vars {
+            hasher.update(key.as_bytes());
+            hasher.update(b"=");
+            hasher.update(env[*key].as_bytes());
+            hasher.update(b"\n");
+        }
+        let mut hash = hex::encode(hasher.finalize());
+        hash.truncate(16);
+        Self {
+            hash,
+            vars: vars.into_iter().cloned().collect(),
+        }
+    }
+}
+
+/// What the service manager knows about a crash, carried in the `details`
+/// of the `Crashed` service event
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrashContext {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<EnvFingerprint>,
+    #[serde(default)]
+    pub restart_count: u32,
+}
+
+impl CrashContext {
+    /// Context of a process that exited with `status`. A process killed by a
+    /// signal has no exit code; the signal comes from the wait status, never
+    /// from the code (a shell exiting with 130 was not itself killed).
+    pub fn exited(status: &ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = {
+            use std::os::unix::process::ExitStatusExt;
+            status.signal()
+        };
+        #[cfg(not(unix))]
+        let signal = None;
+        Self {
+            exit_code: status.code(),
+            signal,
+            ..Default::default()
+        }
+    }
+
+    /// Context of a start that failed before or while launching the process
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self {
+            error: Some(error.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn into_details(self) -> HashMap<String, serde_json::Value> {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(map)) => map.into_iter().collect(),
+            _ => HashMap::new(),
+        }
+    }
+
+    fn from_details(details: &HashMap<String, serde_json::Value>) -> Self {
+        let map: serde_json::Map<_, _> = details
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        serde_json::from_value(serde_json::Value::Object(map)).unwrap_or_default()
+    }
+}
+
+/// Recent output of one service
+#[derive(Default)]
+struct OutputRing {
+    lines: VecDeque<CrashOutputLine>,
+    bytes: usize,
+    /// Lines were dropped since the service started
+    truncated: bool,
+}
+
+impl OutputRing {
+    fn push(&mut self, mut line: CrashOutputLine, capacity: usize) {
+        if line.line.len() > capacity {
+            let mut end = capacity;
+            while !line.line.is_char_boundary(end) {
+                end -= 1;
+            }
+            line.line.truncate(end);
+            self.truncated = true;
+        }
+        self.bytes += line.line.len();
+        self.lines.push_back(line);
+        while self.bytes > capacity {
+            let Some(dropped) = self.lines.pop_front() else {
+                break;
+            };
+            self.bytes -= dropped.line.len();
+            self.truncated = true;
+        }
+    }
+}
+
+pub struct CrashReporter {
+    /// Output bytes kept per service
+    capacity: usize,
+    state: Mutex<ReporterState>,
+}
+
+#[derive(Default)]
+struct ReporterState {
+    output: HashMap<String, OutputRing>,
+    /// Latest crash per service FQN
+    reports: HashMap<String, CrashReport>,
+}
+
+impl CrashReporter {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(ReporterState::default()),
+        }
+    }
+
+    /// Capture service output and snapshot it when a service crashes.
+    pub fn record(&self, event: &ObservabilityEvent) {
+        let mut state = self.state.lock().unwrap();
+        match event {
+            ObservabilityEvent::Log {
+                timestamp,
+                service_fqn,
+                message,
+                stream,
+                ..
+            } => {
+                let line = CrashOutputLine {
+                    timestamp: *timestamp,
+                    stream: match stream {
+                        LogStream::Stdout => "stdout",
+                        LogStream::Stderr => "stderr",
+                    }
+                    .to_string(),
+                    line: message.clone(),
+                };
+                state
+                    .output
+                    .entry(service_fqn.clone())
+                    .or_default()
+                    .push(line, self.capacity);
+            }
+            ObservabilityEvent::ServiceEvent {
+                timestamp,
+                service_fqn,
+                event,
+                details,
+            } => match event {
+                ServiceEventType::Starting => {
+                    state.output.remove(service_fqn);
+                }
+                ServiceEventType::Crashed => {
+                    let report = snapshot(
+                        service_fqn,
+                        *timestamp,
+                        CrashContext::from_details(details),
+                        state.output.get(service_fqn),
+                    );
+                    state.reports.insert(service_fqn.clone(), report);
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Latest crash of a service since the daemon started
+    pub fn get(&self, service_fqn: &str) -> Option<CrashReport> {
+        self.state.lock().unwrap().reports.get(service_fqn).cloned()
+    }
+}
+
+fn snapshot(
+    service_fqn: &str,
+    crashed_at: DateTime<Utc>,
+    context: CrashContext,
+    output: Option<&OutputRing>,
+) -> CrashReport {
+    let env = context.env.unwrap_or_default();
+    CrashReport {
+        fqn: service_fqn.to_string(),
+        crashed_at,
+        exit_code: context.exit_code,
+        signal: context.signal,
+        error: context.error,
+        output: output
+            .map(|o| o.lines.iter().cloned().collect())
+            .unwrap_or_default(),
+        truncated: output.is_some_and(|o| o.truncated),
+        env_fingerprint: (!env.hash.is_empty()).then_some(env.hash),
+        env_vars: env.vars,
+        restart_count: context.restart_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::LogLevel;
+
+    fn log(fqn: &str, message: &str, stream: LogStream) -> ObservabilityEvent {
+        ObservabilityEvent::log(fqn, LogLevel::Info, message, stream)
+    }
+
+    fn crashed(fqn: &str, context: CrashContext) -> ObservabilityEvent {
+        let mut event = ObservabilityEvent::service_event(fqn, ServiceEventType::Crashed);
+        if let ObservabilityEvent::ServiceEvent { details, .. } = &mut event {
+            *details = context.into_details();
+        }
+        event
+    }
+
+    #[test]
+    fn test_env_fingerprint() {
+        let env = HashMap::from([
+            ("PORT".to_string(), "8080".to_string()),
+            ("DATABASE_URL".to_string(), "postgres://secret".to_string()),
+        ]);
+        let fingerprint = EnvFingerprint::of(&env);
+        assert_eq!(fingerprint.hash.len(), 16);
+        assert_eq!(fingerprint.vars, vec!["DATABASE_URL", "PORT"]);
+        assert_eq!(EnvFingerprint::of(&env.clone()), fingerprint);
+
+        let mut changed = env;
+        changed.insert("PORT".to_string(), "8081".to_string());
+        assert_ne!(EnvFingerprint::of(&changed).hash, fingerprint.hash);
+    }
+
+    #[cfg(unix)]
+    fn wait_status(raw: i32) -> ExitStatus {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw(raw)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_signal() {
+        let context = CrashContext::exited(&wait_status(1 << 8));
+        assert_eq!((context.exit_code, context.signal), (Some(1), None));
+        let context = CrashContext::exited(&wait_status(130 << 8));
+        assert_eq!((context.exit_code, context.signal), (Some(130), None));
+        let context = CrashContext::exited(&wait_status(9));
+        assert_eq!((context.exit_code, context.signal), (None, Some(9)));
+    }
+
+    #[test]
+    fn test_crash_snapshots_recent_output() {
+        let reporter = CrashReporter::new(16);
+        reporter.record(&log("app:api", "stale", LogStream::Stdout));
+        reporter.record(&ObservabilityEvent::service_event(
+            "app:api",
+            ServiceEventType::Starting,
+        ));
+        reporter.record(&log("app:api", "listening", LogStream::Stdout));
+        reporter.record(&log("app:api", "out of memory", LogStream::Stderr));
+        reporter.record(&log("app:db", "ready", LogStream::Stdout));
+        assert!(reporter.get("app:api").is_none());
+
+        let mut context = CrashContext {
+            exit_code: Some(137),
+            ..Default::default()
+        };
+        context.env = Some(EnvFingerprint::of(&HashMap::from([(
+            "PORT".to_string(),
+            "8080".to_string(),
+        )])));
+        reporter.record(&crashed("app:api", context));
+
+        let report = reporter.get("app:api").unwrap();
+        assert_eq!(report.exit_code, Some(137));
+        assert_eq!(report.env_vars, vec!["PORT"]);
+        assert!(report.env_fingerprint.is_some());
+        // "listening" no longer fits next to "out of memory"
+        assert!(report.truncated);
+        assert_eq!(report.output.len(), 1);
+        assert_eq!(report.output[0].stream, "stderr");
+        assert_eq!(report.output[0].line, "out of memory");
+
+        reporter.record(&crashed("app:db", CrashContext::failed("build failed")));
+        let report = reporter.get("app:db").unwrap();
+        assert_eq!(report.error.as_deref(), Some("build failed"));
+        assert_eq!(report.exit_code, None);
+        assert!(report.env_fingerprint.is_none());
+        assert!(!report.truncated);
+    }
+}
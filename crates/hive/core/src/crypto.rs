@@ -1,8 +1,18 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Nonce,
+};
 use hmac::{Hmac, Mac};
+use rand::RngCore;
 use sha2::Sha256;
 
 type HmacSha256 = Hmac<Sha256>;
 
+const NONCE_SIZE: usize = 12;
+pub const SALT_SIZE: usize = 16;
+pub const PBKDF2_ITERATIONS: u32 = 100_000;
+
 pub fn hmac_sign(data: &str, secret: &str) -> anyhow::Result<String> {
     anyhow::ensure!(!secret.is_empty(), "HMAC secret must not be empty");
 
@@ -12,6 +22,56 @@ pub fn hmac_sign(data: &str, secret: &str) -> anyhow::Result<String> {
     Ok(hex::encode(mac.finalize().into_bytes()))
 }
 
+/// Derive a 32-byte key from a passphrase with PBKDF2-HMAC-SHA256.
+pub fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> anyhow::Result<[u8; 32]> {
+    anyhow::ensure!(!passphrase.is_empty(), "Passphrase must not be empty");
+    anyhow::ensure!(iterations > 0, "PBKDF2 iterations must be positive");
+
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    Ok(key)
+}
+
+pub fn random_salt() -> [u8; SALT_SIZE] {
+    let mut salt = [0u8; SALT_SIZE];
+    rand::rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// Encrypt with ChaCha20-Poly1305; returns base64(nonce || ciphertext).
+pub fn seal(key: &[u8; 32], plaintext: &[u8]) -> anyhow::Result<String> {
+    let cipher = ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("Failed to create cipher: {}", e))?;
+
+    let mut nonce_bytes = [0u8; NONCE_SIZE];
+    rand::rng().fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .map_err(|e| anyhow::anyhow!("Encryption failed: {}", e))?;
+
+    let mut combined = nonce_bytes.to_vec();
+    combined.extend(ciphertext);
+    Ok(BASE64.encode(combined))
+}
+
+/// Inverse of [`seal`]. Fails on a wrong key or tampered data.
+pub fn open(key: &[u8; 32], sealed: &str) -> anyhow::Result<Vec<u8>> {
+    let combined = BASE64
+        .decode(sealed)
+        .map_err(|e| anyhow::anyhow!("Invalid base64: {}", e))?;
+    anyhow::ensure!(combined.len() > NONCE_SIZE, "Sealed data too short");
+
+    let (nonce_bytes, ciphertext) = combined.split_at(NONCE_SIZE);
+    let cipher = ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| anyhow::anyhow!("Failed to create cipher: {}", e))?;
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Decryption failed: wrong passphrase or corrupted data"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -40,4 +100,21 @@ mod tests {
         assert!(hmac_sign("data", "").is_err());
         assert!(hmac_sign("", "").is_err());
     }
+
+    #[test]
+    fn test_seal_roundtrip() {
+        let salt = random_salt();
+        let key = derive_key("passphrase", &salt, 1_000).unwrap();
+
+        let sealed = seal(&key, b"DATABASE_URL=postgres://").unwrap();
+        assert_eq!(open(&key, &sealed).unwrap(), b"DATABASE_URL=postgres://");
+
+        let wrong = derive_key("other", &salt, 1_000).unwrap();
+        assert!(open(&wrong, &sealed).is_err());
+    }
+
+    #[test]
+    fn test_derive_key_rejects_empty_passphrase() {
+        assert!(derive_key("", &random_salt(), 1_000).is_err());
+    }
 }
@@ -1,8 +1,18 @@
+use crate::crash_reports::CrashReporter;
 use crate::daemon_defaults;
 use crate::dns::{self, DnsConfig, DnsServer};
 use crate::exposure::ExposureManager;
-use crate::observability::{EventCollector, EventSubscription, LogBuffer, LogLevel, LogLine};
+use crate::log_interleaver::LogInterleaver;
+use crate::maintenance::Maintenance;
+use crate::observability::{
+    EventCollector, EventSubscription, HealthStatus, LogBuffer, LogLevel, LogLine,
+    ObservabilityEvent, ServiceEventType, SOURCE_RELOADED_EVENT,
+};
+use crate::port_allocations;
+use crate::service_manager::{healthcheck_from_probe, BlueGreenPhase, SourceProgress};
+use crate::service_metrics::ServiceMetricsTracker;
 use crate::service_proxy::start_service_proxy_server;
+use crate::snapshot;
 use crate::source_manager::{SourceInfo, SourceManager, SourceStatus};
 use anyhow::{anyhow, Result};
 use lib_daemon_core::{
@@ -12,19 +22,20 @@ use std::collections::HashMap;
 use std::net::Ipv4Addr;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 pub use lib_hive_daemon_client::{
-    DaemonClient, DaemonRequest, DaemonResponse, DaemonStatus,
+    DaemonClient, DaemonClientError, DaemonRequest, DaemonResponse, DaemonStatus, ExposeEdgeInfo as WireExposeEdgeInfo,
+    CrashReport, MaintenanceStatus, PortAllocation, ServiceMetrics as WireServiceMetrics, SingletonStatus,
     ExposedServiceInfo as WireExposedServiceInfo, LogLine as WireLogLine, LogStreamHandle,
-    ServiceStatus as WireServiceStatus, ServiceStreamHandle, SourceInfo as WireSourceInfo,
-    SourceStatus as WireSourceStatus, SourceType as WireSourceType,
+    ServiceStatus as WireServiceStatus, ServiceStreamHandle, StatusStreamHandle, StatusUpdate, EventStreamHandle, HiveEvent, SourceEnv as WireSourceEnv, SourceInfo as WireSourceInfo,
+    SourceStatus as WireSourceStatus, SourceType as WireSourceType, RestartStrategy, RestoreReport,
 };
+use lib_hive_daemon_client::{FrameReader, FrameWriter, OperationProgress, WireFormat};
 
-type Writer = Arc<tokio::sync::Mutex<tokio::net::unix::OwnedWriteHalf>>;
+type Writer = Arc<tokio::sync::Mutex<FrameWriter<tokio::net::unix::OwnedWriteHalf>>>;
 
 pub struct DaemonConfig {
     base: BaseDaemonConfig,
@@ -98,9 +109,14 @@ struct ClientContext {
     exposure_manager: Arc<ExposureManager>,
     event_collector: Arc<EventCollector>,
     log_buffer: Arc<LogBuffer>,
+    service_metrics: Arc<ServiceMetricsTracker>,
+    crash_reports: Arc<CrashReporter>,
     shutdown_handle: lib_daemon_core::ShutdownHandle,
     start_time: std::time::Instant,
     proxy_addresses: Vec<String>,
+    maintenance: Maintenance,
+    /// Whether a signaling connection exists to drain cocoons through
+    can_drain: bool,
 }
 
 pub struct HiveDaemon {
@@ -109,6 +125,8 @@ pub struct HiveDaemon {
     exposure_manager: Arc<ExposureManager>,
     event_collector: Arc<EventCollector>,
     log_buffer: Arc<LogBuffer>,
+    service_metrics: Arc<ServiceMetricsTracker>,
+    crash_reports: Arc<CrashReporter>,
     shutdown_coordinator: tokio::sync::Mutex<Option<ShutdownCoordinator>>,
     start_time: std::time::Instant,
     dns_server: Option<Arc<DnsServer>>,
@@ -127,10 +145,12 @@ impl HiveDaemon {
 
         Self {
             config,
+            exposure_manager: source_manager.exposure_manager().clone(),
             source_manager,
-            exposure_manager: Arc::new(ExposureManager::new()),
             event_collector,
             log_buffer: Arc::new(LogBuffer::new(daemon_defaults::LOG_BUFFER_CAPACITY)),
+            service_metrics: Arc::new(ServiceMetricsTracker::new()),
+            crash_reports: Arc::new(CrashReporter::new(daemon_defaults::CRASH_OUTPUT_BYTES)),
             shutdown_coordinator: tokio::sync::Mutex::new(Some(ShutdownCoordinator::new())),
             start_time: std::time::Instant::now(),
             dns_server,
@@ -163,6 +183,11 @@ impl HiveDaemon {
             .write()
             .map_err(|e| anyhow!("Failed to write PID file: {}", e))?;
 
+        // Singletons wait for leader election before sources start them
+        if self.config.signaling.is_some() {
+            self.source_manager.singletons().coordinate();
+        }
+
         self.source_manager.init().await?;
 
         // Create virtual source for dynamic cocoon services if signaling is configured
@@ -229,6 +254,17 @@ impl HiveDaemon {
         let log_buffer = self.log_buffer.clone();
         let event_collector = self.event_collector.clone();
         tokio::spawn(populate_log_buffer(event_collector, log_buffer));
+        tokio::spawn(track_service_metrics(
+            self.event_collector.clone(),
+            self.service_metrics.clone(),
+        ));
+        tokio::spawn(track_crash_reports(
+            self.event_collector.clone(),
+            self.crash_reports.clone(),
+        ));
+
+        tokio::spawn(self.source_manager.clone().watch_expose_changes());
+        tokio::spawn(self.source_manager.clone().watch_source_files());
 
         let socket_path = self.config.socket_path();
         info!("Hive daemon starting on socket: {}", socket_path.display());
@@ -247,12 +283,16 @@ impl HiveDaemon {
             .ok_or_else(|| anyhow!("Daemon already started"))?;
         let shutdown_handle = shutdown_coordinator.handle();
 
+        let maintenance = Maintenance::new();
+
         // Spawn signaling connection for remote cocoon management
         let signaling_handle = if let Some(signaling_config) = self.config.signaling.clone() {
             let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
             let sm = self.source_manager.clone();
+            let maintenance = maintenance.clone();
             let handle = tokio::spawn(async move {
-                crate::hive_signaling::run_signaling_loop(signaling_config, sm, shutdown_rx).await;
+                crate::hive_signaling::run_signaling_loop(signaling_config, sm, maintenance, shutdown_rx)
+                    .await;
             });
             Some((handle, shutdown_tx))
         } else {
@@ -264,9 +304,13 @@ impl HiveDaemon {
             exposure_manager: self.exposure_manager.clone(),
             event_collector: self.event_collector.clone(),
             log_buffer: self.log_buffer.clone(),
+            service_metrics: self.service_metrics.clone(),
+            crash_reports: self.crash_reports.clone(),
             shutdown_handle,
             start_time: self.start_time,
             proxy_addresses: self.config.proxy_bind.clone(),
+            can_drain: signaling_handle.is_some(),
+            maintenance,
         });
 
         loop {
@@ -366,6 +410,70 @@ fn to_wire_log_line(line: &LogLine) -> WireLogLine {
     }
 }
 
+/// The subscriber-facing event for an observability event, if it is one
+/// that `SubscribeEvents` reports.
+fn to_hive_event(event: &ObservabilityEvent) -> Option<HiveEvent> {
+    match event {
+        ObservabilityEvent::ServiceEvent {
+            service_fqn, event, ..
+        } => {
+            let fqn = service_fqn.clone();
+            match event {
+                ServiceEventType::Started => Some(HiveEvent::ServiceStarted { fqn }),
+                ServiceEventType::Stopped => Some(HiveEvent::ServiceStopped { fqn }),
+                ServiceEventType::Crashed => Some(HiveEvent::ServiceCrashed { fqn }),
+                _ => None,
+            }
+        }
+        // Health check events are only emitted when a service's health flips
+        ObservabilityEvent::HealthCheck {
+            service_fqn,
+            status,
+            ..
+        } => {
+            let healthy = match status {
+                HealthStatus::Healthy => true,
+                HealthStatus::Unhealthy => false,
+                HealthStatus::Unknown => return None,
+            };
+            Some(HiveEvent::HealthChanged {
+                fqn: service_fqn.clone(),
+                healthy,
+            })
+        }
+        ObservabilityEvent::Custom {
+            service_fqn,
+            event_name,
+            data,
+            ..
+        } if event_name == SOURCE_RELOADED_EVENT => {
+            let names = |key: &str| -> Vec<String> {
+                data.get(key)
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or_default()
+            };
+            Some(HiveEvent::SourceReloaded {
+                source: service_fqn.clone(),
+                added: names("added"),
+                removed: names("removed"),
+                changed: names("changed"),
+                auto: data.get("auto").and_then(|v| v.as_bool()).unwrap_or(false),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn hive_event_source(event: &HiveEvent) -> &str {
+    match event {
+        HiveEvent::SourceReloaded { source, .. } => source,
+        HiveEvent::ServiceStarted { fqn }
+        | HiveEvent::ServiceStopped { fqn }
+        | HiveEvent::ServiceCrashed { fqn }
+        | HiveEvent::HealthChanged { fqn, .. } => fqn.split(':').next().unwrap_or(fqn),
+    }
+}
+
 fn to_wire_source_info(s: SourceInfo) -> WireSourceInfo {
     WireSourceInfo {
         name: s.name,
@@ -382,24 +490,59 @@ fn to_wire_source_info(s: SourceInfo) -> WireSourceInfo {
             SourceStatus::Stopped => WireSourceStatus::Stopped,
             SourceStatus::Error(e) => WireSourceStatus::Error(e),
         },
+        auto_reload: s.auto_reload,
     }
 }
 
+/// `allocations` are the daemon's port allocations, which the service's
+/// ports are checked against for conflicts.
 fn build_wire_service_status(
     source_name: &str,
     info: &crate::hive_config::ServiceInfo,
+    allocations: &[PortAllocation],
 ) -> WireServiceStatus {
+    let fqn = format!("{}:{}", source_name, info.name);
     WireServiceStatus {
-        fqn: format!("{}:{}", source_name, info.name),
+        port_conflicts: port_allocations::conflicts(&fqn, &info.ports, allocations),
+        fqn,
         source: source_name.to_string(),
         name: info.name.clone(),
         state: info.state.to_string(),
         healthy: info.healthy,
         pid: info.pid,
-        container_id: None,
+        container_id: info.container_id.clone(),
         started_at: None,
         ports: info.ports.clone(),
         restart_count: info.restart_count,
+        last_probe: info.last_probe.clone(),
+    }
+}
+
+fn build_wire_service_metrics(
+    source_name: &str,
+    info: &crate::hive_config::ServiceInfo,
+    service_metrics: &ServiceMetricsTracker,
+) -> WireServiceMetrics {
+    let fqn = format!("{}:{}", source_name, info.name);
+    let running = matches!(
+        info.state,
+        crate::hive_config::ServiceState::Running | crate::hive_config::ServiceState::Unhealthy
+    );
+    let usage = if running {
+        service_metrics.usage(&fqn, info.pid)
+    } else {
+        Default::default()
+    };
+    WireServiceMetrics {
+        fqn,
+        state: info.state.to_string(),
+        pid: info.pid,
+        cpu_percent: usage.cpu_percent,
+        memory_rss_bytes: usage.memory_rss_bytes,
+        open_fds: usage.open_fds,
+        restart_count: info.restart_count,
+        uptime_secs: usage.uptime_secs,
+        sampled_at: chrono::Utc::now(),
     }
 }
 
@@ -407,11 +550,7 @@ fn build_wire_service_status(
 
 /// Serialize and send a response over the writer.
 async fn send_response(writer: &Writer, response: &DaemonResponse) -> Result<()> {
-    let json = serde_json::to_string(response)?;
-    let mut w = writer.lock().await;
-    w.write_all(json.as_bytes()).await?;
-    w.write_all(b"\n").await?;
-    Ok(())
+    Ok(writer.lock().await.send(response).await?)
 }
 
 /// Map a `Result<()>` into a `DaemonResponse` with consistent error formatting.
@@ -427,38 +566,64 @@ fn ok_or_error(result: Result<()>, code: &str, ok_msg: String) -> DaemonResponse
     }
 }
 
+/// Whether an `UpdateService` patch changes nothing besides its health probe
+fn is_empty_patch(patch: &serde_json::Value) -> bool {
+    match patch {
+        serde_json::Value::Null => true,
+        serde_json::Value::Object(fields) => fields.is_empty(),
+        _ => false,
+    }
+}
+
 // --- Client handling ---
 
 async fn handle_client(stream: UnixStream, ctx: &ClientContext) -> Result<()> {
     let (reader, writer) = stream.into_split();
-    let mut reader = BufReader::new(reader);
-    let writer: Writer = Arc::new(tokio::sync::Mutex::new(writer));
-    let mut line = String::new();
+    let mut reader = FrameReader::new(reader);
+    let writer: Writer = Arc::new(tokio::sync::Mutex::new(FrameWriter::new(writer)));
     let mut active_streams = ActiveStreams::new();
 
     loop {
-        line.clear();
-        let bytes_read = reader.read_line(&mut line).await?;
-
-        if bytes_read == 0 {
+        if !reader.read_frame().await? {
             active_streams.cancel_all().await;
             break;
         }
 
-        let request: DaemonRequest = match serde_json::from_str(line.trim()) {
+        let request: DaemonRequest = match reader.decode() {
             Ok(req) => req,
             Err(e) => {
                 let response = DaemonResponse::Error {
                     code: "INVALID_REQUEST".to_string(),
-                    message: format!("Invalid JSON: {}", e),
+                    message: format!("{:#}", e),
                 };
                 send_response(&writer, &response).await?;
                 continue;
             }
         };
 
+        if ctx.maintenance.is_on() && starts_work(&request) {
+            send_response(&writer, &maintenance_refusal()).await?;
+            continue;
+        }
+
         match request {
-            DaemonRequest::StreamLogs { fqn, level } => {
+            DaemonRequest::Hello { formats } => {
+                let format = if formats.contains(&WireFormat::Binary) {
+                    WireFormat::Binary
+                } else {
+                    WireFormat::Json
+                };
+
+                // Reply in the current format, then switch both directions
+                let mut w = writer.lock().await;
+                w.send(&DaemonResponse::Hello { format }).await?;
+                w.set_format(format);
+                reader.set_format(format);
+                debug!("Client negotiated {:?} wire format", format);
+                continue;
+            }
+
+            DaemonRequest::StreamLogs { fqn, fqns, level } => {
                 let stream_id = Uuid::new_v4();
                 let (cancel_tx, cancel_rx) = tokio::sync::mpsc::channel(1);
                 active_streams.add(stream_id, cancel_tx);
@@ -467,9 +632,10 @@ async fn handle_client(stream: UnixStream, ctx: &ClientContext) -> Result<()> {
 
                 let writer = writer.clone();
                 let event_collector = ctx.event_collector.clone();
+                let services = fqn.into_iter().chain(fqns).collect();
                 tokio::spawn(stream_logs(
                     stream_id,
-                    fqn,
+                    services,
                     level,
                     event_collector,
                     writer,
@@ -499,8 +665,163 @@ async fn handle_client(stream: UnixStream, ctx: &ClientContext) -> Result<()> {
                 continue;
             }
 
+            DaemonRequest::SubscribeEvents { source } => {
+                let stream_id = Uuid::new_v4();
+                let (cancel_tx, cancel_rx) = tokio::sync::mpsc::channel(1);
+                active_streams.add(stream_id, cancel_tx);
+
+                send_response(&writer, &DaemonResponse::StreamStarted { stream_id }).await?;
+
+                let writer = writer.clone();
+                let event_collector = ctx.event_collector.clone();
+                tokio::spawn(stream_events(
+                    stream_id,
+                    source,
+                    event_collector,
+                    writer,
+                    cancel_rx,
+                ));
+                continue;
+            }
+
+            DaemonRequest::StreamStatus { interval_ms } => {
+                let stream_id = Uuid::new_v4();
+                let (cancel_tx, cancel_rx) = tokio::sync::mpsc::channel(1);
+                active_streams.add(stream_id, cancel_tx);
+
+                send_response(&writer, &DaemonResponse::StreamStarted { stream_id }).await?;
+
+                let interval = interval_ms
+                    .map(std::time::Duration::from_millis)
+                    .unwrap_or(daemon_defaults::STATUS_STREAM_INTERVAL)
+                    .max(daemon_defaults::STATUS_STREAM_MIN_INTERVAL);
+                let writer = writer.clone();
+                let source_manager = ctx.source_manager.clone();
+                let proxy_addresses = ctx.proxy_addresses.clone();
+                let maintenance = ctx.maintenance.clone();
+                let start_time = ctx.start_time;
+                tokio::spawn(async move {
+                    stream_status(
+                        stream_id,
+                        interval,
+                        &source_manager,
+                        start_time,
+                        &proxy_addresses,
+                        &maintenance,
+                        writer,
+                        cancel_rx,
+                    )
+                    .await
+                });
+                continue;
+            }
+
+            DaemonRequest::StartSource {
+                name,
+                progress: true,
+            } => {
+                let (tx, forwarder) = spawn_progress_forwarder(writer.clone(), source_progress);
+                let result = ctx
+                    .source_manager
+                    .start_source_with_progress(&name, |p| {
+                        let _ = tx.send(p);
+                    })
+                    .await;
+                drop(tx);
+                let _ = forwarder.await;
+
+                let response = ok_or_error(
+                    result,
+                    "START_SOURCE_FAILED",
+                    format!("Started source: {}", name),
+                );
+                send_response(&writer, &response).await?;
+                continue;
+            }
+
+            DaemonRequest::StopSource {
+                name,
+                progress: true,
+            } => {
+                let (tx, forwarder) = spawn_progress_forwarder(writer.clone(), source_progress);
+                let result = ctx
+                    .source_manager
+                    .stop_source_with_progress(&name, |p| {
+                        let _ = tx.send(p);
+                    })
+                    .await;
+                drop(tx);
+                let _ = forwarder.await;
+
+                let response = ok_or_error(
+                    result,
+                    "STOP_SOURCE_FAILED",
+                    format!("Stopped source: {}", name),
+                );
+                send_response(&writer, &response).await?;
+                continue;
+            }
+
+            DaemonRequest::RestartService {
+                fqn,
+                strategy: RestartStrategy::BlueGreen { drain_seconds },
+                progress: true,
+            } => {
+                let (tx, forwarder) = spawn_progress_forwarder(writer.clone(), blue_green_progress);
+                let result = ctx
+                    .source_manager
+                    .restart_service_blue_green(
+                        &fqn,
+                        std::time::Duration::from_secs(drain_seconds as u64),
+                        |p| {
+                            let _ = tx.send(p);
+                        },
+                    )
+                    .await;
+                drop(tx);
+                let _ = forwarder.await;
+
+                let response = ok_or_error(
+                    result,
+                    "RESTART_SERVICE_FAILED",
+                    format!("Restarted service: {}", fqn),
+                );
+                send_response(&writer, &response).await?;
+                continue;
+            }
+
+            DaemonRequest::SetMaintenance { on, reason, drain } => {
+                ctx.maintenance.set(on, reason, drain);
+                let message = match (on, drain) {
+                    (false, _) => "Maintenance mode off".to_string(),
+                    (true, false) => "Maintenance mode on".to_string(),
+                    (true, true) if ctx.can_drain => {
+                        "Maintenance mode on, draining cocoons to other hives".to_string()
+                    }
+                    (true, true) => {
+                        "Maintenance mode on; no signaling connection, so no cocoons are drained"
+                            .to_string()
+                    }
+                };
+                info!("{}", message);
+                send_response(&writer, &DaemonResponse::Ok { message: Some(message) }).await?;
+                continue;
+            }
+
+            DaemonRequest::Batch { requests } => {
+                let maintenance = ctx.maintenance.is_on();
+                let responses = run_batch(requests, |request| {
+                    process_batched_request(request, ctx, maintenance)
+                })
+                .await;
+                send_response(&writer, &DaemonResponse::Batch { responses }).await?;
+                continue;
+            }
+
             DaemonRequest::StopLogStream { stream_id }
-            | DaemonRequest::StopServiceStream { stream_id } => {
+            | DaemonRequest::StopServiceStream { stream_id }
+            | DaemonRequest::StopStatusStream { stream_id }
+            | DaemonRequest::StopEventStream { stream_id } => {
                 let response = if active_streams.remove(&stream_id) {
                     DaemonResponse::StreamEnded { stream_id }
                 } else {
@@ -521,9 +842,12 @@ async fn handle_client(stream: UnixStream, ctx: &ClientContext) -> Result<()> {
             &ctx.source_manager,
             &ctx.exposure_manager,
             &ctx.log_buffer,
+            &ctx.service_metrics,
+            &ctx.crash_reports,
             &ctx.shutdown_handle,
             ctx.start_time,
             &ctx.proxy_addresses,
+            &ctx.maintenance,
         )
         .await;
 
@@ -535,43 +859,115 @@ async fn handle_client(stream: UnixStream, ctx: &ClientContext) -> Result<()> {
 
 // --- Streaming ---
 
+/// Forward progress of one operation to the client as `OperationProgress`.
+///
+/// Progress callbacks are synchronous, so updates go through a channel; drop
+/// the sender and await the handle before sending the final response.
+fn spawn_progress_forwarder<P: Send + 'static>(
+    writer: Writer,
+    to_progress: fn(&P) -> (u32, u32, String),
+) -> (
+    tokio::sync::mpsc::UnboundedSender<P>,
+    tokio::task::JoinHandle<()>,
+) {
+    let op_id = Uuid::new_v4();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<P>();
+
+    let handle = tokio::spawn(async move {
+        while let Some(p) = rx.recv().await {
+            let (step, total, message) = to_progress(&p);
+            let response = DaemonResponse::OperationProgress(OperationProgress {
+                op_id,
+                step,
+                total,
+                message,
+            });
+            if send_response(&writer, &response).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    (tx, handle)
+}
+
+fn source_progress(p: &SourceProgress) -> (u32, u32, String) {
+    (
+        p.done as u32,
+        p.total as u32,
+        format!("{}: {}", p.service, p.phase),
+    )
+}
+
+fn blue_green_progress(p: &BlueGreenPhase) -> (u32, u32, String) {
+    (p.step(), BlueGreenPhase::TOTAL_STEPS, p.to_string())
+}
+
+/// Stream log lines of `services` (all services if empty). When more than
+/// one service can contribute, lines are interleaved by timestamp.
 async fn stream_logs(
     stream_id: Uuid,
-    fqn: Option<String>,
+    services: Vec<String>,
     level: Option<String>,
     event_collector: Arc<EventCollector>,
     writer: Writer,
     mut cancel_rx: tokio::sync::mpsc::Receiver<()>,
 ) {
     let min_level = level.and_then(|l| l.parse::<LogLevel>().ok());
+    let mut interleaver =
+        (services.len() != 1).then(|| LogInterleaver::new(daemon_defaults::LOG_INTERLEAVE_WINDOW));
 
     let subscription = EventSubscription {
         event_types: vec!["log".to_string()],
-        services: fqn.map(|f| vec![f]).unwrap_or_default(),
+        services,
         min_log_level: min_level,
     };
 
     let mut receiver = event_collector.subscribe(subscription);
+    let mut release = tokio::time::interval(daemon_defaults::LOG_INTERLEAVE_WINDOW);
+    let mut ended = false;
 
-    loop {
-        tokio::select! {
+    'stream: loop {
+        let mut lines = tokio::select! {
             result = receiver.recv() => {
                 match result {
-                    Ok(event) => {
-                        if let Some(log_line) = Option::<LogLine>::from(&event) {
-                            let response = DaemonResponse::LogStream {
-                                stream_id,
-                                line: to_wire_log_line(&log_line),
-                            };
-                            if send_response(&writer, &response).await.is_err() {
-                                break;
-                            }
-                        }
+                    Ok(event) => match Option::<LogLine>::from(&event) {
+                        Some(log_line) => vec![log_line],
+                        None => continue,
+                    },
+                    Err(_) => {
+                        ended = true;
+                        Vec::new()
                     }
-                    Err(_) => break,
                 }
             }
+            _ = release.tick(), if interleaver.as_ref().is_some_and(|i| !i.is_empty()) => Vec::new(),
             _ = cancel_rx.recv() => break,
+        };
+
+        if let Some(interleaver) = interleaver.as_mut() {
+            for log_line in lines {
+                interleaver.push(log_line);
+            }
+            lines = if ended {
+                interleaver.drain_all()
+            } else {
+                interleaver.drain_ready(chrono::Utc::now())
+            };
+        }
+
+        for log_line in lines {
+            let response = DaemonResponse::LogStream {
+                stream_id,
+                line: to_wire_log_line(&log_line),
+            };
+            if send_response(&writer, &response).await.is_err() {
+                break 'stream;
+            }
+        }
+
+        if ended {
+            break;
         }
     }
 
@@ -584,11 +980,12 @@ async fn send_service_snapshot(
     source: Option<&str>,
     stream_id: Uuid,
 ) -> bool {
+    let allocations = source_manager.port_allocations().await;
     let services: Vec<WireServiceStatus> = source_manager
         .list_services(source)
         .await
         .into_iter()
-        .map(|(source_name, info)| build_wire_service_status(&source_name, &info))
+        .map(|(source_name, info)| build_wire_service_status(&source_name, &info, &allocations))
         .collect();
 
     let response = DaemonResponse::ServiceStatusUpdate {
@@ -647,40 +1044,170 @@ async fn stream_service_status(
     let _ = send_response(&writer, &DaemonResponse::StreamEnded { stream_id }).await;
 }
 
+async fn stream_events(
+    stream_id: Uuid,
+    source: Option<String>,
+    event_collector: Arc<EventCollector>,
+    writer: Writer,
+    mut cancel_rx: tokio::sync::mpsc::Receiver<()>,
+) {
+    let subscription = EventSubscription {
+        event_types: vec![
+            "service_event".to_string(),
+            "health_check".to_string(),
+            "custom".to_string(),
+        ],
+        services: Vec::new(),
+        min_log_level: None,
+    };
+
+    let mut receiver = event_collector.subscribe(subscription);
+
+    loop {
+        let event = tokio::select! {
+            result = receiver.recv() => match result {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                    // Subscribers rely on seeing every change, so end the
+                    // stream rather than silently skipping some
+                    let response = DaemonResponse::Error {
+                        code: "STREAM_LAGGED".to_string(),
+                        message: format!("Event stream fell behind and dropped {} events", count),
+                    };
+                    let _ = send_response(&writer, &response).await;
+                    break;
+                }
+                Err(_) => break,
+            },
+            _ = cancel_rx.recv() => break,
+        };
+
+        let Some(event) = to_hive_event(&event) else {
+            continue;
+        };
+        if source
+            .as_deref()
+            .is_some_and(|source| hive_event_source(&event) != source)
+        {
+            continue;
+        }
+
+        let response = DaemonResponse::Event { stream_id, event };
+        if send_response(&writer, &response).await.is_err() {
+            break;
+        }
+    }
+
+    let _ = send_response(&writer, &DaemonResponse::StreamEnded { stream_id }).await;
+}
+
+/// Send the daemon status every `interval`, with the services whose state or
+/// health changed since the previous tick (all of them on the first tick).
+#[allow(clippy::too_many_arguments)]
+async fn stream_status(
+    stream_id: Uuid,
+    interval: std::time::Duration,
+    source_manager: &SourceManager,
+    start_time: std::time::Instant,
+    proxy_addresses: &[String],
+    maintenance: &Maintenance,
+    writer: Writer,
+    mut cancel_rx: tokio::sync::mpsc::Receiver<()>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut previous: HashMap<String, (String, Option<bool>)> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = cancel_rx.recv() => break,
+        }
+
+        let status =
+            build_daemon_status(source_manager, start_time, proxy_addresses, maintenance).await;
+
+        let allocations = source_manager.port_allocations().await;
+        let services: Vec<WireServiceStatus> = source_manager
+            .list_services(None)
+            .await
+            .into_iter()
+            .map(|(source_name, info)| build_wire_service_status(&source_name, &info, &allocations))
+            .collect();
+
+        let mut current = HashMap::with_capacity(services.len());
+        let mut changed = Vec::new();
+        for service in services {
+            let key = (service.state.clone(), service.healthy);
+            if previous.get(&service.fqn) != Some(&key) {
+                changed.push(service.clone());
+            }
+            current.insert(service.fqn, key);
+        }
+        previous = current;
+
+        let response = DaemonResponse::StatusUpdate {
+            stream_id,
+            status,
+            changed,
+        };
+        if send_response(&writer, &response).await.is_err() {
+            break;
+        }
+    }
+
+    let _ = send_response(&writer, &DaemonResponse::StreamEnded { stream_id }).await;
+}
+
+async fn build_daemon_status(
+    source_manager: &SourceManager,
+    start_time: std::time::Instant,
+    proxy_addresses: &[String],
+    maintenance: &Maintenance,
+) -> DaemonStatus {
+    let sources = source_manager.list_sources().await;
+    let running_services = sources
+        .iter()
+        .filter(|s| s.status == SourceStatus::Running)
+        .map(|s| s.service_count)
+        .sum();
+    let total_services: usize = sources.iter().map(|s| s.service_count).sum();
+
+    DaemonStatus {
+        running: true,
+        pid: Some(std::process::id()),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        source_count: sources.len(),
+        running_services,
+        total_services,
+        proxy_addresses: proxy_addresses.to_vec(),
+        uptime_secs: start_time.elapsed().as_secs(),
+        maintenance: maintenance.status(),
+        singletons: source_manager.singletons().status(),
+    }
+}
+
 // --- Request processing ---
 
+#[allow(clippy::too_many_arguments)]
 async fn process_request(
     request: DaemonRequest,
     source_manager: &SourceManager,
     exposure_manager: &ExposureManager,
     log_buffer: &LogBuffer,
+    service_metrics: &ServiceMetricsTracker,
+    crash_reports: &CrashReporter,
     shutdown_handle: &lib_daemon_core::ShutdownHandle,
     start_time: std::time::Instant,
     proxy_addresses: &[String],
+    maintenance: &Maintenance,
 ) -> DaemonResponse {
     match request {
         DaemonRequest::Ping => DaemonResponse::Pong,
 
-        DaemonRequest::Status => {
-            let sources = source_manager.list_sources().await;
-            let running_services = sources
-                .iter()
-                .filter(|s| s.status == SourceStatus::Running)
-                .map(|s| s.service_count)
-                .sum();
-            let total_services: usize = sources.iter().map(|s| s.service_count).sum();
-
-            DaemonResponse::Status(DaemonStatus {
-                running: true,
-                pid: Some(std::process::id()),
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                source_count: sources.len(),
-                running_services,
-                total_services,
-                proxy_addresses: proxy_addresses.to_vec(),
-                uptime_secs: start_time.elapsed().as_secs(),
-            })
-        }
+        DaemonRequest::Status => DaemonResponse::Status(
+            build_daemon_status(source_manager, start_time, proxy_addresses, maintenance).await,
+        ),
 
         DaemonRequest::Shutdown { graceful } => {
             if graceful {
@@ -740,6 +1267,16 @@ async fn process_request(
             format!("Reloaded source: {}", name),
         ),
 
+        DaemonRequest::SetSourceAutoReload { name, enabled } => ok_or_error(
+            source_manager.set_auto_reload(&name, enabled).await,
+            "SET_AUTO_RELOAD_FAILED",
+            format!(
+                "Auto-reload {} for source: {}",
+                if enabled { "enabled" } else { "disabled" },
+                name
+            ),
+        ),
+
         DaemonRequest::EnableSource { name } => ok_or_error(
             source_manager.enable_source(&name).await,
             "ENABLE_SOURCE_FAILED",
@@ -752,13 +1289,29 @@ async fn process_request(
             format!("Disabled source: {}", name),
         ),
 
-        DaemonRequest::StartSource { name } => ok_or_error(
+        DaemonRequest::GetSourceEnv { name } => match source_manager.source_env(&name).await {
+            Ok((profile, vars)) => DaemonResponse::SourceEnv {
+                env: WireSourceEnv {
+                    source: name,
+                    env_file: profile.env_file,
+                    path_prepend: profile.path,
+                    direnv: profile.direnv,
+                    vars,
+                },
+            },
+            Err(e) => DaemonResponse::Error {
+                code: "SOURCE_ENV_FAILED".to_string(),
+                message: e.to_string(),
+            },
+        },
+
+        DaemonRequest::StartSource { name, .. } => ok_or_error(
             source_manager.start_source(&name).await,
             "START_SOURCE_FAILED",
             format!("Started source: {}", name),
         ),
 
-        DaemonRequest::StopSource { name } => ok_or_error(
+        DaemonRequest::StopSource { name, .. } => ok_or_error(
             source_manager.stop_source(&name).await,
             "STOP_SOURCE_FAILED",
             format!("Stopped source: {}", name),
@@ -776,16 +1329,30 @@ async fn process_request(
             format!("Stopped service: {}", fqn),
         ),
 
-        DaemonRequest::RestartService { fqn } => ok_or_error(
-            source_manager.restart_service(&fqn).await,
+        DaemonRequest::RestartService { fqn, strategy, .. } => ok_or_error(
+            match strategy {
+                RestartStrategy::Immediate => source_manager.restart_service(&fqn).await,
+                RestartStrategy::BlueGreen { drain_seconds } => {
+                    source_manager
+                        .restart_service_blue_green(
+                            &fqn,
+                            std::time::Duration::from_secs(drain_seconds as u64),
+                            |_| {},
+                        )
+                        .await
+                }
+            },
             "RESTART_SERVICE_FAILED",
             format!("Restarted service: {}", fqn),
         ),
 
         DaemonRequest::GetServiceStatus { fqn } => match source_manager.get_service(&fqn).await {
-            Ok(Some((source_name, info))) => DaemonResponse::Services {
-                services: vec![build_wire_service_status(&source_name, &info)],
-            },
+            Ok(Some((source_name, info))) => {
+                let allocations = source_manager.port_allocations().await;
+                DaemonResponse::Services {
+                    services: vec![build_wire_service_status(&source_name, &info, &allocations)],
+                }
+            }
             Ok(None) => DaemonResponse::Error {
                 code: "NOT_FOUND".to_string(),
                 message: format!("Service '{}' not found", fqn),
@@ -797,27 +1364,123 @@ async fn process_request(
         },
 
         DaemonRequest::ListServices { source } => {
+            let allocations = source_manager.port_allocations().await;
             let services: Vec<WireServiceStatus> = source_manager
                 .list_services(source.as_deref())
                 .await
                 .into_iter()
-                .map(|(source_name, info)| build_wire_service_status(&source_name, &info))
+                .map(|(source_name, info)| {
+                    build_wire_service_status(&source_name, &info, &allocations)
+                })
                 .collect();
 
             DaemonResponse::Services { services }
         }
 
+        DaemonRequest::GetMetrics { fqn } => {
+            let services = match fqn {
+                Some(fqn) => match source_manager.get_service(&fqn).await {
+                    Ok(Some(service)) => vec![service],
+                    Ok(None) => {
+                        return DaemonResponse::Error {
+                            code: "NOT_FOUND".to_string(),
+                            message: format!("Service '{}' not found", fqn),
+                        }
+                    }
+                    Err(e) => {
+                        return DaemonResponse::Error {
+                            code: "INVALID_FQN".to_string(),
+                            message: e.to_string(),
+                        }
+                    }
+                },
+                None => {
+                    let services = source_manager.list_services(None).await;
+                    let pids: Vec<u32> = services.iter().filter_map(|(_, info)| info.pid).collect();
+                    service_metrics.retain_pids(&pids);
+                    services
+                }
+            };
+
+            let metrics = services
+                .iter()
+                .map(|(source_name, info)| {
+                    build_wire_service_metrics(source_name, info, service_metrics)
+                })
+                .collect();
+            DaemonResponse::Metrics { metrics }
+        }
+
+        DaemonRequest::GetCrashReport { fqn } => match source_manager.get_service(&fqn).await {
+            Ok(Some(_)) => DaemonResponse::CrashReport {
+                report: crash_reports.get(&fqn),
+            },
+            Ok(None) => DaemonResponse::Error {
+                code: "NOT_FOUND".to_string(),
+                message: format!("Service '{}' not found", fqn),
+            },
+            Err(e) => DaemonResponse::Error {
+                code: "INVALID_FQN".to_string(),
+                message: e.to_string(),
+            },
+        },
+
+        DaemonRequest::ReservePort { port, owner, name } => {
+            let configured = source_manager.configured_ports().await;
+            match source_manager
+                .port_reservations()
+                .reserve(port, &owner, name, &configured)
+            {
+                Ok(allocation) => DaemonResponse::PortReserved { allocation },
+                Err(holder) => DaemonResponse::Error {
+                    code: "PORT_CONFLICT".to_string(),
+                    message: match holder.name {
+                        Some(name) => {
+                            format!("Port {} is held by {} ({})", port, holder.owner, name)
+                        }
+                        None => format!("Port {} is held by {}", port, holder.owner),
+                    },
+                },
+            }
+        }
+
+        DaemonRequest::ReleasePort { port, owner } => {
+            match source_manager.port_reservations().release(port, &owner) {
+                Ok(Some(allocation)) => DaemonResponse::Ok {
+                    message: Some(format!("Released port {} ({})", port, allocation.owner)),
+                },
+                Ok(None) => DaemonResponse::Error {
+                    code: "NOT_FOUND".to_string(),
+                    message: format!("Port {} is not reserved", port),
+                },
+                Err(holder) => DaemonResponse::Error {
+                    code: "PORT_CONFLICT".to_string(),
+                    message: format!("Port {} is reserved by {}", port, holder.owner),
+                },
+            }
+        }
+
+        DaemonRequest::ListPortAllocations => DaemonResponse::PortAllocations {
+            allocations: source_manager.port_allocations().await,
+        },
+
         DaemonRequest::CreateService {
             source_id,
             name,
             config,
+            health,
         } => {
             match serde_json::from_value::<crate::hive_config::ServiceConfig>(config) {
-                Ok(service_config) => ok_or_error(
-                    source_manager.create_service(&source_id, &name, service_config).await,
-                    "CREATE_SERVICE_FAILED",
-                    format!("Created service {}:{}", source_id, name),
-                ),
+                Ok(mut service_config) => {
+                    if let Some(probe) = &health {
+                        service_config.healthcheck = Some(healthcheck_from_probe(probe));
+                    }
+                    ok_or_error(
+                        source_manager.create_service(&source_id, &name, service_config).await,
+                        "CREATE_SERVICE_FAILED",
+                        format!("Created service {}:{}", source_id, name),
+                    )
+                }
                 Err(e) => DaemonResponse::Error {
                     code: "INVALID_CONFIG".to_string(),
                     message: format!("Invalid service config: {}", e),
@@ -825,7 +1488,20 @@ async fn process_request(
             }
         }
 
-        DaemonRequest::UpdateService { fqn, patch: _ } => DaemonResponse::Error {
+        // Only the health probe can be updated so far
+        DaemonRequest::UpdateService {
+            fqn,
+            patch,
+            health: Some(probe),
+        } if is_empty_patch(&patch) => ok_or_error(
+            source_manager
+                .set_healthcheck(&fqn, healthcheck_from_probe(&probe))
+                .await,
+            "UPDATE_SERVICE_FAILED",
+            format!("Updated health probe of {}", fqn),
+        ),
+
+        DaemonRequest::UpdateService { fqn, .. } => DaemonResponse::Error {
             code: "NOT_IMPLEMENTED".to_string(),
             message: format!("UpdateService not yet implemented for '{}'", fqn),
         },
@@ -847,11 +1523,38 @@ async fn process_request(
                     healthy: e.healthy,
                     var_names: e.vars.keys().cloned().collect(),
                     port_names: e.ports.keys().cloned().collect(),
+                    ports: e.ports,
                 })
                 .collect();
             DaemonResponse::Exposed { exposed: info }
         }
 
+        DaemonRequest::ExposeGraph => {
+            let sources: Vec<(String, crate::hive_config::HiveConfig)> = source_manager
+                .source_configs()
+                .await
+                .into_iter()
+                .filter_map(|(info, config)| config.map(|c| (info.name, c)))
+                .collect();
+
+            let exposed = exposure_manager.list_exposed().await;
+            let edges: Vec<WireExposeEdgeInfo> = crate::exposure::expose_graph(&sources)
+                .into_iter()
+                .map(|e| {
+                    let resolved = exposed
+                        .iter()
+                        .any(|x| x.provider_fqn() == e.provider && x.healthy);
+                    WireExposeEdgeInfo {
+                        consumer: e.consumer,
+                        provider: e.provider,
+                        vars: e.vars,
+                        resolved,
+                    }
+                })
+                .collect();
+            DaemonResponse::ExposeGraph { edges }
+        }
+
         DaemonRequest::GetLogs {
             fqn,
             lines,
@@ -896,16 +1599,140 @@ async fn process_request(
             }
         }
 
+        DaemonRequest::Snapshot {
+            include_logs,
+            passphrase,
+        } => {
+            let logs = if include_logs {
+                log_buffer.get_all(None, None).iter().map(to_wire_log_line).collect()
+            } else {
+                Vec::new()
+            };
+
+            match snapshot::create(source_manager, logs, passphrase.as_deref())
+                .await
+                .and_then(|s| s.to_json())
+            {
+                Ok(archive) => DaemonResponse::Snapshot { archive },
+                Err(e) => DaemonResponse::Error {
+                    code: "SNAPSHOT_FAILED".to_string(),
+                    message: format!("{:#}", e),
+                },
+            }
+        }
+
+        DaemonRequest::Restore {
+            archive,
+            passphrase,
+        } => match snapshot::restore(source_manager, &archive, passphrase.as_deref()).await {
+            Ok(report) => DaemonResponse::Restored(report),
+            Err(e) => DaemonResponse::Error {
+                code: "RESTORE_FAILED".to_string(),
+                message: format!("{:#}", e),
+            },
+        },
+
         DaemonRequest::StreamLogs { .. }
         | DaemonRequest::StopLogStream { .. }
         | DaemonRequest::SubscribeServices { .. }
-        | DaemonRequest::StopServiceStream { .. } => DaemonResponse::Error {
+        | DaemonRequest::StopServiceStream { .. }
+        | DaemonRequest::StreamStatus { .. }
+        | DaemonRequest::StopStatusStream { .. }
+        | DaemonRequest::SubscribeEvents { .. }
+        | DaemonRequest::StopEventStream { .. }
+        | DaemonRequest::SetMaintenance { .. }
+        | DaemonRequest::Batch { .. }
+        | DaemonRequest::Hello { .. } => DaemonResponse::Error {
             code: "INTERNAL_ERROR".to_string(),
-            message: "Streaming requests should be handled separately".to_string(),
+            message: "Streaming, handshake and maintenance requests should be handled separately"
+                .to_string(),
         },
     }
 }
 
+/// Run the requests of a `Batch` one after another, so a request sees the
+/// effects of the ones before it (e.g. start-then-status).
+async fn run_batch<F, Fut>(requests: Vec<DaemonRequest>, mut run: F) -> Vec<DaemonResponse>
+where
+    F: FnMut(DaemonRequest) -> Fut,
+    Fut: std::future::Future<Output = DaemonResponse>,
+{
+    let mut responses = Vec::with_capacity(requests.len());
+    for request in requests {
+        responses.push(run(request).await);
+    }
+    responses
+}
+
+/// Run one request of a `Batch`. Requests that depend on connection state are
+/// refused on their own, without affecting the rest of the batch.
+/// `maintenance` is sampled once per batch, so all of it sees the same mode.
+async fn process_batched_request(
+    request: DaemonRequest,
+    ctx: &ClientContext,
+    maintenance: bool,
+) -> DaemonResponse {
+    if !batchable(&request) {
+        return DaemonResponse::Error {
+            code: "NOT_BATCHABLE".to_string(),
+            message: "Streaming, handshake, maintenance and batch requests cannot be batched"
+                .to_string(),
+        };
+    }
+    if maintenance && starts_work(&request) {
+        return maintenance_refusal();
+    }
+
+    process_request(
+        request,
+        &ctx.source_manager,
+        &ctx.exposure_manager,
+        &ctx.log_buffer,
+        &ctx.service_metrics,
+        &ctx.crash_reports,
+        &ctx.shutdown_handle,
+        ctx.start_time,
+        &ctx.proxy_addresses,
+        &ctx.maintenance,
+    )
+    .await
+}
+
+fn batchable(request: &DaemonRequest) -> bool {
+    !matches!(
+        request,
+        DaemonRequest::StreamLogs { .. }
+            | DaemonRequest::StopLogStream { .. }
+            | DaemonRequest::SubscribeServices { .. }
+            | DaemonRequest::StopServiceStream { .. }
+            | DaemonRequest::StreamStatus { .. }
+            | DaemonRequest::StopStatusStream { .. }
+            | DaemonRequest::SubscribeEvents { .. }
+            | DaemonRequest::StopEventStream { .. }
+            | DaemonRequest::SetMaintenance { .. }
+            | DaemonRequest::Batch { .. }
+            | DaemonRequest::Hello { .. }
+    )
+}
+
+fn maintenance_refusal() -> DaemonResponse {
+    DaemonResponse::Error {
+        code: "MAINTENANCE".to_string(),
+        message: "Hive is in maintenance mode; turn it off with `adi hive maintenance off`"
+            .to_string(),
+    }
+}
+
+/// Requests refused while in maintenance mode
+fn starts_work(request: &DaemonRequest) -> bool {
+    matches!(
+        request,
+        DaemonRequest::StartSource { .. }
+            | DaemonRequest::StartService { .. }
+            | DaemonRequest::CreateService { .. }
+    )
+}
+
 async fn populate_log_buffer(event_collector: Arc<EventCollector>, log_buffer: Arc<LogBuffer>) {
     let subscription = EventSubscription::logs();
     let mut receiver = event_collector.subscribe(subscription);
@@ -930,6 +1757,48 @@ async fn populate_log_buffer(event_collector: Arc<EventCollector>, log_buffer: A
     }
 }
 
+async fn track_service_metrics(
+    event_collector: Arc<EventCollector>,
+    service_metrics: Arc<ServiceMetricsTracker>,
+) {
+    let subscription = EventSubscription {
+        event_types: vec!["service_event".to_string(), "resource_metrics".to_string()],
+        ..Default::default()
+    };
+    let mut receiver = event_collector.subscribe(subscription);
+
+    loop {
+        match receiver.recv().await {
+            Ok(event) => service_metrics.record(&event),
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                warn!("Service metrics tracking lagged by {} events", count);
+            }
+        }
+    }
+}
+
+async fn track_crash_reports(
+    event_collector: Arc<EventCollector>,
+    crash_reports: Arc<CrashReporter>,
+) {
+    let subscription = EventSubscription {
+        event_types: vec!["log".to_string(), "service_event".to_string()],
+        ..Default::default()
+    };
+    let mut receiver = event_collector.subscribe(subscription);
+
+    loop {
+        match receiver.recv().await {
+            Ok(event) => crash_reports.record(&event),
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                warn!("Crash report capture lagged by {} events", count);
+            }
+        }
+    }
+}
+
 fn extract_dns_port(bind: &str) -> Result<u16> {
     bind.rsplit(':')
         .next()
@@ -940,6 +1809,7 @@ fn extract_dns_port(bind: &str) -> Result<u16> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hive_config::ServiceDiff;
 
     #[test]
     fn test_extract_dns_port() {
@@ -952,6 +1822,96 @@ mod tests {
         assert!(extract_dns_port("invalid").is_err());
     }
 
+    #[test]
+    fn test_hive_event_mapping() {
+        let started = ObservabilityEvent::service_event("app:api", ServiceEventType::Started);
+        let event = to_hive_event(&started).unwrap();
+        assert_eq!(
+            event,
+            HiveEvent::ServiceStarted {
+                fqn: "app:api".to_string()
+            }
+        );
+        assert_eq!(hive_event_source(&event), "app");
+
+        let restarting = ObservabilityEvent::service_event("app:api", ServiceEventType::Restarting);
+        assert!(to_hive_event(&restarting).is_none());
+
+        let unhealthy =
+            ObservabilityEvent::health_check("app:api", "http", HealthStatus::Unhealthy, 0, None);
+        assert!(matches!(
+            to_hive_event(&unhealthy),
+            Some(HiveEvent::HealthChanged { healthy: false, .. })
+        ));
+
+        let diff = ServiceDiff {
+            added: vec!["web".to_string()],
+            removed: Vec::new(),
+            changed: vec!["api".to_string()],
+        };
+        let reloaded =
+            to_hive_event(&ObservabilityEvent::source_reloaded("app", &diff, true)).unwrap();
+        assert_eq!(hive_event_source(&reloaded), "app");
+        assert_eq!(
+            reloaded,
+            HiveEvent::SourceReloaded {
+                source: "app".to_string(),
+                added: vec!["web".to_string()],
+                removed: Vec::new(),
+                changed: vec!["api".to_string()],
+                auto: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_batchable() {
+        assert!(batchable(&DaemonRequest::StopService {
+            fqn: "app:api".to_string()
+        }));
+        assert!(!batchable(&DaemonRequest::StreamStatus {
+            interval_ms: None
+        }));
+        assert!(!batchable(&DaemonRequest::Batch {
+            requests: Vec::new()
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_in_order() {
+        // The first request finishes last if run concurrently
+        let started = std::sync::Arc::new(std::sync::Mutex::new(false));
+        let requests = vec![
+            DaemonRequest::StartService {
+                fqn: "app:api".to_string(),
+            },
+            DaemonRequest::GetServiceStatus {
+                fqn: "app:api".to_string(),
+            },
+        ];
+        let responses = run_batch(requests, |request| {
+            let started = started.clone();
+            async move {
+                match request {
+                    DaemonRequest::StartService { .. } => {
+                        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                        *started.lock().unwrap() = true;
+                        DaemonResponse::Ok { message: None }
+                    }
+                    _ => DaemonResponse::Ok {
+                        message: Some(started.lock().unwrap().to_string()),
+                    },
+                }
+            }
+        })
+        .await;
+
+        assert!(matches!(
+            &responses[1],
+            DaemonResponse::Ok { message: Some(m) } if m == "true"
+        ));
+    }
+
     #[test]
     fn test_daemon_config_paths() {
         let config = DaemonConfig::new("/tmp/test-hive");
@@ -992,6 +1952,8 @@ mod tests {
             total_services: 10,
             proxy_addresses: vec!["127.0.0.1:8080".to_string()],
             uptime_secs: 3600,
+            maintenance: None,
+            singletons: vec![],
         });
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains("status"));
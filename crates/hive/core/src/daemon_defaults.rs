@@ -1,10 +1,15 @@
 //! Centralized default values for the hive daemon.
 
+/// Output bytes kept per service for crash reports
+pub const CRASH_OUTPUT_BYTES: usize = 64 * 1024;
 pub const DNS_BIND: &str = "127.0.0.1:15353";
 pub const DNS_UPSTREAM: &str = "8.8.8.8:53";
 pub const DNS_TTL: u32 = 60;
 pub const LOG_BUFFER_CAPACITY: usize = 10000;
+pub const LOG_INTERLEAVE_WINDOW: std::time::Duration = std::time::Duration::from_millis(200);
 pub const LOG_LINES_LIMIT: usize = 100;
 pub const PID_NAME: &str = "adi-hive.pid";
 pub const SOCKET_NAME: &str = "adi-hive.sock";
+pub const STATUS_STREAM_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+pub const STATUS_STREAM_MIN_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
 
@@ -2,15 +2,22 @@
 //!
 //! Enables cross-source dependencies through expose/uses declarations.
 //! Services can expose variables and ports to other sources, and
-//! consuming services can declare dependencies on exposed services.
+//! consuming services can declare dependencies on exposed services, either
+//! through `uses` or by referencing single variables with
+//! `${expose:source:service:VAR}` in their environment.
 
-use crate::hive_config::{ExposeConfig, RuntimeContext, UsesConfig};
+use crate::hive_config::{
+    interpolate_expose_refs, ExposeConfig, ExposeRef, HiveConfig, RuntimeContext, UsesConfig,
+};
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, info};
 
+/// Capacity of the exposed-value change channel
+const CHANGE_CHANNEL_CAPACITY: usize = 64;
+
 /// Compute HMAC-SHA256 of a secret using the expose name as key material
 fn hmac_hash(secret: &str, expose_name: &str) -> Result<String> {
     // Note: Uses expose_name as the key and secret as data (for access control hashing)
@@ -33,8 +40,35 @@ pub struct ExposedService {
     pub healthy: bool,
 }
 
+impl ExposedService {
+    /// Fully qualified name of the exposing service (`source:service`)
+    pub fn provider_fqn(&self) -> String {
+        format!("{}:{}", self.source_name, self.service_name)
+    }
+}
+
+/// Exposed variables of a service changed after it re-registered
+#[derive(Debug, Clone)]
+pub struct ExposeChange {
+    /// Exposing service (`source:service`)
+    pub provider: String,
+    /// Names of variables that were added, removed or changed
+    pub vars: Vec<String>,
+}
+
+/// `consumer` reads `vars` from `provider` through `${expose:...}` references
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExposeEdge {
+    pub consumer: String,
+    pub provider: String,
+    pub vars: Vec<String>,
+}
+
 pub struct ExposureManager {
     exposed: Arc<RwLock<HashMap<String, ExposedService>>>,
+    /// Consumer FQN -> `${expose:...}` references it resolved at its last start
+    consumers: Arc<RwLock<HashMap<String, BTreeSet<ExposeRef>>>>,
+    changes: broadcast::Sender<ExposeChange>,
 }
 
 impl Default for ExposureManager {
@@ -45,11 +79,19 @@ impl Default for ExposureManager {
 
 impl ExposureManager {
     pub fn new() -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_CHANNEL_CAPACITY);
         Self {
             exposed: Arc::new(RwLock::new(HashMap::new())),
+            consumers: Arc::new(RwLock::new(HashMap::new())),
+            changes,
         }
     }
 
+    /// Notifications for exposed values that changed on re-registration
+    pub fn subscribe(&self) -> broadcast::Receiver<ExposeChange> {
+        self.changes.subscribe()
+    }
+
     pub async fn register_exposed(
         &self,
         source_name: &str,
@@ -86,6 +128,7 @@ impl ExposureManager {
         let mut services = self.exposed.write().await;
         
         // Check for conflicts
+        let mut changed_vars = Vec::new();
         if let Some(existing) = services.get(&config.name) {
             if existing.source_name != source_name || existing.service_name != service_name {
                 return Err(anyhow!(
@@ -95,13 +138,25 @@ impl ExposureManager {
                     existing.service_name
                 ));
             }
+            changed_vars = changed_vars_between(&existing.vars, &exposed.vars);
         }
 
         info!(
             "Registered exposed service: {} ({}:{})",
             config.name, source_name, service_name
         );
+        let provider = exposed.provider_fqn();
         services.insert(config.name.clone(), exposed);
+        drop(services);
+
+        if !changed_vars.is_empty() {
+            info!("Exposed vars of {} changed: {:?}", provider, changed_vars);
+            // No receivers just means nobody restarts consumers
+            let _ = self.changes.send(ExposeChange {
+                provider,
+                vars: changed_vars,
+            });
+        }
 
         Ok(())
     }
@@ -237,11 +292,159 @@ impl ExposureManager {
         services.get(name).cloned()
     }
 
-    pub async fn get_consumers(&self, _expose_name: &str) -> Vec<String> {
-        // This would require tracking which services use which exposed services
-        // For now, return empty - can be implemented with additional tracking
-        Vec::new()
+    /// Exposed service registered by `source:service`
+    pub async fn get_exposed_by_service(&self, source: &str, service: &str) -> Option<ExposedService> {
+        let services = self.exposed.read().await;
+        services
+            .values()
+            .find(|s| s.source_name == source && s.service_name == service)
+            .cloned()
+    }
+
+    /// Wait until the service behind an `${expose:...}` reference has
+    /// registered and is healthy
+    pub async fn wait_for_provider(&self, expose_ref: &ExposeRef, timeout_secs: u64) -> Result<()> {
+        let timeout = std::time::Duration::from_secs(timeout_secs);
+        let start = std::time::Instant::now();
+
+        loop {
+            if let Some(exposed) = self.get_exposed_by_service(&expose_ref.source, &expose_ref.service).await {
+                if exposed.healthy {
+                    return Ok(());
+                }
+            }
+
+            if start.elapsed() > timeout {
+                return Err(anyhow!(
+                    "Timeout waiting for {} to expose {}",
+                    expose_ref.provider_fqn(),
+                    expose_ref.var
+                ));
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+    }
+
+    /// Resolve `${expose:...}` references in `value`. Providers that require a
+    /// secret must be listed in the consumer's `uses` with that secret.
+    pub async fn interpolate(&self, value: &str, uses: &[UsesConfig]) -> Result<String> {
+        let services = self.exposed.read().await;
+
+        interpolate_expose_refs(value, |expose_ref| {
+            let Some(exposed) = services
+                .values()
+                .find(|s| s.source_name == expose_ref.source && s.service_name == expose_ref.service)
+            else {
+                return Err(anyhow!(
+                    "{} does not expose any variables (referenced by {})",
+                    expose_ref.provider_fqn(),
+                    expose_ref
+                ));
+            };
+
+            if let Some(secret_hash) = &exposed.secret_hash {
+                let secret = uses
+                    .iter()
+                    .find(|u| u.name == exposed.name)
+                    .and_then(|u| u.secret.as_deref())
+                    .ok_or_else(|| anyhow!(
+                        "Exposed service '{}' requires a secret; add it under `uses`",
+                        exposed.name
+                    ))?;
+                if !hmac_verify(secret, &exposed.name, secret_hash)? {
+                    return Err(anyhow!(
+                        "Invalid secret for exposed service '{}'",
+                        exposed.name
+                    ));
+                }
+            }
+
+            Ok(exposed.vars.get(&expose_ref.var).cloned())
+        })
+    }
+
+    /// Remember which references `consumer` resolved, replacing earlier ones
+    pub async fn record_consumer(&self, consumer: &str, refs: BTreeSet<ExposeRef>) {
+        let mut consumers = self.consumers.write().await;
+        if refs.is_empty() {
+            consumers.remove(consumer);
+        } else {
+            consumers.insert(consumer.to_string(), refs);
+        }
+    }
+
+    /// Consumers of `provider` that read at least one of `vars`
+    pub async fn consumers_of(&self, provider: &str, vars: &[String]) -> Vec<String> {
+        let consumers = self.consumers.read().await;
+        let mut result: Vec<String> = consumers
+            .iter()
+            .filter(|(_, refs)| {
+                refs.iter()
+                    .any(|r| r.provider_fqn() == provider && vars.contains(&r.var))
+            })
+            .map(|(consumer, _)| consumer.clone())
+            .collect();
+        result.sort();
+        result
+    }
+
+    pub async fn get_consumers(&self, expose_name: &str) -> Vec<String> {
+        let Some(provider) = self.get_exposed(expose_name).await.map(|e| e.provider_fqn()) else {
+            return Vec::new();
+        };
+
+        let consumers = self.consumers.read().await;
+        let mut result: Vec<String> = consumers
+            .iter()
+            .filter(|(_, refs)| refs.iter().any(|r| r.provider_fqn() == provider))
+            .map(|(consumer, _)| consumer.clone())
+            .collect();
+        result.sort();
+        result
+    }
+}
+
+fn changed_vars_between(old: &HashMap<String, String>, new: &HashMap<String, String>) -> Vec<String> {
+    let keys: HashSet<&String> = old.keys().chain(new.keys()).collect();
+    let mut changed: Vec<String> = keys
+        .into_iter()
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect();
+    changed.sort();
+    changed
+}
+
+/// Expose/consume edges declared by `${expose:...}` references in the
+/// configs of all sources, grouped per consumer and provider.
+pub fn expose_graph(sources: &[(String, HiveConfig)]) -> Vec<ExposeEdge> {
+    let mut edges = Vec::new();
+
+    for (source_name, config) in sources {
+        let mut names: Vec<&String> = config.services.keys().collect();
+        names.sort();
+
+        for name in names {
+            let mut by_provider: std::collections::BTreeMap<String, Vec<String>> = Default::default();
+            for expose_ref in config.expose_refs(name) {
+                by_provider
+                    .entry(expose_ref.provider_fqn())
+                    .or_default()
+                    .push(expose_ref.var);
+            }
+
+            for (provider, vars) in by_provider {
+                edges.push(ExposeEdge {
+                    consumer: format!("{}:{}", source_name, name),
+                    provider,
+                    vars,
+                });
+            }
+        }
     }
+
+    edges
 }
 
 #[cfg(test)]
@@ -347,4 +550,47 @@ mod tests {
         // Check ports are available under alias
         assert_eq!(uses_ports.get("pg").unwrap().get("db"), Some(&5432));
     }
+
+    #[tokio::test]
+    async fn test_expose_refs_resolve_and_notify_changes() {
+        let manager = ExposureManager::new();
+        let mut changes = manager.subscribe();
+
+        let mut config = ExposeConfig {
+            name: "shared-cache".to_string(),
+            secret: Some("cache-secret".to_string()),
+            vars: HashMap::from([("URL".to_string(), "redis://localhost:{{runtime.port.redis}}".to_string())]),
+        };
+        let ports = HashMap::from([("redis".to_string(), 6379)]);
+        manager.register_exposed("infra", "redis", &config, &ports).await.unwrap();
+
+        let uses = vec![UsesConfig {
+            name: "shared-cache".to_string(),
+            secret: Some("cache-secret".to_string()),
+            alias: None,
+            vars: HashMap::new(),
+        }];
+        let value = manager.interpolate("${expose:infra:redis:URL}/0", &uses).await.unwrap();
+        assert_eq!(value, "redis://localhost:6379/0");
+
+        // Secret-protected providers can't be read without a matching `uses`
+        assert!(manager.interpolate("${expose:infra:redis:URL}", &[]).await.is_err());
+        assert!(manager.interpolate("${expose:infra:redis:MISSING}", &uses).await.is_err());
+
+        let refs = crate::hive_config::find_expose_refs("${expose:infra:redis:URL}");
+        manager.record_consumer("app:api", refs.into_iter().collect()).await;
+        assert_eq!(manager.get_consumers("shared-cache").await, ["app:api"]);
+
+        // Same values: no change notification
+        manager.register_exposed("infra", "redis", &config, &ports).await.unwrap();
+        assert!(changes.try_recv().is_err());
+
+        config.vars.insert("URL".to_string(), "redis://cache:6379".to_string());
+        manager.register_exposed("infra", "redis", &config, &ports).await.unwrap();
+        let change = changes.try_recv().unwrap();
+        assert_eq!(change.provider, "infra:redis");
+        assert_eq!(change.vars, ["URL"]);
+        assert_eq!(manager.consumers_of(&change.provider, &change.vars).await, ["app:api"]);
+        assert!(manager.consumers_of("infra:redis", &["OTHER".to_string()]).await.is_empty());
+    }
 }
@@ -0,0 +1,136 @@
+//! Service-level difference between two versions of a source's config.
+//!
+//! Used when a hive.yaml changes on disk to restart only what the edit
+//! touched.
+
+use serde::{Deserialize, Serialize};
+
+use super::types::HiveConfig;
+
+/// Services added, removed or changed between two configs, sorted by name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ServiceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare `old` and `new` service by service.
+///
+/// Source-wide settings every service inherits (`environment`, `profile`,
+/// `defaults`) count as a change to all services present in both configs.
+/// Proxy and hook settings do not: they take effect without a restart.
+pub fn diff_services(old: &HiveConfig, new: &HiveConfig) -> ServiceDiff {
+    let inherited_changed = to_json(&old.environment) != to_json(&new.environment)
+        || to_json(&old.profile) != to_json(&new.profile)
+        || to_json(&old.defaults) != to_json(&new.defaults);
+
+    let mut diff = ServiceDiff::default();
+    for (name, service) in &new.services {
+        match old.services.get(name) {
+            None => diff.added.push(name.clone()),
+            Some(previous) => {
+                if inherited_changed || to_json(previous) != to_json(service) {
+                    diff.changed.push(name.clone());
+                }
+            }
+        }
+    }
+    diff.removed = old
+        .services
+        .keys()
+        .filter(|name| !new.services.contains_key(*name))
+        .cloned()
+        .collect();
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort();
+    diff
+}
+
+/// Config types have no `PartialEq`; compare their serialized form
+fn to_json<T: Serialize>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hive_config::HiveConfigParser;
+
+    fn parse(yaml: &str) -> HiveConfig {
+        HiveConfigParser::new(".").parse_str(yaml).unwrap()
+    }
+
+    const BASE: &str = r#"
+version: "1"
+services:
+  api:
+    runner:
+      type: script
+      script:
+        run: ./api
+  worker:
+    runner:
+      type: script
+      script:
+        run: ./worker
+  cron:
+    runner:
+      type: script
+      script:
+        run: ./cron
+"#;
+
+    #[test]
+    fn test_diff_services() {
+        let old = parse(BASE);
+        let new = parse(
+            r#"
+version: "1"
+services:
+  api:
+    runner:
+      type: script
+      script:
+        run: ./api --verbose
+  worker:
+    runner:
+      type: script
+      script:
+        run: ./worker
+  web:
+    runner:
+      type: script
+      script:
+        run: ./web
+"#,
+        );
+
+        let diff = diff_services(&old, &new);
+        assert_eq!(diff.added, vec!["web"]);
+        assert_eq!(diff.removed, vec!["cron"]);
+        assert_eq!(diff.changed, vec!["api"]);
+        assert!(diff_services(&old, &parse(BASE)).is_empty());
+    }
+
+    #[test]
+    fn test_inherited_environment_changes_every_service() {
+        let old = parse(BASE);
+        let new = parse(&format!(
+            "{}environment:\n  static:\n    LOG: debug\n",
+            BASE
+        ));
+
+        let diff = diff_services(&old, &new);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.changed, vec!["api", "cron", "worker"]);
+    }
+}
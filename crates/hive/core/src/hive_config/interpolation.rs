@@ -1,8 +1,10 @@
 //! Variable Interpolation
 //!
-//! Handles two types of variable interpolation:
+//! Handles three types of variable interpolation:
 //! 1. Parse-time plugins (`${plugin.key}`) - resolved when YAML is parsed
 //! 2. Runtime templates (`{{runtime...}}`) - resolved at service start
+//! 3. Exposed variables (`${expose:source:service:VAR}`) - resolved at service
+//!    start from values exposed by other services
 
 use anyhow::{anyhow, Context, Result};
 use regex::Regex;
@@ -21,6 +23,11 @@ static USES_PORT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"\{\{uses\.([a-zA-Z_][a-zA-Z0-9_]*)\.port\.([a-zA-Z_][a-zA-Z0-9_]*)\}\}").unwrap()
 });
 
+static EXPOSE_REF_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\$\{expose:([a-zA-Z0-9_.-]+):([a-zA-Z0-9_.-]+):([a-zA-Z_][a-zA-Z0-9_]*)\}")
+        .unwrap()
+});
+
 static ESCAPED_DOLLAR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$\$\{").unwrap());
 static ESCAPED_BRACE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{\{").unwrap());
 
@@ -297,6 +304,78 @@ impl RuntimeContext {
     }
 }
 
+/// Reference to a variable exposed by another service: `${expose:source:service:VAR}`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExposeRef {
+    pub source: String,
+    pub service: String,
+    pub var: String,
+}
+
+impl ExposeRef {
+    /// Fully qualified name of the exposing service (`source:service`)
+    pub fn provider_fqn(&self) -> String {
+        format!("{}:{}", self.source, self.service)
+    }
+}
+
+impl std::fmt::Display for ExposeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "${{expose:{}:{}:{}}}",
+            self.source, self.service, self.var
+        )
+    }
+}
+
+/// All `${expose:...}` references in `input`
+pub fn find_expose_refs(input: &str) -> Vec<ExposeRef> {
+    EXPOSE_REF_REGEX
+        .captures_iter(input)
+        .map(|cap| ExposeRef {
+            source: cap[1].to_string(),
+            service: cap[2].to_string(),
+            var: cap[3].to_string(),
+        })
+        .collect()
+}
+
+/// Replace `${expose:...}` references using `resolve`, failing on the first
+/// reference it cannot resolve.
+pub fn interpolate_expose_refs<F>(input: &str, mut resolve: F) -> Result<String>
+where
+    F: FnMut(&ExposeRef) -> Result<Option<String>>,
+{
+    let mut output = String::new();
+    let mut last_end = 0;
+
+    for cap in EXPOSE_REF_REGEX.captures_iter(input) {
+        let full_match = cap.get(0).unwrap();
+        let expose_ref = ExposeRef {
+            source: cap[1].to_string(),
+            service: cap[2].to_string(),
+            var: cap[3].to_string(),
+        };
+
+        output.push_str(&input[last_end..full_match.start()]);
+        match resolve(&expose_ref)? {
+            Some(value) => {
+                trace!(reference = %expose_ref, "Resolved exposed variable");
+                output.push_str(&value);
+            }
+            None => {
+                warn!(reference = %expose_ref, "Unresolved exposed variable");
+                return Err(anyhow!("Unresolved exposed variable: {}", expose_ref));
+            }
+        }
+        last_end = full_match.end();
+    }
+    output.push_str(&input[last_end..]);
+
+    Ok(output)
+}
+
 pub fn interpolate_json_value(
     value: &mut serde_json::Value,
     parse_ctx: &ParseContext,
@@ -376,6 +455,32 @@ mod tests {
         assert_eq!(result, "port: 5432");
     }
 
+    #[test]
+    fn test_expose_refs() {
+        let input =
+            "postgres://${expose:infra:postgres:DB_USER}@${expose:infra:postgres:DB_HOST}/app";
+        let refs = find_expose_refs(input);
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].provider_fqn(), "infra:postgres");
+        assert_eq!(refs[1].var, "DB_HOST");
+
+        let result = interpolate_expose_refs(input, |r| {
+            Ok(Some(match r.var.as_str() {
+                "DB_USER" => "app".to_string(),
+                _ => "localhost".to_string(),
+            }))
+        })
+        .unwrap();
+        assert_eq!(result, "postgres://app@localhost/app");
+
+        let err = interpolate_expose_refs("${expose:infra:redis:URL}", |_| Ok(None)).unwrap_err();
+        assert!(err.to_string().contains("${expose:infra:redis:URL}"));
+
+        // Parse-time interpolation leaves expose references alone
+        let ctx = ParseContext::new();
+        assert_eq!(ctx.interpolate(input).unwrap(), input);
+    }
+
     #[test]
     fn test_service_name_plugin() {
         let mut ctx = ParseContext::new();
@@ -9,16 +9,19 @@
 //! - Runtime template resolution ({{runtime.port.X}})
 //! - Plugin-based architecture for runners, environment, health checks, and rollout
 
+mod diff;
 mod interpolation;
 mod parser;
 mod types;
 mod validation;
 
+pub use diff::*;
 pub use interpolation::*;
 pub use parser::{
     extract_blue_green_config, extract_cmd_health_config, extract_docker_config,
     extract_http_health_config, extract_recreate_config, extract_script_config,
     extract_tcp_health_config, find_project_root, get_rollout_ports, HiveConfigParser,
+    HIVE_YAML_PATH,
 };
 pub use types::*;
 pub use validation::*;
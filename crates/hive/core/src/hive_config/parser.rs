@@ -323,6 +323,54 @@ services:
         assert_eq!(web.runner.runner_type, "script");
     }
 
+    #[test]
+    fn test_expose_refs_link_dependencies() {
+        let yaml = r#"
+version: "1"
+
+environment:
+  static:
+    CACHE_URL: "${expose:infra:redis:URL}"
+
+services:
+  db:
+    runner:
+      type: script
+      script:
+        run: postgres
+    expose:
+      name: app-db
+      vars:
+        DB_HOST: localhost
+  api:
+    runner:
+      type: script
+      script:
+        run: cargo run
+    environment:
+      static:
+        DATABASE_URL: "postgres://${expose:app:db:DB_HOST}/app"
+"#;
+
+        let parser = HiveConfigParser::new(".");
+        let mut config = parser.parse_str(yaml).unwrap();
+
+        let refs: Vec<String> = config
+            .expose_refs("api")
+            .iter()
+            .map(|r| r.to_string())
+            .collect();
+        assert_eq!(
+            refs,
+            ["${expose:app:db:DB_HOST}", "${expose:infra:redis:URL}"]
+        );
+
+        config.link_expose_dependencies("app");
+        assert_eq!(config.services["api"].depends_on, ["db"]);
+        // Other sources are waited for at start time, not linked
+        assert!(config.services["db"].depends_on.is_empty());
+    }
+
     #[test]
     fn test_parse_with_env_interpolation() {
         std::env::set_var("TEST_PORT", "8080");
@@ -2,9 +2,10 @@
 //!
 //! Data structures for representing hive.yaml configuration according to the spec.
 
+use super::interpolation::{find_expose_refs, ExposeRef};
 use lib_plugin_abi_v3::hooks::HooksConfig;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::path::PathBuf;
 
 /// Built-in rollout strategy: recreate (stop-then-start)
@@ -34,6 +35,10 @@ pub struct HiveConfig {
     #[serde(default)]
     pub environment: Option<EnvironmentConfig>,
 
+    /// Toolchain isolation for every service of this source
+    #[serde(default)]
+    pub profile: Option<EnvProfileConfig>,
+
     #[serde(default)]
     pub observability: Option<ObservabilityConfig>,
 
@@ -41,6 +46,11 @@ pub struct HiveConfig {
     #[serde(default)]
     pub hooks: Option<HooksConfig>,
 
+    /// Re-read this file when it changes on disk and restart only the
+    /// services the edit touched
+    #[serde(default)]
+    pub auto_reload: bool,
+
     pub services: HashMap<String, ServiceConfig>,
 }
 
@@ -52,13 +62,57 @@ impl Default for HiveConfig {
             defaults: HashMap::new(),
             proxy: None,
             environment: None,
+            profile: None,
             observability: None,
             hooks: None,
+            auto_reload: false,
             services: HashMap::new(),
         }
     }
 }
 
+impl HiveConfig {
+    /// `${expose:...}` references in the static environment of `service`,
+    /// including the global environment it inherits
+    pub fn expose_refs(&self, service: &str) -> BTreeSet<ExposeRef> {
+        let service_env = self
+            .services
+            .get(service)
+            .and_then(|s| s.environment.as_ref());
+
+        [self.environment.as_ref(), service_env]
+            .into_iter()
+            .flatten()
+            .filter_map(|env| env.static_env.as_ref())
+            .flat_map(|vars| vars.values())
+            .flat_map(|value| find_expose_refs(value))
+            .collect()
+    }
+
+    /// Add `depends_on` edges for services that consume variables exposed by
+    /// other services of the same source, so they start in the right order.
+    pub fn link_expose_dependencies(&mut self, source_name: &str) {
+        let names: Vec<String> = self.services.keys().cloned().collect();
+        for name in names {
+            let providers: BTreeSet<String> = self
+                .expose_refs(&name)
+                .into_iter()
+                .filter(|r| r.source == source_name && r.service != name)
+                .filter(|r| self.services.contains_key(&r.service))
+                .map(|r| r.service)
+                .collect();
+
+            if let Some(service) = self.services.get_mut(&name) {
+                for provider in providers {
+                    if !service.depends_on.contains(&provider) {
+                        service.depends_on.push(provider);
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DnsProxyConfig {
     #[serde(default = "default_true")]
@@ -147,6 +201,24 @@ pub struct EnvironmentConfig {
     pub providers: HashMap<String, serde_json::Value>,
 }
 
+/// Per-source environment profile, so sources needing conflicting toolchains
+/// (Node versions, PATH entries) can share one daemon. Applied under the
+/// source-wide and per-service `environment` sections.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EnvProfileConfig {
+    /// Dotenv file, relative to the source root
+    #[serde(default)]
+    pub env_file: Option<String>,
+
+    /// Directories put in front of PATH, relative to the source root
+    #[serde(default)]
+    pub path: Vec<String>,
+
+    /// Apply `direnv export json` for the source root (needs an allowed .envrc)
+    #[serde(default)]
+    pub direnv: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ObservabilityConfig {
     #[serde(default)]
@@ -218,6 +290,11 @@ pub struct ServiceConfig {
     /// Lifecycle hooks (pre-up, post-up, pre-down, post-down)
     #[serde(default)]
     pub hooks: Option<HooksConfig>,
+
+    /// Run on one hive at a time; the hives listing it elect a leader through
+    /// signaling (see [`crate::singleton`])
+    #[serde(default)]
+    pub singleton: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -639,6 +716,8 @@ pub struct ServiceInfo {
     /// Resolved port assignments
     pub ports: HashMap<String, u16>,
     pub healthy: Option<bool>,
+    /// Most recent health probe, while the service's healthcheck runs
+    pub last_probe: Option<lib_hive_daemon_client::ProbeResult>,
     pub last_error: Option<String>,
     pub restart_count: u32,
 }
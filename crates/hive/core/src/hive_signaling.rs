@@ -1,23 +1,52 @@
 //! Hive ↔ Signaling Server WebSocket connection.
 //!
 //! Registers the hive daemon as a device on the signaling server,
-//! advertises supported cocoon kinds, and translates spawn/terminate
-//! requests into hive daemon `CreateService`/`StartService`/`DeleteService` calls.
+//! advertises the cocoon kinds whose runner is available on this host along
+//! with its GPUs (refreshed by periodic heartbeats), and translates
+//! spawn/terminate requests into hive daemon
+//! `CreateService`/`StartService`/`DeleteService` calls.
+//!
+//! In maintenance mode spawns are refused and, when draining, every cocoon
+//! spawned over this connection is offered to the scheduler with its original
+//! setup token; the local copy is deleted once another hive reports it
+//! running. Spawned cocoons are only tracked in memory, so cocoons from
+//! before a daemon restart are not drained.
+//!
+//! `singleton` services are listed in the registration and every heartbeat;
+//! `HiveSingletonLeader` pushes start or stop the local copy (see
+//! [`crate::singleton`]), and services led here are stopped when the
+//! connection drops.
+//!
+//! Warm pools (see [`crate::cocoon_pool`]) are refilled in the background and
+//! resized by `HiveSetPoolSize`; their state is reported with `HivePoolStatus`
+//! on every change and heartbeat. A `from_pool` spawn is answered once
+//! signaling confirmed the claimed slot's `HiveRekeyCocoon`.
 
+use crate::cocoon_pool::{generate_secret, CocoonPool};
 use crate::hive_config::ServiceConfig;
+use crate::maintenance::{Maintenance, MaintenanceMode};
+use crate::singleton::SingletonAction;
 use crate::source_manager::SourceManager;
 use futures::{SinkExt, StreamExt};
 use hmac::{Hmac, Mac};
-use lib_signaling_protocol::{CocoonKind, SignalingMessage};
+use lib_signaling_protocol::{
+    CocoonKind, DisconnectInfo, GpuInfo, RequestId, SignalingMessage, MAX_POOL_SIZE,
+};
 use sha2::Sha256;
+use std::collections::{HashMap, HashSet};
+use std::process::Stdio;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::watch;
+use tokio::process::Command;
+use tokio::sync::{mpsc, watch};
 use tokio_tungstenite::tungstenite::Message;
 use tracing::{debug, error, info, warn};
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// How often free VRAM is re-reported to the signaling server.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
 /// Configuration for connecting the hive daemon to the signaling server.
 #[derive(Debug, Clone)]
 pub struct HiveSignalingConfig {
@@ -27,6 +56,46 @@ pub struct HiveSignalingConfig {
     pub cocoon_kinds: Vec<CocoonKind>,
     pub cocoon_source_id: String,
     pub reconnect_delay: Duration,
+    /// Warm pool size per cocoon kind; kinds not listed start without a pool
+    pub pool_sizes: HashMap<String, u32>,
+}
+
+/// Cocoon spawned over signaling, kept so it can be drained to another hive.
+#[derive(Debug, Clone)]
+struct SpawnedCocoon {
+    setup_token: String,
+    kind: String,
+    gpu_required: Option<bool>,
+    min_vram_mb: Option<u64>,
+}
+
+/// Cocoons spawned over signaling and drains awaiting a result.
+#[derive(Debug, Default)]
+struct Cocoons {
+    /// container name → spawn parameters
+    spawned: HashMap<String, SpawnedCocoon>,
+    /// drain request_id → container name
+    draining: HashMap<RequestId, String>,
+    /// spawn request_id → warm pool slot being re-keyed for it
+    claiming: HashMap<RequestId, ClaimedSlot>,
+    pool: CocoonPool,
+}
+
+/// Warm pool slot handed to a spawn, until signaling confirms the re-key
+#[derive(Debug)]
+struct ClaimedSlot {
+    slot: String,
+    /// Container name for a cold start should the re-key fail
+    name: Option<String>,
+    cocoon: SpawnedCocoon,
+}
+
+/// Outcome of booting a warm pool slot in the background
+#[derive(Debug)]
+struct WarmedSlot {
+    kind: String,
+    slot: String,
+    booted: bool,
 }
 
 fn hmac_sign(data: &str, secret: &str) -> String {
@@ -36,55 +105,217 @@ fn hmac_sign(data: &str, secret: &str) -> String {
     hex::encode(mac.finalize().into_bytes())
 }
 
+/// Runner types usable on this host, probed the same way the cocoon-spawner
+/// runner does: Docker daemon ping, `podman info`, and a reachable systemd
+/// user manager for `process`.
+pub async fn detect_runner_types() -> Vec<String> {
+    let mut available = Vec::new();
+
+    let docker = match bollard::Docker::connect_with_local_defaults() {
+        Ok(client) => client.ping().await.is_ok(),
+        Err(_) => false,
+    };
+    if docker {
+        available.push("docker".to_string());
+    }
+    if command_succeeds("podman", &["info", "--format", "{{.Host.Security.Rootless}}"]).await {
+        available.push("podman".to_string());
+    }
+    if command_succeeds("systemd-run", &["--version"]).await
+        && command_succeeds("systemctl", &["--user", "show-environment"]).await
+    {
+        available.push("process".to_string());
+    }
+
+    available
+}
+
+async fn command_succeeds(program: &str, args: &[&str]) -> bool {
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// GPUs on this host, from `nvidia-smi`; empty when there is no NVIDIA driver.
+pub async fn detect_gpus() -> Vec<GpuInfo> {
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=name,memory.total,memory.free,driver_version",
+            "--format=csv,noheader,nounits",
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Parse `nvidia-smi --format=csv,noheader,nounits` rows (memory in MiB).
+fn parse_nvidia_smi(output: &str) -> Vec<GpuInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [model, total, free, driver] = fields.as_slice() else {
+                return None;
+            };
+            Some(GpuInfo {
+                model: model.to_string(),
+                vram_total_mb: total.parse().ok()?,
+                vram_free_mb: free.parse().ok()?,
+                driver: (!driver.is_empty()).then(|| driver.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Kinds whose runner is available; the rest are logged and not advertised.
+fn supported_kinds(kinds: &[CocoonKind], runners: &[String]) -> Vec<CocoonKind> {
+    kinds
+        .iter()
+        .filter(|kind| {
+            let supported = runners.contains(&kind.runner_type);
+            if !supported {
+                warn!(
+                    "cocoon kind '{}' needs runner '{}', which is not available; not advertising it",
+                    kind.id, kind.runner_type
+                );
+            }
+            supported
+        })
+        .cloned()
+        .collect()
+}
+
 /// Run the signaling connection loop with automatic reconnection.
 ///
 /// Registers as a device, then translates `HiveSpawnCocoon`/`HiveTerminateCocoon`
-/// into hive service operations. Reconnects on disconnect until `shutdown_rx` fires.
+/// into hive service operations and reports `maintenance` changes. Reconnects
+/// on disconnect until `shutdown_rx` fires.
 pub async fn run_signaling_loop(
     config: HiveSignalingConfig,
     source_manager: Arc<SourceManager>,
+    maintenance: Maintenance,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
+    let mut cocoons = Cocoons {
+        pool: CocoonPool::new(&config.pool_sizes),
+        ..Cocoons::default()
+    };
+    // Slots keep booting across reconnects, so results outlive a connection
+    let (warmed_tx, mut warmed_rx) = mpsc::unbounded_channel();
+
     loop {
         if *shutdown_rx.borrow() {
             info!("signaling shutdown requested");
             return;
         }
 
-        match connect_and_run(&config, &source_manager, &mut shutdown_rx).await {
+        let mut hint = None;
+        match connect_and_run(
+            &config,
+            &source_manager,
+            &maintenance,
+            &mut cocoons,
+            &warmed_tx,
+            &mut warmed_rx,
+            &mut hint,
+            &mut shutdown_rx,
+        )
+        .await
+        {
             Ok(()) => info!("signaling connection closed cleanly"),
             Err(e) => warn!("signaling connection error: {e}"),
         }
 
+        // Signaling hands our singletons to other hives once we go silent
+        for service in source_manager.singletons().disconnected() {
+            info!("stopping singleton {service}: signaling connection lost");
+            if let Err(e) = source_manager.stop_service(&service).await {
+                warn!("failed to stop singleton {service}: {e}");
+            }
+        }
+
         if *shutdown_rx.borrow() {
             return;
         }
 
-        info!("reconnecting to signaling in {}s", config.reconnect_delay.as_secs());
+        let Some(delay) = reconnect_delay(config.reconnect_delay, hint.as_ref()) else {
+            error!("signaling server ended the connection for good ({hint:?}); not reconnecting");
+            return;
+        };
+        info!("reconnecting to signaling in {}s", delay.as_secs());
         tokio::select! {
-            _ = tokio::time::sleep(config.reconnect_delay) => {}
+            _ = tokio::time::sleep(delay) => {}
             _ = shutdown_rx.changed() => return,
         }
     }
 }
 
+/// Delay before reconnecting, honoring the server's disconnect hint; `None`
+/// when the hint forbids reconnecting.
+fn reconnect_delay(default: Duration, hint: Option<&DisconnectInfo>) -> Option<Duration> {
+    match hint {
+        Some(info) if !info.should_retry() => None,
+        Some(info) => Some(info.retry_after().map_or(default, |after| after.max(default))),
+        None => Some(default),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn connect_and_run(
     config: &HiveSignalingConfig,
     source_manager: &Arc<SourceManager>,
+    maintenance: &Maintenance,
+    cocoons: &mut Cocoons,
+    warmed_tx: &mpsc::UnboundedSender<WarmedSlot>,
+    warmed_rx: &mut mpsc::UnboundedReceiver<WarmedSlot>,
+    hint: &mut Option<DisconnectInfo>,
     shutdown_rx: &mut watch::Receiver<bool>,
 ) -> anyhow::Result<()> {
     info!("connecting to signaling server: {}", config.signaling_url);
 
+    // The server forgets pending drains when the connection drops
+    cocoons.draining.clear();
+    maintenance.set_draining(0);
+    // Claims whose spawn was never answered; the slots may be half handed over
+    let unconfirmed = cocoons.claiming.drain().map(|(_, claim)| claim.slot).collect();
+    remove_slots(unconfirmed, config, source_manager);
+
+    // Probed on every connect so runtimes installed later get picked up
+    let runners = detect_runner_types().await;
+    let kinds = supported_kinds(&config.cocoon_kinds, &runners);
+    info!("available cocoon runners: {runners:?}, advertising {} kind(s)", kinds.len());
+    let gpus = detect_gpus().await;
+    if !gpus.is_empty() {
+        info!("advertising {} GPU(s)", gpus.len());
+    }
+
     let (ws, _) = tokio_tungstenite::connect_async(&config.signaling_url).await?;
     let (mut sink, mut stream) = ws.split();
 
     // Register as a hive device
     let hive_id_signature = hmac_sign("hive", &config.hive_secret);
     let register_msg = SignalingMessage::HiveRegister {
-        hive_id: "hive".to_string(),
+        hive_id: "hive".into(),
         version: env!("CARGO_PKG_VERSION").to_string(),
-        cocoon_kinds: config.cocoon_kinds.clone(),
+        cocoon_kinds: kinds.clone(),
         hive_id_signature,
+        runners: Some(runners),
+        gpus: Some(gpus),
+        singletons: Some(source_manager.singleton_services().await),
     };
 
     let json = serde_json::to_string(&register_msg)?;
@@ -93,14 +324,94 @@ async fn connect_and_run(
     // Wait for registration confirmation
     let hive_id = wait_for_registration(&mut stream).await?;
     info!("registered as hive: {hive_id}");
+    source_manager.singletons().connected(&hive_id);
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.tick().await;
+
+    let mut maintenance_rx = maintenance.subscribe();
+    let mode = maintenance_rx.borrow_and_update().clone();
+    if mode.is_some() {
+        for msg in maintenance_messages(mode.as_ref(), cocoons) {
+            sink.send(Message::Text(serde_json::to_string(&msg)?.into())).await?;
+        }
+        maintenance.set_draining(cocoons.draining.len());
+    }
+
+    // The server only knows the pools it was told about on this connection
+    refill_pools(
+        cocoons,
+        &kinds,
+        config,
+        source_manager,
+        maintenance,
+        warmed_tx,
+    );
+    cocoons.pool.take_changed();
+    let msg = SignalingMessage::HivePoolStatus {
+        pools: cocoons.pool.status(),
+    };
+    sink.send(Message::Text(serde_json::to_string(&msg)?.into()))
+        .await?;
 
     // Message loop
     loop {
+        if cocoons.pool.take_changed() {
+            let msg = SignalingMessage::HivePoolStatus {
+                pools: cocoons.pool.status(),
+            };
+            sink.send(Message::Text(serde_json::to_string(&msg)?.into()))
+                .await?;
+        }
+
         tokio::select! {
+            _ = heartbeat.tick() => {
+                let msg = SignalingMessage::HiveHeartbeat {
+                    gpus: detect_gpus().await,
+                    singletons: Some(source_manager.singleton_services().await),
+                };
+                sink.send(Message::Text(serde_json::to_string(&msg)?.into())).await?;
+                let msg = SignalingMessage::HivePoolStatus { pools: cocoons.pool.status() };
+                sink.send(Message::Text(serde_json::to_string(&msg)?.into())).await?;
+                // Slots that failed to boot are retried here rather than right away
+                refill_pools(cocoons, &kinds, config, source_manager, maintenance, warmed_tx);
+            }
+            Some(warmed) = warmed_rx.recv() => {
+                let kept = cocoons.pool.warmed(&warmed.kind, &warmed.slot, warmed.booted);
+                if warmed.booted && !kept {
+                    remove_slots(vec![warmed.slot], config, source_manager);
+                } else if kept {
+                    info!("warm pool slot ready: {}", warmed.slot);
+                }
+            }
+            Ok(()) = maintenance_rx.changed() => {
+                let mode = maintenance_rx.borrow_and_update().clone();
+                for msg in maintenance_messages(mode.as_ref(), cocoons) {
+                    sink.send(Message::Text(serde_json::to_string(&msg)?.into())).await?;
+                }
+                maintenance.set_draining(cocoons.draining.len());
+            }
             msg = stream.next() => {
                 match msg {
                     Some(Ok(Message::Text(text))) => {
-                        handle_message(&text, config, source_manager, &mut sink).await;
+                        if let Ok(SignalingMessage::DeviceDisconnect { info }) =
+                            serde_json::from_str(&text)
+                        {
+                            info!("signaling server is closing the connection: {info:?}");
+                            *hint = Some(info);
+                            continue;
+                        }
+                        handle_message(
+                            &text,
+                            config,
+                            &kinds,
+                            source_manager,
+                            maintenance,
+                            cocoons,
+                            &mut sink,
+                        )
+                        .await;
+                        refill_pools(cocoons, &kinds, config, source_manager, maintenance, warmed_tx);
                     }
                     Some(Ok(Message::Ping(data))) => {
                         let _ = sink.send(Message::Pong(data)).await;
@@ -131,7 +442,7 @@ async fn wait_for_registration(
             if let Ok(SignalingMessage::HiveRegisterResponse { hive_id }) =
                 serde_json::from_str::<SignalingMessage>(&text)
             {
-                return Ok(hive_id);
+                return Ok(hive_id.into_inner());
             }
         }
     }
@@ -141,7 +452,10 @@ async fn wait_for_registration(
 async fn handle_message<S>(
     text: &str,
     config: &HiveSignalingConfig,
+    kinds: &[CocoonKind],
     source_manager: &Arc<SourceManager>,
+    maintenance: &Maintenance,
+    cocoons: &mut Cocoons,
     sink: &mut S,
 ) where
     S: SinkExt<Message> + Unpin,
@@ -156,28 +470,180 @@ async fn handle_message<S>(
     };
 
     let response = match msg {
+        SignalingMessage::HiveSpawnCocoon { request_id, .. } if maintenance.is_on() => {
+            Some(spawn_error(request_id, "hive is in maintenance mode".to_string()))
+        }
         SignalingMessage::HiveSpawnCocoon {
             request_id,
             setup_token,
             name,
             kind,
+            gpu_required,
+            min_vram_mb,
+            from_pool,
         } => {
             info!("spawn request: kind={kind} request_id={request_id}");
-            Some(handle_spawn(
-                request_id,
+            let slot = match from_pool {
+                Some(true) if kinds.iter().any(|k| k.id == kind) => cocoons.pool.claim(&kind),
+                _ => None,
+            };
+            let cocoon = SpawnedCocoon {
                 setup_token,
-                name,
-                &kind,
-                config,
-                source_manager,
-            ).await)
+                kind,
+                gpu_required,
+                min_vram_mb,
+            };
+            match slot {
+                // The running slot becomes the caller's cocoon once signaling
+                // pushed it the setup token and a new secret
+                Some(slot) => {
+                    info!("claimed warm pool slot {}", slot.name);
+                    let rekey = SignalingMessage::HiveRekeyCocoon {
+                        request_id: request_id.clone(),
+                        slot_secret: slot.secret,
+                        setup_token: cocoon.setup_token.clone(),
+                        secret: generate_secret(),
+                    };
+                    cocoons.claiming.insert(request_id, ClaimedSlot {
+                        slot: slot.name,
+                        name,
+                        cocoon,
+                    });
+                    Some(rekey)
+                }
+                None => Some(
+                    cold_spawn(
+                        request_id,
+                        name,
+                        cocoon,
+                        from_pool.map(|_| false),
+                        config,
+                        kinds,
+                        source_manager,
+                        cocoons,
+                    )
+                    .await,
+                ),
+            }
+        }
+        SignalingMessage::HiveRekeyCocoonResponse {
+            request_id,
+            success,
+            error,
+        } => match cocoons.claiming.remove(&request_id) {
+            Some(claim) if success => {
+                info!("warm pool slot {} handed over", claim.slot);
+                cocoons.spawned.insert(claim.slot.clone(), claim.cocoon);
+                Some(SignalingMessage::HiveSpawnCocoonResult {
+                    request_id,
+                    success: true,
+                    device_id: None,
+                    container_id: Some(claim.slot),
+                    error: None,
+                    from_pool: Some(true),
+                })
+            }
+            Some(claim) => {
+                warn!(
+                    "re-keying warm pool slot {} failed: {}; starting a fresh cocoon",
+                    claim.slot,
+                    error.as_deref().unwrap_or("unknown error")
+                );
+                remove_slots(vec![claim.slot], config, source_manager);
+                Some(
+                    cold_spawn(
+                        request_id,
+                        claim.name,
+                        claim.cocoon,
+                        Some(false),
+                        config,
+                        kinds,
+                        source_manager,
+                        cocoons,
+                    )
+                    .await,
+                )
+            }
+            None => None,
+        },
+        SignalingMessage::HiveSetPoolSize { kind, size } => {
+            info!("warm pool size of {kind} set to {size}");
+            if size > MAX_POOL_SIZE {
+                warn!("warm pools hold at most {MAX_POOL_SIZE} cocoons; capping {kind}");
+            }
+            if !kinds.iter().any(|k| k.id == kind) {
+                warn!("cocoon kind '{kind}' is not advertised here; its pool stays empty");
+            }
+            let surplus = cocoons.pool.set_size(&kind, size);
+            remove_slots(surplus, config, source_manager);
+            None
         }
         SignalingMessage::HiveTerminateCocoon {
             request_id,
             container_id,
         } => {
             info!("terminate request: container_id={container_id} request_id={request_id}");
-            Some(handle_terminate(request_id, &container_id, config, source_manager).await)
+            let result = handle_terminate(request_id, &container_id, config, source_manager).await;
+            if matches!(result, SignalingMessage::HiveTerminateCocoonResult { success: true, .. }) {
+                cocoons.spawned.remove(&container_id);
+            }
+            Some(result)
+        }
+        SignalingMessage::HiveDrainCocoonResult {
+            request_id,
+            success,
+            target_hive_id,
+            error,
+            ..
+        } => {
+            if let Some(container_id) = cocoons.draining.remove(&request_id) {
+                if success {
+                    info!(
+                        "cocoon {container_id} drained to hive {}",
+                        target_hive_id.as_deref().unwrap_or("?")
+                    );
+                    let fqn = format!("{}:{}", config.cocoon_source_id, container_id);
+                    if let Err(e) = source_manager.delete_service(&fqn).await {
+                        warn!("failed to remove drained cocoon {container_id}: {e}");
+                    }
+                    cocoons.spawned.remove(&container_id);
+                } else {
+                    warn!(
+                        "draining cocoon {container_id} failed: {}",
+                        error.as_deref().unwrap_or("unknown error")
+                    );
+                }
+                maintenance.set_draining(cocoons.draining.len());
+            }
+            None
+        }
+        SignalingMessage::HiveSingletonLeader {
+            service,
+            leader_hive_id,
+            fencing_token,
+        } => {
+            let action =
+                source_manager
+                    .singletons()
+                    .apply(&service, leader_hive_id.clone().map(String::from), fencing_token);
+            let result = match action {
+                Some(SingletonAction::Start) => {
+                    info!("elected leader of singleton {service} (fencing token {fencing_token})");
+                    source_manager.start_service(&service).await
+                }
+                Some(SingletonAction::Stop) => {
+                    info!(
+                        "singleton {service} moved to hive {}; stopping local copy",
+                        leader_hive_id.as_deref().unwrap_or("?")
+                    );
+                    source_manager.stop_service(&service).await
+                }
+                None => Ok(()),
+            };
+            if let Err(e) = result {
+                warn!("failed to apply leader change of singleton {service}: {e}");
+            }
+            None
         }
         _ => {
             debug!("ignoring message type");
@@ -194,16 +660,55 @@ async fn handle_message<S>(
     }
 }
 
+/// Start a fresh cocoon for a spawn and keep it for draining.
+#[allow(clippy::too_many_arguments)]
+async fn cold_spawn(
+    request_id: RequestId,
+    name: Option<String>,
+    cocoon: SpawnedCocoon,
+    from_pool: Option<bool>,
+    config: &HiveSignalingConfig,
+    kinds: &[CocoonKind],
+    source_manager: &Arc<SourceManager>,
+    cocoons: &mut Cocoons,
+) -> SignalingMessage {
+    let mut result = handle_spawn(
+        request_id,
+        cocoon.setup_token.clone(),
+        name,
+        &cocoon.kind,
+        config,
+        kinds,
+        source_manager,
+    )
+    .await;
+    if let SignalingMessage::HiveSpawnCocoonResult {
+        success,
+        ref container_id,
+        from_pool: ref mut pooled,
+        ..
+    } = result
+    {
+        *pooled = from_pool;
+        if let (true, Some(container_id)) = (success, container_id) {
+            cocoons.spawned.insert(container_id.clone(), cocoon);
+        }
+    }
+    result
+}
+
 /// Translate a cocoon spawn request into hive CreateService + StartService.
 async fn handle_spawn(
-    request_id: String,
+    request_id: RequestId,
     setup_token: String,
     name: Option<String>,
     kind: &str,
     config: &HiveSignalingConfig,
+    kinds: &[CocoonKind],
     source_manager: &Arc<SourceManager>,
 ) -> SignalingMessage {
-    let kind_config = match config.cocoon_kinds.iter().find(|k| k.id == kind) {
+    // Only advertised kinds, so kinds without an available runner are refused here too
+    let kind_config = match kinds.iter().find(|k| k.id == kind) {
         Some(k) => k,
         None => {
             return spawn_error(request_id, format!("unknown cocoon kind: {kind}"));
@@ -215,56 +720,156 @@ async fn handle_spawn(
         format!("cocoon-{short_id}")
     });
 
-    // Build a ServiceConfig for the cocoon-spawner runner
+    let service_config = match cocoon_service_config(
+        kind_config,
+        &config.signaling_url,
+        Some(&setup_token),
+        None,
+    ) {
+        Ok(c) => c,
+        Err(e) => return spawn_error(request_id, e),
+    };
+
+    if let Err(e) = start_cocoon(
+        source_manager,
+        &config.cocoon_source_id,
+        &container_name,
+        service_config,
+    )
+    .await
+    {
+        return spawn_error(request_id, e);
+    }
+
+    info!("cocoon spawned: {container_name}");
+
+    SignalingMessage::HiveSpawnCocoonResult {
+        request_id,
+        success: true,
+        device_id: None,
+        container_id: Some(container_name),
+        error: None,
+        from_pool: None,
+    }
+}
+
+/// ServiceConfig for the cocoon-spawner runner; warm pool slots boot
+/// without a setup token, under the secret their hive hands over.
+fn cocoon_service_config(
+    kind_config: &CocoonKind,
+    signaling_url: &str,
+    setup_token: Option<&str>,
+    pool_secret: Option<&str>,
+) -> Result<ServiceConfig, String> {
     let service_config_json = serde_json::json!({
         "runner": {
             "type": "cocoon-spawner",
             "cocoon-spawner": {
+                "runner": kind_config.runner_type,
+                "runner_config": kind_config.runner_config,
                 "image": kind_config.image,
-                "signaling_url": config.signaling_url,
+                "signaling_url": signaling_url,
                 "setup_token": setup_token,
+                "pool_secret": pool_secret,
             }
         },
         "restart": "never"
     });
 
-    let service_config: ServiceConfig = match serde_json::from_value(service_config_json) {
-        Ok(c) => c,
-        Err(e) => {
-            return spawn_error(request_id, format!("failed to build service config: {e}"));
-        }
-    };
+    serde_json::from_value(service_config_json)
+        .map_err(|e| format!("failed to build service config: {e}"))
+}
 
-    // Create the service
-    if let Err(e) = source_manager
-        .create_service(&config.cocoon_source_id, &container_name, service_config)
+/// Create and start a cocoon service, removing it again if it fails to start.
+async fn start_cocoon(
+    source_manager: &SourceManager,
+    source_id: &str,
+    name: &str,
+    service_config: ServiceConfig,
+) -> Result<(), String> {
+    source_manager
+        .create_service(source_id, name, service_config)
         .await
-    {
-        return spawn_error(request_id, format!("create service failed: {e}"));
-    }
+        .map_err(|e| format!("create service failed: {e}"))?;
 
-    // Start the service
-    let fqn = format!("{}:{}", config.cocoon_source_id, container_name);
+    let fqn = format!("{source_id}:{name}");
     if let Err(e) = source_manager.start_service(&fqn).await {
-        // Clean up on start failure
         let _ = source_manager.delete_service(&fqn).await;
-        return spawn_error(request_id, format!("start service failed: {e}"));
+        return Err(format!("start service failed: {e}"));
     }
+    Ok(())
+}
 
-    info!("cocoon spawned: {container_name}");
+/// Boot the slots the warm pools are missing, in the background. Nothing is
+/// warmed in maintenance mode.
+fn refill_pools(
+    cocoons: &mut Cocoons,
+    kinds: &[CocoonKind],
+    config: &HiveSignalingConfig,
+    source_manager: &Arc<SourceManager>,
+    maintenance: &Maintenance,
+    warmed_tx: &mpsc::UnboundedSender<WarmedSlot>,
+) {
+    if maintenance.is_on() {
+        return;
+    }
 
-    SignalingMessage::HiveSpawnCocoonResult {
-        request_id,
-        success: true,
-        device_id: None,
-        container_id: Some(container_name),
-        error: None,
+    for kind in cocoons.pool.short_kinds() {
+        let Some(kind_config) = kinds.iter().find(|k| k.id == kind) else {
+            continue;
+        };
+        for slot in cocoons.pool.warm(&kind) {
+            let service_config = cocoon_service_config(
+                kind_config,
+                &config.signaling_url,
+                None,
+                Some(&slot.secret),
+            );
+            let slot = slot.name;
+            let source_manager = source_manager.clone();
+            let source_id = config.cocoon_source_id.clone();
+            let warmed_tx = warmed_tx.clone();
+            let kind = kind.clone();
+            tokio::spawn(async move {
+                let started = match service_config {
+                    Ok(service_config) => {
+                        start_cocoon(&source_manager, &source_id, &slot, service_config).await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(ref e) = started {
+                    warn!("failed to warm pool slot {slot}: {e}");
+                }
+                let _ = warmed_tx.send(WarmedSlot {
+                    kind,
+                    slot,
+                    booted: started.is_ok(),
+                });
+            });
+        }
+    }
+}
+
+/// Delete warm pool slots in the background.
+fn remove_slots(
+    slots: Vec<String>,
+    config: &HiveSignalingConfig,
+    source_manager: &Arc<SourceManager>,
+) {
+    for slot in slots {
+        let source_manager = source_manager.clone();
+        let fqn = format!("{}:{}", config.cocoon_source_id, slot);
+        tokio::spawn(async move {
+            if let Err(e) = source_manager.delete_service(&fqn).await {
+                warn!("failed to remove warm pool slot {fqn}: {e}");
+            }
+        });
     }
 }
 
 /// Translate a cocoon terminate request into hive DeleteService (which stops first).
 async fn handle_terminate(
-    request_id: String,
+    request_id: RequestId,
     container_id: &str,
     config: &HiveSignalingConfig,
     source_manager: &Arc<SourceManager>,
@@ -288,7 +893,48 @@ async fn handle_terminate(
     }
 }
 
-fn spawn_error(request_id: String, error: String) -> SignalingMessage {
+/// Announce the maintenance mode and, when draining, hand every cocoon that is
+/// not already being drained to the scheduler.
+fn maintenance_messages(
+    mode: Option<&MaintenanceMode>,
+    cocoons: &mut Cocoons,
+) -> Vec<SignalingMessage> {
+    let mut messages = vec![SignalingMessage::HiveMaintenance {
+        on: mode.is_some(),
+        reason: mode.and_then(|m| m.reason.clone()),
+    }];
+    if !mode.is_some_and(|m| m.drain) {
+        return messages;
+    }
+
+    let in_flight: HashSet<&String> = cocoons.draining.values().collect();
+    let pending: Vec<String> = cocoons
+        .spawned
+        .keys()
+        .filter(|container_id| !in_flight.contains(container_id))
+        .cloned()
+        .collect();
+
+    for container_id in pending {
+        let cocoon = &cocoons.spawned[&container_id];
+        let request_id = RequestId::from(uuid::Uuid::new_v4().to_string());
+        info!("draining cocoon {container_id} (request_id={request_id})");
+        messages.push(SignalingMessage::HiveDrainCocoon {
+            request_id: request_id.clone(),
+            container_id: container_id.clone(),
+            setup_token: cocoon.setup_token.clone(),
+            name: Some(container_id.clone()),
+            kind: cocoon.kind.clone(),
+            gpu_required: cocoon.gpu_required,
+            min_vram_mb: cocoon.min_vram_mb,
+        });
+        cocoons.draining.insert(request_id, container_id);
+    }
+
+    messages
+}
+
+fn spawn_error(request_id: RequestId, error: String) -> SignalingMessage {
     error!("spawn failed: {error}");
     SignalingMessage::HiveSpawnCocoonResult {
         request_id,
@@ -296,5 +942,76 @@ fn spawn_error(request_id: String, error: String) -> SignalingMessage {
         device_id: None,
         container_id: None,
         error: Some(error),
+        from_pool: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_signaling_protocol::DisconnectReason;
+
+    #[test]
+    fn test_reconnect_delay_honors_hint() {
+        let default = Duration::from_secs(5);
+        assert_eq!(reconnect_delay(default, None), Some(default));
+        let shutdown = DisconnectInfo::transient(
+            DisconnectReason::ServerShutdown,
+            Some(Duration::from_secs(30)),
+        );
+        assert_eq!(reconnect_delay(default, Some(&shutdown)), Some(Duration::from_secs(30)));
+        let banned = DisconnectInfo::permanent(DisconnectReason::Banned);
+        assert_eq!(reconnect_delay(default, Some(&banned)), None);
+    }
+
+    #[test]
+    fn test_parse_nvidia_smi() {
+        let gpus = parse_nvidia_smi(
+            "NVIDIA GeForce RTX 4090, 24564, 23012, 550.54.14\nNVIDIA A100-SXM4-80GB, 81920, [N/A], 550.54.14\n",
+        );
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].model, "NVIDIA GeForce RTX 4090");
+        assert_eq!(gpus[0].vram_total_mb, 24564);
+        assert_eq!(gpus[0].vram_free_mb, 23012);
+        assert_eq!(gpus[0].driver.as_deref(), Some("550.54.14"));
+
+        assert!(parse_nvidia_smi("").is_empty());
+    }
+
+    #[test]
+    fn test_maintenance_messages_drain_each_cocoon_once() {
+        let mut cocoons = Cocoons::default();
+        for name in ["cocoon-a", "cocoon-b"] {
+            cocoons.spawned.insert(name.to_string(), SpawnedCocoon {
+                setup_token: "token".to_string(),
+                kind: "linux".to_string(),
+                gpu_required: None,
+                min_vram_mb: None,
+            });
+        }
+
+        let maintenance = Maintenance::new();
+        maintenance.set(true, Some("upgrade".to_string()), false);
+        let messages = maintenance_messages(maintenance.mode().as_ref(), &mut cocoons);
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            &messages[0],
+            SignalingMessage::HiveMaintenance { on: true, reason: Some(r) } if r == "upgrade"
+        ));
+
+        maintenance.set(true, None, true);
+        let messages = maintenance_messages(maintenance.mode().as_ref(), &mut cocoons);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(cocoons.draining.len(), 2);
+
+        // Drains already in flight are not sent again
+        let messages = maintenance_messages(maintenance.mode().as_ref(), &mut cocoons);
+        assert_eq!(messages.len(), 1);
+
+        let messages = maintenance_messages(None, &mut cocoons);
+        assert!(matches!(
+            &messages[0],
+            SignalingMessage::HiveMaintenance { on: false, reason: None }
+        ));
     }
 }
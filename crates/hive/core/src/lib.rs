@@ -4,6 +4,7 @@
 //! - Hive YAML configuration parsing and management
 //! - Service lifecycle management
 //! - Service proxy based on hive.yaml routing
+//! - Reverse proxy config generation for exposed services
 //! - Service exposure for cross-source dependencies
 //! - Multi-source configuration management
 //! - Daemon mode with Unix socket control
@@ -11,8 +12,12 @@
 //! - Plugin system with auto-install
 //! - SQLite configuration backend
 //! - Remote control via signaling server
+//! - Singleton services failing over between hives
+//! - Warm pools of pre-booted cocoons
 
+pub mod cocoon_pool;
 pub mod core_plugins;
+pub mod crash_reports;
 pub mod crypto;
 pub mod daemon;
 pub mod daemon_defaults;
@@ -23,33 +28,45 @@ pub mod exposure;
 pub mod global_registry;
 pub mod hive_config;
 pub mod hive_signaling;
+pub mod log_interleaver;
+pub mod maintenance;
 pub mod observability;
 pub mod observability_plugins;
 pub mod plugin_system;
 pub mod plugins;
+pub mod port_allocations;
+pub mod proxy_config;
 pub mod proxy_plugins;
 pub mod runtime_db;
 pub mod service_manager;
+pub mod service_metrics;
 pub mod service_proxy;
 pub mod signaling_control;
+pub mod singleton;
+pub mod snapshot;
 pub mod source_manager;
 pub mod sqlite_backend;
 
+pub use cocoon_pool::CocoonPool;
 pub use core_plugins::{CorePlugin, CorePluginRegistry, DaemonEvent};
 pub use crypto::hmac_sign;
 pub use daemon::{
-    DaemonClient, DaemonConfig, DaemonRequest, DaemonResponse, DaemonStatus, HiveDaemon,
-    WireServiceStatus, WireExposedServiceInfo, WireLogLine, LogStreamHandle,
-    WireSourceInfo, WireSourceType, WireSourceStatus,
+    CrashReport, DaemonClient, DaemonClientError, DaemonConfig, DaemonRequest, DaemonResponse, DaemonStatus,
+    HiveDaemon, MaintenanceStatus, RestartStrategy, SingletonStatus,
+    WireServiceStatus, WireExposeEdgeInfo, WireExposedServiceInfo, WireLogLine, LogStreamHandle,
+    WireSourceInfo, WireSourceType, WireSourceStatus, StatusStreamHandle, StatusUpdate, EventStreamHandle, HiveEvent,
 };
 pub use dns::{DnsConfig, DnsServer};
 pub use defaults::{apply_all_defaults, apply_service_defaults, merge_json, DefaultsManager};
-pub use exposure::{ExposedService, ExposureManager};
+pub use exposure::{expose_graph, ExposeChange, ExposeEdge, ExposedService, ExposureManager};
 pub use hive_config::{
-    find_project_root, get_rollout_ports, topological_sort, topological_sort_levels,
-    validate_config, ConfigSource, ExposeConfig, HiveConfig, HiveConfigParser, ParseContext,
-    ParsePlugin, RuntimeContext, ServiceConfig, ServiceInfo, ServiceState, SourceType, UsesConfig,
+    find_expose_refs, find_project_root, get_rollout_ports, topological_sort,
+    topological_sort_levels, validate_config, ConfigSource, ExposeConfig, ExposeRef, HiveConfig,
+    HiveConfigParser, ParseContext, ParsePlugin, RuntimeContext, ServiceConfig, ServiceInfo, ServiceState, SourceType, UsesConfig,
 };
+pub use log_interleaver::LogInterleaver;
+pub use maintenance::{Maintenance, MaintenanceMode};
+pub use port_allocations::PortReservations;
 pub use observability::{
     EventCollector, EventSubscription, HealthStatus, LogBuffer, LogLevel, LogLine, LogStream,
     MetricValue, ObservabilityEvent, ServiceEventType, SpanStatus,
@@ -61,14 +78,16 @@ pub use plugin_system::{
     is_core_plugin, plugin_registry, resolve_plugin_id, PluginInfo, PluginRegistry, PluginStatus,
     PluginType,
 };
+pub use proxy_config::{proxy_routes, render_proxy_config, ProxyFormat, ProxyRoute, TlsFiles};
 pub use proxy_plugins::{
     CorsMiddleware, HeadersMiddleware, IpFilterMiddleware, MiddlewareChain, ProxyMiddleware,
     ProxyMiddlewareResult, RateLimitBy, RateLimitMiddleware,
 };
 pub use service_manager::{
-    parse_duration, BlueGreenColor, BlueGreenDeployment, BlueGreenState, DotenvPlugin,
+    parse_duration, BlueGreenColor, BlueGreenDeployment, BlueGreenPhase, BlueGreenState, DotenvPlugin,
     EnvironmentResolver, HealthChecker, OnFailureAction,
     PortsParsePlugin, ProcessManager, ProcessType, RolloutManager, ServiceManager, ServicePhase,
+    SourceProgress,
 };
 pub use service_proxy::{
     create_service_proxy_router, start_service_proxy_server, Route, ServiceProxyState,
@@ -82,6 +101,7 @@ pub use signaling_control::{
 pub use hive_signaling::HiveSignalingConfig;
 pub use global_registry::{GlobalRegistry, RegisteredSource};
 pub use runtime_db::RuntimeDb;
+pub use singleton::{SingletonAction, Singletons};
 pub use source_manager::{read_sources_registry, SourceInfo, SourceManager, SourceStatus};
 pub use sqlite_backend::{RuntimeState, ServicePatch, SqliteBackend};
 
@@ -0,0 +1,99 @@
+//! Log Interleaver
+//!
+//! Merges log lines of several services into one stream ordered by timestamp.
+//! Lines arrive per service in order but services race each other, so lines
+//! are held back for a short window and released oldest first once no earlier
+//! line can still arrive within that window.
+
+use crate::observability::LogLine;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+pub struct LogInterleaver {
+    window: chrono::Duration,
+    pending: Vec<LogLine>,
+}
+
+impl LogInterleaver {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window: chrono::Duration::from_std(window).unwrap_or(chrono::Duration::zero()),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, line: LogLine) {
+        self.pending.push(line);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Release lines older than `now - window`, ordered by timestamp.
+    pub fn drain_ready(&mut self, now: DateTime<Utc>) -> Vec<LogLine> {
+        let cutoff = now - self.window;
+        let (mut ready, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|l| l.timestamp <= cutoff);
+        self.pending = pending;
+        ready.sort_by_key(|l| l.timestamp);
+        ready
+    }
+
+    /// Release every held line, ordered by timestamp.
+    pub fn drain_all(&mut self) -> Vec<LogLine> {
+        let mut lines = std::mem::take(&mut self.pending);
+        lines.sort_by_key(|l| l.timestamp);
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::{LogLevel, LogStream};
+
+    fn line(fqn: &str, at: DateTime<Utc>, message: &str) -> LogLine {
+        LogLine {
+            timestamp: at,
+            service_fqn: fqn.to_string(),
+            level: LogLevel::Info,
+            message: message.to_string(),
+            stream: LogStream::Stdout,
+        }
+    }
+
+    fn messages(lines: Vec<LogLine>) -> Vec<String> {
+        lines.into_iter().map(|l| l.message).collect()
+    }
+
+    #[test]
+    fn test_orders_by_timestamp() {
+        let base = Utc::now();
+        let ms = chrono::Duration::milliseconds;
+        let mut interleaver = LogInterleaver::new(Duration::from_millis(200));
+
+        interleaver.push(line("a:api", base + ms(10), "a1"));
+        interleaver.push(line("a:api", base + ms(30), "a2"));
+        interleaver.push(line("b:db", base, "b1"));
+        interleaver.push(line("b:db", base + ms(20), "b2"));
+
+        assert_eq!(messages(interleaver.drain_all()), ["b1", "a1", "b2", "a2"]);
+        assert!(interleaver.is_empty());
+    }
+
+    #[test]
+    fn test_holds_recent_lines() {
+        let base = Utc::now();
+        let ms = chrono::Duration::milliseconds;
+        let mut interleaver = LogInterleaver::new(Duration::from_millis(200));
+
+        interleaver.push(line("a:api", base + ms(150), "late"));
+        interleaver.push(line("b:db", base, "early"));
+
+        assert_eq!(messages(interleaver.drain_ready(base + ms(250))), ["early"]);
+        assert_eq!(messages(interleaver.drain_ready(base + ms(350))), ["late"]);
+        assert!(interleaver.is_empty());
+    }
+}
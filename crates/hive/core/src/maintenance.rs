@@ -0,0 +1,112 @@
+//! Maintenance mode for the hive daemon.
+//!
+//! While on, the daemon refuses requests that start new work: starting
+//! sources or services, creating services and cocoon spawns arriving over
+//! signaling. With `drain`, the signaling connection also hands running
+//! cocoons to other hives through the signaling server's scheduler and
+//! deletes each local copy once its replacement is running.
+
+use chrono::{DateTime, Utc};
+use lib_hive_daemon_client::MaintenanceStatus;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Requested maintenance mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaintenanceMode {
+    pub reason: Option<String>,
+    pub since: DateTime<Utc>,
+    /// Move running cocoons to other hives
+    pub drain: bool,
+}
+
+/// Shared maintenance state; the mode is `None` while off.
+#[derive(Clone)]
+pub struct Maintenance {
+    mode: Arc<watch::Sender<Option<MaintenanceMode>>>,
+    draining: Arc<AtomicUsize>,
+}
+
+impl Maintenance {
+    pub fn new() -> Self {
+        let (mode, _) = watch::channel(None);
+        Self {
+            mode: Arc::new(mode),
+            draining: Arc::default(),
+        }
+    }
+
+    /// Switch maintenance on or off; turning it on again keeps the original `since`.
+    pub fn set(&self, on: bool, reason: Option<String>, drain: bool) {
+        self.mode.send_modify(|mode| {
+            let since = mode.as_ref().map_or_else(Utc::now, |m| m.since);
+            *mode = on.then_some(MaintenanceMode {
+                reason,
+                since,
+                drain,
+            });
+        });
+        if !on {
+            self.draining.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn mode(&self) -> Option<MaintenanceMode> {
+        self.mode.borrow().clone()
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.mode.borrow().is_some()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Option<MaintenanceMode>> {
+        self.mode.subscribe()
+    }
+
+    /// Record how many cocoons are waiting for a replacement on another hive
+    pub fn set_draining(&self, count: usize) {
+        self.draining.store(count, Ordering::Relaxed);
+    }
+
+    /// Status reported in `DaemonStatus`
+    pub fn status(&self) -> Option<MaintenanceStatus> {
+        self.mode().map(|mode| MaintenanceStatus {
+            reason: mode.reason,
+            since: mode.since,
+            draining: self.draining.load(Ordering::Relaxed),
+        })
+    }
+}
+
+impl Default for Maintenance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maintenance_status() {
+        let maintenance = Maintenance::new();
+        assert!(maintenance.status().is_none());
+
+        maintenance.set(true, Some("kernel upgrade".to_string()), false);
+        let since = maintenance.status().unwrap().since;
+
+        // Turning it on again updates the mode but keeps the start time
+        maintenance.set(true, None, true);
+        maintenance.set_draining(2);
+        let status = maintenance.status().unwrap();
+        assert_eq!(status.since, since);
+        assert_eq!(status.reason, None);
+        assert_eq!(status.draining, 2);
+        assert!(maintenance.mode().unwrap().drain);
+
+        maintenance.set(false, None, false);
+        assert!(!maintenance.is_on());
+    }
+}
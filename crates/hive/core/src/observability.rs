@@ -13,6 +13,7 @@
 //! - **ProxyRequest**: HTTP/WebSocket proxy request traces
 //! - **ResourceMetrics**: Process resource utilization (CPU, memory, etc.)
 
+use crate::hive_config::ServiceDiff;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -101,6 +102,9 @@ pub enum HealthStatus {
     Unknown,
 }
 
+/// `event_name` of the `Custom` event emitted when a source is reloaded
+pub const SOURCE_RELOADED_EVENT: &str = "source_reloaded";
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ServiceEventType {
@@ -280,6 +284,23 @@ impl ObservabilityEvent {
         }
     }
 
+    /// A source's config was re-read from disk. Source-level, so
+    /// `service_fqn` holds the source name; `data` holds the service diff and
+    /// whether the reload was triggered by a file change (`auto`).
+    pub fn source_reloaded(source: impl Into<String>, diff: &ServiceDiff, auto: bool) -> Self {
+        ObservabilityEvent::Custom {
+            timestamp: Utc::now(),
+            service_fqn: source.into(),
+            event_name: SOURCE_RELOADED_EVENT.to_string(),
+            data: serde_json::json!({
+                "added": diff.added,
+                "removed": diff.removed,
+                "changed": diff.changed,
+                "auto": auto,
+            }),
+        }
+    }
+
     pub fn health_check(
         service_fqn: impl Into<String>,
         check_type: impl Into<String>,
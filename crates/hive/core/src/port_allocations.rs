@@ -0,0 +1,183 @@
+//! Port Allocations
+//!
+//! Ports are claimed in two ways: by a service config (rollout ports) or by a
+//! `ReservePort` request, e.g. for a dev server started by hand. A port with
+//! more than one owner is a conflict; `ServiceStatus.port_conflicts` lists the
+//! other owners of a service's ports so the CLI can warn before it is started.
+//! Only the owner may release a reservation. Reservations live in memory; a
+//! snapshot carries them over to a restored daemon.
+
+use chrono::Utc;
+use lib_hive_daemon_client::PortAllocation;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+/// Ports reserved with `ReservePort`, shared across all sources
+#[derive(Debug, Clone, Default)]
+pub struct PortReservations {
+    reserved: Arc<RwLock<BTreeMap<u16, PortAllocation>>>,
+}
+
+impl PortReservations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserve `port` for `owner`. Fails with the allocation holding the port
+    /// when another owner reserved it or has it in `configured`.
+    pub fn reserve(
+        &self,
+        port: u16,
+        owner: &str,
+        name: Option<String>,
+        configured: &[PortAllocation],
+    ) -> Result<PortAllocation, PortAllocation> {
+        if let Some(holder) = configured
+            .iter()
+            .find(|a| a.port == port && a.owner != owner)
+        {
+            return Err(holder.clone());
+        }
+
+        let mut reserved = self.reserved.write().unwrap();
+        match reserved.get(&port) {
+            Some(existing) if existing.owner != owner => Err(existing.clone()),
+            Some(existing) => Ok(existing.clone()),
+            None => {
+                let allocation = PortAllocation {
+                    port,
+                    owner: owner.to_string(),
+                    name,
+                    reserved_at: Some(Utc::now()),
+                };
+                reserved.insert(port, allocation.clone());
+                Ok(allocation)
+            }
+        }
+    }
+
+    /// Drop `owner`'s reservation of `port`, returning it if there was one.
+    /// Fails with the reservation when another owner holds the port.
+    pub fn release(
+        &self,
+        port: u16,
+        owner: &str,
+    ) -> Result<Option<PortAllocation>, PortAllocation> {
+        let mut reserved = self.reserved.write().unwrap();
+        match reserved.get(&port) {
+            Some(existing) if existing.owner != owner => Err(existing.clone()),
+            _ => Ok(reserved.remove(&port)),
+        }
+    }
+
+    pub fn list(&self) -> Vec<PortAllocation> {
+        self.reserved.read().unwrap().values().cloned().collect()
+    }
+
+    /// Configured ports plus reservations, sorted by port and owner
+    pub fn allocations(&self, configured: Vec<PortAllocation>) -> Vec<PortAllocation> {
+        let mut allocations = configured;
+        allocations.extend(self.list());
+        allocations.sort_by(|a, b| (a.port, &a.owner).cmp(&(b.port, &b.owner)));
+        allocations
+    }
+}
+
+/// Allocations by other owners of the ports `owner` holds, either through
+/// `allocations` or its live `ports`
+pub fn conflicts(
+    owner: &str,
+    ports: &HashMap<String, u16>,
+    allocations: &[PortAllocation],
+) -> Vec<PortAllocation> {
+    let held: HashSet<u16> = allocations
+        .iter()
+        .filter(|a| a.owner == owner)
+        .map(|a| a.port)
+        .chain(ports.values().copied())
+        .collect();
+
+    allocations
+        .iter()
+        .filter(|a| a.owner != owner && held.contains(&a.port))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configured(owner: &str, name: &str, port: u16) -> PortAllocation {
+        PortAllocation {
+            port,
+            owner: owner.to_string(),
+            name: Some(name.to_string()),
+            reserved_at: None,
+        }
+    }
+
+    #[test]
+    fn test_reserve_refuses_other_owners() {
+        let reservations = PortReservations::new();
+        let config = vec![configured("app:api", "http", 8080)];
+
+        let holder = reservations
+            .reserve(8080, "manual", None, &config)
+            .unwrap_err();
+        assert_eq!(holder.owner, "app:api");
+
+        // The configured owner may reserve its own port
+        assert!(reservations
+            .reserve(8080, "app:api", Some("http".to_string()), &config)
+            .is_ok());
+
+        let first = reservations.reserve(3000, "manual", None, &config).unwrap();
+        assert!(first.is_reservation());
+        assert_eq!(
+            reservations.reserve(3000, "manual", None, &config),
+            Ok(first)
+        );
+        assert_eq!(
+            reservations
+                .reserve(3000, "app:web", None, &config)
+                .unwrap_err()
+                .owner,
+            "manual"
+        );
+
+        assert_eq!(
+            reservations.release(3000, "app:web").unwrap_err().owner,
+            "manual"
+        );
+        assert!(reservations.release(3000, "manual").unwrap().is_some());
+        assert!(reservations.release(3000, "manual").unwrap().is_none());
+        assert!(reservations.reserve(3000, "app:web", None, &config).is_ok());
+    }
+
+    #[test]
+    fn test_conflicts_list_other_owners() {
+        let reservations = PortReservations::new();
+        reservations.reserve(5432, "manual", None, &[]).unwrap();
+        let allocations = reservations.allocations(vec![
+            configured("app:db", "pg", 5432),
+            configured("app:api", "http", 8080),
+            configured("other:api", "http", 8080),
+        ]);
+        assert_eq!(allocations[0].owner, "app:db");
+        assert_eq!(allocations[1].owner, "manual");
+
+        let db = conflicts("app:db", &HashMap::new(), &allocations);
+        assert_eq!(db.len(), 1);
+        assert_eq!(db[0].owner, "manual");
+
+        let api = conflicts("app:api", &HashMap::new(), &allocations);
+        assert_eq!(api.len(), 1);
+        assert_eq!(api[0].owner, "other:api");
+
+        // Live ports count even without a configured allocation
+        let live = HashMap::from([("http".to_string(), 5432)]);
+        assert_eq!(conflicts("app:web", &live, &allocations).len(), 2);
+        assert!(conflicts("app:web", &HashMap::new(), &allocations).is_empty());
+    }
+}
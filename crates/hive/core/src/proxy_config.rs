@@ -0,0 +1,323 @@
+//! Reverse proxy configuration for exposed services.
+//!
+//! Renders a Caddy, nginx or Traefik config that routes one host per exposed
+//! port to the local service (`adi hive proxy-config`). Hosts are
+//! `{expose}.{domain}` for the primary port (`http`, or the only one) and
+//! `{expose}-{port}.{domain}` for the rest. Certificates issued by the
+//! proxy-ssl plugin are picked up from its certificate directory, either for
+//! the exact host or a `*.{domain}` wildcard.
+
+use crate::daemon::WireExposedServiceInfo;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Certificate directory of the proxy-ssl plugin
+pub const DEFAULT_CERT_DIR: &str = "/var/lib/hive/certs";
+
+/// Port name routed to the bare `{expose}.{domain}` host
+const PRIMARY_PORT: &str = "http";
+
+const HEADER: &str = "Generated by `adi hive proxy-config`; manual changes are overwritten.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyFormat {
+    Caddy,
+    Nginx,
+    Traefik,
+}
+
+impl FromStr for ProxyFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "caddy" => Ok(Self::Caddy),
+            "nginx" => Ok(Self::Nginx),
+            "traefik" => Ok(Self::Traefik),
+            other => Err(format!(
+                "Unknown proxy format '{}' (expected caddy, nginx or traefik)",
+                other
+            )),
+        }
+    }
+}
+
+/// Certificate and key of a host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// One public host in front of a local port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyRoute {
+    pub host: String,
+    pub upstream: String,
+    /// `None` serves plain HTTP (Caddy provisions its own certificate)
+    pub tls: Option<TlsFiles>,
+}
+
+/// Routes for every exposed port, sorted by host
+pub fn proxy_routes(
+    exposed: &[WireExposedServiceInfo],
+    domain: &str,
+    upstream_host: &str,
+    cert_dir: &Path,
+) -> Vec<ProxyRoute> {
+    let mut routes = Vec::new();
+    for service in exposed {
+        let mut ports: Vec<(&String, &u16)> = service.ports.iter().collect();
+        ports.sort();
+        let single = ports.len() == 1;
+        let name = host_label(&service.name);
+
+        for (port_name, port) in ports {
+            let host = if single || port_name == PRIMARY_PORT {
+                format!("{}.{}", name, domain)
+            } else {
+                format!("{}-{}.{}", name, host_label(port_name), domain)
+            };
+            routes.push(ProxyRoute {
+                tls: find_certificate(cert_dir, &host, domain),
+                upstream: format!("{}:{}", upstream_host, port),
+                host,
+            });
+        }
+    }
+    routes.sort_by(|a, b| a.host.cmp(&b.host));
+    routes
+}
+
+/// Render `routes` in the proxy's own config format
+pub fn render_proxy_config(format: ProxyFormat, routes: &[ProxyRoute]) -> String {
+    match format {
+        ProxyFormat::Caddy => render_caddy(routes),
+        ProxyFormat::Nginx => render_nginx(routes),
+        ProxyFormat::Traefik => render_traefik(routes),
+    }
+}
+
+fn render_caddy(routes: &[ProxyRoute]) -> String {
+    let mut out = format!("# {}\n", HEADER);
+    for route in routes {
+        let _ = writeln!(out, "\n{} {{", route.host);
+        if let Some(tls) = &route.tls {
+            let _ = writeln!(out, "\ttls {} {}", tls.cert.display(), tls.key.display());
+        }
+        let _ = writeln!(out, "\treverse_proxy {}", route.upstream);
+        out.push_str("}\n");
+    }
+    out
+}
+
+fn render_nginx(routes: &[ProxyRoute]) -> String {
+    let mut out = format!("# {}\n", HEADER);
+    for route in routes {
+        out.push_str("\nserver {\n");
+        match &route.tls {
+            Some(tls) => {
+                out.push_str("    listen 443 ssl;\n");
+                let _ = writeln!(out, "    server_name {};", route.host);
+                let _ = writeln!(out, "    ssl_certificate {};", tls.cert.display());
+                let _ = writeln!(out, "    ssl_certificate_key {};", tls.key.display());
+            }
+            None => {
+                out.push_str("    listen 80;\n");
+                let _ = writeln!(out, "    server_name {};", route.host);
+            }
+        }
+        out.push_str("\n    location / {\n");
+        let _ = writeln!(out, "        proxy_pass http://{};", route.upstream);
+        out.push_str(
+            "        proxy_http_version 1.1;\n\
+             \x20       proxy_set_header Host $host;\n\
+             \x20       proxy_set_header X-Real-IP $remote_addr;\n\
+             \x20       proxy_set_header X-Forwarded-For $proxy_add_x_forwarded_for;\n\
+             \x20       proxy_set_header X-Forwarded-Proto $scheme;\n\
+             \x20       proxy_set_header Upgrade $http_upgrade;\n\
+             \x20       proxy_set_header Connection \"upgrade\";\n\
+             \x20   }\n\
+             }\n",
+        );
+
+        if route.tls.is_some() {
+            out.push_str("\nserver {\n    listen 80;\n");
+            let _ = writeln!(out, "    server_name {};", route.host);
+            out.push_str("    return 301 https://$host$request_uri;\n}\n");
+        }
+    }
+    out
+}
+
+/// Traefik file-provider dynamic configuration (YAML)
+fn render_traefik(routes: &[ProxyRoute]) -> String {
+    let mut out = format!("# {}\n", HEADER);
+    if routes.is_empty() {
+        out.push_str("http: {}\n");
+        return out;
+    }
+
+    out.push_str("http:\n  routers:\n");
+    for route in routes {
+        let id = route.host.replace('.', "-");
+        let _ = writeln!(out, "    {}:", id);
+        let _ = writeln!(out, "      rule: \"Host(`{}`)\"", route.host);
+        let _ = writeln!(out, "      service: {}", id);
+        if route.tls.is_some() {
+            out.push_str("      entryPoints: [websecure]\n      tls: {}\n");
+        } else {
+            out.push_str("      entryPoints: [web]\n");
+        }
+    }
+
+    out.push_str("  services:\n");
+    for route in routes {
+        let _ = writeln!(out, "    {}:", route.host.replace('.', "-"));
+        out.push_str("      loadBalancer:\n        servers:\n");
+        let _ = writeln!(out, "          - url: \"http://{}\"", route.upstream);
+    }
+
+    let mut certs: Vec<&TlsFiles> = Vec::new();
+    for tls in routes.iter().filter_map(|r| r.tls.as_ref()) {
+        if !certs.contains(&tls) {
+            certs.push(tls);
+        }
+    }
+    if !certs.is_empty() {
+        out.push_str("tls:\n  certificates:\n");
+        for tls in certs {
+            let _ = writeln!(out, "    - certFile: {}", tls.cert.display());
+            let _ = writeln!(out, "      keyFile: {}", tls.key.display());
+        }
+    }
+    out
+}
+
+/// Certificate of `host`, falling back to a `*.{domain}` wildcard
+fn find_certificate(cert_dir: &Path, host: &str, domain: &str) -> Option<TlsFiles> {
+    [host.to_string(), format!("*.{}", domain)]
+        .iter()
+        .map(|name| {
+            let dir = cert_dir.join(sanitize_domain(name));
+            TlsFiles {
+                cert: dir.join("cert.pem"),
+                key: dir.join("privkey.pem"),
+            }
+        })
+        .find(|tls| tls.cert.is_file() && tls.key.is_file())
+}
+
+/// Directory name proxy-ssl stores a domain's certificate under
+fn sanitize_domain(domain: &str) -> String {
+    domain
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn host_label(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn exposed(name: &str, ports: &[(&str, u16)]) -> WireExposedServiceInfo {
+        let ports: HashMap<String, u16> = ports.iter().map(|(n, p)| (n.to_string(), *p)).collect();
+        WireExposedServiceInfo {
+            name: name.to_string(),
+            source: "app".to_string(),
+            service: name.to_string(),
+            healthy: true,
+            var_names: Vec::new(),
+            port_names: ports.keys().cloned().collect(),
+            ports,
+        }
+    }
+
+    #[test]
+    fn test_proxy_routes_hosts_and_certificates() {
+        let certs = tempfile::tempdir().unwrap();
+        let wildcard = certs.path().join("_.example.com");
+        std::fs::create_dir_all(&wildcard).unwrap();
+        std::fs::write(wildcard.join("cert.pem"), "").unwrap();
+        std::fs::write(wildcard.join("privkey.pem"), "").unwrap();
+
+        let routes = proxy_routes(
+            &[
+                exposed("api", &[("http", 8080), ("grpc", 9090)]),
+                exposed("web_ui", &[("main", 3000)]),
+                exposed("config", &[]),
+            ],
+            "example.com",
+            "127.0.0.1",
+            certs.path(),
+        );
+
+        let hosts: Vec<&str> = routes.iter().map(|r| r.host.as_str()).collect();
+        assert_eq!(
+            hosts,
+            [
+                "api-grpc.example.com",
+                "api.example.com",
+                "web-ui.example.com"
+            ]
+        );
+        assert_eq!(routes[1].upstream, "127.0.0.1:8080");
+        assert_eq!(
+            routes[0].tls.as_ref().unwrap().cert,
+            wildcard.join("cert.pem")
+        );
+    }
+
+    #[test]
+    fn test_render_proxy_config() {
+        let routes = vec![
+            ProxyRoute {
+                host: "api.example.com".to_string(),
+                upstream: "127.0.0.1:8080".to_string(),
+                tls: Some(TlsFiles {
+                    cert: PathBuf::from("/certs/api/cert.pem"),
+                    key: PathBuf::from("/certs/api/privkey.pem"),
+                }),
+            },
+            ProxyRoute {
+                host: "web.example.com".to_string(),
+                upstream: "127.0.0.1:3000".to_string(),
+                tls: None,
+            },
+        ];
+
+        let caddy = render_proxy_config(ProxyFormat::Caddy, &routes);
+        assert!(caddy.contains(
+            "api.example.com {\n\ttls /certs/api/cert.pem /certs/api/privkey.pem\n\treverse_proxy 127.0.0.1:8080\n}"
+        ));
+        assert!(caddy.contains("web.example.com {\n\treverse_proxy 127.0.0.1:3000\n}"));
+
+        let nginx = render_proxy_config(ProxyFormat::Nginx, &routes);
+        assert!(nginx.contains("listen 443 ssl;\n    server_name api.example.com;"));
+        assert!(nginx.contains("proxy_pass http://127.0.0.1:3000;"));
+        assert_eq!(nginx.matches("return 301").count(), 1);
+
+        let traefik = render_proxy_config(ProxyFormat::Traefik, &routes);
+        assert!(traefik.contains("rule: \"Host(`web.example.com`)\""));
+        assert!(traefik.contains("- url: \"http://127.0.0.1:8080\""));
+        assert!(traefik.contains("- certFile: /certs/api/cert.pem"));
+
+        assert_eq!("nginx".parse(), Ok(ProxyFormat::Nginx));
+        assert!("apache".parse::<ProxyFormat>().is_err());
+    }
+}
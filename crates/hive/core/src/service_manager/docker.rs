@@ -0,0 +1,352 @@
+//! Docker integration for services run by the `docker` runner.
+//!
+//! The runner plugin creates and removes containers; this module follows a
+//! running container from the daemon side: its output goes into the event
+//! collector like script output, its Docker health status backs `healthy`
+//! when the service has no hive healthcheck, and its stats are reported as
+//! `resource_metrics` events.
+
+use crate::observability::{EventCollector, HealthStatus, LogStream, ObservabilityEvent};
+use crate::service_manager::ProcessManager;
+use anyhow::{Context, Result};
+use bollard::container::{
+    InspectContainerOptions, LogOutput, LogsOptions, RestartContainerOptions, Stats, StatsOptions,
+};
+use bollard::models::{ContainerState, HealthStatusEnum};
+use bollard::Docker;
+use chrono::Utc;
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Used when the source sets no `observability.resource_interval`
+pub const DEFAULT_STATS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Seconds Docker waits for a graceful stop before killing, as in the runner
+const STOP_TIMEOUT_SECS: isize = 10;
+
+/// Background log and stats collection for one container; stops on drop.
+pub struct DockerMonitor {
+    container: String,
+    health: watch::Receiver<Option<bool>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl DockerMonitor {
+    /// Start following `container`. Stats are only collected when
+    /// `stats_interval` is set.
+    pub fn start(
+        container: String,
+        service_fqn: String,
+        event_collector: Option<Arc<EventCollector>>,
+        stats_interval: Option<Duration>,
+    ) -> Result<Self> {
+        let docker =
+            Docker::connect_with_local_defaults().context("Failed to connect to Docker")?;
+        let (health_tx, health) = watch::channel(None);
+
+        let mut tasks = Vec::new();
+        if let Some(collector) = &event_collector {
+            tasks.push(tokio::spawn(follow_logs(
+                docker.clone(),
+                container.clone(),
+                service_fqn.clone(),
+                collector.clone(),
+            )));
+        }
+        tasks.push(tokio::spawn(watch_container(
+            docker,
+            container.clone(),
+            service_fqn,
+            event_collector,
+            stats_interval,
+            health_tx,
+        )));
+
+        Ok(Self {
+            container,
+            health,
+            tasks,
+        })
+    }
+
+    pub fn container(&self) -> &str {
+        &self.container
+    }
+
+    /// Last Docker health status; `None` if the image has no HEALTHCHECK or
+    /// the check is still starting.
+    pub fn healthy(&self) -> Option<bool> {
+        *self.health.borrow()
+    }
+}
+
+impl Drop for DockerMonitor {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Restart a container in place through the Docker API.
+pub async fn restart_container(container: &str) -> Result<()> {
+    let docker = Docker::connect_with_local_defaults().context("Failed to connect to Docker")?;
+    docker
+        .restart_container(
+            container,
+            Some(RestartContainerOptions {
+                t: STOP_TIMEOUT_SECS,
+            }),
+        )
+        .await
+        .with_context(|| format!("Failed to restart container {}", container))
+}
+
+/// One-off health lookup, for status queries without a running monitor.
+pub async fn container_health(container: &str) -> Option<bool> {
+    let docker = Docker::connect_with_local_defaults().ok()?;
+    let info = docker
+        .inspect_container(container, None::<InspectContainerOptions>)
+        .await
+        .ok()?;
+    info.state.as_ref().and_then(health_from_state)
+}
+
+/// A stopped container is unhealthy; otherwise use its HEALTHCHECK result.
+fn health_from_state(state: &ContainerState) -> Option<bool> {
+    if state.running == Some(false) {
+        return Some(false);
+    }
+    match state.health.as_ref()?.status? {
+        HealthStatusEnum::HEALTHY => Some(true),
+        HealthStatusEnum::UNHEALTHY => Some(false),
+        HealthStatusEnum::STARTING | HealthStatusEnum::NONE | HealthStatusEnum::EMPTY => None,
+    }
+}
+
+/// Stream container output into the event collector, reattaching after the
+/// container restarts.
+async fn follow_logs(
+    docker: Docker,
+    container: String,
+    service_fqn: String,
+    collector: Arc<EventCollector>,
+) {
+    let mut since = Utc::now().timestamp();
+    loop {
+        let options = LogsOptions::<String> {
+            follow: true,
+            stdout: true,
+            stderr: true,
+            since,
+            ..Default::default()
+        };
+        let mut stream = docker.logs(&container, Some(options));
+        while let Some(output) = stream.next().await {
+            match output {
+                Ok(output) => {
+                    for event in log_events(&service_fqn, output) {
+                        collector.emit(event);
+                    }
+                }
+                Err(e) => {
+                    debug!("Log stream for {} ended: {}", container, e);
+                    break;
+                }
+            }
+        }
+        since = Utc::now().timestamp();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+fn log_events(service_fqn: &str, output: LogOutput) -> Vec<ObservabilityEvent> {
+    let (stream, message) = match output {
+        LogOutput::StdErr { message } => (LogStream::Stderr, message),
+        LogOutput::StdOut { message } | LogOutput::Console { message } => {
+            (LogStream::Stdout, message)
+        }
+        LogOutput::StdIn { .. } => return Vec::new(),
+    };
+    String::from_utf8_lossy(&message)
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let level = ProcessManager::detect_log_level(line);
+            ObservabilityEvent::log(service_fqn, level, line, stream)
+        })
+        .collect()
+}
+
+/// Poll health every few seconds and stats every `stats_interval`.
+async fn watch_container(
+    docker: Docker,
+    container: String,
+    service_fqn: String,
+    collector: Option<Arc<EventCollector>>,
+    stats_interval: Option<Duration>,
+    health_tx: watch::Sender<Option<bool>>,
+) {
+    let tick = Duration::from_secs(5).min(stats_interval.unwrap_or(Duration::MAX));
+    let mut last_stats: Option<tokio::time::Instant> = None;
+    loop {
+        let state = docker
+            .inspect_container(&container, None::<InspectContainerOptions>)
+            .await
+            .ok()
+            .and_then(|info| info.state);
+
+        let healthy = state.as_ref().and_then(health_from_state);
+        let changed = health_tx.send_if_modified(|current| {
+            let changed = *current != healthy;
+            *current = healthy;
+            changed
+        });
+        if let (true, Some(collector), Some(healthy)) = (changed, &collector, healthy) {
+            let status = if healthy {
+                HealthStatus::Healthy
+            } else {
+                HealthStatus::Unhealthy
+            };
+            collector.emit(ObservabilityEvent::health_check(
+                &service_fqn,
+                "docker",
+                status,
+                0,
+                None,
+            ));
+        }
+
+        let running = state.as_ref().and_then(|s| s.running).unwrap_or(false);
+        let stats_due = stats_interval
+            .is_some_and(|interval| last_stats.is_none_or(|at| at.elapsed() >= interval));
+        if let (true, true, Some(collector)) = (running, stats_due, &collector) {
+            last_stats = Some(tokio::time::Instant::now());
+            let pid = state.as_ref().and_then(|s| s.pid).unwrap_or(0) as u32;
+            // Not one-shot: Docker waits for a second sample so CPU usage has a delta
+            let options = StatsOptions {
+                stream: false,
+                one_shot: false,
+            };
+            match docker.stats(&container, Some(options)).next().await {
+                Some(Ok(stats)) => collector.emit(resource_metrics(&service_fqn, pid, &stats)),
+                Some(Err(e)) => debug!("Failed to read stats for {}: {}", container, e),
+                None => {}
+            }
+        }
+
+        tokio::time::sleep(tick).await;
+    }
+}
+
+fn resource_metrics(service_fqn: &str, pid: u32, stats: &Stats) -> ObservabilityEvent {
+    let cpu_delta = stats
+        .cpu_stats
+        .cpu_usage
+        .total_usage
+        .saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
+    let system_delta = stats
+        .cpu_stats
+        .system_cpu_usage
+        .zip(stats.precpu_stats.system_cpu_usage)
+        .map(|(now, before)| now.saturating_sub(before))
+        .unwrap_or(0);
+    let cpus = stats.cpu_stats.online_cpus.unwrap_or(1).max(1);
+    let cpu_percent = if system_delta > 0 {
+        (cpu_delta as f64 / system_delta as f64 * cpus as f64 * 100.0) as f32
+    } else {
+        0.0
+    };
+
+    let (rx, tx) = stats
+        .networks
+        .iter()
+        .flat_map(|networks| networks.values())
+        .fold((0, 0), |(rx, tx), n| (rx + n.rx_bytes, tx + n.tx_bytes));
+
+    ObservabilityEvent::ResourceMetrics {
+        timestamp: Utc::now(),
+        service_fqn: service_fqn.to_string(),
+        pid,
+        cpu_percent,
+        memory_rss_bytes: stats.memory_stats.usage.unwrap_or(0),
+        memory_vms_bytes: 0,
+        open_fds: 0,
+        threads: stats.pids_stats.current.unwrap_or(0) as u32,
+        network_rx_bytes: rx,
+        network_tx_bytes: tx,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::LogLevel;
+    use bollard::models::Health;
+
+    fn state(running: bool, health: Option<HealthStatusEnum>) -> ContainerState {
+        ContainerState {
+            running: Some(running),
+            health: health.map(|status| Health {
+                status: Some(status),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_health_from_state() {
+        assert_eq!(health_from_state(&state(true, None)), None);
+        assert_eq!(
+            health_from_state(&state(true, Some(HealthStatusEnum::STARTING))),
+            None
+        );
+        assert_eq!(
+            health_from_state(&state(true, Some(HealthStatusEnum::HEALTHY))),
+            Some(true)
+        );
+        assert_eq!(
+            health_from_state(&state(true, Some(HealthStatusEnum::UNHEALTHY))),
+            Some(false)
+        );
+        assert_eq!(
+            health_from_state(&state(false, Some(HealthStatusEnum::HEALTHY))),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_log_events_split_lines() {
+        let output = LogOutput::StdErr {
+            message: "ready\n\n[ERROR] disk full\n".into(),
+        };
+        let events = log_events("default:db", output);
+        assert_eq!(events.len(), 2);
+        match &events[1] {
+            ObservabilityEvent::Log {
+                level,
+                message,
+                stream,
+                ..
+            } => {
+                assert_eq!(*level, LogLevel::Error);
+                assert_eq!(message, "[ERROR] disk full");
+                assert_eq!(*stream, LogStream::Stderr);
+            }
+            other => panic!("expected log event, got {:?}", other),
+        }
+        assert!(log_events(
+            "default:db",
+            LogOutput::StdIn {
+                message: "x".into()
+            }
+        )
+        .is_empty());
+    }
+}
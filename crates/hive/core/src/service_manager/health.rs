@@ -2,11 +2,14 @@ use crate::hive_config::{
     extract_cmd_health_config, extract_http_health_config, extract_tcp_health_config, HealthCheck,
     HealthCheckConfig, RuntimeContext,
 };
+use crate::observability::{self, EventCollector, ObservabilityEvent};
 use anyhow::{anyhow, Result};
+use chrono::Utc;
+use lib_hive_daemon_client::{HealthProbe, ProbeCheck, ProbeResult};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
 use tokio::process::Command;
@@ -15,13 +18,47 @@ use tracing::{debug, info, warn};
 /// Shared via Arc — updated by check tasks, read by anyone.
 pub struct HealthStatus {
     results: Vec<AtomicBool>,
+    /// Latest probe of each check
+    probes: Vec<Mutex<Option<ProbeResult>>>,
     total: usize,
+    /// Set when the checked instance is replaced; its check tasks then exit
+    retired: AtomicBool,
 }
 
 impl HealthStatus {
     pub fn new(total: usize) -> Self {
         let results = (0..total).map(|_| AtomicBool::new(false)).collect();
-        Self { results, total }
+        let probes = (0..total).map(|_| Mutex::new(None)).collect();
+        Self {
+            results,
+            probes,
+            total,
+            retired: AtomicBool::new(false),
+        }
+    }
+
+    /// Stop the check tasks feeding this status, e.g. after a blue/green
+    /// restart moved the service to other ports.
+    pub fn retire(&self) {
+        self.retired.store(true, Ordering::Relaxed);
+    }
+
+    /// The most recent failed probe, so a status can say why the service is
+    /// unhealthy; the most recent probe when none failed.
+    pub fn last_probe(&self) -> Option<ProbeResult> {
+        let probes: Vec<ProbeResult> = self
+            .probes
+            .iter()
+            .filter_map(|probe| probe.lock().ok().and_then(|p| p.clone()))
+            .collect();
+        let latest = |passed: bool| {
+            probes
+                .iter()
+                .filter(|p| p.passed == passed)
+                .max_by_key(|p| p.checked_at)
+                .cloned()
+        };
+        latest(false).or_else(|| latest(true))
     }
 
     pub fn healthy_count(&self) -> usize {
@@ -36,6 +73,38 @@ impl HealthStatus {
     }
 }
 
+/// Detail of one check run
+struct ProbeOutcome {
+    passed: bool,
+    status_code: Option<u16>,
+    failure: Option<String>,
+}
+
+impl ProbeOutcome {
+    fn passed() -> Self {
+        Self {
+            passed: true,
+            status_code: None,
+            failure: None,
+        }
+    }
+
+    fn failed(reason: impl Into<String>) -> Self {
+        Self {
+            passed: false,
+            status_code: None,
+            failure: Some(reason.into()),
+        }
+    }
+}
+
+/// Reports when a service's overall health flips, as a `HealthCheck` event.
+#[derive(Clone)]
+pub struct HealthReporter {
+    pub collector: Arc<EventCollector>,
+    pub service_fqn: String,
+}
+
 pub struct HealthChecker {
     client: reqwest::Client,
 }
@@ -62,6 +131,7 @@ impl HealthChecker {
         service_name: &str,
         config: &HealthCheckConfig,
         ports: &HashMap<String, u16>,
+        reporter: Option<HealthReporter>,
     ) -> Arc<HealthStatus> {
         let checks = config.checks();
         let interval = self.parse_interval(&checks);
@@ -74,6 +144,7 @@ impl HealthChecker {
             let check = check.clone();
             let status = Arc::clone(&status);
             let name = service_name.to_string();
+            let reporter = reporter.clone();
 
             tokio::spawn(async move {
                 if start_period > Duration::ZERO {
@@ -87,8 +158,17 @@ impl HealthChecker {
                 }
 
                 let checker = HealthChecker { client: checker };
-                run_check_loop(&checker, &name, &check, &ports, &status.results[i], interval)
-                    .await;
+                run_check_loop(
+                    &checker,
+                    &name,
+                    &check,
+                    &ports,
+                    &status,
+                    i,
+                    reporter.as_ref(),
+                    interval,
+                )
+                .await;
             });
         }
 
@@ -100,6 +180,36 @@ impl HealthChecker {
         check: &HealthCheck,
         ports: &HashMap<String, u16>,
     ) -> Result<bool> {
+        Ok(self.run_probe(check, ports).await?.passed)
+    }
+
+    /// Run `check` once and time it. Config errors count as failed probes.
+    /// `consecutive_failures` is left for the caller to fill in.
+    pub async fn probe(&self, check: &HealthCheck, ports: &HashMap<String, u16>) -> ProbeResult {
+        let started = Instant::now();
+        let outcome = self
+            .run_probe(check, ports)
+            .await
+            .unwrap_or_else(|e| ProbeOutcome::failed(e.to_string()));
+        ProbeResult {
+            check: match check.check_type.as_str() {
+                "cmd" => "exec".to_string(),
+                other => other.to_string(),
+            },
+            passed: outcome.passed,
+            checked_at: Utc::now(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            status_code: outcome.status_code,
+            failure: outcome.failure,
+            consecutive_failures: 0,
+        }
+    }
+
+    async fn run_probe(
+        &self,
+        check: &HealthCheck,
+        ports: &HashMap<String, u16>,
+    ) -> Result<ProbeOutcome> {
         let mut runtime_ctx = RuntimeContext::new();
         runtime_ctx.set_ports(ports.clone());
 
@@ -111,7 +221,11 @@ impl HealthChecker {
         }
     }
 
-    async fn check_http(&self, check: &HealthCheck, runtime_ctx: &RuntimeContext) -> Result<bool> {
+    async fn check_http(
+        &self,
+        check: &HealthCheck,
+        runtime_ctx: &RuntimeContext,
+    ) -> Result<ProbeOutcome> {
         let config = extract_http_health_config(check)?;
 
         let port_str = runtime_ctx.interpolate(&config.port)?;
@@ -146,16 +260,30 @@ impl HealthChecker {
                     status.as_u16() == expected
                 };
 
-                Ok(is_healthy)
+                Ok(ProbeOutcome {
+                    passed: is_healthy,
+                    status_code: Some(status.as_u16()),
+                    failure: (!is_healthy).then(|| format!("HTTP {}", status)),
+                })
             }
             Err(e) => {
                 debug!("HTTP health check failed: {}", e);
-                Ok(false)
+                Ok(ProbeOutcome::failed(if e.is_timeout() {
+                    timed_out(timeout)
+                } else if e.is_connect() {
+                    format!("connection to port {} failed", port)
+                } else {
+                    e.to_string()
+                }))
             }
         }
     }
 
-    async fn check_tcp(&self, check: &HealthCheck, runtime_ctx: &RuntimeContext) -> Result<bool> {
+    async fn check_tcp(
+        &self,
+        check: &HealthCheck,
+        runtime_ctx: &RuntimeContext,
+    ) -> Result<ProbeOutcome> {
         let config = extract_tcp_health_config(check)?;
 
         let port_str = runtime_ctx.interpolate(&config.port)?;
@@ -174,20 +302,23 @@ impl HealthChecker {
         match tokio::time::timeout(timeout, TcpStream::connect(&addr)).await {
             Ok(Ok(mut stream)) => {
                 let _ = stream.shutdown().await;
-                Ok(true)
+                Ok(ProbeOutcome::passed())
             }
             Ok(Err(e)) => {
                 debug!("TCP health check failed to connect: {}", e);
-                Ok(false)
+                Ok(ProbeOutcome::failed(format!(
+                    "connection to port {} failed: {}",
+                    port, e
+                )))
             }
             Err(_) => {
                 debug!("TCP health check timed out");
-                Ok(false)
+                Ok(ProbeOutcome::failed(timed_out(timeout)))
             }
         }
     }
 
-    async fn check_cmd(&self, check: &HealthCheck) -> Result<bool> {
+    async fn check_cmd(&self, check: &HealthCheck) -> Result<ProbeOutcome> {
         let config = extract_cmd_health_config(check)?;
 
         let timeout = config
@@ -206,14 +337,17 @@ impl HealthChecker {
         }
 
         match tokio::time::timeout(timeout, cmd.output()).await {
-            Ok(Ok(output)) => Ok(output.status.success()),
+            Ok(Ok(output)) => Ok(command_outcome(&output)),
             Ok(Err(e)) => {
                 debug!("Command health check failed: {}", e);
-                Ok(false)
+                Ok(ProbeOutcome::failed(format!(
+                    "failed to run command: {}",
+                    e
+                )))
             }
             Err(_) => {
                 debug!("Command health check timed out");
-                Ok(false)
+                Ok(ProbeOutcome::failed(timed_out(timeout)))
             }
         }
     }
@@ -235,7 +369,7 @@ impl HealthChecker {
         cmd.args(["exec", container_name, "sh", "-c", &config.command]);
 
         match tokio::time::timeout(timeout, cmd.output()).await {
-            Ok(Ok(output)) => Ok(output.status.success()),
+            Ok(Ok(output)) => Ok(command_outcome(&output).passed),
             Ok(Err(e)) => {
                 debug!("Docker exec health check failed: {}", e);
                 Ok(false)
@@ -262,6 +396,16 @@ impl HealthChecker {
         Duration::from_secs(10)
     }
 
+    /// Failed runs of `check` tolerated before it counts as failing
+    fn parse_retries(check: &HealthCheck) -> u32 {
+        check
+            .config
+            .get(&check.check_type)
+            .and_then(|c| c.get("retries"))
+            .and_then(|v| v.as_u64())
+            .map_or(0, |r| r as u32)
+    }
+
     fn parse_start_period(&self, checks: &[&HealthCheck]) -> Duration {
         for check in checks {
             if let Some(period) = check
@@ -316,22 +460,40 @@ impl HealthChecker {
     }
 }
 
-/// Runs one check forever, writes result to its atomic slot.
+/// Runs one check forever, writes result to its slot in `status` and reports
+/// when the service as a whole turns healthy or unhealthy. A passing check
+/// only starts failing after more failed runs in a row than its `retries`.
+#[allow(clippy::too_many_arguments)]
 async fn run_check_loop(
     checker: &HealthChecker,
     service_name: &str,
     check: &HealthCheck,
     ports: &HashMap<String, u16>,
-    slot: &AtomicBool,
+    status: &HealthStatus,
+    index: usize,
+    reporter: Option<&HealthReporter>,
     interval: Duration,
 ) {
-    loop {
-        let ok = checker
-            .run_single_check(check, ports)
-            .await
-            .unwrap_or(false);
+    let retries = HealthChecker::parse_retries(check);
+    let mut consecutive_failures = 0u32;
+    while !status.retired.load(Ordering::Relaxed) {
+        let mut probe = checker.probe(check, ports).await;
+        consecutive_failures = if probe.passed {
+            0
+        } else {
+            consecutive_failures.saturating_add(1)
+        };
+        probe.consecutive_failures = consecutive_failures;
+        let failing = consecutive_failures > retries;
+        let ok = probe.passed || (!failing && status.results[index].load(Ordering::Relaxed));
+        let latency_ms = probe.latency_ms.min(u32::MAX as u64) as u32;
+        let failure = probe.failure.clone();
+        if let Ok(mut slot) = status.probes[index].lock() {
+            *slot = Some(probe);
+        }
 
-        let was = slot.swap(ok, Ordering::Relaxed);
+        let was_healthy = status.is_healthy();
+        let was = status.results[index].swap(ok, Ordering::Relaxed);
         if ok && !was {
             info!(
                 "Health check {} now passing for {}",
@@ -339,13 +501,134 @@ async fn run_check_loop(
             );
         } else if !ok && was {
             warn!(
-                "Health check {} now failing for {}",
-                check.check_type, service_name
+                "Health check {} now failing for {}: {}",
+                check.check_type,
+                service_name,
+                failure.as_deref().unwrap_or("-")
             );
         }
 
+        let healthy = status.is_healthy();
+        if let (true, Some(reporter)) = (healthy != was_healthy, reporter) {
+            let health = if healthy {
+                observability::HealthStatus::Healthy
+            } else {
+                observability::HealthStatus::Unhealthy
+            };
+            reporter.collector.emit(ObservabilityEvent::health_check(
+                &reporter.service_fqn,
+                &check.check_type,
+                health,
+                latency_ms,
+                failure,
+            ));
+        }
+
         tokio::time::sleep(interval).await;
     }
 }
 
+fn timed_out(timeout: Duration) -> String {
+    format!("timed out after {}ms", timeout.as_millis())
+}
+
+/// Exit code 0 passes; otherwise the failure carries the last stderr line
+fn command_outcome(output: &std::process::Output) -> ProbeOutcome {
+    if output.status.success() {
+        return ProbeOutcome::passed();
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.lines().rev().find(|line| !line.trim().is_empty()) {
+        Some(line) => ProbeOutcome::failed(format!("{}: {}", output.status, line.trim())),
+        None => ProbeOutcome::failed(output.status.to_string()),
+    }
+}
+
+/// Healthcheck config for a probe set with `CreateService`/`UpdateService`
+pub fn healthcheck_from_probe(probe: &HealthProbe) -> HealthCheckConfig {
+    let (check_type, mut settings) = match &probe.check {
+        ProbeCheck::Http { port, path, status } => (
+            "http",
+            serde_json::json!({ "port": port, "path": path, "status": status }),
+        ),
+        ProbeCheck::Tcp { port } => ("tcp", serde_json::json!({ "port": port })),
+        ProbeCheck::Exec { command } => ("cmd", serde_json::json!({ "command": command })),
+    };
+    if let Some(ms) = probe.interval_ms {
+        settings["interval"] = format!("{}ms", ms).into();
+    }
+    if let Some(ms) = probe.timeout_ms {
+        settings["timeout"] = format!("{}ms", ms).into();
+    }
+    if let Some(threshold) = probe.failure_threshold {
+        settings["retries"] = threshold.saturating_sub(1).into();
+    }
+    HealthCheckConfig::Single(HealthCheck {
+        check_type: check_type.to_string(),
+        config: HashMap::from([(check_type.to_string(), settings)]),
+    })
+}
+
 pub use lib_plugin_abi_v3::utils::parse_duration;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_healthcheck_from_probe() {
+        let probe = HealthProbe {
+            check: ProbeCheck::Http {
+                port: "{{runtime.port.http}}".to_string(),
+                path: "/ready".to_string(),
+                status: Some(204),
+            },
+            interval_ms: Some(500),
+            timeout_ms: Some(2000),
+            failure_threshold: Some(3),
+        };
+        let config = healthcheck_from_probe(&probe);
+        let check = config.checks()[0];
+        assert_eq!(check.check_type, "http");
+        assert_eq!(HealthChecker::parse_retries(check), 2);
+
+        let http = extract_http_health_config(check).unwrap();
+        assert_eq!(http.path, "/ready");
+        assert_eq!(http.status, Some(204));
+        assert_eq!(
+            http.timeout.as_deref().and_then(parse_duration),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            HealthChecker::new().parse_interval(&config.checks()),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_reports_failure_reason() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let config = healthcheck_from_probe(&HealthProbe {
+            check: ProbeCheck::Tcp {
+                port: "{{runtime.port.http}}".to_string(),
+            },
+            interval_ms: None,
+            timeout_ms: None,
+            failure_threshold: None,
+        });
+        let check = config.checks()[0];
+        let ports = HashMap::from([("http".to_string(), port)]);
+        let checker = HealthChecker::new();
+
+        let probe = checker.probe(check, &ports).await;
+        assert!(probe.passed);
+        assert_eq!(probe.check, "tcp");
+        assert!(probe.failure.is_none());
+
+        drop(listener);
+        let probe = checker.probe(check, &ports).await;
+        assert!(!probe.passed);
+        assert!(probe.failure.unwrap().contains(&port.to_string()));
+    }
+}
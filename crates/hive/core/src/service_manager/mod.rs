@@ -1,17 +1,23 @@
+mod docker;
 mod env_plugins;
 mod environment;
 mod health;
 mod process;
+mod profile;
 mod rollout;
 
+pub use docker::DockerMonitor;
 pub use env_plugins::*;
 pub use environment::*;
 pub use health::*;
 pub use process::*;
+pub use profile::*;
 pub use rollout::*;
 
+use crate::crash_reports::{CrashContext, EnvFingerprint};
+use crate::exposure::ExposureManager;
 use crate::hive_config::{
-    get_rollout_ports, topological_sort, topological_sort_levels, HiveConfig, RestartPolicy,
+    find_expose_refs, get_rollout_ports, topological_sort, topological_sort_levels, HiveConfig, RestartPolicy,
     RuntimeContext, ServiceConfig, ServiceInfo, ServiceState, ROLLOUT_TYPE_BLUE_GREEN,
 };
 use crate::observability::{
@@ -20,12 +26,15 @@ use crate::observability::{
 use crate::plugins::plugin_manager;
 use crate::runtime_db::RuntimeDb;
 use crate::service_proxy::ServiceProxyState;
+use crate::singleton::{Singletons, FENCING_TOKEN_ENV};
 use anyhow::{anyhow, Context, Result};
 use futures::future::join_all;
 use lib_plugin_abi_v3::hooks::{HookContext, HookEvent, HookExecutor, HookOutputStream};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 
@@ -85,9 +94,35 @@ pub enum ServicePhase {
     Starting,
     PostHooks,
     Running,
+    Stopped,
     Failed(String),
 }
 
+impl std::fmt::Display for ServicePhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServicePhase::WaitingFor(dep) => write!(f, "waiting for {}", dep),
+            ServicePhase::PreHooks => write!(f, "running pre-up hooks"),
+            ServicePhase::Building => write!(f, "building"),
+            ServicePhase::Starting => write!(f, "starting"),
+            ServicePhase::PostHooks => write!(f, "running post-up hooks"),
+            ServicePhase::Running => write!(f, "running"),
+            ServicePhase::Stopped => write!(f, "stopped"),
+            ServicePhase::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
+/// Progress through a whole-source start or stop
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceProgress {
+    /// Services finished so far
+    pub done: usize,
+    pub total: usize,
+    pub service: String,
+    pub phase: ServicePhase,
+}
+
 #[derive(Clone)]
 pub struct ServiceManager {
     project_root: PathBuf,
@@ -100,6 +135,10 @@ pub struct ServiceManager {
     proxy_state: Arc<ServiceProxyState>,
     event_collector: Option<Arc<EventCollector>>,
     source_name: String,
+    /// Registry for `expose` configs and `${expose:...}` references
+    exposure: Option<Arc<ExposureManager>>,
+    /// Leader election of `singleton` services; `None` starts them like any other
+    singletons: Option<Singletons>,
 }
 
 pub struct ServiceRuntime {
@@ -110,6 +149,10 @@ pub struct ServiceRuntime {
     pub health: Option<Arc<HealthStatus>>,
     pub restart_count: u32,
     pub last_error: Option<String>,
+    /// Log, health and stats collection for docker runner containers
+    pub docker: Option<DockerMonitor>,
+    /// Environment of the current run, for crash reports
+    pub env: Option<EnvFingerprint>,
 }
 
 impl ServiceRuntime {
@@ -122,6 +165,8 @@ impl ServiceRuntime {
             health: None,
             restart_count: 0,
             last_error: None,
+            docker: None,
+            env: None,
         }
     }
 
@@ -130,9 +175,19 @@ impl ServiceRuntime {
             name: self.name.clone(),
             state: self.state.clone(),
             pid: self.process.as_ref().and_then(|p| p.pid()),
-            container_id: None,
+            container_id: self
+                .process
+                .as_ref()
+                .and_then(|p| p.container_id().or(p.container_name()))
+                .map(String::from),
             ports: self.ports.clone(),
-            healthy: self.health.as_ref().map(|h| h.is_healthy()),
+            // A hive healthcheck wins over the image's own HEALTHCHECK
+            healthy: self
+                .health
+                .as_ref()
+                .map(|h| h.is_healthy())
+                .or_else(|| self.docker.as_ref().and_then(|d| d.healthy())),
+            last_probe: self.health.as_ref().and_then(|h| h.last_probe()),
             last_error: self.last_error.clone(),
             restart_count: self.restart_count,
         }
@@ -140,7 +195,8 @@ impl ServiceRuntime {
 }
 
 impl ServiceManager {
-    pub fn new(project_root: impl AsRef<Path>, config: HiveConfig) -> Result<Self> {
+    pub fn new(project_root: impl AsRef<Path>, mut config: HiveConfig) -> Result<Self> {
+        config.link_expose_dependencies("default");
         let proxy_state = Arc::new(ServiceProxyState::from_config(&config));
         let rollout_manager = Arc::new(RolloutManager::new(proxy_state.clone()));
 
@@ -164,14 +220,17 @@ impl ServiceManager {
             proxy_state,
             event_collector: None,
             source_name: "default".to_string(),
+            exposure: None,
+            singletons: None,
         })
     }
 
     pub fn with_proxy_state(
         project_root: impl AsRef<Path>,
-        config: HiveConfig,
+        mut config: HiveConfig,
         proxy_state: Arc<ServiceProxyState>,
     ) -> Result<Self> {
+        config.link_expose_dependencies("default");
         let rollout_manager = Arc::new(RolloutManager::new(proxy_state.clone()));
 
         let mut env_resolver = EnvironmentResolver::new();
@@ -194,16 +253,19 @@ impl ServiceManager {
             proxy_state,
             event_collector: None,
             source_name: "default".to_string(),
+            exposure: None,
+            singletons: None,
         })
     }
 
     pub fn with_observability(
         project_root: impl AsRef<Path>,
-        config: HiveConfig,
+        mut config: HiveConfig,
         proxy_state: Arc<ServiceProxyState>,
         event_collector: Arc<EventCollector>,
         source_name: String,
     ) -> Result<Self> {
+        config.link_expose_dependencies(&source_name);
         let rollout_manager = Arc::new(RolloutManager::new(proxy_state.clone()));
 
         let mut env_resolver = EnvironmentResolver::new();
@@ -229,9 +291,36 @@ impl ServiceManager {
             proxy_state,
             event_collector: Some(event_collector),
             source_name,
+            exposure: None,
+            singletons: None,
         })
     }
 
+    /// Publish `expose` configs to, and resolve `${expose:...}` references
+    /// from, a shared exposure registry.
+    pub fn with_exposure(mut self, exposure: Arc<ExposureManager>) -> Self {
+        self.exposure = Some(exposure);
+        self
+    }
+
+    pub fn with_singletons(mut self, singletons: Singletons) -> Self {
+        self.singletons = Some(singletons);
+        self
+    }
+
+    /// Singleton service that another hive is elected to run
+    fn led_elsewhere(&self, service_name: &str) -> bool {
+        let is_singleton = self
+            .config
+            .services
+            .get(service_name)
+            .is_some_and(|s| s.singleton);
+        is_singleton
+            && self.singletons.as_ref().is_some_and(|singletons| {
+                !singletons.may_run(&format!("{}:{}", self.source_name, service_name))
+            })
+    }
+
     async fn runner_for(
         &self,
         runner_type: &str,
@@ -266,7 +355,8 @@ impl ServiceManager {
     }
 
     /// Used by auto-reload when config file changes on disk.
-    pub fn update_config(&mut self, config: HiveConfig) {
+    pub fn update_config(&mut self, mut config: HiveConfig) {
+        config.link_expose_dependencies(&self.source_name);
         self.config = config;
     }
 
@@ -278,9 +368,76 @@ impl ServiceManager {
         }
     }
 
+    /// Emit `Crashed` with what the runtime knows about the run, for the
+    /// crash report.
+    fn emit_crash(&self, runtime: Option<&ServiceRuntime>, name: &str, mut context: CrashContext) {
+        if let Some(collector) = &self.event_collector {
+            if let Some(runtime) = runtime {
+                context.env = runtime.env.clone();
+                context.restart_count = runtime.restart_count;
+            }
+            let fqn = format!("{}:{}", self.source_name, name);
+            let mut event = ObservabilityEvent::service_event(fqn, ServiceEventType::Crashed);
+            if let ObservabilityEvent::ServiceEvent { details, .. } = &mut event {
+                *details = context.into_details();
+            }
+            collector.emit(event);
+        }
+    }
+
+    /// Follow the container of a `docker` runner service; `None` for other runners.
+    fn docker_monitor(&self, name: &str, service_config: &ServiceConfig) -> Option<DockerMonitor> {
+        if service_config.runner.runner_type != "docker" {
+            return None;
+        }
+        let container = docker_container_name(name, service_config);
+        let fqn = format!("{}:{}", self.source_name, name);
+        match DockerMonitor::start(container, fqn, self.event_collector.clone(), self.docker_stats_interval()) {
+            Ok(monitor) => Some(monitor),
+            Err(e) => {
+                warn!("Not collecting container logs and stats for {}: {}", name, e);
+                None
+            }
+        }
+    }
+
+    /// `observability.resource_interval`, or `None` when resource collection is off.
+    fn docker_stats_interval(&self) -> Option<Duration> {
+        let observability = self.config.observability.as_ref();
+        let enabled = observability
+            .and_then(|o| o.collectors.as_ref())
+            .map(|c| c.resources)
+            .unwrap_or(true);
+        if !enabled {
+            return None;
+        }
+        Some(
+            observability
+                .and_then(|o| o.resource_interval.as_deref())
+                .and_then(parse_duration)
+                .unwrap_or(docker::DEFAULT_STATS_INTERVAL),
+        )
+    }
+
     pub async fn start_all(&self) -> Result<()> {
-        let order =
-            topological_sort(&self.config).context("Failed to determine service start order")?;
+        self.start_all_with_progress(|_| {}).await
+    }
+
+    pub async fn start_all_with_progress<F>(&self, mut on_progress: F) -> Result<()>
+    where
+        F: FnMut(SourceProgress),
+    {
+        let order: Vec<String> = topological_sort(&self.config)
+            .context("Failed to determine service start order")?
+            .into_iter()
+            .filter(|name| {
+                let skip = self.led_elsewhere(name);
+                if skip {
+                    info!("Not starting singleton {}: this hive is not its leader", name);
+                }
+                !skip
+            })
+            .collect();
 
         info!("Starting services in order: {:?}", order);
 
@@ -300,8 +457,27 @@ impl ServiceManager {
             }
         }
 
-        for service_name in order {
-            self.start_service(&service_name).await?;
+        let total = order.len();
+        for (done, service_name) in order.iter().enumerate() {
+            self.start_service_with_progress(service_name, |phase| {
+                // Reported below, also for services that were already running
+                if phase != ServicePhase::Running {
+                    on_progress(SourceProgress {
+                        done,
+                        total,
+                        service: service_name.clone(),
+                        phase,
+                    });
+                }
+            })
+            .await?;
+
+            on_progress(SourceProgress {
+                done: done + 1,
+                total,
+                service: service_name.clone(),
+                phase: ServicePhase::Running,
+            });
         }
 
         Ok(())
@@ -351,6 +527,7 @@ impl ServiceManager {
         let is_blue_green = self.is_blue_green_rollout(service_config);
 
         if self.reconcile_running_state(name, service_config, is_blue_green).await? {
+            self.publish_exposed(name, service_config).await?;
             return Ok(());
         }
 
@@ -358,6 +535,8 @@ impl ServiceManager {
 
         self.wait_for_dependencies(name, service_config, &mut on_progress).await?;
 
+        self.wait_for_exposed_providers(name, &mut on_progress).await?;
+
         if is_blue_green {
             if let Some(rollout) = &service_config.rollout {
                 self.rollout_manager.init_blue_green(name, rollout).await?;
@@ -379,6 +558,8 @@ impl ServiceManager {
 
         self.setup_health_and_deployment(name, service_config, is_blue_green).await?;
 
+        self.publish_exposed(name, service_config).await?;
+
         self.run_post_hooks(name, service_config, &env, &mut on_progress).await?;
 
         on_progress(ServicePhase::Running);
@@ -449,6 +630,9 @@ impl ServiceManager {
             if let Some(pid) = self.process_manager.is_service_running(name) {
                 runtime.process = Some(ProcessHandle::from_pid(pid));
             }
+        } else if service_config.runner.runner_type.as_str() == "docker" {
+            runtime.process = Some(ProcessHandle::docker(docker_container_name(name, service_config)));
+            runtime.docker = self.docker_monitor(name, service_config);
         }
         let mut services = self.services.write().await;
         services.insert(name.to_string(), runtime);
@@ -518,10 +702,15 @@ impl ServiceManager {
     where
         F: FnMut(ServicePhase),
     {
-        match self.build_environment(name, service_config).await {
-            Ok(env) => Ok(env),
+        match self.build_environment(name, service_config, None).await {
+            Ok(env) => {
+                if let Some(runtime) = self.services.write().await.get_mut(name) {
+                    runtime.env = Some(EnvFingerprint::of(&env));
+                }
+                Ok(env)
+            }
             Err(e) => {
-                self.mark_service_crashed(name).await;
+                self.mark_service_crashed(name, &e).await;
                 on_progress(ServicePhase::Failed(e.to_string()));
                 Err(e)
             }
@@ -549,7 +738,7 @@ impl ServiceManager {
         }
 
         if let Err(e) = self.run_hooks(HookEvent::PreUp, name, service_config, env).await {
-            self.mark_service_crashed(name).await;
+            self.mark_service_crashed(name, &e).await;
             on_progress(ServicePhase::Failed(e.to_string()));
             return Err(e);
         }
@@ -570,7 +759,7 @@ impl ServiceManager {
             let shell = resolve_service_shell(service_config);
             on_progress(ServicePhase::Building);
             if let Err(e) = self.run_build(name, build, env, &shell).await {
-                self.mark_service_crashed(name).await;
+                self.mark_service_crashed(name, &e).await;
                 on_progress(ServicePhase::Failed(e.to_string()));
                 return Err(e);
             }
@@ -591,7 +780,7 @@ impl ServiceManager {
         match self.start_process(name, service_config, env).await {
             Ok(p) => Ok(p),
             Err(e) => {
-                self.mark_service_crashed(name).await;
+                self.mark_service_crashed(name, &e).await;
                 on_progress(ServicePhase::Failed(e.to_string()));
                 Err(e)
             }
@@ -603,10 +792,16 @@ impl ServiceManager {
         if let Some(pid) = process.pid() {
             let _ = self.process_manager.runtime_db().save_pid(name, pid);
         }
+        let docker = self
+            .config
+            .services
+            .get(name)
+            .and_then(|config| self.docker_monitor(name, config));
         let mut services = self.services.write().await;
         if let Some(runtime) = services.get_mut(name) {
             runtime.process = Some(process);
             runtime.state = ServiceState::Running;
+            runtime.docker = docker;
         }
         self.emit_service_event(name, ServiceEventType::Started);
     }
@@ -625,6 +820,23 @@ impl ServiceManager {
         Ok(())
     }
 
+    /// Register the service's `expose` config with the resolved runtime ports.
+    async fn publish_exposed(&self, name: &str, service_config: &ServiceConfig) -> Result<()> {
+        let (Some(exposure), Some(expose)) = (&self.exposure, &service_config.expose) else {
+            return Ok(());
+        };
+
+        let ports = match &service_config.rollout {
+            Some(rollout) => get_rollout_ports(rollout)?,
+            None => HashMap::new(),
+        };
+        exposure
+            .register_exposed(&self.source_name, name, expose, &ports)
+            .await?;
+        exposure.update_health(&expose.name, true).await;
+        Ok(())
+    }
+
     async fn run_post_hooks<F>(
         &self,
         name: &str,
@@ -656,14 +868,30 @@ impl ServiceManager {
 
     /// Parallel within each dependency level, reverse order.
     pub async fn stop_all(&self) -> Result<()> {
+        self.stop_all_with_progress(|_| {}).await
+    }
+
+    pub async fn stop_all_with_progress<F>(&self, mut on_progress: F) -> Result<()>
+    where
+        F: FnMut(SourceProgress),
+    {
         let mut levels = topological_sort_levels(&self.config)?;
         levels.reverse();
 
+        let total = levels.iter().map(|l| l.len()).sum();
+        let mut done = 0;
         for level in levels {
             let handles: Vec<_> = level.iter().map(|name| self.stop_service(name)).collect();
             let results = join_all(handles).await;
-            for result in results {
+            for (name, result) in level.into_iter().zip(results) {
                 result?;
+                done += 1;
+                on_progress(SourceProgress {
+                    done,
+                    total,
+                    service: name,
+                    phase: ServicePhase::Stopped,
+                });
             }
         }
 
@@ -672,8 +900,12 @@ impl ServiceManager {
 
     pub async fn stop_service(&self, name: &str) -> Result<()> {
         if let Some(service_config) = self.config.services.get(name) {
+            if let (Some(exposure), Some(expose)) = (&self.exposure, &service_config.expose) {
+                exposure.update_health(&expose.name, false).await;
+            }
+
             let env = self
-                .build_environment(name, service_config)
+                .build_environment(name, service_config, None)
                 .await
                 .unwrap_or_default();
             if let Err(e) = self
@@ -693,17 +925,11 @@ impl ServiceManager {
             }
 
             runtime.state = ServiceState::Stopping;
+            runtime.docker = None;
             self.emit_service_event(name, ServiceEventType::Stopping);
 
             if let Some(process) = runtime.process.take() {
-                let runner_type = self.config.services.get(name)
-                    .map(|c| c.runner.runner_type.clone())
-                    .unwrap_or_else(|| "script".to_string());
-                if let Ok(runner) = self.runner_for(&runner_type).await {
-                    let mut abi_handle = lib_plugin_abi_v3::runner::ProcessHandle::from(process);
-                    abi_handle.metadata.insert("service_name".to_string(), name.to_string());
-                    runner.stop(&abi_handle).await.map_err(|e| anyhow!("{}", e))?;
-                }
+                self.stop_process(name, process).await?;
             }
             let _ = self.process_manager.runtime_db().clear_pid(name);
 
@@ -734,7 +960,7 @@ impl ServiceManager {
 
         if let Some(service_config) = self.config.services.get(name) {
             let env = self
-                .build_environment(name, service_config)
+                .build_environment(name, service_config, None)
                 .await
                 .unwrap_or_default();
             if let Err(e) = self
@@ -748,12 +974,174 @@ impl ServiceManager {
         Ok(())
     }
 
+    /// Stop one process of a service through its runner, without hooks.
+    async fn stop_process(&self, name: &str, process: ProcessHandle) -> Result<()> {
+        let runner_type = self.config.services.get(name)
+            .map(|c| c.runner.runner_type.clone())
+            .unwrap_or_else(|| "script".to_string());
+        if let Ok(runner) = self.runner_for(&runner_type).await {
+            let mut abi_handle = lib_plugin_abi_v3::runner::ProcessHandle::from(process);
+            abi_handle.metadata.insert("service_name".to_string(), name.to_string());
+            runner.stop(&abi_handle).await.map_err(|e| anyhow!("{}", e))?;
+        }
+        Ok(())
+    }
+
     pub async fn restart_service(&self, name: &str) -> Result<()> {
+        if self.restart_container(name).await? {
+            return Ok(());
+        }
         self.stop_service(name).await?;
         self.start_service(name).await?;
         Ok(())
     }
 
+    /// Restart a running service without downtime: start a second instance on
+    /// the idle blue/green ports, switch the proxy route once it is healthy,
+    /// then stop the old instance after `drain`.
+    ///
+    /// Needs a `blue-green` rollout and a healthcheck. Only the script runner
+    /// can run two instances side by side; docker containers have fixed
+    /// names. If the new instance never gets healthy it is stopped and the
+    /// old one keeps serving. A stopped service is simply started.
+    pub async fn restart_blue_green<F>(
+        &self,
+        name: &str,
+        drain: Duration,
+        mut on_phase: F,
+    ) -> Result<()>
+    where
+        F: FnMut(BlueGreenPhase),
+    {
+        let service_config = self
+            .config
+            .services
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown service: {}", name))?;
+        let Some(rollout) = service_config
+            .rollout
+            .as_ref()
+            .filter(|r| r.rollout_type == ROLLOUT_TYPE_BLUE_GREEN)
+        else {
+            return Err(anyhow!("Service {} has no blue-green rollout", name));
+        };
+        let Some(healthcheck) = &service_config.healthcheck else {
+            return Err(anyhow!(
+                "Blue-green restart of {} requires a healthcheck",
+                name
+            ));
+        };
+        if service_config.runner.runner_type != "script" {
+            return Err(anyhow!(
+                "Blue-green restart is not supported for the {} runner",
+                service_config.runner.runner_type
+            ));
+        }
+
+        let running = {
+            let services = self.services.read().await;
+            services
+                .get(name)
+                .is_some_and(|r| r.state == ServiceState::Running && r.process.is_some())
+        };
+        if !running {
+            self.start_service(name).await?;
+            on_phase(BlueGreenPhase::Done);
+            return Ok(());
+        }
+
+        let state = match self.rollout_manager.get_blue_green_state(name).await {
+            Some(state) => state,
+            None => self.rollout_manager.init_blue_green(name, rollout).await?,
+        };
+        let new_color = state.active.opposite();
+        let deployment = BlueGreenDeployment::new(name, state);
+        let new_ports = deployment.new_instance_ports();
+
+        self.emit_service_event(name, ServiceEventType::Restarting);
+        on_phase(BlueGreenPhase::StartingNew(new_color));
+        let env = self
+            .build_environment(name, service_config, Some(&new_ports))
+            .await?;
+        let process = self.start_process(name, service_config, env).await?;
+
+        on_phase(BlueGreenPhase::WaitingForHealth);
+        if let Err(e) = self
+            .wait_for_healthy(name, healthcheck, &new_ports, &deployment)
+            .await
+        {
+            error!("Blue-green restart of {} failed: {}", name, e);
+            if let Err(stop_err) = self.stop_process(name, process).await {
+                warn!("Failed to stop new instance of {}: {}", name, stop_err);
+            }
+            self.emit_service_event(name, ServiceEventType::Started);
+            return Err(anyhow!("{}; old instance kept", e));
+        }
+
+        on_phase(BlueGreenPhase::SwitchingRoute);
+        self.rollout_manager.switch_blue_green(name).await?;
+        if let Some(pid) = process.pid() {
+            let _ = self.process_manager.runtime_db().save_pid(name, pid);
+        }
+        let old = {
+            let mut services = self.services.write().await;
+            let runtime = services
+                .get_mut(name)
+                .ok_or_else(|| anyhow!("Service {} disappeared during restart", name))?;
+            runtime.ports = new_ports;
+            runtime.restart_count += 1;
+            if let Some(health) = runtime.health.take() {
+                health.retire();
+            }
+            runtime.process.replace(process)
+        };
+        self.start_health_check(name).await?;
+
+        on_phase(BlueGreenPhase::Draining(drain));
+        tokio::time::sleep(drain).await;
+
+        on_phase(BlueGreenPhase::StoppingOld);
+        if let Some(old) = old {
+            if let Err(e) = self.stop_process(name, old).await {
+                warn!("Failed to stop old instance of {}: {}", name, e);
+            }
+        }
+
+        self.emit_service_event(name, ServiceEventType::Started);
+        on_phase(BlueGreenPhase::Done);
+        info!("Service {} restarted blue-green on {:?}", name, new_color);
+        Ok(())
+    }
+
+    /// Restart a running docker runner service in place through the Docker API,
+    /// keeping its container. Returns `false` if the service needs a full
+    /// stop and start instead.
+    async fn restart_container(&self, name: &str) -> Result<bool> {
+        let container = {
+            let services = self.services.read().await;
+            match services.get(name) {
+                Some(runtime) if runtime.state == ServiceState::Running => {
+                    runtime.docker.as_ref().map(|d| d.container().to_string())
+                }
+                _ => None,
+            }
+        };
+        let Some(container) = container else {
+            return Ok(false);
+        };
+
+        self.emit_service_event(name, ServiceEventType::Restarting);
+        docker::restart_container(&container).await?;
+
+        let mut services = self.services.write().await;
+        if let Some(runtime) = services.get_mut(name) {
+            runtime.restart_count += 1;
+        }
+        self.emit_service_event(name, ServiceEventType::Started);
+        info!("Service {} restarted (container {})", name, container);
+        Ok(true)
+    }
+
     pub async fn get_status(&self, name: &str) -> Option<ServiceInfo> {
         let services = self.services.read().await;
         services.get(name).map(|r| r.to_info())
@@ -806,6 +1194,7 @@ impl ServiceManager {
                                 container_id: None,
                                 ports,
                                 healthy,
+                                last_probe: None,
                                 restart_count: 0,
                                 last_error: None,
                             },
@@ -831,6 +1220,7 @@ impl ServiceManager {
                                     container_id: None,
                                     ports,
                                     healthy: None,
+                                    last_probe: None,
                                     restart_count: 0,
                                     last_error: Some(format!(
                                         "Port {} is in use by another process (not managed by hive)",
@@ -848,6 +1238,7 @@ impl ServiceManager {
                                     container_id: None,
                                     ports: HashMap::new(),
                                     healthy: None,
+                                    last_probe: None,
                                     restart_count: 0,
                                     last_error: None,
                                 },
@@ -877,8 +1268,12 @@ impl ServiceManager {
                         let running = runner.is_running(&handle).await;
                         let state = if running { ServiceState::Running } else { ServiceState::Stopped };
 
-                        let healthy = if running {
+                        let healthy = if !running {
+                            None
+                        } else if service_config.healthcheck.is_some() {
                             self.check_health_for_status(service_config, &ports, Some(&container_name)).await
+                        } else if runner_type == "docker" {
+                            docker::container_health(&container_name).await
                         } else {
                             None
                         };
@@ -894,6 +1289,7 @@ impl ServiceManager {
                                 container_id: Some(container_name),
                                 ports,
                                 healthy,
+                                last_probe: None,
                                 restart_count: 0,
                                 last_error: None,
                             },
@@ -908,6 +1304,7 @@ impl ServiceManager {
                                 container_id: None,
                                 ports: HashMap::new(),
                                 healthy: None,
+                                last_probe: None,
                                 restart_count: 0,
                                 last_error: None,
                             },
@@ -983,12 +1380,13 @@ impl ServiceManager {
         Ok(Vec::new())
     }
 
-    async fn mark_service_crashed(&self, name: &str) {
+    async fn mark_service_crashed(&self, name: &str, error: &anyhow::Error) {
         let mut services = self.services.write().await;
         if let Some(runtime) = services.get_mut(name) {
             runtime.state = ServiceState::Crashed;
+            runtime.last_error = Some(error.to_string());
         }
-        self.emit_service_event(name, ServiceEventType::Crashed);
+        self.emit_crash(services.get(name), name, CrashContext::failed(error.to_string()));
     }
 
     async fn wait_for_dependencies<F>(
@@ -1038,12 +1436,49 @@ impl ServiceManager {
         Ok(())
     }
 
+    /// Block until every provider referenced via `${expose:...}` is healthy.
+    /// Same-source providers are already covered by `depends_on`.
+    async fn wait_for_exposed_providers<F>(&self, name: &str, mut on_progress: F) -> Result<()>
+    where
+        F: FnMut(ServicePhase),
+    {
+        let Some(exposure) = &self.exposure else {
+            return Ok(());
+        };
+
+        let own_fqn = format!("{}:{}", self.source_name, name);
+        let mut waited = Vec::new();
+        for expose_ref in self.config.expose_refs(name) {
+            let provider = expose_ref.provider_fqn();
+            if provider == own_fqn || waited.contains(&provider) {
+                continue;
+            }
+            info!("Waiting for exposed provider {} before starting {}", provider, name);
+            on_progress(ServicePhase::WaitingFor(provider.clone()));
+            exposure.wait_for_provider(&expose_ref, 60).await?;
+            waited.push(provider);
+        }
+
+        Ok(())
+    }
+
+    /// Variables the source's environment profile sets; empty without one.
+    pub async fn profile_env(&self) -> Result<BTreeMap<String, String>> {
+        match &self.config.profile {
+            Some(profile) => resolve_profile(&self.project_root, profile).await,
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    /// Environment of a service run. `ports` are the rollout ports the
+    /// instance listens on; `None` takes them from the rollout config.
     async fn build_environment(
         &self,
-        _name: &str,
+        name: &str,
         config: &ServiceConfig,
+        ports: Option<&HashMap<String, u16>>,
     ) -> Result<HashMap<String, String>> {
-        let mut env = HashMap::new();
+        let mut env: HashMap<String, String> = self.profile_env().await?.into_iter().collect();
 
         if let Some(global_env) = &self.config.environment {
             let global_resolved = self.env_resolver.resolve(global_env).await?;
@@ -1056,15 +1491,35 @@ impl ServiceManager {
         }
 
         let mut runtime_ctx = RuntimeContext::new();
-        if let Some(rollout) = &config.rollout {
-            let ports = get_rollout_ports(rollout)?;
-            runtime_ctx.set_ports(ports);
+        match (ports, &config.rollout) {
+            (Some(ports), _) => runtime_ctx.set_ports(ports.clone()),
+            (None, Some(rollout)) => runtime_ctx.set_ports(get_rollout_ports(rollout)?),
+            (None, None) => {}
+        }
+
+        if let Some(exposure) = &self.exposure {
+            let refs: BTreeSet<_> = env.values().flat_map(|v| find_expose_refs(v)).collect();
+            if !refs.is_empty() {
+                for value in env.values_mut() {
+                    *value = exposure.interpolate(value, &config.uses).await?;
+                }
+                exposure
+                    .record_consumer(&format!("{}:{}", self.source_name, name), refs)
+                    .await;
+            }
         }
 
         for value in env.values_mut() {
             *value = runtime_ctx.interpolate(value)?;
         }
 
+        if config.singleton {
+            let fqn = format!("{}:{}", self.source_name, name);
+            if let Some(token) = self.singletons.as_ref().and_then(|s| s.fencing_token(&fqn)) {
+                env.insert(FENCING_TOKEN_ENV.to_string(), token.to_string());
+            }
+        }
+
         Ok(env)
     }
 
@@ -1188,9 +1643,16 @@ impl ServiceManager {
                     .unwrap_or_default()
             };
 
-            let status = self
-                .health_checker
-                .start_health_checks(name, healthcheck, &ports);
+            let reporter = self
+                .event_collector
+                .as_ref()
+                .map(|collector| HealthReporter {
+                    collector: collector.clone(),
+                    service_fqn: format!("{}:{}", self.source_name, name),
+                });
+            let status =
+                self.health_checker
+                    .start_health_checks(name, healthcheck, &ports, reporter);
 
             let mut services = self.services.write().await;
             if let Some(runtime) = services.get_mut(name) {
@@ -1315,7 +1777,7 @@ impl ServiceManager {
         }
     }
 
-    pub async fn handle_service_exit(&self, name: &str, exit_code: i32) -> Result<()> {
+    pub async fn handle_service_exit(&self, name: &str, status: ExitStatus) -> Result<()> {
         let config = self
             .config
             .services
@@ -1324,7 +1786,7 @@ impl ServiceManager {
 
         let should_restart = match config.restart {
             RestartPolicy::Never => false,
-            RestartPolicy::OnFailure => exit_code != 0,
+            RestartPolicy::OnFailure => !status.success(),
             RestartPolicy::Always => true,
             RestartPolicy::UnlessStopped => {
                 let services = self.services.read().await;
@@ -1341,10 +1803,10 @@ impl ServiceManager {
                 if let Some(runtime) = services.get_mut(name) {
                     runtime.restart_count += 1;
                     runtime.state = ServiceState::Crashed;
-                    runtime.last_error = Some(format!("Exit code: {}", exit_code));
+                    runtime.last_error = Some(format!("Process exited ({})", status));
                 }
+                self.emit_crash(services.get(name), name, CrashContext::exited(&status));
             }
-            self.emit_service_event(name, ServiceEventType::Crashed);
 
             // Exponential backoff
             let services = self.services.read().await;
@@ -1353,8 +1815,8 @@ impl ServiceManager {
 
             let delay = std::cmp::min(60, 1 << restart_count.min(6));
             warn!(
-                "Service {} crashed with exit code {}. Restarting in {}s...",
-                name, exit_code, delay
+                "Service {} crashed ({}). Restarting in {}s...",
+                name, status, delay
             );
 
             self.emit_service_event(name, ServiceEventType::Restarting);
@@ -1364,11 +1826,11 @@ impl ServiceManager {
         } else {
             let mut services = self.services.write().await;
             if let Some(runtime) = services.get_mut(name) {
-                runtime.state = if exit_code == 0 {
+                runtime.state = if status.success() {
                     self.emit_service_event(name, ServiceEventType::Stopped);
                     ServiceState::Exited
                 } else {
-                    self.emit_service_event(name, ServiceEventType::Crashed);
+                    self.emit_crash(Some(&*runtime), name, CrashContext::exited(&status));
                     ServiceState::Crashed
                 };
             }
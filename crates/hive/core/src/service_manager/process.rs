@@ -5,7 +5,7 @@ use anyhow::{Context, Result};
 use chrono::Utc;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
@@ -112,7 +112,9 @@ impl From<ProcessHandle> for lib_plugin_abi_v3::runner::ProcessHandle {
 impl From<lib_plugin_abi_v3::runner::ProcessHandle> for ProcessHandle {
     fn from(h: lib_plugin_abi_v3::runner::ProcessHandle) -> Self {
         if let Some(name) = h.container_name {
-            Self::docker(name)
+            let mut handle = Self::docker(name);
+            handle.container_id = h.metadata.get("container_id").cloned();
+            handle
         } else if let Some(pid) = h.pid {
             Self::from_pid(pid)
         } else {
@@ -383,7 +385,7 @@ impl ProcessManager {
     }
 
     /// Uses word-boundary matching to avoid false positives like "0 errors found".
-    fn detect_log_level(message: &str) -> LogLevel {
+    pub(crate) fn detect_log_level(message: &str) -> LogLevel {
         // Bracketed indicators are most reliable
         let lower = message.to_lowercase();
         if lower.contains("[err]") || lower.contains("[error]") || lower.contains("[fatal]") {
@@ -557,10 +559,9 @@ impl ProcessManager {
         }
     }
 
-    pub async fn wait(&self, handle: &mut ProcessHandle) -> Result<Option<i32>> {
+    pub async fn wait(&self, handle: &mut ProcessHandle) -> Result<Option<ExitStatus>> {
         if let Some(child) = &mut handle.child {
-            let status = child.wait().await?;
-            Ok(status.code())
+            Ok(Some(child.wait().await?))
         } else {
             Ok(None)
         }
@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use super::env_plugins::DotenvPlugin;
+use super::environment::EnvPlugin;
+use crate::hive_config::EnvProfileConfig;
+
+/// Variables a source's environment profile sets, PATH included.
+///
+/// Layered as direnv, then the profile's env file, then the PATH prepends on
+/// top of whatever PATH those left (the daemon's own PATH otherwise).
+pub async fn resolve_profile(
+    project_root: &Path,
+    profile: &EnvProfileConfig,
+) -> Result<BTreeMap<String, String>> {
+    let mut vars = BTreeMap::new();
+
+    if profile.direnv {
+        vars.extend(direnv_export(project_root).await?);
+    }
+
+    if let Some(env_file) = &profile.env_file {
+        let path = project_root.join(env_file);
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            return Err(anyhow!("Profile env file not found: {}", path.display()));
+        }
+        let loaded = DotenvPlugin::new(project_root)
+            .load(&serde_json::json!({ "file": env_file }))
+            .await?;
+        vars.extend(loaded);
+    }
+
+    if !profile.path.is_empty() {
+        let base = vars
+            .get("PATH")
+            .cloned()
+            .or_else(|| std::env::var("PATH").ok())
+            .unwrap_or_default();
+        let dirs = profile
+            .path
+            .iter()
+            .map(|dir| resolve_dir(project_root, dir))
+            .chain(std::env::split_paths(&base));
+        let joined = std::env::join_paths(dirs).context("Invalid profile PATH entry")?;
+        vars.insert("PATH".to_string(), joined.to_string_lossy().into_owned());
+    }
+
+    debug!(root = %project_root.display(), vars = vars.len(), "Resolved environment profile");
+    Ok(vars)
+}
+
+/// Profile PATH entry: `~/` is the daemon user's home, relative entries are
+/// under the source root.
+fn resolve_dir(project_root: &Path, dir: &str) -> PathBuf {
+    if let Some(rest) = dir.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    project_root.join(dir)
+}
+
+/// `direnv export json` for the source root. Variables direnv unsets come
+/// back as null and are skipped, as spawned services start from the daemon's
+/// environment anyway.
+async fn direnv_export(project_root: &Path) -> Result<HashMap<String, String>> {
+    let output = tokio::process::Command::new("direnv")
+        .args(["export", "json"])
+        .current_dir(project_root)
+        .output()
+        .await
+        .context("Failed to run direnv (is it installed?)")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "direnv export failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    // No output at all when there is nothing to change
+    if output.stdout.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(HashMap::new());
+    }
+
+    let exported: HashMap<String, Option<String>> =
+        serde_json::from_slice(&output.stdout).context("Invalid direnv export output")?;
+    Ok(exported
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|v| (key, v)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_profile_env_file_and_path() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(".env.node20"),
+            "NODE_VERSION=20\nPATH=/usr/bin\n",
+        )
+        .unwrap();
+
+        let profile = EnvProfileConfig {
+            env_file: Some(".env.node20".to_string()),
+            path: vec!["node_modules/.bin".to_string()],
+            direnv: false,
+        };
+        let vars = resolve_profile(dir.path(), &profile).await.unwrap();
+
+        assert_eq!(vars.get("NODE_VERSION"), Some(&"20".to_string()));
+        let expected = std::env::join_paths([
+            dir.path().join("node_modules/.bin"),
+            PathBuf::from("/usr/bin"),
+        ])
+        .unwrap();
+        assert_eq!(
+            vars.get("PATH"),
+            Some(&expected.to_string_lossy().into_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_profile_missing_env_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = EnvProfileConfig {
+            env_file: Some(".env.missing".to_string()),
+            ..Default::default()
+        };
+        assert!(resolve_profile(dir.path(), &profile).await.is_err());
+    }
+}
@@ -264,6 +264,53 @@ impl RolloutManager {
     }
 }
 
+/// Phase of a blue/green restart of a running service
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlueGreenPhase {
+    StartingNew(BlueGreenColor),
+    WaitingForHealth,
+    SwitchingRoute,
+    Draining(Duration),
+    StoppingOld,
+    Done,
+}
+
+impl BlueGreenPhase {
+    pub const TOTAL_STEPS: u32 = 5;
+
+    /// Phases completed before this one
+    pub fn step(&self) -> u32 {
+        match self {
+            BlueGreenPhase::StartingNew(_) => 0,
+            BlueGreenPhase::WaitingForHealth => 1,
+            BlueGreenPhase::SwitchingRoute => 2,
+            BlueGreenPhase::Draining(_) => 3,
+            BlueGreenPhase::StoppingOld => 4,
+            BlueGreenPhase::Done => Self::TOTAL_STEPS,
+        }
+    }
+}
+
+impl std::fmt::Display for BlueGreenPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlueGreenPhase::StartingNew(BlueGreenColor::Blue) => {
+                write!(f, "starting blue instance")
+            }
+            BlueGreenPhase::StartingNew(BlueGreenColor::Green) => {
+                write!(f, "starting green instance")
+            }
+            BlueGreenPhase::WaitingForHealth => write!(f, "waiting for health"),
+            BlueGreenPhase::SwitchingRoute => write!(f, "switching proxy route"),
+            BlueGreenPhase::Draining(drain) => {
+                write!(f, "draining old instance for {}s", drain.as_secs())
+            }
+            BlueGreenPhase::StoppingOld => write!(f, "stopping old instance"),
+            BlueGreenPhase::Done => write!(f, "restarted"),
+        }
+    }
+}
+
 pub struct BlueGreenDeployment {
     state: BlueGreenState,
     start_time: std::time::Instant,
@@ -308,6 +355,18 @@ mod tests {
         assert_eq!(BlueGreenColor::Green.opposite(), BlueGreenColor::Blue);
     }
 
+    #[test]
+    fn test_blue_green_phase_progress() {
+        let phase = BlueGreenPhase::StartingNew(BlueGreenColor::Green);
+        assert_eq!(phase.step(), 0);
+        assert_eq!(phase.to_string(), "starting green instance");
+
+        let phase = BlueGreenPhase::Draining(Duration::from_secs(30));
+        assert_eq!(phase.step(), 3);
+        assert_eq!(phase.to_string(), "draining old instance for 30s");
+        assert_eq!(BlueGreenPhase::Done.step(), BlueGreenPhase::TOTAL_STEPS);
+    }
+
     #[test]
     fn test_blue_green_state() {
         let mut ports = HashMap::new();
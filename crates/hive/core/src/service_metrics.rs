@@ -0,0 +1,269 @@
+//! Service Metrics
+//!
+//! Resource usage per service for `GetMetrics`. Docker services report it
+//! through `ResourceMetrics` events (emitted when `stats_interval` is set);
+//! process services are sampled from `/proc` on request. CPU usage of a
+//! process is the delta since its previous sample, so the first request after
+//! a start reports none. Uptime is measured from the last `Started` event.
+
+use crate::observability::{ObservabilityEvent, ServiceEventType};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Resource usage of one service at a point in time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub cpu_percent: Option<f32>,
+    pub memory_rss_bytes: Option<u64>,
+    pub open_fds: Option<u32>,
+    pub uptime_secs: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct ServiceMetricsTracker {
+    state: Mutex<TrackerState>,
+}
+
+#[derive(Default)]
+struct TrackerState {
+    /// Last `Started` event per service FQN
+    started: HashMap<String, DateTime<Utc>>,
+    /// Last `ResourceMetrics` event per service FQN
+    reported: HashMap<String, ResourceUsage>,
+    /// Previous CPU tick count per pid, for usage deltas
+    cpu_ticks: HashMap<u32, (u64, Instant)>,
+}
+
+impl ServiceMetricsTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track service lifecycle and reported resource usage.
+    pub fn record(&self, event: &ObservabilityEvent) {
+        let mut state = self.state.lock().unwrap();
+        match event {
+            ObservabilityEvent::ServiceEvent {
+                timestamp,
+                service_fqn,
+                event,
+                ..
+            } => match event {
+                ServiceEventType::Started => {
+                    state.started.insert(service_fqn.clone(), *timestamp);
+                }
+                ServiceEventType::Stopped | ServiceEventType::Crashed => {
+                    state.started.remove(service_fqn);
+                    state.reported.remove(service_fqn);
+                }
+                _ => {}
+            },
+            ObservabilityEvent::ResourceMetrics {
+                service_fqn,
+                cpu_percent,
+                memory_rss_bytes,
+                open_fds,
+                ..
+            } => {
+                let usage = ResourceUsage {
+                    cpu_percent: Some(*cpu_percent),
+                    memory_rss_bytes: Some(*memory_rss_bytes),
+                    open_fds: (*open_fds > 0).then_some(*open_fds),
+                    uptime_secs: None,
+                };
+                state.reported.insert(service_fqn.clone(), usage);
+            }
+            _ => {}
+        }
+    }
+
+    /// Current usage of a service. A reported sample wins over `/proc`, which
+    /// only covers services with a host pid.
+    pub fn usage(&self, service_fqn: &str, pid: Option<u32>) -> ResourceUsage {
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+
+        let mut usage = match (state.reported.get(service_fqn), pid) {
+            (Some(reported), _) => reported.clone(),
+            (None, Some(pid)) => sample_process(&mut state.cpu_ticks, pid),
+            (None, None) => ResourceUsage::default(),
+        };
+
+        usage.uptime_secs = state
+            .started
+            .get(service_fqn)
+            .map(|at| (now - *at).num_seconds().max(0) as u64)
+            .or(usage.uptime_secs);
+        usage
+    }
+
+    /// Forget CPU samples of processes that are gone.
+    pub fn retain_pids(&self, pids: &[u32]) {
+        self.state
+            .lock()
+            .unwrap()
+            .cpu_ticks
+            .retain(|pid, _| pids.contains(pid));
+    }
+}
+
+fn sample_process(cpu_ticks: &mut HashMap<u32, (u64, Instant)>, pid: u32) -> ResourceUsage {
+    let proc_dir = Path::new("/proc").join(pid.to_string());
+    let Some(stat) = std::fs::read_to_string(proc_dir.join("stat"))
+        .ok()
+        .and_then(|s| parse_stat(&s))
+    else {
+        return ResourceUsage::default();
+    };
+
+    let ticks_per_sec = clock_ticks_per_sec();
+    let now = Instant::now();
+    let cpu_percent = cpu_ticks
+        .insert(pid, (stat.cpu_ticks, now))
+        .and_then(|(before, at)| {
+            let elapsed = now.duration_since(at).as_secs_f64();
+            (elapsed > 0.0).then(|| {
+                let used = stat.cpu_ticks.saturating_sub(before) as f64 / ticks_per_sec as f64;
+                (used / elapsed * 100.0) as f32
+            })
+        });
+
+    let memory_rss_bytes = std::fs::read_to_string(proc_dir.join("status"))
+        .ok()
+        .and_then(|s| parse_vm_rss(&s));
+    let open_fds = std::fs::read_dir(proc_dir.join("fd"))
+        .ok()
+        .map(|entries| entries.count() as u32);
+    let uptime_secs = boot_time().map(|boot| {
+        let started = boot + stat.start_ticks / ticks_per_sec;
+        (Utc::now().timestamp() as u64).saturating_sub(started)
+    });
+
+    ResourceUsage {
+        cpu_percent,
+        memory_rss_bytes,
+        open_fds,
+        uptime_secs,
+    }
+}
+
+struct ProcStat {
+    /// utime + stime
+    cpu_ticks: u64,
+    /// Start time after boot
+    start_ticks: u64,
+}
+
+/// Parse `/proc/<pid>/stat`. The command name may contain spaces and
+/// parentheses, so fields are counted from the last `)`.
+fn parse_stat(stat: &str) -> Option<ProcStat> {
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    // fields[0] is field 3 (state) of proc(5)
+    let field = |n: usize| fields.get(n - 3)?.parse::<u64>().ok();
+    Some(ProcStat {
+        cpu_ticks: field(14)? + field(15)?,
+        start_ticks: field(22)?,
+    })
+}
+
+/// Resident memory from `/proc/<pid>/status` (`VmRSS:  1234 kB`)
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("VmRSS:"))
+        .and_then(|v| v.split_whitespace().next())
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+fn boot_time() -> Option<u64> {
+    std::fs::read_to_string("/proc/stat")
+        .ok()?
+        .lines()
+        .find_map(|l| l.strip_prefix("btime "))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+fn clock_ticks_per_sec() -> u64 {
+    #[cfg(unix)]
+    {
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks > 0 {
+            return ticks as u64;
+        }
+    }
+    100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = "4242 (my (odd) app) S 1 4242 4242 0 -1 4194560 1000 0 0 0 150 50 0 0 20 0 4 0 98765 1000000 250 18446744073709551615";
+        let parsed = parse_stat(stat).unwrap();
+        assert_eq!(parsed.cpu_ticks, 200);
+        assert_eq!(parsed.start_ticks, 98765);
+
+        assert!(parse_stat("4242 (truncated) S 1").is_none());
+    }
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\tapp\nVmPeak:\t  9000 kB\nVmRSS:\t  2048 kB\nThreads:\t4\n";
+        assert_eq!(parse_vm_rss(status), Some(2048 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tkthreadd\n"), None);
+    }
+
+    #[test]
+    fn test_reported_usage_until_stopped() {
+        let tracker = ServiceMetricsTracker::new();
+        tracker.record(&ObservabilityEvent::service_event(
+            "app:db",
+            ServiceEventType::Started,
+        ));
+        tracker.record(&ObservabilityEvent::ResourceMetrics {
+            timestamp: Utc::now(),
+            service_fqn: "app:db".to_string(),
+            pid: 0,
+            cpu_percent: 12.5,
+            memory_rss_bytes: 4096,
+            memory_vms_bytes: 0,
+            open_fds: 0,
+            threads: 3,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+        });
+
+        let usage = tracker.usage("app:db", None);
+        assert_eq!(usage.cpu_percent, Some(12.5));
+        assert_eq!(usage.memory_rss_bytes, Some(4096));
+        assert_eq!(usage.open_fds, None);
+        assert_eq!(usage.uptime_secs, Some(0));
+
+        tracker.record(&ObservabilityEvent::service_event(
+            "app:db",
+            ServiceEventType::Stopped,
+        ));
+        assert_eq!(tracker.usage("app:db", None), ResourceUsage::default());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_samples_own_process() {
+        let tracker = ServiceMetricsTracker::new();
+        let pid = std::process::id();
+
+        let first = tracker.usage("self:test", Some(pid));
+        assert!(first.cpu_percent.is_none());
+        assert!(first.memory_rss_bytes.unwrap() > 0);
+        assert!(first.open_fds.unwrap() > 0);
+
+        let second = tracker.usage("self:test", Some(pid));
+        assert!(second.cpu_percent.is_some());
+    }
+}
@@ -0,0 +1,232 @@
+//! Singleton services across hives.
+//!
+//! A service marked `singleton: true` runs on one hive at a time. Hives list
+//! their singletons (`source:service`) when registering with signaling and in
+//! every heartbeat; the signaling server elects a leader per service and
+//! moves it to another hive when the leader stops heartbeating.
+//!
+//! With signaling configured a hive runs a singleton only while it leads it:
+//! source starts skip it, explicit starts are refused, and it is stopped when
+//! leadership moves or the signaling connection drops. Leader changes carry a
+//! fencing token; one lower than the highest seen for the service is stale
+//! and ignored. Tokens are only compared within a connection, since the
+//! server numbers them from scratch after a restart. The leader's token is
+//! passed to the service as `HIVE_FENCING_TOKEN`, so it can fence writes to
+//! shared resources. Without signaling, singletons run like any other service.
+
+use lib_hive_daemon_client::SingletonStatus;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+/// Environment variable carrying the fencing token to a singleton service
+pub const FENCING_TOKEN_ENV: &str = "HIVE_FENCING_TOKEN";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Lease {
+    leader_hive_id: Option<String>,
+    fencing_token: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    coordinated: bool,
+    /// Id assigned by signaling while connected
+    hive_id: Option<String>,
+    /// service → latest lease
+    leases: HashMap<String, Lease>,
+}
+
+impl State {
+    fn leads(&self, lease: &Lease) -> bool {
+        self.hive_id.is_some() && lease.leader_hive_id == self.hive_id
+    }
+}
+
+/// What to do with the local copy of a singleton after a leader change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SingletonAction {
+    Start,
+    Stop,
+}
+
+/// Shared leadership state of singleton services
+#[derive(Debug, Clone, Default)]
+pub struct Singletons {
+    state: Arc<RwLock<State>>,
+}
+
+impl Singletons {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run singletons only while elected; called when signaling is configured.
+    pub fn coordinate(&self) {
+        self.state.write().unwrap().coordinated = true;
+    }
+
+    pub fn is_coordinated(&self) -> bool {
+        self.state.read().unwrap().coordinated
+    }
+
+    /// Registered with signaling as `hive_id`
+    pub fn connected(&self, hive_id: &str) {
+        let mut state = self.state.write().unwrap();
+        state.hive_id = Some(hive_id.to_string());
+        state.leases.clear();
+    }
+
+    /// Signaling connection lost; returns the services this hive led, which
+    /// must be stopped since the server hands them to other hives.
+    pub fn disconnected(&self) -> Vec<String> {
+        let mut state = self.state.write().unwrap();
+        let mut led: Vec<String> = state
+            .leases
+            .iter()
+            .filter(|(_, lease)| state.leads(lease))
+            .map(|(service, _)| service.clone())
+            .collect();
+        led.sort();
+        state.hive_id = None;
+        state.leases.clear();
+        led
+    }
+
+    /// Record a `hive_singleton_leader` push; `None` when the local copy is
+    /// unaffected or the token is stale.
+    pub fn apply(
+        &self,
+        service: &str,
+        leader_hive_id: Option<String>,
+        fencing_token: u64,
+    ) -> Option<SingletonAction> {
+        let mut state = self.state.write().unwrap();
+        let was_leader = match state.leases.get(service) {
+            Some(lease) if fencing_token < lease.fencing_token => {
+                warn!(
+                    "ignoring stale leader of singleton {service} (fencing token {fencing_token} < {})",
+                    lease.fencing_token
+                );
+                return None;
+            }
+            Some(lease) => state.leads(lease),
+            None => false,
+        };
+
+        let lease = Lease {
+            leader_hive_id,
+            fencing_token,
+        };
+        let is_leader = state.leads(&lease);
+        state.leases.insert(service.to_string(), lease);
+
+        match (was_leader, is_leader) {
+            (false, true) => Some(SingletonAction::Start),
+            (true, false) => Some(SingletonAction::Stop),
+            _ => None,
+        }
+    }
+
+    /// Whether singleton `service` may run here: always without coordination,
+    /// otherwise only while this hive leads it.
+    pub fn may_run(&self, service: &str) -> bool {
+        let state = self.state.read().unwrap();
+        !state.coordinated
+            || state
+                .leases
+                .get(service)
+                .is_some_and(|lease| state.leads(lease))
+    }
+
+    /// Fencing token of the lease this hive holds for `service`
+    pub fn fencing_token(&self, service: &str) -> Option<u64> {
+        let state = self.state.read().unwrap();
+        state
+            .leases
+            .get(service)
+            .filter(|lease| state.leads(lease))
+            .map(|lease| lease.fencing_token)
+    }
+
+    /// Status reported in `DaemonStatus`, sorted by service
+    pub fn status(&self) -> Vec<SingletonStatus> {
+        let state = self.state.read().unwrap();
+        let mut status: Vec<SingletonStatus> = state
+            .leases
+            .iter()
+            .map(|(service, lease)| SingletonStatus {
+                service: service.clone(),
+                leader_hive_id: lease.leader_hive_id.clone(),
+                fencing_token: lease.fencing_token,
+                leader: state.leads(lease),
+            })
+            .collect();
+        status.sort_by(|a, b| a.service.cmp(&b.service));
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uncoordinated_singletons_always_run() {
+        let singletons = Singletons::new();
+        assert!(singletons.may_run("app:scheduler"));
+
+        singletons.coordinate();
+        assert!(!singletons.may_run("app:scheduler"));
+    }
+
+    #[test]
+    fn test_leader_changes_and_fencing() {
+        let singletons = Singletons::new();
+        singletons.coordinate();
+        singletons.connected("h1");
+
+        let leader = |id: &str| Some(id.to_string());
+        assert_eq!(singletons.apply("app:scheduler", leader("h2"), 1), None);
+        assert!(!singletons.may_run("app:scheduler"));
+
+        assert_eq!(
+            singletons.apply("app:scheduler", leader("h1"), 2),
+            Some(SingletonAction::Start)
+        );
+        assert!(singletons.may_run("app:scheduler"));
+        assert_eq!(singletons.fencing_token("app:scheduler"), Some(2));
+        // Repeated announcement of the same lease does not start it twice
+        assert_eq!(singletons.apply("app:scheduler", leader("h1"), 2), None);
+
+        // A delayed message from an older election is ignored
+        assert_eq!(singletons.apply("app:scheduler", leader("h2"), 1), None);
+        assert!(singletons.may_run("app:scheduler"));
+
+        assert_eq!(
+            singletons.apply("app:scheduler", leader("h2"), 3),
+            Some(SingletonAction::Stop)
+        );
+        assert!(!singletons.may_run("app:scheduler"));
+        assert_eq!(singletons.fencing_token("app:scheduler"), None);
+
+        let status = singletons.status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].leader_hive_id.as_deref(), Some("h2"));
+        assert_eq!(status[0].fencing_token, 3);
+        assert!(!status[0].leader);
+    }
+
+    #[test]
+    fn test_disconnect_gives_up_leadership() {
+        let singletons = Singletons::new();
+        singletons.coordinate();
+        singletons.connected("h1");
+        singletons.apply("app:scheduler", Some("h1".to_string()), 1);
+        singletons.apply("app:mailer", Some("h2".to_string()), 1);
+
+        assert_eq!(singletons.disconnected(), vec!["app:scheduler"]);
+        assert!(!singletons.may_run("app:scheduler"));
+        assert!(singletons.status().is_empty());
+    }
+}
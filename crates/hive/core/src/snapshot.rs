@@ -0,0 +1,577 @@
+//! Daemon State Snapshots
+//!
+//! Captures sources, dynamically created services, port reservations and
+//! (passphrase-encrypted) secrets into a versioned JSON archive, and rebuilds
+//! that state on a replacement machine.
+
+use crate::crypto;
+use crate::daemon::{RestoreReport, WireLogLine};
+use crate::hive_config::{HiveConfig, ServiceConfig, SourceType, HIVE_YAML_PATH};
+use crate::source_manager::{SourceInfo, SourceManager};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use lib_hive_daemon_client::PortAllocation;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Bumped whenever the archive layout changes incompatibly.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+const KDF_PBKDF2_SHA256: &str = "pbkdf2-sha256";
+const VIRTUAL_PATH_PREFIX: &str = "<virtual:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HiveSnapshot {
+    pub format_version: u32,
+    pub hive_version: String,
+    pub created_at: DateTime<Utc>,
+    pub sources: Vec<SourceSnapshot>,
+    #[serde(default)]
+    pub ports: Vec<PortReservation>,
+    /// Ports held through `ReservePort`, re-reserved on restore
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reservations: Vec<PortAllocation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<SealedSecrets>,
+    /// Secrets left out because no passphrase was given
+    #[serde(default)]
+    pub omitted_secrets: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<WireLogLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceSnapshot {
+    pub name: String,
+    pub path: PathBuf,
+    pub source_type: SourceType,
+    pub enabled: bool,
+    /// Raw `.adi/hive.yaml` contents (None for virtual sources)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hive_yaml: Option<String>,
+    /// Services created at runtime that are not in `hive_yaml`, with secret
+    /// fields blanked out
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dynamic_services: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortReservation {
+    pub fqn: String,
+    pub name: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedSecrets {
+    pub kdf: String,
+    pub iterations: u32,
+    /// Hex-encoded salt
+    pub salt: String,
+    /// base64(nonce || ciphertext) of a JSON-encoded [`SecretBundle`]
+    pub data: String,
+}
+
+/// Plaintext secret material carried inside [`SealedSecrets`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SecretBundle {
+    /// source name -> dotenv path (as written in hive.yaml) -> file contents
+    #[serde(default)]
+    dotenv: BTreeMap<String, BTreeMap<String, String>>,
+    /// service FQN -> JSON pointer into the service definition -> secret
+    #[serde(default)]
+    service_secrets: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl SecretBundle {
+    fn len(&self) -> usize {
+        self.dotenv.values().map(BTreeMap::len).sum::<usize>()
+            + self
+                .service_secrets
+                .values()
+                .map(BTreeMap::len)
+                .sum::<usize>()
+    }
+
+    fn seal(&self, passphrase: &str) -> Result<SealedSecrets> {
+        let salt = crypto::random_salt();
+        let key = crypto::derive_key(passphrase, &salt, crypto::PBKDF2_ITERATIONS)?;
+        let plaintext = serde_json::to_vec(self)?;
+
+        Ok(SealedSecrets {
+            kdf: KDF_PBKDF2_SHA256.to_string(),
+            iterations: crypto::PBKDF2_ITERATIONS,
+            salt: hex::encode(salt),
+            data: crypto::seal(&key, &plaintext)?,
+        })
+    }
+}
+
+impl SealedSecrets {
+    fn open(&self, passphrase: &str) -> Result<SecretBundle> {
+        if self.kdf != KDF_PBKDF2_SHA256 {
+            return Err(anyhow!("Unsupported key derivation: {}", self.kdf));
+        }
+
+        let salt = hex::decode(&self.salt).context("Invalid secrets salt")?;
+        let key = crypto::derive_key(passphrase, &salt, self.iterations)?;
+        let plaintext = crypto::open(&key, &self.data)?;
+
+        serde_json::from_slice(&plaintext).context("Invalid secrets payload")
+    }
+}
+
+impl HiveSnapshot {
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize snapshot")
+    }
+
+    pub fn from_json(archive: &str) -> Result<Self> {
+        let snapshot: Self = serde_json::from_str(archive).context("Invalid snapshot archive")?;
+
+        if snapshot.format_version > SNAPSHOT_FORMAT_VERSION {
+            return Err(anyhow!(
+                "Snapshot format v{} is newer than supported v{}; upgrade hive",
+                snapshot.format_version,
+                SNAPSHOT_FORMAT_VERSION
+            ));
+        }
+
+        Ok(snapshot)
+    }
+}
+
+/// Capture the daemon's current state.
+///
+/// Without a passphrase, dotenv files and service secrets are left out and
+/// only counted in `omitted_secrets`.
+pub async fn create(
+    source_manager: &SourceManager,
+    logs: Vec<WireLogLine>,
+    passphrase: Option<&str>,
+) -> Result<HiveSnapshot> {
+    let mut bundle = SecretBundle::default();
+    let mut sources = Vec::new();
+
+    let mut source_configs = source_manager.source_configs().await;
+    source_configs.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+
+    for (info, config) in source_configs {
+        sources.push(snapshot_source(info, config.as_ref(), &mut bundle)?);
+    }
+
+    let mut ports: Vec<PortReservation> = source_manager
+        .list_services(None)
+        .await
+        .into_iter()
+        .flat_map(|(source, service)| {
+            let fqn = format!("{}:{}", source, service.name);
+            service
+                .ports
+                .into_iter()
+                .map(move |(name, port)| PortReservation {
+                    fqn: fqn.clone(),
+                    name,
+                    port,
+                })
+        })
+        .collect();
+    ports.sort_by(|a, b| (&a.fqn, &a.name).cmp(&(&b.fqn, &b.name)));
+
+    let reservations = source_manager.port_reservations().list();
+
+    let (secrets, omitted_secrets) = match passphrase {
+        Some(passphrase) if bundle.len() > 0 => (Some(bundle.seal(passphrase)?), 0),
+        _ => (None, bundle.len()),
+    };
+
+    info!(
+        "Created snapshot: {} sources, {} service ports, {} port reservations",
+        sources.len(),
+        ports.len(),
+        reservations.len()
+    );
+
+    Ok(HiveSnapshot {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        hive_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now(),
+        sources,
+        ports,
+        reservations,
+        secrets,
+        omitted_secrets,
+        logs,
+    })
+}
+
+fn snapshot_source(
+    info: SourceInfo,
+    config: Option<&HiveConfig>,
+    bundle: &mut SecretBundle,
+) -> Result<SourceSnapshot> {
+    let is_virtual = info.path.to_string_lossy().starts_with(VIRTUAL_PATH_PREFIX);
+
+    let hive_yaml = if is_virtual {
+        None
+    } else {
+        std::fs::read_to_string(info.path.join(HIVE_YAML_PATH)).ok()
+    };
+
+    let raw: Option<Value> = hive_yaml
+        .as_deref()
+        .and_then(|content| serde_yml::from_str(content).ok());
+
+    let file_services: HashSet<String> = raw
+        .as_ref()
+        .and_then(|raw| raw.get("services"))
+        .and_then(Value::as_object)
+        .map(|services| services.keys().cloned().collect())
+        .unwrap_or_default();
+
+    for file in dotenv_files(raw.as_ref()) {
+        let full_path = resolve_path(&info.path, &file);
+        if let Ok(content) = std::fs::read_to_string(&full_path) {
+            bundle
+                .dotenv
+                .entry(info.name.clone())
+                .or_default()
+                .insert(file, content);
+        }
+    }
+
+    let mut dynamic_services = BTreeMap::new();
+    if let Some(config) = config {
+        for (name, service) in &config.services {
+            if file_services.contains(name) {
+                continue;
+            }
+
+            let mut value = serde_json::to_value(service)
+                .with_context(|| format!("Failed to serialize service {}:{}", info.name, name))?;
+            let mut secrets = BTreeMap::new();
+            extract_secrets(&mut value, String::new(), &mut secrets);
+            if !secrets.is_empty() {
+                bundle
+                    .service_secrets
+                    .insert(format!("{}:{}", info.name, name), secrets);
+            }
+            dynamic_services.insert(name.clone(), value);
+        }
+    }
+
+    Ok(SourceSnapshot {
+        name: info.name,
+        path: info.path,
+        source_type: info.source_type,
+        enabled: info.enabled,
+        hive_yaml,
+        dynamic_services,
+    })
+}
+
+/// Rebuild state from an archive produced by [`create`].
+///
+/// Existing files are never overwritten and existing services are left
+/// alone; such conflicts are reported as warnings.
+pub async fn restore(
+    source_manager: &SourceManager,
+    archive: &str,
+    passphrase: Option<&str>,
+) -> Result<RestoreReport> {
+    let snapshot = HiveSnapshot::from_json(archive)?;
+    let mut report = RestoreReport::default();
+
+    let bundle = match (&snapshot.secrets, passphrase) {
+        (Some(sealed), Some(passphrase)) => Some(sealed.open(passphrase)?),
+        (Some(_), None) => {
+            report.warnings.push(
+                "Archive contains encrypted secrets; no passphrase given, skipping them"
+                    .to_string(),
+            );
+            None
+        }
+        (None, _) => None,
+    };
+    report.secrets_restored = bundle.is_some();
+
+    if snapshot.omitted_secrets > 0 {
+        report.warnings.push(format!(
+            "Snapshot was taken without a passphrase; {} secrets were not captured",
+            snapshot.omitted_secrets
+        ));
+    }
+
+    let existing: HashSet<String> = source_manager
+        .list_sources()
+        .await
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+
+    for source in &snapshot.sources {
+        if let Err(e) = restore_source(
+            source_manager,
+            source,
+            bundle.as_ref(),
+            &existing,
+            &mut report,
+        )
+        .await
+        {
+            warn!("Failed to restore source '{}': {:#}", source.name, e);
+            report
+                .warnings
+                .push(format!("Source '{}': {:#}", source.name, e));
+        }
+    }
+
+    for reservation in &snapshot.ports {
+        if std::net::TcpListener::bind(("127.0.0.1", reservation.port)).is_err() {
+            report.warnings.push(format!(
+                "Port {} ({} {}) is already in use",
+                reservation.port, reservation.fqn, reservation.name
+            ));
+        }
+    }
+
+    let configured = source_manager.configured_ports().await;
+    for reservation in &snapshot.reservations {
+        if let Err(holder) = source_manager.port_reservations().reserve(
+            reservation.port,
+            &reservation.owner,
+            reservation.name.clone(),
+            &configured,
+        ) {
+            report.warnings.push(format!(
+                "Port {} reserved by {} is now held by {}",
+                reservation.port, reservation.owner, holder.owner
+            ));
+        }
+    }
+
+    info!(
+        "Restored snapshot: {} sources, {} services, {} warnings",
+        report.sources.len(),
+        report.services.len(),
+        report.warnings.len()
+    );
+
+    Ok(report)
+}
+
+async fn restore_source(
+    source_manager: &SourceManager,
+    source: &SourceSnapshot,
+    bundle: Option<&SecretBundle>,
+    existing: &HashSet<String>,
+    report: &mut RestoreReport,
+) -> Result<()> {
+    if let Some(hive_yaml) = &source.hive_yaml {
+        write_if_missing(&source.path.join(HIVE_YAML_PATH), hive_yaml, report)?;
+
+        if let Some(files) = bundle.and_then(|b| b.dotenv.get(&source.name)) {
+            for (file, content) in files {
+                write_if_missing(&resolve_path(&source.path, file), content, report)?;
+            }
+        }
+
+        if !existing.contains(&source.name) {
+            source_manager
+                .add_source(&source.path, Some(&source.name))
+                .await?;
+        }
+    } else if source.name != "default" {
+        source_manager.add_virtual_source(&source.name).await?;
+    }
+    report.sources.push(source.name.clone());
+
+    if !source.enabled {
+        source_manager.disable_source(&source.name).await?;
+    }
+
+    for (name, definition) in &source.dynamic_services {
+        let fqn = format!("{}:{}", source.name, name);
+        let mut definition = definition.clone();
+
+        if let Some(secrets) = bundle.and_then(|b| b.service_secrets.get(&fqn)) {
+            for (pointer, secret) in secrets {
+                if let Some(slot) = definition.pointer_mut(pointer) {
+                    *slot = Value::String(secret.clone());
+                }
+            }
+        }
+
+        let config: ServiceConfig = match serde_json::from_value(definition) {
+            Ok(config) => config,
+            Err(e) => {
+                report
+                    .warnings
+                    .push(format!("Service {}: invalid definition: {}", fqn, e));
+                continue;
+            }
+        };
+
+        match source_manager
+            .create_service(&source.name, name, config)
+            .await
+        {
+            Ok(()) => report.services.push(fqn),
+            Err(e) => report.warnings.push(format!("Service {}: {}", fqn, e)),
+        }
+    }
+
+    Ok(())
+}
+
+fn dotenv_files(raw: Option<&Value>) -> Vec<String> {
+    raw.and_then(|raw| raw.pointer("/environment/dotenv/files"))
+        .and_then(Value::as_array)
+        .map(|files| {
+            files
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn resolve_path(root: &Path, file: &str) -> PathBuf {
+    if Path::new(file).is_absolute() {
+        PathBuf::from(file)
+    } else {
+        root.join(file)
+    }
+}
+
+fn write_if_missing(path: &Path, content: &str, report: &mut RestoreReport) -> Result<()> {
+    if path.exists() {
+        if std::fs::read_to_string(path).ok().as_deref() != Some(content) {
+            report.warnings.push(format!(
+                "Kept existing {} (differs from snapshot)",
+                path.display()
+            ));
+        }
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+    }
+
+    Ok(())
+}
+
+/// Move every string-valued `secret` field out of `value`, keyed by JSON pointer.
+fn extract_secrets(value: &mut Value, pointer: String, out: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                let child_pointer =
+                    format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+                if key == "secret" {
+                    if let Value::String(secret) = child {
+                        out.insert(child_pointer, std::mem::take(secret));
+                        *child = Value::Null;
+                        continue;
+                    }
+                }
+                extract_secrets(child, child_pointer, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter_mut().enumerate() {
+                extract_secrets(child, format!("{}/{}", pointer, i), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_secrets_roundtrip() {
+        let original = serde_json::json!({
+            "expose": { "name": "db", "secret": "s3cret" },
+            "uses": [{ "name": "auth", "secret": "other" }, { "name": "cache" }],
+        });
+
+        let mut stripped = original.clone();
+        let mut secrets = BTreeMap::new();
+        extract_secrets(&mut stripped, String::new(), &mut secrets);
+
+        assert_eq!(secrets.len(), 2);
+        assert_eq!(stripped["expose"]["secret"], Value::Null);
+        assert_eq!(stripped["uses"][0]["secret"], Value::Null);
+
+        for (pointer, secret) in &secrets {
+            *stripped.pointer_mut(pointer).unwrap() = Value::String(secret.clone());
+        }
+        assert_eq!(stripped, original);
+    }
+
+    #[test]
+    fn test_sealed_bundle_requires_passphrase() {
+        let mut bundle = SecretBundle::default();
+        bundle
+            .dotenv
+            .entry("app".to_string())
+            .or_default()
+            .insert(".env".to_string(), "TOKEN=abc".to_string());
+
+        let sealed = bundle.seal("correct horse").unwrap();
+        let opened = sealed.open("correct horse").unwrap();
+        assert_eq!(opened.dotenv["app"][".env"], "TOKEN=abc");
+
+        assert!(sealed.open("wrong").is_err());
+    }
+
+    #[test]
+    fn test_rejects_newer_format() {
+        let archive = serde_json::json!({
+            "format_version": SNAPSHOT_FORMAT_VERSION + 1,
+            "hive_version": "0.0.0",
+            "created_at": Utc::now(),
+            "sources": [],
+        })
+        .to_string();
+
+        assert!(HiveSnapshot::from_json(&archive).is_err());
+    }
+
+    #[test]
+    fn test_reservations_survive_archive() {
+        let archive = serde_json::json!({
+            "format_version": SNAPSHOT_FORMAT_VERSION,
+            "hive_version": "0.0.0",
+            "created_at": Utc::now(),
+            "sources": [],
+        });
+        let mut snapshot = HiveSnapshot::from_json(&archive.to_string()).unwrap();
+        assert!(snapshot.reservations.is_empty());
+
+        snapshot.reservations.push(PortAllocation {
+            port: 3000,
+            owner: "manual".to_string(),
+            name: Some("dev".to_string()),
+            reserved_at: Some(Utc::now()),
+        });
+        let restored = HiveSnapshot::from_json(&snapshot.to_json().unwrap()).unwrap();
+        assert_eq!(restored.reservations, snapshot.reservations);
+    }
+}
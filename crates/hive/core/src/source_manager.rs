@@ -4,20 +4,28 @@
 //! with unified service management across all sources.
 
 use crate::global_registry::GlobalRegistry;
-use crate::hive_config::{validate_config, HiveConfig, HiveConfigParser, ServiceConfig, ServiceInfo, ServiceState, SourceType};
+use crate::hive_config::{diff_services, topological_sort, validate_config, EnvProfileConfig, HealthCheckConfig, HiveConfig, HiveConfigParser, ServiceConfig, ServiceDiff, ServiceInfo, ServiceState, SourceType};
 use crate::exposure::ExposureManager;
-use crate::observability::EventCollector;
-use crate::service_manager::ServiceManager;
+use crate::observability::{EventCollector, ObservabilityEvent};
+use crate::port_allocations::PortReservations;
+use crate::service_manager::{resolve_profile, BlueGreenPhase, ServiceManager, SourceProgress};
 use crate::service_proxy::ServiceProxyState;
+use crate::singleton::Singletons;
 use anyhow::{anyhow, Context, Result};
-use std::collections::HashMap;
+use lib_hive_daemon_client::PortAllocation;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{mpsc, Notify, RwLock};
 use tracing::{debug, info, warn};
 
 const DEFAULT_SOURCE_DIR: &str = ".adi/hive";
 
+/// Editors save in several steps (truncate, write, rename); wait this long
+/// after a change to a hive.yaml before reloading it
+const AUTO_RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SourceInfo {
     /// Source name (e.g., "default", "my-project")
@@ -27,6 +35,9 @@ pub struct SourceInfo {
     pub enabled: bool,
     pub service_count: usize,
     pub status: SourceStatus,
+    /// Reloaded when its hive.yaml changes on disk
+    #[serde(default)]
+    pub auto_reload: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -46,6 +57,13 @@ pub struct SourceManager {
     proxy_state: Arc<ServiceProxyState>,
     registry: GlobalRegistry,
     event_collector: Arc<EventCollector>,
+    /// Leadership of `singleton` services (shared across all sources)
+    singletons: Singletons,
+    /// Ports claimed with `ReservePort` (shared across all sources)
+    port_reservations: PortReservations,
+    /// Wakes `watch_source_files` when the set of auto-reloaded sources may
+    /// have changed
+    auto_reload_changed: Notify,
 }
 
 struct ManagedSource {
@@ -53,6 +71,18 @@ struct ManagedSource {
     /// Parsed configuration (for YAML sources)
     config: Option<HiveConfig>,
     service_manager: Option<ServiceManager>,
+    /// Set with `SetSourceAutoReload`; overrides the file's `auto_reload`
+    auto_reload: Option<bool>,
+}
+
+/// Outcome of re-reading a source's file
+struct Refreshed {
+    diff: ServiceDiff,
+    /// Service manager as it was before the reload, still holding the old
+    /// config (its runtime state is shared with the updated one)
+    previous: Option<ServiceManager>,
+    config: HiveConfig,
+    running: bool,
 }
 
 impl SourceManager {
@@ -67,6 +97,9 @@ impl SourceManager {
             proxy_state: Arc::new(ServiceProxyState::new()),
             registry: Self::open_registry(),
             event_collector,
+            singletons: Singletons::new(),
+            port_reservations: PortReservations::new(),
+            auto_reload_changed: Notify::new(),
         }
     }
 
@@ -107,12 +140,14 @@ impl SourceManager {
             enabled: true,
             service_count: 0,
             status: SourceStatus::Loaded,
+            auto_reload: false,
         };
 
         let managed = ManagedSource {
             info,
             config: Some(config),
             service_manager: None,
+            auto_reload: None,
         };
 
         let mut sources = self.sources.write().await;
@@ -188,18 +223,21 @@ impl SourceManager {
             enabled: true,
             service_count,
             status: SourceStatus::Loaded,
+            auto_reload: config.as_ref().is_some_and(|c| c.auto_reload),
         };
 
         let managed = ManagedSource {
             info,
             config,
             service_manager: None,
+            auto_reload: None,
         };
 
         {
             let mut sources = self.sources.write().await;
             sources.insert(name.clone(), managed);
         }
+        self.auto_reload_changed.notify_one();
 
         self.persist_source(&name, &path, source_type_for_registry, true);
 
@@ -215,6 +253,7 @@ impl SourceManager {
             .ok_or_else(|| anyhow!("Unknown source: {}", name))?;
 
         drop(sources);
+        self.auto_reload_changed.notify_one();
 
         self.registry.remove_source(name).ok();
 
@@ -249,11 +288,22 @@ impl SourceManager {
     }
 
     pub async fn reload_source(&self, name: &str) -> Result<()> {
+        let refreshed = self.refresh_source(name).await?;
+
+        info!("Reloaded source '{}'", name);
+        self.event_collector
+            .emit(ObservabilityEvent::source_reloaded(name, &refreshed.diff, false));
+        Ok(())
+    }
+
+    /// Re-read a source's hive.yaml and put the new config in place without
+    /// touching running services
+    async fn refresh_source(&self, name: &str) -> Result<Refreshed> {
         let mut sources = self.sources.write().await;
         let source = sources.get_mut(name)
             .ok_or_else(|| anyhow!("Unknown source: {}", name))?;
 
-        match source.info.source_type {
+        let refreshed = match source.info.source_type {
             SourceType::Yaml => {
                 let parser = HiveConfigParser::new(&source.info.path);
                 let config = parser.parse()
@@ -267,24 +317,197 @@ impl SourceManager {
                     return Err(anyhow!("Configuration errors:\n{}", errors.join("\n")));
                 }
 
+                let diff = match &source.config {
+                    Some(old) => diff_services(old, &config),
+                    None => diff_services(&HiveConfig::default(), &config),
+                };
+                let previous = source.service_manager.clone();
+
                 source.info.service_count = config.services.len();
+                source.info.auto_reload = source.auto_reload.unwrap_or(config.auto_reload);
                 if let Some(manager) = &mut source.service_manager {
                     manager.update_config(config.clone());
                 }
                 // Update proxy routes so new/changed services are immediately routable
                 self.proxy_state.load_source_config(name, &config);
-                source.config = Some(config);
+                source.config = Some(config.clone());
+
+                Refreshed {
+                    diff,
+                    previous,
+                    config,
+                    running: source.info.status == SourceStatus::Running,
+                }
             }
             SourceType::Sqlite => {
                 return Err(anyhow!("SQLite source type is not yet implemented"));
             }
+        };
+        drop(sources);
+
+        // `auto_reload` itself may have been edited
+        self.auto_reload_changed.notify_one();
+        Ok(refreshed)
+    }
+
+    /// Reload a source and bring its services in line with the new config:
+    /// removed services are stopped, changed ones that were running are
+    /// restarted, and added ones are started if the source is running.
+    /// Services the edit did not touch keep running.
+    pub async fn apply_source_changes(&self, name: &str) -> Result<ServiceDiff> {
+        let Refreshed { diff, previous, config, running } = self.refresh_source(name).await?;
+
+        // Stop with the old config, so pre-down hooks and containers match
+        // what was started
+        let mut restart = HashSet::new();
+        if let Some(previous) = &previous {
+            for service in diff.removed.iter().chain(&diff.changed) {
+                let active = matches!(
+                    previous.get_status(service).await,
+                    Some(info) if matches!(info.state, ServiceState::Running | ServiceState::Unhealthy)
+                );
+                if !active {
+                    continue;
+                }
+                if let Err(e) = previous.stop_service(service).await {
+                    warn!("Failed to stop {}:{}: {}", name, service, e);
+                }
+                if diff.changed.contains(service) {
+                    restart.insert(service.clone());
+                }
+            }
+        }
+        if running {
+            restart.extend(diff.added.iter().cloned());
         }
 
-        info!("Reloaded source '{}'", name);
+        let order = topological_sort(&config).unwrap_or_else(|_| config.services.keys().cloned().collect());
+        for service in order.iter().filter(|s| restart.contains(*s)) {
+            if let Err(e) = self.start_service(&format!("{}:{}", name, service)).await {
+                warn!("Failed to start {}:{}: {}", name, service, e);
+            }
+        }
+
+        info!(
+            "Applied changes to source '{}': added {:?}, removed {:?}, changed {:?}",
+            name, diff.added, diff.removed, diff.changed
+        );
+        self.event_collector
+            .emit(ObservabilityEvent::source_reloaded(name, &diff, true));
+        Ok(diff)
+    }
+
+    /// Turn auto-reload of a YAML source on or off until the daemon restarts;
+    /// `auto_reload` in its hive.yaml is the persistent setting
+    pub async fn set_auto_reload(&self, name: &str, enabled: bool) -> Result<()> {
+        let mut sources = self.sources.write().await;
+        let source = sources.get_mut(name)
+            .ok_or_else(|| anyhow!("Unknown source: {}", name))?;
+        if source.info.source_type != SourceType::Yaml || !source.info.path.exists() {
+            return Err(anyhow!("Source '{}' has no hive.yaml to watch", name));
+        }
+        source.auto_reload = Some(enabled);
+        source.info.auto_reload = enabled;
+        drop(sources);
+
+        self.auto_reload_changed.notify_one();
+        info!("{} auto-reload of source '{}'", if enabled { "Enabled" } else { "Disabled" }, name);
         Ok(())
     }
 
+    /// Reload sources with `auto_reload` when their hive.yaml changes and
+    /// apply the difference with `apply_source_changes`. A file that fails to
+    /// parse or validate leaves the running config in place. Runs for the
+    /// lifetime of the daemon.
+    pub async fn watch_source_files(self: Arc<Self>) {
+        use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<PathBuf>();
+        let watcher = RecommendedWatcher::new(
+            move |res: std::result::Result<Event, notify::Error>| {
+                if let Ok(event) = res {
+                    let relevant = matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    );
+                    if relevant {
+                        for path in event.paths {
+                            let _ = tx.send(path);
+                        }
+                    }
+                }
+            },
+            notify::Config::default(),
+        );
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("Sources will not be auto-reloaded: {}", e);
+                return;
+            }
+        };
+
+        // hive.yaml path -> source name. The parent directory is watched so
+        // that editors replacing the file by rename are noticed.
+        let mut watched: HashMap<PathBuf, String> = HashMap::new();
+        loop {
+            let wanted = self.auto_reload_files().await;
+            for path in watched.keys().filter(|p| !wanted.contains_key(*p)) {
+                if let Some(dir) = path.parent() {
+                    let _ = watcher.unwatch(dir);
+                }
+            }
+            for (path, name) in wanted.iter().filter(|(p, _)| !watched.contains_key(*p)) {
+                let Some(dir) = path.parent() else { continue };
+                match watcher.watch(dir, RecursiveMode::NonRecursive) {
+                    Ok(()) => debug!("Watching {} for source '{}'", path.display(), name),
+                    Err(e) => warn!("Failed to watch {}: {}", path.display(), e),
+                }
+            }
+            watched = wanted;
+
+            tokio::select! {
+                _ = self.auto_reload_changed.notified() => {}
+                path = rx.recv() => {
+                    let Some(path) = path else { break };
+                    let mut changed: HashSet<String> = watched.get(&path).into_iter().cloned().collect();
+                    if changed.is_empty() {
+                        continue;
+                    }
+
+                    tokio::time::sleep(AUTO_RELOAD_DEBOUNCE).await;
+                    while let Ok(path) = rx.try_recv() {
+                        changed.extend(watched.get(&path).cloned());
+                    }
+
+                    for name in changed {
+                        info!("hive.yaml of source '{}' changed, reloading", name);
+                        if let Err(e) = self.apply_source_changes(&name).await {
+                            warn!("Keeping the running config of source '{}': {:#}", name, e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// hive.yaml of every source with auto-reload on, mapped to its source
+    async fn auto_reload_files(&self) -> HashMap<PathBuf, String> {
+        let sources = self.sources.read().await;
+        sources.values()
+            .filter(|s| s.info.auto_reload && s.info.source_type == SourceType::Yaml)
+            .map(|s| (HiveConfigParser::new(&s.info.path).config_path(), s.info.name.clone()))
+            .collect()
+    }
+
     pub async fn start_source(&self, name: &str) -> Result<()> {
+        self.start_source_with_progress(name, |_| {}).await
+    }
+
+    pub async fn start_source_with_progress<F>(&self, name: &str, on_progress: F) -> Result<()>
+    where
+        F: FnMut(SourceProgress),
+    {
         // Phase 1: Setup (short write lock)
         let manager = {
             let mut sources = self.sources.write().await;
@@ -299,7 +522,7 @@ impl SourceManager {
         }; // WRITE LOCK RELEASED — concurrent reads (list_services polling) now work
 
         // Phase 2: Start services (no lock held)
-        manager.start_all().await?;
+        manager.start_all_with_progress(on_progress).await?;
 
         // Phase 3: Update source status (short write lock)
         {
@@ -314,12 +537,19 @@ impl SourceManager {
     }
 
     pub async fn stop_source(&self, name: &str) -> Result<()> {
+        self.stop_source_with_progress(name, |_| {}).await
+    }
+
+    pub async fn stop_source_with_progress<F>(&self, name: &str, on_progress: F) -> Result<()>
+    where
+        F: FnMut(SourceProgress),
+    {
         let mut sources = self.sources.write().await;
         let source = sources.get_mut(name)
             .ok_or_else(|| anyhow!("Unknown source: {}", name))?;
 
         if let Some(manager) = &source.service_manager {
-            manager.stop_all().await?;
+            manager.stop_all_with_progress(on_progress).await?;
         }
 
         source.info.status = SourceStatus::Stopped;
@@ -338,6 +568,13 @@ impl SourceManager {
             let source = sources.get_mut(&source_name)
                 .ok_or_else(|| anyhow!("Unknown source: {}", source_name))?;
 
+            let is_singleton = source.config.as_ref()
+                .and_then(|c| c.services.get(&service_name))
+                .is_some_and(|s| s.singleton);
+            if is_singleton && !self.singletons.may_run(fqn) {
+                return Err(anyhow!("Singleton {} runs on the hive elected as its leader", fqn));
+            }
+
             self.ensure_service_manager(source, &source_name)?
         }; // WRITE LOCK RELEASED
 
@@ -386,11 +623,61 @@ impl SourceManager {
         self.start_service(fqn).await
     }
 
+    /// Restart a service by bringing up a second instance before stopping the
+    /// running one; see `ServiceManager::restart_blue_green`.
+    ///
+    /// Unlike `restart_service` this does not reload hive.yaml first: that
+    /// would reset the proxy route to the blue ports while the running
+    /// instance may be on green.
+    pub async fn restart_service_blue_green<F>(
+        &self,
+        fqn: &str,
+        drain: std::time::Duration,
+        on_phase: F,
+    ) -> Result<()>
+    where
+        F: FnMut(BlueGreenPhase),
+    {
+        let (source_name, service_name) = parse_fqn(fqn)?;
+
+        let manager = {
+            let mut sources = self.sources.write().await;
+            let source = sources.get_mut(&source_name)
+                .ok_or_else(|| anyhow!("Unknown source: {}", source_name))?;
+            self.ensure_service_manager(source, &source_name)?
+        };
+
+        manager.restart_blue_green(&service_name, drain, on_phase).await?;
+        info!("Restarted service {}:{} blue-green", source_name, service_name);
+        Ok(())
+    }
+
     pub async fn list_sources(&self) -> Vec<SourceInfo> {
         let sources = self.sources.read().await;
         sources.values().map(|s| s.info.clone()).collect()
     }
 
+    /// Source info paired with its in-memory config, including dynamically
+    /// created services that are not in the source's file.
+    pub async fn source_configs(&self) -> Vec<(SourceInfo, Option<HiveConfig>)> {
+        let sources = self.sources.read().await;
+        sources.values().map(|s| (s.info.clone(), s.config.clone())).collect()
+    }
+
+    /// Environment profile of a source and the variables it resolves to
+    pub async fn source_env(&self, name: &str) -> Result<(EnvProfileConfig, BTreeMap<String, String>)> {
+        let (path, profile) = {
+            let sources = self.sources.read().await;
+            let source = sources
+                .get(name)
+                .ok_or_else(|| anyhow!("Unknown source: {}", name))?;
+            let profile = source.config.as_ref().and_then(|c| c.profile.clone());
+            (source.info.path.clone(), profile.unwrap_or_default())
+        };
+        let vars = resolve_profile(&path, &profile).await?;
+        Ok((profile, vars))
+    }
+
     /// List all services across sources, optionally filtered by source name.
     pub async fn list_services(&self, source_filter: Option<&str>) -> Vec<(String, ServiceInfo)> {
         let sources = self.sources.read().await;
@@ -418,6 +705,7 @@ impl SourceManager {
                         container_id: None,
                         ports: HashMap::new(),
                         healthy: None,
+                        last_probe: None,
                         last_error: None,
                         restart_count: 0,
                     }));
@@ -443,7 +731,9 @@ impl SourceManager {
                 self.proxy_state.clone(),
                 self.event_collector.clone(),
                 name.to_string(),
-            )?);
+            )?
+            .with_exposure(self.exposure_manager.clone())
+            .with_singletons(self.singletons.clone()));
         }
         Ok(source.service_manager.as_ref().unwrap().clone())
     }
@@ -481,6 +771,7 @@ impl SourceManager {
                         container_id: None,
                         ports: HashMap::new(),
                         healthy: None,
+                        last_probe: None,
                         last_error: None,
                         restart_count: 0,
                     })));
@@ -523,6 +814,29 @@ impl SourceManager {
         Ok(())
     }
 
+    /// Replace a service's healthcheck. A running service keeps its current
+    /// checks until it is restarted.
+    pub async fn set_healthcheck(&self, fqn: &str, healthcheck: HealthCheckConfig) -> Result<()> {
+        let (source_name, service_name) = parse_fqn(fqn)?;
+        let mut sources = self.sources.write().await;
+        let source = sources.get_mut(&source_name)
+            .ok_or_else(|| anyhow!("Unknown source: {}", source_name))?;
+
+        let hive_config = source.config.as_mut()
+            .ok_or_else(|| anyhow!("Source '{}' has no configuration", source_name))?;
+
+        let service = hive_config.services.get_mut(&service_name)
+            .ok_or_else(|| anyhow!("Service '{}' not found in source '{}'", service_name, source_name))?;
+        service.healthcheck = Some(healthcheck);
+
+        if let Some(manager) = &mut source.service_manager {
+            manager.update_config(hive_config.clone());
+        }
+
+        info!("Updated healthcheck of {}", fqn);
+        Ok(())
+    }
+
     /// Delete a service from an existing source.
     ///
     /// Stops the service if running, then removes it from the source config.
@@ -559,10 +873,96 @@ impl SourceManager {
         &self.exposure_manager
     }
 
+    /// Restart running consumers whenever a provider re-registers with
+    /// different values for the variables they reference. Runs until the
+    /// exposure manager is dropped.
+    pub async fn watch_expose_changes(self: Arc<Self>) {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut changes = self.exposure_manager.subscribe();
+        loop {
+            let change = match changes.recv().await {
+                Ok(change) => change,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Missed {} exposed variable changes", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            for consumer in self.exposure_manager.consumers_of(&change.provider, &change.vars).await {
+                let running = matches!(
+                    self.get_service(&consumer).await,
+                    Ok(Some((_, info))) if info.state == ServiceState::Running
+                );
+                if !running {
+                    continue;
+                }
+
+                info!("Restarting {} after {} changed {:?}", consumer, change.provider, change.vars);
+                if let Err(e) = self.restart_service(&consumer).await {
+                    warn!("Failed to restart {}: {}", consumer, e);
+                }
+            }
+        }
+    }
+
     pub fn proxy_state(&self) -> &Arc<ServiceProxyState> {
         &self.proxy_state
     }
 
+    pub fn singletons(&self) -> &Singletons {
+        &self.singletons
+    }
+
+    pub fn port_reservations(&self) -> &PortReservations {
+        &self.port_reservations
+    }
+
+    /// Rollout ports of every service in enabled sources
+    pub async fn configured_ports(&self) -> Vec<PortAllocation> {
+        let sources = self.sources.read().await;
+        sources.iter()
+            .filter(|(_, source)| source.info.enabled)
+            .filter_map(|(name, source)| source.config.as_ref().map(|c| (name, c)))
+            .flat_map(|(source_name, config)| {
+                config.services.iter().flat_map(move |(service, service_config)| {
+                    let ports = service_config.rollout.as_ref()
+                        .and_then(|r| crate::hive_config::get_rollout_ports(r).ok())
+                        .unwrap_or_default();
+                    ports.into_iter().map(move |(port_name, port)| PortAllocation {
+                        port,
+                        owner: format!("{}:{}", source_name, service),
+                        name: Some(port_name),
+                        reserved_at: None,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Configured ports plus reservations, sorted by port
+    pub async fn port_allocations(&self) -> Vec<PortAllocation> {
+        let configured = self.configured_ports().await;
+        self.port_reservations.allocations(configured)
+    }
+
+    /// FQNs of `singleton` services in enabled sources, sorted
+    pub async fn singleton_services(&self) -> Vec<String> {
+        let sources = self.sources.read().await;
+        let mut services: Vec<String> = sources.iter()
+            .filter(|(_, source)| source.info.enabled)
+            .filter_map(|(name, source)| source.config.as_ref().map(|c| (name, c)))
+            .flat_map(|(name, config)| {
+                config.services.iter()
+                    .filter(|(_, service)| service.singleton)
+                    .map(move |(service, _)| format!("{}:{}", name, service))
+            })
+            .collect();
+        services.sort();
+        services
+    }
+
     async fn check_conflicts(&self, config: &HiveConfig, new_source: &str) -> Result<()> {
         let sources = self.sources.read().await;
 
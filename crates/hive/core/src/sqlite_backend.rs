@@ -246,8 +246,10 @@ impl SqliteBackend {
             defaults,
             proxy,
             environment: None,
+            profile: None,
             observability: None,
             hooks: None,
+            auto_reload: false,
             services,
         })
     }
@@ -321,6 +323,7 @@ impl SqliteBackend {
                 expose,
                 uses,
                 hooks: None,
+                singleton: false,
             };
 
             trace!(service = %name, "Loaded service config");
@@ -1067,6 +1070,7 @@ mod tests {
             expose: None,
             uses: vec![],
             hooks: None,
+            singleton: false,
         };
 
         backend.create_service("test-service", &service).unwrap();
@@ -17,6 +17,12 @@ cmd-daemon-help = Daemon management
 cmd-source-help = Source management
 cmd-proxy-help = Socket activation & proxy
 cmd-doctor-help = Check and fix /etc/resolver files for proxy hostnames
+cmd-snapshot-help = Save daemon state to a snapshot archive
+cmd-restore-help = Restore daemon state from a snapshot archive
+cmd-expose-help = Show which services consume variables exposed by others
+cmd-proxy-config-help = Generate a Caddy, nginx or Traefik config for exposed services
+cmd-env-help = Show the environment profile applied to a source's services
+cmd-maintenance-help = Stop accepting new work and optionally drain cocoons to other hives
 
 # Help text
 hive-help-title = ADI Hive - Service Orchestration
@@ -27,12 +33,24 @@ hive-help-status = Show service status
 hive-help-restart = Restart a service
 hive-help-logs = View service logs
 hive-help-doctor = Check and fix /etc/resolver files + flush DNS cache
+hive-help-snapshot = Save sources, dynamic services, secrets and ports to an archive
+hive-help-restore = Rebuild daemon state from a snapshot archive
+hive-help-expose = Show the expose/consume graph across sources
+hive-help-proxy-config = Generate a reverse proxy config for exposed services
+hive-help-env = Show a source's environment profile (env file, PATH, direnv)
+hive-help-maintenance = Refuse new spawns/starts; --drain moves cocoons to other hives
 hive-help-usage-section = Usage:
 hive-help-up-usage = adi hive up [service...] [-d] [--name <source>]  Start services (interactive)
 hive-help-down-usage = adi hive down [--name <source>]                  Stop all services
 hive-help-status-usage = adi hive status [--all] [--name <source>]    Show service status
-hive-help-restart-usage = adi hive restart <service> [--name <source>] Restart a service
-hive-help-logs-usage = adi hive logs [service] [-f] [--tail <n>] [--level <level>]
+hive-help-restart-usage = adi hive restart <service> [--name <source>] [--blue-green [--drain <duration>]] Restart a service
+hive-help-logs-usage = adi hive logs [service...] [-f] [--tail <n>] [--level <level>] [--grep <text>]
+hive-help-snapshot-usage = adi hive snapshot [--output <file>] [--include-logs]  (secrets need HIVE_SNAPSHOT_PASSPHRASE)
+hive-help-restore-usage = adi hive restore <file>                         (secrets need HIVE_SNAPSHOT_PASSPHRASE)
+hive-help-expose-usage = adi hive expose [graph|list]                    Show exposed-variable consumers or exposed services
+hive-help-proxy-config-usage = adi hive proxy-config [--format caddy|nginx|traefik] [--domain <d>] [--output <file>] [--watch] [--reload <cmd>]
+hive-help-env-usage = adi hive env <source>                           Show what a source's profile sets
+hive-help-maintenance-usage = adi hive maintenance [on|off] [--reason <text>] [--drain]
 hive-help-source-section = Source Resolution:
 hive-help-source-name = --name <source>   Target a registered source by name (from any directory)
 hive-help-source-omit = (omit --name)     Auto-detect from current directory (walks up to find .adi/hive.yaml)
@@ -40,9 +58,10 @@ hive-help-startup-section = Startup Options:
 hive-help-startup-detached = -d                    Detached mode: start services and exit (no log streaming)
 hive-help-startup-default = (default)             Interactive mode: show startup progress and stream logs
 hive-help-logs-section = Logs Options:
-hive-help-logs-follow = -f                    Follow logs (stream new entries)
+hive-help-logs-follow = -f, --follow          Follow logs (stream new entries)
 hive-help-logs-tail = --tail <n>            Number of lines to show (default: 100)
 hive-help-logs-level = --level <level>       Minimum log level (trace, debug, info, warn, error)
+hive-help-logs-grep = --grep <text>         Only show lines containing <text>
 hive-help-daemon-section = Daemon Management:
 hive-help-daemon-status = daemon status                      Check if daemon is running
 hive-help-daemon-start = daemon start                       Start the daemon in the background
@@ -94,9 +113,11 @@ hive-status-failed = Failed: { $message }
 # Restart command
 hive-restart-missing-service = Missing service name. Usage: adi hive restart <service> [--name <source>]
 hive-restart-unknown-service = Unknown service: { $service }
+hive-restart-unknown-service-hint = List services with: adi hive status
 hive-restart-restarting = Restarting service: { $service }
 hive-restart-success = Service { $service } restarted
 hive-restart-failed = Failed to restart { $service }
+hive-restart-invalid-drain = Invalid drain duration: { $drain } (e.g. 30s, 2m)
 
 # Logs command
 hive-logs-streaming = Streaming logs{ $service_suffix }...
@@ -104,11 +125,15 @@ hive-logs-press-ctrlc = Press Ctrl+C to stop
 hive-logs-stream-ended = Log stream ended.
 hive-logs-empty = No logs found.
 
+# Status watch
+hive-status-watching = Watching daemon status...
+hive-status-stream-ended = Status stream ended.
+
 # Config errors
 hive-config-not-found = No .adi/hive.yaml found in { $path }.
 hive-config-not-found-hint = Create a hive.yaml configuration file to use 'hive up'.
 hive-config-not-found-name-hint = The specified source does not contain .adi/hive.yaml.
-hive-config-not-found-source-hint = Tip: Use --name <source> to target a registered source from any directory.
+hive-config-not-found-source-hint = Use --name <source> to target a registered source from any directory.
 hive-config-parse-error = Failed to parse hive.yaml: { $error }
 hive-config-validation-errors = Configuration errors:
 
@@ -157,7 +182,46 @@ hive-source-removed = Removed source: { $name }
 hive-source-reloaded = Reloaded source: { $name }
 hive-source-enabled = Enabled source: { $name }
 hive-source-disabled = Disabled source: { $name }
+hive-source-auto-reload-usage = Usage: adi hive source auto-reload <name> on|off
+hive-source-auto-reload-on = Source { $name } now reloads when its hive.yaml changes
+hive-source-auto-reload-off = Source { $name } no longer reloads when its hive.yaml changes
+
+
+# Snapshot / restore commands
+hive-snapshot-written = Snapshot written to { $path }
+hive-snapshot-no-passphrase = { $env } not set: dotenv files and service secrets were left out of the snapshot
+hive-restore-missing-path = Missing archive. Usage: adi hive restore <file>
+hive-restore-summary = Restored { $sources } sources and { $services } services
+hive-restore-secrets = Secrets decrypted and written back
+
+# Expose commands
+hive-expose-no-edges = No services reference exposed variables.
+hive-expose-none = No services are exposed.
+hive-expose-unresolved = { $vars } (unresolved)
 
+# Reverse proxy config
+hive-proxy-config-written = Wrote { $path }
+hive-proxy-config-updated = Wrote { $path } ({ $count } routes)
+hive-proxy-config-watching = Watching exposed services for { $path }...
+hive-proxy-config-stream-ended = Event stream ended.
+hive-proxy-config-watch-output = --watch needs --output <file>
+hive-proxy-config-reload-failed = Proxy reload failed: { $error }
+
+# Environment profiles
+hive-env-missing-source = Missing source name. Usage: adi hive env <source>
+hive-env-direnv = direnv export applied
+hive-env-none = No environment profile.
+
+# Maintenance mode
+hive-maintenance-on = In maintenance since { $since }
+hive-maintenance-off = Not in maintenance
+hive-maintenance-draining = { $count } cocoon(s) draining
+
+# Singleton services
+hive-singleton-leader = leader
+hive-singleton-standby = standby, led by { $hive }
+hive-singleton-unassigned = no leader
+hive-singleton-token = fencing token { $token }
 
 # Proxy / socket activation
 hive-proxy-active = Socket activation is active
@@ -168,11 +232,16 @@ hive-proxy-not-installed = Socket-activated service is not installed
 
 # Errors
 error-daemon-not-running = Daemon is not running. Start it with 'adi hive daemon start'.
+error-daemon-connection-lost = Lost connection to the daemon. Check it with 'adi hive daemon status'.
+error-daemon-timeout = The daemon did not answer in time. Check it with 'adi hive daemon status'.
 error-bg-unix-only = Background daemon is only supported on Unix systems.
 error-unknown-command = Unknown command: { $cmd }. Run 'adi hive' for help.
 error-unknown-source-command = Unknown source command: { $cmd }. Run 'adi hive source help' for help.
+error-unknown-expose-command = Unknown expose command: { $cmd }. Use 'graph' or 'list'.
 error-create-runtime = Failed to create runtime: { $error }
 error-start-log-stream = Failed to start log stream: { $error }
+error-start-status-stream = Failed to start status stream: { $error }
+error-start-event-stream = Failed to start event stream: { $error }
 error-get-logs = Failed to get logs: { $error }
 error-stream = Stream error: { $error }
 error-register-source = Failed to register source: { $error }
@@ -190,9 +259,20 @@ error-remove-source = Failed to remove source: { $error }
 error-reload-source = Failed to reload source: { $error }
 error-enable-source = Failed to enable source: { $error }
 error-disable-source = Failed to disable source: { $error }
+error-set-auto-reload = Failed to set auto-reload: { $error }
 error-spawn-daemon-thread = Failed to spawn daemon thread: { $error }
 error-daemon-thread-terminated = Daemon thread terminated unexpectedly
 error-build-tokio-runtime = Failed to build Tokio runtime: { $error }
+error-snapshot = Failed to create snapshot: { $error }
+error-write-snapshot = Failed to write { $path }: { $error }
+error-expose-graph = Failed to get expose graph: { $error }
+error-expose-list = Failed to list exposed services: { $error }
+error-read-snapshot = Failed to read { $path }: { $error }
+error-restore = Failed to restore snapshot: { $error }
+error-write-proxy-config = Failed to write { $path }: { $error }
+error-source-env = Failed to get source environment: { $error }
+error-maintenance = Failed to change maintenance mode: { $error }
+error-maintenance-state = Unknown maintenance state: { $state }. Use 'on' or 'off'.
 
 # UI Labels
 label-status = Status
@@ -202,11 +282,22 @@ label-uptime = Uptime
 label-sources = Sources
 label-services = Services
 label-hint = Hint
+label-maintenance = Maintenance
+label-singletons = Singletons
+label-env-file = env file
+label-path-prepend = PATH +
 
 # Section headers
 section-services = Services
 section-recent-logs = Recent Logs (issues)
 section-recent-activity = Recent Activity
+section-crash-reports = Crash Reports
+
+# Crash reports
+crash-exit-code = exit code { $code }
+crash-signal = signal { $signal }
+crash-env = env { $fingerprint } ({ $count } vars)
+crash-output-truncated = … earlier output dropped
 
 # Table headers
 header-service = Service
@@ -220,6 +311,8 @@ header-type = TYPE
 header-path = PATH
 header-services = SERVICES
 header-status = STATUS
+header-provider = PROVIDER
+header-vars = VARS
 
 # State / status strings
 state-running = running
@@ -229,6 +322,7 @@ state-healthy = healthy
 state-unhealthy = unhealthy
 state-loaded = loaded
 state-error = error: { $error }
+health-probe-failed = { $service }: { $reason } ({ $latency }ms, { $failures } failed in a row)
 
 # Summary strings
 summary-running = { $count } running
@@ -249,12 +343,14 @@ hive-source-help-cmd-remove = remove <name>              Remove a source
 hive-source-help-cmd-reload = reload <name>              Reload source configuration
 hive-source-help-cmd-enable = enable <name>              Enable a disabled source
 hive-source-help-cmd-disable = disable <name>             Disable a source (stops services)
+hive-source-help-cmd-auto-reload = auto-reload <name> on|off  Apply hive.yaml edits automatically (until daemon restart)
 hive-source-help-usage = Usage:
 hive-source-help-usage-list = adi hive source list
 hive-source-help-usage-add = adi hive source add ~/projects/myapp
 hive-source-help-usage-add-name = adi hive source add ~/projects/myapp --name myapp
 hive-source-help-usage-remove = adi hive source remove myapp
 hive-source-help-usage-reload = adi hive source reload myapp
+hive-source-help-usage-auto-reload = adi hive source auto-reload myapp on
 hive-source-help-sources-desc = Sources are configuration directories containing:
 hive-source-help-yaml-desc = .adi/hive.yaml  (YAML configuration, read-only)
 hive-source-help-sqlite-desc = hive.db         (SQLite configuration, read-write)
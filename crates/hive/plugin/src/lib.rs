@@ -1,7 +1,7 @@
 use hive_core::{HiveConfigParser, ServiceInfo, ServiceManager, ServiceState};
 use lib_console_output::{
     blocks::{Columns, KeyValue, Renderable, Section, Table},
-    info, out_error, out_info, out_success, out_warn, spinner, theme,
+    info, out_error, out_info, out_success, out_warn, progress_bar, spinner, theme, ProgressBar,
 };
 use lib_plugin_abi_v3::{
     logs::{LogLine as AbiLogLine, LogProvider, LogStream as AbiLogStream, LogStreamContext},
@@ -44,18 +44,30 @@ pub struct DownArgs {}
 pub struct StatusArgs {
     #[arg(long)]
     pub all: bool,
+    /// Keep printing daemon status and service state changes
+    #[arg(long)]
+    pub watch: bool,
 }
 
 #[derive(CliArgs)]
 pub struct RestartArgs {
     #[arg(position = 0)]
     pub service: String,
+
+    /// Start a new instance before stopping the running one
+    #[arg(long)]
+    pub blue_green: bool,
+
+    /// How long the old instance keeps serving after the switch (blue/green only)
+    #[arg(long)]
+    pub drain: Option<String>,
 }
 
 #[derive(CliArgs)]
 pub struct LogsArgs {
+    /// Services by name or FQN; all services when empty
     #[arg(position = 0)]
-    pub service: Option<String>,
+    pub services: Vec<String>,
 
     #[arg(long = "f")]
     pub follow: bool,
@@ -65,6 +77,10 @@ pub struct LogsArgs {
 
     #[arg(long)]
     pub level: Option<String>,
+
+    /// Only show lines whose message contains this text
+    #[arg(long)]
+    pub grep: Option<String>,
 }
 
 #[derive(CliArgs)]
@@ -75,6 +91,10 @@ pub struct SourceArgs {
     #[arg(position = 1)]
     pub path_or_name: Option<String>,
 
+    /// `on` or `off` for `auto-reload`
+    #[arg(position = 2)]
+    pub state: Option<String>,
+
     #[arg(long)]
     pub name: Option<String>,
 }
@@ -82,6 +102,85 @@ pub struct SourceArgs {
 #[derive(CliArgs)]
 pub struct DoctorArgs {}
 
+#[derive(CliArgs)]
+pub struct SnapshotArgs {
+    #[arg(long)]
+    pub output: Option<String>,
+
+    #[arg(long)]
+    pub include_logs: bool,
+}
+
+#[derive(CliArgs)]
+pub struct RestoreArgs {
+    #[arg(position = 0)]
+    pub archive: Option<String>,
+}
+
+#[derive(CliArgs)]
+pub struct ExposeArgs {
+    #[arg(position = 0)]
+    pub subcommand: Option<String>,
+}
+
+#[derive(CliArgs)]
+pub struct ProxyConfigArgs {
+    /// `caddy` (default), `nginx` or `traefik`
+    #[arg(long)]
+    pub format: Option<String>,
+
+    /// Hosts are `<expose>.<domain>`; defaults to `localhost`
+    #[arg(long)]
+    pub domain: Option<String>,
+
+    /// Address the proxy reaches services on; defaults to 127.0.0.1
+    #[arg(long)]
+    pub upstream: Option<String>,
+
+    /// proxy-ssl certificate directory
+    #[arg(long)]
+    pub cert_dir: Option<String>,
+
+    #[arg(long)]
+    pub output: Option<String>,
+
+    /// Rewrite `--output` whenever the exposed services change
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Shell command run after the file changed, e.g. `nginx -s reload`
+    #[arg(long)]
+    pub reload: Option<String>,
+}
+
+#[derive(CliArgs)]
+pub struct EnvArgs {
+    #[arg(position = 0)]
+    pub source: Option<String>,
+}
+
+#[derive(CliArgs)]
+pub struct MaintenanceArgs {
+    /// `on` or `off`; omitted shows the current state
+    #[arg(position = 0)]
+    pub state: Option<String>,
+
+    #[arg(long)]
+    pub reason: Option<String>,
+
+    #[arg(long)]
+    pub drain: bool,
+}
+
+/// Passphrase for encrypting/decrypting snapshot secrets
+const SNAPSHOT_PASSPHRASE_ENV: &str = "HIVE_SNAPSHOT_PASSPHRASE";
+
+/// Drain of `restart --blue-green` without `--drain`
+const DEFAULT_DRAIN_SECS: u64 = 10;
+
+/// Output lines of a crash report shown by `status`
+const CRASH_OUTPUT_LINES: usize = 10;
+
 pub struct HivePlugin;
 
 impl HivePlugin {
@@ -150,6 +249,12 @@ impl CliCommands for HivePlugin {
         source_cmd.has_subcommands = true;
         commands.push(source_cmd);
         commands.push(Self::__sdk_cmd_meta_doctor());
+        commands.push(Self::__sdk_cmd_meta_snapshot());
+        commands.push(Self::__sdk_cmd_meta_restore());
+        commands.push(Self::__sdk_cmd_meta_expose());
+        commands.push(Self::__sdk_cmd_meta_proxy_config());
+        commands.push(Self::__sdk_cmd_meta_env());
+        commands.push(Self::__sdk_cmd_meta_maintenance());
 
         commands
     }
@@ -163,6 +268,12 @@ impl CliCommands for HivePlugin {
             Some("logs") => self.__sdk_cmd_handler_logs(ctx).await,
             Some("source") => self.__sdk_cmd_handler_source(ctx).await,
             Some("doctor") => self.__sdk_cmd_handler_doctor(ctx).await,
+            Some("snapshot") => self.__sdk_cmd_handler_snapshot(ctx).await,
+            Some("restore") => self.__sdk_cmd_handler_restore(ctx).await,
+            Some("expose") => self.__sdk_cmd_handler_expose(ctx).await,
+            Some("proxy-config") => self.__sdk_cmd_handler_proxy_config(ctx).await,
+            Some("env") => self.__sdk_cmd_handler_env(ctx).await,
+            Some("maintenance") => self.__sdk_cmd_handler_maintenance(ctx).await,
             Some("") | Some("help") | None => Ok(CliResult::success(self.help())),
             Some(cmd) => Ok(CliResult::error(t!(
                 "error-unknown-command",
@@ -353,12 +464,24 @@ impl HivePlugin {
              \x20 status    {}\n\
              \x20 restart   {}\n\
              \x20 logs      {}\n\
-             \x20 doctor    {}\n\n\
+             \x20 doctor    {}\n\
+             \x20 snapshot  {}\n\
+             \x20 restore   {}\n\
+             \x20 expose    {}\n\
+             \x20 proxy-config {}\n\
+             \x20 env       {}\n\
+             \x20 maintenance {}\n\n\
              {}\n\
              \x20 {}\n\
              \x20 {}\n\
              \x20 {}\n\
              \x20 {}\n\
+             \x20 {}\n\
+             \x20 {}\n\
+             \x20 {}\n\
+             \x20 {}\n\
+             \x20 {}\n\
+             \x20 {}\n\
              \x20 {}\n\n\
              {}\n\
              \x20 {}\n\
@@ -369,6 +492,7 @@ impl HivePlugin {
              {}\n\
              \x20 {}\n\
              \x20 {}\n\
+             \x20 {}\n\
              \x20 {}\n\n\
              {}\n\
              \x20 {}\n\
@@ -387,12 +511,24 @@ impl HivePlugin {
             t!("hive-help-restart"),
             t!("hive-help-logs"),
             t!("hive-help-doctor"),
+            t!("hive-help-snapshot"),
+            t!("hive-help-restore"),
+            t!("hive-help-expose"),
+            t!("hive-help-proxy-config"),
+            t!("hive-help-env"),
+            t!("hive-help-maintenance"),
             t!("hive-help-usage-section"),
             t!("hive-help-up-usage"),
             t!("hive-help-down-usage"),
             t!("hive-help-status-usage"),
             t!("hive-help-restart-usage"),
             t!("hive-help-logs-usage"),
+            t!("hive-help-snapshot-usage"),
+            t!("hive-help-restore-usage"),
+            t!("hive-help-expose-usage"),
+            t!("hive-help-proxy-config-usage"),
+            t!("hive-help-env-usage"),
+            t!("hive-help-maintenance-usage"),
             t!("hive-help-source-section"),
             t!("hive-help-source-name"),
             t!("hive-help-source-omit"),
@@ -403,6 +539,7 @@ impl HivePlugin {
             t!("hive-help-logs-follow"),
             t!("hive-help-logs-tail"),
             t!("hive-help-logs-level"),
+            t!("hive-help-logs-grep"),
             t!("hive-help-source-mgmt-section"),
             t!("hive-help-source-list"),
             t!("hive-help-source-add"),
@@ -442,25 +579,45 @@ impl HivePlugin {
             .and_then(|n| n.to_str())
             .unwrap_or("default");
 
-        runtime.block_on(async {
-            client
-                .send_fire_and_forget(hive_core::DaemonRequest::StartSource {
-                    name: source_name.clone(),
-                })
-                .await
-                .map_err(|e| t!("error-start-source", "error" => e.to_string()))
-        })?;
-
-        out_success!(
+        out_info!(
             "{}",
             t!("hive-up-starting", "source" => source_display_name)
         );
 
+        // The daemon streams a step per service phase; the bar appears with the first one
+        let mut bar: Option<ProgressBar> = None;
+        let mut total = 0;
+        let result = runtime.block_on(client.start_source_with_progress(&source_name, |p| {
+            total = p.total;
+            let pb = bar.get_or_insert_with(|| progress_bar(p.total as u64, p.message.as_str()));
+            pb.set_message(p.message);
+            pb.set(p.step as u64);
+        }));
+
+        if let Err(e) = result {
+            if let Some(pb) = bar {
+                pb.fail(None);
+            }
+            return Err(t!("error-start-source", "error" => e.to_string()));
+        }
+
+        match bar {
+            Some(pb) => {
+                pb.success(None);
+                out_success!(
+                    "{}",
+                    t!("hive-up-started-success", "count" => total.to_string())
+                );
+                out_info!("{}", t!("hive-up-logs-hint"));
+            }
+            None => out_info!("{}", t!("hive-up-no-services")),
+        }
+
         Ok(String::new())
     }
 
     #[command(name = "down", description = "cmd-down-help")]
-    async fn down(&self, _args: DownArgs) -> CmdResult {
+    async fn down(&self, _args: DownArgs) -> CmdResult<CliError> {
         use hive_core::topological_sort_levels;
 
         trace!("cmd_down started");
@@ -471,16 +628,12 @@ impl HivePlugin {
         let parser = HiveConfigParser::new(&project_root);
 
         if !parser.config_exists() {
-            return Err(format!(
-                "{}\n{}",
-                t!("hive-config-not-found", "path" => project_root.display().to_string()),
-                t!("hive-config-not-found-source-hint")
-            ));
+            return Err(hive_config_not_found(&project_root));
         }
 
         let config = parser
             .parse()
-            .map_err(|e| t!("hive-config-parse-error", "error" => e.to_string()))?;
+            .map_err(|e| CliError::config(t!("hive-config-parse-error", "error" => e.to_string())))?;
 
         let daemon_config = ensure_daemon_running()?;
         let client = std::sync::Arc::new(hive_core::DaemonClient::new(daemon_config.socket_path()));
@@ -542,22 +695,23 @@ impl HivePlugin {
                     .join(", ");
                 let sp = spinner(&t!("hive-down-stopping-parallel", "names" => names.as_str()));
 
-                let results: Vec<anyhow::Result<()>> = runtime.block_on(async {
-                    let handles: Vec<_> = level
-                        .iter()
-                        .map(|name| {
-                            let client = client.clone();
-                            let fqn = format!("{}:{}", source_name, name);
-                            async move {
-                                client
-                                    .stop_service(&fqn)
-                                    .await
-                                    .map_err(|e| anyhow::anyhow!("{}", e))
-                            }
-                        })
-                        .collect();
-                    futures::future::join_all(handles).await
+                // One round trip for the whole level
+                let batch = level.iter().fold(client.batch(), |batch, name| {
+                    batch.stop_service(&format!("{}:{}", source_name, name))
                 });
+                let results: Vec<anyhow::Result<()>> = match runtime.block_on(batch.send()) {
+                    Ok(results) => results
+                        .into_iter()
+                        .map(|r| r.map(|_| ()).map_err(|e| anyhow::anyhow!("{}", e)))
+                        .collect(),
+                    Err(e) => {
+                        let message = daemon_error_text(&e);
+                        level
+                            .iter()
+                            .map(|_| Err(anyhow::anyhow!("{}", message)))
+                            .collect()
+                    }
+                };
 
                 let level_failed = results.iter().any(|r| r.is_err());
                 if level_failed {
@@ -605,10 +759,14 @@ impl HivePlugin {
     }
 
     #[command(name = "status", description = "cmd-status-help")]
-    async fn status(&self, _args: StatusArgs) -> CmdResult {
+    async fn status(&self, args: StatusArgs) -> CmdResult<CliError> {
         use hive_core::DaemonClient;
 
         trace!("cmd_status started");
+        if args.watch {
+            return cmd_status_watch().map_err(CliError::general);
+        }
+
         let runtime = get_runtime();
         let project_root = resolve_hive_root()?;
         trace!(project_root = %project_root.display(), "Resolved hive root");
@@ -616,16 +774,12 @@ impl HivePlugin {
         let parser = HiveConfigParser::new(&project_root);
 
         if !parser.config_exists() {
-            return Err(format!(
-                "{}\n{}",
-                t!("hive-config-not-found", "path" => project_root.display().to_string()),
-                t!("hive-config-not-found-source-hint")
-            ));
+            return Err(hive_config_not_found(&project_root));
         }
 
         let config = parser
             .parse()
-            .map_err(|e| t!("hive-config-parse-error", "error" => e.to_string()))?;
+            .map_err(|e| CliError::config(t!("hive-config-parse-error", "error" => e.to_string())))?;
 
         let daemon_config = hive_daemon_config();
         let daemon_info = hive_core::HiveDaemon::is_running(&daemon_config)
@@ -656,6 +810,7 @@ impl HivePlugin {
                             container_id: s.container_id,
                             ports: s.ports,
                             healthy: s.healthy,
+                            last_probe: s.last_probe,
                             last_error: None,
                             restart_count: s.restart_count,
                         };
@@ -680,6 +835,7 @@ impl HivePlugin {
 
         if daemon_info.is_some() {
             let client = DaemonClient::new(daemon_config.socket_path());
+            output.push_str(&build_crash_section(&client, runtime, source_name, &svc_status));
             output.push_str(&build_logs_section(&client, runtime, &counts));
         }
 
@@ -689,7 +845,7 @@ impl HivePlugin {
     }
 
     #[command(name = "restart", description = "cmd-restart-help")]
-    async fn restart(&self, args: RestartArgs) -> CmdResult {
+    async fn restart(&self, args: RestartArgs) -> CmdResult<CliError> {
         let runtime = get_runtime();
         let service_name = &args.service;
         debug!(service = %service_name, "cmd_restart started");
@@ -698,22 +854,19 @@ impl HivePlugin {
         let parser = HiveConfigParser::new(&project_root);
 
         if !parser.config_exists() {
-            return Err(format!(
-                "{}\n{}",
-                t!("hive-config-not-found", "path" => project_root.display().to_string()),
-                t!("hive-config-not-found-source-hint")
-            ));
+            return Err(hive_config_not_found(&project_root));
         }
 
         let config = parser
             .parse()
-            .map_err(|e| t!("hive-config-parse-error", "error" => e.to_string()))?;
+            .map_err(|e| CliError::config(t!("hive-config-parse-error", "error" => e.to_string())))?;
 
         if !config.services.contains_key(service_name.as_str()) {
-            return Err(t!(
+            return Err(CliError::not_found(t!(
                 "hive-restart-unknown-service",
                 "service" => service_name.as_str()
-            ));
+            ))
+            .with_hint(t!("hive-restart-unknown-service-hint")));
         }
 
         let daemon_config = ensure_daemon_running()?;
@@ -725,14 +878,27 @@ impl HivePlugin {
             .unwrap_or("default");
         let fqn = format!("{}:{}", source_name, service_name);
 
-        let sp = spinner(&t!(
+        let mut sp = spinner(&t!(
             "hive-restart-restarting",
             "service" => service_name.as_str()
         ));
 
-        let result: anyhow::Result<()> = runtime
-            .block_on(client.restart_service(&fqn))
-            .map_err(|e| anyhow::anyhow!("{}", e));
+        let result = if args.blue_green {
+            let drain = match args.drain.as_deref() {
+                Some(drain) => hive_core::parse_duration(drain).ok_or_else(|| {
+                    CliError::invalid_input(t!("hive-restart-invalid-drain", "drain" => drain))
+                })?,
+                None => std::time::Duration::from_secs(DEFAULT_DRAIN_SECS),
+            };
+            let strategy = hive_core::RestartStrategy::BlueGreen {
+                drain_seconds: drain.as_secs() as u32,
+            };
+            runtime.block_on(client.restart_service_with_progress(&fqn, strategy, |p| {
+                sp.set_message(format!("{}: {}", service_name, p.message));
+            }))
+        } else {
+            runtime.block_on(client.restart_service(&fqn))
+        };
 
         match result {
             Ok(_) => {
@@ -748,9 +914,11 @@ impl HivePlugin {
                         "hive-restart-failed",
                         "service" => service_name.as_str()
                     )),
-                    Some(&e.to_string()),
+                    Some(&daemon_error_text(&e)),
                 );
-                Err(t!("error-restart-service", "error" => e.to_string()))
+                Err(CliError::general(
+                    t!("error-restart-service", "error" => daemon_error_text(&e)),
+                ))
             }
         }
     }
@@ -759,17 +927,26 @@ impl HivePlugin {
     async fn logs(&self, args: LogsArgs) -> CmdResult {
         trace!("cmd_logs started");
 
-        let follow = args.follow;
+        // The host only parses `--` options, so a short `-f` arrives as a positional
+        let (short_follow, services): (Vec<&str>, Vec<&str>) = args
+            .services
+            .iter()
+            .map(String::as_str)
+            .partition(|s| *s == "-f");
+        let follow = args.follow || !short_follow.is_empty();
         let tail: Option<u32> = args.tail.as_deref().and_then(|s| s.parse().ok());
         let level = args.level.as_deref();
+        let mut view = LogView::new(&services, args.grep.clone());
 
         let (client, runtime) = require_daemon_client()?;
-        let service_fqn = args.service.as_deref();
 
         if follow {
-            let service_suffix = service_fqn
-                .map(|s| t!("hive-logs-service-suffix", "service" => s))
-                .unwrap_or_default();
+            let service_suffix = if services.is_empty() {
+                String::new()
+            } else {
+                let names = services.join(", ");
+                t!("hive-logs-service-suffix", "service" => names.as_str())
+            };
             info(&t!(
                 "hive-logs-streaming",
                 "service_suffix" => service_suffix.as_str()
@@ -777,26 +954,24 @@ impl HivePlugin {
             info(&t!("hive-logs-press-ctrlc"));
 
             runtime.block_on(async {
-                let mut handle = client
-                    .stream_logs(service_fqn, level)
-                    .await
-                    .map_err(|e| t!("error-start-log-stream", "error" => e.to_string()))?;
+                // Several services go in one stream, which the daemon interleaves by timestamp
+                let stream = match services.as_slice() {
+                    [] => client.stream_logs(None, level).await,
+                    [service] => client.stream_logs(Some(*service), level).await,
+                    _ => client.stream_logs_of(&services, level).await,
+                };
+                let mut handle = stream
+                    .map_err(|e| t!("error-start-log-stream", "error" => daemon_error_text(&e)))?;
 
                 loop {
                     match handle.recv().await {
                         Ok(Some(line)) => {
-                            let level_colored = format_log_level(&line.level);
-                            let timestamp = line.timestamp.format("%H:%M:%S%.3f");
-                            out_info!(
-                                "{} {} [{}] {}",
-                                timestamp,
-                                line.service_fqn,
-                                level_colored,
-                                line.message
-                            );
+                            if let Some(rendered) = view.render(&line) {
+                                out_info!("{}", rendered);
+                            }
                         }
                         Ok(None) => break,
-                        Err(e) => return Err(t!("error-stream", "error" => e.to_string())),
+                        Err(e) => return Err(t!("error-stream", "error" => daemon_error_text(&e))),
                     }
                 }
                 Ok::<(), String>(())
@@ -806,21 +981,35 @@ impl HivePlugin {
         }
 
         let logs = runtime
-            .block_on(client.get_logs(service_fqn, tail, None, level))
+            .block_on(async {
+                if services.len() <= 1 {
+                    return client
+                        .get_logs(services.first().copied(), tail, None, level)
+                        .await;
+                }
+                let mut logs = Vec::new();
+                for service in &services {
+                    logs.extend(client.get_logs(Some(*service), tail, None, level).await?);
+                }
+                logs.sort_by_key(|line| line.timestamp);
+                if let Some(tail) = tail {
+                    let skip = logs.len().saturating_sub(tail as usize);
+                    logs.drain(..skip);
+                }
+                Ok(logs)
+            })
             .map_err(|e| t!("error-get-logs", "error" => e.to_string()))?;
 
-        if logs.is_empty() {
-            return Ok(t!("hive-logs-empty"));
+        let mut output = String::new();
+        for line in &logs {
+            if let Some(rendered) = view.render(line) {
+                output.push_str(&rendered);
+                output.push('\n');
+            }
         }
 
-        let mut output = String::new();
-        for line in logs {
-            let level_colored = format_log_level(&line.level);
-            let timestamp = line.timestamp.format("%H:%M:%S%.3f");
-            output.push_str(&format!(
-                "{} {} [{}] {}\n",
-                timestamp, line.service_fqn, level_colored, line.message
-            ));
+        if output.is_empty() {
+            return Ok(t!("hive-logs-empty"));
         }
         Ok(output)
     }
@@ -837,6 +1026,9 @@ impl HivePlugin {
             "reload" => cmd_source_reload(args.path_or_name.as_deref()),
             "enable" => cmd_source_enable(args.path_or_name.as_deref()),
             "disable" => cmd_source_disable(args.path_or_name.as_deref()),
+            "auto-reload" => {
+                cmd_source_auto_reload(args.path_or_name.as_deref(), args.state.as_deref())
+            }
             "help" | "" => Ok(get_source_help()),
             _ => Err(t!(
                 "error-unknown-source-command",
@@ -849,6 +1041,78 @@ impl HivePlugin {
     async fn doctor(&self, _args: DoctorArgs) -> CmdResult {
         cmd_doctor()
     }
+
+    #[command(name = "snapshot", description = "cmd-snapshot-help")]
+    async fn snapshot(&self, args: SnapshotArgs) -> CmdResult {
+        cmd_snapshot(args.output.as_deref(), args.include_logs)
+    }
+
+    #[command(name = "restore", description = "cmd-restore-help")]
+    async fn restore(&self, args: RestoreArgs) -> CmdResult {
+        cmd_restore(args.archive.as_deref())
+    }
+
+    #[command(name = "expose", description = "cmd-expose-help")]
+    async fn expose(&self, args: ExposeArgs) -> CmdResult {
+        let subcommand = args.subcommand.as_deref().unwrap_or("graph");
+        debug!(subcommand, "cmd_expose");
+
+        match subcommand {
+            "graph" | "" => cmd_expose_graph(),
+            "list" => cmd_expose_list(),
+            _ => Err(t!(
+                "error-unknown-expose-command",
+                "cmd" => subcommand
+            )),
+        }
+    }
+
+    #[command(name = "proxy-config", description = "cmd-proxy-config-help")]
+    async fn proxy_config(&self, args: ProxyConfigArgs) -> CmdResult {
+        let target = ProxyConfigTarget {
+            format: args.format.as_deref().unwrap_or("caddy").parse()?,
+            domain: args.domain.unwrap_or_else(|| "localhost".to_string()),
+            upstream: args.upstream.unwrap_or_else(|| "127.0.0.1".to_string()),
+            cert_dir: args
+                .cert_dir
+                .unwrap_or_else(|| hive_core::proxy_config::DEFAULT_CERT_DIR.to_string())
+                .into(),
+        };
+
+        if args.watch {
+            let output = args
+                .output
+                .ok_or_else(|| t!("hive-proxy-config-watch-output"))?;
+            return cmd_proxy_config_watch(&target, &output, args.reload.as_deref());
+        }
+
+        let (client, runtime) = require_daemon_client()?;
+        let (config, _) = runtime.block_on(render_exposed_proxy_config(&client, &target))?;
+        match args.output {
+            Some(output) => {
+                write_proxy_config(&output, &config, args.reload.as_deref())?;
+                let written = t!("hive-proxy-config-written", "path" => output.as_str());
+                Ok(theme::success(&written).to_string())
+            }
+            None => Ok(config),
+        }
+    }
+
+    #[command(name = "env", description = "cmd-env-help")]
+    async fn env(&self, args: EnvArgs) -> CmdResult {
+        cmd_env(args.source.as_deref())
+    }
+
+    #[command(name = "maintenance", description = "cmd-maintenance-help")]
+    async fn maintenance(&self, args: MaintenanceArgs) -> CmdResult {
+        let on = match args.state.as_deref() {
+            None => return cmd_maintenance_status(),
+            Some("on") => true,
+            Some("off") => false,
+            Some(other) => return Err(t!("error-maintenance-state", "state" => other)),
+        };
+        cmd_maintenance(on, args.reason.as_deref(), args.drain)
+    }
 }
 
 fn ensure_daemon_running() -> std::result::Result<hive_core::DaemonConfig, String> {
@@ -1021,6 +1285,76 @@ fn format_log_level(level: &str) -> String {
     }
 }
 
+/// Renders log lines the way `docker compose logs` does: an aligned,
+/// color-coded service prefix, timestamps in local time and an optional
+/// `--grep` filter.
+struct LogView {
+    grep: Option<String>,
+    /// Prefix label and color of each service FQN seen so far
+    labels: HashMap<String, (String, usize)>,
+    width: usize,
+}
+
+impl LogView {
+    fn new(services: &[&str], grep: Option<String>) -> Self {
+        let width = services
+            .iter()
+            .map(|s| service_label(s).len())
+            .max()
+            .unwrap_or(0);
+        Self {
+            grep,
+            labels: HashMap::new(),
+            width,
+        }
+    }
+
+    /// `None` if the line doesn't match `--grep`.
+    fn render(&mut self, line: &hive_core::WireLogLine) -> Option<String> {
+        if let Some(grep) = &self.grep {
+            if !line.message.contains(grep.as_str()) {
+                return None;
+            }
+        }
+
+        let next_color = self.labels.len();
+        let (label, color) = self
+            .labels
+            .entry(line.service_fqn.clone())
+            .or_insert_with(|| (service_label(&line.service_fqn).to_string(), next_color))
+            .clone();
+        // Services from different sources can share a name; keep them apart
+        let shared = self
+            .labels
+            .iter()
+            .any(|(fqn, (other, _))| *fqn != line.service_fqn && *other == label);
+        let label = if shared {
+            line.service_fqn.clone()
+        } else {
+            label
+        };
+        self.width = self.width.max(label.len());
+
+        let prefix = format!("{:<width$} |", label, width = self.width);
+        let timestamp = line
+            .timestamp
+            .with_timezone(&chrono::Local)
+            .format("%H:%M:%S%.3f");
+        Some(format!(
+            "{} {} [{}] {}",
+            theme::series(prefix, color),
+            timestamp,
+            format_log_level(&line.level),
+            line.message
+        ))
+    }
+}
+
+/// Service name of an FQN, for log prefixes
+fn service_label(fqn: &str) -> &str {
+    fqn.rsplit(':').next().unwrap_or(fqn)
+}
+
 struct ServiceCounts {
     running: usize,
     stopped: usize,
@@ -1057,6 +1391,16 @@ fn build_daemon_section(daemon_info: Option<&hive_core::DaemonStatus>) -> String
                     ds.total_services
                 ),
             );
+        let kv = match &ds.maintenance {
+            Some(m) => kv.entry(&t!("label-maintenance"), format_maintenance(m)),
+            None => kv,
+        };
+        let kv = if ds.singletons.is_empty() {
+            kv
+        } else {
+            let singletons: Vec<String> = ds.singletons.iter().map(format_singleton).collect();
+            kv.entry(&t!("label-singletons"), singletons.join(", "))
+        };
         output.push_str(&kv.to_string());
     } else {
         let kv = KeyValue::new()
@@ -1217,9 +1561,124 @@ fn build_services_section(
 
     output.push_str(&table.to_string());
     output.push('\n');
+    output.push_str(&build_probe_failures(&service_names, svc_status));
     (output, counts)
 }
 
+/// Why unhealthy services failed their latest health probe
+fn build_probe_failures(
+    service_names: &[&String],
+    svc_status: &HashMap<String, hive_core::ServiceInfo>,
+) -> String {
+    let mut output = String::new();
+    for name in service_names {
+        let Some(info) = svc_status.get(*name) else {
+            continue;
+        };
+        let Some(probe) = info.last_probe.as_ref().filter(|p| !p.passed) else {
+            continue;
+        };
+        if info.healthy != Some(false) {
+            continue;
+        }
+        let reason = probe.failure.clone().unwrap_or_else(|| probe.check.clone());
+        output.push_str(&format!(
+            "  {} {}\n",
+            theme::error(theme::icons::ERROR),
+            t!(
+                "health-probe-failed",
+                "service" => name.as_str(),
+                "reason" => reason,
+                "latency" => probe.latency_ms.to_string(),
+                "failures" => probe.consecutive_failures.to_string()
+            )
+        ));
+    }
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+/// Crash reports of crashed services: exit code or signal, the env
+/// fingerprint and the last output lines before the crash.
+fn build_crash_section(
+    client: &hive_core::DaemonClient,
+    runtime: &Runtime,
+    source_name: &str,
+    svc_status: &HashMap<String, ServiceInfo>,
+) -> String {
+    let mut crashed: Vec<_> = svc_status
+        .iter()
+        .filter(|(_, info)| info.state == ServiceState::Crashed)
+        .map(|(name, _)| name)
+        .collect();
+    crashed.sort();
+
+    let reports: Vec<_> = crashed
+        .into_iter()
+        .filter_map(|name| {
+            let fqn = format!("{}:{}", source_name, name);
+            let report = runtime.block_on(client.get_crash_report(&fqn)).ok().flatten()?;
+            Some((name, report))
+        })
+        .collect();
+
+    let mut output = String::new();
+    if reports.is_empty() {
+        return output;
+    }
+    output.push_str(&Section::new(&t!("section-crash-reports")).width(60).render());
+    output.push('\n');
+
+    for (name, report) in &reports {
+        let mut cause = Vec::new();
+        if let Some(code) = report.exit_code {
+            cause.push(t!("crash-exit-code", "code" => code.to_string()));
+        }
+        if let Some(signal) = report.signal {
+            cause.push(t!("crash-signal", "signal" => signal.to_string()));
+        }
+        output.push_str(&format!(
+            "  {} {} {}\n",
+            theme::error(name),
+            cause.join(", "),
+            theme::muted(report.crashed_at.format("%Y-%m-%d %H:%M:%S"))
+        ));
+        if let Some(error) = &report.error {
+            output.push_str(&format!("    {}\n", theme::error(error)));
+        }
+        if let Some(fingerprint) = &report.env_fingerprint {
+            output.push_str(&format!(
+                "    {}\n",
+                theme::muted(&t!("crash-env",
+                    "fingerprint" => fingerprint.as_str(),
+                    "count" => report.env_vars.len().to_string()))
+            ));
+        }
+
+        let skip = report.output.len().saturating_sub(CRASH_OUTPUT_LINES);
+        if report.truncated || skip > 0 {
+            output.push_str(&format!("    {}\n", theme::muted(&t!("crash-output-truncated"))));
+        }
+        for line in &report.output[skip..] {
+            let text = if line.stream == "stderr" {
+                theme::error(&line.line).to_string()
+            } else {
+                line.line.clone()
+            };
+            output.push_str(&format!(
+                "    {} {}\n",
+                theme::muted(line.timestamp.format("%H:%M:%S")),
+                text
+            ));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
 fn build_logs_section(
     client: &hive_core::DaemonClient,
     runtime: &Runtime,
@@ -1308,6 +1767,74 @@ fn build_status_summary(counts: &ServiceCounts) -> String {
     }
 }
 
+fn hive_config_not_found(project_root: &std::path::Path) -> CliError {
+    CliError::config(t!("hive-config-not-found", "path" => project_root.display().to_string()))
+        .with_hint(t!("hive-config-not-found-source-hint"))
+}
+
+/// Follow the daemon status stream until it ends or the user interrupts.
+fn cmd_status_watch() -> CmdResult {
+    let (client, runtime) = require_daemon_client()?;
+    info(&t!("hive-status-watching"));
+    info(&t!("hive-logs-press-ctrlc"));
+
+    runtime.block_on(async {
+        let mut handle = client
+            .stream_status(None)
+            .await
+            .map_err(|e| t!("error-start-status-stream", "error" => daemon_error_text(&e)))?;
+
+        loop {
+            match handle.recv().await {
+                Ok(Some(update)) => {
+                    let status = &update.status;
+                    let mut line = format!(
+                        "{} {}/{}  {} {}  {} {}",
+                        t!("label-services"),
+                        theme::success(status.running_services),
+                        status.total_services,
+                        t!("label-sources"),
+                        status.source_count,
+                        t!("label-uptime"),
+                        format_uptime(status.uptime_secs)
+                    );
+                    if let Some(m) = &status.maintenance {
+                        line.push_str(&format!("  {}", format_maintenance(m)));
+                    }
+                    out_info!("{}", line);
+
+                    for service in &update.changed {
+                        let health = match service.healthy {
+                            Some(true) => theme::success(&t!("state-healthy")).to_string(),
+                            Some(false) => theme::error(&t!("state-unhealthy")).to_string(),
+                            None => theme::muted("-").to_string(),
+                        };
+                        out_info!("  {} {} {}", service.fqn, service.state, health);
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => return Err(t!("error-stream", "error" => daemon_error_text(&e))),
+            }
+        }
+        Ok::<(), String>(())
+    })?;
+
+    Ok(t!("hive-status-stream-ended"))
+}
+
+/// User-facing text for a daemon client failure, so a daemon that stopped or
+/// hung reads as such rather than as a socket or timeout error.
+fn daemon_error_text(e: &hive_core::DaemonClientError) -> String {
+    use hive_core::DaemonClientError;
+
+    match e {
+        DaemonClientError::NotRunning { .. } => t!("error-daemon-not-running"),
+        DaemonClientError::ConnectionLost(_) => t!("error-daemon-connection-lost"),
+        DaemonClientError::Timeout(_) => t!("error-daemon-timeout"),
+        other => other.to_string(),
+    }
+}
+
 fn require_daemon_client(
 ) -> std::result::Result<(hive_core::DaemonClient, &'static Runtime), String> {
     use hive_core::DaemonClient;
@@ -1335,12 +1862,14 @@ fn get_source_help() -> String {
          \x20 {}\n\
          \x20 {}\n\
          \x20 {}\n\
+         \x20 {}\n\
          \x20 {}\n\n\
          {}\n\
          \x20 {}\n\
          \x20 {}\n\
          \x20 {}\n\
          \x20 {}\n\
+         \x20 {}\n\
          \x20 {}\n\n\
          {}\n\
          \x20 - {}\n\
@@ -1354,12 +1883,14 @@ fn get_source_help() -> String {
         t!("hive-source-help-cmd-reload"),
         t!("hive-source-help-cmd-enable"),
         t!("hive-source-help-cmd-disable"),
+        t!("hive-source-help-cmd-auto-reload"),
         t!("hive-source-help-usage"),
         t!("hive-source-help-usage-list"),
         t!("hive-source-help-usage-add"),
         t!("hive-source-help-usage-add-name"),
         t!("hive-source-help-usage-remove"),
         t!("hive-source-help-usage-reload"),
+        t!("hive-source-help-usage-auto-reload"),
         t!("hive-source-help-sources-desc"),
         t!("hive-source-help-yaml-desc"),
         t!("hive-source-help-sqlite-desc"),
@@ -1429,6 +1960,333 @@ fn cmd_source_list() -> CmdResult {
     Ok(cols.to_string())
 }
 
+fn snapshot_passphrase() -> Option<String> {
+    std::env::var(SNAPSHOT_PASSPHRASE_ENV)
+        .ok()
+        .filter(|p| !p.is_empty())
+}
+
+fn cmd_snapshot(output: Option<&str>, include_logs: bool) -> CmdResult {
+    let (client, runtime) = require_daemon_client()?;
+    let passphrase = snapshot_passphrase();
+
+    let output = output.map(str::to_string).unwrap_or_else(|| {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        format!("hive-snapshot-{}.json", secs)
+    });
+
+    let archive = runtime
+        .block_on(client.snapshot(include_logs, passphrase.as_deref()))
+        .map_err(|e| t!("error-snapshot", "error" => e.to_string()))?;
+
+    std::fs::write(&output, &archive)
+        .map_err(|e| t!("error-write-snapshot", "path" => output.as_str(), "error" => e.to_string()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&output, std::fs::Permissions::from_mode(0o600));
+    }
+
+    if passphrase.is_none() {
+        out_warn!(
+            "{}",
+            t!("hive-snapshot-no-passphrase", "env" => SNAPSHOT_PASSPHRASE_ENV)
+        );
+    }
+
+    Ok(format!(
+        "{}",
+        theme::success(&t!("hive-snapshot-written", "path" => output.as_str()))
+    ))
+}
+
+fn cmd_maintenance(on: bool, reason: Option<&str>, drain: bool) -> CmdResult {
+    let (client, runtime) = require_daemon_client()?;
+    let message = runtime
+        .block_on(client.set_maintenance(on, reason, drain))
+        .map_err(|e| t!("error-maintenance", "error" => e.to_string()))?;
+    Ok(theme::success(message.unwrap_or_default()).to_string())
+}
+
+fn cmd_maintenance_status() -> CmdResult {
+    let (client, runtime) = require_daemon_client()?;
+    let status = runtime
+        .block_on(client.status())
+        .map_err(|e| t!("error-maintenance", "error" => e.to_string()))?;
+    Ok(match &status.maintenance {
+        Some(m) => format_maintenance(m),
+        None => t!("hive-maintenance-off"),
+    })
+}
+
+fn format_maintenance(status: &hive_core::MaintenanceStatus) -> String {
+    let mut text = theme::warning(&t!(
+        "hive-maintenance-on",
+        "since" => status.since.format("%Y-%m-%d %H:%M UTC").to_string()
+    ))
+    .to_string();
+    if let Some(reason) = &status.reason {
+        text.push_str(&format!(" ({})", reason));
+    }
+    if status.draining > 0 {
+        text.push_str(&format!(
+            ", {}",
+            t!("hive-maintenance-draining", "count" => status.draining.to_string())
+        ));
+    }
+    text
+}
+
+fn format_singleton(status: &hive_core::SingletonStatus) -> String {
+    let role = if status.leader {
+        theme::success(&t!("hive-singleton-leader")).to_string()
+    } else {
+        match &status.leader_hive_id {
+            Some(hive) => t!("hive-singleton-standby", "hive" => hive.as_str()),
+            None => theme::warning(&t!("hive-singleton-unassigned")).to_string(),
+        }
+    };
+    format!(
+        "{} {} ({})",
+        status.service,
+        role,
+        t!("hive-singleton-token", "token" => status.fencing_token.to_string())
+    )
+}
+
+fn cmd_restore(archive_path: Option<&str>) -> CmdResult {
+    let archive_path = archive_path.ok_or_else(|| t!("hive-restore-missing-path"))?;
+    let archive = std::fs::read_to_string(archive_path).map_err(
+        |e| t!("error-read-snapshot", "path" => archive_path, "error" => e.to_string()),
+    )?;
+    let (client, runtime) = require_daemon_client()?;
+
+    let report = runtime
+        .block_on(client.restore(archive, snapshot_passphrase().as_deref()))
+        .map_err(|e| t!("error-restore", "error" => e.to_string()))?;
+
+    let mut output = format!(
+        "{}\n",
+        theme::success(&t!(
+            "hive-restore-summary",
+            "sources" => report.sources.len().to_string(),
+            "services" => report.services.len().to_string()
+        ))
+    );
+    if report.secrets_restored {
+        output.push_str(&format!("  {}\n", theme::muted(t!("hive-restore-secrets"))));
+    }
+    for warning in &report.warnings {
+        output.push_str(&format!("  {} {}\n", theme::warning("!"), warning));
+    }
+
+    Ok(output)
+}
+
+fn cmd_expose_graph() -> CmdResult {
+    let (client, runtime) = require_daemon_client()?;
+
+    let edges = runtime
+        .block_on(client.expose_graph())
+        .map_err(|e| t!("error-expose-graph", "error" => e.to_string()))?;
+
+    if edges.is_empty() {
+        return Ok(t!("hive-expose-no-edges"));
+    }
+
+    let mut by_provider: std::collections::BTreeMap<&str, Vec<&hive_core::WireExposeEdgeInfo>> =
+        std::collections::BTreeMap::new();
+    for edge in &edges {
+        by_provider.entry(&edge.provider).or_default().push(edge);
+    }
+
+    let mut output = String::new();
+    for (provider, consumers) in by_provider {
+        output.push_str(&format!("{}\n", theme::bold(provider)));
+        for edge in consumers {
+            let vars = edge.vars.join(", ");
+            let vars = if edge.resolved {
+                theme::muted(&vars).to_string()
+            } else {
+                theme::warning(&t!("hive-expose-unresolved", "vars" => vars.as_str())).to_string()
+            };
+            output.push_str(&format!("  {} {}  {}\n", theme::borders::LAST_BRANCH, edge.consumer, vars));
+        }
+    }
+    Ok(output)
+}
+
+fn cmd_expose_list() -> CmdResult {
+    let (client, runtime) = require_daemon_client()?;
+
+    let exposed = runtime
+        .block_on(client.list_exposed())
+        .map_err(|e| t!("error-expose-list", "error" => e.to_string()))?;
+
+    if exposed.is_empty() {
+        return Ok(t!("hive-expose-none"));
+    }
+
+    let mut cols = Columns::new()
+        .header([
+            &*t!("header-name"),
+            &*t!("header-provider"),
+            &*t!("header-vars"),
+            &*t!("header-status"),
+        ])
+        .indent(0)
+        .gap(2);
+
+    for e in exposed {
+        let mut vars = e.var_names;
+        vars.sort();
+        let status = if e.healthy {
+            theme::success(&t!("state-healthy")).to_string()
+        } else {
+            theme::warning(&t!("state-unhealthy")).to_string()
+        };
+        cols = cols.row([
+            e.name,
+            format!("{}:{}", e.source, e.service),
+            vars.join(", "),
+            status,
+        ]);
+    }
+
+    Ok(cols.to_string())
+}
+
+struct ProxyConfigTarget {
+    format: hive_core::ProxyFormat,
+    domain: String,
+    upstream: String,
+    cert_dir: std::path::PathBuf,
+}
+
+/// Proxy config for the currently exposed services and its route count
+async fn render_exposed_proxy_config(
+    client: &hive_core::DaemonClient,
+    target: &ProxyConfigTarget,
+) -> std::result::Result<(String, usize), String> {
+    let exposed = client
+        .list_exposed()
+        .await
+        .map_err(|e| t!("error-expose-list", "error" => daemon_error_text(&e)))?;
+    let routes =
+        hive_core::proxy_routes(&exposed, &target.domain, &target.upstream, &target.cert_dir);
+    Ok((
+        hive_core::render_proxy_config(target.format, &routes),
+        routes.len(),
+    ))
+}
+
+/// Write the config and run the reload command; a failed reload only warns
+fn write_proxy_config(
+    path: &str,
+    config: &str,
+    reload: Option<&str>,
+) -> std::result::Result<(), String> {
+    std::fs::write(path, config)
+        .map_err(|e| t!("error-write-proxy-config", "path" => path, "error" => e.to_string()))?;
+
+    let Some(reload) = reload else {
+        return Ok(());
+    };
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(reload)
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => out_warn!(
+            "{}",
+            t!("hive-proxy-config-reload-failed", "error" => status.to_string())
+        ),
+        Err(e) => out_warn!(
+            "{}",
+            t!("hive-proxy-config-reload-failed", "error" => e.to_string())
+        ),
+    }
+    Ok(())
+}
+
+/// Regenerate the config on every daemon event and rewrite it when it changed
+fn cmd_proxy_config_watch(
+    target: &ProxyConfigTarget,
+    output: &str,
+    reload: Option<&str>,
+) -> CmdResult {
+    let (client, runtime) = require_daemon_client()?;
+    info(&t!("hive-proxy-config-watching", "path" => output));
+    info(&t!("hive-logs-press-ctrlc"));
+
+    runtime.block_on(async {
+        let mut events = client
+            .subscribe_events(None)
+            .await
+            .map_err(|e| t!("error-start-event-stream", "error" => daemon_error_text(&e)))?;
+
+        // The file on disk may predate this run, so the first render is always written
+        let mut current: Option<String> = None;
+        loop {
+            let (config, routes) = render_exposed_proxy_config(&client, target).await?;
+            if current.as_ref() != Some(&config) {
+                write_proxy_config(output, &config, reload)?;
+                out_info!(
+                    "{}",
+                    t!("hive-proxy-config-updated", "path" => output, "count" => routes.to_string())
+                );
+                current = Some(config);
+            }
+
+            match events.recv().await {
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(e) => return Err(t!("error-stream", "error" => daemon_error_text(&e))),
+            }
+        }
+        Ok::<(), String>(())
+    })?;
+
+    Ok(t!("hive-proxy-config-stream-ended"))
+}
+
+fn cmd_env(source: Option<&str>) -> CmdResult {
+    let source = source.ok_or_else(|| t!("hive-env-missing-source"))?;
+    let (client, runtime) = require_daemon_client()?;
+
+    let env = runtime
+        .block_on(client.get_source_env(source))
+        .map_err(|e| t!("error-source-env", "error" => e.to_string()))?;
+
+    let mut output = String::new();
+    output.push_str(&format!("{}\n", theme::bold(&env.source)));
+    if let Some(env_file) = &env.env_file {
+        output.push_str(&format!("  {}  {}\n", t!("label-env-file"), env_file));
+    }
+    for dir in &env.path_prepend {
+        output.push_str(&format!("  {}  {}\n", t!("label-path-prepend"), dir));
+    }
+    if env.direnv {
+        output.push_str(&format!("  {}\n", t!("hive-env-direnv")));
+    }
+
+    if env.vars.is_empty() {
+        output.push_str(&format!("{}\n", theme::muted(&t!("hive-env-none"))));
+        return Ok(output);
+    }
+
+    output.push('\n');
+    for (key, value) in &env.vars {
+        output.push_str(&format!("{}={}\n", key, value));
+    }
+    Ok(output)
+}
+
 fn cmd_source_add(path: Option<&str>, name: Option<&str>) -> CmdResult {
     let path = path.ok_or_else(|| t!("hive-source-missing-path"))?;
     let (client, runtime) = require_daemon_client()?;
@@ -1531,6 +2389,32 @@ fn cmd_source_disable(name: Option<&str>) -> CmdResult {
     ))
 }
 
+fn cmd_source_auto_reload(name: Option<&str>, state: Option<&str>) -> CmdResult {
+    let name = name.ok_or_else(|| {
+        t!(
+            "hive-source-missing-name",
+            "command" => "auto-reload"
+        )
+    })?;
+    let enabled = match state {
+        Some("on") => true,
+        Some("off") => false,
+        _ => return Err(t!("hive-source-auto-reload-usage")),
+    };
+    let (client, runtime) = require_daemon_client()?;
+
+    runtime
+        .block_on(client.set_source_auto_reload(name, enabled))
+        .map_err(|e| t!("error-set-auto-reload", "error" => e.to_string()))?;
+
+    let message = if enabled {
+        t!("hive-source-auto-reload-on", "name" => name)
+    } else {
+        t!("hive-source-auto-reload-off", "name" => name)
+    };
+    Ok(format!("{}", theme::success(&message)))
+}
+
 fn cmd_doctor() -> CmdResult {
     use hive_core::dns::collect_tlds;
     use hive_core::daemon_defaults::DNS_BIND;
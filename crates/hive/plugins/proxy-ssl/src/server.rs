@@ -156,6 +156,7 @@ impl HttpsServer {
         let challenge_manager = self.challenge_manager.clone();
         let tls_manager = self.tls_manager.clone();
         let renew_before_days = self.config.renew_before_days;
+        let domains = self.config.domains.clone();
 
         tokio::spawn(async move {
             let interval = Duration::from_secs(12 * 60 * 60);
@@ -169,18 +170,22 @@ impl HttpsServer {
                             Ok(true) => {
                                 info!("Certificate renewal needed (expires within {} days)", renew_before_days);
 
-                                match acme_client.obtain_certificate(&challenge_manager).await {
+                                let error = match acme_client.obtain_certificate(&challenge_manager).await {
                                     Ok(_) => {
                                         if let Err(e) = tls_manager.reload().await {
                                             error!("Failed to reload TLS config after renewal: {}", e);
+                                            Some(e.to_string())
                                         } else {
                                             info!("Certificate renewed and loaded successfully");
+                                            None
                                         }
                                     }
                                     Err(e) => {
                                         error!("Certificate renewal failed: {}", e);
+                                        Some(e.to_string())
                                     }
-                                }
+                                };
+                                publish_cert_expiry(&domains, renew_before_days, error).await;
                             }
                             Ok(false) => {
                                 info!("Certificate still valid, no renewal needed");
@@ -374,3 +379,25 @@ pub struct CertificateIssueResult {
     pub domain: String,
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
 }
+
+/// Tell the ADI daemon bus that a certificate reached its renewal window.
+/// Hive may run without a local daemon, so this never fails the renewal loop.
+async fn publish_cert_expiry(domains: &[String], renew_before_days: u32, error: Option<String>) {
+    use lib_daemon_client::{events::topics, DaemonClient, DaemonEvent};
+
+    let client = DaemonClient::new().with_timeout(Duration::from_secs(2));
+    if !client.socket_exists() {
+        return;
+    }
+
+    let payload = serde_json::json!({
+        "domains": domains,
+        "renew_before_days": renew_before_days,
+        "renewed": error.is_none(),
+        "error": error,
+    });
+    let event = DaemonEvent::new(topics::CERT_EXPIRY, "hive.proxy-ssl", payload.to_string());
+    if let Err(e) = client.publish(event).await {
+        tracing::debug!("Failed to publish cert.expiry event: {}", e);
+    }
+}
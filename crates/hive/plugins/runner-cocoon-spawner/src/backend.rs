@@ -0,0 +1,123 @@
+//! Backends a cocoon can run on, selected per cocoon kind via `runner_type`.
+
+use anyhow::{anyhow, Result};
+use lib_plugin_abi_v3::{async_trait, runner::ProcessHandle};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::debug;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    Docker,
+    /// Rootless podman, driven through the `podman` CLI
+    Podman,
+    /// Plain `cocoon` process in a transient `systemd-run --user` unit
+    Process,
+}
+
+impl BackendKind {
+    pub const ALL: [BackendKind; 3] = [Self::Docker, Self::Podman, Self::Process];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::Podman => "podman",
+            Self::Process => "process",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Everything a backend needs to start one cocoon.
+#[derive(Debug, Clone)]
+pub struct CocoonSpec {
+    /// Container or unit name
+    pub name: String,
+    pub image: String,
+    pub env: Vec<(String, String)>,
+    /// `runner_config` of the cocoon kind
+    pub options: serde_json::Value,
+}
+
+/// Resource usage snapshot; `None` where a backend cannot measure a value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CocoonStats {
+    pub cpu_percent: Option<f64>,
+    pub memory_bytes: Option<u64>,
+    pub memory_limit_bytes: Option<u64>,
+}
+
+#[async_trait]
+pub trait CocoonBackend: Send + Sync {
+    fn kind(&self) -> BackendKind;
+
+    /// Whether the backend can be used on this host.
+    async fn is_available(&self) -> bool;
+
+    async fn spawn(&self, spec: CocoonSpec) -> Result<ProcessHandle>;
+
+    /// Stop the cocoon and remove its container or unit.
+    async fn terminate(&self, handle: &ProcessHandle) -> Result<()>;
+
+    async fn is_running(&self, handle: &ProcessHandle) -> bool;
+
+    async fn logs(&self, handle: &ProcessHandle, lines: usize) -> Result<Vec<String>>;
+
+    async fn stats(&self, handle: &ProcessHandle) -> Result<CocoonStats>;
+}
+
+/// Container or unit name stored in the handle.
+pub(crate) fn handle_name(handle: &ProcessHandle) -> Result<String> {
+    handle
+        .container_name
+        .clone()
+        .ok_or_else(|| anyhow!("Missing container name in handle"))
+}
+
+/// Run a CLI tool, returning trimmed stdout or an error carrying stderr.
+pub(crate) async fn run_command(program: &str, args: &[String]) -> Result<String> {
+    debug!("Running: {} {}", program, args.join(" "));
+
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| anyhow!("Failed to run {program}: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("{program} failed: {}", stderr.trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_kind_round_trip() {
+        for kind in BackendKind::ALL {
+            assert_eq!(BackendKind::parse(kind.as_str()), Some(kind));
+            let json = serde_json::to_value(kind).unwrap();
+            assert_eq!(json, kind.as_str());
+        }
+        assert_eq!(BackendKind::parse("lxc"), None);
+    }
+}
@@ -0,0 +1,278 @@
+//! Docker backend, talking to the daemon through bollard.
+
+use crate::backend::{handle_name, BackendKind, CocoonBackend, CocoonSpec, CocoonStats};
+use anyhow::{anyhow, Context, Result};
+use bollard::container::{
+    Config, CreateContainerOptions, LogsOptions, RemoveContainerOptions, StartContainerOptions,
+    Stats, StatsOptions, StopContainerOptions,
+};
+use bollard::image::{CreateImageOptions, ListImagesOptions};
+use bollard::Docker;
+use futures::StreamExt;
+use lib_plugin_abi_v3::{async_trait, runner::ProcessHandle};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::OnceLock;
+use tracing::{debug, info};
+
+/// Bollard needs a Tokio reactor, so calls run on a runtime owned by the plugin.
+#[derive(Default)]
+pub struct DockerBackend {
+    client: OnceLock<Docker>,
+    runtime: OnceLock<tokio::runtime::Runtime>,
+}
+
+impl DockerBackend {
+    fn runtime_handle(&self) -> tokio::runtime::Handle {
+        self.runtime
+            .get_or_init(|| {
+                tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(1)
+                    .enable_all()
+                    .build()
+                    .expect("Failed to create Tokio runtime for cocoon runner")
+            })
+            .handle()
+            .clone()
+    }
+
+    fn get_client(&self) -> Result<Docker> {
+        if let Some(client) = self.client.get() {
+            return Ok(client.clone());
+        }
+        let _guard = self.runtime_handle().enter();
+        let client = Docker::connect_with_local_defaults()
+            .or_else(|_| Docker::connect_with_unix_defaults())
+            .or_else(|_| {
+                Docker::connect_with_socket(
+                    "/var/run/docker.sock",
+                    120,
+                    bollard::API_DEFAULT_VERSION,
+                )
+            })
+            .context("Failed to connect to Docker. Is Docker running?")?;
+        let _ = self.client.set(client);
+        Ok(self
+            .client
+            .get()
+            .ok_or_else(|| anyhow!("Failed to initialize Docker client"))?
+            .clone())
+    }
+
+    async fn run<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(Docker) -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let client = self.get_client()?;
+        self.runtime_handle()
+            .spawn(f(client))
+            .await
+            .map_err(|e| anyhow!("Cocoon task panicked: {e}"))?
+    }
+}
+
+#[async_trait]
+impl CocoonBackend for DockerBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Docker
+    }
+
+    async fn is_available(&self) -> bool {
+        self.run(|client| async move { Ok(client.ping().await?) })
+            .await
+            .is_ok()
+    }
+
+    async fn spawn(&self, spec: CocoonSpec) -> Result<ProcessHandle> {
+        let container_config = Config {
+            image: Some(spec.image.clone()),
+            env: Some(spec.env.iter().map(|(k, v)| format!("{k}={v}")).collect()),
+            host_config: Some(bollard::service::HostConfig {
+                cap_drop: Some(vec!["ALL".to_string()]),
+                security_opt: Some(vec!["no-new-privileges:true".to_string()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let CocoonSpec {
+            name: container_name,
+            image,
+            ..
+        } = spec;
+
+        self.run(|client| async move {
+            pull_image_if_needed(&client, &image).await?;
+
+            let _ = client
+                .remove_container(
+                    &container_name,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await;
+
+            info!("Creating cocoon container {container_name} from {image}");
+            client
+                .create_container(
+                    Some(CreateContainerOptions {
+                        name: &container_name,
+                        platform: None,
+                    }),
+                    container_config,
+                )
+                .await
+                .context("Failed to create cocoon container")?;
+
+            client
+                .start_container(&container_name, None::<StartContainerOptions<String>>)
+                .await
+                .context("Failed to start cocoon container")?;
+
+            info!("Cocoon container {container_name} started");
+            Ok(ProcessHandle::docker(container_name).with_metadata("image", &image))
+        })
+        .await
+    }
+
+    async fn terminate(&self, handle: &ProcessHandle) -> Result<()> {
+        let container_name = handle_name(handle)?;
+
+        self.run(|client| async move {
+            info!("Stopping cocoon container {container_name}");
+            client
+                .stop_container(&container_name, Some(StopContainerOptions { t: 10 }))
+                .await
+                .context("Failed to stop cocoon container")?;
+
+            client
+                .remove_container(
+                    &container_name,
+                    Some(RemoveContainerOptions {
+                        force: false,
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .context("Failed to remove cocoon container")?;
+
+            info!("Cocoon container {container_name} stopped and removed");
+            Ok(())
+        })
+        .await
+    }
+
+    async fn is_running(&self, handle: &ProcessHandle) -> bool {
+        let Ok(container_name) = handle_name(handle) else {
+            return false;
+        };
+
+        self.run(|client| async move {
+            let info = client.inspect_container(&container_name, None).await?;
+            Ok(info.state.and_then(|s| s.running).unwrap_or(false))
+        })
+        .await
+        .unwrap_or(false)
+    }
+
+    async fn logs(&self, handle: &ProcessHandle, lines: usize) -> Result<Vec<String>> {
+        let container_name = handle_name(handle)?;
+
+        self.run(move |client| async move {
+            let options = LogsOptions::<String> {
+                stdout: true,
+                stderr: true,
+                tail: lines.to_string(),
+                ..Default::default()
+            };
+
+            let mut stream = client.logs(&container_name, Some(options));
+            let mut logs = Vec::new();
+
+            while let Some(result) = stream.next().await {
+                match result {
+                    Ok(output) => logs.push(output.to_string()),
+                    Err(e) => {
+                        debug!("Error reading cocoon log: {e}");
+                        break;
+                    }
+                }
+            }
+            Ok(logs)
+        })
+        .await
+    }
+
+    async fn stats(&self, handle: &ProcessHandle) -> Result<CocoonStats> {
+        let container_name = handle_name(handle)?;
+
+        self.run(|client| async move {
+            // Not one-shot: Docker waits for a second sample so CPU usage has a delta
+            let options = StatsOptions {
+                stream: false,
+                one_shot: false,
+            };
+            let stats = client
+                .stats(&container_name, Some(options))
+                .next()
+                .await
+                .ok_or_else(|| anyhow!("No stats returned for {container_name}"))??;
+            Ok(stats_from_docker(&stats))
+        })
+        .await
+    }
+}
+
+fn stats_from_docker(stats: &Stats) -> CocoonStats {
+    let cpu_delta = stats
+        .cpu_stats
+        .cpu_usage
+        .total_usage
+        .saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
+    let system_delta = stats
+        .cpu_stats
+        .system_cpu_usage
+        .zip(stats.precpu_stats.system_cpu_usage)
+        .map(|(now, before)| now.saturating_sub(before));
+    let cpus = stats.cpu_stats.online_cpus.unwrap_or(1).max(1);
+
+    CocoonStats {
+        cpu_percent: system_delta
+            .filter(|delta| *delta > 0)
+            .map(|delta| cpu_delta as f64 / delta as f64 * cpus as f64 * 100.0),
+        memory_bytes: stats.memory_stats.usage,
+        memory_limit_bytes: stats.memory_stats.limit,
+    }
+}
+
+async fn pull_image_if_needed(client: &Docker, image: &str) -> Result<()> {
+    let mut filters = HashMap::new();
+    filters.insert("reference", vec![image]);
+    let options = ListImagesOptions {
+        filters,
+        ..Default::default()
+    };
+    let images = client.list_images(Some(options)).await?;
+    if !images.is_empty() {
+        return Ok(());
+    }
+
+    info!("Pulling cocoon image: {image}");
+    let mut stream = client.create_image(
+        Some(CreateImageOptions {
+            from_image: image,
+            ..Default::default()
+        }),
+        None,
+        None,
+    );
+    while let Some(result) = stream.next().await {
+        result?;
+    }
+    info!("Image pulled: {image}");
+    Ok(())
+}
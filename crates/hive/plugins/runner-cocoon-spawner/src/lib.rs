@@ -1,7 +1,8 @@
 //! Cocoon Spawner Runner Plugin for Hive
 //!
-//! Spawns cocoon containers via Docker with signaling-specific environment
-//! variables injected automatically.
+//! Spawns cocoons with signaling-specific environment variables injected
+//! automatically. The backend is chosen per cocoon kind via `runner`:
+//! `docker` (default), `podman` (rootless) or `process` (`systemd-run --user`).
 //!
 //! ## Configuration
 //!
@@ -9,19 +10,27 @@
 //! runner:
 //!   type: cocoon-spawner
 //!   cocoon-spawner:
+//!     runner: podman
 //!     image: adi/cocoon-ubuntu:latest
 //!     signaling_url: ws://signaling.example.com/ws
 //!     setup_token: <token>
+//!     secret: <COCOON_SECRET, optional>
+//!     pool_secret: <COCOON_POOL_SECRET, warm pool slots only>
 //!     ice_servers: stun:stun.l.google.com:19302
 //! ```
+//!
+//! The `process` backend ignores `image` and runs `runner_config.binary`
+//! (default `cocoon`) with `runner_config.args`.
+
+mod backend;
+mod docker;
+mod podman;
+mod process;
+
+pub use backend::{BackendKind, CocoonBackend, CocoonSpec, CocoonStats};
 
 use anyhow::{anyhow, Context, Result as AnyhowResult};
-use bollard::container::{
-    Config, CreateContainerOptions, LogsOptions, RemoveContainerOptions, StartContainerOptions,
-    StopContainerOptions,
-};
-use bollard::image::{CreateImageOptions, ListImagesOptions};
-use bollard::Docker;
+use docker::DockerBackend;
 use lib_plugin_abi_v3::{
     async_trait,
     hooks::HookExitStatus,
@@ -29,13 +38,18 @@ use lib_plugin_abi_v3::{
     Plugin, PluginCategory, PluginContext, PluginMetadata, PluginType,
     Result as PluginResult, SERVICE_RUNNER,
 };
+use podman::PodmanBackend;
+use process::ProcessBackend;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tracing::{debug, info, warn};
+use tracing::{info, warn};
+
+const DEFAULT_LOG_LINES: usize = 100;
 
 pub struct CocoonRunnerPlugin {
-    client: std::sync::OnceLock<Docker>,
-    runtime: std::sync::OnceLock<tokio::runtime::Runtime>,
+    docker: DockerBackend,
+    podman: PodmanBackend,
+    process: ProcessBackend,
 }
 
 impl Default for CocoonRunnerPlugin {
@@ -47,45 +61,40 @@ impl Default for CocoonRunnerPlugin {
 impl CocoonRunnerPlugin {
     pub fn new_lazy() -> Self {
         Self {
-            client: std::sync::OnceLock::new(),
-            runtime: std::sync::OnceLock::new(),
+            docker: DockerBackend::default(),
+            podman: PodmanBackend::default(),
+            process: ProcessBackend,
         }
     }
 
-    fn runtime_handle(&self) -> tokio::runtime::Handle {
-        self.runtime
-            .get_or_init(|| {
-                tokio::runtime::Builder::new_multi_thread()
-                    .worker_threads(1)
-                    .enable_all()
-                    .build()
-                    .expect("Failed to create Tokio runtime for cocoon runner")
-            })
-            .handle()
-            .clone()
+    fn backend(&self, kind: BackendKind) -> &dyn CocoonBackend {
+        match kind {
+            BackendKind::Docker => &self.docker,
+            BackendKind::Podman => &self.podman,
+            BackendKind::Process => &self.process,
+        }
+    }
+
+    fn backend_for_handle(&self, handle: &ProcessHandle) -> AnyhowResult<&dyn CocoonBackend> {
+        BackendKind::parse(&handle.runner_type)
+            .map(|kind| self.backend(kind))
+            .ok_or_else(|| anyhow!("Unsupported cocoon runner: {}", handle.runner_type))
     }
 
-    fn get_client(&self) -> AnyhowResult<Docker> {
-        if let Some(client) = self.client.get() {
-            return Ok(client.clone());
+    /// Backends usable on this host.
+    pub async fn available_backends(&self) -> Vec<BackendKind> {
+        let mut available = Vec::new();
+        for kind in BackendKind::ALL {
+            if self.backend(kind).is_available().await {
+                available.push(kind);
+            }
         }
-        let _guard = self.runtime_handle().enter();
-        let client = Docker::connect_with_local_defaults()
-            .or_else(|_| Docker::connect_with_unix_defaults())
-            .or_else(|_| {
-                Docker::connect_with_socket(
-                    "/var/run/docker.sock",
-                    120,
-                    bollard::API_DEFAULT_VERSION,
-                )
-            })
-            .context("Failed to connect to Docker. Is Docker running?")?;
-        let _ = self.client.set(client);
-        Ok(self
-            .client
-            .get()
-            .ok_or_else(|| anyhow!("Failed to initialize Docker client"))?
-            .clone())
+        available
+    }
+
+    /// Resource usage of a running cocoon.
+    pub async fn stats(&self, handle: &ProcessHandle) -> PluginResult<CocoonStats> {
+        Ok(self.backend_for_handle(handle)?.stats(handle).await?)
     }
 
     fn extract_config(config: &serde_json::Value) -> AnyhowResult<CocoonConfig> {
@@ -110,13 +119,17 @@ impl Plugin for CocoonRunnerPlugin {
         }
     }
 
-    async fn init(&mut self, _ctx: &PluginContext) -> PluginResult<()> {
-        match self.get_client() {
-            Ok(_) => info!("Cocoon runner: Docker client initialized"),
-            Err(e) => {
-                warn!("Cocoon runner: failed to connect to Docker: {}", e);
-                return Err(e.into());
-            }
+    async fn init(&mut self, ctx: &PluginContext) -> PluginResult<()> {
+        if let Some(socket) = ctx.config.get("podman_socket").and_then(|v| v.as_str()) {
+            self.podman = PodmanBackend::new(Some(socket.to_string()));
+        }
+
+        let available = self.available_backends().await;
+        if available.is_empty() {
+            warn!("Cocoon runner: no backend available (docker, podman, process)");
+        } else {
+            let names: Vec<&str> = available.iter().map(|k| k.as_str()).collect();
+            info!("Cocoon runner: available backends: {}", names.join(", "));
         }
         Ok(())
     }
@@ -140,146 +153,52 @@ impl Runner for CocoonRunnerPlugin {
         _ctx: &RuntimeContext,
     ) -> PluginResult<ProcessHandle> {
         let cocoon_config = Self::extract_config(config)?;
-        let container_name = format!("cocoon-{service_name}");
 
-        let mut env_vec: Vec<String> = env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        let mut env_vec: Vec<(String, String)> = env.into_iter().collect();
 
         // Inject cocoon-specific environment
-        env_vec.push(format!(
-            "SIGNALING_SERVER_URL={}",
-            cocoon_config.signaling_url
+        env_vec.push((
+            "SIGNALING_SERVER_URL".to_string(),
+            cocoon_config.signaling_url.clone(),
         ));
         if let Some(token) = &cocoon_config.setup_token {
-            env_vec.push(format!("COCOON_SETUP_TOKEN={token}"));
+            env_vec.push(("COCOON_SETUP_TOKEN".to_string(), token.clone()));
+        }
+        if let Some(secret) = &cocoon_config.secret {
+            env_vec.push(("COCOON_SECRET".to_string(), secret.clone()));
+        }
+        if let Some(secret) = &cocoon_config.pool_secret {
+            env_vec.push(("COCOON_POOL_SECRET".to_string(), secret.clone()));
         }
         if let Some(ice) = &cocoon_config.ice_servers {
-            env_vec.push(format!("WEBRTC_ICE_SERVERS={ice}"));
+            env_vec.push(("WEBRTC_ICE_SERVERS".to_string(), ice.clone()));
         }
         if let Some(turn_user) = &cocoon_config.turn_username {
-            env_vec.push(format!("WEBRTC_TURN_USERNAME={turn_user}"));
+            env_vec.push(("WEBRTC_TURN_USERNAME".to_string(), turn_user.clone()));
         }
         if let Some(turn_cred) = &cocoon_config.turn_credential {
-            env_vec.push(format!("WEBRTC_TURN_CREDENTIAL={turn_cred}"));
+            env_vec.push(("WEBRTC_TURN_CREDENTIAL".to_string(), turn_cred.clone()));
         }
 
-        let container_config = Config {
-            image: Some(cocoon_config.image.clone()),
-            env: Some(env_vec),
-            host_config: Some(bollard::service::HostConfig {
-                cap_drop: Some(vec!["ALL".to_string()]),
-                security_opt: Some(vec!["no-new-privileges:true".to_string()]),
-                ..Default::default()
-            }),
-            ..Default::default()
+        let spec = CocoonSpec {
+            name: format!("cocoon-{service_name}"),
+            image: cocoon_config.image,
+            env: env_vec,
+            options: cocoon_config.runner_config,
         };
 
-        let client = self.get_client()?;
-        let rt = self.runtime_handle();
-        let image = cocoon_config.image.clone();
-
-        let inner = rt
-            .spawn(async move {
-                pull_image_if_needed(&client, &image).await?;
-
-                let _ = client
-                    .remove_container(
-                        &container_name,
-                        Some(RemoveContainerOptions {
-                            force: true,
-                            ..Default::default()
-                        }),
-                    )
-                    .await;
-
-                info!("Creating cocoon container {container_name} from {image}");
-                client
-                    .create_container(
-                        Some(CreateContainerOptions {
-                            name: &container_name,
-                            platform: None,
-                        }),
-                        container_config,
-                    )
-                    .await
-                    .context("Failed to create cocoon container")?;
-
-                client
-                    .start_container(&container_name, None::<StartContainerOptions<String>>)
-                    .await
-                    .context("Failed to start cocoon container")?;
-
-                info!("Cocoon container {container_name} started");
-                Ok::<_, anyhow::Error>(
-                    ProcessHandle::docker(container_name).with_metadata("image", &image),
-                )
-            })
-            .await
-            .map_err(|e| anyhow!("Cocoon task panicked: {e}"))?;
-
-        Ok(inner?)
+        Ok(self.backend(cocoon_config.runner).spawn(spec).await?)
     }
 
     async fn stop(&self, handle: &ProcessHandle) -> PluginResult<()> {
-        let container_name = handle
-            .container_name
-            .as_ref()
-            .ok_or_else(|| anyhow!("Missing container name in handle"))?
-            .clone();
-
-        let client = self.get_client()?;
-        let rt = self.runtime_handle();
-
-        let inner = rt
-            .spawn(async move {
-                info!("Stopping cocoon container {container_name}");
-                client
-                    .stop_container(
-                        &container_name,
-                        Some(StopContainerOptions { t: 10 }),
-                    )
-                    .await
-                    .context("Failed to stop cocoon container")?;
-
-                client
-                    .remove_container(
-                        &container_name,
-                        Some(RemoveContainerOptions {
-                            force: false,
-                            ..Default::default()
-                        }),
-                    )
-                    .await
-                    .context("Failed to remove cocoon container")?;
-
-                info!("Cocoon container {container_name} stopped and removed");
-                Ok::<_, anyhow::Error>(())
-            })
-            .await
-            .map_err(|e| anyhow!("Cocoon task panicked: {e}"))?;
-
-        Ok(inner?)
+        Ok(self.backend_for_handle(handle)?.terminate(handle).await?)
     }
 
     async fn is_running(&self, handle: &ProcessHandle) -> bool {
-        let container_name = match &handle.container_name {
-            Some(name) => name.clone(),
-            None => return false,
-        };
-
-        let client = match self.get_client() {
-            Ok(c) => c,
-            Err(_) => return false,
-        };
-
-        let rt = self.runtime_handle();
-        rt.spawn(async move {
-            match client.inspect_container(&container_name, None).await {
-                Ok(info) => info.state.and_then(|s| s.running).unwrap_or(false),
-                Err(_) => false,
-            }
-        })
-        .await
-        .unwrap_or(false)
+        match self.backend_for_handle(handle) {
+            Ok(backend) => backend.is_running(handle).await,
+            Err(_) => false,
+        }
     }
 
     async fn logs(
@@ -287,46 +206,8 @@ impl Runner for CocoonRunnerPlugin {
         handle: &ProcessHandle,
         lines: Option<usize>,
     ) -> PluginResult<Vec<String>> {
-        let container_name = handle
-            .container_name
-            .as_ref()
-            .ok_or_else(|| anyhow!("Missing container name in handle"))?
-            .clone();
-
-        let client = self.get_client()?;
-        let rt = self.runtime_handle();
-        let tail = lines
-            .map(|l| l.to_string())
-            .unwrap_or_else(|| "100".to_string());
-
-        let inner = rt
-            .spawn(async move {
-                use futures::StreamExt;
-                let options = LogsOptions::<String> {
-                    stdout: true,
-                    stderr: true,
-                    tail,
-                    ..Default::default()
-                };
-
-                let mut stream = client.logs(&container_name, Some(options));
-                let mut logs = Vec::new();
-
-                while let Some(result) = stream.next().await {
-                    match result {
-                        Ok(output) => logs.push(output.to_string()),
-                        Err(e) => {
-                            debug!("Error reading cocoon log: {e}");
-                            break;
-                        }
-                    }
-                }
-                Ok::<_, anyhow::Error>(logs)
-            })
-            .await
-            .map_err(|e| anyhow!("Cocoon task panicked: {e}"))?;
-
-        Ok(inner?)
+        let lines = lines.unwrap_or(DEFAULT_LOG_LINES);
+        Ok(self.backend_for_handle(handle)?.logs(handle, lines).await?)
     }
 
     fn supports_hooks(&self) -> bool {
@@ -343,41 +224,21 @@ impl Runner for CocoonRunnerPlugin {
     }
 }
 
-async fn pull_image_if_needed(client: &Docker, image: &str) -> AnyhowResult<()> {
-    use futures::StreamExt;
-
-    let mut filters = HashMap::new();
-    filters.insert("reference", vec![image]);
-    let options = ListImagesOptions {
-        filters,
-        ..Default::default()
-    };
-    let images = client.list_images(Some(options)).await?;
-    if !images.is_empty() {
-        return Ok(());
-    }
-
-    info!("Pulling cocoon image: {image}");
-    let mut stream = client.create_image(
-        Some(CreateImageOptions {
-            from_image: image,
-            ..Default::default()
-        }),
-        None,
-        None,
-    );
-    while let Some(result) = stream.next().await {
-        result?;
-    }
-    info!("Image pulled: {image}");
-    Ok(())
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CocoonConfig {
+    #[serde(default)]
+    pub runner: BackendKind,
+    /// Backend-specific options (e.g. `binary` for `process`)
+    #[serde(default)]
+    pub runner_config: serde_json::Value,
     pub image: String,
     pub signaling_url: String,
     pub setup_token: Option<String>,
+    /// Device secret; the cocoon generates one when unset
+    pub secret: Option<String>,
+    /// Secret of a warm pool slot, used only until its hive hands the cocoon
+    /// over with a new one
+    pub pool_secret: Option<String>,
     pub ice_servers: Option<String>,
     pub turn_username: Option<String>,
     pub turn_credential: Option<String>,
@@ -422,5 +283,31 @@ mod tests {
         assert_eq!(cocoon_config.image, "adi/cocoon-ubuntu:latest");
         assert_eq!(cocoon_config.signaling_url, "ws://signaling.example.com/ws");
         assert_eq!(cocoon_config.setup_token, Some("abc123".to_string()));
+        assert_eq!(cocoon_config.secret, None);
+        assert_eq!(cocoon_config.pool_secret, None);
+        assert_eq!(cocoon_config.runner, BackendKind::Docker);
+    }
+
+    #[test]
+    fn test_extract_config_with_runner() {
+        let config = serde_json::json!({
+            "cocoon-spawner": {
+                "runner": "process",
+                "runner_config": { "binary": "/usr/local/bin/cocoon" },
+                "image": "",
+                "signaling_url": "ws://signaling.example.com/ws"
+            }
+        });
+
+        let cocoon_config = CocoonRunnerPlugin::extract_config(&config).unwrap();
+        assert_eq!(cocoon_config.runner, BackendKind::Process);
+        assert_eq!(cocoon_config.runner_config["binary"], "/usr/local/bin/cocoon");
+    }
+
+    #[test]
+    fn test_unknown_handle_runner_rejected() {
+        let plugin = CocoonRunnerPlugin::new_lazy();
+        let handle = ProcessHandle::script(1);
+        assert!(plugin.backend_for_handle(&handle).is_err());
     }
 }
@@ -0,0 +1,204 @@
+//! Podman backend, driven through the `podman` CLI as the hive user (rootless).
+
+use crate::backend::{
+    handle_name, run_command, BackendKind, CocoonBackend, CocoonSpec, CocoonStats,
+};
+use anyhow::{anyhow, Context, Result};
+use lib_plugin_abi_v3::{async_trait, runner::ProcessHandle};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+#[derive(Default)]
+pub struct PodmanBackend {
+    /// Remote podman service (`podman --url`), local podman when unset
+    socket: Option<String>,
+}
+
+impl PodmanBackend {
+    pub fn new(socket: Option<String>) -> Self {
+        Self { socket }
+    }
+
+    async fn podman(&self, args: &[&str]) -> Result<String> {
+        let mut full: Vec<String> = Vec::with_capacity(args.len() + 2);
+        if let Some(socket) = &self.socket {
+            full.push("--url".to_string());
+            full.push(socket.clone());
+        }
+        full.extend(args.iter().map(|a| a.to_string()));
+        run_command("podman", &full).await
+    }
+}
+
+#[async_trait]
+impl CocoonBackend for PodmanBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Podman
+    }
+
+    async fn is_available(&self) -> bool {
+        match self
+            .podman(&["info", "--format", "{{.Host.Security.Rootless}}"])
+            .await
+        {
+            Ok(rootless) => {
+                if rootless != "true" {
+                    warn!("Podman is not running rootless; cocoons get root-owned containers");
+                }
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    async fn spawn(&self, spec: CocoonSpec) -> Result<ProcessHandle> {
+        let _ = self.podman(&["rm", "-f", &spec.name]).await;
+
+        let env: Vec<String> = spec.env.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        let mut args = vec![
+            "run",
+            "-d",
+            "--name",
+            &spec.name,
+            "--pull",
+            "missing",
+            "--cap-drop",
+            "ALL",
+            "--security-opt",
+            "no-new-privileges",
+        ];
+        for entry in &env {
+            args.push("-e");
+            args.push(entry);
+        }
+        args.push(&spec.image);
+
+        info!("Creating podman cocoon {} from {}", spec.name, spec.image);
+        self.podman(&args)
+            .await
+            .context("Failed to start podman cocoon")?;
+        info!("Podman cocoon {} started", spec.name);
+
+        Ok(ProcessHandle {
+            id: format!("podman-{}", spec.name),
+            runner_type: BackendKind::Podman.to_string(),
+            pid: None,
+            container_name: Some(spec.name),
+            metadata: [("image".to_string(), spec.image)].into_iter().collect(),
+        })
+    }
+
+    async fn terminate(&self, handle: &ProcessHandle) -> Result<()> {
+        let name = handle_name(handle)?;
+        info!("Stopping podman cocoon {name}");
+        self.podman(&["stop", "-t", "10", &name])
+            .await
+            .context("Failed to stop podman cocoon")?;
+        self.podman(&["rm", &name])
+            .await
+            .context("Failed to remove podman cocoon")?;
+        info!("Podman cocoon {name} stopped and removed");
+        Ok(())
+    }
+
+    async fn is_running(&self, handle: &ProcessHandle) -> bool {
+        let Ok(name) = handle_name(handle) else {
+            return false;
+        };
+        self.podman(&["inspect", "--format", "{{.State.Running}}", &name])
+            .await
+            .map(|out| out == "true")
+            .unwrap_or(false)
+    }
+
+    async fn logs(&self, handle: &ProcessHandle, lines: usize) -> Result<Vec<String>> {
+        let name = handle_name(handle)?;
+        let tail = lines.to_string();
+        let output = self.podman(&["logs", "--tail", &tail, &name]).await?;
+        Ok(output.lines().map(String::from).collect())
+    }
+
+    async fn stats(&self, handle: &ProcessHandle) -> Result<CocoonStats> {
+        let name = handle_name(handle)?;
+        let output = self
+            .podman(&["stats", "--no-stream", "--format", "json", &name])
+            .await?;
+        parse_stats(&output)
+    }
+}
+
+/// One entry of `podman stats --format json`; values are human-formatted.
+#[derive(Deserialize)]
+struct PodmanStats {
+    cpu_percent: String,
+    /// `"<usage> / <limit>"`
+    mem_usage: String,
+}
+
+fn parse_stats(json: &str) -> Result<CocoonStats> {
+    let entries: Vec<PodmanStats> =
+        serde_json::from_str(json).context("Failed to parse podman stats")?;
+    let entry = entries
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("podman stats returned no entries"))?;
+
+    let (usage, limit) = match entry.mem_usage.split_once('/') {
+        Some((usage, limit)) => (parse_size(usage), parse_size(limit)),
+        None => (parse_size(&entry.mem_usage), None),
+    };
+
+    Ok(CocoonStats {
+        cpu_percent: entry.cpu_percent.trim().trim_end_matches('%').parse().ok(),
+        memory_bytes: usage,
+        memory_limit_bytes: limit,
+    })
+}
+
+/// Parse sizes like `5.743MB`, `512kB` or `1.5GiB`.
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier: f64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        "tib" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+
+    Some((number * multiplier).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512B"), Some(512));
+        assert_eq!(parse_size("1.5kB"), Some(1500));
+        assert_eq!(parse_size(" 5.743MB "), Some(5_743_000));
+        assert_eq!(parse_size("2GiB"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("12 parsecs"), None);
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        let json = r#"[{"id":"abc","name":"cocoon-x","cpu_percent":"2.50%","mem_usage":"5.743MB / 16.62GB","mem_percent":"0.03%"}]"#;
+        let stats = parse_stats(json).unwrap();
+        assert_eq!(stats.cpu_percent, Some(2.5));
+        assert_eq!(stats.memory_bytes, Some(5_743_000));
+        assert_eq!(stats.memory_limit_bytes, Some(16_620_000_000));
+    }
+}
@@ -0,0 +1,204 @@
+//! Process backend: runs the `cocoon` binary directly on the host inside a
+//! transient `systemd-run --user` unit, so it survives hive restarts and gets
+//! journald logs and cgroup accounting.
+
+use crate::backend::{
+    handle_name, run_command, BackendKind, CocoonBackend, CocoonSpec, CocoonStats,
+};
+use anyhow::{Context, Result};
+use lib_plugin_abi_v3::{async_trait, runner::ProcessHandle};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+const DEFAULT_BINARY: &str = "cocoon";
+
+/// Interval between the two CPU samples a stats call takes.
+const CPU_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// `runner_config` of a `process` cocoon kind.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProcessOptions {
+    /// Cocoon binary, looked up in `PATH` (default `cocoon`)
+    #[serde(default)]
+    pub binary: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct ProcessBackend;
+
+async fn systemctl(args: &[&str]) -> Result<String> {
+    let mut full = vec!["--user".to_string()];
+    full.extend(args.iter().map(|a| a.to_string()));
+    run_command("systemctl", &full).await
+}
+
+async fn unit_properties(unit: &str, properties: &[&str]) -> Result<HashMap<String, String>> {
+    let mut args = vec!["show", unit];
+    for property in properties {
+        args.push("-p");
+        args.push(property);
+    }
+    Ok(parse_properties(&systemctl(&args).await?))
+}
+
+#[async_trait]
+impl CocoonBackend for ProcessBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Process
+    }
+
+    async fn is_available(&self) -> bool {
+        // Both the tool and a reachable user manager are required
+        run_command("systemd-run", &["--version".to_string()])
+            .await
+            .is_ok()
+            && systemctl(&["show-environment"]).await.is_ok()
+    }
+
+    async fn spawn(&self, spec: CocoonSpec) -> Result<ProcessHandle> {
+        let options: ProcessOptions = if spec.options.is_null() {
+            ProcessOptions::default()
+        } else {
+            serde_json::from_value(spec.options.clone())
+                .context("Failed to parse process runner_config")?
+        };
+        let binary = options.binary.as_deref().unwrap_or(DEFAULT_BINARY);
+
+        // A unit left over from a crashed cocoon would block the name
+        let _ = systemctl(&["stop", &spec.name]).await;
+        let _ = systemctl(&["reset-failed", &spec.name]).await;
+
+        let mut args = vec![
+            "--user".to_string(),
+            "--unit".to_string(),
+            spec.name.clone(),
+            "--collect".to_string(),
+            "--quiet".to_string(),
+            "--property".to_string(),
+            "NoNewPrivileges=yes".to_string(),
+        ];
+        for (key, value) in &spec.env {
+            args.push("--setenv".to_string());
+            args.push(format!("{key}={value}"));
+        }
+        args.push("--".to_string());
+        args.push(binary.to_string());
+        args.extend(options.args);
+
+        info!("Starting process cocoon {} ({binary})", spec.name);
+        run_command("systemd-run", &args)
+            .await
+            .context("Failed to start process cocoon")?;
+        info!("Process cocoon {} started", spec.name);
+
+        // The unit name takes the place of a container name
+        Ok(ProcessHandle {
+            id: format!("process-{}", spec.name),
+            runner_type: BackendKind::Process.to_string(),
+            pid: None,
+            container_name: Some(spec.name),
+            metadata: [("binary".to_string(), binary.to_string())]
+                .into_iter()
+                .collect(),
+        })
+    }
+
+    async fn terminate(&self, handle: &ProcessHandle) -> Result<()> {
+        let unit = handle_name(handle)?;
+        info!("Stopping process cocoon {unit}");
+        systemctl(&["stop", &unit])
+            .await
+            .context("Failed to stop process cocoon")?;
+        info!("Process cocoon {unit} stopped");
+        Ok(())
+    }
+
+    async fn is_running(&self, handle: &ProcessHandle) -> bool {
+        let Ok(unit) = handle_name(handle) else {
+            return false;
+        };
+        systemctl(&["is-active", &unit])
+            .await
+            .map(|state| state == "active")
+            .unwrap_or(false)
+    }
+
+    async fn logs(&self, handle: &ProcessHandle, lines: usize) -> Result<Vec<String>> {
+        let unit = handle_name(handle)?;
+        let args = [
+            "--user",
+            "--unit",
+            &unit,
+            "--lines",
+            &lines.to_string(),
+            "--no-pager",
+            "--output",
+            "cat",
+        ]
+        .map(String::from);
+        let output = run_command("journalctl", &args).await?;
+        Ok(output.lines().map(String::from).collect())
+    }
+
+    async fn stats(&self, handle: &ProcessHandle) -> Result<CocoonStats> {
+        let unit = handle_name(handle)?;
+
+        let before = unit_properties(&unit, &["CPUUsageNSec"]).await?;
+        let started = Instant::now();
+        tokio::time::sleep(CPU_SAMPLE_INTERVAL).await;
+        let after = unit_properties(&unit, &["CPUUsageNSec", "MemoryCurrent", "MemoryMax"]).await?;
+
+        let cpu_percent = numeric(&before, "CPUUsageNSec")
+            .zip(numeric(&after, "CPUUsageNSec"))
+            .map(|(before, after)| {
+                after.saturating_sub(before) as f64 / started.elapsed().as_nanos() as f64 * 100.0
+            });
+
+        Ok(CocoonStats {
+            cpu_percent,
+            memory_bytes: numeric(&after, "MemoryCurrent"),
+            memory_limit_bytes: numeric(&after, "MemoryMax"),
+        })
+    }
+}
+
+/// Parse `systemctl show` output (`Key=value` lines).
+fn parse_properties(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Numeric property; `[not set]` and `infinity` are treated as unknown.
+fn numeric(properties: &HashMap<String, String>, key: &str) -> Option<u64> {
+    properties.get(key)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_properties() {
+        let properties =
+            parse_properties("CPUUsageNSec=1500000\nMemoryCurrent=[not set]\nMemoryMax=infinity\n");
+        assert_eq!(numeric(&properties, "CPUUsageNSec"), Some(1_500_000));
+        assert_eq!(numeric(&properties, "MemoryCurrent"), None);
+        assert_eq!(numeric(&properties, "MemoryMax"), None);
+        assert_eq!(numeric(&properties, "TasksCurrent"), None);
+    }
+
+    #[test]
+    fn test_options_default_binary() {
+        let options: ProcessOptions =
+            serde_json::from_value(serde_json::json!({ "args": ["--verbose"] })).unwrap();
+        assert_eq!(options.binary, None);
+        assert_eq!(options.args, vec!["--verbose"]);
+    }
+}
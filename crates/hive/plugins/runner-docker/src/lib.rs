@@ -375,7 +375,7 @@ impl Runner for DockerRunnerPlugin {
                 "Creating container {} from image {}",
                 container_name, image
             );
-            client
+            let created = client
                 .create_container(
                     Some(CreateContainerOptions {
                         name: &container_name,
@@ -395,7 +395,8 @@ impl Runner for DockerRunnerPlugin {
 
             Ok::<_, anyhow::Error>(
                 ProcessHandle::docker(container_name)
-                    .with_metadata("image", &image),
+                    .with_metadata("image", &image)
+                    .with_metadata("container_id", &created.id),
             )
         })
         .await
@@ -0,0 +1,248 @@
+//! Lint baseline for tracking violations across runs.
+//!
+//! Diagnostics are grouped by file and rule into [`BaselineEntry`] records
+//! stored in `.adi/lint-baseline.json`. Comparing a fresh run against the
+//! stored baseline tells which groups are new, which changed size and which
+//! disappeared, so integrations (e.g. `adi lint run --create-tasks`) can keep
+//! an external record per group in sync. Each entry remembers the ID of the
+//! task opened for it.
+
+use crate::types::{Diagnostic, Severity};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Baseline location relative to the project root.
+pub const BASELINE_FILE: &str = ".adi/lint-baseline.json";
+
+/// All violations of one rule in one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    /// File path relative to the project root.
+    pub file: PathBuf,
+    /// `<linter_id>/<rule_id>`.
+    pub rule: String,
+    /// Highest severity in the group.
+    pub severity: Severity,
+    /// Number of violations.
+    pub count: usize,
+    /// Line of the first violation.
+    pub first_line: u32,
+    /// Message of the first violation.
+    pub message: String,
+    /// Task tracking this group, if one was opened.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<i64>,
+}
+
+impl BaselineEntry {
+    /// Stable key of the group, `<file>::<rule>`.
+    pub fn key(&self) -> String {
+        format!("{}::{}", self.file.display(), self.rule)
+    }
+}
+
+/// Stored violation groups, keyed by [`BaselineEntry::key`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub entries: BTreeMap<String, BaselineEntry>,
+}
+
+/// Difference between a stored baseline and the current run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BaselineDiff {
+    /// Groups not in the baseline.
+    pub new: Vec<BaselineEntry>,
+    /// Groups whose violation count changed; carries the stored `task_id`.
+    pub changed: Vec<BaselineEntry>,
+    /// Stored groups that no longer have violations.
+    pub resolved: Vec<BaselineEntry>,
+}
+
+impl BaselineDiff {
+    pub fn is_empty(&self) -> bool {
+        self.new.is_empty() && self.changed.is_empty() && self.resolved.is_empty()
+    }
+}
+
+impl Baseline {
+    /// Group diagnostics at or above `min_severity` by file and rule.
+    pub fn from_diagnostics(
+        root: &Path,
+        diagnostics: &[Diagnostic],
+        min_severity: Severity,
+    ) -> Self {
+        let mut entries: BTreeMap<String, BaselineEntry> = BTreeMap::new();
+
+        for diag in diagnostics.iter().filter(|d| d.severity >= min_severity) {
+            let file = diag
+                .location
+                .file
+                .strip_prefix(root)
+                .unwrap_or(&diag.location.file)
+                .to_path_buf();
+            let entry = BaselineEntry {
+                file,
+                rule: format!("{}/{}", diag.linter_id, diag.rule_id),
+                severity: diag.severity,
+                count: 0,
+                first_line: diag.location.start_line,
+                message: diag.message.clone(),
+                task_id: None,
+            };
+            let entry = entries.entry(entry.key()).or_insert(entry);
+            entry.count += 1;
+            entry.severity = entry.severity.max(diag.severity);
+            if diag.location.start_line < entry.first_line {
+                entry.first_line = diag.location.start_line;
+                entry.message = diag.message.clone();
+            }
+        }
+
+        Self { entries }
+    }
+
+    /// Load the baseline of a project; a missing file is an empty baseline.
+    pub fn load(root: &Path) -> anyhow::Result<Self> {
+        let path = root.join(BASELINE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, root: &Path) -> anyhow::Result<()> {
+        let path = root.join(BASELINE_FILE);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// Compare against the groups of the current run.
+    ///
+    /// Entries in `changed` and unchanged groups keep their stored `task_id`.
+    pub fn diff(&self, current: &Baseline) -> BaselineDiff {
+        let mut diff = BaselineDiff::default();
+
+        for (key, entry) in &current.entries {
+            match self.entries.get(key) {
+                None => diff.new.push(entry.clone()),
+                Some(stored) if stored.count != entry.count => diff.changed.push(BaselineEntry {
+                    task_id: stored.task_id,
+                    ..entry.clone()
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.resolved = self
+            .entries
+            .iter()
+            .filter(|(key, _)| !current.entries.contains_key(*key))
+            .map(|(_, entry)| entry.clone())
+            .collect();
+
+        diff
+    }
+
+    /// Carry stored `task_id`s over to matching groups of `current`.
+    pub fn link_tasks(&self, current: &mut Baseline) {
+        for (key, entry) in current.entries.iter_mut() {
+            if let Some(stored) = self.entries.get(key) {
+                entry.task_id = entry.task_id.or(stored.task_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Category, Location};
+
+    fn diag(file: &str, rule: &str, line: u32, severity: Severity) -> Diagnostic {
+        Diagnostic::new(
+            rule,
+            "test-linter",
+            Category::CodeQuality,
+            severity,
+            format!("{} at {}", rule, line),
+            Location::line(PathBuf::from("/repo").join(file), line),
+        )
+    }
+
+    #[test]
+    fn test_groups_by_file_and_rule() {
+        let diagnostics = vec![
+            diag("a.rs", "no-unwrap", 10, Severity::Error),
+            diag("a.rs", "no-unwrap", 3, Severity::Error),
+            diag("a.rs", "no-todo", 1, Severity::Warning),
+            diag("b.rs", "no-unwrap", 7, Severity::Error),
+        ];
+        let baseline =
+            Baseline::from_diagnostics(Path::new("/repo"), &diagnostics, Severity::Error);
+
+        assert_eq!(baseline.entries.len(), 2);
+        let entry = &baseline.entries["a.rs::test-linter/no-unwrap"];
+        assert_eq!(entry.count, 2);
+        assert_eq!(entry.first_line, 3);
+        assert_eq!(entry.message, "no-unwrap at 3");
+    }
+
+    #[test]
+    fn test_diff_and_link_tasks() {
+        let root = Path::new("/repo");
+        let mut stored = Baseline::from_diagnostics(
+            root,
+            &[
+                diag("a.rs", "no-unwrap", 1, Severity::Error),
+                diag("b.rs", "no-unwrap", 1, Severity::Error),
+            ],
+            Severity::Error,
+        );
+        for (id, entry) in stored.entries.values_mut().enumerate() {
+            entry.task_id = Some(id as i64 + 1);
+        }
+
+        let mut current = Baseline::from_diagnostics(
+            root,
+            &[
+                diag("a.rs", "no-unwrap", 1, Severity::Error),
+                diag("a.rs", "no-unwrap", 2, Severity::Error),
+                diag("c.rs", "no-unwrap", 1, Severity::Error),
+            ],
+            Severity::Error,
+        );
+
+        let diff = stored.diff(&current);
+        assert_eq!(diff.new.len(), 1);
+        assert_eq!(diff.new[0].file, PathBuf::from("c.rs"));
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].task_id, Some(1));
+        assert_eq!(diff.resolved.len(), 1);
+        assert_eq!(diff.resolved[0].task_id, Some(2));
+
+        stored.link_tasks(&mut current);
+        assert_eq!(
+            current.entries["a.rs::test-linter/no-unwrap"].task_id,
+            Some(1)
+        );
+        assert_eq!(current.entries["c.rs::test-linter/no-unwrap"].task_id, None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Baseline::load(dir.path()).unwrap(), Baseline::default());
+
+        let baseline = Baseline::from_diagnostics(
+            dir.path(),
+            &[diag("a.rs", "no-unwrap", 1, Severity::Error)],
+            Severity::Hint,
+        );
+        baseline.save(dir.path()).unwrap();
+        assert_eq!(Baseline::load(dir.path()).unwrap(), baseline);
+    }
+}
@@ -0,0 +1,344 @@
+//! Comment tokens of source files, as far as suppressions need them.
+//!
+//! A small lexer per language family, picked by file extension. It skips
+//! string literals, so comment markers inside them are not taken for
+//! comments, and it tells documentation comments (`///`, `//!`, `/** */`,
+//! `/*! */`) apart from plain ones.
+
+use std::path::Path;
+
+/// What the lexer found on one line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineTokens {
+    /// Whether the line has anything outside comments (code or strings).
+    pub code: bool,
+    /// Text of the plain comments on the line, delimiters stripped.
+    /// Documentation comments are left out.
+    pub comments: Vec<String>,
+}
+
+/// Comment and string syntax of a language family.
+struct Syntax {
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    /// Whether block comments nest (Rust)
+    nested: bool,
+    /// Whether `///`, `//!`, `/**` and `/*!` open documentation
+    doc: bool,
+    /// String delimiters, longest first, and whether the string may span lines
+    quotes: &'static [(&'static str, bool)],
+    /// Rust raw strings (`r#"..."#`) and char literals vs. lifetimes
+    rust: bool,
+}
+
+const RUST: Syntax = Syntax {
+    line: &["//"],
+    block: &[("/*", "*/")],
+    nested: true,
+    doc: true,
+    quotes: &[("\"", true)],
+    rust: true,
+};
+
+const C_LIKE: Syntax = Syntax {
+    line: &["//"],
+    block: &[("/*", "*/")],
+    nested: false,
+    doc: true,
+    quotes: &[("\"\"\"", true), ("\"", false), ("'", false), ("`", true)],
+    rust: false,
+};
+
+const HASH: Syntax = Syntax {
+    line: &["#"],
+    block: &[],
+    nested: false,
+    doc: false,
+    quotes: &[("\"\"\"", true), ("'''", true), ("\"", false), ("'", false)],
+    rust: false,
+};
+
+const DASH: Syntax = Syntax {
+    line: &["--"],
+    block: &[("--[[", "]]"), ("/*", "*/")],
+    nested: false,
+    doc: false,
+    quotes: &[("\"", false), ("'", false)],
+    rust: false,
+};
+
+const MARKUP: Syntax = Syntax {
+    line: &[],
+    block: &[("<!--", "-->")],
+    nested: false,
+    doc: false,
+    quotes: &[],
+    rust: false,
+};
+
+const FALLBACK: Syntax = Syntax {
+    line: &["//", "#"],
+    block: &[("/*", "*/"), ("<!--", "-->")],
+    nested: false,
+    doc: false,
+    quotes: &[("\"", false)],
+    rust: false,
+};
+
+fn syntax_for(file: &Path) -> &'static Syntax {
+    let ext = file
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "rs" => &RUST,
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "java" | "kt" | "kts" | "swift" | "cs"
+        | "go" | "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" | "scala" | "dart"
+        | "css" | "scss" | "less" | "tsp" => &C_LIKE,
+        "py" | "pyi" | "sh" | "bash" | "zsh" | "fish" | "rb" | "pl" | "yaml" | "yml" | "toml"
+        | "r" | "nix" | "cmake" | "dockerfile" => &HASH,
+        "sql" | "lua" | "hs" => &DASH,
+        "html" | "htm" | "xml" | "svg" | "vue" | "md" | "markdown" => &MARKUP,
+        _ => match file.file_name().and_then(|n| n.to_str()) {
+            Some("Dockerfile" | "Makefile" | "justfile" | "Justfile") => &HASH,
+            _ => &FALLBACK,
+        },
+    }
+}
+
+/// Split `content` into per-line tokens; the result has one entry per line.
+pub fn lex(file: &Path, content: &str) -> Vec<LineTokens> {
+    let syntax = syntax_for(file);
+    let mut lines = vec![LineTokens::default()];
+    let mut i = 0;
+
+    while i < content.len() {
+        let rest = &content[i..];
+
+        if rest.starts_with('\n') {
+            lines.push(LineTokens::default());
+            i += 1;
+            continue;
+        }
+
+        if let Some(&(open, close)) = syntax.block.iter().find(|(open, _)| rest.starts_with(open)) {
+            let len = block_len(rest, open, close, syntax.nested);
+            let text = &rest[..len];
+            let body = text[open.len()..]
+                .strip_suffix(close)
+                .unwrap_or(&text[open.len()..]);
+            let doc = syntax.doc && is_block_doc(text);
+            for (n, segment) in body.split('\n').enumerate() {
+                if n > 0 {
+                    lines.push(LineTokens::default());
+                }
+                if !doc {
+                    let line = lines.last_mut().unwrap();
+                    line.comments
+                        .push(segment.trim_end_matches('\r').to_string());
+                }
+            }
+            i += len;
+            continue;
+        }
+
+        if let Some(open) = syntax.line.iter().find(|open| rest.starts_with(*open)) {
+            let len = rest.find('\n').unwrap_or(rest.len());
+            let text = &rest[..len];
+            if !(syntax.doc && is_line_doc(text)) {
+                let line = lines.last_mut().unwrap();
+                line.comments
+                    .push(text[open.len()..].trim_end_matches('\r').to_string());
+            }
+            i += len;
+            continue;
+        }
+
+        let string_len = if syntax.rust {
+            rust_literal_len(content, i)
+        } else {
+            None
+        }
+        .or_else(|| {
+            syntax
+                .quotes
+                .iter()
+                .find(|(quote, _)| rest.starts_with(quote))
+                .map(|&(quote, multiline)| string_len(rest, quote, multiline))
+        });
+        if let Some(len) = string_len {
+            lines.last_mut().unwrap().code = true;
+            for _ in rest[..len].matches('\n') {
+                lines.push(LineTokens {
+                    code: true,
+                    ..Default::default()
+                });
+            }
+            i += len;
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap();
+        if !c.is_whitespace() {
+            lines.last_mut().unwrap().code = true;
+        }
+        i += c.len_utf8();
+    }
+
+    // `str::lines` does not yield an empty last line
+    if content.ends_with('\n') {
+        lines.pop();
+    }
+    lines
+}
+
+fn is_line_doc(text: &str) -> bool {
+    (text.starts_with("///") && !text.starts_with("////")) || text.starts_with("//!")
+}
+
+fn is_block_doc(text: &str) -> bool {
+    (text.starts_with("/**") && !text.starts_with("/***") && text != "/**/")
+        || text.starts_with("/*!")
+}
+
+/// Length of the block comment at the start of `rest`, to the end of input if unterminated.
+fn block_len(rest: &str, open: &str, close: &str, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < rest.len() {
+        if rest[i..].starts_with(open) && (nested || depth == 0) {
+            depth += 1;
+            i += open.len();
+        } else if rest[i..].starts_with(close) {
+            depth -= 1;
+            i += close.len();
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += rest[i..].chars().next().unwrap().len_utf8();
+        }
+    }
+    rest.len()
+}
+
+/// Length of the string literal at the start of `rest`, honoring backslash
+/// escapes. Single-line strings end at the line break if unterminated.
+fn string_len(rest: &str, quote: &str, multiline: bool) -> usize {
+    let mut i = quote.len();
+    while i < rest.len() {
+        let tail = &rest[i..];
+        if tail.starts_with(quote) {
+            return i + quote.len();
+        }
+        let c = tail.chars().next().unwrap();
+        if c == '\n' && !multiline {
+            return i;
+        }
+        i += c.len_utf8();
+        if c == '\\' {
+            if let Some(escaped) = rest[i..].chars().next() {
+                i += escaped.len_utf8();
+            }
+        }
+    }
+    rest.len()
+}
+
+/// Rust raw strings and char literals at `i`. Lifetimes (`'a`) are not literals.
+fn rust_literal_len(content: &str, i: usize) -> Option<usize> {
+    let rest = &content[i..];
+    let before = content[..i].chars().next_back();
+
+    if let Some(literal) = rest.strip_prefix('\'') {
+        let mut chars = literal.chars();
+        return match chars.next()? {
+            '\\' => {
+                // Skip the escaped character, which may itself be a quote
+                let start = 2 + chars.next()?.len_utf8();
+                rest[start..].find('\'').map(|end| start + end + 1)
+            }
+            c => (chars.next() == Some('\'')).then_some(c.len_utf8() + 2),
+        };
+    }
+
+    // `r"..."`, `r#"..."#` and `br"..."`, but not identifiers ending in `r`
+    let prefix = if rest.starts_with("br") { 2 } else { 1 };
+    if !rest.starts_with('r') && prefix == 1 || before.is_some_and(is_ident) {
+        return None;
+    }
+    let hashes = rest[prefix..].chars().take_while(|&c| c == '#').count();
+    let open = prefix + hashes;
+    if !rest[open..].starts_with('"') {
+        return None;
+    }
+    let close = format!("\"{}", "#".repeat(hashes));
+    Some(
+        rest[open + 1..]
+            .find(&close)
+            .map_or(rest.len(), |end| open + 1 + end + close.len()),
+    )
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comments(file: &str, content: &str) -> Vec<Vec<String>> {
+        lex(Path::new(file), content)
+            .into_iter()
+            .map(|l| l.comments)
+            .collect()
+    }
+
+    #[test]
+    fn test_strings_are_not_comments() {
+        let content =
+            "let a = \"// not\"; // yes\nlet b = r#\"/* \"no\" */\"#;\nlet c = '\"'; // c\n";
+        assert_eq!(
+            comments("a.rs", content),
+            vec![vec![" yes".to_string()], vec![], vec![" c".to_string()]]
+        );
+    }
+
+    #[test]
+    fn test_lifetimes_and_doc_comments() {
+        let content = "/// doc\nfn f<'a>(x: &'a str) {} // plain\n//! inner\n/** block doc */\n";
+        let lines = lex(Path::new("a.rs"), content);
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].comments.is_empty() && !lines[0].code);
+        assert_eq!(lines[1].comments, vec![" plain"]);
+        assert!(lines[1].code);
+        assert!(lines[2].comments.is_empty());
+        assert!(lines[3].comments.is_empty());
+    }
+
+    #[test]
+    fn test_block_comments_span_lines() {
+        let content = "x(); /* one\n   two /* nested */ still\n*/ y();\n";
+        let lines = lex(Path::new("a.rs"), content);
+        assert_eq!(lines[0].comments, vec![" one"]);
+        assert_eq!(lines[1].comments, vec!["   two /* nested */ still"]);
+        assert!(!lines[1].code);
+        assert!(lines[2].code);
+    }
+
+    #[test]
+    fn test_other_languages() {
+        let py = "s = \"# no\"\n'''\n# docstring\n'''\nx = 1  # yes\n";
+        assert_eq!(
+            comments("a.py", py),
+            vec![vec![], vec![], vec![], vec![], vec![" yes".to_string()]]
+        );
+        let html = "<p>// no</p>\n<!-- yes -->\n";
+        assert_eq!(
+            comments("a.html", html),
+            vec![vec![], vec![" yes ".to_string()]]
+        );
+    }
+}
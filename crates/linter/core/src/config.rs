@@ -32,6 +32,14 @@ pub struct LinterConfig {
     /// Rules configuration.
     #[serde(default)]
     pub rules: RulesConfig,
+
+    /// Metrics mode settings.
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    /// Inline suppression policy.
+    #[serde(default)]
+    pub suppressions: SuppressionConfig,
 }
 
 impl Default for LinterConfig {
@@ -41,6 +49,8 @@ impl Default for LinterConfig {
             autofix: AutofixConfig::default(),
             categories: HashMap::new(),
             rules: RulesConfig::default(),
+            metrics: MetricsConfig::default(),
+            suppressions: SuppressionConfig::default(),
         }
     }
 }
@@ -114,6 +124,107 @@ fn default_max_iterations() -> usize {
     10
 }
 
+/// Inline suppression policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuppressionConfig {
+    /// Honor `adi-lint:` suppression comments.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Report suppressions without an `owner`.
+    #[serde(default = "default_true")]
+    pub require_owner: bool,
+
+    /// Report suppressions without a `reason`.
+    #[serde(default)]
+    pub require_reason: bool,
+
+    /// Severity of expired or ownerless suppressions.
+    #[serde(default = "default_suppression_severity")]
+    pub severity: Severity,
+}
+
+impl Default for SuppressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            require_owner: true,
+            require_reason: false,
+            severity: default_suppression_severity(),
+        }
+    }
+}
+
+fn default_suppression_severity() -> Severity {
+    Severity::Error
+}
+
+/// Metrics mode configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Files to analyze.
+    #[serde(default = "default_metrics_glob")]
+    pub glob: GlobPatterns,
+
+    /// Minimum number of significant lines for a duplicate block (0 disables).
+    #[serde(default = "default_duplicate_min_lines")]
+    pub duplicate_min_lines: usize,
+
+    /// Thresholds that fail the run when exceeded.
+    #[serde(default)]
+    pub thresholds: MetricsThresholds,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            glob: default_metrics_glob(),
+            duplicate_min_lines: default_duplicate_min_lines(),
+            thresholds: MetricsThresholds::default(),
+        }
+    }
+}
+
+fn default_metrics_glob() -> GlobPatterns {
+    GlobPatterns::Multiple(
+        [
+            "rs", "ts", "tsx", "js", "jsx", "go", "py", "c", "cpp", "h", "hpp", "java", "cs",
+            "swift", "kt",
+        ]
+        .iter()
+        .map(|ext| format!("**/*.{}", ext))
+        .collect(),
+    )
+}
+
+fn default_duplicate_min_lines() -> usize {
+    6
+}
+
+/// Metric thresholds. Unset thresholds are reported but never fail.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsThresholds {
+    /// Maximum cyclomatic complexity per function.
+    #[serde(default)]
+    pub max_complexity: Option<u32>,
+
+    /// Maximum lines per function.
+    #[serde(default)]
+    pub max_function_lines: Option<usize>,
+
+    /// Maximum lines of code per file.
+    #[serde(default)]
+    pub max_file_lines: Option<usize>,
+
+    /// Maximum TODO markers per 1000 lines of code in a file.
+    #[serde(default)]
+    pub max_todo_density: Option<f64>,
+
+    /// Maximum number of duplicate blocks across the project.
+    #[serde(default)]
+    pub max_duplicate_blocks: Option<usize>,
+}
+
 /// Category configuration from file.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -430,6 +541,10 @@ pub struct GlobalLinterConfig {
     pub autofix: AutofixConfig,
     #[serde(default)]
     pub categories: HashMap<String, CategoryConfigFile>,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub suppressions: SuppressionConfig,
 }
 
 /// Individual rule file configuration.
@@ -667,6 +782,8 @@ impl LinterConfig {
             config.linter = global_config.linter;
             config.autofix = global_config.autofix;
             config.categories = global_config.categories;
+            config.metrics = global_config.metrics;
+            config.suppressions = global_config.suppressions;
         }
 
         // Load individual rule files
@@ -783,7 +900,8 @@ impl LinterConfig {
         let mut config = crate::runner::RunnerConfig::new(root)
             .parallel(self.linter.parallel)
             .fail_fast(self.linter.fail_fast)
-            .timeout(Duration::from_secs(self.linter.timeout));
+            .timeout(Duration::from_secs(self.linter.timeout))
+            .suppressions(self.suppressions.clone());
 
         if let Some(workers) = self.linter.max_workers {
             config = config.max_workers(workers);
@@ -847,6 +965,30 @@ style = false
         assert_eq!(config.autofix.max_iterations, 5);
     }
 
+    #[test]
+    fn test_parse_metrics_config() {
+        let toml = r#"
+[metrics]
+duplicate_min_lines = 8
+
+[metrics.thresholds]
+max_complexity = 15
+max_todo_density = 2.5
+"#;
+
+        let config: GlobalLinterConfig = toml::from_str(toml).unwrap();
+
+        assert_eq!(config.metrics.duplicate_min_lines, 8);
+        assert_eq!(config.metrics.thresholds.max_complexity, Some(15));
+        assert_eq!(config.metrics.thresholds.max_todo_density, Some(2.5));
+        assert_eq!(config.metrics.thresholds.max_file_lines, None);
+        assert!(config
+            .metrics
+            .glob
+            .to_vec()
+            .contains(&"**/*.rs".to_string()));
+    }
+
     #[test]
     fn test_parse_command_rule() {
         let toml = r#"
@@ -7,6 +7,10 @@
 //! - **Priority-based execution**: Higher priority linters run first
 //! - **Parallel execution**: Linters within same priority level run concurrently
 //! - **Autofix support**: Sequential fix application with full re-linting
+//! - **Inline suppressions**: `adi-lint: disable` comments with expiry and ownership
+//! - **Metrics mode**: Complexity, size, TODO density and duplication with CI thresholds
+//! - **Baseline**: Violations grouped by file and rule, diffed between runs
+//! - **Live diagnostics**: New diagnostics of periodic runs, with editor deep links
 //!
 //! # Example
 //!
@@ -37,22 +41,31 @@
 //! ```
 
 pub mod autofix;
+pub mod baseline;
+pub mod comments;
 pub mod config;
 pub mod files;
 pub mod linter;
+pub mod live;
+pub mod metrics;
 pub mod output;
 pub mod registry;
 pub mod runner;
+pub mod suppression;
 pub mod types;
 
 // Re-exports for convenience
 pub use autofix::{AutofixConfig, AutofixEngine, AutofixResult};
-pub use config::LinterConfig;
+pub use baseline::{Baseline, BaselineDiff, BaselineEntry};
+pub use config::{LinterConfig, MetricsConfig, MetricsThresholds, SuppressionConfig};
 pub use files::{FileIterator, FileIteratorBuilder};
 pub use linter::{LintContext, Linter};
+pub use live::{LiveDiagnostic, LiveDiagnostics};
+pub use metrics::{MetricsAnalyzer, MetricsReport};
 pub use output::{format_to_stdout, format_to_string, OutputFormat};
 pub use registry::{CategoryConfig, LinterRegistry, LinterRegistryBuilder};
 pub use runner::{LintResult, Runner, RunnerConfig};
+pub use suppression::Suppression;
 pub use types::{Category, Diagnostic, Fix, Location, Range, Severity, TextEdit};
 
 /// Run linting with default configuration.
@@ -0,0 +1,183 @@
+//! Live diagnostics for long-running hosts such as cocoon dev servers.
+//!
+//! [`LiveDiagnostics`] re-lints on an interval and reports only diagnostics
+//! that were not present in the previous run, each with an editor deep link.
+//! Hosts publish them as service events on [`LIVE_SERVICE`] so browser
+//! consoles can subscribe to them.
+
+use crate::runner::Runner;
+use crate::types::{Diagnostic, Severity};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Service name diagnostics are published under.
+pub const LIVE_SERVICE: &str = "linter";
+
+/// Event name of a single new diagnostic.
+pub const DIAGNOSTIC_EVENT: &str = "diagnostic";
+
+/// Editor link template; `{path}`, `{line}` and `{col}` are substituted.
+pub const DEFAULT_EDITOR_LINK: &str = "vscode://file{path}:{line}:{col}";
+
+/// A new diagnostic, flattened for consoles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiveDiagnostic {
+    /// File path relative to the project root.
+    pub file: PathBuf,
+    /// Line (1-based).
+    pub line: u32,
+    /// Column (1-based).
+    pub col: u32,
+    pub severity: Severity,
+    /// `<linter_id>/<rule_id>`.
+    pub rule: String,
+    pub message: String,
+    /// Opens the location in the editor.
+    pub link: String,
+}
+
+/// Tracks which diagnostics were already reported.
+pub struct LiveDiagnostics {
+    root: PathBuf,
+    editor_link: String,
+    seen: HashSet<String>,
+}
+
+impl LiveDiagnostics {
+    /// Report diagnostics of the project at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            editor_link: DEFAULT_EDITOR_LINK.to_string(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Use another editor link template, see [`DEFAULT_EDITOR_LINK`].
+    pub fn editor_link(mut self, template: impl Into<String>) -> Self {
+        self.editor_link = template.into();
+        self
+    }
+
+    /// Diagnostics of this run that were not in the previous one. A
+    /// diagnostic that is fixed and comes back is reported again.
+    pub fn update(&mut self, diagnostics: &[Diagnostic]) -> Vec<LiveDiagnostic> {
+        let current: Vec<LiveDiagnostic> = diagnostics.iter().map(|d| self.live(d)).collect();
+        let keys: HashSet<String> = current.iter().map(key).collect();
+
+        let new = current
+            .into_iter()
+            .filter(|d| !self.seen.contains(&key(d)))
+            .collect();
+        self.seen = keys;
+        new
+    }
+
+    /// Lint every `interval` and pass new diagnostics to `publish`. Failed
+    /// runs are logged and retried on the next tick.
+    pub async fn watch<F>(mut self, runner: Runner, interval: Duration, mut publish: F)
+    where
+        F: FnMut(LiveDiagnostic),
+    {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match runner.run(None).await {
+                Ok(result) => {
+                    for diag in self.update(&result.diagnostics) {
+                        publish(diag);
+                    }
+                }
+                Err(e) => tracing::warn!("Live lint run failed: {}", e),
+            }
+        }
+    }
+
+    fn live(&self, diag: &Diagnostic) -> LiveDiagnostic {
+        let file = diag
+            .location
+            .file
+            .strip_prefix(&self.root)
+            .unwrap_or(&diag.location.file)
+            .to_path_buf();
+        let line = diag.location.start_line;
+        let col = diag.location.start_col;
+        LiveDiagnostic {
+            link: self.link(&self.root.join(&file), line, col),
+            file,
+            line,
+            col,
+            severity: diag.severity,
+            rule: format!("{}/{}", diag.linter_id, diag.rule_id),
+            message: diag.message.clone(),
+        }
+    }
+
+    fn link(&self, path: &Path, line: u32, col: u32) -> String {
+        self.editor_link
+            .replace("{path}", &path.display().to_string())
+            .replace("{line}", &line.to_string())
+            .replace("{col}", &col.to_string())
+    }
+}
+
+fn key(diag: &LiveDiagnostic) -> String {
+    format!(
+        "{}:{}:{}:{}:{}",
+        diag.file.display(),
+        diag.line,
+        diag.col,
+        diag.rule,
+        diag.message
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Category, Location};
+
+    fn diag(file: &str, line: u32, rule: &str) -> Diagnostic {
+        Diagnostic::new(
+            rule,
+            "test",
+            Category::CodeQuality,
+            Severity::Warning,
+            "message",
+            Location::new(PathBuf::from(file), line, 3, line, 10),
+        )
+    }
+
+    #[test]
+    fn test_update_reports_only_new_diagnostics() {
+        let mut live = LiveDiagnostics::new("/project");
+
+        let first = live.update(&[diag("/project/src/a.rs", 1, "r1")]);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].file, PathBuf::from("src/a.rs"));
+        assert_eq!(first[0].rule, "test/r1");
+        assert_eq!(first[0].link, "vscode://file/project/src/a.rs:1:3");
+
+        let second = live.update(&[
+            diag("/project/src/a.rs", 1, "r1"),
+            diag("/project/src/b.rs", 7, "r2"),
+        ]);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].line, 7);
+
+        // Fixed, then reintroduced
+        assert!(live.update(&[]).is_empty());
+        assert_eq!(live.update(&[diag("/project/src/a.rs", 1, "r1")]).len(), 1);
+    }
+
+    #[test]
+    fn test_editor_link_template() {
+        let mut live =
+            LiveDiagnostics::new("/p").editor_link("idea://open?file={path}&line={line}");
+        let reported = live.update(&[diag("/p/x.rs", 4, "r")]);
+        assert_eq!(reported[0].link, "idea://open?file=/p/x.rs&line=4");
+    }
+}
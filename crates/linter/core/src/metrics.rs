@@ -0,0 +1,816 @@
+//! Code metrics - complexity, size, TODO density and duplication hints.
+//!
+//! Unlike rules, metrics never produce diagnostics on their own. They describe
+//! the codebase and only turn into failures when a configured threshold is
+//! exceeded, which makes them suitable as a CI gate.
+//!
+//! Complexity is a language-agnostic approximation of cyclomatic complexity:
+//! one plus the number of branching keywords and short-circuit operators found
+//! in a function body, with comments and string literals stripped first.
+
+use crate::config::MetricsConfig;
+use crate::files::FileIterator;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Metrics for a single function.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionMetrics {
+    /// Function name.
+    pub name: String,
+    /// Line of the signature (1-indexed).
+    pub line: u32,
+    /// Number of lines from signature to end of body.
+    pub lines: usize,
+    /// Approximate cyclomatic complexity.
+    pub complexity: u32,
+}
+
+/// Line counts of a file.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct LineCounts {
+    pub total: usize,
+    pub code: usize,
+    pub comment: usize,
+    pub blank: usize,
+}
+
+impl std::ops::AddAssign for LineCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.total += other.total;
+        self.code += other.code;
+        self.comment += other.comment;
+        self.blank += other.blank;
+    }
+}
+
+/// Metrics for a single file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMetrics {
+    /// File path, relative to the project root.
+    pub path: PathBuf,
+    /// Line counts.
+    pub lines: LineCounts,
+    /// Number of TODO/FIXME/XXX/HACK markers.
+    pub todos: usize,
+    /// TODO markers per 1000 lines of code.
+    pub todo_density: f64,
+    /// Detected functions.
+    pub functions: Vec<FunctionMetrics>,
+}
+
+impl FileMetrics {
+    /// Highest function complexity in the file (0 if there are no functions).
+    pub fn max_complexity(&self) -> u32 {
+        self.functions
+            .iter()
+            .map(|f| f.complexity)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A block of lines that appears more than once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateBlock {
+    /// Number of (non-blank, non-comment) lines in the block.
+    pub lines: usize,
+    /// Where the block occurs.
+    pub locations: Vec<DuplicateLocation>,
+}
+
+/// One occurrence of a duplicated block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateLocation {
+    pub path: PathBuf,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// A threshold that was exceeded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricViolation {
+    /// Metric name (matches the threshold key in config, e.g. `max_complexity`).
+    pub metric: String,
+    pub path: PathBuf,
+    /// Line of the offending function, if the metric is per-function.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    pub value: f64,
+    pub threshold: f64,
+}
+
+/// Totals across all analyzed files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsSummary {
+    pub files: usize,
+    pub functions: usize,
+    pub lines: LineCounts,
+    pub todos: usize,
+    pub todo_density: f64,
+    pub average_complexity: f64,
+    pub max_complexity: u32,
+    pub duplicate_blocks: usize,
+}
+
+/// Result of a metrics run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsReport {
+    pub summary: MetricsSummary,
+    pub files: Vec<FileMetrics>,
+    pub duplicates: Vec<DuplicateBlock>,
+    pub violations: Vec<MetricViolation>,
+}
+
+impl MetricsReport {
+    /// Check if any threshold was exceeded.
+    pub fn has_violations(&self) -> bool {
+        !self.violations.is_empty()
+    }
+
+    /// Write the report as JSON.
+    pub fn write_json<W: Write>(&self, w: &mut W) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(&mut *w, self)?;
+        writeln!(w)?;
+        Ok(())
+    }
+
+    /// Write a human-readable summary.
+    pub fn write_pretty<W: Write>(&self, w: &mut W, top: usize) -> anyhow::Result<()> {
+        let s = &self.summary;
+        writeln!(w, "📊 Code metrics")?;
+        writeln!(
+            w,
+            "   files: {}  functions: {}  lines: {} ({} code, {} comment, {} blank)",
+            s.files, s.functions, s.lines.total, s.lines.code, s.lines.comment, s.lines.blank
+        )?;
+        writeln!(
+            w,
+            "   complexity: avg {:.1}, max {}  todos: {} ({:.1}/kloc)  duplicate blocks: {}",
+            s.average_complexity, s.max_complexity, s.todos, s.todo_density, s.duplicate_blocks
+        )?;
+
+        let mut functions: Vec<_> = self
+            .files
+            .iter()
+            .flat_map(|f| f.functions.iter().map(move |func| (&f.path, func)))
+            .collect();
+        functions.sort_by_key(|(_, func)| std::cmp::Reverse(func.complexity));
+
+        if !functions.is_empty() && top > 0 {
+            writeln!(w, "\nMost complex functions:")?;
+            for (path, func) in functions.iter().take(top) {
+                writeln!(
+                    w,
+                    "   {:>4}  {}:{}  {} ({} lines)",
+                    func.complexity,
+                    path.display(),
+                    func.line,
+                    func.name,
+                    func.lines
+                )?;
+            }
+        }
+
+        if !self.duplicates.is_empty() && top > 0 {
+            writeln!(w, "\nDuplicate blocks:")?;
+            for block in self.duplicates.iter().take(top) {
+                let places: Vec<_> = block
+                    .locations
+                    .iter()
+                    .map(|l| format!("{}:{}-{}", l.path.display(), l.start_line, l.end_line))
+                    .collect();
+                writeln!(w, "   {} lines  {}", block.lines, places.join(", "))?;
+            }
+        }
+
+        if self.violations.is_empty() {
+            writeln!(w, "\n✓ All metrics within thresholds")?;
+        } else {
+            writeln!(w, "\n✗ {} threshold violation(s):", self.violations.len())?;
+            for v in &self.violations {
+                let location = match (v.line, &v.function) {
+                    (Some(line), Some(name)) => format!("{}:{} {}", v.path.display(), line, name),
+                    _ => v.path.display().to_string(),
+                };
+                writeln!(
+                    w,
+                    "   {}  {} = {} (max {})",
+                    location, v.metric, v.value, v.threshold
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes metrics for files in a project.
+pub struct MetricsAnalyzer {
+    root: PathBuf,
+    config: MetricsConfig,
+    branch_pattern: Regex,
+    fn_pattern: Regex,
+    def_pattern: Regex,
+    todo_pattern: Regex,
+}
+
+impl MetricsAnalyzer {
+    /// Create an analyzer rooted at the given path.
+    pub fn new(root: impl Into<PathBuf>, config: MetricsConfig) -> Self {
+        Self {
+            root: root.into(),
+            config,
+            branch_pattern: Regex::new(
+                r"\b(?:if|elif|for|while|loop|case|catch|except|guard)\b|&&|\|\||\band\b|\bor\b",
+            )
+            .expect("branch pattern must compile"),
+            fn_pattern: Regex::new(
+                r#"(?x)
+                ^\s*
+                (?:pub\s*(?:\(crate\)\s*)?|async\s+|const\s+|unsafe\s+|extern\s+(?:"C"\s+)?|export\s+|default\s+|static\s+|private\s+|protected\s+|public\s+|override\s+|virtual\s+)*
+                (?:fn|func|function|fun)\s+
+                (\w+)
+                "#,
+            )
+            .expect("function pattern must compile"),
+            def_pattern: Regex::new(r"^(\s*)(?:async\s+)?def\s+(\w+)")
+                .expect("python def pattern must compile"),
+            todo_pattern: Regex::new(r"\b(?:TODO|FIXME|XXX|HACK)\b")
+                .expect("todo pattern must compile"),
+        }
+    }
+
+    /// Analyze all files matching the configured patterns.
+    pub fn run(&self) -> anyhow::Result<MetricsReport> {
+        let files = FileIterator::new(&self.root)
+            .patterns(&self.config.glob.to_vec())?
+            .use_gitignore(true)
+            .use_adiignore(true)
+            .collect();
+
+        let mut sources = Vec::new();
+        for path in files {
+            // Binary and non-UTF-8 files have no meaningful metrics
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf();
+            sources.push((relative, content));
+        }
+
+        Ok(self.analyze(&sources))
+    }
+
+    /// Analyze in-memory sources given as `(path, content)` pairs.
+    pub fn analyze(&self, sources: &[(PathBuf, String)]) -> MetricsReport {
+        let files: Vec<FileMetrics> = sources
+            .iter()
+            .map(|(path, content)| self.file_metrics(path, content))
+            .collect();
+        let duplicates = find_duplicates(sources, self.config.duplicate_min_lines);
+
+        let mut summary = MetricsSummary {
+            files: files.len(),
+            duplicate_blocks: duplicates.len(),
+            ..Default::default()
+        };
+        let mut complexity_total = 0u64;
+        for file in &files {
+            summary.lines += file.lines;
+            summary.todos += file.todos;
+            summary.functions += file.functions.len();
+            summary.max_complexity = summary.max_complexity.max(file.max_complexity());
+            complexity_total += file
+                .functions
+                .iter()
+                .map(|f| f.complexity as u64)
+                .sum::<u64>();
+        }
+        summary.todo_density = per_kloc(summary.todos, summary.lines.code);
+        if summary.functions > 0 {
+            summary.average_complexity = complexity_total as f64 / summary.functions as f64;
+        }
+
+        let mut report = MetricsReport {
+            summary,
+            files,
+            duplicates,
+            violations: Vec::new(),
+        };
+        report.violations = self.check_thresholds(&report);
+        report
+    }
+
+    /// Compute metrics for a single file.
+    pub fn file_metrics(&self, path: &Path, content: &str) -> FileMetrics {
+        let lines: Vec<&str> = content.lines().collect();
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let hash_comments = uses_hash_comments(ext);
+
+        let mut counts = LineCounts {
+            total: lines.len(),
+            ..Default::default()
+        };
+        let mut todos = 0;
+        let mut in_block = false;
+        let mut code_lines = Vec::with_capacity(lines.len());
+        for line in &lines {
+            let trimmed = line.trim();
+            if self.todo_pattern.is_match(line) {
+                todos += 1;
+            }
+            let code = strip_line(trimmed, hash_comments, &mut in_block);
+            if trimmed.is_empty() {
+                counts.blank += 1;
+            } else if code.trim().is_empty() {
+                counts.comment += 1;
+            } else {
+                counts.code += 1;
+            }
+            code_lines.push(code);
+        }
+
+        let functions = if ext == "py" {
+            self.python_functions(&lines, &code_lines)
+        } else if is_brace_language(ext) {
+            self.brace_functions(&code_lines, ext == "rs")
+        } else {
+            Vec::new()
+        };
+
+        FileMetrics {
+            path: path.to_path_buf(),
+            lines: counts,
+            todos,
+            todo_density: per_kloc(todos, counts.code),
+            functions,
+        }
+    }
+
+    fn brace_functions(&self, code: &[String], rust: bool) -> Vec<FunctionMetrics> {
+        let mut functions = Vec::new();
+        let mut i = 0;
+        while i < code.len() {
+            let Some(caps) = self.fn_pattern.captures(&code[i]) else {
+                i += 1;
+                continue;
+            };
+            let name = caps.get(1).map(|m| m.as_str()).unwrap_or("unknown");
+
+            // Find the opening brace; a `;` first means a declaration without body
+            let mut open = None;
+            for (j, line) in code.iter().enumerate().skip(i).take(5) {
+                if line.contains('{') {
+                    open = Some(j);
+                    break;
+                }
+                if line.trim_end().ends_with(';') {
+                    break;
+                }
+            }
+            let Some(open) = open else {
+                i += 1;
+                continue;
+            };
+
+            let mut depth = 0i32;
+            let mut end = code.len() - 1;
+            for (j, line) in code.iter().enumerate().skip(open) {
+                for ch in line.chars() {
+                    match ch {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                }
+                if depth <= 0 {
+                    end = j;
+                    break;
+                }
+            }
+
+            functions.push(FunctionMetrics {
+                name: name.to_string(),
+                line: i as u32 + 1,
+                lines: end - i + 1,
+                complexity: self.complexity(&code[i..=end], rust),
+            });
+            i = end + 1;
+        }
+        functions
+    }
+
+    fn python_functions(&self, lines: &[&str], code: &[String]) -> Vec<FunctionMetrics> {
+        let mut functions = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let Some(caps) = self.def_pattern.captures(lines[i]) else {
+                i += 1;
+                continue;
+            };
+            let indent = caps.get(1).map(|m| m.as_str().len()).unwrap_or(0);
+            let name = caps.get(2).map(|m| m.as_str()).unwrap_or("unknown");
+
+            let mut end = i;
+            for (j, line) in lines.iter().enumerate().skip(i + 1) {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if line.len() - line.trim_start().len() <= indent {
+                    break;
+                }
+                end = j;
+            }
+
+            functions.push(FunctionMetrics {
+                name: name.to_string(),
+                line: i as u32 + 1,
+                lines: end - i + 1,
+                complexity: self.complexity(&code[i..=end], false),
+            });
+            i = end + 1;
+        }
+        functions
+    }
+
+    fn complexity(&self, body: &[String], rust: bool) -> u32 {
+        let mut points = 0usize;
+        let mut arms = 0usize;
+        let mut matches = 0usize;
+        for line in body {
+            points += self.branch_pattern.find_iter(line).count();
+            if rust {
+                arms += line.matches("=>").count();
+                matches += count_word(line, "match");
+            }
+        }
+        // Each match arm is a branch; a match with n arms adds n - 1
+        1 + (points + arms.saturating_sub(matches)) as u32
+    }
+
+    fn check_thresholds(&self, report: &MetricsReport) -> Vec<MetricViolation> {
+        let t = &self.config.thresholds;
+        let mut violations = Vec::new();
+
+        for file in &report.files {
+            let mut push = |metric: &str, func: Option<&FunctionMetrics>, value: f64, max: f64| {
+                violations.push(MetricViolation {
+                    metric: metric.to_string(),
+                    path: file.path.clone(),
+                    line: func.map(|f| f.line),
+                    function: func.map(|f| f.name.clone()),
+                    value,
+                    threshold: max,
+                });
+            };
+
+            for func in &file.functions {
+                if let Some(max) = t.max_complexity {
+                    if func.complexity > max {
+                        push(
+                            "max_complexity",
+                            Some(func),
+                            func.complexity as f64,
+                            max as f64,
+                        );
+                    }
+                }
+                if let Some(max) = t.max_function_lines {
+                    if func.lines > max {
+                        push(
+                            "max_function_lines",
+                            Some(func),
+                            func.lines as f64,
+                            max as f64,
+                        );
+                    }
+                }
+            }
+            if let Some(max) = t.max_file_lines {
+                if file.lines.code > max {
+                    push("max_file_lines", None, file.lines.code as f64, max as f64);
+                }
+            }
+            if let Some(max) = t.max_todo_density {
+                if file.todo_density > max {
+                    push("max_todo_density", None, round(file.todo_density), max);
+                }
+            }
+        }
+
+        if let Some(max) = t.max_duplicate_blocks {
+            if report.duplicates.len() > max {
+                violations.push(MetricViolation {
+                    metric: "max_duplicate_blocks".to_string(),
+                    path: PathBuf::from("."),
+                    line: None,
+                    function: None,
+                    value: report.duplicates.len() as f64,
+                    threshold: max as f64,
+                });
+            }
+        }
+
+        violations
+    }
+}
+
+/// Compute metrics for a project using its `.adi/linters/config.toml`.
+pub fn analyze_project(root: &Path) -> anyhow::Result<MetricsReport> {
+    let config = crate::config::LinterConfig::load_from_project(root)?;
+    MetricsAnalyzer::new(root, config.metrics).run()
+}
+
+fn find_duplicates(sources: &[(PathBuf, String)], min_lines: usize) -> Vec<DuplicateBlock> {
+    if min_lines == 0 {
+        return Vec::new();
+    }
+
+    // Normalized significant lines per file, keeping the original line number
+    let files: Vec<Vec<(u32, &str)>> = sources
+        .iter()
+        .map(|(path, content)| {
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            let comment = if uses_hash_comments(ext) { "#" } else { "//" };
+            content
+                .lines()
+                .enumerate()
+                .map(|(i, l)| (i as u32 + 1, l.trim()))
+                .filter(|(_, l)| {
+                    !l.starts_with(comment) && l.chars().filter(|c| c.is_alphanumeric()).count() > 2
+                })
+                .collect()
+        })
+        .collect();
+
+    let window_hash = |file: usize, start: usize| -> Option<u64> {
+        let lines = files[file].get(start..start + min_lines)?;
+        let mut hasher = DefaultHasher::new();
+        for (_, line) in lines {
+            line.hash(&mut hasher);
+        }
+        Some(hasher.finish())
+    };
+
+    let mut groups: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+    for (f, lines) in files.iter().enumerate() {
+        for start in 0..lines.len().saturating_sub(min_lines - 1) {
+            if let Some(h) = window_hash(f, start) {
+                let entry = groups.entry(h).or_default();
+                // Overlapping windows of the same file are repetition, not duplication
+                if entry
+                    .last()
+                    .is_some_and(|&(lf, ls)| lf == f && start < ls + min_lines)
+                {
+                    continue;
+                }
+                entry.push((f, start));
+            }
+        }
+    }
+
+    let mut blocks = Vec::new();
+    for locations in groups.values().filter(|l| l.len() > 1) {
+        // Windows that continue an earlier duplicate are folded into it
+        let continues = |offset: isize| {
+            let hashes: Vec<_> = locations
+                .iter()
+                .map(|&(f, s)| {
+                    s.checked_add_signed(offset)
+                        .and_then(|start| window_hash(f, start))
+                })
+                .collect();
+            hashes[0].is_some() && hashes.iter().all(|h| *h == hashes[0])
+        };
+        if continues(-1) {
+            continue;
+        }
+        let mut extra = 0;
+        while continues(extra as isize + 1) {
+            extra += 1;
+        }
+
+        let lines = min_lines + extra;
+        blocks.push(DuplicateBlock {
+            lines,
+            locations: locations
+                .iter()
+                .map(|&(f, s)| DuplicateLocation {
+                    path: sources[f].0.clone(),
+                    start_line: files[f][s].0,
+                    end_line: files[f][s + lines - 1].0,
+                })
+                .collect(),
+        });
+    }
+
+    blocks.sort_by(|a, b| {
+        b.lines.cmp(&a.lines).then_with(|| {
+            let key = |d: &DuplicateBlock| (d.locations[0].path.clone(), d.locations[0].start_line);
+            key(a).cmp(&key(b))
+        })
+    });
+    blocks
+}
+
+/// Remove comments and string literal contents from a line of code.
+fn strip_line(line: &str, hash_comments: bool, in_block: &mut bool) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        if *in_block {
+            if c == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                *in_block = false;
+            }
+            continue;
+        }
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+                out.push(c);
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' | '`' => {
+                // Rust lifetimes and char-like apostrophes are left alone
+                if c == '\'' && !hash_comments && chars.clone().nth(1) != Some('\'') {
+                    out.push(c);
+                    continue;
+                }
+                quote = Some(c);
+                out.push(c);
+            }
+            '#' if hash_comments => break,
+            '/' if !hash_comments && chars.peek() == Some(&'/') => break,
+            '/' if !hash_comments && chars.peek() == Some(&'*') => {
+                chars.next();
+                *in_block = true;
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn count_word(line: &str, word: &str) -> usize {
+    line.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| *w == word)
+        .count()
+}
+
+fn uses_hash_comments(ext: &str) -> bool {
+    matches!(
+        ext,
+        "py" | "rb" | "sh" | "bash" | "zsh" | "toml" | "yaml" | "yml"
+    )
+}
+
+fn is_brace_language(ext: &str) -> bool {
+    matches!(
+        ext,
+        "rs" | "ts"
+            | "js"
+            | "tsx"
+            | "jsx"
+            | "go"
+            | "c"
+            | "cpp"
+            | "h"
+            | "hpp"
+            | "java"
+            | "cs"
+            | "swift"
+            | "kt"
+    )
+}
+
+fn per_kloc(count: usize, lines: usize) -> f64 {
+    if lines == 0 {
+        0.0
+    } else {
+        count as f64 * 1000.0 / lines as f64
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MetricsThresholds;
+
+    fn analyzer(thresholds: MetricsThresholds) -> MetricsAnalyzer {
+        MetricsAnalyzer::new(
+            ".",
+            MetricsConfig {
+                thresholds,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_rust_complexity() {
+        let source = r#"
+fn simple() -> u32 {
+    1
+}
+
+pub fn branchy(x: u32) -> u32 {
+    // if this were a comment it would not count
+    if x > 1 && x < 10 {
+        return 1;
+    }
+    let s = "while in a string";
+    match x {
+        0 => 0,
+        1 => 1,
+        _ => 2,
+    }
+}
+"#;
+        let metrics = analyzer(Default::default()).file_metrics(Path::new("a.rs"), source);
+
+        assert_eq!(metrics.functions.len(), 2);
+        assert_eq!(metrics.functions[0].name, "simple");
+        assert_eq!(metrics.functions[0].complexity, 1);
+        assert_eq!(metrics.functions[1].name, "branchy");
+        assert_eq!(metrics.functions[1].line, 6);
+        // if + && + 3 arms - 1 match
+        assert_eq!(metrics.functions[1].complexity, 5);
+        assert_eq!(metrics.lines.comment, 1);
+        assert_eq!(metrics.lines.blank, 2);
+    }
+
+    #[test]
+    fn test_python_functions_and_todos() {
+        let source = "def f(x):\n    # TODO: handle None\n    if x and x > 1:\n        return 1\n    return 0\n\ndef g():\n    pass\n";
+        let metrics = analyzer(Default::default()).file_metrics(Path::new("a.py"), source);
+
+        assert_eq!(metrics.functions.len(), 2);
+        assert_eq!(metrics.functions[0].complexity, 3);
+        assert_eq!(metrics.functions[0].lines, 5);
+        assert_eq!(metrics.todos, 1);
+        assert_eq!(metrics.lines.code, 6);
+    }
+
+    #[test]
+    fn test_duplicates_are_merged() {
+        let block = "let alpha = compute(1);\nlet beta = compute(2);\nlet gamma = compute(3);\nlet delta = compute(4);\n";
+        let a = format!("fn a() {{\n{block}}}\n");
+        let b = format!("fn b() {{\n\n{block}}}\n");
+        let sources = vec![(PathBuf::from("a.rs"), a), (PathBuf::from("b.rs"), b)];
+
+        let config = MetricsConfig {
+            duplicate_min_lines: 3,
+            ..Default::default()
+        };
+        let report = MetricsAnalyzer::new(".", config).analyze(&sources);
+
+        assert_eq!(report.duplicates.len(), 1);
+        let dup = &report.duplicates[0];
+        assert_eq!(dup.lines, 4);
+        assert_eq!(dup.locations[0].start_line, 2);
+        assert_eq!(dup.locations[1].start_line, 3);
+        assert_eq!(dup.locations[1].end_line, 6);
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let source = "fn f(a: bool, b: bool) {\n    if a || b {}\n    while a {}\n}\n";
+        let sources = vec![(PathBuf::from("a.rs"), source.to_string())];
+
+        let report = analyzer(MetricsThresholds {
+            max_complexity: Some(3),
+            ..Default::default()
+        })
+        .analyze(&sources);
+        assert!(report.has_violations());
+        assert_eq!(report.violations[0].metric, "max_complexity");
+        assert_eq!(report.violations[0].value, 4.0);
+        assert_eq!(report.violations[0].function.as_deref(), Some("f"));
+
+        let report = analyzer(MetricsThresholds {
+            max_complexity: Some(4),
+            ..Default::default()
+        })
+        .analyze(&sources);
+        assert!(!report.has_violations());
+    }
+}
@@ -181,6 +181,8 @@ pub struct JsonSummary {
     pub by_category: HashMap<String, usize>,
     /// Fixable count.
     pub fixable: usize,
+    /// Diagnostics silenced by suppression comments.
+    pub suppressed: usize,
     /// Files checked.
     pub files_checked: usize,
     /// Duration in milliseconds.
@@ -203,6 +205,7 @@ impl JsonSummary {
                 .map(|(k, v)| (k.clone(), v.total))
                 .collect(),
             fixable: result.fixable_count(),
+            suppressed: result.suppressed,
             files_checked: result.files_checked,
             duration_ms: result.duration.as_millis() as u64,
         }
@@ -260,6 +263,7 @@ mod tests {
             files_checked: 5,
             duration: Duration::from_millis(150),
             errors: vec![],
+            suppressed: 0,
             by_category,
             by_severity,
         }
@@ -229,11 +229,15 @@ impl PrettyFormatter {
         }
 
         // Duration and files
-        writeln!(
+        write!(
             w,
-            "{dim}Checked {} files in {:?}{reset}",
+            "{dim}Checked {} files in {:?}",
             result.files_checked, result.duration
         )?;
+        if result.suppressed > 0 {
+            write!(w, ", {} suppressed", result.suppressed)?;
+        }
+        writeln!(w, "{reset}")?;
 
         // Errors during linting
         if !result.errors.is_empty() {
@@ -368,6 +372,7 @@ mod tests {
             files_checked: 5,
             duration: Duration::from_millis(150),
             errors: vec![],
+            suppressed: 0,
             by_category,
             by_severity,
         }
@@ -400,6 +405,7 @@ mod tests {
             files_checked: 10,
             duration: Duration::from_millis(50),
             errors: vec![],
+            suppressed: 0,
             by_category: HashMap::new(),
             by_severity: HashMap::new(),
         };
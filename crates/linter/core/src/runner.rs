@@ -1,8 +1,10 @@
 //! Lint runner - orchestrates parallel linting execution.
 
+use crate::config::SuppressionConfig;
 use crate::files::FileIterator;
 use crate::linter::{LintContext, Linter};
 use crate::registry::LinterRegistry;
+use crate::suppression;
 use crate::types::{Diagnostic, LintScope, Severity};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -23,6 +25,8 @@ pub struct RunnerConfig {
     pub fail_fast: bool,
     /// Timeout per linter (per file).
     pub timeout: Duration,
+    /// Inline suppression policy.
+    pub suppressions: SuppressionConfig,
 }
 
 impl Default for RunnerConfig {
@@ -33,6 +37,7 @@ impl Default for RunnerConfig {
             max_workers: num_cpus::get(),
             fail_fast: false,
             timeout: Duration::from_secs(30),
+            suppressions: SuppressionConfig::default(),
         }
     }
 }
@@ -69,6 +74,12 @@ impl RunnerConfig {
         self.timeout = timeout;
         self
     }
+
+    /// Set suppression policy.
+    pub fn suppressions(mut self, config: SuppressionConfig) -> Self {
+        self.suppressions = config;
+        self
+    }
 }
 
 /// Result of a lint run.
@@ -82,6 +93,8 @@ pub struct LintResult {
     pub duration: Duration,
     /// Errors that occurred during linting (not lint issues).
     pub errors: Vec<LintError>,
+    /// Number of diagnostics silenced by suppression comments.
+    pub suppressed: usize,
     /// Per-category summary.
     pub by_category: HashMap<String, CategorySummary>,
     /// Per-severity summary.
@@ -190,9 +203,25 @@ impl Runner {
             }
         }
 
-        // Deduplicate diagnostics
+        // Deduplicate first so each silenced finding is counted once
         all_diagnostics = deduplicate_diagnostics(all_diagnostics);
 
+        // Apply inline suppressions and report the ones that violate policy
+        let mut suppressed = 0;
+        if self.config.suppressions.enabled {
+            let (suppressions, invalid) = suppression::scan_files(&files);
+            let (remaining, count) = suppression::apply(
+                all_diagnostics,
+                &suppressions,
+                &self.config.suppressions,
+                suppression::today(),
+            );
+            all_diagnostics = remaining;
+            all_diagnostics.extend(invalid);
+            sort_diagnostics(&mut all_diagnostics);
+            suppressed = count;
+        }
+
         // Build summaries
         let by_category = build_category_summary(&all_diagnostics);
         let by_severity = build_severity_summary(&all_diagnostics);
@@ -202,6 +231,7 @@ impl Runner {
             files_checked,
             duration: start.elapsed(),
             errors: all_errors,
+            suppressed,
             by_category,
             by_severity,
         })
@@ -371,8 +401,8 @@ fn has_errors(diagnostics: &[Diagnostic]) -> bool {
     diagnostics.iter().any(|d| d.severity == Severity::Error)
 }
 
-fn deduplicate_diagnostics(mut diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
-    // Sort by file, line, col, rule_id for stable deduplication
+/// Sort by file, line, col, rule_id.
+fn sort_diagnostics(diagnostics: &mut [Diagnostic]) {
     diagnostics.sort_by(|a, b| {
         a.location
             .file
@@ -381,6 +411,11 @@ fn deduplicate_diagnostics(mut diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic>
             .then_with(|| a.location.start_col.cmp(&b.location.start_col))
             .then_with(|| a.rule_id.cmp(&b.rule_id))
     });
+}
+
+fn deduplicate_diagnostics(mut diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    // Sorted for stable deduplication
+    sort_diagnostics(&mut diagnostics);
 
     // Remove duplicates (same file, line, col, rule)
     diagnostics.dedup_by(|a, b| {
@@ -485,6 +520,27 @@ mod tests {
         assert_eq!(result.diagnostics.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_suppressions_count_deduplicated() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("a.rs"),
+            "// adi-lint: disable test-linter owner=alice\n// TODO\n",
+        )
+        .unwrap();
+
+        // The same finding reported twice is one silenced diagnostic
+        let mut registry = LinterRegistry::new();
+        registry.register(create_test_linter());
+        registry.register(create_test_linter());
+
+        let runner = Runner::new(registry, RunnerConfig::new(dir.path()));
+        let result = runner.run(None).await.unwrap();
+
+        assert!(result.diagnostics.is_empty());
+        assert_eq!(result.suppressed, 1);
+    }
+
     #[test]
     fn test_deduplication() {
         use crate::types::Location;
@@ -0,0 +1,486 @@
+//! Inline suppression comments.
+//!
+//! A suppression silences diagnostics for one line or a whole file:
+//!
+//! ```text
+//! let x = y.unwrap(); // adi-lint: disable no-unwrap reason="checked above" until=2025-09-01 owner=alice
+//!
+//! // adi-lint: disable no-todo,max-line-length owner=bob
+//! let long_line = "...";
+//!
+//! // adi-lint: disable-file no-unwrap reason="generated code" owner=platform
+//! ```
+//!
+//! `disable` on a line with code applies to that line; on a comment-only line
+//! it applies to the next line. Rules are matched against the diagnostic rule ID
+//! or linter ID, and `all` matches everything.
+//!
+//! Only a plain comment that starts with the marker is a directive. The marker
+//! inside a string literal, a documentation comment or the middle of a comment
+//! is left alone (see [`crate::comments`]).
+//!
+//! Suppressions are debt with an owner: an expired suppression stops working and
+//! is reported, and a suppression without an owner (or reason, if required) is a
+//! violation of its own.
+
+use crate::comments;
+use crate::config::SuppressionConfig;
+use crate::types::{Category, Diagnostic, Location, Severity};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Marker that starts a suppression directive inside a comment.
+pub const MARKER: &str = "adi-lint:";
+
+/// Linter ID used for diagnostics about suppressions themselves.
+pub const LINTER_ID: &str = "adi-lint";
+
+/// Rule ID: suppression is past its `until` date.
+pub const RULE_EXPIRED: &str = "expired-suppression";
+/// Rule ID: suppression has no `owner`.
+pub const RULE_MISSING_OWNER: &str = "suppression-missing-owner";
+/// Rule ID: suppression has no `reason` (only when required by config).
+pub const RULE_MISSING_REASON: &str = "suppression-missing-reason";
+/// Rule ID: suppression directive could not be parsed.
+pub const RULE_INVALID: &str = "invalid-suppression";
+
+/// What a suppression applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "line", rename_all = "lowercase")]
+pub enum SuppressionScope {
+    /// A single line (1-indexed).
+    Line(u32),
+    /// The whole file.
+    File,
+}
+
+/// A parsed suppression comment.
+#[derive(Debug, Clone, Serialize)]
+pub struct Suppression {
+    /// File containing the comment.
+    pub file: PathBuf,
+    /// Line of the comment itself (1-indexed).
+    pub line: u32,
+    /// Lines covered.
+    pub scope: SuppressionScope,
+    /// Suppressed rule or linter IDs.
+    pub rules: Vec<String>,
+    /// Why the suppression exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Last day the suppression is honored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+    /// Who is responsible for removing it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+impl Suppression {
+    /// Check if the suppression is past its `until` date.
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        self.until.is_some_and(|until| today > until)
+    }
+
+    /// Check if this suppression silences the given diagnostic.
+    pub fn covers(&self, diag: &Diagnostic) -> bool {
+        if diag.location.file != self.file || diag.linter_id == LINTER_ID {
+            return false;
+        }
+        let in_scope = match self.scope {
+            SuppressionScope::File => true,
+            SuppressionScope::Line(line) => diag.location.start_line == line,
+        };
+        in_scope
+            && self
+                .rules
+                .iter()
+                .any(|r| r == "all" || *r == diag.rule_id || *r == diag.linter_id)
+    }
+
+    fn diagnostic(&self, rule_id: &str, severity: Severity, message: String) -> Diagnostic {
+        Diagnostic::new(
+            rule_id,
+            LINTER_ID,
+            Category::BestPractices,
+            severity,
+            message,
+            Location::line(self.file.clone(), self.line),
+        )
+    }
+}
+
+/// Parse all suppression comments in a file.
+///
+/// Returns the valid suppressions and diagnostics for directives that could not be parsed.
+pub fn parse_file(file: &Path, content: &str) -> (Vec<Suppression>, Vec<Diagnostic>) {
+    let lines = comments::lex(file, content);
+    let directives: Vec<Option<&str>> = lines
+        .iter()
+        .map(|line| line.comments.iter().find_map(|c| directive_of(c)))
+        .collect();
+    let mut suppressions = Vec::new();
+    let mut invalid = Vec::new();
+
+    for (idx, directive) in directives.iter().enumerate() {
+        let Some(directive) = directive else {
+            continue;
+        };
+        let line_num = idx as u32 + 1;
+        // A directive on a line without code applies to the next one
+        let standalone = !lines[idx].code;
+
+        match parse_directive(directive) {
+            Ok((kind, fields)) => {
+                let scope = match kind {
+                    DirectiveKind::File => SuppressionScope::File,
+                    DirectiveKind::NextLine => {
+                        SuppressionScope::Line(next_code_line(content, &directives, idx))
+                    }
+                    DirectiveKind::Line if standalone => {
+                        SuppressionScope::Line(next_code_line(content, &directives, idx))
+                    }
+                    DirectiveKind::Line => SuppressionScope::Line(line_num),
+                };
+                suppressions.push(Suppression {
+                    file: file.to_path_buf(),
+                    line: line_num,
+                    scope,
+                    rules: fields.rules,
+                    reason: fields.reason,
+                    until: fields.until,
+                    owner: fields.owner,
+                });
+            }
+            Err(e) => invalid.push(Diagnostic::new(
+                RULE_INVALID,
+                LINTER_ID,
+                Category::BestPractices,
+                Severity::Error,
+                format!("Invalid suppression: {}", e),
+                Location::line(file.to_path_buf(), line_num),
+            )),
+        }
+    }
+
+    (suppressions, invalid)
+}
+
+/// Apply suppressions to diagnostics.
+///
+/// Returns the remaining diagnostics (including policy violations of the suppressions
+/// themselves) and the number of diagnostics that were silenced.
+pub fn apply(
+    diagnostics: Vec<Diagnostic>,
+    suppressions: &[Suppression],
+    config: &SuppressionConfig,
+    today: NaiveDate,
+) -> (Vec<Diagnostic>, usize) {
+    let active: Vec<&Suppression> = suppressions
+        .iter()
+        .filter(|s| !s.is_expired(today))
+        .collect();
+
+    let before = diagnostics.len();
+    let mut remaining: Vec<Diagnostic> = diagnostics
+        .into_iter()
+        .filter(|d| !active.iter().any(|s| s.covers(d)))
+        .collect();
+    let suppressed = before - remaining.len();
+
+    remaining.extend(
+        suppressions
+            .iter()
+            .flat_map(|s| violations(s, config, today)),
+    );
+    (remaining, suppressed)
+}
+
+/// Policy violations of a single suppression.
+pub fn violations(
+    suppression: &Suppression,
+    config: &SuppressionConfig,
+    today: NaiveDate,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let rules = suppression.rules.join(", ");
+
+    if let Some(until) = suppression.until.filter(|_| suppression.is_expired(today)) {
+        diagnostics.push(suppression.diagnostic(
+            RULE_EXPIRED,
+            config.severity,
+            format!("Suppression of {} expired on {}", rules, until),
+        ));
+    }
+    if config.require_owner && suppression.owner.is_none() {
+        diagnostics.push(suppression.diagnostic(
+            RULE_MISSING_OWNER,
+            config.severity,
+            format!("Suppression of {} has no owner", rules),
+        ));
+    }
+    if config.require_reason && suppression.reason.is_none() {
+        diagnostics.push(suppression.diagnostic(
+            RULE_MISSING_REASON,
+            config.severity,
+            format!("Suppression of {} has no reason", rules),
+        ));
+    }
+
+    diagnostics
+}
+
+/// Current local date, used to evaluate `until`.
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+/// Read and parse suppressions from a set of files, skipping unreadable ones.
+pub fn scan_files(files: &[PathBuf]) -> (Vec<Suppression>, Vec<Diagnostic>) {
+    let mut suppressions = Vec::new();
+    let mut invalid = Vec::new();
+    for file in files {
+        let Ok(content) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        if !content.contains(MARKER) {
+            continue;
+        }
+        let (found, errors) = parse_file(file, &content);
+        suppressions.extend(found);
+        invalid.extend(errors);
+    }
+    (suppressions, invalid)
+}
+
+/// Age of a suppression comment in days, from `git blame`.
+///
+/// Returns `None` outside a git repository or for uncommitted lines.
+pub fn age_days(root: &Path, suppression: &Suppression, today: NaiveDate) -> Option<i64> {
+    let output = Command::new("git")
+        .arg("blame")
+        .arg("--porcelain")
+        .arg("-L")
+        .arg(format!("{0},{0}", suppression.line))
+        .arg("--")
+        .arg(&suppression.file)
+        .current_dir(root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Uncommitted lines are attributed to the all-zero commit
+    if stdout.starts_with("0000000000000000000000000000000000000000") {
+        return None;
+    }
+    let timestamp: i64 = stdout
+        .lines()
+        .find_map(|l| l.strip_prefix("author-time "))?
+        .trim()
+        .parse()
+        .ok()?;
+    let date = chrono::DateTime::from_timestamp(timestamp, 0)?.date_naive();
+    Some((today - date).num_days())
+}
+
+enum DirectiveKind {
+    Line,
+    NextLine,
+    File,
+}
+
+#[derive(Default)]
+struct DirectiveFields {
+    rules: Vec<String>,
+    reason: Option<String>,
+    until: Option<NaiveDate>,
+    owner: Option<String>,
+}
+
+fn parse_directive(directive: &str) -> Result<(DirectiveKind, DirectiveFields), String> {
+    let tokens = tokenize(directive)?;
+    let mut tokens = tokens.into_iter();
+
+    let kind = match tokens.next().as_deref() {
+        Some("disable") => DirectiveKind::Line,
+        Some("disable-next-line") => DirectiveKind::NextLine,
+        Some("disable-file") => DirectiveKind::File,
+        Some(other) => return Err(format!("unknown directive '{}'", other)),
+        None => return Err("missing directive".to_string()),
+    };
+
+    let mut fields = DirectiveFields::default();
+    for token in tokens {
+        match token.split_once('=') {
+            Some(("reason", value)) => fields.reason = Some(value.to_string()),
+            Some(("owner", value)) => fields.owner = Some(value.to_string()),
+            Some(("until", value)) => {
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD", value))?;
+                fields.until = Some(date);
+            }
+            Some((key, _)) => return Err(format!("unknown field '{}'", key)),
+            None => fields.rules.extend(
+                token
+                    .split(',')
+                    .filter(|r| !r.is_empty())
+                    .map(str::to_string),
+            ),
+        }
+    }
+
+    if fields.rules.is_empty() {
+        return Err("no rules given (use 'all' to suppress everything)".to_string());
+    }
+    Ok((kind, fields))
+}
+
+/// Split on whitespace, keeping double-quoted values (`reason="a b"`) together.
+fn tokenize(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quote".to_string());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// Directive text of a comment that starts with the marker (block comment
+/// continuation lines may lead with `*`).
+fn directive_of(comment: &str) -> Option<&str> {
+    comment
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '*')
+        .strip_prefix(MARKER)
+        .map(str::trim)
+}
+
+/// First line after `idx` that is neither blank nor another suppression comment.
+fn next_code_line(content: &str, directives: &[Option<&str>], idx: usize) -> u32 {
+    content
+        .lines()
+        .enumerate()
+        .skip(idx + 1)
+        .find(|(i, l)| !l.trim().is_empty() && directives.get(*i).is_none_or(Option::is_none))
+        .map(|(i, _)| i as u32 + 1)
+        .unwrap_or(idx as u32 + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn diag(rule: &str, line: u32) -> Diagnostic {
+        Diagnostic::new(
+            rule,
+            "test-linter",
+            Category::CodeQuality,
+            Severity::Warning,
+            "issue",
+            Location::line(PathBuf::from("a.rs"), line),
+        )
+    }
+
+    #[test]
+    fn test_parse_scopes() {
+        let content = r#"let a = 1; // adi-lint: disable no-unwrap reason="checked above" until=2025-09-01 owner=alice
+// adi-lint: disable no-todo,max-line-length owner=bob
+
+let b = 2;
+/* adi-lint: disable-file all owner=platform */
+"#;
+        let (found, invalid) = parse_file(Path::new("a.rs"), content);
+
+        assert!(invalid.is_empty());
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].scope, SuppressionScope::Line(1));
+        assert_eq!(found[0].reason.as_deref(), Some("checked above"));
+        assert_eq!(found[0].until, Some(date("2025-09-01")));
+        assert_eq!(found[0].owner.as_deref(), Some("alice"));
+        assert_eq!(found[1].scope, SuppressionScope::Line(4));
+        assert_eq!(found[1].rules, vec!["no-todo", "max-line-length"]);
+        assert_eq!(found[2].scope, SuppressionScope::File);
+    }
+
+    #[test]
+    fn test_only_comment_tokens() {
+        let content = r#"let s = "// adi-lint: disable all owner=a";
+/// adi-lint: disable no-todo owner=a
+// see adi-lint: disable for details
+x(); /* adi-lint: disable no-unwrap owner=a */
+/* note */ y(); // adi-lint: disable no-todo owner=a
+"#;
+        let (found, invalid) = parse_file(Path::new("a.rs"), content);
+
+        assert!(invalid.is_empty());
+        let scopes: Vec<_> = found.iter().map(|s| s.scope).collect();
+        assert_eq!(
+            scopes,
+            vec![SuppressionScope::Line(4), SuppressionScope::Line(5)]
+        );
+    }
+
+    #[test]
+    fn test_invalid_directive() {
+        let content = "// adi-lint: disable no-todo until=soon owner=a\n// adi-lint: disable\n";
+        let (found, invalid) = parse_file(Path::new("a.rs"), content);
+
+        assert!(found.is_empty());
+        assert_eq!(invalid.len(), 2);
+        assert_eq!(invalid[0].rule_id, RULE_INVALID);
+        assert_eq!(invalid[1].location.start_line, 2);
+    }
+
+    #[test]
+    fn test_apply_suppresses_and_reports() {
+        let content = "x(); // adi-lint: disable no-todo owner=alice until=2025-09-01\ny(); // adi-lint: disable no-todo\n";
+        let (found, _) = parse_file(Path::new("a.rs"), content);
+        let config = SuppressionConfig::default();
+
+        // Before expiry: both silenced, the ownerless one is reported
+        let (remaining, suppressed) = apply(
+            vec![diag("no-todo", 1), diag("no-todo", 2), diag("other", 2)],
+            &found,
+            &config,
+            date("2025-09-01"),
+        );
+        assert_eq!(suppressed, 2);
+        let rules: Vec<_> = remaining.iter().map(|d| d.rule_id.as_str()).collect();
+        assert_eq!(rules, vec!["other", RULE_MISSING_OWNER]);
+
+        // After expiry: the first one no longer applies and is itself a violation
+        let (remaining, suppressed) = apply(
+            vec![diag("no-todo", 1)],
+            &found,
+            &config,
+            date("2025-09-02"),
+        );
+        assert_eq!(suppressed, 0);
+        let rules: Vec<_> = remaining.iter().map(|d| d.rule_id.as_str()).collect();
+        assert_eq!(rules, vec!["no-todo", RULE_EXPIRED, RULE_MISSING_OWNER]);
+    }
+}
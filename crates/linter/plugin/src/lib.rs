@@ -2,8 +2,13 @@
 //!
 //! Code linting with configurable rules and auto-fix support.
 
+mod tasks;
+
 use lib_plugin_prelude::*;
-use linter_core::{format_to_string, LinterConfig, OutputFormat};
+use linter_core::{
+    format_to_string, suppression, FileIterator, LinterConfig, MetricsAnalyzer, OutputFormat,
+    Severity,
+};
 
 pub struct LinterPlugin;
 
@@ -16,7 +21,8 @@ impl Plugin for LinterPlugin {
             .with_description("Code linting with configurable rules and auto-fix support")
     }
 
-    async fn init(&mut self, _ctx: &PluginContext) -> Result<()> {
+    async fn init(&mut self, ctx: &PluginContext) -> Result<()> {
+        PluginCtx::init(ctx);
         Ok(())
     }
 
@@ -32,7 +38,11 @@ impl CliCommands for LinterPlugin {
             CliCommand {
                 name: "run".to_string(),
                 description: "Run linting on files".to_string(),
-                args: vec![CliArg::optional("--format", CliArgType::String)],
+                args: vec![
+                    CliArg::optional("--format", CliArgType::String),
+                    CliArg::optional("--create-tasks", CliArgType::Bool),
+                    CliArg::optional("--severity", CliArgType::String),
+                ],
                 has_subcommands: false,
             },
             CliCommand {
@@ -41,6 +51,26 @@ impl CliCommands for LinterPlugin {
                 args: vec![],
                 has_subcommands: false,
             },
+            CliCommand {
+                name: "metrics".to_string(),
+                description: "Report complexity, size, TODO and duplication metrics".to_string(),
+                args: vec![
+                    CliArg::optional("--format", CliArgType::String),
+                    CliArg::optional("--top", CliArgType::Int),
+                    CliArg::optional("--max-complexity", CliArgType::Int),
+                    CliArg::optional("--max-function-lines", CliArgType::Int),
+                    CliArg::optional("--max-file-lines", CliArgType::Int),
+                    CliArg::optional("--max-todo-density", CliArgType::Float),
+                    CliArg::optional("--max-duplicate-blocks", CliArgType::Int),
+                ],
+                has_subcommands: false,
+            },
+            CliCommand {
+                name: "suppressions".to_string(),
+                description: "List active suppression comments with their age".to_string(),
+                args: vec![CliArg::optional("--format", CliArgType::String)],
+                has_subcommands: false,
+            },
             CliCommand {
                 name: "list".to_string(),
                 description: "List configured linters".to_string(),
@@ -54,6 +84,8 @@ impl CliCommands for LinterPlugin {
         match ctx.subcommand.as_deref() {
             Some("run") => cmd_run(ctx).await,
             Some("fix") => cmd_fix(ctx).await,
+            Some("metrics") => cmd_metrics(ctx).await,
+            Some("suppressions") => cmd_suppressions(ctx).await,
             Some("list") => cmd_list(ctx).await,
             Some(cmd) => Ok(CliResult::error(format!("Unknown command: {}", cmd))),
             None => Ok(CliResult::success(help())),
@@ -64,9 +96,11 @@ impl CliCommands for LinterPlugin {
 fn help() -> String {
     "ADI Linter - Code linting with configurable rules\n\n\
      Commands:\n  \
-     run   Run linting on files\n  \
-     fix   Apply auto-fixes\n  \
-     list  List configured linters\n\n\
+     run           Run linting on files (--create-tasks to track violations as tasks)\n  \
+     fix           Apply auto-fixes\n  \
+     metrics       Report code metrics and check thresholds\n  \
+     suppressions  List active suppression comments\n  \
+     list          List configured linters\n\n\
      Usage: lint <command> [options]"
         .to_string()
 }
@@ -81,9 +115,32 @@ async fn cmd_run(ctx: &CliContext) -> Result<CliResult> {
         .await
         .map_err(|e| PluginError::CommandFailed(e.to_string()))?;
 
-    let output = format_to_string(&result, format)
+    let mut output = format_to_string(&result, format)
         .map_err(|e| PluginError::CommandFailed(e.to_string()))?;
 
+    if ctx.has_flag("create-tasks") {
+        let severity = match ctx.option::<String>("severity") {
+            None => Severity::Error,
+            Some(s) => ctx.option::<Severity>("severity").ok_or_else(|| {
+                PluginError::InvalidInput(format!(
+                    "Unknown severity '{}' (expected error, warning, info or hint)",
+                    s
+                ))
+            })?,
+        };
+        // The tasks plugin picks the task store of the active project
+        let service = PluginCtx::adi_service(tasks::TASKS_PLUGIN_ID).await?;
+        let summary = tasks::sync(service.as_ref(), &ctx.cwd, &result, severity)
+            .await
+            .map_err(PluginError::CommandFailed)?;
+        if format == OutputFormat::Pretty {
+            output.push_str(&format!(
+                "\nTasks: {} created, {} updated, {} closed",
+                summary.created, summary.updated, summary.closed
+            ));
+        }
+    }
+
     if result.has_errors() {
         Ok(CliResult::custom(1, output, String::new()))
     } else {
@@ -106,6 +163,105 @@ async fn cmd_fix(ctx: &CliContext) -> Result<CliResult> {
     Ok(CliResult::success(output))
 }
 
+async fn cmd_metrics(ctx: &CliContext) -> Result<CliResult> {
+    let mut config = LinterConfig::load_from_project(&ctx.cwd)
+        .map_err(|e| PluginError::Config(e.to_string()))?
+        .metrics;
+
+    // Command-line thresholds override the project config
+    let thresholds = &mut config.thresholds;
+    if let Some(max) = ctx.option::<u32>("max-complexity") {
+        thresholds.max_complexity = Some(max);
+    }
+    if let Some(max) = ctx.option::<usize>("max-function-lines") {
+        thresholds.max_function_lines = Some(max);
+    }
+    if let Some(max) = ctx.option::<usize>("max-file-lines") {
+        thresholds.max_file_lines = Some(max);
+    }
+    if let Some(max) = ctx.option::<f64>("max-todo-density") {
+        thresholds.max_todo_density = Some(max);
+    }
+    if let Some(max) = ctx.option::<usize>("max-duplicate-blocks") {
+        thresholds.max_duplicate_blocks = Some(max);
+    }
+
+    let report = MetricsAnalyzer::new(&ctx.cwd, config)
+        .run()
+        .map_err(|e| PluginError::CommandFailed(e.to_string()))?;
+
+    let mut buffer = Vec::new();
+    if ctx.option::<String>("format").as_deref() == Some("json") {
+        report.write_json(&mut buffer)
+    } else {
+        report.write_pretty(&mut buffer, ctx.option::<usize>("top").unwrap_or(10))
+    }
+    .map_err(|e| PluginError::CommandFailed(e.to_string()))?;
+    let output = String::from_utf8_lossy(&buffer).trim_end().to_string();
+
+    if report.has_violations() {
+        Ok(CliResult::custom(1, output, String::new()))
+    } else {
+        Ok(CliResult::success(output))
+    }
+}
+
+async fn cmd_suppressions(ctx: &CliContext) -> Result<CliResult> {
+    let files = FileIterator::new(&ctx.cwd)
+        .use_gitignore(true)
+        .use_adiignore(true)
+        .collect();
+    let (mut suppressions, _) = suppression::scan_files(&files);
+
+    let today = suppression::today();
+    suppressions.retain(|s| !s.is_expired(today));
+    for s in &mut suppressions {
+        s.file = s.file.strip_prefix(&ctx.cwd).unwrap_or(&s.file).to_path_buf();
+    }
+    let ages: Vec<_> = suppressions
+        .iter()
+        .map(|s| suppression::age_days(&ctx.cwd, s, today))
+        .collect();
+
+    if ctx.option::<String>("format").as_deref() == Some("json") {
+        let entries: Vec<serde_json::Value> = suppressions
+            .iter()
+            .zip(&ages)
+            .map(|(s, age)| {
+                let mut entry = serde_json::to_value(s).unwrap_or_default();
+                entry["age_days"] = serde_json::json!(age);
+                entry
+            })
+            .collect();
+        let output = serde_json::to_string_pretty(&entries)
+            .map_err(|e| PluginError::CommandFailed(e.to_string()))?;
+        return Ok(CliResult::success(output));
+    }
+
+    if suppressions.is_empty() {
+        return Ok(CliResult::success("No active suppressions.".to_string()));
+    }
+
+    let mut output = format!("{} active suppression(s):\n\n", suppressions.len());
+    for (s, age) in suppressions.iter().zip(&ages) {
+        let age = age.map(|d| format!("{}d", d)).unwrap_or_else(|| "new".to_string());
+        output.push_str(&format!(
+            "  {}:{}  {}  [age {}, owner {}, until {}]\n",
+            s.file.display(),
+            s.line,
+            s.rules.join(", "),
+            age,
+            s.owner.as_deref().unwrap_or("-"),
+            s.until.map(|d| d.to_string()).unwrap_or_else(|| "-".to_string()),
+        ));
+        if let Some(reason) = &s.reason {
+            output.push_str(&format!("    {}\n", reason));
+        }
+    }
+
+    Ok(CliResult::success(output.trim_end().to_string()))
+}
+
 async fn cmd_list(ctx: &CliContext) -> Result<CliResult> {
     let config = LinterConfig::load_from_project(&ctx.cwd)
         .map_err(|e| PluginError::Config(e.to_string()))?;
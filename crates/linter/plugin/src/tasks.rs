@@ -0,0 +1,209 @@
+//! Keep one task per baseline entry for `run --create-tasks`.
+//!
+//! Tasks are managed through the tasks plugin's service API (`create` and
+//! `update` on an [`AdiService`]), which the host hands out for the active
+//! project, so the linter only depends on the published method contract. The task ID is recorded in the
+//! baseline entry and the baseline key is written into the task description,
+//! linking both ways.
+
+use bytes::Bytes;
+use lib_adi_service::{AdiCallerContext, AdiHandleResult, AdiService};
+use linter_core::{Baseline, BaselineEntry, LintResult, Severity};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Plugin serving the task API
+pub const TASKS_PLUGIN_ID: &str = "adi.tasks";
+
+/// What a sync changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TaskSyncSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub closed: usize,
+}
+
+/// Reconcile tasks with the violations of `result` at or above `min_severity`
+/// and store the new baseline.
+pub async fn sync(
+    service: &dyn AdiService,
+    root: &Path,
+    result: &LintResult,
+    min_severity: Severity,
+) -> Result<TaskSyncSummary, String> {
+    let stored = Baseline::load(root).map_err(|e| e.to_string())?;
+    let mut current = Baseline::from_diagnostics(root, &result.diagnostics, min_severity);
+    let diff = stored.diff(&current);
+    stored.link_tasks(&mut current);
+
+    let mut summary = TaskSyncSummary::default();
+
+    for entry in &diff.changed {
+        let Some(task_id) = entry.task_id else {
+            continue;
+        };
+        let params = json!({
+            "task_id": task_id,
+            "title": title(entry),
+            "description": description(entry),
+        });
+        match call(service, "update", params).await {
+            Ok(_) => summary.updated += 1,
+            // Deleted by hand; open a fresh one below
+            Err(e) if e.starts_with("not_found") => {
+                if let Some(current) = current.entries.get_mut(&entry.key()) {
+                    current.task_id = None;
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    // New groups, and groups recorded before tasks were enabled
+    for entry in current.entries.values_mut().filter(|e| e.task_id.is_none()) {
+        let params = json!({
+            "title": title(entry),
+            "description": description(entry),
+        });
+        let created = call(service, "create", params).await?;
+        entry.task_id = created.get("task_id").and_then(Value::as_i64);
+        summary.created += 1;
+    }
+
+    for task_id in diff.resolved.iter().filter_map(|e| e.task_id) {
+        let params = json!({ "task_id": task_id, "status": "done" });
+        match call(service, "update", params).await {
+            Ok(_) => summary.closed += 1,
+            // Deleted by hand; nothing left to close
+            Err(e) if e.starts_with("not_found") => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    current.save(root).map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+fn title(entry: &BaselineEntry) -> String {
+    let count = if entry.count == 1 {
+        String::new()
+    } else {
+        format!(" ({}x)", entry.count)
+    };
+    format!("Fix {} in {}{}", entry.rule, entry.file.display(), count)
+}
+
+fn description(entry: &BaselineEntry) -> String {
+    format!(
+        "{}:{}: {}\n\nlint-baseline: {}",
+        entry.file.display(),
+        entry.first_line,
+        entry.message,
+        entry.key()
+    )
+}
+
+async fn call(service: &dyn AdiService, method: &str, params: Value) -> Result<Value, String> {
+    let payload = Bytes::from(serde_json::to_vec(&params).map_err(|e| e.to_string())?);
+    match service
+        .handle(&AdiCallerContext::anonymous(), method, payload)
+        .await
+    {
+        Ok(AdiHandleResult::Success(data)) => {
+            serde_json::from_slice(&data).map_err(|e| e.to_string())
+        }
+        Ok(AdiHandleResult::Stream(_)) => Err(format!("{}: unexpected stream response", method)),
+        Err(e) => Err(format!("{}: {}", e.code, e.message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linter_core::{Category, Diagnostic, Location};
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tasks_core::TasksService;
+
+    fn result(root: &Path, violations: &[(&str, u32)]) -> LintResult {
+        LintResult {
+            diagnostics: violations
+                .iter()
+                .map(|(file, line)| {
+                    Diagnostic::new(
+                        "no-unwrap",
+                        "rust",
+                        Category::CodeQuality,
+                        Severity::Error,
+                        "unwrap() may panic",
+                        Location::line(root.join(file), *line),
+                    )
+                })
+                .collect(),
+            files_checked: violations.len(),
+            duration: Duration::ZERO,
+            errors: Vec::new(),
+            suppressed: 0,
+            by_category: HashMap::new(),
+            by_severity: HashMap::new(),
+        }
+    }
+
+    async fn task(service: &TasksService, task_id: i64) -> Value {
+        call(service, "get", json!({ "task_id": task_id }))
+            .await
+            .unwrap()["task"]
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_sync_creates_updates_and_closes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let service = TasksService::new(root).unwrap();
+
+        let first = result(root, &[("a.rs", 1), ("b.rs", 4)]);
+        let summary = sync(&service, root, &first, Severity::Error).await.unwrap();
+        assert_eq!(
+            summary,
+            TaskSyncSummary {
+                created: 2,
+                updated: 0,
+                closed: 0
+            }
+        );
+
+        let baseline = Baseline::load(root).unwrap();
+        let a = baseline.entries["a.rs::rust/no-unwrap"].task_id.unwrap();
+        let b = baseline.entries["b.rs::rust/no-unwrap"].task_id.unwrap();
+        assert!(task(&service, a).await["description"]
+            .as_str()
+            .unwrap()
+            .ends_with("lint-baseline: a.rs::rust/no-unwrap"));
+
+        // a.rs gets worse, b.rs is fixed
+        let second = result(root, &[("a.rs", 1), ("a.rs", 9)]);
+        let summary = sync(&service, root, &second, Severity::Error)
+            .await
+            .unwrap();
+        assert_eq!(
+            summary,
+            TaskSyncSummary {
+                created: 0,
+                updated: 1,
+                closed: 1
+            }
+        );
+        assert_eq!(
+            task(&service, a).await["title"],
+            "Fix rust/no-unwrap in a.rs (2x)"
+        );
+        assert_eq!(task(&service, b).await["status"], "done");
+
+        // Nothing changed, nothing to do
+        let summary = sync(&service, root, &second, Severity::Error)
+            .await
+            .unwrap();
+        assert_eq!(summary, TaskSyncSummary::default());
+    }
+}
@@ -0,0 +1,125 @@
+//! Run scenario files and write a JUnit report.
+//!
+//! ```text
+//! scenario scenarios/*.yaml --junit target/scenario-junit.xml
+//! scenario scenarios/silk-echo.yaml --driver remote \
+//!     --signaling-url wss://adi.the-ihor.com/api/signaling/ws --access-token $TOKEN
+//! ```
+
+use clap::{Parser, ValueEnum};
+use scenario::{junit, CaseOutcome, Driver, LoopbackDriver, Runner, Scenario};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DriverKind {
+    Loopback,
+    #[cfg(feature = "remote")]
+    Remote,
+}
+
+#[derive(Parser)]
+#[command(
+    name = "scenario",
+    about = "Run end-to-end scenarios against the ADI stack"
+)]
+struct Args {
+    /// Scenario YAML files
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    #[arg(long, value_enum, default_value = "loopback")]
+    driver: DriverKind,
+
+    /// Write a JUnit XML report here
+    #[arg(long)]
+    junit: Option<PathBuf>,
+
+    /// Signaling server WebSocket URL (remote driver)
+    #[arg(long, env = "SIGNALING_URL")]
+    signaling_url: Option<String>,
+
+    /// Access token of the user scenarios run as (remote driver)
+    #[arg(long, env = "SIGNALING_ACCESS_TOKEN", hide_env_values = true)]
+    access_token: Option<String>,
+}
+
+fn driver(args: &Args) -> Result<Box<dyn Driver>, String> {
+    match args.driver {
+        DriverKind::Loopback => Ok(Box::new(LoopbackDriver::new())),
+        #[cfg(feature = "remote")]
+        DriverKind::Remote => {
+            let url = args
+                .signaling_url
+                .clone()
+                .ok_or("--signaling-url is required for the remote driver")?;
+            let token = args
+                .access_token
+                .clone()
+                .ok_or("--access-token is required for the remote driver")?;
+            Ok(Box::new(scenario::RemoteDriver::new(url, token)))
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "scenario=info".into()),
+        )
+        .with_writer(std::io::stderr)
+        .try_init();
+    let args = Args::parse();
+
+    // Parse everything first so a typo in the last file does not waste a run
+    let mut scenarios = Vec::with_capacity(args.files.len());
+    for path in &args.files {
+        match Scenario::from_file(path) {
+            Ok(scenario) => scenarios.push(scenario),
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let driver = match driver(&args) {
+        Ok(driver) => driver,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let runner = Runner::new(driver.as_ref());
+    let mut reports = Vec::with_capacity(scenarios.len());
+    for scenario in &scenarios {
+        let report = runner.run(scenario).await;
+        println!(
+            "{} {} ({:.1}s)",
+            if report.passed() { "PASS" } else { "FAIL" },
+            report.name,
+            report.duration.as_secs_f64()
+        );
+        for case in &report.cases {
+            if let CaseOutcome::Failed(message) = &case.outcome {
+                println!("  ✗ {}\n    {}", case.name, message.replace('\n', "\n    "));
+            }
+        }
+        reports.push(report);
+    }
+
+    if let Some(path) = &args.junit {
+        if let Err(e) = std::fs::write(path, junit::write_report(&reports)) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            return ExitCode::from(2);
+        }
+    }
+
+    if reports.iter().all(|r| r.passed()) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,43 @@
+//! What a scenario needs from the stack under test.
+
+use async_trait::async_trait;
+
+/// A cocoon spawned by a driver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CocoonHandle {
+    pub device_id: String,
+    /// Hive-side container, needed to terminate it
+    pub container_id: Option<String>,
+    pub kind: String,
+}
+
+/// An open Silk session, opaque to the runner
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionHandle(pub String);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOutput {
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Transport a scenario runs against. Errors are reported as step failures,
+/// so they should say what went wrong in terms a test log reader understands.
+#[async_trait]
+pub trait Driver: Send + Sync {
+    /// Short name shown in reports
+    fn name(&self) -> &str;
+
+    async fn spawn(&self, kind: &str) -> Result<CocoonHandle, String>;
+
+    /// Return once the cocoon is registered and owned by the scenario's user
+    async fn claim(&self, cocoon: &CocoonHandle) -> Result<(), String>;
+
+    async fn open_silk(&self, cocoon: &CocoonHandle) -> Result<SessionHandle, String>;
+
+    async fn run(&self, session: &SessionHandle, command: &str) -> Result<CommandOutput, String>;
+
+    /// Stop the cocoon; sessions on it are closed
+    async fn terminate(&self, cocoon: &CocoonHandle) -> Result<(), String>;
+}
@@ -0,0 +1,118 @@
+//! JUnit XML reports: one `<testsuite>` per scenario, one `<testcase>` per step.
+
+use crate::runner::{CaseOutcome, ScenarioReport};
+use std::fmt::Write;
+
+pub fn write_report(reports: &[ScenarioReport]) -> String {
+    let tests: usize = reports.iter().map(|r| r.cases.len()).sum();
+    let failures: usize = reports.iter().map(ScenarioReport::failures).sum();
+    let skipped: usize = reports.iter().map(ScenarioReport::skipped).sum();
+    let time: f64 = reports.iter().map(|r| r.duration.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        r#"<testsuites name="scenario" tests="{}" failures="{}" skipped="{}" time="{:.3}">"#,
+        tests, failures, skipped, time
+    );
+    for report in reports {
+        let suite = escape(&report.name);
+        let _ = writeln!(
+            xml,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" skipped="{}" time="{:.3}">"#,
+            suite,
+            report.cases.len(),
+            report.failures(),
+            report.skipped(),
+            report.duration.as_secs_f64()
+        );
+        let _ = writeln!(
+            xml,
+            r#"    <properties><property name="driver" value="{}"/></properties>"#,
+            escape(&report.driver)
+        );
+        for case in &report.cases {
+            let _ = write!(
+                xml,
+                r#"    <testcase classname="{}" name="{}" time="{:.3}""#,
+                suite,
+                escape(&case.name),
+                case.duration.as_secs_f64()
+            );
+            match &case.outcome {
+                CaseOutcome::Passed => xml.push_str("/>\n"),
+                CaseOutcome::Skipped => xml.push_str("><skipped/></testcase>\n"),
+                CaseOutcome::Failed(message) => {
+                    let first_line = message.lines().next().unwrap_or_default();
+                    let _ = writeln!(
+                        xml,
+                        r#"><failure message="{}">{}</failure></testcase>"#,
+                        escape(first_line),
+                        escape(message)
+                    );
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escape text for both element content and attribute values. Characters XML
+/// 1.0 cannot represent (most control codes) are dropped.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' | '\t' | '\r' => out.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::CaseReport;
+    use std::time::Duration;
+
+    #[test]
+    fn reports_outcomes_and_escapes() {
+        let report = ScenarioReport {
+            name: "a & b".to_string(),
+            driver: "loopback".to_string(),
+            duration: Duration::from_millis(1500),
+            cases: vec![
+                CaseReport {
+                    name: "01 run `echo <x>`".to_string(),
+                    duration: Duration::from_millis(250),
+                    outcome: CaseOutcome::Failed("exit code 1\x1b[0m\n--- stdout".to_string()),
+                },
+                CaseReport {
+                    name: "02 terminate a".to_string(),
+                    duration: Duration::ZERO,
+                    outcome: CaseOutcome::Skipped,
+                },
+            ],
+        };
+        let xml = write_report(&[report]);
+
+        assert!(xml.contains(
+            r#"<testsuites name="scenario" tests="2" failures="1" skipped="1" time="1.500">"#
+        ));
+        assert!(xml.contains(r#"<testsuite name="a &amp; b""#));
+        assert!(xml.contains(
+            r#"name="01 run `echo &lt;x&gt;`" time="0.250"><failure message="exit code 1[0m">"#
+        ));
+        assert!(xml.contains("<skipped/>"));
+        assert!(!xml.contains('\x1b'));
+    }
+}
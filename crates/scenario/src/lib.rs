@@ -0,0 +1,47 @@
+//! Declarative end-to-end scenarios for the whole stack.
+//!
+//! A scenario is a YAML list of steps ("spawn a cocoon of kind X, claim it,
+//! open a Silk session, run a command, check the output, terminate") that a
+//! [`Runner`] drives through a [`Driver`]:
+//!
+//! - [`LoopbackDriver`] keeps everything in process: cocoons are local work
+//!   directories and Silk commands run in a local shell. Good for checking
+//!   scenarios and the harness itself without any infrastructure.
+//! - [`RemoteDriver`] (`remote` feature) goes through a real signaling server
+//!   and hive, exactly like the web app and CLI do.
+//!
+//! Results are collected per step and written as a JUnit XML report with
+//! [`junit::write_report`].
+//!
+//! ```ignore
+//! let scenario = Scenario::from_file("scenarios/silk-echo.yaml")?;
+//! let report = Runner::new(&LoopbackDriver::new()).run(&scenario).await;
+//! std::fs::write("junit.xml", junit::write_report(&[report]))?;
+//! ```
+
+mod driver;
+pub mod junit;
+mod loopback;
+#[cfg(feature = "remote")]
+mod remote;
+mod runner;
+mod spec;
+
+pub use driver::{CocoonHandle, CommandOutput, Driver, SessionHandle};
+pub use loopback::LoopbackDriver;
+#[cfg(feature = "remote")]
+pub use remote::RemoteDriver;
+pub use runner::{CaseOutcome, CaseReport, Runner, ScenarioReport};
+pub use spec::{Expect, Scenario, Step};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScenarioError {
+    #[error("Failed to read scenario: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid scenario: {0}")]
+    Yaml(#[from] serde_yml::Error),
+
+    #[error("Invalid scenario: {0}")]
+    Invalid(String),
+}
@@ -0,0 +1,169 @@
+//! In-process driver: no signaling server, hive or containers.
+//!
+//! Each cocoon is a fresh work directory under the system temp dir, and Silk
+//! commands run there with `sh -c`, so files written by one command are seen by
+//! the next one, like in a container. Ownership is tracked the way the
+//! signaling server enforces it: sessions can only be opened on a claimed
+//! cocoon, and nothing works on a terminated one.
+
+use crate::driver::{CocoonHandle, CommandOutput, Driver, SessionHandle};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Mutex;
+
+struct LoopbackCocoon {
+    workdir: PathBuf,
+    claimed: bool,
+}
+
+#[derive(Default)]
+struct State {
+    cocoons: HashMap<String, LoopbackCocoon>,
+    /// Session ID → device ID
+    sessions: HashMap<String, String>,
+}
+
+#[derive(Default)]
+pub struct LoopbackDriver {
+    state: Mutex<State>,
+    next_id: AtomicU64,
+}
+
+impl LoopbackDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+#[async_trait]
+impl Driver for LoopbackDriver {
+    fn name(&self) -> &str {
+        "loopback"
+    }
+
+    async fn spawn(&self, kind: &str) -> Result<CocoonHandle, String> {
+        let device_id = format!(
+            "loopback-{}-{}-{}",
+            kind,
+            std::process::id(),
+            self.next_id()
+        );
+        let workdir = std::env::temp_dir().join(format!("scenario-{}", device_id));
+        tokio::fs::create_dir_all(&workdir)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", workdir.display(), e))?;
+
+        self.state.lock().await.cocoons.insert(
+            device_id.clone(),
+            LoopbackCocoon {
+                workdir,
+                claimed: false,
+            },
+        );
+        Ok(CocoonHandle {
+            container_id: Some(device_id.clone()),
+            device_id,
+            kind: kind.to_string(),
+        })
+    }
+
+    async fn claim(&self, cocoon: &CocoonHandle) -> Result<(), String> {
+        let mut state = self.state.lock().await;
+        let entry = state
+            .cocoons
+            .get_mut(&cocoon.device_id)
+            .ok_or_else(|| format!("Cocoon {} is not running", cocoon.device_id))?;
+        entry.claimed = true;
+        Ok(())
+    }
+
+    async fn open_silk(&self, cocoon: &CocoonHandle) -> Result<SessionHandle, String> {
+        let mut state = self.state.lock().await;
+        match state.cocoons.get(&cocoon.device_id) {
+            None => return Err(format!("Cocoon {} is not running", cocoon.device_id)),
+            Some(c) if !c.claimed => {
+                return Err(format!("Cocoon {} is not claimed", cocoon.device_id))
+            }
+            Some(_) => {}
+        }
+        let session_id = format!("silk-{}", self.next_id());
+        state
+            .sessions
+            .insert(session_id.clone(), cocoon.device_id.clone());
+        Ok(SessionHandle(session_id))
+    }
+
+    async fn run(&self, session: &SessionHandle, command: &str) -> Result<CommandOutput, String> {
+        let workdir = {
+            let state = self.state.lock().await;
+            let device_id = state
+                .sessions
+                .get(&session.0)
+                .ok_or_else(|| format!("Session {} is closed", session.0))?;
+            state
+                .cocoons
+                .get(device_id)
+                .map(|c| c.workdir.clone())
+                .ok_or_else(|| format!("Cocoon {} is not running", device_id))?
+        };
+
+        let output = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(&workdir)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run sh: {}", e))?;
+
+        Ok(CommandOutput {
+            // Killed by a signal: report like a shell would
+            exit_code: output.status.code().unwrap_or(128),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    async fn terminate(&self, cocoon: &CocoonHandle) -> Result<(), String> {
+        let removed = {
+            let mut state = self.state.lock().await;
+            state
+                .sessions
+                .retain(|_, device| device != &cocoon.device_id);
+            state.cocoons.remove(&cocoon.device_id)
+        };
+        let removed =
+            removed.ok_or_else(|| format!("Cocoon {} is not running", cocoon.device_id))?;
+        tokio::fs::remove_dir_all(&removed.workdir)
+            .await
+            .map_err(|e| format!("Failed to remove {}: {}", removed.workdir.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sessions_require_a_claimed_running_cocoon() {
+        let driver = LoopbackDriver::new();
+        let cocoon = driver.spawn("ubuntu").await.unwrap();
+        assert!(driver.open_silk(&cocoon).await.is_err());
+
+        driver.claim(&cocoon).await.unwrap();
+        let session = driver.open_silk(&cocoon).await.unwrap();
+        driver.run(&session, "echo state > file").await.unwrap();
+        let out = driver.run(&session, "cat file").await.unwrap();
+        assert_eq!(out.stdout, "state\n");
+
+        driver.terminate(&cocoon).await.unwrap();
+        assert!(driver.run(&session, "true").await.is_err());
+        assert!(driver.terminate(&cocoon).await.is_err());
+    }
+}
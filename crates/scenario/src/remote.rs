@@ -0,0 +1,252 @@
+//! Driver for a real environment: a signaling server with at least one hive.
+//!
+//! Cocoons are spawned with `hive_spawn_cocoon`, passing the scenario user's
+//! access token as setup token so the cocoon claims itself to that user on
+//! registration. `claim` waits for that to show up in the user's device list.
+//! Silk sessions are [`AdiClient`] connections to the cocoon, the same path the
+//! CLI takes.
+
+use crate::driver::{CocoonHandle, CommandOutput, Driver, SessionHandle};
+use async_trait::async_trait;
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use lib_adi_client::{AdiClient, ClientConfig};
+use lib_signaling_protocol::SignalingMessage;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// How often `claim` polls the device list
+const CLAIM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+struct Signaling {
+    sink: SplitSink<Socket, Message>,
+    stream: SplitStream<Socket>,
+}
+
+impl Signaling {
+    async fn connect(url: &str, access_token: &str) -> Result<Self, String> {
+        let (ws, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+        let (sink, stream) = ws.split();
+        let mut signaling = Self { sink, stream };
+
+        let mut authenticating = false;
+        loop {
+            match signaling.recv().await? {
+                SignalingMessage::AuthHello { .. } if !authenticating => {
+                    authenticating = true;
+                    signaling
+                        .send(&SignalingMessage::AuthAuthenticate {
+                            access_token: access_token.to_string(),
+                        })
+                        .await?;
+                }
+                SignalingMessage::AuthHelloAuthed { .. } => return Ok(signaling),
+                SignalingMessage::SystemError { message } => {
+                    return Err(format!("Authentication failed: {}", message))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn send(&mut self, msg: &SignalingMessage) -> Result<(), String> {
+        let json = serde_json::to_string(msg).map_err(|e| e.to_string())?;
+        self.sink
+            .send(Message::Text(json))
+            .await
+            .map_err(|e| format!("Signaling connection lost: {}", e))
+    }
+
+    async fn recv(&mut self) -> Result<SignalingMessage, String> {
+        loop {
+            match self.stream.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    if let Ok(msg) = serde_json::from_str(&text) {
+                        return Ok(msg);
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(format!("Signaling connection lost: {}", e)),
+                None => return Err("Signaling server closed the connection".to_string()),
+            }
+        }
+    }
+}
+
+pub struct RemoteDriver {
+    signaling_url: String,
+    access_token: String,
+    /// Opened on first use; requests are answered in order on it
+    signaling: Mutex<Option<Signaling>>,
+    /// Session ID → (device ID, client)
+    sessions: Mutex<HashMap<String, (String, AdiClient)>>,
+}
+
+impl RemoteDriver {
+    pub fn new(signaling_url: impl Into<String>, access_token: impl Into<String>) -> Self {
+        Self {
+            signaling_url: signaling_url.into(),
+            access_token: access_token.into(),
+            signaling: Mutex::new(None),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Send `msg` and wait for the reply `matches` picks out. A dropped
+    /// connection is reopened on the next call.
+    async fn request<T>(
+        &self,
+        msg: SignalingMessage,
+        mut matches: impl FnMut(SignalingMessage) -> Option<T> + Send,
+    ) -> Result<T, String> {
+        let mut guard = self.signaling.lock().await;
+        if guard.is_none() {
+            *guard = Some(Signaling::connect(&self.signaling_url, &self.access_token).await?);
+        }
+        let signaling = guard.as_mut().expect("connected above");
+
+        let result = async {
+            signaling.send(&msg).await?;
+            loop {
+                if let Some(reply) = matches(signaling.recv().await?) {
+                    return Ok(reply);
+                }
+            }
+        }
+        .await;
+        if result.is_err() {
+            *guard = None;
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl Driver for RemoteDriver {
+    fn name(&self) -> &str {
+        "remote"
+    }
+
+    async fn spawn(&self, kind: &str) -> Result<CocoonHandle, String> {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let msg = SignalingMessage::HiveSpawnCocoon {
+            request_id: request_id.clone().into(),
+            setup_token: self.access_token.clone(),
+            name: Some(format!("scenario-{}", &request_id[..8])),
+            kind: kind.to_string(),
+            gpu_required: None,
+            min_vram_mb: None,
+            from_pool: None,
+        };
+        let (device_id, container_id) = self
+            .request(msg, |reply| match reply {
+                SignalingMessage::HiveSpawnCocoonResult {
+                    request_id: id,
+                    success,
+                    device_id,
+                    container_id,
+                    error,
+                    ..
+                } if id == request_id => Some(if success {
+                    device_id
+                        .map(|d| (d.into_inner(), container_id))
+                        .ok_or_else(|| "Hive reported success without a device ID".to_string())
+                } else {
+                    Err(error.unwrap_or_else(|| "Spawn failed".to_string()))
+                }),
+                _ => None,
+            })
+            .await??;
+
+        Ok(CocoonHandle {
+            device_id,
+            container_id,
+            kind: kind.to_string(),
+        })
+    }
+
+    async fn claim(&self, cocoon: &CocoonHandle) -> Result<(), String> {
+        // Bounded by the runner's step timeout
+        loop {
+            let devices = lib_adi_client::list_devices(&self.signaling_url, &self.access_token)
+                .await
+                .map_err(|e| e.to_string())?;
+            if devices
+                .iter()
+                .any(|d| d.device_id == cocoon.device_id && d.online)
+            {
+                return Ok(());
+            }
+            tokio::time::sleep(CLAIM_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn open_silk(&self, cocoon: &CocoonHandle) -> Result<SessionHandle, String> {
+        let config =
+            ClientConfig::new(&self.signaling_url, &self.access_token).device(&cocoon.device_id);
+        let client = AdiClient::connect(config)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        self.sessions
+            .lock()
+            .await
+            .insert(session_id.clone(), (cocoon.device_id.clone(), client));
+        Ok(SessionHandle(session_id))
+    }
+
+    async fn run(&self, session: &SessionHandle, command: &str) -> Result<CommandOutput, String> {
+        let sessions = self.sessions.lock().await;
+        let (_, client) = sessions
+            .get(&session.0)
+            .ok_or_else(|| format!("Session {} is closed", session.0))?;
+        let output = client.exec(command).await.map_err(|e| e.to_string())?;
+        Ok(CommandOutput {
+            exit_code: output.exit_code,
+            stdout: output.stdout,
+            stderr: output.stderr,
+        })
+    }
+
+    async fn terminate(&self, cocoon: &CocoonHandle) -> Result<(), String> {
+        self.sessions.lock().await.retain(|_, (device_id, client)| {
+            let keep = device_id != &cocoon.device_id;
+            if !keep {
+                client.close();
+            }
+            keep
+        });
+
+        let container_id = cocoon
+            .container_id
+            .clone()
+            .ok_or_else(|| format!("No container known for {}", cocoon.device_id))?;
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let msg = SignalingMessage::HiveTerminateCocoon {
+            request_id: request_id.clone().into(),
+            container_id,
+        };
+        self.request(msg, |reply| match reply {
+            SignalingMessage::HiveTerminateCocoonResult {
+                request_id: id,
+                success,
+                error,
+            } if id == request_id => Some(if success {
+                Ok(())
+            } else {
+                Err(error.unwrap_or_else(|| "Terminate failed".to_string()))
+            }),
+            _ => None,
+        })
+        .await?
+    }
+}
@@ -0,0 +1,275 @@
+//! Drives a scenario's steps through a driver and records each as a test case.
+
+use crate::driver::{CocoonHandle, CommandOutput, Driver, SessionHandle};
+use crate::spec::{Expect, Scenario, Step};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Output included in a failure message is cut to this many bytes per stream
+const MAX_OUTPUT_IN_FAILURE: usize = 2048;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseOutcome {
+    Passed,
+    Failed(String),
+    /// Not run because an earlier step failed
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaseReport {
+    pub name: String,
+    pub duration: Duration,
+    pub outcome: CaseOutcome,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub driver: String,
+    pub duration: Duration,
+    pub cases: Vec<CaseReport>,
+}
+
+impl ScenarioReport {
+    pub fn passed(&self) -> bool {
+        self.failures() == 0
+    }
+
+    pub fn failures(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|c| matches!(c.outcome, CaseOutcome::Failed(_)))
+            .count()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|c| c.outcome == CaseOutcome::Skipped)
+            .count()
+    }
+}
+
+pub struct Runner<'a> {
+    driver: &'a dyn Driver,
+}
+
+#[derive(Default)]
+struct Aliases {
+    /// Spawned and not yet terminated, in spawn order for cleanup
+    cocoons: Vec<(String, CocoonHandle)>,
+    sessions: HashMap<String, SessionHandle>,
+}
+
+impl Aliases {
+    fn cocoon(&self, alias: &str) -> Result<&CocoonHandle, String> {
+        self.cocoons
+            .iter()
+            .find(|(a, _)| a == alias)
+            .map(|(_, c)| c)
+            .ok_or_else(|| format!("Cocoon '{}' is not running", alias))
+    }
+}
+
+impl<'a> Runner<'a> {
+    pub fn new(driver: &'a dyn Driver) -> Self {
+        Self { driver }
+    }
+
+    pub async fn run(&self, scenario: &Scenario) -> ScenarioReport {
+        let started = Instant::now();
+        let mut aliases = Aliases::default();
+        let mut cases = Vec::with_capacity(scenario.steps.len());
+        let mut failed = false;
+
+        for (i, step) in scenario.steps.iter().enumerate() {
+            let name = format!("{:02} {}", i + 1, step.describe());
+            if failed {
+                cases.push(CaseReport {
+                    name,
+                    duration: Duration::ZERO,
+                    outcome: CaseOutcome::Skipped,
+                });
+                continue;
+            }
+
+            tracing::info!("▶ {}: {}", scenario.name, name);
+            let step_started = Instant::now();
+            let result =
+                match tokio::time::timeout(scenario.step_timeout(), self.step(step, &mut aliases))
+                    .await
+                {
+                    Ok(result) => result,
+                    Err(_) => Err(format!(
+                        "Timed out after {}s",
+                        scenario.step_timeout().as_secs()
+                    )),
+                };
+            let outcome = match result {
+                Ok(()) => CaseOutcome::Passed,
+                Err(message) => {
+                    tracing::warn!("✗ {}: {}: {}", scenario.name, name, message);
+                    failed = true;
+                    CaseOutcome::Failed(message)
+                }
+            };
+            cases.push(CaseReport {
+                name,
+                duration: step_started.elapsed(),
+                outcome,
+            });
+        }
+
+        // Never leave cocoons behind, whatever happened above
+        for (alias, cocoon) in std::mem::take(&mut aliases.cocoons).into_iter().rev() {
+            let step_started = Instant::now();
+            let outcome =
+                match tokio::time::timeout(scenario.step_timeout(), self.driver.terminate(&cocoon))
+                    .await
+                {
+                    Ok(Ok(())) => CaseOutcome::Passed,
+                    Ok(Err(message)) => CaseOutcome::Failed(message),
+                    Err(_) => CaseOutcome::Failed(format!(
+                        "Timed out after {}s",
+                        scenario.step_timeout().as_secs()
+                    )),
+                };
+            cases.push(CaseReport {
+                name: format!("cleanup: terminate {}", alias),
+                duration: step_started.elapsed(),
+                outcome,
+            });
+        }
+
+        ScenarioReport {
+            name: scenario.name.clone(),
+            driver: self.driver.name().to_string(),
+            duration: started.elapsed(),
+            cases,
+        }
+    }
+
+    async fn step(&self, step: &Step, aliases: &mut Aliases) -> Result<(), String> {
+        match step {
+            Step::Spawn { kind, alias } => {
+                let cocoon = self.driver.spawn(kind).await?;
+                tracing::info!("🐛 {} is {}", alias, cocoon.device_id);
+                aliases.cocoons.push((alias.clone(), cocoon));
+            }
+            Step::Claim(alias) => self.driver.claim(aliases.cocoon(alias)?).await?,
+            Step::OpenSilk { cocoon, alias } => {
+                let session = self.driver.open_silk(aliases.cocoon(cocoon)?).await?;
+                aliases.sessions.insert(alias.clone(), session);
+            }
+            Step::Run {
+                session,
+                command,
+                expect,
+            } => {
+                let session = aliases
+                    .sessions
+                    .get(session)
+                    .ok_or_else(|| format!("Session '{}' is not open", session))?;
+                let output = self.driver.run(session, command).await?;
+                check(expect, &output)?;
+            }
+            Step::Sleep { ms } => tokio::time::sleep(Duration::from_millis(*ms)).await,
+            Step::Terminate(alias) => {
+                self.driver.terminate(aliases.cocoon(alias)?).await?;
+                aliases.cocoons.retain(|(a, _)| a != alias);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare a command's result to the expectations; the error lists every
+/// mismatch followed by the output.
+fn check(expect: &Expect, output: &CommandOutput) -> Result<(), String> {
+    let mut mismatches = Vec::new();
+    if let Some(code) = expect.exit_code {
+        if output.exit_code != code {
+            mismatches.push(format!(
+                "exit code {} (expected {})",
+                output.exit_code, code
+            ));
+        }
+    }
+    if let Some(needle) = &expect.stdout_contains {
+        if !output.stdout.contains(needle.as_str()) {
+            mismatches.push(format!("stdout does not contain {:?}", needle));
+        }
+    }
+    if let Some(needle) = &expect.stdout_not_contains {
+        if output.stdout.contains(needle.as_str()) {
+            mismatches.push(format!("stdout contains {:?}", needle));
+        }
+    }
+    if let Some(needle) = &expect.stderr_contains {
+        if !output.stderr.contains(needle.as_str()) {
+            mismatches.push(format!("stderr does not contain {:?}", needle));
+        }
+    }
+
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{}\n--- stdout\n{}\n--- stderr\n{}",
+        mismatches.join("; "),
+        truncate(&output.stdout),
+        truncate(&output.stderr)
+    ))
+}
+
+fn truncate(s: &str) -> &str {
+    if s.len() <= MAX_OUTPUT_IN_FAILURE {
+        return s;
+    }
+    let mut end = MAX_OUTPUT_IN_FAILURE;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LoopbackDriver;
+
+    #[tokio::test]
+    async fn runs_example_scenario_on_loopback() {
+        let scenario = Scenario::parse(include_str!("../scenarios/silk-echo.yaml")).unwrap();
+        let driver = LoopbackDriver::new();
+        let report = Runner::new(&driver).run(&scenario).await;
+
+        assert!(report.passed(), "{:#?}", report.cases);
+        assert_eq!(report.cases.len(), scenario.steps.len());
+        assert_eq!(report.driver, "loopback");
+    }
+
+    #[tokio::test]
+    async fn failure_skips_rest_and_cleans_up() {
+        let yaml = r#"
+name: failing
+steps:
+  - spawn: { kind: ubuntu, as: a }
+  - claim: a
+  - open_silk: { cocoon: a, as: s }
+  - run: { session: s, command: "echo nope", expect: { stdout_contains: yes } }
+  - terminate: a
+"#;
+        let scenario = Scenario::parse(yaml).unwrap();
+        let driver = LoopbackDriver::new();
+        let report = Runner::new(&driver).run(&scenario).await;
+
+        let outcomes: Vec<_> = report.cases.iter().map(|c| &c.outcome).collect();
+        assert!(matches!(outcomes[3], CaseOutcome::Failed(m) if m.contains("nope")));
+        assert_eq!(outcomes[4], &CaseOutcome::Skipped);
+        assert_eq!(report.cases[5].name, "cleanup: terminate a");
+        assert_eq!(outcomes[5], &CaseOutcome::Passed);
+        assert_eq!((report.failures(), report.skipped()), (1, 1));
+    }
+}
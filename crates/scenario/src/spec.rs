@@ -0,0 +1,175 @@
+//! YAML scenario format.
+//!
+//! ```yaml
+//! name: silk echo on ubuntu
+//! step_timeout_secs: 120       # per step (default 60)
+//! steps:
+//!   - spawn: { kind: ubuntu, as: box }
+//!   - claim: box
+//!   - open_silk: { cocoon: box, as: shell }
+//!   - run:
+//!       session: shell
+//!       command: echo hello
+//!       expect: { exit_code: 0, stdout_contains: hello }
+//!   - terminate: box
+//! ```
+//!
+//! Cocoons and sessions are referred to by the alias given in `as`. Steps run
+//! in order; after the first failure the rest are skipped, and cocoons that
+//! were spawned but not terminated are terminated during cleanup.
+
+use crate::ScenarioError;
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default = "default_step_timeout_secs")]
+    pub step_timeout_secs: u64,
+    /// Written as `- spawn: {...}` rather than YAML tags
+    #[serde(with = "serde_yml::with::singleton_map_recursive")]
+    pub steps: Vec<Step>,
+}
+
+fn default_step_timeout_secs() -> u64 {
+    60
+}
+
+impl Scenario {
+    pub fn parse(yaml: &str) -> Result<Self, ScenarioError> {
+        let scenario: Self = serde_yml::from_str(yaml)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn step_timeout(&self) -> Duration {
+        Duration::from_secs(self.step_timeout_secs)
+    }
+
+    /// Reject references to aliases no earlier step defines, so typos fail
+    /// before anything is spawned.
+    fn validate(&self) -> Result<(), ScenarioError> {
+        let mut cocoons = Vec::new();
+        let mut sessions = Vec::new();
+        let invalid =
+            |i: usize, msg: String| ScenarioError::Invalid(format!("step {}: {}", i + 1, msg));
+
+        for (i, step) in self.steps.iter().enumerate() {
+            let cocoon = match step {
+                Step::Spawn { alias, .. } => {
+                    cocoons.push(alias.as_str());
+                    continue;
+                }
+                Step::Claim(cocoon) | Step::Terminate(cocoon) => cocoon,
+                Step::OpenSilk { cocoon, alias } => {
+                    sessions.push(alias.as_str());
+                    cocoon
+                }
+                Step::Run { session, .. } => {
+                    if !sessions.contains(&session.as_str()) {
+                        return Err(invalid(i, format!("unknown session '{}'", session)));
+                    }
+                    continue;
+                }
+                Step::Sleep { .. } => continue,
+            };
+            if !cocoons.contains(&cocoon.as_str()) {
+                return Err(invalid(i, format!("unknown cocoon '{}'", cocoon)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    /// Spawn a cocoon of `kind` through a hive
+    Spawn {
+        kind: String,
+        #[serde(rename = "as")]
+        alias: String,
+    },
+    /// Wait until the spawned cocoon is registered and owned by the caller
+    Claim(String),
+    /// Open a Silk shell session on a claimed cocoon
+    OpenSilk {
+        cocoon: String,
+        #[serde(rename = "as")]
+        alias: String,
+    },
+    /// Run a command in a Silk session and check its output
+    Run {
+        session: String,
+        command: String,
+        #[serde(default)]
+        expect: Expect,
+    },
+    Sleep {
+        ms: u64,
+    },
+    Terminate(String),
+}
+
+impl Step {
+    /// Test case name in reports
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Spawn { kind, alias } => format!("spawn {} as {}", kind, alias),
+            Self::Claim(cocoon) => format!("claim {}", cocoon),
+            Self::OpenSilk { cocoon, alias } => format!("open silk on {} as {}", cocoon, alias),
+            Self::Run {
+                session, command, ..
+            } => format!("run `{}` in {}", command, session),
+            Self::Sleep { ms } => format!("sleep {}ms", ms),
+            Self::Terminate(cocoon) => format!("terminate {}", cocoon),
+        }
+    }
+}
+
+/// Checks on a command's result; unset fields are not checked.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expect {
+    pub exit_code: Option<i32>,
+    pub stdout_contains: Option<String>,
+    pub stdout_not_contains: Option<String>,
+    pub stderr_contains: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_full_scenario() {
+        let scenario = Scenario::parse(include_str!("../scenarios/silk-echo.yaml")).unwrap();
+        assert_eq!(scenario.steps.len(), 6);
+        assert!(matches!(&scenario.steps[1], Step::Claim(c) if c == "box"));
+        match &scenario.steps[3] {
+            Step::Run { expect, .. } => assert_eq!(expect.exit_code, Some(0)),
+            other => panic!("unexpected step {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_aliases() {
+        let yaml = "name: t\nsteps:\n  - spawn: { kind: k, as: a }\n  - claim: b\n";
+        let err = Scenario::parse(yaml).unwrap_err();
+        assert!(
+            err.to_string().contains("step 2: unknown cocoon 'b'"),
+            "{}",
+            err
+        );
+
+        let yaml = "name: t\nsteps:\n  - run: { session: s, command: ls }\n";
+        assert!(Scenario::parse(yaml).is_err());
+    }
+}
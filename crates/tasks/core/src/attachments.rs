@@ -0,0 +1,212 @@
+//! Content-addressed storage for files attached to tasks.
+//!
+//! Local files are copied into `<tasks dir>/attachments/<sha256>` so the
+//! attachment survives the original being moved or deleted, and attaching the
+//! same file twice stores it once. A stored file is removed once no attachment
+//! refers to its hash. URLs are only recorded, never fetched.
+
+use crate::error::{Error, Result};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Size limits for copied files. URLs do not count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttachmentLimits {
+    /// Largest single file that may be attached
+    pub max_file_bytes: u64,
+    /// Total size of all stored files, counting each content hash once
+    pub quota_bytes: u64,
+}
+
+impl Default for AttachmentLimits {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 25 * 1024 * 1024,
+            quota_bytes: 512 * 1024 * 1024,
+        }
+    }
+}
+
+/// Whether an attach target is a link rather than a local path.
+#[must_use]
+pub fn is_url(target: &str) -> bool {
+    target.split_once("://").is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    })
+}
+
+/// MIME type from a file name or URL path extension; `None` when unknown.
+#[must_use]
+pub fn guess_mime_type(name: &str) -> Option<&'static str> {
+    let name = name.split(['?', '#']).next().unwrap_or(name);
+    let ext = Path::new(name).extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match ext.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "fig" => "application/x-figma",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// Directory of stored attachment files, keyed by content hash.
+pub struct AttachmentStore {
+    dir: PathBuf,
+}
+
+impl AttachmentStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    #[must_use]
+    pub fn path_for(&self, content_hash: &str) -> PathBuf {
+        self.dir.join(content_hash)
+    }
+
+    /// SHA-256 of a file, hex encoded.
+    pub fn hash_file(path: &Path) -> Result<String> {
+        let mut file = std::fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buf = [0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Copy a file in under its hash unless that content is already stored.
+    /// Returns whether a new copy was written.
+    pub fn store(&self, source: &Path, content_hash: &str) -> Result<bool> {
+        let dest = self.path_for(content_hash);
+        if dest.is_file() {
+            return Ok(false);
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        // Copy under a temporary name so a failed copy never looks stored.
+        // The name is unique per attach, so concurrent attaches of the same
+        // content each rename a complete copy into place.
+        static NEXT_PARTIAL: AtomicU64 = AtomicU64::new(0);
+        let partial = self.dir.join(format!(
+            ".{content_hash}.{}-{}.partial",
+            std::process::id(),
+            NEXT_PARTIAL.fetch_add(1, Ordering::Relaxed)
+        ));
+        let copied = std::fs::copy(source, &partial).and_then(|_| std::fs::rename(&partial, &dest));
+        if let Err(e) = copied {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e.into());
+        }
+        Ok(true)
+    }
+
+    /// Delete the stored copy of `content_hash`. Returns whether there was one.
+    pub fn remove(&self, content_hash: &str) -> Result<bool> {
+        match std::fs::remove_file(self.path_for(content_hash)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reject files over the per-file limit or that would push the store
+    /// over its quota. `stored_bytes` is what the store already holds.
+    pub fn check_limits(limits: &AttachmentLimits, size: u64, stored_bytes: u64) -> Result<()> {
+        if size > limits.max_file_bytes {
+            return Err(Error::AttachmentTooLarge {
+                size,
+                limit: limits.max_file_bytes,
+            });
+        }
+        if stored_bytes + size > limits.quota_bytes {
+            return Err(Error::AttachmentQuotaExceeded {
+                used: stored_bytes,
+                limit: limits.quota_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_classification() {
+        assert!(is_url("https://figma.com/file/abc"));
+        assert!(is_url("s3://bucket/build.log"));
+        assert!(!is_url("./logs/build.log"));
+        assert!(!is_url("/tmp/a://b"));
+        assert_eq!(guess_mime_type("design.PNG"), Some("image/png"));
+        assert_eq!(
+            guess_mime_type("https://x.io/report.pdf?dl=1"),
+            Some("application/pdf")
+        );
+        assert_eq!(guess_mime_type("Makefile"), None);
+    }
+
+    #[test]
+    fn test_store_deduplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("build.log");
+        std::fs::write(&source, "error: linker failed\n").unwrap();
+
+        let store = AttachmentStore::new(dir.path().join("attachments"));
+        let hash = AttachmentStore::hash_file(&source).unwrap();
+        assert!(store.store(&source, &hash).unwrap());
+        assert!(!store.store(&source, &hash).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(store.path_for(&hash)).unwrap(),
+            "error: linker failed\n"
+        );
+        // Only the stored copy is left behind
+        assert_eq!(
+            std::fs::read_dir(dir.path().join("attachments"))
+                .unwrap()
+                .count(),
+            1
+        );
+        assert!(store.remove(&hash).unwrap());
+        assert!(!store.remove(&hash).unwrap());
+
+        let limits = AttachmentLimits {
+            max_file_bytes: 10,
+            quota_bytes: 15,
+        };
+        assert!(AttachmentStore::check_limits(&limits, 8, 0).is_ok());
+        assert!(matches!(
+            AttachmentStore::check_limits(&limits, 11, 0),
+            Err(Error::AttachmentTooLarge { .. })
+        ));
+        assert!(matches!(
+            AttachmentStore::check_limits(&limits, 8, 8),
+            Err(Error::AttachmentQuotaExceeded { .. })
+        ));
+    }
+}
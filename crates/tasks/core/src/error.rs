@@ -29,6 +29,27 @@ pub enum Error {
 
     #[error("Not initialized: {0}")]
     NotInitialized(String),
+
+    #[error("Attachment not found: {0}")]
+    AttachmentNotFound(String),
+
+    #[error("Attachment is {size} bytes, over the {limit} byte limit")]
+    AttachmentTooLarge { size: u64, limit: u64 },
+
+    #[error("Task {0} has no attachment {1}")]
+    NotAttached(TaskId, String),
+
+    #[error("Attachment storage quota exceeded: {used} of {limit} bytes in use")]
+    AttachmentQuotaExceeded { used: u64, limit: u64 },
+
+    #[error("Webhook not found: {0}")]
+    WebhookNotFound(i64),
+
+    #[error("Invalid webhook: {0}")]
+    InvalidWebhook(String),
+
+    #[error("Cannot watch the task store: {0}")]
+    Watch(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
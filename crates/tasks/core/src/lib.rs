@@ -6,6 +6,10 @@
 //! - Cycle detection in dependency graphs
 //! - Full-text search capabilities
 //! - Project-scoped and global task stores
+//! - Link and file attachments
+//! - Signed outbound webhooks for task events
+//! - Priorities, tags and time tracking, with next-task recommendations
+//! - Change notifications across processes
 //!
 //! # Example
 //!
@@ -17,20 +21,31 @@
 //! manager.update_status(id, TaskStatus::InProgress).unwrap();
 //! ```
 
+pub mod attachments;
 pub mod error;
 pub mod graph;
 mod migrations;
 pub mod service;
 pub mod storage;
 pub mod types;
+pub mod watch;
+pub mod webhooks;
 
+pub use attachments::AttachmentLimits;
 pub use error::{Error, Result};
 pub use service::TasksService;
 pub use storage::{SqliteTaskStorage, TaskStorage};
 pub use types::{
-    unix_timestamp_now, CreateTask, Task, TaskId, TaskStatus, TaskWithDependencies, TasksStatus,
-    COMPLETE_STATUSES_SQL,
+    normalize_tags, unix_timestamp_now, CreateTask, NextTask, Task, TaskAttachment, TaskId,
+    TaskStatus, TaskWithDependencies, TasksStatus, TimeEntry, COMPLETE_STATUSES_SQL,
 };
+pub use watch::StoreWatcher;
+pub use webhooks::{
+    DeliveryReport, DeliveryStatus, RetryPolicy, Webhook, WebhookDelivery, WebhookEvent,
+    WebhookTransport,
+};
+
+use attachments::AttachmentStore;
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -40,6 +55,11 @@ use std::sync::Arc;
 pub struct TaskManager {
     storage: Arc<dyn TaskStorage>,
     path: PathBuf,
+    /// Directory holding the database
+    store_dir: PathBuf,
+    attachments: AttachmentStore,
+    attachment_limits: AttachmentLimits,
+    retry_policy: RetryPolicy,
 }
 
 impl TaskManager {
@@ -53,6 +73,10 @@ impl TaskManager {
         Ok(Self {
             storage: Arc::new(storage),
             path: project_path.to_path_buf(),
+            attachments: AttachmentStore::new(tasks_dir.join("attachments")),
+            store_dir: tasks_dir,
+            attachment_limits: AttachmentLimits::default(),
+            retry_policy: RetryPolicy::default(),
         })
     }
 
@@ -65,10 +89,44 @@ impl TaskManager {
 
         Ok(Self {
             storage: Arc::new(storage),
+            attachments: AttachmentStore::new(global_dir.join("attachments")),
+            attachment_limits: AttachmentLimits::default(),
+            retry_policy: RetryPolicy::default(),
+            store_dir: global_dir.clone(),
             path: global_dir,
         })
     }
 
+    #[must_use]
+    pub fn with_attachment_limits(mut self, limits: AttachmentLimits) -> Self {
+        self.attachment_limits = limits;
+        self
+    }
+
+    #[must_use]
+    pub fn with_webhook_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// A second handle on the same store, for callers that must not hold on
+    /// to this one (e.g. behind a lock) for long.
+    pub fn reopen(&self) -> Result<Self> {
+        let manager = if self.is_global() {
+            Self::open_global()?
+        } else {
+            Self::open(&self.path)?
+        };
+        Ok(manager
+            .with_attachment_limits(self.attachment_limits)
+            .with_webhook_retry_policy(self.retry_policy))
+    }
+
+    /// Watch the store for changes made by any process.
+    pub fn watch(&self) -> Result<StoreWatcher> {
+        StoreWatcher::new(&self.store_dir)
+    }
+
     #[must_use]
     pub fn global_path() -> PathBuf {
         dirs::data_local_dir()
@@ -91,11 +149,15 @@ impl TaskManager {
         let mut task = Task::new(&input.title);
         task.description = input.description;
         task.symbol_id = input.symbol_id;
+        task.priority = input.priority;
+        task.tags = normalize_tags(input.tags);
 
         let id = self.storage.create_task(&task)?;
+        task.id = id;
+        self.enqueue_webhooks(WebhookEvent::Created, &task, None)?;
 
         for dep_id in input.depends_on {
-            self.storage.add_dependency(id, dep_id)?;
+            self.add_dependency(id, dep_id)?;
         }
 
         Ok(id)
@@ -105,8 +167,25 @@ impl TaskManager {
         self.storage.get_task(id)
     }
 
+    /// Entering `InProgress` starts a time entry and leaving it stops one.
     pub fn update_task(&self, task: &Task) -> Result<()> {
-        self.storage.update_task(task)
+        let previous = self.storage.get_task(task.id)?.status;
+        self.storage.update_task(task)?;
+
+        if previous != task.status {
+            let now = unix_timestamp_now();
+            if task.status == TaskStatus::InProgress {
+                self.storage.start_time_entry(task.id, now)?;
+            } else if previous == TaskStatus::InProgress {
+                self.storage.stop_time_entry(task.id, now)?;
+            }
+
+            self.enqueue_webhooks(WebhookEvent::StatusChanged, task, Some(previous))?;
+            if task.status == TaskStatus::Blocked {
+                self.enqueue_webhooks(WebhookEvent::Blocked, task, Some(previous))?;
+            }
+        }
+        Ok(())
     }
 
     pub fn update_status(&self, id: TaskId, status: TaskStatus) -> Result<()> {
@@ -115,8 +194,12 @@ impl TaskManager {
         self.update_task(&task)
     }
 
+    /// Deletes a task, its attachments, and the stored files no other task
+    /// refers to.
     pub fn delete_task(&self, id: TaskId) -> Result<()> {
-        self.storage.delete_task(id)
+        let attachments = self.storage.get_attachments(id)?;
+        self.storage.delete_task(id)?;
+        self.release_files(&attachments)
     }
 
     pub fn list(&self) -> Result<Vec<Task>> {
@@ -134,7 +217,22 @@ impl TaskManager {
 
     /// Adds a dependency. Circular dependencies are allowed and tracked via [`detect_cycles`](Self::detect_cycles).
     pub fn add_dependency(&self, from: TaskId, to: TaskId) -> Result<()> {
-        self.storage.add_dependency(from, to)
+        if !self.has_webhooks_for(WebhookEvent::Blocked)? {
+            return self.storage.add_dependency(from, to);
+        }
+
+        let was_waiting = self
+            .storage
+            .get_dependencies(from)?
+            .iter()
+            .any(|dep| !dep.status.is_complete());
+        self.storage.add_dependency(from, to)?;
+
+        if !was_waiting && !self.storage.get_task(to)?.status.is_complete() {
+            let task = self.storage.get_task(from)?;
+            self.enqueue_webhooks(WebhookEvent::Blocked, &task, None)?;
+        }
+        Ok(())
     }
 
     pub fn remove_dependency(&self, from: TaskId, to: TaskId) -> Result<()> {
@@ -157,10 +255,336 @@ impl TaskManager {
         Ok(TaskWithDependencies {
             depends_on: self.storage.get_dependencies(id)?,
             dependents: self.storage.get_dependents(id)?,
+            attachments: self.storage.get_attachments(id)?,
+            tracked_secs: self.tracked_secs(id)?,
             task,
         })
     }
 
+    /// Recommends up to `limit` tasks to pick up now: todo tasks with every
+    /// dependency complete, highest priority first, then oldest. With `tag`,
+    /// only tasks carrying it are considered.
+    pub fn next_tasks(&self, tag: Option<&str>, limit: usize) -> Result<Vec<NextTask>> {
+        let mut ready: Vec<Task> = self
+            .storage
+            .get_ready_tasks()?
+            .into_iter()
+            .filter(|t| t.status == TaskStatus::Todo)
+            .filter(|t| tag.is_none_or(|tag| t.has_tag(tag)))
+            .collect();
+        ready.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.created_at.cmp(&b.created_at))
+                .then(a.id.get().cmp(&b.id.get()))
+        });
+
+        ready
+            .iter()
+            .enumerate()
+            .take(limit)
+            .map(|(i, task)| {
+                let unblocks = self
+                    .storage
+                    .get_dependents(task.id)?
+                    .iter()
+                    .filter(|t| !t.status.is_complete())
+                    .count();
+                Ok(NextTask {
+                    completed_dependencies: self.storage.get_dependencies(task.id)?.len(),
+                    unblocks,
+                    candidates: ready.len(),
+                    same_priority: ready[i + 1..]
+                        .iter()
+                        .filter(|t| t.priority == task.priority)
+                        .count(),
+                    task: task.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Moves a task to `InProgress`, which starts its time tracking.
+    pub fn start_task(&self, id: TaskId) -> Result<Task> {
+        self.update_status(id, TaskStatus::InProgress)?;
+        self.get_task(id)
+    }
+
+    pub fn time_entries(&self, id: TaskId) -> Result<Vec<TimeEntry>> {
+        self.get_task(id)?;
+        self.storage.get_time_entries(id)
+    }
+
+    /// Total seconds spent in progress, counting a running entry up to now.
+    pub fn tracked_secs(&self, id: TaskId) -> Result<i64> {
+        let now = unix_timestamp_now();
+        Ok(self
+            .storage
+            .get_time_entries(id)?
+            .iter()
+            .map(|e| e.duration_secs(now))
+            .sum())
+    }
+
+    /// Attaches a URL or a local file. Files are copied into the store's
+    /// content-addressed `attachments/` directory, within the attachment limits.
+    pub fn attach(
+        &self,
+        id: TaskId,
+        target: &str,
+        added_by: Option<&str>,
+    ) -> Result<TaskAttachment> {
+        self.get_task(id)?;
+
+        let mut attachment = TaskAttachment {
+            id: 0,
+            task_id: id,
+            target: target.to_string(),
+            content_hash: None,
+            mime_type: attachments::guess_mime_type(target).map(String::from),
+            size_bytes: None,
+            added_by: added_by.map(String::from),
+            added_at: unix_timestamp_now(),
+        };
+
+        if !attachments::is_url(target) {
+            let path = Path::new(target);
+            let size = std::fs::metadata(path)
+                .ok()
+                .filter(|m| m.is_file())
+                .ok_or_else(|| Error::AttachmentNotFound(target.to_string()))?
+                .len();
+            let hash = AttachmentStore::hash_file(path)?;
+
+            if !self.attachments.path_for(&hash).is_file() {
+                let stored = self.storage.stored_attachment_bytes()?;
+                AttachmentStore::check_limits(&self.attachment_limits, size, stored)?;
+                self.attachments.store(path, &hash)?;
+            }
+
+            let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            attachment.target = path.display().to_string();
+            attachment.content_hash = Some(hash);
+            attachment.size_bytes = Some(size);
+        }
+
+        attachment.id = self.storage.add_attachment(&attachment)?;
+        Ok(attachment)
+    }
+
+    pub fn get_attachments(&self, id: TaskId) -> Result<Vec<TaskAttachment>> {
+        self.get_task(id)?;
+        self.storage.get_attachments(id)
+    }
+
+    /// Removes the attachments of `id` whose target is `target`, as given to
+    /// [`attach`](Self::attach), and the stored files nothing else refers to.
+    pub fn detach(&self, id: TaskId, target: &str) -> Result<Vec<TaskAttachment>> {
+        let canonical = if attachments::is_url(target) {
+            None
+        } else {
+            Path::new(target)
+                .canonicalize()
+                .ok()
+                .map(|p| p.display().to_string())
+        };
+        let removed: Vec<TaskAttachment> = self
+            .get_attachments(id)?
+            .into_iter()
+            .filter(|a| a.target == target || canonical.as_deref() == Some(a.target.as_str()))
+            .collect();
+        if removed.is_empty() {
+            return Err(Error::NotAttached(id, target.to_string()));
+        }
+
+        for attachment in &removed {
+            self.storage.remove_attachment(attachment.id)?;
+        }
+        self.release_files(&removed)?;
+        Ok(removed)
+    }
+
+    /// Deletes the stored files of removed attachments unless another
+    /// attachment still refers to them.
+    fn release_files(&self, removed: &[TaskAttachment]) -> Result<()> {
+        for hash in removed.iter().filter_map(|a| a.content_hash.as_deref()) {
+            if !self.storage.attachment_hash_in_use(hash)? {
+                self.attachments.remove(hash)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Where the stored copy of a file attachment lives; `None` for URLs.
+    #[must_use]
+    pub fn attachment_path(&self, attachment: &TaskAttachment) -> Option<PathBuf> {
+        attachment
+            .content_hash
+            .as_deref()
+            .map(|hash| self.attachments.path_for(hash))
+    }
+
+    /// Registers a webhook for `events` (all events if empty). A secret is
+    /// generated when none is given.
+    pub fn add_webhook(
+        &self,
+        url: &str,
+        events: &[WebhookEvent],
+        secret: Option<&str>,
+    ) -> Result<Webhook> {
+        webhooks::validate_url(url)?;
+
+        let mut webhook = Webhook {
+            id: 0,
+            url: url.to_string(),
+            events: if events.is_empty() {
+                WebhookEvent::ALL.to_vec()
+            } else {
+                events.to_vec()
+            },
+            secret: match secret {
+                Some(secret) if !secret.is_empty() => secret.to_string(),
+                Some(_) => return Err(Error::InvalidWebhook("secret must not be empty".into())),
+                None => webhooks::generate_secret()?,
+            },
+            created_at: unix_timestamp_now(),
+        };
+        webhook.id = self.storage.add_webhook(&webhook)?;
+        Ok(webhook)
+    }
+
+    pub fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        self.storage.list_webhooks()
+    }
+
+    pub fn remove_webhook(&self, id: i64) -> Result<()> {
+        self.storage.remove_webhook(id)
+    }
+
+    /// The delivery log, newest first.
+    pub fn webhook_deliveries(
+        &self,
+        webhook_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<WebhookDelivery>> {
+        self.storage.list_webhook_deliveries(webhook_id, limit)
+    }
+
+    /// Sends the deliveries that are due, oldest first and at most `limit` of
+    /// them, scheduling retries for failures.
+    pub async fn deliver_webhooks(
+        &self,
+        transport: &dyn WebhookTransport,
+        limit: Option<usize>,
+    ) -> Result<DeliveryReport> {
+        let mut report = DeliveryReport::default();
+        let due = self
+            .storage
+            .due_webhook_deliveries(unix_timestamp_now(), limit)?;
+        if due.is_empty() {
+            return Ok(report);
+        }
+
+        let hooks: HashMap<i64, Webhook> = self
+            .storage
+            .list_webhooks()?
+            .into_iter()
+            .map(|w| (w.id, w))
+            .collect();
+
+        for mut delivery in due {
+            let Some(webhook) = hooks.get(&delivery.webhook_id) else {
+                continue;
+            };
+
+            let timestamp = unix_timestamp_now();
+            let headers = [
+                (webhooks::EVENT_HEADER, delivery.event.to_string()),
+                (webhooks::DELIVERY_HEADER, delivery.id.to_string()),
+                (webhooks::TIMESTAMP_HEADER, timestamp.to_string()),
+                (
+                    webhooks::SIGNATURE_HEADER,
+                    format!(
+                        "sha256={}",
+                        webhooks::sign(&webhook.secret, timestamp, &delivery.payload)
+                    ),
+                ),
+            ];
+            let result = transport
+                .post(&webhook.url, &headers, &delivery.payload)
+                .await;
+
+            let now = unix_timestamp_now();
+            delivery.attempts += 1;
+            delivery.updated_at = now;
+            (delivery.response_code, delivery.error) = match result {
+                Ok(code) if (200..300).contains(&code) => (Some(code), None),
+                Ok(code) => (Some(code), Some(format!("HTTP {}", code))),
+                Err(e) => (None, Some(e)),
+            };
+
+            if delivery.error.is_none() {
+                delivery.status = DeliveryStatus::Delivered;
+                report.delivered += 1;
+            } else if delivery.attempts >= self.retry_policy.max_attempts {
+                delivery.status = DeliveryStatus::Failed;
+                report.failed += 1;
+            } else {
+                delivery.next_attempt_at =
+                    now + self.retry_policy.delay_after(delivery.attempts) as i64;
+                report.retrying += 1;
+            }
+            self.storage.update_webhook_delivery(&delivery)?;
+        }
+
+        Ok(report)
+    }
+
+    fn has_webhooks_for(&self, event: WebhookEvent) -> Result<bool> {
+        Ok(self
+            .storage
+            .list_webhooks()?
+            .iter()
+            .any(|w| w.subscribes_to(event)))
+    }
+
+    fn enqueue_webhooks(
+        &self,
+        event: WebhookEvent,
+        task: &Task,
+        previous_status: Option<TaskStatus>,
+    ) -> Result<()> {
+        let hooks: Vec<Webhook> = self
+            .storage
+            .list_webhooks()?
+            .into_iter()
+            .filter(|w| w.subscribes_to(event))
+            .collect();
+        if hooks.is_empty() {
+            return Ok(());
+        }
+
+        let now = unix_timestamp_now();
+        let payload = webhooks::payload(event, task, previous_status, now)?;
+        for webhook in hooks {
+            self.storage.add_webhook_delivery(&WebhookDelivery {
+                id: 0,
+                webhook_id: webhook.id,
+                event,
+                task_id: task.id,
+                payload: payload.clone(),
+                status: DeliveryStatus::Pending,
+                attempts: 0,
+                next_attempt_at: now,
+                response_code: None,
+                error: None,
+                created_at: now,
+                updated_at: now,
+            })?;
+        }
+        Ok(())
+    }
+
     /// Returns tasks with no incomplete dependencies (ready to start).
     pub fn get_ready(&self) -> Result<Vec<Task>> {
         self.storage.get_ready_tasks()
@@ -379,6 +803,26 @@ mod tests {
         assert_eq!(status.in_progress_count, 1);
     }
 
+    #[test]
+    fn test_watch_sees_other_handles() {
+        let dir = tempdir().unwrap();
+        let manager = TaskManager::open(dir.path()).unwrap();
+        let watcher = manager.watch().unwrap();
+        assert!(!watcher.changed());
+
+        let other = manager.reopen().unwrap();
+        other
+            .create_task(CreateTask::new("From elsewhere"))
+            .unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !watcher.changed() {
+            assert!(std::time::Instant::now() < deadline, "no change noticed");
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(manager.list().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_task_manager_collection() {
         let dir1 = tempdir().unwrap();
@@ -404,6 +848,247 @@ mod tests {
         assert_eq!(status.total_tasks, 2);
     }
 
+    #[test]
+    fn test_attachments() {
+        let dir = tempdir().unwrap();
+        let manager = TaskManager::open(dir.path())
+            .unwrap()
+            .with_attachment_limits(AttachmentLimits {
+                max_file_bytes: 64,
+                quota_bytes: 64,
+            });
+        let id = manager.create_task(CreateTask::new("Fix build")).unwrap();
+
+        let link = manager
+            .attach(id, "https://ci.example.com/runs/42/log.txt", Some("ada"))
+            .unwrap();
+        assert!(!link.is_file());
+        assert_eq!(link.mime_type.as_deref(), Some("text/plain"));
+
+        let log = dir.path().join("build.log");
+        std::fs::write(&log, "linker failed\n").unwrap();
+        let file = manager.attach(id, log.to_str().unwrap(), None).unwrap();
+        let stored = manager.attachment_path(&file).unwrap();
+        assert!(stored.starts_with(dir.path().join(".adi/tasks/attachments")));
+        assert_eq!(std::fs::read_to_string(stored).unwrap(), "linker failed\n");
+
+        // Same content again is deduplicated and does not count twice
+        manager.attach(id, log.to_str().unwrap(), None).unwrap();
+        let big = dir.path().join("core.dump");
+        std::fs::write(&big, [0u8; 60]).unwrap();
+        assert!(matches!(
+            manager.attach(id, big.to_str().unwrap(), None),
+            Err(Error::AttachmentQuotaExceeded { .. })
+        ));
+        assert!(matches!(
+            manager.attach(id, "missing.log", None),
+            Err(Error::AttachmentNotFound(_))
+        ));
+
+        let with_deps = manager.get_task_with_dependencies(id).unwrap();
+        assert_eq!(with_deps.attachments.len(), 3);
+        assert_eq!(with_deps.attachments[0], link);
+
+        // Detaching drops both copies of the log; its file stays while
+        // another task still refers to it
+        let other = manager.create_task(CreateTask::new("Fix CI")).unwrap();
+        manager.attach(other, log.to_str().unwrap(), None).unwrap();
+        let stored = manager.attachment_path(&file).unwrap();
+        assert_eq!(manager.detach(id, log.to_str().unwrap()).unwrap().len(), 2);
+        assert!(stored.is_file());
+        assert!(matches!(
+            manager.detach(id, log.to_str().unwrap()),
+            Err(Error::NotAttached(..))
+        ));
+        manager.detach(id, &link.target).unwrap();
+        assert!(manager.get_attachments(id).unwrap().is_empty());
+
+        manager.delete_task(other).unwrap();
+        assert!(!stored.exists());
+    }
+
+    type Request = (String, Vec<(&'static str, String)>, String);
+
+    /// Answers each URL with the next queued result, recording requests.
+    struct ScriptedTransport {
+        results: std::sync::Mutex<HashMap<String, Vec<std::result::Result<u16, String>>>>,
+        requests: std::sync::Mutex<Vec<Request>>,
+    }
+
+    #[async_trait::async_trait]
+    impl WebhookTransport for ScriptedTransport {
+        async fn post(
+            &self,
+            url: &str,
+            headers: &[(&'static str, String)],
+            body: &str,
+        ) -> std::result::Result<u16, String> {
+            self.requests.lock().unwrap().push((
+                url.to_string(),
+                headers.to_vec(),
+                body.to_string(),
+            ));
+            self.results.lock().unwrap().get_mut(url).unwrap().remove(0)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_deliveries() {
+        let dir = tempdir().unwrap();
+        let manager = TaskManager::open(dir.path())
+            .unwrap()
+            .with_webhook_retry_policy(RetryPolicy {
+                max_attempts: 2,
+                initial_delay_secs: 0,
+                max_delay_secs: 0,
+            });
+        let chat = manager
+            .add_webhook(
+                "https://chat.example.com/hook",
+                &[WebhookEvent::Created, WebhookEvent::StatusChanged],
+                Some("s3cret"),
+            )
+            .unwrap();
+        manager
+            .add_webhook(
+                "https://tracker.example.com/hook",
+                &[WebhookEvent::Blocked],
+                None,
+            )
+            .unwrap();
+        assert!(matches!(
+            manager.add_webhook("ftp://example.com", &[], None),
+            Err(Error::InvalidWebhook(_))
+        ));
+
+        let id = manager.create_task(CreateTask::new("Ship it")).unwrap();
+        manager.update_status(id, TaskStatus::Blocked).unwrap();
+
+        let transport = ScriptedTransport {
+            results: std::sync::Mutex::new(HashMap::from([
+                (
+                    "https://chat.example.com/hook".to_string(),
+                    vec![Ok(503), Ok(204), Ok(200)],
+                ),
+                (
+                    "https://tracker.example.com/hook".to_string(),
+                    vec![Err("connection refused".to_string()), Ok(500)],
+                ),
+            ])),
+            requests: std::sync::Mutex::new(Vec::new()),
+        };
+
+        let report = manager.deliver_webhooks(&transport, None).await.unwrap();
+        assert_eq!(
+            report,
+            DeliveryReport {
+                delivered: 1,
+                retrying: 2,
+                failed: 0
+            }
+        );
+        let report = manager.deliver_webhooks(&transport, None).await.unwrap();
+        assert_eq!(
+            report,
+            DeliveryReport {
+                delivered: 1,
+                retrying: 0,
+                failed: 1
+            }
+        );
+        let report = manager.deliver_webhooks(&transport, None).await.unwrap();
+        assert_eq!(report, DeliveryReport::default());
+
+        let (_, headers, body) = transport.requests.lock().unwrap()[0].clone();
+        let header = |name: &str| headers.iter().find(|(n, _)| *n == name).unwrap().1.clone();
+        let timestamp: i64 = header(webhooks::TIMESTAMP_HEADER).parse().unwrap();
+        assert_eq!(header(webhooks::EVENT_HEADER), "created");
+        assert_eq!(
+            header(webhooks::SIGNATURE_HEADER),
+            format!("sha256={}", webhooks::sign("s3cret", timestamp, &body))
+        );
+
+        let log = manager.webhook_deliveries(Some(chat.id), 10).unwrap();
+        let summary: Vec<_> = log
+            .iter()
+            .map(|d| (d.event, d.status, d.attempts))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (WebhookEvent::StatusChanged, DeliveryStatus::Delivered, 1),
+                (WebhookEvent::Created, DeliveryStatus::Delivered, 2),
+            ]
+        );
+        let blocked = &manager.webhook_deliveries(None, 10).unwrap()[0];
+        assert_eq!(blocked.status, DeliveryStatus::Failed);
+        assert_eq!(blocked.response_code, Some(500));
+        assert!(blocked.payload.contains(r#""previous_status":"todo""#));
+
+        manager.remove_webhook(chat.id).unwrap();
+        assert_eq!(manager.webhook_deliveries(None, 10).unwrap().len(), 1);
+        assert!(matches!(
+            manager.remove_webhook(chat.id),
+            Err(Error::WebhookNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery_limit() {
+        let dir = tempdir().unwrap();
+        let manager = TaskManager::open(dir.path()).unwrap();
+        manager
+            .add_webhook(
+                "https://chat.example.com/hook",
+                &[WebhookEvent::Created],
+                None,
+            )
+            .unwrap();
+        for title in ["One", "Two", "Three"] {
+            manager.create_task(CreateTask::new(title)).unwrap();
+        }
+
+        let transport = ScriptedTransport {
+            results: std::sync::Mutex::new(HashMap::from([(
+                "https://chat.example.com/hook".to_string(),
+                vec![Ok(200); 3],
+            )])),
+            requests: std::sync::Mutex::new(Vec::new()),
+        };
+        let report = manager.deliver_webhooks(&transport, Some(2)).await.unwrap();
+        assert_eq!(report.delivered, 2);
+        assert!(transport.requests.lock().unwrap()[0].2.contains("One"));
+        let report = manager.deliver_webhooks(&transport, None).await.unwrap();
+        assert_eq!(report.delivered, 1);
+        assert!(transport.requests.lock().unwrap()[2].2.contains("Three"));
+    }
+
+    #[test]
+    fn test_dependency_on_open_task_fires_blocked() {
+        let dir = tempdir().unwrap();
+        let manager = TaskManager::open(dir.path()).unwrap();
+        manager
+            .add_webhook(
+                "http://localhost:9000/hook",
+                &[WebhookEvent::Blocked],
+                Some("k"),
+            )
+            .unwrap();
+
+        let first = manager.create_task(CreateTask::new("Design")).unwrap();
+        let second = manager.create_task(CreateTask::new("Review")).unwrap();
+        let build = manager
+            .create_task(CreateTask::new("Build").with_dependencies(vec![first]))
+            .unwrap();
+        // Already waiting on `first`, so no second event
+        manager.add_dependency(build, second).unwrap();
+
+        let deliveries = manager.webhook_deliveries(None, 10).unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].task_id, build);
+        assert_eq!(deliveries[0].event, WebhookEvent::Blocked);
+    }
+
     #[test]
     fn test_circular_dependencies_allowed() {
         let dir = tempdir().unwrap();
@@ -418,4 +1103,62 @@ mod tests {
         let cycles = manager.detect_cycles().unwrap();
         assert!(!cycles.is_empty());
     }
+
+    #[test]
+    fn test_next_tasks() {
+        let dir = tempdir().unwrap();
+        let manager = TaskManager::open(dir.path()).unwrap();
+
+        let old = manager
+            .create_task(CreateTask::new("Cleanup").with_tags(vec!["backend".into()]))
+            .unwrap();
+        let schema = manager
+            .create_task(
+                CreateTask::new("Schema")
+                    .with_priority(1)
+                    .with_tags(vec!["backend".into()]),
+            )
+            .unwrap();
+        let docs = manager
+            .create_task(
+                CreateTask::new("Docs")
+                    .with_priority(1)
+                    .with_tags(vec!["docs".into()]),
+            )
+            .unwrap();
+        let ui = manager
+            .create_task(
+                CreateTask::new("UI")
+                    .with_priority(2)
+                    .with_dependencies(vec![schema]),
+            )
+            .unwrap();
+
+        // `ui` outranks everything but waits on `schema`
+        let next = manager.next_tasks(None, 5).unwrap();
+        let ids: Vec<TaskId> = next.iter().map(|n| n.task.id).collect();
+        assert_eq!(ids, vec![schema, docs, old]);
+        assert_eq!(next[0].candidates, 3);
+        assert_eq!(next[0].same_priority, 1);
+        assert_eq!(next[0].unblocks, 1);
+
+        let backend = manager.next_tasks(Some("Backend"), 1).unwrap();
+        assert_eq!(backend.len(), 1);
+        assert_eq!(backend[0].task.id, schema);
+
+        let started = manager.start_task(schema).unwrap();
+        assert_eq!(started.status, TaskStatus::InProgress);
+        let entries = manager.time_entries(schema).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].ended_at.is_none());
+        assert_eq!(manager.next_tasks(None, 1).unwrap()[0].task.id, docs);
+
+        manager.update_status(schema, TaskStatus::Done).unwrap();
+        assert!(manager.time_entries(schema).unwrap()[0].ended_at.is_some());
+
+        let next = manager.next_tasks(None, 1).unwrap();
+        assert_eq!(next[0].task.id, ui);
+        assert_eq!(next[0].completed_dependencies, 1);
+        assert!(manager.next_tasks(Some("ops"), 1).unwrap().is_empty());
+    }
 }
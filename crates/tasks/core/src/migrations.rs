@@ -1,7 +1,7 @@
 use lib_migrations::SqlMigration;
 
 pub fn migrations() -> Vec<SqlMigration> {
-    vec![migration_v1()]
+    vec![migration_v1(), migration_v2(), migration_v3(), migration_v4()]
 }
 
 fn migration_v1() -> SqlMigration {
@@ -79,3 +79,109 @@ fn migration_v1() -> SqlMigration {
         "#,
     )
 }
+
+fn migration_v2() -> SqlMigration {
+    SqlMigration::new(
+        2,
+        "task_attachments",
+        r#"
+        -- Links and content-addressed file copies attached to tasks
+        CREATE TABLE IF NOT EXISTS task_attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            target TEXT NOT NULL,
+            content_hash TEXT,
+            mime_type TEXT,
+            size_bytes INTEGER,
+            added_by TEXT,
+            added_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_attachments_task ON task_attachments(task_id);
+        CREATE INDEX IF NOT EXISTS idx_attachments_hash ON task_attachments(content_hash);
+        "#,
+    )
+    .with_down(
+        r#"
+        DROP INDEX IF EXISTS idx_attachments_hash;
+        DROP INDEX IF EXISTS idx_attachments_task;
+        DROP TABLE IF EXISTS task_attachments;
+        "#,
+    )
+}
+
+fn migration_v3() -> SqlMigration {
+    SqlMigration::new(
+        3,
+        "task_webhooks",
+        r#"
+        -- Outbound webhooks; events is a comma-separated list of event names
+        CREATE TABLE IF NOT EXISTS task_webhooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL,
+            events TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+
+        -- Delivery outbox and log (task_id is kept after the task is deleted)
+        CREATE TABLE IF NOT EXISTS task_webhook_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            webhook_id INTEGER NOT NULL REFERENCES task_webhooks(id) ON DELETE CASCADE,
+            event TEXT NOT NULL,
+            task_id INTEGER NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at INTEGER NOT NULL,
+            response_code INTEGER,
+            error TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_deliveries_due ON task_webhook_deliveries(status, next_attempt_at);
+        CREATE INDEX IF NOT EXISTS idx_deliveries_webhook ON task_webhook_deliveries(webhook_id);
+        "#,
+    )
+    .with_down(
+        r#"
+        DROP INDEX IF EXISTS idx_deliveries_webhook;
+        DROP INDEX IF EXISTS idx_deliveries_due;
+        DROP TABLE IF EXISTS task_webhook_deliveries;
+        DROP TABLE IF EXISTS task_webhooks;
+        "#,
+    )
+}
+
+fn migration_v4() -> SqlMigration {
+    SqlMigration::new(
+        4,
+        "task_priority_tags_time",
+        r#"
+        -- Higher priority runs first; tags is a comma-separated list
+        ALTER TABLE tasks ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE tasks ADD COLUMN tags TEXT NOT NULL DEFAULT '';
+
+        -- Time spent in progress; ended_at is NULL while the task is still in progress
+        CREATE TABLE IF NOT EXISTS task_time_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            started_at INTEGER NOT NULL,
+            ended_at INTEGER
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_tasks_priority ON tasks(priority);
+        CREATE INDEX IF NOT EXISTS idx_time_entries_task ON task_time_entries(task_id);
+        "#,
+    )
+    .with_down(
+        r#"
+        DROP INDEX IF EXISTS idx_time_entries_task;
+        DROP INDEX IF EXISTS idx_tasks_priority;
+        DROP TABLE IF EXISTS task_time_entries;
+        ALTER TABLE tasks DROP COLUMN tags;
+        ALTER TABLE tasks DROP COLUMN priority;
+        "#,
+    )
+}
@@ -54,6 +54,8 @@ impl TasksService {
             "title": task.title,
             "description": task.description,
             "status": task.status.to_string(),
+            "priority": task.priority,
+            "tags": task.tags,
             "created_at": task.created_at,
             "updated_at": task.updated_at
         })
@@ -97,11 +99,24 @@ impl TasksService {
             })
             .unwrap_or_default();
 
+        let tags: Vec<String> = params
+            .get("tags")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
         let mut create_task = CreateTask::new(title);
         if let Some(desc) = description {
             create_task = create_task.with_description(desc);
         }
-        create_task = create_task.with_dependencies(depends_on);
+        if let Some(priority) = params.get("priority").and_then(|v| v.as_i64()) {
+            create_task = create_task.with_priority(priority);
+        }
+        create_task = create_task.with_dependencies(depends_on).with_tags(tags);
 
         let manager = self.manager.lock().await;
         let task_id = manager
@@ -383,6 +398,12 @@ impl AdiService for TasksService {
                             "type": "array",
                             "items": { "type": "integer" },
                             "description": "IDs of tasks this task depends on"
+                        },
+                        "priority": { "type": "integer", "description": "Higher runs first, default 0" },
+                        "tags": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Tags for filtering"
                         }
                     }
                 })),
@@ -4,7 +4,8 @@ mod sqlite;
 pub use sqlite::SqliteTaskStorage;
 
 use crate::error::Result;
-use crate::types::{Task, TaskId, TaskStatus, TasksStatus};
+use crate::types::{Task, TaskAttachment, TaskId, TaskStatus, TasksStatus, TimeEntry};
+use crate::webhooks::{Webhook, WebhookDelivery};
 
 /// Implementations must be thread-safe (`Send + Sync`).
 pub trait TaskStorage: Send + Sync {
@@ -29,4 +30,50 @@ pub trait TaskStorage: Send + Sync {
 
     fn get_all_dependencies(&self) -> Result<Vec<(TaskId, TaskId)>>;
     fn get_status(&self) -> Result<TasksStatus>;
+
+    /// Assigns the attachment's `id`, ignoring the one passed in.
+    fn add_attachment(&self, attachment: &TaskAttachment) -> Result<i64>;
+    fn get_attachments(&self, task_id: TaskId) -> Result<Vec<TaskAttachment>>;
+    fn remove_attachment(&self, id: i64) -> Result<()>;
+
+    /// Whether any attachment still refers to the stored file `content_hash`.
+    fn attachment_hash_in_use(&self, content_hash: &str) -> Result<bool>;
+
+    /// Bytes held by stored files, counting each content hash once.
+    fn stored_attachment_bytes(&self) -> Result<u64>;
+
+    /// Opens a time entry unless one is already running; returns its id.
+    fn start_time_entry(&self, task_id: TaskId, started_at: i64) -> Result<i64>;
+
+    /// Closes the running time entry, if any; returns whether one was open.
+    fn stop_time_entry(&self, task_id: TaskId, ended_at: i64) -> Result<bool>;
+
+    /// Oldest first.
+    fn get_time_entries(&self, task_id: TaskId) -> Result<Vec<TimeEntry>>;
+
+    /// Assigns the webhook's `id`, ignoring the one passed in.
+    fn add_webhook(&self, webhook: &Webhook) -> Result<i64>;
+    fn list_webhooks(&self) -> Result<Vec<Webhook>>;
+
+    /// Also removes the webhook's deliveries.
+    fn remove_webhook(&self, id: i64) -> Result<()>;
+
+    /// Assigns the delivery's `id`, ignoring the one passed in.
+    fn add_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<i64>;
+    fn update_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<()>;
+
+    /// Pending deliveries with `next_attempt_at <= now`, oldest first, at
+    /// most `limit` of them.
+    fn due_webhook_deliveries(
+        &self,
+        now: i64,
+        limit: Option<usize>,
+    ) -> Result<Vec<WebhookDelivery>>;
+
+    /// Newest first, optionally for one webhook.
+    fn list_webhook_deliveries(
+        &self,
+        webhook_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<WebhookDelivery>>;
 }
@@ -1,9 +1,12 @@
 use crate::error::{Error, Result};
 use crate::migrations::migrations;
 use crate::storage::TaskStorage;
-use crate::types::{unix_timestamp_now, Task, TaskId, TaskStatus, TasksStatus};
+use crate::types::{
+    unix_timestamp_now, Task, TaskAttachment, TaskId, TaskStatus, TasksStatus, TimeEntry,
+};
+use crate::webhooks::{DeliveryStatus, Webhook, WebhookDelivery, WebhookEvent};
 use lib_migrations::{MigrationRunner, SqliteMigrationBackend};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::path::Path;
 use std::sync::{Mutex, MutexGuard};
 
@@ -61,25 +64,92 @@ impl SqliteTaskStorage {
             status,
             symbol_id: row.get(4)?,
             project_path: row.get(5)?,
+            priority: row.get(8)?,
+            tags: split_tags(&row.get::<_, String>(9)?),
             created_at: row.get(6)?,
             updated_at: row.get(7)?,
         })
     }
+
+    fn row_to_time_entry(row: &rusqlite::Row) -> rusqlite::Result<TimeEntry> {
+        Ok(TimeEntry {
+            id: row.get(0)?,
+            task_id: TaskId::new(row.get(1)?),
+            started_at: row.get(2)?,
+            ended_at: row.get(3)?,
+        })
+    }
+
+    fn row_to_attachment(row: &rusqlite::Row) -> rusqlite::Result<TaskAttachment> {
+        Ok(TaskAttachment {
+            id: row.get(0)?,
+            task_id: TaskId::new(row.get(1)?),
+            target: row.get(2)?,
+            content_hash: row.get(3)?,
+            mime_type: row.get(4)?,
+            size_bytes: row.get::<_, Option<i64>>(5)?.map(|s| s as u64),
+            added_by: row.get(6)?,
+            added_at: row.get(7)?,
+        })
+    }
+
+    fn row_to_webhook(row: &rusqlite::Row) -> rusqlite::Result<Webhook> {
+        let events: String = row.get(2)?;
+        Ok(Webhook {
+            id: row.get(0)?,
+            url: row.get(1)?,
+            events: events.split(',').filter_map(|e| e.parse().ok()).collect(),
+            secret: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    }
+
+    fn row_to_delivery(row: &rusqlite::Row) -> rusqlite::Result<WebhookDelivery> {
+        let event: String = row.get(2)?;
+        let status: String = row.get(5)?;
+        Ok(WebhookDelivery {
+            id: row.get(0)?,
+            webhook_id: row.get(1)?,
+            event: event.parse().unwrap_or(WebhookEvent::StatusChanged),
+            task_id: TaskId::new(row.get(3)?),
+            payload: row.get(4)?,
+            status: status.parse().unwrap_or(DeliveryStatus::Pending),
+            attempts: row.get(6)?,
+            next_attempt_at: row.get(7)?,
+            response_code: row.get(8)?,
+            error: row.get(9)?,
+            created_at: row.get(10)?,
+            updated_at: row.get(11)?,
+        })
+    }
 }
 
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+const DELIVERY_COLUMNS: &str =
+    "id, webhook_id, event, task_id, payload, status, attempts, next_attempt_at, \
+     response_code, error, created_at, updated_at";
+
 impl TaskStorage for SqliteTaskStorage {
     fn create_task(&self, task: &Task) -> Result<TaskId> {
         let conn = self.lock_conn()?;
 
         conn.execute(
-            r#"INSERT INTO tasks (title, description, status, symbol_id, project_path, created_at, updated_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+            r#"INSERT INTO tasks (title, description, status, symbol_id, project_path, priority, tags, created_at, updated_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"#,
             params![
                 task.title,
                 task.description,
                 task.status.as_str(),
                 task.symbol_id,
                 task.project_path,
+                task.priority,
+                task.tags.join(","),
                 task.created_at,
                 task.updated_at,
             ],
@@ -92,7 +162,7 @@ impl TaskStorage for SqliteTaskStorage {
         let conn = self.lock_conn()?;
 
         conn.query_row(
-            "SELECT id, title, description, status, symbol_id, project_path, created_at, updated_at
+            "SELECT id, title, description, status, symbol_id, project_path, created_at, updated_at, priority, tags
              FROM tasks WHERE id = ?1",
             params![id.get()],
             Self::row_to_task,
@@ -109,14 +179,17 @@ impl TaskStorage for SqliteTaskStorage {
 
         let rows = conn.execute(
             r#"UPDATE tasks
-               SET title = ?1, description = ?2, status = ?3, symbol_id = ?4, project_path = ?5, updated_at = ?6
-               WHERE id = ?7"#,
+               SET title = ?1, description = ?2, status = ?3, symbol_id = ?4, project_path = ?5,
+                   priority = ?6, tags = ?7, updated_at = ?8
+               WHERE id = ?9"#,
             params![
                 task.title,
                 task.description,
                 task.status.as_str(),
                 task.symbol_id,
                 task.project_path,
+                task.priority,
+                task.tags.join(","),
                 now,
                 task.id.get(),
             ],
@@ -146,7 +219,7 @@ impl TaskStorage for SqliteTaskStorage {
 
         if let Some(path) = project_path {
             let mut stmt = conn.prepare(
-                "SELECT id, title, description, status, symbol_id, project_path, created_at, updated_at
+                "SELECT id, title, description, status, symbol_id, project_path, created_at, updated_at, priority, tags
                  FROM tasks WHERE project_path = ?1 ORDER BY created_at DESC",
             )?;
             let tasks = stmt
@@ -156,7 +229,7 @@ impl TaskStorage for SqliteTaskStorage {
         }
 
         let mut stmt = conn.prepare(
-            "SELECT id, title, description, status, symbol_id, project_path, created_at, updated_at
+            "SELECT id, title, description, status, symbol_id, project_path, created_at, updated_at, priority, tags
              FROM tasks ORDER BY created_at DESC",
         )?;
         let tasks = stmt
@@ -170,7 +243,7 @@ impl TaskStorage for SqliteTaskStorage {
         let conn = self.lock_conn()?;
 
         let mut stmt = conn.prepare(
-            "SELECT id, title, description, status, symbol_id, project_path, created_at, updated_at
+            "SELECT id, title, description, status, symbol_id, project_path, created_at, updated_at, priority, tags
              FROM tasks WHERE status = ?1 ORDER BY created_at DESC",
         )?;
 
@@ -185,7 +258,7 @@ impl TaskStorage for SqliteTaskStorage {
         let conn = self.lock_conn()?;
 
         let mut stmt = conn.prepare(
-            "SELECT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at
+            "SELECT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at, t.priority, t.tags
              FROM tasks t
              JOIN tasks_fts fts ON t.id = fts.rowid
              WHERE tasks_fts MATCH ?1
@@ -254,7 +327,7 @@ impl TaskStorage for SqliteTaskStorage {
         let conn = self.lock_conn()?;
 
         let mut stmt = conn.prepare(
-            "SELECT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at
+            "SELECT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at, t.priority, t.tags
              FROM tasks t
              JOIN task_dependencies d ON t.id = d.to_task_id
              WHERE d.from_task_id = ?1",
@@ -271,7 +344,7 @@ impl TaskStorage for SqliteTaskStorage {
         let conn = self.lock_conn()?;
 
         let mut stmt = conn.prepare(
-            "SELECT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at
+            "SELECT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at, t.priority, t.tags
              FROM tasks t
              JOIN task_dependencies d ON t.id = d.from_task_id
              WHERE d.to_task_id = ?1",
@@ -303,7 +376,7 @@ impl TaskStorage for SqliteTaskStorage {
         let cancelled = TaskStatus::Cancelled.as_str();
 
         let mut stmt = conn.prepare(
-            r#"SELECT DISTINCT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at
+            r#"SELECT DISTINCT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at, t.priority, t.tags
                FROM tasks t
                JOIN task_dependencies d ON t.id = d.from_task_id
                JOIN tasks dep ON d.to_task_id = dep.id
@@ -325,7 +398,7 @@ impl TaskStorage for SqliteTaskStorage {
         let cancelled = TaskStatus::Cancelled.as_str();
 
         let mut stmt = conn.prepare(
-            r#"SELECT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at
+            r#"SELECT t.id, t.title, t.description, t.status, t.symbol_id, t.project_path, t.created_at, t.updated_at, t.priority, t.tags
                FROM tasks t
                WHERE t.status NOT IN (?1, ?2)
                  AND NOT EXISTS (
@@ -412,6 +485,270 @@ impl TaskStorage for SqliteTaskStorage {
             has_cycles: false, // Computed by graph module
         })
     }
+
+    fn add_attachment(&self, attachment: &TaskAttachment) -> Result<i64> {
+        let conn = self.lock_conn()?;
+
+        let task_exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)",
+            params![attachment.task_id.get()],
+            |row| row.get(0),
+        )?;
+
+        if !task_exists {
+            return Err(Error::TaskNotFound(attachment.task_id));
+        }
+
+        conn.execute(
+            r#"INSERT INTO task_attachments (task_id, target, content_hash, mime_type, size_bytes, added_by, added_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+            params![
+                attachment.task_id.get(),
+                attachment.target,
+                attachment.content_hash,
+                attachment.mime_type,
+                attachment.size_bytes.map(|s| s as i64),
+                attachment.added_by,
+                attachment.added_at,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn get_attachments(&self, task_id: TaskId) -> Result<Vec<TaskAttachment>> {
+        let conn = self.lock_conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, target, content_hash, mime_type, size_bytes, added_by, added_at
+             FROM task_attachments WHERE task_id = ?1 ORDER BY added_at ASC, id ASC",
+        )?;
+
+        let attachments = stmt
+            .query_map(params![task_id.get()], Self::row_to_attachment)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(attachments)
+    }
+
+    fn remove_attachment(&self, id: i64) -> Result<()> {
+        let conn = self.lock_conn()?;
+
+        let rows = conn.execute("DELETE FROM task_attachments WHERE id = ?1", params![id])?;
+
+        if rows == 0 {
+            return Err(Error::AttachmentNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn attachment_hash_in_use(&self, content_hash: &str) -> Result<bool> {
+        let conn = self.lock_conn()?;
+
+        let in_use = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM task_attachments WHERE content_hash = ?1)",
+            params![content_hash],
+            |row| row.get(0),
+        )?;
+
+        Ok(in_use)
+    }
+
+    fn stored_attachment_bytes(&self) -> Result<u64> {
+        let conn = self.lock_conn()?;
+
+        let bytes: i64 = conn.query_row(
+            "SELECT COALESCE(SUM(size_bytes), 0) FROM (
+                 SELECT MAX(size_bytes) AS size_bytes FROM task_attachments
+                 WHERE content_hash IS NOT NULL GROUP BY content_hash
+             )",
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(bytes as u64)
+    }
+
+    fn start_time_entry(&self, task_id: TaskId, started_at: i64) -> Result<i64> {
+        let conn = self.lock_conn()?;
+
+        let open: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM task_time_entries WHERE task_id = ?1 AND ended_at IS NULL",
+                params![task_id.get()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = open {
+            return Ok(id);
+        }
+
+        conn.execute(
+            "INSERT INTO task_time_entries (task_id, started_at) VALUES (?1, ?2)",
+            params![task_id.get(), started_at],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn stop_time_entry(&self, task_id: TaskId, ended_at: i64) -> Result<bool> {
+        let conn = self.lock_conn()?;
+
+        let rows = conn.execute(
+            "UPDATE task_time_entries SET ended_at = MAX(?2, started_at)
+             WHERE task_id = ?1 AND ended_at IS NULL",
+            params![task_id.get(), ended_at],
+        )?;
+
+        Ok(rows > 0)
+    }
+
+    fn get_time_entries(&self, task_id: TaskId) -> Result<Vec<TimeEntry>> {
+        let conn = self.lock_conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, started_at, ended_at
+             FROM task_time_entries WHERE task_id = ?1 ORDER BY started_at ASC, id ASC",
+        )?;
+
+        let entries = stmt
+            .query_map(params![task_id.get()], Self::row_to_time_entry)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    fn add_webhook(&self, webhook: &Webhook) -> Result<i64> {
+        let conn = self.lock_conn()?;
+
+        let events: Vec<&str> = webhook.events.iter().map(|e| e.as_str()).collect();
+        conn.execute(
+            "INSERT INTO task_webhooks (url, events, secret, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                webhook.url,
+                events.join(","),
+                webhook.secret,
+                webhook.created_at
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        let conn = self.lock_conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, url, events, secret, created_at FROM task_webhooks ORDER BY id ASC",
+        )?;
+
+        let webhooks = stmt
+            .query_map([], Self::row_to_webhook)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(webhooks)
+    }
+
+    fn remove_webhook(&self, id: i64) -> Result<()> {
+        let conn = self.lock_conn()?;
+
+        let rows = conn.execute("DELETE FROM task_webhooks WHERE id = ?1", params![id])?;
+
+        if rows == 0 {
+            return Err(Error::WebhookNotFound(id));
+        }
+
+        Ok(())
+    }
+
+    fn add_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<i64> {
+        let conn = self.lock_conn()?;
+
+        conn.execute(
+            r#"INSERT INTO task_webhook_deliveries
+               (webhook_id, event, task_id, payload, status, attempts, next_attempt_at, response_code, error, created_at, updated_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"#,
+            params![
+                delivery.webhook_id,
+                delivery.event.as_str(),
+                delivery.task_id.get(),
+                delivery.payload,
+                delivery.status.as_str(),
+                delivery.attempts,
+                delivery.next_attempt_at,
+                delivery.response_code,
+                delivery.error,
+                delivery.created_at,
+                delivery.updated_at,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn update_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let conn = self.lock_conn()?;
+
+        conn.execute(
+            r#"UPDATE task_webhook_deliveries
+               SET status = ?1, attempts = ?2, next_attempt_at = ?3, response_code = ?4, error = ?5, updated_at = ?6
+               WHERE id = ?7"#,
+            params![
+                delivery.status.as_str(),
+                delivery.attempts,
+                delivery.next_attempt_at,
+                delivery.response_code,
+                delivery.error,
+                delivery.updated_at,
+                delivery.id,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn due_webhook_deliveries(
+        &self,
+        now: i64,
+        limit: Option<usize>,
+    ) -> Result<Vec<WebhookDelivery>> {
+        let conn = self.lock_conn()?;
+
+        // A negative LIMIT means no limit
+        let limit = limit.map_or(-1, |l| l as i64);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM task_webhook_deliveries
+             WHERE status = 'pending' AND next_attempt_at <= ?1 ORDER BY id ASC LIMIT ?2",
+            DELIVERY_COLUMNS
+        ))?;
+
+        let deliveries = stmt
+            .query_map(params![now, limit], Self::row_to_delivery)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(deliveries)
+    }
+
+    fn list_webhook_deliveries(
+        &self,
+        webhook_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<WebhookDelivery>> {
+        let conn = self.lock_conn()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM task_webhook_deliveries
+             WHERE ?1 IS NULL OR webhook_id = ?1 ORDER BY id DESC LIMIT ?2",
+            DELIVERY_COLUMNS
+        ))?;
+
+        let deliveries = stmt
+            .query_map(params![webhook_id, limit as i64], Self::row_to_delivery)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(deliveries)
+    }
 }
 
 #[cfg(test)]
@@ -5,6 +5,9 @@
 //! - [`TaskStatus`] - Task lifecycle states
 //! - [`Task`] - The main task entity
 //! - [`CreateTask`] - Input DTO for creating tasks
+//! - [`TaskAttachment`] - Link or file attached to a task
+//! - [`TimeEntry`] - A span of time spent in progress on a task
+//! - [`NextTask`] - A recommended task and why it was picked
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -137,6 +140,11 @@ pub struct Task {
     pub symbol_id: Option<i64>,
     /// Project path for project-scoped tasks, None for global tasks.
     pub project_path: Option<String>,
+    /// Higher runs first; 0 is normal.
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -154,6 +162,8 @@ impl Task {
             status: TaskStatus::Todo,
             symbol_id: None,
             project_path: None,
+            priority: 0,
+            tags: vec![],
             created_at: now,
             updated_at: now,
         }
@@ -178,10 +188,48 @@ impl Task {
         self
     }
 
+    #[must_use]
+    pub fn with_priority(mut self, priority: i64) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    #[must_use]
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = normalize_tags(tags);
+        self
+    }
+
     #[must_use]
     pub fn is_global(&self) -> bool {
         self.project_path.is_none()
     }
+
+    /// Tags compare case-insensitively.
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+}
+
+/// Trims tags and drops empty and duplicate ones, keeping their order.
+/// Commas are not allowed inside a tag since storage joins on them.
+#[must_use]
+pub fn normalize_tags<I, S>(tags: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        for part in tag.as_ref().split(',') {
+            let part = part.trim();
+            if !part.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(part)) {
+                normalized.push(part.to_string());
+            }
+        }
+    }
+    normalized
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -191,6 +239,67 @@ pub struct TaskWithDependencies {
     pub depends_on: Vec<Task>,
     /// Tasks that depend on this task.
     pub dependents: Vec<Task>,
+    #[serde(default)]
+    pub attachments: Vec<TaskAttachment>,
+    /// Seconds spent in progress, including a running time entry.
+    #[serde(default)]
+    pub tracked_secs: i64,
+}
+
+/// A URL or local file attached to a task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskAttachment {
+    pub id: i64,
+    pub task_id: TaskId,
+    /// The URL, or the path the file was attached from.
+    pub target: String,
+    /// SHA-256 of the stored copy; `None` for URLs.
+    pub content_hash: Option<String>,
+    pub mime_type: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub added_by: Option<String>,
+    pub added_at: i64,
+}
+
+impl TaskAttachment {
+    #[must_use]
+    pub fn is_file(&self) -> bool {
+        self.content_hash.is_some()
+    }
+}
+
+/// Time spent on a task, opened when it goes in progress and closed when it
+/// leaves that status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub task_id: TaskId,
+    pub started_at: i64,
+    /// `None` while the task is still in progress.
+    pub ended_at: Option<i64>,
+}
+
+impl TimeEntry {
+    /// Length of the entry, counting a running one up to `now`.
+    #[must_use]
+    pub fn duration_secs(&self, now: i64) -> i64 {
+        (self.ended_at.unwrap_or(now) - self.started_at).max(0)
+    }
+}
+
+/// A task recommended by [`TaskManager::next_tasks`](crate::TaskManager::next_tasks),
+/// with what made it win.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NextTask {
+    pub task: Task,
+    /// Dependencies it waited on, all complete now.
+    pub completed_dependencies: usize,
+    /// Open tasks waiting on this one.
+    pub unblocks: usize,
+    /// Ready tasks it was picked from.
+    pub candidates: usize,
+    /// Younger ready tasks at the same priority, which it beat by age.
+    pub same_priority: usize,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -211,6 +320,10 @@ pub struct CreateTask {
     pub description: Option<String>,
     pub symbol_id: Option<i64>,
     pub depends_on: Vec<TaskId>,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl CreateTask {
@@ -220,6 +333,8 @@ impl CreateTask {
             description: None,
             symbol_id: None,
             depends_on: vec![],
+            priority: 0,
+            tags: vec![],
         }
     }
 
@@ -234,6 +349,18 @@ impl CreateTask {
         self.depends_on = deps;
         self
     }
+
+    #[must_use]
+    pub fn with_priority(mut self, priority: i64) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    #[must_use]
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -268,4 +395,13 @@ mod tests {
         assert!(TaskStatus::Done.is_complete());
         assert!(TaskStatus::Cancelled.is_complete());
     }
+
+    #[test]
+    fn test_task_tags() {
+        let task = Task::new("Tagged").with_tags(vec![" backend ".into(), "ui,Backend".into(), "".into()]);
+
+        assert_eq!(task.tags, vec!["backend".to_string(), "ui".to_string()]);
+        assert!(task.has_tag("BACKEND"));
+        assert!(!task.has_tag("docs"));
+    }
 }
@@ -0,0 +1,63 @@
+//! Change notifications for a task store.
+//!
+//! Every writer of a store, in this process or another (`adi tasks`, the
+//! tasks service, agents), goes through its SQLite database, so watching the
+//! database and its write-ahead log catches them all.
+
+use crate::{Error, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+
+/// Files whose changes mean the store changed. The `-shm` index is left
+/// out: readers touch it too.
+const WATCHED_FILES: [&str; 2] = ["tasks.sqlite", "tasks.sqlite-wal"];
+
+/// Watches one store until dropped.
+pub struct StoreWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<()>,
+}
+
+impl StoreWatcher {
+    pub(crate) fn new(store_dir: &Path) -> Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: std::result::Result<Event, notify::Error>| {
+                let Ok(event) = res else {
+                    return;
+                };
+                let relevant = matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) && event.paths.iter().any(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| WATCHED_FILES.contains(&name))
+                });
+                if relevant {
+                    let _ = tx.send(());
+                }
+            },
+            notify::Config::default(),
+        )
+        .map_err(|e| Error::Watch(e.to_string()))?;
+        watcher
+            .watch(store_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| Error::Watch(e.to_string()))?;
+
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    /// Whether the store changed since the last call. Never blocks.
+    pub fn changed(&self) -> bool {
+        let mut changed = false;
+        while self.rx.try_recv().is_ok() {
+            changed = true;
+        }
+        changed
+    }
+}
@@ -0,0 +1,288 @@
+//! Outbound webhooks for task events.
+//!
+//! A task change writes one delivery per subscribed webhook into the
+//! `task_webhook_deliveries` outbox, in the same database as the change.
+//! [`TaskManager::deliver_webhooks`](crate::TaskManager::deliver_webhooks)
+//! later sends due deliveries through a [`WebhookTransport`], retrying failed
+//! ones with exponential backoff up to [`RetryPolicy::max_attempts`]. The
+//! outbox doubles as the delivery log.
+//!
+//! Every request carries [`SIGNATURE_HEADER`]: `sha256=` followed by the hex
+//! HMAC-SHA256 of `"{timestamp}.{body}"` under the webhook's secret, where
+//! `timestamp` is the value of [`TIMESTAMP_HEADER`].
+
+use crate::error::{Error, Result};
+use crate::types::{Task, TaskId, TaskStatus};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::str::FromStr;
+
+pub const EVENT_HEADER: &str = "X-Adi-Event";
+pub const DELIVERY_HEADER: &str = "X-Adi-Delivery";
+pub const TIMESTAMP_HEADER: &str = "X-Adi-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Adi-Signature";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A task was created.
+    Created,
+    /// A task moved to another status.
+    StatusChanged,
+    /// A task was set to `blocked`, or gained its first incomplete dependency.
+    Blocked,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 3] = [Self::Created, Self::StatusChanged, Self::Blocked];
+
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::StatusChanged => "status_changed",
+            Self::Blocked => "blocked",
+        }
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "created" => Ok(Self::Created),
+            "status_changed" | "status-changed" => Ok(Self::StatusChanged),
+            "blocked" => Ok(Self::Blocked),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    /// HMAC key for [`SIGNATURE_HEADER`]; never serialized.
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    pub created_at: i64,
+}
+
+impl Webhook {
+    #[must_use]
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.events.contains(&event)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Not sent yet, or waiting for a retry.
+    Pending,
+    Delivered,
+    /// Gave up after the last retry.
+    Failed,
+}
+
+impl DeliveryStatus {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "delivered" => Ok(Self::Delivered),
+            "failed" => Ok(Self::Failed),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event: WebhookEvent,
+    pub task_id: TaskId,
+    /// JSON body, fixed when the event happened.
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    /// HTTP status of the last attempt, if a response arrived.
+    pub response_code: Option<u16>,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Backoff between delivery attempts: `initial_delay_secs`, doubling per
+/// failed attempt, capped at `max_delay_secs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay_secs: u64,
+    pub max_delay_secs: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            initial_delay_secs: 30,
+            max_delay_secs: 3600,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Seconds to wait after `attempts` failed attempts.
+    #[must_use]
+    pub fn delay_after(&self, attempts: u32) -> u64 {
+        let doublings = attempts.saturating_sub(1).min(32);
+        self.initial_delay_secs
+            .saturating_mul(1 << doublings)
+            .min(self.max_delay_secs)
+    }
+}
+
+/// Outcome of one [`deliver_webhooks`](crate::TaskManager::deliver_webhooks) pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    pub delivered: usize,
+    /// Failed this time, retried later.
+    pub retrying: usize,
+    /// Failed for the last time.
+    pub failed: usize,
+}
+
+/// Sends a signed delivery. Returns the HTTP status code, or an error if no
+/// response arrived.
+#[async_trait]
+pub trait WebhookTransport: Send + Sync {
+    async fn post(
+        &self,
+        url: &str,
+        headers: &[(&'static str, String)],
+        body: &str,
+    ) -> std::result::Result<u16, String>;
+}
+
+/// Hex HMAC-SHA256 of `"{timestamp}.{body}"`.
+#[must_use]
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC-SHA256 accepts any key size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+pub fn generate_secret() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| Error::Storage(format!("Failed to generate webhook secret: {}", e)))?;
+    Ok(hex::encode(bytes))
+}
+
+pub(crate) fn validate_url(url: &str) -> Result<()> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(Error::InvalidWebhook(format!(
+            "URL must start with http:// or https://: {}",
+            url
+        )))
+    }
+}
+
+pub(crate) fn payload(
+    event: WebhookEvent,
+    task: &Task,
+    previous_status: Option<TaskStatus>,
+    occurred_at: i64,
+) -> Result<String> {
+    let mut body = serde_json::json!({
+        "event": event,
+        "occurred_at": occurred_at,
+        "task": task,
+    });
+    if let Some(previous) = previous_status {
+        body["previous_status"] = serde_json::json!(previous);
+    }
+    Ok(serde_json::to_string(&body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_delay_secs: 30,
+            max_delay_secs: 300,
+        };
+        let delays: Vec<u64> = (1..=6).map(|n| policy.delay_after(n)).collect();
+        assert_eq!(delays, [30, 60, 120, 240, 300, 300]);
+        assert_eq!(policy.delay_after(u32::MAX), 300);
+    }
+
+    #[test]
+    fn test_signature_covers_timestamp_and_body() {
+        let signature = sign("secret", 1700000000, r#"{"event":"created"}"#);
+        assert_eq!(signature.len(), 64);
+        assert_eq!(
+            signature,
+            sign("secret", 1700000000, r#"{"event":"created"}"#)
+        );
+        assert_ne!(
+            signature,
+            sign("secret", 1700000001, r#"{"event":"created"}"#)
+        );
+        assert_ne!(
+            signature,
+            sign("other", 1700000000, r#"{"event":"created"}"#)
+        );
+    }
+
+    #[test]
+    fn test_event_names() {
+        for event in WebhookEvent::ALL {
+            assert_eq!(event.as_str().parse::<WebhookEvent>(), Ok(event));
+        }
+        assert_eq!("status-changed".parse(), Ok(WebhookEvent::StatusChanged));
+        assert!("deleted".parse::<WebhookEvent>().is_err());
+    }
+}
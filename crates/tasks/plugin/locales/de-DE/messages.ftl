@@ -10,6 +10,7 @@ plugin-description = Aufgabenverwaltung mit Abhängigkeitsverfolgung
 # Befehlsbeschreibungen
 cmd-list-help = Alle Aufgaben auflisten
 cmd-add-help = Neue Aufgabe hinzufügen
+cmd-next-help = Nächste Aufgabe zum Bearbeiten empfehlen
 cmd-show-help = Aufgabendetails anzeigen
 cmd-status-help = Aufgabenstatus aktualisieren
 cmd-delete-help = Aufgabe löschen
@@ -20,6 +21,12 @@ cmd-search-help = Aufgaben suchen
 cmd-blocked-help = Blockierte Aufgaben anzeigen
 cmd-cycles-help = Zyklische Abhängigkeiten erkennen
 cmd-stats-help = Aufgabenstatistik anzeigen
+cmd-board-help = Interaktives Kanban-Board öffnen
+cmd-breakdown-help = Aufgabe mit KI in Unteraufgaben zerlegen
+cmd-attach-help = URL oder Datei an eine Aufgabe anhängen
+cmd-detach-help = Anhang von einer Aufgabe entfernen
+cmd-attachments-help = Anhänge einer Aufgabe anzeigen
+cmd-webhooks-help = Webhooks für Aufgabenereignisse verwalten
 
 # Hilfetext
 tasks-help-title = ADI Aufgaben - Aufgabenverwaltung mit Abhängigkeitsverfolgung
@@ -35,6 +42,18 @@ tasks-list-scope-project = [Projekt]
 tasks-add-missing-title = Titel fehlt. Verwendung: add <Titel> [--description <Beschreibung>]
 tasks-add-created = Aufgabe #{ $id } erstellt: { $title }
 
+# Nächste-Befehl
+tasks-next-title = Als Nächstes:
+tasks-next-empty = Keine bereiten Aufgaben
+tasks-next-empty-tag = Keine bereiten Aufgaben mit Tag { $tag }
+tasks-next-reason-no-deps = bereit: keine Abhängigkeiten
+tasks-next-reason-ready = bereit: alle { $count } Abhängigkeiten erledigt
+tasks-next-reason-rank = #{ $rank } von { $count } bereiten Aufgaben nach Priorität ({ $priority }), dann Alter ({ $age })
+tasks-next-reason-tie = älter als { $count } andere bereite Aufgaben gleicher Priorität
+tasks-next-reason-unblocks = gibt { $count } wartende Aufgaben frei
+tasks-next-reason-tag = mit Tag { $tag }
+tasks-next-started = #{ $id } gestartet: { $title } (Zeiterfassung läuft)
+
 # Anzeigen-Befehl
 tasks-show-missing-id = Aufgaben-ID fehlt. Verwendung: show <id>
 tasks-show-invalid-id = Ungültige Aufgaben-ID
@@ -44,8 +63,12 @@ tasks-show-field-status = Status: { $status }
 tasks-show-field-description = Beschreibung: { $description }
 tasks-show-field-symbol = Verknüpftes Symbol: #{ $symbol_id }
 tasks-show-field-scope = Bereich: { $scope }
+tasks-show-field-priority = Priorität: { $priority }
+tasks-show-field-tags = Tags: { $tags }
+tasks-show-field-tracked = Erfasste Zeit: { $duration }
 tasks-show-dependencies = Abhängigkeiten:
 tasks-show-dependents = Abhängige:
+tasks-show-attachments = Anhänge:
 
 # Status-Befehl
 tasks-status-missing-args = Argumente fehlen. Verwendung: status <id> <status>
@@ -104,6 +127,64 @@ tasks-stats-dependencies = Abhängigkeiten: { $count }
 tasks-stats-cycles-yes = Zyklen: Ja (führen Sie 'cycles' aus, um sie zu sehen)
 tasks-stats-cycles-no = Zyklen: Keine
 
+# Board-Befehl
+tasks-board-column-todo = Zu erledigen
+tasks-board-column-in-progress = In Bearbeitung
+tasks-board-column-blocked = Blockiert
+tasks-board-column-done = Erledigt
+tasks-board-column-cancelled = Abgebrochen
+tasks-board-hint = ←→↑↓ navigieren · Umschalt+←→ verschieben · Leertaste ziehen · a hinzufügen · / filtern · r aktualisieren · q beenden
+tasks-board-drag-hint = ←→ Spalte wählen · Enter/Leertaste ablegen · Esc abbrechen
+tasks-board-filter-prompt = Filter:
+tasks-board-add-prompt = Neue Aufgabe:
+tasks-board-no-terminal = Board kann nicht geöffnet werden: { $error }
+tasks-board-closed = Board geschlossen ({ $moved } verschoben, { $created } erstellt)
+
+# Breakdown-Befehl
+tasks-breakdown-confirm = Unteraufgaben für #{ $id } { $title }:
+tasks-breakdown-after = nach { $steps }
+tasks-breakdown-created = { $count } Unteraufgaben für #{ $id } erstellt:
+tasks-breakdown-empty = Keine Unteraufgaben für #{ $id } vorgeschlagen
+tasks-breakdown-cancelled = Zerlegung abgebrochen, keine Aufgaben erstellt
+tasks-breakdown-failed = Zerlegung nicht möglich: { $error }
+tasks-breakdown-failed-hint = SIGNALING_URL, SIGNALING_ACCESS_TOKEN und SIGNALING_PROXY_TOKEN setzen oder --provider mit dessen API-Schlüssel angeben
+tasks-breakdown-no-model = Kein Modell für die Aufteilung der Aufgabe
+tasks-breakdown-no-model-hint = --model angeben oder breakdown_model in der adi.tasks-Konfiguration setzen (z. B. ADI_CONFIG_ADI_TASKS__BREAKDOWN_MODEL)
+
+# Anhang-Befehl
+tasks-attach-link = Link an #{ $id } angehängt: { $target }
+tasks-attach-file = Datei an #{ $id } angehängt: { $target }
+tasks-detach-done = Von #{ $id } entfernt: { $target }
+tasks-attachments-title = Anhänge von #{ $id }:
+tasks-attachments-empty = Aufgabe #{ $id } hat keine Anhänge
+tasks-attachments-size = { $bytes } Bytes
+tasks-attachments-stored = gespeichert unter { $path }
+
+# Webhooks-Befehl
+tasks-webhooks-title = Webhooks:
+tasks-webhooks-empty = Keine Webhooks. Hinzufügen mit: adi tasks webhooks add <url> [--events created,status_changed,blocked]
+tasks-webhooks-added = Webhook { $webhook } hinzugefügt
+tasks-webhooks-secret = Signaturgeheimnis (wird nur einmal angezeigt): { $secret }
+tasks-webhooks-removed = Webhook #{ $id } entfernt
+tasks-webhooks-missing-url = URL fehlt. Verwendung: webhooks add <url> [--events <liste>] [--secret <geheimnis>]
+tasks-webhooks-missing-id = Webhook-ID fehlt oder ist ungültig
+tasks-webhooks-invalid-event = Ungültiges Ereignis: { $event }. Gültig: created, status_changed, blocked
+tasks-webhooks-unknown-command = Unbekannter webhooks-Befehl: { $cmd }. Gültig: list, add, remove, deliveries, deliver
+tasks-webhooks-deliveries-title = Zustellungen:
+tasks-webhooks-no-deliveries = Noch keine Zustellungen
+tasks-webhooks-delivered = zugestellt
+tasks-webhooks-failed = fehlgeschlagen: { $error }
+tasks-webhooks-pending = ausstehend
+tasks-webhooks-retrying = neuer Versuch in { $seconds }s nach { $error }
+tasks-webhooks-attempts = { $count } Versuche
+tasks-webhooks-deliver-report = { $delivered } zugestellt, { $retrying } werden wiederholt, { $failed } fehlgeschlagen
+
 # Fehler
 error-not-initialized = Aufgaben nicht initialisiert
 error-task-not-found = Aufgabe { $id } nicht gefunden
+error-attachment-not-found = Datei nicht gefunden: { $target }
+error-not-attached = Aufgabe #{ $id } hat keinen Anhang { $target }
+error-not-attached-hint = Anhänge anzeigen mit: adi tasks attachments { $id }
+error-attachment-quota-hint = Stattdessen einen Link anhängen oder Dateien entfernen mit: adi tasks detach
+error-webhook-not-found = Webhook #{ $id } nicht gefunden
+error-webhook-not-found-hint = Webhooks anzeigen mit: adi tasks webhooks list
@@ -10,6 +10,7 @@ plugin-description = Task management with dependency tracking
 # Command descriptions
 cmd-list-help = List all tasks
 cmd-add-help = Add a new task
+cmd-next-help = Recommend the next task to pick up
 cmd-show-help = Show task details
 cmd-status-help = Update task status
 cmd-delete-help = Delete a task
@@ -20,6 +21,12 @@ cmd-search-help = Search tasks
 cmd-blocked-help = Show blocked tasks
 cmd-cycles-help = Detect dependency cycles
 cmd-stats-help = Show task statistics
+cmd-board-help = Open interactive Kanban board
+cmd-breakdown-help = Break a task into subtasks with AI
+cmd-attach-help = Attach a URL or file to a task
+cmd-detach-help = Remove an attachment from a task
+cmd-attachments-help = List a task's attachments
+cmd-webhooks-help = Manage webhooks for task events
 
 # Help text
 tasks-help-title = ADI Tasks - Task management with dependency tracking
@@ -35,6 +42,18 @@ tasks-list-scope-project = [project]
 tasks-add-missing-title = Missing title. Usage: add <title> [--description <desc>]
 tasks-add-created = Created task #{ $id }: { $title }
 
+# Next command
+tasks-next-title = Next up:
+tasks-next-empty = No ready tasks to pick up
+tasks-next-empty-tag = No ready tasks tagged { $tag }
+tasks-next-reason-no-deps = ready: no dependencies
+tasks-next-reason-ready = ready: all { $count } dependencies done
+tasks-next-reason-rank = #{ $rank } of { $count } ready tasks by priority ({ $priority }), then age ({ $age })
+tasks-next-reason-tie = older than { $count } other ready tasks at the same priority
+tasks-next-reason-unblocks = unblocks { $count } waiting tasks
+tasks-next-reason-tag = tagged { $tag }
+tasks-next-started = Started #{ $id }: { $title } (time tracking on)
+
 # Show command
 tasks-show-missing-id = Missing task ID. Usage: show <id>
 tasks-show-invalid-id = Invalid task ID
@@ -44,8 +63,12 @@ tasks-show-field-status = Status: { $status }
 tasks-show-field-description = Description: { $description }
 tasks-show-field-symbol = Linked symbol: #{ $symbol_id }
 tasks-show-field-scope = Scope: { $scope }
+tasks-show-field-priority = Priority: { $priority }
+tasks-show-field-tags = Tags: { $tags }
+tasks-show-field-tracked = Time tracked: { $duration }
 tasks-show-dependencies = Dependencies:
 tasks-show-dependents = Dependents:
+tasks-show-attachments = Attachments:
 
 # Status command
 tasks-status-missing-args = Missing arguments. Usage: status <id> <status>
@@ -104,6 +127,65 @@ tasks-stats-dependencies = Dependencies: { $count }
 tasks-stats-cycles-yes = Cycles: Yes (run 'cycles' to see)
 tasks-stats-cycles-no = Cycles: None
 
+# Board command
+tasks-board-column-todo = Todo
+tasks-board-column-in-progress = In Progress
+tasks-board-column-blocked = Blocked
+tasks-board-column-done = Done
+tasks-board-column-cancelled = Cancelled
+tasks-board-hint = ←→↑↓ navigate · shift+←→ move · space drag · a add · / filter · r refresh · q quit
+tasks-board-drag-hint = ←→ choose column · enter/space drop · esc cancel
+tasks-board-filter-prompt = Filter:
+tasks-board-add-prompt = New task:
+tasks-board-no-terminal = Cannot open the board: { $error }
+tasks-board-closed = Board closed ({ $moved } moved, { $created } created)
+
+# Breakdown command
+tasks-breakdown-confirm = Subtasks for #{ $id } { $title }:
+tasks-breakdown-after = after { $steps }
+tasks-breakdown-created = Created { $count } subtasks for #{ $id }:
+tasks-breakdown-empty = No subtasks proposed for #{ $id }
+tasks-breakdown-cancelled = Breakdown cancelled, no tasks created
+tasks-breakdown-failed = Could not get a breakdown: { $error }
+tasks-breakdown-failed-hint = Set SIGNALING_URL, SIGNALING_ACCESS_TOKEN and SIGNALING_PROXY_TOKEN, or pass --provider with its API key
+tasks-breakdown-no-model = No model to break the task down with
+tasks-breakdown-no-model-hint = Pass --model, or set breakdown_model in the adi.tasks config (e.g. ADI_CONFIG_ADI_TASKS__BREAKDOWN_MODEL)
+
+# Attach command
+tasks-attach-link = Attached link to #{ $id }: { $target }
+tasks-attach-file = Attached file to #{ $id }: { $target }
+tasks-detach-done = Detached from #{ $id }: { $target }
+tasks-attachments-title = Attachments of #{ $id }:
+tasks-attachments-empty = Task #{ $id } has no attachments
+tasks-attachments-size = { $bytes } bytes
+tasks-attachments-stored = stored at { $path }
+
+# Webhooks command
+tasks-webhooks-title = Webhooks:
+tasks-webhooks-empty = No webhooks. Add one with: adi tasks webhooks add <url> [--events created,status_changed,blocked]
+tasks-webhooks-added = Added webhook { $webhook }
+tasks-webhooks-secret = Signing secret (shown once): { $secret }
+tasks-webhooks-removed = Removed webhook #{ $id }
+tasks-webhooks-missing-url = Missing URL. Usage: webhooks add <url> [--events <list>] [--secret <secret>]
+tasks-webhooks-missing-id = Missing or invalid webhook ID
+tasks-webhooks-invalid-event = Invalid event: { $event }. Valid: created, status_changed, blocked
+tasks-webhooks-unknown-command = Unknown webhooks command: { $cmd }. Valid: list, add, remove, deliveries, deliver
+tasks-webhooks-deliveries-title = Deliveries:
+tasks-webhooks-no-deliveries = No deliveries yet
+tasks-webhooks-delivered = delivered
+tasks-webhooks-failed = failed: { $error }
+tasks-webhooks-pending = pending
+tasks-webhooks-retrying = retry in { $seconds }s after { $error }
+tasks-webhooks-attempts = { $count } attempts
+tasks-webhooks-deliver-report = Delivered { $delivered }, retrying { $retrying }, failed { $failed }
+
 # Errors
 error-not-initialized = Tasks not initialized
 error-task-not-found = Task { $id } not found
+error-task-not-found-hint = List tasks with: adi tasks list
+error-attachment-not-found = No such file: { $target }
+error-not-attached = Task #{ $id } has no attachment { $target }
+error-not-attached-hint = List its attachments with: adi tasks attachments { $id }
+error-attachment-quota-hint = Attach a link instead, or detach files with: adi tasks detach
+error-webhook-not-found = Webhook #{ $id } not found
+error-webhook-not-found-hint = List webhooks with: adi tasks webhooks list
@@ -10,6 +10,7 @@ plugin-description = Управління завданнями з відстеж
 # Описи команд
 cmd-list-help = Показати всі завдання
 cmd-add-help = Додати нове завдання
+cmd-next-help = Порадити наступне завдання для роботи
 cmd-show-help = Показати деталі завдання
 cmd-status-help = Оновити статус завдання
 cmd-delete-help = Видалити завдання
@@ -20,6 +21,12 @@ cmd-search-help = Шукати завдання
 cmd-blocked-help = Показати заблоковані завдання
 cmd-cycles-help = Виявити циклічні залежності
 cmd-stats-help = Показати статистику завдань
+cmd-board-help = Відкрити інтерактивну Kanban-дошку
+cmd-breakdown-help = Розбити задачу на підзадачі за допомогою ШІ
+cmd-attach-help = Прикріпити URL або файл до завдання
+cmd-detach-help = Відкріпити вкладення від завдання
+cmd-attachments-help = Показати вкладення завдання
+cmd-webhooks-help = Керувати вебхуками для подій завдань
 
 # Текст довідки
 tasks-help-title = ADI Завдання - Управління завданнями з відстеженням залежностей
@@ -35,6 +42,18 @@ tasks-list-scope-project = [проєкт]
 tasks-add-missing-title = Відсутній заголовок. Використання: add <заголовок> [--description <опис>]
 tasks-add-created = Створено завдання #{ $id }: { $title }
 
+# Команда наступного
+tasks-next-title = Далі:
+tasks-next-empty = Немає готових завдань
+tasks-next-empty-tag = Немає готових завдань з тегом { $tag }
+tasks-next-reason-no-deps = готове: немає залежностей
+tasks-next-reason-ready = готове: усі { $count } залежностей виконано
+tasks-next-reason-rank = #{ $rank } з { $count } готових завдань за пріоритетом ({ $priority }), потім віком ({ $age })
+tasks-next-reason-tie = старше за { $count } інших готових завдань з тим самим пріоритетом
+tasks-next-reason-unblocks = розблоковує { $count } завдань, що очікують
+tasks-next-reason-tag = з тегом { $tag }
+tasks-next-started = Розпочато #{ $id }: { $title } (облік часу ввімкнено)
+
 # Команда показу
 tasks-show-missing-id = Відсутній ID завдання. Використання: show <id>
 tasks-show-invalid-id = Невірний ID завдання
@@ -44,8 +63,12 @@ tasks-show-field-status = Статус: { $status }
 tasks-show-field-description = Опис: { $description }
 tasks-show-field-symbol = Пов'язаний символ: #{ $symbol_id }
 tasks-show-field-scope = Область: { $scope }
+tasks-show-field-priority = Пріоритет: { $priority }
+tasks-show-field-tags = Теги: { $tags }
+tasks-show-field-tracked = Витрачено часу: { $duration }
 tasks-show-dependencies = Залежності:
 tasks-show-dependents = Залежать від цього:
+tasks-show-attachments = Вкладення:
 
 # Команда статусу
 tasks-status-missing-args = Відсутні аргументи. Використання: status <id> <статус>
@@ -104,6 +127,64 @@ tasks-stats-dependencies = Залежностей: { $count }
 tasks-stats-cycles-yes = Цикли: Так (виконайте 'cycles' для перегляду)
 tasks-stats-cycles-no = Цикли: Немає
 
+# Команда дошки
+tasks-board-column-todo = До виконання
+tasks-board-column-in-progress = В процесі
+tasks-board-column-blocked = Заблоковано
+tasks-board-column-done = Виконано
+tasks-board-column-cancelled = Скасовано
+tasks-board-hint = ←→↑↓ навігація · shift+←→ перемістити · пробіл перетягнути · a додати · / фільтр · r оновити · q вийти
+tasks-board-drag-hint = ←→ обрати колонку · enter/пробіл відпустити · esc скасувати
+tasks-board-filter-prompt = Фільтр:
+tasks-board-add-prompt = Нове завдання:
+tasks-board-no-terminal = Не вдалося відкрити дошку: { $error }
+tasks-board-closed = Дошку закрито (переміщено: { $moved }, створено: { $created })
+
+# Команда breakdown
+tasks-breakdown-confirm = Підзадачі для #{ $id } { $title }:
+tasks-breakdown-after = після { $steps }
+tasks-breakdown-created = Створено { $count } підзадач для #{ $id }:
+tasks-breakdown-empty = Для #{ $id } не запропоновано підзадач
+tasks-breakdown-cancelled = Розбиття скасовано, задачі не створено
+tasks-breakdown-failed = Не вдалося отримати розбиття: { $error }
+tasks-breakdown-failed-hint = Встановіть SIGNALING_URL, SIGNALING_ACCESS_TOKEN і SIGNALING_PROXY_TOKEN або вкажіть --provider з його API-ключем
+tasks-breakdown-no-model = Не вказано модель для розбиття задачі
+tasks-breakdown-no-model-hint = Вкажіть --model або задайте breakdown_model у конфігурації adi.tasks (напр. ADI_CONFIG_ADI_TASKS__BREAKDOWN_MODEL)
+
+# Команда attach
+tasks-attach-link = Посилання прикріплено до #{ $id }: { $target }
+tasks-attach-file = Файл прикріплено до #{ $id }: { $target }
+tasks-detach-done = Відкріплено від #{ $id }: { $target }
+tasks-attachments-title = Вкладення #{ $id }:
+tasks-attachments-empty = Завдання #{ $id } не має вкладень
+tasks-attachments-size = { $bytes } байт
+tasks-attachments-stored = збережено в { $path }
+
+# Команда webhooks
+tasks-webhooks-title = Вебхуки:
+tasks-webhooks-empty = Вебхуків немає. Додайте: adi tasks webhooks add <url> [--events created,status_changed,blocked]
+tasks-webhooks-added = Додано вебхук { $webhook }
+tasks-webhooks-secret = Секрет підпису (показується один раз): { $secret }
+tasks-webhooks-removed = Вебхук #{ $id } видалено
+tasks-webhooks-missing-url = Не вказано URL. Використання: webhooks add <url> [--events <список>] [--secret <секрет>]
+tasks-webhooks-missing-id = Не вказано або некоректний ID вебхука
+tasks-webhooks-invalid-event = Некоректна подія: { $event }. Допустимі: created, status_changed, blocked
+tasks-webhooks-unknown-command = Невідома команда webhooks: { $cmd }. Допустимі: list, add, remove, deliveries, deliver
+tasks-webhooks-deliveries-title = Доставки:
+tasks-webhooks-no-deliveries = Доставок ще немає
+tasks-webhooks-delivered = доставлено
+tasks-webhooks-failed = помилка: { $error }
+tasks-webhooks-pending = очікує
+tasks-webhooks-retrying = повтор через { $seconds }с після { $error }
+tasks-webhooks-attempts = спроб: { $count }
+tasks-webhooks-deliver-report = Доставлено { $delivered }, повторюється { $retrying }, не вдалося { $failed }
+
 # Помилки
 error-not-initialized = Завдання не ініціалізовано
 error-task-not-found = Завдання { $id } не знайдено
+error-attachment-not-found = Файл не знайдено: { $target }
+error-not-attached = Завдання #{ $id } не має вкладення { $target }
+error-not-attached-hint = Перегляньте вкладення: adi tasks attachments { $id }
+error-attachment-quota-hint = Прикріпіть посилання або відкріпіть файли: adi tasks detach
+error-webhook-not-found = Вебхук #{ $id } не знайдено
+error-webhook-not-found-hint = Перелік вебхуків: adi tasks webhooks list
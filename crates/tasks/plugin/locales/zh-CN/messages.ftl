@@ -10,6 +10,7 @@ plugin-description = 带依赖关系的任务管理
 # 命令描述
 cmd-list-help = 列出所有任务
 cmd-add-help = 添加新任务
+cmd-next-help = 推荐接下来要处理的任务
 cmd-show-help = 显示任务详情
 cmd-status-help = 更新任务状态
 cmd-delete-help = 删除任务
@@ -20,6 +21,12 @@ cmd-search-help = 搜索任务
 cmd-blocked-help = 显示被阻塞的任务
 cmd-cycles-help = 检测循环依赖
 cmd-stats-help = 显示任务统计
+cmd-board-help = 打开交互式看板
+cmd-breakdown-help = 使用 AI 将任务拆分为子任务
+cmd-attach-help = 将 URL 或文件附加到任务
+cmd-detach-help = 从任务中移除附件
+cmd-attachments-help = 列出任务的附件
+cmd-webhooks-help = 管理任务事件的 Webhook
 
 # 帮助文本
 tasks-help-title = ADI 任务 - 带依赖关系的任务管理
@@ -35,6 +42,18 @@ tasks-list-scope-project = [项目]
 tasks-add-missing-title = 缺少标题。用法: add <标题> [--description <描述>]
 tasks-add-created = 创建任务 #{ $id }: { $title }
 
+# 下一个命令
+tasks-next-title = 接下来:
+tasks-next-empty = 没有可开始的任务
+tasks-next-empty-tag = 没有带标签 { $tag } 的可开始任务
+tasks-next-reason-no-deps = 可开始: 无依赖
+tasks-next-reason-ready = 可开始: 全部 { $count } 个依赖已完成
+tasks-next-reason-rank = 在 { $count } 个可开始任务中按优先级 ({ $priority }) 和时长 ({ $age }) 排第 { $rank }
+tasks-next-reason-tie = 比同优先级的其他 { $count } 个可开始任务更早
+tasks-next-reason-unblocks = 完成后解除 { $count } 个等待中的任务
+tasks-next-reason-tag = 标签 { $tag }
+tasks-next-started = 已开始 #{ $id }: { $title } (开始计时)
+
 # 显示命令
 tasks-show-missing-id = 缺少任务 ID。用法: show <id>
 tasks-show-invalid-id = 无效的任务 ID
@@ -44,8 +63,12 @@ tasks-show-field-status = 状态: { $status }
 tasks-show-field-description = 描述: { $description }
 tasks-show-field-symbol = 关联符号: #{ $symbol_id }
 tasks-show-field-scope = 范围: { $scope }
+tasks-show-field-priority = 优先级: { $priority }
+tasks-show-field-tags = 标签: { $tags }
+tasks-show-field-tracked = 已记录时间: { $duration }
 tasks-show-dependencies = 依赖:
 tasks-show-dependents = 被依赖:
+tasks-show-attachments = 附件:
 
 # 状态命令
 tasks-status-missing-args = 缺少参数。用法: status <id> <状态>
@@ -104,6 +127,64 @@ tasks-stats-dependencies = 依赖关系: { $count }
 tasks-stats-cycles-yes = 循环: 是 (运行 'cycles' 查看)
 tasks-stats-cycles-no = 循环: 无
 
+# 看板命令
+tasks-board-column-todo = 待办
+tasks-board-column-in-progress = 进行中
+tasks-board-column-blocked = 已阻塞
+tasks-board-column-done = 已完成
+tasks-board-column-cancelled = 已取消
+tasks-board-hint = ←→↑↓ 导航 · shift+←→ 移动 · 空格 拖动 · a 添加 · / 筛选 · r 刷新 · q 退出
+tasks-board-drag-hint = ←→ 选择列 · enter/空格 放下 · esc 取消
+tasks-board-filter-prompt = 筛选:
+tasks-board-add-prompt = 新任务:
+tasks-board-no-terminal = 无法打开看板: { $error }
+tasks-board-closed = 看板已关闭 (移动 { $moved } 个, 创建 { $created } 个)
+
+# 拆分命令
+tasks-breakdown-confirm = #{ $id } { $title } 的子任务:
+tasks-breakdown-after = 在 { $steps } 之后
+tasks-breakdown-created = 已为 #{ $id } 创建 { $count } 个子任务:
+tasks-breakdown-empty = 未为 #{ $id } 提出子任务
+tasks-breakdown-cancelled = 已取消拆分，未创建任务
+tasks-breakdown-failed = 无法获取拆分结果: { $error }
+tasks-breakdown-failed-hint = 设置 SIGNALING_URL、SIGNALING_ACCESS_TOKEN 和 SIGNALING_PROXY_TOKEN，或使用 --provider 并提供其 API 密钥
+tasks-breakdown-no-model = 未指定用于拆分任务的模型
+tasks-breakdown-no-model-hint = 使用 --model，或在 adi.tasks 配置中设置 breakdown_model（例如 ADI_CONFIG_ADI_TASKS__BREAKDOWN_MODEL）
+
+# 附件命令
+tasks-attach-link = 已将链接附加到 #{ $id }: { $target }
+tasks-attach-file = 已将文件附加到 #{ $id }: { $target }
+tasks-detach-done = 已从 #{ $id } 移除: { $target }
+tasks-attachments-title = #{ $id } 的附件:
+tasks-attachments-empty = 任务 #{ $id } 没有附件
+tasks-attachments-size = { $bytes } 字节
+tasks-attachments-stored = 存储于 { $path }
+
+# Webhooks 命令
+tasks-webhooks-title = Webhook：
+tasks-webhooks-empty = 没有 Webhook。添加方式：adi tasks webhooks add <url> [--events created,status_changed,blocked]
+tasks-webhooks-added = 已添加 Webhook { $webhook }
+tasks-webhooks-secret = 签名密钥（仅显示一次）：{ $secret }
+tasks-webhooks-removed = 已删除 Webhook #{ $id }
+tasks-webhooks-missing-url = 缺少 URL。用法：webhooks add <url> [--events <列表>] [--secret <密钥>]
+tasks-webhooks-missing-id = 缺少或无效的 Webhook ID
+tasks-webhooks-invalid-event = 无效事件：{ $event }。可选：created、status_changed、blocked
+tasks-webhooks-unknown-command = 未知的 webhooks 命令：{ $cmd }。可选：list、add、remove、deliveries、deliver
+tasks-webhooks-deliveries-title = 投递记录：
+tasks-webhooks-no-deliveries = 暂无投递记录
+tasks-webhooks-delivered = 已投递
+tasks-webhooks-failed = 失败：{ $error }
+tasks-webhooks-pending = 等待中
+tasks-webhooks-retrying = { $seconds } 秒后重试，上次错误：{ $error }
+tasks-webhooks-attempts = 尝试 { $count } 次
+tasks-webhooks-deliver-report = 已投递 { $delivered }，待重试 { $retrying }，失败 { $failed }
+
 # 错误
 error-not-initialized = 任务未初始化
 error-task-not-found = 找不到任务 { $id }
+error-attachment-not-found = 文件不存在: { $target }
+error-not-attached = 任务 #{ $id } 没有附件 { $target }
+error-not-attached-hint = 查看附件: adi tasks attachments { $id }
+error-attachment-quota-hint = 请改为附加链接，或使用 adi tasks detach 移除文件
+error-webhook-not-found = 未找到 Webhook #{ $id }
+error-webhook-not-found-hint = 查看 Webhook：adi tasks webhooks list
@@ -0,0 +1,602 @@
+//! Interactive Kanban board for `adi tasks board`.
+//!
+//! The board state is kept separate from terminal I/O so key handling can be
+//! exercised without a TTY. The tasks database is re-read whenever the store
+//! changes, which picks up changes made by other `adi tasks` invocations or
+//! by agents going through the tasks service. Where the store cannot be
+//! watched, it is re-read every [`FALLBACK_RELOAD`].
+
+use std::time::{Duration, Instant};
+
+use lib_plugin_prelude::t;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use tasks_core::{CreateTask, StoreWatcher, Task, TaskId, TaskManager, TaskStatus};
+
+/// How often the board checks for store changes between key presses
+const WATCH_TICK: Duration = Duration::from_millis(200);
+
+/// Reload interval when the store cannot be watched
+pub const FALLBACK_RELOAD: Duration = Duration::from_secs(2);
+
+/// Column order, left to right.
+pub const COLUMNS: [TaskStatus; 5] = [
+    TaskStatus::Todo,
+    TaskStatus::InProgress,
+    TaskStatus::Blocked,
+    TaskStatus::Done,
+    TaskStatus::Cancelled,
+];
+
+#[derive(Debug, Clone)]
+pub struct Card {
+    pub task: Task,
+    /// Dependencies that are not done or cancelled yet.
+    pub open_deps: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Browse,
+    Filter,
+    QuickAdd,
+    /// A card picked up with Space; arrows choose the drop column.
+    Dragging {
+        id: TaskId,
+        target: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Quit,
+    Refresh,
+    Move(TaskId, TaskStatus),
+    Create(String),
+}
+
+pub struct Board {
+    cards: Vec<Card>,
+    column: usize,
+    rows: [usize; COLUMNS.len()],
+    filter: String,
+    input: String,
+    mode: Mode,
+    notice: Option<String>,
+}
+
+impl Board {
+    pub fn new(filter: Option<String>) -> Self {
+        Self {
+            cards: Vec::new(),
+            column: 0,
+            rows: [0; COLUMNS.len()],
+            filter: filter.unwrap_or_default(),
+            input: String::new(),
+            mode: Mode::Browse,
+            notice: None,
+        }
+    }
+
+    pub fn set_cards(&mut self, cards: Vec<Card>) {
+        let selected = self.selected().map(|c| c.task.id);
+        self.cards = cards;
+
+        // Keep the cursor on the same task if it is still visible after a reload
+        if let Some(id) = selected {
+            for (col, _) in COLUMNS.iter().enumerate() {
+                if let Some(row) = self.column_cards(col).iter().position(|c| c.task.id == id) {
+                    self.column = col;
+                    self.rows[col] = row;
+                }
+            }
+        }
+        self.clamp_rows();
+    }
+
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    pub fn set_notice(&mut self, notice: impl Into<String>) {
+        self.notice = Some(notice.into());
+    }
+
+    fn matches_filter(&self, task: &Task) -> bool {
+        if self.filter.is_empty() {
+            return true;
+        }
+        let needle = self.filter.to_lowercase();
+        task.title.to_lowercase().contains(&needle)
+            || task
+                .description
+                .as_deref()
+                .is_some_and(|d| d.to_lowercase().contains(&needle))
+            || format!("#{}", task.id.get()) == needle
+    }
+
+    pub fn column_cards(&self, col: usize) -> Vec<&Card> {
+        self.cards
+            .iter()
+            .filter(|c| c.task.status == COLUMNS[col] && self.matches_filter(&c.task))
+            .collect()
+    }
+
+    pub fn selected(&self) -> Option<&Card> {
+        self.column_cards(self.column)
+            .get(self.rows[self.column])
+            .copied()
+    }
+
+    fn clamp_rows(&mut self) {
+        for col in 0..COLUMNS.len() {
+            let len = self.column_cards(col).len();
+            self.rows[col] = self.rows[col].min(len.saturating_sub(1));
+        }
+    }
+
+    fn step_column(col: usize, right: bool) -> usize {
+        if right {
+            (col + 1).min(COLUMNS.len() - 1)
+        } else {
+            col.saturating_sub(1)
+        }
+    }
+
+    /// Move the selected card one column over without the pick-up step.
+    fn shift_selected(&mut self, right: bool) -> Action {
+        let Some(card) = self.selected() else {
+            return Action::None;
+        };
+        let id = card.task.id;
+        let target = Self::step_column(self.column, right);
+        if target == self.column {
+            return Action::None;
+        }
+        self.column = target;
+        Action::Move(id, COLUMNS[target])
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        match self.mode.clone() {
+            Mode::Browse => self.handle_browse(key),
+            Mode::Filter => {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.mode = Mode::Browse,
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    KeyCode::Char(c) => self.filter.push(c),
+                    _ => {}
+                }
+                self.clamp_rows();
+                Action::None
+            }
+            Mode::QuickAdd => match key.code {
+                KeyCode::Esc => {
+                    self.input.clear();
+                    self.mode = Mode::Browse;
+                    Action::None
+                }
+                KeyCode::Enter => {
+                    self.mode = Mode::Browse;
+                    let title = std::mem::take(&mut self.input).trim().to_string();
+                    if title.is_empty() {
+                        Action::None
+                    } else {
+                        Action::Create(title)
+                    }
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                    Action::None
+                }
+                KeyCode::Char(c) => {
+                    self.input.push(c);
+                    Action::None
+                }
+                _ => Action::None,
+            },
+            Mode::Dragging { id, target } => match key.code {
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.mode = Mode::Dragging {
+                        id,
+                        target: Self::step_column(target, false),
+                    };
+                    Action::None
+                }
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.mode = Mode::Dragging {
+                        id,
+                        target: Self::step_column(target, true),
+                    };
+                    Action::None
+                }
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    self.mode = Mode::Browse;
+                    if target == self.column {
+                        return Action::None;
+                    }
+                    self.column = target;
+                    Action::Move(id, COLUMNS[target])
+                }
+                KeyCode::Esc => {
+                    self.mode = Mode::Browse;
+                    Action::None
+                }
+                _ => Action::None,
+            },
+        }
+    }
+
+    fn handle_browse(&mut self, key: KeyEvent) -> Action {
+        let shifted = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Action::Quit
+            }
+            KeyCode::Left if shifted => return self.shift_selected(false),
+            KeyCode::Right if shifted => return self.shift_selected(true),
+            KeyCode::Char('<') | KeyCode::Char('H') => return self.shift_selected(false),
+            KeyCode::Char('>') | KeyCode::Char('L') => return self.shift_selected(true),
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.column = Self::step_column(self.column, false)
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.column = Self::step_column(self.column, true)
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.rows[self.column] = self.rows[self.column].saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let len = self.column_cards(self.column).len();
+                if self.rows[self.column] + 1 < len {
+                    self.rows[self.column] += 1;
+                }
+            }
+            KeyCode::Char(' ') => {
+                if let Some(card) = self.selected() {
+                    self.mode = Mode::Dragging {
+                        id: card.task.id,
+                        target: self.column,
+                    };
+                }
+            }
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('a') => self.mode = Mode::QuickAdd,
+            KeyCode::Char('r') => return Action::Refresh,
+            _ => {}
+        }
+        self.notice = None;
+        Action::None
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [board_area, status_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .areas(frame.area());
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Ratio(1, COLUMNS.len() as u32); COLUMNS.len()])
+            .split(board_area);
+
+        for (col, area) in columns.iter().enumerate() {
+            self.render_column(frame, col, *area);
+        }
+
+        frame.render_widget(Paragraph::new(self.status_line()), status_area);
+    }
+
+    fn render_column(&self, frame: &mut Frame, col: usize, area: Rect) {
+        let status = COLUMNS[col];
+        let cards = self.column_cards(col);
+        let drop_target = matches!(self.mode, Mode::Dragging { target, .. } if target == col);
+        let focused = col == self.column;
+
+        let dragged = match self.mode {
+            Mode::Dragging { id, .. } => Some(id),
+            _ => None,
+        };
+
+        let items: Vec<ListItem> = cards
+            .iter()
+            .map(|card| {
+                let mut spans = vec![
+                    Span::styled(
+                        format!("#{} ", card.task.id.get()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw(card.task.title.clone()),
+                ];
+                if card.open_deps > 0 {
+                    spans.push(Span::styled(
+                        format!(" ⛓{}", card.open_deps),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                let mut item = ListItem::new(Line::from(spans));
+                if Some(card.task.id) == dragged {
+                    item = item.style(Style::default().add_modifier(Modifier::DIM));
+                }
+                item
+            })
+            .collect();
+
+        let border = if drop_target {
+            Style::default().fg(Color::Yellow)
+        } else if focused {
+            Style::default().fg(status_color(status))
+        } else {
+            Style::default()
+        };
+
+        let title = format!(
+            " {} {} ({}) ",
+            status.icon(),
+            column_title(status),
+            cards.len()
+        );
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border)
+                    .title(title),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = ListState::default();
+        if focused && !cards.is_empty() {
+            state.select(Some(self.rows[col]));
+        }
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn status_line(&self) -> Line<'_> {
+        match &self.mode {
+            Mode::Filter => Line::from(format!(
+                "{} {}▏",
+                t!("tasks-board-filter-prompt"),
+                self.filter
+            )),
+            Mode::QuickAdd => {
+                Line::from(format!("{} {}▏", t!("tasks-board-add-prompt"), self.input))
+            }
+            Mode::Dragging { .. } => Line::from(t!("tasks-board-drag-hint")),
+            Mode::Browse => {
+                let mut text = t!("tasks-board-hint");
+                if !self.filter.is_empty() {
+                    text = format!(
+                        "[{}: {}] {}",
+                        t!("tasks-board-filter-prompt"),
+                        self.filter,
+                        text
+                    );
+                }
+                if let Some(ref notice) = self.notice {
+                    text = format!("{} — {}", notice, text);
+                }
+                Line::from(text)
+            }
+        }
+    }
+}
+
+fn column_title(status: TaskStatus) -> String {
+    match status {
+        TaskStatus::Todo => t!("tasks-board-column-todo"),
+        TaskStatus::InProgress => t!("tasks-board-column-in-progress"),
+        TaskStatus::Blocked => t!("tasks-board-column-blocked"),
+        TaskStatus::Done => t!("tasks-board-column-done"),
+        TaskStatus::Cancelled => t!("tasks-board-column-cancelled"),
+    }
+}
+
+fn status_color(status: TaskStatus) -> Color {
+    match status {
+        TaskStatus::Todo => Color::White,
+        TaskStatus::InProgress => Color::Blue,
+        TaskStatus::Done => Color::Green,
+        TaskStatus::Blocked => Color::Red,
+        TaskStatus::Cancelled => Color::DarkGray,
+    }
+}
+
+fn load_cards(tasks: &TaskManager) -> Result<Vec<Card>, String> {
+    let all = tasks.list().map_err(|e| e.to_string())?;
+    let mut cards = Vec::with_capacity(all.len());
+    for task in all {
+        let open_deps = tasks
+            .get_dependencies(task.id)
+            .map_err(|e| e.to_string())?
+            .iter()
+            .filter(|d| !d.status.is_complete())
+            .count();
+        cards.push(Card { task, open_deps });
+    }
+    Ok(cards)
+}
+
+/// Counters reported back to the shell once the board closes.
+#[derive(Debug, Default)]
+pub struct BoardSummary {
+    pub moved: usize,
+    pub created: usize,
+}
+
+/// Run the board until the user quits. Blocks the calling thread on terminal input.
+pub fn run(tasks: &TaskManager, filter: Option<String>) -> Result<BoardSummary, String> {
+    let mut board = Board::new(filter);
+    board.set_cards(load_cards(tasks)?);
+    // Without a watcher the board falls back to periodic reloads
+    let watcher = tasks.watch().ok();
+
+    let mut terminal =
+        ratatui::try_init().map_err(|e| t!("tasks-board-no-terminal", "error" => e.to_string()))?;
+    let result = event_loop(&mut terminal, &mut board, tasks, watcher.as_ref());
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    board: &mut Board,
+    tasks: &TaskManager,
+    watcher: Option<&StoreWatcher>,
+) -> Result<BoardSummary, String> {
+    let mut summary = BoardSummary::default();
+    let mut last_reload = Instant::now();
+
+    loop {
+        terminal
+            .draw(|f| board.render(f))
+            .map_err(|e| e.to_string())?;
+
+        let mut reload = match watcher {
+            Some(watcher) => watcher.changed(),
+            None => last_reload.elapsed() >= FALLBACK_RELOAD,
+        };
+
+        if !reload && event::poll(WATCH_TICK).map_err(|e| e.to_string())? {
+            let Event::Key(key) = event::read().map_err(|e| e.to_string())? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match board.handle_key(key) {
+                Action::None => {}
+                Action::Quit => return Ok(summary),
+                Action::Refresh => reload = true,
+                Action::Move(id, status) => {
+                    match tasks.update_status(id, status) {
+                        Ok(()) => {
+                            summary.moved += 1;
+                            board.set_notice(t!("tasks-status-updated", "id" => id.get().to_string(), "status" => status.to_string()));
+                        }
+                        Err(e) => board.set_notice(e.to_string()),
+                    }
+                    reload = true;
+                }
+                Action::Create(title) => {
+                    match tasks.create_task(CreateTask::new(&title)) {
+                        Ok(id) => {
+                            summary.created += 1;
+                            board.set_notice(t!("tasks-add-created", "id" => id.get().to_string(), "title" => title.as_str()));
+                        }
+                        Err(e) => board.set_notice(e.to_string()),
+                    }
+                    reload = true;
+                }
+            }
+        }
+
+        if reload {
+            board.set_cards(load_cards(tasks)?);
+            last_reload = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(id: i64, title: &str, status: TaskStatus) -> Card {
+        let mut task = Task::new(title);
+        task.id = TaskId::new(id);
+        task.status = status;
+        Card { task, open_deps: 0 }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn board() -> Board {
+        let mut board = Board::new(None);
+        board.set_cards(vec![
+            card(1, "Write parser", TaskStatus::Todo),
+            card(2, "Write lexer", TaskStatus::Todo),
+            card(3, "Ship it", TaskStatus::InProgress),
+        ]);
+        board
+    }
+
+    #[test]
+    fn test_shift_arrow_moves_card() {
+        let mut board = board();
+        board.handle_key(key(KeyCode::Down));
+
+        let action = board.handle_key(KeyEvent::new(KeyCode::Right, KeyModifiers::SHIFT));
+        assert_eq!(action, Action::Move(TaskId::new(2), TaskStatus::InProgress));
+    }
+
+    #[test]
+    fn test_drag_and_drop() {
+        let mut board = board();
+        board.handle_key(key(KeyCode::Char(' ')));
+        assert!(matches!(board.mode(), Mode::Dragging { .. }));
+
+        board.handle_key(key(KeyCode::Right));
+        board.handle_key(key(KeyCode::Right));
+        let action = board.handle_key(key(KeyCode::Enter));
+        assert_eq!(action, Action::Move(TaskId::new(1), TaskStatus::Blocked));
+        assert_eq!(board.mode(), &Mode::Browse);
+    }
+
+    #[test]
+    fn test_drop_in_place_is_noop() {
+        let mut board = board();
+        board.handle_key(key(KeyCode::Char(' ')));
+        assert_eq!(board.handle_key(key(KeyCode::Enter)), Action::None);
+    }
+
+    #[test]
+    fn test_filter_narrows_columns() {
+        let mut board = board();
+        board.handle_key(key(KeyCode::Char('/')));
+        for c in "lexer".chars() {
+            board.handle_key(key(KeyCode::Char(c)));
+        }
+        board.handle_key(key(KeyCode::Enter));
+
+        assert_eq!(board.column_cards(0).len(), 1);
+        assert_eq!(board.selected().unwrap().task.id, TaskId::new(2));
+    }
+
+    #[test]
+    fn test_quick_add() {
+        let mut board = board();
+        board.handle_key(key(KeyCode::Char('a')));
+        for c in "New task".chars() {
+            board.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(
+            board.handle_key(key(KeyCode::Enter)),
+            Action::Create("New task".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reload_keeps_selection() {
+        let mut board = board();
+        board.handle_key(key(KeyCode::Down));
+
+        let mut cards = vec![card(2, "Write lexer", TaskStatus::InProgress)];
+        cards.push(card(1, "Write parser", TaskStatus::Todo));
+        board.set_cards(cards);
+
+        assert_eq!(board.selected().unwrap().task.id, TaskId::new(2));
+    }
+}
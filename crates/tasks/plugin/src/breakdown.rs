@@ -0,0 +1,164 @@
+//! AI-assisted task breakdown for `adi tasks breakdown`.
+//!
+//! The task is sent to an LLM through agent-loop's provider factory; the
+//! default `signaling` provider routes the request over the ADI channel to
+//! the LLM proxy service of a paired cocoon. The model answers with a
+//! JSON list of subtasks whose dependencies point at earlier entries, which
+//! keeps every proposal acyclic by construction.
+
+use agent_loop_core::{create_provider, LlmConfig, Message, ProviderConfig};
+use serde::Deserialize;
+
+use tasks_core::{CreateTask, Task, TaskId, TaskManager};
+
+const SYSTEM_PROMPT: &str = "You break software tasks into small, concrete subtasks. \
+Reply with a JSON array only, no prose. Each element is an object with \
+\"title\" (short imperative sentence), optional \"description\", and \
+\"depends_on\": zero-based indices of EARLIER elements that must be finished first. \
+Propose between 2 and 8 subtasks.";
+
+/// Plugin config key naming the model used when `--model` is not given
+pub const MODEL_CONFIG_KEY: &str = "breakdown_model";
+
+/// A subtask suggested by the model
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Proposal {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Indices of earlier proposals
+    #[serde(default)]
+    pub depends_on: Vec<usize>,
+}
+
+/// Ask the model for subtasks of `task`.
+pub async fn propose(task: &Task, provider: &str, model: &str) -> Result<Vec<Proposal>, String> {
+    let config = ProviderConfig::from_env(provider, model).map_err(|e| e.to_string())?;
+    let llm = create_provider(config).await.map_err(|e| e.to_string())?;
+
+    let messages = [
+        Message::system(SYSTEM_PROMPT),
+        Message::user(user_prompt(task)),
+    ];
+    let llm_config = LlmConfig {
+        model: model.to_string(),
+        temperature: 0.2,
+        max_tokens: 2048,
+        ..Default::default()
+    };
+    let response = llm
+        .complete(&messages, &[], &llm_config)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    parse(response.message.content().unwrap_or_default())
+}
+
+fn user_prompt(task: &Task) -> String {
+    match task.description.as_deref() {
+        Some(desc) if !desc.trim().is_empty() => format!("Task: {}\n\n{}", task.title, desc),
+        _ => format!("Task: {}", task.title),
+    }
+}
+
+/// Parse the model's reply, tolerating a Markdown code fence around the array.
+pub fn parse(reply: &str) -> Result<Vec<Proposal>, String> {
+    let start = reply.find('[').ok_or("reply contains no JSON array")?;
+    let end = reply.rfind(']').ok_or("reply contains no JSON array")?;
+    if end < start {
+        return Err("reply contains no JSON array".to_string());
+    }
+
+    let proposals: Vec<Proposal> = serde_json::from_str(&reply[start..=end])
+        .map_err(|e| format!("invalid subtask list: {}", e))?;
+
+    for (i, p) in proposals.iter().enumerate() {
+        if p.title.trim().is_empty() {
+            return Err(format!("subtask {} has no title", i + 1));
+        }
+        if let Some(&dep) = p.depends_on.iter().find(|&&dep| dep >= i) {
+            return Err(format!(
+                "subtask {} depends on non-earlier subtask {}",
+                i + 1,
+                dep + 1
+            ));
+        }
+    }
+    Ok(proposals)
+}
+
+/// Create the `selected` proposals and make `parent` depend on them.
+///
+/// Dependencies on proposals that were not selected are dropped. Returns the
+/// new task ids in proposal order.
+pub fn create(
+    tasks: &TaskManager,
+    parent: TaskId,
+    proposals: &[Proposal],
+    selected: &[usize],
+) -> tasks_core::Result<Vec<TaskId>> {
+    let mut ids: Vec<Option<TaskId>> = vec![None; proposals.len()];
+
+    for (i, proposal) in proposals.iter().enumerate() {
+        if !selected.contains(&i) {
+            continue;
+        }
+        let deps = proposal
+            .depends_on
+            .iter()
+            .filter_map(|&dep| ids[dep])
+            .collect();
+        let mut input = CreateTask::new(&proposal.title).with_dependencies(deps);
+        if let Some(desc) = &proposal.description {
+            input = input.with_description(desc);
+        }
+        let id = tasks.create_task(input)?;
+        tasks.add_dependency(parent, id)?;
+        ids[i] = Some(id);
+    }
+
+    Ok(ids.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fenced_reply() {
+        let reply = "Here you go:\n```json\n[\
+            {\"title\": \"Add schema\"},\
+            {\"title\": \"Write handler\", \"description\": \"POST /tasks\", \"depends_on\": [0]}\
+        ]\n```";
+        let proposals = parse(reply).unwrap();
+        assert_eq!(proposals.len(), 2);
+        assert_eq!(proposals[1].description.as_deref(), Some("POST /tasks"));
+        assert_eq!(proposals[1].depends_on, vec![0]);
+    }
+
+    #[test]
+    fn test_parse_rejects_forward_dependencies() {
+        assert!(parse(r#"[{"title": "a", "depends_on": [1]}, {"title": "b"}]"#).is_err());
+        assert!(parse(r#"[{"title": "a", "depends_on": [0]}]"#).is_err());
+        assert!(parse("no idea").is_err());
+    }
+
+    #[test]
+    fn test_create_drops_unselected_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let tasks = TaskManager::open(dir.path()).unwrap();
+        let parent = tasks.create_task(CreateTask::new("Ship API")).unwrap();
+
+        let proposals = parse(
+            r#"[{"title": "Schema"}, {"title": "Handler", "depends_on": [0]}, {"title": "Docs", "depends_on": [1]}]"#,
+        )
+        .unwrap();
+        let ids = create(&tasks, parent, &proposals, &[0, 2]).unwrap();
+        assert_eq!(ids.len(), 2);
+
+        let docs = tasks.get_dependencies(ids[1]).unwrap();
+        assert!(docs.is_empty());
+        let parent_deps = tasks.get_dependencies(parent).unwrap();
+        assert_eq!(parent_deps.len(), 2);
+    }
+}
@@ -1,9 +1,17 @@
+mod board;
+mod breakdown;
+mod webhooks;
+
 use lib_plugin_prelude::*;
 use serde_json::json;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use tasks_core::{CreateTask, TaskId, TaskManager, TaskStatus};
+use lib_console_output::theme::{borders, icons, Glyph};
+use tasks_core::{
+    unix_timestamp_now, CreateTask, NextTask, TaskAttachment, TaskId, TaskManager, TaskStatus, TasksService,
+    WebhookEvent,
+};
 
 #[derive(CliArgs)]
 pub struct ListArgs {
@@ -30,6 +38,27 @@ pub struct AddArgs {
 
     #[arg(long = "depends-on")]
     pub depends_on: Option<String>,
+
+    #[arg(long, default = 0)]
+    pub priority: i64,
+
+    #[arg(long)]
+    pub tags: Option<String>,
+}
+
+#[derive(CliArgs)]
+pub struct NextArgs {
+    #[arg(long)]
+    pub tag: Option<String>,
+
+    #[arg(long, default = 1)]
+    pub limit: i64,
+
+    #[arg(long)]
+    pub start: bool,
+
+    #[arg(long, default = "text".to_string())]
+    pub format: String,
 }
 
 #[derive(CliArgs)]
@@ -89,6 +118,76 @@ pub struct SearchArgs {
     pub limit: i64,
 }
 
+#[derive(CliArgs)]
+pub struct BoardArgs {
+    #[arg(long)]
+    pub filter: Option<String>,
+}
+
+#[derive(CliArgs)]
+pub struct BreakdownArgs {
+    #[arg(position = 0)]
+    pub id: i64,
+
+    #[arg(long, default = "signaling".to_string())]
+    pub provider: String,
+
+    // Defaults to the `breakdown_model` config key
+    #[arg(long)]
+    pub model: Option<String>,
+
+    #[arg(long)]
+    pub yes: bool,
+}
+
+#[derive(CliArgs)]
+pub struct AttachArgs {
+    #[arg(position = 0)]
+    pub id: i64,
+
+    #[arg(position = 1)]
+    pub target: String,
+}
+
+#[derive(CliArgs)]
+pub struct DetachArgs {
+    #[arg(position = 0)]
+    pub id: i64,
+
+    #[arg(position = 1)]
+    pub target: String,
+}
+
+#[derive(CliArgs)]
+pub struct AttachmentsArgs {
+    #[arg(position = 0)]
+    pub id: i64,
+
+    #[arg(long, default = "text".to_string())]
+    pub format: String,
+}
+
+#[derive(CliArgs)]
+pub struct WebhooksArgs {
+    #[arg(position = 0)]
+    pub subcommand: Option<String>,
+
+    #[arg(position = 1)]
+    pub target: Option<String>,
+
+    #[arg(long)]
+    pub events: Option<String>,
+
+    #[arg(long)]
+    pub secret: Option<String>,
+
+    #[arg(long, default = 20)]
+    pub limit: i64,
+
+    #[arg(long, default = "text".to_string())]
+    pub format: String,
+}
+
 pub struct TasksPlugin {
     tasks: Arc<RwLock<Option<TaskManager>>>,
 }
@@ -117,7 +216,8 @@ impl Plugin for TasksPlugin {
             .with_description(t!("plugin-description"))
     }
 
-    async fn init(&mut self, _ctx: &PluginContext) -> Result<()> {
+    async fn init(&mut self, ctx: &PluginContext) -> Result<()> {
+        PluginCtx::init(ctx);
         lib_plugin_prelude::init_plugin_i18n(
             "en-US",
             include_str!("../locales/en-US/messages.ftl"),
@@ -129,8 +229,71 @@ impl Plugin for TasksPlugin {
         Ok(())
     }
 
+    async fn handle_event(&self, event: &PluginEvent) -> Result<()> {
+        let PluginEvent::ProjectChanged(project) = event else {
+            return Ok(());
+        };
+
+        let manager = match project_store(project.as_ref()) {
+            Some(root) => TaskManager::open(root),
+            None => TaskManager::open_global(),
+        };
+        *self.tasks.write().await = manager.ok();
+        Ok(())
+    }
+
     fn provides(&self) -> Vec<&'static str> {
-        vec![SERVICE_CLI_COMMANDS]
+        vec![SERVICE_CLI_COMMANDS, SERVICE_SEARCH_PROVIDER, SERVICE_ADI_SERVICE]
+    }
+}
+
+/// Root of the task store `project` uses: projects that keep their own task
+/// store use it, everything else the global one (`None`)
+fn project_store(project: Option<&ProjectContext>) -> Option<&std::path::Path> {
+    project
+        .filter(|project| project.adi_dir().join("tasks").is_dir())
+        .map(|project| project.root.as_path())
+}
+
+#[async_trait]
+impl AdiServiceProvider for TasksPlugin {
+    async fn adi_service(&self, project: Option<&ProjectContext>) -> Result<Arc<dyn AdiService>> {
+        let service = match project_store(project) {
+            Some(root) => TasksService::new(root),
+            None => TasksService::new_global(),
+        }
+        .map_err(PluginError::Runtime)?;
+        Ok(Arc::new(service))
+    }
+}
+
+#[async_trait]
+impl SearchProvider for TasksPlugin {
+    fn search_types(&self) -> Vec<String> {
+        vec!["tasks".to_string()]
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let guard = self.tasks.read().await;
+        let Some(tasks) = guard.as_ref() else {
+            return Ok(Vec::new());
+        };
+
+        let results = tasks
+            .search(&query.text, query.limit)
+            .map_err(|e| PluginError::Runtime(e.to_string()))?;
+        let total = results.len();
+
+        Ok(results
+            .into_iter()
+            .enumerate()
+            .map(|(rank, task)| {
+                let id = task.id.get();
+                SearchHit::new("tasks", id.to_string(), task.title, rank_score(rank, total))
+                    .with_snippet(task.status.to_string())
+                    .with_open_command(format!("adi tasks show {}", id))
+            })
+            .collect())
     }
 }
 
@@ -140,6 +303,7 @@ impl CliCommands for TasksPlugin {
         vec![
             Self::__sdk_cmd_meta_list(),
             Self::__sdk_cmd_meta_add(),
+            Self::__sdk_cmd_meta_next(),
             Self::__sdk_cmd_meta_show(),
             Self::__sdk_cmd_meta_status(),
             Self::__sdk_cmd_meta_delete(),
@@ -150,6 +314,12 @@ impl CliCommands for TasksPlugin {
             Self::__sdk_cmd_meta_blocked(),
             Self::__sdk_cmd_meta_cycles(),
             Self::__sdk_cmd_meta_stats(),
+            Self::__sdk_cmd_meta_board(),
+            Self::__sdk_cmd_meta_breakdown(),
+            Self::__sdk_cmd_meta_attach(),
+            Self::__sdk_cmd_meta_detach(),
+            Self::__sdk_cmd_meta_attachments(),
+            Self::__sdk_cmd_meta_webhooks(),
         ]
     }
 
@@ -157,6 +327,7 @@ impl CliCommands for TasksPlugin {
         match ctx.subcommand.as_deref() {
             Some("list") => self.__sdk_cmd_handler_list(ctx).await,
             Some("add") => self.__sdk_cmd_handler_add(ctx).await,
+            Some("next") => self.__sdk_cmd_handler_next(ctx).await,
             Some("show") => self.__sdk_cmd_handler_show(ctx).await,
             Some("status") => self.__sdk_cmd_handler_status(ctx).await,
             Some("delete") => self.__sdk_cmd_handler_delete(ctx).await,
@@ -167,12 +338,123 @@ impl CliCommands for TasksPlugin {
             Some("blocked") => self.__sdk_cmd_handler_blocked(ctx).await,
             Some("cycles") => self.__sdk_cmd_handler_cycles(ctx).await,
             Some("stats") => self.__sdk_cmd_handler_stats(ctx).await,
+            Some("board") => self.__sdk_cmd_handler_board(ctx).await,
+            Some("breakdown") => self.__sdk_cmd_handler_breakdown(ctx).await,
+            Some("attach") => self.__sdk_cmd_handler_attach(ctx).await,
+            Some("detach") => self.__sdk_cmd_handler_detach(ctx).await,
+            Some("attachments") => self.__sdk_cmd_handler_attachments(ctx).await,
+            Some("webhooks") => self.__sdk_cmd_handler_webhooks(ctx).await,
             Some(cmd) => Ok(CliResult::error(format!("Unknown command: {}", cmd))),
             None => Ok(CliResult::success(self.help())),
         }
     }
 }
 
+/// Categorize a core error so scripts can tell a missing task from a storage failure.
+fn task_error(e: tasks_core::Error) -> CliError {
+    use tasks_core::Error;
+    match e {
+        Error::TaskNotFound(id) => {
+            CliError::not_found(t!("error-task-not-found", "id" => id.get().to_string()))
+                .with_hint(t!("error-task-not-found-hint"))
+        }
+        Error::DependencyNotFound { .. } => CliError::not_found(e.to_string()),
+        Error::SelfDependency(_) | Error::InvalidStatus(_) => CliError::invalid_input(e.to_string()),
+        Error::NotInitialized(_) => CliError::unavailable(e.to_string()),
+        Error::AttachmentNotFound(ref target) => {
+            CliError::not_found(t!("error-attachment-not-found", "target" => target.as_str()))
+        }
+        Error::NotAttached(id, ref target) => CliError::not_found(
+            t!("error-not-attached", "id" => id.get().to_string(), "target" => target.as_str()),
+        )
+        .with_hint(t!("error-not-attached-hint", "id" => id.get().to_string())),
+        Error::AttachmentTooLarge { .. } => CliError::invalid_input(e.to_string()),
+        Error::AttachmentQuotaExceeded { .. } => {
+            CliError::unavailable(e.to_string()).with_hint(t!("error-attachment-quota-hint"))
+        }
+        Error::WebhookNotFound(id) => {
+            CliError::not_found(t!("error-webhook-not-found", "id" => id.to_string()))
+                .with_hint(t!("error-webhook-not-found-hint"))
+        }
+        Error::InvalidWebhook(_) => CliError::invalid_input(e.to_string()),
+        _ => CliError::general(e.to_string()),
+    }
+}
+
+/// Who to record as having added an attachment
+fn current_user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|user| !user.is_empty())
+}
+
+fn attachment_line(attachment: &TaskAttachment) -> String {
+    let mut details = Vec::new();
+    if let Some(ref mime) = attachment.mime_type {
+        details.push(mime.clone());
+    }
+    if let Some(size) = attachment.size_bytes {
+        details.push(t!("tasks-attachments-size", "bytes" => size.to_string()));
+    }
+    if let Some(ref user) = attachment.added_by {
+        details.push(user.clone());
+    }
+    if details.is_empty() {
+        attachment.target.clone()
+    } else {
+        format!("{} ({})", attachment.target, details.join(", "))
+    }
+}
+
+/// Status icon in the active output theme (`adi config set output.theme`)
+fn status_icon(status: TaskStatus) -> Glyph {
+    match status {
+        TaskStatus::Todo => icons::PENDING,
+        TaskStatus::InProgress => icons::IN_PROGRESS,
+        TaskStatus::Done => icons::COMPLETE,
+        TaskStatus::Blocked => icons::BLOCKED,
+        TaskStatus::Cancelled => icons::CANCELLED,
+    }
+}
+
+/// Compact duration such as `1h 20m`
+fn format_duration(secs: i64) -> String {
+    let secs = secs.max(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// Why `next` picked a task, one line per reason
+fn next_reasons(pick: &NextTask, rank: usize, tag: Option<&str>, now: i64) -> Vec<String> {
+    let mut reasons = vec![if pick.completed_dependencies == 0 {
+        t!("tasks-next-reason-no-deps")
+    } else {
+        t!("tasks-next-reason-ready", "count" => pick.completed_dependencies.to_string())
+    }];
+    reasons.push(t!(
+        "tasks-next-reason-rank",
+        "rank" => rank.to_string(),
+        "count" => pick.candidates.to_string(),
+        "priority" => pick.task.priority.to_string(),
+        "age" => format_duration(now - pick.task.created_at)
+    ));
+    if pick.same_priority > 0 {
+        reasons.push(t!("tasks-next-reason-tie", "count" => pick.same_priority.to_string()));
+    }
+    if pick.unblocks > 0 {
+        reasons.push(t!("tasks-next-reason-unblocks", "count" => pick.unblocks.to_string()));
+    }
+    if let Some(tag) = tag {
+        reasons.push(t!("tasks-next-reason-tag", "tag" => tag));
+    }
+    reasons
+}
+
 fn scope_label(task: &tasks_core::Task) -> String {
     if task.is_global() {
         t!("tasks-list-scope-global")
@@ -182,10 +464,10 @@ fn scope_label(task: &tasks_core::Task) -> String {
 }
 
 impl TasksPlugin {
-    async fn manager(&self) -> std::result::Result<tokio::sync::RwLockReadGuard<'_, Option<TaskManager>>, String> {
+    async fn manager(&self) -> std::result::Result<tokio::sync::RwLockReadGuard<'_, Option<TaskManager>>, CliError> {
         let guard = self.tasks.read().await;
         if guard.is_none() {
-            return Err(t!("error-not-initialized"));
+            return Err(CliError::unavailable(t!("error-not-initialized")));
         }
         Ok(guard)
     }
@@ -195,6 +477,7 @@ impl TasksPlugin {
             "{}\n\n{}\n  \
              list     {}\n  \
              add      {}\n  \
+             next     {}\n  \
              show     {}\n  \
              status   {}\n  \
              delete   {}\n  \
@@ -204,12 +487,19 @@ impl TasksPlugin {
              search   {}\n  \
              blocked  {}\n  \
              cycles   {}\n  \
-             stats    {}\n\n\
+             stats    {}\n  \
+             board    {}\n  \
+             breakdown {}\n  \
+             attach   {}\n  \
+             detach   {}\n  \
+             attachments {}\n  \
+             webhooks {}\n\n\
              {}",
             t!("tasks-help-title"),
             t!("tasks-help-commands"),
             t!("cmd-list-help"),
             t!("cmd-add-help"),
+            t!("cmd-next-help"),
             t!("cmd-show-help"),
             t!("cmd-status-help"),
             t!("cmd-delete-help"),
@@ -220,30 +510,36 @@ impl TasksPlugin {
             t!("cmd-blocked-help"),
             t!("cmd-cycles-help"),
             t!("cmd-stats-help"),
+            t!("cmd-board-help"),
+            t!("cmd-breakdown-help"),
+            t!("cmd-attach-help"),
+            t!("cmd-detach-help"),
+            t!("cmd-attachments-help"),
+            t!("cmd-webhooks-help"),
             t!("tasks-help-usage"),
         )
     }
 
     #[command(name = "list", description = "cmd-list-help")]
-    async fn list(&self, args: ListArgs) -> CmdResult {
+    async fn list(&self, args: ListArgs) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
 
         let task_list = if args.ready {
-            tasks.get_ready().map_err(|e| e.to_string())?
+            tasks.get_ready().map_err(task_error)?
         } else if args.blocked {
-            tasks.get_blocked().map_err(|e| e.to_string())?
+            tasks.get_blocked().map_err(task_error)?
         } else if let Some(ref status_str) = args.status {
             let status: TaskStatus = status_str.parse().map_err(|_| {
-                t!("tasks-status-invalid-status", "status" => status_str.as_str())
+                CliError::invalid_input(t!("tasks-status-invalid-status", "status" => status_str.as_str()))
             })?;
-            tasks.get_by_status(status).map_err(|e| e.to_string())?
+            tasks.get_by_status(status).map_err(task_error)?
         } else {
-            tasks.list().map_err(|e| e.to_string())?
+            tasks.list().map_err(task_error)?
         };
 
         if args.format == "json" {
-            return serde_json::to_string_pretty(&task_list).map_err(|e| e.to_string());
+            return serde_json::to_string_pretty(&task_list).map_err(|e| CliError::general(e.to_string()));
         }
 
         if task_list.is_empty() {
@@ -253,13 +549,13 @@ impl TasksPlugin {
         let mut output = String::new();
         for task in task_list {
             let scope = scope_label(&task);
-            output.push_str(&format!("{} #{} {} {}\n", task.status.icon(), task.id.get(), task.title, scope));
+            output.push_str(&format!("{} #{} {} {}\n", status_icon(task.status), task.id.get(), task.title, scope));
         }
         Ok(output.trim_end().to_string())
     }
 
     #[command(name = "add", description = "cmd-add-help")]
-    async fn add(&self, args: AddArgs) -> CmdResult {
+    async fn add(&self, args: AddArgs) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
 
@@ -268,24 +564,75 @@ impl TasksPlugin {
             .map(|s| s.split(',').filter_map(|id| id.trim().parse().ok()).collect())
             .unwrap_or_default();
 
-        let mut input = CreateTask::new(&args.title);
+        let mut input = CreateTask::new(&args.title).with_priority(args.priority);
         if let Some(desc) = args.description {
             input = input.with_description(desc);
         }
+        if let Some(tags) = args.tags {
+            input = input.with_tags(vec![tags]);
+        }
         if !depends_on_ids.is_empty() {
             input = input.with_dependencies(depends_on_ids.into_iter().map(TaskId::new).collect());
         }
 
-        let id = tasks.create_task(input).map_err(|e| e.to_string())?;
+        let id = tasks.create_task(input).map_err(task_error)?;
+        webhooks::send_due(tasks).await;
         Ok(t!("tasks-add-created", "id" => id.get().to_string(), "title" => args.title.as_str()))
     }
 
+    #[command(name = "next", description = "cmd-next-help")]
+    async fn next(&self, args: NextArgs) -> CmdResult<CliError> {
+        let guard = self.manager().await?;
+        let tasks = guard.as_ref().unwrap();
+
+        let tag = args.tag.as_deref().map(str::trim).filter(|t| !t.is_empty());
+        let mut picks = tasks.next_tasks(tag, args.limit.max(1) as usize).map_err(task_error)?;
+
+        // Only the top pick is started, whatever the limit
+        let started = match picks.first_mut() {
+            Some(pick) if args.start => {
+                pick.task = tasks.start_task(pick.task.id).map_err(task_error)?;
+                webhooks::send_due(tasks).await;
+                Some(pick.task.clone())
+            }
+            _ => None,
+        };
+
+        if args.format == "json" {
+            return serde_json::to_string_pretty(&picks).map_err(|e| CliError::general(e.to_string()));
+        }
+
+        if picks.is_empty() {
+            return Ok(match tag {
+                Some(tag) => t!("tasks-next-empty-tag", "tag" => tag),
+                None => t!("tasks-next-empty"),
+            });
+        }
+
+        let now = unix_timestamp_now();
+        let mut output = format!("{}\n\n", t!("tasks-next-title"));
+        for (i, pick) in picks.iter().enumerate() {
+            output.push_str(&format!("{} #{} {}\n", status_icon(pick.task.status), pick.task.id.get(), pick.task.title));
+
+            let reasons = next_reasons(pick, i + 1, tag, now);
+            for (j, reason) in reasons.iter().enumerate() {
+                let prefix = if j == reasons.len() - 1 { borders::LAST_BRANCH } else { borders::BRANCH };
+                output.push_str(&format!("  {} {}\n", prefix, reason));
+            }
+        }
+
+        if let Some(task) = started {
+            output.push_str(&format!("\n{}\n", t!("tasks-next-started", "id" => task.id.get().to_string(), "title" => task.title.as_str())));
+        }
+        Ok(output.trim_end().to_string())
+    }
+
     #[command(name = "show", description = "cmd-show-help")]
-    async fn show(&self, args: ShowArgs) -> CmdResult {
+    async fn show(&self, args: ShowArgs) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
 
-        let task_with_deps = tasks.get_task_with_dependencies(TaskId::new(args.id)).map_err(|e| e.to_string())?;
+        let task_with_deps = tasks.get_task_with_dependencies(TaskId::new(args.id)).map_err(task_error)?;
         let task = &task_with_deps.task;
 
         let mut output = format!("{}\n", t!("tasks-show-title", "id" => task.id.get().to_string()));
@@ -298,6 +645,15 @@ impl TasksPlugin {
         if let Some(symbol_id) = task.symbol_id {
             output.push_str(&format!("  {}\n", t!("tasks-show-field-symbol", "symbol_id" => symbol_id.to_string())));
         }
+        if task.priority != 0 {
+            output.push_str(&format!("  {}\n", t!("tasks-show-field-priority", "priority" => task.priority.to_string())));
+        }
+        if !task.tags.is_empty() {
+            output.push_str(&format!("  {}\n", t!("tasks-show-field-tags", "tags" => task.tags.join(", "))));
+        }
+        if task_with_deps.tracked_secs > 0 {
+            output.push_str(&format!("  {}\n", t!("tasks-show-field-tracked", "duration" => format_duration(task_with_deps.tracked_secs))));
+        }
 
         let scope = if task.is_global() { "global" } else { "project" };
         output.push_str(&format!("  {}\n", t!("tasks-show-field-scope", "scope" => scope)));
@@ -314,28 +670,35 @@ impl TasksPlugin {
                 output.push_str(&format!("    #{}: {}\n", dep.id.get(), dep.title));
             }
         }
+        if !task_with_deps.attachments.is_empty() {
+            output.push_str(&format!("\n  {}\n", t!("tasks-show-attachments")));
+            for attachment in &task_with_deps.attachments {
+                output.push_str(&format!("    {}\n", attachment_line(attachment)));
+            }
+        }
 
         Ok(output.trim_end().to_string())
     }
 
     #[command(name = "status", description = "cmd-status-help")]
-    async fn status(&self, args: StatusArgs) -> CmdResult {
+    async fn status(&self, args: StatusArgs) -> CmdResult<CliError> {
         let status: TaskStatus = args.status.parse().map_err(|_| {
-            t!("tasks-status-invalid-status", "status" => args.status.as_str())
+            CliError::invalid_input(t!("tasks-status-invalid-status", "status" => args.status.as_str()))
         })?;
 
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
-        tasks.update_status(TaskId::new(args.id), status).map_err(|e| e.to_string())?;
+        tasks.update_status(TaskId::new(args.id), status).map_err(task_error)?;
+        webhooks::send_due(tasks).await;
         Ok(t!("tasks-status-updated", "id" => args.id.to_string(), "status" => status.to_string()))
     }
 
     #[command(name = "delete", description = "cmd-delete-help")]
-    async fn delete(&self, args: DeleteArgs) -> CmdResult {
+    async fn delete(&self, args: DeleteArgs) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
 
-        let task = tasks.get_task(TaskId::new(args.id)).map_err(|e| e.to_string())?;
+        let task = tasks.get_task(TaskId::new(args.id)).map_err(task_error)?;
 
         if !args.force {
             return Ok(format!(
@@ -345,42 +708,45 @@ impl TasksPlugin {
             ));
         }
 
-        tasks.delete_task(TaskId::new(args.id)).map_err(|e| e.to_string())?;
+        tasks.delete_task(TaskId::new(args.id)).map_err(task_error)?;
         Ok(t!("tasks-delete-success", "id" => args.id.to_string(), "title" => task.title.as_str()))
     }
 
     #[command(name = "depend", description = "cmd-depend-help")]
-    async fn depend(&self, args: DependArgs) -> CmdResult {
+    async fn depend(&self, args: DependArgs) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
-        tasks.add_dependency(TaskId::new(args.task_id), TaskId::new(args.depends_on)).map_err(|e| e.to_string())?;
+        tasks.add_dependency(TaskId::new(args.task_id), TaskId::new(args.depends_on)).map_err(task_error)?;
+        webhooks::send_due(tasks).await;
         Ok(t!("tasks-depend-success", "task_id" => args.task_id.to_string(), "depends_on" => args.depends_on.to_string()))
     }
 
     #[command(name = "undepend", description = "cmd-undepend-help")]
-    async fn undepend(&self, args: UndependArgs) -> CmdResult {
+    async fn undepend(&self, args: UndependArgs) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
-        tasks.remove_dependency(TaskId::new(args.task_id), TaskId::new(args.depends_on)).map_err(|e| e.to_string())?;
+        tasks.remove_dependency(TaskId::new(args.task_id), TaskId::new(args.depends_on)).map_err(task_error)?;
         Ok(t!("tasks-undepend-success", "task_id" => args.task_id.to_string(), "depends_on" => args.depends_on.to_string()))
     }
 
     #[command(name = "graph", description = "cmd-graph-help")]
-    async fn graph(&self, args: GraphArgs) -> CmdResult {
+    async fn graph(&self, args: GraphArgs) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
-        let all_tasks = tasks.list().map_err(|e| e.to_string())?;
+        let all_tasks = tasks.list().map_err(task_error)?;
 
         if args.format == "json" {
             let mut graph_data = Vec::new();
             for task in &all_tasks {
-                let deps = tasks.get_dependencies(task.id).map_err(|e| e.to_string())?;
+                let deps = tasks.get_dependencies(task.id).map_err(task_error)?;
+                let attachments = tasks.get_attachments(task.id).map_err(task_error)?;
                 graph_data.push(json!({
                     "task": task,
-                    "dependencies": deps.iter().map(|d| d.id.get()).collect::<Vec<_>>()
+                    "dependencies": deps.iter().map(|d| d.id.get()).collect::<Vec<_>>(),
+                    "attachments": attachments
                 }));
             }
-            return serde_json::to_string_pretty(&graph_data).map_err(|e| e.to_string());
+            return serde_json::to_string_pretty(&graph_data).map_err(|e| CliError::general(e.to_string()));
         }
 
         if args.format == "dot" {
@@ -389,7 +755,7 @@ impl TasksPlugin {
                 let label = task.title.replace('"', "\\\"");
                 output.push_str(&format!("  {} [label=\"{}\" color=\"{}\"];\n", task.id.get(), label, task.status.color()));
 
-                let deps = tasks.get_dependencies(task.id).map_err(|e| e.to_string())?;
+                let deps = tasks.get_dependencies(task.id).map_err(task_error)?;
                 for dep in deps {
                     output.push_str(&format!("  {} -> {};\n", task.id.get(), dep.id.get()));
                 }
@@ -405,24 +771,24 @@ impl TasksPlugin {
 
         let mut output = format!("{}\n\n", t!("tasks-graph-title"));
         for task in &all_tasks {
-            output.push_str(&format!("{} #{} {}\n", task.status.icon(), task.id.get(), task.title));
+            output.push_str(&format!("{} #{} {}\n", status_icon(task.status), task.id.get(), task.title));
 
-            let deps = tasks.get_dependencies(task.id).map_err(|e| e.to_string())?;
+            let deps = tasks.get_dependencies(task.id).map_err(task_error)?;
             for (i, dep) in deps.iter().enumerate() {
-                let prefix = if i == deps.len() - 1 { "  └─" } else { "  ├─" };
-                output.push_str(&format!("{} {}\n", prefix, t!("tasks-graph-depends-on", "id" => dep.id.get().to_string(), "title" => dep.title.as_str())));
+                let prefix = if i == deps.len() - 1 { borders::LAST_BRANCH } else { borders::BRANCH };
+                output.push_str(&format!("  {} {}\n", prefix, t!("tasks-graph-depends-on", "id" => dep.id.get().to_string(), "title" => dep.title.as_str())));
             }
         }
         Ok(output.trim_end().to_string())
     }
 
     #[command(name = "search", description = "cmd-search-help")]
-    async fn search(&self, args: SearchArgs) -> CmdResult {
+    async fn search(&self, args: SearchArgs) -> CmdResult<CliError> {
         let limit = args.limit as usize;
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
 
-        let results = tasks.search(&args.query, limit).map_err(|e| e.to_string())?;
+        let results = tasks.search(&args.query, limit).map_err(task_error)?;
 
         if results.is_empty() {
             return Ok(t!("tasks-search-empty"));
@@ -430,16 +796,16 @@ impl TasksPlugin {
 
         let mut output = format!("{}\n\n", t!("tasks-search-results", "count" => results.len().to_string(), "query" => args.query.as_str()));
         for task in results {
-            output.push_str(&format!("{} #{} {}\n", task.status.icon(), task.id.get(), task.title));
+            output.push_str(&format!("{} #{} {}\n", status_icon(task.status), task.id.get(), task.title));
         }
         Ok(output.trim_end().to_string())
     }
 
     #[command(name = "blocked", description = "cmd-blocked-help")]
-    async fn blocked(&self) -> CmdResult {
+    async fn blocked(&self) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
-        let blocked = tasks.get_blocked().map_err(|e| e.to_string())?;
+        let blocked = tasks.get_blocked().map_err(task_error)?;
 
         if blocked.is_empty() {
             return Ok(t!("tasks-blocked-empty"));
@@ -447,13 +813,13 @@ impl TasksPlugin {
 
         let mut output = format!("{}\n\n", t!("tasks-blocked-title"));
         for task in blocked {
-            output.push_str(&format!("✕ #{} {}\n", task.id.get(), task.title));
+            output.push_str(&format!("{} #{} {}\n", icons::BLOCKED, task.id.get(), task.title));
 
-            let blockers = tasks.get_dependencies(task.id).map_err(|e| e.to_string())?;
+            let blockers = tasks.get_dependencies(task.id).map_err(task_error)?;
             let incomplete_blockers: Vec<_> = blockers.iter().filter(|t| !t.status.is_complete()).collect();
 
             for blocker in incomplete_blockers {
-                output.push_str(&format!("  └─ {}\n", t!("tasks-blocked-by", 
+                output.push_str(&format!("  {} {}\n", borders::LAST_BRANCH, t!("tasks-blocked-by", 
                     "id" => blocker.id.get().to_string(), 
                     "title" => blocker.title.as_str(), 
                     "status" => format!("{:?}", blocker.status)
@@ -464,10 +830,10 @@ impl TasksPlugin {
     }
 
     #[command(name = "cycles", description = "cmd-cycles-help")]
-    async fn cycles(&self) -> CmdResult {
+    async fn cycles(&self) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
-        let cycles = tasks.detect_cycles().map_err(|e| e.to_string())?;
+        let cycles = tasks.detect_cycles().map_err(task_error)?;
 
         if cycles.is_empty() {
             return Ok(t!("tasks-cycles-empty"));
@@ -483,10 +849,10 @@ impl TasksPlugin {
     }
 
     #[command(name = "stats", description = "cmd-stats-help")]
-    async fn stats(&self) -> CmdResult {
+    async fn stats(&self) -> CmdResult<CliError> {
         let guard = self.manager().await?;
         let tasks = guard.as_ref().unwrap();
-        let status = tasks.status().map_err(|e| e.to_string())?;
+        let status = tasks.status().map_err(task_error)?;
 
         let mut output = format!("{}\n\n", t!("tasks-stats-title"));
         output.push_str(&format!("  {}\n", t!("tasks-stats-total", "count" => status.total_tasks.to_string())));
@@ -505,6 +871,224 @@ impl TasksPlugin {
 
         Ok(output.trim_end().to_string())
     }
+
+    #[command(name = "board", description = "cmd-board-help")]
+    async fn board(&self, args: BoardArgs) -> CmdResult<CliError> {
+        // The board runs on its own handle so the plugin's store stays free
+        let tasks = {
+            let guard = self.manager().await?;
+            guard.as_ref().unwrap().reopen().map_err(task_error)?
+        };
+
+        let summary = board::run(&tasks, args.filter)?;
+        webhooks::send_due(&tasks).await;
+        Ok(t!("tasks-board-closed", "moved" => summary.moved.to_string(), "created" => summary.created.to_string()))
+    }
+
+    #[command(name = "breakdown", description = "cmd-breakdown-help")]
+    async fn breakdown(&self, args: BreakdownArgs) -> CmdResult<CliError> {
+        // The store stays unlocked while the model thinks and the user picks
+        let parent = {
+            let guard = self.manager().await?;
+            guard.as_ref().unwrap().get_task(TaskId::new(args.id)).map_err(task_error)?
+        };
+
+        let model = args
+            .model
+            .or_else(|| PluginCtx::config().get(breakdown::MODEL_CONFIG_KEY).and_then(|v| v.as_str()).map(str::to_string))
+            .ok_or_else(|| CliError::invalid_input(t!("tasks-breakdown-no-model")).with_hint(t!("tasks-breakdown-no-model-hint")))?;
+        let proposals = breakdown::propose(&parent, &args.provider, &model)
+            .await
+            .map_err(|e| {
+                CliError::unavailable(t!("tasks-breakdown-failed", "error" => e))
+                    .with_hint(t!("tasks-breakdown-failed-hint"))
+            })?;
+        if proposals.is_empty() {
+            return Ok(t!("tasks-breakdown-empty", "id" => args.id.to_string()));
+        }
+
+        let mut selected: Vec<usize> = if args.yes {
+            (0..proposals.len()).collect()
+        } else {
+            let items = proposals.iter().enumerate().map(|(i, p)| {
+                let label = if p.depends_on.is_empty() {
+                    format!("{}. {}", i + 1, p.title)
+                } else {
+                    let deps = p.depends_on.iter().map(|d| (d + 1).to_string()).collect::<Vec<_>>().join(", ");
+                    format!("{}. {} ({})", i + 1, p.title, t!("tasks-breakdown-after", "steps" => deps))
+                };
+                (label, i)
+            });
+            let prompt = t!("tasks-breakdown-confirm", "id" => args.id.to_string(), "title" => parent.title.as_str());
+            match lib_console_output::MultiSelect::new(prompt)
+                .items(items)
+                .defaults(0..proposals.len())
+                .run()
+            {
+                Some(selected) => selected,
+                None => return Ok(t!("tasks-breakdown-cancelled")),
+            }
+        };
+        if selected.is_empty() {
+            return Ok(t!("tasks-breakdown-cancelled"));
+        }
+        selected.sort_unstable();
+
+        let guard = self.manager().await?;
+        let tasks = guard.as_ref().unwrap();
+        let ids = breakdown::create(tasks, parent.id, &proposals, &selected).map_err(task_error)?;
+        webhooks::send_due(tasks).await;
+
+        let mut output = format!("{}\n", t!("tasks-breakdown-created", "count" => ids.len().to_string(), "id" => args.id.to_string()));
+        for (id, i) in ids.iter().zip(&selected) {
+            output.push_str(&format!("  #{} {}\n", id.get(), proposals[*i].title));
+        }
+        Ok(output.trim_end().to_string())
+    }
+
+    #[command(name = "attach", description = "cmd-attach-help")]
+    async fn attach(&self, args: AttachArgs) -> CmdResult<CliError> {
+        let guard = self.manager().await?;
+        let tasks = guard.as_ref().unwrap();
+
+        let user = current_user();
+        let attachment = tasks
+            .attach(TaskId::new(args.id), &args.target, user.as_deref())
+            .map_err(task_error)?;
+
+        let key = if attachment.is_file() { "tasks-attach-file" } else { "tasks-attach-link" };
+        Ok(t!(key, "id" => args.id.to_string(), "target" => attachment.target.as_str()))
+    }
+
+    #[command(name = "detach", description = "cmd-detach-help")]
+    async fn detach(&self, args: DetachArgs) -> CmdResult<CliError> {
+        let guard = self.manager().await?;
+        let tasks = guard.as_ref().unwrap();
+
+        let removed = tasks.detach(TaskId::new(args.id), &args.target).map_err(task_error)?;
+        Ok(t!("tasks-detach-done", "id" => args.id.to_string(), "target" => removed[0].target.as_str()))
+    }
+
+    #[command(name = "attachments", description = "cmd-attachments-help")]
+    async fn attachments(&self, args: AttachmentsArgs) -> CmdResult<CliError> {
+        let guard = self.manager().await?;
+        let tasks = guard.as_ref().unwrap();
+        let attachments = tasks.get_attachments(TaskId::new(args.id)).map_err(task_error)?;
+
+        if args.format == "json" {
+            let entries: Vec<_> = attachments
+                .iter()
+                .map(|a| json!({ "attachment": a, "stored_path": tasks.attachment_path(a) }))
+                .collect();
+            return serde_json::to_string_pretty(&entries).map_err(|e| CliError::general(e.to_string()));
+        }
+
+        if attachments.is_empty() {
+            return Ok(t!("tasks-attachments-empty", "id" => args.id.to_string()));
+        }
+
+        let mut output = format!("{}\n", t!("tasks-attachments-title", "id" => args.id.to_string()));
+        for attachment in &attachments {
+            output.push_str(&format!("  {}\n", attachment_line(attachment)));
+            if let Some(path) = tasks.attachment_path(attachment) {
+                output.push_str(&format!("    {}\n", t!("tasks-attachments-stored", "path" => path.display().to_string())));
+            }
+        }
+        Ok(output.trim_end().to_string())
+    }
+
+    #[command(name = "webhooks", description = "cmd-webhooks-help")]
+    async fn webhooks(&self, args: WebhooksArgs) -> CmdResult<CliError> {
+        let guard = self.manager().await?;
+        let tasks = guard.as_ref().unwrap();
+
+        match args.subcommand.as_deref().unwrap_or("list") {
+            "list" => {
+                let hooks = tasks.list_webhooks().map_err(task_error)?;
+                if args.format == "json" {
+                    return serde_json::to_string_pretty(&hooks).map_err(|e| CliError::general(e.to_string()));
+                }
+                if hooks.is_empty() {
+                    return Ok(t!("tasks-webhooks-empty"));
+                }
+                let mut output = format!("{}\n", t!("tasks-webhooks-title"));
+                for hook in &hooks {
+                    output.push_str(&format!("  {}\n", webhooks::webhook_line(hook)));
+                }
+                Ok(output.trim_end().to_string())
+            }
+            "add" => {
+                let url = args
+                    .target
+                    .ok_or_else(|| CliError::invalid_input(t!("tasks-webhooks-missing-url")))?;
+                let events = args
+                    .events
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|e| !e.is_empty())
+                    .map(|e| {
+                        e.parse::<WebhookEvent>()
+                            .map_err(|_| CliError::invalid_input(t!("tasks-webhooks-invalid-event", "event" => e)))
+                    })
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+
+                let hook = tasks.add_webhook(&url, &events, args.secret.as_deref()).map_err(task_error)?;
+                Ok(format!(
+                    "{}\n{}",
+                    t!("tasks-webhooks-added", "webhook" => webhooks::webhook_line(&hook)),
+                    t!("tasks-webhooks-secret", "secret" => hook.secret.as_str())
+                ))
+            }
+            "remove" => {
+                let id = args
+                    .target
+                    .as_deref()
+                    .and_then(|id| id.trim_start_matches('#').parse::<i64>().ok())
+                    .ok_or_else(|| CliError::invalid_input(t!("tasks-webhooks-missing-id")))?;
+                tasks.remove_webhook(id).map_err(task_error)?;
+                Ok(t!("tasks-webhooks-removed", "id" => id.to_string()))
+            }
+            "deliveries" => {
+                let webhook_id = match args.target.as_deref() {
+                    Some(id) => Some(
+                        id.trim_start_matches('#')
+                            .parse::<i64>()
+                            .map_err(|_| CliError::invalid_input(t!("tasks-webhooks-missing-id")))?,
+                    ),
+                    None => None,
+                };
+                let deliveries = tasks
+                    .webhook_deliveries(webhook_id, args.limit.max(1) as usize)
+                    .map_err(task_error)?;
+                if args.format == "json" {
+                    return serde_json::to_string_pretty(&deliveries).map_err(|e| CliError::general(e.to_string()));
+                }
+                if deliveries.is_empty() {
+                    return Ok(t!("tasks-webhooks-no-deliveries"));
+                }
+                let mut output = format!("{}\n", t!("tasks-webhooks-deliveries-title"));
+                for delivery in &deliveries {
+                    output.push_str(&format!("  {}\n", webhooks::delivery_line(delivery)));
+                }
+                Ok(output.trim_end().to_string())
+            }
+            "deliver" => {
+                let report = tasks
+                    .deliver_webhooks(webhooks::HttpTransport::shared(), None)
+                    .await
+                    .map_err(task_error)?;
+                Ok(t!(
+                    "tasks-webhooks-deliver-report",
+                    "delivered" => report.delivered.to_string(),
+                    "retrying" => report.retrying.to_string(),
+                    "failed" => report.failed.to_string()
+                ))
+            }
+            other => Err(CliError::invalid_input(t!("tasks-webhooks-unknown-command", "cmd" => other))),
+        }
+    }
 }
 
 #[no_mangle]
@@ -516,3 +1100,13 @@ pub fn plugin_create() -> Box<dyn Plugin> {
 pub fn plugin_create_cli() -> Box<dyn CliCommands> {
     Box::new(TasksPlugin::new())
 }
+
+#[no_mangle]
+pub fn plugin_create_search_provider() -> Box<dyn SearchProvider> {
+    Box::new(TasksPlugin::new())
+}
+
+#[no_mangle]
+pub fn plugin_create_adi_service() -> Box<dyn AdiServiceProvider> {
+    Box::new(TasksPlugin::new())
+}
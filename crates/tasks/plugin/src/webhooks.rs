@@ -0,0 +1,100 @@
+//! Webhook delivery for `adi tasks`.
+//!
+//! tasks-core queues a delivery whenever a task changes; this module sends
+//! the queued ones over HTTP. There is no background process, so due
+//! deliveries (including retries whose backoff has passed) go out after each
+//! command that changes tasks, a few at a time, and all at once on
+//! `adi tasks webhooks deliver`.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use lib_plugin_prelude::*;
+use tasks_core::webhooks::{DeliveryStatus, WebhookDelivery};
+use tasks_core::{unix_timestamp_now, DeliveryReport, TaskManager, Webhook, WebhookTransport};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deliveries sent after a command, so a slow endpoint and a long backlog
+/// cannot hold the command for more than a few request timeouts.
+const AFTER_COMMAND_LIMIT: usize = 3;
+
+pub struct HttpTransport {
+    client: reqwest::Client,
+}
+
+impl HttpTransport {
+    /// Shared client, so connections are reused across deliveries.
+    pub fn shared() -> &'static Self {
+        static TRANSPORT: OnceLock<HttpTransport> = OnceLock::new();
+        TRANSPORT.get_or_init(|| Self {
+            client: reqwest::Client::builder()
+                .user_agent(concat!("adi-tasks/", env!("CARGO_PKG_VERSION")))
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[async_trait]
+impl WebhookTransport for HttpTransport {
+    async fn post(
+        &self,
+        url: &str,
+        headers: &[(&'static str, String)],
+        body: &str,
+    ) -> std::result::Result<u16, String> {
+        let mut request = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .body(body.to_string());
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        Ok(response.status().as_u16())
+    }
+}
+
+/// Send the oldest due deliveries; the rest wait for the next command.
+/// Failures stay in the delivery log, so they never fail the command that
+/// triggered them.
+pub async fn send_due(tasks: &TaskManager) -> Option<DeliveryReport> {
+    tasks
+        .deliver_webhooks(HttpTransport::shared(), Some(AFTER_COMMAND_LIMIT))
+        .await
+        .ok()
+}
+
+pub fn webhook_line(webhook: &Webhook) -> String {
+    let events: Vec<&str> = webhook.events.iter().map(|e| e.as_str()).collect();
+    format!("#{} {} ({})", webhook.id, webhook.url, events.join(", "))
+}
+
+pub fn delivery_line(delivery: &WebhookDelivery) -> String {
+    let outcome = match delivery.status {
+        DeliveryStatus::Delivered => t!("tasks-webhooks-delivered"),
+        DeliveryStatus::Failed => t!(
+            "tasks-webhooks-failed",
+            "error" => delivery.error.as_deref().unwrap_or_default()
+        ),
+        DeliveryStatus::Pending if delivery.attempts == 0 => t!("tasks-webhooks-pending"),
+        DeliveryStatus::Pending => t!(
+            "tasks-webhooks-retrying",
+            "seconds" => (delivery.next_attempt_at - unix_timestamp_now()).max(0).to_string(),
+            "error" => delivery.error.as_deref().unwrap_or_default()
+        ),
+    };
+    format!(
+        "#{} {} #{} -> webhook #{}: {} ({})",
+        delivery.id,
+        delivery.event,
+        delivery.task_id.get(),
+        delivery.webhook_id,
+        outcome,
+        t!("tasks-webhooks-attempts", "count" => delivery.attempts.to_string())
+    )
+}
@@ -12,6 +12,8 @@ export interface Task {
   status: TaskStatus;
   symbolId?: number;
   projectPath?: string;
+  priority: number;
+  tags: string[];
   createdAt: number;
   updatedAt: number;
 }
@@ -20,6 +22,19 @@ export interface TaskWithDependencies {
   task: Task;
   dependsOn: Task[];
   dependents: Task[];
+  attachments: TaskAttachment[];
+  trackedSecs: number;
+}
+
+export interface TaskAttachment {
+  id: number;
+  taskId: number;
+  target: string;
+  contentHash?: string;
+  mimeType?: string;
+  sizeBytes?: number;
+  addedBy?: string;
+  addedAt: number;
 }
 
 export interface TasksStatus {
@@ -38,6 +53,8 @@ export interface CreateTaskInput {
   description?: string;
   dependsOn?: number[];
   symbolId?: number;
+  priority?: number;
+  tags?: string[];
 }
 
 export interface UpdateTaskInput {
@@ -67,6 +84,7 @@ export interface SearchQuery {
 export interface GraphNode {
   task: Task;
   dependencies: number[];
+  attachments: TaskAttachment[];
 }
 
 export interface IdResponse {
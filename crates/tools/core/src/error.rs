@@ -17,6 +17,12 @@ pub enum Error {
     #[error("Discovery error: {0}")]
     Discovery(String),
 
+    #[error("Sandbox error: {0}")]
+    Sandbox(String),
+
+    #[error("Output parse error: {0}")]
+    OutputParse(String),
+
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 }
@@ -29,6 +29,8 @@ mod storage;
 mod discovery;
 mod search;
 mod help_parser;
+mod output_parser;
+mod sandbox;
 pub mod service;
 
 pub use error::{Error, Result};
@@ -37,6 +39,8 @@ pub use storage::Storage;
 pub use discovery::*;
 pub use search::ToolSearch;
 pub use help_parser::parse_help_text;
+pub use output_parser::{parse_output, OutputFormat, ParsedOutput, LOW_CONFIDENCE};
+pub use sandbox::{Mount, SandboxConfig, SandboxMode, DEFAULT_SANDBOX_IMAGE, SANDBOX_WORKDIR};
 pub use service::{
     FileSystemToolProvider, McpServerProvider, ShellToolProvider, ToolCategory, ToolContentType,
     ToolDef, ToolProvider, ToolResult, ToolsService,
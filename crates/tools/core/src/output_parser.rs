@@ -0,0 +1,394 @@
+//! Convert plain-text tool output into JSON for agents.
+//!
+//! Every parser reports a confidence between 0 and 1. Below
+//! [`LOW_CONFIDENCE`] the raw text is carried along in [`ParsedOutput::raw`]
+//! so an agent can fall back to reading it directly.
+
+use crate::{Error, Result};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+use std::str::FromStr;
+
+/// Results under this confidence include the raw text.
+pub const LOW_CONFIDENCE: f64 = 0.5;
+
+/// How to read a tool's stdout
+#[derive(Debug, Clone)]
+pub enum OutputFormat {
+    /// Column-aligned (`docker ps`) or pipe-separated tables
+    Table,
+    /// A JSON document, or one JSON value per line
+    Json,
+    /// `key: value` or `key=value` lines
+    Kv,
+    /// One object per matching line, keyed by capture group name or index
+    Regex(Regex),
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            "kv" => Ok(Self::Kv),
+            _ => match s.strip_prefix("regex:") {
+                Some(pattern) => Regex::new(pattern)
+                    .map(Self::Regex)
+                    .map_err(|e| Error::OutputParse(format!("Invalid pattern: {}", e))),
+                None => Err(Error::OutputParse(format!(
+                    "Unknown format '{}', expected table, json, kv or regex:<pattern>",
+                    s
+                ))),
+            },
+        }
+    }
+}
+
+impl OutputFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Table => "table",
+            Self::Json => "json",
+            Self::Kv => "kv",
+            Self::Regex(_) => "regex",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParsedOutput {
+    pub format: &'static str,
+    pub confidence: f64,
+    pub data: JsonValue,
+    /// The unparsed text, present when confidence is low
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+}
+
+/// Parse tool output in the given format.
+pub fn parse_output(text: &str, format: &OutputFormat) -> ParsedOutput {
+    let (data, confidence) = match format {
+        OutputFormat::Table => parse_table(text),
+        OutputFormat::Json => parse_json(text),
+        OutputFormat::Kv => parse_kv(text),
+        OutputFormat::Regex(re) => parse_regex(text, re),
+    };
+    let confidence = (confidence * 100.0).round() / 100.0;
+
+    ParsedOutput {
+        format: format.name(),
+        confidence,
+        data,
+        raw: (confidence < LOW_CONFIDENCE).then(|| text.to_string()),
+    }
+}
+
+fn content_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim_end)
+        .filter(|l| !l.trim().is_empty())
+        .collect()
+}
+
+fn parse_json(text: &str) -> (JsonValue, f64) {
+    if let Ok(value) = serde_json::from_str::<JsonValue>(text) {
+        return (value, 1.0);
+    }
+
+    // JSON lines, tolerating log noise between records
+    let lines = content_lines(text);
+    let values: Vec<JsonValue> = lines
+        .iter()
+        .filter_map(|l| serde_json::from_str(l.trim()).ok())
+        .collect();
+    if values.is_empty() {
+        return (JsonValue::Null, 0.0);
+    }
+    let confidence = 0.9 * values.len() as f64 / lines.len() as f64;
+    (JsonValue::Array(values), confidence)
+}
+
+fn parse_kv(text: &str) -> (JsonValue, f64) {
+    let lines = content_lines(text);
+    let mut map = Map::new();
+    let mut matched = 0;
+
+    for line in &lines {
+        let Some((key, value)) = split_kv(line) else {
+            continue;
+        };
+        matched += 1;
+        map.insert(key, JsonValue::String(value.to_string()));
+    }
+
+    if lines.is_empty() {
+        return (JsonValue::Object(map), 0.0);
+    }
+    (JsonValue::Object(map), matched as f64 / lines.len() as f64)
+}
+
+/// Split on whichever of `:` or `=` comes first, so URLs in values survive.
+fn split_kv(line: &str) -> Option<(String, &str)> {
+    let at = line.find([':', '='])?;
+    let key = line[..at].trim();
+    if key.is_empty() || key.len() > 64 {
+        return None;
+    }
+    Some((normalize_key(key), line[at + 1..].trim()))
+}
+
+fn normalize_key(key: &str) -> String {
+    let mut out = String::with_capacity(key.len());
+    for c in key.trim().chars() {
+        if c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        } else if !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    out.trim_matches('_').to_string()
+}
+
+fn parse_regex(text: &str, re: &Regex) -> (JsonValue, f64) {
+    let lines = content_lines(text);
+    let names: Vec<Option<&str>> = re.capture_names().collect();
+    let mut rows = Vec::new();
+
+    for line in &lines {
+        let Some(caps) = re.captures(line) else {
+            continue;
+        };
+        let mut row = Map::new();
+        for (i, name) in names.iter().enumerate().skip(1) {
+            let key = name.map(str::to_string).unwrap_or_else(|| i.to_string());
+            let value = caps
+                .get(i)
+                .map(|m| JsonValue::String(m.as_str().to_string()))
+                .unwrap_or(JsonValue::Null);
+            row.insert(key, value);
+        }
+        if names.len() == 1 {
+            row.insert("match".to_string(), JsonValue::String(caps[0].to_string()));
+        }
+        rows.push(JsonValue::Object(row));
+    }
+
+    if lines.is_empty() {
+        return (JsonValue::Array(rows), 0.0);
+    }
+    let confidence = rows.len() as f64 / lines.len() as f64;
+    (JsonValue::Array(rows), confidence)
+}
+
+fn is_separator(line: &str) -> bool {
+    let t = line.trim();
+    !t.is_empty()
+        && t.chars()
+            .all(|c| matches!(c, '-' | '=' | '+' | '|' | ':' | ' '))
+}
+
+fn parse_table(text: &str) -> (JsonValue, f64) {
+    let lines: Vec<&str> = content_lines(text)
+        .into_iter()
+        .filter(|l| !is_separator(l))
+        .collect();
+    let Some((header, rows)) = lines.split_first() else {
+        return (JsonValue::Array(Vec::new()), 0.0);
+    };
+
+    let piped = lines.iter().all(|l| l.contains('|'));
+    let (columns, cells): (Vec<String>, Vec<Vec<String>>) = if piped {
+        let split = |line: &str| -> Vec<String> {
+            let line = line.trim().trim_start_matches('|').trim_end_matches('|');
+            line.split('|').map(|c| c.trim().to_string()).collect()
+        };
+        (split(header), rows.iter().map(|r| split(r)).collect())
+    } else {
+        let spans = column_spans(&lines);
+        let cut = |line: &str| -> Vec<String> {
+            let chars: Vec<char> = line.chars().collect();
+            spans
+                .iter()
+                .enumerate()
+                .map(|(i, &(start, end))| {
+                    // The last column takes whatever overflows it
+                    let end = if i + 1 == spans.len() {
+                        chars.len()
+                    } else {
+                        end.min(chars.len())
+                    };
+                    chars
+                        .get(start.min(chars.len())..end.max(start.min(chars.len())))
+                        .map(|s| s.iter().collect::<String>().trim().to_string())
+                        .unwrap_or_default()
+                })
+                .collect()
+        };
+        (cut(header), rows.iter().map(|r| cut(r)).collect())
+    };
+
+    let keys: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| match normalize_key(c) {
+            k if k.is_empty() || columns[..i].iter().any(|p| normalize_key(p) == k) => {
+                format!("column_{}", i + 1)
+            }
+            k => k,
+        })
+        .collect();
+
+    let mut consistent = 0;
+    let records: Vec<JsonValue> = cells
+        .into_iter()
+        .map(|row| {
+            let filled = row.iter().filter(|c| !c.is_empty()).count();
+            if row.len() == keys.len() && filled * 2 >= keys.len() {
+                consistent += 1;
+            }
+            let map: Map<String, JsonValue> = keys
+                .iter()
+                .cloned()
+                .zip(
+                    row.into_iter()
+                        .map(JsonValue::String)
+                        .chain(std::iter::repeat(JsonValue::Null)),
+                )
+                .collect();
+            JsonValue::Object(map)
+        })
+        .collect();
+
+    let confidence = match (keys.len(), records.len()) {
+        (_, 0) => 0.3,
+        (1, _) => 0.2,
+        (_, n) => 0.5 + 0.5 * consistent as f64 / n as f64,
+    };
+    (JsonValue::Array(records), confidence)
+}
+
+/// Character ranges of fixed-width columns: runs of positions where at
+/// least one line has a non-space character, separated by positions that
+/// are blank in every line. When the header pads columns with two or more
+/// spaces, a single space inside it joins a multi-word name ("CONTAINER ID").
+fn column_spans(lines: &[&str]) -> Vec<(usize, usize)> {
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let mut used = vec![false; width];
+    for line in lines {
+        for (i, c) in line.chars().enumerate() {
+            if !c.is_whitespace() {
+                used[i] = true;
+            }
+        }
+    }
+
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, &u) in used.iter().enumerate() {
+        match (u, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                spans.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, width));
+    }
+
+    let header: Vec<char> = lines
+        .first()
+        .map(|l| l.chars().collect())
+        .unwrap_or_default();
+    if !lines.first().is_some_and(|l| l.contains("  ")) {
+        return spans;
+    }
+    let joined_in_header = |gap: usize| {
+        gap > 0
+            && header.get(gap - 1).is_some_and(|c| !c.is_whitespace())
+            && header.get(gap + 1).is_some_and(|c| !c.is_whitespace())
+    };
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.0 == last.1 + 1 && joined_in_header(last.1) => last.1 = span.1,
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_fixed_width_table() {
+        let text = "\
+CONTAINER ID   IMAGE     STATUS         PORTS
+abc123         nginx     Up 2 hours     0.0.0.0:80->80/tcp
+def456         redis     Up 5 minutes
+";
+        let parsed = parse_output(text, &"table".parse().unwrap());
+        assert_eq!(
+            parsed.data,
+            json!([
+                { "container_id": "abc123", "image": "nginx", "status": "Up 2 hours", "ports": "0.0.0.0:80->80/tcp" },
+                { "container_id": "def456", "image": "redis", "status": "Up 5 minutes", "ports": "" },
+            ])
+        );
+        assert!(parsed.confidence >= 0.9);
+        assert!(parsed.raw.is_none());
+    }
+
+    #[test]
+    fn test_parse_pipe_table() {
+        let text = "| name | size |\n|------|------|\n| a.txt | 12 |\n| b.txt | 40 |\n";
+        let parsed = parse_output(text, &OutputFormat::Table);
+        assert_eq!(
+            parsed.data,
+            json!([{ "name": "a.txt", "size": "12" }, { "name": "b.txt", "size": "40" }])
+        );
+        assert_eq!(parsed.confidence, 1.0);
+    }
+
+    #[test]
+    fn test_parse_json_kv_and_regex() {
+        let parsed = parse_output("{\"ok\": true}", &OutputFormat::Json);
+        assert_eq!(
+            (parsed.data, parsed.confidence),
+            (json!({ "ok": true }), 1.0)
+        );
+
+        let parsed = parse_output("{\"a\":1}\nwarning: slow\n{\"a\":2}\n", &OutputFormat::Json);
+        assert_eq!(parsed.data, json!([{ "a": 1 }, { "a": 2 }]));
+        assert_eq!(parsed.confidence, 0.6);
+
+        let parsed = parse_output(
+            "Server Version: 24.0\nRoot Dir=/var/lib/docker\n",
+            &OutputFormat::Kv,
+        );
+        assert_eq!(
+            parsed.data,
+            json!({ "server_version": "24.0", "root_dir": "/var/lib/docker" })
+        );
+
+        let format: OutputFormat = r"regex:^(?P<file>\S+):(?P<line>\d+)".parse().unwrap();
+        let parsed = parse_output("src/a.rs:10: unused\nnoise\n", &format);
+        assert_eq!(parsed.data, json!([{ "file": "src/a.rs", "line": "10" }]));
+        assert_eq!(parsed.confidence, 0.5);
+
+        let parsed = parse_output("just some prose", &OutputFormat::Json);
+        assert_eq!(parsed.confidence, 0.0);
+        assert_eq!(parsed.raw.as_deref(), Some("just some prose"));
+
+        assert!("regex:(".parse::<OutputFormat>().is_err());
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+}
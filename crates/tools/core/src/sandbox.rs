@@ -0,0 +1,302 @@
+//! Containerized execution for untrusted tools.
+//!
+//! A sandboxed run mounts the tool executable and the working directory
+//! read-only into a throwaway Docker container with no network, no
+//! capabilities and a read-only root filesystem. Anything else the tool may
+//! touch has to be granted explicitly with a [`Mount`].
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+/// Image used when a sandbox config does not name one.
+pub const DEFAULT_SANDBOX_IMAGE: &str = "debian:stable-slim";
+
+/// Where the working directory is mounted inside the container
+pub const SANDBOX_WORKDIR: &str = "/work";
+
+/// Where the tool executable is mounted inside the container
+const SANDBOX_TOOL_PATH: &str = "/opt/adi-tool";
+
+/// How a tool is executed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxMode {
+    /// Run directly on the host
+    #[default]
+    None,
+    /// Run inside a minimal Docker container
+    Docker,
+}
+
+impl fmt::Display for SandboxMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SandboxMode::None => write!(f, "none"),
+            SandboxMode::Docker => write!(f, "docker"),
+        }
+    }
+}
+
+impl FromStr for SandboxMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(SandboxMode::None),
+            "docker" => Ok(SandboxMode::Docker),
+            other => Err(Error::Sandbox(format!(
+                "Unknown sandbox mode: {} (expected: none, docker)",
+                other
+            ))),
+        }
+    }
+}
+
+/// An explicit host path grant, parsed from `host[:target][:ro|rw]`.
+///
+/// Relative host paths are resolved against the working directory; without a
+/// target they keep the same relative location under [`SANDBOX_WORKDIR`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mount {
+    pub host: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<PathBuf>,
+    #[serde(default)]
+    pub writable: bool,
+}
+
+impl Mount {
+    /// Parse a comma-separated list of mount specs.
+    pub fn parse_list(specs: &str) -> Result<Vec<Self>> {
+        specs
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    fn volume_arg(&self, cwd: &Path) -> Result<String> {
+        let host = if self.host.is_absolute() {
+            self.host.clone()
+        } else {
+            cwd.join(&self.host)
+        };
+        let host = host
+            .canonicalize()
+            .map_err(|e| Error::Sandbox(format!("Cannot mount {}: {}", host.display(), e)))?;
+
+        let target = match &self.target {
+            Some(target) => target.clone(),
+            None if self.host.is_absolute() => host.clone(),
+            None => Path::new(SANDBOX_WORKDIR).join(&self.host),
+        };
+        if !target.is_absolute() {
+            return Err(Error::Sandbox(format!(
+                "Mount target must be absolute: {}",
+                target.display()
+            )));
+        }
+
+        let mode = if self.writable { "rw" } else { "ro" };
+        Ok(format!("{}:{}:{}", host.display(), target.display(), mode))
+    }
+}
+
+impl FromStr for Mount {
+    type Err = Error;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let mut parts: Vec<&str> = spec.split(':').collect();
+
+        let writable = match parts.last() {
+            Some(&"rw") if parts.len() > 1 => {
+                parts.pop();
+                true
+            }
+            Some(&"ro") if parts.len() > 1 => {
+                parts.pop();
+                false
+            }
+            _ => false,
+        };
+
+        match parts.as_slice() {
+            [host] if !host.is_empty() => Ok(Mount {
+                host: PathBuf::from(host),
+                target: None,
+                writable,
+            }),
+            [host, target] if !host.is_empty() && !target.is_empty() => Ok(Mount {
+                host: PathBuf::from(host),
+                target: Some(PathBuf::from(target)),
+                writable,
+            }),
+            _ => Err(Error::Sandbox(format!(
+                "Invalid mount: {} (expected host[:target][:ro|rw])",
+                spec
+            ))),
+        }
+    }
+}
+
+/// Per-tool sandbox settings, stored in the index.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    pub mode: SandboxMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<Mount>,
+    /// Allow network access (off by default)
+    #[serde(default)]
+    pub network: bool,
+}
+
+impl SandboxConfig {
+    pub fn docker() -> Self {
+        Self {
+            mode: SandboxMode::Docker,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_sandboxed(&self) -> bool {
+        self.mode != SandboxMode::None
+    }
+
+    pub fn image(&self) -> &str {
+        self.image.as_deref().unwrap_or(DEFAULT_SANDBOX_IMAGE)
+    }
+
+    /// Arguments for `docker` that run `tool_path` with `args` in `cwd`.
+    pub fn docker_args(
+        &self,
+        tool_path: &Path,
+        cwd: &Path,
+        args: &[String],
+    ) -> Result<Vec<String>> {
+        let tool_path = tool_path.canonicalize().map_err(|e| {
+            Error::Sandbox(format!("Cannot mount tool {}: {}", tool_path.display(), e))
+        })?;
+        let cwd = cwd.canonicalize()?;
+
+        let mut docker_args: Vec<String> = vec![
+            "run".into(),
+            "--rm".into(),
+            "--read-only".into(),
+            "--cap-drop".into(),
+            "ALL".into(),
+            "--security-opt".into(),
+            "no-new-privileges".into(),
+            "--tmpfs".into(),
+            "/tmp".into(),
+        ];
+        if !self.network {
+            docker_args.extend(["--network".into(), "none".into()]);
+        }
+
+        docker_args.extend([
+            "-v".into(),
+            format!("{}:{}:ro", cwd.display(), SANDBOX_WORKDIR),
+            "-w".into(),
+            SANDBOX_WORKDIR.into(),
+            "-v".into(),
+            format!("{}:{}:ro", tool_path.display(), SANDBOX_TOOL_PATH),
+        ]);
+        for mount in &self.mounts {
+            docker_args.extend(["-v".into(), mount.volume_arg(&cwd)?]);
+        }
+
+        docker_args.extend([
+            "--entrypoint".into(),
+            SANDBOX_TOOL_PATH.into(),
+            self.image().to_string(),
+        ]);
+        docker_args.extend(args.iter().cloned());
+
+        Ok(docker_args)
+    }
+
+    /// Build the `docker run` command for a tool executable.
+    pub fn docker_command(&self, tool_path: &Path, cwd: &Path, args: &[String]) -> Result<Command> {
+        let mut cmd = Command::new("docker");
+        cmd.args(self.docker_args(tool_path, cwd, args)?);
+        Ok(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mounts() {
+        let mounts = Mount::parse_list("out:rw, /data:/mnt/data ,cache:/cache:ro").unwrap();
+        assert_eq!(
+            mounts,
+            vec![
+                Mount {
+                    host: "out".into(),
+                    target: None,
+                    writable: true,
+                },
+                Mount {
+                    host: "/data".into(),
+                    target: Some("/mnt/data".into()),
+                    writable: false,
+                },
+                Mount {
+                    host: "cache".into(),
+                    target: Some("/cache".into()),
+                    writable: false,
+                },
+            ]
+        );
+
+        assert!("".parse::<Mount>().is_err());
+        assert!("a:b:c:rw".parse::<Mount>().is_err());
+        assert!("docker".parse::<SandboxMode>().is_ok());
+        assert!("podman".parse::<SandboxMode>().is_err());
+    }
+
+    #[test]
+    fn test_docker_args_default_to_read_only_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = dir.path().join("tool");
+        std::fs::write(&tool, "#!/bin/sh\n").unwrap();
+        std::fs::create_dir(dir.path().join("out")).unwrap();
+        let cwd = dir.path().canonicalize().unwrap();
+
+        let config = SandboxConfig::docker();
+        let args = config
+            .docker_args(&tool, &cwd, &["--json".to_string()])
+            .unwrap();
+
+        let joined = args.join(" ");
+        assert!(joined.contains("--network none"));
+        assert!(joined.contains(&format!("{}:/work:ro", cwd.display())));
+        assert!(!joined.contains(":rw"));
+        assert_eq!(args[args.len() - 2], DEFAULT_SANDBOX_IMAGE);
+        assert_eq!(args.last().unwrap(), "--json");
+
+        let config = SandboxConfig {
+            mounts: Mount::parse_list("out:rw").unwrap(),
+            network: true,
+            ..SandboxConfig::docker()
+        };
+        let joined = config.docker_args(&tool, &cwd, &[]).unwrap().join(" ");
+        assert!(!joined.contains("--network none"));
+        assert!(joined.contains(&format!("{}/out:/work/out:rw", cwd.display())));
+
+        let missing = SandboxConfig {
+            mounts: Mount::parse_list("missing").unwrap(),
+            ..SandboxConfig::docker()
+        };
+        assert!(missing.docker_args(&tool, &cwd, &[]).is_err());
+    }
+}
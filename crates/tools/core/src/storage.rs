@@ -1,4 +1,4 @@
-use crate::{Error, MatchType, Result, SearchResult, Tool, ToolSource, ToolUsage};
+use crate::{Error, MatchType, Result, SandboxConfig, SearchResult, Tool, ToolSource, ToolUsage};
 use rusqlite::{params, Connection};
 use std::path::Path;
 use std::sync::Mutex;
@@ -52,6 +52,12 @@ impl Storage {
                 flags TEXT
             );
 
+            -- Kept across re-indexing so sandbox choices stick to the tool
+            CREATE TABLE IF NOT EXISTS tool_sandbox (
+                tool_id TEXT PRIMARY KEY,
+                config TEXT NOT NULL
+            );
+
             CREATE TRIGGER IF NOT EXISTS tools_ai AFTER INSERT ON tools BEGIN
                 INSERT INTO tools_fts(rowid, name, description)
                 VALUES (new.rowid, new.name, new.description);
@@ -223,9 +229,34 @@ impl Storage {
         }
     }
 
+    pub fn set_sandbox(&self, tool_id: &str, config: &SandboxConfig) -> Result<()> {
+        let config = serde_json::to_string(config)?;
+
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO tool_sandbox (tool_id, config) VALUES (?1, ?2)",
+            params![tool_id, config],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_sandbox(&self, tool_id: &str) -> Result<Option<SandboxConfig>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT config FROM tool_sandbox WHERE tool_id = ?1")?;
+
+        let mut rows = stmt.query(params![tool_id])?;
+        if let Some(row) = rows.next()? {
+            let config: String = row.get(0)?;
+            Ok(Some(serde_json::from_str(&config)?))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn delete_tool(&self, id: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute("DELETE FROM tool_usage WHERE tool_id = ?1", params![id])?;
+        conn.execute("DELETE FROM tool_sandbox WHERE tool_id = ?1", params![id])?;
         conn.execute("DELETE FROM tools WHERE id = ?1", params![id])?;
         Ok(())
     }
@@ -324,4 +355,22 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].tool.id, "docker-ps");
     }
+
+    #[test]
+    fn test_sandbox_survives_reindex() {
+        let storage = Storage::open_in_memory().unwrap();
+        assert!(storage.get_sandbox("untrusted").unwrap().is_none());
+
+        let config = SandboxConfig {
+            image: Some("alpine:3".to_string()),
+            ..SandboxConfig::docker()
+        };
+        storage.set_sandbox("untrusted", &config).unwrap();
+
+        storage.clear().unwrap();
+        assert_eq!(storage.get_sandbox("untrusted").unwrap(), Some(config));
+
+        storage.delete_tool("untrusted").unwrap();
+        assert!(storage.get_sandbox("untrusted").unwrap().is_none());
+    }
 }
@@ -5,7 +5,10 @@
 //! and pull full usage docs only when needed.
 
 use lib_plugin_prelude::*;
-use tools_core::{discover_all, discover_tool_from_path, fetch_help, Config, ToolSearch};
+use tools_core::{
+    discover_all, discover_tool_from_path, fetch_help, parse_output, Config, Mount, OutputFormat,
+    SandboxConfig, SandboxMode, ToolSearch,
+};
 use std::sync::{Arc, Mutex};
 
 pub struct ToolsPlugin {
@@ -45,7 +48,40 @@ impl Plugin for ToolsPlugin {
     }
 
     fn provides(&self) -> Vec<&'static str> {
-        vec![SERVICE_CLI_COMMANDS]
+        vec![SERVICE_CLI_COMMANDS, SERVICE_SEARCH_PROVIDER]
+    }
+}
+
+#[async_trait]
+impl SearchProvider for ToolsPlugin {
+    fn search_types(&self) -> Vec<String> {
+        vec!["tools".to_string()]
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let mut guard = self.search.lock().unwrap();
+        // Search-only instances are not init()-ed, so open the index lazily
+        if guard.is_none() {
+            *guard = ToolSearch::open(&self.config).ok();
+        }
+        let Some(ref search) = *guard else {
+            return Ok(Vec::new());
+        };
+
+        let results = search
+            .find(&query.text, query.limit)
+            .map_err(|e| PluginError::Runtime(e.to_string()))?;
+        let total = results.len();
+
+        Ok(results
+            .into_iter()
+            .enumerate()
+            .map(|(rank, result)| {
+                SearchHit::new("tools", result.tool.id.clone(), result.tool.name, rank_score(rank, total))
+                    .with_snippet(result.tool.description)
+                    .with_open_command(format!("adi tools help {}", result.tool.id))
+            })
+            .collect())
     }
 }
 
@@ -80,7 +116,14 @@ impl CliCommands for ToolsPlugin {
             CliCommand {
                 name: "run".to_string(),
                 description: "Run a tool".to_string(),
-                args: vec![CliArg::positional(0, "tool-id", CliArgType::String, true)],
+                args: vec![
+                    CliArg::positional(0, "tool-id", CliArgType::String, true),
+                    CliArg::optional("--sandbox", CliArgType::String),
+                    CliArg::optional("--mount", CliArgType::String),
+                    CliArg::optional("--image", CliArgType::String),
+                    CliArg::optional("--network", CliArgType::Bool),
+                    CliArg::optional("--parse", CliArgType::String),
+                ],
                 has_subcommands: false,
             },
             CliCommand {
@@ -186,6 +229,11 @@ pub fn plugin_create_cli() -> Box<dyn CliCommands> {
     Box::new(ToolsPlugin::new())
 }
 
+#[no_mangle]
+pub fn plugin_create_search_provider() -> Box<dyn SearchProvider> {
+    Box::new(ToolsPlugin::new())
+}
+
 fn get_help() -> String {
     r#"ADI Tools - Searchable CLI Tool Index
 
@@ -206,7 +254,20 @@ Examples:
   adi tools help docker-ps
   adi tools list --source plugin
   adi tools run git-status
-  adi tools index"#
+  adi tools run untrusted-tool --sandbox docker --mount out:rw
+  adi tools run docker-ps --parse table
+  adi tools index
+
+Sandbox (run):
+  --sandbox <docker|none>  Run inside a container; the choice is saved per tool
+  --mount <specs>          Extra grants: host[:target][:ro|rw], comma-separated
+  --image <image>          Container image (default: debian:stable-slim)
+  --network                Allow network access inside the sandbox
+
+Output parsing (run):
+  --parse <format>         Convert stdout to JSON: table, json, kv or regex:<pattern>
+                           Prints {format, confidence, data}; low-confidence results
+                           also carry the raw text"#
         .to_string()
 }
 
@@ -308,12 +369,22 @@ fn cmd_run(search: &ToolSearch, ctx: &CliContext) -> CmdResult {
         .get(tool_id)
         .map_err(|e| e.to_string())?
         .ok_or_else(|| format!("Tool not found: {}", tool_id))?;
+    let parse = parse_format(ctx)?;
 
     // Get remaining args
     let args: Vec<String> = (1..).map_while(|i| ctx.arg(i).map(|s| s.to_string())).collect();
 
+    let sandbox = resolve_sandbox(search, tool_id, ctx)?;
+
     match &tool.source {
         tools_core::ToolSource::Plugin { command, .. } => {
+            if sandbox.is_sandboxed() {
+                return Err(format!(
+                    "Plugin tools cannot run sandboxed. Use: adi tools run {} --sandbox none",
+                    tool_id
+                ));
+            }
+
             // Run: adi <command> [args...]
             let mut cmd_args = vec![command.clone()];
             cmd_args.extend(args);
@@ -324,7 +395,7 @@ fn cmd_run(search: &ToolSearch, ctx: &CliContext) -> CmdResult {
                 .map_err(|e| format!("Failed to run adi {}: {}", command, e))?;
 
             if output.status.success() {
-                Ok(String::from_utf8_lossy(&output.stdout).to_string())
+                render_output(&String::from_utf8_lossy(&output.stdout), parse.as_ref())
             } else {
                 let stderr = String::from_utf8_lossy(&output.stderr);
                 let stdout = String::from_utf8_lossy(&output.stdout);
@@ -333,13 +404,26 @@ fn cmd_run(search: &ToolSearch, ctx: &CliContext) -> CmdResult {
         }
         tools_core::ToolSource::ToolDir { path, .. }
         | tools_core::ToolSource::System { path } => {
-            let output = std::process::Command::new(path)
-                .args(&args)
+            let mut command = match sandbox.mode {
+                SandboxMode::None => {
+                    let mut command = std::process::Command::new(path);
+                    command.args(&args);
+                    command
+                }
+                SandboxMode::Docker => {
+                    let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+                    sandbox
+                        .docker_command(path, &cwd, &args)
+                        .map_err(|e| e.to_string())?
+                }
+            };
+
+            let output = command
                 .output()
                 .map_err(|e| format!("Failed to run {}: {}", path.display(), e))?;
 
             if output.status.success() {
-                Ok(String::from_utf8_lossy(&output.stdout).to_string())
+                render_output(&String::from_utf8_lossy(&output.stdout), parse.as_ref())
             } else {
                 let stderr = String::from_utf8_lossy(&output.stderr);
                 let stdout = String::from_utf8_lossy(&output.stdout);
@@ -349,11 +433,73 @@ fn cmd_run(search: &ToolSearch, ctx: &CliContext) -> CmdResult {
     }
 }
 
+/// Sandbox settings for this run. An explicit `--sandbox` is saved for the
+/// tool so later runs (e.g. by agents) default to it; `--mount`, `--image`
+/// and `--network` alone only adjust the saved settings for one run.
+fn resolve_sandbox(
+    search: &ToolSearch,
+    tool_id: &str,
+    ctx: &CliContext,
+) -> std::result::Result<SandboxConfig, String> {
+    let stored = search
+        .storage()
+        .get_sandbox(tool_id)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+
+    let mode = ctx
+        .option::<String>("sandbox")
+        .map(|m| m.parse::<SandboxMode>())
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let mounts = ctx
+        .option::<String>("mount")
+        .map(|m| Mount::parse_list(&m))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let mut config = stored;
+    if let Some(mode) = mode {
+        config.mode = mode;
+        config.mounts = mounts.unwrap_or_default();
+        config.network = ctx.has_flag("network");
+        if let Some(image) = ctx.option::<String>("image") {
+            config.image = Some(image);
+        }
+        search
+            .storage()
+            .set_sandbox(tool_id, &config)
+            .map_err(|e| e.to_string())?;
+        return Ok(config);
+    }
+
+    if let Some(mounts) = mounts {
+        config.mounts.extend(mounts);
+    }
+    if let Some(image) = ctx.option::<String>("image") {
+        config.image = Some(image);
+    }
+    if ctx.has_flag("network") {
+        config.network = true;
+    }
+
+    if !config.is_sandboxed() && (!config.mounts.is_empty() || config.image.is_some()) {
+        return Err("--mount and --image require --sandbox docker".to_string());
+    }
+
+    Ok(config)
+}
+
 fn cmd_run_direct(ctx: &CliContext) -> CmdResult {
     let tool_id = ctx
         .arg(0)
         .ok_or_else(|| "Missing tool ID. Usage: run <tool-id> [args...]".to_string())?;
 
+    if ctx.option::<String>("sandbox").is_some() {
+        return Err("Sandboxed runs need the tool index. Run: adi tools index".to_string());
+    }
+    let parse = parse_format(ctx)?;
+
     let args: Vec<String> = (1..).map_while(|i| ctx.arg(i).map(|s| s.to_string())).collect();
 
     let output = std::process::Command::new(tool_id)
@@ -362,12 +508,28 @@ fn cmd_run_direct(ctx: &CliContext) -> CmdResult {
         .map_err(|e| format!("Failed to run {}: {}", tool_id, e))?;
 
     if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        render_output(&String::from_utf8_lossy(&output.stdout), parse.as_ref())
     } else {
         Err(String::from_utf8_lossy(&output.stderr).to_string())
     }
 }
 
+/// The `--parse` format, checked before the tool runs.
+fn parse_format(ctx: &CliContext) -> std::result::Result<Option<OutputFormat>, String> {
+    ctx.option::<String>("parse")
+        .map(|f| f.parse::<OutputFormat>())
+        .transpose()
+        .map_err(|e| e.to_string())
+}
+
+/// Stdout as-is, or as parsed JSON with its confidence under `--parse`.
+fn render_output(stdout: &str, parse: Option<&OutputFormat>) -> CmdResult {
+    let Some(format) = parse else {
+        return Ok(stdout.to_string());
+    };
+    serde_json::to_string_pretty(&parse_output(stdout, format)).map_err(|e| e.to_string())
+}
+
 fn cmd_index(
     search_lock: &Arc<Mutex<Option<ToolSearch>>>,
     config: &Config,
@@ -256,6 +256,8 @@ fn do_generate(cli: &Cli) -> Result<Vec<String>> {
             tag: cli.protocol_tag.clone(),
             rename: cli.protocol_rename.clone(),
             enum_name: cli.protocol_enum_name.clone(),
+            field_types: Vec::new(),
+            type_attrs: Vec::new(),
         };
         generator = generator.with_rust_protocol_config(protocol_config);
     }
@@ -27,6 +27,14 @@ pub struct RustProtocolConfig {
     pub rename: String,
     /// Name of the generated enum (e.g., "SignalingMessage")
     pub enum_name: String,
+    /// Rust types for string fields by field name (e.g., `device_id` →
+    /// `crate::ids::DeviceId`), in messages and models alike. Applies to
+    /// `string`, `string[]` and their optional forms; the type must
+    /// serialize as a plain string.
+    pub field_types: Vec<(String, String)>,
+    /// Attributes put on every generated message enum, model and enum, e.g.
+    /// `#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]`
+    pub type_attrs: Vec<String>,
 }
 
 /// Message kind derived from operation decorators.
@@ -107,7 +115,13 @@ pub fn generate(
     generated.push(messages_path.display().to_string());
 
     // Generate types.rs (models + enums)
-    let types = generate_types(file, &scalars, &models)?;
+    let types = generate_types(
+        file,
+        &scalars,
+        &models,
+        &config.field_types,
+        &config.type_attrs,
+    )?;
     let types_path = src_dir.join("types.rs");
     fs::write(&types_path, &types)?;
     generated.push(types_path.display().to_string());
@@ -214,6 +228,7 @@ fn generate_messages(
 
     // Main enum
     writeln!(out, "#[derive(Debug, Clone, Serialize, Deserialize)]")?;
+    write_type_attrs(&mut out, &config.type_attrs)?;
     writeln!(
         out,
         "#[serde(tag = \"{}\", rename_all = \"{}\")]",
@@ -243,7 +258,13 @@ fn generate_messages(
                         "        #[serde(skip_serializing_if = \"Option::is_none\")]"
                     )?;
                 }
-                let rust_type = type_to_rust(&field.type_ref, field.optional, scalars);
+                let rust_type = field_type(
+                    &field.name,
+                    &field.type_ref,
+                    field.optional,
+                    scalars,
+                    &config.field_types,
+                );
                 writeln!(out, "        {}: {},", field.name, rust_type)?;
             }
             writeln!(out, "    }},")?;
@@ -255,11 +276,45 @@ fn generate_messages(
     Ok(out)
 }
 
+/// Rust type of a field, with `field_types` overriding the `String` of
+/// string-typed fields.
+fn field_type(
+    name: &str,
+    type_ref: &TypeRef,
+    optional: bool,
+    scalars: &ScalarMap,
+    field_types: &[(String, String)],
+) -> String {
+    let rust_type = type_to_rust(type_ref, optional, scalars);
+    match field_types.iter().find(|(field, _)| field == name) {
+        Some((_, ty)) if is_string_like(type_ref) => rust_type.replace("String", ty),
+        _ => rust_type,
+    }
+}
+
+/// `string`, `string[]` or an optional of either.
+fn is_string_like(type_ref: &TypeRef) -> bool {
+    match type_ref {
+        TypeRef::Builtin(name) => name == "string",
+        TypeRef::Array(inner) | TypeRef::Optional(inner) => is_string_like(inner),
+        _ => false,
+    }
+}
+
+fn write_type_attrs(out: &mut String, attrs: &[String]) -> std::fmt::Result {
+    for attr in attrs {
+        writeln!(out, "{}", attr)?;
+    }
+    Ok(())
+}
+
 /// Generate the types.rs file containing supporting models and enums.
 fn generate_types(
     file: &TypeSpecFile,
     scalars: &ScalarMap,
     models: &ModelMap<'_>,
+    field_types: &[(String, String)],
+    type_attrs: &[String],
 ) -> Result<String, CodegenError> {
     let mut out = String::new();
 
@@ -286,12 +341,14 @@ fn generate_types(
         all_props.extend(model.properties.iter());
 
         writeln!(out, "#[derive(Debug, Clone, Serialize, Deserialize)]")?;
+        write_type_attrs(&mut out, type_attrs)?;
         writeln!(out, "#[serde(rename_all = \"snake_case\")]")?;
         writeln!(out, "pub struct {} {{", model.name)?;
 
         for prop in &all_props {
-            let rust_type = type_to_rust(&prop.type_ref, prop.optional, scalars);
             let field_name = prop.name.to_case(Case::Snake);
+            let rust_type =
+                field_type(&field_name, &prop.type_ref, prop.optional, scalars, field_types);
 
             if prop.optional {
                 writeln!(
@@ -309,6 +366,7 @@ fn generate_types(
     // Generate enums
     for e in file.enums() {
         writeln!(out, "#[derive(Debug, Clone, Serialize, Deserialize)]")?;
+        write_type_attrs(&mut out, type_attrs)?;
         writeln!(out, "pub enum {} {{", e.name)?;
 
         for member in &e.members {
@@ -626,6 +684,8 @@ interface WebRtc {
             tag: "type".to_string(),
             rename: "snake_case".to_string(),
             enum_name: "SignalingMessage".to_string(),
+            field_types: Vec::new(),
+            type_attrs: Vec::new(),
         };
 
         let output = generate_messages(&variants, &config, &scalars).unwrap();
@@ -640,6 +700,52 @@ interface WebRtc {
         assert!(output.contains("#[serde(skip_serializing_if = \"Option::is_none\")]"));
     }
 
+    #[test]
+    fn test_field_types_override_strings() {
+        let source = r#"
+model Peer {
+    device_id: string;
+}
+
+@channel("device")
+interface Device {
+    @request
+    list(device_ids?: string[], device_id: string, count: int32): {
+        peers: Peer[];
+    };
+}
+"#;
+
+        let file = parse(source).expect("parse failed");
+        let scalars = build_scalar_map(&file);
+        let models = build_model_map(&file);
+        let (variants, _) = collect_protocol_data(&file, &models);
+        let field_types = vec![
+            ("device_id".to_string(), "DeviceId".to_string()),
+            ("device_ids".to_string(), "DeviceId".to_string()),
+            ("count".to_string(), "DeviceId".to_string()),
+        ];
+        let config = RustProtocolConfig {
+            tag: "type".to_string(),
+            rename: "snake_case".to_string(),
+            enum_name: "Msg".to_string(),
+            field_types: field_types.clone(),
+            type_attrs: vec!["#[derive(Eq)]".to_string()],
+        };
+
+        let messages = generate_messages(&variants, &config, &scalars).unwrap();
+        assert!(messages.contains("device_ids: Option<Vec<DeviceId>>,"));
+        assert!(messages.contains("device_id: DeviceId,"));
+        // Only string fields are retyped
+        assert!(messages.contains("count: i32,"));
+
+        let types = generate_types(&file, &scalars, &models, &field_types, &config.type_attrs)
+            .unwrap();
+        assert!(types.contains("pub device_id: DeviceId,"));
+        assert!(types.contains("#[derive(Eq)]\n#[serde(rename_all"));
+        assert!(messages.contains("#[derive(Eq)]\n#[serde(tag"));
+    }
+
     #[test]
     fn test_generates_handler_traits() {
         let source = r#"
@@ -695,7 +801,7 @@ interface WebRtc {
         let scalars = build_scalar_map(&file);
         let models = build_model_map(&file);
 
-        let output = generate_types(&file, &scalars, &models).unwrap();
+        let output = generate_types(&file, &scalars, &models, &[], &[]).unwrap();
 
         assert!(output.contains("pub struct WebRtcSessionInfo {"));
         assert!(output.contains("pub session_id: String,"));
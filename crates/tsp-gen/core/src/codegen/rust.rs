@@ -1596,7 +1596,7 @@ fn generate_adi_service(
                     let json_type = rust_type_to_json_schema_type(&p.rust_type);
                     writeln!(
                         out,
-                        "                        \"{}\": {{ \"type\": \"{}\" }},",
+                        "                        \"{}\": {{ \"type\": {} }},",
                         p.name.to_case(Case::Snake),
                         json_type
                     )?;
@@ -1700,20 +1700,28 @@ fn generate_adi_service(
 
     Ok(out)
 }
-/// Map a Rust type string to a JSON Schema type string
-fn rust_type_to_json_schema_type(rust_type: &str) -> &str {
-    match rust_type {
+/// Map a Rust type string to a JSON Schema `type` value, as JSON source
+fn rust_type_to_json_schema_type(rust_type: &str) -> String {
+    // The router validates params against this schema, so optional params
+    // must accept both their inner type and null
+    if let Some(inner) = rust_type
+        .strip_prefix("Option<")
+        .and_then(|t| t.strip_suffix('>'))
+    {
+        return format!("[{}, \"null\"]", rust_type_to_json_schema_type(inner));
+    }
+    let json_type = match rust_type {
         "String" => "string",
         "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" => "integer",
         "f32" | "f64" => "number",
         "bool" => "boolean",
         t if t.starts_with("Vec<") => "array",
-        t if t.starts_with("Option<") => "string",
         t if t.starts_with("HashMap<") => "object",
         "Uuid" => "string",
         "DateTime<Utc>" => "string",
         _ => "object",
-    }
+    };
+    format!("\"{}\"", json_type)
 }
 
 /// Generate a deserialization expression for extracting a typed param from JSON
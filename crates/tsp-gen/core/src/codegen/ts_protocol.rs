@@ -280,6 +280,8 @@ interface Device {
             tag: "type".to_string(),
             rename: "snake_case".to_string(),
             enum_name: "SignalingMessage".to_string(),
+            field_types: Vec::new(),
+            type_attrs: Vec::new(),
         };
 
         let output = generate_messages(&variants, &config, &file).unwrap();
@@ -329,6 +331,8 @@ interface Device {
             tag: "type".to_string(),
             rename: "snake_case".to_string(),
             enum_name: "Msg".to_string(),
+            field_types: Vec::new(),
+            type_attrs: Vec::new(),
         };
 
         let output = generate_messages(&variants, &config, &file).unwrap();
@@ -363,6 +367,8 @@ interface Auth {
             tag: "type".to_string(),
             rename: "snake_case".to_string(),
             enum_name: "Msg".to_string(),
+            field_types: Vec::new(),
+            type_attrs: Vec::new(),
         };
 
         let output = generate_messages(&variants, &config, &file).unwrap();
@@ -404,6 +410,8 @@ interface System {
             tag: "type".to_string(),
             rename: "snake_case".to_string(),
             enum_name: "SignalingMessage".to_string(),
+            field_types: Vec::new(),
+            type_attrs: Vec::new(),
         };
 
         let dir = tempfile::tempdir().unwrap();
@@ -435,6 +435,8 @@ fn do_generate(opts: &GenerateOptions) -> CmdResult {
             tag: opts.protocol_tag.clone(),
             rename: opts.protocol_rename.clone(),
             enum_name: opts.protocol_enum_name.clone(),
+            field_types: Vec::new(),
+            type_attrs: Vec::new(),
         });
     }
 
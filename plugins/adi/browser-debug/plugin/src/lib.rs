@@ -0,0 +1,268 @@
+//! Browser Debug plugin - inspect tabs an extension shared with a debug token.
+//!
+//! The CLI sends `browser_debug_*` queries for a token through the signaling
+//! server, which routes them to the extension holding the tab; the extension
+//! answers from its capture buffers.
+
+use futures::{SinkExt, StreamExt};
+use lib_credential_store::CredentialStore;
+use lib_env_parse::{env_opt, env_vars};
+use lib_tarminal_sync::{
+    render_websocket_timeline, SignalingMessage, WebSocketConnection, WebSocketDirection,
+    WebSocketFilters,
+};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+env_vars! {
+    SignalingServerUrl => "SIGNALING_SERVER_URL",
+}
+
+use lib_plugin_prelude::*;
+
+/// How long the extension may take to answer a query.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn get_help_text() -> &'static str {
+    r#"Browser Debug - inspect browser tabs shared with a debug token
+
+USAGE:
+    adi browser-debug [COMMAND] [ARGS]
+
+COMMANDS:
+    ws [token]          Show the WebSocket frame timeline of a tab
+    help                Show this help message
+
+WS OPTIONS:
+    --filter TEXT       Only connections whose URL contains TEXT
+    --direction DIR     Only sent or received frames
+    --since MS          Only frames at or after this unix ms timestamp
+    --limit N           Most recent N frames per connection
+    --url URL           Signaling server URL
+    The token is cached per signaling URL, so later calls can omit it.
+    Tokens scoped no_bodies show frame sizes without payloads.
+
+ENVIRONMENT VARIABLES:
+    SIGNALING_SERVER_URL    WebSocket URL (default: ws://localhost:8080/ws)
+
+EXAMPLES:
+    # Frames of every socket the tab opened
+    adi browser-debug ws dbg-7a3f
+
+    # Last 20 frames the page received from its chat socket
+    adi browser-debug ws --filter /chat --direction received --limit 20
+"#
+}
+
+/// Options for `ws`: `[token] --filter /chat --direction sent --limit 20`.
+#[derive(CliArgs)]
+pub struct WsArgs {
+    #[arg(position = 0)]
+    pub token: Option<String>,
+
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    #[arg(long)]
+    pub direction: Option<String>,
+
+    #[arg(long)]
+    pub since: Option<i64>,
+
+    #[arg(long)]
+    pub limit: Option<u32>,
+
+    #[arg(long)]
+    pub url: Option<String>,
+}
+
+pub struct BrowserDebugPlugin;
+
+impl BrowserDebugPlugin {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for BrowserDebugPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Plugin for BrowserDebugPlugin {
+    fn metadata(&self) -> PluginMetadata {
+        PluginMetadata::new(
+            "adi.browser-debug",
+            "Browser Debug",
+            env!("CARGO_PKG_VERSION"),
+        )
+        .with_type(PluginType::Core)
+        .with_author("ADI Team")
+        .with_description("Inspect browser tabs shared with a debug token")
+    }
+
+    async fn init(&mut self, _ctx: &PluginContext) -> Result<()> {
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn provides(&self) -> Vec<&'static str> {
+        vec![SERVICE_CLI_COMMANDS]
+    }
+}
+
+#[async_trait]
+impl CliCommands for BrowserDebugPlugin {
+    async fn list_commands(&self) -> Vec<CliCommand> {
+        vec![Self::__sdk_cmd_meta_ws()]
+    }
+
+    async fn run_command(&self, ctx: &CliContext) -> Result<CliResult> {
+        match ctx.subcommand.as_deref() {
+            Some("ws") | Some("websockets") => self.__sdk_cmd_handler_ws(ctx).await,
+            Some("help") | Some("-h") | Some("--help") | Some("") | None => {
+                Ok(CliResult::success(get_help_text().to_string()))
+            }
+            Some(cmd) => Ok(CliResult::error(format!(
+                "Unknown command: {}. Run 'adi browser-debug help' for usage information.",
+                cmd
+            ))),
+        }
+    }
+}
+
+impl BrowserDebugPlugin {
+    #[command(
+        name = "ws",
+        description = "Show the WebSocket frame timeline of a tab"
+    )]
+    async fn ws(&self, args: WsArgs) -> CmdResult {
+        let direction = match args.direction.as_deref() {
+            None => None,
+            Some("sent") => Some(WebSocketDirection::Sent),
+            Some("received") => Some(WebSocketDirection::Received),
+            Some(other) => {
+                return Err(format!(
+                    "Unknown direction '{}': use sent or received",
+                    other
+                ))
+            }
+        };
+        let (signaling_url, token) = debug_token(args.url, args.token)?;
+        let filters = WebSocketFilters {
+            url_pattern: args.filter,
+            direction,
+            since: args.since,
+            limit: args.limit,
+        };
+
+        let connections = query_web_sockets(&signaling_url, &token, filters).await?;
+        print!("{}", render_websocket_timeline(&connections));
+        Ok(format!("{} WebSocket connection(s)", connections.len()))
+    }
+}
+
+/// Ask the extension holding `token`'s tab for its WebSocket capture.
+async fn query_web_sockets(
+    signaling_url: &str,
+    token: &str,
+    filters: WebSocketFilters,
+) -> std::result::Result<Vec<WebSocketConnection>, String> {
+    let (ws, _) = tokio_tungstenite::connect_async(signaling_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", signaling_url, e))?;
+    let (mut sink, mut stream) = ws.split();
+
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let request = SignalingMessage::BrowserDebugGetWebSockets {
+        request_id: request_id.clone(),
+        token: token.to_string(),
+        filters: Some(filters),
+    };
+    let text = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+    sink.send(Message::Text(text))
+        .await
+        .map_err(|e| format!("Failed to send to signaling server: {}", e))?;
+
+    loop {
+        let next = tokio::time::timeout(QUERY_TIMEOUT, stream.next())
+            .await
+            .map_err(|_| "Timed out waiting for the browser extension".to_string())?;
+        let text = match next {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(format!("Signaling connection failed: {}", e)),
+            None => return Err("Signaling server closed the connection".to_string()),
+        };
+
+        match serde_json::from_str::<SignalingMessage>(&text) {
+            Ok(SignalingMessage::BrowserDebugWebSocketData {
+                request_id: id,
+                connections,
+            }) if id == request_id => return Ok(connections),
+            Ok(SignalingMessage::BrowserDebugTokenRevoked { token: t, reason }) if t == token => {
+                return Err(format!(
+                    "Debug token {} is no longer valid{}",
+                    token,
+                    reason.map(|r| format!(": {}", r)).unwrap_or_default()
+                ))
+            }
+            Ok(SignalingMessage::Error { message }) => return Err(message),
+            _ => {}
+        }
+    }
+}
+
+/// Signaling URL and debug token, from the flags or the environment. A given
+/// token is cached per signaling URL, so later commands can omit it.
+fn debug_token(
+    url: Option<String>,
+    token: Option<String>,
+) -> std::result::Result<(String, String), String> {
+    let signaling_url = url
+        .or_else(|| env_opt(EnvVar::SignalingServerUrl.as_str()))
+        .unwrap_or_else(|| "ws://localhost:8080/ws".to_string());
+    let store = credential_store();
+    let token = match token {
+        Some(token) => {
+            if let Some(store) = &store {
+                if let Err(e) = store.set_browser_debug_token(&signaling_url, &token, None) {
+                    tracing::warn!("Failed to cache debug token: {}", e);
+                }
+            }
+            token
+        }
+        None => store
+            .and_then(|store| store.browser_debug_token(&signaling_url).ok().flatten())
+            .ok_or("No debug token. Usage: adi browser-debug ws <token>")?,
+    };
+    Ok((signaling_url, token))
+}
+
+/// The local credential store; a cache miss rather than an error when it
+/// can't be opened.
+fn credential_store() -> Option<CredentialStore> {
+    CredentialStore::open_default()
+        .map_err(|e| tracing::warn!("Credential store unavailable: {}", e))
+        .ok()
+}
+
+#[no_mangle]
+pub extern "C" fn plugin_abi_version() -> u32 {
+    3
+}
+
+#[no_mangle]
+pub fn plugin_create() -> Box<dyn Plugin> {
+    Box::new(BrowserDebugPlugin::new())
+}
+
+#[no_mangle]
+pub fn plugin_create_cli() -> Box<dyn CliCommands> {
+    Box::new(BrowserDebugPlugin::new())
+}
@@ -19,6 +19,8 @@ fn main() {
         tag: "type".to_string(),
         rename: "snake_case".to_string(),
         enum_name: "CocoonMessage".to_string(),
+        field_types: Vec::new(),
+        type_attrs: Vec::new(),
     };
 
     Generator::new(&file, &proto_dir, "cocoon")
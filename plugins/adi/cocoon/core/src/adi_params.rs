@@ -0,0 +1,159 @@
+//! Validation of ADI request params against `AdiMethodInfo::params_schema`.
+//!
+//! Schemas are compiled once when a plugin registers. The router validates a
+//! request's payload before handing it to the plugin and answers invalid
+//! requests itself with an `invalid_params` error listing each violation by
+//! JSON pointer. Methods without a schema, or with `skip_params_validation`,
+//! are passed through untouched.
+
+use crate::adi_router::AdiMethodInfo;
+use bytes::Bytes;
+use jsonschema::Validator;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// One schema violation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParamsError {
+    /// JSON pointer into the params, `""` for the params themselves
+    pub path: String,
+    pub message: String,
+}
+
+/// Compiled params schemas of one plugin, by method name
+#[derive(Default)]
+pub struct ParamsValidator {
+    methods: HashMap<String, Validator>,
+}
+
+impl ParamsValidator {
+    /// Compile the schemas of `methods`. A schema that does not compile is
+    /// logged and the method is left unvalidated.
+    pub fn new(plugin_id: &str, methods: &[AdiMethodInfo]) -> Self {
+        let mut compiled = HashMap::new();
+        for method in methods {
+            let Some(schema) = &method.params_schema else {
+                continue;
+            };
+            if method.skip_params_validation == Some(true) {
+                continue;
+            }
+            match jsonschema::validator_for(schema) {
+                Ok(validator) => {
+                    compiled.insert(method.name.clone(), validator);
+                }
+                Err(e) => tracing::warn!(
+                    "Invalid params_schema for {}.{}, not validating: {}",
+                    plugin_id,
+                    method.name,
+                    e
+                ),
+            }
+        }
+        Self { methods: compiled }
+    }
+
+    /// Check a raw request payload. An empty payload counts as `{}`.
+    pub fn validate(&self, method: &str, payload: &[u8]) -> Result<(), Vec<ParamsError>> {
+        let Some(validator) = self.methods.get(method) else {
+            return Ok(());
+        };
+        let params: JsonValue = if payload.is_empty() {
+            JsonValue::Object(Default::default())
+        } else {
+            serde_json::from_slice(payload).map_err(|e| {
+                vec![ParamsError {
+                    path: String::new(),
+                    message: format!("params are not valid JSON: {}", e),
+                }]
+            })?
+        };
+
+        let errors: Vec<ParamsError> = validator
+            .iter_errors(&params)
+            .map(|e| ParamsError {
+                path: e.instance_path.to_string(),
+                message: e.to_string(),
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Error payload for a request that failed validation, shaped like
+/// `AdiServiceError::to_payload` plus the individual violations.
+pub fn invalid_params_payload(method: &str, errors: &[ParamsError]) -> Bytes {
+    let message = match errors {
+        [only] if only.path.is_empty() => only.message.clone(),
+        [only] => format!("{}: {}", only.path, only.message),
+        _ => format!("{} params errors for '{}'", errors.len(), method),
+    };
+    let json = serde_json::json!({
+        "code": "invalid_params",
+        "message": message,
+        "errors": errors,
+    });
+    Bytes::from(serde_json::to_vec(&json).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn method(name: &str, schema: JsonValue) -> AdiMethodInfo {
+        AdiMethodInfo {
+            name: name.to_string(),
+            params_schema: Some(schema),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_json_pointers() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["title"]
+        });
+        let validator = ParamsValidator::new("adi.test", &[method("create", schema)]);
+
+        assert!(validator.validate("create", br#"{"title":"x"}"#).is_ok());
+        assert!(validator.validate("other", b"not json").is_ok());
+
+        let errors = validator
+            .validate("create", br#"{"title":1,"tags":["a",2]}"#)
+            .unwrap_err();
+        let mut paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/tags/1", "/title"]);
+
+        let missing = validator.validate("create", b"").unwrap_err();
+        assert_eq!(missing[0].path, "");
+        assert!(validator.validate("create", b"{").is_err());
+
+        let payload: JsonValue =
+            serde_json::from_slice(&invalid_params_payload("create", &errors)).unwrap();
+        assert_eq!(payload["code"], "invalid_params");
+        assert_eq!(payload["errors"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_opt_out_and_invalid_schema_skip_validation() {
+        let mut fast = method("fast", json!({ "type": "object" }));
+        fast.skip_params_validation = Some(true);
+        let broken = method("broken", json!({ "type": 12 }));
+        let validator = ParamsValidator::new("adi.test", &[fast, broken]);
+
+        assert!(validator.validate("fast", b"[1]").is_ok());
+        assert!(validator.validate("broken", b"[1]").is_ok());
+    }
+}
@@ -11,12 +11,17 @@
 use async_trait::async_trait;
 use bytes::Bytes;
 use crate::adi_frame::{self, ResponseStatus};
+use crate::adi_params::{self, ParamsValidator};
+use crate::adi_usage::{Admission, UsageMeter, QUOTA_EXCEEDED, USAGE_PLUGIN_ID};
+use crate::delegation::DelegatedAccess;
 #[cfg(test)]
 use crate::adi_frame::RequestHeader;
+use crate::plugin_catalog::{CatalogDiff, PluginCatalog};
 use serde::{Serialize, Deserialize};
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{broadcast, mpsc, RwLock};
 use uuid::Uuid;
 
@@ -40,8 +45,17 @@ pub enum AdiDiscovery {
 #[derive(Debug, Clone)]
 pub enum AdiNotification {
     PluginsChanged { added: Vec<String>, removed: Vec<String>, updated: Vec<String> },
+    /// Event of a background service (e.g. live lint diagnostics); clients
+    /// receive it by subscribing to `service` like to a plugin
+    ServiceEvent { service: String, event: String, data: JsonValue },
 }
 
+/// Subscribing to this event name delivers every event of a service.
+pub const ALL_SERVICE_EVENTS: &str = "*";
+
+/// Error code for requests outside a delegated session's scopes.
+pub const FORBIDDEN: &str = "forbidden";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AdiSubscription {
@@ -49,9 +63,19 @@ pub enum AdiSubscription {
     Subscribed { request_id: Uuid, subscription_id: Uuid, plugin: String, event: String },
     Unsubscribe { subscription_id: Uuid },
     Unsubscribed { subscription_id: Uuid },
+    Event { subscription_id: Uuid, event: String, data: JsonValue },
     Error { request_id: Uuid, code: String, message: String },
 }
 
+/// Sent by a client to stop a streaming call before its last chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AdiStreamControl {
+    /// Also accepted under its `cocoon.tsp` name
+    #[serde(alias = "adi_cancel_stream")]
+    CancelStream { request_id: Uuid },
+}
+
 #[derive(Debug)]
 pub struct ActiveSubscription {
     pub plugin: String,
@@ -60,8 +84,17 @@ pub struct ActiveSubscription {
 
 pub struct AdiRouter {
     plugins: HashMap<String, Arc<dyn AdiService>>,
+    /// Compiled `params_schema`s, by plugin ID
+    validators: HashMap<String, ParamsValidator>,
     subscriptions: Arc<RwLock<HashMap<Uuid, ActiveSubscription>>>,
+    /// Streaming calls being forwarded, by request ID
+    streams: Arc<std::sync::Mutex<HashMap<Uuid, tokio::task::AbortHandle>>>,
     notification_tx: broadcast::Sender<AdiNotification>,
+    /// Services publishing `ServiceEvent`s that clients may subscribe to
+    event_sources: HashSet<String>,
+    /// What clients were last told about; notifications are diffs against it
+    catalog: PluginCatalog,
+    usage: Arc<UsageMeter>,
 }
 
 impl Default for AdiRouter {
@@ -70,14 +103,35 @@ impl Default for AdiRouter {
 
 impl AdiRouter {
     pub fn new() -> Self {
+        Self::with_catalog(PluginCatalog::default())
+    }
+
+    /// Router diffing registrations against a previously persisted catalogue.
+    /// Call [`reconcile`](Self::reconcile) once startup registration is done.
+    pub fn with_catalog(catalog: PluginCatalog) -> Self {
         let (notification_tx, _) = broadcast::channel(256);
         Self {
             plugins: HashMap::new(),
+            validators: HashMap::new(),
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            streams: Arc::new(std::sync::Mutex::new(HashMap::new())),
             notification_tx,
+            event_sources: HashSet::new(),
+            catalog,
+            usage: Arc::new(UsageMeter::default()),
         }
     }
 
+    pub fn catalog(&self) -> &PluginCatalog {
+        &self.catalog
+    }
+
+    /// Per-client usage of the routed requests, and the quota enforced by
+    /// [`admit`](Self::admit).
+    pub fn usage_meter(&self) -> Arc<UsageMeter> {
+        self.usage.clone()
+    }
+
     pub fn notification_receiver(&self) -> broadcast::Receiver<AdiNotification> {
         self.notification_tx.subscribe()
     }
@@ -86,6 +140,17 @@ impl AdiRouter {
         let _ = self.notification_tx.send(notification);
     }
 
+    /// Sender for publishing from background tasks without holding the router.
+    pub fn notification_sender(&self) -> broadcast::Sender<AdiNotification> {
+        self.notification_tx.clone()
+    }
+
+    /// Let clients subscribe to the `ServiceEvent`s of `service`. A plugin
+    /// registered under the same ID takes precedence.
+    pub fn register_event_source(&mut self, service: &str) {
+        self.event_sources.insert(service.to_string());
+    }
+
     pub fn register(&mut self, plugin: Arc<dyn AdiService>) {
         let id = plugin.plugin_id().to_string();
         let caps = plugin.capabilities();
@@ -95,23 +160,20 @@ impl AdiRouter {
             caps.streaming, caps.notifications, caps.subscriptions
         );
 
-        let was_new = !self.plugins.contains_key(&id);
-        self.plugins.insert(id.clone(), plugin);
-
-        if was_new {
-            self.broadcast_notification(AdiNotification::PluginsChanged {
-                added: vec![id], removed: vec![], updated: vec![],
-            });
-        } else {
-            self.broadcast_notification(AdiNotification::PluginsChanged {
-                added: vec![], removed: vec![], updated: vec![id],
-            });
+        let info = plugin_info(plugin.as_ref());
+        self.validators.insert(id.clone(), ParamsValidator::new(&id, &info.methods));
+        let diff = self.catalog.upsert(info);
+        self.plugins.insert(id, plugin);
+        if let Some(notification) = diff.into_notification() {
+            self.broadcast_notification(notification);
         }
     }
 
     pub fn unregister(&mut self, plugin_id: &str) -> bool {
         if self.plugins.remove(plugin_id).is_some() {
             tracing::info!("Unregistered ADI plugin: {}", plugin_id);
+            self.validators.remove(plugin_id);
+            self.catalog.remove(plugin_id);
             self.broadcast_notification(AdiNotification::PluginsChanged {
                 added: vec![], removed: vec![plugin_id.to_string()], updated: vec![],
             });
@@ -121,6 +183,17 @@ impl AdiRouter {
         }
     }
 
+    /// Drop catalogue entries for plugins that are no longer registered,
+    /// e.g. ones persisted by a previous run, and announce their removal.
+    pub fn reconcile(&mut self) -> CatalogDiff {
+        let registered: Vec<&str> = self.plugins.keys().map(String::as_str).collect();
+        let diff = CatalogDiff { removed: self.catalog.retain(&registered), ..Default::default() };
+        if let Some(notification) = diff.clone().into_notification() {
+            self.broadcast_notification(notification);
+        }
+        diff
+    }
+
     pub fn has_plugin(&self, plugin_id: &str) -> bool {
         self.plugins.contains_key(plugin_id)
     }
@@ -130,17 +203,7 @@ impl AdiRouter {
     }
 
     pub fn list_plugins(&self) -> Vec<AdiPluginInfo> {
-        self.plugins
-            .values()
-            .map(|s| AdiPluginInfo {
-                id: s.plugin_id().to_string(),
-                name: s.name().to_string(),
-                version: s.version().to_string(),
-                description: s.description().map(String::from),
-                methods: s.methods(),
-                capabilities: s.capabilities(),
-            })
-            .collect()
+        self.plugins.values().map(|s| plugin_info(s.as_ref())).collect()
     }
 
     pub fn handle_discovery(&self, discovery: AdiDiscovery) -> AdiDiscovery {
@@ -153,50 +216,242 @@ impl AdiRouter {
         }
     }
 
-    pub async fn handle_subscription(&self, subscription: AdiSubscription) -> AdiSubscription {
+    /// Handle a subscription message. A successful `Subscribe` also returns
+    /// the plugin's event receiver, to be passed to
+    /// [`forward_events`](Self::forward_events).
+    pub async fn handle_subscription(
+        &self,
+        subscription: AdiSubscription,
+    ) -> (AdiSubscription, Option<broadcast::Receiver<SubscriptionEvent>>) {
+        self.handle_subscription_scoped(None, subscription).await
+    }
+
+    /// [`handle_subscription`](Self::handle_subscription) for a session opened
+    /// with delegated access: subscribing needs a scope for the plugin.
+    pub async fn handle_subscription_scoped(
+        &self,
+        access: Option<&DelegatedAccess>,
+        subscription: AdiSubscription,
+    ) -> (AdiSubscription, Option<broadcast::Receiver<SubscriptionEvent>>) {
+        if let (Some(access), AdiSubscription::Subscribe { request_id, plugin, .. }) = (access, &subscription) {
+            if let Err(message) = access.check_plugin(plugin) {
+                return (AdiSubscription::Error {
+                    request_id: *request_id,
+                    code: FORBIDDEN.to_string(),
+                    message,
+                }, None);
+            }
+        }
+
         match subscription {
             AdiSubscription::Subscribe { request_id, plugin, event, filter } => {
                 let svc = match self.plugins.get(&plugin) {
                     Some(s) => s,
-                    None => return AdiSubscription::Error {
+                    None if self.event_sources.contains(&plugin) => {
+                        let receiver = self.service_events(&plugin, &event);
+                        let subscription_id = self.add_subscription(&plugin, &event).await;
+                        return (
+                            AdiSubscription::Subscribed { request_id, subscription_id, plugin, event },
+                            Some(receiver),
+                        );
+                    }
+                    None => return (AdiSubscription::Error {
                         request_id,
                         code: "plugin_not_found".to_string(),
                         message: format!("Plugin '{}' not found", plugin),
-                    },
+                    }, None),
                 };
 
                 if !svc.capabilities().subscriptions {
-                    return AdiSubscription::Error {
+                    return (AdiSubscription::Error {
                         request_id,
                         code: "not_supported".to_string(),
                         message: format!("Plugin '{}' does not support subscriptions", plugin),
-                    };
+                    }, None);
                 }
 
                 match svc.subscribe(&event, filter).await {
-                    Ok(_receiver) => {
-                        let subscription_id = Uuid::new_v4();
-                        let mut subs = self.subscriptions.write().await;
-                        subs.insert(subscription_id, ActiveSubscription {
-                            plugin: plugin.clone(),
-                            event: event.clone(),
-                        });
-
-                        AdiSubscription::Subscribed { request_id, subscription_id, plugin, event }
+                    Ok(receiver) => {
+                        let subscription_id = self.add_subscription(&plugin, &event).await;
+                        (AdiSubscription::Subscribed { request_id, subscription_id, plugin, event }, Some(receiver))
                     }
-                    Err(e) => AdiSubscription::Error {
+                    Err(e) => (AdiSubscription::Error {
                         request_id, code: e.code, message: e.message,
-                    },
+                    }, None),
                 }
             }
 
             AdiSubscription::Unsubscribe { subscription_id } => {
-                let mut subs = self.subscriptions.write().await;
-                subs.remove(&subscription_id);
-                AdiSubscription::Unsubscribed { subscription_id }
+                self.remove_subscription(subscription_id).await;
+                (AdiSubscription::Unsubscribed { subscription_id }, None)
             }
 
-            other => other,
+            other => (other, None),
+        }
+    }
+
+    /// Drop a subscription; its forwarding task stops at the next event.
+    /// `false` if no such subscription exists.
+    pub async fn remove_subscription(&self, subscription_id: Uuid) -> bool {
+        self.subscriptions.write().await.remove(&subscription_id).is_some()
+    }
+
+    async fn add_subscription(&self, plugin: &str, event: &str) -> Uuid {
+        let subscription_id = Uuid::new_v4();
+        self.subscriptions.write().await.insert(subscription_id, ActiveSubscription {
+            plugin: plugin.to_string(),
+            event: event.to_string(),
+        });
+        subscription_id
+    }
+
+    /// `service`'s `ServiceEvent`s named `event` (or all, for
+    /// [`ALL_SERVICE_EVENTS`]) as subscription events. The piping task ends
+    /// at the first event after the receiver is dropped.
+    fn service_events(&self, service: &str, event: &str) -> broadcast::Receiver<SubscriptionEvent> {
+        let (tx, rx) = broadcast::channel(256);
+        let mut notifications = self.notification_tx.subscribe();
+        let service = service.to_string();
+        let wanted = event.to_string();
+        tokio::spawn(async move {
+            loop {
+                let notification = match notifications.recv().await {
+                    Ok(notification) => notification,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let AdiNotification::ServiceEvent { service: from, event, data } = notification else {
+                    continue;
+                };
+                if from != service || (wanted != ALL_SERVICE_EVENTS && event != wanted) {
+                    continue;
+                }
+                if tx.send(SubscriptionEvent { event, data }).is_err() {
+                    break;
+                }
+            }
+        });
+        rx
+    }
+
+    /// Send a subscription's events with `send` as `AdiSubscription::Event`
+    /// JSON until the plugin closes the stream, the client unsubscribes or
+    /// `send` returns `false` because the client is gone.
+    pub fn forward_events<F, Fut>(
+        &self,
+        subscription_id: Uuid,
+        mut events: broadcast::Receiver<SubscriptionEvent>,
+        send: F,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn(String) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = bool> + Send,
+    {
+        let subscriptions = self.subscriptions.clone();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Subscription {} lagged, {} events dropped", subscription_id, skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if !subscriptions.read().await.contains_key(&subscription_id) {
+                    return;
+                }
+                let msg = AdiSubscription::Event {
+                    subscription_id,
+                    event: event.event,
+                    data: event.data,
+                };
+                let Ok(json) = serde_json::to_string(&msg) else {
+                    continue;
+                };
+                if !send(json).await {
+                    break;
+                }
+            }
+            subscriptions.write().await.remove(&subscription_id);
+        })
+    }
+
+    /// Send a streaming call's chunks with `send` as binary frames until the
+    /// last chunk, until `send` returns `false` because the client is gone,
+    /// or until the client cancels it with [`cancel_stream`](Self::cancel_stream).
+    pub fn forward_stream<F, Fut>(
+        &self,
+        request_id: Uuid,
+        mut receiver: mpsc::Receiver<(Bytes, bool)>,
+        send: F,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn(Bytes) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = bool> + Send,
+    {
+        let streams = self.streams.clone();
+        // Held until the handle is stored, so a stream that ends right away
+        // still removes its entry
+        let mut running = self.streams.lock().unwrap();
+        let handle = tokio::spawn(async move {
+            let mut seq = 0u32;
+            while let Some((chunk_data, is_final)) = receiver.recv().await {
+                let frame = if is_final {
+                    adi_frame::stream_end(request_id, seq, &chunk_data)
+                } else {
+                    adi_frame::stream_chunk(request_id, seq, &chunk_data)
+                };
+                seq += 1;
+
+                if !send(frame).await || is_final {
+                    break;
+                }
+            }
+            streams.lock().unwrap().remove(&request_id);
+        });
+        running.insert(request_id, handle.abort_handle());
+        handle
+    }
+
+    /// Stop forwarding a streaming call. Dropping its receiver tells the
+    /// plugin to stop producing chunks. `false` if no such stream is running.
+    pub fn cancel_stream(&self, request_id: Uuid) -> bool {
+        match self.streams.lock().unwrap().remove(&request_id) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of streaming calls being forwarded
+    pub fn stream_count(&self) -> usize {
+        self.streams.lock().unwrap().len()
+    }
+
+    /// Check a binary-framed request against the caller's quota before it is
+    /// handled. `Err` is the `quota_exceeded` response to send instead;
+    /// `Ok(Some(delay))` asks the caller to wait that long first, without
+    /// holding the router, so a throttled client does not hold up others.
+    pub fn admit(
+        &self,
+        ctx: &AdiCallerContext,
+        access: Option<&DelegatedAccess>,
+        raw: &[u8],
+    ) -> Result<Option<Duration>, Bytes> {
+        // Malformed frames are answered by the handler
+        let Ok((header, _)) = adi_frame::parse_request(raw) else {
+            return Ok(None);
+        };
+        match self.usage.admit(&UsageMeter::client_of(ctx, access), &header.plugin) {
+            Admission::Allow => Ok(None),
+            Admission::Throttle(delay) => Ok(Some(delay)),
+            Admission::Reject => Err(adi_frame::error_response(
+                header.id,
+                &AdiServiceError::new(QUOTA_EXCEEDED, "Usage quota exceeded, retry once the window ends").to_payload(),
+            )),
         }
     }
 
@@ -205,6 +460,18 @@ impl AdiRouter {
     /// Parses the frame header, routes to the plugin, and returns a complete
     /// binary response frame ready to send over the wire.
     pub async fn handle_binary(&self, ctx: &AdiCallerContext, raw: &[u8]) -> AdiRouterBinaryResult {
+        self.handle_binary_scoped(ctx, None, raw).await
+    }
+
+    /// [`handle_binary`](Self::handle_binary) for a session opened with
+    /// delegated access: methods outside the grant's scopes are answered with
+    /// a `forbidden` error before the plugin sees the request.
+    pub async fn handle_binary_scoped(
+        &self,
+        ctx: &AdiCallerContext,
+        access: Option<&DelegatedAccess>,
+        raw: &[u8],
+    ) -> AdiRouterBinaryResult {
         let (header, payload) = match adi_frame::parse_request(raw) {
             Ok(r) => r,
             Err(e) => {
@@ -235,17 +502,108 @@ impl AdiRouter {
             ));
         }
 
-        match plugin_svc.handle(ctx, &header.method, payload).await {
-            Ok(AdiHandleResult::Success(data)) => {
-                AdiRouterBinaryResult::Single(adi_frame::success_response(header.id, &data))
+        if let Some(Err(message)) = access.map(|a| a.check_method(&header.plugin, &header.method)) {
+            return AdiRouterBinaryResult::Single(adi_frame::error_response(
+                header.id,
+                &AdiServiceError::new(FORBIDDEN, message).to_payload(),
+            ));
+        }
+
+        if let Some(validator) = self.validators.get(&header.plugin) {
+            if let Err(errors) = validator.validate(&header.method, &payload) {
+                return AdiRouterBinaryResult::Single(adi_frame::error_response(
+                    header.id,
+                    &adi_params::invalid_params_payload(&header.method, &errors),
+                ));
             }
+        }
+
+        let client = UsageMeter::client_of(ctx, access);
+        self.usage.record_request(&client, &header.plugin, raw.len());
+
+        // Plugins only see the context, so usage is answered for the client
+        // accounted here, delegated tokens included
+        let handled = if header.plugin == USAGE_PLUGIN_ID {
+            self.usage.client_usage(client.clone()).map(AdiHandleResult::Success)
+        } else {
+            plugin_svc.handle(ctx, &header.method, payload).await
+        };
+        let response = match handled {
+            Ok(AdiHandleResult::Success(data)) => adi_frame::success_response(header.id, &data),
             Ok(AdiHandleResult::Stream(rx)) => {
-                AdiRouterBinaryResult::Stream { request_id: header.id, receiver: rx }
+                let receiver = self.usage.meter_stream(client, header.plugin, rx);
+                return AdiRouterBinaryResult::Stream { request_id: header.id, receiver };
             }
-            Err(e) => {
-                AdiRouterBinaryResult::Single(adi_frame::error_response(header.id, &e.to_payload()))
+            Err(e) => adi_frame::error_response(header.id, &e.to_payload()),
+        };
+        self.usage.record_response(&client, &header.plugin, response.len());
+        AdiRouterBinaryResult::Single(response)
+    }
+
+    /// Serve a `capability_request` another cocoon sent for `plugin`.
+    ///
+    /// The payload is `{ "method": ..., "params": ... }`; the result is the
+    /// method's JSON answer. Streaming methods are refused since a capability
+    /// response carries a single payload.
+    pub async fn handle_capability(
+        &self,
+        ctx: &AdiCallerContext,
+        plugin: &str,
+        payload: &JsonValue,
+    ) -> Result<JsonValue, AdiServiceError> {
+        let plugin_svc = self
+            .plugins
+            .get(plugin)
+            .ok_or_else(|| AdiServiceError::not_found(format!("Plugin '{}' not found", plugin)))?;
+        let method = payload
+            .get("method")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| AdiServiceError::invalid_params("Missing 'method'"))?;
+        let info = plugin_svc
+            .methods()
+            .into_iter()
+            .find(|m| m.name == method)
+            .ok_or_else(|| AdiServiceError::method_not_found(method))?;
+        if info.streaming {
+            return Err(AdiServiceError::not_supported(format!(
+                "Method '{}' streams; capability requests take single responses",
+                method
+            )));
+        }
+
+        let params = serde_json::to_vec(payload.get("params").unwrap_or(&JsonValue::Null))
+            .map_err(|e| AdiServiceError::invalid_params(e.to_string()))?;
+        if let Some(validator) = self.validators.get(plugin) {
+            if let Err(errors) = validator.validate(method, &params) {
+                let messages: Vec<String> = errors
+                    .iter()
+                    .map(|e| match e.path.as_str() {
+                        "" => e.message.clone(),
+                        path => format!("{}: {}", path, e.message),
+                    })
+                    .collect();
+                return Err(AdiServiceError::invalid_params(messages.join("; ")));
             }
         }
+
+        let client = UsageMeter::client_of(ctx, None);
+        if matches!(self.usage.admit(&client, plugin), Admission::Reject) {
+            return Err(AdiServiceError::new(
+                QUOTA_EXCEEDED,
+                "Usage quota exceeded, retry once the window ends",
+            ));
+        }
+        self.usage.record_request(&client, plugin, params.len());
+        let data = match plugin_svc.handle(ctx, method, Bytes::from(params)).await? {
+            AdiHandleResult::Success(data) => data,
+            AdiHandleResult::Stream(_) => {
+                return Err(AdiServiceError::not_supported(format!("Method '{}' streamed", method)))
+            }
+        };
+        self.usage.record_response(&client, plugin, data.len());
+        serde_json::from_slice(&data).map_err(|e| {
+            AdiServiceError::internal(format!("Method '{}' did not answer with JSON: {}", method, e))
+        })
     }
 
     pub fn client_connected(&self, client_id: &str) {
@@ -274,6 +632,17 @@ impl AdiRouter {
     }
 }
 
+fn plugin_info(s: &dyn AdiService) -> AdiPluginInfo {
+    AdiPluginInfo {
+        id: s.plugin_id().to_string(),
+        name: s.name().to_string(),
+        version: s.version().to_string(),
+        description: s.description().map(String::from),
+        methods: s.methods(),
+        capabilities: s.capabilities(),
+    }
+}
+
 /// Result from binary-framed router handling.
 pub enum AdiRouterBinaryResult {
     /// Single response frame (ready to send)
@@ -311,7 +680,10 @@ mod tests {
                     name: "count".to_string(),
                     description: "Count to N (streaming)".to_string(),
                     streaming: true,
-                    params_schema: None,
+                    params_schema: Some(json!({
+                        "type": "object",
+                        "properties": { "n": { "type": "integer", "minimum": 1 } }
+                    })),
                     ..Default::default()
                 },
             ]
@@ -377,6 +749,32 @@ mod tests {
         assert_eq!(plugins[0].methods.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_router_notifications_follow_catalog() {
+        let mut previous = PluginCatalog::new(Some("device-1".to_string()));
+        previous.upsert(plugin_info(&TestService));
+        previous.upsert(AdiPluginInfo { id: "adi.gone".to_string(), ..plugin_info(&TestService) });
+
+        let mut router = AdiRouter::with_catalog(previous);
+        let mut rx = router.notification_receiver();
+
+        // Unchanged since the last run: nothing to announce
+        router.register(Arc::new(TestService));
+        assert!(rx.try_recv().is_err());
+
+        let diff = router.reconcile();
+        assert_eq!(diff.removed, vec!["adi.gone"]);
+        let AdiNotification::PluginsChanged { added, removed, updated } = rx.try_recv().unwrap() else {
+            panic!("expected plugins_changed");
+        };
+        assert!(added.is_empty() && updated.is_empty());
+        assert_eq!(removed, vec!["adi.gone"]);
+
+        assert!(router.reconcile().is_empty());
+        assert!(router.unregister("adi.test"));
+        assert_eq!(router.catalog().ids().count(), 0);
+    }
+
     #[tokio::test]
     async fn test_router_handle_success() {
         let mut router = AdiRouter::new();
@@ -443,6 +841,54 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_router_rejects_invalid_params() {
+        let mut router = AdiRouter::new();
+        router.register(Arc::new(TestService));
+
+        let frame = build_frame("adi.test", "count", br#"{"n": 0}"#);
+        let result = router.handle_binary(&AdiCallerContext::anonymous(), &frame).await;
+        match result {
+            AdiRouterBinaryResult::Single(response_frame) => {
+                let header_len = u32::from_be_bytes([
+                    response_frame[0], response_frame[1], response_frame[2], response_frame[3],
+                ]) as usize;
+                let header: adi_frame::ResponseHeader =
+                    serde_json::from_slice(&response_frame[4..4 + header_len]).unwrap();
+                assert_eq!(header.status, ResponseStatus::Error);
+                let error: JsonValue = serde_json::from_slice(&response_frame[4 + header_len..]).unwrap();
+                assert_eq!(error["code"], "invalid_params");
+                assert_eq!(error["errors"][0]["path"], "/n");
+            }
+            _ => panic!("Expected single response"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_handles_capability_requests() {
+        let mut router = AdiRouter::new();
+        router.register(Arc::new(TestService));
+        let ctx = AdiCallerContext { user_id: None, device_id: Some("cocoon-a".to_string()) };
+
+        let echoed = router
+            .handle_capability(&ctx, "adi.test", &json!({ "method": "echo", "params": { "hello": "world" } }))
+            .await
+            .unwrap();
+        assert_eq!(echoed["hello"], "world");
+
+        let missing = router.handle_capability(&ctx, "adi.gone", &json!({ "method": "echo" })).await;
+        assert_eq!(missing.unwrap_err().code, "not_found");
+
+        let unknown = router.handle_capability(&ctx, "adi.test", &json!({ "method": "nope" })).await;
+        assert_eq!(unknown.unwrap_err().code, "method_not_found");
+
+        // Streams can't be carried by a single capability response
+        let streaming = router
+            .handle_capability(&ctx, "adi.test", &json!({ "method": "count", "params": { "n": 2 } }))
+            .await;
+        assert_eq!(streaming.unwrap_err().code, "not_supported");
+    }
+
     #[tokio::test]
     async fn test_router_streaming() {
         let mut router = AdiRouter::new();
@@ -469,4 +915,257 @@ mod tests {
             _ => panic!("Expected streaming response"),
         }
     }
+
+    #[tokio::test]
+    async fn test_cancel_stream_stops_forwarding() {
+        let router = AdiRouter::new();
+        let request_id = Uuid::new_v4();
+        let (tx, receiver) = mpsc::channel(4);
+        let (sent_tx, mut sent) = mpsc::unbounded_channel();
+        let forward = router.forward_stream(request_id, receiver, move |frame: Bytes| {
+            let sent_tx = sent_tx.clone();
+            async move { sent_tx.send(frame).is_ok() }
+        });
+
+        tx.send((Bytes::from_static(b"1"), false)).await.unwrap();
+        assert!(sent.recv().await.is_some());
+        assert_eq!(router.stream_count(), 1);
+
+        assert!(router.cancel_stream(request_id));
+        assert!(forward.await.unwrap_err().is_cancelled());
+        // The plugin sees its receiver gone and stops producing
+        assert!(tx.send((Bytes::from_static(b"2"), false)).await.is_err());
+        assert_eq!(router.stream_count(), 0);
+        assert!(!router.cancel_stream(request_id));
+
+        let (tx, receiver) = mpsc::channel(4);
+        tx.send((Bytes::from_static(b"done"), true)).await.unwrap();
+        router
+            .forward_stream(Uuid::new_v4(), receiver, |_| async { true })
+            .await
+            .unwrap();
+        assert_eq!(router.stream_count(), 0);
+    }
+
+    struct EventService {
+        events: broadcast::Sender<SubscriptionEvent>,
+    }
+
+    #[async_trait]
+    impl AdiService for EventService {
+        fn plugin_id(&self) -> &str { "adi.events" }
+        fn name(&self) -> &str { "Event Service" }
+        fn version(&self) -> &str { "1.0.0" }
+        fn methods(&self) -> Vec<AdiMethodInfo> { vec![] }
+
+        fn capabilities(&self) -> AdiPluginCapabilities {
+            AdiPluginCapabilities { subscriptions: true, ..Default::default() }
+        }
+
+        async fn handle(
+            &self,
+            _ctx: &AdiCallerContext,
+            method: &str,
+            _payload: Bytes,
+        ) -> Result<AdiHandleResult, AdiServiceError> {
+            Err(AdiServiceError::method_not_found(method))
+        }
+
+        async fn subscribe(
+            &self,
+            _event: &str,
+            _filter: Option<JsonValue>,
+        ) -> Result<broadcast::Receiver<SubscriptionEvent>, AdiServiceError> {
+            Ok(self.events.subscribe())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscription_events_forwarded_until_unsubscribed() {
+        let (events, _) = broadcast::channel(8);
+        let mut router = AdiRouter::new();
+        router.register(Arc::new(EventService { events: events.clone() }));
+        router.register(Arc::new(TestService));
+
+        let (response, receiver) = router
+            .handle_subscription(AdiSubscription::Subscribe {
+                request_id: Uuid::nil(),
+                plugin: "adi.events".to_string(),
+                event: "changed".to_string(),
+                filter: None,
+            })
+            .await;
+        let AdiSubscription::Subscribed { subscription_id, .. } = response else {
+            panic!("expected subscribed, got {:?}", response);
+        };
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let task = router.forward_events(subscription_id, receiver.unwrap(), move |json| {
+            let tx = tx.clone();
+            async move { tx.send(json).is_ok() }
+        });
+
+        events.send(SubscriptionEvent { event: "changed".to_string(), data: json!({ "id": 1 }) }).unwrap();
+        let msg: AdiSubscription = serde_json::from_str(&rx.recv().await.unwrap()).unwrap();
+        assert!(matches!(
+            msg,
+            AdiSubscription::Event { subscription_id: id, ref data, .. } if id == subscription_id && data["id"] == 1
+        ));
+
+        router.handle_subscription(AdiSubscription::Unsubscribe { subscription_id }).await;
+        events.send(SubscriptionEvent { event: "changed".to_string(), data: json!({ "id": 2 }) }).unwrap();
+        task.await.unwrap();
+        assert!(rx.try_recv().is_err());
+
+        let (response, receiver) = router
+            .handle_subscription(AdiSubscription::Subscribe {
+                request_id: Uuid::nil(),
+                plugin: "adi.test".to_string(),
+                event: "changed".to_string(),
+                filter: None,
+            })
+            .await;
+        assert!(matches!(response, AdiSubscription::Error { ref code, .. } if code == "not_supported"));
+        assert!(receiver.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_service_event_subscription() {
+        let mut router = AdiRouter::new();
+        router.register_event_source("linter");
+        let notifications = router.notification_sender();
+
+        let (response, receiver) = router
+            .handle_subscription(AdiSubscription::Subscribe {
+                request_id: Uuid::nil(),
+                plugin: "linter".to_string(),
+                event: "diagnostic".to_string(),
+                filter: None,
+            })
+            .await;
+        assert!(matches!(response, AdiSubscription::Subscribed { .. }));
+        let mut receiver = receiver.unwrap();
+
+        let publish = |service: &str, event: &str, id: i64| {
+            notifications
+                .send(AdiNotification::ServiceEvent {
+                    service: service.to_string(),
+                    event: event.to_string(),
+                    data: json!({ "id": id }),
+                })
+                .unwrap();
+        };
+        publish("other", "diagnostic", 1);
+        publish("linter", "summary", 2);
+        publish("linter", "diagnostic", 3);
+
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.event, "diagnostic");
+        assert_eq!(event.data["id"], 3);
+
+        let (response, _) = router
+            .handle_subscription(AdiSubscription::Subscribe {
+                request_id: Uuid::nil(),
+                plugin: "unknown".to_string(),
+                event: "diagnostic".to_string(),
+                filter: None,
+            })
+            .await;
+        assert!(matches!(response, AdiSubscription::Error { ref code, .. } if code == "plugin_not_found"));
+    }
+
+    #[tokio::test]
+    async fn test_delegated_access_scopes_requests() {
+        let mut router = AdiRouter::new();
+        router.register(Arc::new(TestService));
+        let access = crate::delegation::DelegatedAccess {
+            token_id: "t1".to_string(),
+            issued_by: "owner".to_string(),
+            scopes: vec![crate::delegation::Scope::parse("test:ro").unwrap()],
+            expires_at: u64::MAX,
+        };
+
+        let frame = build_frame("adi.test", "echo", b"{}");
+        let AdiRouterBinaryResult::Single(response) = router
+            .handle_binary_scoped(&AdiCallerContext::anonymous(), Some(&access), &frame)
+            .await
+        else {
+            panic!("Expected single response");
+        };
+        let header_len = u32::from_be_bytes([response[0], response[1], response[2], response[3]]) as usize;
+        let header: adi_frame::ResponseHeader = serde_json::from_slice(&response[4..4 + header_len]).unwrap();
+        let error: JsonValue = serde_json::from_slice(&response[4 + header_len..]).unwrap();
+        assert_eq!(header.status, ResponseStatus::Error);
+        assert_eq!(error["code"], FORBIDDEN);
+
+        // Read-only scopes still allow read-like methods
+        let frame = build_frame("adi.test", "count", &serde_json::to_vec(&json!({"n": 1})).unwrap());
+        assert!(matches!(
+            router.handle_binary_scoped(&AdiCallerContext::anonymous(), Some(&access), &frame).await,
+            AdiRouterBinaryResult::Stream { .. }
+        ));
+
+        let (response, receiver) = router
+            .handle_subscription_scoped(Some(&access), AdiSubscription::Subscribe {
+                request_id: Uuid::nil(),
+                plugin: "adi.other".to_string(),
+                event: "changed".to_string(),
+                filter: None,
+            })
+            .await;
+        assert!(matches!(response, AdiSubscription::Error { ref code, .. } if code == FORBIDDEN));
+        assert!(receiver.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_usage_accounting_and_quota() {
+        let mut router = AdiRouter::new();
+        router.register(Arc::new(TestService));
+        let meter = router.usage_meter();
+        meter.set_policy(Some(crate::adi_usage::QuotaPolicy {
+            window_secs: 60,
+            max_requests: Some(2),
+            max_bytes: None,
+            max_stream_secs: None,
+            on_exceed: crate::adi_usage::OverageAction::Reject,
+        }));
+        let ctx = AdiCallerContext { user_id: Some("alice".to_string()), device_id: None };
+
+        let echo = build_frame("adi.test", "echo", b"{}");
+        assert_eq!(router.admit(&ctx, None, &echo), Ok(None));
+        router.handle_binary(&ctx, &echo).await;
+
+        let count = build_frame("adi.test", "count", &serde_json::to_vec(&json!({"n": 2})).unwrap());
+        assert_eq!(router.admit(&ctx, None, &count), Ok(None));
+        let AdiRouterBinaryResult::Stream { mut receiver, .. } = router.handle_binary(&ctx, &count).await else {
+            panic!("Expected streaming response");
+        };
+        while receiver.recv().await.is_some() {}
+
+        let usage = meter.usage("alice");
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].requests, 2);
+        assert_eq!(usage[0].bytes_in, (echo.len() + count.len()) as u64);
+        assert!(usage[0].bytes_out > 0);
+
+        let Err(response) = router.admit(&ctx, None, &echo) else {
+            panic!("Expected the quota to be exceeded");
+        };
+        let header_len = u32::from_be_bytes([response[0], response[1], response[2], response[3]]) as usize;
+        let error: JsonValue = serde_json::from_slice(&response[4 + header_len..]).unwrap();
+        assert_eq!(error["code"], QUOTA_EXCEEDED);
+        // Other callers have their own budget
+        assert_eq!(router.admit(&AdiCallerContext::anonymous(), None, &echo), Ok(None));
+        // A claimed device id does not make the caller someone else
+        let spoofed = AdiCallerContext { user_id: None, device_id: Some("alice".to_string()) };
+        assert_eq!(UsageMeter::client_of(&spoofed, None), crate::adi_usage::ANONYMOUS_CLIENT);
+        // Delegated callers without a user are accounted under their token
+        let access = crate::delegation::DelegatedAccess {
+            token_id: "t1".to_string(),
+            issued_by: "alice".to_string(),
+            scopes: vec![crate::delegation::Scope::parse("test:ro").unwrap()],
+            expires_at: u64::MAX,
+        };
+        assert_eq!(UsageMeter::client_of(&AdiCallerContext::anonymous(), Some(&access)), "token:t1");
+    }
 }
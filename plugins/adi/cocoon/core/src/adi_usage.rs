@@ -0,0 +1,490 @@
+//! Per-client accounting of ADI requests.
+//!
+//! The router counts every request per client and service: requests, bytes
+//! in and out, and time spent streaming responses. The totals since start go
+//! upstream with the device heartbeat and are answered by the `adi.usage`
+//! service. A [`QuotaPolicy`] from the device config caps what one client may
+//! use per window, over all services; a client over it is throttled or
+//! refused with [`QUOTA_EXCEEDED`].
+
+use crate::adi_router::{
+    AdiCallerContext, AdiHandleResult, AdiMethodInfo, AdiService, AdiServiceError,
+};
+use crate::delegation::DelegatedAccess;
+use async_trait::async_trait;
+use bytes::Bytes;
+use lib_signaling_protocol::AdiServiceUsage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Error code for requests over the caller's quota.
+pub const QUOTA_EXCEEDED: &str = "quota_exceeded";
+
+/// Plugin ID of [`UsageService`]. Its requests are never held back, so a
+/// client over its quota can still find out why.
+pub const USAGE_PLUGIN_ID: &str = "adi.usage";
+
+/// Client name of callers with neither a verified user nor a delegated token.
+pub const ANONYMOUS_CLIENT: &str = "anonymous";
+
+/// Chunks buffered between a plugin's stream and the client
+const STREAM_BUFFER: usize = 16;
+
+fn default_window_secs() -> u64 {
+    60
+}
+
+/// What one client may use per window, summed over all services. Limits
+/// left out are not enforced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuotaPolicy {
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_requests: Option<u64>,
+    /// Request and response bytes together
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_stream_secs: Option<u64>,
+    #[serde(default)]
+    pub on_exceed: OverageAction,
+}
+
+impl QuotaPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_secs == 0 {
+            return Err("adi_quota.window_secs must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+
+    fn exceeded_by(&self, used: &WindowUsage) -> bool {
+        self.max_requests.is_some_and(|max| used.requests >= max)
+            || self.max_bytes.is_some_and(|max| used.bytes >= max)
+            || self
+                .max_stream_secs
+                .is_some_and(|max| used.stream_ms >= max.saturating_mul(1000))
+    }
+}
+
+/// What happens to requests of a client over its quota.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverageAction {
+    /// Answer with `quota_exceeded` until the window ends
+    #[default]
+    Reject,
+    /// Hold the requests until the window ends
+    Throttle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Allow,
+    /// Handle the request after waiting this long
+    Throttle(Duration),
+    Reject,
+}
+
+/// A client's usage in its current quota window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct WindowUsage {
+    pub requests: u64,
+    pub bytes: u64,
+    pub stream_ms: u64,
+}
+
+struct ClientWindow {
+    started: Instant,
+    used: WindowUsage,
+}
+
+#[derive(Default)]
+struct MeterState {
+    policy: Option<QuotaPolicy>,
+    /// Totals since start, by client and service
+    totals: BTreeMap<(String, String), AdiServiceUsage>,
+    /// Bumped by every change to `totals`
+    generation: u64,
+    windows: HashMap<String, ClientWindow>,
+}
+
+impl MeterState {
+    fn totals(&mut self, client: &str, service: &str) -> &mut AdiServiceUsage {
+        self.generation += 1;
+        self.totals
+            .entry((client.to_string(), service.to_string()))
+            .or_insert_with(|| AdiServiceUsage {
+                client: client.to_string(),
+                service: service.to_string(),
+                requests: 0,
+                bytes_in: 0,
+                bytes_out: 0,
+                stream_ms: 0,
+                throttled: 0,
+                rejected: 0,
+            })
+    }
+
+    /// `client`'s window, restarted if the policy's window has passed
+    fn window(&mut self, client: &str, now: Instant) -> &mut ClientWindow {
+        let length = self.policy.as_ref().map(QuotaPolicy::window);
+        let window = self
+            .windows
+            .entry(client.to_string())
+            .or_insert(ClientWindow {
+                started: now,
+                used: WindowUsage::default(),
+            });
+        if length.is_some_and(|length| now.duration_since(window.started) >= length) {
+            *window = ClientWindow {
+                started: now,
+                used: WindowUsage::default(),
+            };
+        }
+        window
+    }
+}
+
+/// Usage counters shared by the router, the `adi.usage` service and the
+/// heartbeat.
+#[derive(Default)]
+pub struct UsageMeter {
+    state: Mutex<MeterState>,
+}
+
+impl UsageMeter {
+    /// Name a caller is accounted under: the user signaling verified for
+    /// its session, else the delegated token it connected with. The device
+    /// id is client-supplied and never counts.
+    pub fn client_of(ctx: &AdiCallerContext, access: Option<&DelegatedAccess>) -> String {
+        match (&ctx.user_id, access) {
+            (Some(user_id), _) => user_id.clone(),
+            (None, Some(access)) => format!("token:{}", access.token_id),
+            (None, None) => ANONYMOUS_CLIENT.to_string(),
+        }
+    }
+
+    /// `adi.usage/usage` answer for `client`
+    pub fn client_usage(&self, client: String) -> Result<Bytes, AdiServiceError> {
+        let usage = ClientUsage {
+            services: self.usage(&client),
+            quota: self.policy(),
+            window: self
+                .window_usage(&client)
+                .map(|(used, resets_in)| WindowReport {
+                    used,
+                    resets_in_ms: resets_in.as_millis() as u64,
+                }),
+            client,
+        };
+        serde_json::to_vec(&usage)
+            .map(Bytes::from)
+            .map_err(|e| AdiServiceError::internal(e.to_string()))
+    }
+
+    pub fn policy(&self) -> Option<QuotaPolicy> {
+        self.state.lock().unwrap().policy.clone()
+    }
+
+    /// Replace the quota; `None` lifts it. Windows in progress keep running.
+    pub fn set_policy(&self, policy: Option<QuotaPolicy>) {
+        self.state.lock().unwrap().policy = policy;
+    }
+
+    /// Whether `client` may call `service` now. Throttled and rejected
+    /// requests are counted.
+    pub fn admit(&self, client: &str, service: &str) -> Admission {
+        self.admit_at(client, service, Instant::now())
+    }
+
+    fn admit_at(&self, client: &str, service: &str, now: Instant) -> Admission {
+        let mut state = self.state.lock().unwrap();
+        let Some(policy) = state.policy.clone() else {
+            return Admission::Allow;
+        };
+        if service == USAGE_PLUGIN_ID {
+            return Admission::Allow;
+        }
+
+        let window = state.window(client, now);
+        if !policy.exceeded_by(&window.used) {
+            return Admission::Allow;
+        }
+        let resets_in = (window.started + policy.window()).saturating_duration_since(now);
+        let totals = state.totals(client, service);
+        match policy.on_exceed {
+            OverageAction::Reject => {
+                totals.rejected += 1;
+                Admission::Reject
+            }
+            OverageAction::Throttle => {
+                totals.throttled += 1;
+                Admission::Throttle(resets_in)
+            }
+        }
+    }
+
+    pub fn record_request(&self, client: &str, service: &str, bytes_in: usize) {
+        let mut state = self.state.lock().unwrap();
+        let totals = state.totals(client, service);
+        totals.requests += 1;
+        totals.bytes_in += bytes_in as u64;
+        let used = &mut state.window(client, Instant::now()).used;
+        used.requests += 1;
+        used.bytes += bytes_in as u64;
+    }
+
+    pub fn record_response(&self, client: &str, service: &str, bytes_out: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.totals(client, service).bytes_out += bytes_out as u64;
+        state.window(client, Instant::now()).used.bytes += bytes_out as u64;
+    }
+
+    pub fn record_stream(&self, client: &str, service: &str, elapsed: Duration) {
+        let ms = elapsed.as_millis() as u64;
+        let mut state = self.state.lock().unwrap();
+        state.totals(client, service).stream_ms += ms;
+        state.window(client, Instant::now()).used.stream_ms += ms;
+    }
+
+    /// Pass a plugin's response stream through, counting its bytes and how
+    /// long it ran.
+    pub fn meter_stream(
+        self: &Arc<Self>,
+        client: String,
+        service: String,
+        mut stream: mpsc::Receiver<(Bytes, bool)>,
+    ) -> mpsc::Receiver<(Bytes, bool)> {
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        let meter = self.clone();
+        tokio::spawn(async move {
+            let started = Instant::now();
+            while let Some((data, is_final)) = stream.recv().await {
+                meter.record_response(&client, &service, data.len());
+                if tx.send((data, is_final)).await.is_err() || is_final {
+                    break;
+                }
+            }
+            meter.record_stream(&client, &service, started.elapsed());
+        });
+        rx
+    }
+
+    /// Totals of `client`, by service
+    pub fn usage(&self, client: &str) -> Vec<AdiServiceUsage> {
+        self.state
+            .lock()
+            .unwrap()
+            .totals
+            .values()
+            .filter(|usage| usage.client == client)
+            .cloned()
+            .collect()
+    }
+
+    /// All totals, by client and service, as sent with the heartbeat, and
+    /// a number that changes whenever they do
+    pub fn report(&self) -> (u64, Vec<AdiServiceUsage>) {
+        let state = self.state.lock().unwrap();
+        (state.generation, state.totals.values().cloned().collect())
+    }
+
+    /// `client`'s current window and when it ends; `None` without a quota
+    pub fn window_usage(&self, client: &str) -> Option<(WindowUsage, Duration)> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let length = state.policy.as_ref()?.window();
+        let window = state.window(client, now);
+        Some((
+            window.used,
+            (window.started + length).saturating_duration_since(now),
+        ))
+    }
+}
+
+/// Answer of `adi.usage/usage`
+#[derive(Debug, Serialize)]
+struct ClientUsage {
+    client: String,
+    services: Vec<AdiServiceUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quota: Option<QuotaPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    window: Option<WindowReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct WindowReport {
+    #[serde(flatten)]
+    used: WindowUsage,
+    resets_in_ms: u64,
+}
+
+/// `adi.usage`: lets a client see its own usage and quota.
+pub struct UsageService {
+    meter: Arc<UsageMeter>,
+}
+
+impl UsageService {
+    pub fn new(meter: Arc<UsageMeter>) -> Self {
+        Self { meter }
+    }
+}
+
+#[async_trait]
+impl AdiService for UsageService {
+    fn plugin_id(&self) -> &str {
+        USAGE_PLUGIN_ID
+    }
+
+    fn name(&self) -> &str {
+        "Usage"
+    }
+
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Requests, bytes and streaming time used by the caller, and its quota")
+    }
+
+    fn methods(&self) -> Vec<AdiMethodInfo> {
+        vec![AdiMethodInfo {
+            name: "usage".to_string(),
+            description: "The caller's usage per service since the cocoon started".to_string(),
+            skip_params_validation: Some(true),
+            ..Default::default()
+        }]
+    }
+
+    async fn handle(
+        &self,
+        ctx: &AdiCallerContext,
+        method: &str,
+        _payload: Bytes,
+    ) -> Result<AdiHandleResult, AdiServiceError> {
+        if method != "usage" {
+            return Err(AdiServiceError::method_not_found(method));
+        }
+        // The router answers sessions itself, with their delegated access;
+        // here only the context is known
+        let data = self.meter.client_usage(UsageMeter::client_of(ctx, None))?;
+        Ok(AdiHandleResult::Success(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(on_exceed: OverageAction) -> QuotaPolicy {
+        QuotaPolicy {
+            window_secs: 60,
+            max_requests: Some(2),
+            max_bytes: None,
+            max_stream_secs: None,
+            on_exceed,
+        }
+    }
+
+    #[test]
+    fn test_counts_per_client_and_service() {
+        let meter = UsageMeter::default();
+        meter.record_request("alice", "adi.tasks", 10);
+        meter.record_response("alice", "adi.tasks", 100);
+        meter.record_request("alice", "adi.tasks", 5);
+        meter.record_request("bob", "adi.llm-proxy", 7);
+        meter.record_stream("bob", "adi.llm-proxy", Duration::from_millis(1500));
+
+        let alice = meter.usage("alice");
+        assert_eq!(alice.len(), 1);
+        assert_eq!(
+            (alice[0].requests, alice[0].bytes_in, alice[0].bytes_out),
+            (2, 15, 100)
+        );
+        let (generation, report) = meter.report();
+        assert_eq!(generation, 5);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[1].client, "bob");
+        assert_eq!(report[1].stream_ms, 1500);
+    }
+
+    #[test]
+    fn test_quota_rejects_until_window_ends() {
+        let meter = UsageMeter::default();
+        meter.set_policy(Some(policy(OverageAction::Reject)));
+        let start = Instant::now();
+
+        for _ in 0..2 {
+            assert_eq!(
+                meter.admit_at("alice", "adi.tasks", start),
+                Admission::Allow
+            );
+            meter.record_request("alice", "adi.tasks", 1);
+        }
+        assert_eq!(
+            meter.admit_at("alice", "adi.tasks", start),
+            Admission::Reject
+        );
+        // Others and the usage service are not held back
+        assert_eq!(meter.admit_at("bob", "adi.tasks", start), Admission::Allow);
+        assert_eq!(
+            meter.admit_at("alice", USAGE_PLUGIN_ID, start),
+            Admission::Allow
+        );
+        assert_eq!(meter.usage("alice")[0].rejected, 1);
+
+        let later = start + Duration::from_secs(61);
+        assert_eq!(
+            meter.admit_at("alice", "adi.tasks", later),
+            Admission::Allow
+        );
+    }
+
+    #[test]
+    fn test_quota_throttles_until_window_ends() {
+        let meter = UsageMeter::default();
+        meter.set_policy(Some(QuotaPolicy {
+            max_requests: None,
+            max_bytes: Some(100),
+            ..policy(OverageAction::Throttle)
+        }));
+        meter.record_request("alice", "adi.tasks", 40);
+        meter.record_response("alice", "adi.tasks", 60);
+        let start = Instant::now();
+
+        let Admission::Throttle(delay) =
+            meter.admit_at("alice", "adi.tasks", start + Duration::from_secs(20))
+        else {
+            panic!("Expected throttling");
+        };
+        assert!(delay <= Duration::from_secs(40) && delay > Duration::from_secs(30));
+        assert_eq!(meter.usage("alice")[0].throttled, 1);
+    }
+
+    #[tokio::test]
+    async fn test_meter_stream_counts_bytes_and_time() {
+        let meter = Arc::new(UsageMeter::default());
+        let (tx, rx) = mpsc::channel(4);
+        let mut metered = meter.meter_stream("alice".to_string(), "adi.test".to_string(), rx);
+
+        tx.send((Bytes::from_static(b"abc"), false)).await.unwrap();
+        tx.send((Bytes::from_static(b"de"), true)).await.unwrap();
+        assert_eq!(metered.recv().await.unwrap().0.len(), 3);
+        assert!(metered.recv().await.unwrap().1);
+        assert!(metered.recv().await.is_none());
+
+        assert_eq!(meter.usage("alice")[0].bytes_out, 5);
+    }
+}
@@ -0,0 +1,203 @@
+//! Per-label data channel delivery settings.
+//!
+//! Terminal traffic must arrive in order, while telemetry or file chunks can
+//! tolerate reordering and loss in exchange for lower latency. The browser
+//! opens the channels, so `WebRtcManager` checks each one against the policy
+//! for its label and closes channels whose settings do not match; `to_init`
+//! gives the settings for a peer opening a channel itself.
+
+use std::collections::HashMap;
+use webrtc::data_channel::data_channel_init::RTCDataChannelInit;
+
+/// Relative scheduling priority of a channel, as in the WebRTC priority API.
+///
+/// webrtc-rs does not schedule SCTP streams by priority, so this is only
+/// recorded for peers that do; it is not validated on remote channels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChannelPriority {
+    VeryLow,
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelPolicy {
+    pub ordered: bool,
+    /// Give up on a message after this many retransmissions
+    pub max_retransmits: Option<u16>,
+    /// Give up on a message after this many milliseconds
+    pub max_packet_lifetime_ms: Option<u16>,
+    pub priority: ChannelPriority,
+}
+
+impl Default for ChannelPolicy {
+    fn default() -> Self {
+        Self::reliable()
+    }
+}
+
+impl ChannelPolicy {
+    /// Ordered, fully reliable delivery (the WebRTC default)
+    pub fn reliable() -> Self {
+        Self {
+            ordered: true,
+            max_retransmits: None,
+            max_packet_lifetime_ms: None,
+            priority: ChannelPriority::default(),
+        }
+    }
+
+    /// Unordered delivery that retransmits at most `max_retransmits` times
+    pub fn unordered(max_retransmits: Option<u16>) -> Self {
+        Self {
+            ordered: false,
+            max_retransmits,
+            max_packet_lifetime_ms: None,
+            priority: ChannelPriority::default(),
+        }
+    }
+
+    pub fn with_max_packet_lifetime(mut self, ms: u16) -> Self {
+        self.max_packet_lifetime_ms = Some(ms);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: ChannelPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// A channel may limit retransmissions or lifetime, not both.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_retransmits.is_some() && self.max_packet_lifetime_ms.is_some() {
+            return Err(
+                "max_retransmits and max_packet_lifetime are mutually exclusive".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    pub fn to_init(&self) -> RTCDataChannelInit {
+        RTCDataChannelInit {
+            ordered: Some(self.ordered),
+            max_retransmits: self.max_retransmits,
+            max_packet_life_time: self.max_packet_lifetime_ms,
+            ..Default::default()
+        }
+    }
+
+    /// Why a channel opened with these settings does not match the policy,
+    /// or `None` when it does.
+    pub fn mismatch(
+        &self,
+        ordered: bool,
+        max_retransmits: Option<u16>,
+        max_packet_lifetime_ms: Option<u16>,
+    ) -> Option<String> {
+        let mut problems = Vec::new();
+        if ordered != self.ordered {
+            problems.push(format!("ordered={} (expected {})", ordered, self.ordered));
+        }
+        if max_retransmits != self.max_retransmits {
+            problems.push(format!(
+                "max_retransmits={:?} (expected {:?})",
+                max_retransmits, self.max_retransmits
+            ));
+        }
+        if max_packet_lifetime_ms != self.max_packet_lifetime_ms {
+            problems.push(format!(
+                "max_packet_lifetime={:?} (expected {:?})",
+                max_packet_lifetime_ms, self.max_packet_lifetime_ms
+            ));
+        }
+        (!problems.is_empty()).then(|| problems.join(", "))
+    }
+}
+
+/// Policies by channel label, falling back to a default for other labels.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelPolicies {
+    default: ChannelPolicy,
+    by_label: HashMap<String, ChannelPolicy>,
+    /// Check remote channels without an explicit policy against the default
+    strict: bool,
+}
+
+impl ChannelPolicies {
+    pub fn new(default: ChannelPolicy) -> Result<Self, String> {
+        default.validate()?;
+        Ok(Self {
+            default,
+            ..Default::default()
+        })
+    }
+
+    pub fn set(&mut self, label: impl Into<String>, policy: ChannelPolicy) -> Result<(), String> {
+        let label = label.into();
+        policy
+            .validate()
+            .map_err(|e| format!("Invalid policy for channel {}: {}", label, e))?;
+        self.by_label.insert(label, policy);
+        Ok(())
+    }
+
+    /// Also validate remote channels without an explicit policy, against the
+    /// default. Otherwise those are accepted whatever their settings.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    pub fn get(&self, label: &str) -> &ChannelPolicy {
+        self.by_label.get(label).unwrap_or(&self.default)
+    }
+
+    /// Policy a remote channel must match, if any.
+    pub fn for_remote(&self, label: &str) -> Option<&ChannelPolicy> {
+        match self.by_label.get(label) {
+            Some(policy) => Some(policy),
+            None if self.strict => Some(&self.default),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_validation() {
+        let both = ChannelPolicy::unordered(Some(0)).with_max_packet_lifetime(500);
+        assert!(both.validate().is_err());
+
+        let mut policies = ChannelPolicies::default();
+        assert!(policies.set("telemetry", both).is_err());
+        policies
+            .set("telemetry", ChannelPolicy::unordered(Some(0)))
+            .unwrap();
+
+        let init = policies.get("telemetry").to_init();
+        assert_eq!(init.ordered, Some(false));
+        assert_eq!(init.max_retransmits, Some(0));
+        assert_eq!(policies.get("silk"), &ChannelPolicy::reliable());
+    }
+
+    #[test]
+    fn test_remote_mismatch() {
+        let mut policies = ChannelPolicies::default();
+        policies.set("silk", ChannelPolicy::reliable()).unwrap();
+
+        let silk = policies.for_remote("silk").unwrap();
+        assert!(silk.mismatch(true, None, None).is_none());
+        let reason = silk.mismatch(false, Some(3), None).unwrap();
+        assert!(reason.contains("ordered=false"));
+        assert!(reason.contains("max_retransmits=Some(3)"));
+
+        assert!(policies.for_remote("files").is_none());
+        let policies = policies.strict();
+        assert!(policies.for_remote("files").is_some());
+    }
+}
@@ -0,0 +1,158 @@
+//! Push a config patch to cocoons (`adi cocoon config push`).
+//!
+//! Signaling forwards the patch to the caller's online devices matching the
+//! ids or labels; each cocoon applies it as described in
+//! [`crate::device_config`] and reports back whether it did.
+
+use crate::ownership_history::resolve_device_id;
+use crate::remote_exec::{authenticate, send};
+use futures::StreamExt;
+use lib_signaling_protocol::{DeviceId, SignalingMessage};
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio_tungstenite::tungstenite::Message;
+
+/// How long signaling may take to accept the push.
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long cocoons get to report back after the push was forwarded.
+const APPLY_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone)]
+pub struct ConfigPushRequest {
+    pub signaling_url: String,
+    pub access_token: String,
+    /// Device ids, or unique prefixes of the caller's devices
+    pub devices: Vec<String>,
+    /// Tags a device must all have
+    pub labels: HashMap<String, String>,
+    /// JSON merge patch
+    pub patch: serde_json::Value,
+    /// Defaults to the current time in milliseconds, so later pushes win
+    pub version: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigApplyReport {
+    pub device_id: DeviceId,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigPushOutcome {
+    pub version: u64,
+    pub reports: Vec<ConfigApplyReport>,
+    /// Matching devices that were offline and did not get the patch
+    pub offline: Vec<String>,
+    /// Devices that got the patch but did not report back in time
+    pub unanswered: Vec<String>,
+}
+
+/// Push `request.patch` and wait for the targeted cocoons to report back.
+pub async fn run_config_push(request: ConfigPushRequest) -> Result<ConfigPushOutcome, String> {
+    if request.devices.is_empty() && request.labels.is_empty() {
+        return Err("Select devices with --device or --label".to_string());
+    }
+    let version = request.version.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0)
+    });
+
+    let (ws, _) = tokio_tungstenite::connect_async(&request.signaling_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", request.signaling_url, e))?;
+    let (mut sink, mut stream) = ws.split();
+
+    let devices = authenticate(&mut sink, &mut stream, &request.access_token).await?;
+    let device_ids = request
+        .devices
+        .iter()
+        .map(|id| resolve_device_id(&devices, id))
+        .collect::<Result<Vec<_>, _>>()?;
+    send(
+        &mut sink,
+        &SignalingMessage::DeviceConfigPush {
+            device_ids: (!device_ids.is_empty()).then_some(device_ids),
+            label_selector: (!request.labels.is_empty()).then_some(request.labels),
+            config_patch: request.patch,
+            version,
+        },
+    )
+    .await?;
+
+    let mut deadline = Instant::now() + PUSH_TIMEOUT;
+    let mut pending: Option<Vec<String>> = None;
+    let mut outcome = ConfigPushOutcome {
+        version,
+        reports: Vec::new(),
+        offline: Vec::new(),
+        unanswered: Vec::new(),
+    };
+
+    loop {
+        if pending.as_ref().is_some_and(|p| p.is_empty()) {
+            return Ok(outcome);
+        }
+        let next = match tokio::time::timeout_at(deadline.into(), stream.next()).await {
+            Ok(next) => next,
+            Err(_) => match pending {
+                // Devices that never answered are reported, not an error
+                Some(unanswered) => {
+                    outcome.unanswered = unanswered;
+                    return Ok(outcome);
+                }
+                None => return Err("Timed out waiting for the signaling server".to_string()),
+            },
+        };
+        let text = match next {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(format!("Signaling connection failed: {}", e)),
+            None => return Err("Signaling server closed the connection".to_string()),
+        };
+
+        match serde_json::from_str::<SignalingMessage>(&text) {
+            Ok(SignalingMessage::DeviceConfigPushResponse {
+                version: v,
+                sent_to,
+                offline,
+            }) if v == version && pending.is_none() => {
+                outcome.offline = offline;
+                // A fast cocoon may report before signaling's reply arrives
+                let reported = &outcome.reports;
+                pending = Some(
+                    sent_to
+                        .into_iter()
+                        .filter(|id| !reported.iter().any(|r| r.device_id == *id))
+                        .collect(),
+                );
+                deadline = Instant::now() + APPLY_TIMEOUT;
+            }
+            Ok(SignalingMessage::DeviceConfigApplied {
+                device_id,
+                version: v,
+                success,
+                error,
+            }) if v == version => {
+                if let Some(ref mut waiting) = pending {
+                    let Some(pos) = waiting.iter().position(|id| *id == device_id) else {
+                        continue;
+                    };
+                    waiting.remove(pos);
+                }
+                outcome.reports.push(ConfigApplyReport {
+                    device_id,
+                    success,
+                    error,
+                });
+            }
+            Ok(SignalingMessage::SystemError { message }) if pending.is_none() => {
+                return Err(message)
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,9 +1,21 @@
 use crate::adi_router::AdiRouter;
-use crate::silk::{AnsiToHtml, SilkSession};
-use futures::{SinkExt, StreamExt};
+use crate::delegation::DelegationValidator;
+use crate::device_config::{log_filter, CocoonConfig, ConfigApplier, DEVICE_CONFIG_PATH};
+use crate::lan::{DiscoveryIdentity, LanAccess};
+use crate::plugin_catalog::{PluginCatalog, PLUGIN_CATALOG_PATH};
+use crate::silk::{read_download, write_upload, AnsiToHtml, SilkSession};
+use futures::StreamExt;
 use crate::protocol::messages::CocoonMessage;
 use crate::protocol::types::{SilkHtmlSpan, SilkStream};
-use lib_signaling_protocol::SignalingMessage;
+use crate::registration::{
+    RegistrationCache, CONNECT_TIMEOUT, REGISTER_TIMEOUT, REGISTRATION_CACHE_PATH,
+    REGISTRATION_RETRY,
+};
+use crate::port_forward::RelayForwards;
+use crate::relay_queue::RelaySender;
+use crate::service_logs::ServiceLogRelay;
+use lib_signaling_protocol::{Capability, CapabilitySet, RelayPriority, SignalingMessage, VerifiedSender};
+use lib_silk_detect::OutputDetector;
 use portable_pty::{CommandBuilder, PtySize};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -15,8 +27,10 @@ use std::process::Stdio;
 use std::sync::Arc;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::{broadcast, Mutex};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tracing_subscriber::prelude::*;
 use uuid::Uuid;
+use lib_credential_store::{CredentialKey, CredentialStore};
 use lib_env_parse::{env_vars, env_opt, env_or};
 
 env_vars! {
@@ -26,13 +40,28 @@ env_vars! {
     CocoonSetupToken => "COCOON_SETUP_TOKEN",
     CocoonName => "COCOON_NAME",
     CocoonProtocols => "COCOON_PROTOCOLS",
+    CocoonPoolSecret => "COCOON_POOL_SECRET",
 }
 
 const OUTPUT_DIR: &str = "/cocoon/output";
 const RESPONSE_PATH: &str = "/cocoon/output/response.json";
-const SECRET_PATH: &str = "/cocoon/.secret";
+/// Sealed credential store on the cocoon volume, holding the secret
+const CREDENTIALS_DIR: &str = "/cocoon/.credentials";
+/// Plaintext secret written by earlier versions; moved into the store on start
+const LEGACY_SECRET_PATH: &str = "/cocoon/.secret";
+/// Store entry of this cocoon's own secret
+const SECRET_ENTRY: &str = "self";
 const DEVICE_ID_PATH: &str = "/cocoon/.device_id";
 
+/// Proxied bodies up to this size go back in one `proxy_result`; larger,
+/// unsized and event-stream bodies are streamed
+const PROXY_INLINE_BODY_LIMIT: u64 = 64 * 1024;
+/// How long a proxied service gets to answer with its headers
+const PROXY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How often ADI usage is reported upstream
+const HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 // Secret security requirements
 const MIN_SECRET_LENGTH: usize = 32;
 const GENERATED_SECRET_LENGTH: usize = 48; // 288 bits of entropy
@@ -89,6 +118,23 @@ enum SilkResponse {
     SessionClosed {
         session_id: Uuid,
     },
+    #[serde(rename = "silk_file_chunk")]
+    FileChunk {
+        session_id: Uuid,
+        path: String,
+        chunk: String,
+        seq: u64,
+        done: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sha256: Option<String>,
+    },
+    #[serde(rename = "silk_file_uploaded")]
+    FileUploaded {
+        session_id: Uuid,
+        path: String,
+        size: u64,
+        sha256: String,
+    },
     #[serde(rename = "silk_error")]
     Error {
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -172,6 +218,41 @@ enum CommandRequest {
     },
 
     SilkCloseSession { session_id: Uuid },
+
+    /// One base64 chunk of a file dropped into the session
+    SilkUploadFile {
+        session_id: Uuid,
+        path: String,
+        chunk: String,
+        seq: u64,
+        done: bool,
+        #[serde(default)]
+        sha256: Option<String>,
+    },
+
+    SilkDownloadFile { session_id: Uuid, path: String },
+
+    /// Follow hive service logs, throttled per service before relaying
+    StreamServiceLogs {
+        stream_id: Uuid,
+        #[serde(default)]
+        fqn: Option<String>,
+        #[serde(default)]
+        level: Option<String>,
+    },
+
+    StopServiceLogs { stream_id: Uuid },
+
+    /// Ask another cocoon of the owner for a capability, directly when a
+    /// session to it is open, else through signaling
+    CapabilityCall {
+        request_id: String,
+        capability: Capability,
+        #[serde(default)]
+        payload: JsonValue,
+        #[serde(default)]
+        prefer_device: Option<String>,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -208,10 +289,42 @@ enum CommandResponse {
 
     Error { code: String, message: String },
 
+    CapabilityResult {
+        request_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payload: Option<JsonValue>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        from_device: Option<String>,
+    },
+
     #[serde(untagged)]
     SilkResponse(SilkResponse),
 }
 
+impl CommandResponse {
+    /// Results, proxied bodies and file chunks can be large; everything else echoes a live session.
+    fn relay_priority(&self) -> RelayPriority {
+        match self {
+            Self::ExecuteResult { .. }
+            | Self::ProxyResult { .. }
+            | Self::QueryResult { .. }
+            | Self::CapabilityResult { .. }
+            | Self::SilkResponse(SilkResponse::FileChunk { .. }) => RelayPriority::Bulk,
+            _ => RelayPriority::Interactive,
+        }
+    }
+
+    fn into_sync_data(self) -> SignalingMessage {
+        let priority = Some(self.relay_priority());
+        SignalingMessage::SyncData {
+            payload: serde_json::to_value(&self).expect("CommandResponse serialization cannot fail"),
+            priority,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct ErrorInfo {
     code: String,
@@ -234,16 +347,10 @@ struct PtySession {
     writer: Box<dyn std::io::Write + Send>,
 }
 
-type SharedWriter = Arc<
-    Mutex<
-        futures::stream::SplitSink<
-            tokio_tungstenite::WebSocketStream<
-                tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
-            >,
-            Message,
-        >,
-    >,
->;
+type SharedWriter = RelaySender;
+
+type SignalingRead =
+    futures::stream::SplitStream<WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>>;
 
 async fn collect_output_files(dir: &str) -> Vec<OutputFile> {
     let mut files = Vec::new();
@@ -420,21 +527,7 @@ async fn create_pty_session(
                         data,
                     };
 
-                    let msg = SignalingMessage::SyncData {
-                        payload: serde_json::to_value(&response)
-                            .expect("CommandResponse serialization cannot fail"),
-                    };
-
-                    let writer_clone = writer.clone();
-                    tokio::spawn(async move {
-                        let mut w = writer_clone.lock().await;
-                        let _ = w
-                            .send(Message::Text(
-                                serde_json::to_string(&msg)
-                                    .expect("SignalingMessage serialization cannot fail"),
-                            ))
-                            .await;
-                    });
+                    let _ = writer.send(&response.into_sync_data());
                 }
                 Err(e) => {
                     tracing::warn!("PTY read error: {}", e);
@@ -462,6 +555,126 @@ async fn create_pty_session(
     ))
 }
 
+/// Run a Silk command in a new PTY and point the client at it.
+async fn start_silk_pty(
+    command: &str,
+    session_id: Uuid,
+    command_id: String,
+    reason: String,
+    writer: SharedWriter,
+    pty_sessions: &Mutex<HashMap<Uuid, PtySession>>,
+    silk_sessions: &Mutex<HashMap<Uuid, SilkSession>>,
+) -> CommandResponse {
+    let mut env = HashMap::new();
+    env.insert("TERM".to_string(), "xterm-256color".to_string());
+
+    match create_pty_session(command, 80, 24, &env, writer).await {
+        Ok((pty_session_id, pty_session)) => {
+            pty_sessions.lock().await.insert(pty_session_id, pty_session);
+            if let Some(s) = silk_sessions.lock().await.get_mut(&session_id) {
+                s.set_pty_session(command_id.clone(), pty_session_id);
+            }
+            CommandResponse::SilkResponse(SilkResponse::InteractiveRequired {
+                session_id,
+                command_id,
+                reason,
+                pty_session_id,
+            })
+        }
+        Err(e) => CommandResponse::SilkResponse(SilkResponse::Error {
+            session_id: Some(session_id),
+            command_id: Some(command_id),
+            code: "pty_create_failed".to_string(),
+            message: e,
+        }),
+    }
+}
+
+/// Wrap one message of a streamed proxy response for the relay.
+fn proxy_stream_data(msg: lib_tarminal_sync::SignalingMessage) -> SignalingMessage {
+    SignalingMessage::SyncData {
+        payload: serde_json::to_value(&msg).expect("SignalingMessage serialization cannot fail"),
+        priority: Some(RelayPriority::Bulk),
+    }
+}
+
+/// Take the decodable prefix of `carry`, leaving a trailing partial character
+/// for the next read; invalid bytes become U+FFFD.
+fn take_utf8(carry: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    loop {
+        match std::str::from_utf8(carry) {
+            Ok(valid) => {
+                text.push_str(valid);
+                carry.clear();
+                return text;
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                text.push_str(&String::from_utf8_lossy(&carry[..valid]));
+                match e.error_len() {
+                    Some(len) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        carry.drain(..valid + len);
+                    }
+                    None => {
+                        carry.drain(..valid);
+                        return text;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Stream an upstream body as `proxy_response_start`, chunks and `proxy_response_end`.
+async fn stream_proxy_response(
+    request_id: String,
+    status_code: u16,
+    headers: HashMap<String, String>,
+    mut response: reqwest::Response,
+    writer: &SharedWriter,
+) {
+    let mut stream = lib_tarminal_sync::ProxyResponseStream::new(request_id);
+    if writer.send(&proxy_stream_data(stream.start(status_code, headers))).is_err() {
+        return;
+    }
+
+    let mut carry = Vec::new();
+    let error = loop {
+        match response.chunk().await {
+            Ok(Some(bytes)) => {
+                carry.extend_from_slice(&bytes);
+                let text = take_utf8(&mut carry);
+                if text.is_empty() {
+                    continue;
+                }
+                if writer.send(&proxy_stream_data(stream.chunk(text))).is_err() {
+                    // Relay gone; nobody is left to read the rest
+                    return;
+                }
+            }
+            Ok(None) => break None,
+            Err(e) => {
+                tracing::warn!("Failed to read proxied response body: {}", e);
+                break Some(e.to_string());
+            }
+        }
+    };
+
+    if !carry.is_empty() {
+        let text = String::from_utf8_lossy(&carry).into_owned();
+        let _ = writer.send(&proxy_stream_data(stream.chunk(text)));
+    }
+    if let Err(e) = writer.send(&proxy_stream_data(stream.end(error))) {
+        tracing::error!("❌ Failed to end proxied response: {}", e);
+    }
+}
+
+/// Proxy a request to a local service. Small bodies come back as a
+/// `proxy_result`; anything else is streamed through `writer` and `None` is
+/// returned.
+#[allow(clippy::too_many_arguments)]
 async fn handle_proxy_request(
     request_id: String,
     service_name: String,
@@ -470,17 +683,18 @@ async fn handle_proxy_request(
     headers: HashMap<String, String>,
     body: Option<String>,
     services: &HashMap<String, u16>,
-) -> CommandResponse {
+    writer: &SharedWriter,
+) -> Option<CommandResponse> {
     let port = match services.get(&service_name) {
         Some(port) => *port,
         None => {
             tracing::warn!("Service not found: {}", service_name);
-            return CommandResponse::ProxyResult {
+            return Some(CommandResponse::ProxyResult {
                 request_id,
                 status_code: 404,
                 headers: HashMap::new(),
                 body: Some(format!("Service not found: {}", service_name)),
-            };
+            });
         }
     };
 
@@ -499,12 +713,12 @@ async fn handle_proxy_request(
         "OPTIONS" => reqwest::Method::OPTIONS,
         _ => {
             tracing::warn!("Unsupported HTTP method: {}", method);
-            return CommandResponse::ProxyResult {
+            return Some(CommandResponse::ProxyResult {
                 request_id,
                 status_code: 405,
                 headers: HashMap::new(),
                 body: Some(format!("Unsupported method: {}", method)),
-            };
+            });
         }
     };
 
@@ -518,12 +732,9 @@ async fn handle_proxy_request(
         request_builder = request_builder.body(body_str);
     }
 
-    match request_builder
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .await
-    {
-        Ok(response) => {
+    // Bounds the wait for headers only; streamed bodies may stay open
+    match tokio::time::timeout(PROXY_TIMEOUT, request_builder.send()).await {
+        Ok(Ok(response)) => {
             let status_code = response.status().as_u16();
             let mut response_headers = HashMap::new();
 
@@ -533,29 +744,55 @@ async fn handle_proxy_request(
                 }
             }
 
-            let response_body = match response.text().await {
-                Ok(text) => Some(text),
-                Err(e) => {
+            let event_stream = response_headers
+                .get("content-type")
+                .is_some_and(|ct| ct.starts_with("text/event-stream"));
+            let inline = !event_stream
+                && response
+                    .content_length()
+                    .is_some_and(|len| len <= PROXY_INLINE_BODY_LIMIT);
+            if !inline {
+                stream_proxy_response(request_id, status_code, response_headers, response, writer)
+                    .await;
+                return None;
+            }
+
+            let response_body = match tokio::time::timeout(PROXY_TIMEOUT, response.text()).await {
+                Ok(Ok(text)) => Some(text),
+                Ok(Err(e)) => {
                     tracing::warn!("Failed to read response body: {}", e);
                     None
                 }
+                Err(_) => {
+                    tracing::warn!("Timed out reading response body");
+                    None
+                }
             };
 
-            CommandResponse::ProxyResult {
+            Some(CommandResponse::ProxyResult {
                 request_id,
                 status_code,
                 headers: response_headers,
                 body: response_body,
-            }
+            })
         }
-        Err(e) => {
+        Ok(Err(e)) => {
             tracing::error!("HTTP proxy request failed: {}", e);
-            CommandResponse::ProxyResult {
+            Some(CommandResponse::ProxyResult {
                 request_id,
                 status_code: 502,
                 headers: HashMap::new(),
                 body: Some(format!("Proxy error: {}", e)),
-            }
+            })
+        }
+        Err(_) => {
+            tracing::error!("HTTP proxy request to {} timed out", url);
+            Some(CommandResponse::ProxyResult {
+                request_id,
+                status_code: 504,
+                headers: HashMap::new(),
+                body: Some(format!("Proxy timeout after {}s", PROXY_TIMEOUT.as_secs())),
+            })
         }
     }
 }
@@ -709,21 +946,70 @@ async fn save_device_id(device_id: &str) {
 
 async fn send_deregister(writer: &SharedWriter, device_id: &str, reason: Option<&str>) {
     let deregister_msg = SignalingMessage::DeviceDeregister {
-        device_id: device_id.to_string(),
+        device_id: device_id.into(),
         reason: reason.map(|r| r.to_string()),
     };
 
-    let mut w = writer.lock().await;
-    if let Err(e) = w
-        .send(Message::Text(
-            serde_json::to_string(&deregister_msg)
-                .expect("SignalingMessage serialization cannot fail"),
-        ))
-        .await
-    {
+    if let Err(e) = writer.send(&deregister_msg) {
         tracing::warn!("⚠️ Failed to send deregister message: {}", e);
-    } else {
-        tracing::info!("📤 Sent deregister message to server");
+        return;
+    }
+    writer.flush().await;
+    tracing::info!("📤 Sent deregister message to server");
+}
+
+/// Best-effort notification on the local daemon bus; cocoons in containers
+/// usually have no daemon socket, so failures are only logged at debug.
+async fn publish_daemon_event(topic: &str, payload: serde_json::Value) {
+    let client = lib_daemon_client::DaemonClient::new().with_timeout(std::time::Duration::from_secs(1));
+    if !client.socket_exists() {
+        return;
+    }
+    let event = lib_daemon_client::DaemonEvent::new(topic, "adi.cocoon", payload.to_string());
+    if let Err(e) = client.publish(event).await {
+        tracing::debug!("Failed to publish {} to daemon: {}", topic, e);
+    }
+}
+
+fn credential_store() -> Option<CredentialStore> {
+    CredentialStore::open(CREDENTIALS_DIR)
+        .map_err(|e| tracing::warn!("⚠️ Credential store unavailable at {}: {}", CREDENTIALS_DIR, e))
+        .ok()
+}
+
+/// Secret kept in the credential store. A plaintext secret left by an older
+/// version is moved into the store first.
+async fn load_secret() -> Option<String> {
+    let store = credential_store()?;
+    if let Ok(legacy) = tokio::fs::read_to_string(LEGACY_SECRET_PATH).await {
+        match store.set_cocoon_secret(SECRET_ENTRY, legacy.trim()) {
+            Ok(()) => {
+                let _ = tokio::fs::remove_file(LEGACY_SECRET_PATH).await;
+                tracing::info!("🔐 Moved secret from {} into the credential store", LEGACY_SECRET_PATH);
+            }
+            Err(e) => tracing::warn!("⚠️ Could not move secret into the credential store: {}", e),
+        }
+    }
+    match store.cocoon_secret(SECRET_ENTRY) {
+        Ok(secret) => secret,
+        Err(e) => {
+            tracing::warn!("⚠️ Could not read secret from the credential store: {}", e);
+            None
+        }
+    }
+}
+
+/// Persist the secret; may fail in read-only containers
+fn save_secret(secret: &str) -> Result<(), String> {
+    credential_store()
+        .ok_or_else(|| "credential store unavailable".to_string())?
+        .set_cocoon_secret(SECRET_ENTRY, secret)
+        .map_err(|e| e.to_string())
+}
+
+fn forget_secret() {
+    if let Some(store) = credential_store() {
+        let _ = store.remove(CredentialKey::CocoonSecret(SECRET_ENTRY));
     }
 }
 
@@ -747,22 +1033,24 @@ async fn get_or_create_secret() -> Result<(String, Option<String>), Box<dyn std:
         return Ok((secret, device_id));
     }
 
-    match tokio::fs::read_to_string(SECRET_PATH).await {
-        Ok(secret) => {
-            let secret = secret.trim().to_string();
-
-            if let Err(e) = validate_secret(&secret) {
-                tracing::error!("❌ Invalid secret from {}: {}", SECRET_PATH, e);
-                tracing::error!("💡 Deleting weak secret and generating new one");
-                let _ = tokio::fs::remove_file(SECRET_PATH).await;
-                // Also delete device_id since secret changed
-                let _ = tokio::fs::remove_file(DEVICE_ID_PATH).await;
-            } else {
-                tracing::info!("🔑 Loaded existing secret from {}", SECRET_PATH);
-                return Ok((secret, device_id));
-            }
+    if let Some(secret) = load_secret().await {
+        if let Err(e) = validate_secret(&secret) {
+            tracing::error!("❌ Invalid stored secret: {}", e);
+            tracing::error!("💡 Deleting weak secret and generating new one");
+            forget_secret();
+            // Also delete device_id since secret changed
+            let _ = tokio::fs::remove_file(DEVICE_ID_PATH).await;
+            let _ = tokio::fs::remove_file(REGISTRATION_CACHE_PATH).await;
+        } else {
+            tracing::info!("🔑 Loaded existing secret from the credential store");
+            return Ok((secret, device_id));
         }
-        Err(_) => {}
+    }
+
+    // A warm pool cocoon keeps its hive's secret until it is handed over
+    if let Some(secret) = env_opt(EnvVar::CocoonPoolSecret.as_str()) {
+        tracing::info!("🏊 Waiting in a warm pool until a spawn claims this cocoon");
+        return Ok((secret, None));
     }
 
     let secret = generate_strong_secret();
@@ -773,53 +1061,312 @@ async fn get_or_create_secret() -> Result<(String, Option<String>), Box<dyn std:
     );
 
     // Try to save it (may fail in read-only containers, that's ok)
-    if let Err(e) = tokio::fs::write(SECRET_PATH, &secret).await {
-        tracing::warn!(
-            "⚠️ Could not save secret to {} (ephemeral session): {}",
-            SECRET_PATH,
-            e
-        );
+    if let Err(e) = save_secret(&secret) {
+        tracing::warn!("⚠️ Could not save secret (ephemeral session): {}", e);
         tracing::warn!(
             "💡 Set COCOON_SECRET env var or mount volume at /cocoon for persistent sessions"
         );
     } else {
-        tracing::info!("💾 Saved secret to {} for persistent sessions", SECRET_PATH);
+        tracing::info!("💾 Saved secret to the credential store for persistent sessions");
     }
 
     // New secret means no device_id yet (first registration)
     Ok((secret, None))
 }
 
+/// Everything needed to (re)register with the signaling server. Registration
+/// state is cached on disk so it survives restarts while offline.
+struct Registrar {
+    signaling_url: String,
+    /// Replaced when a hive hands this warm pool cocoon over
+    secret: Mutex<String>,
+    version: String,
+    name: Option<String>,
+    device_config: Option<JsonValue>,
+    cache: Mutex<RegistrationCache>,
+    current_device_id: Arc<Mutex<Option<String>>>,
+    /// ADI plugins served, announced after every registration
+    capabilities: CapabilitySet,
+    /// Direct sessions to other cocoons, kept while the device id is
+    peer_link: Arc<crate::peer_link::PeerLink>,
+}
+
+impl Registrar {
+    async fn register_message(&self) -> SignalingMessage {
+        let cache = self.cache.lock().await;
+        let mut tags = HashMap::new();
+        if let Some(token) = cache.next_claim() {
+            tags.insert("setup_token".to_string(), token.to_string());
+        }
+        if let Some(ref name) = self.name {
+            tags.insert("name".to_string(), name.clone());
+        }
+
+        SignalingMessage::DeviceRegister {
+            secret: self.secret.lock().await.clone(),
+            device_id: cache.device_id.clone().map(Into::into),
+            version: self.version.clone(),
+            tags: if tags.is_empty() { None } else { Some(tags) },
+            device_type: Some("cocoon".to_string()),
+            device_config: self.device_config.clone(),
+        }
+    }
+
+    /// Persist a confirmed registration.
+    async fn record(&self, device_id: &str, tags: Option<&HashMap<String, String>>) {
+        save_device_id(device_id).await;
+
+        let mut cache = self.cache.lock().await;
+        cache.record_registration(device_id, tags);
+        if let Err(e) = cache.save(REGISTRATION_CACHE_PATH).await {
+            tracing::debug!(
+                "Could not save registration cache to {}: {}",
+                REGISTRATION_CACHE_PATH,
+                e
+            );
+        }
+        drop(cache);
+
+        *self.current_device_id.lock().await = Some(device_id.to_string());
+        self.peer_link.registered(device_id).await;
+    }
+
+    /// Take over the identity a hive hands this warm pool cocoon: a new
+    /// secret, and the setup token to claim it with on the next registration.
+    /// Refused once owned, so only an unclaimed cocoon can be re-keyed.
+    async fn rekey(&self, secret: String, setup_token: &str) -> Result<(), String> {
+        validate_secret(&secret)?;
+        let mut cache = self.cache.lock().await;
+        if cache.owner_id.is_some() {
+            return Err("cocoon is already owned".to_string());
+        }
+        cache.set_device_id(None);
+        cache.add_pending_claim(setup_token);
+        if let Err(e) = cache.save(REGISTRATION_CACHE_PATH).await {
+            tracing::debug!(
+                "Could not save registration cache to {}: {}",
+                REGISTRATION_CACHE_PATH,
+                e
+            );
+        }
+        drop(cache);
+
+        // Wins over COCOON_POOL_SECRET on restart
+        if let Err(e) = save_secret(&secret) {
+            tracing::warn!("⚠️ Could not save secret: {}", e);
+        }
+        let _ = tokio::fs::remove_file(DEVICE_ID_PATH).await;
+        *self.secret.lock().await = secret;
+        Ok(())
+    }
+
+    /// Connect and register, retrying with backoff until it succeeds.
+    async fn connect(&self, writer: &SharedWriter) -> SignalingRead {
+        let mut backoff = REGISTRATION_RETRY.backoff();
+        loop {
+            match self.try_connect(writer).await {
+                Ok(read) => return read,
+                Err(e) => {
+                    writer.detach();
+                    // The policy has no limits, so there is always a next delay
+                    let delay = backoff.next_delay().unwrap_or(REGISTRATION_RETRY.max_delay);
+                    tracing::warn!(
+                        "⚠️ {} (retrying in {}s, running offline)",
+                        e,
+                        delay.as_secs()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    async fn try_connect(&self, writer: &SharedWriter) -> Result<SignalingRead, String> {
+        tracing::info!("🔗 Connecting to signaling server: {}", self.signaling_url);
+
+        let (ws_stream, _) =
+            tokio::time::timeout(CONNECT_TIMEOUT, connect_async(&self.signaling_url))
+                .await
+                .map_err(|_| "Timed out connecting to signaling server".to_string())?
+                .map_err(|e| format!("Failed to connect to signaling server: {}", e))?;
+
+        let (write, mut read) = ws_stream.split();
+        writer.attach(write);
+
+        // Send DeviceRegister immediately (cocoon endpoint skips auth)
+        tracing::info!("⏳ Registering with signaling server...");
+        writer
+            .send(&self.register_message().await)
+            .map_err(|e| format!("Failed to send register: {}", e))?;
+
+        tokio::time::timeout(REGISTER_TIMEOUT, self.await_registration(writer, &mut read))
+            .await
+            .map_err(|_| "Timed out waiting for registration".to_string())??;
+        Ok(read)
+    }
+    /// Wait for `DeviceRegisterResponse` and record it.
+    async fn await_registration(
+        &self,
+        writer: &SharedWriter,
+        read: &mut SignalingRead,
+    ) -> Result<(), String> {
+        while let Some(Ok(msg)) = read.next().await {
+            let text = match msg {
+                Message::Text(t) => t,
+                Message::Close(_) => return Err("Connection closed during registration".into()),
+                _ => continue,
+            };
+            let parsed: SignalingMessage = match serde_json::from_str(&text) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            let retrieve_queued = lib_signaling_protocol::retrieve_after_register(&parsed);
+            match parsed {
+                SignalingMessage::DeviceRegisterResponse { device_id: assigned_id, tags } => {
+                    tracing::info!("✅ Registration confirmed");
+                    tracing::info!("🆔 Device ID: {}", assigned_id);
+
+                    if let Some(ref t) = tags {
+                        if let Some(owner_id) = t.get("owner_id") {
+                            tracing::info!("👤 Owner: {}", owner_id);
+                            if let Some(name) = t.get("name") {
+                                tracing::info!("📛 Name: {}", name);
+                            }
+                            tracing::info!("🎉 Cocoon is ready and claimed by your account!");
+                        }
+                    }
+
+                    self.record(&assigned_id, tags.as_ref()).await;
+                    // Replays sync_data the server held while we were offline
+                    if let Some(retrieve) = retrieve_queued {
+                        writer
+                            .send(&retrieve)
+                            .map_err(|e| format!("Failed to request queued data: {}", e))?;
+                    }
+                    writer
+                        .send(&self.capabilities.to_update_message())
+                        .map_err(|e| format!("Failed to announce capabilities: {}", e))?;
+                    publish_daemon_event(
+                        lib_daemon_client::events::topics::COCOON_CONNECTED,
+                        serde_json::json!({ "device_id": assigned_id }),
+                    )
+                    .await;
+                    return Ok(());
+                }
+                SignalingMessage::SystemError { message } => {
+                    tracing::error!("❌ Server error during registration: {}", message);
+                    return Err(format!("Server error: {}", message));
+                }
+                _ => continue,
+            }
+        }
+
+        Err("Connection closed before registration completed".into())
+    }
+}
+
+/// Where a WebRTC signaling message came from
+enum WebrtcOrigin<'a> {
+    /// Relayed by signaling, with the sender it verified for app messages
+    Signaling {
+        sender: Option<VerifiedSender>,
+        delegation: &'a DelegationValidator,
+    },
+    /// A direct LAN client, which presented the LAN token
+    Lan,
+}
+
+/// User id of a sender signaling verified as the device's owner
+fn verified_owner(sender: Option<&VerifiedSender>) -> Option<&str> {
+    sender
+        .filter(|s| s.grant.is_none())
+        .and_then(|s| s.user_id.as_deref())
+}
+
+/// Sender signaling stamped on a relayed app payload
+fn relayed_sender(payload: &JsonValue) -> Option<VerifiedSender> {
+    serde_json::from_value(payload.get("sender")?.clone()).ok()
+}
+
+/// Device and message of a payload another cocoon of the owner sent;
+/// signaling delivers those as `{ "from": <device_id>, "data": <message> }`
+fn cocoon_envelope(payload: &JsonValue) -> Option<(String, JsonValue)> {
+    let envelope = payload.as_object().filter(|o| o.len() == 2)?;
+    let from = envelope.get("from")?.as_str()?;
+    Some((from.to_string(), envelope.get("data")?.clone()))
+}
+
 async fn handle_cocoon_webrtc(
     msg: CocoonMessage,
     webrtc: Arc<crate::webrtc::WebRtcManager>,
+    origin: WebrtcOrigin<'_>,
     writer: SharedWriter,
 ) {
     async fn send_cocoon_msg(writer: &SharedWriter, msg: &CocoonMessage) {
         let sync_msg = SignalingMessage::SyncData {
             payload: serde_json::to_value(msg).expect("CocoonMessage serialization cannot fail"),
+            priority: Some(RelayPriority::Interactive),
         };
-        let mut w = writer.lock().await;
-        let _ = w
-            .send(Message::Text(
-                serde_json::to_string(&sync_msg).expect("SignalingMessage serialization cannot fail"),
-            ))
-            .await;
+        let _ = writer.send(&sync_msg);
     }
 
     match msg {
         CocoonMessage::WebrtcStartSession {
             session_id,
             device_id: client_id,
-            user_id,
             data_channels,
+            delegated_token,
+            // Client-supplied; only the identity signaling verified counts
+            user_id: _,
         } => {
+            let verified = match &origin {
+                WebrtcOrigin::Signaling { sender, .. } => sender.as_ref().and_then(|s| s.user_id.clone()),
+                WebrtcOrigin::Lan => None,
+            };
             if let Some(ref channels) = data_channels {
-                tracing::info!("🎥 WebRTC session request from {}: {} (user_id={:?}, data_channels={:?})", client_id, session_id, user_id, channels);
+                tracing::info!("🎥 WebRTC session request from {}: {} (user_id={:?}, data_channels={:?})", client_id, session_id, verified, channels);
             } else {
-                tracing::info!("🎥 WebRTC session request from {}: {} (user_id={:?})", client_id, session_id, user_id);
+                tracing::info!("🎥 WebRTC session request from {}: {} (user_id={:?})", client_id, session_id, verified);
             }
-            match webrtc.create_session(session_id.clone(), user_id).await {
+
+            let access = match (origin, delegated_token) {
+                (WebrtcOrigin::Signaling { delegation, .. }, Some(token)) => match delegation.validate(&token).await {
+                    Ok(access) => {
+                        tracing::info!("🔑 Session {} uses delegated token {} from {}", session_id, access.token_id, access.issued_by);
+                        Some(access)
+                    }
+                    Err(e) => {
+                        tracing::warn!("🚫 Rejected delegated token for session {}: {}", session_id, e);
+                        send_cocoon_msg(&writer, &CocoonMessage::WebrtcError {
+                            session_id,
+                            code: "unauthorized".to_string(),
+                            message: e,
+                        }).await;
+                        return;
+                    }
+                },
+                (WebrtcOrigin::Signaling { sender, .. }, None) if verified_owner(sender.as_ref()).is_none() => {
+                    tracing::warn!("🚫 Rejected session {} without a delegated token from a non-owner", session_id);
+                    send_cocoon_msg(&writer, &CocoonMessage::WebrtcError {
+                        session_id,
+                        code: "unauthorized".to_string(),
+                        message: "Only the owner can connect without a delegated token".to_string(),
+                    }).await;
+                    return;
+                }
+                (WebrtcOrigin::Lan, Some(_)) => {
+                    send_cocoon_msg(&writer, &CocoonMessage::WebrtcError {
+                        session_id,
+                        code: "unauthorized".to_string(),
+                        message: "Delegated tokens are only accepted through signaling".to_string(),
+                    }).await;
+                    return;
+                }
+                _ => None,
+            };
+            let user_id = verified;
+
+            match webrtc.create_session_with_access(session_id.clone(), user_id, access).await {
                 Ok(()) => {
                     tracing::info!("✅ WebRTC session {} created", session_id);
                 }
@@ -834,6 +1381,53 @@ async fn handle_cocoon_webrtc(
             }
         }
 
+        CocoonMessage::WebrtcPrewarm {
+            session_id,
+            client_id,
+            user_id: _,
+        } => {
+            // Warm sessions carry no scopes, so only owners get them
+            let user_id = match &origin {
+                WebrtcOrigin::Signaling { sender, .. } => match verified_owner(sender.as_ref()) {
+                    Some(owner) => Some(owner.to_string()),
+                    None => {
+                        send_cocoon_msg(&writer, &CocoonMessage::WebrtcError {
+                            session_id,
+                            code: "unauthorized".to_string(),
+                            message: "Only the owner can prewarm sessions".to_string(),
+                        }).await;
+                        return;
+                    }
+                },
+                WebrtcOrigin::Lan => None,
+            };
+            tracing::info!("🔥 WebRTC prewarm request from {}: {} (user_id={:?})", client_id, session_id, user_id);
+            if let Err(e) = webrtc.prewarm_session(session_id.clone(), client_id, user_id).await {
+                tracing::error!("❌ Failed to prewarm WebRTC session: {}", e);
+                send_cocoon_msg(&writer, &CocoonMessage::WebrtcError {
+                    session_id,
+                    code: "session_create_failed".to_string(),
+                    message: e,
+                }).await;
+            }
+        }
+
+        CocoonMessage::WebrtcClaim { session_id } => {
+            match webrtc.claim_session(&session_id).await {
+                Ok(()) => {
+                    send_cocoon_msg(&writer, &CocoonMessage::WebrtcClaimed { session_id }).await;
+                }
+                Err(e) => {
+                    tracing::warn!("⚠️ Failed to claim warm WebRTC session: {}", e);
+                    send_cocoon_msg(&writer, &CocoonMessage::WebrtcError {
+                        session_id,
+                        code: "claim_failed".to_string(),
+                        message: e,
+                    }).await;
+                }
+            }
+        }
+
         CocoonMessage::WebrtcOffer { session_id, sdp } => {
             tracing::info!("📥 WebRTC offer received for session {}", session_id);
             match webrtc.handle_offer(&session_id, &sdp).await {
@@ -886,13 +1480,14 @@ async fn handle_cocoon_webrtc(
         }
 
         CocoonMessage::WebrtcData {
-            session_id: _,
+            session_id,
             channel,
             data,
             binary,
         } => {
             tracing::debug!("📦 WebRTC data received: {} bytes on channel {}", data.len(), channel);
             match channel.as_str() {
+                "adi" => webrtc.handle_relayed_adi(&session_id, &data, binary).await,
                 "terminal" => tracing::debug!("Terminal data: {}", data),
                 "file-transfer" => tracing::debug!("File transfer data: {} bytes, binary: {}", data.len(), binary),
                 _ => tracing::debug!("Unknown channel: {}", channel),
@@ -910,12 +1505,121 @@ async fn handle_cocoon_webrtc(
     }
 }
 
+/// Accept direct WebSocket connections from clients on the same network
+async fn serve_lan(
+    listener: tokio::net::TcpListener,
+    lan: Arc<LanAccess>,
+    adi_router: Arc<Mutex<AdiRouter>>,
+    device_id: Arc<Mutex<Option<String>>>,
+) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!("⚠️ LAN accept failed: {}", e);
+                continue;
+            }
+        };
+
+        let lan = lan.clone();
+        let adi_router = adi_router.clone();
+        let device_id = device_id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_lan_connection(stream, lan, adi_router, device_id).await {
+                tracing::warn!("⚠️ LAN connection from {} closed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// One direct client: `lan_hello` handshake, then WebRTC signaling exactly as
+/// it would arrive through the relay. Sessions negotiated here belong to this
+/// socket and are closed with it.
+async fn handle_lan_connection(
+    stream: tokio::net::TcpStream,
+    lan: Arc<LanAccess>,
+    adi_router: Arc<Mutex<AdiRouter>>,
+    device_id: Arc<Mutex<Option<String>>>,
+) -> Result<(), String> {
+    fn parse_frame(msg: Message) -> Option<CocoonMessage> {
+        let Message::Text(text) = msg else { return None };
+        match serde_json::from_str(&text) {
+            Ok(SignalingMessage::SyncData { payload, .. }) => serde_json::from_value(payload).ok(),
+            _ => None,
+        }
+    }
+
+    fn sync(msg: &CocoonMessage) -> SignalingMessage {
+        SignalingMessage::SyncData {
+            payload: serde_json::to_value(msg).expect("CocoonMessage serialization cannot fail"),
+            priority: Some(RelayPriority::Interactive),
+        }
+    }
+
+    let ws = tokio_tungstenite::accept_async(stream)
+        .await
+        .map_err(|e| format!("handshake failed: {}", e))?;
+    let (sink, mut read) = ws.split();
+
+    let hello = tokio::time::timeout(crate::lan::HELLO_TIMEOUT, read.next())
+        .await
+        .map_err(|_| "no lan_hello".to_string())?;
+    match hello.and_then(|m| m.ok()).and_then(parse_frame) {
+        Some(CocoonMessage::LanHello { token }) if lan.verify(&token) => {}
+        _ => return Err("rejected: invalid LAN token".to_string()),
+    }
+
+    let writer = RelaySender::spawn(sink);
+    let device_id = device_id.lock().await.clone().unwrap_or_default();
+    let _ = writer.send(&sync(&CocoonMessage::LanWelcome { device_id }));
+    tracing::info!("🏠 Direct LAN client connected");
+
+    let (signaling_tx, mut signaling_rx) =
+        tokio::sync::mpsc::unbounded_channel::<SignalingMessage>();
+    let webrtc = Arc::new(crate::webrtc::WebRtcManager::with_adi_router(
+        signaling_tx,
+        adi_router,
+    ));
+    let writer_for_signaling = writer.clone();
+    tokio::spawn(async move {
+        while let Some(msg) = signaling_rx.recv().await {
+            let _ = writer_for_signaling.send(&msg);
+        }
+    });
+
+    let mut sweep = tokio::time::interval(std::time::Duration::from_secs(30));
+    loop {
+        tokio::select! {
+            _ = sweep.tick() => {
+                webrtc.expire_warm_sessions(crate::webrtc::WARM_SESSION_TTL).await;
+            }
+            frame = read.next() => {
+                let Some(Ok(frame)) = frame else { break };
+                match parse_frame(frame) {
+                    Some(CocoonMessage::LanPing { sent_at }) => {
+                        let _ = writer.send(&sync(&CocoonMessage::LanPong { sent_at }));
+                    }
+                    Some(msg) => handle_cocoon_webrtc(msg, webrtc.clone(), WebrtcOrigin::Lan, writer.clone()).await,
+                    None => tracing::debug!("📨 Ignoring unrecognized LAN frame"),
+                }
+            }
+        }
+    }
+
+    for session_id in webrtc.list_sessions().await {
+        let _ = webrtc.close_session(&session_id).await;
+    }
+    tracing::info!("🏠 Direct LAN client disconnected");
+    Ok(())
+}
+
 pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("cocoon=info".parse().expect("valid tracing directive")),
-        )
+    // Reloadable so a pushed `log_level` takes effect without a restart
+    let (log_filter, log_reload) =
+        tracing_subscriber::reload::Layer::new(log_filter(&CocoonConfig::default()));
+    let _ = tracing_subscriber::registry()
+        .with(log_filter)
+        .with(tracing_subscriber::fmt::layer())
         .try_init();
 
     tracing::info!("🐛 Cocoon starting (v{})", env!("CARGO_PKG_VERSION"));
@@ -929,26 +1633,33 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
         format!("{}?kind=cocoon", base_url)
     };
 
-    tracing::info!("🔗 Connecting to signaling server: {}", signaling_url);
+    // Attached once connected; local services start without waiting for the network
+    let writer = RelaySender::detached();
+    #[cfg(feature = "chaos")]
+    if let Some(profile) = crate::relay_chaos::ChaosProfile::from_env() {
+        tracing::warn!("🐒 Relay chaos enabled: {:?}", profile);
+        writer.set_chaos(Some(profile));
+    }
 
-    let (ws_stream, _) = match connect_async(&signaling_url).await {
-        Ok(conn) => conn,
-        Err(e) => {
-            tracing::error!("❌ Failed to connect to signaling server: {}", e);
-            return Err(format!("Failed to connect to signaling server: {}", e).into());
+    let sub_relay = match crate::sub_relay::relay_listen_addr() {
+        Some(addr) => {
+            let listener = tokio::net::TcpListener::bind(&addr).await?;
+            tracing::info!("🔀 Relaying for downstream devices on ws://{}", addr);
+            let relay = crate::sub_relay::SubRelay::new(writer.clone());
+            tokio::spawn(relay.clone().serve(listener));
+            Some(relay)
         }
+        None => None,
     };
 
-    let (write, mut read) = ws_stream.split();
-    let writer = Arc::new(Mutex::new(write));
-
     let pty_sessions: Arc<Mutex<HashMap<Uuid, PtySession>>> = Arc::new(Mutex::new(HashMap::new()));
 
     let silk_sessions: Arc<Mutex<HashMap<Uuid, SilkSession>>> =
         Arc::new(Mutex::new(HashMap::new()));
 
     let adi_router = {
-        let mut router = AdiRouter::new();
+        let catalog = PluginCatalog::load(PLUGIN_CATALOG_PATH, device_id.clone()).await;
+        let mut router = AdiRouter::with_catalog(catalog);
 
         #[cfg(feature = "tasks-core")]
         {
@@ -1001,6 +1712,26 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
+        #[cfg(feature = "linter-core")]
+        if let Some(root) = crate::live_lint::lint_root() {
+            match crate::live_lint::start(&mut router, root.clone()) {
+                Ok(()) => tracing::info!("🔍 Streaming lint diagnostics for {}", root.display()),
+                Err(e) => tracing::warn!("⚠️ Failed to start live linting: {}", e),
+            }
+        }
+
+        router.register(std::sync::Arc::new(crate::adi_usage::UsageService::new(
+            router.usage_meter(),
+        )));
+
+        let diff = router.reconcile();
+        if !diff.removed.is_empty() {
+            tracing::info!("📦 ADI plugins gone since last run: {}", diff.removed.join(", "));
+        }
+        if let Err(e) = router.catalog().save(PLUGIN_CATALOG_PATH).await {
+            tracing::debug!("Failed to save plugin catalogue: {}", e);
+        }
+
         router
     };
 
@@ -1010,9 +1741,32 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
         .map(|s| format!("{}:{}", s.id, s.version))
         .collect();
 
+    // The plugin set is fixed once loaded, so every announcement is version 1
+    let capabilities = CapabilitySet::new(
+        adi_router.list_plugins().into_iter().map(|s| Capability {
+            protocol: s.id,
+            version: s.version,
+        }),
+        1,
+    );
+
+    let usage_meter = adi_router.usage_meter();
     let adi_router = Arc::new(Mutex::new(adi_router));
+    let adi_router_for_lan = adi_router.clone();
+    let peer_link = crate::peer_link::PeerLink::new(writer.clone(), adi_router.clone());
+
+    // Signaling from other cocoons, handled in arrival order like webrtc_msg_tx
+    let (peer_signal_tx, mut peer_signal_rx) =
+        tokio::sync::mpsc::unbounded_channel::<(String, CocoonMessage)>();
+    let peer_link_for_signals = peer_link.clone();
+    tokio::spawn(async move {
+        while let Some((from, msg)) = peer_signal_rx.recv().await {
+            peer_link_for_signals.handle_signal(&from, msg).await;
+        }
+    });
 
     let (webrtc_tx, mut webrtc_rx) = tokio::sync::mpsc::unbounded_channel::<SignalingMessage>();
+    let delegation = Arc::new(DelegationValidator::new(webrtc_tx.clone()));
 
     let webrtc_manager = Arc::new(crate::webrtc::WebRtcManager::with_adi_router(
         webrtc_tx,
@@ -1022,27 +1776,54 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let writer_for_webrtc = writer.clone();
     tokio::spawn(async move {
         while let Some(msg) = webrtc_rx.recv().await {
-            let mut w = writer_for_webrtc.lock().await;
-            if let Err(e) = w
-                .send(Message::Text(
-                    serde_json::to_string(&msg).unwrap_or_default(),
-                ))
-                .await
-            {
+            if let Err(e) = writer_for_webrtc.send(&msg) {
                 tracing::warn!("⚠️ Failed to send WebRTC signaling message: {}", e);
             }
         }
     });
 
+    // Close prewarmed sessions nobody claimed
+    let webrtc_manager_for_sweep = webrtc_manager.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            webrtc_manager_for_sweep
+                .expire_warm_sessions(crate::webrtc::WARM_SESSION_TTL)
+                .await;
+        }
+    });
+
     // Serialized WebRTC message channel — processes signaling messages one at a time
     // so create_session() always completes before handle_offer() runs for the same session.
     let (webrtc_msg_tx, mut webrtc_msg_rx) =
-        tokio::sync::mpsc::unbounded_channel::<CocoonMessage>();
+        tokio::sync::mpsc::unbounded_channel::<(CocoonMessage, Option<VerifiedSender>)>();
     let webrtc_manager_for_task = webrtc_manager.clone();
+    let delegation_for_task = delegation.clone();
     let writer_for_webrtc_msgs = writer.clone();
     tokio::spawn(async move {
-        while let Some(msg) = webrtc_msg_rx.recv().await {
-            handle_cocoon_webrtc(msg, webrtc_manager_for_task.clone(), writer_for_webrtc_msgs.clone()).await;
+        while let Some((msg, sender)) = webrtc_msg_rx.recv().await {
+            handle_cocoon_webrtc(
+                msg,
+                webrtc_manager_for_task.clone(),
+                WebrtcOrigin::Signaling {
+                    sender,
+                    delegation: &delegation_for_task,
+                },
+                writer_for_webrtc_msgs.clone(),
+            )
+            .await;
+        }
+    });
+
+    // Relayed port forwards, handled in arrival order so stream data stays ordered
+    let (forward_msg_tx, mut forward_msg_rx) =
+        tokio::sync::mpsc::unbounded_channel::<CocoonMessage>();
+    let relay_forwards = RelayForwards::default();
+    let writer_for_forwards = writer.clone();
+    tokio::spawn(async move {
+        while let Some(msg) = forward_msg_rx.recv().await {
+            relay_forwards.handle(msg, writer_for_forwards.clone()).await;
         }
     });
 
@@ -1070,15 +1851,38 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let setup_token = env_opt(EnvVar::CocoonSetupToken.as_str());
     let cocoon_name = env_opt(EnvVar::CocoonName.as_str());
 
-    let cocoon_version = env!("CARGO_PKG_VERSION").to_string();
-    let mut tags = std::collections::HashMap::new();
+    let usage_meter_for_config = usage_meter.clone();
+    let service_logs = ServiceLogRelay::new();
+    let service_logs_for_config = service_logs.clone();
+    let mut config_applier = ConfigApplier::load(
+        DEVICE_CONFIG_PATH,
+        Box::new(move |config: &CocoonConfig| {
+            log_reload
+                .reload(log_filter(config))
+                .map_err(|e| e.to_string())?;
+            usage_meter_for_config.set_policy(config.adi_quota.clone());
+            service_logs_for_config.set_configs(config.log_shipping.clone());
+            Ok(())
+        }),
+    )
+    .await;
+    if config_applier.version() > 0 {
+        tracing::info!("⚙️ Device config version {}", config_applier.version());
+    }
+
+    let mut cache = RegistrationCache::load(REGISTRATION_CACHE_PATH).await;
+    cache.set_device_id(device_id);
     if let Some(ref token) = setup_token {
         tracing::info!("🎫 Using setup token for auto-registration");
-        tags.insert("setup_token".to_string(), token.clone());
+        if cache.add_pending_claim(token) {
+            // Persist now so the claim is retried even if we restart before going online
+            let _ = cache.save(REGISTRATION_CACHE_PATH).await;
+        }
     }
-    if let Some(ref name) = cocoon_name {
-        tags.insert("name".to_string(), name.clone());
+    if let Some(ref owner_id) = cache.owner_id {
+        tracing::info!("👤 Last known owner: {}", owner_id);
     }
+
     let protocols: Vec<String> = env_opt(EnvVar::CocoonProtocols.as_str())
         .map(|s| s.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect())
         .unwrap_or_else(|| vec!["silk".to_string()]);
@@ -1088,72 +1892,46 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
         "protocols": protocols,
     }));
 
-    let register_msg = SignalingMessage::DeviceRegister {
-        secret,
-        device_id: device_id.clone(),
-        version: cocoon_version,
-        tags: if tags.is_empty() { None } else { Some(tags) },
-        device_type: Some("cocoon".to_string()),
-        device_config,
-    };
-
     let current_device_id: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
 
-    // Send DeviceRegister immediately (cocoon endpoint skips auth)
-    tracing::info!("⏳ Registering with signaling server...");
-    {
-        let mut w = writer.lock().await;
-        w.send(Message::Text(
-            serde_json::to_string(&register_msg).unwrap(),
-        ))
-        .await
-        .map_err(|e| format!("Failed to send register: {}", e))?;
-    }
-
-    let mut registered = false;
-    while let Some(Ok(msg)) = read.next().await {
-        let text = match msg {
-            Message::Text(t) => t,
-            Message::Close(_) => return Err("Connection closed during registration".into()),
-            _ => continue,
-        };
-        let parsed: SignalingMessage = match serde_json::from_str(&text) {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
-        match parsed {
-            SignalingMessage::DeviceRegisterResponse { device_id: assigned_id, tags } => {
-                registered = true;
-                tracing::info!("✅ Registration confirmed");
-                tracing::info!("🆔 Device ID: {}", assigned_id);
-
-                if let Some(ref t) = tags {
-                    if let Some(owner_id) = t.get("owner_id") {
-                        tracing::info!("👤 Owner: {}", owner_id);
-                        if let Some(name) = t.get("name") {
-                            tracing::info!("📛 Name: {}", name);
-                        }
-                        tracing::info!("🎉 Cocoon is ready and claimed by your account!");
-                    }
-                }
-
-                save_device_id(&assigned_id).await;
-                *current_device_id.lock().await = Some(assigned_id);
-                break;
+    let lan_access = if crate::lan::lan_enabled() {
+        let lan = Arc::new(LanAccess::new(crate::lan::lan_port()));
+        match tokio::net::TcpListener::bind(("0.0.0.0", lan.port)).await {
+            Ok(listener) => {
+                tracing::info!("🏠 Direct LAN connections on ws://0.0.0.0:{}", lan.port);
+                tokio::spawn(serve_lan(
+                    listener,
+                    lan.clone(),
+                    adi_router_for_lan,
+                    current_device_id.clone(),
+                ));
+                tokio::spawn(crate::lan::run_discovery_responder(DiscoveryIdentity {
+                    device_id: current_device_id.clone(),
+                    name: cocoon_name.clone(),
+                    port: lan.port,
+                }));
+                Some(lan)
             }
-            SignalingMessage::SystemError { message } => {
-                tracing::error!("❌ Server error during registration: {}", message);
-                return Err(format!("Server error: {}", message).into());
+            Err(e) => {
+                tracing::warn!("⚠️ Direct LAN access disabled, cannot bind port {}: {}", lan.port, e);
+                None
             }
-            _ => continue,
         }
-    }
-
-    if !registered {
-        return Err("Connection closed before registration completed".into());
-    }
+    } else {
+        None
+    };
 
-    let current_device_id_for_loop = current_device_id.clone();
+    let registrar = Registrar {
+        signaling_url,
+        secret: Mutex::new(secret),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        name: cocoon_name,
+        device_config,
+        cache: Mutex::new(cache),
+        current_device_id: current_device_id.clone(),
+        capabilities,
+        peer_link: peer_link.clone(),
+    };
 
     let (shutdown_tx, mut shutdown_rx) = broadcast::channel::<()>(1);
     let writer_for_shutdown = writer.clone();
@@ -1191,32 +1969,94 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
         let _ = shutdown_tx.send(());
     });
 
+    let mut read = tokio::select! {
+        _ = shutdown_rx.recv() => {
+            tracing::info!("🐛 Cocoon shutting down before registration completed");
+            return Ok(());
+        }
+        read = registrar.connect(&writer) => read,
+    };
+    // Reconnect hint the server sent before closing the connection
+    let mut disconnect_hint = None;
+
+    // Load report for the platform; only sent when usage changed
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut reported_generation = 0;
+
     loop {
         tokio::select! {
             _ = shutdown_rx.recv() => {
                 tracing::info!("🛑 Shutdown signal received, exiting main loop...");
                 break;
             }
+            _ = heartbeat.tick() => {
+                let Some(device_id) = current_device_id.lock().await.clone() else {
+                    continue;
+                };
+                let (generation, adi_usage) = usage_meter.report();
+                if generation == reported_generation {
+                    continue;
+                }
+                match writer.send(&SignalingMessage::DeviceHeartbeat { device_id: device_id.into(), adi_usage }) {
+                    Ok(()) => reported_generation = generation,
+                    Err(e) => tracing::warn!("⚠️ Failed to send heartbeat: {}", e),
+                }
+            }
             msg_result = read.next() => {
-                let msg = match msg_result {
-                    Some(Ok(msg)) => msg,
+                let text = match msg_result {
+                    Some(Ok(Message::Text(t))) => Some(t),
+                    Some(Ok(Message::Close(_))) => {
+                        tracing::info!("🔌 Connection closed");
+                        None
+                    }
+                    Some(Ok(_)) => continue,
                     Some(Err(e)) => {
                         tracing::error!("❌ WebSocket error: {}", e);
-                        break;
+                        None
                     }
                     None => {
                         tracing::info!("🔌 Connection closed by server");
-                        break;
+                        None
                     }
                 };
 
-                let text = match msg {
-                    Message::Text(t) => t,
-                    Message::Close(_) => {
-                        tracing::info!("🔌 Connection closed");
+                let Some(text) = text else {
+                    // Keep serving locally and re-register in the background
+                    writer.detach();
+                    if let Some(ref relay) = sub_relay {
+                        relay.close_all().await;
+                    }
+                    let device_id = current_device_id.lock().await.take();
+                    publish_daemon_event(
+                        lib_daemon_client::events::topics::COCOON_DISCONNECTED,
+                        serde_json::json!({ "device_id": device_id }),
+                    )
+                    .await;
+
+                    let hint: Option<lib_signaling_protocol::DisconnectInfo> = disconnect_hint.take();
+                    if hint.as_ref().is_some_and(|info| !info.should_retry()) {
+                        tracing::error!(
+                            "🚫 Signaling server ended this device's connection for good ({:?}), staying offline",
+                            hint
+                        );
+                        let _ = shutdown_rx.recv().await;
                         break;
                     }
-                    _ => continue,
+                    let wait = hint.and_then(|info| info.retry_after()).unwrap_or_default();
+
+                    tokio::select! {
+                        _ = shutdown_rx.recv() => {
+                            tracing::info!("🛑 Shutdown signal received while offline");
+                            break;
+                        }
+                        new_read = async {
+                            tokio::time::sleep(wait).await;
+                            registrar.connect(&writer).await
+                        } => {
+                            read = new_read;
+                            continue;
+                        }
+                    }
                 };
 
                 let message: SignalingMessage = match serde_json::from_str(&text) {
@@ -1255,20 +2095,105 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                         }
                         tracing::info!("");
 
-                        *current_device_id_for_loop.lock().await = Some(assigned_id.clone());
-                        save_device_id(&assigned_id).await;
+                        registrar.record(&assigned_id, tags.as_ref()).await;
+                        if let Err(e) = writer.send(&registrar.capabilities.to_update_message()) {
+                            tracing::warn!("⚠️ Failed to announce capabilities: {}", e);
+                        }
+                    }
+
+                    SignalingMessage::CapabilityResync { version } => {
+                        tracing::debug!("Server holds capabilities at version {}, announcing the full set", version);
+                        if let Err(e) = writer.send(&registrar.capabilities.to_update_message()) {
+                            tracing::warn!("⚠️ Failed to announce capabilities: {}", e);
+                        }
+                    }
+
+                    SignalingMessage::SyncRetrieveQueuedResponse { delivered, expired, .. } => {
+                        if delivered > 0 || expired > 0 {
+                            tracing::info!("📬 Replayed {} queued messages ({} expired while offline)", delivered, expired);
+                        }
                     }
 
                     SignalingMessage::DeviceDeregisterResponse { device_id } => {
                         tracing::info!("✅ Deregistration confirmed for device: {}", device_id);
                     }
 
-                    SignalingMessage::SyncData { payload } => {
+                    SignalingMessage::DeviceValidateDelegatedTokenResponse { token, grant, .. } => {
+                        delegation.resolve(&token, grant);
+                    }
+
+                    SignalingMessage::DeviceRekey { setup_token, secret } => {
+                        if let Err(e) = registrar.rekey(secret, &setup_token).await {
+                            tracing::warn!("⚠️ Ignoring re-key: {}", e);
+                            continue;
+                        }
+                        tracing::info!("🔑 Claimed from a warm pool, registering under the new secret");
+                        writer.detach();
+                        current_device_id.lock().await.take();
+                        tokio::select! {
+                            _ = shutdown_rx.recv() => {
+                                tracing::info!("🛑 Shutdown signal received while re-registering");
+                                break;
+                            }
+                            new_read = registrar.connect(&writer) => read = new_read,
+                        }
+                    }
+
+                    SignalingMessage::DeviceRevokeDelegatedToken { token_id } => {
+                        let closed = webrtc_manager.close_delegated_sessions(&token_id).await;
+                        tracing::info!("🔑 Delegated token {} revoked, closed {} session(s)", token_id, closed);
+                    }
+
+                    SignalingMessage::DeviceConfigPush { config_patch, version, .. } => {
+                        let result = config_applier.apply(version, &config_patch).await;
+                        match result {
+                            Ok(()) => tracing::info!("⚙️ Applied device config version {}", version),
+                            Err(ref e) => tracing::warn!("⚠️ Rejected device config version {}: {}", version, e),
+                        }
+                        // Signaling fills in the device id from the connection
+                        let applied = SignalingMessage::DeviceConfigApplied {
+                            device_id: current_device_id.lock().await.clone().unwrap_or_default().into(),
+                            version,
+                            success: result.is_ok(),
+                            error: result.err(),
+                        };
+                        if let Err(e) = writer.send(&applied) {
+                            tracing::error!("❌ Failed to report device config: {}", e);
+                        }
+                    }
+
+                    request @ SignalingMessage::CapabilityRequest { .. } => {
+                        let peer_link = peer_link.clone();
+                        tokio::spawn(async move { peer_link.serve(request, false).await });
+                    }
+
+                    answer @ (SignalingMessage::CapabilityResponse { .. }
+                    | SignalingMessage::CapabilityUnavailable { .. }) => peer_link.deliver(answer),
+
+                    msg @ (SignalingMessage::RelayFrame { .. }
+                    | SignalingMessage::RelayClose { .. }) => match sub_relay {
+                        Some(ref relay) => relay.handle_upstream(msg).await,
+                        None => tracing::debug!("Relay frame received without COCOON_RELAY_LISTEN"),
+                    },
+
+                    SignalingMessage::SyncData { payload, .. } => {
+                        // Only cocoon↔cocoon WebRTC negotiation uses the envelope
+                        if let Some((from, data)) = cocoon_envelope(&payload) {
+                            match serde_json::from_value::<CocoonMessage>(data) {
+                                Ok(msg) => {
+                                    let _ = peer_signal_tx.send((from, msg));
+                                }
+                                Err(e) => tracing::warn!("⚠️ Invalid message from cocoon {}: {}", from, e),
+                            }
+                            continue;
+                        }
+
                         let type_str = payload.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                        let sender = relayed_sender(&payload);
                         if type_str.starts_with("webrtc_") {
                             match serde_json::from_value::<CocoonMessage>(payload) {
                                 Ok(cocoon_msg) => {
-                                    let _ = webrtc_msg_tx.send(cocoon_msg);
+                                    let _ = webrtc_msg_tx.send((cocoon_msg, sender));
                                     continue;
                                 }
                                 Err(e) => {
@@ -1278,6 +2203,52 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                             }
                         }
 
+                        // Delegated access is scoped to WebRTC sessions
+                        if sender.as_ref().is_some_and(|s| s.grant.is_some()) {
+                            tracing::warn!("🚫 Dropped '{}' from a delegated sender", type_str);
+                            continue;
+                        }
+
+                        if type_str.starts_with("forward_") {
+                            match serde_json::from_value::<CocoonMessage>(payload) {
+                                Ok(cocoon_msg) => {
+                                    let _ = forward_msg_tx.send(cocoon_msg);
+                                }
+                                Err(e) => {
+                                    tracing::warn!("⚠️ Invalid forward message: {}", e);
+                                }
+                            }
+                            continue;
+                        }
+
+                        // The LAN token grants owner access
+                        if type_str == "lan_info_request" {
+                            if verified_owner(sender.as_ref()).is_none() {
+                                tracing::warn!("🚫 Dropped LAN info request from a non-owner");
+                                continue;
+                            }
+                            let request_id = payload.get("request_id").and_then(|v| v.as_str()).unwrap_or("").to_string();
+                            let info = match lan_access {
+                                Some(ref lan) => CocoonMessage::LanInfo {
+                                    request_id,
+                                    addresses: crate::lan::lan_addresses(),
+                                    port: lan.port as i32,
+                                    token: lan.token().to_string(),
+                                },
+                                None => CocoonMessage::LanInfo {
+                                    request_id,
+                                    addresses: Vec::new(),
+                                    port: 0,
+                                    token: String::new(),
+                                },
+                            };
+                            let _ = writer.send(&SignalingMessage::SyncData {
+                                payload: serde_json::to_value(&info).expect("CocoonMessage serialization cannot fail"),
+                                priority: Some(RelayPriority::Interactive),
+                            });
+                            continue;
+                        }
+
                         // Handle query protocol messages (query_query_local → query_query_result)
                         if type_str == "query_query_local" {
                             let query_id = payload.get("query_id").and_then(|v| v.as_str()).unwrap_or("").to_string();
@@ -1307,11 +2278,11 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                                         "data": data,
                                         "is_final": is_final,
                                     });
-                                    let sync_msg = SignalingMessage::SyncData { payload: response };
-                                    let mut w = writer_clone.lock().await;
-                                    let _ = w.send(Message::Text(
-                                        serde_json::to_string(&sync_msg).expect("serialization cannot fail"),
-                                    )).await;
+                                    let sync_msg = SignalingMessage::SyncData {
+                                        payload: response,
+                                        priority: Some(RelayPriority::Bulk),
+                                    };
+                                    let _ = writer_clone.send(&sync_msg);
                                 }
                             });
                             continue;
@@ -1329,6 +2300,8 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                         let sessions_clone = pty_sessions.clone();
                         let services_clone = services.clone();
                         let silk_sessions_clone = silk_sessions.clone();
+                        let service_logs_clone = service_logs.clone();
+                        let peer_link_clone = peer_link.clone();
 
                         tokio::spawn(async move {
                             let response: Option<CommandResponse> = match request {
@@ -1450,18 +2423,17 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                                 path,
                                 service_name
                             );
-                            Some(
-                                handle_proxy_request(
-                                    request_id,
-                                    service_name,
-                                    method,
-                                    path,
-                                    headers,
-                                    body,
-                                    &services_clone,
-                                )
-                                .await,
+                            handle_proxy_request(
+                                request_id,
+                                service_name,
+                                method,
+                                path,
+                                headers,
+                                body,
+                                &services_clone,
+                                &writer_clone,
                             )
+                            .await
                         }
 
                         CommandRequest::QueryLocal {
@@ -1507,68 +2479,26 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                             if let Some(session) = silk_sessions.get_mut(&session_id) {
                                 match session.execute(&command, command_id.clone()) {
                                     Ok((interactive, child_opt)) => {
+                                        let recorder = session.recorder.clone();
                                         if interactive {
                                             drop(silk_sessions); // Release lock before async call
 
-                                            let mut env = HashMap::new();
-                                            env.insert(
-                                                "TERM".to_string(),
-                                                "xterm-256color".to_string(),
-                                            );
-
-                                            match create_pty_session(
-                                                &command,
-                                                80,
-                                                24,
-                                                &env,
-                                                writer_clone.clone(),
+                                            Some(
+                                                start_silk_pty(
+                                                    &command,
+                                                    session_id,
+                                                    command_id,
+                                                    SilkSession::interactive_reason(&command),
+                                                    writer_clone.clone(),
+                                                    &sessions_clone,
+                                                    &silk_sessions_clone,
+                                                )
+                                                .await,
                                             )
-                                            .await
-                                            {
-                                                Ok((pty_session_id, pty_session)) => {
-                                                    sessions_clone
-                                                        .lock()
-                                                        .await
-                                                        .insert(pty_session_id, pty_session);
-
-                                                    if let Some(s) = silk_sessions_clone
-                                                        .lock()
-                                                        .await
-                                                        .get_mut(&session_id)
-                                                    {
-                                                        s.set_pty_session(
-                                                            command_id.clone(),
-                                                            pty_session_id,
-                                                        );
-                                                    }
-
-                                                    Some(CommandResponse::SilkResponse(
-                                                        SilkResponse::InteractiveRequired {
-                                                            session_id,
-                                                            command_id,
-                                                            reason: format!(
-                                                                "Command '{}' requires interactive mode",
-                                                                command
-                                                                    .split_whitespace()
-                                                                    .next()
-                                                                    .unwrap_or(&command)
-                                                            ),
-                                                            pty_session_id,
-                                                        },
-                                                    ))
-                                                }
-                                                Err(e) => Some(CommandResponse::SilkResponse(
-                                                    SilkResponse::Error {
-                                                        session_id: Some(session_id),
-                                                        command_id: Some(command_id),
-                                                        code: "pty_create_failed".to_string(),
-                                                        message: e,
-                                                    },
-                                                )),
-                                            }
                                         } else if let Some(mut child) = child_opt {
                                             let writer_for_output = writer_clone.clone();
                                             let sessions_for_cwd = silk_sessions_clone.clone();
+                                            let pty_sessions_for_switch = sessions_clone.clone();
                                             let cmd_for_cwd = command.clone();
                                             let command_id_for_spawn = command_id.clone();
 
@@ -1577,21 +2507,9 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                                                 command_id,
                                                 interactive: false,
                                             };
-                                            let started_msg = SignalingMessage::SyncData {
-                                                payload: serde_json::to_value(
-                                                    &CommandResponse::SilkResponse(started),
-                                                )
-                                                .expect("CommandResponse serialization cannot fail"),
-                                            };
-                                            let mut w = writer_clone.lock().await;
-                                            let _ = w
-                                                .send(Message::Text(
-                                                    serde_json::to_string(&started_msg).expect(
-                                                        "SignalingMessage serialization cannot fail",
-                                                    ),
-                                                ))
-                                                .await;
-                                            drop(w);
+                                            let _ = writer_clone.send(
+                                                &CommandResponse::SilkResponse(started).into_sync_data(),
+                                            );
 
                                             if let Some(stdin) = child.stdin.take() {
                                                 let mut silk_lock = silk_sessions_clone.lock().await;
@@ -1612,13 +2530,21 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                                                 );
 
                                                 let mut buf = [0u8; 4096];
+                                                let mut detector = OutputDetector::new();
+                                                let mut needs_pty = None;
                                                 loop {
                                                     match stdout_reader.get_mut().read(&mut buf) {
                                                         Ok(0) => break,
                                                         Ok(n) => {
+                                                            needs_pty = detector
+                                                                .feed(&buf[..n])
+                                                                .filter(|d| d.is_confident());
                                                             let data =
                                                                 String::from_utf8_lossy(&buf[..n])
                                                                     .to_string();
+                                                            if let Some(recorder) = &recorder {
+                                                                recorder.output(&data);
+                                                            }
                                                             let html = AnsiToHtml::convert(&data);
                                                             let output = SilkResponse::Output {
                                                                 session_id,
@@ -1627,32 +2553,51 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                                                                 data: data.clone(),
                                                                 html: Some(html),
                                                             };
-                                                            let msg = SignalingMessage::SyncData {
-                                                                payload: serde_json::to_value(
-                                                                    &CommandResponse::SilkResponse(
-                                                                        output,
-                                                                    ),
-                                                                )
-                                                                .expect("CommandResponse serialization cannot fail"),
-                                                            };
-                                                            let mut w =
-                                                                writer_for_output.lock().await;
-                                                            let _ = w
-                                                                .send(Message::Text(
-                                                                    serde_json::to_string(&msg)
-                                                                        .expect("SignalingMessage serialization cannot fail"),
-                                                                ))
-                                                                .await;
+                                                            let _ = writer_for_output.send(
+                                                                &CommandResponse::SilkResponse(output)
+                                                                    .into_sync_data(),
+                                                            );
+                                                            if needs_pty.is_some() {
+                                                                break;
+                                                            }
                                                         }
                                                         Err(_) => break,
                                                     }
                                                 }
 
+                                                // Waiting on a prompt or driving the terminal:
+                                                // piped I/O can't serve it, so rerun in a PTY
+                                                if let Some(detection) = needs_pty {
+                                                    tracing::info!(
+                                                        "🧵 Restarting {} in a PTY: {}",
+                                                        cmd_for_cwd,
+                                                        detection.reason
+                                                    );
+                                                    let _ = child.kill();
+                                                    let _ = child.wait();
+                                                    let response = start_silk_pty(
+                                                        &cmd_for_cwd,
+                                                        session_id,
+                                                        command_id,
+                                                        detection.reason,
+                                                        writer_for_output.clone(),
+                                                        &pty_sessions_for_switch,
+                                                        &sessions_for_cwd,
+                                                    )
+                                                    .await;
+                                                    let _ = writer_for_output
+                                                        .send(&response.into_sync_data());
+                                                    return;
+                                                }
+
                                                 let mut stderr_buf = Vec::new();
                                                 let _ = stderr_reader.read_to_end(&mut stderr_buf);
                                                 if !stderr_buf.is_empty() {
                                                     let data = String::from_utf8_lossy(&stderr_buf)
                                                         .to_string();
+                                                    if let Some(recorder) = &recorder {
+                                                        recorder.output(&data);
+                                                    }
                                                     let html = AnsiToHtml::convert(&data);
                                                     let output = SilkResponse::Output {
                                                         session_id,
@@ -1661,20 +2606,10 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                                                         data: data.clone(),
                                                         html: Some(html),
                                                     };
-                                                    let msg = SignalingMessage::SyncData {
-                                                        payload: serde_json::to_value(
-                                                            &CommandResponse::SilkResponse(output),
-                                                        )
-                                                        .expect("CommandResponse serialization cannot fail"),
-                                                    };
-                                                    let mut w = writer_for_output.lock().await;
-                                                    let _ = w
-                                                        .send(Message::Text(
-                                                            serde_json::to_string(&msg).expect(
-                                                                "SignalingMessage serialization cannot fail",
-                                                            ),
-                                                        ))
-                                                        .await;
+                                                    let _ = writer_for_output.send(
+                                                        &CommandResponse::SilkResponse(output)
+                                                            .into_sync_data(),
+                                                    );
                                                 }
 
                                                 let exit_code = child
@@ -1696,22 +2631,10 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                                                                 exit_code,
                                                                 cwd: s.cwd.clone(),
                                                             };
-                                                        let msg = SignalingMessage::SyncData {
-                                                            payload: serde_json::to_value(
-                                                                &CommandResponse::SilkResponse(
-                                                                    completed,
-                                                                ),
-                                                            )
-                                                            .expect("CommandResponse serialization cannot fail"),
-                                                        };
-                                                        let mut w = writer_for_output.lock().await;
-                                                        let _ = w
-                                                            .send(Message::Text(
-                                                                serde_json::to_string(&msg).expect(
-                                                                    "SignalingMessage serialization cannot fail",
-                                                                ),
-                                                            ))
-                                                            .await;
+                                                        let _ = writer_for_output.send(
+                                                            &CommandResponse::SilkResponse(completed)
+                                                                .into_sync_data(),
+                                                        );
                                                     }
                                                 }
                                             });
@@ -1887,22 +2810,152 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                                 }))
                             }
                         }
+
+                        CommandRequest::SilkUploadFile {
+                            session_id,
+                            path,
+                            chunk,
+                            seq,
+                            done,
+                            sha256,
+                        } => {
+                            let mut silk_sessions = silk_sessions_clone.lock().await;
+                            if let Some(session) = silk_sessions.get_mut(&session_id) {
+                                let pushed =
+                                    session.push_upload(&path, seq, &chunk, done, sha256.as_deref());
+                                drop(silk_sessions);
+                                match pushed {
+                                    Ok(None) => None,
+                                    Ok(Some((target, data))) => {
+                                        tracing::info!("🧵 Silk upload: {} ({} bytes)", target.display(), data.len());
+                                        match write_upload(&target, &data).await {
+                                            Ok(()) => Some(CommandResponse::SilkResponse(
+                                                SilkResponse::FileUploaded {
+                                                    session_id,
+                                                    path,
+                                                    size: data.len() as u64,
+                                                    sha256: lib_tarminal_sync::sha256_hex(&data),
+                                                },
+                                            )),
+                                            Err(e) => Some(CommandResponse::SilkResponse(
+                                                SilkResponse::Error {
+                                                    session_id: Some(session_id),
+                                                    command_id: None,
+                                                    code: "upload_failed".to_string(),
+                                                    message: format!("{}: {}", path, e),
+                                                },
+                                            )),
+                                        }
+                                    }
+                                    Err(e) => Some(CommandResponse::SilkResponse(SilkResponse::Error {
+                                        session_id: Some(session_id),
+                                        command_id: None,
+                                        code: "upload_failed".to_string(),
+                                        message: format!("{}: {}", path, e),
+                                    })),
+                                }
+                            } else {
+                                Some(CommandResponse::SilkResponse(SilkResponse::Error {
+                                    session_id: Some(session_id),
+                                    command_id: None,
+                                    code: "session_not_found".to_string(),
+                                    message: format!("Silk session {} not found", session_id),
+                                }))
+                            }
+                        }
+
+                        CommandRequest::SilkDownloadFile { session_id, path } => {
+                            let source = silk_sessions_clone
+                                .lock()
+                                .await
+                                .get(&session_id)
+                                .map(|session| session.resolve_path(&path));
+                            match source {
+                                Some(source) => {
+                                    tracing::info!("🧵 Silk download: {}", source.display());
+                                    match read_download(&source).await {
+                                        Ok(data) => {
+                                            for chunk in lib_tarminal_sync::split_file(&data) {
+                                                let response = SilkResponse::FileChunk {
+                                                    session_id,
+                                                    path: path.clone(),
+                                                    chunk: chunk.chunk,
+                                                    seq: chunk.seq,
+                                                    done: chunk.done,
+                                                    sha256: chunk.sha256,
+                                                };
+                                                if let Err(e) = writer_clone.send(
+                                                    &CommandResponse::SilkResponse(response).into_sync_data(),
+                                                ) {
+                                                    tracing::error!("❌ Failed to send file chunk: {}", e);
+                                                    break;
+                                                }
+                                            }
+                                            None // Chunks sent above
+                                        }
+                                        Err(e) => Some(CommandResponse::SilkResponse(
+                                            SilkResponse::Error {
+                                                session_id: Some(session_id),
+                                                command_id: None,
+                                                code: "download_failed".to_string(),
+                                                message: format!("{}: {}", path, e),
+                                            },
+                                        )),
+                                    }
+                                }
+                                None => Some(CommandResponse::SilkResponse(SilkResponse::Error {
+                                    session_id: Some(session_id),
+                                    command_id: None,
+                                    code: "session_not_found".to_string(),
+                                    message: format!("Silk session {} not found", session_id),
+                                })),
+                            }
+                        }
+
+                        CommandRequest::StreamServiceLogs {
+                            stream_id,
+                            fqn,
+                            level,
+                        } => {
+                            tracing::info!("📜 Streaming service logs ({})", fqn.as_deref().unwrap_or("*"));
+                            service_logs_clone.start(stream_id, fqn, level, writer_clone.clone());
+                            None // Lines follow as service_logs batches
+                        }
+
+                        CommandRequest::StopServiceLogs { stream_id } => {
+                            if service_logs_clone.stop(stream_id) {
+                                None
+                            } else {
+                                Some(CommandResponse::Error {
+                                    code: "stream_not_found".into(),
+                                    message: format!("Service log stream {} not found", stream_id),
+                                })
+                            }
+                        }
+
+                        CommandRequest::CapabilityCall { request_id, capability, payload, prefer_device } => {
+                            let result = peer_link_clone
+                                .request(request_id.clone().into(), capability, payload, prefer_device)
+                                .await;
+                            Some(match result {
+                                Ok((payload, from_device)) => CommandResponse::CapabilityResult {
+                                    request_id,
+                                    payload: Some(payload),
+                                    error: None,
+                                    from_device,
+                                },
+                                Err(e) => CommandResponse::CapabilityResult {
+                                    request_id,
+                                    payload: None,
+                                    error: Some(e),
+                                    from_device: None,
+                                },
+                            })
+                        }
                     };
 
                                 if let Some(response) = response {
-                                    let response_msg = SignalingMessage::SyncData {
-                                        payload: serde_json::to_value(&response)
-                                            .expect("CommandResponse serialization cannot fail"),
-                                    };
-
-                                    let mut w = writer_clone.lock().await;
-                                    if let Err(e) = w
-                                        .send(Message::Text(
-                                            serde_json::to_string(&response_msg)
-                                                .expect("SignalingMessage serialization cannot fail"),
-                                        ))
-                                        .await
-                                    {
+                                    if let Err(e) = writer_clone.send(&response.into_sync_data()) {
                                         tracing::error!("❌ Failed to send response: {}", e);
                                     }
                                 }
@@ -1913,8 +2966,14 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                         tracing::info!("👋 Peer connected: {}", peer_id);
                     }
 
-                    SignalingMessage::DevicePeerDisconnected { peer_id } => {
-                        tracing::info!("👋 Peer disconnected: {}", peer_id);
+                    SignalingMessage::DevicePeerDisconnected { peer_id, info } => match info {
+                        Some(info) => tracing::info!("👋 Peer disconnected: {} ({:?})", peer_id, info.reason),
+                        None => tracing::info!("👋 Peer disconnected: {}", peer_id),
+                    },
+
+                    SignalingMessage::DeviceDisconnect { info } => {
+                        tracing::info!("🔌 Signaling server is closing the connection: {:?}", info);
+                        disconnect_hint = Some(info);
                     }
 
                     SignalingMessage::SystemError { message } => {
@@ -1929,6 +2988,13 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    let device_id = current_device_id.lock().await.clone();
+    publish_daemon_event(
+        lib_daemon_client::events::topics::COCOON_DISCONNECTED,
+        serde_json::json!({ "device_id": device_id }),
+    )
+    .await;
+
     tracing::info!("🐛 Cocoon shutting down");
     Ok(())
 }
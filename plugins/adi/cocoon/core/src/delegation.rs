@@ -0,0 +1,417 @@
+//! Delegated access for users who do not own the cocoon (`adi cocoon share`).
+//!
+//! An owner asks signaling for a token scoped to some services of one device.
+//! A client presenting that token when it starts a WebRTC session gets a
+//! session limited to those scopes: the cocoon validates the token with
+//! signaling and checks every ADI request, Silk command and file request
+//! against the grant.
+//!
+//! A scope names a service, optionally read-only (`silk:ro`):
+//! - an ADI plugin, where `tasks` also matches the `adi.tasks` plugin. Read-only
+//!   plugin scopes allow methods named like reads (`list…`, `get…`, …).
+//! - `silk`: terminal sessions. Read-only sessions run a fixed set of inspection
+//!   commands and take no input.
+//! - `files`: the "file" channel, which is read-only anyway.
+//! - `forward`: port forwarding.
+
+use lib_signaling_protocol::{DelegatedGrant, SignalingMessage};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::protocol::messages::CocoonMessage;
+
+pub const SILK_SCOPE: &str = "silk";
+pub const FILES_SCOPE: &str = "files";
+pub const FORWARD_SCOPE: &str = "forward";
+
+/// How long signaling may take to validate a token.
+const VALIDATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Method name prefixes a read-only plugin scope allows
+const READ_METHOD_PREFIXES: &[&str] = &[
+    "list", "get", "read", "search", "query", "find", "count", "show", "status", "stat",
+    "describe", "info",
+];
+
+/// Programs a read-only Silk session may run
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "cat", "head", "tail", "ls", "pwd", "grep", "rg", "wc", "stat", "du", "df", "ps", "git",
+];
+
+/// Git subcommands a read-only Silk session may run
+const READ_ONLY_GIT: &[&str] = &["status", "log", "diff", "show"];
+
+/// Options that make an otherwise read-only program write files or run others
+const WRITING_OPTIONS: &[&str] = &["--output", "--pre"];
+
+/// Commands run through `sh -c`; these would chain, substitute or redirect
+const SHELL_METACHARACTERS: &[char] = &[';', '|', '&', '>', '<', '$', '`', '(', ')', '\n', '\r'];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    pub service: String,
+    pub read_only: bool,
+}
+
+impl Scope {
+    pub fn parse(scope: &str) -> Result<Self, String> {
+        let (service, read_only) = match scope.split_once(':') {
+            Some((service, "ro")) => (service, true),
+            Some(_) => return Err(format!("Invalid scope '{}'", scope)),
+            None => (scope, false),
+        };
+        if service.is_empty() {
+            return Err(format!("Invalid scope '{}'", scope));
+        }
+        Ok(Self {
+            service: service.to_string(),
+            read_only,
+        })
+    }
+
+    fn covers(&self, service: &str) -> bool {
+        self.service == service || service.strip_prefix("adi.") == Some(self.service.as_str())
+    }
+}
+
+/// The grant a session was opened with
+#[derive(Debug, Clone)]
+pub struct DelegatedAccess {
+    pub token_id: String,
+    /// Owner who shared the device
+    pub issued_by: String,
+    pub scopes: Vec<Scope>,
+    /// Unix seconds
+    pub expires_at: u64,
+}
+
+impl DelegatedAccess {
+    pub fn from_grant(grant: &DelegatedGrant) -> Result<Self, String> {
+        let scopes = grant
+            .scopes
+            .iter()
+            .map(|s| Scope::parse(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            token_id: grant.token_id.clone(),
+            issued_by: grant.issued_by.clone(),
+            scopes,
+            expires_at: grant.expires_at,
+        })
+    }
+
+    pub fn is_expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.expires_at <= now
+    }
+
+    /// Scope granted for `service`; a full scope wins over a read-only one
+    fn scope(&self, service: &str) -> Result<&Scope, String> {
+        if self.is_expired() {
+            return Err("Delegated access has expired".to_string());
+        }
+        self.scopes
+            .iter()
+            .filter(|s| s.covers(service))
+            .min_by_key(|s| s.read_only)
+            .ok_or_else(|| format!("Delegated access does not include '{}'", service))
+    }
+
+    /// May call `method` of ADI plugin `plugin`
+    pub fn check_method(&self, plugin: &str, method: &str) -> Result<(), String> {
+        let scope = self.scope(plugin)?;
+        if scope.read_only && !is_read_method(method) {
+            return Err(format!(
+                "Delegated access to '{}' is read-only; '{}' is not allowed",
+                plugin, method
+            ));
+        }
+        Ok(())
+    }
+
+    /// May subscribe to events of `plugin`
+    pub fn check_plugin(&self, plugin: &str) -> Result<(), String> {
+        self.scope(plugin).map(|_| ())
+    }
+
+    /// May use the "file" channel
+    pub fn check_files(&self) -> Result<(), String> {
+        self.scope(FILES_SCOPE).map(|_| ())
+    }
+
+    /// May open port forwards
+    pub fn check_forward(&self) -> Result<(), String> {
+        self.scope(FORWARD_SCOPE).map(|_| ())
+    }
+
+    /// May send `msg` on the "silk" channel
+    pub fn check_silk(&self, msg: &CocoonMessage) -> Result<(), String> {
+        let scope = self.scope(SILK_SCOPE)?;
+        if !scope.read_only {
+            return Ok(());
+        }
+        match msg {
+            CocoonMessage::SilkCreateSession { env, shell, .. } => {
+                if shell.is_some() || env.as_ref().is_some_and(|e| !e.is_empty()) {
+                    return Err("Read-only sessions use the default shell".to_string());
+                }
+                Ok(())
+            }
+            CocoonMessage::SilkExecute { command, .. } => check_read_only_command(command),
+            CocoonMessage::SilkInput { .. } | CocoonMessage::SilkSignal { .. } => {
+                Err("Read-only Silk sessions take no input".to_string())
+            }
+            CocoonMessage::SilkUploadFile { .. } => {
+                Err("Read-only Silk sessions cannot upload files".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_read_method(method: &str) -> bool {
+    let method = method.to_ascii_lowercase();
+    READ_METHOD_PREFIXES.iter().any(|p| method.starts_with(p))
+}
+
+fn check_read_only_command(command: &str) -> Result<(), String> {
+    let denied = || Err(format!("'{}' is not allowed when read-only", command));
+    if command.contains(SHELL_METACHARACTERS) {
+        return denied();
+    }
+    let mut words = command.split_whitespace();
+    let Some(program) = words.next() else {
+        return denied();
+    };
+    let args: Vec<&str> = words.collect();
+    if !READ_ONLY_COMMANDS.contains(&program)
+        || args
+            .iter()
+            .any(|a| WRITING_OPTIONS.iter().any(|o| a.starts_with(o)))
+    {
+        return denied();
+    }
+    if program == "git" && !args.first().is_some_and(|sub| READ_ONLY_GIT.contains(sub)) {
+        return denied();
+    }
+    Ok(())
+}
+
+/// Validates delegated tokens with signaling
+///
+/// Requests go out on the signaling connection and the main loop hands each
+/// `DeviceValidateDelegatedTokenResponse` to `resolve`. Concurrent checks of
+/// the same token share one request.
+pub struct DelegationValidator {
+    signaling_tx: mpsc::UnboundedSender<SignalingMessage>,
+    pending: Mutex<HashMap<String, Vec<oneshot::Sender<Option<DelegatedGrant>>>>>,
+}
+
+impl DelegationValidator {
+    pub fn new(signaling_tx: mpsc::UnboundedSender<SignalingMessage>) -> Self {
+        Self {
+            signaling_tx,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn validate(&self, token: &str) -> Result<DelegatedAccess, String> {
+        let (tx, rx) = oneshot::channel();
+        let first = {
+            let mut pending = self.pending.lock().unwrap();
+            let waiters = pending.entry(token.to_string()).or_default();
+            waiters.push(tx);
+            waiters.len() == 1
+        };
+        if first {
+            let request = SignalingMessage::DeviceValidateDelegatedToken {
+                token: token.to_string(),
+            };
+            if self.signaling_tx.send(request).is_err() {
+                self.pending.lock().unwrap().remove(token);
+                return Err("Signaling connection is closed".to_string());
+            }
+        }
+
+        let grant = match tokio::time::timeout(VALIDATE_TIMEOUT, rx).await {
+            Ok(Ok(grant)) => grant,
+            Ok(Err(_)) => return Err("Token validation was dropped".to_string()),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(token);
+                return Err("Timed out validating the delegated token".to_string());
+            }
+        };
+        let access = grant
+            .as_ref()
+            .ok_or_else(|| "Delegated token is invalid or expired".to_string())
+            .and_then(DelegatedAccess::from_grant)?;
+        if access.is_expired() {
+            return Err("Delegated token is invalid or expired".to_string());
+        }
+        Ok(access)
+    }
+
+    /// Answer everyone waiting on `token`
+    pub fn resolve(&self, token: &str, grant: Option<DelegatedGrant>) {
+        let waiters = self.pending.lock().unwrap().remove(token);
+        for waiter in waiters.into_iter().flatten() {
+            let _ = waiter.send(grant.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn grant(scopes: &[&str], expires_at: u64) -> DelegatedGrant {
+        DelegatedGrant {
+            token_id: "t1".to_string(),
+            device_id: "dev".into(),
+            issued_by: "owner".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_at,
+        }
+    }
+
+    fn access(scopes: &[&str]) -> DelegatedAccess {
+        DelegatedAccess::from_grant(&grant(scopes, u64::MAX)).unwrap()
+    }
+
+    fn execute(command: &str) -> CocoonMessage {
+        CocoonMessage::SilkExecute {
+            session_id: "s".to_string(),
+            command: command.to_string(),
+            command_id: "c".to_string(),
+            cols: None,
+            rows: None,
+            env: None,
+        }
+    }
+
+    #[test]
+    fn test_scope_parse() {
+        assert_eq!(
+            Scope::parse("silk:ro").unwrap(),
+            Scope {
+                service: "silk".to_string(),
+                read_only: true
+            }
+        );
+        assert!(!Scope::parse("tasks").unwrap().read_only);
+        assert!(Scope::parse("silk:rw").is_err());
+        assert!(Scope::parse(":ro").is_err());
+    }
+
+    #[test]
+    fn test_plugin_scopes() {
+        let shared = access(&["tasks:ro", "knowledgebase"]);
+
+        assert!(shared.check_method("adi.tasks", "list").is_ok());
+        assert!(shared.check_method("adi.tasks", "getTask").is_ok());
+        assert!(shared.check_method("adi.tasks", "create").is_err());
+        assert!(shared.check_method("adi.knowledgebase", "delete").is_ok());
+        assert!(shared.check_method("adi.agent", "list").is_err());
+        assert!(shared.check_plugin("tasks").is_ok());
+        assert!(shared.check_files().is_err());
+
+        // A full scope wins over a read-only one for the same service
+        let both = access(&["tasks:ro", "tasks"]);
+        assert!(both.check_method("adi.tasks", "create").is_ok());
+
+        let expired = DelegatedAccess::from_grant(&grant(&["tasks"], 1)).unwrap();
+        assert!(expired.check_method("adi.tasks", "list").is_err());
+    }
+
+    #[test]
+    fn test_read_only_silk() {
+        let ro = access(&["silk:ro"]);
+
+        assert!(ro.check_silk(&execute("git log --oneline")).is_ok());
+        assert!(ro.check_silk(&execute("tail -n 100 app.log")).is_ok());
+        assert!(ro.check_silk(&execute("rm -rf /")).is_err());
+        assert!(ro.check_silk(&execute("cat a; rm a")).is_err());
+        assert!(ro.check_silk(&execute("ls $(rm a)")).is_err());
+        assert!(ro.check_silk(&execute("git push")).is_err());
+        assert!(ro.check_silk(&execute("git diff --output=x")).is_err());
+        assert!(ro
+            .check_silk(&CocoonMessage::SilkInput {
+                session_id: "s".to_string(),
+                command_id: "c".to_string(),
+                data: "y\n".to_string(),
+            })
+            .is_err());
+        assert!(ro
+            .check_silk(&CocoonMessage::SilkCreateSession {
+                cwd: None,
+                env: None,
+                shell: Some("/tmp/evil".to_string()),
+            })
+            .is_err());
+        assert!(ro
+            .check_silk(&CocoonMessage::SilkUploadFile {
+                session_id: "s".to_string(),
+                path: ".bashrc".to_string(),
+                chunk: String::new(),
+                seq: 0,
+                done: true,
+                sha256: None,
+            })
+            .is_err());
+        assert!(ro
+            .check_silk(&CocoonMessage::SilkDownloadFile {
+                session_id: "s".to_string(),
+                path: "app.log".to_string(),
+            })
+            .is_ok());
+
+        assert!(access(&["silk"])
+            .check_silk(&execute("rm -rf build"))
+            .is_ok());
+        assert!(access(&["tasks"]).check_silk(&execute("ls")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validator_resolves_waiters() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let validator = Arc::new(DelegationValidator::new(tx));
+
+        let first = tokio::spawn({
+            let validator = validator.clone();
+            async move { validator.validate("tok").await }
+        });
+        let second = tokio::spawn({
+            let validator = validator.clone();
+            async move { validator.validate("tok").await }
+        });
+
+        assert!(matches!(
+            rx.recv().await,
+            Some(SignalingMessage::DeviceValidateDelegatedToken { ref token }) if token == "tok"
+        ));
+        // Wait until the second check joined the pending request
+        while validator.pending.lock().unwrap()["tok"].len() < 2 {
+            tokio::task::yield_now().await;
+        }
+        validator.resolve("tok", Some(grant(&["silk:ro"], u64::MAX)));
+
+        assert_eq!(first.await.unwrap().unwrap().scopes[0].service, "silk");
+        assert!(second.await.unwrap().is_ok());
+        // Both waiters shared one request
+        assert!(rx.try_recv().is_err());
+
+        let rejected = tokio::spawn({
+            let validator = validator.clone();
+            async move { validator.validate("bad").await }
+        });
+        rx.recv().await.unwrap();
+        validator.resolve("bad", None);
+        assert!(rejected.await.unwrap().is_err());
+    }
+}
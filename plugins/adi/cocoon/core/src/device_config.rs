@@ -0,0 +1,316 @@
+//! Configuration pushed from the platform (`adi cocoon config push`).
+//!
+//! Each push carries a JSON merge patch (RFC 7386) and a version. The patch
+//! is applied to a copy of the current config, which is validated, applied
+//! and persisted; if applying or persisting fails the previous config is put
+//! back, so a bad push never leaves the cocoon half-configured.
+
+use crate::adi_usage::QuotaPolicy;
+use crate::log_shipper::LogShippingConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing_subscriber::EnvFilter;
+
+pub const DEVICE_CONFIG_PATH: &str = "/cocoon/.config.json";
+
+/// Settings the platform can change at runtime.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CocoonConfig {
+    /// Tracing filter directives, e.g. `debug` or `cocoon=debug,warn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub feature_flags: BTreeMap<String, bool>,
+    /// Per-client budget for ADI requests; unlimited when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adi_quota: Option<QuotaPolicy>,
+    /// Throttling of relayed service logs by service FQN; `*` covers the rest
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub log_shipping: BTreeMap<String, LogShippingConfig>,
+}
+
+impl CocoonConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(ref level) = self.log_level {
+            EnvFilter::try_new(level)
+                .map_err(|e| format!("Invalid log_level '{}': {}", level, e))?;
+        }
+        if let Some(name) = self
+            .feature_flags
+            .keys()
+            .find(|name| name.trim().is_empty())
+        {
+            return Err(format!("Invalid feature flag name '{}'", name));
+        }
+        if let Some(ref quota) = self.adi_quota {
+            quota.validate()?;
+        }
+        for (service, shipping) in &self.log_shipping {
+            shipping
+                .validate()
+                .map_err(|e| format!("log_shipping.{}: {}", service, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn feature_enabled(&self, name: &str) -> bool {
+        self.feature_flags.get(name).copied().unwrap_or(false)
+    }
+}
+
+/// Tracing filter for `config`: its `log_level`, or `RUST_LOG` plus `cocoon=info`
+pub fn log_filter(config: &CocoonConfig) -> EnvFilter {
+    config
+        .log_level
+        .as_deref()
+        .and_then(|level| EnvFilter::try_new(level).ok())
+        .unwrap_or_else(|| {
+            EnvFilter::from_default_env()
+                .add_directive("cocoon=info".parse().expect("valid tracing directive"))
+        })
+}
+
+/// Config as last applied, as stored in [`DEVICE_CONFIG_PATH`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct AppliedConfig {
+    version: u64,
+    config: CocoonConfig,
+}
+
+/// Puts a validated config into effect; called again with the previous
+/// config on rollback.
+pub type ApplyHook = Box<dyn Fn(&CocoonConfig) -> Result<(), String> + Send + Sync>;
+
+pub struct ConfigApplier {
+    path: PathBuf,
+    applied: AppliedConfig,
+    hook: ApplyHook,
+}
+
+impl ConfigApplier {
+    /// Load the persisted config and put it into effect. A missing or
+    /// unreadable file starts from the default config at version 0.
+    pub async fn load(path: impl AsRef<Path>, hook: ApplyHook) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let applied = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "⚠️ Ignoring corrupt device config {}: {}",
+                    path.display(),
+                    e
+                );
+                AppliedConfig::default()
+            }),
+            Err(_) => AppliedConfig::default(),
+        };
+        if let Err(e) = hook(&applied.config) {
+            tracing::warn!("⚠️ Failed to apply stored device config: {}", e);
+        }
+        Self {
+            path,
+            applied,
+            hook,
+        }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.applied.version
+    }
+
+    pub fn config(&self) -> &CocoonConfig {
+        &self.applied.config
+    }
+
+    /// Apply `patch` as `version`. Repeating the applied version is a no-op;
+    /// older versions are rejected.
+    pub async fn apply(&mut self, version: u64, patch: &JsonValue) -> Result<(), String> {
+        if version == self.applied.version {
+            return Ok(());
+        }
+        if version < self.applied.version {
+            return Err(format!(
+                "Version {} is older than applied version {}",
+                version, self.applied.version
+            ));
+        }
+
+        let mut value = serde_json::to_value(&self.applied.config).map_err(|e| e.to_string())?;
+        merge_patch(&mut value, patch);
+        let config: CocoonConfig =
+            serde_json::from_value(value).map_err(|e| format!("Invalid config: {}", e))?;
+        config.validate()?;
+
+        let next = AppliedConfig { version, config };
+        if let Err(e) = (self.hook)(&next.config) {
+            self.rollback();
+            return Err(format!("Failed to apply config: {}", e));
+        }
+        if let Err(e) = save(&self.path, &next).await {
+            self.rollback();
+            return Err(format!("Failed to persist config: {}", e));
+        }
+
+        self.applied = next;
+        Ok(())
+    }
+
+    fn rollback(&self) {
+        if let Err(e) = (self.hook)(&self.applied.config) {
+            tracing::error!("❌ Failed to restore previous device config: {}", e);
+        }
+    }
+}
+
+/// Write atomically (temp file + rename).
+async fn save(path: &Path, applied: &AppliedConfig) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(applied)?).await?;
+    tokio::fs::rename(&tmp, path).await
+}
+
+/// RFC 7386 JSON merge patch: objects merge recursively, `null` removes a
+/// key, anything else replaces the target.
+fn merge_patch(target: &mut JsonValue, patch: &JsonValue) {
+    let JsonValue::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = JsonValue::Object(Default::default());
+    }
+    if let JsonValue::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(JsonValue::Null), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    /// Hook that records applied configs and fails for a `log_level` of "fail"
+    fn recording_hook() -> (ApplyHook, Arc<Mutex<Vec<CocoonConfig>>>) {
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let seen = applied.clone();
+        let hook: ApplyHook = Box::new(move |config: &CocoonConfig| {
+            seen.lock().unwrap().push(config.clone());
+            match config.log_level.as_deref() {
+                Some("fail") => Err("reload failed".to_string()),
+                _ => Ok(()),
+            }
+        });
+        (hook, applied)
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        let mut target = json!({ "a": 1, "b": { "c": 2, "d": 3 } });
+        merge_patch(
+            &mut target,
+            &json!({ "a": null, "b": { "c": 4 }, "e": [1] }),
+        );
+        assert_eq!(target, json!({ "b": { "c": 4, "d": 3 }, "e": [1] }));
+    }
+
+    #[tokio::test]
+    async fn test_apply_persists_and_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let (hook, _) = recording_hook();
+        let mut applier = ConfigApplier::load(&path, hook).await;
+        assert_eq!(applier.version(), 0);
+
+        applier
+            .apply(
+                1,
+                &json!({ "log_level": "debug", "feature_flags": { "silk": true } }),
+            )
+            .await
+            .unwrap();
+        applier
+            .apply(2, &json!({ "log_level": null }))
+            .await
+            .unwrap();
+        assert!(applier.config().feature_enabled("silk"));
+
+        let (hook, applied) = recording_hook();
+        let restarted = ConfigApplier::load(&path, hook).await;
+        assert_eq!(restarted.version(), 2);
+        assert_eq!(restarted.config().log_level, None);
+        assert_eq!(applied.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_and_stale_pushes_keep_current_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let (hook, applied) = recording_hook();
+        let mut applier = ConfigApplier::load(dir.path().join("config.json"), hook).await;
+        applier
+            .apply(5, &json!({ "log_level": "info" }))
+            .await
+            .unwrap();
+
+        assert!(applier.apply(6, &json!({ "unknown": 1 })).await.is_err());
+        assert!(applier
+            .apply(6, &json!({ "adi_quota": { "window_secs": 0 } }))
+            .await
+            .is_err());
+        assert!(applier
+            .apply(6, &json!({ "log_level": "[" }))
+            .await
+            .is_err());
+        assert!(applier
+            .apply(4, &json!({ "log_level": "warn" }))
+            .await
+            .is_err());
+        applier
+            .apply(5, &json!({ "log_level": "warn" }))
+            .await
+            .unwrap();
+
+        assert_eq!(applier.version(), 5);
+        assert_eq!(applier.config().log_level.as_deref(), Some("info"));
+        // Rejected before the hook ran
+        assert_eq!(applied.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_apply_rolls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let (hook, applied) = recording_hook();
+        let mut applier = ConfigApplier::load(dir.path().join("config.json"), hook).await;
+        applier
+            .apply(1, &json!({ "log_level": "debug" }))
+            .await
+            .unwrap();
+
+        let err = applier
+            .apply(2, &json!({ "log_level": "fail" }))
+            .await
+            .unwrap_err();
+        assert!(err.contains("reload failed"));
+        assert_eq!(applier.version(), 1);
+
+        let restored = applied.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(restored.log_level.as_deref(), Some("debug"));
+
+        // A config that cannot be persisted is rolled back as well
+        let mut applier =
+            ConfigApplier::load(dir.path().join("missing/config.json"), recording_hook().0).await;
+        assert!(applier
+            .apply(1, &json!({ "log_level": "debug" }))
+            .await
+            .is_err());
+        assert_eq!(applier.config().log_level, None);
+    }
+}
@@ -141,19 +141,19 @@ mod test_signaling {
                     send_msg(
                         &tx,
                         &SignalingMessage::DeviceRegisterResponse {
-                            device_id: derived_id,
+                            device_id: derived_id.into(),
                             tags: clean_tags,
                         },
                     );
                 }
 
-                SignalingMessage::SyncData { payload } => {
+                SignalingMessage::SyncData { payload, priority } => {
                     if let Some(ref did) = device_id {
                         if let Some(peer_id) = state.paired_devices.get(did) {
                             if let Some(peer_tx) = state.connections.get(peer_id.value()) {
                                 send_msg(
                                     peer_tx.value(),
-                                    &SignalingMessage::SyncData { payload },
+                                    &SignalingMessage::SyncData { payload, priority },
                                 );
                             }
                         }
@@ -421,6 +421,7 @@ async fn test_signaling_pairing_and_message_relay() {
         device_id: "device-b".to_string(),
         user_id: None,
         data_channels: Some(vec!["silk".to_string(), "adi".to_string()]),
+        delegated_token: None,
     })
     .unwrap();
 
@@ -428,6 +429,7 @@ async fn test_signaling_pairing_and_message_relay() {
         &mut sink_b,
         &SignalingMessage::SyncData {
             payload: test_payload.clone(),
+            priority: None,
         },
     )
     .await;
@@ -435,7 +437,7 @@ async fn test_signaling_pairing_and_message_relay() {
     // Cocoon A should receive the message
     let relayed = ws_recv(&mut stream_a).await;
     match relayed {
-        SignalingMessage::SyncData { payload } => {
+        SignalingMessage::SyncData { payload, .. } => {
             let msg: CocoonMessage = serde_json::from_value(payload).unwrap();
             match msg {
                 CocoonMessage::WebrtcStartSession {
@@ -551,7 +553,7 @@ async fn test_webrtc_silk_create_session_e2e() {
     let client_pc_for_ice = client_pc.clone();
     tokio::spawn(async move {
         while let Some(msg) = signaling_rx.recv().await {
-            if let SignalingMessage::SyncData { payload } = msg {
+            if let SignalingMessage::SyncData { payload, .. } = msg {
                 if let Ok(cocoon_msg) = serde_json::from_value::<CocoonMessage>(payload) {
                     if let CocoonMessage::WebrtcIceCandidate {
                         candidate,
@@ -735,7 +737,7 @@ async fn test_webrtc_adi_plugin_echo_e2e() {
     let client_pc_for_ice = client_pc.clone();
     tokio::spawn(async move {
         while let Some(msg) = signaling_rx.recv().await {
-            if let SignalingMessage::SyncData { payload } = msg {
+            if let SignalingMessage::SyncData { payload, .. } = msg {
                 if let Ok(cocoon_msg) = serde_json::from_value::<CocoonMessage>(payload) {
                     if let CocoonMessage::WebrtcIceCandidate {
                         candidate,
@@ -936,7 +938,7 @@ impl WebRtcTestHarness {
         let client_pc_for_ice = client_pc.clone();
         tokio::spawn(async move {
             while let Some(msg) = signaling_rx.recv().await {
-                if let SignalingMessage::SyncData { payload } = msg {
+                if let SignalingMessage::SyncData { payload, .. } = msg {
                     if let Ok(cocoon_msg) = serde_json::from_value::<CocoonMessage>(payload) {
                         if let CocoonMessage::WebrtcIceCandidate {
                             candidate,
@@ -2033,3 +2035,49 @@ async fn test_adi_large_payload_e2e() {
 
     harness.cleanup().await;
 }
+
+// ── Channel policies ──────────────────────────────────────────────────────
+
+/// Test 28: A channel opened with settings that break its label's policy is closed.
+#[tokio::test]
+async fn test_channel_policy_mismatch_closes_channel_e2e() {
+    use webrtc::data_channel::data_channel_init::RTCDataChannelInit;
+    use webrtc::data_channel::data_channel_state::RTCDataChannelState;
+
+    let harness = WebRtcTestHarness::new("policy-test", None).await;
+
+    let unordered = harness
+        .client_pc
+        .create_data_channel(
+            "silk",
+            Some(RTCDataChannelInit {
+                ordered: Some(false),
+                max_retransmits: Some(0),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+    let (closed_tx, closed_rx) = tokio::sync::oneshot::channel::<()>();
+    let closed_tx = Arc::new(Mutex::new(Some(closed_tx)));
+    unordered.on_close(Box::new(move || {
+        let tx = closed_tx.clone();
+        Box::pin(async move {
+            if let Some(tx) = tx.lock().await.take() {
+                let _ = tx.send(());
+            }
+        })
+    }));
+
+    tokio::time::timeout(std::time::Duration::from_secs(10), closed_rx)
+        .await
+        .expect("Cocoon did not close the unordered silk channel")
+        .expect("DC close sender dropped");
+
+    // The reliable channels opened by the harness are unaffected
+    assert_eq!(harness.silk_dc.ready_state(), RTCDataChannelState::Open);
+    assert_eq!(harness.adi_dc.ready_state(), RTCDataChannelState::Open);
+
+    harness.cleanup().await;
+}
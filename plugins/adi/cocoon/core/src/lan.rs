@@ -0,0 +1,313 @@
+//! Direct LAN access to a cocoon, bypassing the signaling relay
+//!
+//! When enabled (the default; `COCOON_LAN=false` turns it off) the cocoon runs
+//! two listeners next to its relay connection:
+//!
+//! - a UDP discovery responder on [`DISCOVERY_PORT`] that answers broadcast
+//!   `cocoon_probe` datagrams with a `cocoon_announce` naming the device and
+//!   its WebSocket port (`adi cocoon discover` uses this);
+//! - a WebSocket server on `COCOON_LAN_PORT` (default [`DEFAULT_LAN_PORT`]).
+//!
+//! Discovery never hands out credentials. Clients get the LAN token from a
+//! `lan_info` reply over the relay, which only lets owners reach the device,
+//! and present it in `lan_hello` as the first frame on the direct socket.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket as StdUdpSocket};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+
+use lib_env_parse::{env_bool_default_true, env_opt, env_vars};
+
+env_vars! {
+    CocoonLan => "COCOON_LAN",
+    CocoonLanPort => "COCOON_LAN_PORT",
+    CocoonLanAddress => "COCOON_LAN_ADDRESS",
+}
+
+/// UDP port cocoons listen on for discovery probes
+pub const DISCOVERY_PORT: u16 = 47820;
+
+/// Default port of the direct WebSocket server
+pub const DEFAULT_LAN_PORT: u16 = 47821;
+
+/// How long a direct connection may take to send `lan_hello`
+pub const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+const TOKEN_BYTES: usize = 32;
+const MAX_DATAGRAM: usize = 2048;
+
+/// Whether LAN discovery and direct connections are enabled ($COCOON_LAN)
+pub fn lan_enabled() -> bool {
+    env_bool_default_true(EnvVar::CocoonLan.as_str())
+}
+
+/// Direct WebSocket port ($COCOON_LAN_PORT or 47821)
+pub fn lan_port() -> u16 {
+    env_opt(EnvVar::CocoonLanPort.as_str())
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_LAN_PORT)
+}
+
+/// Addresses clients should dial for the direct path
+///
+/// `COCOON_LAN_ADDRESS` (comma-separated) wins, which is what containers need
+/// since their own interface is not reachable from the LAN. Otherwise this is
+/// the address of the interface holding the default route.
+pub fn lan_addresses() -> Vec<String> {
+    if let Some(configured) = env_opt(EnvVar::CocoonLanAddress.as_str()) {
+        return configured
+            .split(',')
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect();
+    }
+
+    primary_address()
+        .map(|ip| vec![ip.to_string()])
+        .unwrap_or_default()
+}
+
+/// Local address of the default route; connecting a UDP socket sends nothing
+fn primary_address() -> Option<IpAddr> {
+    let socket = StdUdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+/// Credentials and endpoint of the direct WebSocket server
+pub struct LanAccess {
+    token: String,
+    pub port: u16,
+}
+
+impl LanAccess {
+    /// Fresh token per process; clients fetch it again after a restart
+    pub fn new(port: u16) -> Self {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        rand::rng().fill(&mut bytes[..]);
+        let token = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        Self { token, port }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Constant-time token check
+    pub fn verify(&self, token: &str) -> bool {
+        let (a, b) = (self.token.as_bytes(), token.as_bytes());
+        a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+    }
+}
+
+/// Datagrams exchanged on the discovery port
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DiscoveryMessage {
+    CocoonProbe { nonce: String },
+    CocoonAnnounce(Announce),
+}
+
+/// A cocoon's answer to a probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Announce {
+    pub nonce: String,
+    pub device_id: Option<String>,
+    pub name: Option<String>,
+    pub version: String,
+    /// Direct WebSocket port, on the address the announce came from
+    pub port: u16,
+}
+
+/// Identity reported in announces; the device id appears once registered
+pub struct DiscoveryIdentity {
+    pub device_id: Arc<Mutex<Option<String>>>,
+    pub name: Option<String>,
+    pub port: u16,
+}
+
+/// Bind the discovery port and answer probes until the socket fails
+pub async fn run_discovery_responder(identity: DiscoveryIdentity) {
+    match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT)).await {
+        Ok(socket) => {
+            tracing::info!("📡 LAN discovery listening on udp/{}", DISCOVERY_PORT);
+            if let Err(e) = serve_discovery(socket, identity).await {
+                tracing::warn!("⚠️ LAN discovery stopped: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!(
+            "⚠️ LAN discovery disabled, cannot bind udp/{}: {}",
+            DISCOVERY_PORT,
+            e
+        ),
+    }
+}
+
+/// Answer probes arriving on `socket`
+pub async fn serve_discovery(socket: UdpSocket, identity: DiscoveryIdentity) -> io::Result<()> {
+    let mut buf = [0u8; MAX_DATAGRAM];
+    loop {
+        let (len, from) = socket.recv_from(&mut buf).await?;
+        let Ok(DiscoveryMessage::CocoonProbe { nonce }) = serde_json::from_slice(&buf[..len])
+        else {
+            continue;
+        };
+
+        let announce = DiscoveryMessage::CocoonAnnounce(Announce {
+            nonce,
+            device_id: identity.device_id.lock().await.clone(),
+            name: identity.name.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            port: identity.port,
+        });
+        let reply = serde_json::to_vec(&announce).expect("announce serialization cannot fail");
+        if let Err(e) = socket.send_to(&reply, from).await {
+            tracing::debug!("LAN discovery reply to {} failed: {}", from, e);
+        }
+    }
+}
+
+/// A cocoon that answered a probe
+#[derive(Debug, Clone)]
+pub struct DiscoveredCocoon {
+    pub announce: Announce,
+    /// Address the announce came from
+    pub ip: IpAddr,
+    /// Probe round trip, a rough measure of how close the cocoon is
+    pub rtt: Duration,
+}
+
+impl DiscoveredCocoon {
+    /// URL of the cocoon's direct WebSocket server
+    pub fn ws_url(&self) -> String {
+        match self.ip {
+            IpAddr::V4(ip) => format!("ws://{}:{}", ip, self.announce.port),
+            IpAddr::V6(ip) => format!("ws://[{}]:{}", ip, self.announce.port),
+        }
+    }
+}
+
+/// Broadcast a probe on the local network and collect answers for `timeout`
+pub async fn discover(timeout: Duration) -> io::Result<Vec<DiscoveredCocoon>> {
+    discover_at(
+        &[SocketAddr::from((Ipv4Addr::BROADCAST, DISCOVERY_PORT))],
+        timeout,
+    )
+    .await
+}
+
+/// Probe the given addresses and collect answers for `timeout`, fastest first
+pub async fn discover_at(
+    targets: &[SocketAddr],
+    timeout: Duration,
+) -> io::Result<Vec<DiscoveredCocoon>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.set_broadcast(true)?;
+
+    let nonce = uuid::Uuid::new_v4().to_string();
+    let probe = serde_json::to_vec(&DiscoveryMessage::CocoonProbe {
+        nonce: nonce.clone(),
+    })
+    .expect("probe serialization cannot fail");
+
+    let sent_at = Instant::now();
+    for target in targets {
+        socket.send_to(&probe, target).await?;
+    }
+
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    let mut buf = [0u8; MAX_DATAGRAM];
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (len, from) = received?;
+        let Ok(DiscoveryMessage::CocoonAnnounce(announce)) = serde_json::from_slice(&buf[..len])
+        else {
+            continue;
+        };
+        if announce.nonce != nonce {
+            continue;
+        }
+
+        let key = announce
+            .device_id
+            .clone()
+            .unwrap_or_else(|| format!("{}:{}", from.ip(), announce.port));
+        if seen.insert(key) {
+            found.push(DiscoveredCocoon {
+                announce,
+                ip: from.ip(),
+                rtt: sent_at.elapsed(),
+            });
+        }
+    }
+
+    found.sort_by_key(|c| c.rtt);
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_verification() {
+        let access = LanAccess::new(DEFAULT_LAN_PORT);
+        assert_eq!(access.token().len(), TOKEN_BYTES * 2);
+        assert!(access.verify(access.token()));
+        assert!(!access.verify(""));
+        assert!(!access.verify(&access.token()[1..]));
+        assert!(!LanAccess::new(DEFAULT_LAN_PORT).verify(access.token()));
+    }
+
+    #[test]
+    fn test_discovery_message_format() {
+        let probe: DiscoveryMessage =
+            serde_json::from_str(r#"{"type":"cocoon_probe","nonce":"n1"}"#).unwrap();
+        assert!(matches!(probe, DiscoveryMessage::CocoonProbe { nonce } if nonce == "n1"));
+
+        let announce = DiscoveryMessage::CocoonAnnounce(Announce {
+            nonce: "n1".to_string(),
+            device_id: Some("dev".to_string()),
+            name: None,
+            version: "1.0.0".to_string(),
+            port: 47821,
+        });
+        let json = serde_json::to_value(&announce).unwrap();
+        assert_eq!(json["type"], "cocoon_announce");
+        assert_eq!(json["device_id"], "dev");
+        assert_eq!(json["port"], 47821);
+    }
+
+    #[tokio::test]
+    async fn test_discover_finds_responder() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(serve_discovery(
+            socket,
+            DiscoveryIdentity {
+                device_id: Arc::new(Mutex::new(Some("device-1".to_string()))),
+                name: Some("worker".to_string()),
+                port: 9999,
+            },
+        ));
+
+        // Duplicate answers from the same device are collapsed
+        let found = discover_at(&[addr, addr], Duration::from_millis(300))
+            .await
+            .unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].announce.device_id.as_deref(), Some("device-1"));
+        assert_eq!(found[0].announce.name.as_deref(), Some("worker"));
+        assert_eq!(found[0].ws_url(), "ws://127.0.0.1:9999");
+    }
+}
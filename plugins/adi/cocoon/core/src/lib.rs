@@ -12,6 +12,7 @@ impl Default for protocol::types::AdiMethodInfo {
             result_schema: None,
             deprecated: None,
             deprecated_message: None,
+            skip_params_validation: None,
         }
     }
 }
@@ -27,22 +28,57 @@ impl Default for protocol::types::AdiPluginCapabilities {
 }
 
 pub mod adi_frame;
+pub mod adi_params;
 pub mod adi_router;
+pub mod adi_usage;
+pub mod channel_policy;
+mod config_push;
 mod core;
+pub mod delegation;
+pub mod device_config;
 pub mod filesystem;
 mod interactive;
+pub mod lan;
+#[cfg(feature = "linter-core")]
+mod live_lint;
+pub mod log_shipper;
+mod ownership_history;
+mod peer_link;
+mod peer_sessions;
+pub mod plugin_catalog;
+mod port_forward;
+pub mod recording;
+mod registration;
+#[cfg(feature = "chaos")]
+pub mod relay_chaos;
+mod relay_queue;
+mod remote_exec;
+mod remote_forward;
 mod runtime;
 mod self_update;
+mod service_logs;
 mod setup;
+mod share;
 pub mod silk;
+mod sub_relay;
 pub mod webrtc;
 
 pub use adi_router::{
     create_stream_channel, AdiCallerContext, AdiHandleResult, AdiRouter, AdiService,
     AdiServiceError, StreamSender,
 };
+pub use adi_usage::{OverageAction, QuotaPolicy, UsageMeter, UsageService};
+pub use channel_policy::{ChannelPolicies, ChannelPolicy, ChannelPriority};
+pub use config_push::{run_config_push, ConfigApplyReport, ConfigPushOutcome, ConfigPushRequest};
 pub use core::run;
+pub use ownership_history::{
+    run_ownership_history, HistoryRequest, OwnershipAction, OwnershipAuditEvent, OwnershipTokenType,
+};
+pub use port_forward::ForwardSpec;
+pub use remote_exec::{run_exec, DeviceExit, ExecRequest, ExecSessionCache, ExecTarget};
+pub use remote_forward::{run_forward, ForwardRequest};
 pub use runtime::{CocoonInfo, CocoonStatus, Runtime, RuntimeManager, RuntimeType};
+pub use share::{parse_ttl, run_revoke, run_share, DelegatedGrant, ShareRequest};
 pub use silk::{AnsiToHtml, SilkSession};
 pub use webrtc::WebRtcManager;
 
@@ -0,0 +1,63 @@
+//! Lint diagnostics of a hosted project, streamed to browser consoles
+//!
+//! With `COCOON_LINT_ROOT=<dir>` the cocoon re-lints that project every
+//! `COCOON_LINT_INTERVAL` seconds (default 30) and publishes each new
+//! diagnostic as an `AdiNotification::ServiceEvent` on service `linter`,
+//! event `diagnostic`. Web clients receive them by sending an ADI
+//! `subscribe` for plugin `linter`. Every diagnostic carries an editor deep
+//! link built from `COCOON_LINT_EDITOR_LINK` (default
+//! [`linter_core::live::DEFAULT_EDITOR_LINK`]).
+
+use crate::adi_router::{AdiNotification, AdiRouter};
+use linter_core::live::{LiveDiagnostics, DIAGNOSTIC_EVENT, LIVE_SERVICE};
+use linter_core::{LinterConfig, Runner};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use lib_env_parse::{env_opt, env_vars};
+
+env_vars! {
+    CocoonLintRoot => "COCOON_LINT_ROOT",
+    CocoonLintInterval => "COCOON_LINT_INTERVAL",
+    CocoonLintEditorLink => "COCOON_LINT_EDITOR_LINK",
+}
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Project to lint ($COCOON_LINT_ROOT); unset disables live linting
+pub fn lint_root() -> Option<PathBuf> {
+    env_opt(EnvVar::CocoonLintRoot.as_str()).map(PathBuf::from)
+}
+
+/// Register the `linter` event source and start linting `root` in the
+/// background.
+pub fn start(router: &mut AdiRouter, root: PathBuf) -> anyhow::Result<()> {
+    let config = LinterConfig::load_from_project(&root)?;
+    let runner = Runner::new(config.build_registry()?, config.runner_config(&root));
+
+    let interval = env_opt(EnvVar::CocoonLintInterval.as_str())
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_INTERVAL);
+    let mut live = LiveDiagnostics::new(root.clone());
+    if let Some(template) = env_opt(EnvVar::CocoonLintEditorLink.as_str()) {
+        live = live.editor_link(template);
+    }
+
+    router.register_event_source(LIVE_SERVICE);
+    let notifications = router.notification_sender();
+    tokio::spawn(live.watch(runner, interval, move |diagnostic| {
+        let Ok(data) = serde_json::to_value(&diagnostic) else {
+            return;
+        };
+        // No receivers just means no console is watching
+        let _ = notifications.send(AdiNotification::ServiceEvent {
+            service: LIVE_SERVICE.to_string(),
+            event: DIAGNOSTIC_EVENT.to_string(),
+            data,
+        });
+    }));
+
+    Ok(())
+}
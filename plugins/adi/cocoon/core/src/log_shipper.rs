@@ -0,0 +1,438 @@
+//! Throttling for service logs relayed upstream.
+//!
+//! A chatty service must not flood the signaling relay connection, so every
+//! line passes through a [`LogShipper`] before it is queued on the relay.
+//! Per service, in order:
+//!
+//! - **Always-pass**: lines at or above `always_pass` are forwarded untouched
+//! - **Duplicates**: consecutive identical lines collapse into "repeated N times"
+//! - **Sampling**: only `sample_rate` of the remaining lines are kept
+//! - **Rate limit**: token bucket; dropped lines are reported once tokens return
+
+use lib_hive_daemon_client::LogLine;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// Key in the per-service config map that applies to unlisted services
+pub const DEFAULT_SERVICE: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Notice,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(Self::Trace),
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "notice" => Ok(Self::Notice),
+            "warn" | "warning" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            "fatal" => Ok(Self::Fatal),
+            _ => Err(format!("Unknown log level '{}'", s)),
+        }
+    }
+}
+
+/// Throttling applied to one service's logs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogShippingConfig {
+    /// Fraction of lines forwarded, from 0.0 to 1.0
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+    /// `null` disables rate limiting
+    #[serde(default = "default_rate_limit")]
+    pub rate_limit: Option<LogRateLimit>,
+    /// Collapse consecutive identical lines into "repeated N times"
+    #[serde(default = "default_true")]
+    pub collapse_duplicates: bool,
+    /// Lines at or above this level skip every other rule
+    #[serde(default = "default_always_pass")]
+    pub always_pass: LogLevel,
+}
+
+impl Default for LogShippingConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: default_sample_rate(),
+            rate_limit: default_rate_limit(),
+            collapse_duplicates: true,
+            always_pass: default_always_pass(),
+        }
+    }
+}
+
+impl LogShippingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.sample_rate) {
+            return Err(format!(
+                "Invalid sample_rate {}: must be between 0 and 1",
+                self.sample_rate
+            ));
+        }
+        if let Some(ref limit) = self.rate_limit {
+            if limit.lines_per_sec == 0 || limit.burst == 0 {
+                return Err("Invalid rate_limit: lines_per_sec and burst must be positive".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Token bucket: `burst` lines at once, refilled at `lines_per_sec`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogRateLimit {
+    pub lines_per_sec: u32,
+    pub burst: u32,
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
+}
+
+fn default_rate_limit() -> Option<LogRateLimit> {
+    Some(LogRateLimit {
+        lines_per_sec: 200,
+        burst: 1000,
+    })
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_always_pass() -> LogLevel {
+    LogLevel::Warn
+}
+
+pub struct LogShipper {
+    configs: BTreeMap<String, LogShippingConfig>,
+    default_config: LogShippingConfig,
+    states: HashMap<String, ShipperState>,
+}
+
+struct ShipperState {
+    tokens: f64,
+    last_refill: Instant,
+    sample_credit: f64,
+    /// Last forwarded line, while nothing else has been seen since
+    last_line: Option<LogLine>,
+    repeats: u64,
+    rate_limited: u64,
+}
+
+impl ShipperState {
+    fn new(config: &LogShippingConfig, now: Instant) -> Self {
+        Self {
+            tokens: config
+                .rate_limit
+                .as_ref()
+                .map(|r| r.burst as f64)
+                .unwrap_or(0.0),
+            last_refill: now,
+            sample_credit: 0.0,
+            last_line: None,
+            repeats: 0,
+            rate_limited: 0,
+        }
+    }
+
+    /// Summary for the pending run of duplicates, if any
+    fn take_repeats(&mut self) -> Option<LogLine> {
+        if self.repeats == 0 {
+            return None;
+        }
+        let last = self.last_line.as_ref()?;
+        let summary = annotate(
+            last,
+            format!("last message repeated {}", plural(self.repeats, "time")),
+        );
+        self.repeats = 0;
+        Some(summary)
+    }
+
+    /// Summary of lines the rate limit dropped, reported ahead of `line`
+    fn take_rate_limited(&mut self, line: &LogLine) -> Option<LogLine> {
+        if self.rate_limited == 0 {
+            return None;
+        }
+        let summary = annotate(
+            line,
+            format!(
+                "{} dropped by rate limit",
+                plural(self.rate_limited, "line")
+            ),
+        );
+        self.rate_limited = 0;
+        Some(summary)
+    }
+
+    fn sample(&mut self, config: &LogShippingConfig) -> bool {
+        if config.sample_rate >= 1.0 {
+            return true;
+        }
+        self.sample_credit += config.sample_rate.max(0.0);
+        if self.sample_credit >= 1.0 {
+            self.sample_credit -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_token(&mut self, config: &LogShippingConfig, now: Instant) -> bool {
+        let Some(limit) = &config.rate_limit else {
+            return true;
+        };
+
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.lines_per_sec as f64).min(limit.burst as f64);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl LogShipper {
+    /// Shipper with per-service configs keyed by FQN (source:service);
+    /// [`DEFAULT_SERVICE`] covers services without their own entry.
+    pub fn new(configs: BTreeMap<String, LogShippingConfig>) -> Self {
+        let default_config = configs.get(DEFAULT_SERVICE).cloned().unwrap_or_default();
+        Self {
+            configs,
+            default_config,
+            states: HashMap::new(),
+        }
+    }
+
+    /// Lines to forward for `line`: none, the line itself, and/or summaries
+    /// of lines collapsed or dropped before it.
+    pub fn ship(&mut self, line: LogLine) -> Vec<LogLine> {
+        self.ship_at(line, Instant::now())
+    }
+
+    fn ship_at(&mut self, line: LogLine, now: Instant) -> Vec<LogLine> {
+        let config = self
+            .configs
+            .get(&line.service_fqn)
+            .unwrap_or(&self.default_config);
+        let state = self
+            .states
+            .entry(line.service_fqn.clone())
+            .or_insert_with(|| ShipperState::new(config, now));
+        let level = line.level.parse().unwrap_or(LogLevel::Info);
+
+        let duplicate = config.collapse_duplicates
+            && level < config.always_pass
+            && state
+                .last_line
+                .as_ref()
+                .is_some_and(|last| last.level == line.level && last.message == line.message);
+        if duplicate {
+            state.repeats += 1;
+            return Vec::new();
+        }
+
+        let mut out: Vec<LogLine> = state.take_repeats().into_iter().collect();
+        state.last_line = None;
+
+        if level < config.always_pass {
+            if !state.sample(config) {
+                return out;
+            }
+            if !state.take_token(config, now) {
+                state.rate_limited += 1;
+                return out;
+            }
+        }
+
+        out.extend(state.take_rate_limited(&line));
+        if config.collapse_duplicates {
+            state.last_line = Some(line.clone());
+        }
+        out.push(line);
+        out
+    }
+
+    /// Summaries for duplicate runs still pending; call periodically so a
+    /// service that goes quiet after repeating itself still reports the count.
+    pub fn flush(&mut self) -> Vec<LogLine> {
+        self.states
+            .values_mut()
+            .filter_map(|state| state.take_repeats())
+            .collect()
+    }
+}
+
+fn plural(n: u64, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+fn annotate(line: &LogLine, message: String) -> LogLine {
+    LogLine {
+        timestamp: line.timestamp,
+        level: line.level.clone(),
+        service_fqn: line.service_fqn.clone(),
+        message,
+        fields: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::time::Duration;
+
+    fn line(level: &str, message: &str) -> LogLine {
+        LogLine {
+            timestamp: Utc::now(),
+            level: level.to_string(),
+            service_fqn: "test:service".to_string(),
+            message: message.to_string(),
+            fields: None,
+        }
+    }
+
+    fn messages(lines: Vec<LogLine>) -> Vec<String> {
+        lines.into_iter().map(|l| l.message).collect()
+    }
+
+    fn shipper(config: LogShippingConfig) -> LogShipper {
+        LogShipper::new(BTreeMap::from([(DEFAULT_SERVICE.to_string(), config)]))
+    }
+
+    #[test]
+    fn test_collapse_duplicates() {
+        let mut shipper = shipper(LogShippingConfig::default());
+
+        assert_eq!(messages(shipper.ship(line("info", "tick"))), ["tick"]);
+        assert!(shipper.ship(line("info", "tick")).is_empty());
+        assert!(shipper.ship(line("info", "tick")).is_empty());
+        assert_eq!(
+            messages(shipper.ship(line("info", "tock"))),
+            ["last message repeated 2 times", "tock"]
+        );
+
+        shipper.ship(line("info", "tock"));
+        assert_eq!(messages(shipper.flush()), ["last message repeated 1 time"]);
+        assert!(shipper.flush().is_empty());
+    }
+
+    #[test]
+    fn test_always_pass_is_never_collapsed() {
+        let mut shipper = shipper(LogShippingConfig::default());
+
+        assert_eq!(messages(shipper.ship(line("error", "boom"))), ["boom"]);
+        assert_eq!(messages(shipper.ship(line("error", "boom"))), ["boom"]);
+    }
+
+    #[test]
+    fn test_sampled_out_lines_do_not_start_a_run() {
+        let mut shipper = shipper(LogShippingConfig {
+            sample_rate: 0.5,
+            rate_limit: None,
+            collapse_duplicates: true,
+            always_pass: LogLevel::Error,
+        });
+
+        // The first line is sampled out, so the second is not its duplicate
+        assert!(shipper.ship(line("info", "tick")).is_empty());
+        assert_eq!(messages(shipper.ship(line("info", "tick"))), ["tick"]);
+    }
+
+    #[test]
+    fn test_sampling_and_always_pass() {
+        let mut shipper = shipper(LogShippingConfig {
+            sample_rate: 0.25,
+            rate_limit: None,
+            collapse_duplicates: false,
+            always_pass: LogLevel::Error,
+        });
+
+        let kept: usize = (0..100)
+            .map(|i| shipper.ship(line("info", &format!("line {}", i))).len())
+            .sum();
+        assert_eq!(kept, 25);
+
+        let errors: usize = (0..10)
+            .map(|i| shipper.ship(line("error", &format!("boom {}", i))).len())
+            .sum();
+        assert_eq!(errors, 10);
+    }
+
+    #[test]
+    fn test_rate_limit_reports_dropped() {
+        let mut shipper = shipper(LogShippingConfig {
+            sample_rate: 1.0,
+            rate_limit: Some(LogRateLimit {
+                lines_per_sec: 10,
+                burst: 5,
+            }),
+            collapse_duplicates: false,
+            always_pass: LogLevel::Warn,
+        });
+        let start = Instant::now();
+
+        let shipped: usize = (0..20)
+            .map(|i| {
+                shipper
+                    .ship_at(line("info", &format!("line {}", i)), start)
+                    .len()
+            })
+            .sum();
+        assert_eq!(shipped, 5);
+
+        // Warnings are never throttled
+        assert_eq!(
+            messages(shipper.ship_at(line("warn", "careful"), start)),
+            ["15 lines dropped by rate limit", "careful"]
+        );
+
+        let later = start + Duration::from_secs(1);
+        assert_eq!(
+            messages(shipper.ship_at(line("info", "back"), later)),
+            ["back"]
+        );
+    }
+
+    #[test]
+    fn test_per_service_config() {
+        let mut shipper = LogShipper::new(BTreeMap::from([(
+            "test:service".to_string(),
+            LogShippingConfig {
+                sample_rate: 0.0,
+                ..LogShippingConfig::default()
+            },
+        )]));
+
+        assert!(shipper.ship(line("info", "hidden")).is_empty());
+        let mut other = line("info", "shown");
+        other.service_fqn = "test:other".to_string();
+        assert_eq!(messages(shipper.ship(other)), ["shown"]);
+    }
+}
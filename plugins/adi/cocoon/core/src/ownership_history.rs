@@ -0,0 +1,113 @@
+//! Ownership audit log of a device (`adi cocoon history`).
+//!
+//! Signaling keeps a per-device record of claims, transfers and removals and
+//! answers only current and past owners.
+
+use crate::remote_exec::{authenticate, send};
+use futures::StreamExt;
+use lib_signaling_protocol::{DeviceId, DeviceInfo, SignalingMessage};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+pub use lib_signaling_protocol::{OwnershipAction, OwnershipAuditEvent, OwnershipTokenType};
+
+/// How long signaling may take to answer the history query.
+const HISTORY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct HistoryRequest {
+    pub signaling_url: String,
+    pub access_token: String,
+    /// Device id, or a unique prefix of one of the caller's devices
+    pub device: String,
+}
+
+/// Fetch the audit log of `request.device`, oldest first.
+pub async fn run_ownership_history(
+    request: HistoryRequest,
+) -> Result<(DeviceId, Vec<OwnershipAuditEvent>), String> {
+    let (ws, _) = tokio_tungstenite::connect_async(&request.signaling_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", request.signaling_url, e))?;
+    let (mut sink, mut stream) = ws.split();
+
+    let devices = authenticate(&mut sink, &mut stream, &request.access_token).await?;
+    let device_id = resolve_device_id(&devices, &request.device)?;
+    send(
+        &mut sink,
+        &SignalingMessage::DeviceOwnershipHistory {
+            device_id: device_id.clone(),
+        },
+    )
+    .await?;
+
+    loop {
+        let next = tokio::time::timeout(HISTORY_TIMEOUT, stream.next())
+            .await
+            .map_err(|_| "Timed out waiting for the signaling server".to_string())?;
+        let text = match next {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(format!("Signaling connection failed: {}", e)),
+            None => return Err("Signaling server closed the connection".to_string()),
+        };
+
+        match serde_json::from_str::<SignalingMessage>(&text) {
+            Ok(SignalingMessage::DeviceOwnershipHistoryResponse {
+                device_id: id,
+                events,
+            }) if id == device_id => return Ok((device_id, events)),
+            Ok(SignalingMessage::SystemError { message }) => return Err(message),
+            _ => {}
+        }
+    }
+}
+
+/// Full id of one of the caller's devices by id or unique prefix. Devices
+/// the caller no longer owns are not listed, so anything else is passed
+/// through as a full id.
+pub(crate) fn resolve_device_id(devices: &[DeviceInfo], id: &str) -> Result<DeviceId, String> {
+    if devices.iter().any(|d| d.device_id == id) {
+        return Ok(id.into());
+    }
+    let matches: Vec<_> = devices
+        .iter()
+        .filter(|d| d.device_id.starts_with(id))
+        .collect();
+    match matches.as_slice() {
+        [device] => Ok(device.device_id.clone()),
+        [] => Ok(id.into()),
+        _ => Err(format!(
+            "Device id '{}' is ambiguous ({} matches)",
+            id,
+            matches.len()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn device(id: &str) -> DeviceInfo {
+        DeviceInfo {
+            device_id: id.into(),
+            tags: HashMap::new(),
+            online: false,
+            device_type: None,
+            device_config: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_device_id() {
+        let devices = vec![device("abc123"), device("abd456")];
+
+        assert_eq!(resolve_device_id(&devices, "abc").unwrap(), "abc123");
+        assert_eq!(resolve_device_id(&devices, "abd456").unwrap(), "abd456");
+        // Past devices are not listed and go through unchanged
+        assert_eq!(resolve_device_id(&devices, "ffff").unwrap(), "ffff");
+        assert!(resolve_device_id(&devices, "ab").is_err());
+    }
+}
@@ -0,0 +1,706 @@
+//! Direct WebRTC sessions to other cocoons of the same owner.
+//!
+//! Capability traffic between cocoons starts out on the signaling relay.
+//! Once a relayed request is answered, the requester opens a peer connection
+//! to the device that answered; either side may start one. Negotiation
+//! reuses the `webrtc_*` messages inside the relay's cocoon envelope
+//! (`{ "to": <device_id>, "data": <message> }` out,
+//! `{ "from": <device_id>, "data": <message> }` in), and capability messages
+//! then travel as JSON text on the session's [`CAPABILITY_CHANNEL`].
+//! [`PeerSessions`] decides which path each message takes, so requests keep
+//! working through the relay while a session is negotiating or after it
+//! drops.
+
+use crate::adi_router::{AdiCallerContext, AdiRouter};
+use crate::peer_sessions::{PeerSessionState, PeerSessions, PeerStart, Route, CAPABILITY_CHANNEL};
+use crate::protocol::messages::CocoonMessage;
+use crate::relay_queue::RelaySender;
+use crate::webrtc::build_ice_servers;
+use lib_signaling_protocol::{
+    Capability, CapabilityMismatch, RelayPriority, RequestId, SignalingMessage,
+};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::APIBuilder;
+use webrtc::data_channel::data_channel_message::DataChannelMessage;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::interceptor::registry::Registry;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+
+/// How long a capability request may wait for its response.
+const CAPABILITY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a session may negotiate before capability traffic gives up on it.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long to wait before trying a peer again after an attempt.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+type RouterHandle = Arc<tokio::sync::Mutex<AdiRouter>>;
+
+/// Peer connection of one session
+struct PeerConnection {
+    peer: Arc<RTCPeerConnection>,
+    channel: Option<Arc<RTCDataChannel>>,
+    /// Remote description is set; candidates before that wait in `candidates`
+    described: bool,
+    candidates: Vec<RTCIceCandidateInit>,
+}
+
+/// A capability request waiting for its response
+struct PendingRequest {
+    capability: Capability,
+    tx: oneshot::Sender<SignalingMessage>,
+}
+
+pub struct PeerLink {
+    sessions: Mutex<PeerSessions>,
+    /// By session id
+    connections: Mutex<HashMap<String, PeerConnection>>,
+    /// Device that last answered each requested capability
+    providers: Mutex<HashMap<(String, String), String>>,
+    attempts: Mutex<HashMap<String, Instant>>,
+    pending: Mutex<HashMap<RequestId, PendingRequest>>,
+    writer: RelaySender,
+    adi_router: RouterHandle,
+}
+
+impl PeerLink {
+    pub fn new(writer: RelaySender, adi_router: RouterHandle) -> Arc<Self> {
+        Arc::new(Self {
+            // Replaced once signaling assigns the device id
+            sessions: Mutex::new(PeerSessions::new("")),
+            connections: Mutex::new(HashMap::new()),
+            providers: Mutex::new(HashMap::new()),
+            attempts: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+            writer,
+            adi_router,
+        })
+    }
+
+    /// Registration confirmed as `device_id`. Sessions survive reconnects
+    /// under the same id; a new id starts over.
+    pub async fn registered(&self, device_id: &str) {
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            if sessions.device_id() == device_id {
+                return;
+            }
+            *sessions = PeerSessions::new(device_id);
+        }
+        let closed: Vec<_> = self.connections.lock().unwrap().drain().collect();
+        for (_, connection) in closed {
+            let _ = connection.peer.close().await;
+        }
+    }
+
+    /// Send a capability request and wait for its answer: the response
+    /// payload and the device that served it.
+    ///
+    /// The request goes straight to `prefer_device`, or to the device that
+    /// last served the capability, when a session to it is open; otherwise
+    /// signaling routes it.
+    pub async fn request(
+        self: &Arc<Self>,
+        request_id: RequestId,
+        capability: Capability,
+        payload: JsonValue,
+        prefer_device: Option<String>,
+    ) -> Result<(JsonValue, Option<String>), String> {
+        let peer = prefer_device.clone().or_else(|| {
+            let key = (capability.protocol.clone(), capability.version.clone());
+            self.providers.lock().unwrap().get(&key).cloned()
+        });
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(
+            request_id.clone(),
+            PendingRequest {
+                capability: capability.clone(),
+                tx,
+            },
+        );
+
+        let request = SignalingMessage::CapabilityRequest {
+            request_id: request_id.clone(),
+            capability,
+            payload,
+            prefer_device,
+            from_device: None,
+        };
+        match peer {
+            Some(peer) => self.send_to(&peer, request).await,
+            None => self.relay(&request),
+        }
+
+        let answer = tokio::time::timeout(CAPABILITY_TIMEOUT, rx).await;
+        self.pending.lock().unwrap().remove(&request_id);
+        self.sessions.lock().unwrap().complete(&request_id);
+        match answer {
+            Ok(Ok(SignalingMessage::CapabilityResponse {
+                payload,
+                error: None,
+                from_device,
+                ..
+            })) => Ok((payload, from_device)),
+            Ok(Ok(SignalingMessage::CapabilityResponse { error: Some(e), .. })) => Err(e),
+            Ok(Ok(SignalingMessage::CapabilityUnavailable {
+                reason,
+                closest_match,
+                ..
+            })) => Err(format!(
+                "Capability unavailable: {}",
+                CapabilityMismatch {
+                    reason,
+                    closest_match
+                }
+            )),
+            Ok(_) => Err("Capability request dropped".to_string()),
+            Err(_) => Err("Timed out waiting for the capability response".to_string()),
+        }
+    }
+
+    /// Hand a `capability_response` or `capability_unavailable` to the
+    /// request waiting for it. A device answering through the relay is
+    /// offered a direct session for the next request.
+    pub fn deliver(self: &Arc<Self>, msg: SignalingMessage) {
+        let request_id = match &msg {
+            SignalingMessage::CapabilityResponse { request_id, .. }
+            | SignalingMessage::CapabilityUnavailable { request_id, .. } => request_id.clone(),
+            _ => return,
+        };
+        self.sessions.lock().unwrap().complete(&request_id);
+        let Some(pending) = self.pending.lock().unwrap().remove(&request_id) else {
+            tracing::debug!("Capability response {} has no waiting request", request_id);
+            return;
+        };
+
+        if let SignalingMessage::CapabilityResponse {
+            error: None,
+            from_device: Some(device),
+            ..
+        } = &msg
+        {
+            let key = (pending.capability.protocol, pending.capability.version);
+            self.providers.lock().unwrap().insert(key, device.clone());
+            self.connect(device);
+        }
+        let _ = pending.tx.send(msg);
+    }
+
+    /// Answer a capability request from another cocoon on the path it came
+    /// by. Relayed requests are answered through the relay, which has the
+    /// route back; direct ones on the session, and dropped if it ended, since
+    /// the requester then sends them again through the relay.
+    pub async fn serve(self: &Arc<Self>, request: SignalingMessage, direct: bool) {
+        let SignalingMessage::CapabilityRequest {
+            request_id,
+            capability,
+            payload,
+            from_device,
+            ..
+        } = request
+        else {
+            return;
+        };
+        let ctx = AdiCallerContext {
+            user_id: None,
+            device_id: from_device.clone(),
+        };
+        let result = self
+            .adi_router
+            .lock()
+            .await
+            .handle_capability(&ctx, &capability.protocol, &payload)
+            .await;
+        let (payload, error) = match result {
+            Ok(payload) => (payload, None),
+            Err(e) => (JsonValue::Null, Some(format!("{}: {}", e.code, e.message))),
+        };
+        let response = SignalingMessage::CapabilityResponse {
+            request_id,
+            payload,
+            error,
+            from_device: None,
+        };
+
+        match from_device {
+            Some(peer) if direct => {
+                let route = self.sessions.lock().unwrap().route(&peer, response);
+                match route {
+                    Route::Direct { session_id, data } => self.send_direct(&session_id, data).await,
+                    Route::Relay(_) => {
+                        tracing::debug!("Session to {} ended before its request was answered", peer)
+                    }
+                }
+            }
+            _ => self.relay(&response),
+        }
+    }
+
+    /// Signaling from another cocoon, in the order it arrived
+    pub async fn handle_signal(self: &Arc<Self>, from: &str, msg: CocoonMessage) {
+        match msg {
+            CocoonMessage::WebrtcStartSession { session_id, .. } => {
+                let start = self
+                    .sessions
+                    .lock()
+                    .unwrap()
+                    .accept_start(from, session_id.clone());
+                let PeerStart::Accept { replaces, resend } = start else {
+                    tracing::debug!("Both ends started a session with {}, keeping ours", from);
+                    return;
+                };
+                if let Some(replaced) = replaces {
+                    self.close(&replaced, "replaced", false).await;
+                }
+                for request in resend {
+                    self.relay(&request);
+                }
+                if let Err(e) = self.peer_connection(from, &session_id).await {
+                    tracing::warn!("⚠️ Direct session with {} failed: {}", from, e);
+                    self.close(&session_id, "failed", true).await;
+                }
+            }
+
+            CocoonMessage::WebrtcOffer { session_id, sdp } => {
+                let Some(pc) = self.connection_from(from, &session_id) else {
+                    return;
+                };
+                match answer(&pc, sdp).await {
+                    Ok(sdp) => {
+                        self.signal(
+                            from,
+                            &CocoonMessage::WebrtcAnswer {
+                                session_id: session_id.clone(),
+                                sdp,
+                            },
+                        );
+                        self.described(&session_id).await;
+                    }
+                    Err(e) => {
+                        tracing::warn!("⚠️ Direct session with {} failed: {}", from, e);
+                        self.close(&session_id, "failed", true).await;
+                    }
+                }
+            }
+
+            CocoonMessage::WebrtcAnswer { session_id, sdp } => {
+                let Some(pc) = self.connection_from(from, &session_id) else {
+                    return;
+                };
+                let described = match RTCSessionDescription::answer(sdp) {
+                    Ok(answer) => pc
+                        .set_remote_description(answer)
+                        .await
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                match described {
+                    Ok(()) => self.described(&session_id).await,
+                    Err(e) => {
+                        tracing::warn!("⚠️ Direct session with {} failed: {}", from, e);
+                        self.close(&session_id, "failed", true).await;
+                    }
+                }
+            }
+
+            CocoonMessage::WebrtcIceCandidate {
+                session_id,
+                candidate,
+                sdp_mid,
+                sdp_mline_index,
+            } => {
+                if self.connection_from(from, &session_id).is_none() {
+                    return;
+                }
+                let candidate = ice_candidate(candidate, sdp_mid, sdp_mline_index);
+                let pc = {
+                    let mut connections = self.connections.lock().unwrap();
+                    let Some(connection) = connections.get_mut(&session_id) else {
+                        return;
+                    };
+                    if !connection.described {
+                        connection.candidates.push(candidate);
+                        return;
+                    }
+                    connection.peer.clone()
+                };
+                let _ = pc.add_ice_candidate(candidate).await;
+            }
+
+            CocoonMessage::WebrtcSessionEnded { session_id, .. }
+            | CocoonMessage::WebrtcError { session_id, .. } => {
+                if self.connection_from(from, &session_id).is_some() {
+                    self.close(&session_id, "ended by peer", false).await;
+                }
+            }
+
+            _ => tracing::debug!("Ignoring a non-WebRTC message from cocoon {}", from),
+        }
+    }
+
+    /// Start a session with `peer` in the background unless there is one or
+    /// one was tried recently.
+    fn connect(self: &Arc<Self>, peer: &str) {
+        let session_id = Uuid::new_v4().to_string();
+        {
+            let mut attempts = self.attempts.lock().unwrap();
+            if attempts
+                .get(peer)
+                .is_some_and(|at| at.elapsed() < RETRY_INTERVAL)
+            {
+                return;
+            }
+            let mut sessions = self.sessions.lock().unwrap();
+            if sessions.device_id().is_empty() || !sessions.start(peer, session_id.clone()) {
+                return;
+            }
+            attempts.insert(peer.to_string(), Instant::now());
+        }
+
+        let link = self.clone();
+        let peer = peer.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = link.offer(&peer, &session_id).await {
+                tracing::warn!("⚠️ Direct session with {} failed: {}", peer, e);
+                link.close(&session_id, "failed", true).await;
+            }
+        });
+    }
+
+    async fn offer(self: &Arc<Self>, peer: &str, session_id: &str) -> Result<(), String> {
+        let pc = self.peer_connection(peer, session_id).await?;
+        let channel = pc
+            .create_data_channel(CAPABILITY_CHANNEL, None)
+            .await
+            .map_err(|e| format!("Failed to create data channel: {}", e))?;
+        self.attach_channel(session_id, channel);
+
+        let offer = pc
+            .create_offer(None)
+            .await
+            .map_err(|e| format!("Failed to create offer: {}", e))?;
+        let device_id = self.sessions.lock().unwrap().device_id().to_string();
+        self.signal(
+            peer,
+            &CocoonMessage::WebrtcStartSession {
+                session_id: session_id.to_string(),
+                device_id,
+                user_id: None,
+                data_channels: Some(vec![CAPABILITY_CHANNEL.to_string()]),
+                delegated_token: None,
+            },
+        );
+        self.signal(
+            peer,
+            &CocoonMessage::WebrtcOffer {
+                session_id: session_id.to_string(),
+                sdp: offer.sdp.clone(),
+            },
+        );
+        pc.set_local_description(offer)
+            .await
+            .map_err(|e| format!("Failed to set local description: {}", e))
+    }
+
+    /// Create and track the peer connection of a session
+    async fn peer_connection(
+        self: &Arc<Self>,
+        peer: &str,
+        session_id: &str,
+    ) -> Result<Arc<RTCPeerConnection>, String> {
+        let mut media_engine = MediaEngine::default();
+        let registry = register_default_interceptors(Registry::new(), &mut media_engine)
+            .map_err(|e| format!("Failed to register interceptors: {}", e))?;
+        let api = APIBuilder::new()
+            .with_media_engine(media_engine)
+            .with_interceptor_registry(registry)
+            .build();
+        let config = RTCConfiguration {
+            ice_servers: build_ice_servers(),
+            ..Default::default()
+        };
+        let pc = Arc::new(
+            api.new_peer_connection(config)
+                .await
+                .map_err(|e| format!("Failed to create peer connection: {}", e))?,
+        );
+
+        let link = Arc::downgrade(self);
+        let candidate_peer = peer.to_string();
+        let candidate_session = session_id.to_string();
+        pc.on_ice_candidate(Box::new(move |candidate| {
+            if let (Some(link), Some(json)) =
+                (link.upgrade(), candidate.and_then(|c| c.to_json().ok()))
+            {
+                // webrtc-rs leaves sdp_mid empty; the data channel is media section "0"
+                let sdp_mid = match json.sdp_mid.as_deref() {
+                    Some("") | None => Some("0".to_string()),
+                    other => other.map(|s| s.to_string()),
+                };
+                link.signal(
+                    &candidate_peer,
+                    &CocoonMessage::WebrtcIceCandidate {
+                        session_id: candidate_session.clone(),
+                        candidate: json.candidate,
+                        sdp_mid,
+                        sdp_mline_index: json.sdp_mline_index.map(|i| i as i32),
+                    },
+                );
+            }
+            Box::pin(async {})
+        }));
+
+        let link = Arc::downgrade(self);
+        let state_session = session_id.to_string();
+        pc.on_peer_connection_state_change(Box::new(move |state| {
+            let reason = match state {
+                RTCPeerConnectionState::Failed => Some(("failed", true)),
+                RTCPeerConnectionState::Closed => Some(("closed", false)),
+                _ => None,
+            };
+            if let (Some(link), Some((reason, notify))) = (link.upgrade(), reason) {
+                let session_id = state_session.clone();
+                tokio::spawn(async move { link.close(&session_id, reason, notify).await });
+            }
+            Box::pin(async {})
+        }));
+
+        let link = Arc::downgrade(self);
+        let channel_session = session_id.to_string();
+        pc.on_data_channel(Box::new(move |dc: Arc<RTCDataChannel>| {
+            let link = link.clone();
+            let session_id = channel_session.clone();
+            Box::pin(async move {
+                if dc.label() != CAPABILITY_CHANNEL {
+                    tracing::warn!("🚫 Data channel {} refused on a cocoon session", dc.label());
+                    let _ = dc.close().await;
+                    return;
+                }
+                if let Some(link) = link.upgrade() {
+                    link.attach_channel(&session_id, dc);
+                }
+            })
+        }));
+
+        self.connections.lock().unwrap().insert(
+            session_id.to_string(),
+            PeerConnection {
+                peer: pc.clone(),
+                channel: None,
+                described: false,
+                candidates: Vec::new(),
+            },
+        );
+
+        // Capability traffic keeps using the relay meanwhile; stop trying
+        // once negotiation stalls
+        let link = Arc::downgrade(self);
+        let peer = peer.to_string();
+        let session_id = session_id.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(CONNECT_TIMEOUT).await;
+            let Some(link) = link.upgrade() else {
+                return;
+            };
+            let stalled = {
+                let sessions = link.sessions.lock().unwrap();
+                sessions.session_id(&peer) == Some(session_id.as_str())
+                    && sessions.state(&peer) == Some(PeerSessionState::Connecting)
+            };
+            if stalled {
+                link.close(&session_id, "timed out", true).await;
+            }
+        });
+
+        Ok(pc)
+    }
+
+    fn attach_channel(self: &Arc<Self>, session_id: &str, dc: Arc<RTCDataChannel>) {
+        if let Some(connection) = self.connections.lock().unwrap().get_mut(session_id) {
+            connection.channel = Some(dc.clone());
+        }
+
+        let link = Arc::downgrade(self);
+        let open_session = session_id.to_string();
+        dc.on_open(Box::new(move || {
+            if let Some(link) = link.upgrade() {
+                let mut sessions = link.sessions.lock().unwrap();
+                sessions.channel_open(&open_session);
+                if let Some(peer) = sessions.peer_of(&open_session) {
+                    tracing::info!("🔗 Direct session with cocoon {} is open", peer);
+                }
+            }
+            Box::pin(async {})
+        }));
+
+        let link = Arc::downgrade(self);
+        let message_session = session_id.to_string();
+        dc.on_message(Box::new(move |msg: DataChannelMessage| {
+            if let (Some(link), Ok(text)) = (link.upgrade(), String::from_utf8(msg.data.to_vec())) {
+                let session_id = message_session.clone();
+                tokio::spawn(async move { link.receive(&session_id, &text).await });
+            }
+            Box::pin(async {})
+        }));
+    }
+
+    /// A capability message arrived on a session's channel
+    async fn receive(self: &Arc<Self>, session_id: &str, data: &str) {
+        let msg = self.sessions.lock().unwrap().receive(session_id, data);
+        match msg {
+            Some(request @ SignalingMessage::CapabilityRequest { .. }) => {
+                self.serve(request, true).await
+            }
+            Some(response) => self.deliver(response),
+            None => tracing::debug!("Dropped a message on session {}", session_id),
+        }
+    }
+
+    /// Remote description set; add the candidates that arrived before it
+    async fn described(&self, session_id: &str) {
+        let (pc, candidates) = {
+            let mut connections = self.connections.lock().unwrap();
+            let Some(connection) = connections.get_mut(session_id) else {
+                return;
+            };
+            connection.described = true;
+            (
+                connection.peer.clone(),
+                std::mem::take(&mut connection.candidates),
+            )
+        };
+        for candidate in candidates {
+            let _ = pc.add_ice_candidate(candidate).await;
+        }
+    }
+
+    /// Send on a peer: directly when a session to it is open, else through
+    /// the relay
+    async fn send_to(&self, peer: &str, msg: SignalingMessage) {
+        let route = self.sessions.lock().unwrap().route(peer, msg);
+        match route {
+            Route::Direct { session_id, data } => self.send_direct(&session_id, data).await,
+            Route::Relay(msg) => self.relay(&msg),
+        }
+    }
+
+    /// Write on a session's channel; a failed write ends the session, which
+    /// sends its unanswered requests through the relay
+    async fn send_direct(&self, session_id: &str, data: String) {
+        let channel = self
+            .connections
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .and_then(|c| c.channel.clone());
+        let sent = match channel {
+            Some(dc) => dc.send_text(data).await.is_ok(),
+            None => false,
+        };
+        if !sent {
+            self.close(session_id, "send failed", true).await;
+        }
+    }
+
+    /// End a session: close its connection, optionally tell the peer, and
+    /// send its unanswered requests through the relay
+    async fn close(&self, session_id: &str, reason: &str, notify: bool) {
+        let (peer, resend) = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let peer = sessions.peer_of(session_id).map(str::to_string);
+            (peer, sessions.session_ended(session_id))
+        };
+        let connection = self.connections.lock().unwrap().remove(session_id);
+
+        if let Some(peer) = peer.as_ref().filter(|_| notify) {
+            self.signal(
+                peer,
+                &CocoonMessage::WebrtcSessionEnded {
+                    session_id: session_id.to_string(),
+                    reason: Some(reason.to_string()),
+                },
+            );
+        }
+        if let Some(connection) = connection {
+            if let Some(peer) = &peer {
+                tracing::info!("🔌 Direct session with cocoon {} ended: {}", peer, reason);
+            }
+            let _ = connection.peer.close().await;
+        }
+        for request in resend {
+            self.relay(&request);
+        }
+    }
+
+    /// Peer connection of `session_id`, if `from` is the peer it belongs to
+    fn connection_from(&self, from: &str, session_id: &str) -> Option<Arc<RTCPeerConnection>> {
+        if self.sessions.lock().unwrap().peer_of(session_id) != Some(from) {
+            return None;
+        }
+        self.connections
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .map(|c| c.peer.clone())
+    }
+
+    /// Address WebRTC signaling to another cocoon through the relay
+    fn signal(&self, peer: &str, msg: &CocoonMessage) {
+        let envelope = SignalingMessage::SyncData {
+            payload: serde_json::json!({ "to": peer, "data": msg }),
+            priority: Some(RelayPriority::Interactive),
+        };
+        if let Err(e) = self.writer.send(&envelope) {
+            tracing::warn!("⚠️ Failed to signal cocoon {}: {}", peer, e);
+        }
+    }
+
+    fn relay(&self, msg: &SignalingMessage) {
+        if let Err(e) = self.writer.send(msg) {
+            tracing::warn!("⚠️ Failed to relay capability message: {}", e);
+        }
+    }
+}
+
+/// Accept an offer and return the answer SDP
+async fn answer(pc: &RTCPeerConnection, sdp: String) -> Result<String, String> {
+    let offer = RTCSessionDescription::offer(sdp)
+        .map_err(|e| format!("Failed to parse SDP offer: {}", e))?;
+    pc.set_remote_description(offer)
+        .await
+        .map_err(|e| format!("Failed to set remote description: {}", e))?;
+    let answer = pc
+        .create_answer(None)
+        .await
+        .map_err(|e| format!("Failed to create answer: {}", e))?;
+    let sdp = answer.sdp.clone();
+    pc.set_local_description(answer)
+        .await
+        .map_err(|e| format!("Failed to set local description: {}", e))?;
+    Ok(sdp)
+}
+
+fn ice_candidate(
+    candidate: String,
+    sdp_mid: Option<String>,
+    sdp_mline_index: Option<i32>,
+) -> RTCIceCandidateInit {
+    RTCIceCandidateInit {
+        candidate,
+        sdp_mid,
+        sdp_mline_index: sdp_mline_index.map(|i| i as u16),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,356 @@
+//! Which path capability traffic to another cocoon takes
+//!
+//! `capability_request` and `capability_response` go through the relay unless
+//! the two cocoons have a direct WebRTC session (opened by `peer_link`). Once
+//! the session's [`CAPABILITY_CHANNEL`] is open, [`PeerSessions::route`] puts
+//! capability messages on it; before that and after the session ends they
+//! take the relay. Requests still unanswered when a session ends are handed
+//! back by [`PeerSessions::session_ended`] so the caller can send them again
+//! through the relay.
+//!
+//! If both cocoons start a session at the same time, the one started by the
+//! lower device ID is kept; see [`PeerSessions::accept_start`].
+
+use lib_signaling_protocol::{RequestId, SignalingMessage};
+use std::collections::HashMap;
+
+/// Data channel carrying capability messages as JSON text
+pub const CAPABILITY_CHANNEL: &str = "capability";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerSessionState {
+    /// Negotiating; capability traffic still uses the relay
+    Connecting,
+    /// Capability channel is open
+    Open,
+}
+
+/// Where to send a capability message
+#[derive(Debug, Clone)]
+pub enum Route {
+    /// Write `data` on the session's [`CAPABILITY_CHANNEL`]
+    Direct { session_id: String, data: String },
+    /// Send through the signaling relay
+    Relay(Box<SignalingMessage>),
+}
+
+/// Outcome of a session start received from a peer
+#[derive(Debug, Clone)]
+pub enum PeerStart {
+    /// Answer the peer's offer. `replaces` is our previous session to the
+    /// peer, to be closed; `resend` are its unanswered requests, to be sent
+    /// again through the relay.
+    Accept {
+        replaces: Option<String>,
+        resend: Vec<SignalingMessage>,
+    },
+    /// Our own pending session wins the collision; ignore the start
+    Ignore,
+}
+
+#[derive(Debug)]
+struct PeerSession {
+    session_id: String,
+    /// Started by this cocoon
+    initiator: bool,
+    state: PeerSessionState,
+    /// Requests sent on this session and not answered yet
+    in_flight: HashMap<RequestId, SignalingMessage>,
+}
+
+impl PeerSession {
+    fn new(session_id: String, initiator: bool) -> Self {
+        Self {
+            session_id,
+            initiator,
+            state: PeerSessionState::Connecting,
+            in_flight: HashMap::new(),
+        }
+    }
+}
+
+/// Direct sessions of one cocoon, keyed by peer device ID
+#[derive(Debug)]
+pub struct PeerSessions {
+    device_id: String,
+    peers: HashMap<String, PeerSession>,
+}
+
+impl PeerSessions {
+    pub fn new(device_id: impl Into<String>) -> Self {
+        Self {
+            device_id: device_id.into(),
+            peers: HashMap::new(),
+        }
+    }
+
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    /// Begin a session with `peer`; `false` if there already is one.
+    pub fn start(&mut self, peer: &str, session_id: impl Into<String>) -> bool {
+        if self.peers.contains_key(peer) {
+            return false;
+        }
+        self.peers
+            .insert(peer.to_string(), PeerSession::new(session_id.into(), true));
+        true
+    }
+
+    /// Handle a session start received from `peer`.
+    pub fn accept_start(&mut self, peer: &str, session_id: impl Into<String>) -> PeerStart {
+        if let Some(own) = self.peers.get(peer) {
+            let collides = own.initiator && own.state == PeerSessionState::Connecting;
+            if collides && self.device_id.as_str() < peer {
+                return PeerStart::Ignore;
+            }
+        }
+
+        let previous = self
+            .peers
+            .insert(peer.to_string(), PeerSession::new(session_id.into(), false));
+        match previous {
+            Some(old) => PeerStart::Accept {
+                replaces: Some(old.session_id),
+                resend: old.in_flight.into_values().collect(),
+            },
+            None => PeerStart::Accept {
+                replaces: None,
+                resend: Vec::new(),
+            },
+        }
+    }
+
+    /// The session's capability channel opened
+    pub fn channel_open(&mut self, session_id: &str) {
+        if let Some(session) = self.peers.values_mut().find(|s| s.session_id == session_id) {
+            session.state = PeerSessionState::Open;
+        }
+    }
+
+    /// Forget an ended session and return its unanswered requests.
+    pub fn session_ended(&mut self, session_id: &str) -> Vec<SignalingMessage> {
+        let Some(peer) = self.peer_of(session_id).map(str::to_string) else {
+            return Vec::new();
+        };
+        self.peers
+            .remove(&peer)
+            .map(|session| session.in_flight.into_values().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn state(&self, peer: &str) -> Option<PeerSessionState> {
+        self.peers.get(peer).map(|s| s.state)
+    }
+
+    pub fn session_id(&self, peer: &str) -> Option<&str> {
+        self.peers.get(peer).map(|s| s.session_id.as_str())
+    }
+
+    pub fn peer_of(&self, session_id: &str) -> Option<&str> {
+        self.peers
+            .iter()
+            .find(|(_, s)| s.session_id == session_id)
+            .map(|(peer, _)| peer.as_str())
+    }
+
+    /// Pick the path for a message to `peer`.
+    ///
+    /// Only capability messages use the direct channel; requests sent on it
+    /// are remembered until [`complete`](Self::complete) or the session ends.
+    pub fn route(&mut self, peer: &str, msg: SignalingMessage) -> Route {
+        let capability = matches!(
+            msg,
+            SignalingMessage::CapabilityRequest { .. }
+                | SignalingMessage::CapabilityResponse { .. }
+        );
+        let session = match self.peers.get_mut(peer) {
+            Some(session) if capability && session.state == PeerSessionState::Open => session,
+            _ => return Route::Relay(Box::new(msg)),
+        };
+        let Ok(data) = serde_json::to_string(&msg) else {
+            return Route::Relay(Box::new(msg));
+        };
+
+        if let SignalingMessage::CapabilityRequest { request_id, .. } = &msg {
+            session.in_flight.insert(request_id.clone(), msg.clone());
+        }
+        Route::Direct {
+            session_id: session.session_id.clone(),
+            data,
+        }
+    }
+
+    /// Parse a message received on a session's capability channel, stamped
+    /// with the peer it came from.
+    ///
+    /// Responses complete the matching request.
+    pub fn receive(&mut self, session_id: &str, data: &str) -> Option<SignalingMessage> {
+        let peer = self.peer_of(session_id)?.to_string();
+        let mut msg: SignalingMessage = serde_json::from_str(data).ok()?;
+        match &mut msg {
+            SignalingMessage::CapabilityRequest { from_device, .. } => {
+                *from_device = Some(peer);
+            }
+            SignalingMessage::CapabilityResponse {
+                request_id,
+                from_device,
+                ..
+            } => {
+                *from_device = Some(peer);
+                let request_id = request_id.clone();
+                self.complete(&request_id);
+            }
+            _ => return None,
+        }
+        Some(msg)
+    }
+
+    /// Forget a request once answered, whichever path the answer took
+    pub fn complete(&mut self, request_id: &str) {
+        for session in self.peers.values_mut() {
+            session.in_flight.remove(request_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lib_signaling_protocol::Capability;
+
+    fn request(id: &str) -> SignalingMessage {
+        SignalingMessage::CapabilityRequest {
+            request_id: id.into(),
+            capability: Capability {
+                protocol: "embeddings".to_string(),
+                version: "^1.0".to_string(),
+            },
+            payload: serde_json::json!({ "text": "hello" }),
+            prefer_device: Some("cocoon-b".to_string()),
+            from_device: None,
+        }
+    }
+
+    fn response(id: &str) -> SignalingMessage {
+        SignalingMessage::CapabilityResponse {
+            request_id: id.into(),
+            payload: serde_json::json!({ "vector": [0.1] }),
+            error: None,
+            from_device: None,
+        }
+    }
+
+    #[test]
+    fn test_routes_direct_once_channel_opens() {
+        let mut sessions = PeerSessions::new("cocoon-a");
+        assert!(matches!(
+            sessions.route("cocoon-b", request("r1")),
+            Route::Relay(_)
+        ));
+
+        assert!(sessions.start("cocoon-b", "s1"));
+        assert!(!sessions.start("cocoon-b", "s2"));
+        assert!(matches!(
+            sessions.route("cocoon-b", request("r1")),
+            Route::Relay(_)
+        ));
+
+        sessions.channel_open("s1");
+        match sessions.route("cocoon-b", request("r2")) {
+            Route::Direct { session_id, data } => {
+                assert_eq!(session_id, "s1");
+                assert!(data.contains("capability_request"));
+            }
+            Route::Relay(_) => panic!("expected direct route"),
+        }
+
+        // Other traffic keeps using the relay
+        let error = SignalingMessage::SystemError {
+            message: "x".to_string(),
+        };
+        assert!(matches!(sessions.route("cocoon-b", error), Route::Relay(_)));
+    }
+
+    #[test]
+    fn test_unanswered_requests_fall_back_to_relay() {
+        let mut sessions = PeerSessions::new("cocoon-a");
+        sessions.start("cocoon-b", "s1");
+        sessions.channel_open("s1");
+        sessions.route("cocoon-b", request("r1"));
+        sessions.route("cocoon-b", request("r2"));
+
+        let answer = serde_json::to_string(&response("r1")).unwrap();
+        match sessions.receive("s1", &answer) {
+            Some(SignalingMessage::CapabilityResponse { from_device, .. }) => {
+                assert_eq!(from_device.as_deref(), Some("cocoon-b"));
+            }
+            other => panic!("expected a response, got {:?}", other),
+        }
+        assert!(sessions.receive("unknown", &answer).is_none());
+
+        let resend = sessions.session_ended("s1");
+        assert_eq!(resend.len(), 1);
+        assert!(
+            matches!(&resend[0], SignalingMessage::CapabilityRequest { request_id, .. } if request_id == "r2")
+        );
+        assert_eq!(sessions.state("cocoon-b"), None);
+        assert!(matches!(
+            sessions.route("cocoon-b", request("r3")),
+            Route::Relay(_)
+        ));
+    }
+
+    #[test]
+    fn test_received_requests_name_the_peer() {
+        let mut sessions = PeerSessions::new("cocoon-b");
+        assert!(matches!(
+            sessions.accept_start("cocoon-a", "s1"),
+            PeerStart::Accept { replaces: None, .. }
+        ));
+
+        // A peer cannot claim to be another device
+        let mut forged = request("r1");
+        if let SignalingMessage::CapabilityRequest { from_device, .. } = &mut forged {
+            *from_device = Some("cocoon-c".to_string());
+        }
+        let data = serde_json::to_string(&forged).unwrap();
+        match sessions.receive("s1", &data) {
+            Some(SignalingMessage::CapabilityRequest { from_device, .. }) => {
+                assert_eq!(from_device.as_deref(), Some("cocoon-a"));
+            }
+            other => panic!("expected a request, got {:?}", other),
+        }
+
+        // Only capability traffic is accepted on the channel
+        let other = serde_json::to_string(&SignalingMessage::SystemError {
+            message: "x".to_string(),
+        })
+        .unwrap();
+        assert!(sessions.receive("s1", &other).is_none());
+    }
+
+    #[test]
+    fn test_simultaneous_starts_keep_lower_device_id() {
+        let mut a = PeerSessions::new("cocoon-a");
+        let mut b = PeerSessions::new("cocoon-b");
+        a.start("cocoon-b", "from-a");
+        b.start("cocoon-a", "from-b");
+
+        assert!(matches!(
+            a.accept_start("cocoon-b", "from-b"),
+            PeerStart::Ignore
+        ));
+        match b.accept_start("cocoon-a", "from-a") {
+            PeerStart::Accept { replaces, resend } => {
+                assert_eq!(replaces.as_deref(), Some("from-b"));
+                assert!(resend.is_empty());
+            }
+            PeerStart::Ignore => panic!("cocoon-b should yield"),
+        }
+        assert_eq!(a.session_id("cocoon-b"), Some("from-a"));
+        assert_eq!(b.session_id("cocoon-a"), Some("from-a"));
+        assert_eq!(b.peer_of("from-a"), Some("cocoon-a"));
+    }
+}
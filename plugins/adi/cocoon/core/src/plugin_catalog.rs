@@ -0,0 +1,221 @@
+//! Last known ADI plugin catalogue, so `plugins_changed` notifications are
+//! diffed against what clients saw before instead of guessed.
+//!
+//! The catalogue is persisted per device: a cocoon restarting with the same
+//! device ID diffs its freshly registered plugins against the previous run,
+//! while a file left behind by another device is ignored.
+
+use crate::adi_router::{AdiNotification, AdiPluginInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const PLUGIN_CATALOG_PATH: &str = "/cocoon/.plugin-catalog.json";
+
+/// Plugins known to a device, keyed by plugin ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginCatalog {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    #[serde(default)]
+    plugins: BTreeMap<String, AdiPluginInfo>,
+}
+
+/// Plugin IDs that differ between two catalogues
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CatalogDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub updated: Vec<String>,
+}
+
+impl CatalogDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+
+    /// The notification to broadcast, or `None` if nothing changed
+    pub fn into_notification(self) -> Option<AdiNotification> {
+        (!self.is_empty()).then_some(AdiNotification::PluginsChanged {
+            added: self.added,
+            removed: self.removed,
+            updated: self.updated,
+        })
+    }
+}
+
+impl PluginCatalog {
+    pub fn new(device_id: Option<String>) -> Self {
+        Self {
+            device_id,
+            plugins: BTreeMap::new(),
+        }
+    }
+
+    /// Load the catalogue of `device_id`, falling back to an empty one if the
+    /// file is missing, unreadable or belongs to another device.
+    pub async fn load(path: impl AsRef<Path>, device_id: Option<String>) -> Self {
+        let path = path.as_ref();
+        let catalog = match tokio::fs::read_to_string(path).await {
+            Ok(content) => serde_json::from_str::<Self>(&content).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "⚠️ Ignoring corrupt plugin catalogue {}: {}",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        if catalog.device_id.is_some() && catalog.device_id == device_id {
+            catalog
+        } else {
+            Self::new(device_id)
+        }
+    }
+
+    /// Write the catalogue atomically (temp file + rename).
+    pub async fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("json.tmp");
+        let content = serde_json::to_vec_pretty(self)?;
+        tokio::fs::write(&tmp, content).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.plugins.keys().map(String::as_str)
+    }
+
+    pub fn get(&self, plugin_id: &str) -> Option<&AdiPluginInfo> {
+        self.plugins.get(plugin_id)
+    }
+
+    /// Changes needed to turn `self` into `other`
+    pub fn diff(&self, other: &Self) -> CatalogDiff {
+        let mut diff = CatalogDiff::default();
+        for (id, info) in &other.plugins {
+            match self.plugins.get(id) {
+                None => diff.added.push(id.clone()),
+                Some(old) if !same_info(old, info) => diff.updated.push(id.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = self
+            .plugins
+            .keys()
+            .filter(|id| !other.plugins.contains_key(*id))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Record a registered plugin and return what changed for it.
+    pub fn upsert(&mut self, info: AdiPluginInfo) -> CatalogDiff {
+        let mut diff = CatalogDiff::default();
+        match self.plugins.get(&info.id) {
+            None => diff.added.push(info.id.clone()),
+            Some(old) if !same_info(old, &info) => diff.updated.push(info.id.clone()),
+            Some(_) => return diff,
+        }
+        self.plugins.insert(info.id.clone(), info);
+        diff
+    }
+
+    /// Forget a plugin; returns `false` if it was not in the catalogue.
+    pub fn remove(&mut self, plugin_id: &str) -> bool {
+        self.plugins.remove(plugin_id).is_some()
+    }
+
+    /// Keep only `plugin_ids` and return the removed IDs.
+    pub fn retain(&mut self, plugin_ids: &[&str]) -> Vec<String> {
+        let removed: Vec<String> = self
+            .plugins
+            .keys()
+            .filter(|id| !plugin_ids.contains(&id.as_str()))
+            .cloned()
+            .collect();
+        for id in &removed {
+            self.plugins.remove(id);
+        }
+        removed
+    }
+}
+
+impl FromIterator<AdiPluginInfo> for PluginCatalog {
+    fn from_iter<I: IntoIterator<Item = AdiPluginInfo>>(iter: I) -> Self {
+        Self {
+            device_id: None,
+            plugins: iter.into_iter().map(|p| (p.id.clone(), p)).collect(),
+        }
+    }
+}
+
+/// `AdiPluginInfo` has no `PartialEq`; compare the serialized form clients see.
+fn same_info(a: &AdiPluginInfo, b: &AdiPluginInfo) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, version: &str) -> AdiPluginInfo {
+        AdiPluginInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            version: version.to_string(),
+            description: None,
+            methods: vec![],
+            capabilities: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_diff_and_upsert() {
+        let old: PluginCatalog = [info("adi.tasks", "1.0.0"), info("adi.tools", "1.0.0")]
+            .into_iter()
+            .collect();
+        let new: PluginCatalog = [info("adi.tasks", "1.1.0"), info("adi.llm-proxy", "1.0.0")]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            old.diff(&new),
+            CatalogDiff {
+                added: vec!["adi.llm-proxy".to_string()],
+                removed: vec!["adi.tools".to_string()],
+                updated: vec!["adi.tasks".to_string()],
+            }
+        );
+        assert!(new.diff(&new).is_empty());
+
+        let mut catalog = old;
+        assert!(catalog
+            .upsert(info("adi.tools", "1.0.0"))
+            .into_notification()
+            .is_none());
+        assert_eq!(
+            catalog.upsert(info("adi.tools", "2.0.0")).updated,
+            vec!["adi.tools"]
+        );
+        assert_eq!(catalog.retain(&["adi.tools"]), vec!["adi.tasks"]);
+        assert_eq!(catalog.ids().collect::<Vec<_>>(), vec!["adi.tools"]);
+    }
+
+    #[tokio::test]
+    async fn test_catalog_persists_per_device() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+
+        let mut catalog = PluginCatalog::new(Some("device-1".to_string()));
+        catalog.upsert(info("adi.tasks", "1.0.0"));
+        catalog.save(&path).await.unwrap();
+
+        let loaded = PluginCatalog::load(&path, Some("device-1".to_string())).await;
+        assert!(loaded.get("adi.tasks").is_some());
+
+        let other = PluginCatalog::load(&path, Some("device-2".to_string())).await;
+        assert_eq!(other.device_id.as_deref(), Some("device-2"));
+        assert_eq!(other.ids().count(), 0);
+    }
+}
@@ -0,0 +1,405 @@
+//! TCP port forwarding (`adi cocoon forward`).
+//!
+//! Each forwarded connection reaches the cocoon either as its own WebRTC data
+//! channel labelled `forward:<id>:<host>:<port>`, or, when no WebRTC
+//! connection could be made, as a `forward_*` stream multiplexed over the
+//! signaling relay. Either way the cocoon dials `host:port` and pipes bytes
+//! in both directions until one side closes.
+
+use crate::protocol::messages::CocoonMessage;
+use crate::relay_queue::RelaySender;
+use base64::Engine;
+use bytes::Bytes;
+use lib_signaling_protocol::{RelayPriority, SignalingMessage};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Mutex};
+use webrtc::data_channel::data_channel_message::DataChannelMessage;
+use webrtc::data_channel::RTCDataChannel;
+
+pub const LABEL_PREFIX: &str = "forward:";
+
+/// Data channel the client opens up front so its offer negotiates SCTP.
+/// Carries no traffic.
+pub const CONTROL_LABEL: &str = "forward";
+
+/// Largest chunk read from a socket per message; stays well below the SCTP
+/// message size limit.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// `local_port:host:port`, as in `ssh -L`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardSpec {
+    pub local_port: u16,
+    pub host: String,
+    pub port: u16,
+}
+
+impl FromStr for ForwardSpec {
+    type Err = String;
+
+    /// Accepts `8080:localhost:3000`, `8080:3000` and `3000`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_port = |p: &str| {
+            p.parse::<u16>()
+                .ok()
+                .filter(|p| *p != 0)
+                .ok_or_else(|| format!("Invalid port '{}' in '{}'", p, s))
+        };
+
+        let (local, rest) = match s.split_once(':') {
+            Some((local, rest)) => (local, rest),
+            None => (s, s),
+        };
+        let (host, port) = match rest.rsplit_once(':') {
+            Some((host, port)) => (host.trim_matches(['[', ']']), port),
+            None => ("localhost", rest),
+        };
+        if host.is_empty() {
+            return Err(format!("Missing host in '{}'", s));
+        }
+
+        Ok(Self {
+            local_port: parse_port(local)?,
+            host: host.to_string(),
+            port: parse_port(port)?,
+        })
+    }
+}
+
+impl fmt::Display for ForwardSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.local_port, self.host, self.port)
+    }
+}
+
+/// Data channel label for forwarded connection `id`.
+pub fn channel_label(id: u64, host: &str, port: u16) -> String {
+    format!("{}{}:{}:{}", LABEL_PREFIX, id, host, port)
+}
+
+/// Target `(host, port)` of a forward data channel label.
+pub fn parse_label(label: &str) -> Option<(String, u16)> {
+    let (_id, target) = label.strip_prefix(LABEL_PREFIX)?.split_once(':')?;
+    let (host, port) = target.rsplit_once(':')?;
+    Some((host.to_string(), port.parse().ok()?))
+}
+
+/// Buffer a data channel's messages until the socket on the other end is
+/// ready. The receiver ends when the channel closes.
+pub fn data_channel_receiver(dc: &Arc<RTCDataChannel>) -> mpsc::UnboundedReceiver<Bytes> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let tx = Arc::new(std::sync::Mutex::new(Some(tx)));
+
+    let on_message = tx.clone();
+    dc.on_message(Box::new(move |msg: DataChannelMessage| {
+        if let Some(tx) = on_message.lock().unwrap().as_ref() {
+            let _ = tx.send(msg.data);
+        }
+        Box::pin(async {})
+    }));
+    dc.on_close(Box::new(move || {
+        tx.lock().unwrap().take();
+        Box::pin(async {})
+    }));
+
+    rx
+}
+
+/// Pipe `tcp` through `dc` until either side closes. Returns the bytes sent
+/// to and received from the data channel.
+pub async fn pipe_data_channel(
+    dc: Arc<RTCDataChannel>,
+    mut incoming: mpsc::UnboundedReceiver<Bytes>,
+    tcp: TcpStream,
+) -> (u64, u64) {
+    let (mut reader, mut writer) = tcp.into_split();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let (mut sent, mut received) = (0u64, 0u64);
+
+    loop {
+        tokio::select! {
+            data = incoming.recv() => {
+                let Some(data) = data else { break };
+                if writer.write_all(&data).await.is_err() {
+                    break;
+                }
+                received += data.len() as u64;
+            }
+            n = reader.read(&mut buf) => {
+                let n = match n {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                if dc.send(&Bytes::copy_from_slice(&buf[..n])).await.is_err() {
+                    break;
+                }
+                sent += n as u64;
+            }
+        }
+    }
+
+    let _ = writer.shutdown().await;
+    let _ = dc.close().await;
+    (sent, received)
+}
+
+/// Cocoon side of a `forward:` data channel: dial the target from the label
+/// and pipe it through.
+pub(crate) fn accept_data_channel(dc: Arc<RTCDataChannel>) {
+    let Some((host, port)) = parse_label(dc.label()) else {
+        return;
+    };
+    let incoming = data_channel_receiver(&dc);
+
+    tokio::spawn(async move {
+        match TcpStream::connect((host.as_str(), port)).await {
+            Ok(tcp) => {
+                tracing::info!("🔀 Forwarding {} to {}:{}", dc.label(), host, port);
+                pipe_data_channel(dc, incoming, tcp).await;
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Forward to {}:{} failed: {}", host, port, e);
+                let _ = dc.close().await;
+            }
+        }
+    });
+}
+
+/// Pipe `tcp` through relay stream `stream_id` until either side closes.
+/// `incoming` yields the decoded `forward_data` of the stream; `send`
+/// delivers `forward_*` messages to the peer. Returns the bytes sent and
+/// received.
+pub async fn pipe_relay<F>(
+    stream_id: String,
+    mut incoming: mpsc::UnboundedReceiver<Vec<u8>>,
+    tcp: TcpStream,
+    send: F,
+) -> (u64, u64)
+where
+    F: Fn(CocoonMessage),
+{
+    let (mut reader, mut writer) = tcp.into_split();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let (mut sent, mut received) = (0u64, 0u64);
+    let mut error = None;
+
+    loop {
+        tokio::select! {
+            data = incoming.recv() => {
+                // Peer closed the stream
+                let Some(data) = data else { return (sent, received) };
+                if let Err(e) = writer.write_all(&data).await {
+                    error = Some(e.to_string());
+                    break;
+                }
+                received += data.len() as u64;
+            }
+            n = reader.read(&mut buf) => {
+                let n = match n {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) => {
+                        error = Some(e.to_string());
+                        break;
+                    }
+                };
+                send(CocoonMessage::ForwardData {
+                    stream_id: stream_id.clone(),
+                    data: base64::engine::general_purpose::STANDARD.encode(&buf[..n]),
+                });
+                sent += n as u64;
+            }
+        }
+    }
+
+    let _ = writer.shutdown().await;
+    send(CocoonMessage::ForwardClose { stream_id, error });
+    (sent, received)
+}
+
+/// Route a `forward_data` payload to its stream. Returns `false` if the
+/// stream is unknown or gone.
+pub fn deliver(
+    streams: &HashMap<String, mpsc::UnboundedSender<Vec<u8>>>,
+    stream_id: &str,
+    data: &str,
+) -> bool {
+    let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data) else {
+        return false;
+    };
+    streams
+        .get(stream_id)
+        .is_some_and(|tx| tx.send(bytes).is_ok())
+}
+
+/// Cocoon side of the relay fallback: open streams by id.
+#[derive(Clone, Default)]
+pub(crate) struct RelayForwards {
+    streams: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Vec<u8>>>>>,
+}
+
+impl RelayForwards {
+    /// Handle one `forward_*` message. Returns quickly, so messages can be
+    /// handled one at a time in arrival order.
+    pub async fn handle(&self, msg: CocoonMessage, writer: RelaySender) {
+        let send = move |msg: CocoonMessage| {
+            let _ = writer.send(&SignalingMessage::SyncData {
+                payload: serde_json::to_value(&msg)
+                    .expect("CocoonMessage serialization cannot fail"),
+                priority: Some(RelayPriority::Normal),
+            });
+        };
+
+        match msg {
+            CocoonMessage::ForwardOpen {
+                stream_id,
+                host,
+                port,
+            } => {
+                let Ok(port) = u16::try_from(port) else {
+                    send(CocoonMessage::ForwardClose {
+                        stream_id,
+                        error: Some(format!("Invalid port {}", port)),
+                    });
+                    return;
+                };
+
+                // Registered before dialing so data sent right after the open
+                // is buffered instead of rejected
+                let (tx, rx) = mpsc::unbounded_channel();
+                self.streams.lock().await.insert(stream_id.clone(), tx);
+
+                let streams = self.streams.clone();
+                tokio::spawn(async move {
+                    match TcpStream::connect((host.as_str(), port)).await {
+                        Ok(tcp) => {
+                            tracing::info!(
+                                "🔀 Forwarding relay stream {} to {}:{}",
+                                stream_id,
+                                host,
+                                port
+                            );
+                            send(CocoonMessage::ForwardOpened {
+                                stream_id: stream_id.clone(),
+                            });
+                            pipe_relay(stream_id.clone(), rx, tcp, send).await;
+                        }
+                        Err(e) => {
+                            tracing::warn!("⚠️ Forward to {}:{} failed: {}", host, port, e);
+                            send(CocoonMessage::ForwardClose {
+                                stream_id: stream_id.clone(),
+                                error: Some(e.to_string()),
+                            });
+                        }
+                    }
+                    streams.lock().await.remove(&stream_id);
+                });
+            }
+            CocoonMessage::ForwardData { stream_id, data } => {
+                let delivered = deliver(&*self.streams.lock().await, &stream_id, &data);
+                if !delivered {
+                    send(CocoonMessage::ForwardClose {
+                        stream_id,
+                        error: Some("Unknown stream".to_string()),
+                    });
+                }
+            }
+            CocoonMessage::ForwardClose { stream_id, .. } => {
+                // Dropping the sender ends the pipe
+                self.streams.lock().await.remove(&stream_id);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_parse_spec() {
+        let spec: ForwardSpec = "8080:localhost:3000".parse().unwrap();
+        assert_eq!(spec.local_port, 8080);
+        assert_eq!(spec.host, "localhost");
+        assert_eq!(spec.port, 3000);
+        assert_eq!(spec.to_string(), "8080:localhost:3000");
+
+        let spec: ForwardSpec = "8080:3000".parse().unwrap();
+        assert_eq!(
+            (spec.local_port, spec.host.as_str(), spec.port),
+            (8080, "localhost", 3000)
+        );
+
+        let spec: ForwardSpec = "5432".parse().unwrap();
+        assert_eq!((spec.local_port, spec.port), (5432, 5432));
+
+        let spec: ForwardSpec = "8080:[::1]:3000".parse().unwrap();
+        assert_eq!(spec.host, "::1");
+
+        assert!("0:3000".parse::<ForwardSpec>().is_err());
+        assert!("8080:db:http".parse::<ForwardSpec>().is_err());
+        assert!("8080::3000".parse::<ForwardSpec>().is_err());
+    }
+
+    #[test]
+    fn test_label_roundtrip() {
+        let label = channel_label(7, "::1", 3000);
+        assert_eq!(label, "forward:7:::1:3000");
+        assert_eq!(parse_label(&label), Some(("::1".to_string(), 3000)));
+        assert_eq!(parse_label(CONTROL_LABEL), None);
+        assert_eq!(parse_label("silk"), None);
+    }
+
+    #[tokio::test]
+    async fn test_pipe_relay_roundtrip() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let mut tcp = TcpStream::connect(addr).await.unwrap();
+            tcp.write_all(b"ping").await.unwrap();
+            let mut reply = [0u8; 4];
+            tcp.read_exact(&mut reply).await.unwrap();
+            reply
+        });
+        let (tcp, _) = listener.accept().await.unwrap();
+
+        let (in_tx, in_rx) = mpsc::unbounded_channel();
+        let (out_tx, mut out_rx) = mpsc::unbounded_channel();
+        let pipe = tokio::spawn(pipe_relay("s1".to_string(), in_rx, tcp, move |msg| {
+            let _ = out_tx.send(msg);
+        }));
+
+        match out_rx.recv().await.unwrap() {
+            CocoonMessage::ForwardData { stream_id, data } => {
+                assert_eq!(stream_id, "s1");
+                let mut streams = HashMap::new();
+                streams.insert("s1".to_string(), in_tx.clone());
+                assert_eq!(
+                    base64::engine::general_purpose::STANDARD
+                        .decode(&data)
+                        .unwrap(),
+                    b"ping"
+                );
+                assert!(deliver(
+                    &streams,
+                    "s1",
+                    &base64::engine::general_purpose::STANDARD.encode(b"pong")
+                ));
+                assert!(!deliver(&streams, "s2", &data));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(&client.await.unwrap(), b"pong");
+
+        // Peer closing the stream ends the pipe without echoing a close
+        drop(in_tx);
+        assert_eq!(pipe.await.unwrap(), (4, 4));
+    }
+}
@@ -0,0 +1,458 @@
+//! Silk session recordings in asciicast v2 format
+//!
+//! Opt-in with `COCOON_RECORD_SILK=true`. Every Silk session then writes
+//! `<started-unix-secs>-<session-id>.cast` to `COCOON_RECORDINGS_DIR`
+//! (default `/cocoon/recordings`): a JSON header line followed by one
+//! `[seconds, "o", data]` line per output chunk, so the files play in
+//! `asciinema play` as well as `adi cocoon recordings play`.
+//!
+//! Retention is applied each time a recording starts: recordings older than
+//! `COCOON_RECORDING_RETENTION_DAYS` (default 30) are deleted, then the oldest
+//! ones until the directory fits in `COCOON_RECORDING_MAX_MB` (default 1024).
+//! Set either to 0 to disable that limit.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use lib_env_parse::{env_bool, env_opt, env_vars};
+
+env_vars! {
+    CocoonRecordSilk => "COCOON_RECORD_SILK",
+    CocoonRecordingsDir => "COCOON_RECORDINGS_DIR",
+    CocoonRecordingRetentionDays => "COCOON_RECORDING_RETENTION_DAYS",
+    CocoonRecordingMaxMb => "COCOON_RECORDING_MAX_MB",
+}
+
+pub const DEFAULT_RECORDINGS_DIR: &str = "/cocoon/recordings";
+pub const RECORDING_EXT: &str = "cast";
+
+const DEFAULT_RETENTION_DAYS: u64 = 30;
+const DEFAULT_MAX_MB: u64 = 1024;
+const DEFAULT_WIDTH: u16 = 80;
+const DEFAULT_HEIGHT: u16 = 24;
+
+/// Whether Silk sessions are recorded ($COCOON_RECORD_SILK)
+pub fn recording_enabled() -> bool {
+    env_bool(EnvVar::CocoonRecordSilk.as_str())
+}
+
+/// Recordings directory ($COCOON_RECORDINGS_DIR or /cocoon/recordings)
+pub fn recordings_dir() -> PathBuf {
+    env_opt(EnvVar::CocoonRecordingsDir.as_str())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_RECORDINGS_DIR))
+}
+
+/// Start recording a Silk session if recording is enabled
+///
+/// Failures are logged and leave the session unrecorded; recording never
+/// blocks a session from starting.
+pub fn start_recording(session_id: &str, shell: &str) -> Option<Arc<Recorder>> {
+    if !recording_enabled() {
+        return None;
+    }
+
+    let dir = recordings_dir();
+    match RetentionPolicy::from_env().apply(&dir) {
+        Ok(0) => {}
+        Ok(n) => tracing::info!("🎬 Retention removed {} old recording(s)", n),
+        Err(e) => tracing::warn!("⚠️ Failed to apply recording retention: {}", e),
+    }
+
+    match Recorder::create(&dir, session_id, shell, DEFAULT_WIDTH, DEFAULT_HEIGHT) {
+        Ok(recorder) => {
+            tracing::info!(
+                "🎬 Recording silk session {} to {}",
+                session_id,
+                recorder.path().display()
+            );
+            Some(Arc::new(recorder))
+        }
+        Err(e) => {
+            tracing::warn!(
+                "⚠️ Failed to start recording for session {}: {}",
+                session_id,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// asciicast v2 header (first line of a `.cast` file)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CastHeader {
+    pub version: u32,
+    pub width: u16,
+    pub height: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// One recorded event: seconds since start, `"o"` (output) or `"i"` (input), data
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEvent {
+    pub time: f64,
+    pub kind: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cast {
+    pub header: CastHeader,
+    pub events: Vec<CastEvent>,
+}
+
+/// Appends events to a `.cast` file; shared by the tasks streaming a session's output
+pub struct Recorder {
+    path: PathBuf,
+    started: Instant,
+    file: Mutex<LineWriter<File>>,
+}
+
+impl Recorder {
+    pub fn create(
+        dir: &Path,
+        session_id: &str,
+        shell: &str,
+        width: u16,
+        height: u16,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let started_at = unix_now();
+        let path = dir.join(format!("{}-{}.{}", started_at, session_id, RECORDING_EXT));
+        let file = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(&path)?;
+        let mut file = LineWriter::new(file);
+
+        let header = CastHeader {
+            version: 2,
+            width,
+            height,
+            timestamp: Some(started_at),
+            title: Some(format!("silk {} ({})", session_id, shell)),
+        };
+        writeln!(file, "{}", serde_json::to_string(&header)?)?;
+
+        Ok(Self {
+            path,
+            started: Instant::now(),
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record terminal output
+    pub fn output(&self, data: &str) {
+        self.write_event("o", data);
+    }
+
+    /// Record input typed into an interactive command
+    pub fn input(&self, data: &str) {
+        self.write_event("i", data);
+    }
+
+    fn write_event(&self, kind: &str, data: &str) {
+        let time = self.started.elapsed().as_secs_f64();
+        let line = match serde_json::to_string(&(round_time(time), kind, data)) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+        if let Err(e) = writeln!(file, "{}", line) {
+            tracing::warn!(
+                "⚠️ Failed to write recording {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Which recordings to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    pub max_total_bytes: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn from_env() -> Self {
+        let days = env_opt(EnvVar::CocoonRecordingRetentionDays.as_str())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        let max_mb = env_opt(EnvVar::CocoonRecordingMaxMb.as_str())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_MB);
+
+        Self {
+            max_age: (days > 0).then(|| Duration::from_secs(days * 24 * 60 * 60)),
+            max_total_bytes: (max_mb > 0).then(|| max_mb * 1024 * 1024),
+        }
+    }
+
+    /// Delete recordings outside the policy, oldest first; returns how many were deleted
+    pub fn apply(&self, dir: &Path) -> io::Result<usize> {
+        let mut recordings = match list_recordings(dir) {
+            Ok(recordings) => recordings,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        recordings.sort_by_key(|r| r.started_at);
+
+        let now = unix_now();
+        let mut total: u64 = recordings.iter().map(|r| r.size).sum();
+        let mut removed = 0;
+
+        for recording in &recordings {
+            let too_old = self
+                .max_age
+                .is_some_and(|age| now.saturating_sub(recording.started_at) > age.as_secs());
+            let over_budget = self.max_total_bytes.is_some_and(|max| total > max);
+            if !too_old && !over_budget {
+                continue;
+            }
+
+            fs::remove_file(dir.join(recording.file_name()))?;
+            total = total.saturating_sub(recording.size);
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+}
+
+/// A recording file, as listed by the CLI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingInfo {
+    /// File stem: `<started-unix-secs>-<session-id>`
+    pub id: String,
+    pub started_at: u64,
+    pub size: u64,
+}
+
+impl RecordingInfo {
+    /// Parse a `<started>-<session>.cast` file name
+    pub fn from_file_name(name: &str, size: u64) -> Option<Self> {
+        let id = name.strip_suffix(&format!(".{}", RECORDING_EXT))?;
+        let (started, _session) = id.split_once('-')?;
+        Some(Self {
+            id: id.to_string(),
+            started_at: started.parse().ok()?,
+            size,
+        })
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.id, RECORDING_EXT)
+    }
+}
+
+/// File name for a recording id, rejecting anything that isn't one
+pub fn recording_file_name(id: &str) -> Result<String, String> {
+    let file_name = format!("{}.{}", id, RECORDING_EXT);
+    if id.contains(['/', '\\']) || RecordingInfo::from_file_name(&file_name, 0).is_none() {
+        return Err(format!("Invalid recording id: {}", id));
+    }
+    Ok(file_name)
+}
+
+/// Recordings in `dir`, newest first
+pub fn list_recordings(dir: &Path) -> io::Result<Vec<RecordingInfo>> {
+    let mut recordings = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let name = entry.file_name();
+        if let Some(info) = RecordingInfo::from_file_name(&name.to_string_lossy(), metadata.len()) {
+            recordings.push(info);
+        }
+    }
+    recordings.sort_by(|a, b| {
+        b.started_at
+            .cmp(&a.started_at)
+            .then_with(|| b.id.cmp(&a.id))
+    });
+    Ok(recordings)
+}
+
+/// Parse an asciicast v2 file
+///
+/// A truncated last line (the cocoon was killed mid-write) is ignored.
+pub fn parse_cast(text: &str) -> Result<Cast, String> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header_line = lines.next().ok_or("Empty recording")?;
+    let header: CastHeader = serde_json::from_str(header_line)
+        .map_err(|e| format!("Invalid recording header: {}", e))?;
+    if header.version != 2 {
+        return Err(format!("Unsupported asciicast version {}", header.version));
+    }
+
+    let events = lines
+        .filter_map(|line| serde_json::from_str::<(f64, String, String)>(line).ok())
+        .map(|(time, kind, data)| CastEvent { time, kind, data })
+        .collect();
+
+    Ok(Cast { header, events })
+}
+
+/// Replay the output events of a recording in real time
+///
+/// `speed` scales playback; pauses longer than `idle_limit` seconds are
+/// shortened to it.
+pub fn play(
+    cast: &Cast,
+    speed: f64,
+    idle_limit: Option<f64>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let speed = if speed > 0.0 { speed } else { 1.0 };
+    let mut last = 0.0;
+
+    for event in cast.events.iter().filter(|e| e.kind == "o") {
+        let mut delay = (event.time - last).max(0.0);
+        if let Some(limit) = idle_limit {
+            delay = delay.min(limit);
+        }
+        last = event.time;
+
+        if delay > 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(delay / speed));
+        }
+        out.write_all(event.data.as_bytes())?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+fn round_time(secs: f64) -> f64 {
+    (secs * 1_000_000.0).round() / 1_000_000.0
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = Recorder::create(dir.path(), "abc", "/bin/sh", 80, 24).unwrap();
+        recorder.output("$ echo hi\r\n");
+        recorder.output("hi\r\n");
+        recorder.input("q");
+
+        let text = fs::read_to_string(recorder.path()).unwrap();
+        let cast = parse_cast(&text).unwrap();
+        assert_eq!(cast.header.version, 2);
+        assert_eq!(cast.header.width, 80);
+        assert_eq!(cast.events.len(), 3);
+        assert_eq!(cast.events[1].data, "hi\r\n");
+        assert_eq!(cast.events[2].kind, "i");
+        assert!(cast.events.windows(2).all(|w| w[0].time <= w[1].time));
+    }
+
+    #[test]
+    fn test_parse_ignores_truncated_line() {
+        let text = "{\"version\":2,\"width\":80,\"height\":24}\n[0.1,\"o\",\"a\"]\n[0.2,\"o\",\"b";
+        let cast = parse_cast(text).unwrap();
+        assert_eq!(cast.events.len(), 1);
+
+        assert!(parse_cast("").is_err());
+        assert!(parse_cast("{\"version\":1,\"width\":80,\"height\":24}").is_err());
+    }
+
+    #[test]
+    fn test_play_writes_output_only() {
+        let cast = parse_cast(
+            "{\"version\":2,\"width\":80,\"height\":24}\n[0.0,\"o\",\"a\"]\n[0.0,\"i\",\"x\"]\n[5.0,\"o\",\"b\"]",
+        )
+        .unwrap();
+
+        let started = Instant::now();
+        let mut out = Vec::new();
+        play(&cast, 1.0, Some(0.01), &mut out).unwrap();
+
+        assert_eq!(out, b"ab");
+        assert!(
+            started.elapsed() < Duration::from_secs(1),
+            "idle limit caps the pause"
+        );
+    }
+
+    #[test]
+    fn test_recording_file_names() {
+        let info = RecordingInfo::from_file_name("1700000000-5f0c-11ee.cast", 42).unwrap();
+        assert_eq!(info.id, "1700000000-5f0c-11ee");
+        assert_eq!(info.started_at, 1_700_000_000);
+        assert_eq!(info.file_name(), "1700000000-5f0c-11ee.cast");
+
+        assert!(RecordingInfo::from_file_name("notes.txt", 1).is_none());
+        assert!(RecordingInfo::from_file_name("abc-def.cast", 1).is_none());
+
+        assert_eq!(
+            recording_file_name("1700000000-abc").unwrap(),
+            "1700000000-abc.cast"
+        );
+        assert!(recording_file_name("1700000000-../../etc/passwd").is_err());
+        assert!(recording_file_name("latest").is_err());
+    }
+
+    #[test]
+    fn test_retention_by_age_and_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = unix_now();
+        let write = |started: u64, len: usize| {
+            fs::write(
+                dir.path().join(format!("{}-s.cast", started)),
+                vec![b'x'; len],
+            )
+            .unwrap();
+        };
+        write(now - 10 * 86400, 10);
+        write(now - 3, 10);
+        write(now - 2, 10);
+        write(now - 1, 10);
+        fs::write(dir.path().join("keep.txt"), "not a recording").unwrap();
+
+        let policy = RetentionPolicy {
+            max_age: Some(Duration::from_secs(86400)),
+            max_total_bytes: Some(25),
+        };
+        assert_eq!(policy.apply(dir.path()).unwrap(), 2);
+
+        let left: Vec<u64> = list_recordings(dir.path())
+            .unwrap()
+            .iter()
+            .map(|r| r.started_at)
+            .collect();
+        assert_eq!(left, vec![now - 1, now - 2]);
+        assert!(dir.path().join("keep.txt").exists());
+
+        let missing = dir.path().join("missing");
+        assert_eq!(policy.apply(&missing).unwrap(), 0);
+    }
+}
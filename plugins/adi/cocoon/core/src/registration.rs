@@ -0,0 +1,212 @@
+//! Persistent registration state, so a cocoon that boots offline can keep
+//! running locally and finish registering once the signaling server is
+//! reachable again.
+
+use lib_retry::RetryPolicy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const REGISTRATION_CACHE_PATH: &str = "/cocoon/.registration.json";
+
+/// Backoff for reaching the signaling server, with up to 20% jitter
+pub const REGISTRATION_RETRY: RetryPolicy =
+    RetryPolicy::exponential(Duration::from_secs(1), Duration::from_secs(60)).jitter(20);
+
+/// Time allowed to open the WebSocket to the signaling server
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Time allowed for `DeviceRegisterResponse` after sending `DeviceRegister`
+pub const REGISTER_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Last known registration state, written after every successful registration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RegistrationCache {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Setup tokens not yet confirmed by the server (oldest first)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_claims: Vec<String>,
+    /// Setup token that produced the current owner; not sent again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_with: Option<String>,
+    /// UNIX seconds of the last confirmed registration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_registered_at: Option<u64>,
+}
+
+impl RegistrationCache {
+    /// Load the cache, falling back to an empty one if it is missing or unreadable.
+    pub async fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match tokio::fs::read_to_string(path).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "⚠️ Ignoring corrupt registration cache {}: {}",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Write the cache atomically (temp file + rename).
+    pub async fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("json.tmp");
+        let content = serde_json::to_vec_pretty(self)?;
+        tokio::fs::write(&tmp, content).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+
+    /// Switch to `device_id`, dropping ownership info that belonged to another device.
+    pub fn set_device_id(&mut self, device_id: Option<String>) {
+        if self.device_id != device_id {
+            self.owner_id = None;
+            self.claimed_with = None;
+            self.name = None;
+            self.last_registered_at = None;
+        }
+        self.device_id = device_id;
+    }
+
+    /// Queue a setup token to be sent on the next registration. Returns `false`
+    /// if it was already queued or already produced the current owner; a new
+    /// token is queued even while owned, to re-claim the cocoon.
+    pub fn add_pending_claim(&mut self, token: &str) -> bool {
+        if self.claimed_with.as_deref() == Some(token)
+            || self.pending_claims.iter().any(|t| t == token)
+        {
+            return false;
+        }
+        self.pending_claims.push(token.to_string());
+        true
+    }
+
+    /// Most recently queued setup token.
+    pub fn next_claim(&self) -> Option<&str> {
+        self.pending_claims.last().map(String::as_str)
+    }
+
+    /// Record a `DeviceRegisterResponse`. The server reports the current owner
+    /// in the tags, so a missing `owner_id` means the ownership was reset.
+    /// Pending claims are settled once an owner is reported.
+    pub fn record_registration(&mut self, device_id: &str, tags: Option<&HashMap<String, String>>) {
+        self.set_device_id(Some(device_id.to_string()));
+        self.last_registered_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs());
+
+        let Some(tags) = tags else {
+            return;
+        };
+        match tags.get("owner_id") {
+            Some(owner_id) => {
+                self.owner_id = Some(owner_id.clone());
+                if let Some(token) = self.pending_claims.pop() {
+                    self.claimed_with = Some(token);
+                }
+                self.pending_claims.clear();
+            }
+            None => self.owner_id = None,
+        }
+        if let Some(name) = tags.get("name") {
+            self.name = Some(name.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cache_roundtrip_and_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("registration.json");
+
+        assert_eq!(
+            RegistrationCache::load(&path).await,
+            RegistrationCache::default()
+        );
+
+        let mut cache = RegistrationCache::default();
+        cache.add_pending_claim("token-1");
+        cache.record_registration("dev-1", None);
+        cache.save(&path).await.unwrap();
+
+        assert_eq!(RegistrationCache::load(&path).await, cache);
+
+        tokio::fs::write(&path, "not json").await.unwrap();
+        assert_eq!(
+            RegistrationCache::load(&path).await,
+            RegistrationCache::default()
+        );
+    }
+
+    #[test]
+    fn test_claims_settle_when_owner_is_reported() {
+        let mut cache = RegistrationCache::default();
+        assert!(cache.add_pending_claim("token-1"));
+        assert!(cache.add_pending_claim("token-2"));
+        assert!(!cache.add_pending_claim("token-1"));
+        assert_eq!(cache.next_claim(), Some("token-2"));
+
+        // Registered but not claimed yet: keep the tokens for the next attempt
+        cache.record_registration("dev-1", Some(&HashMap::new()));
+        assert_eq!(cache.pending_claims.len(), 2);
+
+        let tags = HashMap::from([
+            ("owner_id".to_string(), "user-1".to_string()),
+            ("name".to_string(), "builder".to_string()),
+        ]);
+        cache.record_registration("dev-1", Some(&tags));
+        assert!(cache.pending_claims.is_empty());
+        assert_eq!(cache.owner_id.as_deref(), Some("user-1"));
+        assert_eq!(cache.name.as_deref(), Some("builder"));
+
+        cache.set_device_id(Some("dev-2".to_string()));
+        assert!(cache.owner_id.is_none());
+    }
+
+    #[test]
+    fn test_new_token_reclaims_owned_cocoon() {
+        let mut cache = RegistrationCache::default();
+        cache.add_pending_claim("token-1");
+        let owned = HashMap::from([("owner_id".to_string(), "user-1".to_string())]);
+        cache.record_registration("dev-1", Some(&owned));
+        assert_eq!(cache.claimed_with.as_deref(), Some("token-1"));
+
+        // The settled token is not sent again on restart
+        assert!(!cache.add_pending_claim("token-1"));
+        assert_eq!(cache.next_claim(), None);
+
+        // Ownership reset on the server
+        cache.record_registration("dev-1", Some(&HashMap::new()));
+        assert!(cache.owner_id.is_none());
+
+        // A fresh token is queued even if an owner is still cached
+        cache.record_registration("dev-1", Some(&owned));
+        assert!(cache.add_pending_claim("token-2"));
+        assert_eq!(cache.next_claim(), Some("token-2"));
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        let max = REGISTRATION_RETRY.max_delay;
+        let delays: Vec<_> = REGISTRATION_RETRY.backoff().take(101).collect();
+        assert!(delays[0] >= REGISTRATION_RETRY.initial_delay);
+        assert!(delays[0] < REGISTRATION_RETRY.initial_delay * 2);
+        assert!(delays[3] >= Duration::from_secs(8));
+        assert!(delays[100] >= max);
+        assert!(delays[100] <= max + max / 5);
+    }
+}
@@ -0,0 +1,346 @@
+/// Fault injection for the signaling relay connection (`chaos` feature).
+///
+/// CI soak tests set `COCOON_RELAY_CHAOS` to make the relay writer delay, drop,
+/// duplicate and reorder outbound messages. Every decision comes from a seeded
+/// generator, so a race found with `seed=1234` replays the same way on the next
+/// run as long as the cocoon enqueues the same messages.
+///
+/// Session-level messages (`auth_*`, `device_register`, `device_deregister`)
+/// always pass untouched: losing them only tests the reconnect loop, not the
+/// handshake logic the faults are meant to stress.
+use futures::{Sink, SinkExt};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Environment variable holding the chaos profile, e.g. `heavy,seed=7`
+pub const CHAOS_ENV: &str = "COCOON_RELAY_CHAOS";
+
+const CRITICAL_TYPES: &[&str] = &["device_register", "device_deregister"];
+const CRITICAL_PREFIXES: &[&str] = &["auth_"];
+
+/// Probabilities (0.0..=1.0) of each fault, applied per non-critical message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChaosProfile {
+    pub seed: u64,
+    pub delay: f64,
+    pub max_delay: Duration,
+    pub drop: f64,
+    pub duplicate: f64,
+    pub reorder: f64,
+}
+
+impl Default for ChaosProfile {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            delay: 0.0,
+            max_delay: Duration::from_millis(200),
+            drop: 0.0,
+            duplicate: 0.0,
+            reorder: 0.0,
+        }
+    }
+}
+
+impl ChaosProfile {
+    pub fn light() -> Self {
+        Self {
+            delay: 0.1,
+            drop: 0.01,
+            duplicate: 0.01,
+            reorder: 0.05,
+            ..Self::default()
+        }
+    }
+
+    pub fn heavy() -> Self {
+        Self {
+            delay: 0.3,
+            max_delay: Duration::from_millis(1000),
+            drop: 0.05,
+            duplicate: 0.05,
+            reorder: 0.2,
+            ..Self::default()
+        }
+    }
+
+    /// Profile from [`CHAOS_ENV`]; `None` when unset or invalid.
+    pub fn from_env() -> Option<Self> {
+        let spec = lib_env_parse::env_opt(CHAOS_ENV)?;
+        match spec.parse() {
+            Ok(profile) => Some(profile),
+            Err(e) => {
+                tracing::warn!("⚠️ Ignoring {}: {}", CHAOS_ENV, e);
+                None
+            }
+        }
+    }
+}
+
+impl FromStr for ChaosProfile {
+    type Err = String;
+
+    /// Comma-separated `key=value` pairs, optionally starting with a preset
+    /// (`light` or `heavy`) that later pairs override.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut profile = Self::default();
+        for (i, part) in spec.split(',').map(str::trim).enumerate() {
+            if part.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = part.split_once('=') else {
+                match (i, part) {
+                    (0, "light") => profile = Self::light(),
+                    (0, "heavy") => profile = Self::heavy(),
+                    _ => return Err(format!("expected key=value, got '{}'", part)),
+                }
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "seed" => {
+                    profile.seed = value
+                        .parse()
+                        .map_err(|_| format!("invalid seed '{}'", value))?
+                }
+                "max_delay_ms" => {
+                    let ms = value
+                        .parse()
+                        .map_err(|_| format!("invalid max_delay_ms '{}'", value))?;
+                    profile.max_delay = Duration::from_millis(ms);
+                }
+                "delay" => profile.delay = probability(key, value)?,
+                "drop" => profile.drop = probability(key, value)?,
+                "duplicate" => profile.duplicate = probability(key, value)?,
+                "reorder" => profile.reorder = probability(key, value)?,
+                _ => return Err(format!("unknown chaos key '{}'", key)),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+fn probability(key: &str, value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+        _ => Err(format!(
+            "{} must be a probability in 0..=1, got '{}'",
+            key, value
+        )),
+    }
+}
+
+/// SplitMix64: tiny and stable across platforms and crate versions, which is
+/// what makes a seed reproducible.
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        // Always draw so the sequence does not depend on which faults are enabled
+        let roll = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        roll < p
+    }
+}
+
+/// Whether a message must bypass fault injection.
+fn is_critical(msg: &Message) -> bool {
+    #[derive(serde::Deserialize)]
+    struct Tagged {
+        #[serde(rename = "type")]
+        kind: String,
+    }
+
+    let Message::Text(text) = msg else {
+        return true;
+    };
+    match serde_json::from_str::<Tagged>(text) {
+        Ok(Tagged { kind }) => {
+            CRITICAL_TYPES.contains(&kind.as_str())
+                || CRITICAL_PREFIXES.iter().any(|p| kind.starts_with(p))
+        }
+        Err(_) => true,
+    }
+}
+
+/// Per-connection fault injector driven by the relay writer.
+#[derive(Debug)]
+pub struct Chaos {
+    profile: ChaosProfile,
+    rng: SplitMix64,
+    /// Message held back by a reorder, written after the next one.
+    held: Option<Message>,
+    /// Set by [`release`](Self::release) so the released message is written
+    /// as is instead of being faulted a second time.
+    releasing: bool,
+    /// Non-critical messages seen, logged with each fault for replay.
+    step: u64,
+}
+
+impl Chaos {
+    pub fn new(profile: ChaosProfile) -> Self {
+        Self {
+            rng: SplitMix64(profile.seed),
+            profile,
+            held: None,
+            releasing: false,
+            step: 0,
+        }
+    }
+
+    /// Decide what to write for `msg`: each entry is a delay before the write
+    /// and the message to write. Empty means dropped or held back.
+    pub fn plan(&mut self, msg: Message) -> Vec<(Duration, Message)> {
+        if std::mem::take(&mut self.releasing) {
+            return vec![(Duration::ZERO, msg)];
+        }
+        if is_critical(&msg) {
+            let mut out = vec![(Duration::ZERO, msg)];
+            out.extend(self.held.take().map(|held| (Duration::ZERO, held)));
+            return out;
+        }
+
+        self.step += 1;
+        let step = self.step;
+        let drop = self.rng.chance(self.profile.drop);
+        let delay = self.rng.chance(self.profile.delay);
+        let delay_roll = self.rng.next_u64();
+        let duplicate = self.rng.chance(self.profile.duplicate);
+        let reorder = self.rng.chance(self.profile.reorder);
+
+        if drop {
+            tracing::debug!("🐒 chaos step {}: drop", step);
+            return self
+                .held
+                .take()
+                .map(|m| (Duration::ZERO, m))
+                .into_iter()
+                .collect();
+        }
+        if reorder && self.held.is_none() {
+            tracing::debug!("🐒 chaos step {}: hold for reorder", step);
+            self.held = Some(msg);
+            return Vec::new();
+        }
+
+        let wait = if delay {
+            let max = self.profile.max_delay.as_millis() as u64;
+            let ms = if max == 0 { 0 } else { delay_roll % (max + 1) };
+            tracing::debug!("🐒 chaos step {}: delay {}ms", step, ms);
+            Duration::from_millis(ms)
+        } else {
+            Duration::ZERO
+        };
+
+        let mut out = vec![(wait, msg.clone())];
+        if duplicate {
+            tracing::debug!("🐒 chaos step {}: duplicate", step);
+            out.push((Duration::ZERO, msg));
+        }
+        out.extend(self.held.take().map(|held| (Duration::ZERO, held)));
+        out
+    }
+
+    /// Hand back a message held for reordering, so nothing is lost when the
+    /// queue runs dry. The writer passes it straight to [`plan`](Self::plan).
+    pub fn release(&mut self) -> Option<Message> {
+        let held = self.held.take();
+        self.releasing = held.is_some();
+        held
+    }
+}
+
+/// Write a plan produced by [`Chaos::plan`].
+pub async fn send_planned<S>(sink: &mut S, plan: Vec<(Duration, Message)>) -> Result<(), S::Error>
+where
+    S: Sink<Message> + Unpin,
+{
+    for (wait, msg) in plan {
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        sink.send(msg).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(kind: &str, n: u32) -> Message {
+        Message::Text(format!(r#"{{"type":"{}","n":{}}}"#, kind, n))
+    }
+
+    fn run(profile: &ChaosProfile, count: u32) -> Vec<Message> {
+        let mut chaos = Chaos::new(profile.clone());
+        let mut out: Vec<Message> = (0..count)
+            .flat_map(|n| chaos.plan(text("sync_data", n)))
+            .map(|(_, m)| m)
+            .collect();
+        out.extend(chaos.release());
+        out
+    }
+
+    #[test]
+    fn parses_preset_with_overrides() {
+        let profile: ChaosProfile = "heavy, seed=42, drop=0".parse().unwrap();
+        assert_eq!(profile.seed, 42);
+        assert_eq!(profile.drop, 0.0);
+        assert_eq!(profile.reorder, ChaosProfile::heavy().reorder);
+
+        assert!("drop=1.5".parse::<ChaosProfile>().is_err());
+        assert!("seed=1,heavy".parse::<ChaosProfile>().is_err());
+        assert!("jitter=0.1".parse::<ChaosProfile>().is_err());
+    }
+
+    #[test]
+    fn same_seed_replays_same_faults() {
+        let profile: ChaosProfile = "heavy,seed=7,max_delay_ms=0".parse().unwrap();
+        let a = run(&profile, 200);
+        assert_eq!(a, run(&profile, 200));
+
+        let other = ChaosProfile { seed: 8, ..profile };
+        assert_ne!(a, run(&other, 200));
+    }
+
+    #[test]
+    fn faults_apply_as_configured() {
+        let dropped = run(&"drop=1".parse().unwrap(), 10);
+        assert!(dropped.is_empty());
+
+        let doubled = run(&"duplicate=1".parse().unwrap(), 10);
+        assert_eq!(doubled.len(), 20);
+
+        // Every other message is held and written after its successor
+        let swapped = run(&"reorder=1".parse().unwrap(), 4);
+        assert_eq!(
+            swapped,
+            vec![
+                text("sync_data", 1),
+                text("sync_data", 0),
+                text("sync_data", 3),
+                text("sync_data", 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn critical_messages_pass_untouched() {
+        let mut chaos = Chaos::new("drop=1,reorder=1".parse().unwrap());
+        for kind in ["device_register", "auth_authenticate", "device_deregister"] {
+            let plan = chaos.plan(text(kind, 0));
+            assert_eq!(plan, vec![(Duration::ZERO, text(kind, 0))]);
+        }
+        assert_eq!(chaos.plan(Message::Binary(vec![1])).len(), 1);
+    }
+}
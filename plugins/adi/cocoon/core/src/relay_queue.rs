@@ -0,0 +1,406 @@
+//! Prioritized outbound queue for the signaling relay connection.
+//!
+//! Every message the cocoon sends goes over a single WebSocket, so a large proxy
+//! body or query result can sit in front of a keystroke echo. `RelaySender`
+//! replaces direct writes to the sink: callers enqueue, and one writer task drains
+//! the queue in strict priority order. To keep bulk traffic from starving, a lower
+//! level is served once it has been passed over `STARVATION_LIMIT` times in a row.
+
+use futures::{Sink, SinkExt};
+use lib_signaling_protocol::{RelayPriority, SignalingMessage};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Consecutive higher-priority sends a waiting level tolerates before it is served.
+const STARVATION_LIMIT: u32 = 16;
+
+const LEVELS: usize = 3;
+
+fn level(priority: &RelayPriority) -> usize {
+    match priority {
+        RelayPriority::Interactive => 0,
+        RelayPriority::Normal => 1,
+        RelayPriority::Bulk => 2,
+    }
+}
+
+/// Scheduling class of an outbound message.
+///
+/// `SyncData` carries its own priority (defaulting to normal); every other
+/// signaling message is small control traffic and always goes first.
+pub fn priority_of(msg: &SignalingMessage) -> RelayPriority {
+    match msg {
+        SignalingMessage::SyncData {
+            priority: Some(p), ..
+        } => p.clone(),
+        SignalingMessage::SyncData { priority: None, .. } => RelayPriority::Normal,
+        _ => RelayPriority::Interactive,
+    }
+}
+
+/// Strict-priority queue with starvation protection.
+#[derive(Debug)]
+pub struct PriorityQueue<T> {
+    levels: [VecDeque<T>; LEVELS],
+    skipped: [u32; LEVELS],
+    limit: u32,
+}
+
+impl<T> Default for PriorityQueue<T> {
+    fn default() -> Self {
+        Self::with_starvation_limit(STARVATION_LIMIT)
+    }
+}
+
+impl<T> PriorityQueue<T> {
+    pub fn with_starvation_limit(limit: u32) -> Self {
+        Self {
+            levels: Default::default(),
+            skipped: [0; LEVELS],
+            limit: limit.max(1),
+        }
+    }
+
+    pub fn push(&mut self, priority: &RelayPriority, item: T) {
+        self.levels[level(priority)].push_back(item);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let highest = self.levels.iter().position(|q| !q.is_empty())?;
+
+        // A starved level wins over the strict choice; prefer the most urgent one
+        let chosen = (highest + 1..LEVELS)
+            .find(|&l| !self.levels[l].is_empty() && self.skipped[l] >= self.limit)
+            .unwrap_or(highest);
+
+        for l in 0..LEVELS {
+            if l == chosen {
+                self.skipped[l] = 0;
+            } else if l > chosen && !self.levels[l].is_empty() {
+                self.skipped[l] += 1;
+            }
+        }
+
+        self.levels[chosen].pop_front()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.iter().all(VecDeque::is_empty)
+    }
+}
+
+#[derive(Debug)]
+pub struct RelayClosed;
+
+impl std::fmt::Display for RelayClosed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "relay connection closed")
+    }
+}
+
+impl std::error::Error for RelayClosed {}
+
+#[derive(Default)]
+struct State {
+    queue: PriorityQueue<Message>,
+    in_flight: bool,
+    closed: bool,
+    /// Bumped on every attach/detach; writer tasks from older sinks exit.
+    generation: u64,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wake: Notify,
+    drained: Notify,
+    /// Fault profile applied afresh to each attached sink.
+    #[cfg(feature = "chaos")]
+    chaos: Mutex<Option<crate::relay_chaos::ChaosProfile>>,
+}
+
+/// Cloneable handle that enqueues messages for the relay writer task.
+#[derive(Clone)]
+pub struct RelaySender {
+    shared: Arc<Shared>,
+}
+
+impl RelaySender {
+    /// Take ownership of the socket sink and spawn the writer task draining the queue.
+    pub fn spawn<S>(sink: S) -> Self
+    where
+        S: Sink<Message> + Unpin + Send + 'static,
+        S::Error: std::fmt::Display,
+    {
+        let sender = Self::detached();
+        sender.attach(sink);
+        sender
+    }
+
+    /// Sender with no connection yet; sends fail with [`RelayClosed`] until
+    /// [`attach`](Self::attach) is called.
+    pub fn detached() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    closed: true,
+                    ..State::default()
+                }),
+                wake: Notify::new(),
+                drained: Notify::new(),
+                #[cfg(feature = "chaos")]
+                chaos: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Start writing to a new socket sink. Clones of this sender stay valid, so
+    /// tasks holding one keep working across reconnects.
+    pub fn attach<S>(&self, mut sink: S)
+    where
+        S: Sink<Message> + Unpin + Send + 'static,
+        S::Error: std::fmt::Display,
+    {
+        let generation = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.generation += 1;
+            state.closed = false;
+            state.in_flight = false;
+            state.queue = PriorityQueue::default();
+            state.generation
+        };
+        // Wake a writer from a previous sink so it notices and exits
+        self.shared.wake.notify_waiters();
+
+        let writer = self.shared.clone();
+        #[cfg(feature = "chaos")]
+        let mut chaos = writer
+            .chaos
+            .lock()
+            .unwrap()
+            .clone()
+            .map(crate::relay_chaos::Chaos::new);
+        tokio::spawn(async move {
+            loop {
+                let next = {
+                    let mut state = writer.state.lock().unwrap();
+                    if state.generation != generation {
+                        drop(state);
+                        // Pass on a wakeup we may have consumed
+                        writer.wake.notify_one();
+                        return;
+                    }
+                    let next = state.queue.pop();
+                    #[cfg(feature = "chaos")]
+                    let next = next.or_else(|| chaos.as_mut().and_then(|c| c.release()));
+                    state.in_flight = next.is_some();
+                    next
+                };
+
+                let Some(msg) = next else {
+                    writer.drained.notify_waiters();
+                    writer.wake.notified().await;
+                    continue;
+                };
+
+                #[cfg(feature = "chaos")]
+                let sent = match chaos.as_mut() {
+                    Some(c) => crate::relay_chaos::send_planned(&mut sink, c.plan(msg)).await,
+                    None => sink.send(msg).await,
+                };
+                #[cfg(not(feature = "chaos"))]
+                let sent = sink.send(msg).await;
+
+                if let Err(e) = sent {
+                    tracing::warn!("⚠️ Relay writer stopped: {}", e);
+                    let mut state = writer.state.lock().unwrap();
+                    if state.generation == generation {
+                        state.closed = true;
+                        state.in_flight = false;
+                        state.queue = PriorityQueue::default();
+                    }
+                    drop(state);
+                    writer.drained.notify_waiters();
+                    return;
+                }
+            }
+        });
+    }
+
+    /// Inject faults into everything written from the next [`attach`](Self::attach) on.
+    #[cfg(feature = "chaos")]
+    pub fn set_chaos(&self, profile: Option<crate::relay_chaos::ChaosProfile>) {
+        *self.shared.chaos.lock().unwrap() = profile;
+    }
+
+    /// Drop the current sink and reject sends until the next [`attach`](Self::attach).
+    pub fn detach(&self) {
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            state.generation += 1;
+            state.closed = true;
+            state.in_flight = false;
+            state.queue = PriorityQueue::default();
+        }
+        self.shared.wake.notify_waiters();
+        self.shared.drained.notify_waiters();
+    }
+
+    /// Enqueue a signaling message at the priority it declares.
+    pub fn send(&self, msg: &SignalingMessage) -> Result<(), RelayClosed> {
+        let text = serde_json::to_string(msg).expect("SignalingMessage serialization cannot fail");
+        self.send_raw(&priority_of(msg), Message::Text(text))
+    }
+
+    pub fn send_raw(&self, priority: &RelayPriority, msg: Message) -> Result<(), RelayClosed> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.closed {
+            return Err(RelayClosed);
+        }
+        state.queue.push(priority, msg);
+        drop(state);
+        self.shared.wake.notify_one();
+        Ok(())
+    }
+
+    /// Wait until everything enqueued so far has been written (or the writer stopped).
+    pub async fn flush(&self) {
+        loop {
+            let drained = self.shared.drained.notified();
+            tokio::pin!(drained);
+            drained.as_mut().enable();
+            {
+                let state = self.shared.state.lock().unwrap();
+                if state.closed || (state.queue.is_empty() && !state.in_flight) {
+                    return;
+                }
+            }
+            drained.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_priority_order() {
+        let mut q = PriorityQueue::default();
+        q.push(&RelayPriority::Bulk, "bulk");
+        q.push(&RelayPriority::Normal, "normal");
+        q.push(&RelayPriority::Interactive, "key");
+
+        assert_eq!(q.pop(), Some("key"));
+        assert_eq!(q.pop(), Some("normal"));
+        assert_eq!(q.pop(), Some("bulk"));
+        assert_eq!(q.pop(), None);
+    }
+
+    #[test]
+    fn fifo_within_level() {
+        let mut q = PriorityQueue::default();
+        q.push(&RelayPriority::Interactive, 1);
+        q.push(&RelayPriority::Interactive, 2);
+        q.push(&RelayPriority::Interactive, 3);
+
+        assert_eq!(q.pop(), Some(1));
+        assert_eq!(q.pop(), Some(2));
+        assert_eq!(q.pop(), Some(3));
+    }
+
+    #[test]
+    fn starved_level_is_served() {
+        let mut q = PriorityQueue::with_starvation_limit(3);
+        q.push(&RelayPriority::Bulk, "bulk");
+        for _ in 0..10 {
+            q.push(&RelayPriority::Interactive, "key");
+        }
+
+        let order: Vec<_> = std::iter::from_fn(|| q.pop()).collect();
+        assert_eq!(order[..4], ["key", "key", "key", "bulk"]);
+        assert_eq!(order.len(), 11);
+    }
+
+    #[test]
+    fn sync_data_priority_defaults_to_normal() {
+        let msg = SignalingMessage::SyncData {
+            payload: serde_json::Value::Null,
+            priority: None,
+        };
+        assert!(matches!(priority_of(&msg), RelayPriority::Normal));
+
+        let msg = SignalingMessage::DeviceDeregister {
+            device_id: "d".into(),
+            reason: None,
+        };
+        assert!(matches!(priority_of(&msg), RelayPriority::Interactive));
+    }
+
+    #[tokio::test]
+    async fn sender_drains_interactive_first() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded::<Message>();
+        let sender = RelaySender::spawn(tx);
+
+        // Hold the writer back by enqueueing under the lock
+        {
+            let mut state = sender.shared.state.lock().unwrap();
+            state
+                .queue
+                .push(&RelayPriority::Bulk, Message::Text("bulk".into()));
+            state
+                .queue
+                .push(&RelayPriority::Interactive, Message::Text("key".into()));
+        }
+        sender.shared.wake.notify_one();
+        sender.flush().await;
+
+        use futures::StreamExt;
+        assert_eq!(rx.next().await, Some(Message::Text("key".into())));
+        assert_eq!(rx.next().await, Some(Message::Text("bulk".into())));
+    }
+
+    #[tokio::test]
+    async fn sender_survives_reattach() {
+        let sender = RelaySender::detached();
+        let msg = SignalingMessage::DeviceDeregister {
+            device_id: "d".into(),
+            reason: None,
+        };
+        assert!(sender.send(&msg).is_err());
+
+        let (tx, _old_rx) = futures::channel::mpsc::unbounded::<Message>();
+        sender.attach(tx);
+        sender.detach();
+        assert!(sender.send(&msg).is_err());
+
+        let (tx, mut rx) = futures::channel::mpsc::unbounded::<Message>();
+        let clone = sender.clone();
+        sender.attach(tx);
+        clone.send(&msg).unwrap();
+        clone.flush().await;
+
+        use futures::StreamExt;
+        assert!(matches!(rx.next().await, Some(Message::Text(_))));
+    }
+
+    #[cfg(feature = "chaos")]
+    #[tokio::test]
+    async fn chaos_releases_held_message_when_idle() {
+        let sender = RelaySender::detached();
+        sender.set_chaos(Some("reorder=1".parse().unwrap()));
+        let (tx, mut rx) = futures::channel::mpsc::unbounded::<Message>();
+        sender.attach(tx);
+
+        let msg = SignalingMessage::SyncData {
+            payload: serde_json::Value::Null,
+            priority: None,
+        };
+        sender.send(&msg).unwrap();
+        sender.flush().await;
+
+        use futures::StreamExt;
+        assert!(matches!(rx.next().await, Some(Message::Text(_))));
+    }
+}
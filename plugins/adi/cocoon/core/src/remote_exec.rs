@@ -0,0 +1,642 @@
+//! One-shot remote command execution (`adi cocoon exec`).
+//!
+//! Connects to signaling as an app client, opens (or reuses) a Silk session on
+//! every target cocoon and streams the command output back. Silk replies carry
+//! no sender, so sessions are created one device at a time; every other event
+//! is routed by its session id.
+
+use crate::protocol::types::SilkStream;
+use futures::{SinkExt, StreamExt};
+use lib_signaling_protocol::{DeviceInfo, RelayPriority, SignalingMessage};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// How long a cocoon may take to answer a session request.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Device ids are long hashes; prefixes are shortened to this many characters.
+const SHORT_ID_LEN: usize = 12;
+
+/// Exit code reported for devices that never ran the command.
+const EXIT_FAILED: i32 = 255;
+
+/// Which cocoons to run on.
+#[derive(Debug, Clone)]
+pub enum ExecTarget {
+    /// Device id, or a unique prefix of one
+    Device(String),
+    /// All online cocoons with this tag (`gpu`) or tag value (`region=eu`)
+    Label(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ExecRequest {
+    pub signaling_url: String,
+    pub access_token: String,
+    pub target: ExecTarget,
+    pub command: String,
+    /// Where Silk sessions are remembered between runs, if anywhere
+    pub session_cache: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceExit {
+    pub device_id: String,
+    pub exit_code: i32,
+}
+
+/// Silk session per device id, reused by the next `exec` on that device.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecSessionCache {
+    #[serde(default)]
+    pub sessions: HashMap<String, Uuid>,
+}
+
+impl ExecSessionCache {
+    /// Load the cache, falling back to an empty one if it is missing or unreadable.
+    pub async fn load(path: impl AsRef<Path>) -> Self {
+        match tokio::fs::read_to_string(path.as_ref()).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Write the cache atomically (temp file + rename).
+    pub async fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(self)?).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+}
+
+/// Silk events relayed back from a cocoon. Only the fields `exec` needs.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum SilkEvent {
+    #[serde(rename = "silk_create_session_response")]
+    SessionCreated { session_id: Uuid },
+    #[serde(rename = "silk_output")]
+    Output {
+        session_id: Uuid,
+        command_id: String,
+        stream: SilkStream,
+        data: String,
+    },
+    #[serde(rename = "silk_interactive_required")]
+    InteractiveRequired {
+        session_id: Uuid,
+        command_id: String,
+        reason: String,
+        pty_session_id: Uuid,
+    },
+    #[serde(rename = "silk_command_completed")]
+    CommandCompleted {
+        session_id: Uuid,
+        command_id: String,
+        exit_code: i32,
+    },
+    #[serde(rename = "silk_error")]
+    Error {
+        #[serde(default)]
+        session_id: Option<Uuid>,
+        code: String,
+        message: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Splits streamed output into lines and prefixes each one.
+#[derive(Debug)]
+struct LinePrefixer {
+    prefix: String,
+    pending: String,
+}
+
+impl LinePrefixer {
+    fn new(prefix: String) -> Self {
+        Self {
+            prefix,
+            pending: String::new(),
+        }
+    }
+
+    /// Complete lines in `data`, prefixed; the unterminated rest is kept.
+    fn push(&mut self, data: &str) -> String {
+        self.pending.push_str(data);
+        let Some(end) = self.pending.rfind('\n') else {
+            return String::new();
+        };
+        let rest = self.pending.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        complete
+            .lines()
+            .map(|line| format!("{}{}\n", self.prefix, line))
+            .collect()
+    }
+
+    /// The unterminated last line, if any.
+    fn finish(&mut self) -> String {
+        if self.pending.is_empty() {
+            return String::new();
+        }
+        format!("{}{}\n", self.prefix, std::mem::take(&mut self.pending))
+    }
+}
+
+struct Target {
+    device_id: String,
+    command_id: String,
+    session_id: Option<Uuid>,
+    /// Session came from the cache and may no longer exist on the cocoon
+    reused: bool,
+    stdout: Option<LinePrefixer>,
+    stderr: Option<LinePrefixer>,
+    exit_code: Option<i32>,
+}
+
+impl Target {
+    fn write(&mut self, stream: SilkStream, data: &str) {
+        let prefixer = match stream {
+            SilkStream::Stdout => &mut self.stdout,
+            SilkStream::Stderr => &mut self.stderr,
+        };
+        let text = match prefixer {
+            Some(p) => p.push(data),
+            None => data.to_string(),
+        };
+        let _ = match stream {
+            SilkStream::Stdout => write_flush(&mut std::io::stdout(), &text),
+            SilkStream::Stderr => write_flush(&mut std::io::stderr(), &text),
+        };
+    }
+
+    fn finish(&mut self, exit_code: i32) {
+        if let Some(p) = &mut self.stdout {
+            print!("{}", p.finish());
+        }
+        if let Some(p) = &mut self.stderr {
+            eprint!("{}", p.finish());
+        }
+        self.exit_code = Some(exit_code);
+    }
+
+    fn fail(&mut self, message: &str) {
+        self.write(SilkStream::Stderr, &format!("{}\n", message));
+        self.finish(EXIT_FAILED);
+    }
+}
+
+/// Run `request.command` on the targeted cocoons, streaming their output to
+/// the local stdout/stderr. Output is prefixed per device when there is more
+/// than one target.
+pub async fn run_exec(request: ExecRequest) -> Result<Vec<DeviceExit>, String> {
+    let (ws, _) = tokio_tungstenite::connect_async(&request.signaling_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", request.signaling_url, e))?;
+    let (mut sink, mut stream) = ws.split();
+
+    let devices = authenticate(&mut sink, &mut stream, &request.access_token).await?;
+    let selected = select_devices(&devices, &request.target)?;
+
+    let mut cache = match &request.session_cache {
+        Some(path) => ExecSessionCache::load(path).await,
+        None => ExecSessionCache::default(),
+    };
+
+    let width = selected
+        .iter()
+        .map(|d| display_name(d).len())
+        .max()
+        .unwrap_or(0);
+    let prefixed = selected.len() > 1;
+    let mut targets: Vec<Target> = selected
+        .iter()
+        .map(|device| {
+            let prefix = format!("{:width$} | ", display_name(device), width = width);
+            let session_id = cache.sessions.get(device.device_id.as_str()).copied();
+            Target {
+                device_id: device.device_id.to_string(),
+                command_id: Uuid::new_v4().to_string(),
+                session_id,
+                reused: session_id.is_some(),
+                stdout: prefixed.then(|| LinePrefixer::new(prefix.clone())),
+                stderr: prefixed.then(|| LinePrefixer::new(prefix)),
+                exit_code: None,
+            }
+        })
+        .collect();
+
+    let mut to_create: VecDeque<usize> = VecDeque::new();
+    for (i, target) in targets.iter().enumerate() {
+        match target.session_id {
+            Some(session_id) => {
+                send_silk(
+                    &mut sink,
+                    &target.device_id,
+                    execute_payload(target, session_id, &request.command),
+                )
+                .await?
+            }
+            None => to_create.push_back(i),
+        }
+    }
+
+    let mut creating: Option<usize> = None;
+    while targets.iter().any(|t| t.exit_code.is_none()) {
+        if creating.is_none() {
+            if let Some(i) = to_create.pop_front() {
+                send_silk(
+                    &mut sink,
+                    &targets[i].device_id,
+                    serde_json::json!({ "type": "silk_create_session" }),
+                )
+                .await?;
+                creating = Some(i);
+            }
+        }
+
+        let next = match creating {
+            Some(i) => match tokio::time::timeout(SESSION_TIMEOUT, stream.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    targets[i].fail("No response from cocoon");
+                    creating = None;
+                    continue;
+                }
+            },
+            None => stream.next().await,
+        };
+
+        let text = match next {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(Message::Close(_))) | None => break,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(format!("Signaling connection failed: {}", e)),
+        };
+
+        let payload = match serde_json::from_str::<SignalingMessage>(&text) {
+            Ok(SignalingMessage::SyncData { payload, .. }) => payload,
+            Ok(SignalingMessage::SystemError { message }) => {
+                return Err(format!("Signaling error: {}", message))
+            }
+            _ => continue,
+        };
+        let Ok(event) = serde_json::from_value::<SilkEvent>(payload) else {
+            continue;
+        };
+
+        match event {
+            SilkEvent::SessionCreated { session_id } => {
+                let Some(i) = creating.take() else {
+                    continue;
+                };
+                let target = &mut targets[i];
+                target.session_id = Some(session_id);
+                cache.sessions.insert(target.device_id.clone(), session_id);
+                send_silk(
+                    &mut sink,
+                    &target.device_id,
+                    execute_payload(target, session_id, &request.command),
+                )
+                .await?;
+            }
+            SilkEvent::Output {
+                session_id,
+                command_id,
+                stream,
+                data,
+            } => {
+                if let Some(target) = find_target(&mut targets, session_id, &command_id) {
+                    target.write(stream, &data);
+                }
+            }
+            SilkEvent::CommandCompleted {
+                session_id,
+                command_id,
+                exit_code,
+            } => {
+                if let Some(target) = find_target(&mut targets, session_id, &command_id) {
+                    target.finish(exit_code);
+                }
+            }
+            SilkEvent::InteractiveRequired {
+                session_id,
+                command_id,
+                reason,
+                pty_session_id,
+            } => {
+                let Some(target) = find_target(&mut targets, session_id, &command_id) else {
+                    continue;
+                };
+                let device_id = target.device_id.clone();
+                target.fail(&format!(
+                    "Command needs a terminal ({}); use an interactive session instead",
+                    reason
+                ));
+                send_silk(
+                    &mut sink,
+                    &device_id,
+                    serde_json::json!({ "type": "pty_close", "session_id": pty_session_id }),
+                )
+                .await?;
+            }
+            SilkEvent::Error {
+                session_id: Some(session_id),
+                code,
+                message,
+            } => {
+                let Some(i) = targets
+                    .iter()
+                    .position(|t| t.session_id == Some(session_id) && t.exit_code.is_none())
+                else {
+                    continue;
+                };
+                if code == "session_not_found" && targets[i].reused {
+                    // Cocoon restarted since the session was cached
+                    cache.sessions.remove(&targets[i].device_id);
+                    targets[i].session_id = None;
+                    targets[i].reused = false;
+                    to_create.push_back(i);
+                } else {
+                    targets[i].fail(&format!("Silk error ({}): {}", code, message));
+                }
+            }
+            SilkEvent::Error {
+                session_id: None,
+                code,
+                message,
+            } => {
+                if let Some(i) = creating.take() {
+                    targets[i].fail(&format!("Failed to create session ({}): {}", code, message));
+                }
+            }
+            SilkEvent::Other => {}
+        }
+    }
+
+    for target in targets.iter_mut().filter(|t| t.exit_code.is_none()) {
+        target.fail("Connection closed before the command completed");
+    }
+
+    if let Some(path) = &request.session_cache {
+        if let Err(e) = cache.save(path).await {
+            tracing::warn!(
+                "Failed to save exec session cache {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+    let _ = sink.close().await;
+
+    Ok(targets
+        .into_iter()
+        .map(|t| DeviceExit {
+            device_id: t.device_id,
+            exit_code: t.exit_code.unwrap_or(EXIT_FAILED),
+        })
+        .collect())
+}
+
+fn write_flush(out: &mut impl Write, text: &str) -> std::io::Result<()> {
+    out.write_all(text.as_bytes())?;
+    out.flush()
+}
+
+pub(crate) async fn authenticate<S, R>(
+    sink: &mut S,
+    stream: &mut R,
+    access_token: &str,
+) -> Result<Vec<DeviceInfo>, String>
+where
+    S: futures::Sink<Message> + Unpin,
+    R: futures::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    let mut authenticating = false;
+    loop {
+        let next = tokio::time::timeout(SESSION_TIMEOUT, stream.next())
+            .await
+            .map_err(|_| "Timed out waiting for the signaling server".to_string())?;
+        let text = match next {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(format!("Signaling connection failed: {}", e)),
+            None => return Err("Signaling server closed the connection".to_string()),
+        };
+
+        match serde_json::from_str::<SignalingMessage>(&text) {
+            Ok(SignalingMessage::AuthHello { .. }) if !authenticating => {
+                authenticating = true;
+                let auth = SignalingMessage::AuthAuthenticate {
+                    access_token: access_token.to_string(),
+                };
+                send(sink, &auth).await?;
+            }
+            Ok(SignalingMessage::AuthHelloAuthed { devices, .. }) => return Ok(devices),
+            Ok(SignalingMessage::SystemError { message }) => {
+                return Err(format!("Authentication failed: {}", message))
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) async fn send<S>(sink: &mut S, msg: &SignalingMessage) -> Result<(), String>
+where
+    S: futures::Sink<Message> + Unpin,
+{
+    let json = serde_json::to_string(msg).map_err(|e| e.to_string())?;
+    sink.send(Message::Text(json))
+        .await
+        .map_err(|_| "Signaling connection closed".to_string())
+}
+
+/// Relay a Silk request to one device (app clients address devices via `to`).
+async fn send_silk<S>(sink: &mut S, device_id: &str, data: serde_json::Value) -> Result<(), String>
+where
+    S: futures::Sink<Message> + Unpin,
+{
+    let msg = SignalingMessage::SyncData {
+        payload: serde_json::json!({ "to": device_id, "data": data }),
+        priority: Some(RelayPriority::Interactive),
+    };
+    send(sink, &msg).await
+}
+
+fn execute_payload(target: &Target, session_id: Uuid, command: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "silk_execute",
+        "session_id": session_id,
+        "command": command,
+        "command_id": target.command_id,
+    })
+}
+
+fn find_target<'a>(
+    targets: &'a mut [Target],
+    session_id: Uuid,
+    command_id: &str,
+) -> Option<&'a mut Target> {
+    targets.iter_mut().find(|t| {
+        t.session_id == Some(session_id) && t.command_id == command_id && t.exit_code.is_none()
+    })
+}
+
+/// Online devices matching `target`.
+pub(crate) fn select_devices(
+    devices: &[DeviceInfo],
+    target: &ExecTarget,
+) -> Result<Vec<DeviceInfo>, String> {
+    match target {
+        ExecTarget::Device(id) => {
+            let exact = devices.iter().find(|d| d.device_id == *id);
+            let device = match exact {
+                Some(device) => device,
+                None => {
+                    let matches: Vec<_> = devices
+                        .iter()
+                        .filter(|d| d.device_id.starts_with(id.as_str()))
+                        .collect();
+                    match matches.as_slice() {
+                        [device] => *device,
+                        [] => return Err(format!("Device '{}' not found", id)),
+                        _ => {
+                            return Err(format!(
+                                "Device id '{}' is ambiguous ({} matches)",
+                                id,
+                                matches.len()
+                            ))
+                        }
+                    }
+                }
+            };
+            if !device.online {
+                return Err(format!("Device '{}' is offline", display_name(device)));
+            }
+            Ok(vec![device.clone()])
+        }
+        ExecTarget::Label(label) => {
+            let selected: Vec<DeviceInfo> = devices
+                .iter()
+                .filter(|d| d.online && matches_label(d, label))
+                .cloned()
+                .collect();
+            if selected.is_empty() {
+                return Err(format!("No online cocoons with label '{}'", label));
+            }
+            Ok(selected)
+        }
+    }
+}
+
+/// `gpu` matches any device with a `gpu` tag, `region=eu` only that value.
+fn matches_label(device: &DeviceInfo, label: &str) -> bool {
+    match label.split_once('=') {
+        Some((key, value)) => device.tags.get(key).is_some_and(|v| v == value),
+        None => device.tags.contains_key(label),
+    }
+}
+
+/// Tagged name if the cocoon has one, otherwise its shortened id.
+pub(crate) fn display_name(device: &DeviceInfo) -> String {
+    match device.tags.get("name") {
+        Some(name) => name.clone(),
+        None => device.device_id.chars().take(SHORT_ID_LEN).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str, online: bool, tags: &[(&str, &str)]) -> DeviceInfo {
+        DeviceInfo {
+            device_id: id.into(),
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            online,
+            device_type: Some("cocoon".to_string()),
+            device_config: None,
+        }
+    }
+
+    #[test]
+    fn test_line_prefixer() {
+        let mut prefixer = LinePrefixer::new("gpu-1 | ".to_string());
+        assert_eq!(prefixer.push("hel"), "");
+        assert_eq!(prefixer.push("lo\nwor"), "gpu-1 | hello\n");
+        assert_eq!(prefixer.push("ld\n\nbye"), "gpu-1 | world\ngpu-1 | \n");
+        assert_eq!(prefixer.finish(), "gpu-1 | bye\n");
+        assert_eq!(prefixer.finish(), "");
+    }
+
+    #[test]
+    fn test_select_by_label() {
+        let devices = vec![
+            device("aaa111", true, &[("gpu", "a100"), ("region", "eu")]),
+            device("bbb222", true, &[("gpu", "h100"), ("region", "us")]),
+            device("ccc333", false, &[("gpu", "a100")]),
+            device("ddd444", true, &[]),
+        ];
+
+        let ids = |target| -> Vec<String> {
+            select_devices(&devices, &target)
+                .unwrap()
+                .into_iter()
+                .map(|d| d.device_id.into_inner())
+                .collect()
+        };
+        assert_eq!(ids(ExecTarget::Label("gpu".into())), ["aaa111", "bbb222"]);
+        assert_eq!(ids(ExecTarget::Label("region=us".into())), ["bbb222"]);
+        assert!(select_devices(&devices, &ExecTarget::Label("tpu".into())).is_err());
+    }
+
+    #[test]
+    fn test_select_by_device_prefix() {
+        let devices = vec![
+            device("aaa111", true, &[]),
+            device("aab222", true, &[]),
+            device("ccc333", false, &[]),
+        ];
+
+        let selected = select_devices(&devices, &ExecTarget::Device("aaa".into())).unwrap();
+        assert_eq!(selected[0].device_id, "aaa111");
+        assert!(select_devices(&devices, &ExecTarget::Device("aa".into())).is_err());
+        assert!(select_devices(&devices, &ExecTarget::Device("ccc333".into())).is_err());
+        assert!(select_devices(&devices, &ExecTarget::Device("zzz".into())).is_err());
+    }
+
+    #[test]
+    fn test_silk_event_parsing() {
+        let event: SilkEvent = serde_json::from_value(serde_json::json!({
+            "type": "silk_command_completed",
+            "session_id": Uuid::nil(),
+            "command_id": "c1",
+            "exit_code": 3,
+            "cwd": "/tmp",
+        }))
+        .unwrap();
+        assert!(matches!(
+            event,
+            SilkEvent::CommandCompleted { exit_code: 3, .. }
+        ));
+
+        let event: SilkEvent = serde_json::from_value(
+            serde_json::json!({ "type": "pty_output", "session_id": Uuid::nil(), "data": "x" }),
+        )
+        .unwrap();
+        assert!(matches!(event, SilkEvent::Other));
+    }
+}
@@ -0,0 +1,659 @@
+//! Client side of TCP port forwarding (`adi cocoon forward`).
+//!
+//! Binds a local listener per forward and tunnels every accepted connection
+//! to the cocoon: over a WebRTC data channel of its own when a peer
+//! connection can be made, otherwise as a `forward_*` stream over the
+//! signaling relay.
+
+use crate::port_forward::{
+    channel_label, data_channel_receiver, deliver, pipe_data_channel, pipe_relay, ForwardSpec,
+    CONTROL_LABEL,
+};
+use crate::protocol::messages::CocoonMessage;
+use crate::remote_exec::{authenticate, display_name, select_devices, send, ExecTarget};
+use crate::webrtc::build_ice_servers;
+use futures::StreamExt;
+use lib_signaling_protocol::{RelayPriority, SignalingMessage};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+use webrtc::api::interceptor_registry::register_default_interceptors;
+use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::APIBuilder;
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::interceptor::registry::Registry;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+
+/// How long to wait for the peer connection before falling back to the relay.
+const WEBRTC_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long pending signaling messages may take to go out on exit.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long the cocoon may take to reach the target of a new connection.
+const OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct ForwardRequest {
+    pub signaling_url: String,
+    pub access_token: String,
+    /// Device id, or a unique prefix of one
+    pub device: String,
+    pub specs: Vec<ForwardSpec>,
+}
+
+/// Connection counters of one forward, printed as connections come and go.
+#[derive(Debug, Default)]
+struct ForwardStatus {
+    active: usize,
+    total: u64,
+    sent: u64,
+    received: u64,
+}
+
+/// Signaling messages addressed to the target cocoon.
+#[derive(Clone)]
+struct DeviceSender {
+    device_id: String,
+    outbox: mpsc::UnboundedSender<SignalingMessage>,
+}
+
+impl DeviceSender {
+    fn send(&self, msg: &CocoonMessage, priority: RelayPriority) {
+        let _ = self.outbox.send(SignalingMessage::SyncData {
+            payload: serde_json::json!({ "to": self.device_id, "data": msg }),
+            priority: Some(priority),
+        });
+    }
+}
+
+/// Relay streams waiting for `forward_opened`, and streams being piped.
+#[derive(Default)]
+struct RelayStreams {
+    opening: HashMap<String, oneshot::Sender<Result<(), String>>>,
+    open: HashMap<String, mpsc::UnboundedSender<Vec<u8>>>,
+}
+
+type SharedStreams = Arc<Mutex<RelayStreams>>;
+
+struct Tunnel {
+    device: DeviceSender,
+    streams: SharedStreams,
+    /// Set once the WebRTC connection is up
+    peer: OnceLock<Arc<RTCPeerConnection>>,
+    next_id: AtomicU64,
+}
+
+/// Forward `request.specs` to the cocoon until interrupted with Ctrl+C or the
+/// signaling connection drops.
+pub async fn run_forward(request: ForwardRequest) -> Result<(), String> {
+    let (ws, _) = tokio_tungstenite::connect_async(&request.signaling_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", request.signaling_url, e))?;
+    let (mut sink, mut stream) = ws.split();
+
+    let devices = authenticate(&mut sink, &mut stream, &request.access_token).await?;
+    let device = select_devices(&devices, &ExecTarget::Device(request.device.clone()))?.remove(0);
+    let name = display_name(&device);
+
+    // Bind everything up front so a busy port fails before anything is opened
+    let mut listeners = Vec::new();
+    for spec in &request.specs {
+        let listener = TcpListener::bind(("127.0.0.1", spec.local_port))
+            .await
+            .map_err(|e| format!("Failed to listen on port {}: {}", spec.local_port, e))?;
+        listeners.push((spec.clone(), listener));
+    }
+
+    let (outbox, mut outbox_rx) = mpsc::unbounded_channel::<SignalingMessage>();
+    let writer = tokio::spawn(async move {
+        while let Some(msg) = outbox_rx.recv().await {
+            if send(&mut sink, &msg).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let session_id = Uuid::new_v4().to_string();
+    let streams = SharedStreams::default();
+    let (webrtc_tx, mut webrtc_rx) = mpsc::unbounded_channel::<CocoonMessage>();
+    let reader_streams = streams.clone();
+    let reader_session = session_id.clone();
+    let mut reader = tokio::spawn(async move {
+        while let Some(Ok(msg)) = stream.next().await {
+            let Message::Text(text) = msg else {
+                continue;
+            };
+            let Ok(SignalingMessage::SyncData { payload, .. }) = serde_json::from_str(&text) else {
+                continue;
+            };
+            let Ok(msg) = serde_json::from_value::<CocoonMessage>(payload) else {
+                continue;
+            };
+            match msg {
+                CocoonMessage::WebrtcAnswer { ref session_id, .. }
+                | CocoonMessage::WebrtcIceCandidate { ref session_id, .. }
+                | CocoonMessage::WebrtcSessionEnded { ref session_id, .. }
+                | CocoonMessage::WebrtcError { ref session_id, .. }
+                    if *session_id == reader_session =>
+                {
+                    let _ = webrtc_tx.send(msg);
+                }
+                msg => handle_relay_message(&reader_streams, msg),
+            }
+        }
+    });
+
+    let device = DeviceSender {
+        device_id: device.device_id.into(),
+        outbox,
+    };
+    for (spec, _) in &listeners {
+        println!(
+            "Forwarding 127.0.0.1:{} -> {}:{}:{}",
+            spec.local_port, name, spec.host, spec.port
+        );
+    }
+
+    let tunnel = Arc::new(Tunnel {
+        device: device.clone(),
+        streams,
+        peer: OnceLock::new(),
+        next_id: AtomicU64::new(1),
+    });
+    let statuses: Vec<Arc<Mutex<ForwardStatus>>> = listeners
+        .iter()
+        .map(|_| Arc::new(Mutex::new(ForwardStatus::default())))
+        .collect();
+
+    // Connections accepted before WebRTC is up go through the relay
+    let accept_loops: Vec<_> = listeners
+        .into_iter()
+        .zip(statuses.iter().cloned())
+        .map(|((spec, listener), status)| {
+            let tunnel = tunnel.clone();
+            tokio::spawn(async move {
+                while let Ok((tcp, _)) = listener.accept().await {
+                    let id = tunnel.next_id.fetch_add(1, Ordering::Relaxed);
+                    tokio::spawn(forward_connection(
+                        tunnel.clone(),
+                        spec.clone(),
+                        status.clone(),
+                        id,
+                        tcp,
+                    ));
+                }
+            })
+        })
+        .collect();
+
+    let webrtc = tokio::spawn({
+        let tunnel = tunnel.clone();
+        let session_id = session_id.clone();
+        async move {
+            let peer = match connect_webrtc(&tunnel.device, &session_id, &mut webrtc_rx).await {
+                Ok(peer) => peer,
+                Err(e) => {
+                    println!(
+                        "WebRTC unavailable ({}), tunneling through the signaling relay",
+                        e
+                    );
+                    return;
+                }
+            };
+            println!("Connected to {} over WebRTC", name);
+            let _ = tunnel.peer.set(peer.clone());
+
+            // Trickled candidates keep arriving after the connection is up
+            while let Some(msg) = webrtc_rx.recv().await {
+                if let CocoonMessage::WebrtcIceCandidate {
+                    candidate,
+                    sdp_mid,
+                    sdp_mline_index,
+                    ..
+                } = msg
+                {
+                    let _ = peer
+                        .add_ice_candidate(ice_candidate(candidate, sdp_mid, sdp_mline_index))
+                        .await;
+                }
+            }
+        }
+    });
+
+    let result = tokio::select! {
+        _ = tokio::signal::ctrl_c() => Ok(()),
+        _ = &mut reader => Err("Signaling connection closed".to_string()),
+    };
+
+    webrtc.abort();
+    for accept_loop in accept_loops {
+        accept_loop.abort();
+    }
+    if let Some(peer) = tunnel.peer.get() {
+        device.send(
+            &CocoonMessage::WebrtcSessionEnded {
+                session_id,
+                reason: Some("forward_stopped".to_string()),
+            },
+            RelayPriority::Interactive,
+        );
+        let _ = peer.close().await;
+    }
+
+    println!();
+    for (spec, status) in request.specs.iter().zip(&statuses) {
+        let status = status.lock().unwrap();
+        println!(
+            "{}: {} connections, {} sent, {} received",
+            spec,
+            status.total,
+            format_bytes(status.sent),
+            format_bytes(status.received)
+        );
+    }
+
+    // Give the writer a moment to deliver the session end
+    reader.abort();
+    let _ = tokio::time::timeout(FLUSH_TIMEOUT, writer).await;
+    result
+}
+
+/// Tunnel one accepted connection and report it in the status output.
+async fn forward_connection(
+    tunnel: Arc<Tunnel>,
+    spec: ForwardSpec,
+    status: Arc<Mutex<ForwardStatus>>,
+    id: u64,
+    tcp: TcpStream,
+) {
+    let active = {
+        let mut status = status.lock().unwrap();
+        status.active += 1;
+        status.total += 1;
+        status.active
+    };
+    println!("[{}] #{} opened ({} active)", spec.local_port, id, active);
+
+    let peer = tunnel
+        .peer
+        .get()
+        .filter(|peer| peer.connection_state() == RTCPeerConnectionState::Connected);
+    let result = match peer {
+        Some(peer) => webrtc_connection(peer, &spec, id, tcp).await,
+        None => relay_connection(&tunnel, &spec, tcp).await,
+    };
+
+    let mut status = status.lock().unwrap();
+    status.active -= 1;
+    match result {
+        Ok((sent, received)) => {
+            status.sent += sent;
+            status.received += received;
+            println!(
+                "[{}] #{} closed, {} sent, {} received ({} active)",
+                spec.local_port,
+                id,
+                format_bytes(sent),
+                format_bytes(received),
+                status.active
+            );
+        }
+        Err(e) => println!(
+            "[{}] #{} failed: {} ({} active)",
+            spec.local_port, id, e, status.active
+        ),
+    }
+}
+
+async fn webrtc_connection(
+    peer: &RTCPeerConnection,
+    spec: &ForwardSpec,
+    id: u64,
+    tcp: TcpStream,
+) -> Result<(u64, u64), String> {
+    let dc = peer
+        .create_data_channel(&channel_label(id, &spec.host, spec.port), None)
+        .await
+        .map_err(|e| format!("Failed to open data channel: {}", e))?;
+    let incoming = data_channel_receiver(&dc);
+
+    let (open_tx, open_rx) = oneshot::channel();
+    let open_tx = Mutex::new(Some(open_tx));
+    dc.on_open(Box::new(move || {
+        if let Some(tx) = open_tx.lock().unwrap().take() {
+            let _ = tx.send(());
+        }
+        Box::pin(async {})
+    }));
+    if !matches!(
+        tokio::time::timeout(OPEN_TIMEOUT, open_rx).await,
+        Ok(Ok(()))
+    ) {
+        let _ = dc.close().await;
+        return Err("Data channel did not open".to_string());
+    }
+
+    Ok(pipe_data_channel(dc, incoming, tcp).await)
+}
+
+async fn relay_connection(
+    tunnel: &Tunnel,
+    spec: &ForwardSpec,
+    tcp: TcpStream,
+) -> Result<(u64, u64), String> {
+    let stream_id = Uuid::new_v4().to_string();
+    let (open_tx, open_rx) = oneshot::channel();
+    let (data_tx, data_rx) = mpsc::unbounded_channel();
+    {
+        let mut streams = tunnel.streams.lock().unwrap();
+        streams.opening.insert(stream_id.clone(), open_tx);
+        streams.open.insert(stream_id.clone(), data_tx);
+    }
+
+    tunnel.device.send(
+        &CocoonMessage::ForwardOpen {
+            stream_id: stream_id.clone(),
+            host: spec.host.clone(),
+            port: spec.port as i32,
+        },
+        RelayPriority::Normal,
+    );
+
+    let opened = match tokio::time::timeout(OPEN_TIMEOUT, open_rx).await {
+        Ok(Ok(result)) => result,
+        _ => {
+            tunnel.device.send(
+                &CocoonMessage::ForwardClose {
+                    stream_id: stream_id.clone(),
+                    error: None,
+                },
+                RelayPriority::Normal,
+            );
+            Err("No response from cocoon".to_string())
+        }
+    };
+    if let Err(e) = opened {
+        let mut streams = tunnel.streams.lock().unwrap();
+        streams.opening.remove(&stream_id);
+        streams.open.remove(&stream_id);
+        return Err(e);
+    }
+
+    let device = tunnel.device.clone();
+    let result = pipe_relay(stream_id.clone(), data_rx, tcp, move |msg| {
+        device.send(&msg, RelayPriority::Normal)
+    })
+    .await;
+    tunnel.streams.lock().unwrap().open.remove(&stream_id);
+    Ok(result)
+}
+
+fn handle_relay_message(streams: &SharedStreams, msg: CocoonMessage) {
+    let mut streams = streams.lock().unwrap();
+    match msg {
+        CocoonMessage::ForwardOpened { stream_id } => {
+            if let Some(tx) = streams.opening.remove(&stream_id) {
+                let _ = tx.send(Ok(()));
+            }
+        }
+        CocoonMessage::ForwardData { stream_id, data } => {
+            deliver(&streams.open, &stream_id, &data);
+        }
+        CocoonMessage::ForwardClose { stream_id, error } => {
+            if let Some(tx) = streams.opening.remove(&stream_id) {
+                let _ = tx.send(Err(error.unwrap_or_else(|| "Closed by cocoon".to_string())));
+            }
+            // Dropping the sender ends the pipe
+            streams.open.remove(&stream_id);
+        }
+        _ => {}
+    }
+}
+
+/// Offer a peer connection to the cocoon and wait until it is connected.
+async fn connect_webrtc(
+    device: &DeviceSender,
+    session_id: &str,
+    events: &mut mpsc::UnboundedReceiver<CocoonMessage>,
+) -> Result<Arc<RTCPeerConnection>, String> {
+    let mut media_engine = MediaEngine::default();
+    let registry = register_default_interceptors(Registry::new(), &mut media_engine)
+        .map_err(|e| format!("Failed to register interceptors: {}", e))?;
+    let api = APIBuilder::new()
+        .with_media_engine(media_engine)
+        .with_interceptor_registry(registry)
+        .build();
+    let config = RTCConfiguration {
+        ice_servers: build_ice_servers(),
+        ..Default::default()
+    };
+    let peer = Arc::new(
+        api.new_peer_connection(config)
+            .await
+            .map_err(|e| format!("Failed to create peer connection: {}", e))?,
+    );
+
+    let result = tokio::time::timeout(WEBRTC_TIMEOUT, negotiate(&peer, device, session_id, events))
+        .await
+        .unwrap_or_else(|_| Err("timed out".to_string()));
+    match result {
+        Ok(()) => Ok(peer),
+        Err(e) => {
+            device.send(
+                &CocoonMessage::WebrtcSessionEnded {
+                    session_id: session_id.to_string(),
+                    reason: Some("forward_fallback".to_string()),
+                },
+                RelayPriority::Interactive,
+            );
+            let _ = peer.close().await;
+            Err(e)
+        }
+    }
+}
+
+async fn negotiate(
+    peer: &Arc<RTCPeerConnection>,
+    device: &DeviceSender,
+    session_id: &str,
+    events: &mut mpsc::UnboundedReceiver<CocoonMessage>,
+) -> Result<(), String> {
+    // Forwarded connections open their channels later; this one puts SCTP
+    // into the offer
+    peer.create_data_channel(CONTROL_LABEL, None)
+        .await
+        .map_err(|e| format!("Failed to create data channel: {}", e))?;
+
+    let (state_tx, mut state_rx) = mpsc::unbounded_channel();
+    peer.on_peer_connection_state_change(Box::new(move |state| {
+        let _ = state_tx.send(state);
+        Box::pin(async {})
+    }));
+
+    let candidate_device = device.clone();
+    let candidate_session = session_id.to_string();
+    peer.on_ice_candidate(Box::new(move |candidate| {
+        if let Some(json) = candidate.and_then(|c| c.to_json().ok()) {
+            // webrtc-rs leaves sdp_mid empty; the data channel is media section "0"
+            let sdp_mid = match json.sdp_mid.as_deref() {
+                Some("") | None => Some("0".to_string()),
+                other => other.map(|s| s.to_string()),
+            };
+            candidate_device.send(
+                &CocoonMessage::WebrtcIceCandidate {
+                    session_id: candidate_session.clone(),
+                    candidate: json.candidate,
+                    sdp_mid,
+                    sdp_mline_index: json.sdp_mline_index.map(|i| i as i32),
+                },
+                RelayPriority::Interactive,
+            );
+        }
+        Box::pin(async {})
+    }));
+
+    let offer = peer
+        .create_offer(None)
+        .await
+        .map_err(|e| format!("Failed to create offer: {}", e))?;
+
+    // Candidates are gathered once the local description is set, so the
+    // cocoon learns about the session before the first one arrives
+    device.send(
+        &CocoonMessage::WebrtcStartSession {
+            session_id: session_id.to_string(),
+            device_id: device.device_id.clone(),
+            user_id: None,
+            data_channels: Some(vec![CONTROL_LABEL.to_string()]),
+            delegated_token: None,
+        },
+        RelayPriority::Interactive,
+    );
+    device.send(
+        &CocoonMessage::WebrtcOffer {
+            session_id: session_id.to_string(),
+            sdp: offer.sdp.clone(),
+        },
+        RelayPriority::Interactive,
+    );
+    peer.set_local_description(offer)
+        .await
+        .map_err(|e| format!("Failed to set local description: {}", e))?;
+
+    let mut answered = false;
+    let mut pending = Vec::new();
+    loop {
+        tokio::select! {
+            state = state_rx.recv() => match state {
+                Some(RTCPeerConnectionState::Connected) => return Ok(()),
+                Some(RTCPeerConnectionState::Failed) | Some(RTCPeerConnectionState::Closed) | None => {
+                    return Err("connection failed".to_string())
+                }
+                Some(_) => {}
+            },
+            msg = events.recv() => match msg {
+                Some(CocoonMessage::WebrtcAnswer { sdp, .. }) => {
+                    let answer = RTCSessionDescription::answer(sdp).map_err(|e| e.to_string())?;
+                    peer.set_remote_description(answer)
+                        .await
+                        .map_err(|e| format!("Failed to set remote description: {}", e))?;
+                    answered = true;
+                    for candidate in pending.drain(..) {
+                        let _ = peer.add_ice_candidate(candidate).await;
+                    }
+                }
+                Some(CocoonMessage::WebrtcIceCandidate { candidate, sdp_mid, sdp_mline_index, .. }) => {
+                    let candidate = ice_candidate(candidate, sdp_mid, sdp_mline_index);
+                    if answered {
+                        let _ = peer.add_ice_candidate(candidate).await;
+                    } else {
+                        pending.push(candidate);
+                    }
+                }
+                Some(CocoonMessage::WebrtcError { message, .. }) => return Err(message),
+                Some(CocoonMessage::WebrtcSessionEnded { reason, .. }) => {
+                    return Err(reason.unwrap_or_else(|| "session ended".to_string()))
+                }
+                Some(_) => {}
+                None => return Err("signaling connection closed".to_string()),
+            },
+        }
+    }
+}
+
+fn ice_candidate(
+    candidate: String,
+    sdp_mid: Option<String>,
+    sdp_mline_index: Option<i32>,
+) -> RTCIceCandidateInit {
+    RTCIceCandidateInit {
+        candidate,
+        sdp_mid,
+        sdp_mline_index: sdp_mline_index.map(|i| i as u16),
+        ..Default::default()
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+    }
+
+    #[test]
+    fn test_relay_messages_route_by_stream() {
+        let streams = SharedStreams::default();
+        let (open_tx, mut open_rx) = oneshot::channel();
+        let (data_tx, mut data_rx) = mpsc::unbounded_channel();
+        {
+            let mut s = streams.lock().unwrap();
+            s.opening.insert("a".to_string(), open_tx);
+            s.open.insert("a".to_string(), data_tx);
+        }
+
+        handle_relay_message(
+            &streams,
+            CocoonMessage::ForwardOpened {
+                stream_id: "b".to_string(),
+            },
+        );
+        assert!(open_rx.try_recv().is_err());
+
+        handle_relay_message(
+            &streams,
+            CocoonMessage::ForwardOpened {
+                stream_id: "a".to_string(),
+            },
+        );
+        assert_eq!(open_rx.try_recv().unwrap(), Ok(()));
+
+        handle_relay_message(
+            &streams,
+            CocoonMessage::ForwardData {
+                stream_id: "a".to_string(),
+                data: "aGk=".to_string(),
+            },
+        );
+        assert_eq!(data_rx.try_recv().unwrap(), b"hi");
+
+        handle_relay_message(
+            &streams,
+            CocoonMessage::ForwardClose {
+                stream_id: "a".to_string(),
+                error: None,
+            },
+        );
+        assert!(streams.lock().unwrap().open.is_empty());
+        assert!(data_rx.try_recv().is_err());
+    }
+}
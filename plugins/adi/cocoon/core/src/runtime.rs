@@ -1,3 +1,4 @@
+use crate::recording::{self, RecordingInfo};
 use crate::self_update;
 use lib_console_output::{out_info, KeyValue, Renderable};
 use std::fmt;
@@ -80,6 +81,10 @@ pub trait Runtime {
     fn runtime_type(&self) -> RuntimeType;
     fn update(&self, name: &str) -> Result<String, String>;
     fn check_update(&self, name: &str) -> Result<String, String>;
+    /// Silk session recordings stored on the cocoon, newest first
+    fn list_recordings(&self, name: &str) -> Result<Vec<RecordingInfo>, String>;
+    /// Raw `.cast` contents of one recording
+    fn read_recording(&self, name: &str, id: &str) -> Result<Vec<u8>, String>;
 }
 
 pub struct DockerRuntime;
@@ -334,6 +339,53 @@ impl Runtime for DockerRuntime {
 
         Ok(hint)
     }
+
+    fn list_recordings(&self, name: &str) -> Result<Vec<RecordingInfo>, String> {
+        // `stat -c` works with both GNU coreutils and busybox images
+        let script = format!(
+            "cd {} 2>/dev/null || exit 0; for f in *.{}; do [ -f \"$f\" ] && stat -c '%s %n' \"$f\"; done; exit 0",
+            recording::DEFAULT_RECORDINGS_DIR,
+            recording::RECORDING_EXT
+        );
+        let output = std::process::Command::new("docker")
+            .args(["exec", name, "sh", "-c", &script])
+            .output()
+            .map_err(|e| format!("Failed to run docker: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("Failed to list recordings: {}", stderr.trim()));
+        }
+
+        let mut recordings: Vec<RecordingInfo> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (size, file) = line.split_once(' ')?;
+                RecordingInfo::from_file_name(file, size.parse().ok()?)
+            })
+            .collect();
+        recordings.sort_by(|a, b| {
+            b.started_at
+                .cmp(&a.started_at)
+                .then_with(|| b.id.cmp(&a.id))
+        });
+        Ok(recordings)
+    }
+
+    fn read_recording(&self, name: &str, id: &str) -> Result<Vec<u8>, String> {
+        let file_name = recording::recording_file_name(id)?;
+        let path = format!("{}/{}", recording::DEFAULT_RECORDINGS_DIR, file_name);
+        let output = std::process::Command::new("docker")
+            .args(["exec", name, "cat", &path])
+            .output()
+            .map_err(|e| format!("Failed to run docker: {}", e))?;
+
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(format!("Recording '{}' not found in '{}'", id, name))
+        }
+    }
 }
 
 const SERVICE_NAME: &str = "adi.cocoon";
@@ -525,6 +577,19 @@ impl Runtime for MachineRuntime {
         let check_result = self_update::check_for_updates()?;
         Ok(self_update::format_check_result(&check_result))
     }
+
+    fn list_recordings(&self, _name: &str) -> Result<Vec<RecordingInfo>, String> {
+        match recording::list_recordings(&recording::recordings_dir()) {
+            Ok(recordings) => Ok(recordings),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(format!("Failed to list recordings: {}", e)),
+        }
+    }
+
+    fn read_recording(&self, _name: &str, id: &str) -> Result<Vec<u8>, String> {
+        let path = recording::recordings_dir().join(recording::recording_file_name(id)?);
+        std::fs::read(&path).map_err(|e| format!("Failed to read recording '{}': {}", id, e))
+    }
 }
 
 pub struct RuntimeManager {
@@ -0,0 +1,163 @@
+//! Hive service logs relayed upstream (`stream_service_logs`).
+//!
+//! The cocoon follows the local hive daemon's log stream and passes every
+//! line through a [`LogShipper`] before it is queued on the relay. Shipping
+//! configs come from the device config (`log_shipping`) and apply to running
+//! streams as soon as a new one is pushed. Local `adi hive logs -f` reads the
+//! daemon directly and is never throttled.
+
+use crate::log_shipper::{LogShipper, LogShippingConfig};
+use crate::relay_queue::RelaySender;
+use lib_hive_daemon_client::{DaemonClient, LogLine};
+use lib_signaling_protocol::{RelayPriority, SignalingMessage};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+/// How often pending "repeated N times" summaries are sent
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServiceLogEvent {
+    ServiceLogs {
+        stream_id: Uuid,
+        lines: Vec<LogLine>,
+    },
+    ServiceLogsEnded {
+        stream_id: Uuid,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+impl ServiceLogEvent {
+    fn into_sync_data(self) -> SignalingMessage {
+        SignalingMessage::SyncData {
+            payload: serde_json::to_value(&self)
+                .expect("ServiceLogEvent serialization cannot fail"),
+            priority: Some(RelayPriority::Bulk),
+        }
+    }
+}
+
+pub struct ServiceLogRelay {
+    configs: watch::Sender<BTreeMap<String, LogShippingConfig>>,
+    streams: Mutex<HashMap<Uuid, AbortHandle>>,
+}
+
+impl ServiceLogRelay {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            configs: watch::Sender::new(BTreeMap::new()),
+            streams: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Replace the per-service shipping configs, including for running streams
+    pub fn set_configs(&self, configs: BTreeMap<String, LogShippingConfig>) {
+        self.configs.send_replace(configs);
+    }
+
+    /// Follow logs of services matching `fqn` (all if `None`) at or above
+    /// `level`, relaying them as `service_logs` batches.
+    pub fn start(
+        self: &Arc<Self>,
+        stream_id: Uuid,
+        fqn: Option<String>,
+        level: Option<String>,
+        writer: RelaySender,
+    ) {
+        let relay = self.clone();
+        let configs = self.configs.subscribe();
+        let mut streams = self.streams.lock().unwrap();
+        let task = tokio::spawn(async move {
+            let error = forward(stream_id, fqn, level, configs, &writer).await.err();
+            relay.streams.lock().unwrap().remove(&stream_id);
+            if let Some(ref e) = error {
+                tracing::warn!("⚠️ Service log stream {} failed: {}", stream_id, e);
+            }
+            let _ = writer
+                .send(&ServiceLogEvent::ServiceLogsEnded { stream_id, error }.into_sync_data());
+        });
+        if let Some(previous) = streams.insert(stream_id, task.abort_handle()) {
+            previous.abort();
+        }
+    }
+
+    /// Stop a stream; `false` if it is not running
+    pub fn stop(&self, stream_id: Uuid) -> bool {
+        match self.streams.lock().unwrap().remove(&stream_id) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+async fn forward(
+    stream_id: Uuid,
+    fqn: Option<String>,
+    level: Option<String>,
+    mut configs: watch::Receiver<BTreeMap<String, LogShippingConfig>>,
+    writer: &RelaySender,
+) -> Result<(), String> {
+    let client = DaemonClient::new_default().map_err(|e| e.to_string())?;
+    let mut handle = client
+        .stream_logs(fqn.as_deref(), level.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Reading a frame is not cancel-safe, so it gets its own task
+    let (line_tx, mut line_rx) = mpsc::channel::<LogLine>(256);
+    let reader = tokio::spawn(async move {
+        while let Some(line) = handle.recv().await.map_err(|e| e.to_string())? {
+            if line_tx.send(line).await.is_err() {
+                break;
+            }
+        }
+        Ok::<(), String>(())
+    });
+    let _reader_guard = AbortOnDrop(reader.abort_handle());
+
+    let mut shipper = LogShipper::new(configs.borrow_and_update().clone());
+    let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+
+    loop {
+        let (lines, ended) = tokio::select! {
+            line = line_rx.recv() => match line {
+                Some(line) => (shipper.ship(line), false),
+                None => (shipper.flush(), true),
+            },
+            _ = flush.tick() => (shipper.flush(), false),
+            Ok(()) = configs.changed() => {
+                let pending = shipper.flush();
+                shipper = LogShipper::new(configs.borrow_and_update().clone());
+                (pending, false)
+            }
+        };
+
+        if !lines.is_empty() {
+            writer
+                .send(&ServiceLogEvent::ServiceLogs { stream_id, lines }.into_sync_data())
+                .map_err(|e| e.to_string())?;
+        }
+        if ended {
+            return reader.await.map_err(|e| e.to_string())?;
+        }
+    }
+}
+
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
@@ -0,0 +1,158 @@
+//! Delegated access to a device for someone else (`adi cocoon share`).
+//!
+//! Signaling issues a token limited to the requested scopes and lifetime;
+//! only owners can share a device, and the issuer or owner can revoke the
+//! token early. See [`crate::delegation`] for how the cocoon enforces the
+//! scopes.
+
+use crate::ownership_history::resolve_device_id;
+use crate::remote_exec::{authenticate, send};
+use futures::StreamExt;
+use lib_signaling_protocol::SignalingMessage;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+pub use lib_signaling_protocol::DelegatedGrant;
+
+/// How long signaling may take to issue the token.
+const SHARE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct ShareRequest {
+    pub signaling_url: String,
+    pub access_token: String,
+    /// Device id, or a unique prefix of one of the caller's devices
+    pub device: String,
+    /// `<service>` or `<service>:ro`
+    pub scopes: Vec<String>,
+    pub ttl: Duration,
+}
+
+/// Issue a delegated token for `request.device`; returns the token and its grant.
+pub async fn run_share(request: ShareRequest) -> Result<(String, DelegatedGrant), String> {
+    let (ws, _) = tokio_tungstenite::connect_async(&request.signaling_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", request.signaling_url, e))?;
+    let (mut sink, mut stream) = ws.split();
+
+    let devices = authenticate(&mut sink, &mut stream, &request.access_token).await?;
+    let device_id = resolve_device_id(&devices, &request.device)?;
+    send(
+        &mut sink,
+        &SignalingMessage::DeviceIssueDelegatedToken {
+            device_id: device_id.clone(),
+            scopes: request.scopes,
+            ttl_secs: request.ttl.as_secs(),
+        },
+    )
+    .await?;
+
+    wait_for(&mut stream, |msg| match msg {
+        SignalingMessage::DeviceIssueDelegatedTokenResponse { token, grant }
+            if grant.device_id == device_id =>
+        {
+            Some((token, grant))
+        }
+        _ => None,
+    })
+    .await
+}
+
+/// Revoke a token issued by [`run_share`]. `Ok(false)` when signaling does not
+/// know it, or it belongs to a device the caller neither owns nor shared.
+pub async fn run_revoke(
+    signaling_url: &str,
+    access_token: &str,
+    token_id: &str,
+) -> Result<bool, String> {
+    let (ws, _) = tokio_tungstenite::connect_async(signaling_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", signaling_url, e))?;
+    let (mut sink, mut stream) = ws.split();
+
+    authenticate(&mut sink, &mut stream, access_token).await?;
+    send(
+        &mut sink,
+        &SignalingMessage::DeviceRevokeDelegatedToken {
+            token_id: token_id.to_string(),
+        },
+    )
+    .await?;
+
+    wait_for(&mut stream, |msg| match msg {
+        SignalingMessage::DeviceRevokeDelegatedTokenResponse {
+            token_id: id,
+            revoked,
+        } if id == token_id => Some(revoked),
+        _ => None,
+    })
+    .await
+}
+
+/// First answer `pick` accepts; a `system_error` fails the request.
+async fn wait_for<R, T>(
+    stream: &mut R,
+    pick: impl Fn(SignalingMessage) -> Option<T>,
+) -> Result<T, String>
+where
+    R: futures::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        let next = tokio::time::timeout(SHARE_TIMEOUT, stream.next())
+            .await
+            .map_err(|_| "Timed out waiting for the signaling server".to_string())?;
+        let text = match next {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(format!("Signaling connection failed: {}", e)),
+            None => return Err("Signaling server closed the connection".to_string()),
+        };
+
+        match serde_json::from_str::<SignalingMessage>(&text) {
+            Ok(SignalingMessage::SystemError { message }) => return Err(message),
+            Ok(msg) => {
+                if let Some(answer) = pick(msg) {
+                    return Ok(answer);
+                }
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Parse a lifetime like `90s`, `30m`, `2h` or `7d`; a bare number is seconds.
+pub fn parse_ttl(ttl: &str) -> Result<Duration, String> {
+    let ttl = ttl.trim();
+    let split = ttl.find(|c: char| !c.is_ascii_digit()).unwrap_or(ttl.len());
+    let (number, unit) = ttl.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration '{}'", ttl))?;
+    let unit_secs = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("Invalid duration '{}' (use s, m, h or d)", ttl)),
+    };
+    match number.checked_mul(unit_secs) {
+        Some(0) | None => Err(format!("Invalid duration '{}'", ttl)),
+        Some(secs) => Ok(Duration::from_secs(secs)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_ttl("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_ttl("1d").unwrap(), Duration::from_secs(86400));
+        assert_eq!(parse_ttl("45").unwrap(), Duration::from_secs(45));
+        assert!(parse_ttl("0h").is_err());
+        assert!(parse_ttl("2w").is_err());
+        assert!(parse_ttl("h").is_err());
+    }
+}
@@ -1,44 +1,25 @@
 use crate::protocol::types::SilkHtmlSpan;
+use crate::recording::{self, Recorder};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 use lib_env_parse::{env_vars, env_opt};
+use lib_silk_detect::detect_command;
+use lib_tarminal_sync::FileAssembler;
 
 env_vars! {
     Shell => "SHELL",
     Home => "HOME",
 }
 
-/// Known interactive commands that always need a PTY
-const INTERACTIVE_COMMANDS: &[&str] = &[
-    "vim",
-    "nvim",
-    "vi",
-    "nano",
-    "emacs",
-    "less",
-    "more",
-    "top",
-    "htop",
-    "btop",
-    "man",
-    "ssh",
-    "fzf",
-    "lazygit",
-    "tig",
-    "claude",
-    "python",
-    "python3",
-    "node",
-    "irb",
-    "rails c",
-    "psql",
-    "mysql",
-    "sqlite3",
-    "mongosh",
-    "redis-cli",
-];
+/// Uploads a session may have in progress at once
+const MAX_UPLOADS: usize = 4;
+/// An upload with no chunk for this long is dropped
+const UPLOAD_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
 
 pub struct SilkSession {
     pub id: Uuid,
@@ -47,6 +28,15 @@ pub struct SilkSession {
     pub env: HashMap<String, String>,
     /// Running commands that may need input
     pub running_commands: HashMap<String, RunningCommand>,
+    /// Set when COCOON_RECORD_SILK is on; output tasks hold clones
+    pub recorder: Option<Arc<Recorder>>,
+    /// Uploads in progress, by path as sent by the client
+    pub uploads: HashMap<String, PendingUpload>,
+}
+
+pub struct PendingUpload {
+    assembler: FileAssembler,
+    last_chunk: Instant,
 }
 
 pub struct RunningCommand {
@@ -75,36 +65,42 @@ impl SilkSession {
             .or_else(|| env_opt(EnvVar::Home.as_str()))
             .unwrap_or_else(|| "/".to_string());
 
-        if !std::path::Path::new(&shell).exists() {
+        if !Path::new(&shell).exists() {
             return Err(format!("Shell not found: {}", shell));
         }
 
         let mut env = env;
         env.insert("SILK_MODE".to_string(), "true".to_string());
 
+        let id = Uuid::new_v4();
+        let recorder = recording::start_recording(&id.to_string(), &shell);
+
         Ok(Self {
-            id: Uuid::new_v4(),
+            id,
             shell,
             cwd,
             env,
             running_commands: HashMap::new(),
+            recorder,
+            uploads: HashMap::new(),
         })
     }
 
+    /// Whether the command needs a PTY, judged from the command line alone
     pub fn is_interactive_command(command: &str) -> bool {
-        let cmd_name = command.split_whitespace().next().unwrap_or("");
-
-        for interactive in INTERACTIVE_COMMANDS {
-            if cmd_name == *interactive || cmd_name.ends_with(&format!("/{}", interactive)) {
-                return true;
-            }
-        }
-
-        if command.contains(" -i") || command.contains(" --interactive") {
-            return true;
-        }
+        detect_command(command).is_some_and(|d| d.is_confident())
+    }
 
-        false
+    /// Why `command` runs in a PTY, for `silk_interactive_required`
+    pub fn interactive_reason(command: &str) -> String {
+        detect_command(command)
+            .map(|d| d.reason)
+            .unwrap_or_else(|| {
+                format!(
+                    "Command '{}' requires interactive mode",
+                    command.split_whitespace().next().unwrap_or(command)
+                )
+            })
     }
 
     pub fn execute(
@@ -114,6 +110,10 @@ impl SilkSession {
     ) -> Result<(bool, Option<Child>), String> {
         let interactive = Self::is_interactive_command(command);
 
+        if let Some(recorder) = &self.recorder {
+            recorder.output(&format!("{} $ {}\r\n", self.cwd, command));
+        }
+
         if interactive {
             // Mark as needing PTY, actual PTY creation happens in core.rs
             self.running_commands.insert(
@@ -191,9 +191,68 @@ impl SilkSession {
         }
     }
 
+    /// File transfer path: relative paths are taken from the session cwd.
+    /// Only `~` and `~/...` are expanded; `~user` is left as is.
+    pub fn resolve_path(&self, path: &str) -> PathBuf {
+        let home = || env_opt(EnvVar::Home.as_str()).map(PathBuf::from);
+        let path = match path.strip_prefix('~') {
+            Some("") => home(),
+            Some(rest) => rest
+                .strip_prefix('/')
+                .and_then(|rest| home().map(|home| home.join(rest))),
+            None => None,
+        }
+        .unwrap_or_else(|| PathBuf::from(path));
+        Path::new(&self.cwd).join(path)
+    }
+
+    /// Add an upload chunk; returns the destination and contents once every
+    /// chunk arrived and the file hash matches. A failed upload is dropped,
+    /// as are uploads that went quiet for `UPLOAD_IDLE_TIMEOUT`. At most
+    /// `MAX_UPLOADS` can be in progress.
+    pub fn push_upload(
+        &mut self,
+        path: &str,
+        seq: u64,
+        chunk: &str,
+        done: bool,
+        sha256: Option<&str>,
+    ) -> Result<Option<(PathBuf, Vec<u8>)>, String> {
+        let now = Instant::now();
+        self.uploads
+            .retain(|_, upload| now.duration_since(upload.last_chunk) < UPLOAD_IDLE_TIMEOUT);
+        if !self.uploads.contains_key(path) && self.uploads.len() >= MAX_UPLOADS {
+            return Err(format!("{} uploads already in progress", MAX_UPLOADS));
+        }
+
+        let upload = self
+            .uploads
+            .entry(path.to_string())
+            .or_insert_with(|| PendingUpload {
+                assembler: FileAssembler::default(),
+                last_chunk: now,
+            });
+        upload.last_chunk = now;
+        match upload.assembler.push(seq, chunk, done, sha256) {
+            Ok(None) => Ok(None),
+            Ok(Some(data)) => {
+                self.uploads.remove(path);
+                Ok(Some((self.resolve_path(path), data)))
+            }
+            Err(e) => {
+                self.uploads.remove(path);
+                Err(e.to_string())
+            }
+        }
+    }
+
+    /// Attach the PTY running `command_id`; a piped command restarted in a
+    /// PTY drops its stdin pipe
     pub fn set_pty_session(&mut self, command_id: String, pty_session_id: Uuid) {
         if let Some(cmd) = self.running_commands.get_mut(&command_id) {
+            cmd.interactive = true;
             cmd.pty_session_id = Some(pty_session_id);
+            cmd.stdin = None;
         }
     }
 
@@ -202,6 +261,29 @@ impl SilkSession {
     }
 }
 
+/// Write a completed Silk upload, creating missing parent directories
+pub async fn write_upload(target: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(target, data).await
+}
+
+/// Read a file for a Silk download, refusing directories and oversized files
+pub async fn read_download(source: &Path) -> Result<Vec<u8>, String> {
+    let meta = tokio::fs::metadata(source).await.map_err(|e| e.to_string())?;
+    if !meta.is_file() {
+        return Err("not a regular file".to_string());
+    }
+    if meta.len() > lib_tarminal_sync::MAX_FILE_BYTES {
+        return Err(format!(
+            "file is larger than {} bytes",
+            lib_tarminal_sync::MAX_FILE_BYTES
+        ));
+    }
+    tokio::fs::read(source).await.map_err(|e| e.to_string())
+}
+
 pub struct AnsiToHtml;
 
 impl AnsiToHtml {
@@ -380,6 +462,58 @@ mod tests {
         assert!(!SilkSession::is_interactive_command("echo hello"));
     }
 
+    fn session() -> SilkSession {
+        SilkSession::new(
+            Some("/work".to_string()),
+            HashMap::new(),
+            Some("/bin/sh".to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let session = session();
+        assert_eq!(session.resolve_path("a.txt"), PathBuf::from("/work/a.txt"));
+        assert_eq!(
+            session.resolve_path("/tmp/a.txt"),
+            PathBuf::from("/tmp/a.txt")
+        );
+        assert_eq!(
+            session.resolve_path("~user/a.txt"),
+            PathBuf::from("/work/~user/a.txt")
+        );
+        if let Some(home) = env_opt(EnvVar::Home.as_str()) {
+            assert_eq!(session.resolve_path("~"), PathBuf::from(&home));
+            assert_eq!(
+                session.resolve_path("~/a.txt"),
+                Path::new(&home).join("a.txt")
+            );
+        }
+    }
+
+    #[test]
+    fn test_upload_limit() {
+        let mut session = session();
+        for i in 0..MAX_UPLOADS {
+            let path = format!("file{}", i);
+            assert!(session
+                .push_upload(&path, 0, "aGk=", false, None)
+                .unwrap()
+                .is_none());
+        }
+        assert!(session.push_upload("file0", 1, "aGk=", false, None).is_ok());
+        assert!(session
+            .push_upload("extra", 0, "aGk=", false, None)
+            .is_err());
+
+        for upload in session.uploads.values_mut() {
+            upload.last_chunk -= UPLOAD_IDLE_TIMEOUT;
+        }
+        assert!(session.push_upload("extra", 0, "aGk=", false, None).is_ok());
+        assert_eq!(session.uploads.len(), 1);
+    }
+
     #[test]
     fn test_ansi_to_html_plain_text() {
         let spans = AnsiToHtml::convert("hello world");
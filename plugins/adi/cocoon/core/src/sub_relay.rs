@@ -0,0 +1,232 @@
+//! Sub-relay mode for cocoons on a jump host
+//!
+//! With `COCOON_RELAY_LISTEN=<addr:port>` the cocoon accepts signaling
+//! connections from devices that cannot reach the internet themselves. Each
+//! downstream connection becomes a relay link: the cocoon announces it
+//! upstream with `relay_open` and then passes every frame through as
+//! `relay_frame`, keeping a routing table from link id to downstream socket.
+//! The signaling server runs each link like a direct connection, enforces the
+//! hop limit and refuses registration loops.
+//!
+//! Downstream cocoons point `SIGNALING_SERVER_URL` at `ws://<jump-host>:<port>/ws`.
+//! Links do not survive a lost upstream connection; downstream devices
+//! reconnect and register again once the jump host is back.
+
+use crate::relay_queue::{priority_of, RelaySender};
+use futures::{SinkExt, StreamExt};
+use lib_signaling_protocol::{RelayPriority, SignalingMessage};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Mutex};
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+use lib_env_parse::{env_opt, env_vars};
+
+env_vars! {
+    CocoonRelayListen => "COCOON_RELAY_LISTEN",
+}
+
+/// Address to accept downstream devices on ($COCOON_RELAY_LISTEN)
+pub fn relay_listen_addr() -> Option<String> {
+    env_opt(EnvVar::CocoonRelayListen.as_str())
+}
+
+/// Routing table of a sub-relay: link id → downstream socket
+pub struct SubRelay {
+    upstream: RelaySender,
+    links: Mutex<HashMap<String, mpsc::UnboundedSender<Message>>>,
+}
+
+impl SubRelay {
+    pub fn new(upstream: RelaySender) -> Arc<Self> {
+        Arc::new(Self {
+            upstream,
+            links: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Accept downstream devices until the listener fails
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    let relay = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = relay.handle_downstream(stream).await {
+                            tracing::warn!("⚠️ Downstream device {} dropped: {}", peer, e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("⚠️ Sub-relay accept failed: {}", e),
+            }
+        }
+    }
+
+    async fn handle_downstream(&self, stream: TcpStream) -> Result<(), String> {
+        let ws = tokio_tungstenite::accept_async(stream)
+            .await
+            .map_err(|e| format!("handshake failed: {}", e))?;
+        let (mut sink, mut read) = ws.split();
+
+        let link_id = Uuid::new_v4().to_string();
+        self.upstream
+            .send(&SignalingMessage::RelayOpen {
+                link_id: link_id.clone(),
+            })
+            .map_err(|e| e.to_string())?;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+        self.links.lock().await.insert(link_id.clone(), tx);
+        tracing::info!("🔀 Relay link {} opened for downstream device", link_id);
+
+        let writer = tokio::spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                if sink.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            let _ = sink.close().await;
+        });
+
+        while let Some(Ok(msg)) = read.next().await {
+            let text = match msg {
+                Message::Text(t) => t,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let Ok(frame) = serde_json::from_str::<serde_json::Value>(&text) else {
+                continue;
+            };
+            if self.forward_upstream(&link_id, frame).is_err() {
+                break;
+            }
+        }
+
+        // Only tell the server if the link was still ours to close
+        if self.links.lock().await.remove(&link_id).is_some() {
+            let _ = self.upstream.send(&SignalingMessage::RelayClose {
+                link_id: link_id.clone(),
+                reason: None,
+            });
+        }
+        writer.abort();
+        tracing::info!("🔀 Relay link {} closed", link_id);
+        Ok(())
+    }
+
+    /// Wrap a downstream frame for the server, keeping the frame's own priority
+    fn forward_upstream(&self, link_id: &str, frame: serde_json::Value) -> Result<(), String> {
+        let priority = serde_json::from_value::<SignalingMessage>(frame.clone())
+            .map(|m| priority_of(&m))
+            .unwrap_or(RelayPriority::Normal);
+        let wrapped = SignalingMessage::RelayFrame {
+            link_id: link_id.to_string(),
+            frame,
+        };
+        let text =
+            serde_json::to_string(&wrapped).expect("SignalingMessage serialization cannot fail");
+        self.upstream
+            .send_raw(&priority, Message::Text(text))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Route a `relay_frame` or `relay_close` from the server
+    pub async fn handle_upstream(&self, msg: SignalingMessage) {
+        match msg {
+            SignalingMessage::RelayFrame { link_id, frame } => {
+                let links = self.links.lock().await;
+                match links.get(&link_id) {
+                    Some(tx) => {
+                        let _ = tx.send(Message::Text(frame.to_string()));
+                    }
+                    None => tracing::debug!("Frame for unknown relay link {}", link_id),
+                }
+            }
+            SignalingMessage::RelayClose { link_id, reason } => {
+                if let Some(tx) = self.links.lock().await.remove(&link_id) {
+                    if let Some(ref reason) = reason {
+                        tracing::warn!("⚠️ Relay link {} refused: {}", link_id, reason);
+                    }
+                    // The writer forwards the close frame to the downstream device
+                    let _ = tx.send(Message::Close(None));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Drop every link; called when the upstream connection is lost
+    pub async fn close_all(&self) {
+        let links = std::mem::take(&mut *self.links.lock().await);
+        if !links.is_empty() {
+            tracing::info!("🔀 Upstream lost, closing {} relay link(s)", links.len());
+        }
+        for tx in links.into_values() {
+            let _ = tx.send(Message::Close(None));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_tungstenite::connect_async;
+
+    #[tokio::test]
+    async fn test_frames_pass_through_link() {
+        let (upstream_tx, mut upstream_rx) = futures::channel::mpsc::unbounded::<Message>();
+        let relay = SubRelay::new(RelaySender::spawn(upstream_tx));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(relay.clone().serve(listener));
+
+        let (ws, _) = connect_async(format!("ws://{}/ws", addr)).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
+
+        let recv_upstream = |text: Message| -> SignalingMessage {
+            serde_json::from_str(text.to_text().unwrap()).unwrap()
+        };
+
+        let link_id = match recv_upstream(upstream_rx.next().await.unwrap()) {
+            SignalingMessage::RelayOpen { link_id } => link_id,
+            other => panic!("Expected RelayOpen, got: {:?}", other),
+        };
+
+        // Downstream → upstream, wrapped
+        sink.send(Message::Text(r#"{"type":"pairing_create_code"}"#.into()))
+            .await
+            .unwrap();
+        match recv_upstream(upstream_rx.next().await.unwrap()) {
+            SignalingMessage::RelayFrame { link_id: id, frame } => {
+                assert_eq!(id, link_id);
+                assert_eq!(frame["type"], "pairing_create_code");
+            }
+            other => panic!("Expected RelayFrame, got: {:?}", other),
+        }
+
+        // Upstream → downstream, unwrapped
+        relay
+            .handle_upstream(SignalingMessage::RelayFrame {
+                link_id: link_id.clone(),
+                frame: serde_json::json!({"type": "pairing_create_code_response", "code": "ABC"}),
+            })
+            .await;
+        let text = stream.next().await.unwrap().unwrap();
+        assert!(text.to_text().unwrap().contains("ABC"));
+
+        // A refusal from the server closes the downstream socket
+        relay
+            .handle_upstream(SignalingMessage::RelayClose {
+                link_id,
+                reason: Some("Hop limit of 4 exceeded".to_string()),
+            })
+            .await;
+        assert!(matches!(
+            stream.next().await,
+            Some(Ok(Message::Close(_))) | None
+        ));
+    }
+}
@@ -16,17 +16,26 @@
 //!
 //! If no ICE servers are configured, defaults to Google's public STUN server.
 
-use crate::adi_frame;
-use crate::adi_router::{AdiCallerContext, AdiDiscovery, AdiRouter, AdiRouterBinaryResult};
+use crate::adi_router::{
+    AdiCallerContext, AdiDiscovery, AdiRouter, AdiRouterBinaryResult, AdiStreamControl,
+    AdiSubscription, FORBIDDEN,
+};
+use bytes::Bytes;
+use crate::channel_policy::{ChannelPolicies, ChannelPolicy};
+use crate::delegation::DelegatedAccess;
 use crate::filesystem::{FileSystemRequest, handle_request as handle_fs_request};
+use crate::port_forward;
 use crate::protocol::messages::CocoonMessage;
 use crate::protocol::types::SilkStream;
-use crate::silk::{AnsiToHtml, SilkSession};
-use lib_signaling_protocol::SignalingMessage;
+use crate::silk::{read_download, write_upload, AnsiToHtml, SilkSession};
+use lib_signaling_protocol::{RelayPriority, SignalingMessage};
+use lib_silk_detect::OutputDetector;
 use portable_pty::PtySize;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::io::Read;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, mpsc};
 use uuid::Uuid;
 use webrtc::api::interceptor_registry::register_default_interceptors;
@@ -51,7 +60,7 @@ env_vars! {
     WebrtcTurnCredential => "WEBRTC_TURN_CREDENTIAL",
 }
 
-fn build_ice_servers() -> Vec<RTCIceServer> {
+pub(crate) fn build_ice_servers() -> Vec<RTCIceServer> {
     let ice_servers_env = env_opt(EnvVar::WebrtcIceServers.as_str());
     let turn_username = env_opt(EnvVar::WebrtcTurnUsername.as_str());
     let turn_credential = env_opt(EnvVar::WebrtcTurnCredential.as_str());
@@ -136,6 +145,35 @@ pub struct WebRtcSession {
     pub data_channels: HashMap<String, Arc<RTCDataChannel>>,
     pub state: String,
     pub user_id: Option<String>,
+    /// Scopes of a session opened with a delegated token; `None` for owners
+    pub access: Option<DelegatedAccess>,
+    /// Subscriptions served over the signaling relay, which has no channel
+    /// close to end them; stopped in `close_session`
+    relayed_subscriptions: Vec<(Uuid, tokio::task::AbortHandle)>,
+}
+
+/// Warmed sessions kept per client; prewarming past this closes the oldest
+pub const WARM_POOL_SIZE: usize = 2;
+
+/// Unclaimed warm sessions are closed after this long
+pub const WARM_SESSION_TTL: Duration = Duration::from_secs(300);
+
+/// A session connected ahead of user action, waiting to be claimed
+struct WarmSession {
+    session_id: String,
+    created_at: Instant,
+}
+
+/// Silk and ADI messages are handled in the order they arrive and never
+/// resent, so those channels must be ordered and fully reliable
+fn default_channel_policies() -> ChannelPolicies {
+    let mut policies = ChannelPolicies::default();
+    for label in ["silk", "adi"] {
+        policies
+            .set(label, ChannelPolicy::reliable())
+            .expect("reliable policy is valid");
+    }
+    policies
 }
 
 pub struct WebRtcManager {
@@ -143,6 +181,9 @@ pub struct WebRtcManager {
     signaling_tx: mpsc::UnboundedSender<SignalingMessage>,
     close_timeout: std::time::Duration,
     adi_router: Option<Arc<Mutex<AdiRouter>>>,
+    /// Unclaimed warm sessions per client, oldest first
+    warm: Mutex<HashMap<String, VecDeque<WarmSession>>>,
+    channel_policies: Arc<ChannelPolicies>,
 }
 
 impl WebRtcManager {
@@ -152,6 +193,8 @@ impl WebRtcManager {
             signaling_tx,
             close_timeout: std::time::Duration::from_secs(5),
             adi_router: None,
+            warm: Mutex::new(HashMap::new()),
+            channel_policies: Arc::new(default_channel_policies()),
         }
     }
 
@@ -164,9 +207,18 @@ impl WebRtcManager {
             signaling_tx,
             close_timeout: std::time::Duration::from_secs(5),
             adi_router: Some(adi_router),
+            warm: Mutex::new(HashMap::new()),
+            channel_policies: Arc::new(default_channel_policies()),
         }
     }
 
+    /// Delivery settings per channel label; channels the browser opens with
+    /// other settings are closed.
+    pub fn with_channel_policies(mut self, policies: ChannelPolicies) -> Self {
+        self.channel_policies = Arc::new(policies);
+        self
+    }
+
     #[cfg(test)]
     pub fn with_close_timeout(
         signaling_tx: mpsc::UnboundedSender<SignalingMessage>,
@@ -177,10 +229,22 @@ impl WebRtcManager {
             signaling_tx,
             close_timeout,
             adi_router: None,
+            warm: Mutex::new(HashMap::new()),
+            channel_policies: Arc::new(default_channel_policies()),
         }
     }
 
     pub async fn create_session(&self, session_id: String, user_id: Option<String>) -> Result<(), String> {
+        self.create_session_with_access(session_id, user_id, None).await
+    }
+
+    /// Create a session limited to the scopes of a delegated token
+    pub async fn create_session_with_access(
+        &self,
+        session_id: String,
+        user_id: Option<String>,
+        access: Option<DelegatedAccess>,
+    ) -> Result<(), String> {
         tracing::info!("🔧 [create_session] START session_id={}", session_id);
         tracing::info!("🔧 [create_session] current session count: {}", self.sessions.lock().await.len());
 
@@ -259,6 +323,7 @@ impl WebRtcManager {
                                 sdp_mid,
                                 sdp_mline_index: json.sdp_mline_index.map(|i| i as i32),
                             }).unwrap(),
+                            priority: Some(RelayPriority::Interactive),
                         });
                     }
                 } else {
@@ -343,6 +408,7 @@ impl WebRtcManager {
                                 session_id: session_id.clone(),
                                 reason: Some(reason.to_string()),
                             }).unwrap(),
+                            priority: Some(RelayPriority::Interactive),
                         });
 
                         sessions.lock().await.remove(&session_id);
@@ -362,7 +428,9 @@ impl WebRtcManager {
         let sessions_clone = self.sessions.clone();
         let adi_router_clone = self.adi_router.clone();
         let user_id_clone = user_id.clone();
+        let access_clone = access.clone();
         let silk_state_clone = silk_state.clone();
+        let policies_clone = self.channel_policies.clone();
         peer_connection.on_data_channel(Box::new(move |dc| {
             let session_id = session_id_clone.clone();
             let tx = signaling_tx_clone.clone();
@@ -370,7 +438,9 @@ impl WebRtcManager {
             let dc_label = dc.label().to_string();
             let adi_router = adi_router_clone.clone();
             let user_id = user_id_clone.clone();
+            let access = access_clone.clone();
             let silk_state = silk_state_clone.clone();
+            let policies = policies_clone.clone();
 
             Box::pin(async move {
                 tracing::warn!(
@@ -381,6 +451,32 @@ impl WebRtcManager {
                     dc.ready_state(),
                 );
 
+                // An unordered silk channel would garble the terminal stream,
+                // so refuse it rather than adapt
+                let mismatch = policies.for_remote(&dc_label).and_then(|policy| {
+                    policy.mismatch(dc.ordered(), dc.max_retransmits(), dc.max_packet_lifetime())
+                });
+                if let Some(reason) = mismatch {
+                    tracing::warn!(
+                        "🚫 Data channel {} refused for session {}: {}",
+                        dc_label, session_id, reason
+                    );
+                    let _ = dc.close().await;
+                    return;
+                }
+
+                // One channel per forwarded TCP connection, short-lived and
+                // not tracked with the session's named channels
+                if dc_label.starts_with(port_forward::LABEL_PREFIX) {
+                    if let Some(Err(e)) = access.as_ref().map(|a| a.check_forward()) {
+                        tracing::warn!("🚫 Port forward refused for session {}: {}", session_id, e);
+                        let _ = dc.close().await;
+                        return;
+                    }
+                    port_forward::accept_data_channel(dc);
+                    return;
+                }
+
                 if let Some(session) = sessions.lock().await.get_mut(&session_id) {
                     session.data_channels.insert(dc_label.clone(), dc.clone());
                 }
@@ -391,6 +487,7 @@ impl WebRtcManager {
                 let dc_clone = dc.clone();
                 let adi_router_for_msg = adi_router.clone();
                 let user_id_for_msg = user_id.clone();
+                let access_for_msg = access.clone();
                 let silk_state_for_msg = silk_state.clone();
                 dc.on_message(Box::new(move |msg: DataChannelMessage| {
                     let session_id = session_id_clone.clone();
@@ -399,6 +496,7 @@ impl WebRtcManager {
                     let dc_for_response = dc_clone.clone();
                     let adi_router = adi_router_for_msg.clone();
                     let user_id = user_id_for_msg.clone();
+                    let access = access_for_msg.clone();
                     let silk_state = silk_state_for_msg.clone();
 
                     Box::pin(async move {
@@ -415,44 +513,19 @@ impl WebRtcManager {
                                     user_id: user_id.clone(),
                                     device_id: None,
                                 };
-
-                                let router_guard = router.lock().await;
-                                let result = router_guard.handle_binary(&ctx, &msg.data).await;
-                                drop(router_guard);
-
-                                match result {
-                                    AdiRouterBinaryResult::Single(response_bytes) => {
-                                        let len = response_bytes.len();
-                                        if let Err(e) = dc_for_response.send(&response_bytes.into()).await {
-                                            tracing::error!("❌ Failed to send ADI binary response: {}", e);
-                                        } else {
-                                            tracing::debug!("📤 ADI binary response sent: {} bytes", len);
-                                        }
-                                    }
-                                    AdiRouterBinaryResult::Stream { request_id, mut receiver } => {
-                                        let dc_for_stream = dc_for_response.clone();
-                                        tokio::spawn(async move {
-                                            let mut seq = 0u32;
-                                            while let Some((chunk_data, is_final)) = receiver.recv().await {
-                                                let frame = if is_final {
-                                                    adi_frame::stream_end(request_id, seq, &chunk_data)
-                                                } else {
-                                                    adi_frame::stream_chunk(request_id, seq, &chunk_data)
-                                                };
-                                                seq += 1;
-
-                                                if let Err(e) = dc_for_stream.send(&frame.into()).await {
-                                                    tracing::error!("❌ Failed to send ADI stream chunk: {}", e);
-                                                    break;
-                                                }
-
-                                                if is_final {
-                                                    break;
-                                                }
+                                serve_adi_frame(router, ctx, access.as_ref(), &msg.data, move |frame: Bytes| {
+                                    let dc = dc_for_response.clone();
+                                    async move {
+                                        match dc.send(&frame).await {
+                                            Ok(_) => true,
+                                            Err(e) => {
+                                                tracing::error!("❌ Failed to send ADI binary response: {}", e);
+                                                false
                                             }
-                                        });
+                                        }
                                     }
-                                }
+                                })
+                                .await;
                             } else {
                                 tracing::warn!("⚠️ ADI binary request received but no router configured");
                             }
@@ -466,9 +539,19 @@ impl WebRtcManager {
                         };
 
                         if channel == "silk" {
-                            tracing::info!("🧵 [DC-MSG] Silk message received: {} bytes, preview={}", data.len(), &data[..data.len().min(200)]);
+                            tracing::info!("🧵 [DC-MSG] Silk message received: {} bytes, preview={}", data.len(), preview(&data));
                             match serde_json::from_str::<CocoonMessage>(&data) {
                                 Ok(cocoon_msg) => {
+                                    if let Some(Err(message)) = access.as_ref().map(|a| a.check_silk(&cocoon_msg)) {
+                                        let (session_id, command_id) = silk_ids(&cocoon_msg);
+                                        dc_send(&dc_for_response, &CocoonMessage::SilkError {
+                                            session_id,
+                                            command_id,
+                                            code: FORBIDDEN.to_string(),
+                                            message,
+                                        }).await;
+                                        return;
+                                    }
                                     let dc = dc_for_response.clone();
                                     tokio::spawn(async move {
                                         handle_silk_dc_msg(cocoon_msg, silk_state, dc).await;
@@ -485,6 +568,20 @@ impl WebRtcManager {
                             tracing::debug!("📁 File system request received: {} bytes", data.len());
                             match serde_json::from_str::<FileSystemRequest>(&data) {
                                 Ok(request) => {
+                                    if let Some(Err(message)) = access.as_ref().map(|a| a.check_files()) {
+                                        let (FileSystemRequest::FsListDir { request_id, .. }
+                                        | FileSystemRequest::FsReadFile { request_id, .. }
+                                        | FileSystemRequest::FsStat { request_id, .. }
+                                        | FileSystemRequest::FsWalk { request_id, .. }) = request;
+                                        let error_response = serde_json::json!({
+                                            "type": "fs_error",
+                                            "request_id": request_id,
+                                            "code": FORBIDDEN,
+                                            "message": message,
+                                        });
+                                        let _ = dc_for_response.send(&error_response.to_string().into_bytes().into()).await;
+                                        return;
+                                    }
                                     let response = handle_fs_request(request).await;
                                     match serde_json::to_string(&response) {
                                         Ok(response_json) => {
@@ -531,9 +628,35 @@ impl WebRtcManager {
                                     return;
                                 }
 
+                                if let Ok(subscription) = serde_json::from_str::<AdiSubscription>(&data) {
+                                    serve_adi_subscription(router, access.as_ref(), subscription, move |json: String| {
+                                        let dc = dc_for_response.clone();
+                                        async move { dc.send(&json.into_bytes().into()).await.is_ok() }
+                                    })
+                                    .await;
+                                    return;
+                                }
+
+                                if let Ok(AdiStreamControl::CancelStream { request_id }) = serde_json::from_str(&data) {
+                                    router.lock().await.cancel_stream(request_id);
+                                    return;
+                                }
+
                                 // Try plugin install request
                                 if let Ok(msg) = serde_json::from_str::<CocoonMessage>(&data) {
                                     if let CocoonMessage::PluginInstallPlugin { request_id, plugin_id, registry, version } = msg {
+                                        if access.is_some() {
+                                            let err = CocoonMessage::PluginInstallError {
+                                                request_id,
+                                                plugin_id,
+                                                code: FORBIDDEN.to_string(),
+                                                message: "Only owners can install plugins".to_string(),
+                                            };
+                                            if let Ok(json) = serde_json::to_string(&err) {
+                                                let _ = dc_for_response.send(&json.into_bytes().into()).await;
+                                            }
+                                            return;
+                                        }
                                         let dc = dc_for_response.clone();
                                         tokio::spawn(async move {
                                             tracing::info!("📦 Installing plugin: {} (registry={:?}, version={:?})", plugin_id, registry, version);
@@ -603,7 +726,7 @@ impl WebRtcManager {
                                 }
 
                                 tracing::warn!("⚠️ Unrecognized text message on adi channel: {}",
-                                    &data[..data.len().min(200)]);
+                                    preview(&data));
                             } else {
                                 tracing::warn!("⚠️ ADI request received but no router configured");
                                 let error_response = serde_json::json!({
@@ -628,6 +751,7 @@ impl WebRtcManager {
                                 data,
                                 binary,
                             }).unwrap(),
+                            priority: None,
                         });
                     })
                 }));
@@ -642,6 +766,8 @@ impl WebRtcManager {
             data_channels: HashMap::new(),
             state: "pending".to_string(),
             user_id,
+            access,
+            relayed_subscriptions: Vec::new(),
         };
 
         self.sessions.lock().await.insert(session_id.clone(), session);
@@ -787,12 +913,117 @@ impl WebRtcManager {
         Ok(())
     }
 
+    /// Serve ADI traffic relayed through the signaling server by a client
+    /// that could not open the session's "adi" data channel. Binary frames
+    /// arrive base64-encoded; responses go back as `webrtc_data` on the same
+    /// channel name.
+    pub async fn handle_relayed_adi(&self, session_id: &str, data: &str, binary: bool) {
+        let Some(router) = self.adi_router.clone() else {
+            tracing::warn!("⚠️ Relayed ADI request received but no router configured");
+            return;
+        };
+        let Some((user_id, access)) = self
+            .sessions
+            .lock()
+            .await
+            .get(session_id)
+            .map(|s| (s.user_id.clone(), s.access.clone()))
+        else {
+            tracing::warn!("⚠️ Relayed ADI message for unknown session {}, dropped", session_id);
+            return;
+        };
+
+        let tx = self.signaling_tx.clone();
+        let session = session_id.to_string();
+        let reply = move |data: String, binary: bool| {
+            let msg = CocoonMessage::WebrtcData {
+                session_id: session.clone(),
+                channel: "adi".to_string(),
+                data,
+                binary,
+            };
+            tx.send(SignalingMessage::SyncData {
+                payload: serde_json::to_value(&msg).unwrap(),
+                priority: Some(RelayPriority::Interactive),
+            })
+            .is_ok()
+        };
+
+        if binary {
+            let raw = match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, data) {
+                Ok(raw) => raw,
+                Err(e) => {
+                    tracing::warn!("⚠️ Invalid base64 in relayed ADI frame: {}", e);
+                    return;
+                }
+            };
+            let ctx = AdiCallerContext { user_id, device_id: None };
+            serve_adi_frame(&router, ctx, access.as_ref(), &raw, move |frame: Bytes| {
+                let sent = reply(
+                    base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &frame),
+                    true,
+                );
+                async move { sent }
+            })
+            .await;
+            return;
+        }
+
+        if let Ok(discovery) = serde_json::from_str::<AdiDiscovery>(data) {
+            let response = router.lock().await.handle_discovery(discovery);
+            if let Ok(json) = serde_json::to_string(&response) {
+                reply(json, false);
+            }
+            return;
+        }
+
+        if let Ok(subscription) = serde_json::from_str::<AdiSubscription>(data) {
+            let forwarding =
+                serve_adi_subscription(&router, access.as_ref(), subscription, move |json: String| {
+                    let sent = reply(json, false);
+                    async move { sent }
+                })
+                .await;
+            if let Some((subscription_id, task)) = forwarding {
+                match self.sessions.lock().await.get_mut(session_id) {
+                    Some(session) => session
+                        .relayed_subscriptions
+                        .push((subscription_id, task.abort_handle())),
+                    // Closed while subscribing
+                    None => {
+                        task.abort();
+                        router.lock().await.remove_subscription(subscription_id).await;
+                    }
+                }
+            }
+            return;
+        }
+
+        if let Ok(AdiStreamControl::CancelStream { request_id }) = serde_json::from_str(data) {
+            router.lock().await.cancel_stream(request_id);
+            return;
+        }
+
+        tracing::warn!("⚠️ Unrecognized relayed ADI message: {}", preview(data));
+    }
+
     /// Close a session
     ///
     /// Uses a timeout for the peer connection close to prevent hanging
     /// when the connection was never fully established.
     pub async fn close_session(&self, session_id: &str) -> Result<(), String> {
-        if let Some(session) = self.sessions.lock().await.remove(session_id) {
+        let session = self.sessions.lock().await.remove(session_id);
+        if let Some(session) = session {
+            if !session.relayed_subscriptions.is_empty() {
+                if let Some(router) = &self.adi_router {
+                    let router = router.lock().await;
+                    for (subscription_id, task) in &session.relayed_subscriptions {
+                        task.abort();
+                        router.remove_subscription(*subscription_id).await;
+                    }
+                }
+            }
+
             // Use a timeout for close() as it can hang if the connection
             // was never fully established (common in tests or rapid page refreshes)
             let close_result = tokio::time::timeout(
@@ -822,6 +1053,23 @@ impl WebRtcManager {
         Ok(())
     }
 
+    /// Close every session opened with the delegated token `token_id`; returns
+    /// how many were closed
+    pub async fn close_delegated_sessions(&self, token_id: &str) -> usize {
+        let session_ids: Vec<String> = self
+            .sessions
+            .lock()
+            .await
+            .values()
+            .filter(|s| s.access.as_ref().is_some_and(|a| a.token_id == token_id))
+            .map(|s| s.session_id.clone())
+            .collect();
+        for session_id in &session_ids {
+            let _ = self.close_session(session_id).await;
+        }
+        session_ids.len()
+    }
+
     pub async fn list_sessions(&self) -> Vec<String> {
         self.sessions
             .lock()
@@ -846,12 +1094,201 @@ impl WebRtcManager {
             .get(session_id)
             .map(|s| s.state.clone())
     }
+
+    /// Create a session ahead of user action and park it in the client's warm pool
+    ///
+    /// The browser negotiates a prewarmed session like any other (offer, answer,
+    /// ICE), so candidates are gathered and DTLS is done by the time the user
+    /// opens a terminal. The session stays in the pool until `claim_session`;
+    /// past `WARM_POOL_SIZE` per client the oldest warm session is closed.
+    pub async fn prewarm_session(
+        &self,
+        session_id: String,
+        client_id: String,
+        user_id: Option<String>,
+    ) -> Result<(), String> {
+        self.create_session(session_id.clone(), user_id).await?;
+
+        let evicted = {
+            let mut warm = self.warm.lock().await;
+            let pool = warm.entry(client_id.clone()).or_default();
+            pool.retain(|w| w.session_id != session_id);
+            pool.push_back(WarmSession {
+                session_id: session_id.clone(),
+                created_at: Instant::now(),
+            });
+            let excess = pool.len().saturating_sub(WARM_POOL_SIZE);
+            pool.drain(..excess).map(|w| w.session_id).collect::<Vec<_>>()
+        };
+
+        tracing::info!(
+            "🔥 Prewarmed session {} for client {} ({} evicted)",
+            session_id,
+            client_id,
+            evicted.len()
+        );
+        for id in evicted {
+            self.close_session(&id).await?;
+        }
+        Ok(())
+    }
+
+    /// Take a warm session out of the pool for use
+    ///
+    /// Fails when the session was never prewarmed, already claimed, expired or
+    /// dropped; the client should then start a fresh session.
+    pub async fn claim_session(&self, session_id: &str) -> Result<(), String> {
+        let claimed = {
+            let mut warm = self.warm.lock().await;
+            let mut found = false;
+            warm.retain(|_, pool| {
+                let before = pool.len();
+                pool.retain(|w| w.session_id != session_id);
+                found |= pool.len() != before;
+                !pool.is_empty()
+            });
+            found
+        };
+
+        if !claimed {
+            return Err(format!("Session {} is not a warm session", session_id));
+        }
+        if !self.session_exists(session_id).await {
+            return Err(format!("Warm session {} is no longer connected", session_id));
+        }
+        tracing::info!("🔥 Claimed warm session {}", session_id);
+        Ok(())
+    }
+
+    /// Number of unclaimed warm sessions for a client
+    pub async fn warm_session_count(&self, client_id: &str) -> usize {
+        self.warm
+            .lock()
+            .await
+            .get(client_id)
+            .map_or(0, |pool| pool.len())
+    }
+
+    /// Close warm sessions older than `ttl` and forget ones that already ended
+    ///
+    /// Returns how many sessions were closed.
+    pub async fn expire_warm_sessions(&self, ttl: Duration) -> usize {
+        let live: Vec<String> = self.list_sessions().await;
+        let expired = {
+            let mut warm = self.warm.lock().await;
+            let mut expired = Vec::new();
+            warm.retain(|_, pool| {
+                pool.retain(|w| {
+                    if !live.contains(&w.session_id) {
+                        return false;
+                    }
+                    if w.created_at.elapsed() >= ttl {
+                        expired.push(w.session_id.clone());
+                        return false;
+                    }
+                    true
+                });
+                !pool.is_empty()
+            });
+            expired
+        };
+
+        for id in &expired {
+            tracing::info!("🔥 Warm session {} expired unclaimed", id);
+            let _ = self.close_session(id).await;
+        }
+        expired.len()
+    }
+}
+
+/// Route one binary ADI request frame and send the response frames with
+/// `send`, which returns `false` once the client is gone.
+async fn serve_adi_frame<F, Fut>(
+    router: &Mutex<AdiRouter>,
+    ctx: AdiCallerContext,
+    access: Option<&DelegatedAccess>,
+    raw: &[u8],
+    send: F,
+) where
+    F: Fn(Bytes) -> Fut + Send + 'static,
+    Fut: Future<Output = bool> + Send,
+{
+    let admission = router.lock().await.admit(&ctx, access, raw);
+    match admission {
+        Ok(None) => {}
+        Ok(Some(delay)) => {
+            tracing::debug!("⏳ ADI request throttled for {:?}", delay);
+            tokio::time::sleep(delay).await;
+        }
+        Err(response) => {
+            send(response).await;
+            return;
+        }
+    }
+
+    let result = router.lock().await.handle_binary_scoped(&ctx, access, raw).await;
+    match result {
+        AdiRouterBinaryResult::Single(response) => {
+            let len = response.len();
+            if send(response).await {
+                tracing::debug!("📤 ADI binary response sent: {} bytes", len);
+            }
+        }
+        AdiRouterBinaryResult::Stream { request_id, receiver } => {
+            router.lock().await.forward_stream(request_id, receiver, send);
+        }
+    }
+}
+
+/// Answer a subscription message with `send` and, once subscribed, keep
+/// sending the subscription's events with it. Returns the subscription and
+/// its forwarding task. The router is not locked while `send` runs.
+async fn serve_adi_subscription<F, Fut>(
+    router: &Mutex<AdiRouter>,
+    access: Option<&DelegatedAccess>,
+    subscription: AdiSubscription,
+    send: F,
+) -> Option<(Uuid, tokio::task::JoinHandle<()>)>
+where
+    F: Fn(String) -> Fut + Send + 'static,
+    Fut: Future<Output = bool> + Send,
+{
+    let (response, events) = router
+        .lock()
+        .await
+        .handle_subscription_scoped(access, subscription)
+        .await;
+    let json = serde_json::to_string(&response).ok()?;
+    let subscribed = match (&response, events) {
+        (AdiSubscription::Subscribed { subscription_id, .. }, Some(events)) => {
+            Some((*subscription_id, events))
+        }
+        _ => None,
+    };
+    if !send(json).await {
+        if let Some((subscription_id, _)) = subscribed {
+            router.lock().await.remove_subscription(subscription_id).await;
+        }
+        return None;
+    }
+    let (subscription_id, events) = subscribed?;
+    let task = router.lock().await.forward_events(subscription_id, events, send);
+    Some((subscription_id, task))
+}
+
+/// At most 200 bytes of `data` for log lines, cut on a char boundary
+fn preview(data: &str) -> &str {
+    let mut end = data.len().min(200);
+    while !data.is_char_boundary(end) {
+        end -= 1;
+    }
+    &data[..end]
 }
 
 async fn dc_send(dc: &RTCDataChannel, msg: &CocoonMessage) {
     match serde_json::to_string(msg) {
         Ok(json) => {
-            tracing::warn!("📤 [dc_send] sending {} bytes, dc_id={}, readyState={:?}, preview={}", json.len(), dc.id(), dc.ready_state(), &json[..json.len().min(200)]);
+            tracing::warn!("📤 [dc_send] sending {} bytes, dc_id={}, readyState={:?}, preview={}", json.len(), dc.id(), dc.ready_state(), preview(&json));
             match dc.send(&json.into_bytes().into()).await {
                 Ok(n) => {
                     tracing::warn!("📤 [dc_send] OK — sent {} bytes", n);
@@ -867,6 +1304,121 @@ async fn dc_send(dc: &RTCDataChannel, msg: &CocoonMessage) {
     }
 }
 
+/// Session and command a Silk request refers to, for error replies
+fn silk_ids(msg: &CocoonMessage) -> (Option<String>, Option<String>) {
+    match msg {
+        CocoonMessage::SilkExecute { session_id, command_id, .. }
+        | CocoonMessage::SilkInput { session_id, command_id, .. }
+        | CocoonMessage::SilkResize { session_id, command_id, .. }
+        | CocoonMessage::SilkSignal { session_id, command_id, .. } => {
+            (Some(session_id.clone()), Some(command_id.clone()))
+        }
+        CocoonMessage::SilkCloseSession { session_id }
+        | CocoonMessage::SilkUploadFile { session_id, .. }
+        | CocoonMessage::SilkDownloadFile { session_id, .. } => (Some(session_id.clone()), None),
+        _ => (None, None),
+    }
+}
+
+/// Run a Silk command in a new PTY and point the client at it.
+async fn start_silk_pty(
+    state: &Arc<SilkDcState>,
+    dc: &Arc<RTCDataChannel>,
+    session_id: String,
+    command_id: String,
+    command: &str,
+    size: PtySize,
+    reason: String,
+) {
+    let recorder = state
+        .silk_sessions
+        .lock()
+        .await
+        .get(&session_id)
+        .and_then(|s| s.recorder.clone());
+    let dc_for_pty = dc.clone();
+    let pty_id = Uuid::new_v4();
+
+    let pty_system = portable_pty::native_pty_system();
+    match pty_system.openpty(size) {
+        Ok(pair) => {
+            let mut cmd = portable_pty::CommandBuilder::new("/bin/sh");
+            cmd.arg("-c");
+            cmd.arg(command);
+            cmd.env("TERM", "xterm-256color");
+
+            match pair.slave.spawn_command(cmd) {
+                Ok(child) => {
+                    if let Some(s) = state.silk_sessions.lock().await.get_mut(&session_id) {
+                        s.set_pty_session(command_id.clone(), pty_id);
+                    }
+
+                    let mut reader = pair.master.try_clone_reader().unwrap();
+                    let session_id_for_pty = session_id.clone();
+                    let command_id_for_pty = command_id.clone();
+                    let pty_id_str = pty_id.to_string();
+                    tokio::task::spawn_blocking(move || {
+                        let mut buf = [0u8; 4096];
+                        loop {
+                            match reader.read(&mut buf) {
+                                Ok(0) => break,
+                                Ok(n) => {
+                                    let data = String::from_utf8_lossy(&buf[..n]).to_string();
+                                    if let Some(recorder) = &recorder {
+                                        recorder.output(&data);
+                                    }
+                                    let response = CocoonMessage::SilkPtyOutput {
+                                        session_id: session_id_for_pty.clone(),
+                                        command_id: command_id_for_pty.clone(),
+                                        pty_session_id: pty_id_str.clone(),
+                                        data,
+                                    };
+                                    let dc_clone = dc_for_pty.clone();
+                                    tokio::spawn(async move {
+                                        dc_send(&dc_clone, &response).await;
+                                    });
+                                }
+                                Err(_) => break,
+                            }
+                        }
+                    });
+
+                    let pty_writer = pair.master.take_writer().unwrap();
+                    let pty_session = SilkPtySession {
+                        pair,
+                        _child: child,
+                        writer: pty_writer,
+                    };
+                    state.pty_sessions.lock().await.insert(command_id.clone(), pty_session);
+
+                    dc_send(dc, &CocoonMessage::SilkInteractiveRequired {
+                        session_id,
+                        command_id,
+                        reason,
+                        pty_session_id: pty_id.to_string(),
+                    }).await;
+                }
+                Err(e) => {
+                    dc_send(dc, &CocoonMessage::SilkError {
+                        session_id: Some(session_id),
+                        command_id: Some(command_id),
+                        code: "pty_spawn_failed".to_string(),
+                        message: e.to_string(),
+                    }).await;
+                }
+            }
+        }
+        Err(e) => {
+            dc_send(dc, &CocoonMessage::SilkError {
+                session_id: Some(session_id),
+                command_id: Some(command_id),
+                code: "pty_create_failed".to_string(),
+                message: e.to_string(),
+            }).await;
+        }
+    }
+}
+
 async fn handle_silk_dc_msg(
     msg: CocoonMessage,
     state: Arc<SilkDcState>,
@@ -906,6 +1458,12 @@ async fn handle_silk_dc_msg(
 
         CocoonMessage::SilkExecute { session_id, command, command_id, cols, rows, .. } => {
             tracing::info!("🧵 [DC] Silk execute: {} (session {})", command, session_id);
+            let size = PtySize {
+                rows: rows.map(|r| r as u16).unwrap_or(24),
+                cols: cols.map(|c| c as u16).unwrap_or(80),
+                pixel_width: 0,
+                pixel_height: 0,
+            };
             let mut sessions = state.silk_sessions.lock().await;
             let Some(session) = sessions.get_mut(&session_id) else {
                 drop(sessions);
@@ -920,89 +1478,19 @@ async fn handle_silk_dc_msg(
 
             match session.execute(&command, command_id.clone()) {
                 Ok((interactive, child_opt)) => {
+                    let recorder = session.recorder.clone();
                     if interactive {
                         drop(sessions);
-                        let dc_for_pty = dc.clone();
-                        let state_for_pty = state.clone();
-                        let term_cols = cols.map(|c| c as u16).unwrap_or(80);
-                        let term_rows = rows.map(|r| r as u16).unwrap_or(24);
-                        let pty_id = Uuid::new_v4();
-
-                        let pty_system = portable_pty::native_pty_system();
-                        match pty_system.openpty(PtySize { rows: term_rows, cols: term_cols, pixel_width: 0, pixel_height: 0 }) {
-                            Ok(pair) => {
-                                let mut cmd = portable_pty::CommandBuilder::new("/bin/sh");
-                                cmd.arg("-c");
-                                cmd.arg(&command);
-                                cmd.env("TERM", "xterm-256color");
-
-                                match pair.slave.spawn_command(cmd) {
-                                    Ok(child) => {
-                                        if let Some(s) = state_for_pty.silk_sessions.lock().await.get_mut(&session_id) {
-                                            s.set_pty_session(command_id.clone(), pty_id);
-                                        }
-
-                                        let mut reader = pair.master.try_clone_reader().unwrap();
-                                        let session_id_for_pty = session_id.clone();
-                                        let command_id_for_pty = command_id.clone();
-                                        let pty_id_str = pty_id.to_string();
-                                        tokio::task::spawn_blocking(move || {
-                                            let mut buf = [0u8; 4096];
-                                            loop {
-                                                match reader.read(&mut buf) {
-                                                    Ok(0) => break,
-                                                    Ok(n) => {
-                                                        let data = String::from_utf8_lossy(&buf[..n]).to_string();
-                                                        let response = CocoonMessage::SilkPtyOutput {
-                                                            session_id: session_id_for_pty.clone(),
-                                                            command_id: command_id_for_pty.clone(),
-                                                            pty_session_id: pty_id_str.clone(),
-                                                            data,
-                                                        };
-                                                        let dc_clone = dc_for_pty.clone();
-                                                        tokio::spawn(async move {
-                                                            dc_send(&dc_clone, &response).await;
-                                                        });
-                                                    }
-                                                    Err(_) => break,
-                                                }
-                                            }
-                                        });
-
-                                        let pty_writer = pair.master.take_writer().unwrap();
-                                        let pty_session = SilkPtySession {
-                                            pair,
-                                            _child: child,
-                                            writer: pty_writer,
-                                        };
-                                        state_for_pty.pty_sessions.lock().await.insert(command_id.clone(), pty_session);
-
-                                        dc_send(&dc, &CocoonMessage::SilkInteractiveRequired {
-                                            session_id,
-                                            command_id,
-                                            reason: format!("Command '{}' requires interactive mode", command.split_whitespace().next().unwrap_or(&command)),
-                                            pty_session_id: pty_id.to_string(),
-                                        }).await;
-                                    }
-                                    Err(e) => {
-                                        dc_send(&dc, &CocoonMessage::SilkError {
-                                            session_id: Some(session_id),
-                                            command_id: Some(command_id),
-                                            code: "pty_spawn_failed".to_string(),
-                                            message: e.to_string(),
-                                        }).await;
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                dc_send(&dc, &CocoonMessage::SilkError {
-                                    session_id: Some(session_id),
-                                    command_id: Some(command_id),
-                                    code: "pty_create_failed".to_string(),
-                                    message: e.to_string(),
-                                }).await;
-                            }
-                        }
+                        start_silk_pty(
+                            &state,
+                            &dc,
+                            session_id,
+                            command_id,
+                            &command,
+                            size,
+                            SilkSession::interactive_reason(&command),
+                        )
+                        .await;
                     } else if let Some(mut child) = child_opt {
                         drop(sessions);
                         let dc_for_out = dc.clone();
@@ -1020,12 +1508,18 @@ async fn handle_silk_dc_msg(
                             let mut stdout = std::io::BufReader::new(child.stdout.take().expect("stdout piped"));
                             let mut stderr = std::io::BufReader::new(child.stderr.take().expect("stderr piped"));
                             let mut buf = [0u8; 4096];
+                            let mut detector = OutputDetector::new();
+                            let mut needs_pty = None;
 
                             loop {
                                 match stdout.get_mut().read(&mut buf) {
                                     Ok(0) => break,
                                     Ok(n) => {
+                                        needs_pty = detector.feed(&buf[..n]).filter(|d| d.is_confident());
                                         let data = String::from_utf8_lossy(&buf[..n]).to_string();
+                                        if let Some(recorder) = &recorder {
+                                            recorder.output(&data);
+                                        }
                                         let html = AnsiToHtml::convert(&data);
                                         dc_send(&dc_for_out, &CocoonMessage::SilkOutput {
                                             session_id: session_id.clone(),
@@ -1034,15 +1528,40 @@ async fn handle_silk_dc_msg(
                                             data,
                                             html: Some(html),
                                         }).await;
+                                        if needs_pty.is_some() {
+                                            break;
+                                        }
                                     }
                                     Err(_) => break,
                                 }
                             }
 
+                            // Waiting on a prompt or driving the terminal:
+                            // piped I/O can't serve it, so rerun in a PTY
+                            if let Some(detection) = needs_pty {
+                                tracing::info!("🧵 [DC] Restarting {} in a PTY: {}", command, detection.reason);
+                                let _ = child.kill();
+                                let _ = child.wait();
+                                start_silk_pty(
+                                    &state_for_out,
+                                    &dc_for_out,
+                                    session_id,
+                                    command_id,
+                                    &command,
+                                    size,
+                                    detection.reason,
+                                )
+                                .await;
+                                return;
+                            }
+
                             let mut stderr_buf = Vec::new();
                             let _ = stderr.read_to_end(&mut stderr_buf);
                             if !stderr_buf.is_empty() {
                                 let data = String::from_utf8_lossy(&stderr_buf).to_string();
+                                if let Some(recorder) = &recorder {
+                                    recorder.output(&data);
+                                }
                                 let html = AnsiToHtml::convert(&data);
                                 dc_send(&dc_for_out, &CocoonMessage::SilkOutput {
                                     session_id: session_id.clone(),
@@ -1093,6 +1612,16 @@ async fn handle_silk_dc_msg(
         }
 
         CocoonMessage::SilkInput { session_id, command_id, data } => {
+            let recorder = state
+                .silk_sessions
+                .lock()
+                .await
+                .get(&session_id)
+                .and_then(|s| s.recorder.clone());
+            if let Some(recorder) = recorder {
+                recorder.input(&data);
+            }
+
             let mut pty_sessions = state.pty_sessions.lock().await;
             if let Some(pty) = pty_sessions.get_mut(&command_id) {
                 if let Err(e) = std::io::Write::write_all(&mut pty.writer, data.as_bytes()) {
@@ -1128,6 +1657,72 @@ async fn handle_silk_dc_msg(
             dc_send(&dc, &CocoonMessage::SilkSessionClosed { session_id }).await;
         }
 
+        CocoonMessage::SilkUploadFile { session_id, path, chunk, seq, done, sha256 } => {
+            let pushed = match state.silk_sessions.lock().await.get_mut(&session_id) {
+                Some(session) => session.push_upload(&path, seq, &chunk, done, sha256.as_deref()),
+                None => Err(format!("Silk session {} not found", session_id)),
+            };
+            let result = match pushed {
+                Ok(None) => return,
+                Ok(Some((target, data))) => {
+                    tracing::info!("🧵 [DC] Silk upload: {} ({} bytes)", target.display(), data.len());
+                    write_upload(&target, &data)
+                        .await
+                        .map(|()| (data.len() as u64, lib_tarminal_sync::sha256_hex(&data)))
+                        .map_err(|e| e.to_string())
+                }
+                Err(e) => Err(e),
+            };
+            let response = match result {
+                Ok((size, sha256)) => CocoonMessage::SilkFileUploaded { session_id, path, size, sha256 },
+                Err(e) => CocoonMessage::SilkError {
+                    session_id: Some(session_id),
+                    command_id: None,
+                    code: "upload_failed".to_string(),
+                    message: format!("{}: {}", path, e),
+                },
+            };
+            dc_send(&dc, &response).await;
+        }
+
+        CocoonMessage::SilkDownloadFile { session_id, path } => {
+            let source = state
+                .silk_sessions
+                .lock()
+                .await
+                .get(&session_id)
+                .map(|session| session.resolve_path(&path));
+            let data = match source {
+                Some(source) => {
+                    tracing::info!("🧵 [DC] Silk download: {}", source.display());
+                    read_download(&source).await
+                }
+                None => Err(format!("Silk session {} not found", session_id)),
+            };
+            match data {
+                Ok(data) => {
+                    for chunk in lib_tarminal_sync::split_file(&data) {
+                        dc_send(&dc, &CocoonMessage::SilkFileChunk {
+                            session_id: session_id.clone(),
+                            path: path.clone(),
+                            chunk: chunk.chunk,
+                            seq: chunk.seq,
+                            done: chunk.done,
+                            sha256: chunk.sha256,
+                        }).await;
+                    }
+                }
+                Err(e) => {
+                    dc_send(&dc, &CocoonMessage::SilkError {
+                        session_id: Some(session_id),
+                        command_id: None,
+                        code: "download_failed".to_string(),
+                        message: format!("{}: {}", path, e),
+                    }).await;
+                }
+            }
+        }
+
         _ => {
             tracing::debug!("🧵 [DC] Unhandled silk message type");
         }
@@ -1519,4 +2114,100 @@ mod tests {
         assert_eq!(close_success, 50, "All 50 sessions should be closed");
         assert_eq!(manager.session_count().await, 0);
     }
+
+    #[tokio::test]
+    async fn test_prewarm_pool_evicts_oldest() {
+        let (manager, _rx) = create_test_manager();
+
+        for i in 1..=WARM_POOL_SIZE + 1 {
+            manager
+                .prewarm_session(format!("warm-{}", i), "client-a".to_string(), None)
+                .await
+                .expect("Failed to prewarm session");
+        }
+
+        assert_eq!(manager.warm_session_count("client-a").await, WARM_POOL_SIZE);
+        assert!(!manager.session_exists("warm-1").await, "Oldest warm session should be closed");
+        assert!(manager.session_exists(&format!("warm-{}", WARM_POOL_SIZE + 1)).await);
+        assert_eq!(manager.warm_session_count("client-b").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_claim_warm_session() {
+        let (manager, _rx) = create_test_manager();
+
+        manager
+            .prewarm_session("warm-claim".to_string(), "client-a".to_string(), None)
+            .await
+            .expect("Failed to prewarm session");
+
+        assert!(manager.claim_session("warm-claim").await.is_ok());
+        assert_eq!(manager.warm_session_count("client-a").await, 0);
+        assert!(manager.session_exists("warm-claim").await, "Claimed session stays open");
+
+        let again = manager.claim_session("warm-claim").await;
+        assert!(again.is_err(), "A session can only be claimed once");
+
+        manager
+            .create_session("cold".to_string(), None)
+            .await
+            .expect("Failed to create session");
+        assert!(manager.claim_session("cold").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expire_warm_sessions() {
+        let (manager, _rx) = create_test_manager();
+
+        manager
+            .prewarm_session("warm-old".to_string(), "client-a".to_string(), None)
+            .await
+            .expect("Failed to prewarm session");
+        manager
+            .prewarm_session("warm-claimed".to_string(), "client-a".to_string(), None)
+            .await
+            .expect("Failed to prewarm session");
+        manager.claim_session("warm-claimed").await.expect("Failed to claim");
+
+        assert_eq!(manager.expire_warm_sessions(WARM_SESSION_TTL).await, 0);
+        assert_eq!(manager.expire_warm_sessions(Duration::ZERO).await, 1);
+
+        assert!(!manager.session_exists("warm-old").await);
+        assert!(manager.session_exists("warm-claimed").await, "Claimed sessions never expire");
+        assert_eq!(manager.warm_session_count("client-a").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_relayed_adi_subscriptions_belong_to_session() {
+        let mut router = AdiRouter::new();
+        router.register_event_source("linter");
+        let router = Arc::new(Mutex::new(router));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let manager = WebRtcManager::with_adi_router(tx, router.clone());
+        let subscribe = serde_json::to_string(&AdiSubscription::Subscribe {
+            request_id: Uuid::nil(),
+            plugin: "linter".to_string(),
+            event: "diagnostic".to_string(),
+            filter: None,
+        })
+        .unwrap();
+
+        manager.handle_relayed_adi("unknown", &subscribe, false).await;
+        assert!(rx.try_recv().is_err(), "Unknown sessions get no answer");
+        assert_eq!(router.lock().await.subscription_count().await, 0);
+
+        manager.create_session("relayed".to_string(), None).await.unwrap();
+        manager.handle_relayed_adi("relayed", &subscribe, false).await;
+        assert_eq!(router.lock().await.subscription_count().await, 1);
+
+        manager.close_session("relayed").await.unwrap();
+        assert_eq!(router.lock().await.subscription_count().await, 0);
+    }
+
+    #[test]
+    fn test_preview_cuts_on_char_boundary() {
+        let text = format!("{}é tail", "a".repeat(199));
+        assert_eq!(preview(&text), "a".repeat(199));
+        assert_eq!(preview("short"), "short");
+    }
 }
@@ -1,7 +1,12 @@
-use cocoon_core::{CocoonStatus, RuntimeManager, RuntimeType};
-use lib_console_output::{out_error, out_info, out_success, theme, KeyValue, Renderable};
+use cocoon_core::{
+    CocoonStatus, ConfigPushRequest, ExecRequest, ExecTarget, ForwardRequest, ForwardSpec,
+    HistoryRequest, OwnershipAction, OwnershipTokenType, RuntimeManager, RuntimeType, ShareRequest,
+};
+use lib_console_output::{out_error, out_info, out_success, theme, KeyValue, Renderable, Table};
+use lib_credential_store::CredentialStore;
 use lib_env_parse::{env_opt, env_vars};
 use once_cell::sync::OnceCell;
+use std::collections::HashMap;
 
 static RUNTIME: OnceCell<tokio::runtime::Runtime> = OnceCell::new();
 
@@ -90,6 +95,7 @@ env_vars! {
     Home => "HOME",
     CocoonSetupToken => "COCOON_SETUP_TOKEN",
     CocoonSecret => "COCOON_SECRET",
+    SignalingAccessToken => "SIGNALING_ACCESS_TOKEN",
 }
 
 use lib_plugin_prelude::*;
@@ -156,6 +162,130 @@ pub struct CheckUpdateArgs {
     pub name: Option<String>,
 }
 
+/// Options for `exec`; the device id and command are read from the
+/// positional args, since the command is everything after `--`.
+#[derive(CliArgs)]
+pub struct ExecArgs {
+    #[arg(long)]
+    pub all: bool,
+
+    #[arg(long)]
+    pub label: Option<String>,
+
+    #[arg(long)]
+    pub url: Option<String>,
+
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+/// Options for `forward`; the device id and port specs are positional.
+#[derive(CliArgs)]
+pub struct ForwardArgs {
+    #[arg(long)]
+    pub url: Option<String>,
+
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+#[derive(CliArgs)]
+pub struct HistoryArgs {
+    #[arg(position = 0)]
+    pub device: Option<String>,
+
+    #[arg(long)]
+    pub url: Option<String>,
+
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+/// Options for `share`: `<device-id> --scope silk:ro,tasks --ttl 2h`.
+#[derive(CliArgs)]
+pub struct ShareArgs {
+    #[arg(position = 0)]
+    pub device: Option<String>,
+
+    /// Comma-separated `<service>` or `<service>:ro` scopes
+    #[arg(long)]
+    pub scope: Option<String>,
+
+    #[arg(long)]
+    pub ttl: Option<String>,
+
+    /// Token id of an earlier share to end early
+    #[arg(long)]
+    pub revoke: Option<String>,
+
+    #[arg(long)]
+    pub url: Option<String>,
+
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+/// Options for `config push`. `-f FILE` is read from the positionals, since
+/// only long options are parsed as options.
+#[derive(CliArgs)]
+pub struct ConfigArgs {
+    #[arg(position = 0)]
+    pub action: Option<String>,
+
+    #[arg(position = 1)]
+    pub rest: Vec<String>,
+
+    #[arg(long)]
+    pub file: Option<String>,
+
+    /// Comma-separated device ids or prefixes
+    #[arg(long)]
+    pub device: Option<String>,
+
+    /// Comma-separated `key=value` tags a cocoon must all have
+    #[arg(long)]
+    pub label: Option<String>,
+
+    #[arg(long)]
+    pub version: Option<String>,
+
+    #[arg(long)]
+    pub url: Option<String>,
+
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+#[derive(CliArgs)]
+pub struct DiscoverArgs {
+    /// Seconds to wait for answers
+    #[arg(long)]
+    pub timeout: Option<f64>,
+}
+
+/// Options for `recordings`: `<list|download|play> <name> [recording-id]`.
+/// Without an id, download and play use the newest recording.
+#[derive(CliArgs)]
+pub struct RecordingsArgs {
+    #[arg(position = 0)]
+    pub action: Option<String>,
+
+    #[arg(position = 1)]
+    pub name: Option<String>,
+
+    #[arg(position = 2)]
+    pub id: Option<String>,
+
+    #[arg(long)]
+    pub output: Option<String>,
+
+    #[arg(long)]
+    pub speed: Option<f64>,
+
+    #[arg(long)]
+    pub idle_limit: Option<f64>,
+}
+
 #[derive(CliArgs)]
 pub struct UpdateArgs {
     #[arg(position = 0)]
@@ -277,7 +407,26 @@ COMMANDS:
     stop <name>         Stop a running cocoon
     restart <name>      Restart a cocoon
     logs <name> [-f]    View cocoon logs (-f to follow)
+    exec <device> -- <command...>
+                        Run a command on a remote cocoon
+    forward <device> <local:host:port...>
+                        Forward local TCP ports to a remote cocoon
+    history <device>    Show who claimed, transferred or removed a cocoon
+    share <device> --scope SCOPES [--ttl DURATION]
+                        Give someone time-limited access to a cocoon
+    share --revoke TOKEN_ID
+                        End a share before it expires
+    config push (--device IDS | --label K=V) -f FILE
+                        Apply a JSON merge patch to cocoons' config
     rm <name> [--force] Remove a cocoon
+    discover [--timeout SECS]
+                        Find cocoons on the local network
+    recordings list <name>
+                        List recorded Silk sessions
+    recordings download <name> [id] [--output FILE]
+                        Save a recording as an asciicast file
+    recordings play <name> [id] [--speed N] [--idle-limit SECS]
+                        Replay a recording in the terminal
     create              Create a new cocoon (interactive)
     run                 Run cocoon natively in foreground
     setup [--port PORT] Start pairing server for browser setup (default: 14730)
@@ -294,6 +443,52 @@ CREATE OPTIONS:
     --secret SECRET     Pre-generated secret
     --start             Start service after create (machine only)
 
+EXEC OPTIONS:
+    --all --label TAG   Run on every online cocoon tagged TAG (or TAG=VALUE)
+    --url URL           Signaling server URL
+    --token TOKEN       Access token (default: $SIGNALING_ACCESS_TOKEN)
+
+FORWARD OPTIONS:
+    --url URL           Signaling server URL
+    --token TOKEN       Access token (default: $SIGNALING_ACCESS_TOKEN)
+
+HISTORY OPTIONS:
+    --url URL           Signaling server URL
+    --token TOKEN       Access token (default: $SIGNALING_ACCESS_TOKEN)
+    Only current and past owners can read a cocoon's history.
+
+SHARE OPTIONS:
+    --scope SCOPES      Comma-separated services, `:ro` for read-only:
+                        an ADI plugin (tasks), silk, files or forward
+    --ttl DURATION      Lifetime, e.g. 30m, 2h, 7d (default: 1h)
+    --revoke TOKEN_ID   Revoke a share; open sessions using it are closed
+    --url URL           Signaling server URL
+    --token TOKEN       Access token (default: $SIGNALING_ACCESS_TOKEN)
+    Read-only Silk runs inspection commands only (ls, cat, git log, ...).
+
+CONFIG PUSH OPTIONS:
+    -f, --file FILE     JSON merge patch: log_level, feature_flags
+    --device IDS        Comma-separated device ids or prefixes
+    --label K=V,...     Every owned cocoon with all of these tags
+    --version N         Config version (default: current time in ms);
+                        cocoons reject versions older than their current one
+    --url URL           Signaling server URL
+    --token TOKEN       Access token (default: $SIGNALING_ACCESS_TOKEN)
+    Each cocoon validates the patch and rolls back if applying it fails.
+    Offline cocoons are listed and keep their config.
+
+RECORDINGS:
+    Recording is opt-in on the cocoon: set COCOON_RECORD_SILK=true.
+    Files are asciicast v2 (.cast) and also play in asciinema.
+    Retention: COCOON_RECORDING_RETENTION_DAYS (default 30),
+    COCOON_RECORDING_MAX_MB (default 1024); 0 disables a limit.
+
+LAN ACCESS:
+    Cocoons answer discovery on udp/47820 and accept direct connections on
+    COCOON_LAN_PORT (default 47821). Web clients use the direct path when it
+    is faster than the relay. Disable with COCOON_LAN=false; containers need
+    COCOON_LAN_ADDRESS set to the host's address and both ports published.
+
 UPDATE OPTIONS:
     --all, -a           Update all cocoons
 
@@ -315,6 +510,31 @@ EXAMPLES:
     adi cocoon stop cocoon-worker
     adi cocoon logs cocoon-worker -f
 
+    # Run a command on a remote cocoon (exit code is propagated)
+    adi cocoon exec 3f9a1c2b -- uname -a
+
+    # Run on every GPU cocoon, output prefixed by cocoon name
+    adi cocoon exec --all --label gpu -- nvidia-smi
+
+    # Reach a cocoon's web app and database on local ports (Ctrl+C to stop)
+    adi cocoon forward 3f9a1c2b 8080:localhost:3000 5432
+
+    # Audit ownership changes of a cocoon
+    adi cocoon history 3f9a1c2b
+
+    # Let a teammate look at a cocoon's terminal and tasks for two hours
+    adi cocoon share 3f9a1c2b --scope silk:ro,tasks:ro --ttl 2h
+    adi cocoon share --revoke 9b2e41d0
+
+    # Turn on debug logging on every cocoon in the EU region
+    adi cocoon config push --label region=eu -f patch.json
+
+    # Find cocoons on this network that clients can reach directly
+    adi cocoon discover
+
+    # Replay the newest recorded Silk session at double speed
+    adi cocoon recordings play cocoon-worker --speed 2
+
     # Create a Docker cocoon
     adi cocoon create --runtime docker --name my-worker --url wss://example.com/ws
 
@@ -337,6 +557,7 @@ ENVIRONMENT VARIABLES:
     SIGNALING_SERVER_URL    WebSocket URL (default: ws://localhost:8080/ws)
     COCOON_SECRET           Pre-generated secret for persistent device ID
     COCOON_SETUP_TOKEN      Setup token for auto-claim
+    SIGNALING_ACCESS_TOKEN  Access token for exec, forward, history, share and config
 "#
 }
 
@@ -386,7 +607,24 @@ impl CliCommands for CocoonPlugin {
             Self::__sdk_cmd_meta_stop(),
             Self::__sdk_cmd_meta_restart(),
             Self::__sdk_cmd_meta_logs(),
+            CliCommand {
+                name: "exec".to_string(),
+                description: "Run a command on remote cocoons".to_string(),
+                args: ExecArgs::schema(),
+                has_subcommands: false,
+            },
+            CliCommand {
+                name: "forward".to_string(),
+                description: "Forward local TCP ports to a remote cocoon".to_string(),
+                args: ForwardArgs::schema(),
+                has_subcommands: false,
+            },
+            Self::__sdk_cmd_meta_history(),
+            Self::__sdk_cmd_meta_share(),
+            Self::__sdk_cmd_meta_config(),
             Self::__sdk_cmd_meta_rm(),
+            Self::__sdk_cmd_meta_discover(),
+            Self::__sdk_cmd_meta_recordings(),
             Self::__sdk_cmd_meta_create(),
             Self::__sdk_cmd_meta_run_native(),
             Self::__sdk_cmd_meta_setup_pairing(),
@@ -404,7 +642,14 @@ impl CliCommands for CocoonPlugin {
             Some("stop") => self.__sdk_cmd_handler_stop(ctx).await,
             Some("restart") => self.__sdk_cmd_handler_restart(ctx).await,
             Some("logs") => self.__sdk_cmd_handler_logs(ctx).await,
+            Some("exec") => self.exec(ctx),
+            Some("forward") | Some("fwd") => self.forward(ctx),
+            Some("history") => self.__sdk_cmd_handler_history(ctx).await,
+            Some("share") => self.__sdk_cmd_handler_share(ctx).await,
+            Some("config") => self.__sdk_cmd_handler_config(ctx).await,
             Some("rm") | Some("remove") => self.__sdk_cmd_handler_rm(ctx).await,
+            Some("discover") => self.__sdk_cmd_handler_discover(ctx).await,
+            Some("recordings") | Some("rec") => self.__sdk_cmd_handler_recordings(ctx).await,
             Some("create") | Some("new") => self.__sdk_cmd_handler_create(ctx).await,
             Some("run") => self.__sdk_cmd_handler_run_native(ctx).await,
             Some("setup") => self.__sdk_cmd_handler_setup_pairing(ctx).await,
@@ -556,6 +801,262 @@ impl CocoonPlugin {
         }
     }
 
+    /// Not a `#[command]`: the exit code of the remote command is passed
+    /// through instead of collapsing to success/error.
+    fn exec(&self, ctx: &CliContext) -> Result<CliResult> {
+        let args = ExecArgs::parse(ctx).map_err(PluginError::InvalidInput)?;
+
+        let (target, command) = if args.all {
+            let Some(label) = args.label else {
+                return Ok(CliResult::error("--all requires --label <tag>"));
+            };
+            (ExecTarget::Label(label), &ctx.args[..])
+        } else {
+            match ctx.args.split_first() {
+                Some((device, command)) => (ExecTarget::Device(device.clone()), command),
+                None => {
+                    return Ok(CliResult::error(
+                        "Usage: adi cocoon exec <device-id> -- <command...>",
+                    ))
+                }
+            }
+        };
+        if command.is_empty() {
+            return Ok(CliResult::error("No command given"));
+        }
+
+        let (signaling_url, access_token) = match signaling_login(args.url, args.token) {
+            Ok(login) => login,
+            Err(e) => return Ok(CliResult::error(e)),
+        };
+
+        let request = ExecRequest {
+            signaling_url,
+            access_token,
+            target,
+            // Joined like ssh does; the remote shell does the splitting
+            command: command.join(" "),
+            session_cache: Some(
+                lib_daemon_client::paths::data_dir()
+                    .join("cocoon")
+                    .join("exec-sessions.json"),
+            ),
+        };
+
+        let exits = match run_with_runtime(cocoon_core::run_exec(request)) {
+            Ok(exits) => exits,
+            Err(e) => return Ok(CliResult::error(e)),
+        };
+
+        let failed: Vec<_> = exits.iter().filter(|e| e.exit_code != 0).collect();
+        let exit_code = exits.iter().map(|e| e.exit_code).max().unwrap_or(0);
+        let summary = if exits.len() > 1 && !failed.is_empty() {
+            format!("{}/{} cocoons failed", failed.len(), exits.len())
+        } else {
+            String::new()
+        };
+        Ok(CliResult::custom(exit_code, String::new(), summary))
+    }
+
+    /// Not a `#[command]`: takes a variable number of positional specs.
+    fn forward(&self, ctx: &CliContext) -> Result<CliResult> {
+        let args = ForwardArgs::parse(ctx).map_err(PluginError::InvalidInput)?;
+
+        let Some((device, specs)) = ctx.args.split_first().filter(|(_, specs)| !specs.is_empty())
+        else {
+            return Ok(CliResult::error(
+                "Usage: adi cocoon forward <device-id> <local-port:host:port>...",
+            ));
+        };
+        let specs = match specs
+            .iter()
+            .map(|s| s.parse::<ForwardSpec>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+        {
+            Ok(specs) => specs,
+            Err(e) => return Ok(CliResult::error(e)),
+        };
+
+        let (signaling_url, access_token) = match signaling_login(args.url, args.token) {
+            Ok(login) => login,
+            Err(e) => return Ok(CliResult::error(e)),
+        };
+
+        let request = ForwardRequest {
+            signaling_url,
+            access_token,
+            device: device.clone(),
+            specs,
+        };
+        match run_with_runtime(cocoon_core::run_forward(request)) {
+            Ok(()) => Ok(CliResult::success(String::new())),
+            Err(e) => Ok(CliResult::error(e)),
+        }
+    }
+
+    #[command(name = "history", description = "Show ownership changes of a cocoon")]
+    async fn history(&self, args: HistoryArgs) -> CmdResult {
+        let device = args.device.ok_or("Usage: adi cocoon history <device-id>")?;
+        let (signaling_url, access_token) = signaling_login(args.url, args.token)?;
+
+        let (device_id, events) = cocoon_core::run_ownership_history(HistoryRequest {
+            signaling_url,
+            access_token,
+            device,
+        })
+        .await?;
+
+        if events.is_empty() {
+            out_info!("No ownership changes recorded for {}", device_id);
+            return Ok("No ownership history".to_string());
+        }
+
+        let mut table = Table::new().header(["When", "Action", "Owner", "By", "Via"]);
+        for event in &events {
+            table = table.row([
+                format_ago(event.at),
+                ownership_action_label(&event.action).to_string(),
+                event.user_id.clone(),
+                event.actor.clone().unwrap_or_else(|| "device".to_string()),
+                ownership_token_label(&event.token_type).to_string(),
+            ]);
+        }
+        table.print();
+        Ok(format!(
+            "{} ownership change(s) for {}",
+            events.len(),
+            device_id
+        ))
+    }
+
+    #[command(name = "share", description = "Share time-limited access to a cocoon")]
+    async fn share(&self, args: ShareArgs) -> CmdResult {
+        const USAGE: &str = "Usage: adi cocoon share <device-id> --scope <scopes> [--ttl 2h]";
+        if let Some(token_id) = args.revoke {
+            let (signaling_url, access_token) = signaling_login(args.url, args.token)?;
+            let revoked = cocoon_core::run_revoke(&signaling_url, &access_token, &token_id).await?;
+            if !revoked {
+                return Err(format!("No share {} you can revoke", token_id));
+            }
+            return Ok(format!("Revoked delegated token {}", token_id));
+        }
+        let device = args.device.ok_or(USAGE)?;
+        let scopes: Vec<String> = args
+            .scope
+            .ok_or(USAGE)?
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let ttl = cocoon_core::parse_ttl(args.ttl.as_deref().unwrap_or("1h"))?;
+        let (signaling_url, access_token) = signaling_login(args.url, args.token)?;
+
+        let (token, grant) = cocoon_core::run_share(ShareRequest {
+            signaling_url,
+            access_token,
+            device,
+            scopes,
+            ttl,
+        })
+        .await?;
+
+        KeyValue::new()
+            .entry("Cocoon", grant.device_id.as_str())
+            .entry("Scopes", grant.scopes.join(", "))
+            .entry("Expires", format_in(grant.expires_at))
+            .entry("Token", &token)
+            .print();
+        out_info!("Anyone with this token can use these scopes until it expires");
+        Ok(format!("Issued delegated token {}", grant.token_id))
+    }
+
+    #[command(name = "config", description = "Push a config patch to cocoons")]
+    async fn config(&self, args: ConfigArgs) -> CmdResult {
+        const USAGE: &str =
+            "Usage: adi cocoon config push (--device <ids> | --label <key=value>) -f <patch.json>";
+        if args.action.as_deref() != Some("push") {
+            return Err(USAGE.to_string());
+        }
+        let file = match args.file {
+            Some(file) => file,
+            None => match args.rest.as_slice() {
+                [flag, file] if flag == "-f" => file.clone(),
+                _ => return Err(USAGE.to_string()),
+            },
+        };
+        let devices: Vec<String> = args
+            .device
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let labels = parse_labels(args.label.as_deref().unwrap_or_default())?;
+        if devices.is_empty() && labels.is_empty() {
+            return Err(USAGE.to_string());
+        }
+        let version = args
+            .version
+            .map(|v| {
+                v.parse::<u64>()
+                    .map_err(|_| format!("Invalid version '{}'", v))
+            })
+            .transpose()?;
+
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {}: {}", file, e))?;
+        let patch: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("{} is not valid JSON: {}", file, e))?;
+        let (signaling_url, access_token) = signaling_login(args.url, args.token)?;
+
+        let outcome = cocoon_core::run_config_push(ConfigPushRequest {
+            signaling_url,
+            access_token,
+            devices,
+            labels,
+            patch,
+            version,
+        })
+        .await?;
+
+        let mut table = Table::new().header(["Cocoon", "Result"]);
+        for report in &outcome.reports {
+            let result = match (report.success, &report.error) {
+                (true, _) => "applied".to_string(),
+                (false, Some(error)) => format!("rolled back: {}", error),
+                (false, None) => "rolled back".to_string(),
+            };
+            table = table.row([report.device_id.to_string(), result]);
+        }
+        for device_id in &outcome.unanswered {
+            table = table.row([device_id.clone(), "no answer".to_string()]);
+        }
+        for device_id in &outcome.offline {
+            table = table.row([device_id.clone(), "offline".to_string()]);
+        }
+        if outcome.reports.is_empty() && outcome.unanswered.is_empty() && outcome.offline.is_empty()
+        {
+            out_info!("No cocoons matched");
+            return Ok("No cocoons matched".to_string());
+        }
+        table.print();
+
+        let applied = outcome.reports.iter().filter(|r| r.success).count();
+        let failed = outcome.reports.len() - applied + outcome.unanswered.len();
+        let summary = format!(
+            "Config version {} applied on {} cocoon(s), {} failed, {} offline",
+            outcome.version,
+            applied,
+            failed,
+            outcome.offline.len()
+        );
+        if failed > 0 {
+            return Err(summary);
+        }
+        Ok(summary)
+    }
+
     #[command(name = "rm", description = "Remove a cocoon")]
     async fn rm(&self, args: RmArgs) -> CmdResult {
         let manager = RuntimeManager::new();
@@ -574,6 +1075,101 @@ impl CocoonPlugin {
         }
     }
 
+    #[command(name = "discover", description = "Find cocoons on the local network")]
+    async fn discover(&self, args: DiscoverArgs) -> CmdResult {
+        let timeout = std::time::Duration::from_secs_f64(args.timeout.unwrap_or(2.0).max(0.1));
+        let found = cocoon_core::lan::discover(timeout)
+            .await
+            .map_err(|e| format!("Discovery failed: {}", e))?;
+
+        if found.is_empty() {
+            out_info!("No cocoons answered on this network");
+            return Ok("No cocoons found".to_string());
+        }
+
+        let mut table = Table::new().header(["Name", "Device", "Address", "Version", "RTT"]);
+        for cocoon in &found {
+            let device = cocoon.announce.device_id.clone().unwrap_or_else(|| "-".to_string());
+            table = table.row([
+                cocoon.announce.name.clone().unwrap_or_else(|| "-".to_string()),
+                device.chars().take(8).collect(),
+                cocoon.ws_url(),
+                cocoon.announce.version.clone(),
+                format!("{:.1}ms", cocoon.rtt.as_secs_f64() * 1000.0),
+            ]);
+        }
+        table.print();
+        Ok(format!("{} cocoon(s) on the local network", found.len()))
+    }
+
+    #[command(name = "recordings", description = "List, download and replay Silk session recordings")]
+    async fn recordings(&self, args: RecordingsArgs) -> CmdResult {
+        const USAGE: &str = "Usage: adi cocoon recordings <list|download|play> <name> [recording-id]";
+
+        let action = args.action.ok_or(USAGE)?;
+        let name = args.name.ok_or(USAGE)?;
+        let manager = RuntimeManager::new();
+        let (_, runtime_type) = manager
+            .find_cocoon(&name)
+            .ok_or_else(|| format!("Cocoon '{}' not found", name))?;
+        let runtime = manager.get_runtime(runtime_type);
+
+        let recordings = runtime.list_recordings(&name)?;
+
+        if action == "list" || action == "ls" {
+            if recordings.is_empty() {
+                out_info!("No recordings on '{}'. Set COCOON_RECORD_SILK=true on the cocoon to record Silk sessions.", name);
+                return Ok("No recordings".to_string());
+            }
+
+            let mut table = Table::new().header(["Recording", "Started", "Size"]);
+            for rec in &recordings {
+                table = table.row([rec.id.clone(), format_ago(rec.started_at), format_size(rec.size)]);
+            }
+            table.print();
+            return Ok(format!("{} recording(s)", recordings.len()));
+        }
+
+        let id = match args.id {
+            Some(id) => id,
+            None => recordings
+                .first()
+                .map(|r| r.id.clone())
+                .ok_or_else(|| format!("No recordings on '{}'", name))?,
+        };
+        let bytes = runtime.read_recording(&name, &id)?;
+
+        match action.as_str() {
+            "download" | "dl" => {
+                let output = args.output.unwrap_or_else(|| format!("{}.cast", id));
+                std::fs::write(&output, &bytes)
+                    .map_err(|e| format!("Failed to write {}: {}", output, e))?;
+                out_success!("Saved recording {} to {}", id, output);
+                Ok(output)
+            }
+            "play" => {
+                let text = String::from_utf8_lossy(&bytes);
+                let cast = cocoon_core::recording::parse_cast(&text)?;
+                out_info!(
+                    "Playing {} ({}x{}), Ctrl+C to stop",
+                    theme::bold(&id),
+                    cast.header.width,
+                    cast.header.height
+                );
+                let idle_limit = args.idle_limit.or(Some(2.0));
+                cocoon_core::recording::play(
+                    &cast,
+                    args.speed.unwrap_or(1.0),
+                    idle_limit,
+                    &mut std::io::stdout(),
+                )
+                .map_err(|e| format!("Playback failed: {}", e))?;
+                Ok(format!("Played {}", id))
+            }
+            other => Err(format!("Unknown recordings action: {}. {}", other, USAGE)),
+        }
+    }
+
     #[command(name = "create", description = "Create a new cocoon")]
     async fn create(&self, args: CreateArgs) -> CmdResult {
         let manager = RuntimeManager::new();
@@ -594,9 +1190,11 @@ impl CocoonPlugin {
                     let setup_token = args
                         .token
                         .or_else(|| env_opt(EnvVar::CocoonSetupToken.as_str()));
-                    let cocoon_secret = args
-                        .secret
-                        .or_else(|| env_opt(EnvVar::CocoonSecret.as_str()));
+                    let cocoon_secret = cached_cocoon_secret(
+                        &name,
+                        args.secret
+                            .or_else(|| env_opt(EnvVar::CocoonSecret.as_str())),
+                    );
                     create_docker_cocoon(
                         &name,
                         &signaling_url,
@@ -789,9 +1387,137 @@ impl CocoonPlugin {
     }
 }
 
-fn run_with_runtime<F: std::future::Future<Output = CmdResult> + Send + 'static>(
-    fut: F,
-) -> CmdResult {
+fn format_in(unix_secs: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let secs = unix_secs.saturating_sub(now);
+    if secs < 60 {
+        format!("in {}s", secs)
+    } else if secs < 3600 {
+        format!("in {}m", secs / 60)
+    } else if secs < 86400 {
+        format!("in {}h", secs / 3600)
+    } else {
+        format!("in {}d", secs / 86400)
+    }
+}
+
+fn format_ago(unix_secs: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let secs = now.saturating_sub(unix_secs);
+    if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86400)
+    }
+}
+
+fn ownership_action_label(action: &OwnershipAction) -> &'static str {
+    match action {
+        OwnershipAction::Claimed => "claimed",
+        OwnershipAction::Transferred => "transferred",
+        OwnershipAction::Removed => "removed",
+    }
+}
+
+fn ownership_token_label(token_type: &OwnershipTokenType) -> &'static str {
+    match token_type {
+        OwnershipTokenType::SetupToken => "setup token",
+        OwnershipTokenType::DeviceSecret => "device secret",
+        OwnershipTokenType::AccessToken => "access token",
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// Parse `key=value,key=value` tag selectors.
+fn parse_labels(labels: &str) -> std::result::Result<HashMap<String, String>, String> {
+    labels
+        .split(',')
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+        .map(|label| match label.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => Err(format!("Invalid label '{}', expected key=value", label)),
+        })
+        .collect()
+}
+
+/// Signaling URL and access token for connecting as an app client, from the
+/// flags or the environment. A given token is cached per signaling URL in the
+/// credential store, so later commands and reconnects can omit it.
+fn signaling_login(
+    url: Option<String>,
+    token: Option<String>,
+) -> std::result::Result<(String, String), String> {
+    let signaling_url = url
+        .or_else(|| env_opt(EnvVar::SignalingServerUrl.as_str()))
+        .unwrap_or_else(|| "ws://localhost:8080/ws".to_string());
+    let store = credential_store();
+    let access_token = match token.or_else(|| env_opt(EnvVar::SignalingAccessToken.as_str())) {
+        Some(token) => {
+            if let Some(store) = &store {
+                if let Err(e) = store.set_access_token(&signaling_url, &token, None) {
+                    tracing::warn!("Failed to cache access token: {}", e);
+                }
+            }
+            token
+        }
+        None => store
+            .and_then(|store| store.access_token(&signaling_url).ok().flatten())
+            .ok_or("No access token. Pass --token or set SIGNALING_ACCESS_TOKEN.")?,
+    };
+    Ok((signaling_url, access_token))
+}
+
+/// Cocoon secret for a Docker cocoon: the given one is cached under the
+/// cocoon's name, otherwise the cached one is reused, so a re-created
+/// container keeps its identity.
+fn cached_cocoon_secret(name: &str, secret: Option<String>) -> Option<String> {
+    let Some(store) = credential_store() else {
+        return secret;
+    };
+    match secret {
+        Some(secret) => {
+            if let Err(e) = store.set_cocoon_secret(name, &secret) {
+                tracing::warn!("Failed to cache cocoon secret: {}", e);
+            }
+            Some(secret)
+        }
+        None => store.cocoon_secret(name).ok().flatten(),
+    }
+}
+
+/// The local credential store; a cache miss rather than an error when it
+/// can't be opened.
+fn credential_store() -> Option<CredentialStore> {
+    CredentialStore::open_default()
+        .map_err(|e| tracing::warn!("Credential store unavailable: {}", e))
+        .ok()
+}
+
+fn run_with_runtime<T, F>(fut: F) -> std::result::Result<T, String>
+where
+    T: Send + 'static,
+    F: std::future::Future<Output = std::result::Result<T, String>> + Send + 'static,
+{
     std::thread::spawn(move || {
         tokio::runtime::Runtime::new()
             .map_err(|e| format!("Failed to create runtime: {e}"))?
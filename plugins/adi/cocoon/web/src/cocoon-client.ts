@@ -3,6 +3,7 @@ import { Logger, trace, type EventBus } from '@adi-family/sdk-plugin';
 import type { SilkResponse } from './silk-types';
 import { SilkSession } from './silk-session';
 import { CocoonWebRTC, type WebRTCConfig } from './cocoon-webrtc';
+import { CocoonLan } from './cocoon-lan';
 import { CocoonConnection } from './cocoon-connection';
 import { CocoonBusKey } from './generated/bus-types';
 import './generated/bus-events';
@@ -26,12 +27,20 @@ export class CocoonClient {
   private readonly webrtc: CocoonWebRTC;
   private readonly sessions = new Map<string, SilkSession>();
   private readonly unsubs: (() => void)[] = [];
+  private disposed = false;
 
   constructor(cocoonId: string, server: SyncDataSender, bus: EventBus, rtcConfig?: WebRTCConfig, userId?: string) {
     this.cocoonId = cocoonId;
     this.bus = bus;
     this.server = server;
     this.webrtc = new CocoonWebRTC(cocoonId, server, bus, rtcConfig, userId);
+    // Connect in the background so the first session doesn't wait on ICE/DTLS
+    this.webrtc.prewarm();
+    // Switch to a direct socket if the cocoon is on this network and closer than the relay
+    void CocoonLan.probe(cocoonId, server, bus).then((lan) => {
+      if (lan && this.disposed) lan.close();
+      else if (lan) this.webrtc.useLan(lan);
+    });
 
     // Route silk responses from WebRTC data channel to session handlers
     this.unsubs.push(
@@ -103,6 +112,7 @@ export class CocoonClient {
   }
 
   dispose(): void {
+    this.disposed = true;
     for (const session of this.sessions.values()) session.dispose();
     this.sessions.clear();
     this.webrtc.dispose();
@@ -157,7 +167,9 @@ export class CocoonClient {
       case 'silk_pty_output':
       case 'silk_interactive_required':
       case 'silk_command_started':
-      case 'silk_command_completed': {
+      case 'silk_command_completed':
+      case 'silk_file_chunk':
+      case 'silk_file_uploaded': {
         const session = this.sessions.get(response.session_id);
         if (session) session._handleResponse(response);
         break;
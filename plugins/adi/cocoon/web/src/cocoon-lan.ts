@@ -0,0 +1,157 @@
+import type { EventBus } from '@adi-family/sdk-plugin';
+import type { SyncDataSender } from './cocoon-client';
+
+const SOURCE = 'cocoon-lan';
+const INFO_TIMEOUT_MS = 3_000;
+const DIAL_TIMEOUT_MS = 1_500;
+
+interface LanInfo {
+  addresses: string[];
+  port: number;
+  token: string;
+  relayRttMs: number;
+}
+
+/**
+ * Direct WebSocket to a cocoon on the same network. Speaks the relay's
+ * `sync_data` framing, so it can stand in for the signaling server when
+ * negotiating WebRTC sessions.
+ */
+export class CocoonLan implements SyncDataSender {
+  private readonly handlers: ((payload: unknown) => void)[] = [];
+  private readonly closeHandlers: (() => void)[] = [];
+
+  private constructor(
+    readonly url: string,
+    private readonly ws: WebSocket,
+    readonly rttMs: number,
+  ) {
+    ws.onmessage = (e) => {
+      const payload = parseSyncData(e.data);
+      if (payload !== undefined) for (const fn of this.handlers) fn(payload);
+    };
+    ws.onclose = () => {
+      for (const fn of this.closeHandlers) fn();
+    };
+  }
+
+  /**
+   * Ask the cocoon for its LAN endpoint through the relay and dial it. Resolves
+   * to a connection only if the direct path answers faster than the relay did.
+   */
+  static async probe(cocoonId: string, server: SyncDataSender, bus: EventBus): Promise<CocoonLan | null> {
+    const info = await requestInfo(cocoonId, server, bus);
+    if (!info || info.addresses.length === 0) return null;
+
+    for (const address of info.addresses) {
+      const host = address.includes(':') ? `[${address}]` : address;
+      const url = `ws://${host}:${info.port}`;
+      const lan = await dial(url, info.token).catch((err: unknown) => {
+        console.log(`[CocoonLan] ${url} unreachable:`, err);
+        return null;
+      });
+      if (!lan) continue;
+
+      if (lan.rttMs < info.relayRttMs) {
+        console.log(`[CocoonLan] using direct path ${url} (${lan.rttMs}ms vs relay ${info.relayRttMs}ms)`);
+        return lan;
+      }
+      console.log(`[CocoonLan] direct path ${url} not faster (${lan.rttMs}ms vs relay ${info.relayRttMs}ms)`);
+      lan.close();
+      return null;
+    }
+    return null;
+  }
+
+  sendSyncData(payload: unknown): void {
+    // Relay messages are addressed `{ to, data }`; the socket already is the cocoon
+    const data = (payload as { data?: unknown }).data ?? payload;
+    if (this.ws.readyState === WebSocket.OPEN) {
+      this.ws.send(JSON.stringify({ type: 'sync_data', payload: data }));
+    }
+  }
+
+  onSyncData(handler: (payload: unknown) => void): () => void {
+    this.handlers.push(handler);
+    return () => {
+      const i = this.handlers.indexOf(handler);
+      if (i >= 0) this.handlers.splice(i, 1);
+    };
+  }
+
+  onClose(handler: () => void): void {
+    this.closeHandlers.push(handler);
+  }
+
+  close(): void {
+    this.ws.close();
+  }
+}
+
+function parseSyncData(data: unknown): unknown {
+  if (typeof data !== 'string') return undefined;
+  try {
+    const frame = JSON.parse(data) as { type?: string; payload?: unknown };
+    return frame.type === 'sync_data' ? frame.payload : undefined;
+  } catch {
+    return undefined;
+  }
+}
+
+function requestInfo(cocoonId: string, server: SyncDataSender, bus: EventBus): Promise<LanInfo | null> {
+  const requestId = crypto.randomUUID();
+  const sentAt = performance.now();
+
+  return new Promise((resolve) => {
+    const timer = setTimeout(() => { unsub(); resolve(null); }, INFO_TIMEOUT_MS);
+    const unsub = bus.on(
+      'adi.signaling:sync-data',
+      ({ url, payload }: { url: string; payload: unknown }) => {
+        if (url !== server.url) return;
+        const msg = payload as Record<string, unknown> | null;
+        if (msg?.['type'] !== 'lan_info' || msg['request_id'] !== requestId) return;
+        clearTimeout(timer);
+        unsub();
+        resolve({
+          addresses: msg['addresses'] as string[],
+          port: msg['port'] as number,
+          token: msg['token'] as string,
+          relayRttMs: performance.now() - sentAt,
+        });
+      },
+      SOURCE,
+    );
+
+    server.sendSyncData({ to: cocoonId, data: { type: 'lan_info_request', request_id: requestId } });
+  });
+}
+
+/** Open the socket, authenticate with the LAN token and time one ping. */
+function dial(url: string, token: string): Promise<CocoonLan> {
+  return new Promise((resolve, reject) => {
+    const ws = new WebSocket(url);
+    const timer = setTimeout(() => { ws.close(); reject(new Error('timeout')); }, DIAL_TIMEOUT_MS);
+    const fail = (err: Error): void => { clearTimeout(timer); ws.close(); reject(err); };
+    let pingSentAt = 0;
+
+    ws.onerror = () => fail(new Error('connection failed'));
+    ws.onclose = () => fail(new Error('closed during handshake'));
+    ws.onopen = () => {
+      ws.send(JSON.stringify({ type: 'sync_data', payload: { type: 'lan_hello', token } }));
+    };
+    ws.onmessage = (e) => {
+      const msg = parseSyncData(e.data) as Record<string, unknown> | undefined;
+      switch (msg?.['type']) {
+        case 'lan_welcome':
+          pingSentAt = performance.now();
+          ws.send(JSON.stringify({ type: 'sync_data', payload: { type: 'lan_ping', sent_at: Date.now() } }));
+          break;
+        case 'lan_pong':
+          clearTimeout(timer);
+          ws.onerror = null;
+          resolve(new CocoonLan(url, ws, performance.now() - pingSentAt));
+          break;
+      }
+    };
+  });
+}
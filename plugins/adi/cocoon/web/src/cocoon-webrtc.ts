@@ -1,9 +1,17 @@
 import type { EventBus } from '@adi-family/sdk-plugin';
 import type { SyncDataSender } from './cocoon-client';
+import type { CocoonLan } from './cocoon-lan';
 
 const SOURCE = 'cocoon-webrtc';
 const CONNECT_TIMEOUT_MS = 30_000;
 
+const randomId = (): string => typeof crypto.randomUUID === 'function'
+  ? crypto.randomUUID()
+  : Array.from(crypto.getRandomValues(new Uint8Array(16)), b => b.toString(16).padStart(2, '0')).join('-');
+
+/** Identifies this browser tab to cocoons, which pool prewarmed sessions per client. */
+const CLIENT_ID = randomId();
+
 export interface WebRTCConfig {
   iceServers?: RTCIceServer[];
 }
@@ -13,9 +21,7 @@ export class CocoonWebRTC {
   private pc: RTCPeerConnection | null = null;
   private silkDc: RTCDataChannel | null = null;
   private adiDc: RTCDataChannel | null = null;
-  private readonly sessionId = typeof crypto.randomUUID === 'function'
-    ? crypto.randomUUID()
-    : Array.from(crypto.getRandomValues(new Uint8Array(16)), b => b.toString(16).padStart(2, '0')).join('-');
+  private sessionId = randomId();
   private readonly msgHandlers: ((msg: unknown) => void)[] = [];
   private readonly adiMsgHandlers: ((msg: unknown) => void)[] = [];
   private readonly adiBinaryHandlers: ((data: ArrayBuffer) => void)[] = [];
@@ -24,6 +30,10 @@ export class CocoonWebRTC {
   private signalUnsub: (() => void) | null = null;
   private answerResolve: (() => void) | null = null;
   private answerReject: ((err: Error) => void) | null = null;
+  private claimResolve: (() => void) | null = null;
+  private claimReject: ((err: Error) => void) | null = null;
+  private warm = false;
+  private lan: CocoonLan | null = null;
 
   constructor(
     private readonly cocoonId: string,
@@ -38,11 +48,69 @@ export class CocoonWebRTC {
   }
 
   connect(): Promise<void> {
+    if (this.warm) {
+      this.warm = false;
+      this.connectPromise = this.connectPromise!
+        .then(() => this.claim())
+        .catch((err: unknown) => {
+          console.warn(`[CocoonWebRTC] warm session ${this.sessionId} unusable, connecting fresh:`, err);
+          this.teardown();
+          this.sessionId = randomId();
+          return this.doConnect();
+        });
+      return this.connectPromise;
+    }
     if (this.connectPromise) return this.connectPromise;
     this.connectPromise = this.doConnect();
     return this.connectPromise;
   }
 
+  /**
+   * Negotiate a session before the user needs it. The cocoon keeps it in its
+   * warm pool; the next `connect()` claims it instead of paying for ICE/DTLS.
+   */
+  prewarm(): void {
+    if (this.connectPromise) return;
+    this.warm = true;
+    this.connectPromise = this.doConnect();
+    this.connectPromise.catch((err: unknown) => {
+      console.warn(`[CocoonWebRTC] prewarm failed session=${this.sessionId}`, err);
+    });
+  }
+
+  /**
+   * Negotiate over a direct LAN socket instead of the relay. An unclaimed warm
+   * session is renegotiated on the new path; a session in use keeps running
+   * and the LAN path applies from the next connect. Losing the socket ends its
+   * sessions on the cocoon, so the next connect goes back through the relay.
+   */
+  useLan(lan: CocoonLan): void {
+    this.lan = lan;
+    lan.onClose(() => {
+      if (this.lan !== lan) return;
+      console.warn(`[CocoonWebRTC] LAN path closed, falling back to relay`);
+      this.lan = null;
+      this.teardown();
+      this.warm = false;
+      this.connectPromise = null;
+    });
+
+    if (this.warm) {
+      this.server.sendSyncData({
+        to: this.cocoonId,
+        data: { type: 'webrtc_session_ended', session_id: this.sessionId, reason: 'lan_upgrade' },
+      });
+      this.teardown();
+      this.sessionId = randomId();
+      this.connectPromise = null;
+      this.prewarm();
+    }
+  }
+
+  private get signaling(): SyncDataSender {
+    return this.lan ?? this.server;
+  }
+
   send(msg: unknown): void {
     const state = this.silkDc?.readyState;
     if (state === 'open') {
@@ -101,22 +169,35 @@ export class CocoonWebRTC {
   }
 
   dispose(): void {
+    this.lan?.close();
+    this.lan = null;
+    this.teardown();
+    this.warm = false;
+    this.connectPromise = null;
+  }
+
+  private teardown(): void {
     this.signalUnsub?.();
     this.signalUnsub = null;
     this.answerReject?.(new Error('CocoonWebRTC disposed'));
     this.answerResolve = null;
     this.answerReject = null;
+    this.claimReject?.(new Error('CocoonWebRTC disposed'));
+    this.claimResolve = null;
+    this.claimReject = null;
     this.adiDc?.close();
     this.silkDc?.close();
     this.pc?.close();
     this.adiDc = null;
     this.silkDc = null;
     this.pc = null;
-    this.connectPromise = null;
+    this.pendingIceCandidates = [];
   }
 
   private async doConnect(): Promise<void> {
-    const iceServers = this.rtcConfig?.iceServers ?? [{ urls: 'stun:stun.l.google.com:19302' }];
+    // Host candidates are enough on a shared network; skip STUN gathering
+    const iceServers = this.lan ? [] : this.rtcConfig?.iceServers ?? [{ urls: 'stun:stun.l.google.com:19302' }];
+    const signaling = this.signaling;
     console.log(`[CocoonWebRTC] doConnect START session=${this.sessionId} cocoon=${this.cocoonId}`);
     console.log(`[CocoonWebRTC] ICE servers:`, iceServers);
     this.pc = new RTCPeerConnection({ iceServers });
@@ -209,42 +290,49 @@ export class CocoonWebRTC {
         },
       };
       if (sessionStartSent) {
-        this.server.sendSyncData(msg);
+        signaling.sendSyncData(msg);
       } else {
         localCandidateQueue.push(msg);
       }
     };
 
     // Subscribe to signaling messages for WebRTC answer + ICE from cocoon
-    this.signalUnsub = this.bus.on(
-      'adi.signaling:sync-data',
-      ({ url, payload }: { url: string; payload: unknown }) => {
-        if (url !== this.server.url) return;
-        void this.handleSignalingMsg(payload);
-      },
-      SOURCE,
-    );
+    this.signalUnsub = this.lan
+      ? this.lan.onSyncData((payload) => void this.handleSignalingMsg(payload))
+      : this.bus.on(
+        'adi.signaling:sync-data',
+        ({ url, payload }: { url: string; payload: unknown }) => {
+          if (url !== this.server.url) return;
+          void this.handleSignalingMsg(payload);
+        },
+        SOURCE,
+      );
 
     const offer = await this.pc.createOffer();
     await this.pc.setLocalDescription(offer);
     console.log(`[CocoonWebRTC] offer created & local description set, sdpLen=${offer.sdp?.length}`);
 
     // Notify cocoon to prepare WebRTC session
-    console.log(`[CocoonWebRTC] sending webrtc_start_session to ${this.cocoonId}`);
-    this.server.sendSyncData({
-      to: this.cocoonId,
-      data: {
+    const start = this.warm
+      ? {
+        type: 'webrtc_prewarm',
+        session_id: this.sessionId,
+        client_id: CLIENT_ID,
+        user_id: this.userId,
+      }
+      : {
         type: 'webrtc_start_session',
         session_id: this.sessionId,
         device_id: this.cocoonId,
         user_id: this.userId,
         data_channels: ['silk', 'adi'],
-      },
-    });
+      };
+    console.log(`[CocoonWebRTC] sending ${start.type} to ${this.cocoonId} via ${this.lan ? 'LAN' : 'relay'}`);
+    signaling.sendSyncData({ to: this.cocoonId, data: start });
 
     // Send the offer
     console.log(`[CocoonWebRTC] sending webrtc_offer to ${this.cocoonId}`);
-    this.server.sendSyncData({
+    signaling.sendSyncData({
       to: this.cocoonId,
       data: { type: 'webrtc_offer', session_id: this.sessionId, sdp: offer.sdp },
     });
@@ -252,7 +340,7 @@ export class CocoonWebRTC {
     // Flush candidates buffered during offer creation; future ones go directly
     sessionStartSent = true;
     console.log(`[CocoonWebRTC] flushing ${localCandidateQueue.length} buffered ICE candidates`);
-    for (const msg of localCandidateQueue) this.server.sendSyncData(msg);
+    for (const msg of localCandidateQueue) signaling.sendSyncData(msg);
     localCandidateQueue.length = 0;
 
     console.log(`[CocoonWebRTC] waiting for answer + DC open...`);
@@ -260,6 +348,26 @@ export class CocoonWebRTC {
     console.log(`[CocoonWebRTC] doConnect COMPLETE — answer received & DC open!`);
   }
 
+  /** Take the prewarmed session out of the cocoon's warm pool. */
+  private claim(): Promise<void> {
+    return new Promise<void>((resolve, reject) => {
+      const timer = setTimeout(() => {
+        this.claimResolve = null;
+        this.claimReject = null;
+        reject(new Error('WebRTC claim timeout'));
+      }, CONNECT_TIMEOUT_MS);
+
+      this.claimResolve = () => { clearTimeout(timer); resolve(); };
+      this.claimReject = (err) => { clearTimeout(timer); reject(err); };
+
+      console.log(`[CocoonWebRTC] claiming warm session ${this.sessionId}`);
+      this.signaling.sendSyncData({
+        to: this.cocoonId,
+        data: { type: 'webrtc_claim', session_id: this.sessionId },
+      });
+    });
+  }
+
   private waitForAnswer(): Promise<void> {
     return new Promise<void>((resolve, reject) => {
       const timer = setTimeout(() => {
@@ -324,19 +432,34 @@ export class CocoonWebRTC {
         }
         break;
       }
+      case 'webrtc_claimed': {
+        console.log(`[CocoonWebRTC] warm session claimed session=${this.sessionId}`);
+        this.claimResolve?.();
+        this.claimResolve = null;
+        this.claimReject = null;
+        break;
+      }
       case 'webrtc_session_ended': {
         const reason = msg['reason'] as string | undefined;
         console.error(`[CocoonWebRTC] session ENDED by cocoon! reason=${reason} session=${this.sessionId}`);
-        this.answerReject?.(new Error(`WebRTC session ended: ${reason ?? 'unknown'}`));
+        const err = new Error(`WebRTC session ended: ${reason ?? 'unknown'}`);
+        this.answerReject?.(err);
         this.answerResolve = null;
         this.answerReject = null;
+        this.claimReject?.(err);
+        this.claimResolve = null;
+        this.claimReject = null;
         break;
       }
       case 'webrtc_error': {
         console.error(`[CocoonWebRTC] error from cocoon: ${msg['message']}`);
-        this.answerReject?.(new Error(msg['message'] as string));
+        const err = new Error(msg['message'] as string);
+        this.answerReject?.(err);
         this.answerResolve = null;
         this.answerReject = null;
+        this.claimReject?.(err);
+        this.claimResolve = null;
+        this.claimReject = null;
         break;
       }
     }
@@ -14,12 +14,16 @@ export type SignalingMessage =
   | { type: 'silk_resize'; session_id: string; command_id: string; cols: number; rows: number }
   | { type: 'silk_signal'; session_id: string; command_id: string; signal: SilkSignal }
   | { type: 'silk_close_session'; session_id: string }
+  | { type: 'silk_upload_file'; session_id: string; path: string; chunk: string; seq: number; done: boolean; sha256?: string }
+  | { type: 'silk_download_file'; session_id: string; path: string }
   | { type: 'silk_command_started'; session_id: string; command_id: string; interactive: boolean }
   | { type: 'silk_output'; session_id: string; command_id: string; stream: SilkStream; data: string; html?: SilkHtmlSpan[] }
   | { type: 'silk_interactive_required'; session_id: string; command_id: string; reason: string; pty_session_id: string }
   | { type: 'silk_pty_output'; session_id: string; command_id: string; pty_session_id: string; data: string }
   | { type: 'silk_command_completed'; session_id: string; command_id: string; exit_code: number; cwd: string }
   | { type: 'silk_session_closed'; session_id: string }
+  | { type: 'silk_file_chunk'; session_id: string; path: string; chunk: string; seq: number; done: boolean; sha256?: string }
+  | { type: 'silk_file_uploaded'; session_id: string; path: string; size: number; sha256: string }
   | { type: 'silk_error'; session_id?: string; command_id?: string; code: string; message: string }
 
   // ── adi ──
@@ -32,6 +36,7 @@ export type SignalingMessage =
   | { type: 'adi_unsubscribed'; subscription_id: string }
   | { type: 'adi_subscription_event'; subscription_id: string; event: string; data: unknown }
   | { type: 'adi_subscription_error'; request_id: string; code: string; message: string }
+  | { type: 'adi_cancel_stream'; request_id: string }
 
   // ── plugin ──
   | { type: 'plugin_install_plugin'; request_id: string; plugin_id: string; registry?: string; version?: string }
@@ -39,7 +44,10 @@ export type SignalingMessage =
   | { type: 'plugin_install_error'; request_id: string; plugin_id: string; code: string; message: string }
 
   // ── webrtc ──
-  | { type: 'webrtc_start_session'; session_id: string; device_id: string; user_id?: string; data_channels?: string[] }
+  | { type: 'webrtc_start_session'; session_id: string; device_id: string; user_id?: string; data_channels?: string[]; delegated_token?: string }
+  | { type: 'webrtc_prewarm'; session_id: string; client_id: string; user_id?: string }
+  | { type: 'webrtc_claim'; session_id: string }
+  | { type: 'webrtc_claimed'; session_id: string }
   | { type: 'webrtc_offer'; session_id: string; sdp: string }
   | { type: 'webrtc_answer'; session_id: string; sdp: string }
   | { type: 'webrtc_ice_candidate'; session_id: string; candidate: string; sdp_mid?: string; sdp_mline_index?: number }
@@ -47,6 +55,20 @@ export type SignalingMessage =
   | { type: 'webrtc_data'; session_id: string; channel: string; data: string; binary: boolean }
   | { type: 'webrtc_error'; session_id: string; code: string; message: string }
 
+  // ── forward ──
+  | { type: 'forward_open'; stream_id: string; host: string; port: number }
+  | { type: 'forward_opened'; stream_id: string }
+  | { type: 'forward_data'; stream_id: string; data: string }
+  | { type: 'forward_close'; stream_id: string; error?: string }
+
+  // ── lan ──
+  | { type: 'lan_info_request'; request_id: string }
+  | { type: 'lan_info'; request_id: string; addresses: string[]; port: number; token: string }
+  | { type: 'lan_hello'; token: string }
+  | { type: 'lan_welcome'; device_id: string }
+  | { type: 'lan_ping'; sent_at: number }
+  | { type: 'lan_pong'; sent_at: number }
+
   // ── query ──
   | { type: 'query_query_local'; query_id: string; query_type: QueryType; params: unknown }
   | { type: 'query_query_result'; query_id: string; data: unknown; is_final: boolean };
@@ -41,6 +41,7 @@ export interface AdiMethodInfo {
   result_schema?: unknown;
   deprecated?: boolean;
   deprecated_message?: string;
+  skip_params_validation?: boolean;
 }
 
 export interface AdiPluginInfo {
@@ -5,8 +5,41 @@ import { SilkCommand } from './silk-command';
 let commandCounter = 0;
 const nextCommandId = (): string => `cmd-${Date.now()}-${++commandCounter}`;
 
+/** Raw bytes per upload chunk; matches `FILE_CHUNK_BYTES` in lib-tarminal-sync. */
+const FILE_CHUNK_BYTES = 48 * 1024;
+
 type Listener<T> = (event: T) => void;
 
+export interface SilkUploadResult {
+  path: string;
+  size: number;
+  sha256: string;
+}
+
+interface PendingUpload {
+  resolve: (result: SilkUploadResult) => void;
+  reject: (error: Error) => void;
+}
+
+interface PendingDownload {
+  chunks: Map<number, Uint8Array>;
+  resolve: (data: Uint8Array) => void;
+  reject: (error: Error) => void;
+}
+
+const toBase64 = (bytes: Uint8Array): string => {
+  let binary = '';
+  for (let i = 0; i < bytes.length; i++) binary += String.fromCharCode(bytes[i]);
+  return btoa(binary);
+};
+
+const fromBase64 = (chunk: string): Uint8Array => Uint8Array.from(atob(chunk), (c) => c.charCodeAt(0));
+
+const sha256Hex = async (data: Uint8Array): Promise<string> => {
+  const digest = await crypto.subtle.digest('SHA-256', data);
+  return Array.from(new Uint8Array(digest), (b) => b.toString(16).padStart(2, '0')).join('');
+};
+
 export class SilkSession {
   readonly sessionId: string;
   readonly cocoonId: string;
@@ -16,6 +49,8 @@ export class SilkSession {
   private readonly server: SyncDataSender;
   private readonly commands = new Map<string, SilkCommand>();
   private readonly closedListeners: Listener<void>[] = [];
+  private readonly uploads = new Map<string, PendingUpload>();
+  private readonly downloads = new Map<string, PendingDownload>();
   private _closed = false;
 
   constructor(
@@ -52,6 +87,37 @@ export class SilkSession {
     return cmd;
   }
 
+  /** Upload a file (e.g. one dropped on the terminal); `path` is relative to the session cwd. */
+  async upload(path: string, data: Uint8Array): Promise<SilkUploadResult> {
+    if (this.uploads.has(path)) throw new Error(`Upload of ${path} already in progress`);
+    const sha256 = await sha256Hex(data);
+    const done = new Promise<SilkUploadResult>((resolve, reject) => this.uploads.set(path, { resolve, reject }));
+    const count = Math.max(1, Math.ceil(data.length / FILE_CHUNK_BYTES));
+    for (let seq = 0; seq < count; seq++) {
+      const last = seq === count - 1;
+      this.sendSilk({
+        type: 'silk_upload_file',
+        session_id: this.sessionId,
+        path,
+        chunk: toBase64(data.subarray(seq * FILE_CHUNK_BYTES, (seq + 1) * FILE_CHUNK_BYTES)),
+        seq,
+        done: last,
+        sha256: last ? sha256 : undefined,
+      });
+    }
+    return done;
+  }
+
+  /** Download a file; resolves once every chunk arrived and the SHA-256 matches. */
+  download(path: string): Promise<Uint8Array> {
+    if (this.downloads.has(path)) return Promise.reject(new Error(`Download of ${path} already in progress`));
+    const done = new Promise<Uint8Array>((resolve, reject) =>
+      this.downloads.set(path, { chunks: new Map(), resolve, reject }),
+    );
+    this.sendSilk({ type: 'silk_download_file', session_id: this.sessionId, path });
+    return done;
+  }
+
   close(): void {
     if (this._closed) return;
     this.sendSilk({
@@ -92,10 +158,27 @@ export class SilkSession {
         }
         break;
       }
+      case 'silk_file_uploaded': {
+        const upload = this.uploads.get(response.path);
+        this.uploads.delete(response.path);
+        upload?.resolve({ path: response.path, size: response.size, sha256: response.sha256 });
+        break;
+      }
+      case 'silk_file_chunk':
+        void this.handleFileChunk(response);
+        break;
       case 'silk_error': {
         if (response.command_id) {
           const cmd = this.commands.get(response.command_id);
           if (cmd) cmd._emitError(response.code, response.message);
+        } else if (response.code === 'upload_failed' || response.code === 'download_failed') {
+          // The cocoon prefixes transfer errors with the path
+          const transfers = response.code === 'upload_failed' ? this.uploads : this.downloads;
+          for (const [path, transfer] of transfers) {
+            if (!response.message.startsWith(`${path}: `)) continue;
+            transfers.delete(path);
+            transfer.reject(new Error(response.message));
+          }
         }
         break;
       }
@@ -103,6 +186,7 @@ export class SilkSession {
         this._closed = true;
         for (const cmd of this.commands.values()) cmd.dispose();
         this.commands.clear();
+        this.rejectTransfers('Silk session closed');
         for (const fn of this.closedListeners) fn();
         break;
     }
@@ -111,9 +195,43 @@ export class SilkSession {
   dispose(): void {
     for (const cmd of this.commands.values()) cmd.dispose();
     this.commands.clear();
+    this.rejectTransfers('Silk session disposed');
     this.closedListeners.length = 0;
   }
 
+  private async handleFileChunk(response: Extract<SilkResponse, { type: 'silk_file_chunk' }>): Promise<void> {
+    const download = this.downloads.get(response.path);
+    if (!download) return;
+    download.chunks.set(response.seq, fromBase64(response.chunk));
+    if (!response.done) return;
+
+    // Chunks of one file go out in order on one channel, so the last one comes after the rest
+    this.downloads.delete(response.path);
+    const parts = Array.from({ length: response.seq + 1 }, (_, seq) => download.chunks.get(seq));
+    if (parts.some((part) => !part)) {
+      download.reject(new Error(`${response.path}: missing chunks`));
+      return;
+    }
+    const data = new Uint8Array(parts.reduce((n, part) => n + part!.length, 0));
+    let offset = 0;
+    for (const part of parts) {
+      data.set(part!, offset);
+      offset += part!.length;
+    }
+    const sha256 = await sha256Hex(data);
+    if (sha256 !== response.sha256) {
+      download.reject(new Error(`${response.path}: sha256 mismatch`));
+      return;
+    }
+    download.resolve(data);
+  }
+
+  private rejectTransfers(reason: string): void {
+    for (const transfer of [...this.uploads.values(), ...this.downloads.values()]) transfer.reject(new Error(reason));
+    this.uploads.clear();
+    this.downloads.clear();
+  }
+
   private sendSilk(request: SilkRequest): void {
     this.server.sendSyncData(request);
   }
@@ -11,7 +11,9 @@ export type SilkRequest =
   | ExtractSilk<SignalingMessage, 'input'>
   | ExtractSilk<SignalingMessage, 'resize'>
   | ExtractSilk<SignalingMessage, 'signal'>
-  | ExtractSilk<SignalingMessage, 'close_session'>;
+  | ExtractSilk<SignalingMessage, 'close_session'>
+  | ExtractSilk<SignalingMessage, 'upload_file'>
+  | ExtractSilk<SignalingMessage, 'download_file'>;
 
 export type SilkResponse =
   | ExtractSilk<SignalingMessage, 'create_session_response'>
@@ -21,4 +23,6 @@ export type SilkResponse =
   | ExtractSilk<SignalingMessage, 'pty_output'>
   | ExtractSilk<SignalingMessage, 'command_completed'>
   | ExtractSilk<SignalingMessage, 'session_closed'>
+  | ExtractSilk<SignalingMessage, 'file_chunk'>
+  | ExtractSilk<SignalingMessage, 'file_uploaded'>
   | ExtractSilk<SignalingMessage, 'error'>;
@@ -1,14 +1,17 @@
 use dashmap::DashMap;
 use serde_json::Value as JsonValue;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     sync::{
         Arc,
         atomic::{AtomicU64, Ordering},
     },
+    time::Instant,
 };
 use tokio::sync::mpsc;
 
+use crate::utils::generate_token;
+
 /// Per-device metadata stored by the signaling server.
 #[derive(Clone, Debug)]
 pub struct DeviceMeta {
@@ -17,6 +20,119 @@ pub struct DeviceMeta {
     pub device_config: Option<JsonValue>,
 }
 
+/// Audit entries kept per device; the oldest are dropped first.
+pub const OWNERSHIP_HISTORY_LIMIT: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OwnershipChange {
+    Claimed,
+    Transferred,
+    Removed,
+}
+
+/// Credential that authorized an ownership change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OwnershipVia {
+    SetupToken,
+    DeviceSecret,
+    /// The owner handed the device over from an app connection
+    AccessToken,
+}
+
+/// One entry of a device's ownership audit log.
+#[derive(Clone, Debug)]
+pub struct OwnershipRecord {
+    pub change: OwnershipChange,
+    /// Owner gained or lost
+    pub user_id: String,
+    /// User whose token made the change; `None` when the device acted alone
+    pub actor: Option<String>,
+    pub via: OwnershipVia,
+    /// Unix seconds
+    pub at: u64,
+}
+
+/// Role of a user sharing a device with its owner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemberRole {
+    Operator,
+    Viewer,
+}
+
+/// A user other than the owner who claimed a device or was handed a role.
+#[derive(Clone, Debug)]
+pub struct DeviceMember {
+    pub role: MemberRole,
+    /// Unix seconds
+    pub claimed_at: u64,
+}
+
+/// Latest capability set a device announced.
+#[derive(Clone, Debug, Default)]
+pub struct DeviceCapabilities {
+    /// Increases with every change the device sends
+    pub version: u64,
+    /// protocol → version offered
+    pub capabilities: BTreeMap<String, String>,
+}
+
+/// A capability request routed to `target`, until it answers.
+#[derive(Clone, Debug)]
+pub struct CapabilityRoute {
+    pub requester: String,
+    pub target: String,
+}
+
+/// Longest lifetime of a delegated token.
+pub const MAX_DELEGATION_TTL_SECS: u64 = 30 * 24 * 3600;
+
+/// Access to one device handed to a non-owner by a delegated token.
+#[derive(Clone, Debug)]
+pub struct DelegatedToken {
+    pub token_id: String,
+    pub device_id: String,
+    /// Owner who issued the token
+    pub issued_by: String,
+    /// `<service>` or `<service>:ro`
+    pub scopes: Vec<String>,
+    /// Unix seconds
+    pub expires_at: u64,
+}
+
+/// `sync_data` held per offline device; the oldest are dropped first.
+pub const SYNC_QUEUE_LIMIT: usize = 256;
+
+/// How long a held `sync_data` waits for its target to come back.
+pub const SYNC_QUEUE_TTL_SECS: u64 = 3600;
+
+/// Where the delivery receipt of a held `sync_data` goes.
+#[derive(Clone, Debug)]
+pub enum SyncSender {
+    /// A member of the target (its owner or anyone sharing it), at every app
+    /// connection they have then; dropped on replay if they are no longer one
+    Member(String),
+    /// App that presented a delegated token, dropped on replay once the token
+    /// is revoked or expired; the receipt goes to `user_id`'s connections, or
+    /// to `connection` (and is lost once it closes) for anonymous holders
+    Delegated {
+        token: String,
+        user_id: Option<String>,
+        connection: mpsc::UnboundedSender<String>,
+    },
+    Device(String),
+}
+
+/// A `sync_data` relayed while its target device was offline.
+#[derive(Clone, Debug)]
+pub struct QueuedSync {
+    pub message_id: String,
+    /// The serialized `sync_data`, exactly as the target would have received it
+    pub json: String,
+    pub sender: SyncSender,
+    /// Unix seconds
+    pub expires_at: u64,
+}
+
 /// A multi-party room where actors (devices) communicate and users collaborate.
 #[derive(Clone, Debug)]
 pub struct Room {
@@ -34,6 +150,13 @@ pub struct AppState {
     pub device_meta: Arc<DashMap<String, DeviceMeta>>,
     /// device_id → owner user_id (from setup_token)
     pub device_owners: Arc<DashMap<String, String>>,
+    /// device_id → user_id → member, for everyone but the owner
+    pub device_members: Arc<DashMap<String, HashMap<String, DeviceMember>>>,
+    /// device_id → ownership audit log, oldest first
+    pub ownership_history: Arc<DashMap<String, VecDeque<OwnershipRecord>>>,
+    /// delegated token → grant; expired grants are dropped when looked up and
+    /// pruned periodically
+    pub delegated_tokens: Arc<DashMap<String, DelegatedToken>>,
     /// user_id → (connection_id → sender) for authenticated app clients
     pub user_connections: Arc<DashMap<String, HashMap<u64, mpsc::UnboundedSender<String>>>>,
     connection_counter: Arc<AtomicU64>,
@@ -47,6 +170,18 @@ pub struct AppState {
     pub device_rooms: Arc<DashMap<String, HashSet<String>>>,
     /// hive_id → registered hive info (for cocoon spawning)
     pub hives: Arc<DashMap<String, RegisteredHive>>,
+    /// spawn request_id → cocoon being drained off a hive in maintenance
+    pub hive_drains: Arc<DashMap<String, PendingDrain>>,
+    /// singleton service (`source:service`) → hive elected to run it
+    pub singleton_leases: Arc<DashMap<String, SingletonLease>>,
+    /// device_id → `sync_data` held while it is offline, oldest first
+    pub sync_queue: Arc<DashMap<String, VecDeque<QueuedSync>>>,
+    /// Users allowed to run the hive fleet, e.g. size its warm pools
+    pub hive_operators: Arc<HashSet<String>>,
+    /// device_id → capabilities it announced while connected
+    pub device_capabilities: Arc<DashMap<String, DeviceCapabilities>>,
+    /// request_id → capability request awaiting its response
+    pub capability_routes: Arc<DashMap<String, CapabilityRoute>>,
 }
 
 impl AppState {
@@ -62,6 +197,9 @@ impl AppState {
             paired_devices: Arc::new(DashMap::new()),
             device_meta: Arc::new(DashMap::new()),
             device_owners: Arc::new(DashMap::new()),
+            device_members: Arc::new(DashMap::new()),
+            ownership_history: Arc::new(DashMap::new()),
+            delegated_tokens: Arc::new(DashMap::new()),
             user_connections: Arc::new(DashMap::new()),
             connection_counter: Arc::new(AtomicU64::new(0)),
             hmac_salt,
@@ -71,9 +209,20 @@ impl AppState {
             rooms: Arc::new(DashMap::new()),
             device_rooms: Arc::new(DashMap::new()),
             hives: Arc::new(DashMap::new()),
+            hive_drains: Arc::new(DashMap::new()),
+            singleton_leases: Arc::new(DashMap::new()),
+            sync_queue: Arc::new(DashMap::new()),
+            hive_operators: Arc::new(HashSet::new()),
+            device_capabilities: Arc::new(DashMap::new()),
+            capability_routes: Arc::new(DashMap::new()),
         }
     }
 
+    pub fn with_hive_operators(mut self, operators: impl IntoIterator<Item = String>) -> Self {
+        self.hive_operators = Arc::new(operators.into_iter().collect());
+        self
+    }
+
     pub fn next_connection_id(&self) -> u64 {
         self.connection_counter.fetch_add(1, Ordering::Relaxed)
     }
@@ -159,6 +308,102 @@ impl AppState {
         }
     }
 
+    /// Append to a device's ownership audit log.
+    pub fn record_ownership(&self, device_id: &str, record: OwnershipRecord) {
+        let mut log = self
+            .ownership_history
+            .entry(device_id.to_string())
+            .or_default();
+        if log.len() == OWNERSHIP_HISTORY_LIMIT {
+            log.pop_front();
+        }
+        log.push_back(record);
+    }
+
+    /// A device's ownership audit log, oldest first.
+    pub fn ownership_history(&self, device_id: &str) -> Vec<OwnershipRecord> {
+        self.ownership_history
+            .get(device_id)
+            .map(|log| log.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether a user owns or has ever owned a device.
+    pub fn is_current_or_past_owner(&self, device_id: &str, user_id: &str) -> bool {
+        self.device_owners
+            .get(device_id)
+            .is_some_and(|owner| owner.value() == user_id)
+            || self
+                .ownership_history
+                .get(device_id)
+                .is_some_and(|log| log.iter().any(|record| record.user_id == user_id))
+    }
+
+    /// Store a delegated grant under a new random token and return the token.
+    pub fn issue_delegated_token(&self, grant: DelegatedToken) -> String {
+        let token = generate_token();
+        self.delegated_tokens.insert(token.clone(), grant);
+        token
+    }
+
+    /// The grant behind `token`, unless it expired by `now` (unix seconds).
+    pub fn delegated_grant(&self, token: &str, now: u64) -> Option<DelegatedToken> {
+        let grant = self.delegated_tokens.get(token)?.value().clone();
+        if grant.expires_at <= now {
+            self.delegated_tokens.remove(token);
+            return None;
+        }
+        Some(grant)
+    }
+
+    /// Drop the token with `token_id`; returns its grant if there was one.
+    pub fn revoke_delegated_token(&self, token_id: &str) -> Option<DelegatedToken> {
+        let token = self
+            .delegated_tokens
+            .iter()
+            .find(|entry| entry.value().token_id == token_id)
+            .map(|entry| entry.key().clone())?;
+        self.delegated_tokens.remove(&token).map(|(_, grant)| grant)
+    }
+
+    /// Drop every token that expired by `now`; returns how many.
+    pub fn prune_delegated_tokens(&self, now: u64) -> usize {
+        let before = self.delegated_tokens.len();
+        self.delegated_tokens.retain(|_, grant| grant.expires_at > now);
+        before - self.delegated_tokens.len()
+    }
+
+    /// Hold a `sync_data` for an offline device until `now + SYNC_QUEUE_TTL_SECS`.
+    /// Returns the message id and expiry to acknowledge to the sender.
+    pub fn queue_sync(&self, device_id: &str, json: String, sender: SyncSender, now: u64) -> (String, u64) {
+        let message_id = generate_token();
+        let expires_at = now + SYNC_QUEUE_TTL_SECS;
+        let mut queue = self.sync_queue.entry(device_id.to_string()).or_default();
+        queue.retain(|queued| queued.expires_at > now);
+        if queue.len() == SYNC_QUEUE_LIMIT {
+            queue.pop_front();
+        }
+        queue.push_back(QueuedSync {
+            message_id: message_id.clone(),
+            json,
+            sender,
+            expires_at,
+        });
+        (message_id, expires_at)
+    }
+
+    /// Remove everything held for a device: the messages still valid at `now`,
+    /// oldest first, and how many had expired.
+    pub fn take_queued_sync(&self, device_id: &str, now: u64) -> (Vec<QueuedSync>, usize) {
+        let Some((_, queue)) = self.sync_queue.remove(device_id) else {
+            return (Vec::new(), 0);
+        };
+        let total = queue.len();
+        let live: Vec<QueuedSync> = queue.into_iter().filter(|queued| queued.expires_at > now).collect();
+        let expired = total - live.len();
+        (live, expired)
+    }
+
     /// Collect all devices owned by a given user.
     pub fn get_user_devices(&self, user_id: &str) -> Vec<UserDevice> {
         self.device_owners
@@ -191,5 +436,39 @@ pub struct UserDevice {
 pub struct RegisteredHive {
     pub hive_id: String,
     pub connection_id: String,
+    /// Kinds the hive can actually run (advertised kinds with an available runner).
     pub cocoon_kinds: Vec<String>,
+    /// Runner types reported by the hive; `None` for hives that predate reporting.
+    pub runners: Option<Vec<String>>,
+    /// Free VRAM of each GPU in MiB, refreshed by hive heartbeats; empty without GPUs.
+    pub gpu_vram_free_mb: Vec<u64>,
+    /// Hive refuses new cocoons while in maintenance.
+    pub maintenance: bool,
+    pub maintenance_reason: Option<String>,
+    /// Singleton services (`source:service`) the hive can lead.
+    pub singletons: Vec<String>,
+    /// Warm cocoons ready to claim per kind, from the hive's pool status.
+    pub warm_cocoons: HashMap<String, u32>,
+    /// Registration or latest heartbeat; silent hives lose their singletons.
+    pub last_heartbeat: Instant,
+}
+
+/// Hive running a singleton service. Leases are kept after their hives
+/// leave so fencing tokens never repeat.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SingletonLease {
+    /// `None` while no live hive lists the service.
+    pub leader_hive_id: Option<String>,
+    /// Bumped on every leader change.
+    pub fencing_token: u64,
+}
+
+/// A cocoon moved off a hive in maintenance, waiting for the target's spawn result.
+#[derive(Clone, Debug)]
+pub struct PendingDrain {
+    /// Connection of the draining hive, told about the outcome.
+    pub source_connection_id: String,
+    /// Container on the draining hive.
+    pub container_id: String,
+    pub target_hive_id: String,
 }
@@ -10,3 +10,9 @@ pub fn generate_pairing_code() -> String {
         })
         .collect()
 }
+
+/// Unguessable bearer token, 32 random bytes as hex.
+pub fn generate_token() -> String {
+    let bytes: [u8; 32] = rand::rng().random();
+    hex::encode(bytes)
+}
@@ -3,6 +3,7 @@ use lib_env_parse::{env_bool, env_opt, env_vars};
 use lib_signaling_protocol::IceServer;
 use signaling_core::state::AppState;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tracing::info;
 
 use crate::ws;
@@ -14,8 +15,12 @@ env_vars! {
     WebrtcIceServers => "WEBRTC_ICE_SERVERS",
     WebrtcTurnUsername => "WEBRTC_TURN_USERNAME",
     WebrtcTurnCredential => "WEBRTC_TURN_CREDENTIAL",
+    HiveOperators => "HIVE_OPERATORS",
 }
 
+/// How long clients are told to wait before reconnecting after a shutdown
+const SHUTDOWN_RETRY_AFTER: Duration = Duration::from_secs(5);
+
 pub fn run_server(port: u16) -> anyhow::Result<()> {
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
@@ -47,24 +52,60 @@ pub fn run_server(port: u16) -> anyhow::Result<()> {
             .filter_map(|s| serde_json::to_value(s).ok())
             .collect();
 
-        let state = AppState::new(hmac_salt, auth_domain, allow_manual, ice_servers_json);
+        // Comma-separated user ids
+        let hive_operators: Vec<String> = env_opt(EnvVar::HiveOperators.as_str())
+            .map(|s| s.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect())
+            .unwrap_or_default();
+        info!("Hive operators: {}", hive_operators.len());
+
+        let state = AppState::new(hmac_salt, auth_domain, allow_manual, ice_servers_json)
+            .with_hive_operators(hive_operators);
+        tokio::spawn(ws::watch_singleton_leases(state.clone()));
+        tokio::spawn(ws::prune_delegated_tokens(state.clone()));
 
         let app = Router::new()
             .route("/ws", get(ws::ws_handler))
             .route("/health", get(|| async { StatusCode::OK }))
-            .with_state(state);
+            .with_state(state.clone());
 
         let addr = SocketAddr::from(([0, 0, 0, 0], port));
         info!("Signaling server listening on {}", addr);
         println!("Signaling server listening on http://{}", addr);
 
         let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
+        tokio::select! {
+            result = axum::serve(listener, app) => result?,
+            _ = shutdown_signal() => {
+                info!("Shutting down, clients may reconnect in {}s", SHUTDOWN_RETRY_AFTER.as_secs());
+                ws::announce_shutdown(&state, SHUTDOWN_RETRY_AFTER);
+                // Give the per-socket send tasks a moment to flush
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+        }
 
         Ok(())
     })
 }
 
+async fn shutdown_signal() {
+    let ctrl_c = tokio::signal::ctrl_c();
+    #[cfg(unix)]
+    {
+        let Ok(mut term) =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        else {
+            let _ = ctrl_c.await;
+            return;
+        };
+        tokio::select! {
+            _ = ctrl_c => {}
+            _ = term.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = ctrl_c.await;
+}
+
 fn parse_ice_servers() -> Vec<IceServer> {
     let urls_str = match env_opt(EnvVar::WebrtcIceServers.as_str()) {
         Some(s) if !s.is_empty() => s,
@@ -6,23 +6,45 @@ use axum::{
     response::IntoResponse,
 };
 use futures::{
-    SinkExt, StreamExt,
-    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt, future,
+    stream::{self, BoxStream, SplitSink, SplitStream},
 };
 use lib_signaling_protocol::{
-    AuthOption, AuthRequirement, ConnectionInfo, DeviceInfo, IceServer, RoomInfo, SignalingMessage,
+    authorize_connect, authorize_member_change, authorize_operate, claim_role, AuthOption,
+    AuthRequirement, Capability, CapabilityDelta, CapabilityMatcher, CapabilitySet, CocoonKind, CocoonMember, CocoonRole, ConnectionInfo, DelegatedGrant,
+    DeviceInfo, DisconnectInfo, DisconnectReason, IceServer, OwnershipAction,
+    OwnershipAuditEvent, OwnershipTokenType, RoomInfo, SignalingEnvelope, SignalingMessage,
+    VerifiedSender, MAX_POOL_SIZE,
 };
 use serde::Deserialize;
 use signaling_core::{
     security::{derive_device_id, validate_secret},
-    state::{AppState, DeviceMeta, RegisteredHive, Room, UserDevice},
+    state::{
+        AppState, CapabilityRoute, DelegatedToken, DeviceCapabilities, DeviceMember, DeviceMeta, MemberRole, OwnershipChange,
+        OwnershipRecord, OwnershipVia, PendingDrain, RegisteredHive, Room, SingletonLease,
+        SyncSender, UserDevice, MAX_DELEGATION_TTL_SECS,
+    },
     tokens::extract_user_id,
     utils::generate_pairing_code,
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 
+/// Sub-relays a relay link may pass through, counting the one that opened it.
+pub const MAX_RELAY_HOPS: usize = 4;
+
+/// Heartbeat gap after which a hive loses the singletons it leads (three
+/// missed hive heartbeats).
+pub const HIVE_SILENCE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// How often leaders of singleton services are checked for silence.
+pub const SINGLETON_SWEEP_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often expired delegated tokens are dropped.
+pub const DELEGATION_SWEEP_INTERVAL: Duration = Duration::from_secs(300);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ClientKind {
@@ -60,7 +82,7 @@ fn build_connection_info(state: &AppState) -> ConnectionInfo {
 
 fn device_info_from(ud: &UserDevice) -> DeviceInfo {
     DeviceInfo {
-        device_id: ud.device_id.clone(),
+        device_id: ud.device_id.clone().into(),
         tags: ud.tags.clone(),
         online: ud.online,
         device_type: ud.device_type.clone(),
@@ -79,6 +101,231 @@ fn notify_device_list(state: &AppState, user_id: &str) {
     }
 }
 
+fn audit_event_from(device_id: &str, record: &OwnershipRecord) -> OwnershipAuditEvent {
+    OwnershipAuditEvent {
+        device_id: device_id.into(),
+        action: match record.change {
+            OwnershipChange::Claimed => OwnershipAction::Claimed,
+            OwnershipChange::Transferred => OwnershipAction::Transferred,
+            OwnershipChange::Removed => OwnershipAction::Removed,
+        },
+        user_id: record.user_id.clone(),
+        actor: record.actor.clone(),
+        token_type: match record.via {
+            OwnershipVia::SetupToken => OwnershipTokenType::SetupToken,
+            OwnershipVia::DeviceSecret => OwnershipTokenType::DeviceSecret,
+            OwnershipVia::AccessToken => OwnershipTokenType::AccessToken,
+        },
+        at: record.at,
+    }
+}
+
+/// Append to the device's audit log and push the entry to `owners`.
+fn record_ownership_change(state: &AppState, device_id: &str, record: OwnershipRecord, owners: &[&str]) {
+    let event = audit_event_from(device_id, &record);
+    state.record_ownership(device_id, record);
+    if let Ok(json) = serde_json::to_string(&SignalingMessage::DeviceOwnershipChanged { event }) {
+        for uid in owners {
+            state.notify_user(uid, &json);
+        }
+    }
+}
+
+/// Stored role of a non-owner member; `None` for `Owner`, who is kept in
+/// `device_owners` instead.
+fn member_role_from(role: &CocoonRole) -> Option<MemberRole> {
+    match role {
+        CocoonRole::Owner => None,
+        CocoonRole::Operator => Some(MemberRole::Operator),
+        CocoonRole::Viewer => Some(MemberRole::Viewer),
+    }
+}
+
+/// Everyone sharing `device_id`: its owner first, then the other members in
+/// claim order. Empty while nobody owns it.
+fn cocoon_members(state: &AppState, device_id: &str) -> Vec<CocoonMember> {
+    let Some(owner) = state.device_owners.get(device_id).map(|o| o.value().clone()) else {
+        return Vec::new();
+    };
+    // The owner's claim time is when they last gained the device
+    let claimed_at = state
+        .ownership_history(device_id)
+        .iter()
+        .rev()
+        .find(|record| record.user_id == owner && record.change != OwnershipChange::Removed)
+        .map_or(0, |record| record.at);
+    let mut members = vec![CocoonMember {
+        user_id: owner,
+        role: CocoonRole::Owner,
+        claimed_at,
+    }];
+    if let Some(others) = state.device_members.get(device_id) {
+        let start = members.len();
+        members.extend(others.iter().map(|(uid, member)| CocoonMember {
+            user_id: uid.clone(),
+            role: match member.role {
+                MemberRole::Operator => CocoonRole::Operator,
+                MemberRole::Viewer => CocoonRole::Viewer,
+            },
+            claimed_at: member.claimed_at,
+        }));
+        members[start..].sort_by(|a, b| (a.claimed_at, &a.user_id).cmp(&(b.claimed_at, &b.user_id)));
+    }
+    members
+}
+
+/// The capabilities held for a device as a protocol set, to apply deltas to
+fn capability_set(held: &DeviceCapabilities) -> CapabilitySet {
+    CapabilitySet::new(
+        held.capabilities.iter().map(|(protocol, version)| Capability {
+            protocol: protocol.clone(),
+            version: version.clone(),
+        }),
+        held.version,
+    )
+}
+
+fn device_capabilities_from(set: &CapabilitySet) -> DeviceCapabilities {
+    DeviceCapabilities {
+        version: set.version(),
+        capabilities: set.to_vec().into_iter().map(|c| (c.protocol, c.version)).collect(),
+    }
+}
+
+/// Resolve a capability request from `requester` against the other online
+/// devices of its owner, `prefer_device` first.
+fn route_capability_request(
+    state: &AppState,
+    requester: &str,
+    requested: &Capability,
+    prefer_device: Option<&str>,
+) -> Result<(String, Capability), lib_signaling_protocol::CapabilityMismatch> {
+    let owner = state.device_owners.get(requester).map(|o| o.value().clone());
+    let mut candidates: Vec<(String, Vec<Capability>)> = state
+        .device_capabilities
+        .iter()
+        .filter(|entry| {
+            entry.key() != requester
+                && owner.is_some()
+                && state.device_owners.get(entry.key()).map(|o| o.value().clone()) == owner
+        })
+        .map(|entry| (entry.key().clone(), capability_set(entry.value()).to_vec()))
+        .collect();
+    candidates.sort_by(|(a, _), (b, _)| {
+        (prefer_device != Some(a.as_str()), a).cmp(&(prefer_device != Some(b.as_str()), b))
+    });
+    CapabilityMatcher::new(requested).resolve_among(
+        candidates
+            .iter()
+            .map(|(device_id, capabilities)| (device_id.clone(), capabilities.as_slice())),
+    )
+}
+
+/// Forget the capability requests `device_id` sent or was asked to answer;
+/// requesters still waiting on it get an error response.
+fn drop_capability_routes(state: &AppState, device_id: &str) {
+    state.capability_routes.retain(|request_id, route| {
+        if route.target != device_id {
+            return route.requester != device_id;
+        }
+        if let Some(requester_tx) = state.connections.get(&route.requester) {
+            send_msg(requester_tx.value(), &SignalingMessage::CapabilityResponse {
+                request_id: request_id.clone().into(),
+                payload: serde_json::Value::Null,
+                error: Some("Device disconnected".to_string()),
+                from_device: Some(route.target.clone()),
+            });
+        }
+        false
+    });
+}
+
+fn delegated_grant_from(token: &DelegatedToken) -> DelegatedGrant {
+    DelegatedGrant {
+        token_id: token.token_id.clone(),
+        device_id: token.device_id.clone().into(),
+        issued_by: token.issued_by.clone(),
+        scopes: token.scopes.clone(),
+        expires_at: token.expires_at,
+    }
+}
+
+/// Scopes are `<service>` or `<service>:ro`; the TTL is capped.
+fn validate_delegation(scopes: &[String], ttl_secs: u64) -> Result<(), String> {
+    if scopes.is_empty() {
+        return Err("At least one scope is required".to_string());
+    }
+    for scope in scopes {
+        let service = scope.strip_suffix(":ro").unwrap_or(scope);
+        let valid = !service.is_empty()
+            && service.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '-' | '_')
+            });
+        if !valid {
+            return Err(format!(
+                "Invalid scope '{}' (expected <service> or <service>:ro)",
+                scope
+            ));
+        }
+    }
+    if ttl_secs == 0 || ttl_secs > MAX_DELEGATION_TTL_SECS {
+        return Err(format!(
+            "TTL must be between 1 and {} seconds",
+            MAX_DELEGATION_TTL_SECS
+        ));
+    }
+    Ok(())
+}
+
+/// Who an app may reach `target` as: one of its members, with their role, or
+/// the holder of a delegated token issued for it. A token wins over
+/// membership so an owner testing a share sees what the recipient would.
+fn verify_app_sender(
+    state: &AppState,
+    user_id: Option<&str>,
+    target: &str,
+    delegated_token: Option<&str>,
+) -> Result<VerifiedSender, String> {
+    if let Some(token) = delegated_token {
+        let grant = state
+            .delegated_grant(token, unix_now())
+            .filter(|g| g.device_id == target)
+            .ok_or_else(|| "Delegated token is invalid or expired".to_string())?;
+        return Ok(VerifiedSender {
+            user_id: user_id.map(str::to_owned),
+            role: None,
+            grant: Some(delegated_grant_from(&grant)),
+        });
+    }
+    let refused = || format!("Not authorized to reach device {}", target);
+    let uid = user_id.ok_or_else(refused)?;
+    let role = authorize_connect(&cocoon_members(state, target), uid).map_err(|_| refused())?;
+    Ok(VerifiedSender {
+        user_id: Some(uid.to_string()),
+        role: Some(role),
+        grant: None,
+    })
+}
+
+/// Set (or, with `None`, remove) `sender` on a forwarded payload so the device
+/// never sees one the client wrote itself.
+fn stamp_sender(mut payload: serde_json::Value, sender: Option<&VerifiedSender>) -> serde_json::Value {
+    if let Some(object) = payload.as_object_mut() {
+        match sender.and_then(|s| serde_json::to_value(s).ok()) {
+            Some(sender) => object.insert("sender".to_string(), sender),
+            None => object.remove("sender"),
+        };
+    }
+    payload
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 pub async fn ws_handler(
     Query(query): Query<WsQuery>,
     State(state): State<AppState>,
@@ -89,7 +336,7 @@ pub async fn ws_handler(
 }
 
 async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
-    let (mut sender, mut receiver): (SplitSink<WebSocket, Message>, SplitStream<WebSocket>) =
+    let (mut sender, receiver): (SplitSink<WebSocket, Message>, SplitStream<WebSocket>) =
         socket.split();
 
     let (tx, mut rx) = mpsc::unbounded_channel::<String>();
@@ -102,10 +349,38 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
         }
     });
 
+    let incoming = receiver
+        .take_while(|msg| future::ready(matches!(msg, Ok(m) if !matches!(m, Message::Close(_)))))
+        .filter_map(|msg| {
+            future::ready(match msg {
+                Ok(Message::Text(t)) => Some(t.to_string()),
+                _ => None,
+            })
+        })
+        .boxed();
+
+    handle_connection(incoming, tx, state, kind, Vec::new()).await;
+
+    send_task.abort();
+}
+
+/// Protocol handling for one client, fed its text frames. Direct sockets and
+/// relay links behind a sub-relay both run through here; `relay_path` lists
+/// the sub-relays between the server and the client, nearest to the server
+/// first.
+async fn handle_connection(
+    mut incoming: BoxStream<'static, String>,
+    tx: mpsc::UnboundedSender<String>,
+    state: AppState,
+    kind: ClientKind,
+    relay_path: Vec<String>,
+) {
     let mut device_id: Option<String> = None;
     let mut user_id: Option<String> = None;
     let mut app_conn_id: Option<u64> = None;
     let auth_required = kind == ClientKind::App && state.auth_domain.is_some();
+    // Relay links this client opened as a sub-relay, by link id
+    let mut links: HashMap<String, mpsc::UnboundedSender<String>> = HashMap::new();
 
     match kind {
         ClientKind::App => {
@@ -143,15 +418,20 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
         }
     }
 
-    while let Some(Ok(msg)) = receiver.next().await {
-        let text = match msg {
-            Message::Text(t) => t.to_string(),
-            Message::Close(_) => break,
-            _ => continue,
-        };
-
-        let parsed: SignalingMessage = match serde_json::from_str(&text) {
-            Ok(m) => m,
+    while let Some(text) = incoming.next().await {
+        // Envelopes and bare messages are both accepted; replies stay bare for now
+        let parsed: SignalingMessage = match serde_json::from_str::<SignalingEnvelope>(&text) {
+            Ok(envelope) => {
+                if !envelope.is_legacy() {
+                    debug!(
+                        version = envelope.version,
+                        message_id = %envelope.message_id,
+                        correlation_id = envelope.correlation_id.as_deref(),
+                        "Received enveloped message"
+                    );
+                }
+                envelope.payload
+            }
             Err(e) => {
                 warn!(error = %e, "Failed to parse incoming message");
                 send_msg(&tx, &SignalingMessage::SystemError {
@@ -221,6 +501,14 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
 
                 let derived_id = derive_device_id(&secret, &state.hmac_salt);
 
+                if relay_path.contains(&derived_id) {
+                    warn!(device_id = %derived_id, "Registration refused — relay loop");
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Relay loop — a device cannot register through its own relay link".to_string(),
+                    });
+                    continue;
+                }
+
                 if let Some(ref provided) = provided_id {
                     if *provided != derived_id {
                         warn!(provided = %provided, derived = %derived_id, "Device ID mismatch");
@@ -231,14 +519,14 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                     }
                 }
 
-                // Validate setup_token and extract owner
-                let mut owner_id: Option<String> = None;
+                // Validate setup_token and extract the claimant
+                let mut claimant_id: Option<String> = None;
                 if let Some(ref t) = tags {
                     if let Some(token) = t.get("setup_token") {
                         match extract_user_id(token) {
                             Ok(uid) => {
-                                info!(device_id = %derived_id, owner = %uid, "Setup token validated, owner assigned");
-                                owner_id = Some(uid);
+                                info!(device_id = %derived_id, claimant = %uid, "Setup token validated");
+                                claimant_id = Some(uid);
                             }
                             Err(e) => {
                                 warn!(error = %e, "Invalid setup_token in registration");
@@ -255,17 +543,56 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                     debug!(old_device_id = %old_id, new_device_id = %derived_id, "Re-registering, removing old connection");
                     state.connections.remove(old_id);
                     state.device_meta.remove(old_id);
+                    state.device_capabilities.remove(old_id);
+                    drop_capability_routes(&state, old_id);
                 }
 
                 device_id = Some(derived_id.clone());
-                state.connections.insert(derived_id.clone(), tx.clone());
+                if let Some(previous) = state.connections.insert(derived_id.clone(), tx.clone()) {
+                    // Another socket holds this device; tell it to stay down
+                    // instead of reconnecting and taking the ID back
+                    if !previous.same_channel(&tx) {
+                        warn!(device_id = %derived_id, "Device registered again elsewhere, replacing old connection");
+                        send_msg(&previous, &SignalingMessage::DeviceDisconnect {
+                            info: DisconnectInfo::permanent(DisconnectReason::Replaced),
+                        });
+                    }
+                }
 
-                if let Some(ref uid) = owner_id {
-                    state.device_owners.insert(derived_id.clone(), uid.clone());
+                // The first claimant owns the device; later ones join with
+                // the claim role and only the owner can raise them
+                if let Some(ref uid) = claimant_id {
+                    let members = cocoon_members(&state, &derived_id);
+                    if authorize_connect(&members, uid).is_err() {
+                        match member_role_from(&claim_role(&members)) {
+                            None => {
+                                state.device_owners.insert(derived_id.clone(), uid.clone());
+                                record_ownership_change(&state, &derived_id, OwnershipRecord {
+                                    change: OwnershipChange::Claimed,
+                                    user_id: uid.clone(),
+                                    actor: Some(uid.clone()),
+                                    via: OwnershipVia::SetupToken,
+                                    at: unix_now(),
+                                }, &[uid.as_str()]);
+                            }
+                            Some(role) => {
+                                info!(device_id = %derived_id, user_id = %uid, role = ?role, "Device claimed again, claimant joined as member");
+                                state.device_members.entry(derived_id.clone()).or_default().insert(
+                                    uid.clone(),
+                                    DeviceMember { role, claimed_at: unix_now() },
+                                );
+                            }
+                        }
+                    }
                 }
 
-                // Strip setup_token from tags — never persist or echo it back
-                let clean_tags = tags.map(|mut t| { t.remove("setup_token"); t });
+                // Strip setup_token from tags — never persist or echo it back.
+                // owner_id is the server's to report, not the device's.
+                let clean_tags = tags.map(|mut t| {
+                    t.remove("setup_token");
+                    t.remove("owner_id");
+                    t
+                });
 
                 let meta = DeviceMeta {
                     tags: clean_tags.clone().unwrap_or_default(),
@@ -274,15 +601,23 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                 };
                 state.device_meta.insert(derived_id.clone(), meta);
 
-                info!(device_id = %derived_id, version = %version, owner = ?owner_id, device_type = ?device_type, "Device registered");
+                info!(device_id = %derived_id, version = %version, claimant = ?claimant_id, device_type = ?device_type, "Device registered");
 
+                // Report the current owner so the device can tell a claimed
+                // registration from a reset one
+                let mut response_tags = clean_tags;
+                if let Some(owner) = state.device_owners.get(&derived_id) {
+                    response_tags
+                        .get_or_insert_with(HashMap::new)
+                        .insert("owner_id".to_string(), owner.clone());
+                }
                 send_msg(&tx, &SignalingMessage::DeviceRegisterResponse {
-                    device_id: derived_id.clone(),
-                    tags: clean_tags,
+                    device_id: derived_id.clone().into(),
+                    tags: response_tags,
                 });
 
                 // Notify owner's app connections about updated device list
-                if let Some(ref uid) = owner_id {
+                if let Some(ref uid) = claimant_id {
                     notify_device_list(&state, uid);
                 }
 
@@ -291,7 +626,7 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                     for room_id in rooms.iter() {
                         if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomActorJoined {
                             room_id: room_id.clone(),
-                            device_id: derived_id.clone(),
+                            device_id: derived_id.clone().into(),
                         }) {
                             state.notify_room(room_id, &json, Some(&derived_id));
                         }
@@ -318,22 +653,34 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                 info!(device_id = %did, reason = ?reason, "Device deregistered");
 
                 // Capture owner before removing
-                let owner = state.device_owners.get(&did).map(|o| o.value().clone());
+                let owner = state.device_owners.get(did.as_str()).map(|o| o.value().clone());
 
-                state.connections.remove(&did);
-                state.device_meta.remove(&did);
-                state.device_owners.remove(&did);
+                state.connections.remove(did.as_str());
+                state.device_meta.remove(did.as_str());
+                state.device_owners.remove(did.as_str());
+                state.device_members.remove(did.as_str());
+                state.device_capabilities.remove(did.as_str());
+                drop_capability_routes(&state, &did);
+                state.sync_queue.remove(did.as_str());
 
                 // Notify owner's app connections
                 if let Some(ref uid) = owner {
+                    record_ownership_change(&state, &did, OwnershipRecord {
+                        change: OwnershipChange::Removed,
+                        user_id: uid.clone(),
+                        actor: None,
+                        via: OwnershipVia::DeviceSecret,
+                        at: unix_now(),
+                    }, &[uid.as_str()]);
                     notify_device_list(&state, uid);
                 }
 
-                if let Some((_, peer_id)) = state.paired_devices.remove(&did) {
+                if let Some((_, peer_id)) = state.paired_devices.remove(did.as_str()) {
                     state.paired_devices.remove(&peer_id);
                     if let Some(peer_tx) = state.connections.get(&peer_id) {
                         send_msg(peer_tx.value(), &SignalingMessage::DevicePeerDisconnected {
-                            peer_id: did.clone(),
+                            peer_id: did.to_string(),
+                            info: Some(DisconnectInfo::permanent(DisconnectReason::Deregistered)),
                         });
                     }
                 }
@@ -396,22 +743,17 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                 }
             }
 
-            SignalingMessage::SyncData { payload } => {
+            SignalingMessage::SyncData { payload, priority } => {
                 // App clients (browsers) may send a routing envelope:
-                //   { "to": "<target_device_id>", "data": <actual_payload> }
-                // The server unwraps it and forwards `data` directly to the target device.
+                //   { "to": "<target_device_id>", "data": <actual_payload>, "delegated_token"?: <token> }
+                // The server checks the app may reach the target, stamps the
+                // verified sender on `data` and forwards it to the device.
                 // Cocoon clients use the existing pairing-based routing.
                 if kind == ClientKind::App {
-                    let to = payload
-                        .as_object()
-                        .and_then(|o| o.get("to"))
-                        .and_then(|v| v.as_str())
-                        .map(str::to_owned);
-                    let inner = payload
-                        .as_object()
-                        .and_then(|o| o.get("data"))
-                        .cloned()
-                        .unwrap_or(serde_json::Value::Null);
+                    let field = |name: &str| payload.as_object().and_then(|o| o.get(name));
+                    let to = field("to").and_then(|v| v.as_str()).map(str::to_owned);
+                    let delegated_token = field("delegated_token").and_then(|v| v.as_str());
+                    let inner = field("data").cloned().unwrap_or(serde_json::Value::Null);
 
                     let Some(target) = to else {
                         send_msg(&tx, &SignalingMessage::SystemError {
@@ -419,12 +761,35 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                         });
                         continue;
                     };
+                    let sender = match verify_app_sender(&state, user_id.as_deref(), &target, delegated_token) {
+                        Ok(sender) => sender,
+                        Err(message) => {
+                            warn!(to = %target, "App SyncData refused: {}", message);
+                            send_msg(&tx, &SignalingMessage::SystemError { message });
+                            continue;
+                        }
+                    };
+                    let inner = stamp_sender(inner, Some(&sender));
 
                     if let Some(peer_tx) = state.connections.get(&target) {
                         info!(to = %target, "App client relaying SyncData to device");
-                        send_msg(peer_tx.value(), &SignalingMessage::SyncData { payload: inner });
+                        send_msg(peer_tx.value(), &SignalingMessage::SyncData { payload: inner, priority });
+                    } else if state.device_owners.contains_key(&target) {
+                        info!(to = %target, "App SyncData queued — target device offline");
+                        // Replay checks the sender again, so keep what it proved
+                        let sender = match (delegated_token, &user_id) {
+                            (Some(token), _) => SyncSender::Delegated {
+                                token: token.to_string(),
+                                user_id: user_id.clone(),
+                                connection: tx.clone(),
+                            },
+                            (None, Some(uid)) => SyncSender::Member(uid.clone()),
+                            // verify_app_sender lets no one else through
+                            (None, None) => continue,
+                        };
+                        queue_sync_data(&state, &tx, &target, &SignalingMessage::SyncData { payload: inner, priority }, sender);
                     } else {
-                        info!(to = %target, "App SyncData dropped — target device offline");
+                        info!(to = %target, "App SyncData dropped — target device unknown");
                     }
                 } else {
                     let Some(ref did) = device_id else {
@@ -434,20 +799,53 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                         continue;
                     };
 
+                    // Cocoons address each other with the same envelope; the
+                    // target gets { "from": <sender>, "data": <payload> } so it
+                    // can answer, e.g. during cocoon↔cocoon WebRTC negotiation
+                    if let Some((target, inner)) = cocoon_envelope(kind, &payload) {
+                        let sender_owner = state.device_owners.get(did).map(|o| o.value().clone());
+                        let target_owner = state.device_owners.get(&target).map(|o| o.value().clone());
+                        if sender_owner.is_none() || sender_owner != target_owner {
+                            send_msg(&tx, &SignalingMessage::SystemError {
+                                message: "Cocoons can only reach cocoons of the same owner".to_string(),
+                            });
+                        } else if let Some(peer_tx) = state.connections.get(&target) {
+                            debug!(from = %did, to = %target, "Relaying cocoon-to-cocoon SyncData");
+                            send_msg(peer_tx.value(), &SignalingMessage::SyncData {
+                                payload: serde_json::json!({ "from": did, "data": inner }),
+                                priority,
+                            });
+                        } else {
+                            debug!(from = %did, to = %target, "Cocoon SyncData queued — target offline");
+                            queue_sync_data(&state, &tx, &target, &SignalingMessage::SyncData {
+                                payload: serde_json::json!({ "from": did, "data": inner }),
+                                priority,
+                            }, SyncSender::Device(did.clone()));
+                        }
+                        continue;
+                    }
+
                     if let Some(peer_id) = state.paired_devices.get(did) {
                         let peer = peer_id.value().clone();
+                        // Only app senders are verified; a paired device cannot claim to be one
+                        let payload = stamp_sender(payload, None);
                         if let Some(peer_tx) = state.connections.get(&peer) {
                             debug!(from = %did, to = %peer, "Relaying SyncData");
-                            send_msg(peer_tx.value(), &SignalingMessage::SyncData { payload });
+                            send_msg(peer_tx.value(), &SignalingMessage::SyncData { payload, priority });
                         } else {
-                            debug!(from = %did, to = %peer, "SyncData dropped — peer offline");
+                            debug!(from = %did, to = %peer, "SyncData queued — peer offline");
+                            queue_sync_data(&state, &tx, &peer, &SignalingMessage::SyncData { payload, priority }, SyncSender::Device(did.clone()));
                         }
                     } else {
-                        // No paired device — route to the device owner's App connections
-                        if let Some(owner_id) = state.device_owners.get(did).map(|o| o.value().clone()) {
-                            if let Ok(json) = serde_json::to_string(&SignalingMessage::SyncData { payload }) {
-                                debug!(from = %did, owner = %owner_id, "Relaying SyncData to owner app connections");
-                                state.notify_user(&owner_id, &json);
+                        // No paired device — route to the App connections of
+                        // everyone sharing the device
+                        let members = cocoon_members(&state, did);
+                        if !members.is_empty() {
+                            if let Ok(json) = serde_json::to_string(&SignalingMessage::SyncData { payload, priority }) {
+                                debug!(from = %did, members = members.len(), "Relaying SyncData to member app connections");
+                                for member in &members {
+                                    state.notify_user(&member.user_id, &json);
+                                }
                             }
                         } else {
                             debug!(device_id = %did, "SyncData dropped — no paired device and no owner");
@@ -456,6 +854,53 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                 }
             }
 
+            SignalingMessage::SyncRetrieveQueued { device_id: requested } if kind == ClientKind::Cocoon => {
+                let Some(ref did) = device_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Must register before retrieving queued data".to_string(),
+                    });
+                    continue;
+                };
+                if requested != *did {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Devices can only retrieve their own queued data".to_string(),
+                    });
+                    continue;
+                }
+
+                let now = unix_now();
+                let (queued, expired) = state.take_queued_sync(did, now);
+                let (queued, unauthorized): (Vec<_>, Vec<_>) =
+                    queued.into_iter().partition(|held| may_still_reach(&state, &held.sender, did, now));
+                info!(device_id = %did, delivered = queued.len(), expired, unauthorized = unauthorized.len(), "Replaying queued SyncData");
+                for held in &queued {
+                    let _ = tx.send(held.json.clone());
+                    let receipt = SignalingMessage::SyncDeliveryReceipt {
+                        message_id: held.message_id.clone().into(),
+                        device_id: did.clone().into(),
+                        delivered_at: now,
+                    };
+                    match &held.sender {
+                        SyncSender::Member(uid) | SyncSender::Delegated { user_id: Some(uid), .. } => {
+                            if let Ok(json) = serde_json::to_string(&receipt) {
+                                state.notify_user(uid, &json);
+                            }
+                        }
+                        SyncSender::Delegated { connection, .. } => send_msg(connection, &receipt),
+                        SyncSender::Device(sender_id) => {
+                            if let Some(sender_tx) = state.connections.get(sender_id) {
+                                send_msg(sender_tx.value(), &receipt);
+                            }
+                        }
+                    }
+                }
+                send_msg(&tx, &SignalingMessage::SyncRetrieveQueuedResponse {
+                    device_id: did.clone().into(),
+                    delivered: queued.len() as u32,
+                    expired: expired as u32,
+                });
+            }
+
             SignalingMessage::DeviceUpdateTags { tags } if kind == ClientKind::Cocoon => {
                 let Some(ref did) = device_id else {
                     send_msg(&tx, &SignalingMessage::SystemError {
@@ -470,7 +915,7 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                 }
 
                 send_msg(&tx, &SignalingMessage::DeviceUpdateTagsResponse {
-                    device_id: did.clone(),
+                    device_id: did.clone().into(),
                     tags,
                 });
 
@@ -508,7 +953,7 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                 state.device_meta.insert(did.clone(), meta);
 
                 send_msg(&tx, &SignalingMessage::DeviceUpdateDeviceResponse {
-                    device_id: did.clone(),
+                    device_id: did.clone().into(),
                     tags: response_tags,
                     device_config: response_config,
                 });
@@ -532,7 +977,7 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                     .map(|entry| {
                         let m = entry.value();
                         DeviceInfo {
-                            device_id: entry.key().clone(),
+                            device_id: entry.key().clone().into(),
                             tags: m.tags.clone(),
                             online: state.connections.contains_key(entry.key()),
                             device_type: m.device_type.clone(),
@@ -545,907 +990,2884 @@ async fn handle_socket(socket: WebSocket, state: AppState, kind: ClientKind) {
                 send_msg(&tx, &SignalingMessage::DeviceQueryDevicesResponse { devices });
             }
 
-            // ── Hive channel: register, spawn, terminate ──
-
-            SignalingMessage::HiveRegister {
-                hive_id,
-                version,
-                cocoon_kinds,
-                hive_id_signature: _,
-            } if kind == ClientKind::Hive => {
-                info!(hive_id = %hive_id, version = %version, kinds = cocoon_kinds.len(), "Hive registering");
-
-                let connection_id = format!("hive-{hive_id}");
-                state.connections.insert(connection_id.clone(), tx.clone());
-
-                let kind_ids: Vec<String> = cocoon_kinds.iter().map(|k| k.id.clone()).collect();
-                state.hives.insert(hive_id.clone(), RegisteredHive {
-                    hive_id: hive_id.clone(),
-                    connection_id,
-                    cocoon_kinds: kind_ids,
-                });
+            SignalingMessage::DeviceOwnershipHistory { device_id: did } => {
+                let Some(ref uid) = user_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Authentication required for ownership history".to_string(),
+                    });
+                    continue;
+                };
+                // Unknown devices and devices never owned by this user look
+                // the same, so the reply does not reveal which IDs exist
+                if !state.is_current_or_past_owner(&did, uid) {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("No ownership history for device {}", did),
+                    });
+                    continue;
+                }
 
-                device_id = Some(format!("hive-{hive_id}"));
-                send_msg(&tx, &SignalingMessage::HiveRegisterResponse { hive_id });
+                let events = state
+                    .ownership_history(&did)
+                    .iter()
+                    .map(|record| audit_event_from(&did, record))
+                    .collect();
+                send_msg(&tx, &SignalingMessage::DeviceOwnershipHistoryResponse { device_id: did, events });
             }
 
-            SignalingMessage::HiveSpawnCocoon {
-                request_id,
-                setup_token,
-                name,
-                kind: cocoon_kind,
-            } if kind == ClientKind::App => {
-                // Find a hive that supports this cocoon kind
-                let target_hive = state.hives.iter().find(|entry| {
-                    entry.value().cocoon_kinds.contains(&cocoon_kind)
-                });
-
-                if let Some(hive_entry) = target_hive {
-                    let hive = hive_entry.value().clone();
-                    drop(hive_entry);
-
-                    // Forward spawn request to the hive
-                    if let Some(hive_tx) = state.connections.get(&hive.connection_id) {
-                        send_msg(hive_tx.value(), &SignalingMessage::HiveSpawnCocoon {
-                            request_id: request_id.clone(),
-                            setup_token,
-                            name,
-                            kind: cocoon_kind,
-                        });
-                    } else {
-                        send_msg(&tx, &SignalingMessage::HiveSpawnCocoonResult {
-                            request_id,
-                            success: false,
-                            device_id: None,
-                            container_id: None,
-                            error: Some("Hive is not connected".to_string()),
-                        });
-                    }
-                } else {
-                    send_msg(&tx, &SignalingMessage::HiveSpawnCocoonResult {
-                        request_id,
-                        success: false,
-                        device_id: None,
-                        container_id: None,
-                        error: Some(format!("No hive supports cocoon kind '{cocoon_kind}'")),
+            SignalingMessage::DeviceListMembers { device_id: did } if kind == ClientKind::App => {
+                let Some(ref uid) = user_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Authentication required to list members".to_string(),
+                    });
+                    continue;
+                };
+                // Unknown devices and devices shared with others look the same
+                let members = cocoon_members(&state, &did);
+                if let Err(e) = authorize_connect(&members, uid) {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("Cannot list members of device {}: {}", did, e),
                     });
+                    continue;
                 }
+                send_msg(&tx, &SignalingMessage::DeviceListMembersResponse { device_id: did, members });
             }
 
-            SignalingMessage::HiveTerminateCocoon {
-                request_id,
-                container_id,
-            } if kind == ClientKind::App => {
-                // Forward terminate to first connected hive
-                let target_hive = state.hives.iter().next();
-
-                if let Some(hive_entry) = target_hive {
-                    let hive = hive_entry.value().clone();
-                    drop(hive_entry);
-
-                    if let Some(hive_tx) = state.connections.get(&hive.connection_id) {
-                        send_msg(hive_tx.value(), &SignalingMessage::HiveTerminateCocoon {
-                            request_id,
-                            container_id,
-                        });
-                    } else {
-                        send_msg(&tx, &SignalingMessage::HiveTerminateCocoonResult {
-                            request_id,
-                            success: false,
-                            error: Some("Hive is not connected".to_string()),
-                        });
-                    }
-                } else {
-                    send_msg(&tx, &SignalingMessage::HiveTerminateCocoonResult {
-                        request_id,
-                        success: false,
-                        error: Some("No hive registered".to_string()),
+            SignalingMessage::DeviceUpdateMemberRole { device_id: did, user_id: target, role } if kind == ClientKind::App => {
+                let Some(ref uid) = user_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Authentication required to change members".to_string(),
+                    });
+                    continue;
+                };
+                let members = cocoon_members(&state, &did);
+                if let Err(e) = authorize_member_change(&members, uid, &target, Some(&role)) {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("Cannot change {} on device {}: {}", target, did, e),
                     });
+                    continue;
                 }
-            }
 
-            // Hive sends results back → broadcast to all app connections for the requesting user
-            SignalingMessage::HiveSpawnCocoonResult { .. } if kind == ClientKind::Hive => {
-                if let Some(ref uid) = user_id {
-                    let json = text.clone();
-                    state.notify_user(uid, &json);
-                }
-                // Also broadcast to all user connections since we don't track per-request routing
-                for entry in state.user_connections.iter() {
-                    for conn_tx in entry.value().values() {
-                        let _ = conn_tx.send(text.clone().to_string());
+                // The check keeps the owner, so a lowered role is never theirs
+                let owner = &members[0];
+                match member_role_from(&role) {
+                    Some(new_role) => {
+                        if let Some(mut others) = state.device_members.get_mut(did.as_str()) {
+                            if let Some(member) = others.get_mut(&target) {
+                                member.role = new_role;
+                            }
+                        }
+                    }
+                    // Hand the device over; the previous owner stays on as an operator
+                    None if owner.user_id != target => {
+                        if let Some(mut others) = state.device_members.get_mut(did.as_str()) {
+                            others.remove(&target);
+                            others.insert(owner.user_id.clone(), DeviceMember {
+                                role: MemberRole::Operator,
+                                claimed_at: owner.claimed_at,
+                            });
+                        }
+                        state.device_owners.insert(did.to_string(), target.clone());
+                        record_ownership_change(&state, &did, OwnershipRecord {
+                            change: OwnershipChange::Transferred,
+                            user_id: target.clone(),
+                            actor: Some(uid.clone()),
+                            via: OwnershipVia::AccessToken,
+                            at: unix_now(),
+                        }, &[target.as_str(), owner.user_id.as_str()]);
+                        notify_device_list(&state, &owner.user_id);
+                        notify_device_list(&state, &target);
                     }
+                    None => {}
                 }
-            }
+                info!(device_id = %did, user_id = %target, role = %role, actor = %uid, "Member role updated");
 
-            SignalingMessage::HiveTerminateCocoonResult { .. } if kind == ClientKind::Hive => {
-                for entry in state.user_connections.iter() {
-                    for conn_tx in entry.value().values() {
-                        let _ = conn_tx.send(text.clone().to_string());
-                    }
+                if let Some(member) = cocoon_members(&state, &did).into_iter().find(|m| m.user_id == target) {
+                    send_msg(&tx, &SignalingMessage::DeviceUpdateMemberRoleResponse { device_id: did, member });
                 }
             }
 
-            ref other if handle_room_message(&state, &tx, user_id.as_deref(), device_id.as_deref(), kind, other) => {}
+            SignalingMessage::DeviceRemoveMember { device_id: did, user_id: target } if kind == ClientKind::App => {
+                let Some(ref uid) = user_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Authentication required to remove members".to_string(),
+                    });
+                    continue;
+                };
+                if let Err(e) = authorize_member_change(&cocoon_members(&state, &did), uid, &target, None) {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("Cannot remove {} from device {}: {}", target, did, e),
+                    });
+                    continue;
+                }
 
-            other => {
-                warn!(kind = ?kind, msg_type = ?std::mem::discriminant(&other), "Unsupported message type for client kind");
-                send_msg(&tx, &SignalingMessage::SystemError {
-                    message: "Unsupported message type for this client kind".to_string(),
-                });
+                // The owner is never removed, so `target` is one of the others
+                if let Some(mut others) = state.device_members.get_mut(did.as_str()) {
+                    others.remove(&target);
+                }
+                info!(device_id = %did, user_id = %target, actor = %uid, "Member removed");
+                send_msg(&tx, &SignalingMessage::DeviceRemoveMemberResponse { device_id: did, user_id: target });
             }
-        }
-    }
 
-    if let Some(ref did) = device_id {
+            SignalingMessage::DeviceIssueDelegatedToken { device_id: did, scopes, ttl_secs } if kind == ClientKind::App => {
+                let Some(ref uid) = user_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Authentication required to share a device".to_string(),
+                    });
+                    continue;
+                };
+                if state.device_owners.get(did.as_str()).is_none_or(|o| o.value() != uid) {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("Only the owner can share device {}", did),
+                    });
+                    continue;
+                }
+                if let Err(e) = validate_delegation(&scopes, ttl_secs) {
+                    send_msg(&tx, &SignalingMessage::SystemError { message: e });
+                    continue;
+                }
+
+                let record = DelegatedToken {
+                    token_id: uuid::Uuid::new_v4().to_string(),
+                    device_id: did.into_inner(),
+                    issued_by: uid.clone(),
+                    scopes,
+                    expires_at: unix_now() + ttl_secs,
+                };
+                let grant = delegated_grant_from(&record);
+                let token = state.issue_delegated_token(record);
+                info!(device_id = %grant.device_id, token_id = %grant.token_id, scopes = ?grant.scopes, "Delegated token issued");
+                send_msg(&tx, &SignalingMessage::DeviceIssueDelegatedTokenResponse { token, grant });
+            }
+
+            SignalingMessage::DeviceValidateDelegatedToken { token } if kind == ClientKind::Cocoon => {
+                let Some(ref did) = device_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Must register before validating tokens".to_string(),
+                    });
+                    continue;
+                };
+                // A token for another device is reported like an unknown one
+                let grant = state
+                    .delegated_grant(&token, unix_now())
+                    .filter(|g| g.device_id == *did)
+                    .map(|g| delegated_grant_from(&g));
+                send_msg(&tx, &SignalingMessage::DeviceValidateDelegatedTokenResponse {
+                    token,
+                    valid: grant.is_some(),
+                    grant,
+                });
+            }
+
+            SignalingMessage::DeviceRevokeDelegatedToken { token_id } if kind == ClientKind::App => {
+                let Some(ref uid) = user_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Authentication required to revoke a token".to_string(),
+                    });
+                    continue;
+                };
+                // Unknown tokens and tokens of other users' devices look the same
+                let allowed = state.delegated_tokens.iter().any(|entry| {
+                    let grant = entry.value();
+                    grant.token_id == token_id
+                        && (grant.issued_by == *uid
+                            || state.device_owners.get(&grant.device_id).is_some_and(|o| o.value() == uid))
+                });
+                let revoked = allowed.then(|| state.revoke_delegated_token(&token_id)).flatten();
+                if let Some(ref grant) = revoked {
+                    info!(device_id = %grant.device_id, token_id = %token_id, "Delegated token revoked");
+                    if let Some(device_tx) = state.connections.get(&grant.device_id) {
+                        send_msg(device_tx.value(), &SignalingMessage::DeviceRevokeDelegatedToken {
+                            token_id: token_id.clone(),
+                        });
+                    }
+                }
+                send_msg(&tx, &SignalingMessage::DeviceRevokeDelegatedTokenResponse {
+                    token_id,
+                    revoked: revoked.is_some(),
+                });
+            }
+
+            SignalingMessage::DeviceConfigPush { device_ids, label_selector, config_patch, version } if kind == ClientKind::App => {
+                let Some(ref uid) = user_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Authentication required to push config".to_string(),
+                    });
+                    continue;
+                };
+                if device_ids.is_none() && label_selector.is_none() {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Config push needs device_ids or label_selector".to_string(),
+                    });
+                    continue;
+                }
+                let refused = device_ids.iter().flatten().find_map(|did| {
+                    authorize_operate(&cocoon_members(&state, did), uid).err().map(|e| (did, e))
+                });
+                if let Some((did, e)) = refused {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("Cannot push config to device {}: {}", did, e),
+                    });
+                    continue;
+                }
+
+                // Named devices may be shared with the sender; a selector
+                // alone only reaches devices they own
+                let candidates: Vec<(String, HashMap<String, String>)> = match device_ids {
+                    Some(ref ids) => ids
+                        .iter()
+                        .map(|id| {
+                            let tags = state.device_meta.get(id.as_str()).map(|m| m.tags.clone()).unwrap_or_default();
+                            (id.to_string(), tags)
+                        })
+                        .collect(),
+                    None => state.get_user_devices(uid).into_iter().map(|d| (d.device_id, d.tags)).collect(),
+                };
+                let targets: Vec<_> = candidates
+                    .into_iter()
+                    .filter(|(_, tags)| {
+                        label_selector.as_ref().is_none_or(|selector| {
+                            selector.iter().all(|(k, v)| tags.get(k) == Some(v))
+                        })
+                    })
+                    .map(|(device_id, _)| device_id)
+                    .collect();
+
+                let push = SignalingMessage::DeviceConfigPush {
+                    device_ids: None,
+                    label_selector: None,
+                    config_patch,
+                    version,
+                };
+                let (mut sent_to, mut offline) = (Vec::new(), Vec::new());
+                for device_id in targets {
+                    match state.connections.get(&device_id) {
+                        Some(device_tx) => {
+                            send_msg(device_tx.value(), &push);
+                            sent_to.push(device_id);
+                        }
+                        None => offline.push(device_id),
+                    }
+                }
+                info!(user_id = %uid, version, sent = sent_to.len(), offline = offline.len(), "Config pushed");
+                send_msg(&tx, &SignalingMessage::DeviceConfigPushResponse { version, sent_to, offline });
+            }
+
+            SignalingMessage::DeviceConfigApplied { version, success, error, .. } if kind == ClientKind::Cocoon => {
+                let Some(ref did) = device_id else {
+                    continue;
+                };
+                if success {
+                    info!(device_id = %did, version, "Config applied");
+                } else {
+                    warn!(device_id = %did, version, error = ?error, "Config rolled back");
+                }
+                // The device id comes from the connection, not the message
+                let applied = SignalingMessage::DeviceConfigApplied {
+                    device_id: did.clone().into(),
+                    version,
+                    success,
+                    error,
+                };
+                if let (Some(owner), Ok(json)) = (state.device_owners.get(did), serde_json::to_string(&applied)) {
+                    state.notify_user(owner.value(), &json);
+                }
+            }
+
+            SignalingMessage::DeviceHeartbeat { adi_usage, .. } if kind == ClientKind::Cocoon => {
+                let Some(ref did) = device_id else {
+                    continue;
+                };
+                debug!(device_id = %did, clients = adi_usage.len(), "Cocoon heartbeat");
+                let heartbeat = SignalingMessage::DeviceHeartbeat {
+                    device_id: did.clone().into(),
+                    adi_usage,
+                };
+                if let (Some(owner), Ok(json)) = (state.device_owners.get(did), serde_json::to_string(&heartbeat)) {
+                    state.notify_user(owner.value(), &json);
+                }
+            }
+
+            // ── Capability channel: the set each device offers ──
+
+            SignalingMessage::CapabilityUpdate { capabilities, version, hash } if kind == ClientKind::Cocoon => {
+                let Some(ref did) = device_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Must register before announcing capabilities".to_string(),
+                    });
+                    continue;
+                };
+                let set = CapabilitySet::new(capabilities, version);
+                if set.hash() != hash {
+                    warn!(device_id = %did, version, "Capability update does not match its hash");
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("Capability hash {} does not match the announced set", hash),
+                    });
+                    continue;
+                }
+                debug!(device_id = %did, version, count = set.len(), "Capabilities updated");
+                state.device_capabilities.insert(did.clone(), device_capabilities_from(&set));
+            }
+
+            SignalingMessage::CapabilityDelta { base_version, version, added, removed, changed, hash }
+                if kind == ClientKind::Cocoon =>
+            {
+                let Some(ref did) = device_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Must register before announcing capabilities".to_string(),
+                    });
+                    continue;
+                };
+                // A device that never sent an update starts from the empty set
+                let mut set = state
+                    .device_capabilities
+                    .get(did)
+                    .map(|held| capability_set(held.value()))
+                    .unwrap_or_default();
+                let delta = CapabilityDelta { base_version, version, added, removed, changed, hash };
+                match set.apply(&delta) {
+                    Ok(()) => {
+                        debug!(device_id = %did, version, count = set.len(), "Capability delta applied");
+                        state.device_capabilities.insert(did.clone(), device_capabilities_from(&set));
+                    }
+                    Err(e) => {
+                        debug!(device_id = %did, error = %e, "Capability delta did not apply, asking for a full update");
+                        send_msg(&tx, &SignalingMessage::CapabilityResync { version: set.version() });
+                    }
+                }
+            }
+
+            SignalingMessage::CapabilityRequest { request_id, capability, payload, prefer_device, .. }
+                if kind == ClientKind::Cocoon =>
+            {
+                let Some(ref did) = device_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Must register before requesting capabilities".to_string(),
+                    });
+                    continue;
+                };
+                if state.capability_routes.contains_key(request_id.as_str()) {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("Capability request {} is already pending", request_id),
+                    });
+                    continue;
+                }
+                let (target, resolved) =
+                    match route_capability_request(&state, did, &capability, prefer_device.as_deref()) {
+                        Ok(routed) => routed,
+                        Err(mismatch) => {
+                            debug!(device_id = %did, request_id = %request_id, "Capability unavailable: {}", mismatch);
+                            send_msg(&tx, &mismatch.to_message(request_id));
+                            continue;
+                        }
+                    };
+                let Some(target_tx) = state.connections.get(&target).map(|t| t.value().clone()) else {
+                    continue;
+                };
+                debug!(
+                    device_id = %did,
+                    target = %target,
+                    protocol = %resolved.protocol,
+                    version = %resolved.version,
+                    "Routing capability request"
+                );
+                state.capability_routes.insert(request_id.to_string(), CapabilityRoute {
+                    requester: did.clone(),
+                    target: target.clone(),
+                });
+                send_msg(&target_tx, &SignalingMessage::CapabilityRequest {
+                    request_id,
+                    capability: resolved,
+                    payload,
+                    prefer_device: None,
+                    from_device: Some(did.clone()),
+                });
+            }
+
+            SignalingMessage::CapabilityResponse { request_id, payload, error, .. } if kind == ClientKind::Cocoon => {
+                let Some(ref did) = device_id else {
+                    continue;
+                };
+                let Some((_, route)) = state
+                    .capability_routes
+                    .remove_if(request_id.as_str(), |_, route| route.target == *did)
+                else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("No capability request {} was routed to this device", request_id),
+                    });
+                    continue;
+                };
+                if let Some(requester_tx) = state.connections.get(&route.requester) {
+                    send_msg(requester_tx.value(), &SignalingMessage::CapabilityResponse {
+                        request_id,
+                        payload,
+                        error,
+                        from_device: Some(did.clone()),
+                    });
+                }
+            }
+
+            // ── Hive channel: register, spawn, terminate ──
+
+            SignalingMessage::HiveRegister {
+                hive_id,
+                version,
+                cocoon_kinds,
+                hive_id_signature: _,
+                runners,
+                gpus,
+                singletons,
+            } if kind == ClientKind::Hive => {
+                info!(hive_id = %hive_id, version = %version, kinds = cocoon_kinds.len(), runners = ?runners, "Hive registering");
+
+                let connection_id = format!("hive-{hive_id}");
+                state.connections.insert(connection_id.clone(), tx.clone());
+
+                let kind_ids = schedulable_kinds(&cocoon_kinds, runners.as_deref());
+                if kind_ids.len() < cocoon_kinds.len() {
+                    warn!(hive_id = %hive_id, "Hive advertises cocoon kinds without an available runner, ignoring them");
+                }
+                state.hives.insert(hive_id.to_string(), RegisteredHive {
+                    hive_id: hive_id.to_string(),
+                    connection_id,
+                    cocoon_kinds: kind_ids,
+                    runners,
+                    gpu_vram_free_mb: gpus.iter().flatten().map(|gpu| gpu.vram_free_mb).collect(),
+                    maintenance: false,
+                    maintenance_reason: None,
+                    singletons: singletons.clone().unwrap_or_default(),
+                    warm_cocoons: HashMap::new(),
+                    last_heartbeat: Instant::now(),
+                });
+
+                device_id = Some(format!("hive-{hive_id}"));
+                send_msg(&tx, &SignalingMessage::HiveRegisterResponse { hive_id });
+
+                // Tell the hive who leads its singletons, even if nothing changed
+                elect_singletons(&state, Instant::now());
+                for service in singletons.iter().flatten() {
+                    if let Some(lease) = state.singleton_leases.get(service) {
+                        send_msg(&tx, &singleton_leader_msg(service, lease.value()));
+                    }
+                }
+            }
+
+            SignalingMessage::HiveHeartbeat { gpus, singletons } if kind == ClientKind::Hive => {
+                let hive_id = device_id.as_deref().and_then(|did| did.strip_prefix("hive-"));
+                if let Some(mut hive) = hive_id.and_then(|id| state.hives.get_mut(id)) {
+                    hive.gpu_vram_free_mb = gpus.iter().map(|gpu| gpu.vram_free_mb).collect();
+                    hive.last_heartbeat = Instant::now();
+                    if let Some(singletons) = singletons {
+                        hive.singletons = singletons;
+                    }
+                }
+                elect_singletons(&state, Instant::now());
+            }
+
+            SignalingMessage::HiveMaintenance { on, reason } if kind == ClientKind::Hive => {
+                let hive_id = device_id.as_deref().and_then(|did| did.strip_prefix("hive-"));
+                if let Some(mut hive) = hive_id.and_then(|id| state.hives.get_mut(id)) {
+                    info!(hive_id = %hive.hive_id, on, reason = ?reason, "Hive maintenance mode changed");
+                    hive.maintenance = on;
+                    hive.maintenance_reason = if on { reason } else { None };
+                }
+            }
+
+            // Hive in maintenance hands a running cocoon over to another hive
+            SignalingMessage::HiveDrainCocoon {
+                request_id,
+                container_id,
+                setup_token,
+                name,
+                kind: cocoon_kind,
+                gpu_required,
+                min_vram_mb,
+            } if kind == ClientKind::Hive => {
+                let Some(ref did) = device_id else {
+                    continue;
+                };
+                let source_hive = did.strip_prefix("hive-").unwrap_or(did);
+                let gpu_required = gpu_required.unwrap_or(false);
+
+                let target = state
+                    .hives
+                    .iter()
+                    .find(|entry| {
+                        entry.key() != source_hive
+                            && can_place(entry.value(), &cocoon_kind, gpu_required, min_vram_mb)
+                    })
+                    .map(|entry| entry.value().clone())
+                    .and_then(|hive| {
+                        let hive_tx = state.connections.get(&hive.connection_id)?.value().clone();
+                        Some((hive.hive_id, hive_tx))
+                    });
+
+                match target {
+                    Some((target_hive_id, hive_tx)) => {
+                        info!(from = %source_hive, to = %target_hive_id, container_id = %container_id, "Draining cocoon");
+                        state.hive_drains.insert(request_id.to_string(), PendingDrain {
+                            source_connection_id: did.clone(),
+                            container_id,
+                            target_hive_id,
+                        });
+                        send_msg(&hive_tx, &SignalingMessage::HiveSpawnCocoon {
+                            request_id,
+                            setup_token,
+                            name,
+                            kind: cocoon_kind.clone(),
+                            gpu_required: Some(gpu_required),
+                            min_vram_mb,
+                            from_pool: None,
+                        });
+                    }
+                    None => send_msg(&tx, &SignalingMessage::HiveDrainCocoonResult {
+                        request_id,
+                        container_id,
+                        success: false,
+                        target_hive_id: None,
+                        error: Some(format!("No other hive can take cocoon kind '{cocoon_kind}'")),
+                    }),
+                }
+            }
+
+            SignalingMessage::HiveSpawnCocoon {
+                request_id,
+                setup_token,
+                name,
+                kind: cocoon_kind,
+                gpu_required,
+                min_vram_mb,
+                from_pool,
+            } if kind == ClientKind::App => {
+                let gpu_required = gpu_required.unwrap_or(false);
+
+                // Pool spawns go to a hive with a warm cocoon of the kind if there is one
+                let warm_hive = if from_pool == Some(true) {
+                    state
+                        .hives
+                        .iter()
+                        .find(|entry| {
+                            has_warm_cocoon(entry.value(), &cocoon_kind)
+                                && can_place(entry.value(), &cocoon_kind, gpu_required, min_vram_mb)
+                        })
+                        .map(|entry| entry.value().clone())
+                } else {
+                    None
+                };
+
+                // Find a hive that supports this cocoon kind and has the GPU it needs
+                let mut kind_supported = false;
+                let mut kind_available = false;
+                let target_hive = warm_hive.or_else(|| {
+                    state
+                        .hives
+                        .iter()
+                        .find(|entry| {
+                            let hive = entry.value();
+                            if !hive.cocoon_kinds.contains(&cocoon_kind) {
+                                return false;
+                            }
+                            kind_supported = true;
+                            kind_available |= !hive.maintenance;
+                            can_place(hive, &cocoon_kind, gpu_required, min_vram_mb)
+                        })
+                        .map(|entry| entry.value().clone())
+                });
+
+                if let Some(hive) = target_hive {
+                    // Claimed until the hive's next pool status says otherwise
+                    if from_pool == Some(true) {
+                        if let Some(mut hive) = state.hives.get_mut(&hive.hive_id) {
+                            if let Some(ready) = hive.warm_cocoons.get_mut(&cocoon_kind) {
+                                *ready = ready.saturating_sub(1);
+                            }
+                        }
+                    }
+
+                    // Forward spawn request to the hive
+                    if let Some(hive_tx) = state.connections.get(&hive.connection_id) {
+                        send_msg(hive_tx.value(), &SignalingMessage::HiveSpawnCocoon {
+                            request_id: request_id.clone(),
+                            setup_token,
+                            name,
+                            kind: cocoon_kind,
+                            gpu_required: Some(gpu_required),
+                            min_vram_mb,
+                            from_pool,
+                        });
+                    } else {
+                        send_msg(&tx, &SignalingMessage::HiveSpawnCocoonResult {
+                            request_id,
+                            success: false,
+                            device_id: None,
+                            container_id: None,
+                            error: Some("Hive is not connected".to_string()),
+                            from_pool: None,
+                        });
+                    }
+                } else {
+                    let error = if !kind_supported {
+                        format!("No hive supports cocoon kind '{cocoon_kind}'")
+                    } else if !kind_available {
+                        format!("Every hive supporting cocoon kind '{cocoon_kind}' is in maintenance")
+                    } else if let Some(min) = min_vram_mb {
+                        format!(
+                            "No hive with {min} MiB free VRAM on one GPU supports cocoon kind '{cocoon_kind}'"
+                        )
+                    } else {
+                        format!("No hive with a GPU supports cocoon kind '{cocoon_kind}'")
+                    };
+                    send_msg(&tx, &SignalingMessage::HiveSpawnCocoonResult {
+                        request_id,
+                        success: false,
+                        device_id: None,
+                        container_id: None,
+                        error: Some(error),
+                        from_pool: None,
+                    });
+                }
+            }
+
+            SignalingMessage::HiveSetPoolSize {
+                kind: cocoon_kind,
+                size,
+            } if kind == ClientKind::App => {
+                if !user_id.as_ref().is_some_and(|uid| state.hive_operators.contains(uid)) {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Only hive operators can size warm pools".to_string(),
+                    });
+                    continue;
+                }
+                if size > MAX_POOL_SIZE {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: format!("Warm pools hold at most {MAX_POOL_SIZE} cocoons"),
+                    });
+                    continue;
+                }
+                let mut forwarded = 0;
+                for entry in state.hives.iter() {
+                    let hive = entry.value();
+                    if !hive.cocoon_kinds.contains(&cocoon_kind) {
+                        continue;
+                    }
+                    if let Some(hive_tx) = state.connections.get(&hive.connection_id) {
+                        send_msg(hive_tx.value(), &SignalingMessage::HiveSetPoolSize {
+                            kind: cocoon_kind.clone(),
+                            size,
+                        });
+                        forwarded += 1;
+                    }
+                }
+                info!(kind = %cocoon_kind, size, hives = forwarded, "Cocoon pool size set");
+            }
+
+            SignalingMessage::HiveRekeyCocoon {
+                request_id,
+                slot_secret,
+                setup_token,
+                secret,
+            } if kind == ClientKind::Hive => {
+                if device_id.is_none() {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Must register before re-keying cocoons".to_string(),
+                    });
+                    continue;
+                }
+                // Only a device nobody owns yet can be handed out, and only
+                // by whoever knows the secret it registered with
+                let slot_id = derive_device_id(&slot_secret, &state.hmac_salt);
+                let error = if state.device_owners.contains_key(&slot_id) {
+                    Some("Warm cocoon is already owned".to_string())
+                } else if let Err(e) = validate_secret(&secret) {
+                    Some(format!("Invalid secret: {e}"))
+                } else if let Some(slot_tx) = state.connections.get(&slot_id) {
+                    send_msg(slot_tx.value(), &SignalingMessage::DeviceRekey { setup_token, secret });
+                    None
+                } else {
+                    Some("Warm cocoon is not connected".to_string())
+                };
+                info!(device_id = %slot_id, success = error.is_none(), "Warm cocoon re-keyed");
+                send_msg(&tx, &SignalingMessage::HiveRekeyCocoonResponse {
+                    request_id,
+                    success: error.is_none(),
+                    error,
+                });
+            }
+
+            SignalingMessage::HivePoolStatus { pools } if kind == ClientKind::Hive => {
+                let hive_id = device_id.as_deref().and_then(|did| did.strip_prefix("hive-"));
+                if let Some(mut hive) = hive_id.and_then(|id| state.hives.get_mut(id)) {
+                    hive.warm_cocoons =
+                        pools.into_iter().map(|pool| (pool.kind, pool.ready)).collect();
+                }
+            }
+
+            SignalingMessage::HiveTerminateCocoon {
+                request_id,
+                container_id,
+            } if kind == ClientKind::App => {
+                // Forward terminate to first connected hive
+                let target_hive = state.hives.iter().next();
+
+                if let Some(hive_entry) = target_hive {
+                    let hive = hive_entry.value().clone();
+                    drop(hive_entry);
+
+                    if let Some(hive_tx) = state.connections.get(&hive.connection_id) {
+                        send_msg(hive_tx.value(), &SignalingMessage::HiveTerminateCocoon {
+                            request_id,
+                            container_id,
+                        });
+                    } else {
+                        send_msg(&tx, &SignalingMessage::HiveTerminateCocoonResult {
+                            request_id,
+                            success: false,
+                            error: Some("Hive is not connected".to_string()),
+                        });
+                    }
+                } else {
+                    send_msg(&tx, &SignalingMessage::HiveTerminateCocoonResult {
+                        request_id,
+                        success: false,
+                        error: Some("No hive registered".to_string()),
+                    });
+                }
+            }
+
+            // Hive sends results back → broadcast to all app connections for the requesting user
+            SignalingMessage::HiveSpawnCocoonResult {
+                request_id,
+                success,
+                error,
+                ..
+            } if kind == ClientKind::Hive => {
+                // Spawn was a drain: the source hive stops its copy once the target has it
+                if let Some((_, drain)) = state.hive_drains.remove(request_id.as_str()) {
+                    if let Some(source_tx) = state.connections.get(&drain.source_connection_id) {
+                        send_msg(source_tx.value(), &SignalingMessage::HiveDrainCocoonResult {
+                            request_id,
+                            container_id: drain.container_id,
+                            success,
+                            target_hive_id: Some(drain.target_hive_id.into()),
+                            error,
+                        });
+                    }
+                }
+                if let Some(ref uid) = user_id {
+                    let json = text.clone();
+                    state.notify_user(uid, &json);
+                }
+                // Also broadcast to all user connections since we don't track per-request routing
+                for entry in state.user_connections.iter() {
+                    for conn_tx in entry.value().values() {
+                        let _ = conn_tx.send(text.clone().to_string());
+                    }
+                }
+            }
+
+            SignalingMessage::HiveTerminateCocoonResult { .. } if kind == ClientKind::Hive => {
+                for entry in state.user_connections.iter() {
+                    for conn_tx in entry.value().values() {
+                        let _ = conn_tx.send(text.clone().to_string());
+                    }
+                }
+            }
+
+            SignalingMessage::RelayOpen { link_id } if kind == ClientKind::Cocoon => {
+                let Some(ref did) = device_id else {
+                    send_msg(&tx, &SignalingMessage::SystemError {
+                        message: "Must register before relaying for other devices".to_string(),
+                    });
+                    continue;
+                };
+
+                let mut link_path = relay_path.clone();
+                link_path.push(did.clone());
+                if link_path.len() > MAX_RELAY_HOPS {
+                    warn!(relay = %did, link_id = %link_id, "Relay link refused — hop limit");
+                    send_msg(&tx, &SignalingMessage::RelayClose {
+                        link_id,
+                        reason: Some(format!("Hop limit of {} exceeded", MAX_RELAY_HOPS)),
+                    });
+                    continue;
+                }
+
+                info!(relay = %did, link_id = %link_id, hops = link_path.len(), "Relay link opened");
+                let (link_tx, link_rx) = mpsc::unbounded_channel::<String>();
+                links.insert(link_id.clone(), link_tx);
+                spawn_relay_link(link_id, link_rx, tx.clone(), state.clone(), link_path);
+            }
+
+            SignalingMessage::RelayFrame { link_id, frame } if kind == ClientKind::Cocoon => {
+                match links.get(&link_id) {
+                    Some(link_tx) => {
+                        let _ = link_tx.send(frame.to_string());
+                    }
+                    None => send_msg(&tx, &SignalingMessage::RelayClose {
+                        link_id,
+                        reason: Some("Unknown relay link".to_string()),
+                    }),
+                }
+            }
+
+            SignalingMessage::RelayClose { link_id, .. } if kind == ClientKind::Cocoon => {
+                // Ends the link's incoming stream; its session cleans up like a disconnect
+                if links.remove(&link_id).is_some() {
+                    debug!(link_id = %link_id, "Relay link closed by sub-relay");
+                }
+            }
+
+            ref other if handle_room_message(&state, &tx, user_id.as_deref(), device_id.as_deref(), kind, other) => {}
+
+            other => {
+                warn!(kind = ?kind, msg_type = ?std::mem::discriminant(&other), "Unsupported message type for client kind");
+                send_msg(&tx, &SignalingMessage::SystemError {
+                    message: "Unsupported message type for this client kind".to_string(),
+                });
+            }
+        }
+    }
+
+    // A connection replaced by a newer registration of the same device no
+    // longer owns the device's state
+    let replaced = device_id.as_ref().is_some_and(|did| {
+        state.connections.get(did).is_some_and(|conn| !conn.same_channel(&tx))
+    });
+
+    if replaced {
+        debug!(device_id = ?device_id, "Replaced connection closed");
+    } else if let Some(ref did) = device_id {
         info!(device_id = %did, "Device disconnected");
         state.connections.remove(did);
         state.device_meta.remove(did);
+        state.device_capabilities.remove(did);
+        drop_capability_routes(&state, did);
+
+        // Clean up hive registration on disconnect
+        if kind == ClientKind::Hive {
+            let hive_id = did.strip_prefix("hive-").unwrap_or(did);
+            state.hives.remove(hive_id);
+            info!(hive_id = %hive_id, "Hive unregistered");
+
+            // Drains waiting on this hive will never get a spawn result
+            state.hive_drains.retain(|request_id, drain| {
+                if drain.target_hive_id != hive_id {
+                    return drain.source_connection_id != *did;
+                }
+                if let Some(source_tx) = state.connections.get(&drain.source_connection_id) {
+                    send_msg(source_tx.value(), &SignalingMessage::HiveDrainCocoonResult {
+                        request_id: request_id.clone().into(),
+                        container_id: drain.container_id.clone(),
+                        success: false,
+                        target_hive_id: Some(drain.target_hive_id.clone().into()),
+                        error: Some("Target hive disconnected".to_string()),
+                    });
+                }
+                false
+            });
+
+            elect_singletons(&state, Instant::now());
+        }
+
+        // Notify room participants that this actor went offline
+        let affected_rooms = state.cleanup_device_rooms(did);
+        for room_id in &affected_rooms {
+            if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomActorLeft {
+                room_id: room_id.clone(),
+                device_id: did.clone().into(),
+            }) {
+                state.notify_room(room_id, &json, None);
+            }
+        }
+
+        // Notify owner's app connections about device going offline
+        if let Some(owner) = state.device_owners.get(did).map(|o| o.value().clone()) {
+            notify_device_list(&state, &owner);
+        }
+
+        if let Some((_, peer_id)) = state.paired_devices.remove(did) {
+            info!(device_id = %did, peer_id = %peer_id, "Notifying peer of disconnect");
+            state.paired_devices.remove(&peer_id);
+            if let Some(peer_tx) = state.connections.get(&peer_id) {
+                send_msg(peer_tx.value(), &SignalingMessage::DevicePeerDisconnected {
+                    peer_id: did.clone(),
+                    info: Some(DisconnectInfo::transient(DisconnectReason::ConnectionLost, None)),
+                });
+            }
+        }
+    } else {
+        debug!("Anonymous connection closed (never registered)");
+    }
+
+    // Clean up app connection from user_connections
+    if let (Some(ref uid), Some(conn_id)) = (&user_id, app_conn_id) {
+        if let Some(mut conns) = state.user_connections.get_mut(uid) {
+            conns.remove(&conn_id);
+            if conns.is_empty() {
+                drop(conns);
+                state.user_connections.remove(uid);
+            }
+        }
+    }
+}
+
+/// Tell every connected client that the server is going away and when to
+/// come back.
+pub fn announce_shutdown(state: &AppState, retry_after: Duration) {
+    let msg = SignalingMessage::DeviceDisconnect {
+        info: DisconnectInfo::transient(DisconnectReason::ServerShutdown, Some(retry_after)),
+    };
+    for conn in state.connections.iter() {
+        send_msg(conn.value(), &msg);
+    }
+    for conns in state.user_connections.iter() {
+        for tx in conns.values() {
+            send_msg(tx, &msg);
+        }
+    }
+}
+
+/// Run a relay link as a connection of its own. Frames for the downstream
+/// client go back to the sub-relay wrapped in `relay_frame`, and once the
+/// link's session is cleaned up the sub-relay gets `relay_close`.
+fn spawn_relay_link(
+    link_id: String,
+    link_rx: mpsc::UnboundedReceiver<String>,
+    relay_tx: mpsc::UnboundedSender<String>,
+    state: AppState,
+    relay_path: Vec<String>,
+) {
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<String>();
+    tokio::spawn(async move {
+        while let Some(json) = out_rx.recv().await {
+            let Ok(frame) = serde_json::from_str(&json) else { continue };
+            send_msg(&relay_tx, &SignalingMessage::RelayFrame {
+                link_id: link_id.clone(),
+                frame,
+            });
+        }
+        // Every sender is gone once the link's session has been cleaned up
+        send_msg(&relay_tx, &SignalingMessage::RelayClose { link_id, reason: None });
+    });
+
+    let incoming = stream::unfold(link_rx, |mut rx| async move {
+        rx.recv().await.map(|text| (text, rx))
+    })
+    .boxed();
+    tokio::spawn(handle_connection(incoming, out_tx, state, ClientKind::Cocoon, relay_path));
+}
+
+fn build_room_info(state: &AppState, room: &Room) -> RoomInfo {
+    let actors: Vec<DeviceInfo> = room
+        .actors
+        .iter()
+        .map(|did| {
+            let meta = state.device_meta.get(did);
+            let (tags, device_type, device_config) = match meta {
+                Some(m) => (m.tags.clone(), m.device_type.clone(), m.device_config.clone()),
+                None => (std::collections::HashMap::new(), None, None),
+            };
+            DeviceInfo {
+                device_id: did.clone().into(),
+                tags,
+                online: state.connections.contains_key(did),
+                device_type,
+                device_config,
+            }
+        })
+        .collect();
+
+    RoomInfo {
+        room_id: room.room_id.clone(),
+        owner_user_id: room.owner_user_id.clone(),
+        granted_users: room.granted_users.iter().cloned().collect(),
+        actors,
+    }
+}
+
+fn notify_room_updated(state: &AppState, room_id: &str) {
+    let Some(room) = state.rooms.get(room_id) else { return };
+    let info = build_room_info(state, &room);
+    if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomUpdated { room: info }) {
+        drop(room);
+        state.notify_room(room_id, &json, None);
+    }
+}
+
+/// Handles all Room* message variants. Returns true if the message was handled.
+fn handle_room_message(
+    state: &AppState,
+    tx: &mpsc::UnboundedSender<String>,
+    user_id: Option<&str>,
+    device_id: Option<&str>,
+    kind: ClientKind,
+    msg: &SignalingMessage,
+) -> bool {
+    match msg {
+        SignalingMessage::RoomCreate { room_id } => {
+            let Some(uid) = user_id else {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Authentication required for room operations".to_string(),
+                });
+                return true;
+            };
+
+            let rid = room_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+            if state.rooms.contains_key(&rid) {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Room already exists".to_string(),
+                });
+                return true;
+            }
+
+            let room = Room {
+                room_id: rid.clone(),
+                owner_user_id: uid.to_string(),
+                granted_users: HashSet::new(),
+                actors: HashSet::new(),
+            };
+            state.rooms.insert(rid.clone(), room);
+            info!(room_id = %rid, owner = %uid, "Room created");
+
+            send_msg(tx, &SignalingMessage::RoomCreateResponse { room_id: rid });
+            true
+        }
+
+        SignalingMessage::RoomDelete { room_id } => {
+            let Some(uid) = user_id else {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Authentication required for room operations".to_string(),
+                });
+                return true;
+            };
+
+            let is_owner = state.rooms.get(room_id).map(|r| r.owner_user_id == uid).unwrap_or(false);
+            if !is_owner {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Only the room owner can delete a room".to_string(),
+                });
+                return true;
+            }
+
+            if let Some((_, room)) = state.rooms.remove(room_id) {
+                for actor_id in &room.actors {
+                    if let Some(mut rooms) = state.device_rooms.get_mut(actor_id) {
+                        rooms.remove(room_id);
+                    }
+                }
+                info!(room_id = %room_id, "Room deleted");
+            }
+
+            send_msg(tx, &SignalingMessage::RoomDeleteResponse { room_id: room_id.clone() });
+            true
+        }
+
+        SignalingMessage::RoomAddActor { room_id, device_id: actor_did } => {
+            let Some(uid) = user_id else {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Authentication required for room operations".to_string(),
+                });
+                return true;
+            };
+
+            if !state.user_has_room_access(room_id, uid) {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "No access to this room".to_string(),
+                });
+                return true;
+            }
+
+            state.add_actor_to_room(room_id, actor_did);
+            info!(room_id = %room_id, device_id = %actor_did, "Actor added to room");
+
+            send_msg(tx, &SignalingMessage::RoomAddActorResponse {
+                room_id: room_id.clone(),
+                device_id: actor_did.clone(),
+            });
+
+            if state.connections.contains_key(actor_did.as_str()) {
+                if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomActorJoined {
+                    room_id: room_id.clone(),
+                    device_id: actor_did.clone(),
+                }) {
+                    state.notify_room(room_id, &json, None);
+                }
+            }
+
+            notify_room_updated(state, room_id);
+            true
+        }
+
+        SignalingMessage::RoomRemoveActor { room_id, device_id: actor_did } => {
+            let Some(uid) = user_id else {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Authentication required for room operations".to_string(),
+                });
+                return true;
+            };
+
+            if !state.user_has_room_access(room_id, uid) {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "No access to this room".to_string(),
+                });
+                return true;
+            }
+
+            state.remove_actor_from_room(room_id, actor_did);
+            info!(room_id = %room_id, device_id = %actor_did, "Actor removed from room");
+
+            send_msg(tx, &SignalingMessage::RoomRemoveActorResponse {
+                room_id: room_id.clone(),
+                device_id: actor_did.clone(),
+            });
+
+            if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomActorLeft {
+                room_id: room_id.clone(),
+                device_id: actor_did.clone(),
+            }) {
+                state.notify_room(room_id, &json, None);
+            }
+
+            notify_room_updated(state, room_id);
+            true
+        }
+
+        SignalingMessage::RoomGrantAccess { room_id, user_id: target_uid } => {
+            let Some(uid) = user_id else {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Authentication required for room operations".to_string(),
+                });
+                return true;
+            };
+
+            if !state.user_has_room_access(room_id, uid) {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "No access to this room".to_string(),
+                });
+                return true;
+            }
+
+            if let Some(mut room) = state.rooms.get_mut(room_id) {
+                room.granted_users.insert(target_uid.clone());
+            }
+            info!(room_id = %room_id, granted_to = %target_uid, "Access granted");
+
+            send_msg(tx, &SignalingMessage::RoomGrantAccessResponse {
+                room_id: room_id.clone(),
+                user_id: target_uid.clone(),
+            });
+
+            notify_room_updated(state, room_id);
+            true
+        }
+
+        SignalingMessage::RoomRevokeAccess { room_id, user_id: target_uid } => {
+            let Some(uid) = user_id else {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Authentication required for room operations".to_string(),
+                });
+                return true;
+            };
+
+            if !state.user_has_room_access(room_id, uid) {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "No access to this room".to_string(),
+                });
+                return true;
+            }
+
+            let is_owner_target = state.rooms.get(room_id)
+                .map(|r| r.owner_user_id == *target_uid)
+                .unwrap_or(false);
+            if is_owner_target {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Cannot revoke access from the room owner".to_string(),
+                });
+                return true;
+            }
+
+            if let Some(mut room) = state.rooms.get_mut(room_id) {
+                room.granted_users.remove(target_uid.as_str());
+            }
+            info!(room_id = %room_id, revoked_from = %target_uid, "Access revoked");
+
+            send_msg(tx, &SignalingMessage::RoomRevokeAccessResponse {
+                room_id: room_id.clone(),
+                user_id: target_uid.clone(),
+            });
+
+            notify_room_updated(state, room_id);
+            true
+        }
+
+        SignalingMessage::RoomList => {
+            let Some(uid) = user_id else {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Authentication required for room operations".to_string(),
+                });
+                return true;
+            };
+
+            let rooms: Vec<RoomInfo> = state
+                .get_user_rooms(uid)
+                .iter()
+                .map(|room| build_room_info(state, room))
+                .collect();
+
+            send_msg(tx, &SignalingMessage::RoomListResponse { rooms });
+            true
+        }
+
+        SignalingMessage::RoomGet { room_id } => {
+            let Some(uid) = user_id else {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "Authentication required for room operations".to_string(),
+                });
+                return true;
+            };
+
+            if !state.user_has_room_access(room_id, uid) {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "No access to this room".to_string(),
+                });
+                return true;
+            }
+
+            if let Some(room) = state.rooms.get(room_id) {
+                let info = build_room_info(state, &room);
+                send_msg(tx, &SignalingMessage::RoomGetResponse {
+                    room_id: info.room_id,
+                    owner_user_id: info.owner_user_id,
+                    granted_users: info.granted_users,
+                    actors: info.actors,
+                });
+            }
+            true
+        }
+
+        SignalingMessage::RoomSend { room_id, to, payload } => {
+            let has_access = match kind {
+                ClientKind::App => user_id.map(|uid| state.user_has_room_access(room_id, uid)).unwrap_or(false),
+                ClientKind::Cocoon => device_id
+                    .and_then(|did| state.device_rooms.get(did))
+                    .map(|rooms| rooms.contains(room_id))
+                    .unwrap_or(false),
+                ClientKind::Hive => false,
+            };
 
-        // Clean up hive registration on disconnect
-        if kind == ClientKind::Hive {
-            let hive_id = did.strip_prefix("hive-").unwrap_or(did);
-            state.hives.remove(hive_id);
-            info!(hive_id = %hive_id, "Hive unregistered");
+            if !has_access {
+                send_msg(tx, &SignalingMessage::SystemError {
+                    message: "No access to this room".to_string(),
+                });
+                return true;
+            }
+
+            let sender_id = match kind {
+                ClientKind::Cocoon => device_id.unwrap_or("unknown"),
+                ClientKind::App => user_id.unwrap_or("unknown"),
+                ClientKind::Hive => device_id.unwrap_or("unknown"),
+            };
+
+            if let Some(target) = to {
+                let target_in_room = state.rooms.get(room_id)
+                    .map(|r| r.actors.contains(target))
+                    .unwrap_or(false);
+
+                if !target_in_room {
+                    send_msg(tx, &SignalingMessage::SystemError {
+                        message: "Target device is not in this room".to_string(),
+                    });
+                    return true;
+                }
+
+                if let Some(peer_tx) = state.connections.get(target) {
+                    send_msg(peer_tx.value(), &SignalingMessage::RoomSend {
+                        room_id: room_id.clone(),
+                        to: None,
+                        payload: payload.clone(),
+                    });
+                }
+            } else {
+                if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomSend {
+                    room_id: room_id.clone(),
+                    to: None,
+                    payload: payload.clone(),
+                }) {
+                    state.notify_room(room_id, &json, device_id);
+                }
+            }
+
+            debug!(room_id = %room_id, sender = %sender_id, "Room message routed");
+            true
         }
 
-        // Notify room participants that this actor went offline
-        let affected_rooms = state.cleanup_device_rooms(did);
-        for room_id in &affected_rooms {
-            if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomActorLeft {
-                room_id: room_id.clone(),
-                device_id: did.clone(),
-            }) {
-                state.notify_room(room_id, &json, None);
+        _ => false,
+    }
+}
+
+/// Whether the sender of a held `sync_data` may still reach `device_id`:
+/// members must still share it, and delegated tokens must still be live and
+/// for it.
+fn may_still_reach(state: &AppState, sender: &SyncSender, device_id: &str, now: u64) -> bool {
+    match sender {
+        SyncSender::Member(uid) => authorize_connect(&cocoon_members(state, device_id), uid).is_ok(),
+        SyncSender::Delegated { token, .. } => {
+            state.delegated_grant(token, now).is_some_and(|grant| grant.device_id == device_id)
+        }
+        SyncSender::Device(_) => true,
+    }
+}
+
+/// Hold a `sync_data` for an offline `target` and tell the sender until when.
+fn queue_sync_data(
+    state: &AppState,
+    tx: &mpsc::UnboundedSender<String>,
+    target: &str,
+    msg: &SignalingMessage,
+    sender: SyncSender,
+) {
+    let Ok(json) = serde_json::to_string(msg) else { return };
+    let (message_id, expires_at) = state.queue_sync(target, json, sender, unix_now());
+    send_msg(tx, &SignalingMessage::SyncQueuedDelivery {
+        message_id: message_id.into(),
+        device_id: target.into(),
+        expires_at,
+    });
+}
+
+fn send_msg(tx: &mpsc::UnboundedSender<String>, msg: &SignalingMessage) {
+    if let Ok(json) = serde_json::to_string(msg) {
+        let _ = tx.send(json);
+    }
+}
+
+/// Target and payload of a cocoon's `{ "to": <device_id>, "data": <payload> }`
+/// envelope; other payloads keep the pairing/owner routing.
+fn cocoon_envelope(kind: ClientKind, payload: &serde_json::Value) -> Option<(String, serde_json::Value)> {
+    if kind != ClientKind::Cocoon {
+        return None;
+    }
+    let envelope = payload.as_object().filter(|o| o.len() == 2)?;
+    let to = envelope.get("to")?.as_str()?;
+    Some((to.to_string(), envelope.get("data")?.clone()))
+}
+
+/// Kind ids a hive can spawn: all advertised kinds, minus those whose runner the
+/// hive reported as unavailable.
+fn schedulable_kinds(kinds: &[CocoonKind], runners: Option<&[String]>) -> Vec<String> {
+    kinds
+        .iter()
+        .filter(|k| match runners {
+            Some(runners) => runners.contains(&k.runner_type),
+            None => true,
+        })
+        .map(|k| k.id.clone())
+        .collect()
+}
+
+/// Re-elect the leader of every singleton service whose leader left, went
+/// silent or stopped listing it, and tell each hive listing the service.
+pub fn elect_singletons(state: &AppState, now: Instant) {
+    // service → (hive_id, eligible) of every hive listing it
+    let mut candidates: HashMap<String, Vec<(String, bool)>> = HashMap::new();
+    for entry in state.hives.iter() {
+        let hive = entry.value();
+        let fresh = now.saturating_duration_since(hive.last_heartbeat) < HIVE_SILENCE_TIMEOUT;
+        for service in &hive.singletons {
+            candidates.entry(service.clone()).or_default().push((hive.hive_id.clone(), fresh));
+        }
+    }
+
+    let mut services: HashSet<String> = candidates.keys().cloned().collect();
+    services.extend(state.singleton_leases.iter().map(|lease| lease.key().clone()));
+
+    for service in services {
+        let hives = candidates.get(&service).map(Vec::as_slice).unwrap_or_default();
+        let current = state.singleton_leases.get(&service).map(|lease| lease.value().clone());
+        let leader = next_singleton_leader(
+            current.as_ref().and_then(|lease| lease.leader_hive_id.as_deref()),
+            hives,
+            |hive_id| state.hives.get(hive_id).is_some_and(|hive| hive.maintenance),
+        );
+        if current.as_ref().is_some_and(|lease| lease.leader_hive_id == leader) {
+            continue;
+        }
+
+        let lease = SingletonLease {
+            leader_hive_id: leader,
+            fencing_token: current.map_or(1, |lease| lease.fencing_token + 1),
+        };
+        info!(service = %service, leader = ?lease.leader_hive_id, fencing_token = lease.fencing_token, "Singleton leader changed");
+        let msg = singleton_leader_msg(&service, &lease);
+        state.singleton_leases.insert(service, lease);
+
+        for (hive_id, _) in hives {
+            let connection_id = state.hives.get(hive_id).map(|hive| hive.connection_id.clone());
+            if let Some(hive_tx) = connection_id.and_then(|id| state.connections.get(&id)) {
+                send_msg(hive_tx.value(), &msg);
+            }
+        }
+    }
+}
+
+/// Keep a leader that still heartbeats and lists the service; otherwise pick
+/// the fresh candidate with the lowest id, preferring hives not in maintenance.
+fn next_singleton_leader(
+    current: Option<&str>,
+    candidates: &[(String, bool)],
+    in_maintenance: impl Fn(&str) -> bool,
+) -> Option<String> {
+    if let Some(current) = current {
+        if candidates.iter().any(|(hive_id, fresh)| *fresh && hive_id == current) {
+            return Some(current.to_string());
+        }
+    }
+    candidates
+        .iter()
+        .filter(|(_, fresh)| *fresh)
+        .map(|(hive_id, _)| hive_id)
+        .min_by_key(|hive_id| (in_maintenance(hive_id), hive_id.as_str()))
+        .cloned()
+}
+
+fn singleton_leader_msg(service: &str, lease: &SingletonLease) -> SignalingMessage {
+    SignalingMessage::HiveSingletonLeader {
+        service: service.to_string(),
+        leader_hive_id: lease.leader_hive_id.clone().map(Into::into),
+        fencing_token: lease.fencing_token,
+    }
+}
+
+/// Move singletons off hives that stopped heartbeating but kept their connection.
+pub async fn watch_singleton_leases(state: AppState) {
+    let mut interval = tokio::time::interval(SINGLETON_SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        elect_singletons(&state, Instant::now());
+    }
+}
+
+/// Drop delegated tokens once they expire, whether or not anyone looks them up.
+pub async fn prune_delegated_tokens(state: AppState) {
+    let mut interval = tokio::time::interval(DELEGATION_SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        let pruned = state.prune_delegated_tokens(unix_now());
+        if pruned > 0 {
+            debug!(pruned, "Dropped expired delegated tokens");
+        }
+    }
+}
+
+/// Whether a hive reported a warm cocoon of `kind` that is ready to claim.
+fn has_warm_cocoon(hive: &RegisteredHive, kind: &str) -> bool {
+    hive.warm_cocoons.get(kind).is_some_and(|ready| *ready > 0)
+}
+
+/// Whether a hive can take a new cocoon: not in maintenance, runs the kind and
+/// has the GPU it needs.
+fn can_place(hive: &RegisteredHive, kind: &str, gpu_required: bool, min_vram_mb: Option<u64>) -> bool {
+    !hive.maintenance
+        && hive.cocoon_kinds.iter().any(|k| k == kind)
+        && meets_gpu_constraints(&hive.gpu_vram_free_mb, gpu_required, min_vram_mb)
+}
+
+/// Whether a hive's GPUs satisfy a spawn's constraints; `min_vram_mb` implies
+/// a GPU and must fit on a single card.
+fn meets_gpu_constraints(
+    vram_free_mb: &[u64],
+    gpu_required: bool,
+    min_vram_mb: Option<u64>,
+) -> bool {
+    match min_vram_mb {
+        Some(min) => vram_free_mb.iter().any(|free| *free >= min),
+        None => !gpu_required || !vram_free_mb.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::get};
+    use futures::{SinkExt, StreamExt};
+    use lib_signaling_protocol::SignalingMessage;
+    use std::collections::HashMap;
+    use tokio_tungstenite::{connect_async, tungstenite::Message as TsMessage};
+
+    /// Drain all pending messages from a stream with a short timeout.
+    async fn drain_pending(
+        stream: &mut (impl StreamExt<Item = Result<TsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin),
+    ) {
+        loop {
+            match tokio::time::timeout(std::time::Duration::from_millis(50), stream.next()).await {
+                Ok(Some(Ok(_))) => continue,
+                _ => break,
+            }
+        }
+    }
+
+    async fn spawn_server() -> String {
+        spawn_server_with_auth(None).await
+    }
+
+    async fn spawn_server_with_auth(auth_domain: Option<String>) -> String {
+        let state = AppState::new(
+            "test-salt-for-deterministic-ids".to_string(),
+            auth_domain,
+            true,
+            vec![],
+        )
+        .with_hive_operators(["operator".to_string()]);
+        let app = Router::new()
+            .route("/ws", get(ws_handler))
+            .with_state(state);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("ws://127.0.0.1:{}/ws", addr.port())
+    }
+
+    async fn recv_msg(
+        stream: &mut (impl StreamExt<Item = Result<TsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin),
+    ) -> SignalingMessage {
+        loop {
+            match stream.next().await.unwrap().unwrap() {
+                TsMessage::Text(t) => return serde_json::from_str(&t).unwrap(),
+                _ => continue,
+            }
+        }
+    }
+
+    async fn send(
+        sink: &mut (impl SinkExt<TsMessage> + Unpin),
+        msg: &SignalingMessage,
+    ) {
+        let json = serde_json::to_string(msg).unwrap();
+        sink.send(TsMessage::Text(json.into())).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_app_gets_auth_hello() {
+        let url = spawn_server().await;
+        // Default kind=app
+        let (ws, _) = connect_async(&url).await.unwrap();
+        let (mut _sink, mut stream) = ws.split();
+
+        let hello = recv_msg(&mut stream).await;
+        match hello {
+            SignalingMessage::AuthHello {
+                auth_kind,
+                auth_requirement,
+                auth_options,
+                ..
+            } => {
+                assert_eq!(auth_kind, "none");
+                assert!(matches!(auth_requirement, AuthRequirement::Optional));
+                assert_eq!(auth_options.len(), 1);
+                assert!(matches!(auth_options[0], AuthOption::Anonymous));
+            }
+            other => panic!("Expected AuthHello, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cocoon_registration_flow() {
+        let url = spawn_server().await;
+        let cocoon_url = format!("{}?kind=cocoon", url);
+        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
+
+        // 1. No AuthHello — cocoon sends DeviceRegister directly
+        let secret = "xK9mP2qR7wL4nJ6vB8cT3fY5hA0gD1eS";
+        let tags = HashMap::from([
+            ("kind".to_string(), "desktop".to_string()),
+            ("os".to_string(), "macos".to_string()),
+        ]);
+
+        send(&mut sink, &SignalingMessage::DeviceRegister {
+            secret: secret.to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: Some(tags.clone()),
+            device_type: Some("cocoon".to_string()),
+            device_config: Some(serde_json::json!({"image": "ubuntu"})),
+        }).await;
+
+        // 2. Server responds with device_id + tags
+        let registered = recv_msg(&mut stream).await;
+        let device_id = match registered {
+            SignalingMessage::DeviceRegisterResponse { ref device_id, ref tags } => {
+                assert!(!device_id.is_empty(), "device_id should be non-empty");
+                let t = tags.as_ref().unwrap();
+                assert_eq!(t["kind"], "desktop");
+                assert_eq!(t["os"], "macos");
+                device_id.clone()
+            }
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        };
+
+        // 3. Re-register with same secret
+        send(&mut sink, &SignalingMessage::DeviceRegister {
+            secret: secret.to_string(),
+            device_id: Some(device_id.clone()),
+            version: "1.0.1".to_string(),
+            tags: Some(HashMap::from([("kind".into(), "laptop".into())])),
+            device_type: Some("cocoon".to_string()),
+            device_config: None,
+        }).await;
+
+        let re_registered = recv_msg(&mut stream).await;
+        match re_registered {
+            SignalingMessage::DeviceRegisterResponse { device_id: did, tags: t } => {
+                assert_eq!(did, device_id, "Same secret must produce same device_id");
+                assert_eq!(t.as_ref().unwrap()["kind"], "laptop");
             }
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
         }
 
-        // Notify owner's app connections about device going offline
-        if let Some(owner) = state.device_owners.get(did).map(|o| o.value().clone()) {
-            notify_device_list(&state, &owner);
+        // 4. Update tags
+        let new_tags = HashMap::from([
+            ("kind".to_string(), "laptop".to_string()),
+            ("expose_ip".to_string(), "true".to_string()),
+        ]);
+        send(&mut sink, &SignalingMessage::DeviceUpdateTags { tags: new_tags.clone() }).await;
+
+        let tags_updated = recv_msg(&mut stream).await;
+        match tags_updated {
+            SignalingMessage::DeviceUpdateTagsResponse { device_id: did, tags: t } => {
+                assert_eq!(did, device_id);
+                assert_eq!(t["expose_ip"], "true");
+                assert_eq!(t["kind"], "laptop");
+            }
+            other => panic!("Expected DeviceUpdateTagsResponse, got: {:?}", other),
         }
 
-        if let Some((_, peer_id)) = state.paired_devices.remove(did) {
-            info!(device_id = %did, peer_id = %peer_id, "Notifying peer of disconnect");
-            state.paired_devices.remove(&peer_id);
-            if let Some(peer_tx) = state.connections.get(&peer_id) {
-                send_msg(peer_tx.value(), &SignalingMessage::DevicePeerDisconnected {
-                    peer_id: did.clone(),
-                });
+        // 5. Query devices
+        send(&mut sink, &SignalingMessage::DeviceQueryDevices {
+            tag_filter: HashMap::from([("kind".into(), "laptop".into())]),
+        }).await;
+
+        let device_list = recv_msg(&mut stream).await;
+        match device_list {
+            SignalingMessage::DeviceQueryDevicesResponse { devices } => {
+                assert_eq!(devices.len(), 1);
+                assert_eq!(devices[0].device_id, device_id);
+                assert!(devices[0].online);
+                assert_eq!(devices[0].device_type, Some("cocoon".to_string()));
             }
+            other => panic!("Expected DeviceQueryDevicesResponse, got: {:?}", other),
         }
-    } else {
-        debug!("Anonymous connection closed (never registered)");
-    }
 
-    // Clean up app connection from user_connections
-    if let (Some(ref uid), Some(conn_id)) = (&user_id, app_conn_id) {
-        if let Some(mut conns) = state.user_connections.get_mut(uid) {
-            conns.remove(&conn_id);
-            if conns.is_empty() {
-                drop(conns);
-                state.user_connections.remove(uid);
+        // 6. Deregister
+        send(&mut sink, &SignalingMessage::DeviceDeregister {
+            device_id: device_id.clone(),
+            reason: Some("test cleanup".to_string()),
+        }).await;
+
+        let deregistered = recv_msg(&mut stream).await;
+        match deregistered {
+            SignalingMessage::DeviceDeregisterResponse { device_id: did } => {
+                assert_eq!(did, device_id);
             }
+            other => panic!("Expected DeviceDeregisterResponse, got: {:?}", other),
         }
     }
 
-    send_task.abort();
-}
+    #[tokio::test]
+    async fn test_duplicate_registration_replaces_old_connection() {
+        let url = spawn_server().await;
+        let cocoon_url = format!("{}?kind=cocoon", url);
+        let secret = "rT5wQ8zL1mN4bV7cX0kJ3hG6fD9sA2pE";
+        let register = SignalingMessage::DeviceRegister {
+            secret: secret.to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: Some(HashMap::from([("kind".into(), "replaced-test".into())])),
+            device_type: Some("cocoon".to_string()),
+            device_config: None,
+        };
 
-fn build_room_info(state: &AppState, room: &Room) -> RoomInfo {
-    let actors: Vec<DeviceInfo> = room
-        .actors
-        .iter()
-        .map(|did| {
-            let meta = state.device_meta.get(did);
-            let (tags, device_type, device_config) = match meta {
-                Some(m) => (m.tags.clone(), m.device_type.clone(), m.device_config.clone()),
-                None => (std::collections::HashMap::new(), None, None),
-            };
-            DeviceInfo {
-                device_id: did.clone(),
-                tags,
-                online: state.connections.contains_key(did),
-                device_type,
-                device_config,
+        let (old_ws, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut old_sink, mut old_stream) = old_ws.split();
+        send(&mut old_sink, &register).await;
+        assert!(matches!(recv_msg(&mut old_stream).await, SignalingMessage::DeviceRegisterResponse { .. }));
+
+        let (new_ws, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut new_sink, mut new_stream) = new_ws.split();
+        send(&mut new_sink, &register).await;
+        assert!(matches!(recv_msg(&mut new_stream).await, SignalingMessage::DeviceRegisterResponse { .. }));
+
+        match recv_msg(&mut old_stream).await {
+            SignalingMessage::DeviceDisconnect { info } => {
+                assert!(matches!(info.reason, DisconnectReason::Replaced));
+                assert!(!info.should_retry());
             }
-        })
-        .collect();
+            other => panic!("Expected DeviceDisconnect, got: {:?}", other),
+        }
 
-    RoomInfo {
-        room_id: room.room_id.clone(),
-        owner_user_id: room.owner_user_id.clone(),
-        granted_users: room.granted_users.iter().cloned().collect(),
-        actors,
-    }
-}
+        // The old socket going away must not take the new registration with it
+        old_sink.close().await.ok();
+        drop(old_stream);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
-fn notify_room_updated(state: &AppState, room_id: &str) {
-    let Some(room) = state.rooms.get(room_id) else { return };
-    let info = build_room_info(state, &room);
-    if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomUpdated { room: info }) {
-        drop(room);
-        state.notify_room(room_id, &json, None);
+        send(&mut new_sink, &SignalingMessage::DeviceQueryDevices {
+            tag_filter: HashMap::from([("kind".into(), "replaced-test".into())]),
+        }).await;
+        match recv_msg(&mut new_stream).await {
+            SignalingMessage::DeviceQueryDevicesResponse { devices } => {
+                assert_eq!(devices.len(), 1);
+                assert!(devices[0].online);
+            }
+            other => panic!("Expected DeviceQueryDevicesResponse, got: {:?}", other),
+        }
     }
-}
 
-/// Handles all Room* message variants. Returns true if the message was handled.
-fn handle_room_message(
-    state: &AppState,
-    tx: &mpsc::UnboundedSender<String>,
-    user_id: Option<&str>,
-    device_id: Option<&str>,
-    kind: ClientKind,
-    msg: &SignalingMessage,
-) -> bool {
-    match msg {
-        SignalingMessage::RoomCreate { room_id } => {
-            let Some(uid) = user_id else {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Authentication required for room operations".to_string(),
-                });
-                return true;
-            };
+    #[tokio::test]
+    async fn test_enveloped_messages_are_accepted() {
+        let url = spawn_server().await;
+        let cocoon_url = format!("{}?kind=cocoon", url);
+        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
 
-            let rid = room_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let envelope = SignalingEnvelope::new(SignalingMessage::DeviceRegister {
+            secret: "xK9mP2qR7wL4nJ6vB8cT3fY5hA0gD1eS".to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: None,
+            device_type: None,
+            device_config: None,
+        });
+        let json = serde_json::to_string(&envelope).unwrap();
+        sink.send(TsMessage::Text(json.into())).await.ok();
 
-            if state.rooms.contains_key(&rid) {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Room already exists".to_string(),
-                });
-                return true;
+        match recv_msg(&mut stream).await {
+            SignalingMessage::DeviceRegisterResponse { device_id, .. } => {
+                assert!(!device_id.is_empty());
             }
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        }
+    }
 
-            let room = Room {
-                room_id: rid.clone(),
-                owner_user_id: uid.to_string(),
-                granted_users: HashSet::new(),
-                actors: HashSet::new(),
-            };
-            state.rooms.insert(rid.clone(), room);
-            info!(room_id = %rid, owner = %uid, "Room created");
+    #[tokio::test]
+    async fn test_cocoon_rejects_weak_secret() {
+        let url = spawn_server().await;
+        let cocoon_url = format!("{}?kind=cocoon", url);
+        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
 
-            send_msg(tx, &SignalingMessage::RoomCreateResponse { room_id: rid });
-            true
+        send(&mut sink, &SignalingMessage::DeviceRegister {
+            secret: "short".to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: None,
+            device_type: None,
+            device_config: None,
+        }).await;
+
+        let err = recv_msg(&mut stream).await;
+        match err {
+            SignalingMessage::SystemError { message } => {
+                assert!(message.contains("Secret too short"));
+            }
+            other => panic!("Expected SystemError, got: {:?}", other),
         }
+    }
 
-        SignalingMessage::RoomDelete { room_id } => {
-            let Some(uid) = user_id else {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Authentication required for room operations".to_string(),
-                });
-                return true;
-            };
+    #[tokio::test]
+    async fn test_pairing_and_sync() {
+        let url = spawn_server().await;
+        let cocoon_url = format!("{}?kind=cocoon", url);
 
-            let is_owner = state.rooms.get(room_id).map(|r| r.owner_user_id == uid).unwrap_or(false);
-            if !is_owner {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Only the room owner can delete a room".to_string(),
-                });
-                return true;
-            }
+        // Device A connects as cocoon
+        let (ws_a, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut sink_a, mut stream_a) = ws_a.split();
 
-            if let Some((_, room)) = state.rooms.remove(room_id) {
-                for actor_id in &room.actors {
-                    if let Some(mut rooms) = state.device_rooms.get_mut(actor_id) {
-                        rooms.remove(room_id);
-                    }
-                }
-                info!(room_id = %room_id, "Room deleted");
-            }
+        send(&mut sink_a, &SignalingMessage::DeviceRegister {
+            secret: "aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV".to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: None,
+            device_type: None,
+            device_config: None,
+        }).await;
+        let reg_a = recv_msg(&mut stream_a).await;
+        let id_a = match reg_a {
+            SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        };
+
+        // Device A creates pairing code
+        send(&mut sink_a, &SignalingMessage::PairingCreateCode).await;
+        let code = match recv_msg(&mut stream_a).await {
+            SignalingMessage::PairingCreateCodeResponse { code } => code,
+            other => panic!("Expected PairingCreateCodeResponse, got: {:?}", other),
+        };
+
+        // Device B connects as cocoon, registers, uses pairing code
+        let (ws_b, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut sink_b, mut stream_b) = ws_b.split();
+
+        send(&mut sink_b, &SignalingMessage::DeviceRegister {
+            secret: "xY9wV8uT7sR6qP5oN4mL3kJ2iH1gF0eD".to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: None,
+            device_type: None,
+            device_config: None,
+        }).await;
+        let reg_b = recv_msg(&mut stream_b).await;
+        let id_b = match reg_b {
+            SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        };
+
+        send(&mut sink_b, &SignalingMessage::PairingUseCode { code }).await;
+
+        // Both should receive Paired
+        let paired_b = recv_msg(&mut stream_b).await;
+        match paired_b {
+            SignalingMessage::PairingUseCodeResponse { peer_id } => assert_eq!(peer_id, id_a),
+            other => panic!("Expected PairingUseCodeResponse for B, got: {:?}", other),
+        }
 
-            send_msg(tx, &SignalingMessage::RoomDeleteResponse { room_id: room_id.clone() });
-            true
+        let paired_a = recv_msg(&mut stream_a).await;
+        match paired_a {
+            SignalingMessage::PairingUseCodeResponse { peer_id } => assert_eq!(peer_id, id_b),
+            other => panic!("Expected PairingUseCodeResponse for A, got: {:?}", other),
         }
 
-        SignalingMessage::RoomAddActor { room_id, device_id: actor_did } => {
-            let Some(uid) = user_id else {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Authentication required for room operations".to_string(),
-                });
-                return true;
-            };
+        // Device A sends SyncData -> Device B receives it
+        let payload = serde_json::json!({"action": "ping", "ts": 12345});
+        send(
+            &mut sink_a,
+            &SignalingMessage::SyncData {
+                payload: payload.clone(),
+                priority: Some(lib_signaling_protocol::RelayPriority::Interactive),
+            },
+        )
+        .await;
 
-            if !state.user_has_room_access(room_id, uid) {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "No access to this room".to_string(),
-                });
-                return true;
+        let sync = recv_msg(&mut stream_b).await;
+        match sync {
+            SignalingMessage::SyncData { payload: p, priority } => {
+                assert_eq!(p["action"], "ping");
+                assert_eq!(p["ts"], 12345);
+                assert!(matches!(
+                    priority,
+                    Some(lib_signaling_protocol::RelayPriority::Interactive)
+                ));
             }
+            other => panic!("Expected SyncData, got: {:?}", other),
+        }
+    }
 
-            state.add_actor_to_room(room_id, actor_did);
-            info!(room_id = %room_id, device_id = %actor_did, "Actor added to room");
-
-            send_msg(tx, &SignalingMessage::RoomAddActorResponse {
-                room_id: room_id.clone(),
-                device_id: actor_did.clone(),
-            });
+    #[tokio::test]
+    async fn test_app_auth_required_blocks_unauthenticated() {
+        let url = spawn_server_with_auth(Some("https://auth.example.com".to_string())).await;
+        let (ws, _) = connect_async(&url).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
 
-            if state.connections.contains_key(actor_did) {
-                if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomActorJoined {
-                    room_id: room_id.clone(),
-                    device_id: actor_did.clone(),
-                }) {
-                    state.notify_room(room_id, &json, None);
-                }
+        // Should receive AuthHello with required auth
+        let hello = recv_msg(&mut stream).await;
+        match hello {
+            SignalingMessage::AuthHello { auth_requirement, .. } => {
+                assert!(matches!(auth_requirement, AuthRequirement::Required));
             }
-
-            notify_room_updated(state, room_id);
-            true
+            other => panic!("Expected AuthHello, got: {:?}", other),
         }
 
-        SignalingMessage::RoomRemoveActor { room_id, device_id: actor_did } => {
-            let Some(uid) = user_id else {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Authentication required for room operations".to_string(),
-                });
-                return true;
-            };
+        // Try to send a non-auth message before authenticating
+        send(&mut sink, &SignalingMessage::DeviceQueryDevices {
+            tag_filter: HashMap::new(),
+        }).await;
 
-            if !state.user_has_room_access(room_id, uid) {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "No access to this room".to_string(),
-                });
-                return true;
+        let err = recv_msg(&mut stream).await;
+        match err {
+            SignalingMessage::SystemError { message } => {
+                assert!(message.contains("Authentication required"));
             }
+            other => panic!("Expected SystemError for unauthenticated request, got: {:?}", other),
+        }
+    }
 
-            state.remove_actor_from_room(room_id, actor_did);
-            info!(room_id = %room_id, device_id = %actor_did, "Actor removed from room");
+    fn make_jwt(sub: &str) -> String {
+        use signaling_core::tokens::base64url_encode;
+        let header = base64url_encode(b"{\"alg\":\"HS256\",\"typ\":\"JWT\"}");
+        let payload = base64url_encode(format!("{{\"sub\":\"{}\"}}", sub).as_bytes());
+        let sig = base64url_encode(b"fake-signature");
+        format!("{}.{}.{}", header, payload, sig)
+    }
 
-            send_msg(tx, &SignalingMessage::RoomRemoveActorResponse {
-                room_id: room_id.clone(),
-                device_id: actor_did.clone(),
-            });
+    #[tokio::test]
+    async fn test_cocoon_setup_token_validates_owner() {
+        let url = spawn_server().await;
+        let cocoon_url = format!("{}?kind=cocoon", url);
+        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
 
-            if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomActorLeft {
-                room_id: room_id.clone(),
-                device_id: actor_did.clone(),
-            }) {
-                state.notify_room(room_id, &json, None);
-            }
+        let token = make_jwt("user-123");
+        let tags = HashMap::from([
+            ("setup_token".to_string(), token),
+            ("name".to_string(), "my-cocoon".to_string()),
+        ]);
 
-            notify_room_updated(state, room_id);
-            true
+        send(&mut sink, &SignalingMessage::DeviceRegister {
+            secret: "xK9mP2qR7wL4nJ6vB8cT3fY5hA0gD1eS".to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: Some(tags),
+            device_type: Some("cocoon".to_string()),
+            device_config: None,
+        }).await;
+
+        let resp = recv_msg(&mut stream).await;
+        match resp {
+            SignalingMessage::DeviceRegisterResponse { ref tags, .. } => {
+                let t = tags.as_ref().unwrap();
+                // setup_token should be stripped from stored tags
+                assert!(!t.contains_key("setup_token"), "setup_token should be stripped");
+                assert_eq!(t["name"], "my-cocoon");
+            }
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
         }
+    }
 
-        SignalingMessage::RoomGrantAccess { room_id, user_id: target_uid } => {
-            let Some(uid) = user_id else {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Authentication required for room operations".to_string(),
-                });
-                return true;
-            };
+    #[tokio::test]
+    async fn test_ownership_audit_events_and_history() {
+        let url = spawn_server().await;
 
-            if !state.user_has_room_access(room_id, uid) {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "No access to this room".to_string(),
-                });
-                return true;
+        // The first owner watches from an app connection
+        let (ws_app, _) = connect_async(&url).await.unwrap();
+        let (mut app_sink, mut app_stream) = ws_app.split();
+        let _ = recv_msg(&mut app_stream).await;
+        send(&mut app_sink, &SignalingMessage::AuthAuthenticate { access_token: make_jwt("user-a") }).await;
+        drain_pending(&mut app_stream).await;
+
+        let (ws, _) = connect_async(&format!("{}?kind=cocoon", url)).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
+        let register = |owner: &str| SignalingMessage::DeviceRegister {
+            secret: "xK9mP2qR7wL4nJ6vB8cT3fY5hA0gD1eS".to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: Some(HashMap::from([("setup_token".to_string(), make_jwt(owner))])),
+            device_type: None,
+            device_config: None,
+        };
+
+        send(&mut sink, &register("user-a")).await;
+        let device_id = match recv_msg(&mut stream).await {
+            SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        };
+        match recv_msg(&mut app_stream).await {
+            SignalingMessage::DeviceOwnershipChanged { event } => {
+                assert!(matches!(event.action, OwnershipAction::Claimed));
+                assert_eq!(event.user_id, "user-a");
+                assert!(matches!(event.token_type, OwnershipTokenType::SetupToken));
             }
+            other => panic!("Expected DeviceOwnershipChanged, got: {:?}", other),
+        }
+        drain_pending(&mut app_stream).await;
 
-            if let Some(mut room) = state.rooms.get_mut(room_id) {
-                room.granted_users.insert(target_uid.clone());
+        // Same owner again is not a change, and another user's token only
+        // joins them; the owner hands the device over
+        send(&mut sink, &register("user-a")).await;
+        let _ = recv_msg(&mut stream).await;
+        drain_pending(&mut app_stream).await;
+        send(&mut sink, &register("user-b")).await;
+        let _ = recv_msg(&mut stream).await;
+        send(&mut app_sink, &SignalingMessage::DeviceUpdateMemberRole {
+            device_id: device_id.clone(),
+            user_id: "user-b".to_string(),
+            role: CocoonRole::Owner,
+        }).await;
+        match recv_msg(&mut app_stream).await {
+            SignalingMessage::DeviceOwnershipChanged { event } => {
+                assert!(matches!(event.action, OwnershipAction::Transferred));
+                assert_eq!(event.user_id, "user-b");
+                assert_eq!(event.actor.as_deref(), Some("user-a"));
+                assert!(matches!(event.token_type, OwnershipTokenType::AccessToken));
             }
-            info!(room_id = %room_id, granted_to = %target_uid, "Access granted");
+            other => panic!("Expected DeviceOwnershipChanged, got: {:?}", other),
+        }
+        drain_pending(&mut app_stream).await;
+
+        // Past owners can still read the history
+        send(&mut app_sink, &SignalingMessage::DeviceOwnershipHistory { device_id: device_id.clone() }).await;
+        match recv_msg(&mut app_stream).await {
+            SignalingMessage::DeviceOwnershipHistoryResponse { events, .. } => {
+                assert_eq!(events.len(), 2);
+                assert!(matches!(events[0].action, OwnershipAction::Claimed));
+                assert!(matches!(events[1].action, OwnershipAction::Transferred));
+            }
+            other => panic!("Expected DeviceOwnershipHistoryResponse, got: {:?}", other),
+        }
 
-            send_msg(tx, &SignalingMessage::RoomGrantAccessResponse {
-                room_id: room_id.clone(),
-                user_id: target_uid.clone(),
-            });
+        // Strangers cannot
+        let (ws_other, _) = connect_async(&url).await.unwrap();
+        let (mut other_sink, mut other_stream) = ws_other.split();
+        let _ = recv_msg(&mut other_stream).await;
+        send(&mut other_sink, &SignalingMessage::AuthAuthenticate { access_token: make_jwt("user-c") }).await;
+        drain_pending(&mut other_stream).await;
+        send(&mut other_sink, &SignalingMessage::DeviceOwnershipHistory { device_id }).await;
+        assert!(matches!(recv_msg(&mut other_stream).await, SignalingMessage::SystemError { .. }));
+    }
 
-            notify_room_updated(state, room_id);
-            true
-        }
+    #[tokio::test]
+    async fn test_cocoon_member_roles() {
+        let url = spawn_server().await;
 
-        SignalingMessage::RoomRevokeAccess { room_id, user_id: target_uid } => {
-            let Some(uid) = user_id else {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Authentication required for room operations".to_string(),
-                });
-                return true;
-            };
+        let (ws, _) = connect_async(&format!("{}?kind=cocoon", url)).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
+        let register = |claimant: &str| SignalingMessage::DeviceRegister {
+            secret: "xK9mP2qR7wL4nJ6vB8cT3fY5hA0gD1eS".to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: Some(HashMap::from([("setup_token".to_string(), make_jwt(claimant))])),
+            device_type: None,
+            device_config: None,
+        };
 
-            if !state.user_has_room_access(room_id, uid) {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "No access to this room".to_string(),
-                });
-                return true;
+        // The first claimant owns the device, a later one joins as a viewer
+        send(&mut sink, &register("owner")).await;
+        let device_id = match recv_msg(&mut stream).await {
+            SignalingMessage::DeviceRegisterResponse { device_id, tags } => {
+                assert_eq!(tags.unwrap()["owner_id"], "owner");
+                device_id
             }
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        };
+        send(&mut sink, &register("guest")).await;
+        match recv_msg(&mut stream).await {
+            SignalingMessage::DeviceRegisterResponse { tags, .. } => assert_eq!(tags.unwrap()["owner_id"], "owner"),
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        }
 
-            let is_owner_target = state.rooms.get(room_id)
-                .map(|r| r.owner_user_id == *target_uid)
-                .unwrap_or(false);
-            if is_owner_target {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Cannot revoke access from the room owner".to_string(),
-                });
-                return true;
+        let app = |user: &'static str| {
+            let url = url.clone();
+            async move {
+                let (ws, _) = connect_async(&url).await.unwrap();
+                let (mut sink, mut stream) = ws.split();
+                let _ = recv_msg(&mut stream).await;
+                send(&mut sink, &SignalingMessage::AuthAuthenticate { access_token: make_jwt(user) }).await;
+                drain_pending(&mut stream).await;
+                (sink, stream)
             }
-
-            if let Some(mut room) = state.rooms.get_mut(room_id) {
-                room.granted_users.remove(target_uid.as_str());
+        };
+        let (mut owner_sink, mut owner_stream) = app("owner").await;
+        let (mut guest_sink, mut guest_stream) = app("guest").await;
+        let (mut other_sink, mut other_stream) = app("stranger").await;
+
+        // Members reach the device with their role; strangers do not
+        let sync = SignalingMessage::SyncData {
+            payload: serde_json::json!({ "to": device_id, "data": {"type": "ping"} }),
+            priority: None,
+        };
+        send(&mut guest_sink, &sync).await;
+        match recv_msg(&mut stream).await {
+            SignalingMessage::SyncData { payload, .. } => {
+                let sender: VerifiedSender = serde_json::from_value(payload["sender"].clone()).unwrap();
+                assert_eq!(sender.user_id.as_deref(), Some("guest"));
+                assert!(matches!(sender.role, Some(CocoonRole::Viewer)));
             }
-            info!(room_id = %room_id, revoked_from = %target_uid, "Access revoked");
-
-            send_msg(tx, &SignalingMessage::RoomRevokeAccessResponse {
-                room_id: room_id.clone(),
-                user_id: target_uid.clone(),
-            });
-
-            notify_room_updated(state, room_id);
-            true
+            other => panic!("Expected SyncData, got: {:?}", other),
+        }
+        send(&mut other_sink, &sync).await;
+        assert!(matches!(recv_msg(&mut other_stream).await, SignalingMessage::SystemError { .. }));
+
+        send(&mut guest_sink, &SignalingMessage::DeviceListMembers { device_id: device_id.clone() }).await;
+        match recv_msg(&mut guest_stream).await {
+            SignalingMessage::DeviceListMembersResponse { members, .. } => {
+                let roles: Vec<_> = members.iter().map(|m| (m.user_id.as_str(), m.role.to_string())).collect();
+                assert_eq!(roles, vec![("owner", "owner".to_string()), ("guest", "viewer".to_string())]);
+            }
+            other => panic!("Expected DeviceListMembersResponse, got: {:?}", other),
+        }
+        send(&mut other_sink, &SignalingMessage::DeviceListMembers { device_id: device_id.clone() }).await;
+        assert!(matches!(recv_msg(&mut other_stream).await, SignalingMessage::SystemError { .. }));
+
+        // Viewers neither operate the device nor raise themselves
+        let push = SignalingMessage::DeviceConfigPush {
+            device_ids: Some(vec![device_id.clone()]),
+            label_selector: None,
+            config_patch: serde_json::json!({"log_level": "debug"}),
+            version: 1,
+        };
+        send(&mut guest_sink, &push).await;
+        assert!(matches!(recv_msg(&mut guest_stream).await, SignalingMessage::SystemError { .. }));
+        let raise = |role: CocoonRole| SignalingMessage::DeviceUpdateMemberRole {
+            device_id: device_id.clone(),
+            user_id: "guest".to_string(),
+            role,
+        };
+        send(&mut guest_sink, &raise(CocoonRole::Operator)).await;
+        assert!(matches!(recv_msg(&mut guest_stream).await, SignalingMessage::SystemError { .. }));
+
+        // The owner raises them to operator, who may then push config
+        send(&mut owner_sink, &raise(CocoonRole::Operator)).await;
+        match recv_msg(&mut owner_stream).await {
+            SignalingMessage::DeviceUpdateMemberRoleResponse { member, .. } => {
+                assert_eq!(member.user_id, "guest");
+                assert!(matches!(member.role, CocoonRole::Operator));
+            }
+            other => panic!("Expected DeviceUpdateMemberRoleResponse, got: {:?}", other),
+        }
+        send(&mut guest_sink, &push).await;
+        assert!(matches!(recv_msg(&mut stream).await, SignalingMessage::DeviceConfigPush { .. }));
+        match recv_msg(&mut guest_stream).await {
+            SignalingMessage::DeviceConfigPushResponse { sent_to, .. } => assert_eq!(sent_to, vec![device_id.clone()]),
+            other => panic!("Expected DeviceConfigPushResponse, got: {:?}", other),
         }
 
-        SignalingMessage::RoomList => {
-            let Some(uid) = user_id else {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Authentication required for room operations".to_string(),
-                });
-                return true;
-            };
+        // The owner cannot leave; other members can, and lose access
+        let remove = |user: &str| SignalingMessage::DeviceRemoveMember {
+            device_id: device_id.clone(),
+            user_id: user.to_string(),
+        };
+        send(&mut owner_sink, &remove("owner")).await;
+        assert!(matches!(recv_msg(&mut owner_stream).await, SignalingMessage::SystemError { .. }));
+        send(&mut guest_sink, &remove("guest")).await;
+        assert!(matches!(
+            recv_msg(&mut guest_stream).await,
+            SignalingMessage::DeviceRemoveMemberResponse { .. }
+        ));
+        send(&mut guest_sink, &sync).await;
+        assert!(matches!(recv_msg(&mut guest_stream).await, SignalingMessage::SystemError { .. }));
+    }
 
-            let rooms: Vec<RoomInfo> = state
-                .get_user_rooms(uid)
-                .iter()
-                .map(|room| build_room_info(state, room))
-                .collect();
+    #[tokio::test]
+    async fn test_delegated_token_issue_and_validate() {
+        let url = spawn_server().await;
 
-            send_msg(tx, &SignalingMessage::RoomListResponse { rooms });
-            true
-        }
+        let (ws, _) = connect_async(&format!("{}?kind=cocoon", url)).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
+        send(&mut sink, &SignalingMessage::DeviceRegister {
+            secret: "xK9mP2qR7wL4nJ6vB8cT3fY5hA0gD1eS".to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: Some(HashMap::from([("setup_token".to_string(), make_jwt("owner"))])),
+            device_type: None,
+            device_config: None,
+        }).await;
+        let device_id = match recv_msg(&mut stream).await {
+            SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        };
 
-        SignalingMessage::RoomGet { room_id } => {
-            let Some(uid) = user_id else {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "Authentication required for room operations".to_string(),
-                });
-                return true;
-            };
+        let app = |user: &'static str| {
+            let url = url.clone();
+            async move {
+                let (ws, _) = connect_async(&url).await.unwrap();
+                let (mut sink, mut stream) = ws.split();
+                let _ = recv_msg(&mut stream).await;
+                send(&mut sink, &SignalingMessage::AuthAuthenticate { access_token: make_jwt(user) }).await;
+                drain_pending(&mut stream).await;
+                (sink, stream)
+            }
+        };
+        let issue = |device_id: &str, scopes: &[&str]| SignalingMessage::DeviceIssueDelegatedToken {
+            device_id: device_id.into(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            ttl_secs: 7200,
+        };
 
-            if !state.user_has_room_access(room_id, uid) {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "No access to this room".to_string(),
-                });
-                return true;
+        // Only the owner may share, and only with well-formed scopes
+        let (mut other_sink, mut other_stream) = app("stranger").await;
+        send(&mut other_sink, &issue(&device_id, &["silk:ro"])).await;
+        assert!(matches!(recv_msg(&mut other_stream).await, SignalingMessage::SystemError { .. }));
+
+        let (mut owner_sink, mut owner_stream) = app("owner").await;
+        send(&mut owner_sink, &issue(&device_id, &["Silk:rw"])).await;
+        assert!(matches!(recv_msg(&mut owner_stream).await, SignalingMessage::SystemError { .. }));
+
+        send(&mut owner_sink, &issue(&device_id, &["silk:ro", "tasks"])).await;
+        let token = match recv_msg(&mut owner_stream).await {
+            SignalingMessage::DeviceIssueDelegatedTokenResponse { token, grant } => {
+                assert_eq!(grant.device_id, device_id);
+                assert_eq!(grant.issued_by, "owner");
+                assert_eq!(grant.scopes, vec!["silk:ro", "tasks"]);
+                token
             }
+            other => panic!("Expected DeviceIssueDelegatedTokenResponse, got: {:?}", other),
+        };
 
-            if let Some(room) = state.rooms.get(room_id) {
-                let info = build_room_info(state, &room);
-                send_msg(tx, &SignalingMessage::RoomGetResponse {
-                    room_id: info.room_id,
-                    owner_user_id: info.owner_user_id,
-                    granted_users: info.granted_users,
-                    actors: info.actors,
-                });
+        // The device checks the token a client presented
+        send(&mut sink, &SignalingMessage::DeviceValidateDelegatedToken { token: token.clone() }).await;
+        match recv_msg(&mut stream).await {
+            SignalingMessage::DeviceValidateDelegatedTokenResponse { token: t, valid, grant } => {
+                assert_eq!(t, token);
+                assert!(valid);
+                assert_eq!(grant.unwrap().scopes, vec!["silk:ro", "tasks"]);
             }
-            true
+            other => panic!("Expected DeviceValidateDelegatedTokenResponse, got: {:?}", other),
         }
 
-        SignalingMessage::RoomSend { room_id, to, payload } => {
-            let has_access = match kind {
-                ClientKind::App => user_id.map(|uid| state.user_has_room_access(room_id, uid)).unwrap_or(false),
-                ClientKind::Cocoon => device_id
-                    .and_then(|did| state.device_rooms.get(did))
-                    .map(|rooms| rooms.contains(room_id))
-                    .unwrap_or(false),
-                ClientKind::Hive => false,
-            };
-
-            if !has_access {
-                send_msg(tx, &SignalingMessage::SystemError {
-                    message: "No access to this room".to_string(),
-                });
-                return true;
+        send(&mut sink, &SignalingMessage::DeviceValidateDelegatedToken { token: "forged".to_string() }).await;
+        match recv_msg(&mut stream).await {
+            SignalingMessage::DeviceValidateDelegatedTokenResponse { valid, grant, .. } => {
+                assert!(!valid);
+                assert!(grant.is_none());
             }
+            other => panic!("Expected DeviceValidateDelegatedTokenResponse, got: {:?}", other),
+        }
 
-            let sender_id = match kind {
-                ClientKind::Cocoon => device_id.unwrap_or("unknown"),
-                ClientKind::App => user_id.unwrap_or("unknown"),
-                ClientKind::Hive => device_id.unwrap_or("unknown"),
-            };
-
-            if let Some(target) = to {
-                let target_in_room = state.rooms.get(room_id)
-                    .map(|r| r.actors.contains(target))
-                    .unwrap_or(false);
-
-                if !target_in_room {
-                    send_msg(tx, &SignalingMessage::SystemError {
-                        message: "Target device is not in this room".to_string(),
-                    });
-                    return true;
-                }
+        // Only the owner and token holders reach the device, stamped with who they are
+        let sync = |token: Option<&str>| SignalingMessage::SyncData {
+            payload: serde_json::json!({
+                "to": device_id,
+                "data": {"type": "webrtc_start_session", "sender": {"user_id": "owner"}},
+                "delegated_token": token,
+            }),
+            priority: None,
+        };
+        send(&mut other_sink, &sync(None)).await;
+        assert!(matches!(recv_msg(&mut other_stream).await, SignalingMessage::SystemError { .. }));
+
+        send(&mut owner_sink, &sync(None)).await;
+        match recv_msg(&mut stream).await {
+            SignalingMessage::SyncData { payload, .. } => {
+                let sender: VerifiedSender = serde_json::from_value(payload["sender"].clone()).unwrap();
+                assert_eq!(sender.user_id.as_deref(), Some("owner"));
+                assert!(matches!(sender.role, Some(CocoonRole::Owner)));
+                assert!(sender.grant.is_none());
+            }
+            other => panic!("Expected SyncData, got: {:?}", other),
+        }
 
-                if let Some(peer_tx) = state.connections.get(target) {
-                    send_msg(peer_tx.value(), &SignalingMessage::RoomSend {
-                        room_id: room_id.clone(),
-                        to: None,
-                        payload: payload.clone(),
-                    });
-                }
-            } else {
-                if let Ok(json) = serde_json::to_string(&SignalingMessage::RoomSend {
-                    room_id: room_id.clone(),
-                    to: None,
-                    payload: payload.clone(),
-                }) {
-                    state.notify_room(room_id, &json, device_id);
-                }
+        send(&mut other_sink, &sync(Some(&token))).await;
+        let token_id = match recv_msg(&mut stream).await {
+            SignalingMessage::SyncData { payload, .. } => {
+                let sender: VerifiedSender = serde_json::from_value(payload["sender"].clone()).unwrap();
+                assert_eq!(sender.user_id.as_deref(), Some("stranger"));
+                sender.grant.unwrap().token_id
             }
+            other => panic!("Expected SyncData, got: {:?}", other),
+        };
 
-            debug!(room_id = %room_id, sender = %sender_id, "Room message routed");
-            true
+        // Only the issuer or owner revokes; the device hears about it
+        let revoke = SignalingMessage::DeviceRevokeDelegatedToken { token_id: token_id.clone() };
+        send(&mut other_sink, &revoke).await;
+        assert!(matches!(
+            recv_msg(&mut other_stream).await,
+            SignalingMessage::DeviceRevokeDelegatedTokenResponse { revoked: false, .. }
+        ));
+        send(&mut owner_sink, &revoke).await;
+        assert!(matches!(
+            recv_msg(&mut owner_stream).await,
+            SignalingMessage::DeviceRevokeDelegatedTokenResponse { revoked: true, .. }
+        ));
+        match recv_msg(&mut stream).await {
+            SignalingMessage::DeviceRevokeDelegatedToken { token_id: t } => assert_eq!(t, token_id),
+            other => panic!("Expected DeviceRevokeDelegatedToken, got: {:?}", other),
         }
 
-        _ => false,
+        send(&mut other_sink, &sync(Some(&token))).await;
+        assert!(matches!(recv_msg(&mut other_stream).await, SignalingMessage::SystemError { .. }));
     }
-}
 
-fn send_msg(tx: &mpsc::UnboundedSender<String>, msg: &SignalingMessage) {
-    if let Ok(json) = serde_json::to_string(msg) {
-        let _ = tx.send(json);
-    }
-}
+    #[tokio::test]
+    async fn test_config_push_by_label_and_applied_report() {
+        let url = spawn_server().await;
+        let cocoon_url = format!("{}?kind=cocoon", url);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use axum::{Router, routing::get};
-    use futures::{SinkExt, StreamExt};
-    use lib_signaling_protocol::SignalingMessage;
-    use std::collections::HashMap;
-    use tokio_tungstenite::{connect_async, tungstenite::Message as TsMessage};
+        let mut cocoons = Vec::new();
+        for (secret, region) in [
+            ("aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV", "eu"),
+            ("xY9wV8uT7sR6qP5oN4mL3kJ2iH1gF0eD", "us"),
+        ] {
+            let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+            let (mut sink, mut stream) = ws.split();
+            send(&mut sink, &SignalingMessage::DeviceRegister {
+                secret: secret.to_string(),
+                device_id: None,
+                version: "1.0.0".to_string(),
+                tags: Some(HashMap::from([
+                    ("setup_token".to_string(), make_jwt("owner")),
+                    ("region".to_string(), region.to_string()),
+                ])),
+                device_type: Some("cocoon".to_string()),
+                device_config: None,
+            }).await;
+            let device_id = match recv_msg(&mut stream).await {
+                SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
+                other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+            };
+            drain_pending(&mut stream).await;
+            cocoons.push((sink, stream, device_id));
+        }
 
-    /// Drain all pending messages from a stream with a short timeout.
-    async fn drain_pending(
-        stream: &mut (impl StreamExt<Item = Result<TsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin),
-    ) {
-        loop {
-            match tokio::time::timeout(std::time::Duration::from_millis(50), stream.next()).await {
-                Ok(Some(Ok(_))) => continue,
-                _ => break,
+        let (ws, _) = connect_async(&url).await.unwrap();
+        let (mut owner_sink, mut owner_stream) = ws.split();
+        let _ = recv_msg(&mut owner_stream).await;
+        send(&mut owner_sink, &SignalingMessage::AuthAuthenticate { access_token: make_jwt("owner") }).await;
+        drain_pending(&mut owner_stream).await;
+
+        let patch = serde_json::json!({ "log_level": "debug" });
+        send(&mut owner_sink, &SignalingMessage::DeviceConfigPush {
+            device_ids: None,
+            label_selector: Some(HashMap::from([("region".to_string(), "eu".to_string())])),
+            config_patch: patch.clone(),
+            version: 3,
+        }).await;
+        match recv_msg(&mut owner_stream).await {
+            SignalingMessage::DeviceConfigPushResponse { version, sent_to, offline } => {
+                assert_eq!(version, 3);
+                assert_eq!(sent_to, vec![cocoons[0].2.clone()]);
+                assert!(offline.is_empty());
             }
+            other => panic!("Expected DeviceConfigPushResponse, got: {:?}", other),
         }
-    }
-
-    async fn spawn_server() -> String {
-        spawn_server_with_auth(None).await
-    }
-
-    async fn spawn_server_with_auth(auth_domain: Option<String>) -> String {
-        let state = AppState::new(
-            "test-salt-for-deterministic-ids".to_string(),
-            auth_domain,
-            true,
-            vec![],
-        );
-        let app = Router::new()
-            .route("/ws", get(ws_handler))
-            .with_state(state);
 
-        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
-        let addr = listener.local_addr().unwrap();
-        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let (eu_sink, eu_stream, eu_id) = &mut cocoons[0];
+        match recv_msg(eu_stream).await {
+            SignalingMessage::DeviceConfigPush { config_patch, version, .. } => {
+                assert_eq!(config_patch, patch);
+                assert_eq!(version, 3);
+            }
+            other => panic!("Expected DeviceConfigPush, got: {:?}", other),
+        }
 
-        format!("ws://127.0.0.1:{}/ws", addr.port())
-    }
+        // The report reaches the owner under the sender's real id
+        send(eu_sink, &SignalingMessage::DeviceConfigApplied {
+            device_id: "someone-else".into(),
+            version: 3,
+            success: true,
+            error: None,
+        }).await;
+        match recv_msg(&mut owner_stream).await {
+            SignalingMessage::DeviceConfigApplied { device_id, version, success, .. } => {
+                assert_eq!(device_id, *eu_id);
+                assert_eq!(version, 3);
+                assert!(success);
+            }
+            other => panic!("Expected DeviceConfigApplied, got: {:?}", other),
+        }
 
-    async fn recv_msg(
-        stream: &mut (impl StreamExt<Item = Result<TsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin),
-    ) -> SignalingMessage {
-        loop {
-            match stream.next().await.unwrap().unwrap() {
-                TsMessage::Text(t) => return serde_json::from_str(&t).unwrap(),
-                _ => continue,
+        // ADI usage reaches the owner the same way
+        send(eu_sink, &SignalingMessage::DeviceHeartbeat {
+            device_id: "someone-else".into(),
+            adi_usage: vec![lib_signaling_protocol::AdiServiceUsage {
+                client: "user-1".to_string(),
+                service: "adi.tasks".to_string(),
+                requests: 3,
+                bytes_in: 120,
+                bytes_out: 480,
+                stream_ms: 0,
+                throttled: 0,
+                rejected: 1,
+            }],
+        }).await;
+        match recv_msg(&mut owner_stream).await {
+            SignalingMessage::DeviceHeartbeat { device_id, adi_usage } => {
+                assert_eq!(device_id, *eu_id);
+                assert_eq!(adi_usage[0].requests, 3);
             }
+            other => panic!("Expected DeviceHeartbeat, got: {:?}", other),
         }
-    }
 
-    async fn send(
-        sink: &mut (impl SinkExt<TsMessage> + Unpin),
-        msg: &SignalingMessage,
-    ) {
-        let json = serde_json::to_string(msg).unwrap();
-        sink.send(TsMessage::Text(json.into())).await.ok();
+        let (_, us_stream, us_id) = &mut cocoons[1];
+        let us_id = us_id.clone();
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(100), us_stream.next()).await.is_err());
+
+        // Devices of other users cannot be targeted by id
+        let (ws, _) = connect_async(&url).await.unwrap();
+        let (mut other_sink, mut other_stream) = ws.split();
+        let _ = recv_msg(&mut other_stream).await;
+        send(&mut other_sink, &SignalingMessage::AuthAuthenticate { access_token: make_jwt("stranger") }).await;
+        drain_pending(&mut other_stream).await;
+        send(&mut other_sink, &SignalingMessage::DeviceConfigPush {
+            device_ids: Some(vec![us_id]),
+            label_selector: None,
+            config_patch: patch,
+            version: 4,
+        }).await;
+        assert!(matches!(recv_msg(&mut other_stream).await, SignalingMessage::SystemError { .. }));
     }
 
     #[tokio::test]
-    async fn test_app_gets_auth_hello() {
-        let url = spawn_server().await;
-        // Default kind=app
-        let (ws, _) = connect_async(&url).await.unwrap();
-        let (mut _sink, mut stream) = ws.split();
+    async fn test_capability_deltas_and_resync() {
+        use lib_signaling_protocol::{Capability, CapabilitySet};
 
-        let hello = recv_msg(&mut stream).await;
-        match hello {
-            SignalingMessage::AuthHello {
-                auth_kind,
-                auth_requirement,
-                auth_options,
-                ..
-            } => {
-                assert_eq!(auth_kind, "none");
-                assert!(matches!(auth_requirement, AuthRequirement::Optional));
-                assert_eq!(auth_options.len(), 1);
-                assert!(matches!(auth_options[0], AuthOption::Anonymous));
-            }
-            other => panic!("Expected AuthHello, got: {:?}", other),
+        let cap = |protocol: &str, version: &str| Capability {
+            protocol: protocol.to_string(),
+            version: version.to_string(),
+        };
+
+        let url = spawn_server().await;
+        let (ws, _) = connect_async(&format!("{}?kind=cocoon", url)).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
+        send(&mut sink, &register("aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV")).await;
+        assert!(matches!(recv_msg(&mut stream).await, SignalingMessage::DeviceRegisterResponse { .. }));
+
+        let mut device = CapabilitySet::new([cap("tasks", "1.0.0")], 1);
+        send(&mut sink, &device.to_update_message()).await;
+        let delta = device.replace([cap("tasks", "1.1.0"), cap("llm.chat", "1.0.0")]).unwrap();
+        send(&mut sink, &delta.clone().into_message()).await;
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(100), stream.next()).await.is_err());
+
+        // Sent again, the delta no longer applies to the set the server holds
+        send(&mut sink, &delta.into_message()).await;
+        match recv_msg(&mut stream).await {
+            SignalingMessage::CapabilityResync { version } => assert_eq!(version, 2),
+            other => panic!("Expected CapabilityResync, got: {:?}", other),
         }
+
+        send(&mut sink, &SignalingMessage::CapabilityUpdate {
+            capabilities: device.to_vec(),
+            version: 3,
+            hash: "0000000000000000".to_string(),
+        }).await;
+        assert!(matches!(recv_msg(&mut stream).await, SignalingMessage::SystemError { .. }));
     }
 
     #[tokio::test]
-    async fn test_cocoon_registration_flow() {
+    async fn test_cocoon_to_cocoon_sync_data() {
         let url = spawn_server().await;
         let cocoon_url = format!("{}?kind=cocoon", url);
-        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
-        let (mut sink, mut stream) = ws.split();
 
-        // 1. No AuthHello — cocoon sends DeviceRegister directly
-        let secret = "xK9mP2qR7wL4nJ6vB8cT3fY5hA0gD1eS";
-        let tags = HashMap::from([
-            ("kind".to_string(), "desktop".to_string()),
-            ("os".to_string(), "macos".to_string()),
-        ]);
+        let mut cocoons = Vec::new();
+        for (secret, owner) in [
+            ("aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV", "user-123"),
+            ("xY9wV8uT7sR6qP5oN4mL3kJ2iH1gF0eD", "user-123"),
+            ("mN4bV5cX6zL7kJ8hG9fD0sA1pO2iU3yT", "user-999"),
+        ] {
+            let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+            let (mut sink, mut stream) = ws.split();
+            send(&mut sink, &SignalingMessage::DeviceRegister {
+                secret: secret.to_string(),
+                device_id: None,
+                version: "1.0.0".to_string(),
+                tags: Some(HashMap::from([("setup_token".to_string(), make_jwt(owner))])),
+                device_type: Some("cocoon".to_string()),
+                device_config: None,
+            }).await;
+            let device_id = match recv_msg(&mut stream).await {
+                SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
+                other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+            };
+            drain_pending(&mut stream).await;
+            cocoons.push((sink, stream, device_id));
+        }
+        let b_id = cocoons[1].2.clone();
+        let c_id = cocoons[2].2.clone();
 
-        send(&mut sink, &SignalingMessage::DeviceRegister {
-            secret: secret.to_string(),
-            device_id: None,
-            version: "1.0.0".to_string(),
-            tags: Some(tags.clone()),
-            device_type: Some("cocoon".to_string()),
-            device_config: Some(serde_json::json!({"image": "ubuntu"})),
+        // Same owner: delivered with the sender attached
+        send(&mut cocoons[0].0, &SignalingMessage::SyncData {
+            payload: serde_json::json!({"to": b_id, "data": {"type": "offer", "sdp": "v=0"}}),
+            priority: None,
         }).await;
-
-        // 2. Server responds with device_id + tags
-        let registered = recv_msg(&mut stream).await;
-        let device_id = match registered {
-            SignalingMessage::DeviceRegisterResponse { ref device_id, ref tags } => {
-                assert!(!device_id.is_empty(), "device_id should be non-empty");
-                let t = tags.as_ref().unwrap();
-                assert_eq!(t["kind"], "desktop");
-                assert_eq!(t["os"], "macos");
-                device_id.clone()
+        match recv_msg(&mut cocoons[1].1).await {
+            SignalingMessage::SyncData { payload, .. } => {
+                assert_eq!(payload["from"], cocoons[0].2.as_str());
+                assert_eq!(payload["data"]["type"], "offer");
             }
-            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
-        };
+            other => panic!("Expected SyncData, got: {:?}", other),
+        }
 
-        // 3. Re-register with same secret
-        send(&mut sink, &SignalingMessage::DeviceRegister {
-            secret: secret.to_string(),
-            device_id: Some(device_id.clone()),
-            version: "1.0.1".to_string(),
-            tags: Some(HashMap::from([("kind".into(), "laptop".into())])),
-            device_type: Some("cocoon".to_string()),
-            device_config: None,
+        // Another owner's cocoon is refused
+        send(&mut cocoons[0].0, &SignalingMessage::SyncData {
+            payload: serde_json::json!({"to": c_id, "data": {"type": "offer"}}),
+            priority: None,
         }).await;
+        assert!(matches!(recv_msg(&mut cocoons[0].1).await, SignalingMessage::SystemError { .. }));
+    }
 
-        let re_registered = recv_msg(&mut stream).await;
-        match re_registered {
-            SignalingMessage::DeviceRegisterResponse { device_id: did, tags: t } => {
-                assert_eq!(did, device_id, "Same secret must produce same device_id");
-                assert_eq!(t.as_ref().unwrap()["kind"], "laptop");
-            }
-            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
-        }
+    #[tokio::test]
+    async fn test_capability_requests_are_routed_by_semver() {
+        use lib_signaling_protocol::{Capability, CapabilitySet, CapabilityUnavailableReason};
 
-        // 4. Update tags
-        let new_tags = HashMap::from([
-            ("kind".to_string(), "laptop".to_string()),
-            ("expose_ip".to_string(), "true".to_string()),
-        ]);
-        send(&mut sink, &SignalingMessage::DeviceUpdateTags { tags: new_tags.clone() }).await;
+        let cap = |version: &str| Capability {
+            protocol: "llm.chat".to_string(),
+            version: version.to_string(),
+        };
 
-        let tags_updated = recv_msg(&mut stream).await;
-        match tags_updated {
-            SignalingMessage::DeviceUpdateTagsResponse { device_id: did, tags: t } => {
-                assert_eq!(did, device_id);
-                assert_eq!(t["expose_ip"], "true");
-                assert_eq!(t["kind"], "laptop");
+        let url = spawn_server().await;
+        let cocoon_url = format!("{}?kind=cocoon", url);
+        let mut cocoons = Vec::new();
+        for (secret, owner, offered) in [
+            ("aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV", "user-123", None),
+            ("xY9wV8uT7sR6qP5oN4mL3kJ2iH1gF0eD", "user-123", Some("1.2.0")),
+            ("mN4bV5cX6zL7kJ8hG9fD0sA1pO2iU3yT", "user-123", Some("1.5.1")),
+            ("qW1eR2tY3uI4oP5aS6dF7gH8jK9lZ0xC", "user-999", Some("1.9.0")),
+        ] {
+            let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+            let (mut sink, mut stream) = ws.split();
+            send(&mut sink, &SignalingMessage::DeviceRegister {
+                secret: secret.to_string(),
+                device_id: None,
+                version: "1.0.0".to_string(),
+                tags: Some(HashMap::from([("setup_token".to_string(), make_jwt(owner))])),
+                device_type: Some("cocoon".to_string()),
+                device_config: None,
+            }).await;
+            let device_id = match recv_msg(&mut stream).await {
+                SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
+                other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+            };
+            drain_pending(&mut stream).await;
+            if let Some(version) = offered {
+                send(&mut sink, &CapabilitySet::new([cap(version)], 1).to_update_message()).await;
             }
-            other => panic!("Expected DeviceUpdateTagsResponse, got: {:?}", other),
+            cocoons.push((sink, stream, device_id));
         }
-
-        // 5. Query devices
-        send(&mut sink, &SignalingMessage::DeviceQueryDevices {
-            tag_filter: HashMap::from([("kind".into(), "laptop".into())]),
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let a_id = cocoons[0].2.clone();
+        let c_id = cocoons[2].2.clone();
+
+        // The highest compatible version among the owner's devices answers
+        send(&mut cocoons[0].0, &SignalingMessage::CapabilityRequest {
+            request_id: "req-1".into(),
+            capability: cap("^1.2"),
+            payload: serde_json::json!({"prompt": "hi"}),
+            prefer_device: None,
+            from_device: None,
         }).await;
+        match recv_msg(&mut cocoons[2].1).await {
+            SignalingMessage::CapabilityRequest { capability, from_device, payload, .. } => {
+                assert_eq!(capability.version, "1.5.1");
+                assert_eq!(from_device.as_deref(), Some(a_id.as_str()));
+                assert_eq!(payload["prompt"], "hi");
+            }
+            other => panic!("Expected CapabilityRequest, got: {:?}", other),
+        }
 
-        let device_list = recv_msg(&mut stream).await;
-        match device_list {
-            SignalingMessage::DeviceQueryDevicesResponse { devices } => {
-                assert_eq!(devices.len(), 1);
-                assert_eq!(devices[0].device_id, device_id);
-                assert!(devices[0].online);
-                assert_eq!(devices[0].device_type, Some("cocoon".to_string()));
+        // Only the device the request went to may answer it
+        let answer = SignalingMessage::CapabilityResponse {
+            request_id: "req-1".into(),
+            payload: serde_json::json!({"text": "hello"}),
+            error: None,
+            from_device: None,
+        };
+        send(&mut cocoons[1].0, &answer).await;
+        assert!(matches!(recv_msg(&mut cocoons[1].1).await, SignalingMessage::SystemError { .. }));
+        send(&mut cocoons[2].0, &answer).await;
+        match recv_msg(&mut cocoons[0].1).await {
+            SignalingMessage::CapabilityResponse { request_id, payload, from_device, .. } => {
+                assert_eq!(request_id, "req-1");
+                assert_eq!(payload["text"], "hello");
+                assert_eq!(from_device.as_deref(), Some(c_id.as_str()));
             }
-            other => panic!("Expected DeviceQueryDevicesResponse, got: {:?}", other),
+            other => panic!("Expected CapabilityResponse, got: {:?}", other),
         }
 
-        // 6. Deregister
-        send(&mut sink, &SignalingMessage::DeviceDeregister {
-            device_id: device_id.clone(),
-            reason: Some("test cleanup".to_string()),
+        // Nothing compatible: the closest offer comes back, never another owner's
+        send(&mut cocoons[0].0, &SignalingMessage::CapabilityRequest {
+            request_id: "req-2".into(),
+            capability: cap("^2"),
+            payload: serde_json::Value::Null,
+            prefer_device: None,
+            from_device: None,
         }).await;
+        match recv_msg(&mut cocoons[0].1).await {
+            SignalingMessage::CapabilityUnavailable { request_id, reason, closest_match } => {
+                assert_eq!(request_id, "req-2");
+                assert!(matches!(reason, CapabilityUnavailableReason::IncompatibleVersion));
+                assert_eq!(closest_match.unwrap().version, "1.5.1");
+            }
+            other => panic!("Expected CapabilityUnavailable, got: {:?}", other),
+        }
 
-        let deregistered = recv_msg(&mut stream).await;
-        match deregistered {
-            SignalingMessage::DeviceDeregisterResponse { device_id: did } => {
-                assert_eq!(did, device_id);
+        // A request still pending when its target goes away fails
+        send(&mut cocoons[0].0, &SignalingMessage::CapabilityRequest {
+            request_id: "req-3".into(),
+            capability: cap("1.2"),
+            payload: serde_json::Value::Null,
+            prefer_device: None,
+            from_device: None,
+        }).await;
+        assert!(matches!(recv_msg(&mut cocoons[2].1).await, SignalingMessage::CapabilityRequest { .. }));
+        let (mut c_sink, _, _) = cocoons.remove(2);
+        c_sink.close().await.ok();
+        match recv_msg(&mut cocoons[0].1).await {
+            SignalingMessage::CapabilityResponse { request_id, error, .. } => {
+                assert_eq!(request_id, "req-3");
+                assert!(error.is_some());
             }
-            other => panic!("Expected DeviceDeregisterResponse, got: {:?}", other),
+            other => panic!("Expected CapabilityResponse, got: {:?}", other),
         }
     }
 
     #[tokio::test]
-    async fn test_cocoon_rejects_weak_secret() {
+    async fn test_offline_sync_data_is_queued_and_replayed() {
         let url = spawn_server().await;
         let cocoon_url = format!("{}?kind=cocoon", url);
-        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
-        let (mut sink, mut stream) = ws.split();
-
-        send(&mut sink, &SignalingMessage::DeviceRegister {
-            secret: "short".to_string(),
+        let secrets = ["aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV", "xY9wV8uT7sR6qP5oN4mL3kJ2iH1gF0eD"];
+        let register = |secret: &str| SignalingMessage::DeviceRegister {
+            secret: secret.to_string(),
             device_id: None,
             version: "1.0.0".to_string(),
-            tags: None,
-            device_type: None,
+            tags: Some(HashMap::from([("setup_token".to_string(), make_jwt("user-123"))])),
+            device_type: Some("cocoon".to_string()),
             device_config: None,
+        };
+
+        let mut cocoons = Vec::new();
+        for secret in secrets {
+            let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+            let (mut sink, mut stream) = ws.split();
+            send(&mut sink, &register(secret)).await;
+            let device_id = match recv_msg(&mut stream).await {
+                SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
+                other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+            };
+            drain_pending(&mut stream).await;
+            cocoons.push((sink, stream, device_id));
+        }
+        let (b_sink, b_stream, b_id) = cocoons.pop().unwrap();
+        let (mut a_sink, mut a_stream, a_id) = cocoons.pop().unwrap();
+        drop((b_sink, b_stream));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        drain_pending(&mut a_stream).await;
+
+        // B is offline: held instead of dropped
+        send(&mut a_sink, &SignalingMessage::SyncData {
+            payload: serde_json::json!({"to": b_id, "data": {"type": "offer"}}),
+            priority: None,
         }).await;
+        let message_id = match recv_msg(&mut a_stream).await {
+            SignalingMessage::SyncQueuedDelivery { message_id, device_id, expires_at } => {
+                assert_eq!(device_id, b_id);
+                assert!(expires_at > unix_now());
+                message_id
+            }
+            other => panic!("Expected SyncQueuedDelivery, got: {:?}", other),
+        };
 
-        let err = recv_msg(&mut stream).await;
-        match err {
-            SignalingMessage::SystemError { message } => {
-                assert!(message.contains("Secret too short"));
+        // Nobody else can take B's messages
+        send(&mut a_sink, &SignalingMessage::SyncRetrieveQueued { device_id: b_id.clone() }).await;
+        assert!(matches!(recv_msg(&mut a_stream).await, SignalingMessage::SystemError { .. }));
+
+        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut b_sink, mut b_stream) = ws.split();
+        send(&mut b_sink, &register(secrets[1])).await;
+        let registered = recv_msg(&mut b_stream).await;
+        let retrieve = lib_signaling_protocol::retrieve_after_register(&registered).unwrap();
+        drain_pending(&mut b_stream).await;
+        send(&mut b_sink, &retrieve).await;
+
+        match recv_msg(&mut b_stream).await {
+            SignalingMessage::SyncData { payload, .. } => {
+                assert_eq!(payload["from"], a_id.as_str());
+                assert_eq!(payload["data"]["type"], "offer");
             }
-            other => panic!("Expected SystemError, got: {:?}", other),
+            other => panic!("Expected SyncData, got: {:?}", other),
+        }
+        match recv_msg(&mut b_stream).await {
+            SignalingMessage::SyncRetrieveQueuedResponse { delivered, expired, .. } => {
+                assert_eq!((delivered, expired), (1, 0));
+            }
+            other => panic!("Expected SyncRetrieveQueuedResponse, got: {:?}", other),
+        }
+
+        // The sender learns it arrived
+        match recv_msg(&mut a_stream).await {
+            SignalingMessage::SyncDeliveryReceipt { message_id: id, device_id, .. } => {
+                assert_eq!(id, message_id);
+                assert_eq!(device_id, b_id);
+            }
+            other => panic!("Expected SyncDeliveryReceipt, got: {:?}", other),
+        }
+
+        // Retrieved messages are gone
+        send(&mut b_sink, &SignalingMessage::SyncRetrieveQueued { device_id: b_id.clone() }).await;
+        match recv_msg(&mut b_stream).await {
+            SignalingMessage::SyncRetrieveQueuedResponse { delivered, .. } => assert_eq!(delivered, 0),
+            other => panic!("Expected SyncRetrieveQueuedResponse, got: {:?}", other),
         }
     }
 
     #[tokio::test]
-    async fn test_pairing_and_sync() {
+    async fn test_queued_sync_data_rechecks_sender_on_replay() {
         let url = spawn_server().await;
         let cocoon_url = format!("{}?kind=cocoon", url);
-
-        // Device A connects as cocoon
-        let (ws_a, _) = connect_async(&cocoon_url).await.unwrap();
-        let (mut sink_a, mut stream_a) = ws_a.split();
-
-        send(&mut sink_a, &SignalingMessage::DeviceRegister {
+        let register = SignalingMessage::DeviceRegister {
             secret: "aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV".to_string(),
             device_id: None,
             version: "1.0.0".to_string(),
-            tags: None,
+            tags: Some(HashMap::from([("setup_token".to_string(), make_jwt("owner"))])),
             device_type: None,
             device_config: None,
-        }).await;
-        let reg_a = recv_msg(&mut stream_a).await;
-        let id_a = match reg_a {
+        };
+        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
+        send(&mut sink, &register).await;
+        let device_id = match recv_msg(&mut stream).await {
             SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
             other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
         };
-
-        // Device A creates pairing code
-        send(&mut sink_a, &SignalingMessage::PairingCreateCode).await;
-        let code = match recv_msg(&mut stream_a).await {
-            SignalingMessage::PairingCreateCodeResponse { code } => code,
-            other => panic!("Expected PairingCreateCodeResponse, got: {:?}", other),
+        drop((sink, stream));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let app = |user: Option<&'static str>| {
+            let url = url.clone();
+            async move {
+                let (ws, _) = connect_async(&url).await.unwrap();
+                let (mut sink, mut stream) = ws.split();
+                let _ = recv_msg(&mut stream).await;
+                if let Some(user) = user {
+                    send(&mut sink, &SignalingMessage::AuthAuthenticate { access_token: make_jwt(user) }).await;
+                }
+                drain_pending(&mut stream).await;
+                (sink, stream)
+            }
         };
+        let (mut owner_sink, mut owner_stream) = app(Some("owner")).await;
+        let (mut holder_sink, mut holder_stream) = app(None).await;
 
-        // Device B connects as cocoon, registers, uses pairing code
-        let (ws_b, _) = connect_async(&cocoon_url).await.unwrap();
-        let (mut sink_b, mut stream_b) = ws_b.split();
-
-        send(&mut sink_b, &SignalingMessage::DeviceRegister {
-            secret: "xY9wV8uT7sR6qP5oN4mL3kJ2iH1gF0eD".to_string(),
-            device_id: None,
-            version: "1.0.0".to_string(),
-            tags: None,
-            device_type: None,
-            device_config: None,
+        send(&mut owner_sink, &SignalingMessage::DeviceIssueDelegatedToken {
+            device_id: device_id.clone(),
+            scopes: vec!["silk:ro".to_string()],
+            ttl_secs: 7200,
         }).await;
-        let reg_b = recv_msg(&mut stream_b).await;
-        let id_b = match reg_b {
-            SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
-            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        let (token, token_id) = match recv_msg(&mut owner_stream).await {
+            SignalingMessage::DeviceIssueDelegatedTokenResponse { token, grant } => (token, grant.token_id),
+            other => panic!("Expected DeviceIssueDelegatedTokenResponse, got: {:?}", other),
         };
 
-        send(&mut sink_b, &SignalingMessage::PairingUseCode { code }).await;
+        let sync = |kind: &str, token: Option<&str>| SignalingMessage::SyncData {
+            payload: serde_json::json!({
+                "to": device_id,
+                "data": {"type": kind},
+                "delegated_token": token,
+            }),
+            priority: None,
+        };
 
-        // Both should receive Paired
-        let paired_b = recv_msg(&mut stream_b).await;
-        match paired_b {
-            SignalingMessage::PairingUseCodeResponse { peer_id } => assert_eq!(peer_id, id_a),
-            other => panic!("Expected PairingUseCodeResponse for B, got: {:?}", other),
-        }
+        // Anonymous apps queue nothing without a token
+        send(&mut holder_sink, &sync("anonymous", None)).await;
+        assert!(matches!(recv_msg(&mut holder_stream).await, SignalingMessage::SystemError { .. }));
 
-        let paired_a = recv_msg(&mut stream_a).await;
-        match paired_a {
-            SignalingMessage::PairingUseCodeResponse { peer_id } => assert_eq!(peer_id, id_b),
-            other => panic!("Expected PairingUseCodeResponse for A, got: {:?}", other),
-        }
+        send(&mut owner_sink, &sync("from_owner", None)).await;
+        assert!(matches!(recv_msg(&mut owner_stream).await, SignalingMessage::SyncQueuedDelivery { .. }));
+        send(&mut holder_sink, &sync("from_holder", Some(&token))).await;
+        assert!(matches!(recv_msg(&mut holder_stream).await, SignalingMessage::SyncQueuedDelivery { .. }));
 
-        // Device A sends SyncData -> Device B receives it
-        let payload = serde_json::json!({"action": "ping", "ts": 12345});
-        send(&mut sink_a, &SignalingMessage::SyncData { payload: payload.clone() }).await;
+        // Revoked while the device was away: the holder's message is not replayed
+        send(&mut owner_sink, &SignalingMessage::DeviceRevokeDelegatedToken { token_id }).await;
+        assert!(matches!(
+            recv_msg(&mut owner_stream).await,
+            SignalingMessage::DeviceRevokeDelegatedTokenResponse { revoked: true, .. }
+        ));
 
-        let sync = recv_msg(&mut stream_b).await;
-        match sync {
-            SignalingMessage::SyncData { payload: p } => {
-                assert_eq!(p["action"], "ping");
-                assert_eq!(p["ts"], 12345);
-            }
+        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
+        send(&mut sink, &register).await;
+        let registered = recv_msg(&mut stream).await;
+        let retrieve = lib_signaling_protocol::retrieve_after_register(&registered).unwrap();
+        drain_pending(&mut stream).await;
+        send(&mut sink, &retrieve).await;
+
+        match recv_msg(&mut stream).await {
+            SignalingMessage::SyncData { payload, .. } => assert_eq!(payload["type"], "from_owner"),
             other => panic!("Expected SyncData, got: {:?}", other),
         }
+        match recv_msg(&mut stream).await {
+            SignalingMessage::SyncRetrieveQueuedResponse { delivered, expired, .. } => {
+                assert_eq!((delivered, expired), (1, 0));
+            }
+            other => panic!("Expected SyncRetrieveQueuedResponse, got: {:?}", other),
+        }
     }
 
     #[tokio::test]
-    async fn test_app_auth_required_blocks_unauthenticated() {
-        let url = spawn_server_with_auth(Some("https://auth.example.com".to_string())).await;
-        let (ws, _) = connect_async(&url).await.unwrap();
-        let (mut sink, mut stream) = ws.split();
+    async fn test_warm_pool_sizing_and_rekey() {
+        let url = spawn_server().await;
 
-        // Should receive AuthHello with required auth
-        let hello = recv_msg(&mut stream).await;
-        match hello {
-            SignalingMessage::AuthHello { auth_requirement, .. } => {
-                assert!(matches!(auth_requirement, AuthRequirement::Required));
-            }
-            other => panic!("Expected AuthHello, got: {:?}", other),
-        }
+        let (ws, _) = connect_async(&format!("{}?kind=hive", url)).await.unwrap();
+        let (mut hive_sink, mut hive_stream) = ws.split();
+        let rekey = |slot_secret: &str| SignalingMessage::HiveRekeyCocoon {
+            request_id: "req-1".into(),
+            slot_secret: slot_secret.to_string(),
+            setup_token: make_jwt("user-123"),
+            secret: "Zq8wX3vB6nM1kL4jH7gF0dS2aP5oI9uY".to_string(),
+        };
+        send(&mut hive_sink, &rekey("aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV")).await;
+        assert!(matches!(recv_msg(&mut hive_stream).await, SignalingMessage::SystemError { .. }));
 
-        // Try to send a non-auth message before authenticating
-        send(&mut sink, &SignalingMessage::DeviceQueryDevices {
-            tag_filter: HashMap::new(),
+        send(&mut hive_sink, &SignalingMessage::HiveRegister {
+            hive_id: "hive-a".into(),
+            version: "1.0.0".to_string(),
+            cocoon_kinds: vec![CocoonKind {
+                id: "linux".to_string(),
+                runner_type: "docker".to_string(),
+                runner_config: serde_json::Value::Null,
+                image: "adi/cocoon".to_string(),
+            }],
+            hive_id_signature: String::new(),
+            runners: None,
+            gpus: None,
+            singletons: None,
         }).await;
-
-        let err = recv_msg(&mut stream).await;
-        match err {
-            SignalingMessage::SystemError { message } => {
-                assert!(message.contains("Authentication required"));
+        assert!(matches!(recv_msg(&mut hive_stream).await, SignalingMessage::HiveRegisterResponse { .. }));
+        drain_pending(&mut hive_stream).await;
+
+        // Only operators size pools, and only up to the cap
+        let app = |user: &'static str| {
+            let url = url.clone();
+            async move {
+                let (ws, _) = connect_async(&url).await.unwrap();
+                let (mut sink, mut stream) = ws.split();
+                let _ = recv_msg(&mut stream).await;
+                send(&mut sink, &SignalingMessage::AuthAuthenticate { access_token: make_jwt(user) }).await;
+                drain_pending(&mut stream).await;
+                (sink, stream)
             }
-            other => panic!("Expected SystemError for unauthenticated request, got: {:?}", other),
+        };
+        let set_size = |size: u32| SignalingMessage::HiveSetPoolSize { kind: "linux".to_string(), size };
+        let (mut user_sink, mut user_stream) = app("user-123").await;
+        send(&mut user_sink, &set_size(2)).await;
+        assert!(matches!(recv_msg(&mut user_stream).await, SignalingMessage::SystemError { .. }));
+
+        let (mut op_sink, mut op_stream) = app("operator").await;
+        send(&mut op_sink, &set_size(MAX_POOL_SIZE + 1)).await;
+        assert!(matches!(recv_msg(&mut op_stream).await, SignalingMessage::SystemError { .. }));
+        send(&mut op_sink, &set_size(2)).await;
+        match recv_msg(&mut hive_stream).await {
+            SignalingMessage::HiveSetPoolSize { kind, size } => assert_eq!((kind.as_str(), size), ("linux", 2)),
+            other => panic!("Expected HiveSetPoolSize, got: {:?}", other),
         }
-    }
-
-    fn make_jwt(sub: &str) -> String {
-        use signaling_core::tokens::base64url_encode;
-        let header = base64url_encode(b"{\"alg\":\"HS256\",\"typ\":\"JWT\"}");
-        let payload = base64url_encode(format!("{{\"sub\":\"{}\"}}", sub).as_bytes());
-        let sig = base64url_encode(b"fake-signature");
-        format!("{}.{}.{}", header, payload, sig)
-    }
-
-    #[tokio::test]
-    async fn test_cocoon_setup_token_validates_owner() {
-        let url = spawn_server().await;
-        let cocoon_url = format!("{}?kind=cocoon", url);
-        let (ws, _) = connect_async(&cocoon_url).await.unwrap();
-        let (mut sink, mut stream) = ws.split();
 
-        let token = make_jwt("user-123");
-        let tags = HashMap::from([
-            ("setup_token".to_string(), token),
-            ("name".to_string(), "my-cocoon".to_string()),
-        ]);
+        // A slot that has not registered cannot be handed out
+        let slot_secret = "aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV";
+        send(&mut hive_sink, &rekey(slot_secret)).await;
+        match recv_msg(&mut hive_stream).await {
+            SignalingMessage::HiveRekeyCocoonResponse { success, error, .. } => {
+                assert!(!success);
+                assert!(error.is_some());
+            }
+            other => panic!("Expected HiveRekeyCocoonResponse, got: {:?}", other),
+        }
 
-        send(&mut sink, &SignalingMessage::DeviceRegister {
-            secret: "xK9mP2qR7wL4nJ6vB8cT3fY5hA0gD1eS".to_string(),
+        // The booted, unowned slot gets the new identity pushed to it
+        let (ws, _) = connect_async(&format!("{}?kind=cocoon", url)).await.unwrap();
+        let (mut slot_sink, mut slot_stream) = ws.split();
+        send(&mut slot_sink, &SignalingMessage::DeviceRegister {
+            secret: slot_secret.to_string(),
             device_id: None,
             version: "1.0.0".to_string(),
-            tags: Some(tags),
+            tags: None,
             device_type: Some("cocoon".to_string()),
             device_config: None,
         }).await;
-
-        let resp = recv_msg(&mut stream).await;
-        match resp {
-            SignalingMessage::DeviceRegisterResponse { ref tags, .. } => {
-                let t = tags.as_ref().unwrap();
-                // setup_token should be stripped from stored tags
-                assert!(!t.contains_key("setup_token"), "setup_token should be stripped");
-                assert_eq!(t["name"], "my-cocoon");
+        assert!(matches!(recv_msg(&mut slot_stream).await, SignalingMessage::DeviceRegisterResponse { .. }));
+        drain_pending(&mut slot_stream).await;
+
+        send(&mut hive_sink, &rekey(slot_secret)).await;
+        match recv_msg(&mut hive_stream).await {
+            SignalingMessage::HiveRekeyCocoonResponse { request_id, success, .. } => {
+                assert_eq!(request_id, "req-1");
+                assert!(success);
             }
-            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+            other => panic!("Expected HiveRekeyCocoonResponse, got: {:?}", other),
+        }
+        match recv_msg(&mut slot_stream).await {
+            SignalingMessage::DeviceRekey { setup_token, secret } => {
+                assert_eq!(setup_token, make_jwt("user-123"));
+                assert_eq!(secret, "Zq8wX3vB6nM1kL4jH7gF0dS2aP5oI9uY");
+            }
+            other => panic!("Expected DeviceRekey, got: {:?}", other),
         }
     }
 
@@ -1563,7 +3985,7 @@ mod tests {
         // Cocoon A sends targeted message to Cocoon B via room
         send(&mut sink_a, &SignalingMessage::RoomSend {
             room_id: "comm-room".to_string(),
-            to: Some(id_b.clone()),
+            to: Some(id_b.to_string()),
             payload: serde_json::json!({"action": "hello-from-a"}),
         }).await;
 
@@ -1690,4 +4112,264 @@ mod tests {
             other => panic!("Expected SystemError for invalid setup_token, got: {:?}", other),
         }
     }
+
+    fn register(secret: &str) -> SignalingMessage {
+        SignalingMessage::DeviceRegister {
+            secret: secret.to_string(),
+            device_id: None,
+            version: "1.0.0".to_string(),
+            tags: None,
+            device_type: None,
+            device_config: None,
+        }
+    }
+
+    /// Wrap `msg` for the device at the end of a chain of relay links,
+    /// outermost link first.
+    fn through(links: &[&str], msg: &SignalingMessage) -> SignalingMessage {
+        links.iter().rev().fold(msg.clone(), |inner, link| SignalingMessage::RelayFrame {
+            link_id: link.to_string(),
+            frame: serde_json::to_value(&inner).unwrap(),
+        })
+    }
+
+    /// Strip one relay frame per link, checking the link ids on the way.
+    fn unwrap_frames(mut msg: SignalingMessage, links: &[&str]) -> SignalingMessage {
+        for link in links {
+            msg = match msg {
+                SignalingMessage::RelayFrame { link_id, frame } => {
+                    assert_eq!(link_id, *link);
+                    serde_json::from_value(frame).unwrap()
+                }
+                other => panic!("Expected RelayFrame for {}, got: {:?}", link, other),
+            };
+        }
+        msg
+    }
+
+    #[tokio::test]
+    async fn test_relay_link_registers_and_routes() {
+        let url = spawn_server().await;
+        let (ws_relay, _) = connect_async(&format!("{}?kind=cocoon", url)).await.unwrap();
+        let (mut relay_sink, mut relay_stream) = ws_relay.split();
+
+        // Opening a link requires being registered
+        send(&mut relay_sink, &SignalingMessage::RelayOpen { link_id: "l1".to_string() }).await;
+        assert!(matches!(recv_msg(&mut relay_stream).await, SignalingMessage::SystemError { .. }));
+
+        send(&mut relay_sink, &register("aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV")).await;
+        assert!(matches!(
+            recv_msg(&mut relay_stream).await,
+            SignalingMessage::DeviceRegisterResponse { .. }
+        ));
+
+        // A device behind the relay registers through its link
+        send(&mut relay_sink, &SignalingMessage::RelayOpen { link_id: "l1".to_string() }).await;
+        let mut claimed = register("xY9wV8uT7sR6qP5oN4mL3kJ2iH1gF0eD");
+        if let SignalingMessage::DeviceRegister { ref mut tags, .. } = claimed {
+            *tags = Some(HashMap::from([("setup_token".to_string(), make_jwt("user-123"))]));
+        }
+        send(&mut relay_sink, &through(&["l1"], &claimed)).await;
+        let downstream_id = match unwrap_frames(recv_msg(&mut relay_stream).await, &["l1"]) {
+            SignalingMessage::DeviceRegisterResponse { device_id, .. } => device_id,
+            other => panic!("Expected DeviceRegisterResponse, got: {:?}", other),
+        };
+
+        // Its owner's traffic addressed to it comes back through the link
+        let (ws_app, _) = connect_async(&url).await.unwrap();
+        let (mut app_sink, mut app_stream) = ws_app.split();
+        let _ = recv_msg(&mut app_stream).await;
+        send(&mut app_sink, &SignalingMessage::AuthAuthenticate { access_token: make_jwt("user-123") }).await;
+        drain_pending(&mut app_stream).await;
+        send(&mut app_sink, &SignalingMessage::SyncData {
+            payload: serde_json::json!({"to": downstream_id, "data": {"action": "ping"}}),
+            priority: None,
+        }).await;
+
+        match unwrap_frames(recv_msg(&mut relay_stream).await, &["l1"]) {
+            SignalingMessage::SyncData { payload, .. } => assert_eq!(payload["action"], "ping"),
+            other => panic!("Expected SyncData, got: {:?}", other),
+        }
+
+        // Closing the link ends the downstream session
+        send(&mut relay_sink, &SignalingMessage::RelayClose {
+            link_id: "l1".to_string(),
+            reason: None,
+        }).await;
+        match recv_msg(&mut relay_stream).await {
+            SignalingMessage::RelayClose { link_id, .. } => assert_eq!(link_id, "l1"),
+            other => panic!("Expected RelayClose, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relay_loop_and_hop_limit() {
+        let url = spawn_server().await;
+        let (ws, _) = connect_async(&format!("{}?kind=cocoon", url)).await.unwrap();
+        let (mut sink, mut stream) = ws.split();
+
+        let relay_secret = "aB3cD4eF5gH6iJ7kL8mN9oP0qR1sT2uV";
+        send(&mut sink, &register(relay_secret)).await;
+        recv_msg(&mut stream).await;
+
+        // The relay cannot register again behind itself
+        send(&mut sink, &SignalingMessage::RelayOpen { link_id: "l1".to_string() }).await;
+        send(&mut sink, &through(&["l1"], &register(relay_secret))).await;
+        match unwrap_frames(recv_msg(&mut stream).await, &["l1"]) {
+            SignalingMessage::SystemError { message } => assert!(message.contains("Relay loop")),
+            other => panic!("Expected relay loop error, got: {:?}", other),
+        }
+
+        // Chain sub-relays until the hop limit refuses the next link
+        let links = ["l1", "l2", "l3", "l4", "l5"];
+        let secrets = [
+            "xY9wV8uT7sR6qP5oN4mL3kJ2iH1gF0eD",
+            "mN4bV5cX6zL7kJ8hG9fD0sA1pO2iU3yT",
+            "qW2eR3tY4uI5oP6aS7dF8gH9jK0lZ1xC",
+            "zX1cV2bN3mA4sD5fG6hJ7kL8qW9eR0tY",
+        ];
+        for depth in 1..=MAX_RELAY_HOPS {
+            let path = &links[..depth];
+            send(&mut sink, &through(path, &register(secrets[depth - 1]))).await;
+            assert!(matches!(
+                unwrap_frames(recv_msg(&mut stream).await, path),
+                SignalingMessage::DeviceRegisterResponse { .. }
+            ));
+            send(&mut sink, &through(path, &SignalingMessage::RelayOpen {
+                link_id: links[depth].to_string(),
+            })).await;
+        }
+
+        match unwrap_frames(recv_msg(&mut stream).await, &links[..MAX_RELAY_HOPS]) {
+            SignalingMessage::RelayClose { link_id, reason } => {
+                assert_eq!(link_id, links[MAX_RELAY_HOPS]);
+                assert!(reason.unwrap().contains("Hop limit"));
+            }
+            other => panic!("Expected RelayClose for hop limit, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_schedulable_kinds_skips_unavailable_runners() {
+        let kind = |id: &str, runner_type: &str| CocoonKind {
+            id: id.to_string(),
+            runner_type: runner_type.to_string(),
+            runner_config: serde_json::Value::Null,
+            image: "adi/cocoon:latest".to_string(),
+        };
+        let kinds = vec![kind("linux", "docker"), kind("rootless", "podman"), kind("bare", "process")];
+
+        let runners = vec!["podman".to_string(), "process".to_string()];
+        assert_eq!(schedulable_kinds(&kinds, Some(&runners)), vec!["rootless", "bare"]);
+
+        // Hives that do not report runners keep every kind
+        assert_eq!(schedulable_kinds(&kinds, None), vec!["linux", "rootless", "bare"]);
+    }
+
+    #[test]
+    fn test_singleton_fails_over_when_leader_goes_silent() {
+        let state = AppState::new("salt".to_string(), None, true, vec![]);
+        let start = Instant::now();
+        let mut inboxes = HashMap::new();
+        for hive_id in ["h1", "h2"] {
+            let (tx, rx) = mpsc::unbounded_channel();
+            state.connections.insert(format!("hive-{hive_id}"), tx);
+            inboxes.insert(hive_id, rx);
+            state.hives.insert(hive_id.to_string(), RegisteredHive {
+                hive_id: hive_id.to_string(),
+                connection_id: format!("hive-{hive_id}"),
+                cocoon_kinds: vec![],
+                runners: None,
+                gpu_vram_free_mb: vec![],
+                maintenance: false,
+                maintenance_reason: None,
+                singletons: vec!["app:scheduler".to_string()],
+                warm_cocoons: HashMap::new(),
+                last_heartbeat: start,
+            });
+        }
+        let mut leader_seen_by = |hive_id: &str| -> Option<(Option<String>, u64)> {
+            let text = inboxes.get_mut(hive_id).unwrap().try_recv().ok()?;
+            match serde_json::from_str(&text).unwrap() {
+                SignalingMessage::HiveSingletonLeader { leader_hive_id, fencing_token, .. } => {
+                    Some((leader_hive_id.map(Into::into), fencing_token))
+                }
+                other => panic!("Expected HiveSingletonLeader, got: {:?}", other),
+            }
+        };
+
+        elect_singletons(&state, start);
+        assert_eq!(leader_seen_by("h1"), Some((Some("h1".to_string()), 1)));
+        assert_eq!(leader_seen_by("h2"), Some((Some("h1".to_string()), 1)));
+
+        // Only h2 keeps heartbeating; nothing changes until h1 has been silent long enough
+        state.hives.get_mut("h2").unwrap().last_heartbeat = start + Duration::from_secs(60);
+        elect_singletons(&state, start + Duration::from_secs(60));
+        assert_eq!(leader_seen_by("h2"), None);
+
+        elect_singletons(&state, start + HIVE_SILENCE_TIMEOUT);
+        assert_eq!(leader_seen_by("h1"), Some((Some("h2".to_string()), 2)));
+        assert_eq!(leader_seen_by("h2"), Some((Some("h2".to_string()), 2)));
+
+        // A recovered h1 does not take the service back
+        state.hives.get_mut("h1").unwrap().last_heartbeat = start + HIVE_SILENCE_TIMEOUT;
+        elect_singletons(&state, start + HIVE_SILENCE_TIMEOUT);
+        assert_eq!(leader_seen_by("h1"), None);
+
+        state.hives.remove("h2");
+        elect_singletons(&state, start + HIVE_SILENCE_TIMEOUT);
+        assert_eq!(leader_seen_by("h1"), Some((Some("h1".to_string()), 3)));
+    }
+
+    #[test]
+    fn test_next_singleton_leader_prefers_hives_out_of_maintenance() {
+        let candidates = vec![("a".to_string(), true), ("b".to_string(), true), ("c".to_string(), false)];
+        assert_eq!(next_singleton_leader(None, &candidates, |_| false), Some("a".to_string()));
+        assert_eq!(next_singleton_leader(None, &candidates, |id| id == "a"), Some("b".to_string()));
+        assert_eq!(next_singleton_leader(Some("b"), &candidates, |_| false), Some("b".to_string()));
+        assert_eq!(next_singleton_leader(Some("c"), &candidates, |_| false), Some("a".to_string()));
+        assert_eq!(next_singleton_leader(None, &[], |_| false), None);
+    }
+
+    #[test]
+    fn test_can_place_skips_hives_in_maintenance() {
+        let mut hive = RegisteredHive {
+            hive_id: "h1".to_string(),
+            connection_id: "hive-h1".to_string(),
+            cocoon_kinds: vec!["linux".to_string()],
+            runners: None,
+            gpu_vram_free_mb: vec![],
+            maintenance: false,
+            maintenance_reason: None,
+            singletons: vec![],
+            warm_cocoons: HashMap::new(),
+            last_heartbeat: Instant::now(),
+        };
+        assert!(can_place(&hive, "linux", false, None));
+        assert!(!can_place(&hive, "macos", false, None));
+        assert!(!can_place(&hive, "linux", true, None));
+
+        hive.maintenance = true;
+        assert!(!can_place(&hive, "linux", false, None));
+        assert!(!has_warm_cocoon(&hive, "linux"));
+
+        hive.warm_cocoons.insert("linux".to_string(), 0);
+        assert!(!has_warm_cocoon(&hive, "linux"));
+        hive.warm_cocoons.insert("linux".to_string(), 2);
+        assert!(has_warm_cocoon(&hive, "linux"));
+    }
+
+    #[test]
+    fn test_meets_gpu_constraints() {
+        // CPU-only spawns fit anywhere
+        assert!(meets_gpu_constraints(&[], false, None));
+        assert!(!meets_gpu_constraints(&[], true, None));
+        assert!(meets_gpu_constraints(&[0], true, None));
+
+        // VRAM has to fit on one card
+        let vram = [8_192, 24_576];
+        assert!(meets_gpu_constraints(&vram, false, Some(16_384)));
+        assert!(!meets_gpu_constraints(&vram, true, Some(32_768)));
+        assert!(!meets_gpu_constraints(&[], false, Some(1)));
+    }
 }
@@ -0,0 +1,97 @@
+//! Criterion benchmarks for the signaling protocol
+//!
+//! Run with: cargo bench -p lib-signaling-protocol
+//!
+//! Covers the relay's hottest traffic: `sync_data` frames carrying Silk
+//! terminal output and browser debug network events. Regression thresholds
+//! that CI enforces live in `tests/throughput_budget.rs`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lib_signaling_protocol::{RelayPriority, SignalingMessage};
+use serde_json::json;
+
+type Builder = fn(usize) -> SignalingMessage;
+
+/// `len` bytes of repeated `pattern`
+fn filler(pattern: &str, len: usize) -> String {
+    pattern.chars().cycle().take(len).collect()
+}
+
+/// Silk `output` chunk as relayed from a cocoon to the web client
+fn silk_output(data_len: usize) -> SignalingMessage {
+    SignalingMessage::SyncData {
+        payload: json!({
+            "to": "4f1c9a0b-device",
+            "data": {
+                "type": "silk_output",
+                "session_id": "b7e2-session",
+                "command_id": "c91d-command",
+                "stream": "stdout",
+                "data": filler("drwxr-xr-x  12 user staff  384 src\n", data_len),
+            }
+        }),
+        priority: Some(RelayPriority::Interactive),
+    }
+}
+
+/// Browser debug `finished` network event with headers and a response body
+fn network_event(body_len: usize) -> SignalingMessage {
+    SignalingMessage::SyncData {
+        payload: json!({
+            "type": "browser_debug_network_event",
+            "token": "dbg-7a3f",
+            "event": "finished",
+            "data": {
+                "request_id": "1234.56",
+                "timestamp": 1_700_000_000_000i64,
+                "status": 200,
+                "mime_type": "application/json",
+                "response_headers": {
+                    "content-type": "application/json",
+                    "cache-control": "no-store",
+                    "x-request-id": "a1b2c3",
+                },
+                "response_body": filler("{\"id\":1,\"title\":\"task\"},", body_len),
+                "response_body_truncated": false,
+                "duration_ms": 42,
+            }
+        }),
+        priority: Some(RelayPriority::Bulk),
+    }
+}
+
+fn benchmark_messages(c: &mut Criterion) {
+    let cases: [(&str, Builder); 2] = [
+        ("silk_output", silk_output),
+        ("network_event", network_event),
+    ];
+
+    for (name, build) in cases {
+        let mut group = c.benchmark_group(name);
+
+        for len in [64, 4 * 1024, 64 * 1024] {
+            let msg = build(len);
+            let json = serde_json::to_string(&msg).unwrap();
+            group.throughput(Throughput::Bytes(json.len() as u64));
+
+            group.bench_with_input(BenchmarkId::new("serialize", len), &msg, |b, msg| {
+                b.iter(|| serde_json::to_string(black_box(msg)).unwrap())
+            });
+            group.bench_with_input(BenchmarkId::new("deserialize", len), &json, |b, json| {
+                b.iter(|| serde_json::from_str::<SignalingMessage>(black_box(json)).unwrap())
+            });
+            // What the relay does per hop: parse, then forward re-encoded
+            group.bench_with_input(BenchmarkId::new("round_trip", len), &json, |b, json| {
+                b.iter(|| {
+                    let msg: SignalingMessage = serde_json::from_str(black_box(json)).unwrap();
+                    serde_json::to_string(&msg).unwrap()
+                })
+            });
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, benchmark_messages);
+criterion_main!(benches);
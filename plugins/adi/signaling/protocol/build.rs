@@ -14,6 +14,22 @@ fn main() {
         tag: "type".to_string(),
         rename: "snake_case".to_string(),
         enum_name: "SignalingMessage".to_string(),
+        // Typed IDs from `src/ids.rs`; they are plain strings on the wire
+        field_types: [
+            ("device_id", "DeviceId"),
+            ("device_ids", "DeviceId"),
+            ("hive_id", "HiveId"),
+            ("target_hive_id", "HiveId"),
+            ("leader_hive_id", "HiveId"),
+            ("request_id", "RequestId"),
+            ("message_id", "MessageId"),
+        ]
+        .into_iter()
+        .map(|(field, ty)| (field.to_string(), format!("crate::ids::{ty}")))
+        .collect(),
+        type_attrs: vec![
+            r#"#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]"#.to_string(),
+        ],
     };
 
     Generator::new(&file, &proto_dir, "signaling")
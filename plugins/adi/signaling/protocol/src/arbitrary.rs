@@ -0,0 +1,1329 @@
+//! `proptest` strategies for the protocol types (feature `proptest`).
+//!
+//! The generated types cannot derive `Arbitrary`, so the strategies are
+//! written out here. `SignalingMessage` and the unit enums are also matched
+//! exhaustively, so a variant added to `signaling.tsp` fails to compile until
+//! it has a strategy, and the round-trip tests below check that every
+//! variant is actually generated.
+
+use crate::{
+    AdiServiceUsage, AuthOption, AuthRequirement, Capability, CapabilityUnavailableReason,
+    CocoonKind, CocoonMember, CocoonPoolStatus, CocoonRole, ConnectionInfo, DelegatedGrant,
+    DeviceId, DeviceInfo, DisconnectInfo, DisconnectReason, GpuInfo, HiveId, IceServer, MessageId,
+    OwnershipAction, OwnershipAuditEvent, OwnershipTokenType, Page, PageRequest, RelayPriority,
+    RequestId, RoomInfo, SessionId, SignalingEnvelope, SignalingMessage, VerifiedSender, WsState,
+};
+use proptest::collection::{hash_map, vec};
+use proptest::option;
+use proptest::prelude::*;
+use proptest::strategy::Union;
+use std::collections::HashMap;
+
+/// Number of `SignalingMessage` variants, see [`variant_index`].
+pub const VARIANT_COUNT: usize = 93;
+
+/// Position of `msg`'s variant in the enum. Exhaustive on purpose.
+pub fn variant_index(msg: &SignalingMessage) -> usize {
+    use SignalingMessage as M;
+    match msg {
+        M::AuthHello { .. } => 0,
+        M::AuthAuthenticate { .. } => 1,
+        M::AuthAuthenticateResponse { .. } => 2,
+        M::AuthHelloAuthed { .. } => 3,
+        M::DeviceRegister { .. } => 4,
+        M::DeviceRegisterResponse { .. } => 5,
+        M::DeviceDeregister { .. } => 6,
+        M::DeviceDeregisterResponse { .. } => 7,
+        M::DevicePeerConnected { .. } => 8,
+        M::DevicePeerDisconnected { .. } => 9,
+        M::DeviceDisconnect { .. } => 10,
+        M::DeviceUpdateTags { .. } => 11,
+        M::DeviceUpdateTagsResponse { .. } => 12,
+        M::DeviceUpdateDevice { .. } => 13,
+        M::DeviceUpdateDeviceResponse { .. } => 14,
+        M::DeviceQueryDevices { .. } => 15,
+        M::DeviceQueryDevicesResponse { .. } => 16,
+        M::DeviceDeviceListUpdated { .. } => 17,
+        M::DeviceOwnershipChanged { .. } => 18,
+        M::DeviceOwnershipHistory { .. } => 19,
+        M::DeviceOwnershipHistoryResponse { .. } => 20,
+        M::DeviceListMembers { .. } => 21,
+        M::DeviceListMembersResponse { .. } => 22,
+        M::DeviceUpdateMemberRole { .. } => 23,
+        M::DeviceUpdateMemberRoleResponse { .. } => 24,
+        M::DeviceRemoveMember { .. } => 25,
+        M::DeviceRemoveMemberResponse { .. } => 26,
+        M::DeviceIssueDelegatedToken { .. } => 27,
+        M::DeviceIssueDelegatedTokenResponse { .. } => 28,
+        M::DeviceValidateDelegatedToken { .. } => 29,
+        M::DeviceValidateDelegatedTokenResponse { .. } => 30,
+        M::DeviceRevokeDelegatedToken { .. } => 31,
+        M::DeviceRevokeDelegatedTokenResponse { .. } => 32,
+        M::DeviceConfigPush { .. } => 33,
+        M::DeviceConfigPushResponse { .. } => 34,
+        M::DeviceConfigApplied { .. } => 35,
+        M::DeviceHeartbeat { .. } => 36,
+        M::DeviceRekey { .. } => 37,
+        M::PairingCreateCode => 38,
+        M::PairingCreateCodeResponse { .. } => 39,
+        M::PairingUseCode { .. } => 40,
+        M::PairingUseCodeResponse { .. } => 41,
+        M::PairingFailed { .. } => 42,
+        M::SyncData { .. } => 43,
+        M::SyncQueuedDelivery { .. } => 44,
+        M::SyncRetrieveQueued { .. } => 45,
+        M::SyncRetrieveQueuedResponse { .. } => 46,
+        M::SyncDeliveryReceipt { .. } => 47,
+        M::HiveRegister { .. } => 48,
+        M::HiveRegisterResponse { .. } => 49,
+        M::HiveHeartbeat { .. } => 50,
+        M::HiveSpawnCocoon { .. } => 51,
+        M::HiveTerminateCocoon { .. } => 52,
+        M::HiveSpawnCocoonResult { .. } => 53,
+        M::HiveTerminateCocoonResult { .. } => 54,
+        M::HiveMaintenance { .. } => 55,
+        M::HiveDrainCocoon { .. } => 56,
+        M::HiveDrainCocoonResult { .. } => 57,
+        M::HiveSingletonLeader { .. } => 58,
+        M::HiveSetPoolSize { .. } => 59,
+        M::HivePoolStatus { .. } => 60,
+        M::HiveRekeyCocoon { .. } => 61,
+        M::HiveRekeyCocoonResponse { .. } => 62,
+        M::RoomCreate { .. } => 63,
+        M::RoomCreateResponse { .. } => 64,
+        M::RoomDelete { .. } => 65,
+        M::RoomDeleteResponse { .. } => 66,
+        M::RoomAddActor { .. } => 67,
+        M::RoomAddActorResponse { .. } => 68,
+        M::RoomRemoveActor { .. } => 69,
+        M::RoomRemoveActorResponse { .. } => 70,
+        M::RoomGrantAccess { .. } => 71,
+        M::RoomGrantAccessResponse { .. } => 72,
+        M::RoomRevokeAccess { .. } => 73,
+        M::RoomRevokeAccessResponse { .. } => 74,
+        M::RoomList => 75,
+        M::RoomListResponse { .. } => 76,
+        M::RoomGet { .. } => 77,
+        M::RoomGetResponse { .. } => 78,
+        M::RoomSend { .. } => 79,
+        M::RoomActorJoined { .. } => 80,
+        M::RoomActorLeft { .. } => 81,
+        M::RoomUpdated { .. } => 82,
+        M::RelayOpen { .. } => 83,
+        M::RelayFrame { .. } => 84,
+        M::RelayClose { .. } => 85,
+        M::CapabilityUpdate { .. } => 86,
+        M::CapabilityDelta { .. } => 87,
+        M::CapabilityResync { .. } => 88,
+        M::CapabilityRequest { .. } => 89,
+        M::CapabilityResponse { .. } => 90,
+        M::CapabilityUnavailable { .. } => 91,
+        M::SystemError { .. } => 92,
+    }
+}
+
+/// Any JSON value except a top-level `null`: optional fields holding `null`
+/// read back as `None`, which is not a structural mistake.
+pub fn json_value() -> impl Strategy<Value = serde_json::Value> {
+    use serde_json::Value;
+
+    // No floats: they do not survive a text round trip bit-for-bit
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        any::<String>().prop_map(Value::String),
+    ];
+    leaf.prop_recursive(3, 24, 4, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..4).prop_map(Value::Array),
+            hash_map(any::<String>(), inner, 0..4)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+    .prop_filter("top-level null", |value| !value.is_null())
+}
+
+fn tags() -> impl Strategy<Value = HashMap<String, String>> {
+    hash_map(any::<String>(), any::<String>(), 0..4)
+}
+
+/// Implements `Arbitrary` for a fieldless enum and checks the variant list
+/// is complete.
+macro_rules! unit_enum_arbitrary {
+    ($name:ident { $($variant:ident),+ $(,)? }) => {
+        impl Arbitrary for $name {
+            type Parameters = ();
+            type Strategy = BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: ()) -> Self::Strategy {
+                const _: fn(&$name) = |value| match value {
+                    $($name::$variant)|+ => {}
+                };
+                prop_oneof![$(Just($name::$variant)),+].boxed()
+            }
+        }
+    };
+}
+
+unit_enum_arbitrary!(WsState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Error,
+});
+unit_enum_arbitrary!(AuthRequirement { Required, Optional });
+unit_enum_arbitrary!(AuthOption {
+    Verified,
+    Anonymous,
+});
+unit_enum_arbitrary!(RelayPriority {
+    Interactive,
+    Normal,
+    Bulk,
+});
+unit_enum_arbitrary!(OwnershipAction {
+    Claimed,
+    Transferred,
+    Removed,
+});
+unit_enum_arbitrary!(OwnershipTokenType {
+    SetupToken,
+    DeviceSecret,
+    AccessToken,
+});
+unit_enum_arbitrary!(CapabilityUnavailableReason {
+    UnknownProtocol,
+    IncompatibleVersion,
+    InvalidRequirement,
+});
+unit_enum_arbitrary!(CocoonRole {
+    Owner,
+    Operator,
+    Viewer,
+});
+unit_enum_arbitrary!(DisconnectReason {
+    ConnectionLost,
+    Deregistered,
+    Replaced,
+    Removed,
+    Banned,
+    ServerShutdown,
+});
+
+/// Implements `Arbitrary` for a string ID from a valid identifier.
+macro_rules! id_arbitrary {
+    ($($name:ident),+) => {
+        $(
+            impl Arbitrary for $name {
+                type Parameters = ();
+                type Strategy = BoxedStrategy<Self>;
+
+                fn arbitrary_with(_: ()) -> Self::Strategy {
+                    "[A-Za-z0-9_.:-]{1,64}".prop_map($name::from).boxed()
+                }
+            }
+        )+
+    };
+}
+
+id_arbitrary!(DeviceId, SessionId, HiveId, RequestId, MessageId);
+
+impl Arbitrary for IceServer {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            vec(any::<String>(), 0..3),
+            option::of(any::<String>()),
+            option::of(any::<String>()),
+        )
+            .prop_map(|(urls, username, credential)| IceServer {
+                urls,
+                username,
+                credential,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for ConnectionInfo {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<bool>(), option::of(vec(any::<IceServer>(), 0..3)))
+            .prop_map(|(manual_allowed, ice_servers)| ConnectionInfo {
+                manual_allowed,
+                ice_servers,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for DeviceInfo {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<DeviceId>(),
+            tags(),
+            any::<bool>(),
+            option::of(any::<String>()),
+            option::of(json_value()),
+        )
+            .prop_map(
+                |(device_id, tags, online, device_type, device_config)| DeviceInfo {
+                    device_id,
+                    tags,
+                    online,
+                    device_type,
+                    device_config,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for DelegatedGrant {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<String>(),
+            any::<DeviceId>(),
+            any::<String>(),
+            vec(any::<String>(), 0..3),
+            any::<u64>(),
+        )
+            .prop_map(
+                |(token_id, device_id, issued_by, scopes, expires_at)| DelegatedGrant {
+                    token_id,
+                    device_id,
+                    issued_by,
+                    scopes,
+                    expires_at,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for VerifiedSender {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            option::of(any::<String>()),
+            option::of(any::<CocoonRole>()),
+            option::of(any::<DelegatedGrant>()),
+        )
+            .prop_map(|(user_id, role, grant)| VerifiedSender {
+                user_id,
+                role,
+                grant,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Capability {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<String>(), any::<String>())
+            .prop_map(|(protocol, version)| Capability { protocol, version })
+            .boxed()
+    }
+}
+
+impl Arbitrary for CocoonMember {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<String>(), any::<CocoonRole>(), any::<u64>())
+            .prop_map(|(user_id, role, claimed_at)| CocoonMember {
+                user_id,
+                role,
+                claimed_at,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for OwnershipAuditEvent {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<DeviceId>(),
+            any::<OwnershipAction>(),
+            any::<String>(),
+            option::of(any::<String>()),
+            any::<OwnershipTokenType>(),
+            any::<u64>(),
+        )
+            .prop_map(
+                |(device_id, action, user_id, actor, token_type, at)| OwnershipAuditEvent {
+                    device_id,
+                    action,
+                    user_id,
+                    actor,
+                    token_type,
+                    at,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for CocoonKind {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<String>(),
+            any::<String>(),
+            json_value(),
+            any::<String>(),
+        )
+            .prop_map(|(id, runner_type, runner_config, image)| CocoonKind {
+                id,
+                runner_type,
+                runner_config,
+                image,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for AdiServiceUsage {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            (any::<String>(), any::<String>()),
+            (any::<u64>(), any::<u64>(), any::<u64>()),
+            (any::<u64>(), any::<u64>(), any::<u64>()),
+        )
+            .prop_map(
+                |(
+                    (client, service),
+                    (requests, bytes_in, bytes_out),
+                    (stream_ms, throttled, rejected),
+                )| AdiServiceUsage {
+                    client,
+                    service,
+                    requests,
+                    bytes_in,
+                    bytes_out,
+                    stream_ms,
+                    throttled,
+                    rejected,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for GpuInfo {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<String>(),
+            any::<u64>(),
+            any::<u64>(),
+            option::of(any::<String>()),
+        )
+            .prop_map(|(model, vram_total_mb, vram_free_mb, driver)| GpuInfo {
+                model,
+                vram_total_mb,
+                vram_free_mb,
+                driver,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for CocoonPoolStatus {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<String>(),
+            any::<u32>(),
+            any::<u32>(),
+            any::<u32>(),
+            any::<u64>(),
+            any::<u64>(),
+        )
+            .prop_map(
+                |(kind, size, ready, warming, claimed, misses)| CocoonPoolStatus {
+                    kind,
+                    size,
+                    ready,
+                    warming,
+                    claimed,
+                    misses,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for DisconnectInfo {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<DisconnectReason>(),
+            option::of(any::<u64>()),
+            any::<bool>(),
+        )
+            .prop_map(|(reason, retry_after_secs, permanent)| DisconnectInfo {
+                reason,
+                retry_after_secs,
+                permanent,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for RoomInfo {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            any::<String>(),
+            any::<String>(),
+            vec(any::<String>(), 0..3),
+            vec(any::<DeviceInfo>(), 0..3),
+        )
+            .prop_map(|(room_id, owner_user_id, granted_users, actors)| RoomInfo {
+                room_id,
+                owner_user_id,
+                granted_users,
+                actors,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for PageRequest {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (option::of(any::<String>()), option::of(any::<u32>()))
+            .prop_map(|(cursor, limit)| PageRequest { cursor, limit })
+            .boxed()
+    }
+}
+
+impl<T> Arbitrary for Page<T>
+where
+    T: Arbitrary + 'static,
+    T::Strategy: 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            vec(any::<T>(), 0..4),
+            any::<u64>(),
+            option::of(any::<String>()),
+        )
+            .prop_map(|(items, total, next_cursor)| Page {
+                items,
+                total,
+                next_cursor,
+            })
+            .boxed()
+    }
+}
+
+/// Bare messages as well as envelopes of any version
+impl Arbitrary for SignalingEnvelope {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let envelope = (
+            1..=crate::PROTOCOL_VERSION + 1,
+            any::<MessageId>(),
+            option::of(any::<MessageId>()),
+            option::of(any::<u64>()),
+            any::<SignalingMessage>(),
+        )
+            .prop_map(|(version, message_id, correlation_id, sent_at, payload)| {
+                SignalingEnvelope {
+                    version,
+                    message_id,
+                    correlation_id,
+                    sent_at,
+                    payload,
+                }
+            });
+        prop_oneof![
+            any::<SignalingMessage>().prop_map(SignalingEnvelope::bare),
+            envelope,
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for SignalingMessage {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use SignalingMessage as M;
+
+        let s = any::<String>;
+        let devices = || vec(any::<DeviceInfo>(), 0..3);
+
+        // One entry per variant, in declaration order
+        let variants: Vec<BoxedStrategy<Self>> = vec![
+            // ── auth ──
+            (
+                s(),
+                s(),
+                any::<AuthRequirement>(),
+                vec(any::<AuthOption>(), 0..3),
+            )
+                .prop_map(
+                    |(auth_kind, auth_domain, auth_requirement, auth_options)| M::AuthHello {
+                        auth_kind,
+                        auth_domain,
+                        auth_requirement,
+                        auth_options,
+                    },
+                )
+                .boxed(),
+            s().prop_map(|access_token| M::AuthAuthenticate { access_token })
+                .boxed(),
+            s().prop_map(|user_id| M::AuthAuthenticateResponse { user_id })
+                .boxed(),
+            (s(), any::<ConnectionInfo>(), devices())
+                .prop_map(|(user_id, connection_info, devices)| M::AuthHelloAuthed {
+                    user_id,
+                    connection_info,
+                    devices,
+                })
+                .boxed(),
+            // ── device ──
+            (
+                s(),
+                option::of(any::<DeviceId>()),
+                s(),
+                option::of(tags()),
+                option::of(s()),
+                option::of(json_value()),
+            )
+                .prop_map(
+                    |(secret, device_id, version, tags, device_type, device_config)| {
+                        M::DeviceRegister {
+                            secret,
+                            device_id,
+                            version,
+                            tags,
+                            device_type,
+                            device_config,
+                        }
+                    },
+                )
+                .boxed(),
+            (any::<DeviceId>(), option::of(tags()))
+                .prop_map(|(device_id, tags)| M::DeviceRegisterResponse { device_id, tags })
+                .boxed(),
+            (any::<DeviceId>(), option::of(s()))
+                .prop_map(|(device_id, reason)| M::DeviceDeregister { device_id, reason })
+                .boxed(),
+            any::<DeviceId>()
+                .prop_map(|device_id| M::DeviceDeregisterResponse { device_id })
+                .boxed(),
+            s().prop_map(|peer_id| M::DevicePeerConnected { peer_id })
+                .boxed(),
+            (s(), option::of(any::<DisconnectInfo>()))
+                .prop_map(|(peer_id, info)| M::DevicePeerDisconnected { peer_id, info })
+                .boxed(),
+            any::<DisconnectInfo>()
+                .prop_map(|info| M::DeviceDisconnect { info })
+                .boxed(),
+            tags().prop_map(|tags| M::DeviceUpdateTags { tags }).boxed(),
+            (any::<DeviceId>(), tags())
+                .prop_map(|(device_id, tags)| M::DeviceUpdateTagsResponse { device_id, tags })
+                .boxed(),
+            (option::of(tags()), option::of(json_value()))
+                .prop_map(|(tags, device_config)| M::DeviceUpdateDevice {
+                    tags,
+                    device_config,
+                })
+                .boxed(),
+            (any::<DeviceId>(), tags(), option::of(json_value()))
+                .prop_map(
+                    |(device_id, tags, device_config)| M::DeviceUpdateDeviceResponse {
+                        device_id,
+                        tags,
+                        device_config,
+                    },
+                )
+                .boxed(),
+            tags()
+                .prop_map(|tag_filter| M::DeviceQueryDevices { tag_filter })
+                .boxed(),
+            devices()
+                .prop_map(|devices| M::DeviceQueryDevicesResponse { devices })
+                .boxed(),
+            devices()
+                .prop_map(|devices| M::DeviceDeviceListUpdated { devices })
+                .boxed(),
+            any::<OwnershipAuditEvent>()
+                .prop_map(|event| M::DeviceOwnershipChanged { event })
+                .boxed(),
+            any::<DeviceId>()
+                .prop_map(|device_id| M::DeviceOwnershipHistory { device_id })
+                .boxed(),
+            (any::<DeviceId>(), vec(any::<OwnershipAuditEvent>(), 0..3))
+                .prop_map(|(device_id, events)| M::DeviceOwnershipHistoryResponse {
+                    device_id,
+                    events,
+                })
+                .boxed(),
+            any::<DeviceId>()
+                .prop_map(|device_id| M::DeviceListMembers { device_id })
+                .boxed(),
+            (any::<DeviceId>(), vec(any::<CocoonMember>(), 0..3))
+                .prop_map(|(device_id, members)| M::DeviceListMembersResponse {
+                    device_id,
+                    members,
+                })
+                .boxed(),
+            (any::<DeviceId>(), s(), any::<CocoonRole>())
+                .prop_map(|(device_id, user_id, role)| M::DeviceUpdateMemberRole {
+                    device_id,
+                    user_id,
+                    role,
+                })
+                .boxed(),
+            (any::<DeviceId>(), any::<CocoonMember>())
+                .prop_map(|(device_id, member)| M::DeviceUpdateMemberRoleResponse {
+                    device_id,
+                    member,
+                })
+                .boxed(),
+            (any::<DeviceId>(), s())
+                .prop_map(|(device_id, user_id)| M::DeviceRemoveMember { device_id, user_id })
+                .boxed(),
+            (any::<DeviceId>(), s())
+                .prop_map(|(device_id, user_id)| M::DeviceRemoveMemberResponse {
+                    device_id,
+                    user_id,
+                })
+                .boxed(),
+            (any::<DeviceId>(), vec(s(), 0..3), any::<u64>())
+                .prop_map(
+                    |(device_id, scopes, ttl_secs)| M::DeviceIssueDelegatedToken {
+                        device_id,
+                        scopes,
+                        ttl_secs,
+                    },
+                )
+                .boxed(),
+            (s(), any::<DelegatedGrant>())
+                .prop_map(|(token, grant)| M::DeviceIssueDelegatedTokenResponse { token, grant })
+                .boxed(),
+            s().prop_map(|token| M::DeviceValidateDelegatedToken { token })
+                .boxed(),
+            (s(), any::<bool>(), option::of(any::<DelegatedGrant>()))
+                .prop_map(
+                    |(token, valid, grant)| M::DeviceValidateDelegatedTokenResponse {
+                        token,
+                        valid,
+                        grant,
+                    },
+                )
+                .boxed(),
+            s().prop_map(|token_id| M::DeviceRevokeDelegatedToken { token_id })
+                .boxed(),
+            (s(), any::<bool>())
+                .prop_map(
+                    |(token_id, revoked)| M::DeviceRevokeDelegatedTokenResponse {
+                        token_id,
+                        revoked,
+                    },
+                )
+                .boxed(),
+            (
+                option::of(vec(any::<DeviceId>(), 0..3)),
+                option::of(tags()),
+                json_value(),
+                any::<u64>(),
+            )
+                .prop_map(|(device_ids, label_selector, config_patch, version)| {
+                    M::DeviceConfigPush {
+                        device_ids,
+                        label_selector,
+                        config_patch,
+                        version,
+                    }
+                })
+                .boxed(),
+            (any::<u64>(), vec(s(), 0..3), vec(s(), 0..3))
+                .prop_map(|(version, sent_to, offline)| M::DeviceConfigPushResponse {
+                    version,
+                    sent_to,
+                    offline,
+                })
+                .boxed(),
+            (
+                any::<DeviceId>(),
+                any::<u64>(),
+                any::<bool>(),
+                option::of(s()),
+            )
+                .prop_map(
+                    |(device_id, version, success, error)| M::DeviceConfigApplied {
+                        device_id,
+                        version,
+                        success,
+                        error,
+                    },
+                )
+                .boxed(),
+            (any::<DeviceId>(), vec(any::<AdiServiceUsage>(), 0..3))
+                .prop_map(|(device_id, adi_usage)| M::DeviceHeartbeat {
+                    device_id,
+                    adi_usage,
+                })
+                .boxed(),
+            (s(), s())
+                .prop_map(|(setup_token, secret)| M::DeviceRekey {
+                    setup_token,
+                    secret,
+                })
+                .boxed(),
+            // ── pairing ──
+            Just(M::PairingCreateCode).boxed(),
+            s().prop_map(|code| M::PairingCreateCodeResponse { code })
+                .boxed(),
+            s().prop_map(|code| M::PairingUseCode { code }).boxed(),
+            s().prop_map(|peer_id| M::PairingUseCodeResponse { peer_id })
+                .boxed(),
+            s().prop_map(|reason| M::PairingFailed { reason }).boxed(),
+            // ── sync ──
+            (json_value(), option::of(any::<RelayPriority>()))
+                .prop_map(|(payload, priority)| M::SyncData { payload, priority })
+                .boxed(),
+            (any::<MessageId>(), any::<DeviceId>(), any::<u64>())
+                .prop_map(
+                    |(message_id, device_id, expires_at)| M::SyncQueuedDelivery {
+                        message_id,
+                        device_id,
+                        expires_at,
+                    },
+                )
+                .boxed(),
+            any::<DeviceId>()
+                .prop_map(|device_id| M::SyncRetrieveQueued { device_id })
+                .boxed(),
+            (any::<DeviceId>(), any::<u32>(), any::<u32>())
+                .prop_map(
+                    |(device_id, delivered, expired)| M::SyncRetrieveQueuedResponse {
+                        device_id,
+                        delivered,
+                        expired,
+                    },
+                )
+                .boxed(),
+            (any::<MessageId>(), any::<DeviceId>(), any::<u64>())
+                .prop_map(
+                    |(message_id, device_id, delivered_at)| M::SyncDeliveryReceipt {
+                        message_id,
+                        device_id,
+                        delivered_at,
+                    },
+                )
+                .boxed(),
+            // ── hive ──
+            (
+                any::<HiveId>(),
+                s(),
+                vec(any::<CocoonKind>(), 0..3),
+                s(),
+                option::of(vec(s(), 0..3)),
+                option::of(vec(any::<GpuInfo>(), 0..3)),
+                option::of(vec(s(), 0..3)),
+            )
+                .prop_map(
+                    |(
+                        hive_id,
+                        version,
+                        cocoon_kinds,
+                        hive_id_signature,
+                        runners,
+                        gpus,
+                        singletons,
+                    )| {
+                        M::HiveRegister {
+                            hive_id,
+                            version,
+                            cocoon_kinds,
+                            hive_id_signature,
+                            runners,
+                            gpus,
+                            singletons,
+                        }
+                    },
+                )
+                .boxed(),
+            any::<HiveId>()
+                .prop_map(|hive_id| M::HiveRegisterResponse { hive_id })
+                .boxed(),
+            (vec(any::<GpuInfo>(), 0..3), option::of(vec(s(), 0..3)))
+                .prop_map(|(gpus, singletons)| M::HiveHeartbeat { gpus, singletons })
+                .boxed(),
+            (
+                any::<RequestId>(),
+                s(),
+                option::of(s()),
+                s(),
+                option::of(any::<bool>()),
+                option::of(any::<u64>()),
+                option::of(any::<bool>()),
+            )
+                .prop_map(
+                    |(
+                        request_id,
+                        setup_token,
+                        name,
+                        kind,
+                        gpu_required,
+                        min_vram_mb,
+                        from_pool,
+                    )| {
+                        M::HiveSpawnCocoon {
+                            request_id,
+                            setup_token,
+                            name,
+                            kind,
+                            gpu_required,
+                            min_vram_mb,
+                            from_pool,
+                        }
+                    },
+                )
+                .boxed(),
+            (any::<RequestId>(), s())
+                .prop_map(|(request_id, container_id)| M::HiveTerminateCocoon {
+                    request_id,
+                    container_id,
+                })
+                .boxed(),
+            (
+                any::<RequestId>(),
+                any::<bool>(),
+                option::of(any::<DeviceId>()),
+                option::of(s()),
+                option::of(s()),
+                option::of(any::<bool>()),
+            )
+                .prop_map(
+                    |(request_id, success, device_id, container_id, error, from_pool)| {
+                        M::HiveSpawnCocoonResult {
+                            request_id,
+                            success,
+                            device_id,
+                            container_id,
+                            error,
+                            from_pool,
+                        }
+                    },
+                )
+                .boxed(),
+            (any::<RequestId>(), any::<bool>(), option::of(s()))
+                .prop_map(
+                    |(request_id, success, error)| M::HiveTerminateCocoonResult {
+                        request_id,
+                        success,
+                        error,
+                    },
+                )
+                .boxed(),
+            (any::<bool>(), option::of(s()))
+                .prop_map(|(on, reason)| M::HiveMaintenance { on, reason })
+                .boxed(),
+            (
+                any::<RequestId>(),
+                s(),
+                s(),
+                option::of(s()),
+                s(),
+                option::of(any::<bool>()),
+                option::of(any::<u64>()),
+            )
+                .prop_map(
+                    |(
+                        request_id,
+                        container_id,
+                        setup_token,
+                        name,
+                        kind,
+                        gpu_required,
+                        min_vram_mb,
+                    )| {
+                        M::HiveDrainCocoon {
+                            request_id,
+                            container_id,
+                            setup_token,
+                            name,
+                            kind,
+                            gpu_required,
+                            min_vram_mb,
+                        }
+                    },
+                )
+                .boxed(),
+            (
+                any::<RequestId>(),
+                s(),
+                any::<bool>(),
+                option::of(any::<HiveId>()),
+                option::of(s()),
+            )
+                .prop_map(
+                    |(request_id, container_id, success, target_hive_id, error)| {
+                        M::HiveDrainCocoonResult {
+                            request_id,
+                            container_id,
+                            success,
+                            target_hive_id,
+                            error,
+                        }
+                    },
+                )
+                .boxed(),
+            (s(), option::of(any::<HiveId>()), any::<u64>())
+                .prop_map(
+                    |(service, leader_hive_id, fencing_token)| M::HiveSingletonLeader {
+                        service,
+                        leader_hive_id,
+                        fencing_token,
+                    },
+                )
+                .boxed(),
+            (s(), any::<u32>())
+                .prop_map(|(kind, size)| M::HiveSetPoolSize { kind, size })
+                .boxed(),
+            vec(any::<CocoonPoolStatus>(), 0..3)
+                .prop_map(|pools| M::HivePoolStatus { pools })
+                .boxed(),
+            (any::<RequestId>(), s(), s(), s())
+                .prop_map(
+                    |(request_id, slot_secret, setup_token, secret)| M::HiveRekeyCocoon {
+                        request_id,
+                        slot_secret,
+                        setup_token,
+                        secret,
+                    },
+                )
+                .boxed(),
+            (any::<RequestId>(), any::<bool>(), option::of(s()))
+                .prop_map(|(request_id, success, error)| M::HiveRekeyCocoonResponse {
+                    request_id,
+                    success,
+                    error,
+                })
+                .boxed(),
+            // ── room ──
+            option::of(s())
+                .prop_map(|room_id| M::RoomCreate { room_id })
+                .boxed(),
+            s().prop_map(|room_id| M::RoomCreateResponse { room_id })
+                .boxed(),
+            s().prop_map(|room_id| M::RoomDelete { room_id }).boxed(),
+            s().prop_map(|room_id| M::RoomDeleteResponse { room_id })
+                .boxed(),
+            (s(), any::<DeviceId>())
+                .prop_map(|(room_id, device_id)| M::RoomAddActor { room_id, device_id })
+                .boxed(),
+            (s(), any::<DeviceId>())
+                .prop_map(|(room_id, device_id)| M::RoomAddActorResponse { room_id, device_id })
+                .boxed(),
+            (s(), any::<DeviceId>())
+                .prop_map(|(room_id, device_id)| M::RoomRemoveActor { room_id, device_id })
+                .boxed(),
+            (s(), any::<DeviceId>())
+                .prop_map(|(room_id, device_id)| M::RoomRemoveActorResponse { room_id, device_id })
+                .boxed(),
+            (s(), s())
+                .prop_map(|(room_id, user_id)| M::RoomGrantAccess { room_id, user_id })
+                .boxed(),
+            (s(), s())
+                .prop_map(|(room_id, user_id)| M::RoomGrantAccessResponse { room_id, user_id })
+                .boxed(),
+            (s(), s())
+                .prop_map(|(room_id, user_id)| M::RoomRevokeAccess { room_id, user_id })
+                .boxed(),
+            (s(), s())
+                .prop_map(|(room_id, user_id)| M::RoomRevokeAccessResponse { room_id, user_id })
+                .boxed(),
+            Just(M::RoomList).boxed(),
+            vec(any::<RoomInfo>(), 0..3)
+                .prop_map(|rooms| M::RoomListResponse { rooms })
+                .boxed(),
+            s().prop_map(|room_id| M::RoomGet { room_id }).boxed(),
+            (s(), s(), vec(s(), 0..3), devices())
+                .prop_map(
+                    |(room_id, owner_user_id, granted_users, actors)| M::RoomGetResponse {
+                        room_id,
+                        owner_user_id,
+                        granted_users,
+                        actors,
+                    },
+                )
+                .boxed(),
+            (s(), option::of(s()), json_value())
+                .prop_map(|(room_id, to, payload)| M::RoomSend {
+                    room_id,
+                    to,
+                    payload,
+                })
+                .boxed(),
+            (s(), any::<DeviceId>())
+                .prop_map(|(room_id, device_id)| M::RoomActorJoined { room_id, device_id })
+                .boxed(),
+            (s(), any::<DeviceId>())
+                .prop_map(|(room_id, device_id)| M::RoomActorLeft { room_id, device_id })
+                .boxed(),
+            any::<RoomInfo>()
+                .prop_map(|room| M::RoomUpdated { room })
+                .boxed(),
+            // ── relay ──
+            s().prop_map(|link_id| M::RelayOpen { link_id }).boxed(),
+            (s(), json_value())
+                .prop_map(|(link_id, frame)| M::RelayFrame { link_id, frame })
+                .boxed(),
+            (s(), option::of(s()))
+                .prop_map(|(link_id, reason)| M::RelayClose { link_id, reason })
+                .boxed(),
+            // ── capability ──
+            (vec(any::<Capability>(), 0..3), any::<u64>(), s())
+                .prop_map(|(capabilities, version, hash)| M::CapabilityUpdate {
+                    capabilities,
+                    version,
+                    hash,
+                })
+                .boxed(),
+            (
+                any::<u64>(),
+                any::<u64>(),
+                vec(any::<Capability>(), 0..3),
+                vec(s(), 0..3),
+                vec(any::<Capability>(), 0..3),
+                s(),
+            )
+                .prop_map(|(base_version, version, added, removed, changed, hash)| {
+                    M::CapabilityDelta {
+                        base_version,
+                        version,
+                        added,
+                        removed,
+                        changed,
+                        hash,
+                    }
+                })
+                .boxed(),
+            any::<u64>()
+                .prop_map(|version| M::CapabilityResync { version })
+                .boxed(),
+            (
+                any::<RequestId>(),
+                any::<Capability>(),
+                json_value(),
+                option::of(s()),
+                option::of(s()),
+            )
+                .prop_map(
+                    |(request_id, capability, payload, prefer_device, from_device)| {
+                        M::CapabilityRequest {
+                            request_id,
+                            capability,
+                            payload,
+                            prefer_device,
+                            from_device,
+                        }
+                    },
+                )
+                .boxed(),
+            (
+                any::<RequestId>(),
+                json_value(),
+                option::of(s()),
+                option::of(s()),
+            )
+                .prop_map(
+                    |(request_id, payload, error, from_device)| M::CapabilityResponse {
+                        request_id,
+                        payload,
+                        error,
+                        from_device,
+                    },
+                )
+                .boxed(),
+            (
+                any::<RequestId>(),
+                any::<CapabilityUnavailableReason>(),
+                option::of(any::<Capability>()),
+            )
+                .prop_map(
+                    |(request_id, reason, closest_match)| M::CapabilityUnavailable {
+                        request_id,
+                        reason,
+                        closest_match,
+                    },
+                )
+                .boxed(),
+            // ── system ──
+            s().prop_map(|message| M::SystemError { message }).boxed(),
+        ];
+        debug_assert_eq!(variants.len(), VARIANT_COUNT);
+
+        Union::new(variants).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinaryFrame, Cursor};
+    use proptest::strategy::ValueTree;
+    use proptest::test_runner::TestRunner;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use std::collections::HashSet;
+
+    /// Serialize, read back and serialize again; both encodings must match.
+    /// Compared as JSON because the generated types have no `PartialEq`.
+    fn json_roundtrip<T: Serialize + DeserializeOwned>(value: &T) -> Result<(), TestCaseError> {
+        let json = serde_json::to_value(value).unwrap();
+        let text = serde_json::to_string(value).unwrap();
+        let decoded: T = serde_json::from_str(&text)
+            .map_err(|e| TestCaseError::fail(format!("{} in {}", e, text)))?;
+        prop_assert_eq!(serde_json::to_value(&decoded).unwrap(), json);
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_signaling_message_roundtrip(msg in any::<SignalingMessage>()) {
+            json_roundtrip(&msg)?;
+        }
+
+        #[test]
+        fn test_types_roundtrip(
+            device in any::<DeviceInfo>(),
+            info in any::<ConnectionInfo>(),
+            kind in any::<CocoonKind>(),
+            gpu in any::<GpuInfo>(),
+            pool in any::<CocoonPoolStatus>(),
+            disconnect in any::<DisconnectInfo>(),
+            room in any::<RoomInfo>(),
+            state in any::<WsState>(),
+            audit in any::<OwnershipAuditEvent>(),
+            grant in any::<DelegatedGrant>(),
+            sender in any::<VerifiedSender>(),
+            member in any::<CocoonMember>(),
+            capability in any::<Capability>(),
+            usage in any::<AdiServiceUsage>(),
+        ) {
+            json_roundtrip(&device)?;
+            json_roundtrip(&info)?;
+            json_roundtrip(&kind)?;
+            json_roundtrip(&gpu)?;
+            json_roundtrip(&pool)?;
+            json_roundtrip(&disconnect)?;
+            json_roundtrip(&room)?;
+            json_roundtrip(&state)?;
+            json_roundtrip(&audit)?;
+            json_roundtrip(&grant)?;
+            json_roundtrip(&sender)?;
+            json_roundtrip(&member)?;
+            json_roundtrip(&capability)?;
+            json_roundtrip(&usage)?;
+        }
+
+        #[test]
+        fn test_ids_roundtrip(
+            device_id in any::<DeviceId>(),
+            session_id in any::<SessionId>(),
+            hive_id in any::<HiveId>(),
+            request_id in any::<RequestId>(),
+            message_id in any::<MessageId>(),
+        ) {
+            prop_assert!(device_id.validate().is_ok());
+            json_roundtrip(&device_id)?;
+            json_roundtrip(&session_id)?;
+            json_roundtrip(&hive_id)?;
+            json_roundtrip(&request_id)?;
+            json_roundtrip(&message_id)?;
+            prop_assert_eq!(device_id.as_str().parse::<DeviceId>().unwrap(), device_id);
+        }
+
+        #[test]
+        fn test_envelope_roundtrip(envelope in any::<SignalingEnvelope>()) {
+            json_roundtrip(&envelope)?;
+        }
+
+        #[test]
+        fn test_binary_frame_roundtrip(
+            envelope in any::<SignalingEnvelope>(),
+            payload in proptest::collection::vec(any::<u8>(), 0..1024),
+        ) {
+            let bytes = BinaryFrame::new(envelope.clone(), &payload).encode().unwrap();
+            let frame = BinaryFrame::decode(&bytes).unwrap();
+            prop_assert_eq!(frame.payload, payload.as_slice());
+            prop_assert_eq!(
+                serde_json::to_value(&frame.envelope).unwrap(),
+                serde_json::to_value(&envelope).unwrap()
+            );
+        }
+
+        #[test]
+        fn test_pagination_roundtrip(
+            page in any::<Page<DeviceInfo>>(),
+            request in any::<PageRequest>(),
+            offset in any::<u64>(),
+        ) {
+            json_roundtrip(&page)?;
+            let text = serde_json::to_string(&request).unwrap();
+            prop_assert_eq!(serde_json::from_str::<PageRequest>(&text).unwrap(), request);
+            prop_assert_eq!(Cursor::decode(&Cursor::new(offset).encode()), Ok(Cursor::new(offset)));
+        }
+    }
+
+    #[test]
+    fn test_every_variant_generated() {
+        let mut runner = TestRunner::deterministic();
+        let strategy = any::<SignalingMessage>();
+        let seen: HashSet<usize> = (0..VARIANT_COUNT * 40)
+            .map(|_| variant_index(&strategy.new_tree(&mut runner).unwrap().current()))
+            .collect();
+        assert_eq!(seen.len(), VARIANT_COUNT);
+    }
+}
@@ -0,0 +1,270 @@
+//! Binary WebSocket frames for messages with large opaque bodies.
+//!
+//! Text frames carry binary bodies as base64 inside JSON, which costs about a
+//! third more bytes plus an encode/decode at every hop. A binary frame keeps
+//! the message as JSON metadata and appends the body raw:
+//!
+//! | Offset  | Size | Field                                                   |
+//! |---------|------|---------------------------------------------------------|
+//! | 0       | 4    | Magic `ADIB`                                            |
+//! | 4       | 1    | Frame format version ([`BINARY_FRAME_VERSION`])         |
+//! | 5       | 1    | Flags, reserved, must be 0                              |
+//! | 6       | 4    | Metadata length `N`, big-endian `u32`                   |
+//! | 10      | N    | Metadata: UTF-8 JSON [`SignalingEnvelope`] (or bare message) |
+//! | 10 + N  | rest | Payload, raw bytes up to the end of the frame           |
+//!
+//! The metadata message's own body field (e.g. `payload` of `sync_data`) is
+//! left `null`; receivers take the body from the frame instead. Relays only
+//! need [`RawBinaryFrame`] to route on the metadata, and forward the original
+//! bytes, or [`RawBinaryFrame::with_metadata`] when they rewrite it, without
+//! touching the payload.
+
+use crate::SignalingEnvelope;
+use std::fmt;
+
+pub const BINARY_FRAME_MAGIC: [u8; 4] = *b"ADIB";
+
+/// Frame format version written by this crate
+pub const BINARY_FRAME_VERSION: u8 = 1;
+
+/// Bytes before the metadata
+pub const BINARY_HEADER_LEN: usize = 10;
+
+/// Metadata is a routing header, not a body; anything larger is rejected
+pub const MAX_METADATA_LEN: usize = 1024 * 1024;
+
+/// A decoded binary frame borrowing its payload from the received bytes.
+#[derive(Debug, Clone)]
+pub struct BinaryFrame<'a> {
+    pub envelope: SignalingEnvelope,
+    pub payload: &'a [u8],
+}
+
+impl<'a> BinaryFrame<'a> {
+    pub fn new(envelope: impl Into<SignalingEnvelope>, payload: &'a [u8]) -> Self {
+        Self {
+            envelope: envelope.into(),
+            payload,
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, BinaryFrameError> {
+        let metadata = serde_json::to_vec(&self.envelope).map_err(BinaryFrameError::Metadata)?;
+        encode_parts(&metadata, self.payload)
+    }
+
+    pub fn decode(frame: &'a [u8]) -> Result<Self, BinaryFrameError> {
+        let raw = RawBinaryFrame::split(frame)?;
+        Ok(Self {
+            envelope: raw.envelope()?,
+            payload: raw.payload,
+        })
+    }
+}
+
+/// A binary frame split into its parts without parsing the metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawBinaryFrame<'a> {
+    /// Metadata JSON
+    pub metadata: &'a [u8],
+    pub payload: &'a [u8],
+}
+
+impl<'a> RawBinaryFrame<'a> {
+    /// Validate the header and locate metadata and payload.
+    pub fn split(frame: &'a [u8]) -> Result<Self, BinaryFrameError> {
+        if frame.len() < BINARY_HEADER_LEN {
+            return Err(BinaryFrameError::Truncated);
+        }
+        if frame[..4] != BINARY_FRAME_MAGIC {
+            return Err(BinaryFrameError::BadMagic);
+        }
+        if frame[4] != BINARY_FRAME_VERSION {
+            return Err(BinaryFrameError::UnsupportedVersion(frame[4]));
+        }
+        if frame[5] != 0 {
+            return Err(BinaryFrameError::UnknownFlags(frame[5]));
+        }
+
+        let len = u32::from_be_bytes([frame[6], frame[7], frame[8], frame[9]]) as usize;
+        if len > MAX_METADATA_LEN {
+            return Err(BinaryFrameError::MetadataTooLarge(len));
+        }
+        let rest = &frame[BINARY_HEADER_LEN..];
+        if rest.len() < len {
+            return Err(BinaryFrameError::Truncated);
+        }
+
+        let (metadata, payload) = rest.split_at(len);
+        Ok(Self { metadata, payload })
+    }
+
+    /// Parse the metadata.
+    pub fn envelope(&self) -> Result<SignalingEnvelope, BinaryFrameError> {
+        serde_json::from_slice(self.metadata).map_err(BinaryFrameError::Metadata)
+    }
+
+    /// Re-encode with new metadata, copying the payload through unchanged.
+    pub fn with_metadata(&self, envelope: &SignalingEnvelope) -> Result<Vec<u8>, BinaryFrameError> {
+        let metadata = serde_json::to_vec(envelope).map_err(BinaryFrameError::Metadata)?;
+        encode_parts(&metadata, self.payload)
+    }
+}
+
+/// Whether `frame` starts like a binary signaling frame.
+pub fn is_binary_frame(frame: &[u8]) -> bool {
+    frame.starts_with(&BINARY_FRAME_MAGIC)
+}
+
+fn encode_parts(metadata: &[u8], payload: &[u8]) -> Result<Vec<u8>, BinaryFrameError> {
+    if metadata.len() > MAX_METADATA_LEN {
+        return Err(BinaryFrameError::MetadataTooLarge(metadata.len()));
+    }
+
+    let mut frame = Vec::with_capacity(BINARY_HEADER_LEN + metadata.len() + payload.len());
+    frame.extend_from_slice(&BINARY_FRAME_MAGIC);
+    frame.push(BINARY_FRAME_VERSION);
+    frame.push(0);
+    frame.extend_from_slice(&(metadata.len() as u32).to_be_bytes());
+    frame.extend_from_slice(metadata);
+    frame.extend_from_slice(payload);
+    Ok(frame)
+}
+
+#[derive(Debug)]
+pub enum BinaryFrameError {
+    /// Shorter than its header or declared metadata
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownFlags(u8),
+    MetadataTooLarge(usize),
+    Metadata(serde_json::Error),
+}
+
+impl fmt::Display for BinaryFrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryFrameError::Truncated => write!(f, "truncated binary frame"),
+            BinaryFrameError::BadMagic => write!(f, "not a signaling binary frame"),
+            BinaryFrameError::UnsupportedVersion(v) => {
+                write!(f, "unsupported binary frame version: {}", v)
+            }
+            BinaryFrameError::UnknownFlags(flags) => {
+                write!(f, "unknown binary frame flags: {:#04x}", flags)
+            }
+            BinaryFrameError::MetadataTooLarge(len) => {
+                write!(f, "binary frame metadata of {} bytes exceeds limit", len)
+            }
+            BinaryFrameError::Metadata(e) => write!(f, "invalid binary frame metadata: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BinaryFrameError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BinaryFrameError::Metadata(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RelayPriority, SignalingMessage};
+
+    fn sync_data() -> SignalingMessage {
+        SignalingMessage::SyncData {
+            payload: serde_json::Value::Null,
+            priority: Some(RelayPriority::Bulk),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_keeps_payload_bytes() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let envelope = SignalingEnvelope::new(sync_data());
+        let bytes = BinaryFrame::new(envelope.clone(), &payload)
+            .encode()
+            .unwrap();
+
+        assert!(is_binary_frame(&bytes));
+        let frame = BinaryFrame::decode(&bytes).unwrap();
+        assert_eq!(frame.payload, payload.as_slice());
+        assert_eq!(frame.envelope.message_id, envelope.message_id);
+        assert!(matches!(
+            frame.envelope.payload,
+            SignalingMessage::SyncData { .. }
+        ));
+    }
+
+    #[test]
+    fn test_bare_metadata_is_accepted() {
+        let bytes = BinaryFrame::new(SignalingEnvelope::bare(sync_data()), b"body")
+            .encode()
+            .unwrap();
+        let raw = RawBinaryFrame::split(&bytes).unwrap();
+        assert!(raw.metadata.starts_with(b"{\"type\":\"sync_data\""));
+        assert!(raw.envelope().unwrap().is_legacy());
+    }
+
+    #[test]
+    fn test_relay_rewrites_metadata_only() {
+        let payload = vec![7u8; 4096];
+        let request = SignalingEnvelope::new(sync_data());
+        let bytes = BinaryFrame::new(request.clone(), &payload)
+            .encode()
+            .unwrap();
+
+        let raw = RawBinaryFrame::split(&bytes).unwrap();
+        let forwarded = raw.with_metadata(&request.reply(sync_data())).unwrap();
+
+        let frame = BinaryFrame::decode(&forwarded).unwrap();
+        assert_eq!(frame.payload, payload.as_slice());
+        assert_eq!(frame.envelope.correlation_id, Some(request.message_id));
+    }
+
+    #[test]
+    fn test_malformed_frames_are_rejected() {
+        let bytes = BinaryFrame::new(sync_data(), b"body").encode().unwrap();
+
+        assert!(matches!(
+            RawBinaryFrame::split(&bytes[..5]),
+            Err(BinaryFrameError::Truncated)
+        ));
+        assert!(matches!(
+            RawBinaryFrame::split(&bytes[..BINARY_HEADER_LEN + 3]),
+            Err(BinaryFrameError::Truncated)
+        ));
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert!(matches!(
+            RawBinaryFrame::split(&bad),
+            Err(BinaryFrameError::BadMagic)
+        ));
+
+        let mut bad = bytes.clone();
+        bad[4] = 2;
+        assert!(matches!(
+            RawBinaryFrame::split(&bad),
+            Err(BinaryFrameError::UnsupportedVersion(2))
+        ));
+
+        let mut bad = bytes.clone();
+        bad[6..10].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            RawBinaryFrame::split(&bad),
+            Err(BinaryFrameError::MetadataTooLarge(_))
+        ));
+
+        let mut bad = bytes;
+        bad[BINARY_HEADER_LEN] = b'[';
+        assert!(matches!(
+            BinaryFrame::decode(&bad),
+            Err(BinaryFrameError::Metadata(_))
+        ));
+    }
+}
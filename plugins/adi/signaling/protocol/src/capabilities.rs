@@ -0,0 +1,347 @@
+//! Versioned capability sets and deltas
+//!
+//! A device announces its full capability set once with `capability_update`
+//! and afterwards sends only `capability_delta`. Every set carries a version
+//! that increases on each change and a content hash, so a peer can tell that
+//! nothing changed without comparing lists and can detect when it has drifted.
+//! The signaling server applies each delta to the set it holds for the device
+//! and answers `capability_resync` when it does not apply.
+//!
+//! The hash is FNV-1a (64-bit, lowercase hex) over `protocol@version\n` lines
+//! sorted by protocol, which is easy to reproduce in any client language.
+
+use crate::{Capability, SignalingMessage};
+use std::collections::BTreeMap;
+use std::fmt;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Capabilities of one device, keyed by protocol
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapabilitySet {
+    version: u64,
+    capabilities: BTreeMap<String, String>,
+}
+
+/// Difference between two versions of a capability set
+#[derive(Debug, Clone)]
+pub struct CapabilityDelta {
+    /// Version the delta applies to
+    pub base_version: u64,
+    /// Version after applying
+    pub version: u64,
+    pub added: Vec<Capability>,
+    /// Protocols no longer offered
+    pub removed: Vec<String>,
+    /// Protocols offered at a different version
+    pub changed: Vec<Capability>,
+    /// Hash of the resulting set
+    pub hash: String,
+}
+
+/// Why a delta could not be applied; the receiver needs a full update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CapabilityDeltaError {
+    /// The delta was computed against a different version
+    VersionMismatch { expected: u64, actual: u64 },
+    /// The result does not match the sender's set
+    HashMismatch { expected: String, actual: String },
+}
+
+impl fmt::Display for CapabilityDeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VersionMismatch { expected, actual } => write!(
+                f,
+                "delta is based on version {} but the set is at version {}",
+                expected, actual
+            ),
+            Self::HashMismatch { expected, actual } => {
+                write!(f, "capability hash {} does not match {}", actual, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CapabilityDeltaError {}
+
+impl CapabilitySet {
+    /// Create a set at `version`; later entries win for duplicate protocols
+    pub fn new(capabilities: impl IntoIterator<Item = Capability>, version: u64) -> Self {
+        Self {
+            version,
+            capabilities: capabilities
+                .into_iter()
+                .map(|c| (c.protocol, c.version))
+                .collect(),
+        }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn len(&self) -> usize {
+        self.capabilities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.capabilities.is_empty()
+    }
+
+    /// Version offered for `protocol`, if any
+    pub fn get(&self, protocol: &str) -> Option<&str> {
+        self.capabilities.get(protocol).map(String::as_str)
+    }
+
+    /// Capabilities sorted by protocol
+    pub fn to_vec(&self) -> Vec<Capability> {
+        self.capabilities
+            .iter()
+            .map(|(protocol, version)| Capability {
+                protocol: protocol.clone(),
+                version: version.clone(),
+            })
+            .collect()
+    }
+
+    /// Content hash, independent of the version number
+    pub fn hash(&self) -> String {
+        let mut hash = FNV_OFFSET;
+        for (protocol, version) in &self.capabilities {
+            for byte in protocol
+                .bytes()
+                .chain(std::iter::once(b'@'))
+                .chain(version.bytes())
+                .chain(std::iter::once(b'\n'))
+            {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+        format!("{:016x}", hash)
+    }
+
+    /// Compute the delta from `self` to `next`, stamped with `next`'s version
+    pub fn diff(&self, next: &CapabilitySet) -> CapabilityDelta {
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for (protocol, version) in &next.capabilities {
+            let capability = Capability {
+                protocol: protocol.clone(),
+                version: version.clone(),
+            };
+            match self.capabilities.get(protocol) {
+                None => added.push(capability),
+                Some(old) if old != version => changed.push(capability),
+                Some(_) => {}
+            }
+        }
+
+        let removed = self
+            .capabilities
+            .keys()
+            .filter(|protocol| !next.capabilities.contains_key(*protocol))
+            .cloned()
+            .collect();
+
+        CapabilityDelta {
+            base_version: self.version,
+            version: next.version,
+            added,
+            removed,
+            changed,
+            hash: next.hash(),
+        }
+    }
+
+    /// Replace the set, bumping the version only if something changed.
+    ///
+    /// Returns the delta to send, or `None` when the new list is identical, so
+    /// repeated plugin rescans do not produce traffic.
+    pub fn replace(
+        &mut self,
+        capabilities: impl IntoIterator<Item = Capability>,
+    ) -> Option<CapabilityDelta> {
+        let next = Self::new(capabilities, self.version + 1);
+        if next.capabilities == self.capabilities {
+            return None;
+        }
+        let delta = self.diff(&next);
+        *self = next;
+        Some(delta)
+    }
+
+    /// Apply a delta received from the owning device.
+    ///
+    /// On error the set is left untouched.
+    pub fn apply(&mut self, delta: &CapabilityDelta) -> Result<(), CapabilityDeltaError> {
+        if delta.base_version != self.version {
+            return Err(CapabilityDeltaError::VersionMismatch {
+                expected: delta.base_version,
+                actual: self.version,
+            });
+        }
+
+        let mut next = self.capabilities.clone();
+        for protocol in &delta.removed {
+            next.remove(protocol);
+        }
+        for capability in delta.added.iter().chain(&delta.changed) {
+            next.insert(capability.protocol.clone(), capability.version.clone());
+        }
+
+        let next = Self {
+            version: delta.version,
+            capabilities: next,
+        };
+        let actual = next.hash();
+        if actual != delta.hash {
+            return Err(CapabilityDeltaError::HashMismatch {
+                expected: delta.hash.clone(),
+                actual,
+            });
+        }
+
+        *self = next;
+        Ok(())
+    }
+
+    /// Full-set announcement
+    pub fn to_update_message(&self) -> SignalingMessage {
+        SignalingMessage::CapabilityUpdate {
+            capabilities: self.to_vec(),
+            version: self.version,
+            hash: self.hash(),
+        }
+    }
+}
+
+impl CapabilityDelta {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    pub fn into_message(self) -> SignalingMessage {
+        SignalingMessage::CapabilityDelta {
+            base_version: self.base_version,
+            version: self.version,
+            added: self.added,
+            removed: self.removed,
+            changed: self.changed,
+            hash: self.hash,
+        }
+    }
+
+    /// Extract a delta from a `capability_delta` message
+    pub fn from_message(msg: SignalingMessage) -> Option<Self> {
+        match msg {
+            SignalingMessage::CapabilityDelta {
+                base_version,
+                version,
+                added,
+                removed,
+                changed,
+                hash,
+            } => Some(Self {
+                base_version,
+                version,
+                added,
+                removed,
+                changed,
+                hash,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cap(protocol: &str, version: &str) -> Capability {
+        Capability {
+            protocol: protocol.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    fn pairs(capabilities: &[Capability]) -> Vec<(&str, &str)> {
+        capabilities
+            .iter()
+            .map(|c| (c.protocol.as_str(), c.version.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_hash_ignores_order_and_version() {
+        let a = CapabilitySet::new([cap("tasks", "1.0.0"), cap("llm.chat", "2.0.0")], 1);
+        let b = CapabilitySet::new([cap("llm.chat", "2.0.0"), cap("tasks", "1.0.0")], 7);
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(a.hash().len(), 16);
+
+        let c = CapabilitySet::new([cap("tasks", "1.0.1"), cap("llm.chat", "2.0.0")], 1);
+        assert_ne!(a.hash(), c.hash());
+
+        // Known value so other clients can check their implementation
+        assert_eq!(CapabilitySet::default().hash(), "cbf29ce484222325");
+    }
+
+    #[test]
+    fn test_replace_and_apply_roundtrip() {
+        let mut device = CapabilitySet::new([cap("tasks", "1.0.0"), cap("embeddings", "1.0.0")], 1);
+        let mut server = device.clone();
+
+        assert!(device
+            .replace([cap("embeddings", "1.0.0"), cap("tasks", "1.0.0")])
+            .is_none());
+        assert_eq!(device.version(), 1);
+
+        let delta = device
+            .replace([cap("tasks", "1.1.0"), cap("llm.chat", "1.0.0")])
+            .unwrap();
+        assert_eq!(delta.base_version, 1);
+        assert_eq!(delta.version, 2);
+        assert_eq!(pairs(&delta.added), vec![("llm.chat", "1.0.0")]);
+        assert_eq!(delta.removed, vec!["embeddings".to_string()]);
+        assert_eq!(pairs(&delta.changed), vec![("tasks", "1.1.0")]);
+        // Same delta as in the conformance corpus
+        assert_eq!(delta.hash, "fd0ec411f39e3433");
+
+        let msg = delta.into_message();
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"type\":\"capability_delta\""));
+        let delta = CapabilityDelta::from_message(serde_json::from_str(&json).unwrap()).unwrap();
+
+        server.apply(&delta).unwrap();
+        assert_eq!(server, device);
+        assert_eq!(server.get("tasks"), Some("1.1.0"));
+    }
+
+    #[test]
+    fn test_apply_rejects_stale_or_drifted_delta() {
+        let base = CapabilitySet::new([cap("tasks", "1.0.0")], 3);
+        let mut next = base.clone();
+        let delta = next.replace([cap("tasks", "2.0.0")]).unwrap();
+
+        let mut stale = CapabilitySet::new([cap("tasks", "1.0.0")], 2);
+        assert_eq!(
+            stale.apply(&delta),
+            Err(CapabilityDeltaError::VersionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        );
+
+        // Same version but different contents: the result cannot match
+        let mut drifted = CapabilitySet::new([cap("tasks", "1.0.0"), cap("extra", "1.0.0")], 3);
+        let before = drifted.clone();
+        assert!(matches!(
+            drifted.apply(&delta),
+            Err(CapabilityDeltaError::HashMismatch { .. })
+        ));
+        assert_eq!(drifted, before);
+    }
+}
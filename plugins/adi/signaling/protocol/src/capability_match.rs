@@ -0,0 +1,417 @@
+//! Semver matching of capability requests
+//!
+//! A request names a protocol and a version requirement, written
+//! `protocol@requirement` (e.g. `llm.chat@^1.2`) or as a `Capability` whose
+//! version is the requirement. Requirements follow Cargo: `^1.2`, `~1.2.3`,
+//! `=1.2.3`, `>=1.2` and `*`; a bare version such as `1.2` means `^1.2`.
+//!
+//! The signaling server resolves every `capability_request` this way against
+//! the sets the owner's online devices announced. When nothing matches, the
+//! answer is `capability_unavailable` with the reason and the advertised
+//! capability closest to the request, so the caller can fall back to it or
+//! report what is actually on offer.
+
+use crate::{Capability, CapabilityUnavailableReason, RequestId, SignalingMessage};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A `major.minor.patch[-pre][+build]` version; build metadata is ignored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemVer {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl SemVer {
+    /// Parse a version; missing minor or patch parts are 0
+    pub fn parse(version: &str) -> Option<Self> {
+        Self::parse_partial(version).map(|(version, _)| version)
+    }
+
+    /// Version and how many of its numeric parts were given
+    fn parse_partial(version: &str) -> Option<(Self, usize)> {
+        let version = version.trim();
+        let version = version.split_once('+').map_or(version, |(v, _)| v);
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) if !pre.is_empty() => (core, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (version, None),
+        };
+
+        let parts = core
+            .split('.')
+            .map(|part| part.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        if parts.is_empty() || parts.len() > 3 || (pre.is_some() && parts.len() < 3) {
+            return None;
+        }
+        let part = |i: usize| parts.get(i).copied().unwrap_or(0);
+        Some((
+            Self {
+                major: part(0),
+                minor: part(1),
+                patch: part(2),
+                pre,
+            },
+            parts.len(),
+        ))
+    }
+
+    fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: None,
+        }
+    }
+
+    fn same_release(&self, other: &Self) -> bool {
+        (self.major, self.minor, self.patch) == (other.major, other.minor, other.patch)
+    }
+}
+
+impl Ord for SemVer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            // A pre-release sorts before its release
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(b),
+            })
+    }
+}
+
+impl PartialOrd for SemVer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for SemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(ref pre) = self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+/// Versions from `min` (inclusive) up to `max` (exclusive, if bounded)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    min: SemVer,
+    max: Option<SemVer>,
+}
+
+impl VersionReq {
+    pub fn any() -> Self {
+        Self {
+            min: SemVer::new(0, 0, 0),
+            max: None,
+        }
+    }
+
+    pub fn parse(requirement: &str) -> Option<Self> {
+        let requirement = requirement.trim();
+        if requirement.is_empty() || requirement == "*" {
+            return Some(Self::any());
+        }
+        let (op, version) = [">=", "^", "~", "="]
+            .iter()
+            .find_map(|op| requirement.strip_prefix(op).map(|rest| (*op, rest)))
+            .unwrap_or(("^", requirement));
+        let (min, parts) = SemVer::parse_partial(version)?;
+
+        let max = match op {
+            ">=" => None,
+            "=" => Some(match parts {
+                1 => SemVer::new(min.major + 1, 0, 0),
+                2 => SemVer::new(min.major, min.minor + 1, 0),
+                _ => SemVer::new(min.major, min.minor, min.patch + 1),
+            }),
+            "~" => Some(match parts {
+                1 => SemVer::new(min.major + 1, 0, 0),
+                _ => SemVer::new(min.major, min.minor + 1, 0),
+            }),
+            // Caret: the left-most non-zero part given may not change
+            _ => Some(if min.major > 0 || parts == 1 {
+                SemVer::new(min.major + 1, 0, 0)
+            } else if min.minor > 0 || parts == 2 {
+                SemVer::new(0, min.minor + 1, 0)
+            } else {
+                SemVer::new(0, 0, min.patch + 1)
+            }),
+        };
+        Some(Self { min, max })
+    }
+
+    /// Whether `version` satisfies the requirement. Pre-releases only match
+    /// a requirement on a pre-release of the same version.
+    pub fn matches(&self, version: &SemVer) -> bool {
+        if version.pre.is_some() && !(self.min.pre.is_some() && self.min.same_release(version)) {
+            return false;
+        }
+        *version >= self.min && self.max.as_ref().is_none_or(|max| version < max)
+    }
+}
+
+/// Why no advertised capability satisfied a request, and what came closest
+#[derive(Debug, Clone)]
+pub struct CapabilityMismatch {
+    pub reason: CapabilityUnavailableReason,
+    /// Same protocol at the nearest version; `None` if nobody offers the
+    /// protocol
+    pub closest_match: Option<Capability>,
+}
+
+impl CapabilityMismatch {
+    /// `capability_unavailable` answer to the request `request_id`
+    pub fn to_message(&self, request_id: impl Into<RequestId>) -> SignalingMessage {
+        SignalingMessage::CapabilityUnavailable {
+            request_id: request_id.into(),
+            reason: self.reason.clone(),
+            closest_match: self.closest_match.clone(),
+        }
+    }
+}
+
+impl fmt::Display for CapabilityMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason {
+            CapabilityUnavailableReason::UnknownProtocol => write!(f, "protocol is not offered")?,
+            CapabilityUnavailableReason::IncompatibleVersion => {
+                write!(f, "no compatible version is offered")?
+            }
+            CapabilityUnavailableReason::InvalidRequirement => {
+                write!(f, "version requirement is invalid")?
+            }
+        }
+        if let Some(ref closest) = self.closest_match {
+            write!(f, " (closest: {}@{})", closest.protocol, closest.version)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CapabilityMismatch {}
+
+/// Resolves one requested protocol and version requirement against
+/// advertised capabilities
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityMatcher {
+    protocol: String,
+    /// `None` if the requirement did not parse
+    requirement: Option<VersionReq>,
+}
+
+impl CapabilityMatcher {
+    /// Matcher for a `CapabilityRequest`, whose version is a requirement
+    pub fn new(requested: &Capability) -> Self {
+        Self {
+            protocol: requested.protocol.clone(),
+            requirement: VersionReq::parse(&requested.version),
+        }
+    }
+
+    /// Matcher for `protocol@requirement`; without `@` any version matches
+    pub fn parse(spec: &str) -> Self {
+        let (protocol, requirement) = spec.split_once('@').unwrap_or((spec, "*"));
+        Self {
+            protocol: protocol.trim().to_string(),
+            requirement: VersionReq::parse(requirement),
+        }
+    }
+
+    pub fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    pub fn matches(&self, capability: &Capability) -> bool {
+        capability.protocol == self.protocol
+            && self
+                .requirement
+                .as_ref()
+                .zip(SemVer::parse(&capability.version))
+                .is_some_and(|(requirement, version)| requirement.matches(&version))
+    }
+
+    /// The highest advertised version that satisfies the request
+    pub fn resolve(&self, advertised: &[Capability]) -> Result<Capability, CapabilityMismatch> {
+        self.resolve_among([((), advertised)])
+            .map(|((), capability)| capability)
+    }
+
+    /// The device offering the highest satisfying version, out of the
+    /// capability lists of several devices; the first device wins ties, so
+    /// callers can list a preferred device first.
+    pub fn resolve_among<'a, D: Clone>(
+        &self,
+        devices: impl IntoIterator<Item = (D, &'a [Capability])>,
+    ) -> Result<(D, Capability), CapabilityMismatch> {
+        let mut best: Option<(D, &Capability, SemVer)> = None;
+        let mut offered: Vec<(&Capability, SemVer)> = Vec::new();
+        for (device, capabilities) in devices {
+            for capability in capabilities.iter().filter(|c| c.protocol == self.protocol) {
+                let Some(version) = SemVer::parse(&capability.version) else {
+                    continue;
+                };
+                if !self.matches(capability) {
+                    offered.push((capability, version));
+                } else if best
+                    .as_ref()
+                    .is_none_or(|(_, _, current)| version > *current)
+                {
+                    best = Some((device.clone(), capability, version));
+                }
+            }
+        }
+
+        if let Some((device, capability, _)) = best {
+            return Ok((device, capability.clone()));
+        }
+        let reason = match self.requirement {
+            None => CapabilityUnavailableReason::InvalidRequirement,
+            Some(_) if offered.is_empty() => CapabilityUnavailableReason::UnknownProtocol,
+            Some(_) => CapabilityUnavailableReason::IncompatibleVersion,
+        };
+        Err(CapabilityMismatch {
+            reason,
+            closest_match: self.closest(&offered).cloned(),
+        })
+    }
+
+    /// Nearest major version to the requested minimum, the higher version
+    /// among equals; the highest version if the requirement is invalid
+    fn closest<'a>(&self, offered: &[(&'a Capability, SemVer)]) -> Option<&'a Capability> {
+        let distance = |version: &SemVer| {
+            self.requirement
+                .as_ref()
+                .map_or(0, |req| version.major.abs_diff(req.min.major))
+        };
+        offered
+            .iter()
+            .min_by(|(_, a), (_, b)| distance(a).cmp(&distance(b)).then_with(|| b.cmp(a)))
+            .map(|(capability, _)| *capability)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cap(protocol: &str, version: &str) -> Capability {
+        Capability {
+            protocol: protocol.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    fn version(result: Result<Capability, CapabilityMismatch>) -> String {
+        result.unwrap().version
+    }
+
+    fn req_matches(requirement: &str, version: &str) -> bool {
+        VersionReq::parse(requirement)
+            .unwrap()
+            .matches(&SemVer::parse(version).unwrap())
+    }
+
+    #[test]
+    fn test_version_requirements() {
+        assert!(req_matches("^1.2", "1.9.0"));
+        assert!(!req_matches("^1.2", "1.1.9"));
+        assert!(!req_matches("^1.2", "2.0.0"));
+        assert!(req_matches("1.2", "1.4.1"));
+        assert!(req_matches("^0.2.3", "0.2.9"));
+        assert!(!req_matches("^0.2.3", "0.3.0"));
+        assert!(!req_matches("^0.0.3", "0.0.4"));
+        assert!(req_matches("~1.2.3", "1.2.9"));
+        assert!(!req_matches("~1.2.3", "1.3.0"));
+        assert!(req_matches("=1.2", "1.2.7"));
+        assert!(!req_matches("=1.2.3", "1.2.4"));
+        assert!(req_matches(">=1.2", "7.0.0"));
+        assert!(req_matches("*", "0.0.1"));
+        assert!(!req_matches("^1.0.0", "1.1.0-beta"));
+        assert!(req_matches("^1.1.0-alpha", "1.1.0-beta"));
+        assert!(req_matches("^1", "1.0.0+build.5"));
+        assert!(VersionReq::parse("^one").is_none());
+        assert!(SemVer::parse("1.2.3.4").is_none());
+    }
+
+    #[test]
+    fn test_resolve_picks_highest_compatible() {
+        let advertised = [
+            cap("llm.chat", "1.2.0"),
+            cap("llm.chat", "1.5.1"),
+            cap("llm.chat", "2.0.0"),
+            cap("embeddings", "1.0.0"),
+        ];
+        let matcher = CapabilityMatcher::parse("llm.chat@^1.2");
+        assert_eq!(version(matcher.resolve(&advertised)), "1.5.1");
+
+        let matcher = CapabilityMatcher::new(&cap("llm.chat", "2"));
+        assert_eq!(version(matcher.resolve(&advertised)), "2.0.0");
+    }
+
+    #[test]
+    fn test_resolve_explains_mismatch() {
+        let advertised = [cap("llm.chat", "1.4.0"), cap("llm.chat", "3.1.0")];
+
+        let mismatch = CapabilityMatcher::parse("llm.chat@^2.1")
+            .resolve(&advertised)
+            .unwrap_err();
+        assert!(matches!(
+            mismatch.reason,
+            CapabilityUnavailableReason::IncompatibleVersion
+        ));
+        // Both are a major version off; the newer one is preferred
+        assert_eq!(mismatch.closest_match.unwrap().version, "3.1.0");
+
+        let mismatch = CapabilityMatcher::parse("llm.chat@^1.5")
+            .resolve(&advertised)
+            .unwrap_err();
+        assert_eq!(mismatch.closest_match.unwrap().version, "1.4.0");
+
+        let mismatch = CapabilityMatcher::parse("tasks@^1")
+            .resolve(&advertised)
+            .unwrap_err();
+        assert!(matches!(
+            mismatch.reason,
+            CapabilityUnavailableReason::UnknownProtocol
+        ));
+        assert!(mismatch.closest_match.is_none());
+
+        let mismatch = CapabilityMatcher::parse("llm.chat@latest")
+            .resolve(&advertised)
+            .unwrap_err();
+        assert!(matches!(
+            mismatch.reason,
+            CapabilityUnavailableReason::InvalidRequirement
+        ));
+
+        let json = serde_json::to_value(mismatch.to_message("req-1")).unwrap();
+        assert_eq!(json["type"], "capability_unavailable");
+        assert_eq!(json["reason"], "invalid_requirement");
+        assert_eq!(json["closest_match"]["version"], "3.1.0");
+    }
+
+    #[test]
+    fn test_resolve_among_devices() {
+        let a = [cap("embeddings", "1.1.0")];
+        let b = [cap("embeddings", "1.3.0")];
+        let c = [cap("embeddings", "1.3.0")];
+        let matcher = CapabilityMatcher::parse("embeddings@^1.1");
+
+        let (device, capability) = matcher
+            .resolve_among([("a", &a[..]), ("b", &b[..]), ("c", &c[..])])
+            .unwrap();
+        assert_eq!((device, capability.version.as_str()), ("b", "1.3.0"));
+    }
+}
@@ -0,0 +1,63 @@
+//! Helpers for the generated `DisconnectInfo` reconnect hints.
+//!
+//! The wire carries `retry_after_secs` so every language can read it;
+//! [`DisconnectInfo::retry_after`] turns it into a `Duration`.
+
+use crate::{DisconnectInfo, DisconnectReason};
+use std::time::Duration;
+
+impl DisconnectInfo {
+    /// Hint that allows reconnecting, optionally after a delay.
+    pub fn transient(reason: DisconnectReason, retry_after: Option<Duration>) -> Self {
+        Self {
+            reason,
+            retry_after_secs: retry_after.map(|d| d.as_secs()),
+            permanent: false,
+        }
+    }
+
+    /// Hint that forbids reconnecting.
+    pub fn permanent(reason: DisconnectReason) -> Self {
+        Self {
+            reason,
+            retry_after_secs: None,
+            permanent: true,
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after_secs.map(Duration::from_secs)
+    }
+
+    /// Whether a client may reconnect at all. Banned and removed devices
+    /// never may, even if a server forgot to set `permanent`.
+    pub fn should_retry(&self) -> bool {
+        !self.permanent
+            && !matches!(
+                self.reason,
+                DisconnectReason::Banned | DisconnectReason::Removed
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disconnect_info_hints() {
+        let shutdown = DisconnectInfo::transient(
+            DisconnectReason::ServerShutdown,
+            Some(Duration::from_secs(5)),
+        );
+        assert!(shutdown.should_retry());
+        assert_eq!(shutdown.retry_after(), Some(Duration::from_secs(5)));
+        assert_eq!(
+            serde_json::to_value(&shutdown).unwrap(),
+            serde_json::json!({ "reason": "server_shutdown", "retry_after_secs": 5, "permanent": false })
+        );
+
+        assert!(!DisconnectInfo::permanent(DisconnectReason::Replaced).should_retry());
+        assert!(!DisconnectInfo::transient(DisconnectReason::Banned, None).should_retry());
+    }
+}
@@ -0,0 +1,234 @@
+//! Versioned envelope around `SignalingMessage`.
+//!
+//! The envelope adds the sender's protocol version, a message id, the id of
+//! the message being answered and a send time, so peers can negotiate a
+//! version and a request can be traced through relays. Hand-written because
+//! it wraps the generated message enum.
+//!
+//! Peers that predate the envelope send bare messages. Those parse as an
+//! envelope with [`LEGACY_VERSION`], and an envelope with that version is
+//! written bare again, so [`SignalingEnvelope::reply`] answers every peer in
+//! the form it understands.
+
+use crate::{MessageId, SignalingMessage};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Envelope version written by this crate
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Version of bare messages from peers without envelope support
+pub const LEGACY_VERSION: u32 = 0;
+
+/// A `SignalingMessage` with version and tracing metadata.
+#[derive(Debug, Clone)]
+pub struct SignalingEnvelope {
+    pub version: u32,
+    /// Unique per sender; empty for bare messages
+    pub message_id: MessageId,
+    /// `message_id` of the message this one answers
+    pub correlation_id: Option<MessageId>,
+    /// Unix time in milliseconds; absent on bare messages
+    pub sent_at: Option<u64>,
+    pub payload: SignalingMessage,
+}
+
+impl SignalingEnvelope {
+    /// Wrap `payload` with a fresh message id and the current time.
+    pub fn new(payload: SignalingMessage) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            message_id: next_message_id(),
+            correlation_id: None,
+            sent_at: Some(now_millis()),
+            payload,
+        }
+    }
+
+    /// A message without envelope, as sent to and by legacy peers.
+    pub fn bare(payload: SignalingMessage) -> Self {
+        Self {
+            version: LEGACY_VERSION,
+            message_id: MessageId::from(""),
+            correlation_id: None,
+            sent_at: None,
+            payload,
+        }
+    }
+
+    /// Answer to this message, at the highest version both sides speak.
+    pub fn reply(&self, payload: SignalingMessage) -> Self {
+        if self.is_legacy() {
+            return Self::bare(payload);
+        }
+        Self {
+            version: self.version.min(PROTOCOL_VERSION),
+            correlation_id: Some(self.message_id.clone()),
+            ..Self::new(payload)
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.version == LEGACY_VERSION
+    }
+}
+
+impl From<SignalingMessage> for SignalingEnvelope {
+    fn from(payload: SignalingMessage) -> Self {
+        Self::new(payload)
+    }
+}
+
+/// Wire form of a versioned envelope
+#[derive(Serialize, Deserialize)]
+struct EnvelopeWire<I, P> {
+    version: u32,
+    message_id: I,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<I>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sent_at: Option<u64>,
+    payload: P,
+}
+
+impl Serialize for SignalingEnvelope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_legacy() {
+            return self.payload.serialize(serializer);
+        }
+        EnvelopeWire {
+            version: self.version,
+            message_id: &self.message_id,
+            correlation_id: self.correlation_id.as_ref(),
+            sent_at: self.sent_at,
+            payload: &self.payload,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SignalingEnvelope {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Every bare message has a `type` tag; an envelope keeps it in `payload`
+        let value = serde_json::Value::deserialize(deserializer)?;
+        if value.get("type").is_some() {
+            return SignalingMessage::deserialize(value)
+                .map(Self::bare)
+                .map_err(D::Error::custom);
+        }
+
+        let wire = EnvelopeWire::<MessageId, SignalingMessage>::deserialize(value)
+            .map_err(D::Error::custom)?;
+        if wire.version == LEGACY_VERSION {
+            return Err(D::Error::custom(
+                "envelope version 0 is reserved for bare messages",
+            ));
+        }
+        Ok(Self {
+            version: wire.version,
+            message_id: wire.message_id,
+            correlation_id: wire.correlation_id,
+            sent_at: wire.sent_at,
+            payload: wire.payload,
+        })
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Process start time and pid plus a counter: unique per sender without
+/// pulling a random number generator into the protocol crate.
+fn next_message_id() -> MessageId {
+    static PREFIX: OnceLock<String> = OnceLock::new();
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let prefix = PREFIX.get_or_init(|| format!("{:x}{:x}", now_millis(), std::process::id()));
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    MessageId::from(format!("{}-{:x}", prefix, n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ping() -> SignalingMessage {
+        SignalingMessage::SyncData {
+            payload: json!({"k": 1}),
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn test_envelope_wire_format() {
+        let envelope = SignalingEnvelope::new(ping());
+        let value = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(value["version"], PROTOCOL_VERSION);
+        assert_eq!(value["message_id"], envelope.message_id.as_str());
+        assert_eq!(value["payload"]["type"], "sync_data");
+        assert!(value.get("correlation_id").is_none());
+
+        let parsed: SignalingEnvelope = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.message_id, envelope.message_id);
+        assert_eq!(parsed.sent_at, envelope.sent_at);
+        assert!(matches!(parsed.payload, SignalingMessage::SyncData { .. }));
+
+        assert_ne!(
+            SignalingEnvelope::new(ping()).message_id,
+            envelope.message_id
+        );
+    }
+
+    #[test]
+    fn test_bare_messages_are_accepted_and_answered_bare() {
+        let bare: SignalingEnvelope =
+            serde_json::from_str(r#"{"type":"sync_data","payload":{"k":1}}"#).unwrap();
+        assert!(bare.is_legacy());
+        assert!(bare.sent_at.is_none());
+
+        let reply = bare.reply(ping());
+        assert_eq!(
+            serde_json::to_value(&reply).unwrap(),
+            json!({"type": "sync_data", "payload": {"k": 1}})
+        );
+    }
+
+    #[test]
+    fn test_reply_correlates_and_negotiates_version() {
+        let request: SignalingEnvelope = serde_json::from_value(json!({
+            "version": PROTOCOL_VERSION + 1,
+            "message_id": "m-1",
+            "sent_at": 1_700_000_000_000u64,
+            "payload": {"type": "sync_data", "payload": {}},
+            "trace": "fields from newer versions are ignored",
+        }))
+        .unwrap();
+        assert_eq!(request.version, PROTOCOL_VERSION + 1);
+
+        let reply = request.reply(ping());
+        assert_eq!(reply.version, PROTOCOL_VERSION);
+        assert_eq!(reply.correlation_id.as_deref(), Some("m-1"));
+    }
+
+    #[test]
+    fn test_invalid_envelopes_are_rejected() {
+        let unknown_type =
+            serde_json::from_str::<SignalingEnvelope>(r#"{"type":"no_such_message"}"#);
+        assert!(unknown_type.is_err());
+
+        let reserved = serde_json::from_value::<SignalingEnvelope>(json!({
+            "version": LEGACY_VERSION,
+            "message_id": "m-1",
+            "payload": {"type": "sync_data", "payload": {}},
+        }));
+        assert!(reserved.is_err());
+    }
+}
@@ -0,0 +1,297 @@
+//! Typed identifiers for devices, sessions, hives, requests and messages.
+//!
+//! All IDs are plain strings on the wire. Deserializing validates them, so a
+//! message carrying an empty or malformed ID is rejected as a whole. The
+//! `From<String>`/`From<&str>` conversions are unchecked so call sites that
+//! still pass bare strings keep compiling with `.into()`, and the IDs compare
+//! equal to strings either way round; use `new` for strings that did not come
+//! through deserialization. The generated messages use these types for their
+//! `device_id`, `hive_id`, `request_id` and `message_id` fields (see
+//! `build.rs`).
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+pub const MAX_ID_LEN: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdError {
+    Empty(&'static str),
+    TooLong(&'static str, usize),
+    InvalidChar(&'static str, char),
+}
+
+impl fmt::Display for IdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdError::Empty(kind) => write!(f, "{} must not be empty", kind),
+            IdError::TooLong(kind, len) => {
+                write!(f, "{} is {} bytes, max is {}", kind, len, MAX_ID_LEN)
+            }
+            IdError::InvalidChar(kind, c) => {
+                write!(f, "{} contains invalid character {:?}", kind, c)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IdError {}
+
+fn validate_id(kind: &'static str, value: &str) -> Result<(), IdError> {
+    if value.is_empty() {
+        return Err(IdError::Empty(kind));
+    }
+    if value.len() > MAX_ID_LEN {
+        return Err(IdError::TooLong(kind, value.len()));
+    }
+    if let Some(c) = value.chars().find(|c| c.is_whitespace() || c.is_control()) {
+        return Err(IdError::InvalidChar(kind, c));
+    }
+    Ok(())
+}
+
+macro_rules! string_id {
+    ($(#[$meta:meta])* $name:ident, $kind:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Self::new(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl $name {
+            /// Create a validated ID.
+            pub fn new(value: impl Into<String>) -> Result<Self, IdError> {
+                let value = value.into();
+                validate_id($kind, &value)?;
+                Ok(Self(value))
+            }
+
+            /// Check an ID that arrived through an unchecked conversion.
+            pub fn validate(&self) -> Result<(), IdError> {
+                validate_id($kind, &self.0)
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_inner(self) -> String {
+                self.0
+            }
+        }
+
+        #[cfg(feature = "schema")]
+        impl schemars::JsonSchema for $name {
+            fn schema_name() -> String {
+                stringify!($name).to_string()
+            }
+
+            fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+                schemars::schema::SchemaObject {
+                    instance_type: Some(schemars::schema::InstanceType::String.into()),
+                    string: Some(Box::new(schemars::schema::StringValidation {
+                        max_length: Some(MAX_ID_LEN as u32),
+                        min_length: Some(1),
+                        pattern: Some(r"^[^\s\p{Cc}]+$".to_string()),
+                    })),
+                    ..Default::default()
+                }
+                .into()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = IdError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::new(s)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                Self(value.to_string())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                &self.0 == other
+            }
+        }
+
+        impl PartialEq<$name> for str {
+            fn eq(&self, other: &$name) -> bool {
+                self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for &str {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for String {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+    };
+}
+
+string_id!(
+    /// Registered device (cocoon, hive, browser, ...).
+    DeviceId,
+    "device_id"
+);
+string_id!(
+    /// WebRTC session between a client and a device.
+    SessionId,
+    "session_id"
+);
+string_id!(
+    /// Hive orchestrator instance.
+    HiveId,
+    "hive_id"
+);
+string_id!(
+    /// Correlates a request with its response.
+    RequestId,
+    "request_id"
+);
+string_id!(
+    /// Identifies one [`SignalingEnvelope`](crate::SignalingEnvelope).
+    MessageId,
+    "message_id"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_ids_are_transparent_on_the_wire() {
+        let id = DeviceId::new("dev-123").unwrap();
+        assert_eq!(serde_json::to_string(&id).unwrap(), r#""dev-123""#);
+
+        let parsed: DeviceId = serde_json::from_str(r#""dev-123""#).unwrap();
+        assert_eq!(parsed, id);
+        assert_eq!(parsed, "dev-123");
+        assert_eq!(parsed.to_string(), "dev-123");
+    }
+
+    #[test]
+    fn test_deserialize_validates() {
+        assert!(serde_json::from_str::<DeviceId>(r#""""#).is_err());
+        assert!(serde_json::from_str::<HiveId>(r#""hive 1""#).is_err());
+
+        let json = r#"{"type":"hive_drain_cocoon_result","request_id":"","container_id":"c-1","success":true}"#;
+        assert!(serde_json::from_str::<crate::SignalingMessage>(json).is_err());
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(SessionId::new(""), Err(IdError::Empty("session_id")));
+        assert_eq!(
+            HiveId::new("hive 1"),
+            Err(IdError::InvalidChar("hive_id", ' '))
+        );
+        assert!(matches!(
+            RequestId::new("x".repeat(MAX_ID_LEN + 1)),
+            Err(IdError::TooLong("request_id", _))
+        ));
+        assert!("req-1".parse::<RequestId>().is_ok());
+
+        // Unchecked conversions defer validation to the caller
+        let unchecked = SessionId::from("");
+        assert!(unchecked.validate().is_err());
+    }
+
+    #[test]
+    fn test_map_lookup_by_str() {
+        let mut sessions = HashMap::new();
+        sessions.insert(SessionId::from("s-1"), 1);
+        assert_eq!(sessions.get("s-1"), Some(&1));
+    }
+
+    #[test]
+    fn test_messages_use_typed_ids() {
+        let json = r#"{"type":"hive_drain_cocoon_result","request_id":"req-1","container_id":"c-1","success":true,"target_hive_id":"hive-b"}"#;
+        let msg: crate::SignalingMessage = serde_json::from_str(json).unwrap();
+        match &msg {
+            crate::SignalingMessage::HiveDrainCocoonResult {
+                request_id,
+                target_hive_id,
+                ..
+            } => {
+                assert_eq!(*request_id, RequestId::from("req-1"));
+                assert_eq!(target_hive_id.as_ref().unwrap(), "hive-b");
+            }
+            other => panic!("Expected HiveDrainCocoonResult, got: {:?}", other),
+        }
+        assert_eq!(
+            serde_json::to_value(&msg).unwrap()["target_hive_id"],
+            "hive-b"
+        );
+    }
+}
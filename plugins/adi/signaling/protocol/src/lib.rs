@@ -5,9 +5,40 @@
 
 include!(concat!(env!("OUT_DIR"), "/generated_protocol.rs"));
 
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+pub mod binary;
+pub mod capabilities;
+pub mod capability_match;
+pub mod disconnect;
+pub mod envelope;
+pub mod ids;
+pub mod members;
+pub mod pagination;
+pub mod queued;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod sdp;
+
+pub use binary::{BinaryFrame, BinaryFrameError, RawBinaryFrame};
+pub use capabilities::{CapabilityDelta, CapabilityDeltaError, CapabilitySet};
+pub use capability_match::{CapabilityMatcher, CapabilityMismatch, SemVer, VersionReq};
+pub use envelope::{SignalingEnvelope, LEGACY_VERSION, PROTOCOL_VERSION};
+pub use ids::{DeviceId, HiveId, IdError, MessageId, RequestId, SessionId};
+pub use members::{
+    authorize_connect, authorize_member_change, authorize_operate, claim_role, member_role,
+    MemberAction, MemberChangeError,
+};
 pub use messages::*;
+pub use pagination::{Cursor, CursorError, Page, PageRequest};
+pub use queued::{retrieve_after_register, DeliveryUpdate, PendingDeliveries, PendingDelivery};
+pub use sdp::{SanitizedSdp, SdpError, SdpMetadata, SdpPolicy};
 pub use types::*;
 
+/// Largest warm pool a hive keeps per cocoon kind: signaling refuses larger
+/// `HiveSetPoolSize` pushes and hives clamp their own pool sizes to it.
+pub const MAX_POOL_SIZE: u32 = 32;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,7 +78,7 @@ mod tests {
     #[test]
     fn test_device_info_serialization() {
         let device = DeviceInfo {
-            device_id: "dev-123".to_string(),
+            device_id: "dev-123".into(),
             tags: std::collections::HashMap::from([("kind".into(), "desktop".into())]),
             online: true,
             device_type: Some("cocoon".to_string()),
@@ -80,6 +111,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sync_data_priority_serialization() {
+        let msg = SignalingMessage::SyncData {
+            payload: serde_json::json!({"k": 1}),
+            priority: Some(RelayPriority::Bulk),
+        };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains("\"priority\":\"bulk\""));
+
+        // Senders that predate priorities omit the field entirely
+        let legacy: SignalingMessage =
+            serde_json::from_str(r#"{"type":"sync_data","payload":{"k":1}}"#).unwrap();
+        match legacy {
+            SignalingMessage::SyncData { payload, priority } => {
+                assert_eq!(payload["k"], 1);
+                assert!(priority.is_none());
+            }
+            _ => panic!("Wrong message type"),
+        }
+    }
+
     #[test]
     fn test_connection_info_serialization() {
         let info = ConnectionInfo {
@@ -0,0 +1,276 @@
+//! Role checks for the users sharing a device.
+//!
+//! The first user to claim a device (registering it with their setup token)
+//! becomes its owner. Later claimants join as viewers; only the owner can
+//! raise them with `device_update_member_role`. Knowing the device secret
+//! proves access to the device, not the owner's consent to share it.
+//!
+//! | Role       | Connect | Operate | Manage members |
+//! |------------|---------|---------|----------------|
+//! | `owner`    | yes     | yes     | yes            |
+//! | `operator` | yes     | yes     | no             |
+//! | `viewer`   | yes     | no      | no             |
+//!
+//! Connecting is reaching the device with `sync_data`; the server stamps the
+//! member's role on the `VerifiedSender` so the device can keep viewers
+//! read-only. Operating covers config pushes and anything the device changes
+//! for the sender. Any member may list the members and leave with
+//! `device_remove_member`; a device always keeps its owner (see
+//! [`authorize_member_change`]).
+
+use crate::{CocoonMember, CocoonRole};
+use std::fmt;
+
+impl CocoonRole {
+    /// Change the device: config pushes, commands, terminal input
+    pub fn can_operate(&self) -> bool {
+        matches!(self, CocoonRole::Owner | CocoonRole::Operator)
+    }
+
+    /// Change other members' roles or remove them
+    pub fn can_manage_members(&self) -> bool {
+        matches!(self, CocoonRole::Owner)
+    }
+}
+
+impl fmt::Display for CocoonRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CocoonRole::Owner => "owner",
+            CocoonRole::Operator => "operator",
+            CocoonRole::Viewer => "viewer",
+        })
+    }
+}
+
+/// Role of `user_id` among `members`, `None` if it is not a member
+pub fn member_role(members: &[CocoonMember], user_id: &str) -> Option<CocoonRole> {
+    members
+        .iter()
+        .find(|m| m.user_id == user_id)
+        .map(|m| m.role.clone())
+}
+
+/// Role later claimants join with
+pub const CLAIM_ROLE: CocoonRole = CocoonRole::Viewer;
+
+/// Role a new claimant gets: owner for the first claim, otherwise
+/// [`CLAIM_ROLE`]
+pub fn claim_role(members: &[CocoonMember]) -> CocoonRole {
+    if members.is_empty() {
+        CocoonRole::Owner
+    } else {
+        CLAIM_ROLE
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MemberChangeError {
+    /// The acting user is not a member of the device
+    NotMember,
+    /// The acting user's role does not allow the action
+    NotPermitted(CocoonRole, MemberAction),
+    /// The target user is not a member of the device
+    UnknownMember(String),
+    /// The change would leave the device without an owner
+    LastOwner,
+}
+
+impl fmt::Display for MemberChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemberChangeError::NotMember => write!(f, "Not a member of this device"),
+            MemberChangeError::NotPermitted(role, action) => {
+                write!(f, "A device {} cannot {}", role, action)
+            }
+            MemberChangeError::UnknownMember(user_id) => {
+                write!(f, "User {} is not a member of this device", user_id)
+            }
+            MemberChangeError::LastOwner => write!(f, "A device must keep its owner"),
+        }
+    }
+}
+
+impl std::error::Error for MemberChangeError {}
+
+/// What a member tried to do, for [`MemberChangeError::NotPermitted`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberAction {
+    Operate,
+    ManageMembers,
+}
+
+impl fmt::Display for MemberAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MemberAction::Operate => "operate it",
+            MemberAction::ManageMembers => "change other members",
+        })
+    }
+}
+
+/// Role `user_id` connects with; members only
+pub fn authorize_connect(
+    members: &[CocoonMember],
+    user_id: &str,
+) -> Result<CocoonRole, MemberChangeError> {
+    member_role(members, user_id).ok_or(MemberChangeError::NotMember)
+}
+
+/// Check that `user_id` may change the device (see [`CocoonRole::can_operate`])
+pub fn authorize_operate(members: &[CocoonMember], user_id: &str) -> Result<(), MemberChangeError> {
+    let role = authorize_connect(members, user_id)?;
+    if !role.can_operate() {
+        return Err(MemberChangeError::NotPermitted(role, MemberAction::Operate));
+    }
+    Ok(())
+}
+
+/// Check that `actor` may give `target` the role `role`, or remove `target`
+/// when `role` is `None`. Owners may change anyone; other members may only
+/// remove themselves.
+pub fn authorize_member_change(
+    members: &[CocoonMember],
+    actor: &str,
+    target: &str,
+    role: Option<&CocoonRole>,
+) -> Result<(), MemberChangeError> {
+    let actor_role = member_role(members, actor).ok_or(MemberChangeError::NotMember)?;
+    let target_role = member_role(members, target)
+        .ok_or_else(|| MemberChangeError::UnknownMember(target.to_string()))?;
+
+    let leaving = actor == target && role.is_none();
+    if !actor_role.can_manage_members() && !leaving {
+        return Err(MemberChangeError::NotPermitted(
+            actor_role,
+            MemberAction::ManageMembers,
+        ));
+    }
+
+    let demotes_owner =
+        matches!(target_role, CocoonRole::Owner) && !matches!(role, Some(CocoonRole::Owner));
+    let owners = members
+        .iter()
+        .filter(|m| matches!(m.role, CocoonRole::Owner))
+        .count();
+    if demotes_owner && owners == 1 {
+        return Err(MemberChangeError::LastOwner);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(user_id: &str, role: CocoonRole) -> CocoonMember {
+        CocoonMember {
+            user_id: user_id.to_string(),
+            role,
+            claimed_at: 1_700_000_000,
+        }
+    }
+
+    fn team() -> Vec<CocoonMember> {
+        vec![
+            member("alice", CocoonRole::Owner),
+            member("bob", CocoonRole::Operator),
+            member("carol", CocoonRole::Viewer),
+        ]
+    }
+
+    #[test]
+    fn test_role_permissions() {
+        assert!(CocoonRole::Operator.can_operate());
+        assert!(!CocoonRole::Viewer.can_operate());
+        assert!(!CocoonRole::Operator.can_manage_members());
+        assert!(CocoonRole::Owner.can_manage_members());
+        assert_eq!(
+            serde_json::to_string(&CocoonRole::Operator).unwrap(),
+            "\"operator\""
+        );
+        assert_eq!(CocoonRole::Viewer.to_string(), "viewer");
+    }
+
+    #[test]
+    fn test_claim_role() {
+        assert!(matches!(claim_role(&[]), CocoonRole::Owner));
+        assert!(matches!(claim_role(&team()), CocoonRole::Viewer));
+    }
+
+    #[test]
+    fn test_authorize_connect_and_operate() {
+        let members = team();
+        assert!(matches!(
+            authorize_connect(&members, "carol"),
+            Ok(CocoonRole::Viewer)
+        ));
+        assert!(matches!(
+            authorize_connect(&members, "mallory"),
+            Err(MemberChangeError::NotMember)
+        ));
+
+        assert!(authorize_operate(&members, "bob").is_ok());
+        assert!(matches!(
+            authorize_operate(&members, "carol"),
+            Err(MemberChangeError::NotPermitted(
+                CocoonRole::Viewer,
+                MemberAction::Operate
+            ))
+        ));
+        assert!(matches!(
+            authorize_operate(&members, "mallory"),
+            Err(MemberChangeError::NotMember)
+        ));
+    }
+
+    #[test]
+    fn test_authorize_member_change() {
+        let members = team();
+        assert!(
+            authorize_member_change(&members, "alice", "bob", Some(&CocoonRole::Viewer)).is_ok()
+        );
+        assert!(authorize_member_change(&members, "alice", "carol", None).is_ok());
+        assert!(matches!(
+            authorize_member_change(&members, "bob", "carol", None),
+            Err(MemberChangeError::NotPermitted(
+                CocoonRole::Operator,
+                MemberAction::ManageMembers
+            ))
+        ));
+        assert!(matches!(
+            authorize_member_change(&members, "bob", "bob", Some(&CocoonRole::Owner)),
+            Err(MemberChangeError::NotPermitted(
+                CocoonRole::Operator,
+                MemberAction::ManageMembers
+            ))
+        ));
+        // Anyone may leave
+        assert!(authorize_member_change(&members, "carol", "carol", None).is_ok());
+        assert!(matches!(
+            authorize_member_change(&members, "mallory", "carol", None),
+            Err(MemberChangeError::NotMember)
+        ));
+        assert!(matches!(
+            authorize_member_change(&members, "alice", "dave", None),
+            Err(MemberChangeError::UnknownMember(user)) if user == "dave"
+        ));
+    }
+
+    #[test]
+    fn test_owner_stays() {
+        let members = team();
+        assert!(matches!(
+            authorize_member_change(&members, "alice", "alice", None),
+            Err(MemberChangeError::LastOwner)
+        ));
+        assert!(matches!(
+            authorize_member_change(&members, "alice", "alice", Some(&CocoonRole::Viewer)),
+            Err(MemberChangeError::LastOwner)
+        ));
+        // Handing the device over is allowed
+        assert!(
+            authorize_member_change(&members, "alice", "bob", Some(&CocoonRole::Owner)).is_ok()
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Cursor-based pagination shared by list responses.
+//!
+//! Hand-written rather than generated: TypeSpec models here have no generics,
+//! so list messages embed `Page<T>` fields and use [`PageRequest`] for input.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// Bumped if the cursor payload changes; old cursors are then rejected.
+const CURSOR_VERSION: &str = "1";
+
+/// One page of a list response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Total number of items across all pages
+    pub total: u64,
+    /// Opaque cursor for the next page; absent on the last page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Pagination parameters sent with list requests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+impl PageRequest {
+    pub fn first(limit: u32) -> Self {
+        Self {
+            cursor: None,
+            limit: Some(limit),
+        }
+    }
+
+    /// Request the page following `page`, or `None` if it was the last one.
+    pub fn after<T>(page: &Page<T>, limit: u32) -> Option<Self> {
+        page.next_cursor.as_ref().map(|cursor| Self {
+            cursor: Some(cursor.clone()),
+            limit: Some(limit),
+        })
+    }
+
+    /// Requested page size, defaulted and clamped to `1..=MAX_PAGE_SIZE`.
+    pub fn effective_limit(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// Offset into the full list decoded from `cursor` (0 when absent).
+    pub fn offset(&self) -> Result<u64, CursorError> {
+        match &self.cursor {
+            Some(cursor) => Ok(Cursor::decode(cursor)?.offset),
+            None => Ok(0),
+        }
+    }
+}
+
+/// Position in a list, serialized as an opaque URL-safe string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub offset: u64,
+}
+
+impl Cursor {
+    pub fn new(offset: u64) -> Self {
+        Self { offset }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", CURSOR_VERSION, self.offset))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, CursorError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|_| CursorError::Malformed)?;
+        let raw = String::from_utf8(bytes).map_err(|_| CursorError::Malformed)?;
+
+        let (version, offset) = raw.split_once(':').ok_or(CursorError::Malformed)?;
+        if version != CURSOR_VERSION {
+            return Err(CursorError::UnsupportedVersion(version.to_string()));
+        }
+
+        offset
+            .parse()
+            .map(Self::new)
+            .map_err(|_| CursorError::Malformed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CursorError {
+    Malformed,
+    UnsupportedVersion(String),
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CursorError::Malformed => write!(f, "malformed pagination cursor"),
+            CursorError::UnsupportedVersion(v) => {
+                write!(f, "unsupported pagination cursor version: {}", v)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CursorError {}
+
+impl<T> Page<T> {
+    /// Slice a fully materialized list according to `request`.
+    pub fn paginate(items: Vec<T>, request: &PageRequest) -> Result<Self, CursorError> {
+        let total = items.len() as u64;
+        let offset = request.offset()?.min(total);
+        let limit = u64::from(request.effective_limit());
+
+        let items: Vec<T> = items
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect();
+
+        let end = offset + items.len() as u64;
+        let next_cursor = (end < total).then(|| Cursor::new(end).encode());
+
+        Ok(Self {
+            items,
+            total,
+            next_cursor,
+        })
+    }
+
+    /// Wrap a complete list as a single page (for endpoints not yet paginating).
+    pub fn complete(items: Vec<T>) -> Self {
+        Self {
+            total: items.len() as u64,
+            items,
+            next_cursor: None,
+        }
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.next_cursor.is_none()
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate_walks_all_pages() {
+        let items: Vec<u32> = (0..7).collect();
+        let mut request = PageRequest::first(3);
+        let mut seen = Vec::new();
+
+        loop {
+            let page = Page::paginate(items.clone(), &request).unwrap();
+            assert_eq!(page.total, 7);
+            seen.extend(page.items.iter().copied());
+            match PageRequest::after(&page, 3) {
+                Some(next) => request = next,
+                None => break,
+            }
+        }
+
+        assert_eq!(seen, items);
+    }
+
+    #[test]
+    fn test_cursor_roundtrip_and_rejects_garbage() {
+        let cursor = Cursor::new(42).encode();
+        assert_eq!(Cursor::decode(&cursor).unwrap().offset, 42);
+
+        assert_eq!(Cursor::decode("!!!"), Err(CursorError::Malformed));
+        let future = URL_SAFE_NO_PAD.encode("9:1");
+        assert_eq!(
+            Cursor::decode(&future),
+            Err(CursorError::UnsupportedVersion("9".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_page_serialization_omits_last_cursor() {
+        let page = Page::complete(vec!["a", "b"]);
+        let json = serde_json::to_string(&page).unwrap();
+        assert_eq!(json, r#"{"items":["a","b"],"total":2}"#);
+
+        let request: PageRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(request.effective_limit(), DEFAULT_PAGE_SIZE);
+        assert_eq!(request.offset().unwrap(), 0);
+    }
+}
@@ -0,0 +1,171 @@
+//! Store-and-forward of `sync_data` for offline devices.
+//!
+//! The server holds `sync_data` whose target device is offline and answers
+//! the sender with `sync_queued_delivery`. Once the device is registered
+//! again it sends `sync_retrieve_queued`: the held messages arrive as plain
+//! `sync_data`, so the device handles them like live traffic, and each sender
+//! gets a `sync_delivery_receipt`. Hand-written because it spans several
+//! messages.
+
+use crate::{DeviceId, MessageId, SignalingMessage};
+use std::collections::HashMap;
+
+/// What a device sends once `msg` confirms its registration, so messages
+/// held while it was offline are replayed. `None` for every other message.
+pub fn retrieve_after_register(msg: &SignalingMessage) -> Option<SignalingMessage> {
+    match msg {
+        SignalingMessage::DeviceRegisterResponse { device_id, .. } => {
+            Some(SignalingMessage::SyncRetrieveQueued {
+                device_id: device_id.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// A message held by the server for an offline device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingDelivery {
+    pub device_id: DeviceId,
+    /// Unix seconds
+    pub expires_at: u64,
+}
+
+/// What [`PendingDeliveries::observe`] learned from a server message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryUpdate {
+    Queued {
+        message_id: MessageId,
+        device_id: DeviceId,
+    },
+    Delivered {
+        message_id: MessageId,
+        device_id: DeviceId,
+    },
+}
+
+/// Sender-side view of the messages the server holds on its behalf.
+#[derive(Debug, Default)]
+pub struct PendingDeliveries {
+    pending: HashMap<MessageId, PendingDelivery>,
+}
+
+impl PendingDeliveries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a `sync_queued_delivery` or settle a `sync_delivery_receipt`;
+    /// other messages are ignored.
+    pub fn observe(&mut self, msg: &SignalingMessage) -> Option<DeliveryUpdate> {
+        match msg {
+            SignalingMessage::SyncQueuedDelivery {
+                message_id,
+                device_id,
+                expires_at,
+            } => {
+                self.pending.insert(
+                    message_id.clone(),
+                    PendingDelivery {
+                        device_id: device_id.clone(),
+                        expires_at: *expires_at,
+                    },
+                );
+                Some(DeliveryUpdate::Queued {
+                    message_id: message_id.clone(),
+                    device_id: device_id.clone(),
+                })
+            }
+            SignalingMessage::SyncDeliveryReceipt {
+                message_id,
+                device_id,
+                ..
+            } => {
+                self.pending.remove(message_id);
+                Some(DeliveryUpdate::Delivered {
+                    message_id: message_id.clone(),
+                    device_id: device_id.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Forget messages the server dropped unretrieved by `now` (unix
+    /// seconds) and return their ids.
+    pub fn expire(&mut self, now: u64) -> Vec<MessageId> {
+        let expired: Vec<MessageId> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.expires_at <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &expired {
+            self.pending.remove(id);
+        }
+        expired
+    }
+
+    pub fn get(&self, message_id: &str) -> Option<&PendingDelivery> {
+        self.pending.get(message_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retrieve_after_register() {
+        let registered = SignalingMessage::DeviceRegisterResponse {
+            device_id: "dev-1".into(),
+            tags: None,
+        };
+        match retrieve_after_register(&registered) {
+            Some(SignalingMessage::SyncRetrieveQueued { device_id }) => {
+                assert_eq!(device_id, "dev-1")
+            }
+            other => panic!("Expected SyncRetrieveQueued, got: {:?}", other),
+        }
+        assert!(retrieve_after_register(&SignalingMessage::PairingCreateCode).is_none());
+    }
+
+    #[test]
+    fn test_pending_deliveries() {
+        let mut pending = PendingDeliveries::new();
+        for (id, expires_at) in [("m1", 100), ("m2", 200)] {
+            pending.observe(&SignalingMessage::SyncQueuedDelivery {
+                message_id: id.into(),
+                device_id: "dev-1".into(),
+                expires_at,
+            });
+        }
+        assert_eq!(pending.len(), 2);
+
+        let update = pending.observe(&SignalingMessage::SyncDeliveryReceipt {
+            message_id: "m2".into(),
+            device_id: "dev-1".into(),
+            delivered_at: 150,
+        });
+        assert_eq!(
+            update,
+            Some(DeliveryUpdate::Delivered {
+                message_id: "m2".into(),
+                device_id: "dev-1".into(),
+            })
+        );
+        assert!(pending.get("m2").is_none());
+
+        assert!(pending.expire(99).is_empty());
+        assert_eq!(pending.expire(100), vec!["m1".to_string()]);
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+//! JSON Schema export of the wire protocol (feature `schema`)
+//!
+//! `signaling.tsp` generates the Rust types and, through them, these schemas
+//! for the browser extension and web clients. Schemas follow the serde form:
+//! messages are objects tagged by `type`, optional fields may be left out and
+//! IDs are non-empty strings without whitespace. Golden messages every
+//! implementation must accept live in `conformance/<name>.json`;
+//! `tests/conformance.rs` checks that they validate and round-trip through
+//! the Rust types unchanged.
+
+use crate::{
+    AuthOption, AuthRequirement, CapabilityUnavailableReason, CocoonRole, DisconnectReason,
+    OwnershipAction, OwnershipTokenType, RelayPriority, SignalingMessage,
+};
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use std::path::{Path, PathBuf};
+
+/// Message schemas with a conformance corpus, by name
+pub const MESSAGE_SCHEMAS: &[&str] = &["signaling_message"];
+
+/// Every exported schema by name: the message enum first, then the enums
+/// shared between messages.
+pub fn protocol_schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("signaling_message", schema_for!(SignalingMessage)),
+        ("auth_requirement", schema_for!(AuthRequirement)),
+        ("auth_option", schema_for!(AuthOption)),
+        ("relay_priority", schema_for!(RelayPriority)),
+        ("disconnect_reason", schema_for!(DisconnectReason)),
+        ("ownership_action", schema_for!(OwnershipAction)),
+        ("ownership_token_type", schema_for!(OwnershipTokenType)),
+        ("cocoon_role", schema_for!(CocoonRole)),
+        (
+            "capability_unavailable_reason",
+            schema_for!(CapabilityUnavailableReason),
+        ),
+    ]
+}
+
+/// Schema of the message enum or a shared enum, by its name in
+/// [`protocol_schemas`]
+pub fn schema(name: &str) -> Option<RootSchema> {
+    protocol_schemas()
+        .into_iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, schema)| schema)
+}
+
+/// Write every schema to `<dir>/<name>.schema.json` and return the paths.
+pub fn write_schemas(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for (name, schema) in protocol_schemas() {
+        let path = dir.join(format!("{}.schema.json", name));
+        let json = serde_json::to_string_pretty(&schema).map_err(std::io::Error::other)?;
+        std::fs::write(&path, json + "\n")?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_schema_lists_variant_tags() {
+        let signaling = serde_json::to_string(&schema("signaling_message").unwrap()).unwrap();
+        for tag in ["device_register", "sync_data", "hive_spawn_cocoon"] {
+            assert!(
+                signaling.contains(&format!("\"{}\"", tag)),
+                "missing {}",
+                tag
+            );
+        }
+
+        let priority = serde_json::to_value(schema("relay_priority").unwrap()).unwrap();
+        assert_eq!(
+            priority["enum"],
+            serde_json::json!(["interactive", "normal", "bulk"])
+        );
+        assert!(schema("unknown").is_none());
+        assert!(MESSAGE_SCHEMAS.iter().all(|name| schema(name).is_some()));
+    }
+
+    #[test]
+    fn test_id_schema_rejects_empty_ids() {
+        let schema = serde_json::to_value(schema_for!(crate::DeviceId)).unwrap();
+        assert_eq!(schema["minLength"], 1);
+        assert_eq!(schema["maxLength"], crate::ids::MAX_ID_LEN);
+    }
+}
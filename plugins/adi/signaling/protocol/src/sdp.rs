@@ -0,0 +1,587 @@
+//! Parsing, validation and sanitization of the SDP carried by WebRTC offers
+//! and answers.
+//!
+//! Offers and answers travel as raw strings, so anything relaying them
+//! should run them through [`sanitize`] first: it rejects oversized or
+//! malformed descriptions, drops attributes the deployment does not allow
+//! (e.g. host candidates that reveal local addresses) and returns
+//! [`SdpMetadata`] that is safe to log. Only the line structure of RFC 8866
+//! is checked; codecs and attribute values are left to the peers.
+
+use serde::Serialize;
+use std::fmt;
+
+/// Default limit on the size of a whole description.
+pub const MAX_SDP_BYTES: usize = 64 * 1024;
+/// Longest single line accepted.
+pub const MAX_LINE_BYTES: usize = 4096;
+/// Most `m=` sections accepted.
+pub const MAX_MEDIA_SECTIONS: usize = 64;
+
+/// One `<type>=<value>` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdpLine {
+    pub kind: char,
+    pub value: String,
+}
+
+impl SdpLine {
+    /// Name of an `a=` attribute (`candidate` for `a=candidate:...`).
+    pub fn attribute_name(&self) -> Option<&str> {
+        if self.kind != 'a' {
+            return None;
+        }
+        Some(
+            self.value
+                .split_once(':')
+                .map_or(self.value.as_str(), |(name, _)| name),
+        )
+    }
+
+    /// Value of an `a=` attribute, `None` for flags like `a=sendrecv`.
+    pub fn attribute_value(&self) -> Option<&str> {
+        if self.kind != 'a' {
+            return None;
+        }
+        self.value.split_once(':').map(|(_, value)| value)
+    }
+}
+
+impl fmt::Display for SdpLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.kind, self.value)
+    }
+}
+
+/// A parsed description: the lines in order, with session-level lines
+/// before the first `m=`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionDescription {
+    pub lines: Vec<SdpLine>,
+}
+
+impl SessionDescription {
+    /// Media kinds, fingerprints and candidate counts.
+    pub fn metadata(&self) -> SdpMetadata {
+        let mut metadata = SdpMetadata::default();
+        for line in &self.lines {
+            match line.kind {
+                'm' => {
+                    let mut fields = line.value.split(' ');
+                    metadata.media.push(MediaSection {
+                        kind: MediaKind::from(fields.next().unwrap_or_default()),
+                        protocol: fields.nth(1).unwrap_or_default().to_string(),
+                        mid: None,
+                    });
+                }
+                'a' => match (line.attribute_name(), line.attribute_value()) {
+                    (Some("mid"), Some(mid)) => {
+                        if let Some(media) = metadata.media.last_mut() {
+                            media.mid = Some(mid.to_string());
+                        }
+                    }
+                    (Some("fingerprint"), Some(value)) => {
+                        if let Some((algorithm, hash)) = value.split_once(' ') {
+                            let fingerprint = Fingerprint {
+                                algorithm: algorithm.to_ascii_lowercase(),
+                                value: hash.to_string(),
+                            };
+                            if !metadata.fingerprints.contains(&fingerprint) {
+                                metadata.fingerprints.push(fingerprint);
+                            }
+                        }
+                    }
+                    (Some("candidate"), Some(value)) => {
+                        metadata.candidates += 1;
+                        if candidate_type(value) == Some("relay") {
+                            metadata.relay_candidates += 1;
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        metadata
+    }
+}
+
+impl fmt::Display for SessionDescription {
+    /// Lines joined with CRLF, as RFC 8866 requires.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            write!(f, "{}\r\n", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Audio,
+    Video,
+    Application,
+    Text,
+    Message,
+    Other(String),
+}
+
+impl From<&str> for MediaKind {
+    fn from(kind: &str) -> Self {
+        match kind {
+            "audio" => Self::Audio,
+            "video" => Self::Video,
+            "application" => Self::Application,
+            "text" => Self::Text,
+            "message" => Self::Message,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for MediaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Audio => write!(f, "audio"),
+            Self::Video => write!(f, "video"),
+            Self::Application => write!(f, "application"),
+            Self::Text => write!(f, "text"),
+            Self::Message => write!(f, "message"),
+            Self::Other(kind) => write!(f, "{}", kind),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MediaSection {
+    pub kind: MediaKind,
+    /// Transport, e.g. `UDP/TLS/RTP/SAVPF` or `UDP/DTLS/SCTP`
+    pub protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mid: Option<String>,
+}
+
+/// DTLS certificate fingerprint (`a=fingerprint:sha-256 AB:CD:...`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Fingerprint {
+    /// Lowercased hash function name
+    pub algorithm: String,
+    pub value: String,
+}
+
+/// What a description carries, without addresses or credentials.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SdpMetadata {
+    pub media: Vec<MediaSection>,
+    pub fingerprints: Vec<Fingerprint>,
+    pub candidates: usize,
+    pub relay_candidates: usize,
+}
+
+impl fmt::Display for SdpMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let media: Vec<String> = self.media.iter().map(|m| m.kind.to_string()).collect();
+        let fingerprints: Vec<&str> = self
+            .fingerprints
+            .iter()
+            .map(|f| f.algorithm.as_str())
+            .collect();
+        write!(
+            f,
+            "media=[{}] fingerprints=[{}] candidates={} (relay {})",
+            media.join(","),
+            fingerprints.join(","),
+            self.candidates,
+            self.relay_candidates
+        )
+    }
+}
+
+/// Which ICE candidates may stay in a description.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CandidatePolicy {
+    #[default]
+    All,
+    /// Only TURN relay candidates; host and reflexive ones expose addresses.
+    RelayOnly,
+    /// No candidates at all; peers trickle them through a relay instead.
+    None,
+}
+
+/// Limits and stripping rules applied by [`sanitize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdpPolicy {
+    pub max_bytes: usize,
+    pub candidates: CandidatePolicy,
+    /// `a=` attribute names removed wherever they appear.
+    pub disallowed_attributes: Vec<String>,
+}
+
+impl Default for SdpPolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: MAX_SDP_BYTES,
+            candidates: CandidatePolicy::All,
+            disallowed_attributes: Vec::new(),
+        }
+    }
+}
+
+impl SdpPolicy {
+    /// For deployments that must not leak local addresses: relay candidates
+    /// only, with connection and related addresses replaced by `0.0.0.0`.
+    pub fn restricted() -> Self {
+        Self {
+            candidates: CandidatePolicy::RelayOnly,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn disallow(mut self, attribute: impl Into<String>) -> Self {
+        self.disallowed_attributes.push(attribute.into());
+        self
+    }
+
+    fn keeps(&self, line: &SdpLine) -> bool {
+        let Some(name) = line.attribute_name() else {
+            return true;
+        };
+        if self.disallowed_attributes.iter().any(|a| a == name) {
+            return false;
+        }
+        match (name, self.candidates) {
+            ("candidate", CandidatePolicy::None) => false,
+            ("candidate", CandidatePolicy::RelayOnly) => {
+                line.attribute_value().and_then(candidate_type) == Some("relay")
+            }
+            _ => true,
+        }
+    }
+}
+
+/// A description after [`sanitize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizedSdp {
+    pub sdp: String,
+    pub metadata: SdpMetadata,
+    /// Lines dropped or rewritten by the policy
+    pub stripped: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdpError {
+    TooLarge {
+        size: usize,
+        max: usize,
+    },
+    Empty,
+    /// The first line is not `v=0`
+    UnsupportedVersion,
+    /// 1-based line number
+    MalformedLine(usize),
+    LineTooLong(usize),
+    MissingField(char),
+    TooManyMediaSections(usize),
+}
+
+impl fmt::Display for SdpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdpError::TooLarge { size, max } => {
+                write!(f, "SDP is {} bytes, the limit is {}", size, max)
+            }
+            SdpError::Empty => write!(f, "SDP is empty"),
+            SdpError::UnsupportedVersion => write!(f, "SDP must start with v=0"),
+            SdpError::MalformedLine(line) => write!(f, "malformed SDP line {}", line),
+            SdpError::LineTooLong(line) => {
+                write!(
+                    f,
+                    "SDP line {} is longer than {} bytes",
+                    line, MAX_LINE_BYTES
+                )
+            }
+            SdpError::MissingField(kind) => write!(f, "SDP has no {}= line", kind),
+            SdpError::TooManyMediaSections(count) => write!(
+                f,
+                "SDP has {} media sections, the limit is {}",
+                count, MAX_MEDIA_SECTIONS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SdpError {}
+
+/// Parse a description, accepting CRLF or bare LF line endings.
+pub fn parse(sdp: &str) -> Result<SessionDescription, SdpError> {
+    parse_with_limit(sdp, MAX_SDP_BYTES)
+}
+
+/// Parse with a custom size limit.
+pub fn parse_with_limit(sdp: &str, max_bytes: usize) -> Result<SessionDescription, SdpError> {
+    if sdp.len() > max_bytes {
+        return Err(SdpError::TooLarge {
+            size: sdp.len(),
+            max: max_bytes,
+        });
+    }
+
+    let body = sdp.trim_end_matches(['\r', '\n']);
+    if body.trim().is_empty() {
+        return Err(SdpError::Empty);
+    }
+
+    let mut lines = Vec::new();
+    let mut media_sections = 0;
+    for (i, raw) in body.split('\n').enumerate() {
+        let number = i + 1;
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        if raw.len() > MAX_LINE_BYTES {
+            return Err(SdpError::LineTooLong(number));
+        }
+
+        let mut chars = raw.chars();
+        let (Some(kind), Some('=')) = (chars.next(), chars.next()) else {
+            return Err(SdpError::MalformedLine(number));
+        };
+        let value = chars.as_str();
+        if !kind.is_ascii_lowercase() || value.chars().any(|c| c.is_control() && c != '\t') {
+            return Err(SdpError::MalformedLine(number));
+        }
+
+        if kind == 'm' {
+            media_sections += 1;
+            if media_sections > MAX_MEDIA_SECTIONS {
+                return Err(SdpError::TooManyMediaSections(media_sections));
+            }
+            // <media> <port> <proto> <fmt> ...
+            if value.split(' ').filter(|f| !f.is_empty()).count() < 4 {
+                return Err(SdpError::MalformedLine(number));
+            }
+        }
+
+        lines.push(SdpLine {
+            kind,
+            value: value.to_string(),
+        });
+    }
+
+    if lines[0]
+        != (SdpLine {
+            kind: 'v',
+            value: "0".to_string(),
+        })
+    {
+        return Err(SdpError::UnsupportedVersion);
+    }
+    let session = lines.iter().take_while(|l| l.kind != 'm');
+    for required in ['o', 's', 't'] {
+        if !session.clone().any(|l| l.kind == required) {
+            return Err(SdpError::MissingField(required));
+        }
+    }
+
+    Ok(SessionDescription { lines })
+}
+
+/// Check a description without keeping the parse.
+pub fn validate(sdp: &str, max_bytes: usize) -> Result<(), SdpError> {
+    parse_with_limit(sdp, max_bytes).map(|_| ())
+}
+
+/// Validate `sdp`, apply `policy` and return the rewritten description with
+/// its metadata. Metadata describes what is left after stripping.
+pub fn sanitize(sdp: &str, policy: &SdpPolicy) -> Result<SanitizedSdp, SdpError> {
+    let parsed = parse_with_limit(sdp, policy.max_bytes)?;
+    let total = parsed.lines.len();
+
+    let mut rewritten = 0;
+    let lines: Vec<SdpLine> = parsed
+        .lines
+        .into_iter()
+        .filter(|line| policy.keeps(line))
+        .map(|mut line| {
+            if policy.candidates != CandidatePolicy::All {
+                let masked = match line.attribute_name() {
+                    _ if line.kind == 'c' => mask_connection(&line.value),
+                    Some("candidate") => Some(mask_related_address(&line.value)),
+                    _ => None,
+                };
+                if let Some(masked) = masked.filter(|m| *m != line.value) {
+                    line.value = masked;
+                    rewritten += 1;
+                }
+            }
+            line
+        })
+        .collect();
+
+    let description = SessionDescription { lines };
+    Ok(SanitizedSdp {
+        stripped: total - description.lines.len() + rewritten,
+        metadata: description.metadata(),
+        sdp: description.to_string(),
+    })
+}
+
+/// `typ` of an `a=candidate` value:
+/// `<foundation> <component> <transport> <priority> <address> <port> typ <type> ...`
+fn candidate_type(value: &str) -> Option<&str> {
+    let mut fields = value.split(' ').skip_while(|f| *f != "typ");
+    fields.next()?;
+    fields.next()
+}
+
+/// Relay candidates name the address they were allocated for in `raddr`;
+/// replace it with `0.0.0.0` and `rport` with `0`, as browsers do in
+/// private mode.
+fn mask_related_address(value: &str) -> String {
+    let mut previous = "";
+    let fields: Vec<&str> = value
+        .split(' ')
+        .map(|field| {
+            let masked = match previous {
+                "raddr" => "0.0.0.0",
+                "rport" => "0",
+                _ => field,
+            };
+            previous = field;
+            masked
+        })
+        .collect();
+    fields.join(" ")
+}
+
+/// `IN IP4 192.168.1.10` -> `IN IP4 0.0.0.0` (`::` for IP6)
+fn mask_connection(value: &str) -> Option<String> {
+    let mut fields = value.split(' ');
+    let (net, addr_type) = (fields.next()?, fields.next()?);
+    let unspecified = match addr_type {
+        "IP4" => "0.0.0.0",
+        "IP6" => "::",
+        _ => return None,
+    };
+    Some(format!("{} {} {}", net, addr_type, unspecified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFER: &str = "v=0\r\n\
+        o=- 4611731400430051336 2 IN IP4 127.0.0.1\r\n\
+        s=-\r\n\
+        t=0 0\r\n\
+        a=group:BUNDLE 0 1\r\n\
+        a=fingerprint:SHA-256 4A:AD:B9:B1:3F:82:18:3B:54:02:12:DF:3E:5D:49:6B:19:E5:7C:AB\r\n\
+        m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+        c=IN IP4 192.168.1.20\r\n\
+        a=mid:0\r\n\
+        a=candidate:1 1 udp 2122260223 192.168.1.20 54321 typ host generation 0\r\n\
+        a=candidate:2 1 udp 1686052607 203.0.113.7 54321 typ srflx raddr 192.168.1.20 rport 54321\r\n\
+        a=candidate:3 1 udp 41885439 198.51.100.2 3478 typ relay raddr 203.0.113.7 rport 54321\r\n\
+        a=sendrecv\r\n\
+        m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+        c=IN IP4 0.0.0.0\r\n\
+        a=mid:1\r\n\
+        a=sctp-port:5000\r\n";
+
+    #[test]
+    fn test_parse_and_metadata() {
+        let parsed = parse(OFFER).unwrap();
+        assert_eq!(parsed.lines[0].to_string(), "v=0");
+        assert_eq!(parsed.lines[4].attribute_name(), Some("group"));
+
+        let metadata = parsed.metadata();
+        assert_eq!(metadata.media.len(), 2);
+        assert_eq!(metadata.media[0].kind, MediaKind::Audio);
+        assert_eq!(metadata.media[0].mid.as_deref(), Some("0"));
+        assert_eq!(metadata.media[1].kind, MediaKind::Application);
+        assert_eq!(metadata.media[1].protocol, "UDP/DTLS/SCTP");
+        assert_eq!(metadata.fingerprints[0].algorithm, "sha-256");
+        assert_eq!(metadata.candidates, 3);
+        assert_eq!(metadata.relay_candidates, 1);
+        assert_eq!(
+            metadata.to_string(),
+            "media=[audio,application] fingerprints=[sha-256] candidates=3 (relay 1)"
+        );
+
+        // Bare LF endings parse too, and re-serialize with CRLF
+        let lf = parse(&OFFER.replace("\r\n", "\n")).unwrap();
+        assert_eq!(lf.to_string(), OFFER);
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        assert_eq!(parse(""), Err(SdpError::Empty));
+        assert_eq!(
+            parse("v=1\r\no=-\r\ns=-\r\nt=0 0\r\n"),
+            Err(SdpError::UnsupportedVersion)
+        );
+        assert_eq!(
+            parse("v=0\r\ns=-\r\nt=0 0\r\n"),
+            Err(SdpError::MissingField('o'))
+        );
+        assert_eq!(
+            parse("v=0\r\no=-\r\ns=-\r\n\r\nt=0 0\r\n"),
+            Err(SdpError::MalformedLine(4))
+        );
+        assert_eq!(
+            parse("v=0\r\no=-\r\ns=-\r\nt=0 0\r\nm=audio 9\r\n"),
+            Err(SdpError::MalformedLine(5))
+        );
+        assert_eq!(
+            parse("v=0\r\no=-\r\ns=-\r\nt=0 0\r\na=x\u{0}y\r\n"),
+            Err(SdpError::MalformedLine(5))
+        );
+        assert_eq!(
+            validate(OFFER, 64),
+            Err(SdpError::TooLarge {
+                size: OFFER.len(),
+                max: 64
+            })
+        );
+
+        let long = format!(
+            "v=0\r\no=-\r\ns={}\r\nt=0 0\r\n",
+            "x".repeat(MAX_LINE_BYTES)
+        );
+        assert_eq!(parse(&long), Err(SdpError::LineTooLong(3)));
+    }
+
+    #[test]
+    fn test_sanitize_restricted() {
+        let sanitized = sanitize(OFFER, &SdpPolicy::restricted()).unwrap();
+        assert!(!sanitized.sdp.contains("192.168.1.20"));
+        assert!(!sanitized.sdp.contains("typ host"));
+        assert!(!sanitized.sdp.contains("typ srflx"));
+        assert!(sanitized.sdp.contains("typ relay"));
+        assert!(!sanitized.sdp.contains("203.0.113.7"));
+        assert!(sanitized
+            .sdp
+            .contains("typ relay raddr 0.0.0.0 rport 0\r\n"));
+        assert!(sanitized.sdp.contains("c=IN IP4 0.0.0.0\r\n"));
+        // Two candidates dropped, a connection line and the relay candidate masked
+        assert_eq!(sanitized.stripped, 4);
+        assert_eq!(sanitized.metadata.candidates, 1);
+        assert!(parse(&sanitized.sdp).is_ok());
+
+        let sanitized = sanitize(
+            OFFER,
+            &SdpPolicy {
+                candidates: CandidatePolicy::None,
+                ..SdpPolicy::default()
+            }
+            .disallow("sctp-port"),
+        )
+        .unwrap();
+        assert_eq!(sanitized.metadata.candidates, 0);
+        assert!(!sanitized.sdp.contains("sctp-port"));
+
+        let untouched = sanitize(OFFER, &SdpPolicy::default()).unwrap();
+        assert_eq!(untouched.sdp, OFFER);
+        assert_eq!(untouched.stripped, 0);
+    }
+}
@@ -0,0 +1,59 @@
+//! Golden messages in `conformance/` must round-trip through the Rust types
+//! unchanged and, with the `schema` feature, validate against the exported
+//! schemas. Other implementations run the same corpus against their own
+//! encoders and the schemas.
+
+use lib_signaling_protocol::SignalingMessage;
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Deserialize)]
+struct Golden {
+    name: String,
+    message: Value,
+}
+
+fn corpus(name: &str) -> Vec<Golden> {
+    let path = format!("{}/conformance/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    serde_json::from_str(&text).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+#[test]
+fn test_golden_messages_round_trip() {
+    for golden in corpus("signaling_message") {
+        let parsed: SignalingMessage = serde_json::from_value(golden.message.clone())
+            .unwrap_or_else(|e| panic!("{}: {}", golden.name, e));
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            golden.message,
+            "{} changed on round trip",
+            golden.name
+        );
+    }
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn test_golden_messages_match_schemas() {
+    for name in lib_signaling_protocol::schema::MESSAGE_SCHEMAS {
+        let schema =
+            serde_json::to_value(lib_signaling_protocol::schema::schema(name).unwrap()).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        for golden in corpus(name) {
+            let errors: Vec<String> = validator
+                .iter_errors(&golden.message)
+                .map(|e| format!("{}: {}", e.instance_path, e))
+                .collect();
+            assert!(errors.is_empty(), "{}/{}: {:?}", name, golden.name, errors);
+        }
+
+        // A message of the wrong shape must not pass
+        assert!(!validator.is_valid(&serde_json::json!({ "type": "no_such_message" })));
+        // Nor one with an empty id
+        assert!(!validator.is_valid(&serde_json::json!({
+            "type": "device_register_response",
+            "device_id": "",
+        })));
+    }
+}
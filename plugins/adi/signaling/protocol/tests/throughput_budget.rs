@@ -0,0 +1,74 @@
+//! Size and scaling budgets for relayed messages
+//!
+//! Envelope overhead budgets and the timing-based round-trip scaling check run
+//! with the regular test suite; for absolute numbers run
+//! `cargo bench -p lib-signaling-protocol`.
+
+use lib_signaling_protocol::{BinaryFrame, RelayPriority, SignalingMessage};
+use lib_timing_budget::{assert_scales_linearly, best_of};
+use serde_json::json;
+
+/// Bytes the `sync_data` envelope may add around its payload
+const MAX_SYNC_DATA_OVERHEAD: usize = 64;
+
+/// Bytes a binary frame may add around its raw payload (header + envelope JSON)
+const MAX_BINARY_FRAME_OVERHEAD: usize = 192;
+
+fn silk_output(data_len: usize) -> serde_json::Value {
+    json!({
+        "type": "silk_output",
+        "session_id": "b7e2-session",
+        "command_id": "c91d-command",
+        "stream": "stdout",
+        "data": "x".repeat(data_len),
+    })
+}
+
+fn sync_data(payload: serde_json::Value) -> SignalingMessage {
+    SignalingMessage::SyncData {
+        payload,
+        priority: Some(RelayPriority::Interactive),
+    }
+}
+
+#[test]
+fn test_sync_data_overhead_is_bounded() {
+    for len in [0, 64, 64 * 1024] {
+        let payload = silk_output(len);
+        let payload_len = serde_json::to_string(&payload).unwrap().len();
+        let frame_len = serde_json::to_string(&sync_data(payload)).unwrap().len();
+        assert!(
+            frame_len - payload_len <= MAX_SYNC_DATA_OVERHEAD,
+            "sync_data adds {} bytes around a {} byte payload",
+            frame_len - payload_len,
+            payload_len
+        );
+    }
+}
+
+#[test]
+fn test_binary_frame_overhead_is_constant() {
+    for len in [0, 64, 1024 * 1024] {
+        let payload = vec![0xa5u8; len];
+        let frame = BinaryFrame::new(sync_data(serde_json::Value::Null), &payload)
+            .encode()
+            .unwrap();
+        assert!(
+            frame.len() - len <= MAX_BINARY_FRAME_OVERHEAD,
+            "binary frame adds {} bytes around a {} byte payload",
+            frame.len() - len,
+            len
+        );
+    }
+}
+
+#[test]
+fn test_round_trip_scales_linearly() {
+    assert_scales_linearly("sync_data round trip", 64 * 1024, 1024 * 1024, |len| {
+        let json = serde_json::to_string(&sync_data(silk_output(len))).unwrap();
+        best_of(5, || {
+            let msg: SignalingMessage = serde_json::from_str(&json).unwrap();
+            serde_json::to_string(&msg).unwrap();
+        })
+    });
+}
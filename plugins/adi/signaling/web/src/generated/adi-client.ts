@@ -36,7 +36,7 @@ export const pairingUseCode = (c: Connection, code: string) =>
 
 const SVC_HIVE = 'hive';
 
-export const hiveRegister = (c: Connection, params: { hive_id: string; version: string; cocoon_kinds: CocoonKind[]; hive_id_signature: string; }) =>
+export const hiveRegister = (c: Connection, params: { hive_id: string; version: string; cocoon_kinds: CocoonKind[]; hive_id_signature: string; runners?: string[]; gpus?: GpuInfo[]; singletons?: string[]; }) =>
   c.request<unknown>(SVC_HIVE, 'register', params);
 
 const SVC_ROOM = 'room';
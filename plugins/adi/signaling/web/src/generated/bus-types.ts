@@ -3,7 +3,7 @@
  * DO NOT EDIT.
  */
 
-import type { ConnectionInfo, DeviceInfo, RoomInfo } from './models';
+import type { ConnectionInfo, DeviceInfo, DisconnectInfo, RoomInfo } from './models';
 
 import { WsState } from './enums';
 
@@ -40,6 +40,7 @@ export interface AdiSignalingPeerConnectedEvent {
 export interface AdiSignalingPeerDisconnectedEvent {
   url: string;
   peerId: string;
+  info?: DisconnectInfo;
 }
 
 export interface AdiSignalingAuthAnonymousEvent {
@@ -56,6 +57,7 @@ export interface AdiSignalingDeviceRegisteredEvent {
 export interface AdiSignalingDeviceDeregisteredEvent {
   url: string;
   deviceId: string;
+  info?: DisconnectInfo;
 }
 
 export interface AdiSignalingTagsUpdatedEvent {
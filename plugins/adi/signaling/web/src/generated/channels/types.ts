@@ -20,6 +20,85 @@ export enum AuthOption {
   Anonymous = "anonymous",
 }
 
+export enum RelayPriority {
+  Interactive = "interactive",
+  Normal = "normal",
+  Bulk = "bulk",
+}
+
+export enum DisconnectReason {
+  ConnectionLost = "connection_lost",
+  Deregistered = "deregistered",
+  Replaced = "replaced",
+  Removed = "removed",
+  Banned = "banned",
+  ServerShutdown = "server_shutdown",
+}
+
+export interface DisconnectInfo {
+  reason: DisconnectReason;
+  retry_after_secs?: number;
+  permanent: boolean;
+}
+
+export enum OwnershipAction {
+  Claimed = "claimed",
+  Transferred = "transferred",
+  Removed = "removed",
+}
+
+export enum OwnershipTokenType {
+  SetupToken = "setup_token",
+  DeviceSecret = "device_secret",
+  AccessToken = "access_token",
+}
+
+export interface OwnershipAuditEvent {
+  device_id: string;
+  action: OwnershipAction;
+  user_id: string;
+  actor?: string;
+  token_type: OwnershipTokenType;
+  at: number;
+}
+
+export interface DelegatedGrant {
+  token_id: string;
+  device_id: string;
+  issued_by: string;
+  scopes: string[];
+  expires_at: number;
+}
+
+export enum CocoonRole {
+  Owner = "owner",
+  Operator = "operator",
+  Viewer = "viewer",
+}
+
+export interface CocoonMember {
+  user_id: string;
+  role: CocoonRole;
+  claimed_at: number;
+}
+
+export interface VerifiedSender {
+  user_id?: string;
+  role?: CocoonRole;
+  grant?: DelegatedGrant;
+}
+
+export interface AdiServiceUsage {
+  client: string;
+  service: string;
+  requests: number;
+  bytes_in: number;
+  bytes_out: number;
+  stream_ms: number;
+  throttled: number;
+  rejected: number;
+}
+
 export interface IceServer {
   urls: string[];
   username?: string;
@@ -46,9 +125,36 @@ export interface CocoonKind {
   image: string;
 }
 
+export interface GpuInfo {
+  model: string;
+  vram_total_mb: number;
+  vram_free_mb: number;
+  driver?: string;
+}
+
+export interface CocoonPoolStatus {
+  kind: string;
+  size: number;
+  ready: number;
+  warming: number;
+  claimed: number;
+  misses: number;
+}
+
 export interface RoomInfo {
   room_id: string;
   owner_user_id: string;
   granted_users: string[];
   actors: DeviceInfo[];
 }
+
+export interface Capability {
+  protocol: string;
+  version: string;
+}
+
+export enum CapabilityUnavailableReason {
+  UnknownProtocol = "unknown_protocol",
+  IncompatibleVersion = "incompatible_version",
+  InvalidRequirement = "invalid_requirement",
+}
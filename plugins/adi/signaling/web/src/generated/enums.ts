@@ -20,3 +20,42 @@ export enum AuthOption {
   Verified = "verified",
   Anonymous = "anonymous",
 }
+
+export enum RelayPriority {
+  Interactive = "interactive",
+  Normal = "normal",
+  Bulk = "bulk",
+}
+
+export enum DisconnectReason {
+  ConnectionLost = "connection_lost",
+  Deregistered = "deregistered",
+  Replaced = "replaced",
+  Removed = "removed",
+  Banned = "banned",
+  ServerShutdown = "server_shutdown",
+}
+
+export enum OwnershipAction {
+  Claimed = "claimed",
+  Transferred = "transferred",
+  Removed = "removed",
+}
+
+export enum OwnershipTokenType {
+  SetupToken = "setup_token",
+  DeviceSecret = "device_secret",
+  AccessToken = "access_token",
+}
+
+export enum CocoonRole {
+  Owner = "owner",
+  Operator = "operator",
+  Viewer = "viewer",
+}
+
+export enum CapabilityUnavailableReason {
+  UnknownProtocol = "unknown_protocol",
+  IncompatibleVersion = "incompatible_version",
+  InvalidRequirement = "invalid_requirement",
+}
@@ -3,7 +3,53 @@
  * DO NOT EDIT.
  */
 
-import { WsState, AuthRequirement, AuthOption } from './enums';
+import { WsState, AuthRequirement, AuthOption, RelayPriority, DisconnectReason, OwnershipAction, OwnershipTokenType, CocoonRole, CapabilityUnavailableReason } from './enums';
+
+export interface DisconnectInfo {
+  reason: DisconnectReason;
+  retry_after_secs?: number;
+  permanent: boolean;
+}
+
+export interface OwnershipAuditEvent {
+  device_id: string;
+  action: OwnershipAction;
+  user_id: string;
+  actor?: string;
+  token_type: OwnershipTokenType;
+  at: number;
+}
+
+export interface DelegatedGrant {
+  token_id: string;
+  device_id: string;
+  issued_by: string;
+  scopes: string[];
+  expires_at: number;
+}
+
+export interface CocoonMember {
+  user_id: string;
+  role: CocoonRole;
+  claimed_at: number;
+}
+
+export interface VerifiedSender {
+  user_id?: string;
+  role?: CocoonRole;
+  grant?: DelegatedGrant;
+}
+
+export interface AdiServiceUsage {
+  client: string;
+  service: string;
+  requests: number;
+  bytes_in: number;
+  bytes_out: number;
+  stream_ms: number;
+  throttled: number;
+  rejected: number;
+}
 
 export interface IceServer {
   urls: string[];
@@ -31,9 +77,30 @@ export interface CocoonKind {
   image: string;
 }
 
+export interface GpuInfo {
+  model: string;
+  vram_total_mb: number;
+  vram_free_mb: number;
+  driver?: string;
+}
+
+export interface CocoonPoolStatus {
+  kind: string;
+  size: number;
+  ready: number;
+  warming: number;
+  claimed: number;
+  misses: number;
+}
+
 export interface RoomInfo {
   room_id: string;
   owner_user_id: string;
   granted_users: string[];
   actors: DeviceInfo[];
 }
+
+export interface Capability {
+  protocol: string;
+  version: string;
+}
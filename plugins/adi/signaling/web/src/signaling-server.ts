@@ -1,14 +1,18 @@
 import { Logger, trace, type EventBus } from '@adi-family/sdk-plugin';
 import { ActionsBusKey } from '@adi-family/plugin-actions-feed';
 import { AdiAuthBusKey, AdiSignalingBusKey, WsState } from './generated';
-import type { RoomInfo, ConnectionInfo } from './generated';
-import type { DeviceInfo, SignalingMessage } from './generated/channels';
+import type { RoomInfo, ConnectionInfo, RelayPriority, DisconnectInfo, DisconnectReason } from './generated';
+import type { DeviceInfo, DisconnectInfo as WireDisconnectInfo, SignalingMessage } from './generated/channels';
 import { createWebSocket, type WsControl } from './websocket';
 
 export type TokenGetter = (authDomain: string) => Promise<string | null>;
 
 const SOURCE = 'signaling';
 
+// The wire and bus enums are separate declarations with the same values.
+const toBusInfo = (info?: WireDisconnectInfo): DisconnectInfo | undefined =>
+  info && { ...info, reason: info.reason as string as DisconnectReason };
+
 export class SignalingServer {
   readonly url: string;
 
@@ -171,8 +175,8 @@ export class SignalingServer {
   }
 
   @trace('sending sync data')
-  sendSyncData(payload: unknown): void {
-    this.ws.send({ type: 'sync_data', payload });
+  sendSyncData(payload: unknown, priority?: RelayPriority): void {
+    this.ws.send({ type: 'sync_data', payload, ...(priority ? { priority } : {}) });
   }
 
   getRooms(): ReadonlyMap<string, RoomInfo> {
@@ -352,7 +356,7 @@ export class SignalingServer {
         this.connectedPeers.delete(msg.peer_id);
         this.bus.emit(
           AdiSignalingBusKey.PeerDisconnected,
-          { url: this.url, peerId: msg.peer_id },
+          { url: this.url, peerId: msg.peer_id, info: toBusInfo(msg.info) },
           SOURCE,
         );
         break;
@@ -453,6 +457,19 @@ export class SignalingServer {
         );
         break;
 
+      case 'device_disconnect':
+        // Reconnect handling lives in the WebSocket client; the hint also
+        // ends this device's registration.
+        if (this.registeredDeviceId) {
+          this.bus.emit(
+            AdiSignalingBusKey.DeviceDeregistered,
+            { url: this.url, deviceId: this.registeredDeviceId, info: toBusInfo(msg.info) },
+            SOURCE,
+          );
+          this.registeredDeviceId = null;
+        }
+        break;
+
       case 'system_error':
         this.log.error({ msg: 'server error', error: msg.message });
         break;
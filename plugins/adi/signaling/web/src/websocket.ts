@@ -1,6 +1,6 @@
 import { Logger, trace } from '@adi-family/sdk-plugin';
 import { WsState } from './generated';
-import type { SignalingMessage } from './generated/channels';
+import { DisconnectReason, type DisconnectInfo, type SignalingMessage } from './generated/channels';
 
 export interface WsHandlers {
   onStateChange(state: WsState): void;
@@ -20,6 +20,12 @@ const RECONNECT_CAP_MS = 30_000;
 const MAX_RECONNECT_ATTEMPTS = 5;
 const STABLE_CONNECTION_MS = 5000;
 
+/** Banned and removed devices never reconnect, even without `permanent`. */
+const shouldRetry = (info: DisconnectInfo): boolean =>
+  !info.permanent &&
+  info.reason !== DisconnectReason.Removed &&
+  info.reason !== DisconnectReason.Banned;
+
 class WebSocketClient implements WsControl {
   private readonly log = new Logger('ws', () => ({
     url: this.url,
@@ -32,6 +38,8 @@ class WebSocketClient implements WsControl {
   private attempts = 0;
   private reconnectTimer: ReturnType<typeof setTimeout> | null = null;
   private connectedAt: number | null = null;
+  /** Hint the server sent before closing the current connection */
+  private disconnectHint: DisconnectInfo | null = null;
 
   constructor(
     private readonly url: string,
@@ -59,7 +67,9 @@ class WebSocketClient implements WsControl {
 
       this.ws.onmessage = (event) => {
         try {
-          this.handlers.onMessage(JSON.parse(event.data as string));
+          const msg = JSON.parse(event.data as string) as SignalingMessage;
+          if (msg.type === 'device_disconnect') this.disconnectHint = msg.info;
+          this.handlers.onMessage(msg);
         } catch (err) {
           this.log.warn({ msg: 'failed to parse message', error: String(err) });
         }
@@ -117,6 +127,14 @@ class WebSocketClient implements WsControl {
     }
     this.connectedAt = null;
 
+    const hint = this.disconnectHint;
+    this.disconnectHint = null;
+    if (hint && !shouldRetry(hint)) {
+      this.log.trace({ msg: 'server ended connection for good', reason: hint.reason });
+      this.handlers.onError(`Disconnected by server: ${hint.reason}`);
+      return;
+    }
+
     if (this.attempts >= MAX_RECONNECT_ATTEMPTS) {
       this.log.trace({ msg: 'max reconnect attempts reached' });
       this.handlers.onError('Max reconnection attempts reached');
@@ -124,7 +142,8 @@ class WebSocketClient implements WsControl {
     }
 
     this.clearReconnect();
-    const delay = Math.min(RECONNECT_BASE_MS * Math.pow(2, this.attempts), RECONNECT_CAP_MS);
+    const backoff = Math.min(RECONNECT_BASE_MS * Math.pow(2, this.attempts), RECONNECT_CAP_MS);
+    const delay = Math.max(backoff, (hint?.retry_after_secs ?? 0) * 1000);
     this.attempts++;
     this.log.trace({ msg: 'scheduling reconnect', delay, attempt: this.attempts });
     this.reconnectTimer = setTimeout(() => this.connect(), delay);